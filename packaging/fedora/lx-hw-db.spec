@@ -40,15 +40,17 @@ anonymization while maintaining statistical utility for compatibility analysis.
 
 %build
 export RUSTFLAGS="%{build_rustflags}"
-cargo build --release --bins
+cargo build --release --bins --features daemon
 
 %install
 # Install binaries
 install -Dm755 target/release/lx-hw-detect %{buildroot}%{_bindir}/lx-hw-detect
 install -Dm755 target/release/lx-hw-indexer %{buildroot}%{_bindir}/lx-hw-indexer
+install -Dm755 target/release/lx-hw-detectd %{buildroot}%{_bindir}/lx-hw-detectd
 
 # Install configuration
 install -Dm644 config/default.toml %{buildroot}%{_sysconfdir}/lx-hw-db/config.toml
+install -Dm644 config/lx-hw-detectd.toml %{buildroot}%{_sysconfdir}/lx-hw-db/lx-hw-detectd.toml
 
 # Install documentation
 install -Dm644 README.md %{buildroot}%{_docdir}/%{name}/README.md
@@ -65,6 +67,10 @@ cp -r web %{buildroot}%{_datadir}/%{name}/
 # Install systemd service
 install -Dm644 packaging/systemd/lx-hw-db-server.service \
     %{buildroot}%{_unitdir}/lx-hw-db-server.service
+install -Dm644 packaging/systemd/lx-hw-detectd.service \
+    %{buildroot}%{_unitdir}/lx-hw-detectd.service
+install -Dm644 packaging/dbus/org.lxhwdb.Detect1.conf \
+    %{buildroot}%{_datadir}/dbus-1/system.d/org.lxhwdb.Detect1.conf
 
 # Install shell completions
 install -Dm644 completions/bash/lx-hw-detect \
@@ -78,24 +84,28 @@ install -Dm644 completions/fish/lx-hw-detect.fish \
 cargo test --release
 
 %post
-%systemd_post lx-hw-db-server.service
+%systemd_post lx-hw-db-server.service lx-hw-detectd.service
 
 %preun
-%systemd_preun lx-hw-db-server.service
+%systemd_preun lx-hw-db-server.service lx-hw-detectd.service
 
 %postun
-%systemd_postun_with_restart lx-hw-db-server.service
+%systemd_postun_with_restart lx-hw-db-server.service lx-hw-detectd.service
 
 %files
 %license LICENSE
 %doc README.md
 %{_bindir}/lx-hw-detect
 %{_bindir}/lx-hw-indexer
+%{_bindir}/lx-hw-detectd
 %config(noreplace) %{_sysconfdir}/lx-hw-db/config.toml
+%config(noreplace) %{_sysconfdir}/lx-hw-db/lx-hw-detectd.toml
 %{_mandir}/man1/lx-hw-detect.1*
 %{_mandir}/man1/lx-hw-indexer.1*
 %{_datadir}/%{name}/
 %{_unitdir}/lx-hw-db-server.service
+%{_unitdir}/lx-hw-detectd.service
+%{_datadir}/dbus-1/system.d/org.lxhwdb.Detect1.conf
 %{_datadir}/bash-completion/completions/lx-hw-detect
 %{_datadir}/zsh/site-functions/_lx-hw-detect
 %{_datadir}/fish/vendor_completions.d/lx-hw-detect.fish
@@ -0,0 +1,48 @@
+//! Hardware monitoring daemon
+//!
+//! Runs detection on hotplug, keeps the snapshot history, and serves
+//! Prometheus metrics and a D-Bus API. See `lx_hw_detect::daemon`.
+
+use clap::Parser;
+use lx_hw_detect::daemon::{ConfigOverrides, Daemon, DEFAULT_CONFIG_PATH};
+use lx_hw_detect::Result;
+use std::path::PathBuf;
+
+#[derive(Parser)]
+#[command(
+    name = "lx-hw-detectd",
+    about = "Linux hardware monitoring daemon",
+    long_about = "Watch for hardware changes, record detection snapshots, and serve Prometheus metrics and a D-Bus API. Change events can also be written as JSON lines to stdout or a log file. Send SIGHUP to reload the configuration.",
+    version
+)]
+struct Cli {
+    /// Configuration file
+    #[arg(short, long, default_value = DEFAULT_CONFIG_PATH)]
+    config: PathBuf,
+
+    /// Re-run detection every SECS seconds, in addition to hotplug events
+    #[arg(long, value_name = "SECS")]
+    interval: Option<u64>,
+
+    /// Write change events as JSON lines to FILE, or to stdout with "-"
+    #[arg(long, value_name = "FILE")]
+    events: Option<PathBuf>,
+
+    /// Enable verbose output
+    #[arg(short, long)]
+    verbose: bool,
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let cli = Cli::parse();
+    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or(if cli.verbose {
+        "debug"
+    } else {
+        "info"
+    }))
+    .init();
+
+    let overrides = ConfigOverrides { interval_secs: cli.interval, event_log: cli.events };
+    Daemon::new(cli.config)?.with_overrides(overrides).run().await
+}
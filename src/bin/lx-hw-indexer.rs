@@ -4,8 +4,12 @@
 //! search indices, compatibility matrices, and statistics for the
 //! GitHub-native Linux Hardware Compatibility Database.
 
+use chrono::Utc;
 use clap::{Parser, Subcommand};
 use lx_hw_detect::errors::Result;
+use lx_hw_detect::indexer::analysis::{
+    AnalysisConfig, CompatibilityAnalyzer, ANALYSIS_FILE, SUMMARY_FILE,
+};
 use lx_hw_detect::indexer::{HardwareIndexer, IndexerConfig};
 use std::path::{Path, PathBuf};
 
@@ -48,6 +52,23 @@ enum Commands {
         /// Minimum number of reports required for inclusion
         #[arg(short, long, default_value = "1")]
         min_reports: usize,
+
+        /// Keep hardware below --min-reports, flagged as low confidence,
+        /// instead of listing it in excluded.json
+        #[arg(long)]
+        include_low_sample: bool,
+
+        /// Custom taxonomy file merged over the built-in component categories
+        #[arg(long)]
+        taxonomy: Option<PathBuf>,
+
+        /// Number of reports loaded at a time when streaming
+        #[arg(long, default_value = "500")]
+        batch_size: usize,
+
+        /// Stream reports in batches when more than this many are found
+        #[arg(long, default_value = "5000")]
+        stream_above: usize,
     },
 
     /// Validate generated indices
@@ -76,6 +97,26 @@ enum Commands {
         indices: PathBuf,
     },
 
+    /// Analyze database health, trends and regressions
+    Analyze {
+        /// Input directory containing hardware reports
+        #[arg(short, long, default_value = "hardware-reports")]
+        input: PathBuf,
+
+        /// Output directory for the analysis JSON and executive summary
+        #[arg(short, long, default_value = "analysis")]
+        output: PathBuf,
+
+        /// Minimum number of reports for a kernel, vendor, category or
+        /// distribution to be analyzed
+        #[arg(short, long, default_value = "3")]
+        min_reports: usize,
+
+        /// Skip regression detection
+        #[arg(long)]
+        no_regressions: bool,
+    },
+
     /// Show statistics about processed reports
     Stats {
         /// Input directory containing hardware reports
@@ -96,9 +137,30 @@ async fn main() -> Result<()> {
     }
 
     match cli.command {
-        Commands::Generate { input, output, api_output, stats_output, min_reports } => {
-            generate_indices(input, output, api_output, stats_output, min_reports, cli.verbose)
-                .await
+        Commands::Generate {
+            input,
+            output,
+            api_output,
+            stats_output,
+            min_reports,
+            include_low_sample,
+            taxonomy,
+            batch_size,
+            stream_above,
+        } => {
+            generate_indices(IndexerConfig {
+                reports_dir: input,
+                indices_dir: output,
+                api_dir: api_output,
+                stats_dir: stats_output,
+                min_reports,
+                verbose: cli.verbose,
+                taxonomy_file: taxonomy,
+                batch_size,
+                streaming_threshold: stream_above,
+                include_low_sample,
+            })
+            .await
         }
         Commands::Validate { indices, reports } => {
             validate_indices(indices, reports, cli.verbose).await
@@ -106,42 +168,39 @@ async fn main() -> Result<()> {
         Commands::GenerateSite { output, template_dir, indices } => {
             generate_site(output, template_dir, indices, cli.verbose).await
         }
+        Commands::Analyze { input, output, min_reports, no_regressions } => {
+            let config = AnalysisConfig {
+                min_reports_threshold: min_reports,
+                enable_regression_detection: !no_regressions,
+                ..AnalysisConfig::default()
+            };
+            analyze_reports(input, output, config, cli.verbose).await
+        }
         Commands::Stats { input } => show_stats(input, cli.verbose).await,
     }
 }
 
 /// Generate all indices from hardware reports
-async fn generate_indices(
-    input: PathBuf,
-    output: PathBuf,
-    api_output: PathBuf,
-    stats_output: PathBuf,
-    min_reports: usize,
-    verbose: bool,
-) -> Result<()> {
+async fn generate_indices(config: IndexerConfig) -> Result<()> {
     println!("Starting hardware compatibility index generation...");
 
-    let config = IndexerConfig {
-        reports_dir: input,
-        indices_dir: output,
-        api_dir: api_output,
-        stats_dir: stats_output,
-        min_reports,
-        verbose,
-    };
-
+    let verbose = config.verbose;
     let mut indexer = HardwareIndexer::new(config);
 
     // Scan and load all reports
     println!("Scanning hardware reports...");
     indexer.scan_reports()?;
 
-    if indexer.reports.is_empty() {
+    if indexer.report_count() == 0 {
         println!("Warning: No hardware reports found. Please check the input directory.");
         return Ok(());
     }
 
-    println!("Loaded {} hardware reports", indexer.reports.len());
+    if indexer.is_streaming() {
+        println!("Found {} hardware reports, streaming in batches", indexer.report_count());
+    } else {
+        println!("Loaded {} hardware reports", indexer.report_count());
+    }
 
     // Build indices
     println!("Building search indices...");
@@ -177,6 +236,7 @@ async fn validate_indices(indices_dir: PathBuf, reports_dir: PathBuf, verbose: b
         stats_dir: PathBuf::from("statistics"), // Not used for validation
         min_reports: 1,
         verbose,
+        ..IndexerConfig::default()
     };
 
     let mut indexer = HardwareIndexer::new(config);
@@ -659,6 +719,47 @@ window.SearchUI = SearchUI;
     Ok(())
 }
 
+/// Run the compatibility analysis and write its results
+async fn analyze_reports(
+    input: PathBuf,
+    output: PathBuf,
+    analysis_config: AnalysisConfig,
+    verbose: bool,
+) -> Result<()> {
+    println!("Analyzing hardware compatibility database...");
+
+    // The analysis needs every report in memory, so streaming is disabled
+    let config = IndexerConfig {
+        reports_dir: input,
+        verbose,
+        streaming_threshold: usize::MAX,
+        ..IndexerConfig::default()
+    };
+
+    let mut indexer = HardwareIndexer::new(config);
+    indexer.scan_reports()?;
+
+    if indexer.report_count() == 0 {
+        println!("Warning: No hardware reports found.");
+        return Ok(());
+    }
+
+    let analysis = CompatibilityAnalyzer::new(&indexer.reports, analysis_config).analyze()?;
+    analysis.write_to(&output, indexer.report_count(), Utc::now())?;
+
+    println!("Analyzed {} reports", indexer.report_count());
+    println!("  Health Score: {}/100", analysis.health_metrics.overall_score);
+    println!("  Kernel Trends: {}", analysis.kernel_trends.len());
+    println!("  Regressions: {}", analysis.regressions.len());
+    println!(
+        "Wrote {} and {}",
+        output.join(ANALYSIS_FILE).display(),
+        output.join(SUMMARY_FILE).display()
+    );
+
+    Ok(())
+}
+
 /// Show statistics about hardware reports
 async fn show_stats(input: PathBuf, verbose: bool) -> Result<()> {
     println!("Analyzing hardware reports...");
@@ -670,12 +771,13 @@ async fn show_stats(input: PathBuf, verbose: bool) -> Result<()> {
         stats_dir: PathBuf::from("statistics"), // Not used
         min_reports: 1,
         verbose,
+        ..IndexerConfig::default()
     };
 
     let mut indexer = HardwareIndexer::new(config);
     indexer.scan_reports()?;
 
-    if indexer.reports.is_empty() {
+    if indexer.report_count() == 0 {
         println!("Warning: No hardware reports found.");
         return Ok(());
     }
@@ -725,13 +827,17 @@ fn print_generation_summary(indexer: &HardwareIndexer) {
 
     let stats = &indexer.indices.statistics;
 
-    println!("Reports Processed: {}", indexer.reports.len());
+    println!("Reports Processed: {}", stats.total_reports);
     println!("Indices Generated:");
     println!("  - Vendor Index: {} vendors", indexer.indices.by_vendor.len());
     println!("  - Component Index: {} component types", indexer.indices.by_component.len());
     println!("  - Kernel Index: {} kernel versions", indexer.indices.by_kernel.len());
     println!("  - Distribution Index: {} distributions", indexer.indices.by_distribution.len());
     println!("  - Search Terms: {} terms", indexer.indices.search_terms.len());
+    if !indexer.derived.is_empty() {
+        let names: Vec<&str> = indexer.derived.keys().map(String::as_str).collect();
+        println!("  - Derived Datasets: {}", names.join(", "));
+    }
     println!(
         "  - Compatibility Matrix: {} hardware/kernel combinations",
         indexer.indices.compatibility_matrix.values().map(|v| v.len()).sum::<usize>()
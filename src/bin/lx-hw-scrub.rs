@@ -0,0 +1,84 @@
+//! Privacy gate for hardware reports
+//!
+//! Reads a report from stdin, enforces the database privacy policy, and
+//! writes the compliant report to stdout. Intended to run in the database
+//! repository's CI regardless of which client version produced the report.
+
+use clap::Parser;
+use lx_hw_detect::errors::Result;
+use lx_hw_detect::hardware::blobs::BlobStore;
+use lx_hw_detect::hardware::HardwareReport;
+use lx_hw_detect::privacy::scrub::{ScrubPolicy, Scrubber};
+use std::path::{Path, PathBuf};
+
+#[derive(Parser)]
+#[command(
+    name = "lx-hw-scrub",
+    about = "Enforce the database privacy policy on a hardware report read from stdin",
+    version
+)]
+struct Cli {
+    /// Fail instead of rewriting when personal data or untrimmed fields are found
+    #[arg(long)]
+    check: bool,
+
+    /// Environment variable holding the re-anonymization key
+    /// (a random key is used when unset)
+    #[arg(long, default_value = "LX_HW_SCRUB_KEY")]
+    key_env: String,
+
+    /// Longest free-text value kept
+    #[arg(long, default_value_t = ScrubPolicy::default().max_text_len)]
+    max_text_len: usize,
+
+    /// Keep the kernel log lines of unknown devices, sanitized under the
+    /// re-anonymization key, instead of removing them
+    #[arg(long)]
+    keep_dmesg_lines: bool,
+
+    /// Blob directory of the report's bundle; referenced blobs are copied to
+    /// --blobs-out, with kernel log blobs sanitized like inline lines
+    #[arg(long, value_name = "DIR", requires = "blobs_out")]
+    blobs: Option<PathBuf>,
+
+    /// Directory the scrubbed report's blobs are written to
+    #[arg(long, value_name = "DIR", requires = "blobs")]
+    blobs_out: Option<PathBuf>,
+}
+
+fn main() -> Result<()> {
+    let cli = Cli::parse();
+
+    let policy = ScrubPolicy {
+        max_text_len: cli.max_text_len,
+        keep_dmesg_lines: cli.keep_dmesg_lines,
+        ..ScrubPolicy::default()
+    };
+    let mut scrubber = match std::env::var(&cli.key_env) {
+        Ok(key) if !key.is_empty() => Scrubber::with_key(policy, key.as_bytes()),
+        _ => Scrubber::new(policy)?,
+    };
+    if let (Some(source), Some(target)) = (cli.blobs, cli.blobs_out) {
+        scrubber = scrubber.with_blob_stores(BlobStore::new(source), BlobStore::new(target));
+    }
+
+    let mut report = HardwareReport::load(Path::new("-"))?;
+    let summary = scrubber.scrub(&mut report);
+
+    for field in &summary.redacted {
+        eprintln!("redacted: {}", field);
+    }
+    for field in &summary.trimmed {
+        eprintln!("trimmed: {}", field);
+    }
+
+    if cli.check && summary.has_findings() {
+        eprintln!("Report does not comply with the privacy policy");
+        std::process::exit(1);
+    }
+
+    let json = serde_json::to_string_pretty(&report)
+        .map_err(|e| lx_hw_detect::LxHwError::SerializationError(e.to_string()))?;
+    println!("{}", json);
+    Ok(())
+}
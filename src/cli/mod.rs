@@ -1,10 +1,21 @@
 //! Command-line interface for the hardware detection tool
 
+use crate::configuration::export::ConfigurationTarget;
+use crate::detectors::naming::NamingRuleStyle;
+use crate::detectors::privilege::Escalation;
+use crate::detectors::{DetectionProfile, RetryPolicy};
+use crate::diff::history::SnapshotHistory;
 use crate::errors::{LxHwError, Result};
+use crate::hardware::notes::UserNote;
+use crate::hardware::pci_class;
 use crate::hardware::PrivacyLevel;
+use crate::logging::LogFormat;
 use crate::output::OutputFormat;
-use clap::{Parser, Subcommand, ValueEnum};
-use std::path::PathBuf;
+use crate::privacy::redact::{RedactionRule, Redactor};
+use crate::privacy::PseudonymKey;
+use crate::telemetry::TelemetryTarget;
+use clap::{CommandFactory, Parser, Subcommand, ValueEnum};
+use std::path::{Path, PathBuf};
 
 /// Linux Hardware Detection CLI Tool
 #[derive(Parser, Debug)]
@@ -34,6 +45,11 @@ pub struct GlobalOptions {
     #[arg(short, long, global = true, conflicts_with = "verbose")]
     pub quiet: bool,
 
+    /// Log line format on stderr: `json` writes one object per line with
+    /// timestamp, level, target, message and fields such as detector timings
+    #[arg(long, global = true, value_enum, default_value_t = LogFormat::Text)]
+    pub log_format: LogFormat,
+
     /// Privacy level for data collection
     #[arg(
         short = 'p',
@@ -47,41 +63,184 @@ pub struct GlobalOptions {
     /// Configuration file path
     #[arg(short, long, global = true)]
     pub config: Option<PathBuf>,
+
+    /// On a crash, write a diagnostic bundle (backtrace, redacted command
+    /// line, detection phase, tool versions) to attach to a bug report
+    #[arg(long, global = true, env = "LX_HW_CRASH_REPORT")]
+    pub crash_report: bool,
 }
 
 #[derive(Subcommand, Debug)]
 pub enum Commands {
     /// Detect hardware and generate compatibility report
     Detect {
-        /// Output format
-        #[arg(short = 'f', long, value_enum, default_value_t = OutputFormat::Markdown)]
-        format: OutputFormat,
+        /// Output format (default: a colored summary on a terminal,
+        /// markdown otherwise)
+        #[arg(short = 'f', long, value_enum)]
+        format: Option<OutputFormat>,
 
-        /// Output file path (default: stdout)
+        /// Output file path (default: stdout, or `-`)
         #[arg(short, long)]
         output: Option<PathBuf>,
 
-        /// Specific tools to run (default: all available)
-        /// Available tools: lshw, dmidecode, lspci, lsusb, inxi
+        /// Canonical output for submission: sorted components and fields,
+        /// fixed float precision
+        #[arg(long)]
+        canonical: bool,
+
+        /// Detection profile: `quick` skips lshw and inxi, time-boxes each
+        /// tool and skips kernel log capture for a fast, reduced report
+        #[arg(long, value_enum, default_value_t = DetectionProfile::Full)]
+        profile: DetectionProfile,
+
+        /// Specific tools to run (default: all available for the profile)
+        /// Available tools: lshw, dmidecode, lspci, lsusb, inxi, fwupd, nvme, smartctl, sysfs
         /// Example: --tools lshw,lspci
         #[arg(short, long, value_delimiter = ',')]
         tools: Option<Vec<String>>,
 
-        /// Timeout for each detection tool in seconds
+        /// Timeout for each detection tool in seconds (default: 30, or 1 with
+        /// --profile quick)
         /// Individual detectors will be terminated if they exceed this time limit
-        #[arg(long, default_value_t = 30)]
-        timeout: u64,
+        #[arg(long)]
+        timeout: Option<u64>,
+
+        /// Attempts per detection tool before a transient failure is reported
+        /// (default: 3 for dmidecode and lsusb, 1 for other tools)
+        #[arg(long, value_parser = clap::value_parser!(u32).range(1..))]
+        retries: Option<u32>,
+
+        /// Initial delay between retries in milliseconds, doubled per retry
+        #[arg(long, requires = "retries")]
+        retry_backoff_ms: Option<u64>,
+
+        /// Run detection tools under bubblewrap without network access,
+        /// capabilities or a writable filesystem; lshw, dmidecode, lspci and
+        /// inxi report less detail
+        #[arg(long)]
+        sandbox: bool,
+
+        /// Run the detection tools on another machine over SSH
+        /// (`user@host`) and build the report here; the host needs the tools
+        /// but not lx-hw-detect, and key-based login must work
+        #[arg(long, value_name = "DESTINATION", conflicts_with = "sandbox")]
+        remote: Option<String>,
+
+        /// Run only the tools lacking privileges they need (dmidecode, lshw,
+        /// smartctl, ...) as root through sudo or pkexec, which ask you to
+        /// authenticate; the rest of detection stays unprivileged
+        #[arg(long, value_enum, value_name = "HELPER", conflicts_with_all = ["sandbox", "remote"])]
+        escalate: Option<Escalation>,
+
+        /// Build the report from tool output saved with --save-raw, or from a
+        /// directory of `<tool>.stdout` files, instead of running the tools;
+        /// checks reading this machine's kernel and sysfs are skipped
+        #[arg(
+            long,
+            value_name = "BUNDLE",
+            conflicts_with_all = ["sandbox", "remote", "escalate", "save_raw"]
+        )]
+        replay: Option<PathBuf>,
 
         /// Skip privacy anonymization (for debugging)
         #[arg(long)]
         no_anonymize: bool,
+
+        /// Shorten anonymized identifiers to this many hex characters
+        /// (12-64, at least 16 with --privacy strict; default: 64). Shorter
+        /// IDs are easier to read but more likely to collide across the
+        /// database; see docs/PRIVACY_GUIDE.md
+        #[arg(long, value_name = "CHARS")]
+        digest_length: Option<usize>,
+
+        /// Derive anonymized identifiers from the secret in this file instead
+        /// of a rotating salt, so this system keeps the same anonymized ID
+        /// across reports; keep the file private
+        #[arg(long, value_name = "FILE", conflicts_with = "no_anonymize")]
+        pseudonym_key: Option<PathBuf>,
+
+        /// Like --pseudonym-key, with a passphrase
+        #[arg(
+            long,
+            env = "LX_HW_PSEUDONYM_PASSPHRASE",
+            hide_env_values = true,
+            conflicts_with_all = ["no_anonymize", "pseudonym_key"]
+        )]
+        pseudonym_passphrase: Option<String>,
+
+        /// Also save what each tool printed, with serial numbers, MAC and IP
+        /// addresses and the hostname replaced, as a .tar.gz bundle to attach
+        /// to bug reports about misdetected hardware
+        #[arg(long, value_name = "PATH")]
+        save_raw: Option<PathBuf>,
+
+        /// Store payloads larger than this many bytes (kernel log excerpts)
+        /// as content-addressed files in a `blobs/` directory next to the output
+        #[arg(long, value_name = "BYTES", requires = "output")]
+        externalize_over: Option<usize>,
+
+        /// Opt in to reporting anonymous fingerprints of tool output that
+        /// failed to parse (tool, version, parser location, hashed excerpt)
+        #[arg(long)]
+        report_parse_failures: bool,
+
+        /// Collection endpoint for parser failure reports
+        /// (default: print a pre-filled GitHub issue link); ignored without
+        /// --report-parse-failures
+        #[arg(long, env = "LX_HW_TELEMETRY_ENDPOINT")]
+        telemetry_endpoint: Option<String>,
+
+        /// Output only hardware added or changed since the last run and record
+        /// this run as the new snapshot; prints nothing when nothing changed
+        #[arg(long, conflicts_with = "no_anonymize")]
+        only_new: bool,
+
+        /// Directory of report snapshots used by --only-new
+        /// (default: $XDG_STATE_HOME/lx-hw-db/history)
+        #[arg(long, env = "LX_HW_HISTORY_DIR", requires = "only_new")]
+        history_dir: Option<PathBuf>,
+
+        /// Attach a note to a component, keyed by its vendor:device ID or a
+        /// category (system, cpu, memory, storage, graphics, network, usb,
+        /// audio); repeatable.
+        /// Example: --annotate 8086:9dc8="works after options snd-hda-intel model=alc255-acer"
+        #[arg(long, value_name = "KEY=NOTE", conflicts_with = "only_new")]
+        annotate: Vec<String>,
+
+        /// List the detected components and ask for notes on the terminal
+        #[arg(long, conflicts_with = "only_new")]
+        annotate_interactive: bool,
+
+        /// Replace text before the report is written, in addition to the
+        /// `[[privacy.redact]]` rules of the config file; repeatable.
+        /// FIELD is a path as shown by privacy-audit, with `*` for any index,
+        /// and REGEX the text to replace; an empty side matches everything.
+        /// Example: --redact-rule 'usb[*].product_name=' --redact-rule '=ASSET-[0-9]+'
+        #[arg(long, value_name = "FIELD=REGEX")]
+        redact_rule: Vec<String>,
     },
 
-    /// Check which detection tools are available
+    /// Check which detection tools and kernel data sources are available
     Check {
         /// Show detailed information about each tool
         #[arg(short, long)]
         detailed: bool,
+
+        /// Output format: `json` lists each tool's path, version and
+        /// required privileges and every data source, for provisioning scripts
+        #[arg(short = 'f', long, value_enum, default_value_t = CheckFormat::Text)]
+        format: CheckFormat,
+    },
+
+    /// Show persistent network and disk names, and generate rules pinning them
+    Naming {
+        /// Generate systemd.link files or udev rules for stable names
+        #[arg(long, value_enum)]
+        generate: Option<NamingRuleStyle>,
+
+        /// Directory to write generated files into (default: print them)
+        #[arg(short, long, requires = "generate")]
+        output: Option<PathBuf>,
     },
 
     /// Validate hardware report files
@@ -93,6 +252,11 @@ pub enum Commands {
         #[arg(long)]
         device: Option<String>,
 
+        /// Analyze devices listed in a hardware report instead of scanning
+        /// this system (`-` reads from stdin)
+        #[arg(long, conflicts_with = "device")]
+        report: Option<PathBuf>,
+
         /// Include detailed kernel source analysis
         #[arg(long)]
         kernel_source: bool,
@@ -104,6 +268,121 @@ pub enum Commands {
         /// Show upgrade recommendations
         #[arg(long)]
         recommendations: bool,
+
+        /// Offer to open a GitHub issue in the database repository for each
+        /// unsupported device (asks before opening every issue)
+        #[arg(long)]
+        open_issues: bool,
+
+        /// Check IOMMU groups and which GPUs and network cards can be passed
+        /// through to virtual machines with VFIO
+        #[arg(long, conflicts_with_all = ["device", "report", "kernel_source", "open_issues"])]
+        vfio: bool,
+    },
+
+    /// Render an existing hardware report in another format
+    Render {
+        /// Hardware report to render (`-` reads from stdin)
+        #[arg(default_value = "-")]
+        input: PathBuf,
+
+        /// Output format
+        #[arg(short = 'f', long, value_enum, default_value_t = OutputFormat::Markdown)]
+        format: OutputFormat,
+
+        /// Output file path (default: stdout, or `-`)
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+
+        /// Canonical output for submission: sorted components and fields,
+        /// fixed float precision
+        #[arg(long)]
+        canonical: bool,
+
+        /// Re-key identifiers for a named audience, e.g. "public" or
+        /// "internal:acme"; each context keeps its own salt in
+        /// $XDG_STATE_HOME/lx-hw-db/salt-contexts
+        #[arg(long, value_name = "NAME")]
+        salt_context: Option<String>,
+    },
+
+    /// Compare two hardware reports and show semantic differences
+    Compare {
+        /// Previous hardware report (`-` reads from stdin)
+        old: PathBuf,
+
+        /// Current hardware report (`-` reads from stdin)
+        new: PathBuf,
+
+        /// Output format
+        #[arg(short = 'f', long, value_enum, default_value_t = OutputFormat::Markdown)]
+        format: OutputFormat,
+    },
+
+    /// Generate deterministic synthetic hardware reports for development
+    #[command(name = "genexamples")]
+    GenExamples {
+        /// Number of reports to generate
+        #[arg(long, default_value = "100")]
+        count: usize,
+
+        /// Seed selecting the corpus; the same seed always yields the same reports
+        #[arg(long, default_value = "0")]
+        seed: u64,
+
+        /// Directory to write reports into, laid out like `hardware-reports/`
+        #[arg(short, long, default_value = "example-reports")]
+        output: PathBuf,
+    },
+
+    /// Refresh kernel LTS and end-of-life data used by upgrade recommendations
+    UpdateKernelReleases {
+        /// kernel.org releases.json to read
+        #[arg(long, default_value = crate::hardware::kernel_releases::KERNEL_ORG_RELEASES_URL)]
+        url: String,
+
+        /// File to write (default: $XDG_CACHE_HOME/lx-hw-db/kernel-releases.toml)
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+
+    /// Browse the community compatibility database offline
+    Browse {
+        /// Directory or http(s) URL holding the generated indices
+        #[arg(long)]
+        indices: String,
+
+        /// Match against a hardware report instead of detecting this system
+        /// (`-` reads from stdin)
+        #[arg(long)]
+        report: Option<PathBuf>,
+    },
+
+    /// Watch devices for changes in the community compatibility database
+    Watch {
+        /// Watchlist file (default: $XDG_CONFIG_HOME/lx-hw-db/watchlist.json)
+        #[arg(long, global = true)]
+        watchlist: Option<PathBuf>,
+
+        #[command(subcommand)]
+        command: WatchCommands,
+    },
+
+    /// Privacy tooling
+    Privacy {
+        #[command(subcommand)]
+        command: PrivacyCommands,
+    },
+
+    /// Show which fields of a report were anonymized, which identifiers
+    /// were left out, and which values could still identify the machine
+    PrivacyAudit {
+        /// Hardware report to audit (`-` reads from stdin)
+        report: PathBuf,
+
+        /// Print the audit as JSON
+        #[arg(long)]
+        json: bool,
     },
 
     /// Generate configuration templates
@@ -119,11 +398,12 @@ pub enum Commands {
         #[arg(long)]
         github_username: Option<String>,
 
-        /// GitHub personal access token (will prompt if not provided)
+        /// GitHub personal access token; without it the token stored by
+        /// `auth login` is used, or the browser authorization is started
         #[arg(long)]
         github_token: Option<String>,
 
-        /// Hardware report file to submit (if not provided, will generate automatically)
+        /// Hardware report file to submit, `-` for stdin (if not provided, will generate automatically)
         #[arg(short, long)]
         report: Option<PathBuf>,
 
@@ -146,7 +426,168 @@ pub enum Commands {
         /// Use draft pull request
         #[arg(long)]
         draft: bool,
+
+        /// Re-key identifiers for a named audience before submitting, e.g.
+        /// "public"; see `render --salt-context`
+        #[arg(long, value_name = "NAME")]
+        salt_context: Option<String>,
+
+        /// Directory or http(s) URL holding the generated indices, to warn
+        /// when few published reports share this hardware combination
+        #[arg(long, value_name = "SOURCE")]
+        indices: Option<String>,
+
+        /// With --indices, re-key serial and MAC digests and drop DIMM
+        /// manufacturers when the hardware combination is rare
+        #[arg(long, requires = "indices")]
+        generalize: bool,
+
+        /// Submit anonymously through the relay at this HTTPS URL instead of
+        /// a GitHub account; the relay opens the pull request
+        #[arg(
+            long,
+            env = "LX_HW_RELAY_URL",
+            value_name = "URL",
+            conflicts_with_all = ["github_username", "github_token", "auto_fork", "draft"]
+        )]
+        relay: Option<String>,
+
+        /// Proxy for the relay connection, e.g. socks5h://127.0.0.1:9050
+        #[arg(long, value_name = "URL", requires = "relay")]
+        proxy: Option<String>,
+
+        /// Connect to the relay through a local Tor daemon
+        /// (socks5h://127.0.0.1:9050)
+        #[arg(long, requires = "relay", conflicts_with = "proxy")]
+        tor: bool,
+    },
+
+    /// Generate a hardware-specific configuration for a distribution
+    Configure {
+        /// Hardware report to configure for instead of scanning this system
+        /// (`-` reads from stdin)
+        #[arg(long)]
+        report: Option<PathBuf>,
+
+        /// Distribution to configure (default: the one in the report)
+        #[arg(short, long)]
+        distribution: Option<String>,
+
+        /// Render the configuration in the native format of a target system
+        #[arg(short, long, value_enum, conflicts_with = "format")]
+        target: Option<ConfigurationTarget>,
+
+        /// Output format: JSON for `apply`, or a step-by-step guide
+        #[arg(short = 'f', long, value_enum, default_value_t = ConfigureFormat::Json)]
+        format: ConfigureFormat,
+
+        /// Also pin the interface and disk names of this system with
+        /// systemd.link files or udev rules (they contain MAC addresses and
+        /// disk serial numbers)
+        #[arg(long, value_enum, value_name = "STYLE")]
+        persistent_names: Option<NamingRuleStyle>,
+
+        /// Output file (default: stdout)
+        #[arg(short, long)]
+        output: Option<PathBuf>,
     },
+
+    /// Show which recommended kernel parameters and sysctls the running
+    /// system already applies, misses or contradicts
+    Drift {
+        /// Hardware report to take the recommendations from instead of
+        /// scanning this system (`-` reads from stdin)
+        #[arg(long)]
+        report: Option<PathBuf>,
+
+        /// Distribution to recommend for (default: the one in the report)
+        #[arg(short, long)]
+        distribution: Option<String>,
+
+        /// Read /proc, /sys and /etc below this directory instead of the
+        /// running system
+        #[arg(long, value_name = "DIR", default_value = "/")]
+        root: PathBuf,
+
+        /// Print the drift report as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Apply a generated configuration step by step, asking before each change
+    Apply {
+        /// Configuration saved as JSON by the configuration engine
+        #[arg(required_unless_present = "rollback")]
+        configuration: Option<PathBuf>,
+
+        /// Apply every step without asking
+        #[arg(short, long)]
+        yes: bool,
+
+        /// Show the steps and what they would change without changing anything
+        #[arg(long)]
+        dry_run: bool,
+
+        /// Undo the changes recorded in the journal instead
+        #[arg(long, conflicts_with_all = ["configuration", "yes", "dry_run"])]
+        rollback: bool,
+
+        /// Rollback journal (default: /var/lib/lx-hw-detect/apply-journal.json
+        /// below the root)
+        #[arg(long, value_name = "PATH")]
+        journal: Option<PathBuf>,
+
+        /// Write files below this directory instead of the running system;
+        /// commands are listed but not run
+        #[arg(long, value_name = "DIR", default_value = "/")]
+        root: PathBuf,
+    },
+
+    /// Manage the GitHub authorization used for submissions
+    Auth {
+        #[command(subcommand)]
+        command: AuthCommands,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum AuthCommands {
+    /// Authorize lx-hw-detect for a GitHub account and keep the token in the
+    /// system keyring
+    Login {
+        /// GitHub account expected to be authorized
+        #[arg(long)]
+        github_username: Option<String>,
+
+        /// Read a personal access token from stdin instead of authorizing in
+        /// the browser
+        #[arg(long)]
+        with_token: bool,
+    },
+
+    /// Remove the stored token from the system keyring
+    Logout,
+
+    /// Show which account the stored token belongs to and whether it still works
+    Status,
+}
+
+/// Output format of the configure command
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ConfigureFormat {
+    /// Configuration that `apply` reads
+    Json,
+    /// Setup guide in Markdown
+    Markdown,
+    /// Setup guide as a standalone HTML page
+    Html,
+}
+
+/// Output format of the check command
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum CheckFormat {
+    Text,
+    Json,
 }
 
 #[derive(Subcommand, Debug)]
@@ -166,6 +607,65 @@ pub enum ConfigCommands {
     Show,
 }
 
+#[derive(Subcommand, Debug)]
+pub enum WatchCommands {
+    /// Add device IDs ("vendor:device") to the watchlist
+    Add {
+        /// Device IDs to watch
+        #[arg(required_unless_present = "report")]
+        device_ids: Vec<String>,
+
+        /// Also watch every device in a hardware report (`-` reads from stdin)
+        #[arg(long)]
+        report: Option<PathBuf>,
+
+        /// Note shown when listing the watchlist
+        #[arg(long)]
+        note: Option<String>,
+    },
+
+    /// Remove device IDs from the watchlist
+    Remove {
+        /// Device IDs to stop watching
+        #[arg(required = true)]
+        device_ids: Vec<String>,
+    },
+
+    /// List watched devices
+    List,
+
+    /// Fetch the latest indices and report changes affecting watched devices
+    Sync {
+        /// Directory or http(s) URL holding the generated indices
+        #[arg(long)]
+        indices: String,
+
+        /// Print changes as JSON
+        #[arg(long)]
+        json: bool,
+
+        /// Also show changes as a desktop notification
+        #[arg(long)]
+        notify: bool,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum PrivacyCommands {
+    /// Verify the anonymization pipeline against published test vectors
+    SelfTest {
+        /// Test vector file (default: built-in vectors)
+        #[arg(long)]
+        vectors: Option<PathBuf>,
+
+        /// Print results as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// List the salt contexts used by `--salt-context`
+    Contexts,
+}
+
 /// CLI implementation for handling command execution
 pub struct CliHandler;
 
@@ -183,26 +683,154 @@ impl CliHandler {
 
     /// Run the CLI with the given arguments
     pub async fn run(&self, cli: Cli) -> Result<()> {
+        if cli.global.crash_report {
+            crate::telemetry::crash::install(crate::telemetry::crash::sanitize_args(
+                std::env::args(),
+                &Cli::command(),
+            ));
+        }
+
         // Initialize logging based on verbosity
-        self.init_logging(cli.global.verbose, cli.global.quiet)?;
+        self.init_logging(cli.global.verbose, cli.global.quiet, cli.global.log_format)?;
 
         // Load configuration
-        let _config = self.load_config(cli.global.config.as_ref())?;
+        let config = self.load_config(cli.global.config.as_ref())?;
 
         // Execute the command
         match cli.command {
-            Commands::Detect { format, output, tools, timeout, no_anonymize } => {
-                self.handle_detect(cli.global.privacy, format, output, tools, timeout, no_anonymize)
-                    .await
+            Commands::Detect {
+                format,
+                output,
+                canonical,
+                profile,
+                tools,
+                timeout,
+                retries,
+                retry_backoff_ms,
+                sandbox,
+                remote,
+                escalate,
+                replay,
+                no_anonymize,
+                digest_length,
+                pseudonym_key,
+                pseudonym_passphrase,
+                save_raw,
+                externalize_over,
+                report_parse_failures,
+                telemetry_endpoint,
+                only_new,
+                history_dir,
+                annotate,
+                annotate_interactive,
+                redact_rule,
+            } => {
+                if sandbox {
+                    crate::detectors::sandbox::enable()?;
+                }
+                if let Some(destination) = &remote {
+                    crate::detectors::remote::enable(destination).await?;
+                }
+                if let Some(escalation) = escalate {
+                    crate::detectors::privilege::enable(escalation).await?;
+                }
+                if let Some(bundle) = &replay {
+                    crate::detectors::replay::enable(bundle)?;
+                }
+                let retry_policy = retries.map(|max_attempts| {
+                    let mut policy = RetryPolicy { max_attempts, ..RetryPolicy::default() };
+                    if let Some(ms) = retry_backoff_ms {
+                        policy.initial_backoff = std::time::Duration::from_millis(ms);
+                    }
+                    policy
+                });
+                let telemetry = report_parse_failures.then_some(match telemetry_endpoint {
+                    Some(url) => TelemetryTarget::Endpoint(url),
+                    None => TelemetryTarget::IssueTemplate,
+                });
+                let notes = annotate
+                    .iter()
+                    .map(|argument| UserNote::parse(argument))
+                    .collect::<Result<Vec<_>>>()?;
+                let history = only_new.then(|| {
+                    SnapshotHistory::new(history_dir.unwrap_or_else(SnapshotHistory::default_dir))
+                });
+                let mut redaction_rules = config.privacy.redact.clone();
+                for argument in &redact_rule {
+                    redaction_rules.push(RedactionRule::parse(argument)?);
+                }
+                let redactor = Redactor::new(&redaction_rules)?;
+                let pseudonym_key = pseudonym_key
+                    .map(PseudonymKey::Keyfile)
+                    .or(pseudonym_passphrase.map(PseudonymKey::Passphrase));
+                self.handle_detect(
+                    cli.global.privacy,
+                    format,
+                    output,
+                    canonical,
+                    profile,
+                    tools,
+                    timeout,
+                    retry_policy,
+                    no_anonymize,
+                    digest_length,
+                    pseudonym_key,
+                    save_raw,
+                    externalize_over,
+                    telemetry,
+                    history,
+                    notes,
+                    annotate_interactive,
+                    redactor,
+                )
+                .await
             }
-            Commands::Check { detailed } => self.handle_check(detailed).await,
+            Commands::Check { detailed, format } => self.handle_check(detailed, format).await,
+            Commands::Naming { generate, output } => self.handle_naming(generate, output),
             Commands::Validate(validate_args) => {
                 crate::validation::cli::execute_validate(validate_args).await?;
                 Ok(())
             }
-            Commands::Analyze { device, kernel_source, kernel_repo, recommendations } => {
-                self.handle_analyze(device, kernel_source, kernel_repo, recommendations).await
+            Commands::Analyze {
+                device,
+                report,
+                kernel_source,
+                kernel_repo,
+                recommendations,
+                open_issues,
+                vfio,
+            } => {
+                if vfio {
+                    self.handle_vfio_analysis();
+                    return Ok(());
+                }
+                self.handle_analyze(
+                    device,
+                    report,
+                    kernel_source,
+                    kernel_repo,
+                    recommendations,
+                    open_issues,
+                    cli.global.privacy,
+                )
+                .await
+            }
+            Commands::Render { input, format, output, canonical, salt_context } => {
+                self.handle_render(&input, format, output, canonical, salt_context.as_deref())
+            }
+            Commands::Compare { old, new, format } => self.handle_compare(&old, &new, format),
+            Commands::GenExamples { count, seed, output } => {
+                self.handle_genexamples(count, seed, &output)
+            }
+            Commands::UpdateKernelReleases { url, output } => {
+                self.handle_update_kernel_releases(&url, output).await
+            }
+            Commands::Browse { indices, report } => {
+                self.handle_browse(cli.global.privacy, &indices, report).await
             }
+            Commands::Watch { watchlist, command } => self.handle_watch(watchlist, command).await,
+            Commands::Privacy { command } => self.handle_privacy(command),
+            Commands::PrivacyAudit { report, json } => self.handle_privacy_audit(&report, json),
             Commands::Config { command } => self.handle_config(command).await,
             Commands::Submit {
                 github_username,
@@ -213,6 +841,12 @@ impl CliHandler {
                 auto_fork,
                 tools,
                 draft,
+                salt_context,
+                indices,
+                generalize,
+                relay,
+                proxy,
+                tor,
             } => {
                 self.handle_submit(
                     github_username,
@@ -223,15 +857,47 @@ impl CliHandler {
                     auto_fork,
                     tools,
                     draft,
+                    salt_context,
+                    indices,
+                    generalize,
+                    relay,
+                    proxy,
+                    tor,
                     &cli.global,
                 )
                 .await
             }
+            Commands::Configure {
+                report,
+                distribution,
+                target,
+                format,
+                persistent_names,
+                output,
+            } => {
+                self.handle_configure(
+                    cli.global.privacy,
+                    report,
+                    distribution,
+                    target,
+                    format,
+                    persistent_names,
+                    output,
+                )
+                .await
+            }
+            Commands::Drift { report, distribution, root, json } => {
+                self.handle_drift(cli.global.privacy, report, distribution, &root, json).await
+            }
+            Commands::Apply { configuration, yes, dry_run, rollback, journal, root } => {
+                self.handle_apply(configuration, yes, dry_run, rollback, journal, root)
+            }
+            Commands::Auth { command } => self.handle_auth(command).await,
         }
     }
 
     /// Initialize logging based on verbosity settings
-    fn init_logging(&self, verbose: bool, quiet: bool) -> Result<()> {
+    fn init_logging(&self, verbose: bool, quiet: bool, format: LogFormat) -> Result<()> {
         let log_level = if quiet {
             "error"
         } else if verbose {
@@ -240,15 +906,21 @@ impl CliHandler {
             "info"
         };
 
-        std::env::set_var("RUST_LOG", format!("lx_hw_detect={}", log_level));
-        env_logger::init();
+        crate::logging::init(&format!("lx_hw_detect={}", log_level), format);
         Ok(())
     }
 
     /// Load configuration from file
-    fn load_config(&self, _config_path: Option<&PathBuf>) -> Result<AppConfig> {
-        // Placeholder implementation
-        Ok(AppConfig::default())
+    fn load_config(&self, config_path: Option<&PathBuf>) -> Result<AppConfig> {
+        let Some(path) = config_path else {
+            return Ok(AppConfig::default());
+        };
+        let contents = std::fs::read_to_string(path).map_err(|e| {
+            LxHwError::ConfigError(format!("Cannot read configuration {}: {}", path.display(), e))
+        })?;
+        toml::from_str(&contents).map_err(|e| {
+            LxHwError::ConfigError(format!("Invalid configuration {}: {}", path.display(), e))
+        })
     }
 
     /// Handle the detect command
@@ -256,39 +928,154 @@ impl CliHandler {
     async fn handle_detect(
         &self,
         privacy: PrivacyLevel,
-        format: OutputFormat,
+        format: Option<OutputFormat>,
         output: Option<PathBuf>,
+        canonical: bool,
+        profile: DetectionProfile,
         tools: Option<Vec<String>>,
-        timeout: u64,
+        timeout: Option<u64>,
+        retry_policy: Option<RetryPolicy>,
         no_anonymize: bool,
+        digest_length: Option<usize>,
+        pseudonym_key: Option<PseudonymKey>,
+        save_raw: Option<PathBuf>,
+        externalize_over: Option<usize>,
+        telemetry: Option<TelemetryTarget>,
+        history: Option<SnapshotHistory>,
+        mut notes: Vec<UserNote>,
+        annotate_interactive: bool,
+        redactor: Redactor,
     ) -> Result<()> {
         use crate::detectors::integration::HardwareAnalyzer;
+        use crate::output::terminal::TerminalRenderer;
         use crate::output::OutputRenderer;
+        use crate::telemetry::{report_parse_failures, SubmissionLog};
+        use std::io::IsTerminal;
         use std::time::Duration;
 
+        // Without --format, people at a terminal get the readable view and
+        // pipelines get markdown
+        let to_stdout =
+            output.as_deref().filter(|path| !crate::hardware::is_stdin_path(path)).is_none();
+        let terminal =
+            format.is_none() && !canonical && to_stdout && std::io::stdout().is_terminal();
+        let format = format.unwrap_or(OutputFormat::Markdown);
+
+        // Progress goes to stderr so stdout carries only the report in pipelines
         log::info!("Starting hardware detection and analysis...");
-        println!("Detecting hardware and analyzing kernel compatibility...\n");
+        eprintln!("Detecting hardware and analyzing kernel compatibility...\n");
 
         // Create hardware analyzer with privacy settings and configure tools/timeout
         let mut analyzer = HardwareAnalyzer::new(privacy)?;
+        analyzer.set_profile(profile);
+        if let Some(length) = digest_length {
+            analyzer.set_digest_length(length)?;
+        }
+        if let Some(key) = &pseudonym_key {
+            analyzer.use_pseudonym_key(key)?;
+        }
+        analyzer.set_capture_raw(save_raw.is_some());
+        if profile == DetectionProfile::Quick {
+            eprintln!("Using quick profile: skipping lshw and inxi");
+        }
 
         // Configure tool filtering if specified
         if let Some(tool_names) = &tools {
             analyzer.set_enabled_tools(tool_names.clone())?;
-            println!("Using only specified tools: {}", tool_names.join(", "));
+            eprintln!("Using only specified tools: {}", tool_names.join(", "));
         }
 
-        // Configure timeout if specified
-        analyzer.set_detection_timeout(Duration::from_secs(timeout));
-        if timeout != 30 {
-            println!("Using custom timeout: {}s per detector", timeout);
+        // Configure timeout if specified; the quick profile brings its own
+        match timeout {
+            Some(timeout) => {
+                analyzer.set_detection_timeout(Duration::from_secs(timeout));
+                eprintln!("Using custom timeout: {}s per detector", timeout);
+            }
+            None if profile == DetectionProfile::Full => {
+                analyzer.set_detection_timeout(Duration::from_secs(30));
+            }
+            None => {}
+        }
+
+        if let Some(policy) = retry_policy {
+            analyzer.set_retry_policy(None, policy)?;
         }
 
         // Run complete analysis
-        let report = analyzer.analyze_system().await?;
+        let mut report = analyzer.analyze_system().await?;
+
+        if let Some(path) = &save_raw {
+            crate::detectors::raw::write_bundle(path, &analyzer.take_raw_outputs(), privacy)?;
+            eprintln!("Raw tool output saved to: {:?}", path);
+        }
+
+        if annotate_interactive {
+            notes.extend(prompt_for_notes(&report)?);
+        }
+        crate::hardware::notes::annotate(&mut report, notes)?;
+
+        let redacted = redactor.apply(&mut report)?;
+        if !redacted.is_empty() {
+            eprintln!("Redacted {} field(s): {}", redacted.len(), redacted.join(", "));
+        }
+
+        // Keep the report small by moving oversized payloads into sidecar blobs
+        if let (Some(threshold), Some(path)) = (externalize_over, output.as_deref()) {
+            if !crate::hardware::is_stdin_path(path) {
+                let store = crate::hardware::blobs::BlobStore::for_report(path);
+                let externalized = report.externalize_blobs(&store, threshold)?;
+                if externalized > 0 {
+                    eprintln!("Stored {} oversized payload(s) as blobs", externalized);
+                }
+            }
+        }
+
+        // Parser failure reporting is strictly opt-in
+        if let Some(target) = telemetry {
+            let failures = analyzer.take_parse_failures();
+            if !failures.is_empty() {
+                let log_path = SubmissionLog::default_path();
+                match report_parse_failures(failures, &target, &log_path).await {
+                    Ok(reported) => {
+                        log::info!("Reported {} parser failure(s)", reported.len())
+                    }
+                    Err(e) => log::warn!("Parser failure reporting failed: {}", e),
+                }
+            }
+        }
+
+        // Report only what changed since the previous snapshot
+        if let Some(history) = history {
+            return match history.record_changes(&report)? {
+                None => {
+                    eprintln!(
+                        "No previous snapshot in {}; recorded this run as the baseline",
+                        history.dir().display()
+                    );
+                    Ok(())
+                }
+                Some(diff) if diff.is_empty() => {
+                    eprintln!("No hardware changes since the last snapshot");
+                    Ok(())
+                }
+                Some(diff) => write_output(&diff.render(format)?, output.as_deref()),
+            };
+        }
 
         // Render output
-        let renderer = OutputRenderer::new(format);
+        if terminal {
+            if no_anonymize {
+                log::warn!(
+                    "Privacy anonymization disabled - report contains identifying information"
+                );
+            }
+            print!("{}", TerminalRenderer::for_stdout().render(&report));
+            return Ok(());
+        }
+        let mut renderer = OutputRenderer::new(format);
+        if canonical {
+            renderer = renderer.canonical();
+        }
         let output_content = if no_anonymize {
             log::warn!("Privacy anonymization disabled - report contains identifying information");
             renderer.render_debug(&report)?
@@ -296,70 +1083,209 @@ impl CliHandler {
             renderer.render(&report)?
         };
 
-        // Write to file or stdout
-        match output {
-            Some(path) => {
-                std::fs::write(&path, output_content).map_err(LxHwError::IoError)?;
-                println!("Hardware report saved to: {:?}", path);
-            }
-            None => {
-                println!("{}", output_content);
-            }
+        write_output(&output_content, output.as_deref())
+    }
+
+    /// Handle the render command
+    fn handle_render(
+        &self,
+        input: &std::path::Path,
+        format: OutputFormat,
+        output: Option<PathBuf>,
+        canonical: bool,
+        salt_context: Option<&str>,
+    ) -> Result<()> {
+        use crate::hardware::HardwareReport;
+        use crate::output::OutputRenderer;
+
+        let mut report = HardwareReport::load(input)?;
+        if let Some(context) = salt_context {
+            apply_salt_context(&mut report, context)?;
+        }
+        let mut renderer = OutputRenderer::new(format);
+        if canonical {
+            renderer = renderer.canonical();
         }
+        let output_content = renderer.render(&report)?;
 
-        Ok(())
+        write_output(&output_content, output.as_deref())
     }
 
     /// Handle the check command
-    async fn handle_check(&self, detailed: bool) -> Result<()> {
-        use crate::detectors::DetectorRegistry;
+    async fn handle_check(&self, detailed: bool, format: CheckFormat) -> Result<()> {
+        use crate::detectors::sources::EnvironmentCheck;
+        use crate::detectors::{DetectorRegistry, Privileges};
+
+        let registry = DetectorRegistry::new();
+        if format == CheckFormat::Json {
+            let check = EnvironmentCheck::run(&registry).await;
+            println!("{}", serde_json::to_string_pretty(&check)?);
+            return Ok(());
+        }
 
         log::info!("Checking hardware detection tool availability...");
         println!("Checking availability of hardware detection tools...\n");
 
-        let registry = DetectorRegistry::new();
-        let detectors = registry.list_detectors();
+        let tools = crate::detectors::sources::check_tools(&registry).await;
+        let total_count = tools.len();
+        let available_count = tools.iter().filter(|tool| tool.available).count();
+        let external_count = tools.iter().filter(|tool| tool.available && !tool.builtin).count();
 
-        let mut available_count = 0;
-        let total_count = detectors.len();
-
-        for detector in detectors {
-            let is_available = detector.is_available().await;
-            let status = if is_available { "✓ Available" } else { "✗ Not found" };
+        for tool in &tools {
+            let status = if tool.builtin {
+                "✓ Available (built in)"
+            } else if tool.available {
+                "✓ Available"
+            } else {
+                "✗ Not found"
+            };
 
-            println!("{:<12} {}", detector.name(), status);
+            println!("{:<12} {}", tool.name, status);
 
-            if detailed && is_available {
-                println!("    Timeout: {:?}", detector.timeout());
+            if detailed && tool.available {
+                if let Some(path) = &tool.path {
+                    println!("    Path: {}", path.display());
+                }
+                if let Some(version) = &tool.version {
+                    println!("    Version: {}", version);
+                }
+                let privileges = match tool.privileges {
+                    Privileges::None => None,
+                    Privileges::RootRecommended => Some("root for full detail"),
+                    Privileges::RootRequired => Some("root required"),
+                };
+                match (privileges, tool.missing_capabilities.is_empty()) {
+                    (None, _) => {}
+                    (Some(privileges), true) => println!("    Privileges: {} (held)", privileges),
+                    (Some(privileges), false) => println!(
+                        "    Privileges: {} (missing {}; see --escalate)",
+                        privileges,
+                        tool.missing_capabilities.join(", ")
+                    ),
+                }
+                println!("    Timeout: {}s", tool.timeout_secs);
                 println!();
             }
-
-            if is_available {
-                available_count += 1;
-            }
         }
 
         println!("\nSummary: {}/{} detection tools available", available_count, total_count);
 
-        if available_count == 0 {
-            println!("Warning: No hardware detection tools found. Install lshw, dmidecode, lspci, lsusb, or inxi for hardware detection.");
+        if external_count == 0 {
+            println!("Warning: No hardware detection tools found; only the built-in sysfs detection will run. Install lshw, dmidecode, lspci, lsusb, or inxi for more detailed reports.");
         } else if available_count < total_count {
             println!("Note: Install missing tools for more comprehensive hardware detection.");
         }
 
+        self.display_data_source_checks(detailed).await;
+
         Ok(())
     }
 
+    /// Handle the naming command
+    fn handle_naming(
+        &self,
+        generate: Option<NamingRuleStyle>,
+        output: Option<PathBuf>,
+    ) -> Result<()> {
+        use crate::detectors::naming::PersistentNames;
+
+        let names = PersistentNames::read();
+
+        println!("Network interfaces:");
+        if names.interfaces.is_empty() {
+            println!("  (none)");
+        }
+        for (interface, stable) in names.stable_interface_names() {
+            println!(
+                "  {:<16} {}  {:<14} {}",
+                interface.name,
+                interface.mac_address,
+                interface.bus_address.as_deref().unwrap_or("-"),
+                interface.driver.as_deref().unwrap_or("-")
+            );
+            if stable != interface.name {
+                println!("    Kernel name, pin as: {}", stable);
+            }
+        }
+
+        println!("\nDisks:");
+        if names.disks.is_empty() {
+            println!("  (none)");
+        }
+        for disk in &names.disks {
+            println!("  {}", disk.name);
+            for link in &disk.by_id {
+                println!("    /dev/disk/by-id/{}", link);
+            }
+            for link in &disk.by_path {
+                println!("    /dev/disk/by-path/{}", link);
+            }
+        }
+
+        let Some(style) = generate else {
+            return Ok(());
+        };
+        let files = names.rule_files(style);
+        match output {
+            Some(dir) => {
+                std::fs::create_dir_all(&dir)?;
+                println!("\nWrote {} file(s) to {}, install as:", files.len(), dir.display());
+                for file in &files {
+                    let name = std::path::Path::new(&file.path)
+                        .file_name()
+                        .expect("generated rule paths end in a file name");
+                    std::fs::write(dir.join(name), &file.content)?;
+                    println!("  {} -> {}", name.to_string_lossy(), file.path);
+                }
+            }
+            None => {
+                for file in &files {
+                    println!("\n# {}\n{}", file.path, file.content);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Display availability of kernel data sources used during analysis
+    async fn display_data_source_checks(&self, detailed: bool) {
+        use crate::detectors::sources::{check_data_sources, SourceStatus};
+
+        println!("\nChecking kernel data sources...\n");
+
+        for check in check_data_sources().await {
+            let status = match check.status {
+                SourceStatus::Available => "✓ Available",
+                SourceStatus::Degraded => "! Degraded",
+                SourceStatus::Unavailable => "✗ Unavailable",
+            };
+
+            println!("{:<14} {}", check.name, status);
+
+            if detailed || check.status != SourceStatus::Available {
+                println!("    {}", check.detail);
+            }
+            if let Some(remediation) = &check.remediation {
+                println!("    Fix: {}", remediation);
+            }
+        }
+    }
+
     /// Handle the analyze command
+    #[allow(clippy::too_many_arguments)]
     async fn handle_analyze(
         &self,
         device: Option<String>,
+        report: Option<PathBuf>,
         kernel_source: bool,
         kernel_repo: Option<PathBuf>,
         recommendations: bool,
+        open_issues: bool,
+        privacy: PrivacyLevel,
     ) -> Result<()> {
         use crate::detectors::kernel::KernelSupportVerifier;
         use crate::detectors::kernel_source::KernelSourceAnalyzer;
+        use crate::detectors::packages::PackageChecker;
 
         println!("Analyzing kernel hardware support...\n");
 
@@ -374,6 +1300,8 @@ impl CliHandler {
         };
 
         // Get device IDs to analyze
+        let single_device = device.is_some();
+        let mut source_report = None;
         let device_ids = if let Some(device_filter) = device {
             if let Some((vendor, device)) = device_filter.split_once(':') {
                 vec![(vendor.to_string(), device.to_string())]
@@ -381,6 +1309,12 @@ impl CliHandler {
                 println!("Error: Device format should be vendor:device (e.g., '8086:1234')");
                 return Ok(());
             }
+        } else if let Some(report_path) = report {
+            let report = crate::hardware::HardwareReport::load(&report_path)?;
+            println!("Reading devices from report...");
+            let device_ids = report_device_ids(&report);
+            source_report = Some(report);
+            device_ids
         } else {
             println!("Scanning system for PCI devices...");
             verifier.extract_system_device_ids().unwrap_or_else(|e| {
@@ -398,10 +1332,48 @@ impl CliHandler {
 
         // Analyze kernel support
         let support_data = verifier.get_support_data(device_ids)?;
-        let user_recommendations = verifier.generate_user_recommendations(&support_data);
+        let mut user_recommendations = verifier.generate_user_recommendations(&support_data);
+
+        // USB devices have no PCI aliases; suggest out-of-tree drivers for the
+        // ones no driver has claimed
+        if !single_device {
+            let usb_ids: Vec<String> = match &source_report {
+                Some(report) => report
+                    .unknown_devices
+                    .iter()
+                    .filter(|d| d.bus == "usb")
+                    .map(|d| format!("{}:{}", d.vendor_id, d.device_id).to_lowercase())
+                    .collect(),
+                None => verifier.system_unclaimed_usb_device_ids(),
+            };
+            user_recommendations
+                .module_actions
+                .extend(usb_ids.iter().filter_map(|id| verifier.out_of_tree_action("usb", id)));
+        }
+
+        // Package names can only be checked against this machine's repositories
+        if source_report.is_none() {
+            if let Some(mut checker) = PackageChecker::detect() {
+                user_recommendations.verify_packages(|package| checker.availability(package));
+            }
+        }
+
+        // Class codes label devices without a driver
+        let device_classes = match &source_report {
+            Some(report) => report
+                .unknown_devices
+                .iter()
+                .filter(|d| d.bus == "pci")
+                .filter_map(|d| {
+                    let id = format!("{}:{}", d.vendor_id, d.device_id).to_lowercase();
+                    Some((id, d.class_code.clone()?))
+                })
+                .collect(),
+            None => verifier.system_device_classes(),
+        };
 
         // Display results
-        self.display_kernel_analysis(&support_data, &user_recommendations);
+        self.display_kernel_analysis(&support_data, &user_recommendations, &device_classes);
 
         // Perform kernel source analysis if requested
         if kernel_source {
@@ -437,14 +1409,93 @@ impl CliHandler {
             self.display_upgrade_recommendations(&user_recommendations);
         }
 
+        if open_issues {
+            self.offer_device_issues(&support_data, source_report.as_ref(), privacy).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Offer to open database issues for unsupported devices
+    ///
+    /// Kernel log lines are sanitized at the report's privacy level, or at
+    /// `privacy` for this system, and shown before anything is posted.
+    #[cfg(feature = "github-submit")]
+    async fn offer_device_issues(
+        &self,
+        support_data: &crate::detectors::kernel::KernelSupportData,
+        source_report: Option<&crate::hardware::HardwareReport>,
+        privacy: PrivacyLevel,
+    ) -> Result<()> {
+        use crate::detectors::integration::{
+            detect_distribution, dmesg_lines_for, local_hostname, read_kernel_log,
+        };
+        use crate::github_submit::{setup_github_config, GitHubSubmitter, UnsupportedDeviceIssue};
+        use crate::hardware::lifecycle::LifecycleDatabase;
+
+        // Reports carry their own kernel log excerpts; otherwise read this system's
+        let (distribution, kernel_log, privacy, hostname) = match source_report {
+            Some(report) => (
+                report.system.distribution.clone(),
+                Vec::new(),
+                report.metadata.privacy_level,
+                None,
+            ),
+            None if privacy == PrivacyLevel::Strict => {
+                (detect_distribution(), Vec::new(), privacy, None)
+            }
+            None => (detect_distribution(), read_kernel_log(), privacy, local_hostname()),
+        };
+        let mut issues = UnsupportedDeviceIssue::for_unsupported_devices(
+            support_data,
+            &LifecycleDatabase::builtin(),
+            distribution.as_deref(),
+            |device_id| match source_report {
+                Some(report) => report
+                    .unknown_devices
+                    .iter()
+                    .find(|d| {
+                        format!("{}:{}", d.vendor_id, d.device_id).eq_ignore_ascii_case(device_id)
+                    })
+                    .map(|d| d.dmesg_lines.clone())
+                    .unwrap_or_default(),
+                None => dmesg_lines_for(&kernel_log, &[format!("[{}]", device_id).as_str()]),
+            },
+        );
+        UnsupportedDeviceIssue::sanitize_dmesg_lines(&mut issues, privacy, hostname.as_deref())?;
+
+        if issues.is_empty() {
+            println!("\nNo unsupported devices need a new database issue.");
+            return Ok(());
+        }
+
+        println!("\n=== DATABASE ISSUES ===");
+        println!("{} unsupported device(s) are not covered by a known driver.", issues.len());
+        let submitter = GitHubSubmitter::new(setup_github_config(None, None).await?);
+        submitter.open_device_issues(&issues).await?;
         Ok(())
     }
 
+    /// Offer to open database issues for unsupported devices
+    #[cfg(not(feature = "github-submit"))]
+    async fn offer_device_issues(
+        &self,
+        _support_data: &crate::detectors::kernel::KernelSupportData,
+        _source_report: Option<&crate::hardware::HardwareReport>,
+        _privacy: PrivacyLevel,
+    ) -> Result<()> {
+        Err(LxHwError::ConfigError(
+            "lx-hw-detect was built without GitHub submission; rebuild with `--features github-submit`"
+                .to_string(),
+        ))
+    }
+
     /// Display kernel analysis results
     fn display_kernel_analysis(
         &self,
         support_data: &crate::detectors::kernel::KernelSupportData,
         recommendations: &crate::detectors::kernel::UserRecommendations,
+        device_classes: &std::collections::HashMap<String, String>,
     ) {
         println!("=== KERNEL SUPPORT ANALYSIS ===");
         println!("Kernel Version: {}", support_data.kernel_version);
@@ -454,9 +1505,16 @@ impl CliHandler {
 
         if recommendations.unsupported_devices > 0 {
             println!("\nUnsupported Devices:");
-            for device in &support_data.supported_devices {
-                if device.support_level == crate::detectors::kernel::SupportLevel::Unsupported {
-                    println!("  {} - No driver found", device.device_id);
+            let unsupported = support_data.supported_devices.iter().filter(|device| {
+                device.support_level == crate::detectors::kernel::SupportLevel::Unsupported
+            });
+            for device in unsupported {
+                let class_name = device_classes.get(&device.device_id).map(String::as_str);
+                match class_name.and_then(pci_class::describe) {
+                    Some(class_name) => {
+                        println!("  {} ({}) - No driver found", device.device_id, class_name)
+                    }
+                    None => println!("  {} - No driver found", device.device_id),
                 }
             }
         }
@@ -468,6 +1526,12 @@ impl CliHandler {
                 for cmd in &action.commands {
                     println!("    {}", cmd);
                 }
+                if matches!(
+                    action.action_type,
+                    crate::detectors::kernel::ActionType::InstallDkmsDriver
+                ) {
+                    println!("    {}", action.explanation);
+                }
             }
         }
 
@@ -498,6 +1562,75 @@ impl CliHandler {
         }
     }
 
+    /// Report IOMMU state, group memberships and VFIO passthrough candidates
+    fn handle_vfio_analysis(&self) {
+        use crate::detectors::iommu::{read_iommu, PassthroughKind};
+
+        let iommu = read_iommu(Path::new("/"));
+        println!("=== VFIO PASSTHROUGH ANALYSIS ===");
+        let vendor = match iommu.vendor {
+            Some(vendor) => format!(" ({:?})", vendor),
+            None => String::new(),
+        };
+        println!("IOMMU: {}{}", if iommu.enabled { "enabled" } else { "disabled" }, vendor);
+
+        if !iommu.groups.is_empty() {
+            println!("\nIOMMU Groups:");
+            for group in &iommu.groups {
+                println!("  Group {}:", group.id);
+                for device in &group.devices {
+                    let class_name = pci_class::describe(&device.class_code).unwrap_or_default();
+                    let driver = device.driver.as_deref().unwrap_or("no driver");
+                    println!(
+                        "    {} [{}] {} ({})",
+                        device.address, device.pci_id, class_name, driver
+                    );
+                }
+            }
+        }
+
+        let candidates = iommu.passthrough_candidates();
+        if !candidates.is_empty() {
+            println!("\nPassthrough Candidates:");
+        }
+        for candidate in &candidates {
+            let kind = match candidate.kind {
+                PassthroughKind::Gpu => "GPU",
+                PassthroughKind::Network => "NIC",
+            };
+            let status = if candidate.is_isolatable() { "isolatable" } else { "shares its group" };
+            println!(
+                "  {} {} [{}] in group {} - {}",
+                kind, candidate.device.address, candidate.device.pci_id, candidate.group, status
+            );
+            for companion in &candidate.companions {
+                println!("    Passed through with: {} [{}]", companion.address, companion.pci_id);
+            }
+            for conflict in &candidate.conflicts {
+                println!("    Also in group: {} [{}]", conflict.address, conflict.pci_id);
+            }
+            if candidate.device.boot_vga {
+                println!("    Note: the host console runs on this GPU");
+            }
+            if !candidate.is_isolatable() {
+                println!(
+                    "    Move the card to another slot or enable ACS in the firmware to split the group"
+                );
+            }
+        }
+
+        let parameters = iommu.vfio_parameters(&candidates);
+        println!("\nRecommended Kernel Parameters:");
+        for parameter in &parameters {
+            let argument = match &parameter.value {
+                Some(value) => format!("{}={}", parameter.parameter, value),
+                None => parameter.parameter.clone(),
+            };
+            let applied = if parameter.applied { " (already set)" } else { "" };
+            println!("  {}{} - {}", argument, applied, parameter.purpose);
+        }
+    }
+
     /// Display upgrade recommendations
     fn display_upgrade_recommendations(
         &self,
@@ -523,6 +1656,329 @@ impl CliHandler {
         }
     }
 
+    /// Handle the compare command
+    fn handle_compare(
+        &self,
+        old: &std::path::Path,
+        new: &std::path::Path,
+        format: OutputFormat,
+    ) -> Result<()> {
+        use crate::diff::diff_reports;
+        use crate::hardware::{is_stdin_path, HardwareReport};
+
+        if is_stdin_path(old) && is_stdin_path(new) {
+            return Err(LxHwError::InvalidInput {
+                message: "Only one of the compared reports can be read from stdin (-)".to_string(),
+            });
+        }
+
+        let old_report = HardwareReport::load(old)?;
+        let new_report = HardwareReport::load(new)?;
+
+        let diff = diff_reports(&old_report, &new_report);
+        println!("{}", diff.render(format)?);
+
+        Ok(())
+    }
+
+    /// Handle the genexamples command
+    fn handle_genexamples(&self, count: usize, seed: u64, output: &std::path::Path) -> Result<()> {
+        use crate::hardware::examples::ExampleGenerator;
+
+        let generator = ExampleGenerator::new(seed);
+        for index in 0..count {
+            let report = generator.report(index);
+            let dir = output.join(report.metadata.generated_at.format("%Y/%m").to_string());
+            std::fs::create_dir_all(&dir)?;
+            let path = dir.join(format!("example-{}-{:05}.json", seed, index));
+            std::fs::write(&path, serde_json::to_string_pretty(&report)?)?;
+        }
+
+        println!("Generated {} example reports in {}", count, output.display());
+        Ok(())
+    }
+
+    /// Handle the update-kernel-releases command
+    #[cfg(feature = "http")]
+    async fn handle_update_kernel_releases(
+        &self,
+        url: &str,
+        output: Option<PathBuf>,
+    ) -> Result<()> {
+        use crate::hardware::kernel_releases::KernelReleases;
+
+        let fetch_error = |e: reqwest::Error| LxHwError::SystemError {
+            message: format!("Failed to fetch {}: {}", url, e),
+        };
+        let json = reqwest::get(url)
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(fetch_error)?
+            .text()
+            .await
+            .map_err(fetch_error)?;
+
+        let path = output.unwrap_or_else(KernelReleases::default_path);
+        let releases = KernelReleases::from_kernel_org(
+            &json,
+            &KernelReleases::load(),
+            chrono::Utc::now().date_naive(),
+        )?;
+        releases.save(&path)?;
+
+        let longterm: Vec<&str> = releases.longterm.iter().map(|l| l.series.as_str()).collect();
+        println!(
+            "Kernel release data written to {}: stable {}, LTS {}",
+            path.display(),
+            releases.stable,
+            longterm.join(", ")
+        );
+        Ok(())
+    }
+
+    #[cfg(not(feature = "http"))]
+    async fn handle_update_kernel_releases(
+        &self,
+        _url: &str,
+        _output: Option<PathBuf>,
+    ) -> Result<()> {
+        Err(LxHwError::ConfigError(
+            "lx-hw-detect was built without HTTP support; rebuild with `--features http`"
+                .to_string(),
+        ))
+    }
+
+    /// Handle the browse command
+    #[cfg(feature = "tui")]
+    async fn handle_browse(
+        &self,
+        privacy: PrivacyLevel,
+        indices: &str,
+        report: Option<PathBuf>,
+    ) -> Result<()> {
+        use crate::detectors::integration::HardwareAnalyzer;
+        use crate::hardware::HardwareReport;
+        use crate::indexer::browse::IndexBrowser;
+
+        let browser = IndexBrowser::load(indices).await?;
+
+        // Local hardware only highlights entries, so detection failures are not fatal
+        let local_report = match report {
+            Some(path) => Some(HardwareReport::load(&path)?),
+            None => {
+                eprintln!("Detecting local hardware (quick profile)...");
+                let detected = match HardwareAnalyzer::new(privacy) {
+                    Ok(mut analyzer) => {
+                        analyzer.set_profile(DetectionProfile::Quick);
+                        analyzer.analyze_system().await
+                    }
+                    Err(e) => Err(e),
+                };
+                detected.map_err(|e| log::warn!("Local hardware detection failed: {}", e)).ok()
+            }
+        };
+        let local = local_report.map(|report| browser.local_matches(&report)).unwrap_or_default();
+
+        crate::tui::run(&browser, local)
+    }
+
+    /// Handle the browse command
+    #[cfg(not(feature = "tui"))]
+    async fn handle_browse(
+        &self,
+        _privacy: PrivacyLevel,
+        _indices: &str,
+        _report: Option<PathBuf>,
+    ) -> Result<()> {
+        Err(LxHwError::ConfigError(
+            "lx-hw-detect was built without terminal UI support; rebuild with `--features tui`"
+                .to_string(),
+        ))
+    }
+
+    /// Handle the watch command
+    #[cfg(feature = "indexer")]
+    async fn handle_watch(&self, watchlist: Option<PathBuf>, command: WatchCommands) -> Result<()> {
+        use crate::hardware::HardwareReport;
+        use crate::indexer::browse::{report_device_ids, IndexBrowser};
+        use crate::watch::{WatchState, Watchlist};
+
+        let path = watchlist.unwrap_or_else(Watchlist::default_path);
+        let mut list = Watchlist::load(&path)?;
+
+        match command {
+            WatchCommands::Add { mut device_ids, report, note } => {
+                if let Some(report) = report {
+                    device_ids.extend(report_device_ids(&HardwareReport::load(&report)?));
+                }
+                for device_id in &device_ids {
+                    if list.add(device_id, note.clone())? {
+                        println!("Watching {}", device_id.to_lowercase());
+                    }
+                }
+                list.save(&path)?;
+            }
+            WatchCommands::Remove { device_ids } => {
+                for device_id in &device_ids {
+                    if !list.remove(device_id) {
+                        eprintln!("{} is not on the watchlist", device_id);
+                    }
+                }
+                list.save(&path)?;
+            }
+            WatchCommands::List => {
+                if list.devices.is_empty() {
+                    println!("The watchlist ({}) is empty", path.display());
+                }
+                for device in &list.devices {
+                    match &device.note {
+                        Some(note) => println!("{}  {}", device.device_id, note),
+                        None => println!("{}", device.device_id),
+                    }
+                }
+            }
+            WatchCommands::Sync { indices, json, notify } => {
+                let browser = IndexBrowser::load(&indices).await?;
+                let state_path = WatchState::default_path();
+                let mut state = WatchState::load(&state_path);
+                let changes = state.sync(&list, &browser);
+                state.save(&state_path)?;
+
+                if json {
+                    println!("{}", serde_json::to_string_pretty(&changes)?);
+                } else if changes.is_empty() {
+                    println!("No changes for {} watched device(s)", list.devices.len());
+                } else {
+                    for change in &changes {
+                        println!("{}", change);
+                    }
+                }
+                if notify {
+                    crate::watch::notify(&changes)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Handle the watch command
+    #[cfg(not(feature = "indexer"))]
+    async fn handle_watch(
+        &self,
+        _watchlist: Option<PathBuf>,
+        _command: WatchCommands,
+    ) -> Result<()> {
+        Err(LxHwError::ConfigError(
+            "lx-hw-detect was built without the indexer; rebuild with `--features indexer`"
+                .to_string(),
+        ))
+    }
+
+    /// Handle the privacy command
+    fn handle_privacy(&self, command: PrivacyCommands) -> Result<()> {
+        use crate::privacy::contexts::SaltContexts;
+        use crate::privacy::testvectors::TestVectors;
+
+        match command {
+            PrivacyCommands::SelfTest { vectors, json } => {
+                let vectors = match vectors {
+                    Some(path) => TestVectors::load(&path)?,
+                    None => TestVectors::builtin(),
+                };
+                let report = vectors.run();
+
+                if json {
+                    let output = serde_json::to_string_pretty(&report)
+                        .map_err(|e| LxHwError::SerializationError(e.to_string()))?;
+                    println!("{}", output);
+                } else {
+                    self.display_self_test(&report);
+                }
+
+                if !report.passed() {
+                    return Err(LxHwError::PrivacyError(format!(
+                        "{} privacy test vectors failed",
+                        report.failures().count()
+                    )));
+                }
+                Ok(())
+            }
+            PrivacyCommands::Contexts => {
+                let store = SaltContexts::open_default();
+                let names = store.list()?;
+                if names.is_empty() {
+                    println!("No salt contexts yet; one is created on first use of --salt-context");
+                } else {
+                    for name in names {
+                        println!("{}", name);
+                    }
+                }
+                eprintln!("Salts are stored in {}", store.dir().display());
+                Ok(())
+            }
+        }
+    }
+
+    /// Display privacy test vector results
+    fn display_self_test(&self, report: &crate::privacy::testvectors::SelfTestReport) {
+        for result in &report.results {
+            let mark = if result.passed { "✓" } else { "✗" };
+            println!("{} {}: {}", mark, result.suite, result.name);
+            if let Some(failure) = &result.failure {
+                println!("    {}", failure);
+                println!("    expected: {}", result.expected);
+                println!("    actual:   {}", result.actual);
+            }
+        }
+        println!(
+            "\n{} of {} test vectors passed",
+            report.results.len() - report.failures().count(),
+            report.results.len()
+        );
+    }
+
+    /// Handle the privacy-audit command
+    fn handle_privacy_audit(&self, path: &std::path::Path, json: bool) -> Result<()> {
+        use crate::hardware::HardwareReport;
+        use crate::privacy::audit::PrivacyAudit;
+
+        // Raw values of this machine, in case the report was made here
+        let mut local = Vec::new();
+        if let Ok(hostname) = std::fs::read_to_string("/proc/sys/kernel/hostname") {
+            local.push((hostname.trim().to_string(), "hostname of this machine"));
+        }
+        if let Ok(user) = std::env::var("USER") {
+            local.push((user, "user name on this machine"));
+        }
+
+        let report = HardwareReport::load(path)?;
+        let audit = PrivacyAudit::run(&report, &local)?;
+        if json {
+            let output = serde_json::to_string_pretty(&audit)
+                .map_err(|e| LxHwError::SerializationError(e.to_string()))?;
+            println!("{}", output);
+            return Ok(());
+        }
+
+        println!(
+            "Privacy level {:?}, {}-character digests\n",
+            audit.privacy_level, audit.digest_length
+        );
+        println!("Anonymized ({}):", audit.anonymized.len());
+        for field in &audit.anonymized {
+            println!("  {:<40} {}", field.field, field.method);
+        }
+        println!("\nLeft out by detection ({}):", audit.discarded.len());
+        for discarded in &audit.discarded {
+            println!("  {:<12} {}", discarded.tool, discarded.identifier);
+        }
+        println!("\nPotentially identifying ({}):", audit.identifying.len());
+        for field in &audit.identifying {
+            println!("  {:<40} {:?} ({})", field.field, field.value, field.reason);
+        }
+        Ok(())
+    }
+
     /// Handle the config command
     async fn handle_config(&self, command: ConfigCommands) -> Result<()> {
         match command {
@@ -554,6 +2010,7 @@ impl CliHandler {
     }
 
     /// Handle the submit command
+    #[cfg(feature = "github-submit")]
     #[allow(clippy::too_many_arguments)]
     async fn handle_submit(
         &self,
@@ -565,21 +2022,58 @@ impl CliHandler {
         auto_fork: bool,
         tools: Option<Vec<String>>,
         _draft: bool,
+        salt_context: Option<String>,
+        indices: Option<String>,
+        generalize: bool,
+        relay: Option<String>,
+        proxy: Option<String>,
+        tor: bool,
         global: &GlobalOptions,
     ) -> Result<()> {
+        use crate::github_submit::relay::{RelayConfig, RelaySubmitter, TOR_PROXY};
         use crate::github_submit::{setup_github_config, GitHubSubmitter, SubmissionInfo};
+        use crate::hardware::is_stdin_path;
         use chrono::Utc;
         use std::fs;
         use tempfile::NamedTempFile;
 
-        println!("🚀 Starting automated GitHub submission...\n");
-
-        // Step 1: Setup GitHub configuration
-        let mut github_config = setup_github_config(github_username, github_token)?;
-        github_config.auto_fork = auto_fork;
+        // Step 1: Setup the relay or GitHub configuration
+        let relay_config = match relay.as_deref() {
+            Some(endpoint) => {
+                let proxy = if tor { Some(TOR_PROXY) } else { proxy.as_deref() };
+                Some(RelayConfig::new(endpoint, proxy)?)
+            }
+            None => None,
+        };
+        let github_config = if relay_config.is_some() {
+            println!("🚀 Starting anonymous relay submission...\n");
+            None
+        } else {
+            println!("🚀 Starting automated GitHub submission...\n");
+            if github_token.is_some() {
+                println!("⚠️  A token on the command line is visible in the process list and shell history; `lx-hw-detect auth login` keeps it in the system keyring instead\n");
+            }
+            let mut github_config = setup_github_config(github_username, github_token).await?;
+            github_config.auto_fork = auto_fork;
+            Some(github_config)
+        };
 
         // Step 2: Generate or use existing report
-        let report_path = if let Some(report_path) = report {
+        // Temporary report files must outlive the submission
+        let temp_file = NamedTempFile::new()
+            .map_err(|e| LxHwError::Io(format!("Failed to create temporary file: {}", e)))?;
+
+        let report_path = if let Some(report_path) = report.as_deref().filter(|p| is_stdin_path(p))
+        {
+            let hardware_report = crate::hardware::HardwareReport::load(report_path)?;
+            let report_json = serde_json::to_string_pretty(&hardware_report)
+                .map_err(|e| LxHwError::SerializationError(e.to_string()))?;
+
+            fs::write(temp_file.path(), report_json)
+                .map_err(|e| LxHwError::Io(format!("Failed to write report: {}", e)))?;
+
+            temp_file.path().to_path_buf()
+        } else if let Some(report_path) = report {
             if !report_path.exists() {
                 return Err(LxHwError::Validation(format!(
                     "Report file not found: {}",
@@ -590,10 +2084,6 @@ impl CliHandler {
         } else {
             println!("📊 No report file provided, generating hardware report...");
 
-            // Create temporary file for the report
-            let temp_file = NamedTempFile::new()
-                .map_err(|e| LxHwError::Io(format!("Failed to create temporary file: {}", e)))?;
-
             // Generate report using the detect functionality
             let mut registry = crate::detectors::DetectorRegistry::new();
 
@@ -621,15 +2111,47 @@ impl CliHandler {
             temp_file.path().to_path_buf()
         };
 
+        // Re-key identifiers for the destination's audience
+        let report_path = if let Some(context) = salt_context.as_deref() {
+            let mut hardware_report = crate::hardware::HardwareReport::load(&report_path)?;
+            apply_salt_context(&mut hardware_report, context)?;
+            let report_json = serde_json::to_string_pretty(&hardware_report)
+                .map_err(|e| LxHwError::SerializationError(e.to_string()))?;
+
+            fs::write(temp_file.path(), report_json)
+                .map_err(|e| LxHwError::Io(format!("Failed to write report: {}", e)))?;
+
+            println!("🔑 Identifiers re-keyed for salt context '{}'", context);
+            temp_file.path().to_path_buf()
+        } else {
+            report_path
+        };
+
+        // Warn about hardware combinations that would single the report out
+        let report_path = match indices.as_deref() {
+            Some(source) => {
+                let mut hardware_report = crate::hardware::HardwareReport::load(&report_path)?;
+                if check_anonymity(&mut hardware_report, source, generalize).await? {
+                    let report_json = serde_json::to_string_pretty(&hardware_report)
+                        .map_err(|e| LxHwError::SerializationError(e.to_string()))?;
+
+                    fs::write(temp_file.path(), report_json)
+                        .map_err(|e| LxHwError::Io(format!("Failed to write report: {}", e)))?;
+
+                    temp_file.path().to_path_buf()
+                } else {
+                    report_path
+                }
+            }
+            None => report_path,
+        };
+
         // Step 3: Get description if not provided
         let description = if let Some(desc) = description {
             desc
         } else {
             // Extract basic system info for default description
-            let content = fs::read_to_string(&report_path)
-                .map_err(|e| LxHwError::Io(format!("Failed to read report: {}", e)))?;
-
-            let report: crate::hardware::HardwareReport = serde_json::from_str(&content)
+            let report = crate::hardware::HardwareReport::load(&report_path)
                 .map_err(|e| LxHwError::Validation(format!("Invalid report format: {}", e)))?;
 
             let cpu_info = report
@@ -662,12 +2184,28 @@ impl CliHandler {
             tools_used: Vec::new(), // Will be populated from report
         };
 
-        // Step 5: Submit to GitHub
-        let mut submitter = GitHubSubmitter::new(github_config);
-        let pr_url = submitter.submit_report(submission, yes).await?;
+        // Step 5: Submit through the relay or to GitHub
+        let (pr_url, submission_id) = match (relay_config, github_config) {
+            (Some(relay_config), _) => {
+                let receipt =
+                    RelaySubmitter::new(relay_config)?.submit_report(submission, yes).await?;
+                (receipt.pull_request_url, Some(receipt.submission_id))
+            }
+            (None, Some(github_config)) => {
+                let mut submitter = GitHubSubmitter::new(github_config);
+                (Some(submitter.submit_report(submission, yes).await?), None)
+            }
+            (None, None) => unreachable!("either the relay or GitHub is configured"),
+        };
 
         println!("\n🎉 Submission completed successfully!");
-        println!("📋 Pull Request: {}", pr_url);
+        if let Some(submission_id) = submission_id {
+            println!("📨 Submission ID: {}", submission_id);
+        }
+        match pr_url {
+            Some(pr_url) => println!("📋 Pull Request: {}", pr_url),
+            None => println!("📋 The relay will open a pull request for the report"),
+        }
         println!("\nNext steps:");
         println!("1. Your submission will be automatically validated");
         println!("2. Community members will review your hardware report");
@@ -676,10 +2214,379 @@ impl CliHandler {
 
         Ok(())
     }
+
+    /// Handle the configure command
+    #[allow(clippy::too_many_arguments)]
+    async fn handle_configure(
+        &self,
+        privacy: PrivacyLevel,
+        report: Option<PathBuf>,
+        distribution: Option<String>,
+        target: Option<ConfigurationTarget>,
+        format: ConfigureFormat,
+        persistent_names: Option<NamingRuleStyle>,
+        output: Option<PathBuf>,
+    ) -> Result<()> {
+        use crate::configuration::engine::ConfigurationEngineImpl;
+        use crate::configuration::guide::{ConfigurationGuide, GuideFormat};
+        use crate::configuration::ConfigurationEngine;
+        use crate::detectors::naming::PersistentNames;
+
+        let hardware = load_or_scan_report(privacy, report).await?;
+        let distribution = target_distribution(
+            &hardware,
+            distribution.or_else(|| target.and_then(|t| t.distribution()).map(str::to_string)),
+        )?;
+
+        let mut engine = ConfigurationEngineImpl::new()?;
+        if let Some(style) = persistent_names {
+            engine.enable_persistent_naming(PersistentNames::read(), style);
+        }
+        let rendered = if let Some(target) = target {
+            engine.export(&hardware, target, &distribution)?
+        } else {
+            let configuration = engine.generate_configuration(&hardware, &distribution)?;
+            match format {
+                ConfigureFormat::Json => serde_json::to_string_pretty(&configuration)
+                    .map_err(|e| LxHwError::SerializationError(e.to_string()))?,
+                ConfigureFormat::Markdown => {
+                    ConfigurationGuide::new(&configuration).render(GuideFormat::Markdown)
+                }
+                ConfigureFormat::Html => {
+                    ConfigurationGuide::new(&configuration).render(GuideFormat::Html)
+                }
+            }
+        };
+
+        match output {
+            Some(path) => {
+                std::fs::write(&path, rendered)?;
+                eprintln!("Configuration written to {}", path.display());
+            }
+            None => print!("{}", rendered),
+        }
+        Ok(())
+    }
+
+    /// Handle the drift command
+    async fn handle_drift(
+        &self,
+        privacy: PrivacyLevel,
+        report: Option<PathBuf>,
+        distribution: Option<String>,
+        root: &Path,
+        json: bool,
+    ) -> Result<()> {
+        use crate::configuration::audit::TuningAuditor;
+        use crate::configuration::engine::ConfigurationEngineImpl;
+        use crate::configuration::ConfigurationEngine;
+
+        let hardware = load_or_scan_report(privacy, report).await?;
+        let distribution = target_distribution(&hardware, distribution)?;
+        let configuration =
+            ConfigurationEngineImpl::new()?.generate_configuration(&hardware, &distribution)?;
+        let drift = TuningAuditor::with_root(root).audit(&configuration.kernel_parameters)?;
+
+        if json {
+            let output = serde_json::to_string_pretty(&drift)
+                .map_err(|e| LxHwError::SerializationError(e.to_string()))?;
+            println!("{}", output);
+        } else {
+            print!("{}", drift.to_markdown());
+        }
+        Ok(())
+    }
+
+    /// Handle the apply command
+    fn handle_apply(
+        &self,
+        configuration: Option<PathBuf>,
+        yes: bool,
+        dry_run: bool,
+        rollback: bool,
+        journal: Option<PathBuf>,
+        root: PathBuf,
+    ) -> Result<()> {
+        use crate::configuration::apply::{self, ConfigurationApplier};
+
+        let mut stdout = std::io::stdout();
+        let journal = journal.unwrap_or_else(|| apply::default_journal_path(&root));
+        if rollback {
+            apply::rollback(&root, &journal, &mut stdout)?;
+            return Ok(());
+        }
+
+        let path = configuration.ok_or_else(|| LxHwError::InvalidInput {
+            message: "No configuration to apply".to_string(),
+        })?;
+        let configuration = ConfigurationApplier::load_configuration(&path)?;
+        ConfigurationApplier::new(&configuration)
+            .root(root)
+            .journal_path(journal)
+            .assume_yes(yes)
+            .dry_run(dry_run)
+            .apply(&mut std::io::stdin().lock(), &mut stdout)?;
+        Ok(())
+    }
+
+    /// Handle the auth command
+    #[cfg(feature = "github-submit")]
+    async fn handle_auth(&self, command: AuthCommands) -> Result<()> {
+        use crate::github_submit::{device_flow, keyring, login};
+        use std::io::Read;
+
+        match command {
+            AuthCommands::Login { github_username, with_token } => {
+                let token = if with_token {
+                    let mut input = String::new();
+                    std::io::stdin()
+                        .read_to_string(&mut input)
+                        .map_err(|e| LxHwError::Io(format!("Failed to read token: {}", e)))?;
+                    Some(input.trim().to_string()).filter(|token| !token.is_empty())
+                } else {
+                    None
+                };
+                if with_token && token.is_none() {
+                    return Err(LxHwError::InvalidInput {
+                        message: "No token on stdin".to_string(),
+                    });
+                }
+
+                let login = login(github_username, token).await?;
+                keyring::store_token(&login.username, &login.token)?;
+                println!(
+                    "✅ Logged in to GitHub as {}; the token is kept in the system keyring",
+                    login.username
+                );
+                Ok(())
+            }
+            AuthCommands::Logout => {
+                if keyring::delete_token()? {
+                    println!("Removed the stored GitHub token from the system keyring");
+                    println!("Revoke the authorization at https://github.com/settings/applications if it is no longer needed");
+                } else {
+                    println!("No GitHub token is stored");
+                }
+                Ok(())
+            }
+            AuthCommands::Status => {
+                let Some(stored) = keyring::load_token()? else {
+                    println!("Not logged in. Run `lx-hw-detect auth login` to authorize a GitHub account.");
+                    return Ok(());
+                };
+                match device_flow::authenticated_user(&stored.token).await {
+                    Ok(account) => println!(
+                        "✅ Logged in to GitHub as {} (token in the system keyring)",
+                        account
+                    ),
+                    Err(e) => {
+                        println!(
+                            "⚠️  The stored token for {} does not work: {}",
+                            stored.username, e
+                        );
+                        println!("Run `lx-hw-detect auth login` to authorize again.");
+                    }
+                }
+                Ok(())
+            }
+        }
+    }
+
+    /// Handle the auth command
+    #[cfg(not(feature = "github-submit"))]
+    async fn handle_auth(&self, _command: AuthCommands) -> Result<()> {
+        Err(LxHwError::ConfigError(
+            "lx-hw-detect was built without GitHub submission; rebuild with `--features github-submit`"
+                .to_string(),
+        ))
+    }
+
+    /// Handle the submit command
+    #[cfg(not(feature = "github-submit"))]
+    #[allow(clippy::too_many_arguments)]
+    async fn handle_submit(
+        &self,
+        _github_username: Option<String>,
+        _github_token: Option<String>,
+        _report: Option<PathBuf>,
+        _description: Option<String>,
+        _yes: bool,
+        _auto_fork: bool,
+        _tools: Option<Vec<String>>,
+        _draft: bool,
+        _salt_context: Option<String>,
+        _indices: Option<String>,
+        _generalize: bool,
+        _relay: Option<String>,
+        _proxy: Option<String>,
+        _tor: bool,
+        _global: &GlobalOptions,
+    ) -> Result<()> {
+        Err(LxHwError::ConfigError(
+            "lx-hw-detect was built without GitHub submission; rebuild with `--features github-submit`"
+                .to_string(),
+        ))
+    }
+}
+
+/// Write command output to a file, or to stdout when no path or `-` is given
+fn write_output(content: &str, output: Option<&std::path::Path>) -> Result<()> {
+    match output.filter(|path| !crate::hardware::is_stdin_path(path)) {
+        Some(path) => {
+            std::fs::write(path, content).map_err(LxHwError::IoError)?;
+            eprintln!("Hardware report saved to: {:?}", path);
+        }
+        None => {
+            println!("{}", content);
+        }
+    }
+
+    Ok(())
+}
+
+/// Report to generate a configuration for: the given one, or a scan of this system
+async fn load_or_scan_report(
+    privacy: PrivacyLevel,
+    report: Option<PathBuf>,
+) -> Result<crate::hardware::HardwareReport> {
+    match report {
+        Some(path) => crate::hardware::HardwareReport::load(&path),
+        None => {
+            let mut analyzer = crate::detectors::integration::HardwareAnalyzer::new(privacy)?;
+            analyzer.analyze_system().await
+        }
+    }
+}
+
+/// Distribution to generate a configuration for, defaulting to the report's
+fn target_distribution(
+    report: &crate::hardware::HardwareReport,
+    distribution: Option<String>,
+) -> Result<String> {
+    distribution.or_else(|| report.system.distribution.clone()).ok_or_else(|| {
+        LxHwError::InvalidInput {
+            message: "The report does not name a distribution; pass --distribution".to_string(),
+        }
+    })
+}
+
+/// Ask on the terminal for notes on a report's components
+///
+/// Prompts go to stderr so stdout carries only the report.
+fn prompt_for_notes(report: &crate::hardware::HardwareReport) -> Result<Vec<UserNote>> {
+    use std::io::{BufRead, Write};
+
+    let keys = crate::hardware::notes::component_keys(report);
+    eprintln!("Components you can add a note to:");
+    for (key, name) in &keys {
+        eprintln!("  {:<12} {}", key, name);
+    }
+
+    let mut stdin = std::io::stdin().lock();
+    let mut read = |prompt: &str| -> Result<String> {
+        eprint!("{}", prompt);
+        std::io::stderr().flush()?;
+        let mut line = String::new();
+        stdin.read_line(&mut line)?;
+        Ok(line.trim().to_string())
+    };
+
+    let mut notes = Vec::new();
+    loop {
+        let component = read("Component key (empty to finish): ")?.to_ascii_lowercase();
+        if component.is_empty() {
+            return Ok(notes);
+        }
+        if !keys.contains_key(&component) {
+            eprintln!("No component with key '{}'", component);
+            continue;
+        }
+        let note = UserNote { component, note: read("Note: ")? };
+        match note.check() {
+            Ok(()) => notes.push(note),
+            Err(e) => eprintln!("{}; the note was not added", e),
+        }
+    }
+}
+
+/// Estimate how many published reports share `report`'s hardware, warning
+/// when it falls below [`DEFAULT_MIN_ANONYMITY`] and generalizing the report
+/// if asked; returns whether the report was changed
+///
+/// [`DEFAULT_MIN_ANONYMITY`]: crate::validation::privacy::DEFAULT_MIN_ANONYMITY
+#[cfg(all(feature = "github-submit", feature = "indexer"))]
+async fn check_anonymity(
+    report: &mut crate::hardware::HardwareReport,
+    indices: &str,
+    generalize: bool,
+) -> Result<bool> {
+    use crate::indexer::browse::IndexBrowser;
+    use crate::validation::privacy::{
+        estimate_anonymity, generalize_report, DEFAULT_MIN_ANONYMITY,
+    };
+
+    let browser = IndexBrowser::load(indices).await?;
+    let estimate = estimate_anonymity(&browser.report_counts(report), DEFAULT_MIN_ANONYMITY);
+    let Some(warning) = estimate.warning(DEFAULT_MIN_ANONYMITY) else {
+        println!("🔒 At least {} published reports share this hardware", estimate.k);
+        return Ok(false);
+    };
+
+    println!("⚠️  {}", warning);
+    if !generalize {
+        println!("   Use --generalize to make this report harder to link to others");
+        return Ok(false);
+    }
+    generalize_report(report).map_err(|e| LxHwError::PrivacyError(e.to_string()))?;
+    println!("🔑 Serial and MAC digests re-keyed and DIMM manufacturers dropped");
+    Ok(true)
+}
+
+#[cfg(all(feature = "github-submit", not(feature = "indexer")))]
+async fn check_anonymity(
+    _report: &mut crate::hardware::HardwareReport,
+    _indices: &str,
+    _generalize: bool,
+) -> Result<bool> {
+    Err(LxHwError::ConfigError(
+        "lx-hw-detect was built without the indexer; rebuild with `--features indexer` to use --indices"
+            .to_string(),
+    ))
+}
+
+/// Re-key a report's identifiers for a named salt context
+fn apply_salt_context(report: &mut crate::hardware::HardwareReport, context: &str) -> Result<()> {
+    use crate::privacy::contexts::SaltContexts;
+    use crate::privacy::PrivacyManager;
+
+    let mut manager = PrivacyManager::new(report.metadata.privacy_level)?;
+    manager.load_salt_context(&SaltContexts::open_default(), context)?;
+    manager.apply_salt_context(report, context)
+}
+
+/// Collect PCI device IDs referenced by a hardware report
+fn report_device_ids(report: &crate::hardware::HardwareReport) -> Vec<(String, String)> {
+    let kernel_ids = report
+        .kernel_support
+        .iter()
+        .flat_map(|support| &support.device_support_details)
+        .map(|device| device.device_id.as_str());
+    let graphics_ids = report.graphics.iter().map(|device| device.pci_id.as_str());
+
+    let mut device_ids: Vec<(String, String)> = kernel_ids
+        .chain(graphics_ids)
+        .filter_map(|id| id.split_once(':'))
+        .map(|(vendor, device)| (vendor.to_lowercase(), device.to_lowercase()))
+        .collect();
+
+    device_ids.sort();
+    device_ids.dedup();
+    device_ids
 }
 
 /// Application configuration
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
 pub struct AppConfig {
     /// Default privacy level
     pub privacy_level: PrivacyLevel,
@@ -692,6 +2599,7 @@ pub struct AppConfig {
 }
 
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
 pub struct ToolConfig {
     /// Default timeout for tools in seconds
     pub timeout: u64,
@@ -704,6 +2612,7 @@ pub struct ToolConfig {
 }
 
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
 pub struct ToolSettings {
     /// Whether this tool is enabled
     pub enabled: bool,
@@ -714,6 +2623,7 @@ pub struct ToolSettings {
 }
 
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
 pub struct PrivacyConfig {
     /// Custom salt rotation period in hours
     pub salt_rotation_hours: Option<u64>,
@@ -721,6 +2631,9 @@ pub struct PrivacyConfig {
     pub preserve_vendor: bool,
     /// Whether to preserve device model information
     pub preserve_model: bool,
+    /// Redaction rules applied to every generated report
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub redact: Vec<RedactionRule>,
 }
 
 impl Default for AppConfig {
@@ -755,7 +2668,12 @@ impl Default for ToolSettings {
 
 impl Default for PrivacyConfig {
     fn default() -> Self {
-        Self { salt_rotation_hours: None, preserve_vendor: true, preserve_model: true }
+        Self {
+            salt_rotation_hours: None,
+            preserve_vendor: true,
+            preserve_model: true,
+            redact: Vec::new(),
+        }
     }
 }
 
@@ -777,19 +2695,30 @@ impl ValueEnum for PrivacyLevel {
     }
 }
 
-// Implement ValueEnum for OutputFormat to work with clap
-impl ValueEnum for OutputFormat {
+// Implement ValueEnum for DetectionProfile to work with clap
+impl ValueEnum for DetectionProfile {
     fn value_variants<'a>() -> &'a [Self] {
-        &[Self::Yaml, Self::Json, Self::Markdown]
+        &[Self::Full, Self::Quick]
     }
 
     fn to_possible_value(&self) -> Option<clap::builder::PossibleValue> {
         Some(match self {
-            Self::Yaml => clap::builder::PossibleValue::new("yaml").help("YAML format output"),
-            Self::Json => clap::builder::PossibleValue::new("json").help("JSON format output"),
-            Self::Markdown => {
-                clap::builder::PossibleValue::new("markdown").help("Markdown with YAML frontmatter")
-            }
+            Self::Full => clap::builder::PossibleValue::new("full")
+                .help("Run every available tool with its normal timeout"),
+            Self::Quick => clap::builder::PossibleValue::new("quick")
+                .help("Fast tools only, time-boxed, for startup and monitoring"),
         })
     }
 }
+
+// Implement ValueEnum for OutputFormat to work with clap, from the format registry
+impl ValueEnum for OutputFormat {
+    fn value_variants<'a>() -> &'a [Self] {
+        OutputFormat::ALL
+    }
+
+    fn to_possible_value(&self) -> Option<clap::builder::PossibleValue> {
+        let spec = self.spec();
+        Some(clap::builder::PossibleValue::new(spec.name).help(spec.help))
+    }
+}
@@ -0,0 +1,945 @@
+//! Apply a generated configuration step by step
+//!
+//! Each step is shown with its risk level and explanation and is applied only
+//! after the user confirms it. Before a file is touched, the change is
+//! recorded as a ConfigurationChange carrying the previous content or value;
+//! commands are recorded with the command that undoes them. The journal is
+//! saved after every step, so `rollback` can restore the system even when a
+//! run was interrupted.
+//!
+//! Files can be written below another root, e.g. a mounted image or a test
+//! directory. Commands such as package installs and bootloader updates only
+//! make sense for the running system and are skipped there.
+//!
+//! Commands are run directly, never through a shell. Commands from the
+//! configuration may chain commands with `&&` and quote words; one that needs
+//! any other shell syntax is left for the user to run by hand.
+
+use crate::configuration::kernel_params::bootloader::{BootloaderEdit, BootloaderWriter};
+use crate::configuration::*;
+use crate::errors::LxHwError;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::{BufRead, Write};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Default location of the rollback journal
+pub const DEFAULT_JOURNAL_PATH: &str = "/var/lib/lx-hw-detect/apply-journal.json";
+
+/// One recorded change, undone in reverse order on rollback
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum JournalEntry {
+    /// A file or a parameter in it. An empty `parameter` means the whole
+    /// file, and an `old_value` of None means the file or parameter did not
+    /// exist. `follow_up` runs after the change is made and after it is undone.
+    Change { change: ConfigurationChange, follow_up: Option<Vec<String>> },
+    /// A command and the command reverting it, if it needs reverting
+    Command { command: Vec<String>, undo: Option<Vec<String>> },
+    /// Bootloader files rewritten as a whole, and the commands activating the
+    /// original configuration once they are restored
+    Bootloader { changes: Vec<ConfigurationChange>, undo: Vec<Vec<String>> },
+}
+
+/// Program and arguments of a command
+type Argv = Vec<String>;
+
+#[derive(Debug, Clone)]
+enum Action {
+    Run { command: Argv, undo: Option<Argv> },
+    Change { change: ConfigurationChange, mode: Option<String>, follow_up: Option<Argv> },
+    Bootloader { writer: BootloaderWriter, edit: BootloaderEdit },
+}
+
+#[derive(Debug, Clone)]
+struct ApplyStep {
+    title: String,
+    risk: RiskLevel,
+    explanation: Vec<String>,
+    actions: Vec<Action>,
+}
+
+pub struct ConfigurationApplier<'a> {
+    configuration: &'a Configuration,
+    root: PathBuf,
+    journal_path: Option<PathBuf>,
+    assume_yes: bool,
+    dry_run: bool,
+}
+
+impl<'a> ConfigurationApplier<'a> {
+    pub fn new(configuration: &'a Configuration) -> Self {
+        Self {
+            configuration,
+            root: PathBuf::from("/"),
+            journal_path: None,
+            assume_yes: false,
+            dry_run: false,
+        }
+    }
+
+    /// Read a configuration saved as JSON by the configuration engine
+    pub fn load_configuration(path: &Path) -> Result<Configuration, LxHwError> {
+        let content = fs::read_to_string(path)?;
+        Ok(serde_json::from_str(&content)?)
+    }
+
+    /// Write files below `root` instead of `/`; commands are not run
+    pub fn root(mut self, root: PathBuf) -> Self {
+        self.root = root;
+        self
+    }
+
+    pub fn journal_path(mut self, path: PathBuf) -> Self {
+        self.journal_path = Some(path);
+        self
+    }
+
+    fn journal(&self) -> PathBuf {
+        self.journal_path.clone().unwrap_or_else(|| default_journal_path(&self.root))
+    }
+
+    /// Apply every step without asking
+    pub fn assume_yes(mut self, assume_yes: bool) -> Self {
+        self.assume_yes = assume_yes;
+        self
+    }
+
+    /// Show the steps and what they would change without changing anything
+    pub fn dry_run(mut self, dry_run: bool) -> Self {
+        self.dry_run = dry_run;
+        self
+    }
+
+    /// Walk through the steps, asking on `input` before each one; returns
+    /// the number of steps applied
+    pub fn apply(
+        &self,
+        input: &mut impl BufRead,
+        output: &mut impl Write,
+    ) -> Result<usize, LxHwError> {
+        if !self.dry_run && is_host_root(&self.root) && !nix::unistd::geteuid().is_root() {
+            return Err(LxHwError::InvalidInput {
+                message: "Applying a configuration requires root; use --dry-run to review it"
+                    .to_string(),
+            });
+        }
+
+        let steps = self.collect_steps();
+        let journal_path = self.journal();
+        let mut journal = if self.dry_run { Vec::new() } else { load_journal(&journal_path)? };
+        let mut applied = 0;
+
+        for (index, step) in steps.iter().enumerate() {
+            writeln!(
+                output,
+                "\n[{}/{}] {} (risk: {:?})",
+                index + 1,
+                steps.len(),
+                step.title,
+                step.risk
+            )?;
+            for line in &step.explanation {
+                writeln!(output, "    {}", line)?;
+            }
+            for action in &step.actions {
+                writeln!(output, "    > {}", describe(action))?;
+            }
+            if self.dry_run {
+                continue;
+            }
+
+            if !self.assume_yes {
+                write!(output, "Apply this change? [y/N/q] ")?;
+                output.flush()?;
+                let mut answer = String::new();
+                input.read_line(&mut answer)?;
+                match answer.trim().to_lowercase().as_str() {
+                    "y" | "yes" => {}
+                    "q" | "quit" => break,
+                    _ => {
+                        writeln!(output, "    Skipped")?;
+                        continue;
+                    }
+                }
+            }
+
+            for action in &step.actions {
+                perform(action, &self.root, &mut journal, &journal_path, output)?;
+            }
+            applied += 1;
+        }
+
+        if applied > 0 {
+            writeln!(
+                output,
+                "\nApplied {} of {} steps. Undo them with the journal at {}.",
+                applied,
+                steps.len(),
+                journal_path.display()
+            )?;
+        }
+        Ok(applied)
+    }
+
+    fn collect_steps(&self) -> Vec<ApplyStep> {
+        let config = self.configuration;
+        let mut steps = Vec::new();
+
+        for package in &config.package_installations {
+            let missing: Vec<String> = package
+                .package_names()
+                .into_iter()
+                .filter(|name| !is_host_root(&self.root) || !package_installed(name))
+                .collect();
+            if missing.is_empty() {
+                continue;
+            }
+            let mut explanation = vec![package.package_description.clone()];
+            let mut actions = command_actions(&package.installation_command, &mut explanation);
+            if let Some(Action::Run { undo, .. }) = actions.last_mut() {
+                *undo = removal_command(&package.installation_command, &missing);
+            }
+            for command in &package.post_install_commands {
+                actions.extend(command_actions(command, &mut explanation));
+            }
+            steps.push(ApplyStep {
+                title: format!("Install {}", missing.join(", ")),
+                risk: RiskLevel::Low,
+                explanation,
+                actions,
+            });
+        }
+
+        let mut files: Vec<&ConfigurationFile> = config.configuration_files.values().collect();
+        files.sort_by(|a, b| a.file_path.cmp(&b.file_path));
+        for file in files {
+            let mut actions = vec![Action::Change {
+                change: ConfigurationChange {
+                    file_path: file.file_path.clone(),
+                    parameter: String::new(),
+                    old_value: fs::read_to_string(resolve(&self.root, &file.file_path)).ok(),
+                    new_value: file.content.clone(),
+                    comment: format!("Configuration file for {}", config.system_id),
+                },
+                mode: Some(file.file_permissions.clone()),
+                follow_up: None,
+            }];
+            let mut explanation = Vec::new();
+            if let Some(command) = &file.validation_command {
+                actions.extend(command_actions(command, &mut explanation));
+            }
+            steps.push(ApplyStep {
+                title: format!("Write {}", file.file_path),
+                risk: RiskLevel::Low,
+                explanation,
+                actions,
+            });
+        }
+
+        if let Some(step) = self.kernel_parameter_step() {
+            steps.push(step);
+        }
+
+        for optimization in &config.performance_optimizations {
+            if optimization.configuration_changes.is_empty() {
+                continue;
+            }
+            let actions = optimization
+                .configuration_changes
+                .iter()
+                .map(|change| {
+                    let old_value = fs::read_to_string(resolve(&self.root, &change.file_path))
+                        .ok()
+                        .and_then(|content| read_parameter(&content, &change.parameter));
+                    Action::Change {
+                        change: ConfigurationChange { old_value, ..change.clone() },
+                        mode: None,
+                        follow_up: None,
+                    }
+                })
+                .collect();
+            steps.push(ApplyStep {
+                title: optimization.optimization_type.clone(),
+                risk: optimization.risk_level.clone(),
+                explanation: vec![
+                    optimization.description.clone(),
+                    format!("Expected improvement: {}", optimization.expected_improvement),
+                ],
+                actions,
+            });
+        }
+
+        for module in &config.dkms_modules {
+            let mut explanation =
+                vec![format!("Out-of-tree module {} from {}", module.version, module.source_url)];
+            let mut actions = Vec::new();
+            for command in &module.installation_steps {
+                actions.extend(command_actions(command, &mut explanation));
+            }
+            if let Some(Action::Run { undo, .. }) = actions.last_mut() {
+                *undo = Some(argv(&[
+                    "dkms",
+                    "remove",
+                    &format!("{}/{}", module.module_name, module.version),
+                    "--all",
+                ]));
+            }
+            steps.push(ApplyStep {
+                title: format!("Build the {} module with DKMS", module.module_name),
+                risk: RiskLevel::High,
+                explanation,
+                actions,
+            });
+        }
+
+        steps
+    }
+
+    /// Merge the kernel parameters into the configuration of the bootloader
+    fn kernel_parameter_step(&self) -> Option<ApplyStep> {
+        let parameters = &self.configuration.kernel_parameters;
+        if parameters.is_empty() {
+            return None;
+        }
+        let mut explanation: Vec<String> =
+            parameters.iter().map(|p| format!("{}: {}", p.parameter, p.purpose)).collect();
+        let planned = BootloaderWriter::with_root(&self.root)
+            .map(|writer| writer.plan(parameters).map(|edit| (writer, edit)));
+        let actions = match planned {
+            Some(Ok((writer, edit))) => vec![Action::Bootloader { writer, edit }],
+            Some(Err(e)) => {
+                explanation.push(format!("Cannot edit the bootloader configuration: {}", e));
+                Vec::new()
+            }
+            None => {
+                explanation.push(
+                    "No GRUB, systemd-boot or kernelstub configuration found; add the \
+                     parameters to the boot entries by hand"
+                        .to_string(),
+                );
+                Vec::new()
+            }
+        };
+
+        Some(ApplyStep {
+            title: "Add kernel boot parameters".to_string(),
+            risk: RiskLevel::Medium,
+            explanation,
+            actions,
+        })
+    }
+}
+
+/// Journal location used when none is given, below `root`
+pub fn default_journal_path(root: &Path) -> PathBuf {
+    resolve(root, DEFAULT_JOURNAL_PATH)
+}
+
+/// Undo the changes recorded in a journal, newest first, and remove it;
+/// `root` must be the one the changes were applied below
+pub fn rollback(
+    root: &Path,
+    journal_path: &Path,
+    output: &mut impl Write,
+) -> Result<usize, LxHwError> {
+    let journal = load_journal(journal_path)?;
+    if journal.is_empty() {
+        writeln!(output, "Nothing to roll back")?;
+        return Ok(0);
+    }
+
+    let mut undone = 0;
+    for entry in journal.iter().rev() {
+        match entry {
+            JournalEntry::Change { change, follow_up } => {
+                writeln!(output, "Restoring {}", describe_change(change))?;
+                restore_change(root, change)?;
+                if let Some(command) = follow_up {
+                    run_host_command(root, command, output)?;
+                }
+            }
+            JournalEntry::Command { undo: Some(undo), .. } => {
+                writeln!(output, "Running {}", display_command(undo))?;
+                run_host_command(root, undo, output)?;
+            }
+            JournalEntry::Command { .. } => continue,
+            JournalEntry::Bootloader { changes, undo } => {
+                for change in changes.iter().rev() {
+                    writeln!(output, "Restoring {}", describe_change(change))?;
+                    restore_change(root, change)?;
+                }
+                for command in undo {
+                    run_host_command(root, command, output)?;
+                }
+            }
+        }
+        undone += 1;
+    }
+
+    fs::remove_file(journal_path)?;
+    writeln!(output, "Rolled back {} changes. Reboot if kernel parameters were changed.", undone)?;
+    Ok(undone)
+}
+
+/// Carry out an action and record it; a file change is journaled before its
+/// follow-up command runs, so it can be rolled back if that command fails
+fn perform(
+    action: &Action,
+    root: &Path,
+    journal: &mut Vec<JournalEntry>,
+    journal_path: &Path,
+    output: &mut impl Write,
+) -> Result<(), LxHwError> {
+    match action {
+        Action::Run { command, undo } => {
+            if !run_host_command(root, command, output)? {
+                return Ok(());
+            }
+            journal.push(JournalEntry::Command { command: command.clone(), undo: undo.clone() });
+            save_journal(journal_path, journal)
+        }
+        Action::Change { change, mode, follow_up } => {
+            let path = resolve(root, &change.file_path);
+            let path = path.as_path();
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            // Record what is there now; earlier steps may have changed the file
+            let current = fs::read_to_string(path).ok();
+            let (old_value, content) = if change.parameter.is_empty() {
+                (current, change.new_value.clone())
+            } else {
+                let current = current.unwrap_or_default();
+                (
+                    read_parameter(&current, &change.parameter),
+                    set_parameter(&current, &change.parameter, Some(&change.new_value)),
+                )
+            };
+            fs::write(path, content)?;
+            journal.push(JournalEntry::Change {
+                change: ConfigurationChange { old_value, ..change.clone() },
+                follow_up: follow_up.clone(),
+            });
+            save_journal(journal_path, journal)?;
+
+            if let Some(mode) = mode {
+                set_mode(path, mode)?;
+            }
+            if let Some(command) = follow_up {
+                run_host_command(root, command, output)?;
+            }
+            Ok(())
+        }
+        Action::Bootloader { writer, edit } => {
+            let changes: Vec<ConfigurationChange> = edit
+                .files
+                .iter()
+                .map(|(path, content)| ConfigurationChange {
+                    file_path: host_path(root, path),
+                    parameter: String::new(),
+                    old_value: fs::read_to_string(path).ok(),
+                    new_value: content.clone(),
+                    comment: "Kernel parameters recommended for this hardware".to_string(),
+                })
+                .collect();
+            // The writer puts the files back itself if validating or
+            // activating them fails, so only a finished edit is journaled
+            if is_host_root(root) {
+                writer.write(edit)?;
+            } else {
+                writer.write_with(edit, |command| {
+                    run_host_command(root, command, output).map(|_| ())
+                })?;
+            }
+            journal.push(JournalEntry::Bootloader { changes, undo: edit.undo.clone() });
+            save_journal(journal_path, journal)
+        }
+    }
+}
+
+/// Put a file or parameter back to the value recorded before `change`
+fn restore_change(root: &Path, change: &ConfigurationChange) -> Result<(), LxHwError> {
+    let path = resolve(root, &change.file_path);
+    let path = path.as_path();
+    if change.parameter.is_empty() {
+        match &change.old_value {
+            Some(content) => fs::write(path, content)?,
+            None => {
+                if path.exists() {
+                    fs::remove_file(path)?;
+                }
+            }
+        }
+        return Ok(());
+    }
+
+    let current = fs::read_to_string(path).unwrap_or_default();
+    fs::write(path, set_parameter(&current, &change.parameter, change.old_value.as_deref()))?;
+    Ok(())
+}
+
+fn describe(action: &Action) -> String {
+    match action {
+        Action::Run { command, .. } => format!("run: {}", display_command(command)),
+        Action::Change { change, .. } => describe_change(change),
+        Action::Bootloader { edit, .. } => {
+            let files = edit.files.iter().map(|(path, _)| format!("write {}", path.display()));
+            let commands = edit.validation.iter().chain(&edit.commands);
+            files
+                .chain(commands.map(|command| format!("run: {}", display_command(command))))
+                .collect::<Vec<_>>()
+                .join("\n    > ")
+        }
+    }
+}
+
+fn describe_change(change: &ConfigurationChange) -> String {
+    if change.parameter.is_empty() {
+        return format!("write {}", change.file_path);
+    }
+    match &change.old_value {
+        Some(old) => format!(
+            "{}: {} = {} (was {})",
+            change.file_path, change.parameter, change.new_value, old
+        ),
+        None => format!("{}: {} = {}", change.file_path, change.parameter, change.new_value),
+    }
+}
+
+/// Value of a `key=value` or `key = value` line
+fn read_parameter(content: &str, parameter: &str) -> Option<String> {
+    content.lines().find_map(|line| {
+        let (key, value) = line.split_once('=')?;
+        (key.trim() == parameter && !line.trim_start().starts_with('#'))
+            .then(|| value.trim().to_string())
+    })
+}
+
+/// Replace, add or (with None) remove the line setting a parameter
+fn set_parameter(content: &str, parameter: &str, value: Option<&str>) -> String {
+    let mut found = false;
+    let mut lines: Vec<String> = Vec::new();
+    for line in content.lines() {
+        let is_parameter = !line.trim_start().starts_with('#')
+            && line.split_once('=').is_some_and(|(key, _)| key.trim() == parameter);
+        if !is_parameter {
+            lines.push(line.to_string());
+        } else if let (Some(value), false) = (value, found) {
+            lines.push(format!("{}={}", parameter, value));
+            found = true;
+        }
+    }
+    if let (Some(value), false) = (value, found) {
+        lines.push(format!("{}={}", parameter, value));
+    }
+    let mut content = lines.join("\n");
+    content.push('\n');
+    content
+}
+
+/// Path of a configuration file below `root`
+fn resolve(root: &Path, path: &str) -> PathBuf {
+    root.join(path.trim_start_matches('/'))
+}
+
+/// Path of a file below `root` as seen from inside that system
+fn host_path(root: &Path, path: &Path) -> String {
+    Path::new("/").join(path.strip_prefix(root).unwrap_or(path)).display().to_string()
+}
+
+fn is_host_root(root: &Path) -> bool {
+    root == Path::new("/")
+}
+
+/// Run a command for the running system; below another root it is only
+/// reported. Returns whether it ran.
+fn run_host_command(
+    root: &Path,
+    command: &[String],
+    output: &mut impl Write,
+) -> Result<bool, LxHwError> {
+    if !is_host_root(root) {
+        writeln!(output, "    Not run below {}: {}", root.display(), display_command(command))?;
+        return Ok(false);
+    }
+    run_command(command)?;
+    Ok(true)
+}
+
+/// Set octal permissions such as "0644"
+fn set_mode(path: &Path, mode: &str) -> Result<(), LxHwError> {
+    use std::os::unix::fs::PermissionsExt;
+
+    let mode = u32::from_str_radix(mode, 8).map_err(|_| LxHwError::InvalidInput {
+        message: format!("Invalid file permissions '{}'", mode),
+    })?;
+    fs::set_permissions(path, fs::Permissions::from_mode(mode))?;
+    Ok(())
+}
+
+fn run_command(command: &[String]) -> Result<(), LxHwError> {
+    let failed = || LxHwError::SystemCommandError { command: display_command(command) };
+    let (program, args) = command.split_first().ok_or_else(failed)?;
+    let status = Command::new(program).args(args).status()?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(failed())
+    }
+}
+
+fn argv(words: &[&str]) -> Argv {
+    words.iter().map(|word| word.to_string()).collect()
+}
+
+/// A command as it would be typed, quoting words that need it
+fn display_command(command: &[String]) -> String {
+    command
+        .iter()
+        .map(|word| {
+            if !word.is_empty()
+                && word.chars().all(|c| c.is_ascii_alphanumeric() || "-_./=:,+@%".contains(c))
+            {
+                word.clone()
+            } else {
+                format!("'{}'", word.replace('\'', "'\\''"))
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Actions running a command from the configuration; one needing a shell is
+/// added to the explanation for the user to run by hand
+fn command_actions(command: &str, explanation: &mut Vec<String>) -> Vec<Action> {
+    match split_command(command) {
+        Some(commands) => {
+            commands.into_iter().map(|command| Action::Run { command, undo: None }).collect()
+        }
+        None => {
+            explanation.push(format!("Run by hand, as it needs a shell: {}", command));
+            Vec::new()
+        }
+    }
+}
+
+/// Split a command line into the commands it chains with `&&`
+///
+/// Words may be quoted with single or double quotes. None when the line uses
+/// other shell syntax, such as pipes, redirections or variables.
+fn split_command(line: &str) -> Option<Vec<Argv>> {
+    let mut commands = Vec::new();
+    let mut command = Vec::new();
+    let mut word: Option<String> = None;
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\'' | '"' => {
+                let word = word.get_or_insert_with(String::new);
+                loop {
+                    match chars.next()? {
+                        quote if quote == c => break,
+                        '$' | '`' | '\\' if c == '"' => return None,
+                        other => word.push(other),
+                    }
+                }
+            }
+            c if c.is_whitespace() => command.extend(word.take()),
+            '&' if word.is_none() && chars.peek() == Some(&'&') => {
+                chars.next();
+                if command.is_empty() {
+                    return None;
+                }
+                commands.push(std::mem::take(&mut command));
+            }
+            '|' | ';' | '&' | '<' | '>' | '$' | '`' | '\\' | '(' | ')' | '*' | '?' | '#' | '~' => {
+                return None
+            }
+            c => word.get_or_insert_with(String::new).push(c),
+        }
+    }
+    command.extend(word);
+    if command.is_empty() {
+        return None;
+    }
+    commands.push(command);
+    Some(commands)
+}
+
+fn command_exists(name: &str) -> bool {
+    Command::new("which").arg(name).output().map(|output| output.status.success()).unwrap_or(false)
+}
+
+fn package_installed(name: &str) -> bool {
+    if command_exists("dpkg-query") {
+        return Command::new("dpkg-query")
+            .args(["-W", "-f=${Status}", name])
+            .output()
+            .map(|output| String::from_utf8_lossy(&output.stdout).contains("install ok installed"))
+            .unwrap_or(false);
+    }
+    let query = if command_exists("rpm") {
+        argv(&["rpm", "-q", name])
+    } else if command_exists("pacman") {
+        argv(&["pacman", "-Q", name])
+    } else if command_exists("portageq") {
+        argv(&["portageq", "has_version", "/", name])
+    } else if command_exists("apk") {
+        argv(&["apk", "info", "-e", name])
+    } else {
+        return false;
+    };
+    Command::new(&query[0])
+        .args(&query[1..])
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::null())
+        .status()
+        .map(|status| status.success())
+        .unwrap_or(false)
+}
+
+/// Command removing packages, using the package manager of an install command
+fn removal_command(installation_command: &str, packages: &[String]) -> Option<Argv> {
+    let command = installation_command.rsplit("&&").next().unwrap_or_default().trim_start();
+    let remove = if command.starts_with("apt") {
+        "apt remove -y"
+    } else if command.starts_with("dnf") {
+        "dnf remove -y"
+    } else if command.starts_with("rpm-ostree") {
+        "rpm-ostree uninstall"
+    } else if command.starts_with("transactional-update") {
+        "transactional-update --non-interactive pkg remove"
+    } else if command.starts_with("zypper") {
+        "zypper --non-interactive remove"
+    } else if command.starts_with("pacman") {
+        "pacman -R --noconfirm"
+    } else if command.starts_with("nix-env") {
+        "nix-env -e"
+    } else if command.starts_with("emerge") {
+        "emerge --ask=n --depclean"
+    } else if command.starts_with("apk") {
+        "apk del"
+    } else {
+        return None;
+    };
+    let mut command: Argv = remove.split_whitespace().map(String::from).collect();
+    command.extend(packages.iter().cloned());
+    Some(command)
+}
+
+fn load_journal(path: &Path) -> Result<Vec<JournalEntry>, LxHwError> {
+    match fs::read_to_string(path) {
+        Ok(content) => Ok(serde_json::from_str(&content)?),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Vec::new()),
+        Err(e) => Err(e.into()),
+    }
+}
+
+fn save_journal(path: &Path, journal: &[JournalEntry]) -> Result<(), LxHwError> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(path, serde_json::to_string_pretty(journal)?)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use tempfile::TempDir;
+
+    fn configuration() -> Configuration {
+        let module_file = ConfigurationFile {
+            file_path: "/etc/modprobe.d/lx-hw-audio.conf".to_string(),
+            content: "options snd_hda_intel power_save=1\n".to_string(),
+            backup_original: true,
+            file_permissions: "0640".to_string(),
+            validation_command: Some("false".to_string()),
+        };
+        Configuration {
+            system_id: "test".to_string(),
+            target_distribution: "Fedora".to_string(),
+            kernel_version: "6.8.0".to_string(),
+            hardware_profile: HardwareProfile {
+                cpu: None,
+                gpu: vec![],
+                network: vec![],
+                storage: vec![],
+                audio: vec![],
+                usb_controllers: vec![],
+            },
+            driver_recommendations: vec![],
+            kernel_parameters: vec![KernelParameter {
+                parameter: "nvme_core.default_ps_max_latency_us".to_string(),
+                value: Some("0".to_string()),
+                purpose: "Avoid NVMe power state timeouts".to_string(),
+                hardware_target: None,
+                distribution_specific: None,
+                boot_order: 1,
+            }],
+            package_installations: vec![],
+            dkms_modules: vec![],
+            configuration_files: HashMap::from([("audio".to_string(), module_file)]),
+            performance_optimizations: vec![PerformanceOptimization {
+                optimization_type: "Swappiness".to_string(),
+                description: "Prefer keeping pages in RAM".to_string(),
+                configuration_changes: vec![ConfigurationChange {
+                    file_path: "/etc/sysctl.d/99-lx-hw.conf".to_string(),
+                    parameter: "vm.swappiness".to_string(),
+                    old_value: None,
+                    new_value: "10".to_string(),
+                    comment: String::new(),
+                }],
+                expected_improvement: "Fewer stalls".to_string(),
+                risk_level: RiskLevel::Low,
+            }],
+            compatibility_score: 90.0,
+        }
+    }
+
+    fn write(root: &Path, file: &str, content: &str) {
+        let path = resolve(root, file);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, content).unwrap();
+    }
+
+    fn read(root: &Path, file: &str) -> Option<String> {
+        fs::read_to_string(resolve(root, file)).ok()
+    }
+
+    #[test]
+    fn test_apply_and_rollback_below_root() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let root = TempDir::new().unwrap();
+        let root = root.path();
+        let grub = "GRUB_TIMEOUT=5\nGRUB_CMDLINE_LINUX_DEFAULT=\"quiet\"\n";
+        let sysctl = "# local tuning\nvm.swappiness=60\nvm.dirty_ratio=20\n";
+        write(root, "/etc/default/grub", grub);
+        write(root, "/etc/sysctl.d/99-lx-hw.conf", sysctl);
+
+        let configuration = configuration();
+        let mut output = Vec::new();
+        let applied = ConfigurationApplier::new(&configuration)
+            .root(root.to_path_buf())
+            .assume_yes(true)
+            .apply(&mut std::io::empty(), &mut output)
+            .unwrap();
+        assert_eq!(applied, 3);
+
+        let module_file = resolve(root, "/etc/modprobe.d/lx-hw-audio.conf");
+        assert_eq!(
+            read(root, "/etc/modprobe.d/lx-hw-audio.conf").unwrap(),
+            "options snd_hda_intel power_save=1\n"
+        );
+        assert_eq!(fs::metadata(&module_file).unwrap().permissions().mode() & 0o777, 0o640);
+        assert!(read(root, "/etc/default/grub").unwrap().contains(
+            "GRUB_CMDLINE_LINUX_DEFAULT=\"quiet nvme_core.default_ps_max_latency_us=0\""
+        ));
+        assert_eq!(
+            read(root, "/etc/sysctl.d/99-lx-hw.conf").unwrap(),
+            "# local tuning\nvm.swappiness=10\nvm.dirty_ratio=20\n"
+        );
+        // The validation command is only reported, so it is not journaled
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.contains("Not run below"));
+
+        let journal_path = resolve(root, DEFAULT_JOURNAL_PATH);
+        let journal = load_journal(&journal_path).unwrap();
+        assert_eq!(journal.len(), 3);
+        assert!(matches!(
+            &journal[1],
+            JournalEntry::Bootloader { changes, .. } if changes[0].file_path == "/etc/default/grub"
+        ));
+
+        let undone = rollback(root, &journal_path, &mut Vec::new()).unwrap();
+        assert_eq!(undone, 3);
+        assert!(!module_file.exists());
+        assert_eq!(read(root, "/etc/default/grub").unwrap(), grub);
+        assert_eq!(read(root, "/etc/sysctl.d/99-lx-hw.conf").unwrap(), sysctl);
+        assert!(!journal_path.exists());
+        assert_eq!(rollback(root, &journal_path, &mut Vec::new()).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_commands_run_without_a_shell() {
+        assert_eq!(
+            split_command("apt update && apt install -y 'nvidia driver' \"a b\"").unwrap(),
+            [argv(&["apt", "update"]), argv(&["apt", "install", "-y", "nvidia driver", "a b"])]
+        );
+        for line in [
+            "emerge --info >/dev/null",
+            "echo x | tee y",
+            "rm -rf $HOME",
+            "true; reboot",
+            "echo \"$(id)\"",
+            "&& true",
+            "'unterminated",
+        ] {
+            assert_eq!(split_command(line), None, "{}", line);
+        }
+
+        let mut explanation = Vec::new();
+        assert!(command_actions("echo early >> /etc/mkinitcpio.conf", &mut explanation).is_empty());
+        assert_eq!(
+            explanation,
+            ["Run by hand, as it needs a shell: echo early >> /etc/mkinitcpio.conf"]
+        );
+        assert_eq!(
+            display_command(&argv(&["grubby", "--args=quiet splash", "it's"])),
+            "grubby '--args=quiet splash' 'it'\\''s'"
+        );
+        assert_eq!(
+            removal_command("apt update && apt install -y foo", &["foo".to_string()]),
+            Some(argv(&["apt", "remove", "-y", "foo"]))
+        );
+    }
+
+    #[test]
+    fn test_dry_run_and_skipped_steps() {
+        let root = TempDir::new().unwrap();
+        let root = root.path();
+        write(root, "/etc/default/grub", "GRUB_CMDLINE_LINUX_DEFAULT=\"\"\n");
+        let configuration = configuration();
+
+        let mut output = Vec::new();
+        let applied = ConfigurationApplier::new(&configuration)
+            .root(root.to_path_buf())
+            .dry_run(true)
+            .apply(&mut std::io::empty(), &mut output)
+            .unwrap();
+        assert_eq!(applied, 0);
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.contains("[1/3] Write /etc/modprobe.d/lx-hw-audio.conf"));
+        assert!(output.contains("(risk: Medium)"));
+        assert!(read(root, "/etc/modprobe.d/lx-hw-audio.conf").is_none());
+        assert!(!resolve(root, DEFAULT_JOURNAL_PATH).exists());
+
+        // Skip the file, apply the boot parameters, then quit
+        let journal_path = root.join("journal.json");
+        let applied = ConfigurationApplier::new(&configuration)
+            .root(root.to_path_buf())
+            .journal_path(journal_path.clone())
+            .apply(&mut "n\ny\nq\n".as_bytes(), &mut Vec::new())
+            .unwrap();
+        assert_eq!(applied, 1);
+        assert!(read(root, "/etc/modprobe.d/lx-hw-audio.conf").is_none());
+        assert!(read(root, "/etc/default/grub").unwrap().contains("nvme_core"));
+        assert!(read(root, "/etc/sysctl.d/99-lx-hw.conf").is_none());
+        assert_eq!(load_journal(&journal_path).unwrap().len(), 1);
+
+        // Applying the parameters again does not repeat them
+        ConfigurationApplier::new(&configuration)
+            .root(root.to_path_buf())
+            .journal_path(journal_path)
+            .apply(&mut "n\ny\nq\n".as_bytes(), &mut Vec::new())
+            .unwrap();
+        assert_eq!(
+            read(root, "/etc/default/grub").unwrap(),
+            "GRUB_CMDLINE_LINUX_DEFAULT=\"nvme_core.default_ps_max_latency_us=0\"\n"
+        );
+    }
+}
@@ -0,0 +1,481 @@
+//! Drift audit of kernel command line and sysctl tuning
+//!
+//! Compares the running system against the kernel parameters recommended by
+//! [`KernelParameterGenerator`](crate::configuration::kernel_params::KernelParameterGenerator)
+//! and reports each setting as applied, missing or conflicting. A setting conflicts
+//! when something on the system explicitly sets it to a different value: the boot
+//! command line, a `sysctl.d` file or a `modprobe.d` option.
+
+use crate::configuration::KernelParameter;
+use crate::errors::LxHwError;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt::Write as _;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Directories read by systemd-sysctl, in increasing precedence
+const SYSCTL_DIRS: [&str; 3] = ["usr/lib/sysctl.d", "run/sysctl.d", "etc/sysctl.d"];
+
+/// Directories holding module options, in increasing precedence
+const MODPROBE_DIRS: [&str; 3] = ["usr/lib/modprobe.d", "run/modprobe.d", "etc/modprobe.d"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SettingKind {
+    /// Boot parameter, including `module.option` parameters
+    KernelCmdline,
+    /// Runtime setting under `/proc/sys`
+    Sysctl,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum DriftStatus {
+    /// The running system already uses the recommended value
+    Applied,
+    /// Nothing sets the parameter
+    Missing,
+    /// The parameter is set to a different value
+    Conflicting { configured_in: String },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SettingDrift {
+    pub parameter: String,
+    pub kind: SettingKind,
+    pub recommended: Option<String>,
+    pub current: Option<String>,
+    pub status: DriftStatus,
+    pub purpose: String,
+    pub hardware_target: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DriftReport {
+    pub kernel_cmdline: String,
+    pub settings: Vec<SettingDrift>,
+}
+
+impl DriftReport {
+    pub fn applied(&self) -> impl Iterator<Item = &SettingDrift> {
+        self.settings.iter().filter(|s| s.status == DriftStatus::Applied)
+    }
+
+    pub fn missing(&self) -> impl Iterator<Item = &SettingDrift> {
+        self.settings.iter().filter(|s| s.status == DriftStatus::Missing)
+    }
+
+    pub fn conflicting(&self) -> impl Iterator<Item = &SettingDrift> {
+        self.settings.iter().filter(|s| matches!(s.status, DriftStatus::Conflicting { .. }))
+    }
+
+    /// True when every recommended setting is applied
+    pub fn is_clean(&self) -> bool {
+        self.applied().count() == self.settings.len()
+    }
+
+    pub fn to_markdown(&self) -> String {
+        let mut out = String::from("# Kernel Tuning Drift Report\n\n");
+        let _ = writeln!(
+            out,
+            "**Applied**: {} | **Missing**: {} | **Conflicting**: {}\n",
+            self.applied().count(),
+            self.missing().count(),
+            self.conflicting().count()
+        );
+        if self.settings.is_empty() {
+            out.push_str("No kernel parameters are recommended for this hardware.\n");
+            return out;
+        }
+
+        out.push_str("| Status | Setting | Recommended | Current | Purpose |\n");
+        out.push_str("|--------|---------|-------------|---------|---------|\n");
+        for setting in &self.settings {
+            let status = match &setting.status {
+                DriftStatus::Applied => "✓ applied".to_string(),
+                DriftStatus::Missing => "missing".to_string(),
+                DriftStatus::Conflicting { configured_in } => {
+                    format!("✗ conflicts ({})", configured_in)
+                }
+            };
+            let _ = writeln!(
+                out,
+                "| {} | `{}` | {} | {} | {} |",
+                status,
+                setting.parameter,
+                setting.recommended.as_deref().unwrap_or("(flag)"),
+                setting.current.as_deref().unwrap_or("-"),
+                setting.purpose
+            );
+        }
+        out
+    }
+}
+
+/// Audits the running system against recommended kernel parameters
+pub struct TuningAuditor {
+    root: PathBuf,
+}
+
+impl Default for TuningAuditor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TuningAuditor {
+    pub fn new() -> Self {
+        Self { root: PathBuf::from("/") }
+    }
+
+    /// Read `/proc`, `/etc` and friends below `root` instead of `/`
+    pub fn with_root<P: AsRef<Path>>(root: P) -> Self {
+        Self { root: root.as_ref().to_path_buf() }
+    }
+
+    pub fn audit(&self, parameters: &[KernelParameter]) -> Result<DriftReport, LxHwError> {
+        let kernel_cmdline = fs::read_to_string(self.root.join("proc/cmdline"))
+            .map_err(|e| LxHwError::SystemError {
+                message: format!("Failed to read /proc/cmdline: {}", e),
+            })?
+            .trim()
+            .to_string();
+        let cmdline = parse_cmdline(&kernel_cmdline);
+        let sysctl_config = self.read_sysctl_config();
+        let module_options = self.read_module_options();
+
+        let mut settings = Vec::new();
+        for param in parameters {
+            let drift = match self.sysctl_path(&param.parameter) {
+                Some(path) => self.audit_sysctl(param, &path, &sysctl_config),
+                None => self.audit_cmdline(param, &cmdline, &module_options),
+            };
+            settings.push(drift);
+        }
+
+        Ok(DriftReport { kernel_cmdline, settings })
+    }
+
+    /// `/proc/sys` file of a dotted sysctl name, if the kernel exposes it
+    fn sysctl_path(&self, name: &str) -> Option<PathBuf> {
+        if !name.contains('.') || name.contains('/') {
+            return None;
+        }
+        let path = self.root.join("proc/sys").join(name.replace('.', "/"));
+        path.is_file().then_some(path)
+    }
+
+    fn audit_sysctl(
+        &self,
+        param: &KernelParameter,
+        path: &Path,
+        config: &HashMap<String, (String, String)>,
+    ) -> SettingDrift {
+        let current = fs::read_to_string(path).ok().map(|value| normalize_sysctl(&value));
+        let recommended = param.value.as_deref().map(normalize_sysctl);
+
+        let status = if current.is_some() && current == recommended {
+            DriftStatus::Applied
+        } else {
+            match config.get(&param.parameter) {
+                Some((value, file)) if Some(value) != recommended.as_ref() => {
+                    DriftStatus::Conflicting { configured_in: file.clone() }
+                }
+                _ => DriftStatus::Missing,
+            }
+        };
+
+        SettingDrift {
+            parameter: param.parameter.clone(),
+            kind: SettingKind::Sysctl,
+            recommended: param.value.clone(),
+            current,
+            status,
+            purpose: param.purpose.clone(),
+            hardware_target: param.hardware_target.clone(),
+        }
+    }
+
+    fn audit_cmdline(
+        &self,
+        param: &KernelParameter,
+        cmdline: &HashMap<String, Option<String>>,
+        module_options: &HashMap<String, (String, String)>,
+    ) -> SettingDrift {
+        let (current, status) =
+            match (cmdline.get(&param.parameter.replace('-', "_")), &param.value) {
+                (Some(current), None) => (current.clone(), DriftStatus::Applied),
+                (Some(current), Some(recommended))
+                    if values_match(current.as_deref(), recommended) =>
+                {
+                    (current.clone(), DriftStatus::Applied)
+                }
+                (Some(current), Some(_)) => (
+                    current.clone(),
+                    DriftStatus::Conflicting { configured_in: "/proc/cmdline".to_string() },
+                ),
+                (None, value) => match self.module_parameter(&param.parameter) {
+                    // Module options may also come from modprobe.d; the loaded module shows the value in effect
+                    Some(current)
+                        if value
+                            .as_deref()
+                            .is_some_and(|value| values_match(Some(&current), value)) =>
+                    {
+                        (Some(current), DriftStatus::Applied)
+                    }
+                    current => {
+                        let status = match module_options.get(&param.parameter) {
+                            Some((configured, file))
+                                if !value
+                                    .as_deref()
+                                    .is_some_and(|value| values_match(Some(configured), value)) =>
+                            {
+                                DriftStatus::Conflicting { configured_in: file.clone() }
+                            }
+                            _ => DriftStatus::Missing,
+                        };
+                        (current, status)
+                    }
+                },
+            };
+
+        SettingDrift {
+            parameter: param.parameter.clone(),
+            kind: SettingKind::KernelCmdline,
+            recommended: param.value.clone(),
+            current,
+            status,
+            purpose: param.purpose.clone(),
+            hardware_target: param.hardware_target.clone(),
+        }
+    }
+
+    /// Value in effect for a `module.option` parameter of a loaded module
+    fn module_parameter(&self, name: &str) -> Option<String> {
+        let (module, option) = name.split_once('.')?;
+        let path = self
+            .root
+            .join("sys/module")
+            .join(module.replace('-', "_"))
+            .join("parameters")
+            .join(option);
+        fs::read_to_string(path).ok().map(|value| value.trim().to_string())
+    }
+
+    /// Sysctl values from configuration files, keyed by name, with the file that sets them
+    fn read_sysctl_config(&self) -> HashMap<String, (String, String)> {
+        let mut values = HashMap::new();
+        // Files with the same name override earlier directories; sysctl.conf is applied last
+        let mut by_name: HashMap<String, PathBuf> = HashMap::new();
+        for dir in SYSCTL_DIRS {
+            for path in conf_files(&self.root.join(dir)) {
+                if let Some(name) = path.file_name().and_then(|n| n.to_str()) {
+                    by_name.insert(name.to_string(), path);
+                }
+            }
+        }
+        let mut names: Vec<_> = by_name.into_iter().collect();
+        names.sort();
+        let mut files: Vec<PathBuf> = names.into_iter().map(|(_, path)| path).collect();
+        files.push(self.root.join("etc/sysctl.conf"));
+
+        for path in files {
+            let Ok(content) = fs::read_to_string(&path) else { continue };
+            for line in content.lines() {
+                let line = line.trim().trim_start_matches('-');
+                if line.is_empty() || line.starts_with('#') || line.starts_with(';') {
+                    continue;
+                }
+                if let Some((key, value)) = line.split_once('=') {
+                    let key = key.trim().replace('/', ".");
+                    values.insert(key, (normalize_sysctl(value), self.display_path(&path)));
+                }
+            }
+        }
+        values
+    }
+
+    /// `options` lines from modprobe.d, keyed as `module.option`
+    fn read_module_options(&self) -> HashMap<String, (String, String)> {
+        let mut values = HashMap::new();
+        for dir in MODPROBE_DIRS {
+            for path in conf_files(&self.root.join(dir)) {
+                let Ok(content) = fs::read_to_string(&path) else { continue };
+                for (key, value) in content.lines().flat_map(parse_options_line) {
+                    values.insert(key, (value, self.display_path(&path)));
+                }
+            }
+        }
+        values
+    }
+
+    /// Path as it appears on the audited system
+    fn display_path(&self, path: &Path) -> String {
+        match path.strip_prefix(&self.root) {
+            Ok(relative) => format!("/{}", relative.display()),
+            Err(_) => path.display().to_string(),
+        }
+    }
+}
+
+/// `module.option` keys and values of a modprobe.d `options` line
+fn parse_options_line(line: &str) -> Vec<(String, String)> {
+    let mut words = line.split_whitespace();
+    if words.next() != Some("options") {
+        return Vec::new();
+    }
+    let Some(module) = words.next() else { return Vec::new() };
+    words
+        .take_while(|option| !option.starts_with('#'))
+        .map(|option| {
+            let (key, value) = option.split_once('=').unwrap_or((option, "1"));
+            (format!("{}.{}", module.replace('-', "_"), key), value.to_string())
+        })
+        .collect()
+}
+
+/// `*.conf` files of a directory in lexical order
+fn conf_files(dir: &Path) -> Vec<PathBuf> {
+    let mut files: Vec<PathBuf> = fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .filter_map(|e| e.ok())
+                .map(|e| e.path())
+                .filter(|p| p.extension().is_some_and(|ext| ext == "conf"))
+                .collect()
+        })
+        .unwrap_or_default();
+    files.sort();
+    files
+}
+
+/// Parameters of a kernel command line; later occurrences win as they do for the kernel
+pub fn parse_cmdline(cmdline: &str) -> HashMap<String, Option<String>> {
+    let mut params = HashMap::new();
+    let mut word = String::new();
+    let mut quoted = false;
+    let mut words = Vec::new();
+    for c in cmdline.chars() {
+        match c {
+            '"' => quoted = !quoted,
+            c if c.is_whitespace() && !quoted => {
+                if !word.is_empty() {
+                    words.push(std::mem::take(&mut word));
+                }
+            }
+            c => word.push(c),
+        }
+    }
+    if !word.is_empty() {
+        words.push(word);
+    }
+
+    for word in words {
+        // Everything after "--" is passed to init
+        if word == "--" {
+            break;
+        }
+        match word.split_once('=') {
+            // The kernel treats dashes and underscores in parameter names alike
+            Some((key, value)) => params.insert(key.replace('-', "_"), Some(value.to_string())),
+            None => params.insert(word.replace('-', "_"), None),
+        };
+    }
+    params
+}
+
+/// Sysctl values compare with runs of whitespace collapsed ("4096\t65536" == "4096 65536")
+fn normalize_sysctl(value: &str) -> String {
+    value.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Compare parameter values, accepting the kernel's spellings of booleans
+pub(crate) fn values_match(current: Option<&str>, recommended: &str) -> bool {
+    let canonical = |value: &str| match value.to_ascii_lowercase().as_str() {
+        "y" | "yes" | "on" | "true" | "1" => "1".to_string(),
+        "n" | "no" | "off" | "false" | "0" => "0".to_string(),
+        other => other.to_string(),
+    };
+    // A bare boolean parameter on the command line means enabled
+    canonical(current.unwrap_or("1")) == canonical(recommended)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn param(parameter: &str, value: &str) -> KernelParameter {
+        KernelParameter {
+            parameter: parameter.to_string(),
+            value: Some(value.to_string()),
+            purpose: String::new(),
+            hardware_target: None,
+            distribution_specific: None,
+            boot_order: 0,
+        }
+    }
+
+    fn write(root: &Path, path: &str, content: &str) {
+        let path = root.join(path);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, content).unwrap();
+    }
+
+    #[test]
+    fn test_sysctl_drift() {
+        let root = TempDir::new().unwrap();
+        write(root.path(), "proc/cmdline", "ro quiet\n");
+        write(root.path(), "proc/sys/net/ipv4/tcp_rmem", "4096\t131072\t6291456\n");
+        write(root.path(), "proc/sys/vm/swappiness", "60\n");
+        write(root.path(), "proc/sys/vm/dirty_ratio", "20\n");
+        // The /etc file of the same name replaces the vendor one
+        write(root.path(), "usr/lib/sysctl.d/50-tuning.conf", "vm.swappiness = 10\n");
+        write(root.path(), "etc/sysctl.d/50-tuning.conf", "vm/swappiness = 60\n");
+
+        let report = TuningAuditor::with_root(root.path())
+            .audit(&[
+                param("net.ipv4.tcp_rmem", "4096 131072 6291456"),
+                param("vm.swappiness", "10"),
+                param("vm.dirty_ratio", "10"),
+            ])
+            .unwrap();
+
+        assert_eq!(report.settings[0].status, DriftStatus::Applied);
+        assert_eq!(report.settings[0].kind, SettingKind::Sysctl);
+        assert_eq!(
+            report.settings[1].status,
+            DriftStatus::Conflicting { configured_in: "/etc/sysctl.d/50-tuning.conf".to_string() }
+        );
+        assert_eq!(report.settings[2].status, DriftStatus::Missing);
+        assert_eq!(report.settings[2].current.as_deref(), Some("20"));
+        assert!(!report.is_clean());
+    }
+
+    #[test]
+    fn test_loaded_module_parameter_counts_as_applied() {
+        let root = TempDir::new().unwrap();
+        write(root.path(), "proc/cmdline", "ro\n");
+        write(root.path(), "sys/module/snd_hda_intel/parameters/power_save", "0\n");
+        write(root.path(), "etc/modprobe.d/audio.conf", "options snd-hda-intel power_save=0\n");
+
+        let report = TuningAuditor::with_root(root.path())
+            .audit(&[param("snd_hda_intel.power_save", "0")])
+            .unwrap();
+
+        assert_eq!(report.settings[0].status, DriftStatus::Applied);
+        assert_eq!(report.settings[0].kind, SettingKind::KernelCmdline);
+        assert!(report.is_clean());
+    }
+
+    #[test]
+    fn test_parse_cmdline() {
+        let params =
+            parse_cmdline("root=/dev/sda1 acpi-osi=\"Windows 2020\" quiet quiet=1 -- single");
+
+        assert_eq!(params["acpi_osi"].as_deref(), Some("Windows 2020"));
+        assert_eq!(params["quiet"].as_deref(), Some("1"));
+        assert!(!params.contains_key("single"));
+    }
+}
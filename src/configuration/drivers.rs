@@ -1,11 +1,15 @@
-use std::collections::HashMap;
-use serde_json::Value;
 use crate::configuration::*;
-use crate::hardware::{HardwareReport, DeviceCompatibility};
+use crate::detectors::modules::ModuleDependencies;
 use crate::errors::LxHwError;
+use crate::hardware::out_of_tree::OutOfTreeDatabase;
+use crate::hardware::{DeviceCompatibility, HardwareReport};
+use serde_json::Value;
+use std::collections::HashMap;
 
 pub struct DriverMapper {
     driver_database: DriverDatabase,
+    module_dependencies: Option<ModuleDependencies>,
+    out_of_tree: OutOfTreeDatabase,
     vendor_mappings: HashMap<String, VendorInfo>,
     device_class_mappings: HashMap<String, DeviceClassInfo>,
 }
@@ -61,6 +65,8 @@ impl DriverMapper {
                 usb_drivers: HashMap::new(),
                 generic_drivers: HashMap::new(),
             },
+            module_dependencies: ModuleDependencies::for_running_kernel().ok(),
+            out_of_tree: OutOfTreeDatabase::builtin(),
             vendor_mappings: HashMap::new(),
             device_class_mappings: HashMap::new(),
         };
@@ -72,7 +78,17 @@ impl DriverMapper {
         Ok(mapper)
     }
 
-    pub fn map_drivers(&self, hardware: &HardwareReport) -> Result<Vec<DriverRecommendation>, LxHwError> {
+    /// Resolve module dependencies with `dependencies` instead of the
+    /// modules.dep of the running kernel
+    pub fn with_module_dependencies(mut self, dependencies: ModuleDependencies) -> Self {
+        self.module_dependencies = Some(dependencies);
+        self
+    }
+
+    pub fn map_drivers(
+        &self,
+        hardware: &HardwareReport,
+    ) -> Result<Vec<DriverRecommendation>, LxHwError> {
         let mut recommendations = Vec::new();
 
         // Map drivers for CPU
@@ -102,98 +118,146 @@ impl DriverMapper {
             }
         }
 
+        // Map out-of-tree drivers for USB devices no driver claimed
+        for device in hardware.unknown_devices.iter().filter(|d| d.bus == "usb") {
+            let device_id = format!("{}:{}", device.vendor_id, device.device_id).to_lowercase();
+            recommendations.extend(self.map_out_of_tree_driver("usb", &device_id));
+        }
+
+        // Expand modules into their full dependency chains
+        for recommendation in &mut recommendations {
+            self.resolve_module_dependencies(recommendation);
+        }
+
         Ok(recommendations)
     }
 
+    /// Replace a recommendation's modules with their dependency chain and the
+    /// firmware the chain needs, so loading them works on first boot
+    fn resolve_module_dependencies(&self, recommendation: &mut DriverRecommendation) {
+        let Some(dependencies) = &self.module_dependencies else {
+            return;
+        };
+
+        let chain = dependencies.resolve(&recommendation.kernel_modules);
+        recommendation.kernel_modules = chain.modules;
+        for file in chain.firmware {
+            if !recommendation.firmware_files.contains(&file) {
+                recommendation.firmware_files.push(file);
+            }
+        }
+    }
+
     fn initialize_driver_database(&mut self) -> Result<(), LxHwError> {
         // Initialize GPU drivers
-        self.driver_database.pci_drivers.insert("nvidia".to_string(), DriverInfo {
-            driver_name: "nvidia".to_string(),
-            driver_type: DriverType::Proprietary,
-            kernel_modules: vec!["nvidia".to_string(), "nvidia_modeset".to_string(), "nvidia_uvm".to_string()],
-            firmware_files: vec![],
-            package_names: {
-                let mut packages = HashMap::new();
-                packages.insert("ubuntu".to_string(), "nvidia-driver-470".to_string());
-                packages.insert("debian".to_string(), "nvidia-driver".to_string());
-                packages.insert("fedora".to_string(), "akmod-nvidia".to_string());
-                packages.insert("arch".to_string(), "nvidia".to_string());
-                packages.insert("nixos".to_string(), "nvidia_x11".to_string());
-                packages
+        self.driver_database.pci_drivers.insert(
+            "nvidia".to_string(),
+            DriverInfo {
+                driver_name: "nvidia".to_string(),
+                driver_type: DriverType::Proprietary,
+                kernel_modules: vec![
+                    "nvidia".to_string(),
+                    "nvidia_modeset".to_string(),
+                    "nvidia_uvm".to_string(),
+                ],
+                firmware_files: vec![],
+                package_names: {
+                    let mut packages = HashMap::new();
+                    packages.insert("ubuntu".to_string(), "nvidia-driver-470".to_string());
+                    packages.insert("debian".to_string(), "nvidia-driver".to_string());
+                    packages.insert("fedora".to_string(), "akmod-nvidia".to_string());
+                    packages.insert("arch".to_string(), "nvidia".to_string());
+                    packages.insert("nixos".to_string(), "nvidia_x11".to_string());
+                    packages
+                },
+                configuration_hints: vec![
+                    "Add nvidia to /etc/modules".to_string(),
+                    "Configure X11 to use nvidia driver".to_string(),
+                ],
+                known_issues: vec![
+                    "May conflict with nouveau driver".to_string(),
+                    "Requires specific kernel version compatibility".to_string(),
+                ],
+                alternatives: vec!["nouveau".to_string()],
             },
-            configuration_hints: vec![
-                "Add nvidia to /etc/modules".to_string(),
-                "Configure X11 to use nvidia driver".to_string(),
-            ],
-            known_issues: vec![
-                "May conflict with nouveau driver".to_string(),
-                "Requires specific kernel version compatibility".to_string(),
-            ],
-            alternatives: vec!["nouveau".to_string()],
-        });
+        );
 
-        self.driver_database.pci_drivers.insert("nouveau".to_string(), DriverInfo {
-            driver_name: "nouveau".to_string(),
-            driver_type: DriverType::InKernel,
-            kernel_modules: vec!["nouveau".to_string()],
-            firmware_files: vec![],
-            package_names: HashMap::new(), // In-kernel driver
-            configuration_hints: vec!["Usually works out of the box".to_string()],
-            known_issues: vec!["Limited performance compared to proprietary nvidia driver".to_string()],
-            alternatives: vec!["nvidia".to_string()],
-        });
+        self.driver_database.pci_drivers.insert(
+            "nouveau".to_string(),
+            DriverInfo {
+                driver_name: "nouveau".to_string(),
+                driver_type: DriverType::InKernel,
+                kernel_modules: vec!["nouveau".to_string()],
+                firmware_files: vec![],
+                package_names: HashMap::new(), // In-kernel driver
+                configuration_hints: vec!["Usually works out of the box".to_string()],
+                known_issues: vec![
+                    "Limited performance compared to proprietary nvidia driver".to_string()
+                ],
+                alternatives: vec!["nvidia".to_string()],
+            },
+        );
 
         // Initialize AMD GPU drivers
-        self.driver_database.pci_drivers.insert("amdgpu".to_string(), DriverInfo {
-            driver_name: "amdgpu".to_string(),
-            driver_type: DriverType::InKernel,
-            kernel_modules: vec!["amdgpu".to_string()],
-            firmware_files: vec!["amdgpu".to_string()],
-            package_names: {
-                let mut packages = HashMap::new();
-                packages.insert("ubuntu".to_string(), "firmware-amd-graphics".to_string());
-                packages.insert("debian".to_string(), "firmware-amd-graphics".to_string());
-                packages.insert("fedora".to_string(), "amd-gpu-firmware".to_string());
-                packages.insert("arch".to_string(), "linux-firmware".to_string());
-                packages.insert("nixos".to_string(), "linux-firmware".to_string());
-                packages
+        self.driver_database.pci_drivers.insert(
+            "amdgpu".to_string(),
+            DriverInfo {
+                driver_name: "amdgpu".to_string(),
+                driver_type: DriverType::InKernel,
+                kernel_modules: vec!["amdgpu".to_string()],
+                firmware_files: vec!["amdgpu".to_string()],
+                package_names: {
+                    let mut packages = HashMap::new();
+                    packages.insert("ubuntu".to_string(), "firmware-amd-graphics".to_string());
+                    packages.insert("debian".to_string(), "firmware-amd-graphics".to_string());
+                    packages.insert("fedora".to_string(), "amd-gpu-firmware".to_string());
+                    packages.insert("arch".to_string(), "linux-firmware".to_string());
+                    packages.insert("nixos".to_string(), "linux-firmware".to_string());
+                    packages
+                },
+                configuration_hints: vec!["Ensure AMD firmware is installed".to_string()],
+                known_issues: vec![],
+                alternatives: vec!["radeon".to_string()],
             },
-            configuration_hints: vec!["Ensure AMD firmware is installed".to_string()],
-            known_issues: vec![],
-            alternatives: vec!["radeon".to_string()],
-        });
+        );
 
         // Initialize Intel GPU drivers
-        self.driver_database.pci_drivers.insert("i915".to_string(), DriverInfo {
-            driver_name: "i915".to_string(),
-            driver_type: DriverType::InKernel,
-            kernel_modules: vec!["i915".to_string()],
-            firmware_files: vec!["i915".to_string()],
-            package_names: {
-                let mut packages = HashMap::new();
-                packages.insert("ubuntu".to_string(), "intel-microcode".to_string());
-                packages.insert("debian".to_string(), "intel-microcode".to_string());
-                packages.insert("fedora".to_string(), "intel-gpu-firmware".to_string());
-                packages.insert("arch".to_string(), "intel-ucode".to_string());
-                packages.insert("nixos".to_string(), "intel-microcode".to_string());
-                packages
+        self.driver_database.pci_drivers.insert(
+            "i915".to_string(),
+            DriverInfo {
+                driver_name: "i915".to_string(),
+                driver_type: DriverType::InKernel,
+                kernel_modules: vec!["i915".to_string()],
+                firmware_files: vec!["i915".to_string()],
+                package_names: {
+                    let mut packages = HashMap::new();
+                    packages.insert("ubuntu".to_string(), "intel-microcode".to_string());
+                    packages.insert("debian".to_string(), "intel-microcode".to_string());
+                    packages.insert("fedora".to_string(), "intel-gpu-firmware".to_string());
+                    packages.insert("arch".to_string(), "intel-ucode".to_string());
+                    packages.insert("nixos".to_string(), "intel-microcode".to_string());
+                    packages
+                },
+                configuration_hints: vec!["Enable early microcode loading".to_string()],
+                known_issues: vec![],
+                alternatives: vec!["xe".to_string()],
             },
-            configuration_hints: vec!["Enable early microcode loading".to_string()],
-            known_issues: vec![],
-            alternatives: vec!["xe".to_string()],
-        });
+        );
 
         // Initialize network drivers
-        self.driver_database.pci_drivers.insert("e1000e".to_string(), DriverInfo {
-            driver_name: "e1000e".to_string(),
-            driver_type: DriverType::InKernel,
-            kernel_modules: vec!["e1000e".to_string()],
-            firmware_files: vec![],
-            package_names: HashMap::new(),
-            configuration_hints: vec!["Usually works out of the box".to_string()],
-            known_issues: vec![],
-            alternatives: vec![],
-        });
+        self.driver_database.pci_drivers.insert(
+            "e1000e".to_string(),
+            DriverInfo {
+                driver_name: "e1000e".to_string(),
+                driver_type: DriverType::InKernel,
+                kernel_modules: vec!["e1000e".to_string()],
+                firmware_files: vec![],
+                package_names: HashMap::new(),
+                configuration_hints: vec!["Usually works out of the box".to_string()],
+                known_issues: vec![],
+                alternatives: vec![],
+            },
+        );
 
         self.driver_database.pci_drivers.insert("iwlwifi".to_string(), DriverInfo {
             driver_name: "iwlwifi".to_string(),
@@ -218,65 +282,105 @@ impl DriverMapper {
     }
 
     fn initialize_vendor_mappings(&mut self) -> Result<(), LxHwError> {
-        self.vendor_mappings.insert("10de".to_string(), VendorInfo {
-            vendor_name: "NVIDIA Corporation".to_string(),
-            vendor_id: "10de".to_string(),
-            typical_drivers: vec!["nvidia".to_string(), "nouveau".to_string()],
-            firmware_prefix: None,
-        });
-
-        self.vendor_mappings.insert("1002".to_string(), VendorInfo {
-            vendor_name: "Advanced Micro Devices".to_string(),
-            vendor_id: "1002".to_string(),
-            typical_drivers: vec!["amdgpu".to_string(), "radeon".to_string()],
-            firmware_prefix: Some("amdgpu".to_string()),
-        });
-
-        self.vendor_mappings.insert("8086".to_string(), VendorInfo {
-            vendor_name: "Intel Corporation".to_string(),
-            vendor_id: "8086".to_string(),
-            typical_drivers: vec!["i915".to_string(), "e1000e".to_string(), "iwlwifi".to_string()],
-            firmware_prefix: Some("intel".to_string()),
-        });
-
-        self.vendor_mappings.insert("1022".to_string(), VendorInfo {
-            vendor_name: "Advanced Micro Devices".to_string(),
-            vendor_id: "1022".to_string(),
-            typical_drivers: vec!["amd64_edac".to_string(), "k10temp".to_string()],
-            firmware_prefix: Some("amd".to_string()),
-        });
+        self.vendor_mappings.insert(
+            "10de".to_string(),
+            VendorInfo {
+                vendor_name: "NVIDIA Corporation".to_string(),
+                vendor_id: "10de".to_string(),
+                typical_drivers: vec!["nvidia".to_string(), "nouveau".to_string()],
+                firmware_prefix: None,
+            },
+        );
+
+        self.vendor_mappings.insert(
+            "1002".to_string(),
+            VendorInfo {
+                vendor_name: "Advanced Micro Devices".to_string(),
+                vendor_id: "1002".to_string(),
+                typical_drivers: vec!["amdgpu".to_string(), "radeon".to_string()],
+                firmware_prefix: Some("amdgpu".to_string()),
+            },
+        );
+
+        self.vendor_mappings.insert(
+            "8086".to_string(),
+            VendorInfo {
+                vendor_name: "Intel Corporation".to_string(),
+                vendor_id: "8086".to_string(),
+                typical_drivers: vec![
+                    "i915".to_string(),
+                    "e1000e".to_string(),
+                    "iwlwifi".to_string(),
+                ],
+                firmware_prefix: Some("intel".to_string()),
+            },
+        );
+
+        self.vendor_mappings.insert(
+            "1022".to_string(),
+            VendorInfo {
+                vendor_name: "Advanced Micro Devices".to_string(),
+                vendor_id: "1022".to_string(),
+                typical_drivers: vec!["amd64_edac".to_string(), "k10temp".to_string()],
+                firmware_prefix: Some("amd".to_string()),
+            },
+        );
 
         Ok(())
     }
 
     fn initialize_device_class_mappings(&mut self) -> Result<(), LxHwError> {
-        self.device_class_mappings.insert("0300".to_string(), DeviceClassInfo {
-            class_name: "VGA compatible controller".to_string(),
-            class_code: "0300".to_string(),
-            common_drivers: vec!["nvidia".to_string(), "nouveau".to_string(), "amdgpu".to_string(), "i915".to_string()],
-            subsystem: "pci".to_string(),
-        });
-
-        self.device_class_mappings.insert("0200".to_string(), DeviceClassInfo {
-            class_name: "Ethernet controller".to_string(),
-            class_code: "0200".to_string(),
-            common_drivers: vec!["e1000e".to_string(), "r8169".to_string(), "bnx2".to_string()],
-            subsystem: "pci".to_string(),
-        });
-
-        self.device_class_mappings.insert("0280".to_string(), DeviceClassInfo {
-            class_name: "Network controller".to_string(),
-            class_code: "0280".to_string(),
-            common_drivers: vec!["iwlwifi".to_string(), "ath9k".to_string(), "rtw88".to_string()],
-            subsystem: "pci".to_string(),
-        });
-
-        self.device_class_mappings.insert("0403".to_string(), DeviceClassInfo {
-            class_name: "Audio device".to_string(),
-            class_code: "0403".to_string(),
-            common_drivers: vec!["snd_hda_intel".to_string(), "snd_hda_codec_realtek".to_string()],
-            subsystem: "pci".to_string(),
-        });
+        self.device_class_mappings.insert(
+            "0300".to_string(),
+            DeviceClassInfo {
+                class_name: "VGA compatible controller".to_string(),
+                class_code: "0300".to_string(),
+                common_drivers: vec![
+                    "nvidia".to_string(),
+                    "nouveau".to_string(),
+                    "amdgpu".to_string(),
+                    "i915".to_string(),
+                ],
+                subsystem: "pci".to_string(),
+            },
+        );
+
+        self.device_class_mappings.insert(
+            "0200".to_string(),
+            DeviceClassInfo {
+                class_name: "Ethernet controller".to_string(),
+                class_code: "0200".to_string(),
+                common_drivers: vec!["e1000e".to_string(), "r8169".to_string(), "bnx2".to_string()],
+                subsystem: "pci".to_string(),
+            },
+        );
+
+        self.device_class_mappings.insert(
+            "0280".to_string(),
+            DeviceClassInfo {
+                class_name: "Network controller".to_string(),
+                class_code: "0280".to_string(),
+                common_drivers: vec![
+                    "iwlwifi".to_string(),
+                    "ath9k".to_string(),
+                    "rtw88".to_string(),
+                ],
+                subsystem: "pci".to_string(),
+            },
+        );
+
+        self.device_class_mappings.insert(
+            "0403".to_string(),
+            DeviceClassInfo {
+                class_name: "Audio device".to_string(),
+                class_code: "0403".to_string(),
+                common_drivers: vec![
+                    "snd_hda_intel".to_string(),
+                    "snd_hda_codec_realtek".to_string(),
+                ],
+                subsystem: "pci".to_string(),
+            },
+        );
 
         Ok(())
     }
@@ -284,10 +388,10 @@ impl DriverMapper {
     #[allow(dead_code)]
     fn map_cpu_drivers(&self, cpu_info: &Value) -> Result<Vec<DriverRecommendation>, LxHwError> {
         let mut recommendations = Vec::new();
-        
+
         if let Some(vendor) = cpu_info.get("vendor").and_then(|v| v.as_str()) {
             let vendor_lower = vendor.to_lowercase();
-            
+
             if vendor_lower.contains("intel") {
                 recommendations.push(DriverRecommendation {
                     hardware_id: "cpu:intel".to_string(),
@@ -300,6 +404,7 @@ impl DriverMapper {
                     installation_priority: 9,
                     compatibility_notes: Some("Early microcode loading recommended".to_string()),
                     kernel_modules: vec!["microcode".to_string()],
+                    firmware_files: vec![],
                 });
             } else if vendor_lower.contains("amd") {
                 recommendations.push(DriverRecommendation {
@@ -311,8 +416,11 @@ impl DriverMapper {
                         package_name: "amd64-microcode".to_string(),
                     },
                     installation_priority: 9,
-                    compatibility_notes: Some("Microcode updates for security and stability".to_string()),
+                    compatibility_notes: Some(
+                        "Microcode updates for security and stability".to_string(),
+                    ),
                     kernel_modules: vec!["microcode".to_string()],
+                    firmware_files: vec![],
                 });
             }
         }
@@ -323,13 +431,13 @@ impl DriverMapper {
     #[allow(dead_code)]
     fn map_gpu_drivers(&self, gpu_info: &Value) -> Result<Vec<DriverRecommendation>, LxHwError> {
         let mut recommendations = Vec::new();
-        
+
         let vendor = gpu_info.get("vendor").and_then(|v| v.as_str()).unwrap_or("unknown");
         let _product = gpu_info.get("product").and_then(|v| v.as_str()).unwrap_or("unknown");
         let device_id = self.extract_pci_id(gpu_info)?;
 
         let vendor_lower = vendor.to_lowercase();
-        
+
         if vendor_lower.contains("nvidia") {
             // Recommend proprietary NVIDIA driver for better performance
             recommendations.push(DriverRecommendation {
@@ -343,6 +451,7 @@ impl DriverMapper {
                 installation_priority: 8,
                 compatibility_notes: Some("Proprietary driver with better performance".to_string()),
                 kernel_modules: vec!["nvidia".to_string(), "nvidia_modeset".to_string()],
+                firmware_files: vec![],
             });
 
             // Also include open-source alternative
@@ -355,6 +464,7 @@ impl DriverMapper {
                 installation_priority: 6,
                 compatibility_notes: Some("Open-source driver, limited performance".to_string()),
                 kernel_modules: vec!["nouveau".to_string()],
+                firmware_files: vec![],
             });
         } else if vendor_lower.contains("amd") || vendor_lower.contains("ati") {
             recommendations.push(DriverRecommendation {
@@ -366,6 +476,7 @@ impl DriverMapper {
                 installation_priority: 8,
                 compatibility_notes: Some("Modern AMD GPUs supported by amdgpu".to_string()),
                 kernel_modules: vec!["amdgpu".to_string()],
+                firmware_files: vec![],
             });
         } else if vendor_lower.contains("intel") {
             recommendations.push(DriverRecommendation {
@@ -377,6 +488,7 @@ impl DriverMapper {
                 installation_priority: 8,
                 compatibility_notes: Some("Intel integrated graphics driver".to_string()),
                 kernel_modules: vec!["i915".to_string()],
+                firmware_files: vec![],
             });
         }
 
@@ -384,16 +496,19 @@ impl DriverMapper {
     }
 
     #[allow(dead_code)]
-    fn map_network_drivers(&self, network_info: &Value) -> Result<Vec<DriverRecommendation>, LxHwError> {
+    fn map_network_drivers(
+        &self,
+        network_info: &Value,
+    ) -> Result<Vec<DriverRecommendation>, LxHwError> {
         let mut recommendations = Vec::new();
-        
+
         let vendor = network_info.get("vendor").and_then(|v| v.as_str()).unwrap_or("unknown");
         let product = network_info.get("product").and_then(|v| v.as_str()).unwrap_or("unknown");
         let device_id = self.extract_pci_id(network_info)?;
 
         let vendor_lower = vendor.to_lowercase();
         let product_lower = product.to_lowercase();
-        
+
         if vendor_lower.contains("intel") {
             if product_lower.contains("wireless") || product_lower.contains("wifi") {
                 recommendations.push(DriverRecommendation {
@@ -405,6 +520,7 @@ impl DriverMapper {
                     installation_priority: 8,
                     compatibility_notes: Some("Requires firmware package".to_string()),
                     kernel_modules: vec!["iwlwifi".to_string()],
+                    firmware_files: vec![],
                 });
             } else if product_lower.contains("ethernet") {
                 recommendations.push(DriverRecommendation {
@@ -416,6 +532,7 @@ impl DriverMapper {
                     installation_priority: 8,
                     compatibility_notes: Some("Intel Ethernet adapter".to_string()),
                     kernel_modules: vec!["e1000e".to_string()],
+                    firmware_files: vec![],
                 });
             }
         } else if vendor_lower.contains("realtek") {
@@ -428,6 +545,7 @@ impl DriverMapper {
                 installation_priority: 8,
                 compatibility_notes: Some("Realtek network adapter".to_string()),
                 kernel_modules: vec!["r8169".to_string()],
+                firmware_files: vec![],
             });
         }
 
@@ -435,9 +553,12 @@ impl DriverMapper {
     }
 
     #[allow(dead_code)]
-    fn map_audio_drivers(&self, audio_info: &Value) -> Result<Vec<DriverRecommendation>, LxHwError> {
+    fn map_audio_drivers(
+        &self,
+        audio_info: &Value,
+    ) -> Result<Vec<DriverRecommendation>, LxHwError> {
         let mut recommendations = Vec::new();
-        
+
         let _vendor = audio_info.get("vendor").and_then(|v| v.as_str()).unwrap_or("unknown");
         let _product = audio_info.get("product").and_then(|v| v.as_str()).unwrap_or("unknown");
         let device_id = self.extract_pci_id(audio_info)?;
@@ -452,18 +573,30 @@ impl DriverMapper {
             installation_priority: 7,
             compatibility_notes: Some("Standard HD Audio driver".to_string()),
             kernel_modules: vec!["snd_hda_intel".to_string()],
+            firmware_files: vec![],
         });
 
         Ok(recommendations)
     }
 
-    fn map_kernel_device_drivers(&self, device: &DeviceCompatibility) -> Result<Vec<DriverRecommendation>, LxHwError> {
+    fn map_kernel_device_drivers(
+        &self,
+        device: &DeviceCompatibility,
+    ) -> Result<Vec<DriverRecommendation>, LxHwError> {
         let mut recommendations = Vec::new();
 
+        // Devices the kernel cannot drive may have a driver maintained outside it
+        if device.support_status == "unsupported" {
+            if let Some(recommendation) = self.map_out_of_tree_driver("pci", &device.device_id) {
+                recommendations.push(recommendation);
+                return Ok(recommendations);
+            }
+        }
+
         // Only create recommendations for devices without existing driver assignments
         if device.driver_module.is_empty() {
             let driver_name = self.infer_driver_from_device(device)?;
-            
+
             if !driver_name.is_empty() && driver_name != "unknown" {
                 recommendations.push(DriverRecommendation {
                     hardware_id: device.device_id.clone(),
@@ -472,8 +605,12 @@ impl DriverMapper {
                     alternative_drivers: vec![],
                     driver_source: DriverSource::KernelBuiltin,
                     installation_priority: 5,
-                    compatibility_notes: Some(format!("Inferred driver for {}", device.device_name)),
+                    compatibility_notes: Some(format!(
+                        "Inferred driver for {}",
+                        device.device_name
+                    )),
                     kernel_modules: vec![driver_name],
+                    firmware_files: vec![],
                 });
             }
         }
@@ -481,6 +618,26 @@ impl DriverMapper {
         Ok(recommendations)
     }
 
+    fn map_out_of_tree_driver(&self, bus: &str, device_id: &str) -> Option<DriverRecommendation> {
+        let driver = self.out_of_tree.lookup(bus, device_id)?;
+        let mut notes = format!("Out-of-tree driver from {}, built with DKMS", driver.project);
+        if !driver.install.is_empty() {
+            notes.push_str(&format!(": {}", driver.install.join(" && ")));
+        }
+
+        Some(DriverRecommendation {
+            hardware_id: device_id.to_string(),
+            component_type: "Device".to_string(),
+            recommended_driver: driver.name.clone(),
+            alternative_drivers: vec![],
+            driver_source: DriverSource::Dkms { module_name: driver.dkms_module.clone() },
+            installation_priority: 7,
+            compatibility_notes: Some(notes),
+            kernel_modules: vec![],
+            firmware_files: vec![],
+        })
+    }
+
     fn infer_driver_from_device(&self, device: &DeviceCompatibility) -> Result<String, LxHwError> {
         let device_name_lower = device.device_name.to_lowercase();
         let device_id_parts: Vec<&str> = device.device_id.split(':').collect();
@@ -494,7 +651,7 @@ impl DriverMapper {
             return Ok("snd_hda_intel".to_string()); // HD Audio driver
         } else if device_name_lower.contains("vga") || device_name_lower.contains("display") {
             // Infer graphics driver from vendor ID
-            if let Some(vendor_id) = device_id_parts.get(0) {
+            if let Some(vendor_id) = device_id_parts.first() {
                 if *vendor_id == "10de" {
                     return Ok("nouveau".to_string()); // NVIDIA
                 } else if *vendor_id == "1002" {
@@ -519,7 +676,7 @@ impl DriverMapper {
                 }
             }
         }
-        
+
         // Fallback to constructing from vendor/product info
         let vendor = device_info.get("vendor").and_then(|v| v.as_str()).unwrap_or("unknown");
         let product = device_info.get("product").and_then(|v| v.as_str()).unwrap_or("unknown");
@@ -527,7 +684,10 @@ impl DriverMapper {
     }
 
     // New methods that work with structured hardware types
-    fn map_cpu_drivers_from_device(&self, cpu: &crate::hardware::CpuInfo) -> Result<Vec<DriverRecommendation>, LxHwError> {
+    fn map_cpu_drivers_from_device(
+        &self,
+        cpu: &crate::hardware::CpuInfo,
+    ) -> Result<Vec<DriverRecommendation>, LxHwError> {
         let mut recommendations = Vec::new();
         let vendor = cpu.vendor.to_lowercase();
 
@@ -548,19 +708,30 @@ impl DriverMapper {
             installation_priority: priority,
             compatibility_notes: Some(format!("CPU frequency scaling driver for {}", cpu.vendor)),
             kernel_modules: vec![driver_name.to_string()],
+            firmware_files: vec![],
         });
 
         Ok(recommendations)
     }
 
-    fn map_gpu_drivers_from_device(&self, gpu: &crate::hardware::GraphicsDevice) -> Result<Vec<DriverRecommendation>, LxHwError> {
+    fn map_gpu_drivers_from_device(
+        &self,
+        gpu: &crate::hardware::GraphicsDevice,
+    ) -> Result<Vec<DriverRecommendation>, LxHwError> {
         let mut recommendations = Vec::new();
         let vendor = gpu.vendor.to_lowercase();
 
+        if let Some(nvidia) = &gpu.nvidia {
+            recommendations.push(self.map_nvidia_driver(gpu, nvidia));
+            return Ok(recommendations);
+        }
+
         let (driver_name, priority, source) = if vendor.contains("nvidia") {
-            ("nvidia", 9, DriverSource::DistributionPackage { 
-                package_name: "nvidia-driver".to_string() 
-            })
+            (
+                "nvidia",
+                9,
+                DriverSource::DistributionPackage { package_name: "nvidia-driver".to_string() },
+            )
         } else if vendor.contains("amd") || vendor.contains("ati") {
             ("amdgpu", 8, DriverSource::KernelBuiltin)
         } else if vendor.contains("intel") {
@@ -578,12 +749,59 @@ impl DriverMapper {
             installation_priority: priority,
             compatibility_notes: Some(format!("Graphics driver for {}", gpu.vendor)),
             kernel_modules: vec![driver_name.to_string()],
+            firmware_files: vec![],
         });
 
         Ok(recommendations)
     }
 
-    fn map_network_drivers_from_device(&self, network: &crate::hardware::NetworkDevice) -> Result<Vec<DriverRecommendation>, LxHwError> {
+    /// Recommend the kernel module flavour nvidia-smi found in use
+    fn map_nvidia_driver(
+        &self,
+        gpu: &crate::hardware::GraphicsDevice,
+        nvidia: &crate::hardware::NvidiaDetails,
+    ) -> DriverRecommendation {
+        use crate::hardware::NvidiaKernelModule;
+
+        let (driver_name, package_name, other) = match nvidia.kernel_module {
+            NvidiaKernelModule::Open => ("nvidia-open", "nvidia-open", "nvidia"),
+            NvidiaKernelModule::Proprietary | NvidiaKernelModule::Unknown => {
+                ("nvidia", "nvidia-driver", "nvidia-open")
+            }
+        };
+
+        let mut notes = format!("NVIDIA driver {} in use", nvidia.driver_version);
+        if let Some(cuda) = &nvidia.cuda_version {
+            notes.push_str(&format!(", supports CUDA {}", cuda));
+        }
+        if let Some(memory) = gpu.memory_bytes {
+            notes.push_str(&format!(", {} MiB VRAM", memory / (1024 * 1024)));
+        }
+
+        DriverRecommendation {
+            hardware_id: gpu.pci_id.clone(),
+            component_type: "GPU".to_string(),
+            recommended_driver: driver_name.to_string(),
+            alternative_drivers: vec![other.to_string(), "nouveau".to_string()],
+            driver_source: DriverSource::DistributionPackage {
+                package_name: package_name.to_string(),
+            },
+            installation_priority: 9,
+            compatibility_notes: Some(notes),
+            kernel_modules: vec![
+                "nvidia".to_string(),
+                "nvidia_modeset".to_string(),
+                "nvidia_uvm".to_string(),
+                "nvidia_drm".to_string(),
+            ],
+            firmware_files: vec![],
+        }
+    }
+
+    fn map_network_drivers_from_device(
+        &self,
+        network: &crate::hardware::NetworkDevice,
+    ) -> Result<Vec<DriverRecommendation>, LxHwError> {
         let mut recommendations = Vec::new();
         let vendor = network.vendor.to_lowercase();
         let device_type = &network.device_type;
@@ -598,7 +816,8 @@ impl DriverMapper {
             } else {
                 ("generic-wifi", 6)
             }
-        } else { // ethernet
+        } else {
+            // ethernet
             if vendor.contains("intel") {
                 ("e1000e", 8)
             } else if vendor.contains("realtek") {
@@ -617,14 +836,21 @@ impl DriverMapper {
             alternative_drivers: vec![],
             driver_source: DriverSource::KernelBuiltin,
             installation_priority: priority,
-            compatibility_notes: Some(format!("{} network driver for {}", device_type, network.vendor)),
+            compatibility_notes: Some(format!(
+                "{} network driver for {}",
+                device_type, network.vendor
+            )),
             kernel_modules: vec![driver_name.to_string()],
+            firmware_files: vec![],
         });
 
         Ok(recommendations)
     }
 
-    fn map_audio_drivers_from_device(&self, audio: &crate::hardware::AudioDevice) -> Result<Vec<DriverRecommendation>, LxHwError> {
+    fn map_audio_drivers_from_device(
+        &self,
+        audio: &crate::hardware::AudioDevice,
+    ) -> Result<Vec<DriverRecommendation>, LxHwError> {
         let mut recommendations = Vec::new();
         let vendor = audio.vendor.to_lowercase();
 
@@ -647,8 +873,170 @@ impl DriverMapper {
             installation_priority: 6,
             compatibility_notes: Some(format!("Audio driver for {}", audio.vendor)),
             kernel_modules: vec![driver_name.to_string(), "snd_pcm".to_string()],
+            firmware_files: vec![],
         });
 
         Ok(recommendations)
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MODULES_DEP: &str = "\
+kernel/drivers/net/wireless/intel/iwlwifi/iwlwifi.ko.zst: kernel/net/wireless/cfg80211.ko.zst
+kernel/net/wireless/cfg80211.ko.zst: kernel/net/rfkill/rfkill.ko.zst
+kernel/net/rfkill/rfkill.ko.zst:
+";
+
+    fn workstation() -> HardwareReport {
+        serde_json::from_str(include_str!("../../tests/fixtures/configuration/workstation.json"))
+            .unwrap()
+    }
+
+    #[test]
+    fn test_recommendations_list_dependency_chain_and_firmware() {
+        let dependencies = ModuleDependencies::parse(MODULES_DEP, "")
+            .with_firmware("iwlwifi", vec!["iwlwifi-so-a0-gf-a0-86.ucode".to_string()]);
+        let mapper = DriverMapper::new().unwrap().with_module_dependencies(dependencies);
+        let recommendations = mapper.map_drivers(&workstation()).unwrap();
+
+        let iwlwifi = recommendations.iter().find(|r| r.recommended_driver == "iwlwifi").unwrap();
+        assert_eq!(iwlwifi.kernel_modules, ["rfkill", "cfg80211", "iwlwifi"]);
+        assert_eq!(iwlwifi.firmware_files, ["iwlwifi-so-a0-gf-a0-86.ucode"]);
+
+        // Modules missing from modules.dep are kept as they are
+        let r8169 = recommendations.iter().find(|r| r.recommended_driver == "r8169").unwrap();
+        assert_eq!(r8169.kernel_modules, ["r8169"]);
+        assert!(r8169.firmware_files.is_empty());
+    }
+
+    fn gpu_recommendation(hardware: &HardwareReport) -> DriverRecommendation {
+        let mapper = DriverMapper::new()
+            .unwrap()
+            .with_module_dependencies(ModuleDependencies::parse("", ""));
+        let recommendations = mapper.map_drivers(hardware).unwrap();
+        recommendations.into_iter().find(|r| r.component_type == "GPU").unwrap()
+    }
+
+    #[test]
+    fn test_nvidia_smi_details_drive_gpu_recommendation() {
+        let gpu = gpu_recommendation(&workstation());
+
+        assert_eq!(gpu.hardware_id, "10de:2484");
+        assert_eq!(gpu.recommended_driver, "nvidia");
+        assert_eq!(gpu.alternative_drivers, ["nvidia-open", "nouveau"]);
+        assert!(matches!(
+            &gpu.driver_source,
+            DriverSource::DistributionPackage { package_name } if package_name == "nvidia-driver"
+        ));
+        assert_eq!(
+            gpu.compatibility_notes.as_deref(),
+            Some("NVIDIA driver 550.67 in use, supports CUDA 12.4, 8192 MiB VRAM")
+        );
+        assert_eq!(gpu.kernel_modules, ["nvidia", "nvidia_modeset", "nvidia_uvm", "nvidia_drm"]);
+    }
+
+    #[test]
+    fn test_open_nvidia_module_recommends_nvidia_open() {
+        let mut hardware = workstation();
+        let nvidia = hardware.graphics[0].nvidia.as_mut().unwrap();
+        nvidia.kernel_module = crate::hardware::NvidiaKernelModule::Open;
+        nvidia.cuda_version = None;
+        hardware.graphics[0].memory_bytes = None;
+        let gpu = gpu_recommendation(&hardware);
+
+        assert_eq!(gpu.recommended_driver, "nvidia-open");
+        assert_eq!(gpu.alternative_drivers, ["nvidia", "nouveau"]);
+        assert!(matches!(
+            &gpu.driver_source,
+            DriverSource::DistributionPackage { package_name } if package_name == "nvidia-open"
+        ));
+        assert_eq!(gpu.compatibility_notes.as_deref(), Some("NVIDIA driver 550.67 in use"));
+    }
+
+    #[test]
+    fn test_nvidia_without_smi_details_uses_vendor_mapping() {
+        let mut hardware = workstation();
+        hardware.graphics[0].nvidia = None;
+        let gpu = gpu_recommendation(&hardware);
+
+        assert_eq!(gpu.recommended_driver, "nvidia");
+        assert!(gpu.alternative_drivers.is_empty());
+        assert_eq!(
+            gpu.compatibility_notes.as_deref(),
+            Some("Graphics driver for NVIDIA Corporation")
+        );
+    }
+
+    fn unknown_device(bus: &str, id: &str) -> crate::hardware::UnknownDevice {
+        let (vendor_id, device_id) = id.split_once(':').unwrap();
+        crate::hardware::UnknownDevice {
+            bus: bus.to_string(),
+            vendor_id: vendor_id.to_string(),
+            device_id: device_id.to_string(),
+            class_code: None,
+            modalias: None,
+            dmesg_lines: vec![],
+        }
+    }
+
+    #[test]
+    fn test_unclaimed_usb_device_gets_out_of_tree_driver() {
+        let mut hardware = workstation();
+        hardware.unknown_devices = vec![
+            unknown_device("usb", "045E:02FE"),
+            unknown_device("usb", "1234:5678"),
+            // PCI devices are matched through the kernel support details instead
+            unknown_device("pci", "10ec:c821"),
+        ];
+        let mapper = DriverMapper::new()
+            .unwrap()
+            .with_module_dependencies(ModuleDependencies::parse("", ""));
+        let recommendations = mapper.map_drivers(&hardware).unwrap();
+
+        let dkms: Vec<_> = recommendations
+            .iter()
+            .filter(|r| matches!(r.driver_source, DriverSource::Dkms { .. }))
+            .collect();
+        assert_eq!(dkms.len(), 1);
+        let xone = dkms[0];
+        assert_eq!(xone.hardware_id, "045e:02fe");
+        assert_eq!(xone.recommended_driver, "xone");
+        assert!(matches!(
+            &xone.driver_source,
+            DriverSource::Dkms { module_name } if module_name == "xone"
+        ));
+        let notes = xone.compatibility_notes.as_deref().unwrap();
+        assert!(notes.starts_with("Out-of-tree driver from https://github.com/medusalix/xone"));
+        assert!(notes.contains("cd xone && sudo ./install.sh && sudo xone-get-firmware.sh"));
+    }
+
+    #[test]
+    fn test_unsupported_pci_device_gets_out_of_tree_driver() {
+        let mut hardware = workstation();
+        hardware.kernel_support.as_mut().unwrap().device_support_details.push(
+            DeviceCompatibility {
+                device_id: "10ec:c821".to_string(),
+                device_name: "RTL8821CE 802.11ac PCIe Wireless Network Adapter".to_string(),
+                support_status: "unsupported".to_string(),
+                driver_module: String::new(),
+                since_kernel_version: None,
+                config_dependencies: vec![],
+                notes: None,
+            },
+        );
+        let mapper = DriverMapper::new()
+            .unwrap()
+            .with_module_dependencies(ModuleDependencies::parse("", ""));
+        let recommendations = mapper.map_drivers(&hardware).unwrap();
+
+        let rtl8821ce = recommendations.iter().find(|r| r.hardware_id == "10ec:c821").unwrap();
+        assert_eq!(rtl8821ce.recommended_driver, "rtl8821ce");
+        assert!(matches!(
+            &rtl8821ce.driver_source,
+            DriverSource::Dkms { module_name } if module_name == "rtl8821ce"
+        ));
+    }
+}
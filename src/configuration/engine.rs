@@ -1,17 +1,23 @@
-use std::collections::HashMap;
-use serde_json::Value;
-use crate::configuration::*;
-use crate::hardware::HardwareReport;
-use crate::errors::LxHwError;
 use crate::configuration::drivers::DriverMapper;
+use crate::configuration::export::ConfigurationTarget;
 use crate::configuration::kernel_params::KernelParameterGenerator;
-use crate::configuration::packages::PackageMapper;
+use crate::configuration::memory::MemoryTuner;
+use crate::configuration::packages::{PackageMapper, PortageSettings};
+use crate::configuration::*;
+use crate::detectors::naming::{NamingRuleStyle, PersistentNames};
+use crate::errors::LxHwError;
+use crate::hardware::HardwareReport;
+use serde_json::Value;
+use std::collections::HashMap;
 
 pub struct ConfigurationEngineImpl {
     driver_mapper: DriverMapper,
     kernel_param_generator: KernelParameterGenerator,
+    memory_tuner: MemoryTuner,
     package_mapper: PackageMapper,
     community_configurations: HashMap<String, Vec<Configuration>>,
+    /// Local device names to pin, set only when the user opts in
+    persistent_naming: Option<(PersistentNames, NamingRuleStyle)>,
 }
 
 impl ConfigurationEngineImpl {
@@ -19,42 +25,102 @@ impl ConfigurationEngineImpl {
         Ok(Self {
             driver_mapper: DriverMapper::new()?,
             kernel_param_generator: KernelParameterGenerator::new()?,
+            memory_tuner: MemoryTuner::new(),
             package_mapper: PackageMapper::new()?,
             community_configurations: HashMap::new(),
+            persistent_naming: None,
         })
     }
 
-    pub fn load_community_configurations(&mut self, configurations: Vec<Configuration>) -> Result<(), LxHwError> {
+    /// Include systemd.link files or udev rules pinning the local system's
+    /// interface and disk names in generated configurations
+    ///
+    /// Opt-in, as the files embed MAC addresses and disk serial numbers that
+    /// the anonymized report does not carry.
+    pub fn enable_persistent_naming(&mut self, names: PersistentNames, style: NamingRuleStyle) {
+        self.persistent_naming = Some((names, style));
+    }
+
+    fn persistent_naming_files(&self) -> HashMap<String, ConfigurationFile> {
+        let Some((names, style)) = &self.persistent_naming else {
+            return HashMap::new();
+        };
+
+        names
+            .rule_files(*style)
+            .into_iter()
+            .map(|file| {
+                let name = file.path.rsplit('/').next().unwrap_or(&file.path).to_string();
+                let validation_command =
+                    file.path.ends_with(".rules").then(|| format!("udevadm verify {}", file.path));
+                (
+                    name,
+                    ConfigurationFile {
+                        file_path: file.path,
+                        content: file.content,
+                        backup_original: true,
+                        file_permissions: "0644".to_string(),
+                        validation_command,
+                    },
+                )
+            })
+            .collect()
+    }
+
+    /// USE flags and make.conf settings for building the hardware's
+    /// packages on Gentoo
+    pub fn portage_settings(&self, hardware: &HardwareReport) -> PortageSettings {
+        self.package_mapper.portage_settings(hardware)
+    }
+
+    /// Generate the configuration for a target system and render it in the
+    /// target's native format; `distribution` applies to targets that do not
+    /// imply one
+    pub fn export(
+        &self,
+        hardware: &HardwareReport,
+        target: ConfigurationTarget,
+        distribution: &str,
+    ) -> Result<String, LxHwError> {
+        let configuration =
+            self.generate_configuration(hardware, target.distribution().unwrap_or(distribution))?;
+        target.render(&configuration)
+    }
+
+    pub fn load_community_configurations(
+        &mut self,
+        configurations: Vec<Configuration>,
+    ) -> Result<(), LxHwError> {
         for config in configurations {
             let hardware_key = self.generate_hardware_key(&config.hardware_profile)?;
-            self.community_configurations
-                .entry(hardware_key)
-                .or_insert_with(Vec::new)
-                .push(config);
+            self.community_configurations.entry(hardware_key).or_default().push(config);
         }
         Ok(())
     }
 
     fn generate_hardware_key(&self, profile: &HardwareProfile) -> Result<String, LxHwError> {
         let mut key_parts = Vec::new();
-        
+
         if let Some(cpu) = &profile.cpu {
             key_parts.push(format!("cpu:{}:{}", cpu.vendor, cpu.architecture));
         }
-        
+
         for gpu in &profile.gpu {
             key_parts.push(format!("gpu:{}:{}", gpu.vendor, gpu.device_id));
         }
-        
+
         for network in &profile.network {
             key_parts.push(format!("net:{}:{}", network.vendor, network.device_id));
         }
-        
+
         key_parts.sort();
         Ok(key_parts.join("|"))
     }
 
-    fn extract_hardware_profile(&self, hardware: &HardwareReport) -> Result<HardwareProfile, LxHwError> {
+    fn extract_hardware_profile(
+        &self,
+        hardware: &HardwareReport,
+    ) -> Result<HardwareProfile, LxHwError> {
         let mut profile = HardwareProfile {
             cpu: None,
             gpu: Vec::new(),
@@ -108,7 +174,10 @@ impl ConfigurationEngineImpl {
         Ok(profile)
     }
 
-    fn extract_cpu_features(&self, cpu_info: &crate::hardware::CpuInfo) -> Result<Vec<String>, LxHwError> {
+    fn extract_cpu_features(
+        &self,
+        cpu_info: &crate::hardware::CpuInfo,
+    ) -> Result<Vec<String>, LxHwError> {
         Ok(cpu_info.flags.clone())
     }
 
@@ -117,10 +186,13 @@ impl ConfigurationEngineImpl {
         Ok(vendor_lower.contains("intel") || vendor_lower.contains("amd"))
     }
 
-    fn detect_power_management(&self, cpu_info: &crate::hardware::CpuInfo) -> Result<PowerManagement, LxHwError> {
+    fn detect_power_management(
+        &self,
+        cpu_info: &crate::hardware::CpuInfo,
+    ) -> Result<PowerManagement, LxHwError> {
         // Default power management settings based on CPU info
         let vendor = cpu_info.vendor.to_lowercase();
-        
+
         let (governor, scaling_driver) = if vendor.contains("intel") {
             ("performance".to_string(), "intel_pstate".to_string())
         } else if vendor.contains("amd") {
@@ -150,7 +222,8 @@ impl ConfigurationEngineImpl {
             "auto"
         } else {
             "powersave"
-        }.to_string();
+        }
+        .to_string();
 
         Ok(GpuProfile {
             vendor: vendor.to_string(),
@@ -167,14 +240,17 @@ impl ConfigurationEngineImpl {
         let vendor = network_info.get("vendor").and_then(|v| v.as_str()).unwrap_or("unknown");
         let product = network_info.get("product").and_then(|v| v.as_str()).unwrap_or("unknown");
         let device_id = self.extract_device_id(network_info)?;
-        
-        let interface_type = if product.to_lowercase().contains("wireless") || product.to_lowercase().contains("wifi") {
+
+        let interface_type = if product.to_lowercase().contains("wireless")
+            || product.to_lowercase().contains("wifi")
+        {
             "wireless"
         } else if product.to_lowercase().contains("ethernet") {
             "ethernet"
         } else {
             "unknown"
-        }.to_string();
+        }
+        .to_string();
 
         let firmware_needed = self.requires_firmware(vendor, &interface_type)?;
 
@@ -183,7 +259,8 @@ impl ConfigurationEngineImpl {
             vendor: vendor.to_string(),
             model: product.to_string(),
             device_id,
-            driver_name: network_info.get("configuration")
+            driver_name: network_info
+                .get("configuration")
                 .and_then(|c| c.get("driver"))
                 .and_then(|d| d.as_str())
                 .map(|s| s.to_string()),
@@ -195,7 +272,7 @@ impl ConfigurationEngineImpl {
     fn extract_storage_profile(&self, storage_info: &Value) -> Result<StorageProfile, LxHwError> {
         let vendor = storage_info.get("vendor").and_then(|v| v.as_str()).unwrap_or("unknown");
         let product = storage_info.get("product").and_then(|v| v.as_str()).unwrap_or("unknown");
-        
+
         let device_type = if product.to_lowercase().contains("ssd") {
             "ssd"
         } else if product.to_lowercase().contains("nvme") {
@@ -204,7 +281,8 @@ impl ConfigurationEngineImpl {
             "hdd"
         } else {
             "unknown"
-        }.to_string();
+        }
+        .to_string();
 
         let interface = if product.to_lowercase().contains("nvme") {
             "nvme"
@@ -212,7 +290,8 @@ impl ConfigurationEngineImpl {
             "sata"
         } else {
             "unknown"
-        }.to_string();
+        }
+        .to_string();
 
         let optimizations = self.get_storage_optimizations(&device_type)?;
 
@@ -221,8 +300,7 @@ impl ConfigurationEngineImpl {
             interface,
             vendor: vendor.to_string(),
             model: product.to_string(),
-            capacity: storage_info.get("size")
-                .and_then(|s| s.as_u64()),
+            capacity: storage_info.get("size").and_then(|s| s.as_u64()),
             optimizations,
         })
     }
@@ -242,7 +320,10 @@ impl ConfigurationEngineImpl {
         })
     }
 
-    fn extract_gpu_profile_from_device(&self, gpu: &crate::hardware::GraphicsDevice) -> Result<GpuProfile, LxHwError> {
+    fn extract_gpu_profile_from_device(
+        &self,
+        gpu: &crate::hardware::GraphicsDevice,
+    ) -> Result<GpuProfile, LxHwError> {
         let driver_options = self.get_gpu_driver_options(&gpu.vendor)?;
         let performance_profile = if gpu.vendor.to_lowercase().contains("nvidia") {
             "performance"
@@ -250,7 +331,8 @@ impl ConfigurationEngineImpl {
             "auto"
         } else {
             "powersave"
-        }.to_string();
+        }
+        .to_string();
 
         Ok(GpuProfile {
             vendor: gpu.vendor.clone(),
@@ -262,7 +344,10 @@ impl ConfigurationEngineImpl {
         })
     }
 
-    fn extract_network_profile_from_device(&self, network: &crate::hardware::NetworkDevice) -> Result<NetworkProfile, LxHwError> {
+    fn extract_network_profile_from_device(
+        &self,
+        network: &crate::hardware::NetworkDevice,
+    ) -> Result<NetworkProfile, LxHwError> {
         let interface_type = network.device_type.clone();
         let firmware_needed = self.requires_firmware(&network.vendor, &interface_type)?;
 
@@ -276,7 +361,10 @@ impl ConfigurationEngineImpl {
         })
     }
 
-    fn extract_storage_profile_from_device(&self, storage: &crate::hardware::StorageDevice) -> Result<StorageProfile, LxHwError> {
+    fn extract_storage_profile_from_device(
+        &self,
+        storage: &crate::hardware::StorageDevice,
+    ) -> Result<StorageProfile, LxHwError> {
         let device_type = storage.device_type.clone();
         let interface = storage.interface.clone().unwrap_or_else(|| "unknown".to_string());
         let optimizations = self.get_storage_optimizations(&device_type)?;
@@ -291,7 +379,10 @@ impl ConfigurationEngineImpl {
         })
     }
 
-    fn extract_audio_profile_from_device(&self, audio: &crate::hardware::AudioDevice) -> Result<AudioProfile, LxHwError> {
+    fn extract_audio_profile_from_device(
+        &self,
+        audio: &crate::hardware::AudioDevice,
+    ) -> Result<AudioProfile, LxHwError> {
         Ok(AudioProfile {
             vendor: audio.vendor.clone(),
             codec: audio.model.clone(),
@@ -301,14 +392,22 @@ impl ConfigurationEngineImpl {
         })
     }
 
-    fn extract_usb_controller_profile(&self, device_info: &crate::hardware::DeviceCompatibility) -> Result<UsbControllerProfile, LxHwError> {
-        let version = if device_info.device_name.contains("USB 3") || device_info.device_name.contains("xHCI") {
+    fn extract_usb_controller_profile(
+        &self,
+        device_info: &crate::hardware::DeviceCompatibility,
+    ) -> Result<UsbControllerProfile, LxHwError> {
+        let version = if device_info.device_name.contains("USB 3")
+            || device_info.device_name.contains("xHCI")
+        {
             "3.0"
-        } else if device_info.device_name.contains("USB 2") || device_info.device_name.contains("EHCI") {
+        } else if device_info.device_name.contains("USB 2")
+            || device_info.device_name.contains("EHCI")
+        {
             "2.0"
         } else {
             "1.1"
-        }.to_string();
+        }
+        .to_string();
 
         Ok(UsbControllerProfile {
             version,
@@ -328,7 +427,7 @@ impl ConfigurationEngineImpl {
                 }
             }
         }
-        
+
         // Fallback to product name or vendor info
         if let Some(product) = device_info.get("product").and_then(|p| p.as_str()) {
             Ok(product.to_string())
@@ -351,55 +450,48 @@ impl ConfigurationEngineImpl {
     }
 
     fn requires_firmware(&self, vendor: &str, interface_type: &str) -> Result<bool, LxHwError> {
-        if interface_type == "wireless" {
-            Ok(true) // Most wireless cards need firmware
-        } else if vendor.to_lowercase().contains("broadcom") {
-            Ok(true) // Broadcom typically needs firmware
-        } else {
-            Ok(false)
-        }
+        // Most wireless cards, and Broadcom cards in general, need firmware
+        Ok(interface_type == "wireless" || vendor.to_lowercase().contains("broadcom"))
     }
 
     fn get_storage_optimizations(&self, device_type: &str) -> Result<Vec<String>, LxHwError> {
         match device_type {
-            "ssd" | "nvme" => Ok(vec![
-                "discard".to_string(),
-                "noatime".to_string(),
-                "scheduler=none".to_string(),
-            ]),
-            "hdd" => Ok(vec![
-                "relatime".to_string(),
-                "scheduler=mq-deadline".to_string(),
-            ]),
+            "ssd" | "nvme" => {
+                Ok(vec!["discard".to_string(), "noatime".to_string(), "scheduler=none".to_string()])
+            }
+            "hdd" => Ok(vec!["relatime".to_string(), "scheduler=mq-deadline".to_string()]),
             _ => Ok(Vec::new()),
         }
     }
 
-    fn calculate_compatibility_score(&self, hardware: &HardwareReport, recommendations: &[DriverRecommendation]) -> Result<f64, LxHwError> {
+    fn calculate_compatibility_score(
+        &self,
+        hardware: &HardwareReport,
+        recommendations: &[DriverRecommendation],
+    ) -> Result<f64, LxHwError> {
         let total_devices = if let Some(kernel_support) = &hardware.kernel_support {
             kernel_support.total_devices_detected as f64
         } else {
             1.0 // Avoid division by zero
         };
 
-        let supported_devices = recommendations.iter()
-            .filter(|r| r.recommended_driver != "unknown")
-            .count() as f64;
+        let supported_devices =
+            recommendations.iter().filter(|r| r.recommended_driver != "unknown").count() as f64;
 
         let compatibility_ratio = supported_devices / total_devices;
-        
+
         // Weight the score based on critical components
         let mut weighted_score = compatibility_ratio * 0.7; // Base compatibility
-        
+
         // Add weights for critical components
         if hardware.cpu.is_some() {
             weighted_score += 0.1;
         }
-        
+
         if !hardware.graphics.is_empty() {
             weighted_score += 0.1;
         }
-        
+
         if !hardware.network.is_empty() {
             weighted_score += 0.1;
         }
@@ -410,12 +502,24 @@ impl ConfigurationEngineImpl {
 }
 
 impl ConfigurationEngine for ConfigurationEngineImpl {
-    fn generate_configuration(&self, hardware: &HardwareReport, target_distribution: &str) -> Result<Configuration, LxHwError> {
+    fn generate_configuration(
+        &self,
+        hardware: &HardwareReport,
+        target_distribution: &str,
+    ) -> Result<Configuration, LxHwError> {
         let hardware_profile = self.extract_hardware_profile(hardware)?;
         let driver_recommendations = self.recommend_drivers(hardware)?;
         let kernel_parameters = self.generate_kernel_parameters(hardware)?;
         let package_installations = self.suggest_packages(hardware, target_distribution)?;
-        let compatibility_score = self.calculate_compatibility_score(hardware, &driver_recommendations)?;
+        let compatibility_score =
+            self.calculate_compatibility_score(hardware, &driver_recommendations)?;
+        let memory_tuning = self.memory_tuner.recommend(hardware);
+        let mut configuration_files = self.persistent_naming_files();
+        configuration_files.extend(memory_tuning.configuration_files);
+        if self.package_mapper.uses_portage(target_distribution) {
+            configuration_files
+                .extend(self.package_mapper.portage_settings(hardware).configuration_files());
+        }
 
         Ok(Configuration {
             system_id: hardware.metadata.anonymized_system_id.clone(),
@@ -426,8 +530,8 @@ impl ConfigurationEngine for ConfigurationEngineImpl {
             kernel_parameters,
             package_installations,
             dkms_modules: Vec::new(), // TODO: Implement DKMS module detection
-            configuration_files: HashMap::new(), // TODO: Implement config file generation
-            performance_optimizations: Vec::new(), // TODO: Implement performance optimizations
+            configuration_files,
+            performance_optimizations: memory_tuning.optimizations,
             compatibility_score,
         })
     }
@@ -437,15 +541,79 @@ impl ConfigurationEngine for ConfigurationEngineImpl {
         self.calculate_compatibility_score(hardware, &recommendations)
     }
 
-    fn recommend_drivers(&self, hardware: &HardwareReport) -> Result<Vec<DriverRecommendation>, LxHwError> {
+    fn recommend_drivers(
+        &self,
+        hardware: &HardwareReport,
+    ) -> Result<Vec<DriverRecommendation>, LxHwError> {
         self.driver_mapper.map_drivers(hardware)
     }
 
-    fn generate_kernel_parameters(&self, hardware: &HardwareReport) -> Result<Vec<KernelParameter>, LxHwError> {
+    fn generate_kernel_parameters(
+        &self,
+        hardware: &HardwareReport,
+    ) -> Result<Vec<KernelParameter>, LxHwError> {
         self.kernel_param_generator.generate_parameters(hardware)
     }
 
-    fn suggest_packages(&self, hardware: &HardwareReport, distribution: &str) -> Result<Vec<PackageInstallation>, LxHwError> {
+    fn suggest_packages(
+        &self,
+        hardware: &HardwareReport,
+        distribution: &str,
+    ) -> Result<Vec<PackageInstallation>, LxHwError> {
         self.package_mapper.map_packages(hardware, distribution)
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::detectors::naming::{DiskName, InterfaceName};
+
+    fn workstation() -> HardwareReport {
+        serde_json::from_str(include_str!("../../tests/fixtures/configuration/workstation.json"))
+            .unwrap()
+    }
+
+    fn names() -> PersistentNames {
+        PersistentNames {
+            interfaces: vec![InterfaceName {
+                name: "eth0".to_string(),
+                mac_address: "52:54:00:12:34:56".to_string(),
+                bus_address: Some("0000:03:00.0".to_string()),
+                driver: Some("r8169".to_string()),
+                wireless: false,
+            }],
+            disks: vec![DiskName {
+                name: "nvme0n1".to_string(),
+                serial: Some("Samsung_SSD_980_PRO_1TB_S5GX".to_string()),
+                by_id: vec![],
+                by_path: vec![],
+            }],
+        }
+    }
+
+    #[test]
+    fn test_persistent_naming_files_are_opt_in() {
+        let mut engine = ConfigurationEngineImpl::new().unwrap();
+        let plain = engine.generate_configuration(&workstation(), "Fedora Linux 40").unwrap();
+        assert!(!plain.configuration_files.values().any(|f| f.content.contains("52:54:00")));
+
+        engine.enable_persistent_naming(names(), NamingRuleStyle::Udev);
+        let pinned = engine.generate_configuration(&workstation(), "Fedora Linux 40").unwrap();
+        let net = &pinned.configuration_files["70-persistent-net.rules"];
+        assert_eq!(net.file_path, "/etc/udev/rules.d/70-persistent-net.rules");
+        assert!(net.content.contains("ATTR{address}==\"52:54:00:12:34:56\", NAME=\"lan0\""));
+        assert_eq!(
+            net.validation_command.as_deref(),
+            Some("udevadm verify /etc/udev/rules.d/70-persistent-net.rules")
+        );
+        assert!(pinned.configuration_files.contains_key("71-persistent-disk.rules"));
+
+        engine.enable_persistent_naming(names(), NamingRuleStyle::Link);
+        let links = engine.generate_configuration(&workstation(), "Fedora Linux 40").unwrap();
+        let link = &links.configuration_files["10-persistent-lan0.link"];
+        assert_eq!(link.file_path, "/etc/systemd/network/10-persistent-lan0.link");
+        assert_eq!(link.validation_command, None);
+        assert!(link.backup_original);
+    }
+}
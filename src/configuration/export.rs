@@ -0,0 +1,78 @@
+//! Configurations rendered in the native format of a target system
+//!
+//! `configure --target <target>` picks one of these instead of the JSON
+//! output. Every target renders the same generated Configuration, so a
+//! target only decides how drivers, parameters and packages are expressed.
+
+use crate::configuration::nixos::NixosModule;
+use crate::configuration::recommendations::ansible_playbook;
+use crate::configuration::*;
+use crate::errors::LxHwError;
+use clap::ValueEnum;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ConfigurationTarget {
+    /// NixOS module to import from configuration.nix
+    Nixos,
+    /// Ansible playbook for hosts running the chosen distribution
+    Ansible,
+    /// Bash script with an --undo mode
+    Shell,
+}
+
+impl ConfigurationTarget {
+    /// Distribution the target implies; targets without one are generated
+    /// for the distribution the user picks
+    pub fn distribution(&self) -> Option<&'static str> {
+        match self {
+            Self::Nixos => Some("NixOS"),
+            Self::Ansible | Self::Shell => None,
+        }
+    }
+
+    /// File name the rendered configuration is saved as
+    pub fn file_name(&self) -> &'static str {
+        match self {
+            Self::Nixos => "lx-hw-configuration.nix",
+            Self::Ansible => "lx-hw-playbook.yml",
+            Self::Shell => "lx-hw-setup.sh",
+        }
+    }
+
+    pub fn render(&self, configuration: &Configuration) -> Result<String, LxHwError> {
+        match self {
+            Self::Nixos => Ok(NixosModule::new(configuration).render()),
+            Self::Ansible => ansible_playbook(configuration),
+            Self::Shell => Ok(configuration.to_shell_script()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::configuration::engine::ConfigurationEngineImpl;
+    use crate::configuration::recommendations::RecommendationEngine;
+    use crate::hardware::HardwareReport;
+
+    fn workstation() -> HardwareReport {
+        serde_json::from_str(include_str!("../../tests/fixtures/configuration/workstation.json"))
+            .unwrap()
+    }
+
+    #[test]
+    fn test_ansible_export_matches_recommendation_engine() {
+        let engine = ConfigurationEngineImpl::new().unwrap();
+        let exported =
+            engine.export(&workstation(), ConfigurationTarget::Ansible, "Ubuntu 24.04").unwrap();
+        let playbook = RecommendationEngine::new()
+            .unwrap()
+            .generate_ansible_playbook(&workstation(), "Ubuntu 24.04")
+            .unwrap();
+
+        assert_eq!(exported, playbook);
+        assert!(exported.contains("# Generated for: Ubuntu 24.04 (kernel 6.8.0)"));
+        assert_eq!(ConfigurationTarget::Ansible.distribution(), None);
+        assert_eq!(ConfigurationTarget::Ansible.file_name(), "lx-hw-playbook.yml");
+    }
+}
@@ -0,0 +1,383 @@
+//! Step-by-step setup guides rendered from a generated configuration
+//!
+//! Guides are meant for sharing with people who will not read the JSON output:
+//! every change becomes a numbered step with an explanation and a copy-paste
+//! command block, and steps are grouped so the low-risk ones come first.
+
+use crate::configuration::*;
+use clap::ValueEnum;
+use std::fmt::Write as _;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum GuideFormat {
+    Markdown,
+    Html,
+}
+
+/// One change the reader applies
+#[derive(Debug, Clone)]
+pub struct GuideStep {
+    pub title: String,
+    pub risk: RiskLevel,
+    pub explanation: Vec<String>,
+    pub commands: Vec<String>,
+}
+
+pub struct ConfigurationGuide<'a> {
+    configuration: &'a Configuration,
+    steps: Vec<GuideStep>,
+}
+
+impl<'a> ConfigurationGuide<'a> {
+    pub fn new(configuration: &'a Configuration) -> Self {
+        Self { configuration, steps: collect_steps(configuration) }
+    }
+
+    pub fn steps(&self) -> &[GuideStep] {
+        &self.steps
+    }
+
+    pub fn render(&self, format: GuideFormat) -> String {
+        match format {
+            GuideFormat::Markdown => self.to_markdown(),
+            GuideFormat::Html => self.to_html(),
+        }
+    }
+
+    pub fn to_markdown(&self) -> String {
+        let config = self.configuration;
+        let mut out = String::from("# Hardware Setup Guide\n\n");
+        let _ = writeln!(
+            out,
+            "**Distribution**: {} | **Kernel**: {} | **Compatibility score**: {:.0}%\n",
+            config.target_distribution, config.kernel_version, config.compatibility_score
+        );
+        out.push_str("Work through the steps in order and reboot once at the end. Commands need administrator rights.\n");
+        if self.steps.is_empty() {
+            out.push_str("\nNo changes are needed for this hardware.\n");
+            return out;
+        }
+
+        let mut number = 0;
+        for risk in [RiskLevel::Low, RiskLevel::Medium, RiskLevel::High] {
+            let steps: Vec<&GuideStep> =
+                self.steps.iter().filter(|s| same_risk(&s.risk, &risk)).collect();
+            if steps.is_empty() {
+                continue;
+            }
+            let (heading, note) = risk_heading(&risk);
+            let _ = write!(out, "\n## {}\n\n{}\n", heading, note);
+            for step in steps {
+                number += 1;
+                let _ = write!(out, "\n### {}. {}\n\n", number, step.title);
+                for line in &step.explanation {
+                    let _ = writeln!(out, "{}\n", line);
+                }
+                if !step.commands.is_empty() {
+                    let _ = write!(out, "```sh\n{}\n```\n", step.commands.join("\n"));
+                }
+            }
+        }
+        out
+    }
+
+    pub fn to_html(&self) -> String {
+        let config = self.configuration;
+        let mut out = String::from("<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n<title>Hardware Setup Guide</title>\n");
+        out.push_str("<style>body{font-family:sans-serif;max-width:50em;margin:2em auto;line-height:1.5}pre{background:#f4f4f4;padding:1em;overflow-x:auto}.risk-low{color:#1a7f37}.risk-medium{color:#9a6700}.risk-high{color:#cf222e}</style>\n</head>\n<body>\n");
+        out.push_str("<h1>Hardware Setup Guide</h1>\n");
+        let _ = writeln!(out, "<p><strong>Distribution</strong>: {} | <strong>Kernel</strong>: {} | <strong>Compatibility score</strong>: {:.0}%</p>", escape_html(&config.target_distribution), escape_html(&config.kernel_version), config.compatibility_score);
+        out.push_str("<p>Work through the steps in order and reboot once at the end. Commands need administrator rights.</p>\n");
+        if self.steps.is_empty() {
+            out.push_str("<p>No changes are needed for this hardware.</p>\n");
+        }
+
+        let mut number = 0;
+        for risk in [RiskLevel::Low, RiskLevel::Medium, RiskLevel::High] {
+            let steps: Vec<&GuideStep> =
+                self.steps.iter().filter(|s| same_risk(&s.risk, &risk)).collect();
+            if steps.is_empty() {
+                continue;
+            }
+            let (heading, note) = risk_heading(&risk);
+            let class = format!("{:?}", risk).to_lowercase();
+            let _ = writeln!(
+                out,
+                "<h2 class=\"risk-{}\">{}</h2>\n<p>{}</p>",
+                class,
+                heading,
+                escape_html(note)
+            );
+            out.push_str("<ol>\n");
+            for step in steps {
+                number += 1;
+                let _ =
+                    writeln!(out, "<li value=\"{}\"><h3>{}</h3>", number, escape_html(&step.title));
+                for line in &step.explanation {
+                    let _ = writeln!(out, "<p>{}</p>", escape_html(line));
+                }
+                if !step.commands.is_empty() {
+                    let _ = writeln!(
+                        out,
+                        "<pre><code>{}</code></pre>",
+                        escape_html(&step.commands.join("\n"))
+                    );
+                }
+                out.push_str("</li>\n");
+            }
+            out.push_str("</ol>\n");
+        }
+        out.push_str("</body>\n</html>\n");
+        out
+    }
+}
+
+fn collect_steps(config: &Configuration) -> Vec<GuideStep> {
+    let mut steps = Vec::new();
+
+    for package in &config.package_installations {
+        let mut commands = vec![package.installation_command.clone()];
+        commands.extend(package.post_install_commands.iter().cloned());
+        steps.push(GuideStep {
+            title: format!("Install {}", package.package_name),
+            risk: RiskLevel::Low,
+            explanation: vec![format!(
+                "{} (needed for {}).",
+                package.package_description.trim_end_matches('.'),
+                reason_text(&package.installation_reason)
+            )],
+            commands,
+        });
+    }
+
+    for driver in &config.driver_recommendations {
+        let mut explanation = vec![format!(
+            "Use the `{}` driver for {} {}.",
+            driver.recommended_driver, driver.component_type, driver.hardware_id
+        )];
+        if let Some(notes) = &driver.compatibility_notes {
+            explanation.push(notes.clone());
+        }
+        if !driver.alternative_drivers.is_empty() {
+            explanation.push(format!("Alternatives: {}.", driver.alternative_drivers.join(", ")));
+        }
+        if !driver.firmware_files.is_empty() {
+            explanation.push(format!("Requires firmware: {}.", driver.firmware_files.join(", ")));
+        }
+        let risk = match driver.driver_source {
+            DriverSource::KernelBuiltin | DriverSource::DistributionPackage { .. } => {
+                RiskLevel::Low
+            }
+            DriverSource::ThirdParty { .. } | DriverSource::Dkms { .. } => RiskLevel::High,
+        };
+        steps.push(GuideStep {
+            title: format!("Load the {} driver", driver.recommended_driver),
+            risk,
+            explanation,
+            commands: driver
+                .kernel_modules
+                .iter()
+                .map(|module| format!("sudo modprobe {}", module))
+                .collect(),
+        });
+    }
+
+    let mut files: Vec<&ConfigurationFile> = config.configuration_files.values().collect();
+    files.sort_by(|a, b| a.file_path.cmp(&b.file_path));
+    for file in files {
+        let mut commands = Vec::new();
+        if file.backup_original {
+            commands.push(format!("sudo cp -a {0} {0}.bak 2>/dev/null || true", file.file_path));
+        }
+        commands.push(format!(
+            "sudo tee {} > /dev/null << 'EOF'\n{}\nEOF",
+            file.file_path,
+            file.content.trim_end()
+        ));
+        commands.push(format!("sudo chmod {} {}", file.file_permissions, file.file_path));
+        if let Some(validation) = &file.validation_command {
+            commands.push(validation.clone());
+        }
+        steps.push(GuideStep {
+            title: format!("Write {}", file.file_path),
+            risk: RiskLevel::Low,
+            explanation: vec![format!(
+                "Creates `{}` with the settings for your hardware.",
+                file.file_path
+            )],
+            commands,
+        });
+    }
+
+    if !config.kernel_parameters.is_empty() {
+        let mut params: Vec<&KernelParameter> = config.kernel_parameters.iter().collect();
+        params.sort_by_key(|p| p.boot_order);
+        let cmdline: Vec<String> = params.iter().map(|p| cmdline_entry(p)).collect();
+        steps.push(GuideStep {
+            title: "Add kernel boot parameters".to_string(),
+            risk: RiskLevel::Medium,
+            explanation: params.iter().map(|p| format!("`{}`: {}", cmdline_entry(p), p.purpose)).collect(),
+            commands: vec![
+                format!("sudo sed -i 's/^GRUB_CMDLINE_LINUX_DEFAULT=\"\\(.*\\)\"/GRUB_CMDLINE_LINUX_DEFAULT=\"\\1 {}\"/' /etc/default/grub", cmdline.join(" ")),
+                "sudo grub-mkconfig -o /boot/grub/grub.cfg".to_string(),
+            ],
+        });
+    }
+
+    for optimization in &config.performance_optimizations {
+        let mut explanation = vec![format!(
+            "{} Expected improvement: {}.",
+            optimization.description,
+            optimization.expected_improvement.trim_end_matches('.')
+        )];
+        explanation.extend(optimization.configuration_changes.iter().map(|change| {
+            format!("`{}` in {}: {}", change.parameter, change.file_path, change.comment)
+        }));
+        steps.push(GuideStep {
+            title: optimization.optimization_type.clone(),
+            risk: optimization.risk_level.clone(),
+            explanation,
+            commands: optimization
+                .configuration_changes
+                .iter()
+                .map(|change| {
+                    format!(
+                        "echo '{}={}' | sudo tee -a {}",
+                        change.parameter, change.new_value, change.file_path
+                    )
+                })
+                .collect(),
+        });
+    }
+
+    for module in &config.dkms_modules {
+        let mut explanation = vec![format!(
+            "Builds the out-of-tree `{}` {} module for your kernel.",
+            module.module_name, module.version
+        )];
+        if !module.build_dependencies.is_empty() {
+            explanation
+                .push(format!("Build dependencies: {}.", module.build_dependencies.join(", ")));
+        }
+        if module.auto_rebuild {
+            explanation
+                .push("DKMS rebuilds the module automatically after kernel updates.".to_string());
+        }
+        steps.push(GuideStep {
+            title: format!("Build the {} module with DKMS", module.module_name),
+            risk: RiskLevel::High,
+            explanation,
+            commands: module.installation_steps.clone(),
+        });
+    }
+
+    steps
+}
+
+fn cmdline_entry(param: &KernelParameter) -> String {
+    match &param.value {
+        Some(value) => format!("{}={}", param.parameter, value),
+        None => param.parameter.clone(),
+    }
+}
+
+fn same_risk(a: &RiskLevel, b: &RiskLevel) -> bool {
+    std::mem::discriminant(a) == std::mem::discriminant(b)
+}
+
+fn risk_heading(risk: &RiskLevel) -> (&'static str, &'static str) {
+    match risk {
+        RiskLevel::Low => ("Low risk", "Safe changes that are easy to undo."),
+        RiskLevel::Medium => ("Medium risk", "Changes to the boot configuration. Keep a previous kernel entry available in case the system fails to boot."),
+        RiskLevel::High => ("High risk", "Third-party or self-built drivers. These can break after kernel updates; make sure you have a backup."),
+    }
+}
+
+fn reason_text(reason: &InstallationReason) -> &'static str {
+    match reason {
+        InstallationReason::HardwareSupport => "hardware support",
+        InstallationReason::PerformanceOptimization => "better performance",
+        InstallationReason::FeatureEnhancement => "additional features",
+        InstallationReason::SecurityUpdate => "security fixes",
+        InstallationReason::DependencyRequirement => "other components",
+    }
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::configuration::engine::ConfigurationEngineImpl;
+    use crate::hardware::HardwareReport;
+
+    fn workstation() -> Configuration {
+        let report: HardwareReport = serde_json::from_str(include_str!(
+            "../../tests/fixtures/configuration/workstation.json"
+        ))
+        .unwrap();
+        ConfigurationEngineImpl::new()
+            .unwrap()
+            .generate_configuration(&report, "Fedora Linux 40")
+            .unwrap()
+    }
+
+    fn risky_optimization() -> PerformanceOptimization {
+        PerformanceOptimization {
+            optimization_type: "Overclock <GPU>".to_string(),
+            description: "Raise the \"power\" limit & clocks.".to_string(),
+            configuration_changes: vec![],
+            expected_improvement: "5% faster".to_string(),
+            risk_level: RiskLevel::High,
+        }
+    }
+
+    #[test]
+    fn test_steps_are_grouped_by_risk_and_numbered_across_groups() {
+        let mut configuration = workstation();
+        configuration.performance_optimizations.insert(0, risky_optimization());
+        let markdown = ConfigurationGuide::new(&configuration).to_markdown();
+
+        let low = markdown.find("## Low risk").unwrap();
+        let medium = markdown.find("## Medium risk").unwrap();
+        let high = markdown.find("## High risk").unwrap();
+        assert!(low < medium && medium < high);
+        // The high-risk step comes first in the configuration but last in the guide
+        assert!(markdown.find("Overclock <GPU>").unwrap() > high);
+
+        let steps = ConfigurationGuide::new(&configuration).steps().len();
+        assert!(markdown.contains(&format!("\n### {}. ", steps)));
+        assert!(!markdown.contains(&format!("\n### {}. ", steps + 1)));
+    }
+
+    #[test]
+    fn test_html_guide_escapes_text() {
+        let mut configuration = workstation();
+        configuration.performance_optimizations.push(risky_optimization());
+        let html = ConfigurationGuide::new(&configuration).render(GuideFormat::Html);
+
+        assert!(html.contains("<h2 class=\"risk-high\">High risk</h2>"));
+        assert!(html.contains("<h3>Overclock &lt;GPU&gt;</h3>"));
+        assert!(html.contains("Raise the &quot;power&quot; limit &amp; clocks."));
+        assert!(!html.contains("<GPU>"));
+        assert!(html.ends_with("</body>\n</html>\n"));
+    }
+
+    #[test]
+    fn test_guide_without_changes() {
+        let mut configuration = workstation();
+        configuration.package_installations.clear();
+        configuration.driver_recommendations.clear();
+        configuration.configuration_files.clear();
+        configuration.kernel_parameters.clear();
+        configuration.performance_optimizations.clear();
+        configuration.dkms_modules.clear();
+        let guide = ConfigurationGuide::new(&configuration);
+
+        assert!(guide.steps().is_empty());
+        assert!(guide.to_markdown().ends_with("\nNo changes are needed for this hardware.\n"));
+        assert!(guide.to_html().contains("<p>No changes are needed for this hardware.</p>"));
+    }
+}
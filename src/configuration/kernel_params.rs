@@ -1,7 +1,13 @@
-use std::collections::HashMap;
+use crate::configuration::audit::values_match;
 use crate::configuration::*;
-use crate::hardware::HardwareReport;
+use crate::detectors::iommu::IommuStatus;
 use crate::errors::LxHwError;
+use crate::hardware::HardwareReport;
+use std::collections::HashMap;
+
+pub mod bootloader;
+
+use bootloader::{BootloaderEdit, BootloaderWriter};
 
 pub struct KernelParameterGenerator {
     parameter_rules: HashMap<String, Vec<ParameterRule>>,
@@ -58,7 +64,10 @@ impl KernelParameterGenerator {
         Ok(generator)
     }
 
-    pub fn generate_parameters(&self, hardware: &HardwareReport) -> Result<Vec<KernelParameter>, LxHwError> {
+    pub fn generate_parameters(
+        &self,
+        hardware: &HardwareReport,
+    ) -> Result<Vec<KernelParameter>, LxHwError> {
         let mut parameters = Vec::new();
 
         // Generate parameters based on detected hardware
@@ -83,11 +92,87 @@ impl KernelParameterGenerator {
         Ok(parameters)
     }
 
+    /// Whether the report shows a recommended parameter already in effect
+    ///
+    /// `module.option` parameters are compared with the value the loaded module reports,
+    /// and blacklist parameters with the modules modprobe already keeps from loading.
+    /// None when the report cannot tell, e.g. for other boot parameters.
+    pub fn is_applied(
+        &self,
+        parameter: &KernelParameter,
+        hardware: &HardwareReport,
+    ) -> Option<bool> {
+        let kernel = hardware.kernel_support.as_ref()?;
+        match (parameter.parameter.as_str(), parameter.value.as_deref()) {
+            ("rd.driver.blacklist" | "modprobe.blacklist" | "module_blacklist", Some(modules)) => {
+                Some(modules.split(',').all(|module| {
+                    let module = module.replace('-', "_");
+                    kernel
+                        .blacklisted_modules
+                        .iter()
+                        .any(|blacklisted| blacklisted.module == module)
+                }))
+            }
+            (name, value) => {
+                let (module, option) = name.split_once('.')?;
+                let module = module.replace('-', "_");
+                let loaded =
+                    kernel.module_parameters.iter().find(|loaded| loaded.module == module)?;
+                let current = loaded.parameters.get(option)?;
+                Some(match value {
+                    Some(value) => values_match(Some(current), value),
+                    None => true,
+                })
+            }
+        }
+    }
+
+    /// Persist parameters in the configuration of the bootloader this system boots with
+    ///
+    /// With `dry_run` the edit is only worked out and returned. Otherwise the files are
+    /// rewritten and the result checked (grub-mkconfig into a scratch file plus
+    /// grub-script-check, or bootctl for systemd-boot) before grub.cfg is regenerated;
+    /// any failure restores the original files.
+    pub fn persist_parameters(
+        &self,
+        parameters: &[KernelParameter],
+        dry_run: bool,
+    ) -> Result<BootloaderEdit, LxHwError> {
+        let writer = BootloaderWriter::detect().ok_or_else(|| {
+            LxHwError::ConfigError(
+                "No supported bootloader configuration found (GRUB, systemd-boot or kernelstub)"
+                    .to_string(),
+            )
+        })?;
+        let edit = writer.plan(parameters)?;
+        if !dry_run {
+            writer.write(&edit)?;
+        }
+        Ok(edit)
+    }
+
+    /// Parameters for passing the isolatable GPUs and network cards of `iommu` through to
+    /// virtual machines with VFIO, leaving out those already on the kernel command line
+    pub fn generate_vfio_parameters(&self, iommu: &IommuStatus) -> Vec<KernelParameter> {
+        let candidates = iommu.passthrough_candidates();
+        iommu
+            .vfio_parameters(&candidates)
+            .into_iter()
+            .filter(|parameter| !parameter.applied)
+            .map(|parameter| KernelParameter {
+                parameter: parameter.parameter,
+                value: parameter.value,
+                purpose: parameter.purpose.to_string(),
+                hardware_target: Some("VFIO passthrough".to_string()),
+                distribution_specific: None,
+                boot_order: 2,
+            })
+            .collect()
+    }
+
     fn initialize_parameter_rules(&mut self) -> Result<(), LxHwError> {
         // CPU-related parameters
-        let mut cpu_rules = Vec::new();
-        
-        cpu_rules.push(ParameterRule {
+        let mut cpu_rules = vec![ParameterRule {
             parameter_name: "intel_pstate".to_string(),
             default_value: Some("enable".to_string()),
             conditions: vec![HardwareCondition {
@@ -99,7 +184,7 @@ impl KernelParameterGenerator {
             purpose: "Enable Intel P-State driver for better power management".to_string(),
             boot_order: 1,
             risk_level: RiskLevel::Low,
-        });
+        }];
 
         cpu_rules.push(ParameterRule {
             parameter_name: "amd_pstate".to_string(),
@@ -132,9 +217,7 @@ impl KernelParameterGenerator {
         self.parameter_rules.insert("cpu".to_string(), cpu_rules);
 
         // GPU-related parameters
-        let mut gpu_rules = Vec::new();
-        
-        gpu_rules.push(ParameterRule {
+        let mut gpu_rules = vec![ParameterRule {
             parameter_name: "nouveau.modeset".to_string(),
             default_value: Some("0".to_string()),
             conditions: vec![HardwareCondition {
@@ -146,7 +229,7 @@ impl KernelParameterGenerator {
             purpose: "Disable nouveau for proprietary NVIDIA driver".to_string(),
             boot_order: 3,
             risk_level: RiskLevel::Low,
-        });
+        }];
 
         gpu_rules.push(ParameterRule {
             parameter_name: "i915.enable_psr".to_string(),
@@ -179,9 +262,7 @@ impl KernelParameterGenerator {
         self.parameter_rules.insert("gpu".to_string(), gpu_rules);
 
         // Memory-related parameters
-        let mut memory_rules = Vec::new();
-        
-        memory_rules.push(ParameterRule {
+        let memory_rules = vec![ParameterRule {
             parameter_name: "transparent_hugepage".to_string(),
             default_value: Some("madvise".to_string()),
             conditions: vec![HardwareCondition {
@@ -193,14 +274,12 @@ impl KernelParameterGenerator {
             purpose: "Optimize memory allocation with transparent hugepages".to_string(),
             boot_order: 5,
             risk_level: RiskLevel::Low,
-        });
+        }];
 
         self.parameter_rules.insert("memory".to_string(), memory_rules);
 
         // Storage-related parameters
-        let mut storage_rules = Vec::new();
-        
-        storage_rules.push(ParameterRule {
+        let storage_rules = vec![ParameterRule {
             parameter_name: "elevator".to_string(),
             default_value: Some("none".to_string()),
             conditions: vec![HardwareCondition {
@@ -212,7 +291,7 @@ impl KernelParameterGenerator {
             purpose: "Disable I/O scheduler for SSDs".to_string(),
             boot_order: 6,
             risk_level: RiskLevel::Low,
-        });
+        }];
 
         self.parameter_rules.insert("storage".to_string(), storage_rules);
 
@@ -221,74 +300,66 @@ impl KernelParameterGenerator {
 
     fn initialize_hardware_optimizations(&mut self) -> Result<(), LxHwError> {
         // Gaming optimizations
-        let gaming_optimizations = vec![
-            OptimizationRule {
-                optimization_type: "gaming".to_string(),
-                parameters: vec![
-                    KernelParameter {
-                        parameter: "preempt".to_string(),
-                        value: Some("voluntary".to_string()),
-                        purpose: "Reduce latency for gaming".to_string(),
-                        hardware_target: Some("CPU".to_string()),
-                        distribution_specific: None,
-                        boot_order: 2,
-                    },
-                    KernelParameter {
-                        parameter: "clocksource".to_string(),
-                        value: Some("tsc".to_string()),
-                        purpose: "Use TSC for precise timing".to_string(),
-                        hardware_target: Some("CPU".to_string()),
-                        distribution_specific: None,
-                        boot_order: 3,
-                    },
-                ],
-                hardware_requirements: vec![
-                    HardwareCondition {
-                        component_type: "GPU".to_string(),
-                        vendor_pattern: Some("NVIDIA|AMD".to_string()),
-                        model_pattern: None,
-                        feature_required: None,
-                    },
-                ],
-                expected_benefit: "Lower input latency and smoother gaming performance".to_string(),
-            },
-        ];
+        let gaming_optimizations = vec![OptimizationRule {
+            optimization_type: "gaming".to_string(),
+            parameters: vec![
+                KernelParameter {
+                    parameter: "preempt".to_string(),
+                    value: Some("voluntary".to_string()),
+                    purpose: "Reduce latency for gaming".to_string(),
+                    hardware_target: Some("CPU".to_string()),
+                    distribution_specific: None,
+                    boot_order: 2,
+                },
+                KernelParameter {
+                    parameter: "clocksource".to_string(),
+                    value: Some("tsc".to_string()),
+                    purpose: "Use TSC for precise timing".to_string(),
+                    hardware_target: Some("CPU".to_string()),
+                    distribution_specific: None,
+                    boot_order: 3,
+                },
+            ],
+            hardware_requirements: vec![HardwareCondition {
+                component_type: "GPU".to_string(),
+                vendor_pattern: Some("NVIDIA|AMD".to_string()),
+                model_pattern: None,
+                feature_required: None,
+            }],
+            expected_benefit: "Lower input latency and smoother gaming performance".to_string(),
+        }];
 
         self.hardware_optimizations.insert("gaming".to_string(), gaming_optimizations);
 
         // Server optimizations
-        let server_optimizations = vec![
-            OptimizationRule {
-                optimization_type: "server".to_string(),
-                parameters: vec![
-                    KernelParameter {
-                        parameter: "nohz_full".to_string(),
-                        value: Some("1-3".to_string()),
-                        purpose: "Isolate CPU cores from kernel ticks".to_string(),
-                        hardware_target: Some("CPU".to_string()),
-                        distribution_specific: None,
-                        boot_order: 1,
-                    },
-                    KernelParameter {
-                        parameter: "rcu_nocbs".to_string(),
-                        value: Some("1-3".to_string()),
-                        purpose: "Move RCU callbacks off isolated cores".to_string(),
-                        hardware_target: Some("CPU".to_string()),
-                        distribution_specific: None,
-                        boot_order: 1,
-                    },
-                ],
-                hardware_requirements: vec![
-                    HardwareCondition {
-                        component_type: "CPU".to_string(),
-                        vendor_pattern: None,
-                        model_pattern: None,
-                        feature_required: Some("cores >= 4".to_string()),
-                    },
-                ],
-                expected_benefit: "Better CPU isolation for high-performance workloads".to_string(),
-            },
-        ];
+        let server_optimizations = vec![OptimizationRule {
+            optimization_type: "server".to_string(),
+            parameters: vec![
+                KernelParameter {
+                    parameter: "nohz_full".to_string(),
+                    value: Some("1-3".to_string()),
+                    purpose: "Isolate CPU cores from kernel ticks".to_string(),
+                    hardware_target: Some("CPU".to_string()),
+                    distribution_specific: None,
+                    boot_order: 1,
+                },
+                KernelParameter {
+                    parameter: "rcu_nocbs".to_string(),
+                    value: Some("1-3".to_string()),
+                    purpose: "Move RCU callbacks off isolated cores".to_string(),
+                    hardware_target: Some("CPU".to_string()),
+                    distribution_specific: None,
+                    boot_order: 1,
+                },
+            ],
+            hardware_requirements: vec![HardwareCondition {
+                component_type: "CPU".to_string(),
+                vendor_pattern: None,
+                model_pattern: None,
+                feature_required: Some("cores >= 4".to_string()),
+            }],
+            expected_benefit: "Better CPU isolation for high-performance workloads".to_string(),
+        }];
 
         self.hardware_optimizations.insert("server".to_string(), server_optimizations);
 
@@ -296,45 +367,63 @@ impl KernelParameterGenerator {
     }
 
     fn initialize_distribution_configs(&mut self) -> Result<(), LxHwError> {
-        self.distribution_specifics.insert("Ubuntu".to_string(), DistributionConfig {
-            distribution_name: "Ubuntu".to_string(),
-            bootloader_type: "grub".to_string(),
-            config_file_path: "/etc/default/grub".to_string(),
-            parameter_prefix: "GRUB_CMDLINE_LINUX_DEFAULT=".to_string(),
-        });
-
-        self.distribution_specifics.insert("Debian".to_string(), DistributionConfig {
-            distribution_name: "Debian".to_string(),
-            bootloader_type: "grub".to_string(),
-            config_file_path: "/etc/default/grub".to_string(),
-            parameter_prefix: "GRUB_CMDLINE_LINUX_DEFAULT=".to_string(),
-        });
-
-        self.distribution_specifics.insert("Fedora".to_string(), DistributionConfig {
-            distribution_name: "Fedora".to_string(),
-            bootloader_type: "grub".to_string(),
-            config_file_path: "/etc/default/grub".to_string(),
-            parameter_prefix: "GRUB_CMDLINE_LINUX=".to_string(),
-        });
-
-        self.distribution_specifics.insert("Arch Linux".to_string(), DistributionConfig {
-            distribution_name: "Arch Linux".to_string(),
-            bootloader_type: "systemd-boot".to_string(),
-            config_file_path: "/boot/loader/entries/arch.conf".to_string(),
-            parameter_prefix: "options ".to_string(),
-        });
-
-        self.distribution_specifics.insert("NixOS".to_string(), DistributionConfig {
-            distribution_name: "NixOS".to_string(),
-            bootloader_type: "systemd-boot".to_string(),
-            config_file_path: "/etc/nixos/configuration.nix".to_string(),
-            parameter_prefix: "boot.kernelParams = [".to_string(),
-        });
+        self.distribution_specifics.insert(
+            "Ubuntu".to_string(),
+            DistributionConfig {
+                distribution_name: "Ubuntu".to_string(),
+                bootloader_type: "grub".to_string(),
+                config_file_path: "/etc/default/grub".to_string(),
+                parameter_prefix: "GRUB_CMDLINE_LINUX_DEFAULT=".to_string(),
+            },
+        );
+
+        self.distribution_specifics.insert(
+            "Debian".to_string(),
+            DistributionConfig {
+                distribution_name: "Debian".to_string(),
+                bootloader_type: "grub".to_string(),
+                config_file_path: "/etc/default/grub".to_string(),
+                parameter_prefix: "GRUB_CMDLINE_LINUX_DEFAULT=".to_string(),
+            },
+        );
+
+        self.distribution_specifics.insert(
+            "Fedora".to_string(),
+            DistributionConfig {
+                distribution_name: "Fedora".to_string(),
+                bootloader_type: "grub".to_string(),
+                config_file_path: "/etc/default/grub".to_string(),
+                parameter_prefix: "GRUB_CMDLINE_LINUX=".to_string(),
+            },
+        );
+
+        self.distribution_specifics.insert(
+            "Arch Linux".to_string(),
+            DistributionConfig {
+                distribution_name: "Arch Linux".to_string(),
+                bootloader_type: "systemd-boot".to_string(),
+                config_file_path: "/boot/loader/entries/arch.conf".to_string(),
+                parameter_prefix: "options ".to_string(),
+            },
+        );
+
+        self.distribution_specifics.insert(
+            "NixOS".to_string(),
+            DistributionConfig {
+                distribution_name: "NixOS".to_string(),
+                bootloader_type: "systemd-boot".to_string(),
+                config_file_path: "/etc/nixos/configuration.nix".to_string(),
+                parameter_prefix: "boot.kernelParams = [".to_string(),
+            },
+        );
 
         Ok(())
     }
 
-    fn generate_cpu_parameters(&self, hardware: &HardwareReport) -> Result<Vec<KernelParameter>, LxHwError> {
+    fn generate_cpu_parameters(
+        &self,
+        hardware: &HardwareReport,
+    ) -> Result<Vec<KernelParameter>, LxHwError> {
         let mut parameters = Vec::new();
 
         if let Some(cpu_info) = &hardware.cpu {
@@ -397,7 +486,10 @@ impl KernelParameterGenerator {
         Ok(parameters)
     }
 
-    fn generate_gpu_parameters(&self, hardware: &HardwareReport) -> Result<Vec<KernelParameter>, LxHwError> {
+    fn generate_gpu_parameters(
+        &self,
+        hardware: &HardwareReport,
+    ) -> Result<Vec<KernelParameter>, LxHwError> {
         let mut parameters = Vec::new();
 
         for gpu in &hardware.graphics {
@@ -455,35 +547,29 @@ impl KernelParameterGenerator {
         Ok(parameters)
     }
 
-    fn generate_memory_parameters(&self, hardware: &HardwareReport) -> Result<Vec<KernelParameter>, LxHwError> {
-        let mut parameters = Vec::new();
-
+    fn generate_memory_parameters(
+        &self,
+        _hardware: &HardwareReport,
+    ) -> Result<Vec<KernelParameter>, LxHwError> {
         // General memory optimizations
-        parameters.push(KernelParameter {
+        let parameters = vec![KernelParameter {
             parameter: "transparent_hugepage".to_string(),
             value: Some("madvise".to_string()),
             purpose: "Use transparent hugepages only when requested by applications".to_string(),
             hardware_target: Some("Memory".to_string()),
             distribution_specific: None,
             boot_order: 6,
-        });
+        }];
 
-        // Check if we have memory information to make smarter decisions
-        if let Some(_memory_info) = &hardware.memory {
-            parameters.push(KernelParameter {
-                parameter: "vm.swappiness".to_string(),
-                value: Some("10".to_string()),
-                purpose: "Reduce swappiness for better responsiveness".to_string(),
-                hardware_target: Some("Memory".to_string()),
-                distribution_specific: None,
-                boot_order: 6,
-            });
-        }
+        // Swappiness depends on the swap setup and is recommended by the memory tuner
 
         Ok(parameters)
     }
 
-    fn generate_storage_parameters(&self, hardware: &HardwareReport) -> Result<Vec<KernelParameter>, LxHwError> {
+    fn generate_storage_parameters(
+        &self,
+        hardware: &HardwareReport,
+    ) -> Result<Vec<KernelParameter>, LxHwError> {
         let mut parameters = Vec::new();
 
         // Check for SSD/NVMe drives
@@ -507,22 +593,28 @@ impl KernelParameterGenerator {
         Ok(parameters)
     }
 
-    fn generate_network_parameters(&self, hardware: &HardwareReport) -> Result<Vec<KernelParameter>, LxHwError> {
+    fn generate_network_parameters(
+        &self,
+        hardware: &HardwareReport,
+    ) -> Result<Vec<KernelParameter>, LxHwError> {
         let mut parameters = Vec::new();
 
         // Check for Intel wireless cards that may need specific parameters
         for network in &hardware.network {
             let vendor = &network.vendor;
             let product = &network.model;
-            
+
             let vendor_lower = vendor.to_lowercase();
             let product_lower = product.to_lowercase();
 
-            if vendor_lower.contains("intel") && (product_lower.contains("wireless") || product_lower.contains("wifi")) {
+            if vendor_lower.contains("intel")
+                && (product_lower.contains("wireless") || product_lower.contains("wifi"))
+            {
                 parameters.push(KernelParameter {
                     parameter: "iwlwifi.power_save".to_string(),
                     value: Some("0".to_string()),
-                    purpose: "Disable power saving for Intel wireless to improve stability".to_string(),
+                    purpose: "Disable power saving for Intel wireless to improve stability"
+                        .to_string(),
                     hardware_target: Some("Intel Wireless".to_string()),
                     distribution_specific: None,
                     boot_order: 8,
@@ -533,43 +625,183 @@ impl KernelParameterGenerator {
         Ok(parameters)
     }
 
-    fn generate_power_management_parameters(&self, _hardware: &HardwareReport) -> Result<Vec<KernelParameter>, LxHwError> {
-        let mut parameters = Vec::new();
-
+    fn generate_power_management_parameters(
+        &self,
+        _hardware: &HardwareReport,
+    ) -> Result<Vec<KernelParameter>, LxHwError> {
         // General power management parameters
-        parameters.push(KernelParameter {
+        let parameters = vec![KernelParameter {
             parameter: "pcie_aspm".to_string(),
             value: Some("off".to_string()),
             purpose: "Disable PCIe Active State Power Management to prevent issues".to_string(),
             hardware_target: Some("PCIe".to_string()),
             distribution_specific: None,
             boot_order: 9,
-        });
+        }];
 
         Ok(parameters)
     }
 
-    fn generate_security_parameters(&self, _hardware: &HardwareReport) -> Result<Vec<KernelParameter>, LxHwError> {
-        let mut parameters = Vec::new();
-
+    fn generate_security_parameters(
+        &self,
+        _hardware: &HardwareReport,
+    ) -> Result<Vec<KernelParameter>, LxHwError> {
         // Enable KASLR by default
-        parameters.push(KernelParameter {
+        let parameters = vec![KernelParameter {
             parameter: "kaslr".to_string(),
             value: None,
             purpose: "Enable Kernel Address Space Layout Randomization".to_string(),
             hardware_target: None,
             distribution_specific: None,
             boot_order: 10,
-        });
+        }];
 
         Ok(parameters)
     }
 
-    fn apply_distribution_adjustments(&self, parameters: Vec<KernelParameter>, _distribution_config: &DistributionConfig) -> Result<Vec<KernelParameter>, LxHwError> {
+    fn apply_distribution_adjustments(
+        &self,
+        parameters: Vec<KernelParameter>,
+        _distribution_config: &DistributionConfig,
+    ) -> Result<Vec<KernelParameter>, LxHwError> {
         // Apply any distribution-specific parameter adjustments
         // For now, we'll just return the parameters as-is
         // In the future, this could modify parameters based on distribution quirks
-        
+
         Ok(parameters)
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::detectors::iommu::{IommuDevice, IommuGroup, IommuVendor};
+
+    fn workstation() -> HardwareReport {
+        serde_json::from_str(include_str!("../../tests/fixtures/configuration/workstation.json"))
+            .unwrap()
+    }
+
+    fn param(parameter: &str, value: Option<&str>) -> KernelParameter {
+        KernelParameter {
+            parameter: parameter.to_string(),
+            value: value.map(str::to_string),
+            purpose: String::new(),
+            hardware_target: None,
+            distribution_specific: None,
+            boot_order: 0,
+        }
+    }
+
+    #[test]
+    fn test_module_option_is_checked_against_loaded_value() {
+        let generator = KernelParameterGenerator::new().unwrap();
+        let hardware = workstation();
+
+        // snd_hda_intel is loaded with power_save=0
+        let applied = param("snd_hda_intel.power_save", Some("0"));
+        assert_eq!(generator.is_applied(&applied, &hardware), Some(true));
+        let dashed = param("snd-hda-intel.power_save", Some("off"));
+        assert_eq!(generator.is_applied(&dashed, &hardware), Some(true));
+        let pending = param("snd_hda_intel.power_save", Some("1"));
+        assert_eq!(generator.is_applied(&pending, &hardware), Some(false));
+
+        // Unknown option, module not loaded and plain boot parameters cannot be judged
+        let unknown_option = param("snd_hda_intel.probe_mask", Some("1"));
+        assert_eq!(generator.is_applied(&unknown_option, &hardware), None);
+        let not_loaded = param("i915.enable_psr", Some("0"));
+        assert_eq!(generator.is_applied(&not_loaded, &hardware), None);
+        assert_eq!(generator.is_applied(&param("quiet", None), &hardware), None);
+    }
+
+    #[test]
+    fn test_blacklist_is_checked_against_modprobe_config() {
+        let generator = KernelParameterGenerator::new().unwrap();
+        let hardware = workstation();
+
+        let nouveau = param("modprobe.blacklist", Some("nouveau"));
+        assert_eq!(generator.is_applied(&nouveau, &hardware), Some(true));
+        let initramfs = param("rd.driver.blacklist", Some("nouveau"));
+        assert_eq!(generator.is_applied(&initramfs, &hardware), Some(true));
+        let partly = param("module_blacklist", Some("nouveau,pcspkr"));
+        assert_eq!(generator.is_applied(&partly, &hardware), Some(false));
+    }
+
+    #[test]
+    fn test_report_without_kernel_support_cannot_tell() {
+        let generator = KernelParameterGenerator::new().unwrap();
+        let mut hardware = workstation();
+        hardware.kernel_support = None;
+
+        let nouveau = param("modprobe.blacklist", Some("nouveau"));
+        assert_eq!(generator.is_applied(&nouveau, &hardware), None);
+    }
+
+    fn device(address: &str, pci_id: &str, class_code: &str) -> IommuDevice {
+        IommuDevice {
+            address: address.to_string(),
+            pci_id: pci_id.to_string(),
+            class_code: class_code.to_string(),
+            driver: None,
+            boot_vga: false,
+        }
+    }
+
+    /// GPU alone with its audio function, and a NIC sharing its group with a USB controller
+    fn iommu(cmdline: &[&str]) -> IommuStatus {
+        IommuStatus {
+            enabled: true,
+            vendor: Some(IommuVendor::Intel),
+            cmdline: cmdline.iter().map(|argument| argument.to_string()).collect(),
+            groups: vec![
+                IommuGroup {
+                    id: 1,
+                    devices: vec![
+                        device("0000:00:01.0", "8086:a70d", "060400"),
+                        device("0000:01:00.0", "10de:2484", "030000"),
+                        device("0000:01:00.1", "10de:228b", "040300"),
+                    ],
+                },
+                IommuGroup {
+                    id: 12,
+                    devices: vec![
+                        device("0000:03:00.0", "10ec:8168", "020000"),
+                        device("0000:04:00.0", "1b21:2142", "0c0330"),
+                    ],
+                },
+            ],
+        }
+    }
+
+    #[test]
+    fn test_vfio_parameters_skip_applied_and_shared_groups() {
+        let generator = KernelParameterGenerator::new().unwrap();
+        let parameters = generator.generate_vfio_parameters(&iommu(&["quiet", "intel_iommu=on"]));
+
+        let arguments: Vec<(&str, Option<&str>)> =
+            parameters.iter().map(|p| (p.parameter.as_str(), p.value.as_deref())).collect();
+        // intel_iommu=on is already set and the NIC cannot be isolated from the USB controller
+        assert_eq!(
+            arguments,
+            [
+                ("iommu", Some("pt")),
+                ("vfio-pci.ids", Some("10de:228b,10de:2484")),
+                ("rd.driver.pre", Some("vfio-pci")),
+            ]
+        );
+        assert!(parameters.iter().all(|p| {
+            p.hardware_target.as_deref() == Some("VFIO passthrough") && p.boot_order == 2
+        }));
+    }
+
+    #[test]
+    fn test_vfio_parameters_without_isolatable_devices() {
+        let generator = KernelParameterGenerator::new().unwrap();
+        let mut status = iommu(&[]);
+        status.groups.remove(0);
+        let parameters = generator.generate_vfio_parameters(&status);
+
+        let names: Vec<&str> = parameters.iter().map(|p| p.parameter.as_str()).collect();
+        assert_eq!(names, ["intel_iommu", "iommu"]);
+    }
+}
@@ -0,0 +1,538 @@
+//! Persist kernel parameters in the bootloader configuration
+//!
+//! GRUB reads the command line from `GRUB_CMDLINE_LINUX_DEFAULT` in
+//! /etc/default/grub and needs grub.cfg regenerated; systemd-boot keeps it on
+//! the `options` line of each entry under loader/entries, with
+//! /etc/kernel/cmdline used by kernel-install for future kernels; Pop!_OS
+//! manages its systemd-boot entries through kernelstub, which must be used
+//! instead of editing them. Single-valued parameters already on the command
+//! line are replaced rather than repeated, so writing twice changes nothing.
+
+use crate::configuration::*;
+use crate::errors::LxHwError;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Directories systemd-boot entries live in, relative to the root
+const LOADER_ENTRY_DIRS: &[&str] =
+    &["boot/loader/entries", "efi/loader/entries", "boot/efi/loader/entries"];
+
+/// Parameters that take one value, so a new value replaces the old one on the
+/// command line. Others, such as `console=` or `module_blacklist=`, may be
+/// given several times and are only added.
+const SINGLE_VALUED_KEYS: &[&str] = &[
+    "amd_iommu",
+    "amd_pstate",
+    "amdgpu.dc",
+    "clocksource",
+    "elevator",
+    "i915.enable_psr",
+    "i915.fastboot",
+    "init",
+    "intel_iommu",
+    "intel_pstate",
+    "iommu",
+    "iwlwifi.power_save",
+    "mitigations",
+    "nohz_full",
+    "nouveau.modeset",
+    "nvme_core.default_ps_max_latency_us",
+    "pcie_aspm",
+    "preempt",
+    "rcu_nocbs",
+    "resume",
+    "root",
+    "transparent_hugepage",
+];
+
+/// Stands for a private scratch directory in the words of validation commands
+const SCRATCH_DIR: &str = "{scratch}";
+
+/// Generated GRUB configurations and the tool that writes each
+const GRUB_CONFIGS: &[(&str, &str)] =
+    &[("boot/grub2/grub.cfg", "grub2-mkconfig"), ("boot/grub/grub.cfg", "grub-mkconfig")];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Bootloader {
+    Grub { generated_config: Option<PathBuf>, mkconfig: String },
+    SystemdBoot { entries_dir: PathBuf },
+    Kernelstub,
+}
+
+/// The edit persisting a set of parameters. Commands are program and
+/// arguments, run without a shell.
+#[derive(Debug, Clone)]
+pub struct BootloaderEdit {
+    pub bootloader: Bootloader,
+    /// Files to rewrite and their new content
+    pub files: Vec<(PathBuf, String)>,
+    /// Commands checking the edited configuration before it is activated
+    pub validation: Vec<Vec<String>>,
+    /// Commands activating the edit
+    pub commands: Vec<Vec<String>>,
+    /// Commands activating the original configuration again once the files
+    /// have been put back
+    pub undo: Vec<Vec<String>>,
+}
+
+#[derive(Debug, Clone)]
+pub struct BootloaderWriter {
+    root: PathBuf,
+    bootloader: Bootloader,
+}
+
+impl BootloaderWriter {
+    /// Writer for the bootloader of the running system
+    pub fn detect() -> Option<Self> {
+        Self::with_root(Path::new("/"))
+    }
+
+    /// Writer for the bootloader of a system mounted at `root`
+    ///
+    /// Edits for another root can be planned, but not written: validating and
+    /// activating them needs the tools of the running system.
+    pub fn with_root(root: &Path) -> Option<Self> {
+        let bootloader = detect_bootloader(root)?;
+        Some(Self { root: root.to_path_buf(), bootloader })
+    }
+
+    pub fn bootloader(&self) -> &Bootloader {
+        &self.bootloader
+    }
+
+    /// Work out the edit adding `parameters` without changing anything
+    pub fn plan(&self, parameters: &[KernelParameter]) -> Result<BootloaderEdit, LxHwError> {
+        let mut sorted: Vec<&KernelParameter> = parameters.iter().collect();
+        sorted.sort_by_key(|p| p.boot_order);
+        let arguments: Vec<String> = sorted
+            .iter()
+            .map(|p| match &p.value {
+                Some(value) => format!("{}={}", p.parameter, value),
+                None => p.parameter.clone(),
+            })
+            .collect();
+
+        let mut edit = BootloaderEdit {
+            bootloader: self.bootloader.clone(),
+            files: Vec::new(),
+            validation: Vec::new(),
+            commands: Vec::new(),
+            undo: Vec::new(),
+        };
+        match &self.bootloader {
+            Bootloader::Grub { generated_config, mkconfig } => {
+                let path = self.root.join("etc/default/grub");
+                let content = fs::read_to_string(&path)?;
+                edit.files.push((path, edit_grub_defaults(&content, &arguments)));
+                // grub-mkconfig has no dry-run mode; generate into a scratch
+                // file and let grub-script-check parse it
+                let scratch_config = format!("{}/grub.cfg", SCRATCH_DIR);
+                edit.validation.push(argv(&[mkconfig.as_str(), "-o", &scratch_config]));
+                edit.validation.push(argv(&["grub-script-check", &scratch_config]));
+                if let Some(config) = generated_config {
+                    let config = Path::new("/")
+                        .join(config.strip_prefix(&self.root).unwrap_or(config))
+                        .display()
+                        .to_string();
+                    let regenerate = argv(&[mkconfig.as_str(), "-o", &config]);
+                    edit.commands.push(regenerate.clone());
+                    edit.undo.push(regenerate);
+                }
+            }
+            Bootloader::SystemdBoot { entries_dir } => {
+                let mut entries: Vec<PathBuf> = fs::read_dir(entries_dir)?
+                    .flatten()
+                    .map(|entry| entry.path())
+                    .filter(|path| path.extension().is_some_and(|ext| ext == "conf"))
+                    .collect();
+                entries.sort();
+                for entry in entries {
+                    let content = fs::read_to_string(&entry)?;
+                    edit.files.push((entry, edit_loader_entry(&content, &arguments)));
+                }
+                let cmdline = self.root.join("etc/kernel/cmdline");
+                if let Ok(content) = fs::read_to_string(&cmdline) {
+                    edit.files.push((
+                        cmdline,
+                        format!("{}\n", merge_cmdline(content.trim(), &arguments)),
+                    ));
+                }
+                edit.validation.push(argv(&["bootctl", "--no-pager", "list"]));
+            }
+            Bootloader::Kernelstub => {
+                // kernelstub only adds and deletes options, so replacing a
+                // single-valued one takes both
+                let current = self.kernelstub_options()?;
+                let merged = merge_cmdline(&current.join(" "), &arguments);
+                let merged: Vec<String> = merged.split_whitespace().map(String::from).collect();
+                let removed: Vec<String> =
+                    current.iter().filter(|option| !merged.contains(option)).cloned().collect();
+                let added: Vec<String> =
+                    merged.iter().filter(|option| !current.contains(option)).cloned().collect();
+                if !removed.is_empty() {
+                    edit.commands.push(argv(&[
+                        "kernelstub",
+                        "--delete-options",
+                        &removed.join(" "),
+                    ]));
+                }
+                if !added.is_empty() {
+                    edit.commands.push(argv(&["kernelstub", "--add-options", &added.join(" ")]));
+                    edit.undo.push(argv(&["kernelstub", "--delete-options", &added.join(" ")]));
+                }
+                if !removed.is_empty() {
+                    edit.undo.push(argv(&["kernelstub", "--add-options", &removed.join(" ")]));
+                }
+            }
+        }
+        Ok(edit)
+    }
+
+    /// Apply an edit: back up and rewrite the files, validate, then run the
+    /// activation commands. The originals are put back when any of this
+    /// fails, and the backups removed once it succeeds.
+    ///
+    /// Refused for a writer made with another root, since the validation and
+    /// activation commands would act on the running system.
+    pub fn write(&self, edit: &BootloaderEdit) -> Result<(), LxHwError> {
+        if self.root != Path::new("/") {
+            return Err(LxHwError::ConfigError(format!(
+                "Bootloader changes below {} cannot be validated or activated from here; \
+                 apply them from inside that system",
+                self.root.display()
+            )));
+        }
+        self.write_with(edit, run)
+    }
+
+    /// `write`, handing the commands to `run` instead of running them; used
+    /// below another root, where they can only be reported
+    pub(crate) fn write_with(
+        &self,
+        edit: &BootloaderEdit,
+        mut run: impl FnMut(&[String]) -> Result<(), LxHwError>,
+    ) -> Result<(), LxHwError> {
+        // Scratch output of validation commands goes to a directory only we can read
+        let scratch = tempfile::Builder::new().prefix("lx-hw-bootloader").tempdir()?;
+        let scratch = scratch.path().display().to_string();
+        let mut backups = Vec::new();
+        let result = (|| {
+            for (path, content) in &edit.files {
+                let backup = PathBuf::from(format!("{}.lx-hw-bak", path.display()));
+                fs::copy(path, &backup)?;
+                backups.push((path.clone(), backup));
+                fs::write(path, content)?;
+            }
+            for command in edit.validation.iter().chain(&edit.commands) {
+                let command: Vec<String> =
+                    command.iter().map(|word| word.replace(SCRATCH_DIR, &scratch)).collect();
+                run(&command)?;
+            }
+            Ok(())
+        })();
+
+        for (path, backup) in &backups {
+            if result.is_err() {
+                fs::rename(backup, path)?;
+            } else {
+                fs::remove_file(backup)?;
+            }
+        }
+        result
+    }
+
+    /// Options kernelstub currently puts on the command line
+    fn kernelstub_options(&self) -> Result<Vec<String>, LxHwError> {
+        let content = fs::read_to_string(self.root.join("etc/kernelstub/configuration"))?;
+        let configuration: serde_json::Value = serde_json::from_str(&content)?;
+        Ok(configuration["user"]["kernel_options"]
+            .as_array()
+            .map(|options| {
+                options.iter().filter_map(|option| option.as_str()).map(String::from).collect()
+            })
+            .unwrap_or_default())
+    }
+}
+
+fn detect_bootloader(root: &Path) -> Option<Bootloader> {
+    if root.join("etc/kernelstub/configuration").exists() {
+        return Some(Bootloader::Kernelstub);
+    }
+
+    let generated_config = GRUB_CONFIGS.iter().find(|(config, _)| root.join(config).exists());
+    let entries_dir = LOADER_ENTRY_DIRS.iter().map(|dir| root.join(dir)).find(|dir| dir.is_dir());
+    if let (Some(entries_dir), None) = (&entries_dir, generated_config) {
+        return Some(Bootloader::SystemdBoot { entries_dir: entries_dir.clone() });
+    }
+
+    if root.join("etc/default/grub").exists() {
+        let mkconfig = match generated_config {
+            Some((_, tool)) => tool.to_string(),
+            None if command_exists("grub2-mkconfig") => "grub2-mkconfig".to_string(),
+            None => "grub-mkconfig".to_string(),
+        };
+        return Some(Bootloader::Grub {
+            generated_config: generated_config.map(|(config, _)| root.join(config)),
+            mkconfig,
+        });
+    }
+    entries_dir.map(|entries_dir| Bootloader::SystemdBoot { entries_dir })
+}
+
+/// /etc/default/grub with the arguments merged into GRUB_CMDLINE_LINUX_DEFAULT
+fn edit_grub_defaults(content: &str, arguments: &[String]) -> String {
+    const KEY: &str = "GRUB_CMDLINE_LINUX_DEFAULT=";
+    let mut found = false;
+    let mut lines: Vec<String> = content
+        .lines()
+        .map(|line| match line.strip_prefix(KEY) {
+            Some(value) if !found => {
+                found = true;
+                let quote = if value.starts_with('\'') { '\'' } else { '"' };
+                let current = value.trim().trim_matches(quote);
+                format!("{}{}{}{}", KEY, quote, merge_cmdline(current, arguments), quote)
+            }
+            _ => line.to_string(),
+        })
+        .collect();
+    if !found {
+        lines.push(format!("{}\"{}\"", KEY, merge_cmdline("", arguments)));
+    }
+    lines.join("\n") + "\n"
+}
+
+/// A systemd-boot entry with the arguments merged into its options line
+fn edit_loader_entry(content: &str, arguments: &[String]) -> String {
+    let mut found = false;
+    let mut lines: Vec<String> = content
+        .lines()
+        .map(|line| match line.strip_prefix("options") {
+            Some(options) if !found && options.starts_with(char::is_whitespace) => {
+                found = true;
+                format!("options {}", merge_cmdline(options.trim(), arguments))
+            }
+            _ => line.to_string(),
+        })
+        .collect();
+    if !found {
+        lines.push(format!("options {}", merge_cmdline("", arguments)));
+    }
+    lines.join("\n") + "\n"
+}
+
+/// Add arguments to a command line. A single-valued parameter replaces the
+/// one with the same key; other arguments are added unless already present.
+pub fn merge_cmdline(current: &str, arguments: &[String]) -> String {
+    // The kernel treats dashes and underscores in parameter names alike
+    let key_of = |argument: &str| argument.split('=').next().unwrap_or(argument).replace('-', "_");
+    let mut merged: Vec<String> = current.split_whitespace().map(String::from).collect();
+    for argument in arguments {
+        if merged.contains(argument) {
+            continue;
+        }
+        let key = key_of(argument);
+        let existing = SINGLE_VALUED_KEYS
+            .contains(&key.as_str())
+            .then(|| merged.iter_mut().find(|existing| key_of(existing) == key))
+            .flatten();
+        match existing {
+            Some(existing) => *existing = argument.clone(),
+            None => merged.push(argument.clone()),
+        }
+    }
+    merged.join(" ")
+}
+
+fn run(command: &[String]) -> Result<(), LxHwError> {
+    let failed = || LxHwError::SystemCommandError { command: command.join(" ") };
+    let (program, args) = command.split_first().ok_or_else(failed)?;
+    let status = Command::new(program).args(args).status()?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(failed())
+    }
+}
+
+fn argv(words: &[&str]) -> Vec<String> {
+    words.iter().map(|word| word.to_string()).collect()
+}
+
+fn command_exists(name: &str) -> bool {
+    Command::new("which").arg(name).output().map(|output| output.status.success()).unwrap_or(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn param(parameter: &str, value: Option<&str>, boot_order: u8) -> KernelParameter {
+        KernelParameter {
+            parameter: parameter.to_string(),
+            value: value.map(String::from),
+            purpose: String::new(),
+            hardware_target: None,
+            distribution_specific: None,
+            boot_order,
+        }
+    }
+
+    fn write(root: &Path, path: &str, content: &str) {
+        let path = root.join(path);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, content).unwrap();
+    }
+
+    #[test]
+    fn test_merge_cmdline_keeps_repeatable_arguments() {
+        let merged = merge_cmdline(
+            "console=tty0 console=ttyS0,115200 mitigations=off transparent-hugepage=always quiet",
+            &[
+                "console=ttyS1".to_string(),
+                "mitigations=auto".to_string(),
+                "transparent_hugepage=madvise".to_string(),
+                "module_blacklist=nouveau".to_string(),
+                "quiet".to_string(),
+            ],
+        );
+        assert_eq!(
+            merged,
+            "console=tty0 console=ttyS0,115200 mitigations=auto transparent_hugepage=madvise \
+             quiet console=ttyS1 module_blacklist=nouveau"
+        );
+        // Merging again changes nothing
+        assert_eq!(merge_cmdline(&merged, &["console=ttyS1".to_string()]), merged);
+    }
+
+    #[test]
+    fn test_grub_plan_below_root() {
+        let root = TempDir::new().unwrap();
+        write(
+            root.path(),
+            "etc/default/grub",
+            "GRUB_TIMEOUT=5\nGRUB_CMDLINE_LINUX_DEFAULT='quiet mitigations=off'\n",
+        );
+        write(root.path(), "boot/grub/grub.cfg", "");
+        let writer = BootloaderWriter::with_root(root.path()).unwrap();
+        let edit = writer
+            .plan(&[param("pcie_aspm", Some("off"), 9), param("mitigations", Some("auto"), 3)])
+            .unwrap();
+
+        assert_eq!(edit.files[0].0, root.path().join("etc/default/grub"));
+        assert_eq!(
+            edit.files[0].1,
+            "GRUB_TIMEOUT=5\nGRUB_CMDLINE_LINUX_DEFAULT='quiet mitigations=auto pcie_aspm=off'\n"
+        );
+        assert_eq!(
+            edit.validation,
+            [
+                argv(&["grub-mkconfig", "-o", "{scratch}/grub.cfg"]),
+                argv(&["grub-script-check", "{scratch}/grub.cfg"])
+            ]
+        );
+        assert_eq!(edit.commands, [argv(&["grub-mkconfig", "-o", "/boot/grub/grub.cfg"])]);
+        assert_eq!(edit.undo, edit.commands);
+
+        // The scratch directory is filled in, and the backup removed once done
+        let mut ran = Vec::new();
+        writer
+            .write_with(&edit, |command| {
+                ran.push(command.to_vec());
+                Ok(())
+            })
+            .unwrap();
+        assert_eq!(ran.len(), 3);
+        assert!(!ran[0][2].contains("{scratch}") && ran[0][2].ends_with("/grub.cfg"));
+        assert_eq!(fs::read_to_string(&edit.files[0].0).unwrap(), edit.files[0].1);
+        assert!(!root.path().join("etc/default/grub.lx-hw-bak").exists());
+
+        // Writing the same parameters again changes nothing
+        assert_eq!(
+            writer.plan(&[param("mitigations", Some("auto"), 3)]).unwrap().files[0].1,
+            edit.files[0].1
+        );
+    }
+
+    #[test]
+    fn test_write_refuses_another_root() {
+        let root = TempDir::new().unwrap();
+        write(
+            root.path(),
+            "boot/loader/entries/linux.conf",
+            "title Linux\noptions root=/dev/sda2\n",
+        );
+        write(root.path(), "etc/kernel/cmdline", "root=/dev/sda2\n");
+        let writer = BootloaderWriter::with_root(root.path()).unwrap();
+        let edit = writer.plan(&[param("intel_iommu", Some("on"), 2)]).unwrap();
+
+        assert!(matches!(writer.bootloader(), Bootloader::SystemdBoot { .. }));
+        assert_eq!(edit.files.len(), 2);
+        assert_eq!(edit.files[0].1, "title Linux\noptions root=/dev/sda2 intel_iommu=on\n");
+        assert_eq!(edit.files[1].1, "root=/dev/sda2 intel_iommu=on\n");
+
+        assert!(writer.write(&edit).is_err());
+        let entry = fs::read_to_string(root.path().join("boot/loader/entries/linux.conf")).unwrap();
+        assert_eq!(entry, "title Linux\noptions root=/dev/sda2\n");
+    }
+
+    #[test]
+    fn test_failed_command_restores_files() {
+        let root = TempDir::new().unwrap();
+        write(
+            root.path(),
+            "boot/loader/entries/linux.conf",
+            "title Linux\noptions root=/dev/sda2\n",
+        );
+        write(root.path(), "etc/kernel/cmdline", "root=/dev/sda2\n");
+        let writer = BootloaderWriter::with_root(root.path()).unwrap();
+        let edit = writer.plan(&[param("intel_iommu", Some("on"), 2)]).unwrap();
+
+        let result = writer.write_with(&edit, |command| {
+            Err(LxHwError::SystemCommandError { command: command.join(" ") })
+        });
+        assert!(matches!(result, Err(LxHwError::SystemCommandError { .. })));
+        let entry = fs::read_to_string(root.path().join("boot/loader/entries/linux.conf")).unwrap();
+        assert_eq!(entry, "title Linux\noptions root=/dev/sda2\n");
+        let cmdline = fs::read_to_string(root.path().join("etc/kernel/cmdline")).unwrap();
+        assert_eq!(cmdline, "root=/dev/sda2\n");
+        assert!(!root.path().join("boot/loader/entries/linux.conf.lx-hw-bak").exists());
+        assert!(!root.path().join("etc/kernel/cmdline.lx-hw-bak").exists());
+    }
+
+    #[test]
+    fn test_kernelstub_plan() {
+        let root = TempDir::new().unwrap();
+        write(
+            root.path(),
+            "etc/kernelstub/configuration",
+            r#"{"user": {"kernel_options": ["quiet", "pcie_aspm=force"]}}"#,
+        );
+        let writer = BootloaderWriter::with_root(root.path()).unwrap();
+        let edit = writer
+            .plan(&[
+                param("kaslr", None, 10),
+                param("pcie_aspm", Some("off"), 9),
+                param("quiet", None, 1),
+            ])
+            .unwrap();
+
+        assert_eq!(writer.bootloader(), &Bootloader::Kernelstub);
+        assert!(edit.files.is_empty());
+        assert_eq!(
+            edit.commands,
+            [
+                argv(&["kernelstub", "--delete-options", "pcie_aspm=force"]),
+                argv(&["kernelstub", "--add-options", "pcie_aspm=off kaslr"])
+            ]
+        );
+        assert_eq!(
+            edit.undo,
+            [
+                argv(&["kernelstub", "--delete-options", "pcie_aspm=off kaslr"]),
+                argv(&["kernelstub", "--add-options", "pcie_aspm=force"])
+            ]
+        );
+    }
+}
@@ -0,0 +1,360 @@
+//! Swap, compressed memory and hibernation recommendations
+//!
+//! Sized from the detected RAM, storage type and active swap. zram is suggested
+//! when there is no disk swap to cache in front of; zswap when there is, since it
+//! keeps hibernation to disk working. The zram and zswap settings themselves are
+//! written as configuration files, the optimizations carry the sysctls that go
+//! with them.
+
+use crate::configuration::*;
+use crate::hardware::{HardwareReport, SwapInfo};
+use std::collections::HashMap;
+
+const GIB: u64 = 1 << 30;
+const SWAP_SYSCTL_FILE: &str = "/etc/sysctl.d/99-swap.conf";
+const ZRAM_GENERATOR_FILE: &str = "/etc/systemd/zram-generator.conf";
+const ZSWAP_TMPFILES_FILE: &str = "/etc/tmpfiles.d/zswap.conf";
+
+/// Recommends zram/zswap, swappiness and hibernation settings
+pub struct MemoryTuner;
+
+/// Memory optimizations and the files implementing them
+#[derive(Debug, Clone, Default)]
+pub struct MemoryTuning {
+    pub optimizations: Vec<PerformanceOptimization>,
+    pub configuration_files: HashMap<String, ConfigurationFile>,
+}
+
+/// Compressed swap suited to the system
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CompressedSwap {
+    Zram,
+    Zswap,
+}
+
+impl MemoryTuner {
+    pub fn new() -> Self {
+        Self
+    }
+
+    pub fn recommend(&self, hardware: &HardwareReport) -> MemoryTuning {
+        let mut tuning = MemoryTuning::default();
+        let Some(memory) = &hardware.memory else {
+            return tuning;
+        };
+        let ram_bytes = memory.total_bytes;
+        if ram_bytes == 0 {
+            return tuning;
+        }
+        let swap = memory.swap.as_ref();
+        let hdd_only = storage_is_rotational(hardware);
+
+        let compressed = match swap {
+            Some(swap) if swap.has_zram() || swap.zswap_enabled == Some(true) => None,
+            Some(swap) if swap.disk_bytes() > 0 => Some(CompressedSwap::Zswap),
+            Some(_) => Some(CompressedSwap::Zram),
+            // Without swap details only suggest zram where RAM is tight
+            None if ram_bytes <= 16 * GIB => Some(CompressedSwap::Zram),
+            None => None,
+        };
+        let zram_in_use =
+            compressed == Some(CompressedSwap::Zram) || swap.is_some_and(SwapInfo::has_zram);
+
+        match compressed {
+            Some(CompressedSwap::Zram) => {
+                tuning.optimizations.push(zram_optimization(ram_bytes));
+                tuning
+                    .configuration_files
+                    .insert("zram-generator.conf".to_string(), zram_generator_file());
+            }
+            Some(CompressedSwap::Zswap) => {
+                tuning.optimizations.push(zswap_optimization(hdd_only));
+                tuning.configuration_files.insert("zswap.conf".to_string(), zswap_tmpfiles_file());
+            }
+            None => {}
+        }
+
+        if zram_in_use {
+            // zram is far cheaper than disk, so swap to it eagerly and without readahead
+            if compressed != Some(CompressedSwap::Zram) {
+                tuning.optimizations.push(sysctl_optimization(
+                    "Swappiness for zram",
+                    "Swap is backed by compressed RAM, which is much faster to reach than reclaiming page cache.",
+                    &[("vm.swappiness", "180"), ("vm.page-cluster", "0")],
+                    "Fewer stalls under memory pressure as cold pages move to zram",
+                ));
+            }
+        } else if swap.is_some_and(|swap| swap.disk_bytes() > 0)
+            && (hdd_only || ram_bytes >= 32 * GIB)
+        {
+            let reason = if hdd_only {
+                "Swap lives on a rotational disk, where swapping in is slow."
+            } else {
+                "With this much RAM, keeping the page cache is cheaper than swapping out application memory."
+            };
+            tuning.optimizations.push(sysctl_optimization(
+                "Lower swappiness",
+                reason,
+                &[("vm.swappiness", "10")],
+                "Applications stay responsive instead of waiting on disk swap",
+            ));
+        }
+
+        if let Some(swap) = swap {
+            tuning.optimizations.push(hibernation_optimization(ram_bytes, swap));
+        }
+
+        tuning
+    }
+}
+
+impl Default for MemoryTuner {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Whether all fixed storage is spinning disks
+fn storage_is_rotational(hardware: &HardwareReport) -> bool {
+    let fixed: Vec<&str> = hardware
+        .storage
+        .iter()
+        .map(|device| device.device_type.as_str())
+        .filter(|kind| !matches!(*kind, "USB Drive" | "Optical Drive"))
+        .collect();
+    !fixed.is_empty() && fixed.iter().all(|kind| *kind == "HDD")
+}
+
+fn zram_optimization(ram_bytes: u64) -> PerformanceOptimization {
+    let size = (ram_bytes / 2).min(8 * GIB);
+    PerformanceOptimization {
+        optimization_type: "Compressed swap in RAM (zram)".to_string(),
+        description: format!(
+            "No disk swap is active. A zstd-compressed zram device of {} (half of {} RAM, at most 8 GiB) is set up by zram-generator through {}; install zram-generator if your distribution does not ship it.",
+            format_gib(size), format_gib(ram_bytes), ZRAM_GENERATOR_FILE
+        ),
+        configuration_changes: sysctl_changes(
+            &[("vm.swappiness", "180"), ("vm.page-cluster", "0")],
+            "Prefer swapping to zram, which is cheaper than dropping page cache",
+        ),
+        expected_improvement: "Roughly 2-3x more usable memory before the OOM killer steps in".to_string(),
+        risk_level: RiskLevel::Low,
+    }
+}
+
+fn zswap_optimization(hdd_only: bool) -> PerformanceOptimization {
+    let storage = if hdd_only { "a rotational disk" } else { "disk" };
+    PerformanceOptimization {
+        optimization_type: "Compressed swap cache (zswap)".to_string(),
+        description: format!(
+            "Swap on {} is active but zswap is off. zswap keeps compressed pages in RAM before they are written out and, unlike zram, leaves hibernation working. It is enabled at boot through {}.",
+            storage, ZSWAP_TMPFILES_FILE
+        ),
+        configuration_changes: vec![],
+        expected_improvement: "Less swap I/O and faster swap-in under memory pressure".to_string(),
+        risk_level: RiskLevel::Low,
+    }
+}
+
+fn hibernation_optimization(ram_bytes: u64, swap: &SwapInfo) -> PerformanceOptimization {
+    let disk_bytes = swap.disk_bytes();
+    let description = if disk_bytes == 0 {
+        format!(
+            "Hibernation needs a swap partition or file of at least {} (the size of RAM); zram cannot hold the hibernation image. Add disk swap to hibernate.",
+            format_gib(ram_bytes)
+        )
+    } else if disk_bytes < ram_bytes {
+        format!(
+            "Disk swap ({}) is smaller than RAM ({}), so hibernation fails once memory is busy. Grow swap to at least {} to hibernate reliably.",
+            format_gib(disk_bytes), format_gib(ram_bytes), format_gib(ram_bytes)
+        )
+    } else {
+        format!(
+            "Disk swap ({}) can hold a hibernation image of {} RAM. Point the kernel at it with `resume=UUID=<uuid>` (the swap partition's UUID from `blkid`, or for a swap file the UUID of the filesystem holding it) and, for a swap file, `resume_offset=<offset>` using the first physical offset from `filefrag -v <swapfile>` (`btrfs inspect-internal map-swapfile -r <swapfile>` on btrfs). Regenerate the initramfs afterwards so it includes resume support.",
+            format_gib(disk_bytes), format_gib(ram_bytes)
+        )
+    };
+    PerformanceOptimization {
+        optimization_type: "Hibernation".to_string(),
+        description,
+        configuration_changes: vec![],
+        expected_improvement: "Suspend to disk that resumes the saved session".to_string(),
+        risk_level: RiskLevel::Low,
+    }
+}
+
+fn sysctl_optimization(
+    name: &str,
+    description: &str,
+    settings: &[(&str, &str)],
+    improvement: &str,
+) -> PerformanceOptimization {
+    PerformanceOptimization {
+        optimization_type: name.to_string(),
+        description: description.to_string(),
+        configuration_changes: sysctl_changes(settings, description),
+        expected_improvement: improvement.to_string(),
+        risk_level: RiskLevel::Low,
+    }
+}
+
+fn sysctl_changes(settings: &[(&str, &str)], comment: &str) -> Vec<ConfigurationChange> {
+    settings
+        .iter()
+        .map(|(parameter, value)| ConfigurationChange {
+            file_path: SWAP_SYSCTL_FILE.to_string(),
+            parameter: parameter.to_string(),
+            old_value: None,
+            new_value: value.to_string(),
+            comment: comment.to_string(),
+        })
+        .collect()
+}
+
+fn zram_generator_file() -> ConfigurationFile {
+    ConfigurationFile {
+        file_path: ZRAM_GENERATOR_FILE.to_string(),
+        content: "[zram0]\nzram-size = min(ram / 2, 8192)\ncompression-algorithm = zstd\nswap-priority = 100\n".to_string(),
+        backup_original: true,
+        file_permissions: "0644".to_string(),
+        validation_command: Some("systemctl daemon-reload && systemctl start systemd-zram-setup@zram0.service".to_string()),
+    }
+}
+
+fn zswap_tmpfiles_file() -> ConfigurationFile {
+    ConfigurationFile {
+        file_path: ZSWAP_TMPFILES_FILE.to_string(),
+        content: "w /sys/module/zswap/parameters/compressor - - - - zstd\nw /sys/module/zswap/parameters/enabled - - - - 1\n".to_string(),
+        backup_original: false,
+        file_permissions: "0644".to_string(),
+        validation_command: Some(format!("systemd-tmpfiles --create {}", ZSWAP_TMPFILES_FILE)),
+    }
+}
+
+fn format_gib(bytes: u64) -> String {
+    format!("{:.1} GiB", bytes as f64 / GIB as f64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hardware::{SwapArea, SwapKind};
+
+    fn workstation() -> HardwareReport {
+        serde_json::from_str(include_str!("../../tests/fixtures/configuration/workstation.json"))
+            .unwrap()
+    }
+
+    fn with_memory(ram_bytes: u64, swap: Option<SwapInfo>) -> HardwareReport {
+        let mut hardware = workstation();
+        let memory = hardware.memory.as_mut().unwrap();
+        memory.total_bytes = ram_bytes;
+        memory.swap = swap;
+        hardware
+    }
+
+    fn names(tuning: &MemoryTuning) -> Vec<&str> {
+        tuning.optimizations.iter().map(|o| o.optimization_type.as_str()).collect()
+    }
+
+    fn sysctl<'a>(tuning: &'a MemoryTuning, parameter: &str) -> Vec<&'a str> {
+        tuning
+            .optimizations
+            .iter()
+            .flat_map(|o| &o.configuration_changes)
+            .filter(|change| change.parameter == parameter)
+            .map(|change| change.new_value.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn test_disk_swap_without_zswap_gets_zswap_and_lower_swappiness() {
+        let tuning = MemoryTuner::new().recommend(&workstation());
+
+        assert_eq!(
+            names(&tuning),
+            ["Compressed swap cache (zswap)", "Lower swappiness", "Hibernation"]
+        );
+        assert_eq!(sysctl(&tuning, "vm.swappiness"), ["10"]);
+        let zswap = &tuning.configuration_files["zswap.conf"];
+        assert_eq!(zswap.file_path, ZSWAP_TMPFILES_FILE);
+        assert!(zswap.content.contains("/sys/module/zswap/parameters/enabled - - - - 1"));
+        assert!(!tuning.configuration_files.contains_key("zram-generator.conf"));
+
+        // 8 GiB of swap cannot hold an image of 32 GiB RAM
+        let hibernation = tuning.optimizations.last().unwrap();
+        assert!(hibernation.description.contains("smaller than RAM"));
+        assert!(hibernation.description.contains("32.0 GiB"));
+    }
+
+    #[test]
+    fn test_no_swap_gets_zram_capped_at_8_gib() {
+        let swap = SwapInfo { areas: vec![], zswap_enabled: Some(false) };
+        let tuning = MemoryTuner::new().recommend(&with_memory(32 * GIB, Some(swap)));
+
+        assert_eq!(names(&tuning), ["Compressed swap in RAM (zram)", "Hibernation"]);
+        let zram = &tuning.optimizations[0];
+        assert!(zram.description.contains("zram device of 8.0 GiB"));
+        assert_eq!(sysctl(&tuning, "vm.swappiness"), ["180"]);
+        assert_eq!(sysctl(&tuning, "vm.page-cluster"), ["0"]);
+        assert!(tuning.configuration_files["zram-generator.conf"]
+            .content
+            .contains("zram-size = min(ram / 2, 8192)"));
+        assert!(tuning.optimizations[1].description.contains("zram cannot hold"));
+    }
+
+    #[test]
+    fn test_existing_zram_only_tunes_swappiness() {
+        let swap = SwapInfo {
+            areas: vec![SwapArea { kind: SwapKind::Zram, size_bytes: 4 * GIB }],
+            zswap_enabled: Some(false),
+        };
+        let tuning = MemoryTuner::new().recommend(&with_memory(8 * GIB, Some(swap)));
+
+        assert_eq!(names(&tuning), ["Swappiness for zram", "Hibernation"]);
+        assert_eq!(sysctl(&tuning, "vm.swappiness"), ["180"]);
+        assert!(tuning.configuration_files.is_empty());
+    }
+
+    #[test]
+    fn test_enabled_zswap_on_small_ssd_system_needs_nothing_but_hibernation() {
+        let swap = SwapInfo {
+            areas: vec![SwapArea { kind: SwapKind::File, size_bytes: 16 * GIB }],
+            zswap_enabled: Some(true),
+        };
+        let tuning = MemoryTuner::new().recommend(&with_memory(16 * GIB, Some(swap)));
+
+        assert_eq!(names(&tuning), ["Hibernation"]);
+        let hibernation = &tuning.optimizations[0];
+        assert!(hibernation.description.contains("resume=UUID=<uuid>"));
+        assert!(hibernation.description.contains("resume_offset=<offset>"));
+    }
+
+    #[test]
+    fn test_rotational_swap_lowers_swappiness_with_little_ram() {
+        let mut hardware = with_memory(
+            8 * GIB,
+            Some(SwapInfo {
+                areas: vec![SwapArea { kind: SwapKind::Partition, size_bytes: 8 * GIB }],
+                zswap_enabled: Some(true),
+            }),
+        );
+        for device in &mut hardware.storage {
+            device.device_type = "HDD".to_string();
+        }
+        let tuning = MemoryTuner::new().recommend(&hardware);
+
+        assert_eq!(names(&tuning), ["Lower swappiness", "Hibernation"]);
+        assert!(tuning.optimizations[0].description.contains("rotational disk"));
+    }
+
+    #[test]
+    fn test_missing_swap_details_only_suggest_zram_for_small_ram() {
+        let small = MemoryTuner::new().recommend(&with_memory(8 * GIB, None));
+        assert_eq!(names(&small), ["Compressed swap in RAM (zram)"]);
+        assert!(small.optimizations[0].description.contains("zram device of 4.0 GiB"));
+
+        let large = MemoryTuner::new().recommend(&with_memory(64 * GIB, None));
+        assert!(large.optimizations.is_empty());
+    }
+}
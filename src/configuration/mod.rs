@@ -1,14 +1,21 @@
-use std::collections::HashMap;
-use serde::{Deserialize, Serialize};
-use crate::hardware::HardwareReport;
 use crate::errors::LxHwError;
+use crate::hardware::HardwareReport;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 
-pub mod engine;
+pub mod apply;
+pub mod audit;
+pub mod dkms;
 pub mod drivers;
+pub mod engine;
+pub mod export;
+pub mod guide;
 pub mod kernel_params;
-pub mod dkms;
+pub mod memory;
+pub mod nixos;
 pub mod packages;
 pub mod recommendations;
+pub mod shell;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Configuration {
@@ -101,7 +108,11 @@ pub struct DriverRecommendation {
     pub driver_source: DriverSource,
     pub installation_priority: u8,
     pub compatibility_notes: Option<String>,
+    /// Modules to load, including dependencies, in load order
     pub kernel_modules: Vec<String>,
+    /// Firmware files required by the modules
+    #[serde(default)]
+    pub firmware_files: Vec<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -131,6 +142,31 @@ pub struct PackageInstallation {
     pub installation_command: String,
     pub post_install_commands: Vec<String>,
     pub dependencies: Vec<String>,
+    /// Caveats to show with the installation, e.g. for layered packages
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub notes: Vec<String>,
+}
+
+impl PackageInstallation {
+    /// Distribution package names the installation command installs; the
+    /// package name is a label when one installation covers several packages
+    pub fn package_names(&self) -> Vec<String> {
+        let command = self.installation_command.rsplit("&&").next().unwrap_or_default();
+        let mut words = command.split_whitespace();
+        let verbs = ["install", "-S", "-iA", "add", "emerge"];
+        if words.by_ref().find(|word| verbs.contains(word)).is_none() {
+            return vec![self.package_name.clone()];
+        }
+        let names: Vec<String> = words
+            .filter(|word| !word.starts_with('-') && !word.ends_with('.'))
+            .map(|word| word.trim_start_matches("nixos.").to_string())
+            .collect();
+        if names.is_empty() {
+            vec![self.package_name.clone()]
+        } else {
+            names
+        }
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -216,9 +252,23 @@ pub struct PowerManagement {
 }
 
 pub trait ConfigurationEngine {
-    fn generate_configuration(&self, hardware: &HardwareReport, target_distribution: &str) -> Result<Configuration, LxHwError>;
+    fn generate_configuration(
+        &self,
+        hardware: &HardwareReport,
+        target_distribution: &str,
+    ) -> Result<Configuration, LxHwError>;
     fn analyze_compatibility(&self, hardware: &HardwareReport) -> Result<f64, LxHwError>;
-    fn recommend_drivers(&self, hardware: &HardwareReport) -> Result<Vec<DriverRecommendation>, LxHwError>;
-    fn generate_kernel_parameters(&self, hardware: &HardwareReport) -> Result<Vec<KernelParameter>, LxHwError>;
-    fn suggest_packages(&self, hardware: &HardwareReport, distribution: &str) -> Result<Vec<PackageInstallation>, LxHwError>;
-}
\ No newline at end of file
+    fn recommend_drivers(
+        &self,
+        hardware: &HardwareReport,
+    ) -> Result<Vec<DriverRecommendation>, LxHwError>;
+    fn generate_kernel_parameters(
+        &self,
+        hardware: &HardwareReport,
+    ) -> Result<Vec<KernelParameter>, LxHwError>;
+    fn suggest_packages(
+        &self,
+        hardware: &HardwareReport,
+        distribution: &str,
+    ) -> Result<Vec<PackageInstallation>, LxHwError>;
+}
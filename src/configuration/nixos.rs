@@ -0,0 +1,248 @@
+//! NixOS module rendered from a generated configuration
+//!
+//! NixOS ignores modprobe.d, package managers and bootloader files, so the
+//! configuration is expressed as NixOS options instead: kernel modules and
+//! parameters under `boot`, firmware and microcode under `hardware`, display
+//! drivers under `services.xserver` and out-of-tree modules as kernel
+//! packages from nixpkgs. The result can be imported from configuration.nix
+//! next to the hardware-configuration.nix written by nixos-generate-config.
+
+use crate::configuration::*;
+use std::fmt::Write as _;
+
+/// Modules nixos-generate-config loads from the initrd so the root file
+/// system can be found
+const INITRD_MODULES: &[&str] = &[
+    "ahci",
+    "ehci_pci",
+    "megaraid_sas",
+    "mpt3sas",
+    "nvme",
+    "rtsx_pci_sdmmc",
+    "sd_mod",
+    "sdhci_pci",
+    "sr_mod",
+    "uas",
+    "usb_storage",
+    "virtio_blk",
+    "virtio_pci",
+    "virtio_scsi",
+    "xhci_pci",
+];
+
+/// Display drivers selected through `services.xserver.videoDrivers`
+const VIDEO_DRIVERS: &[&str] = &["amdgpu", "modesetting", "nouveau", "nvidia"];
+
+/// DKMS modules and the `boot.kernelPackages` attribute packaging them
+const KERNEL_PACKAGES: &[(&str, &str)] = &[
+    ("broadcom-sta", "broadcom_sta"),
+    ("rtl8812au", "rtl8812au"),
+    ("rtl8821ce", "rtl8821ce"),
+    ("rtl88x2bu", "rtl88x2bu"),
+    ("xone", "xone"),
+];
+
+pub struct NixosModule<'a> {
+    configuration: &'a Configuration,
+}
+
+impl<'a> NixosModule<'a> {
+    pub fn new(configuration: &'a Configuration) -> Self {
+        Self { configuration }
+    }
+
+    pub fn render(&self) -> String {
+        let config = self.configuration;
+        let mut out = String::new();
+        let _ = writeln!(
+            out,
+            "# Generated by lx-hw-detect for kernel {} (compatibility score {:.0}%)",
+            config.kernel_version, config.compatibility_score
+        );
+        out.push_str(
+            "# Import it from configuration.nix: imports = [ ./lx-hw-configuration.nix ];\n",
+        );
+        out.push_str("{ config, lib, pkgs, ... }:\n\n{\n");
+
+        let (initrd, modules) = self.kernel_modules();
+        write_list(&mut out, "boot.initrd.availableKernelModules", &initrd);
+        write_list(&mut out, "boot.kernelModules", &modules);
+        write_list(&mut out, "boot.kernelParams", &self.kernel_params());
+
+        let (packages, unpackaged) = self.kernel_packages();
+        if !packages.is_empty() {
+            let _ = writeln!(
+                out,
+                "  boot.extraModulePackages = with config.boot.kernelPackages; [ {} ];",
+                packages.join(" ")
+            );
+        }
+        for module in unpackaged {
+            let _ = writeln!(
+                out,
+                "  # DKMS module {} has no nixpkgs package; build it with boot.extraModulePackages",
+                module
+            );
+        }
+
+        if self.needs_firmware() {
+            out.push_str("  hardware.enableRedistributableFirmware = true;\n");
+        }
+        if let Some(cpu) = config.hardware_profile.cpu.as_ref().filter(|cpu| cpu.microcode_needed) {
+            let vendor = if cpu.vendor.to_lowercase().contains("amd") { "amd" } else { "intel" };
+            let _ = writeln!(out, "  hardware.cpu.{}.updateMicrocode = true;", vendor);
+        }
+
+        let video_drivers = self.video_drivers();
+        if !video_drivers.is_empty() {
+            out.push_str("  hardware.graphics.enable = true;\n");
+            write_list(&mut out, "services.xserver.videoDrivers", &video_drivers);
+        }
+        if video_drivers.iter().any(|driver| driver == "nvidia") {
+            out.push_str("  hardware.nvidia.modesetting.enable = true;\n");
+            let open =
+                config.driver_recommendations.iter().any(|r| r.recommended_driver == "nvidia-open");
+            let _ = writeln!(out, "  hardware.nvidia.open = {};", open);
+        }
+
+        let packages = self.system_packages();
+        if !packages.is_empty() {
+            let _ = writeln!(
+                out,
+                "  environment.systemPackages = with pkgs; [ {} ];",
+                packages.join(" ")
+            );
+        }
+        out.push_str("}\n");
+        out
+    }
+
+    /// Modules of all driver recommendations, split into those needed in the
+    /// initrd and the rest, in first-seen order
+    fn kernel_modules(&self) -> (Vec<String>, Vec<String>) {
+        let mut initrd = Vec::new();
+        let mut modules = Vec::new();
+        for module in
+            self.configuration.driver_recommendations.iter().flat_map(|r| &r.kernel_modules)
+        {
+            let module = module.replace('-', "_");
+            let list =
+                if INITRD_MODULES.contains(&module.as_str()) { &mut initrd } else { &mut modules };
+            push_unique(list, module);
+        }
+        (initrd, modules)
+    }
+
+    fn kernel_params(&self) -> Vec<String> {
+        let mut parameters: Vec<&KernelParameter> =
+            self.configuration.kernel_parameters.iter().collect();
+        parameters.sort_by_key(|p| p.boot_order);
+        let mut params = Vec::new();
+        for parameter in parameters {
+            let param = match &parameter.value {
+                Some(value) => format!("{}={}", parameter.parameter, value),
+                None => parameter.parameter.clone(),
+            };
+            push_unique(&mut params, param);
+        }
+        params
+    }
+
+    /// `boot.kernelPackages` attributes for DKMS modules, and the modules
+    /// nixpkgs does not package
+    fn kernel_packages(&self) -> (Vec<String>, Vec<String>) {
+        let config = self.configuration;
+        let dkms = config.driver_recommendations.iter().filter_map(|r| match &r.driver_source {
+            DriverSource::Dkms { module_name } => Some(module_name.as_str()),
+            _ => None,
+        });
+        let modules = dkms.chain(config.dkms_modules.iter().map(|m| m.module_name.as_str()));
+
+        let mut packages = Vec::new();
+        let mut unpackaged = Vec::new();
+        for module in modules {
+            match KERNEL_PACKAGES.iter().find(|(name, _)| *name == module) {
+                Some((_, attribute)) => push_unique(&mut packages, attribute.to_string()),
+                None => push_unique(&mut unpackaged, module.to_string()),
+            }
+        }
+        (packages, unpackaged)
+    }
+
+    fn needs_firmware(&self) -> bool {
+        let config = self.configuration;
+        config.driver_recommendations.iter().any(|r| !r.firmware_files.is_empty())
+            || config
+                .package_installations
+                .iter()
+                .any(|p| p.package_category == PackageCategory::Firmware)
+            || config.hardware_profile.network.iter().any(|n| n.firmware_needed)
+    }
+
+    fn video_drivers(&self) -> Vec<String> {
+        let mut drivers = Vec::new();
+        for recommendation in
+            self.configuration.driver_recommendations.iter().filter(|r| r.component_type == "GPU")
+        {
+            // Intel GPUs use the generic modesetting driver under X11
+            let driver = match recommendation.recommended_driver.as_str() {
+                "i915" | "xe" => "modesetting",
+                "nvidia-open" => "nvidia",
+                driver => driver,
+            };
+            if VIDEO_DRIVERS.contains(&driver) {
+                push_unique(&mut drivers, driver.to_string());
+            }
+        }
+        drivers
+    }
+
+    /// Utilities from the package suggestions; drivers and firmware are
+    /// covered by the options above
+    fn system_packages(&self) -> Vec<String> {
+        let mut packages = Vec::new();
+        for package in &self.configuration.package_installations {
+            if matches!(
+                package.package_category,
+                PackageCategory::Driver | PackageCategory::Firmware
+            ) {
+                continue;
+            }
+            for name in package.package_names() {
+                push_unique(&mut packages, nix_attribute(&name));
+            }
+        }
+        packages
+    }
+}
+
+fn write_list(out: &mut String, option: &str, values: &[String]) {
+    if values.is_empty() {
+        return;
+    }
+    let values: Vec<String> = values.iter().map(|v| nix_string(v)).collect();
+    let _ = writeln!(out, "  {} = [ {} ];", option, values.join(" "));
+}
+
+fn push_unique(list: &mut Vec<String>, value: String) {
+    if !list.contains(&value) {
+        list.push(value);
+    }
+}
+
+/// Quote a value as a Nix string
+fn nix_string(value: &str) -> String {
+    format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\"").replace("${", "\\${"))
+}
+
+/// Package name as an element of a `with pkgs;` list, spelled out as a
+/// quoted attribute when it is not a valid identifier
+fn nix_attribute(name: &str) -> String {
+    let valid = name.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '\''));
+    if valid {
+        name.to_string()
+    } else {
+        format!("pkgs.{}", nix_string(name))
+    }
+}
@@ -1,8 +1,9 @@
-use std::collections::HashMap;
-use serde_json::Value;
 use crate::configuration::*;
-use crate::hardware::HardwareReport;
+use crate::detectors::immutable::{classify_distribution, ImmutableKind};
 use crate::errors::LxHwError;
+use crate::hardware::HardwareReport;
+use serde_json::Value;
+use std::collections::HashMap;
 
 pub struct PackageMapper {
     distribution_packages: HashMap<String, DistributionPackageMap>,
@@ -58,6 +59,160 @@ pub struct FirmwarePackageInfo {
     pub description: String,
 }
 
+/// /proc/cpuinfo flags and the CPU_FLAGS_X86 names Portage uses for them,
+/// matching what app-portage/cpuid2cpuflags reports
+const CPU_FLAGS_X86: &[(&str, &str)] = &[
+    ("aes", "aes"),
+    ("avx", "avx"),
+    ("avx2", "avx2"),
+    ("avx512f", "avx512f"),
+    ("avx512dq", "avx512dq"),
+    ("avx512cd", "avx512cd"),
+    ("avx512bw", "avx512bw"),
+    ("avx512vl", "avx512vl"),
+    ("f16c", "f16c"),
+    ("fma", "fma3"),
+    ("fma4", "fma4"),
+    ("mmx", "mmx"),
+    ("mmxext", "mmxext"),
+    ("pclmulqdq", "pclmul"),
+    ("popcnt", "popcnt"),
+    ("rdrand", "rdrand"),
+    ("sha_ni", "sha"),
+    ("sse", "sse"),
+    ("sse2", "sse2"),
+    ("pni", "sse3"),
+    ("sse4_1", "sse4_1"),
+    ("sse4_2", "sse4_2"),
+    ("sse4a", "sse4a"),
+    ("ssse3", "ssse3"),
+    ("vpclmulqdq", "vpclmulqdq"),
+];
+
+/// Compile-time configuration for Gentoo derived from the hardware
+///
+/// USE_EXPAND variables such as VIDEO_CARDS are written to package.use with
+/// the `*/* VARIABLE: values` syntax, so applying them does not touch a
+/// make.conf the user maintains; `make_conf_snippet` gives the same settings
+/// for those who prefer to keep them there.
+#[derive(Debug, Clone, Default)]
+pub struct PortageSettings {
+    /// USE_EXPAND and other make.conf variables, in insertion order
+    pub variables: Vec<(String, Vec<String>)>,
+    /// Global USE flags
+    pub use_flags: Vec<String>,
+    /// Package atoms and the licenses they need accepted
+    pub licenses: Vec<(String, String)>,
+}
+
+impl PortageSettings {
+    fn set_variable(&mut self, name: &str, values: Vec<String>) {
+        self.variables.retain(|(existing, _)| existing != name);
+        self.variables.push((name.to_string(), values));
+    }
+
+    fn add_use_flag(&mut self, flag: &str) {
+        if !self.use_flags.iter().any(|f| f == flag) {
+            self.use_flags.push(flag.to_string());
+        }
+    }
+
+    fn add_license(&mut self, atom: &str, license: &str) {
+        if !self.licenses.iter().any(|(a, l)| a == atom && l == license) {
+            self.licenses.push((atom.to_string(), license.to_string()));
+        }
+    }
+
+    /// Settings in make.conf syntax
+    pub fn make_conf_snippet(&self) -> String {
+        let mut snippet = String::from("# Hardware settings suggested by lx-hw-detect\n");
+        if !self.use_flags.is_empty() {
+            snippet.push_str(&format!("USE=\"${{USE}} {}\"\n", self.use_flags.join(" ")));
+        }
+        for (name, values) in &self.variables {
+            snippet.push_str(&format!("{}=\"{}\"\n", name, values.join(" ")));
+        }
+        if !self.licenses.is_empty() {
+            let mut licenses: Vec<&str> =
+                self.licenses.iter().map(|(_, license)| license.as_str()).collect();
+            licenses.dedup();
+            snippet.push_str(&format!(
+                "ACCEPT_LICENSE=\"${{ACCEPT_LICENSE}} {}\"\n",
+                licenses.join(" ")
+            ));
+        }
+        snippet
+    }
+
+    /// /etc/portage/package.use content; `-*` drops the profile's defaults
+    /// so only the detected hardware is built for
+    pub fn package_use(&self) -> String {
+        let mut content = String::from("# Hardware settings generated by lx-hw-detect\n");
+        if !self.use_flags.is_empty() {
+            content.push_str(&format!("*/* {}\n", self.use_flags.join(" ")));
+        }
+        for (name, values) in &self.variables {
+            if name == "MICROCODE_SIGNATURES" {
+                continue; // not a USE_EXPAND variable, set in the package env file
+            }
+            content.push_str(&format!("*/* {}: -* {}\n", name, values.join(" ")));
+        }
+        content
+    }
+
+    /// /etc/portage/package.license content
+    pub fn package_license(&self) -> String {
+        let mut content =
+            String::from("# Licenses needed for detected hardware, generated by lx-hw-detect\n");
+        for (atom, license) in &self.licenses {
+            content.push_str(&format!("{} {}\n", atom, license));
+        }
+        content
+    }
+
+    /// Files applying these settings through /etc/portage directories
+    pub fn configuration_files(&self) -> HashMap<String, ConfigurationFile> {
+        let mut files = HashMap::new();
+        files.insert(
+            "portage-package-use".to_string(),
+            ConfigurationFile {
+                file_path: "/etc/portage/package.use/00lx-hw-detect".to_string(),
+                content: self.package_use(),
+                backup_original: true,
+                file_permissions: "0644".to_string(),
+                validation_command: Some("emerge --info".to_string()),
+            },
+        );
+        if !self.licenses.is_empty() {
+            files.insert(
+                "portage-package-license".to_string(),
+                ConfigurationFile {
+                    file_path: "/etc/portage/package.license/lx-hw-detect".to_string(),
+                    content: self.package_license(),
+                    backup_original: true,
+                    file_permissions: "0644".to_string(),
+                    validation_command: None,
+                },
+            );
+        }
+        if let Some((_, signatures)) =
+            self.variables.iter().find(|(name, _)| name == "MICROCODE_SIGNATURES")
+        {
+            files.insert(
+                "portage-intel-microcode-env".to_string(),
+                ConfigurationFile {
+                    file_path: "/etc/portage/env/sys-firmware/intel-microcode".to_string(),
+                    content: format!("MICROCODE_SIGNATURES=\"{}\"\n", signatures.join(" ")),
+                    backup_original: true,
+                    file_permissions: "0644".to_string(),
+                    validation_command: None,
+                },
+            );
+        }
+        files
+    }
+}
+
 impl PackageMapper {
     pub fn new() -> Result<Self, LxHwError> {
         let mut mapper = Self {
@@ -73,24 +228,28 @@ impl PackageMapper {
         Ok(mapper)
     }
 
-    pub fn map_packages(&self, hardware: &HardwareReport, distribution: &str) -> Result<Vec<PackageInstallation>, LxHwError> {
+    pub fn map_packages(
+        &self,
+        hardware: &HardwareReport,
+        distribution: &str,
+    ) -> Result<Vec<PackageInstallation>, LxHwError> {
         let mut installations = Vec::new();
 
-        // Get distribution-specific package map
-        let dist_map = self.distribution_packages.get(distribution)
-            .or_else(|| {
-                // Try case-insensitive lookup
-                let dist_lower = distribution.to_lowercase();
-                self.distribution_packages.iter()
-                    .find(|(k, _)| k.to_lowercase().contains(&dist_lower) || dist_lower.contains(&k.to_lowercase()))
-                    .map(|(_, v)| v)
-            });
-
-        if dist_map.is_none() {
-            return Ok(installations); // No package mappings available for this distribution
-        }
+        // Immutable distributions use the package names of the distribution
+        // they are built from, installed with their own tooling
+        let immutable = classify_distribution(distribution);
+        let base_distribution = match immutable {
+            Some(ImmutableKind::RpmOstree) => "Fedora",
+            Some(ImmutableKind::TransactionalUpdate) => "openSUSE Tumbleweed",
+            Some(ImmutableKind::SteamOs) => "Arch Linux",
+            None => distribution,
+        };
 
-        let dist_map = dist_map.unwrap();
+        // Get distribution-specific package map
+        let dist_map = match self.distribution_map(base_distribution) {
+            Some(dist_map) => dist_map,
+            None => return Ok(installations), // No package mappings available for this distribution
+        };
 
         // Map packages for CPU
         if let Some(cpu) = &hardware.cpu {
@@ -115,9 +274,130 @@ impl PackageMapper {
         // Map general system packages
         installations.extend(self.map_system_packages(hardware, dist_map)?);
 
+        if let Some(kind) = immutable {
+            for installation in &mut installations {
+                adapt_for_immutable(installation, kind);
+            }
+        }
+
         Ok(installations)
     }
 
+    fn distribution_map(&self, distribution: &str) -> Option<&DistributionPackageMap> {
+        self.distribution_packages.get(distribution).or_else(|| {
+            // Try case-insensitive lookup, treating os-release style IDs
+            // ("opensuse-tumbleweed") like the display names
+            let dist_lower = distribution.to_lowercase().replace(['-', '_'], " ");
+            self.distribution_packages
+                .iter()
+                .find(|(k, _)| k.to_lowercase() == dist_lower)
+                .or_else(|| {
+                    self.distribution_packages.iter().find(|(k, _)| {
+                        k.to_lowercase().contains(&dist_lower)
+                            || dist_lower.contains(&k.to_lowercase())
+                    })
+                })
+                .map(|(_, v)| v)
+        })
+    }
+
+    /// Whether a distribution installs packages with Portage and so needs
+    /// the compile-time settings from `portage_settings`
+    pub fn uses_portage(&self, distribution: &str) -> bool {
+        self.distribution_map(distribution)
+            .is_some_and(|dist_map| matches!(dist_map.package_manager, PackageManager::Portage))
+    }
+
+    /// USE flags, USE_EXPAND variables and licenses a Gentoo system needs
+    /// for its hardware to be supported by the packages it builds
+    pub fn portage_settings(&self, hardware: &HardwareReport) -> PortageSettings {
+        let mut settings = PortageSettings::default();
+        // AMD microcode, AMD GPUs and wireless adapters load linux-firmware
+        let mut needs_firmware = false;
+
+        if let Some(cpu) = &hardware.cpu {
+            let mut cpu_flags: Vec<String> = CPU_FLAGS_X86
+                .iter()
+                .filter(|(proc_flag, _)| cpu.flags.iter().any(|flag| flag == proc_flag))
+                .map(|(_, portage_flag)| portage_flag.to_string())
+                .collect();
+            cpu_flags.sort();
+            cpu_flags.dedup();
+            if !cpu_flags.is_empty() {
+                settings.set_variable("CPU_FLAGS_X86", cpu_flags);
+            }
+            if cpu.vendor.to_lowercase().contains("intel") {
+                // Only install the microcode for this CPU, not every model
+                settings.set_variable("MICROCODE_SIGNATURES", vec!["-S".to_string()]);
+                settings.add_license("sys-firmware/intel-microcode", "intel-ucode");
+            } else if cpu.vendor.to_lowercase().contains("amd") {
+                needs_firmware = true;
+            }
+        }
+
+        let mut video_cards = Vec::new();
+        for gpu in &hardware.graphics {
+            let vendor = gpu.vendor.to_lowercase();
+            // NVIDIA first: "NVIDIA Corporation" contains "ati"
+            let cards: &[&str] = if vendor.contains("intel") {
+                &["intel"]
+            } else if vendor.contains("nvidia") && gpu.driver.as_deref() == Some("nouveau") {
+                &["nouveau"]
+            } else if vendor.contains("nvidia") {
+                settings.add_license("x11-drivers/nvidia-drivers", "NVIDIA-r2");
+                &["nvidia"]
+            } else if vendor.contains("amd") || vendor.contains("ati") {
+                needs_firmware = true;
+                &["amdgpu", "radeonsi"]
+            } else {
+                &[]
+            };
+            for card in cards {
+                if !video_cards.iter().any(|c| c == card) {
+                    video_cards.push(card.to_string());
+                }
+            }
+        }
+        if !video_cards.is_empty() {
+            settings.set_variable("VIDEO_CARDS", video_cards);
+            settings.add_use_flag("vulkan");
+        }
+
+        let mut alsa_cards = Vec::new();
+        for driver in hardware.audio.iter().filter_map(|audio| audio.driver.as_deref()) {
+            let card = match driver {
+                "snd_hda_intel" | "snd-hda-intel" => "hda-intel",
+                "snd_usb_audio" | "snd-usb-audio" => "usb-audio",
+                _ => continue,
+            };
+            if !alsa_cards.iter().any(|c| c == card) {
+                alsa_cards.push(card.to_string());
+            }
+        }
+        if !hardware.audio.is_empty() {
+            settings.add_use_flag("alsa");
+            settings.add_use_flag("pipewire");
+        }
+        if !alsa_cards.is_empty() {
+            settings.set_variable("ALSA_CARDS", alsa_cards);
+        }
+
+        settings.set_variable("INPUT_DEVICES", vec!["libinput".to_string()]);
+        for network in &hardware.network {
+            match network.device_type.as_str() {
+                "wifi" | "wireless" => settings.add_use_flag("wifi"),
+                "bluetooth" => settings.add_use_flag("bluetooth"),
+                _ => continue,
+            }
+            needs_firmware = true;
+        }
+        if needs_firmware {
+            settings.add_license("sys-kernel/linux-firmware", "linux-fw-redistributable");
+        }
+
+        settings
+    }
+
     fn initialize_distribution_packages(&mut self) -> Result<(), LxHwError> {
         // Ubuntu/Debian (APT-based)
         let mut ubuntu_packages = HashMap::new();
@@ -130,28 +410,31 @@ impl PackageMapper {
         ubuntu_packages.insert("pulseaudio".to_string(), "pulseaudio".to_string());
         ubuntu_packages.insert("pipewire".to_string(), "pipewire".to_string());
 
-        self.distribution_packages.insert("Ubuntu".to_string(), DistributionPackageMap {
-            distribution_name: "Ubuntu".to_string(),
-            package_manager: PackageManager::Apt,
-            install_command: "apt update && apt install -y".to_string(),
-            update_command: "apt update && apt upgrade -y".to_string(),
-            search_command: "apt search".to_string(),
-            repositories: vec![
-                PackageRepository {
-                    name: "universe".to_string(),
-                    url: "http://archive.ubuntu.com/ubuntu".to_string(),
-                    enabled_by_default: true,
-                    setup_command: Some("add-apt-repository universe".to_string()),
-                },
-                PackageRepository {
-                    name: "multiverse".to_string(),
-                    url: "http://archive.ubuntu.com/ubuntu".to_string(),
-                    enabled_by_default: false,
-                    setup_command: Some("add-apt-repository multiverse".to_string()),
-                },
-            ],
-            package_mappings: ubuntu_packages,
-        });
+        self.distribution_packages.insert(
+            "Ubuntu".to_string(),
+            DistributionPackageMap {
+                distribution_name: "Ubuntu".to_string(),
+                package_manager: PackageManager::Apt,
+                install_command: "apt update && apt install -y".to_string(),
+                update_command: "apt update && apt upgrade -y".to_string(),
+                search_command: "apt search".to_string(),
+                repositories: vec![
+                    PackageRepository {
+                        name: "universe".to_string(),
+                        url: "http://archive.ubuntu.com/ubuntu".to_string(),
+                        enabled_by_default: true,
+                        setup_command: Some("add-apt-repository universe".to_string()),
+                    },
+                    PackageRepository {
+                        name: "multiverse".to_string(),
+                        url: "http://archive.ubuntu.com/ubuntu".to_string(),
+                        enabled_by_default: false,
+                        setup_command: Some("add-apt-repository multiverse".to_string()),
+                    },
+                ],
+                package_mappings: ubuntu_packages,
+            },
+        );
 
         // Fedora (DNF-based)
         let mut fedora_packages = HashMap::new();
@@ -192,28 +475,32 @@ impl PackageMapper {
         arch_packages.insert("intel-microcode".to_string(), "intel-ucode".to_string());
         arch_packages.insert("amd-microcode".to_string(), "amd-ucode".to_string());
         arch_packages.insert("nvidia-driver".to_string(), "nvidia nvidia-settings".to_string());
-        arch_packages.insert("mesa-drivers".to_string(), "mesa vulkan-radeon vulkan-intel".to_string());
+        arch_packages
+            .insert("mesa-drivers".to_string(), "mesa vulkan-radeon vulkan-intel".to_string());
         arch_packages.insert("firmware-linux".to_string(), "linux-firmware".to_string());
         arch_packages.insert("firmware-iwlwifi".to_string(), "linux-firmware".to_string());
         arch_packages.insert("pulseaudio".to_string(), "pulseaudio".to_string());
         arch_packages.insert("pipewire".to_string(), "pipewire".to_string());
 
-        self.distribution_packages.insert("Arch Linux".to_string(), DistributionPackageMap {
-            distribution_name: "Arch Linux".to_string(),
-            package_manager: PackageManager::Pacman,
-            install_command: "pacman -S --noconfirm".to_string(),
-            update_command: "pacman -Syu --noconfirm".to_string(),
-            search_command: "pacman -Ss".to_string(),
-            repositories: vec![
-                PackageRepository {
+        self.distribution_packages.insert(
+            "Arch Linux".to_string(),
+            DistributionPackageMap {
+                distribution_name: "Arch Linux".to_string(),
+                package_manager: PackageManager::Pacman,
+                install_command: "pacman -S --noconfirm".to_string(),
+                update_command: "pacman -Syu --noconfirm".to_string(),
+                search_command: "pacman -Ss".to_string(),
+                repositories: vec![PackageRepository {
                     name: "multilib".to_string(),
                     url: "https://archlinux.org/packages/".to_string(),
                     enabled_by_default: false,
-                    setup_command: Some("# Uncomment [multilib] section in /etc/pacman.conf".to_string()),
-                },
-            ],
-            package_mappings: arch_packages,
-        });
+                    setup_command: Some(
+                        "# Uncomment [multilib] section in /etc/pacman.conf".to_string(),
+                    ),
+                }],
+                package_mappings: arch_packages,
+            },
+        );
 
         // NixOS (Nix-based)
         let mut nixos_packages = HashMap::new();
@@ -243,13 +530,155 @@ impl PackageMapper {
             package_mappings: nixos_packages,
         });
 
+        // openSUSE Tumbleweed and Leap (Zypper-based)
+        let mut opensuse_packages = HashMap::new();
+        opensuse_packages.insert("intel-microcode".to_string(), "ucode-intel".to_string());
+        opensuse_packages.insert("amd-microcode".to_string(), "ucode-amd".to_string());
+        opensuse_packages.insert("nvidia-driver".to_string(), "nvidia-video-G06".to_string());
+        opensuse_packages.insert(
+            "mesa-drivers".to_string(),
+            "Mesa-dri libvulkan_radeon libvulkan_intel".to_string(),
+        );
+        opensuse_packages.insert("firmware-linux".to_string(), "kernel-firmware-all".to_string());
+        opensuse_packages
+            .insert("firmware-iwlwifi".to_string(), "kernel-firmware-iwlwifi".to_string());
+        opensuse_packages
+            .insert("firmware-brcm80211".to_string(), "kernel-firmware-brcm".to_string());
+        opensuse_packages.insert("pulseaudio".to_string(), "pulseaudio".to_string());
+        opensuse_packages.insert("pipewire".to_string(), "pipewire".to_string());
+
+        let opensuse_releases = [
+            (
+                "openSUSE Tumbleweed",
+                "zypper --non-interactive dup",
+                "openSUSE_Tumbleweed",
+                "tumbleweed",
+            ),
+            (
+                "openSUSE Leap",
+                "zypper --non-interactive update",
+                "openSUSE_Leap_$releasever",
+                "leap/$releasever",
+            ),
+        ];
+        for (name, update_command, packman_release, nvidia_release) in opensuse_releases {
+            let packman_url =
+                format!("https://ftp.gwdg.de/pub/linux/misc/packman/suse/{}/", packman_release);
+            let nvidia_url = format!("https://download.nvidia.com/opensuse/{}", nvidia_release);
+            self.distribution_packages.insert(name.to_string(), DistributionPackageMap {
+                distribution_name: name.to_string(),
+                package_manager: PackageManager::Zypper,
+                install_command: "zypper --non-interactive install".to_string(),
+                update_command: update_command.to_string(),
+                search_command: "zypper search".to_string(),
+                repositories: vec![
+                    PackageRepository {
+                        name: "packman".to_string(),
+                        setup_command: Some(format!("zypper addrepo --refresh --priority 90 '{}' packman && zypper --gpg-auto-import-keys refresh packman", packman_url)),
+                        url: packman_url,
+                        enabled_by_default: false,
+                    },
+                    PackageRepository {
+                        name: "NVIDIA".to_string(),
+                        setup_command: Some(format!("zypper addrepo --refresh '{}' NVIDIA && zypper --gpg-auto-import-keys refresh NVIDIA", nvidia_url)),
+                        url: nvidia_url,
+                        enabled_by_default: false,
+                    },
+                ],
+                package_mappings: opensuse_packages.clone(),
+            });
+        }
+
+        // Gentoo (Portage-based). Packages are built from source, so the
+        // hardware also shapes USE flags; see portage_settings.
+        let mut gentoo_packages = HashMap::new();
+        gentoo_packages
+            .insert("intel-microcode".to_string(), "sys-firmware/intel-microcode".to_string());
+        gentoo_packages
+            .insert("amd-microcode".to_string(), "sys-kernel/linux-firmware".to_string());
+        gentoo_packages
+            .insert("nvidia-driver".to_string(), "x11-drivers/nvidia-drivers".to_string());
+        gentoo_packages.insert("mesa-drivers".to_string(), "media-libs/mesa".to_string());
+        gentoo_packages
+            .insert("firmware-linux".to_string(), "sys-kernel/linux-firmware".to_string());
+        gentoo_packages
+            .insert("firmware-iwlwifi".to_string(), "sys-kernel/linux-firmware".to_string());
+        gentoo_packages
+            .insert("firmware-brcm80211".to_string(), "sys-kernel/linux-firmware".to_string());
+        gentoo_packages.insert("alsa-utils".to_string(), "media-sound/alsa-utils".to_string());
+        gentoo_packages
+            .insert("pulseaudio".to_string(), "media-sound/pulseaudio-daemon".to_string());
+        gentoo_packages.insert("pipewire".to_string(), "media-video/pipewire".to_string());
+
+        self.distribution_packages.insert(
+            "Gentoo".to_string(),
+            DistributionPackageMap {
+                distribution_name: "Gentoo".to_string(),
+                package_manager: PackageManager::Portage,
+                install_command: "emerge --ask=n --noreplace".to_string(),
+                update_command: "emerge --sync && emerge --ask=n --update --deep --newuse @world"
+                    .to_string(),
+                search_command: "emerge --search".to_string(),
+                repositories: vec![PackageRepository {
+                    name: "guru".to_string(),
+                    url: "https://github.com/gentoo/guru".to_string(),
+                    enabled_by_default: false,
+                    setup_command: Some(
+                        "eselect repository enable guru && emerge --sync guru".to_string(),
+                    ),
+                }],
+                package_mappings: gentoo_packages,
+            },
+        );
+
+        // Alpine Linux (apk-based). Alpine builds against musl, so there is no
+        // proprietary NVIDIA driver; nouveau from Mesa is the only option.
+        let mut alpine_packages = HashMap::new();
+        alpine_packages.insert("intel-microcode".to_string(), "intel-ucode".to_string());
+        alpine_packages.insert("amd-microcode".to_string(), "amd-ucode".to_string());
+        alpine_packages.insert(
+            "nvidia-driver".to_string(),
+            "mesa-dri-gallium linux-firmware-nvidia".to_string(),
+        );
+        alpine_packages.insert(
+            "mesa-drivers".to_string(),
+            "mesa-dri-gallium mesa-vulkan-ati mesa-vulkan-intel".to_string(),
+        );
+        alpine_packages.insert("firmware-linux".to_string(), "linux-firmware".to_string());
+        alpine_packages.insert("firmware-iwlwifi".to_string(), "linux-firmware-intel".to_string());
+        alpine_packages.insert("firmware-brcm80211".to_string(), "linux-firmware-brcm".to_string());
+        alpine_packages.insert("pulseaudio".to_string(), "pulseaudio".to_string());
+        alpine_packages.insert("pipewire".to_string(), "pipewire".to_string());
+
+        self.distribution_packages.insert(
+            "Alpine Linux".to_string(),
+            DistributionPackageMap {
+                distribution_name: "Alpine Linux".to_string(),
+                package_manager: PackageManager::Apk,
+                install_command: "apk add".to_string(),
+                update_command: "apk update && apk upgrade".to_string(),
+                search_command: "apk search".to_string(),
+                repositories: vec![PackageRepository {
+                    name: "community".to_string(),
+                    url: "https://dl-cdn.alpinelinux.org/alpine/".to_string(),
+                    enabled_by_default: false,
+                    setup_command: Some(
+                        "sed -i -E '/\\/community$/s/^#//' /etc/apk/repositories && apk update"
+                            .to_string(),
+                    ),
+                }],
+                package_mappings: alpine_packages,
+            },
+        );
+
         Ok(())
     }
 
     fn initialize_hardware_mappings(&mut self) -> Result<(), LxHwError> {
         // Intel CPU packages
-        self.hardware_package_mappings.insert("intel_cpu".to_string(), vec![
-            HardwarePackageMapping {
+        self.hardware_package_mappings.insert(
+            "intel_cpu".to_string(),
+            vec![HardwarePackageMapping {
                 hardware_pattern: HardwarePackagePattern {
                     component_type: "CPU".to_string(),
                     vendor_pattern: Some("Intel".to_string()),
@@ -261,15 +690,16 @@ impl PackageMapper {
                 firmware_packages: vec!["intel-microcode".to_string()],
                 configuration_packages: vec![],
                 post_install_commands: vec![
-                    "echo 'early-microcode' >> /etc/mkinitcpio.conf".to_string(),
+                    "echo 'early-microcode' >> /etc/mkinitcpio.conf".to_string()
                 ],
                 package_priority: 9,
-            },
-        ]);
+            }],
+        );
 
         // AMD CPU packages
-        self.hardware_package_mappings.insert("amd_cpu".to_string(), vec![
-            HardwarePackageMapping {
+        self.hardware_package_mappings.insert(
+            "amd_cpu".to_string(),
+            vec![HardwarePackageMapping {
                 hardware_pattern: HardwarePackagePattern {
                     component_type: "CPU".to_string(),
                     vendor_pattern: Some("AMD".to_string()),
@@ -282,12 +712,13 @@ impl PackageMapper {
                 configuration_packages: vec![],
                 post_install_commands: vec![],
                 package_priority: 9,
-            },
-        ]);
+            }],
+        );
 
         // NVIDIA GPU packages
-        self.hardware_package_mappings.insert("nvidia_gpu".to_string(), vec![
-            HardwarePackageMapping {
+        self.hardware_package_mappings.insert(
+            "nvidia_gpu".to_string(),
+            vec![HardwarePackageMapping {
                 hardware_pattern: HardwarePackagePattern {
                     component_type: "GPU".to_string(),
                     vendor_pattern: Some("NVIDIA".to_string()),
@@ -303,12 +734,13 @@ impl PackageMapper {
                     "nvidia-xconfig".to_string(),
                 ],
                 package_priority: 8,
-            },
-        ]);
+            }],
+        );
 
         // AMD GPU packages
-        self.hardware_package_mappings.insert("amd_gpu".to_string(), vec![
-            HardwarePackageMapping {
+        self.hardware_package_mappings.insert(
+            "amd_gpu".to_string(),
+            vec![HardwarePackageMapping {
                 hardware_pattern: HardwarePackagePattern {
                     component_type: "GPU".to_string(),
                     vendor_pattern: Some("AMD".to_string()),
@@ -321,12 +753,13 @@ impl PackageMapper {
                 configuration_packages: vec!["mesa-utils".to_string()],
                 post_install_commands: vec![],
                 package_priority: 8,
-            },
-        ]);
+            }],
+        );
 
         // Intel wireless packages
-        self.hardware_package_mappings.insert("intel_wifi".to_string(), vec![
-            HardwarePackageMapping {
+        self.hardware_package_mappings.insert(
+            "intel_wifi".to_string(),
+            vec![HardwarePackageMapping {
                 hardware_pattern: HardwarePackagePattern {
                     component_type: "Network".to_string(),
                     vendor_pattern: Some("Intel".to_string()),
@@ -337,12 +770,10 @@ impl PackageMapper {
                 optional_packages: vec!["iw".to_string(), "wireless-tools".to_string()],
                 firmware_packages: vec!["firmware-iwlwifi".to_string()],
                 configuration_packages: vec!["network-manager".to_string()],
-                post_install_commands: vec![
-                    "systemctl enable NetworkManager".to_string(),
-                ],
+                post_install_commands: vec!["systemctl enable NetworkManager".to_string()],
                 package_priority: 7,
-            },
-        ]);
+            }],
+        );
 
         Ok(())
     }
@@ -355,18 +786,27 @@ impl PackageMapper {
         intel_microcode_packages.insert("Fedora".to_string(), "microcode_ctl".to_string());
         intel_microcode_packages.insert("Arch Linux".to_string(), "intel-ucode".to_string());
         intel_microcode_packages.insert("NixOS".to_string(), "intel-microcode".to_string());
-
-        self.firmware_packages.insert("intel-microcode".to_string(), FirmwarePackageInfo {
-            firmware_name: "Intel CPU Microcode".to_string(),
-            package_mappings: intel_microcode_packages,
-            firmware_files: vec![
-                "/lib/firmware/intel-ucode/*".to_string(),
-                "/boot/intel-ucode.img".to_string(),
-            ],
-            installation_path: "/lib/firmware/intel-ucode/".to_string(),
-            license: "Intel Proprietary".to_string(),
-            description: "Intel CPU microcode updates for security and stability".to_string(),
-        });
+        intel_microcode_packages
+            .insert("openSUSE Tumbleweed".to_string(), "ucode-intel".to_string());
+        intel_microcode_packages.insert("openSUSE Leap".to_string(), "ucode-intel".to_string());
+        intel_microcode_packages.insert("Alpine Linux".to_string(), "intel-ucode".to_string());
+        intel_microcode_packages
+            .insert("Gentoo".to_string(), "sys-firmware/intel-microcode".to_string());
+
+        self.firmware_packages.insert(
+            "intel-microcode".to_string(),
+            FirmwarePackageInfo {
+                firmware_name: "Intel CPU Microcode".to_string(),
+                package_mappings: intel_microcode_packages,
+                firmware_files: vec![
+                    "/lib/firmware/intel-ucode/*".to_string(),
+                    "/boot/intel-ucode.img".to_string(),
+                ],
+                installation_path: "/lib/firmware/intel-ucode/".to_string(),
+                license: "Intel Proprietary".to_string(),
+                description: "Intel CPU microcode updates for security and stability".to_string(),
+            },
+        );
 
         // Intel WiFi firmware
         let mut intel_wifi_packages = HashMap::new();
@@ -375,17 +815,24 @@ impl PackageMapper {
         intel_wifi_packages.insert("Fedora".to_string(), "iwl*-firmware".to_string());
         intel_wifi_packages.insert("Arch Linux".to_string(), "linux-firmware".to_string());
         intel_wifi_packages.insert("NixOS".to_string(), "linux-firmware".to_string());
-
-        self.firmware_packages.insert("intel-wifi".to_string(), FirmwarePackageInfo {
-            firmware_name: "Intel WiFi Firmware".to_string(),
-            package_mappings: intel_wifi_packages,
-            firmware_files: vec![
-                "/lib/firmware/iwlwifi-*".to_string(),
-            ],
-            installation_path: "/lib/firmware/".to_string(),
-            license: "Intel Proprietary".to_string(),
-            description: "Intel WiFi adapter firmware files".to_string(),
-        });
+        intel_wifi_packages
+            .insert("openSUSE Tumbleweed".to_string(), "kernel-firmware-iwlwifi".to_string());
+        intel_wifi_packages
+            .insert("openSUSE Leap".to_string(), "kernel-firmware-iwlwifi".to_string());
+        intel_wifi_packages.insert("Alpine Linux".to_string(), "linux-firmware-intel".to_string());
+        intel_wifi_packages.insert("Gentoo".to_string(), "sys-kernel/linux-firmware".to_string());
+
+        self.firmware_packages.insert(
+            "intel-wifi".to_string(),
+            FirmwarePackageInfo {
+                firmware_name: "Intel WiFi Firmware".to_string(),
+                package_mappings: intel_wifi_packages,
+                firmware_files: vec!["/lib/firmware/iwlwifi-*".to_string()],
+                installation_path: "/lib/firmware/".to_string(),
+                license: "Intel Proprietary".to_string(),
+                description: "Intel WiFi adapter firmware files".to_string(),
+            },
+        );
 
         // AMD GPU firmware
         let mut amd_gpu_packages = HashMap::new();
@@ -394,24 +841,36 @@ impl PackageMapper {
         amd_gpu_packages.insert("Fedora".to_string(), "amd-gpu-firmware".to_string());
         amd_gpu_packages.insert("Arch Linux".to_string(), "linux-firmware".to_string());
         amd_gpu_packages.insert("NixOS".to_string(), "linux-firmware".to_string());
-
-        self.firmware_packages.insert("amd-gpu".to_string(), FirmwarePackageInfo {
-            firmware_name: "AMD GPU Firmware".to_string(),
-            package_mappings: amd_gpu_packages,
-            firmware_files: vec![
-                "/lib/firmware/amdgpu/*".to_string(),
-                "/lib/firmware/radeon/*".to_string(),
-            ],
-            installation_path: "/lib/firmware/amdgpu/".to_string(),
-            license: "AMD Proprietary".to_string(),
-            description: "AMD GPU firmware for amdgpu driver".to_string(),
-        });
+        amd_gpu_packages
+            .insert("openSUSE Tumbleweed".to_string(), "kernel-firmware-amdgpu".to_string());
+        amd_gpu_packages.insert("openSUSE Leap".to_string(), "kernel-firmware-amdgpu".to_string());
+        amd_gpu_packages.insert("Alpine Linux".to_string(), "linux-firmware-amdgpu".to_string());
+        amd_gpu_packages.insert("Gentoo".to_string(), "sys-kernel/linux-firmware".to_string());
+
+        self.firmware_packages.insert(
+            "amd-gpu".to_string(),
+            FirmwarePackageInfo {
+                firmware_name: "AMD GPU Firmware".to_string(),
+                package_mappings: amd_gpu_packages,
+                firmware_files: vec![
+                    "/lib/firmware/amdgpu/*".to_string(),
+                    "/lib/firmware/radeon/*".to_string(),
+                ],
+                installation_path: "/lib/firmware/amdgpu/".to_string(),
+                license: "AMD Proprietary".to_string(),
+                description: "AMD GPU firmware for amdgpu driver".to_string(),
+            },
+        );
 
         Ok(())
     }
 
     #[allow(dead_code)]
-    fn map_cpu_packages(&self, cpu_info: &Value, dist_map: &DistributionPackageMap) -> Result<Vec<PackageInstallation>, LxHwError> {
+    fn map_cpu_packages(
+        &self,
+        cpu_info: &Value,
+        dist_map: &DistributionPackageMap,
+    ) -> Result<Vec<PackageInstallation>, LxHwError> {
         let mut installations = Vec::new();
 
         let vendor = cpu_info.get("vendor").and_then(|v| v.as_str()).unwrap_or("unknown");
@@ -435,7 +894,11 @@ impl PackageMapper {
     }
 
     #[allow(dead_code)]
-    fn map_gpu_packages(&self, gpu_info: &Value, dist_map: &DistributionPackageMap) -> Result<Vec<PackageInstallation>, LxHwError> {
+    fn map_gpu_packages(
+        &self,
+        gpu_info: &Value,
+        dist_map: &DistributionPackageMap,
+    ) -> Result<Vec<PackageInstallation>, LxHwError> {
         let mut installations = Vec::new();
 
         let vendor = gpu_info.get("vendor").and_then(|v| v.as_str()).unwrap_or("unknown");
@@ -459,16 +922,22 @@ impl PackageMapper {
     }
 
     #[allow(dead_code)]
-    fn map_network_packages(&self, network_info: &Value, dist_map: &DistributionPackageMap) -> Result<Vec<PackageInstallation>, LxHwError> {
+    fn map_network_packages(
+        &self,
+        network_info: &Value,
+        dist_map: &DistributionPackageMap,
+    ) -> Result<Vec<PackageInstallation>, LxHwError> {
         let mut installations = Vec::new();
 
         let vendor = network_info.get("vendor").and_then(|v| v.as_str()).unwrap_or("unknown");
         let product = network_info.get("product").and_then(|p| p.as_str()).unwrap_or("unknown");
-        
+
         let vendor_lower = vendor.to_lowercase();
         let product_lower = product.to_lowercase();
 
-        if vendor_lower.contains("intel") && (product_lower.contains("wireless") || product_lower.contains("wifi")) {
+        if vendor_lower.contains("intel")
+            && (product_lower.contains("wireless") || product_lower.contains("wifi"))
+        {
             if let Some(mappings) = self.hardware_package_mappings.get("intel_wifi") {
                 for mapping in mappings {
                     installations.push(self.create_package_installation(mapping, dist_map)?);
@@ -480,7 +949,11 @@ impl PackageMapper {
     }
 
     #[allow(dead_code)]
-    fn map_audio_packages(&self, _audio_info: &Value, dist_map: &DistributionPackageMap) -> Result<Vec<PackageInstallation>, LxHwError> {
+    fn map_audio_packages(
+        &self,
+        _audio_info: &Value,
+        dist_map: &DistributionPackageMap,
+    ) -> Result<Vec<PackageInstallation>, LxHwError> {
         let mut installations = Vec::new();
 
         // Most modern Linux distributions use PipeWire or PulseAudio
@@ -493,21 +966,25 @@ impl PackageMapper {
                 package_description: "Audio system packages for PulseAudio and ALSA".to_string(),
                 package_category: PackageCategory::System,
                 installation_reason: InstallationReason::HardwareSupport,
-                installation_command: format!("{} {}", 
+                installation_command: format!(
+                    "{} {}",
                     dist_map.install_command,
                     mapped_packages.join(" ")
                 ),
-                post_install_commands: vec![
-                    "systemctl --user enable pulseaudio".to_string(),
-                ],
+                post_install_commands: vec!["systemctl --user enable pulseaudio".to_string()],
                 dependencies: vec![],
+                notes: Vec::new(),
             });
         }
 
         Ok(installations)
     }
 
-    fn map_system_packages(&self, _hardware: &HardwareReport, dist_map: &DistributionPackageMap) -> Result<Vec<PackageInstallation>, LxHwError> {
+    fn map_system_packages(
+        &self,
+        _hardware: &HardwareReport,
+        dist_map: &DistributionPackageMap,
+    ) -> Result<Vec<PackageInstallation>, LxHwError> {
         let mut installations = Vec::new();
 
         // Essential system packages for hardware management
@@ -526,12 +1003,14 @@ impl PackageMapper {
                 package_description: "Essential system packages".to_string(),
                 package_category: PackageCategory::System,
                 installation_reason: InstallationReason::HardwareSupport,
-                installation_command: format!("{} {}", 
+                installation_command: format!(
+                    "{} {}",
                     dist_map.install_command,
                     mapped_packages.join(" ")
                 ),
                 post_install_commands: vec![],
                 dependencies: vec![],
+                notes: Vec::new(),
             });
         }
 
@@ -539,13 +1018,18 @@ impl PackageMapper {
     }
 
     #[allow(dead_code)]
-    fn create_package_installation(&self, mapping: &HardwarePackageMapping, dist_map: &DistributionPackageMap) -> Result<PackageInstallation, LxHwError> {
+    fn create_package_installation(
+        &self,
+        mapping: &HardwarePackageMapping,
+        dist_map: &DistributionPackageMap,
+    ) -> Result<PackageInstallation, LxHwError> {
         let all_packages = [
             mapping.required_packages.clone(),
             mapping.optional_packages.clone(),
             mapping.firmware_packages.clone(),
             mapping.configuration_packages.clone(),
-        ].concat();
+        ]
+        .concat();
 
         let mapped_packages = self.map_package_names(&all_packages, dist_map);
 
@@ -554,27 +1038,44 @@ impl PackageMapper {
             package_description: "Hardware-specific packages".to_string(),
             package_category: PackageCategory::Driver,
             installation_reason: InstallationReason::HardwareSupport,
-            installation_command: format!("{} {}", 
+            installation_command: format!(
+                "{} {}",
                 dist_map.install_command,
                 mapped_packages.join(" ")
             ),
             post_install_commands: mapping.post_install_commands.clone(),
             dependencies: mapping.required_packages.clone(),
+            notes: Vec::new(),
         })
     }
 
-    fn map_package_names(&self, generic_packages: &[String], dist_map: &DistributionPackageMap) -> Vec<String> {
-        generic_packages.iter()
+    fn map_package_names(
+        &self,
+        generic_packages: &[String],
+        dist_map: &DistributionPackageMap,
+    ) -> Vec<String> {
+        generic_packages
+            .iter()
             .filter_map(|pkg| {
-                dist_map.package_mappings.get(pkg)
-                    .map(|mapped| mapped.clone())
-                    .or_else(|| Some(pkg.clone())) // Fallback to original name
+                dist_map.package_mappings.get(pkg).cloned().or_else(|| Some(pkg.clone()))
+                // Fallback to original name
             })
             .collect()
     }
 
+    /// Install command for a generic package name, using the distribution's
+    /// name for it where the map has one
+    fn install_command(&self, dist_map: &DistributionPackageMap, generic_package: &str) -> String {
+        let packages = self.map_package_names(&[generic_package.to_string()], dist_map);
+        format!("{} {}", dist_map.install_command, packages.join(" "))
+    }
+
     // New methods that work with structured hardware types
-    fn map_cpu_packages_from_device(&self, cpu: &crate::hardware::CpuInfo, dist_map: &DistributionPackageMap) -> Result<Vec<PackageInstallation>, LxHwError> {
+    fn map_cpu_packages_from_device(
+        &self,
+        cpu: &crate::hardware::CpuInfo,
+        dist_map: &DistributionPackageMap,
+    ) -> Result<Vec<PackageInstallation>, LxHwError> {
         let mut installations = Vec::new();
         let vendor = cpu.vendor.to_lowercase();
 
@@ -585,9 +1086,10 @@ impl PackageMapper {
                 package_description: "Intel CPU microcode updates".to_string(),
                 package_category: PackageCategory::Firmware,
                 installation_reason: InstallationReason::HardwareSupport,
-                installation_command: format!("{} intel-microcode", dist_map.install_command),
+                installation_command: self.install_command(dist_map, "intel-microcode"),
                 post_install_commands: vec![],
                 dependencies: vec![],
+                notes: Vec::new(),
             });
         } else if vendor.contains("amd") {
             installations.push(PackageInstallation {
@@ -595,16 +1097,21 @@ impl PackageMapper {
                 package_description: "AMD CPU microcode updates".to_string(),
                 package_category: PackageCategory::Firmware,
                 installation_reason: InstallationReason::HardwareSupport,
-                installation_command: format!("{} amd64-microcode", dist_map.install_command),
+                installation_command: self.install_command(dist_map, "amd-microcode"),
                 post_install_commands: vec![],
                 dependencies: vec![],
+                notes: Vec::new(),
             });
         }
 
         Ok(installations)
     }
 
-    fn map_gpu_packages_from_device(&self, gpu: &crate::hardware::GraphicsDevice, dist_map: &DistributionPackageMap) -> Result<Vec<PackageInstallation>, LxHwError> {
+    fn map_gpu_packages_from_device(
+        &self,
+        gpu: &crate::hardware::GraphicsDevice,
+        dist_map: &DistributionPackageMap,
+    ) -> Result<Vec<PackageInstallation>, LxHwError> {
         let mut installations = Vec::new();
         let vendor = gpu.vendor.to_lowercase();
 
@@ -614,9 +1121,10 @@ impl PackageMapper {
                 package_description: "NVIDIA proprietary graphics driver".to_string(),
                 package_category: PackageCategory::Driver,
                 installation_reason: InstallationReason::HardwareSupport,
-                installation_command: format!("{} nvidia-driver", dist_map.install_command),
+                installation_command: self.install_command(dist_map, "nvidia-driver"),
                 post_install_commands: vec!["nvidia-xconfig".to_string()],
                 dependencies: vec!["linux-headers".to_string()],
+                notes: Vec::new(),
             });
         } else if vendor.contains("amd") {
             installations.push(PackageInstallation {
@@ -624,16 +1132,21 @@ impl PackageMapper {
                 package_description: "Mesa Vulkan drivers for AMD graphics".to_string(),
                 package_category: PackageCategory::Driver,
                 installation_reason: InstallationReason::HardwareSupport,
-                installation_command: format!("{} mesa-vulkan-drivers", dist_map.install_command),
+                installation_command: self.install_command(dist_map, "mesa-drivers"),
                 post_install_commands: vec![],
                 dependencies: vec![],
+                notes: Vec::new(),
             });
         }
 
         Ok(installations)
     }
 
-    fn map_network_packages_from_device(&self, network: &crate::hardware::NetworkDevice, dist_map: &DistributionPackageMap) -> Result<Vec<PackageInstallation>, LxHwError> {
+    fn map_network_packages_from_device(
+        &self,
+        network: &crate::hardware::NetworkDevice,
+        dist_map: &DistributionPackageMap,
+    ) -> Result<Vec<PackageInstallation>, LxHwError> {
         let mut installations = Vec::new();
         let vendor = network.vendor.to_lowercase();
         let device_type = &network.device_type;
@@ -646,9 +1159,12 @@ impl PackageMapper {
                     package_description: "Intel wireless firmware".to_string(),
                     package_category: PackageCategory::Firmware,
                     installation_reason: InstallationReason::HardwareSupport,
-                    installation_command: format!("{} firmware-iwlwifi", dist_map.install_command),
-                    post_install_commands: vec!["modprobe -r iwlwifi && modprobe iwlwifi".to_string()],
+                    installation_command: self.install_command(dist_map, "firmware-iwlwifi"),
+                    post_install_commands: vec![
+                        "modprobe -r iwlwifi && modprobe iwlwifi".to_string()
+                    ],
                     dependencies: vec![],
+                    notes: Vec::new(),
                 });
             } else if vendor.contains("broadcom") {
                 installations.push(PackageInstallation {
@@ -656,9 +1172,10 @@ impl PackageMapper {
                     package_description: "Broadcom wireless firmware".to_string(),
                     package_category: PackageCategory::Firmware,
                     installation_reason: InstallationReason::HardwareSupport,
-                    installation_command: format!("{} firmware-brcm80211", dist_map.install_command),
+                    installation_command: self.install_command(dist_map, "firmware-brcm80211"),
                     post_install_commands: vec![],
                     dependencies: vec![],
+                    notes: Vec::new(),
                 });
             }
         }
@@ -666,20 +1183,231 @@ impl PackageMapper {
         Ok(installations)
     }
 
-    fn map_audio_packages_from_device(&self, _audio: &crate::hardware::AudioDevice, dist_map: &DistributionPackageMap) -> Result<Vec<PackageInstallation>, LxHwError> {
-        let mut installations = Vec::new();
-
+    fn map_audio_packages_from_device(
+        &self,
+        _audio: &crate::hardware::AudioDevice,
+        dist_map: &DistributionPackageMap,
+    ) -> Result<Vec<PackageInstallation>, LxHwError> {
         // Basic audio support
-        installations.push(PackageInstallation {
+        Ok(vec![PackageInstallation {
             package_name: "alsa-utils".to_string(),
             package_description: "ALSA sound utilities".to_string(),
             package_category: PackageCategory::Utility,
             installation_reason: InstallationReason::HardwareSupport,
-            installation_command: format!("{} alsa-utils", dist_map.install_command),
+            installation_command: self.install_command(dist_map, "alsa-utils"),
             post_install_commands: vec!["alsactl init".to_string()],
             dependencies: vec![],
-        });
+            notes: Vec::new(),
+        }])
+    }
+}
 
-        Ok(installations)
+/// Rewrite an installation for an immutable distribution: layer the packages
+/// instead of installing them, hold back post-install steps that need the new
+/// deployment, and say what layering means for drivers and tools
+fn adapt_for_immutable(installation: &mut PackageInstallation, kind: ImmutableKind) {
+    if let Some(command) = kind.rewrite_install_command(&installation.installation_command) {
+        installation.installation_command = command;
     }
-}
\ No newline at end of file
+
+    match installation.package_category {
+        PackageCategory::Driver | PackageCategory::Firmware => installation.notes.push(kind.driver_warning().to_string()),
+        PackageCategory::Utility | PackageCategory::Development => installation.notes.push(
+            "Prefer running tools from a toolbox or distrobox container, or as a Flatpak where one exists, over adding them to the system image".to_string(),
+        ),
+        PackageCategory::System => {}
+    }
+
+    if kind.requires_reboot() {
+        if !installation.post_install_commands.is_empty() {
+            installation.notes.push(format!(
+                "Reboot into the new deployment, then run: {}",
+                installation.post_install_commands.join("; ")
+            ));
+        }
+        installation.post_install_commands.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn workstation() -> HardwareReport {
+        serde_json::from_str(include_str!("../../tests/fixtures/configuration/workstation.json"))
+            .unwrap()
+    }
+
+    fn command_for<'a>(installations: &'a [PackageInstallation], package: &str) -> &'a str {
+        &installations.iter().find(|i| i.package_name == package).unwrap().installation_command
+    }
+
+    #[test]
+    fn test_opensuse_packages() {
+        let mapper = PackageMapper::new().unwrap();
+        // os-release IDs resolve like display names
+        let installations = mapper.map_packages(&workstation(), "opensuse-tumbleweed").unwrap();
+
+        assert_eq!(
+            command_for(&installations, "intel-microcode"),
+            "zypper --non-interactive install ucode-intel"
+        );
+        assert_eq!(
+            command_for(&installations, "nvidia-driver"),
+            "zypper --non-interactive install nvidia-video-G06"
+        );
+        assert_eq!(
+            command_for(&installations, "firmware-iwlwifi"),
+            "zypper --non-interactive install kernel-firmware-iwlwifi"
+        );
+
+        let leap = mapper.distribution_map("openSUSE Leap").unwrap();
+        assert!(matches!(leap.package_manager, PackageManager::Zypper));
+        let packman = leap.repositories.iter().find(|r| r.name == "packman").unwrap();
+        assert_eq!(
+            packman.url,
+            "https://ftp.gwdg.de/pub/linux/misc/packman/suse/openSUSE_Leap_$releasever/"
+        );
+        assert!(!packman.enabled_by_default);
+    }
+
+    #[test]
+    fn test_alpine_packages() {
+        let mapper = PackageMapper::new().unwrap();
+        let installations = mapper.map_packages(&workstation(), "Alpine Linux").unwrap();
+
+        assert_eq!(command_for(&installations, "intel-microcode"), "apk add intel-ucode");
+        assert_eq!(command_for(&installations, "firmware-iwlwifi"), "apk add linux-firmware-intel");
+        // No proprietary NVIDIA driver builds against musl
+        assert_eq!(
+            command_for(&installations, "nvidia-driver"),
+            "apk add mesa-dri-gallium linux-firmware-nvidia"
+        );
+        assert!(matches!(
+            mapper.distribution_map("alpine").unwrap().package_manager,
+            PackageManager::Apk
+        ));
+    }
+
+    #[test]
+    fn test_gentoo_emerges_package_atoms() {
+        let mapper = PackageMapper::new().unwrap();
+        let installations = mapper.map_packages(&workstation(), "Gentoo").unwrap();
+
+        assert!(mapper.uses_portage("gentoo"));
+        assert!(!mapper.uses_portage("Fedora"));
+        assert_eq!(
+            command_for(&installations, "nvidia-driver"),
+            "emerge --ask=n --noreplace x11-drivers/nvidia-drivers"
+        );
+        assert_eq!(
+            command_for(&installations, "alsa-utils"),
+            "emerge --ask=n --noreplace media-sound/alsa-utils"
+        );
+    }
+
+    #[test]
+    fn test_portage_settings_from_hardware() {
+        let settings = PackageMapper::new().unwrap().portage_settings(&workstation());
+
+        assert_eq!(settings.use_flags, ["vulkan", "alsa", "pipewire", "wifi"]);
+        assert_eq!(
+            settings.package_use(),
+            "# Hardware settings generated by lx-hw-detect\n\
+             */* vulkan alsa pipewire wifi\n\
+             */* CPU_FLAGS_X86: -* aes avx avx2 sse4_2\n\
+             */* VIDEO_CARDS: -* nvidia\n\
+             */* ALSA_CARDS: -* hda-intel\n\
+             */* INPUT_DEVICES: -* libinput\n"
+        );
+        assert_eq!(
+            settings.package_license(),
+            "# Licenses needed for detected hardware, generated by lx-hw-detect\n\
+             sys-firmware/intel-microcode intel-ucode\n\
+             x11-drivers/nvidia-drivers NVIDIA-r2\n\
+             sys-kernel/linux-firmware linux-fw-redistributable\n"
+        );
+        let make_conf = settings.make_conf_snippet();
+        assert!(make_conf.contains("USE=\"${USE} vulkan alsa pipewire wifi\"\n"));
+        assert!(make_conf.contains("MICROCODE_SIGNATURES=\"-S\"\n"));
+        assert!(make_conf.contains(
+            "ACCEPT_LICENSE=\"${ACCEPT_LICENSE} intel-ucode NVIDIA-r2 linux-fw-redistributable\"\n"
+        ));
+    }
+
+    #[test]
+    fn test_gentoo_configuration_writes_portage_files() {
+        use crate::configuration::engine::ConfigurationEngineImpl;
+
+        let engine = ConfigurationEngineImpl::new().unwrap();
+        let gentoo = engine.generate_configuration(&workstation(), "Gentoo").unwrap();
+        let files: Vec<&str> =
+            gentoo.configuration_files.values().map(|f| f.file_path.as_str()).collect();
+        assert!(files.contains(&"/etc/portage/package.use/00lx-hw-detect"));
+        assert!(files.contains(&"/etc/portage/package.license/lx-hw-detect"));
+        let microcode = &gentoo.configuration_files["portage-intel-microcode-env"];
+        assert_eq!(microcode.file_path, "/etc/portage/env/sys-firmware/intel-microcode");
+        assert_eq!(microcode.content, "MICROCODE_SIGNATURES=\"-S\"\n");
+
+        let fedora = engine.generate_configuration(&workstation(), "Fedora Linux 40").unwrap();
+        assert!(fedora
+            .configuration_files
+            .values()
+            .all(|f| !f.file_path.starts_with("/etc/portage")));
+    }
+
+    #[test]
+    fn test_rpm_ostree_layers_packages() {
+        let mapper = PackageMapper::new().unwrap();
+        let installations = mapper.map_packages(&workstation(), "Fedora Silverblue 40").unwrap();
+        let nvidia = installations.iter().find(|i| i.package_name == "nvidia-driver").unwrap();
+
+        assert_eq!(nvidia.installation_command, "rpm-ostree install --idempotent akmod-nvidia");
+        assert_eq!(nvidia.package_names(), ["akmod-nvidia"]);
+        // nvidia-xconfig needs the new deployment
+        assert!(nvidia.post_install_commands.is_empty());
+        assert_eq!(nvidia.notes[0], ImmutableKind::RpmOstree.driver_warning());
+        assert_eq!(nvidia.notes[1], "Reboot into the new deployment, then run: nvidia-xconfig");
+
+        let alsa = installations.iter().find(|i| i.package_name == "alsa-utils").unwrap();
+        assert!(alsa.notes[0].contains("toolbox or distrobox"));
+        let system = installations.iter().find(|i| i.package_name == "system-packages").unwrap();
+        assert!(system.notes.is_empty());
+    }
+
+    #[test]
+    fn test_transactional_update_and_steamos_commands() {
+        let mapper = PackageMapper::new().unwrap();
+
+        let microos = mapper.map_packages(&workstation(), "openSUSE MicroOS").unwrap();
+        assert_eq!(
+            command_for(&microos, "intel-microcode"),
+            "transactional-update --non-interactive pkg install ucode-intel"
+        );
+
+        // SteamOS packages are usable right away, so post-install steps stay
+        let steamos = mapper.map_packages(&workstation(), "SteamOS").unwrap();
+        let alsa = steamos.iter().find(|i| i.package_name == "alsa-utils").unwrap();
+        assert_eq!(
+            alsa.installation_command,
+            "steamos-readonly disable && pacman -S --needed alsa-utils"
+        );
+        assert_eq!(alsa.post_install_commands, ["alsactl init"]);
+        assert_eq!(alsa.package_names(), ["alsa-utils"]);
+    }
+
+    #[test]
+    fn test_firmware_packages_cover_new_distributions() {
+        let mapper = PackageMapper::new().unwrap();
+        for firmware in mapper.firmware_packages.values() {
+            for distribution in ["openSUSE Tumbleweed", "openSUSE Leap", "Alpine Linux"] {
+                assert!(
+                    firmware.package_mappings.contains_key(distribution),
+                    "{} has no {} package",
+                    firmware.firmware_name,
+                    distribution
+                );
+            }
+        }
+    }
+}
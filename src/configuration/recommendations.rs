@@ -1,10 +1,12 @@
-use std::collections::HashMap;
-use serde::{Deserialize, Serialize};
+use crate::configuration::audit::{DriftReport, TuningAuditor};
+use crate::configuration::dkms::DkmsManager;
+use crate::configuration::engine::ConfigurationEngineImpl;
 use crate::configuration::*;
-use crate::hardware::HardwareReport;
 use crate::errors::LxHwError;
-use crate::configuration::engine::ConfigurationEngineImpl;
-use crate::configuration::dkms::DkmsManager;
+use crate::hardware::{HardwareReport, MicrocodeStatus};
+use serde::{Deserialize, Serialize};
+use serde_yaml::{Mapping, Value as YamlValue};
+use std::collections::HashMap;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SystemRecommendations {
@@ -39,6 +41,7 @@ pub enum RecommendationCategory {
     ConfigurationFile,
     PerformanceOptimization,
     SecurityConfiguration,
+    FirmwareUpdate,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord)]
@@ -104,31 +107,50 @@ impl RecommendationEngine {
         })
     }
 
-    pub fn generate_recommendations(&self, hardware: &HardwareReport, target_distribution: &str) -> Result<SystemRecommendations, LxHwError> {
-        let configuration = self.config_engine.generate_configuration(hardware, target_distribution)?;
+    pub fn generate_recommendations(
+        &self,
+        hardware: &HardwareReport,
+        target_distribution: &str,
+    ) -> Result<SystemRecommendations, LxHwError> {
+        let configuration =
+            self.config_engine.generate_configuration(hardware, target_distribution)?;
         let dkms_modules = self.dkms_manager.identify_required_modules(hardware)?;
-        
+
         let mut recommendations = Vec::new();
         let mut warnings = Vec::new();
         let mut performance_notes = Vec::new();
 
         // Generate driver recommendations
-        recommendations.extend(self.generate_driver_recommendations(&configuration.driver_recommendations)?);
+        recommendations
+            .extend(self.generate_driver_recommendations(&configuration.driver_recommendations)?);
 
         // Generate kernel parameter recommendations
-        recommendations.extend(self.generate_kernel_parameter_recommendations(&configuration.kernel_parameters)?);
+        recommendations.extend(
+            self.generate_kernel_parameter_recommendations(&configuration.kernel_parameters)?,
+        );
 
         // Generate package installation recommendations
-        recommendations.extend(self.generate_package_recommendations(&configuration.package_installations)?);
+        recommendations
+            .extend(self.generate_package_recommendations(&configuration.package_installations)?);
+
+        // Generate CPU microcode update recommendations
+        recommendations
+            .extend(self.generate_microcode_recommendations(hardware, target_distribution)?);
+
+        // Generate device firmware update recommendations
+        recommendations.extend(self.generate_firmware_recommendations(hardware)?);
 
         // Generate DKMS module recommendations
         recommendations.extend(self.generate_dkms_recommendations(&dkms_modules)?);
 
         // Generate performance optimization recommendations
-        recommendations.extend(self.generate_performance_recommendations(&configuration.performance_optimizations)?);
+        recommendations.extend(
+            self.generate_performance_recommendations(&configuration.performance_optimizations)?,
+        );
 
         // Generate configuration file recommendations
-        let config_file_recommendations = self.generate_configuration_file_recommendations(&configuration.configuration_files)?;
+        let config_file_recommendations =
+            self.generate_configuration_file_recommendations(&configuration.configuration_files)?;
         recommendations.extend(config_file_recommendations);
 
         // Generate warnings and notes
@@ -139,10 +161,12 @@ impl RecommendationEngine {
         recommendations.sort_by(|a, b| b.priority.cmp(&a.priority));
 
         // Generate installation script
-        let installation_script = self.generate_installation_script(&recommendations, target_distribution)?;
+        let installation_script =
+            self.generate_installation_script(&recommendations, target_distribution)?;
 
         // Generate configuration files content
-        let configuration_files = self.generate_configuration_files_content(&configuration.configuration_files)?;
+        let configuration_files =
+            self.generate_configuration_files_content(&configuration.configuration_files)?;
 
         Ok(SystemRecommendations {
             system_id: configuration.system_id,
@@ -157,7 +181,32 @@ impl RecommendationEngine {
         })
     }
 
-    fn generate_driver_recommendations(&self, driver_recommendations: &[DriverRecommendation]) -> Result<Vec<Recommendation>, LxHwError> {
+    /// Ansible playbook applying the configuration recommended for a distribution
+    pub fn generate_ansible_playbook(
+        &self,
+        hardware: &HardwareReport,
+        target_distribution: &str,
+    ) -> Result<String, LxHwError> {
+        let configuration =
+            self.config_engine.generate_configuration(hardware, target_distribution)?;
+        ansible_playbook(&configuration)
+    }
+
+    /// Compare the running kernel cmdline and sysctls against the recommended kernel parameters
+    pub fn audit_tuning(
+        &self,
+        hardware: &HardwareReport,
+        target_distribution: &str,
+    ) -> Result<DriftReport, LxHwError> {
+        let configuration =
+            self.config_engine.generate_configuration(hardware, target_distribution)?;
+        TuningAuditor::new().audit(&configuration.kernel_parameters)
+    }
+
+    fn generate_driver_recommendations(
+        &self,
+        driver_recommendations: &[DriverRecommendation],
+    ) -> Result<Vec<Recommendation>, LxHwError> {
         let mut recommendations = Vec::new();
 
         for driver_rec in driver_recommendations {
@@ -172,20 +221,25 @@ impl RecommendationEngine {
             let implementation = match &driver_rec.driver_source {
                 DriverSource::KernelBuiltin => Implementation {
                     implementation_type: ImplementationType::CommandExecution,
-                    commands: vec![
-                        format!("modprobe {}", driver_rec.kernel_modules.join(" ")),
-                    ],
-                    files_to_modify: vec![
-                        FileModification {
-                            file_path: "/etc/modules".to_string(),
-                            modification_type: ModificationType::Append,
-                            content: driver_rec.kernel_modules.join("\n"),
-                            backup_required: true,
-                        },
-                    ],
-                    verification_commands: vec![
-                        format!("lsmod | grep -E '{}'", driver_rec.kernel_modules.join("|")),
-                    ],
+                    // Modules are listed dependencies first; `-a` loads every one
+                    commands: vec![format!("modprobe -a {}", driver_rec.kernel_modules.join(" "))],
+                    files_to_modify: vec![FileModification {
+                        file_path: "/etc/modules".to_string(),
+                        modification_type: ModificationType::Append,
+                        content: driver_rec.kernel_modules.join("\n"),
+                        backup_required: true,
+                    }],
+                    verification_commands: std::iter::once(format!(
+                        "lsmod | grep -E '{}'",
+                        driver_rec.kernel_modules.join("|")
+                    ))
+                    .chain(
+                        driver_rec
+                            .firmware_files
+                            .iter()
+                            .map(|file| format!("ls /lib/firmware/{}* >/dev/null", file)),
+                    )
+                    .collect(),
                 },
                 DriverSource::DistributionPackage { package_name } => Implementation {
                     implementation_type: ImplementationType::PackageInstallation,
@@ -194,9 +248,10 @@ impl RecommendationEngine {
                         format!("# Package: {}", package_name),
                     ],
                     files_to_modify: vec![],
-                    verification_commands: vec![
-                        format!("dpkg -l | grep {} || rpm -q {} || pacman -Q {}", package_name, package_name, package_name),
-                    ],
+                    verification_commands: vec![format!(
+                        "dpkg -l | grep {} || rpm -q {} || pacman -Q {}",
+                        package_name, package_name, package_name
+                    )],
                 },
                 DriverSource::Dkms { module_name } => Implementation {
                     implementation_type: ImplementationType::CommandExecution,
@@ -205,9 +260,7 @@ impl RecommendationEngine {
                         format!("# Module: {}", module_name),
                     ],
                     files_to_modify: vec![],
-                    verification_commands: vec![
-                        format!("dkms status | grep {}", module_name),
-                    ],
+                    verification_commands: vec![format!("dkms status | grep {}", module_name)],
                 },
                 DriverSource::ThirdParty { source_url } => Implementation {
                     implementation_type: ImplementationType::Combined,
@@ -223,26 +276,55 @@ impl RecommendationEngine {
             recommendations.push(Recommendation {
                 category: RecommendationCategory::DriverInstallation,
                 priority: priority_clone,
-                title: format!("Install {} driver for {}", driver_rec.recommended_driver, driver_rec.component_type),
+                title: format!(
+                    "Install {} driver for {}",
+                    driver_rec.recommended_driver, driver_rec.component_type
+                ),
                 description: format!(
-                    "Install the {} driver for your {} hardware. {}",
+                    "Install the {} driver for your {} hardware. {}{}",
                     driver_rec.recommended_driver,
                     driver_rec.component_type,
-                    driver_rec.compatibility_notes.as_ref().unwrap_or(&"".to_string())
+                    driver_rec.compatibility_notes.as_ref().unwrap_or(&"".to_string()),
+                    if driver_rec.firmware_files.is_empty() {
+                        String::new()
+                    } else {
+                        format!(
+                            " Requires firmware: {} (rebuild the initramfs after installing it).",
+                            driver_rec.firmware_files.join(", ")
+                        )
+                    }
                 ),
                 implementation,
-                expected_outcome: format!("Proper hardware support for {}", driver_rec.component_type),
+                expected_outcome: format!(
+                    "Proper hardware support for {}",
+                    driver_rec.component_type
+                ),
                 risk_assessment: RiskAssessment {
-                    risk_level: if priority == Priority::Critical { RiskLevel::Low } else { RiskLevel::Medium },
+                    risk_level: if priority == Priority::Critical {
+                        RiskLevel::Low
+                    } else {
+                        RiskLevel::Medium
+                    },
                     potential_issues: vec![
                         "Driver conflicts with existing drivers".to_string(),
                         "System instability if driver is incompatible".to_string(),
                     ],
                     rollback_instructions: vec![
-                        format!("modprobe -r {}", driver_rec.kernel_modules.join(" ")),
+                        format!(
+                            "modprobe -r {}",
+                            driver_rec
+                                .kernel_modules
+                                .iter()
+                                .rev()
+                                .cloned()
+                                .collect::<Vec<_>>()
+                                .join(" ")
+                        ),
                         "Reboot to previous kernel if issues persist".to_string(),
                     ],
-                    compatibility_notes: driver_rec.alternative_drivers.iter()
+                    compatibility_notes: driver_rec
+                        .alternative_drivers
+                        .iter()
                         .map(|alt| format!("Alternative: {}", alt))
                         .collect(),
                 },
@@ -252,21 +334,23 @@ impl RecommendationEngine {
         Ok(recommendations)
     }
 
-    fn generate_kernel_parameter_recommendations(&self, kernel_parameters: &[KernelParameter]) -> Result<Vec<Recommendation>, LxHwError> {
+    fn generate_kernel_parameter_recommendations(
+        &self,
+        kernel_parameters: &[KernelParameter],
+    ) -> Result<Vec<Recommendation>, LxHwError> {
         let mut recommendations = Vec::new();
 
         // Group parameters by hardware target for better organization
         let mut grouped_params: HashMap<String, Vec<&KernelParameter>> = HashMap::new();
-        
+
         for param in kernel_parameters {
-            let target = param.hardware_target.as_ref()
-                .unwrap_or(&"General".to_string())
-                .clone();
-            grouped_params.entry(target).or_insert_with(Vec::new).push(param);
+            let target = param.hardware_target.as_ref().unwrap_or(&"General".to_string()).clone();
+            grouped_params.entry(target).or_default().push(param);
         }
 
         for (target, params) in grouped_params {
-            let parameter_strings: Vec<String> = params.iter()
+            let parameter_strings: Vec<String> = params
+                .iter()
                 .map(|p| {
                     if let Some(value) = &p.value {
                         format!("{}={}", p.parameter, value)
@@ -323,7 +407,155 @@ impl RecommendationEngine {
         Ok(recommendations)
     }
 
-    fn generate_package_recommendations(&self, package_installations: &[PackageInstallation]) -> Result<Vec<Recommendation>, LxHwError> {
+    fn generate_microcode_recommendations(
+        &self,
+        hardware: &HardwareReport,
+        target_distribution: &str,
+    ) -> Result<Vec<Recommendation>, LxHwError> {
+        let Some(cpu) = &hardware.cpu else {
+            return Ok(vec![]);
+        };
+        let Some(microcode) = &cpu.microcode else {
+            return Ok(vec![]);
+        };
+
+        let intel = cpu.vendor.to_lowercase().contains("intel");
+        let distribution = target_distribution.to_lowercase();
+        let (package, command) = match (distribution.as_str(), intel) {
+            (d, true) if d.contains("ubuntu") || d.contains("debian") => {
+                ("intel-microcode", "sudo apt install --only-upgrade intel-microcode")
+            }
+            (d, false) if d.contains("ubuntu") || d.contains("debian") => {
+                ("amd64-microcode", "sudo apt install --only-upgrade amd64-microcode")
+            }
+            (d, true) if d.contains("fedora") || d.contains("rhel") || d.contains("centos") => {
+                ("microcode_ctl", "sudo dnf upgrade microcode_ctl")
+            }
+            (d, true) if d.contains("arch") || d.contains("manjaro") => {
+                ("intel-ucode", "sudo pacman -Syu intel-ucode")
+            }
+            (d, false) if d.contains("arch") || d.contains("manjaro") => {
+                ("amd-ucode", "sudo pacman -Syu amd-ucode")
+            }
+            (d, true) if d.contains("suse") => ("ucode-intel", "sudo zypper update ucode-intel"),
+            (d, false) if d.contains("suse") => ("ucode-amd", "sudo zypper update ucode-amd"),
+            (d, true) if d.contains("nixos") => {
+                ("hardware.cpu.intel.updateMicrocode", "sudo nixos-rebuild switch --upgrade")
+            }
+            (d, false) if d.contains("nixos") => {
+                ("hardware.cpu.amd.updateMicrocode", "sudo nixos-rebuild switch --upgrade")
+            }
+            _ => {
+                ("linux-firmware", "# Update the linux-firmware package with your package manager")
+            }
+        };
+
+        let (priority, title, description, commands) = match microcode.status {
+            MicrocodeStatus::RebootRequired => (
+                Priority::Medium,
+                "Reboot to load updated CPU microcode".to_string(),
+                format!(
+                    "Microcode revision {:#x} is installed but the CPU is still running {:#x}. It is loaded early at boot, so a reboot is needed to apply it",
+                    microcode.installed_revision.unwrap_or_default(), microcode.revision
+                ),
+                vec!["# Reboot the system to load the installed microcode".to_string()],
+            ),
+            MicrocodeStatus::Outdated => (
+                Priority::High,
+                format!("Update CPU microcode ({})", package),
+                format!(
+                    "The CPU is running microcode revision {:#x} but revision {:#x} has been published. Microcode updates fix CPU errata and security vulnerabilities",
+                    microcode.revision, microcode.latest_known_revision.unwrap_or_default()
+                ),
+                vec![command.to_string()],
+            ),
+            MicrocodeStatus::Current | MicrocodeStatus::Unknown => return Ok(vec![]),
+        };
+
+        Ok(vec![Recommendation {
+            category: RecommendationCategory::FirmwareUpdate,
+            priority,
+            title,
+            description,
+            implementation: Implementation {
+                implementation_type: ImplementationType::PackageInstallation,
+                commands,
+                files_to_modify: vec![],
+                verification_commands: vec!["grep -m1 microcode /proc/cpuinfo".to_string()],
+            },
+            expected_outcome: "CPU runs the newest available microcode after the next boot".to_string(),
+            risk_assessment: RiskAssessment {
+                risk_level: RiskLevel::Low,
+                potential_issues: vec![
+                    "Distribution packages may lag behind the newest published revision".to_string(),
+                ],
+                rollback_instructions: vec![
+                    "Boot with the 'dis_ucode_ldr' kernel parameter to skip early microcode loading".to_string(),
+                ],
+                compatibility_notes: vec![],
+            },
+        }])
+    }
+
+    fn generate_firmware_recommendations(
+        &self,
+        hardware: &HardwareReport,
+    ) -> Result<Vec<Recommendation>, LxHwError> {
+        let mut recommendations = Vec::new();
+
+        for device in &hardware.firmware_updates {
+            let Some(update_version) = &device.update_version else {
+                continue;
+            };
+            let priority = match device.update_urgency.as_deref() {
+                Some("critical") => Priority::Critical,
+                Some("high") => Priority::High,
+                Some("medium") => Priority::Medium,
+                _ => Priority::Low,
+            };
+
+            recommendations.push(Recommendation {
+                category: RecommendationCategory::FirmwareUpdate,
+                priority,
+                title: format!("Update {} firmware to {}", device.name, update_version),
+                description: format!(
+                    "{} is running firmware {} and version {} is available from the LVFS{}",
+                    device.name,
+                    device.current_version.as_deref().unwrap_or("unknown"),
+                    update_version,
+                    device.update_summary.as_ref().map(|s| format!(": {}", s)).unwrap_or_default()
+                ),
+                implementation: Implementation {
+                    implementation_type: ImplementationType::CommandExecution,
+                    commands: vec![
+                        "fwupdmgr refresh".to_string(),
+                        "fwupdmgr update".to_string(),
+                    ],
+                    files_to_modify: vec![],
+                    verification_commands: vec!["fwupdmgr get-updates".to_string()],
+                },
+                expected_outcome: "Device runs the newest vendor firmware, fixing known hardware issues".to_string(),
+                risk_assessment: RiskAssessment {
+                    risk_level: RiskLevel::Medium,
+                    potential_issues: vec![
+                        "Interrupted firmware updates can leave a device unusable; keep the system on AC power".to_string(),
+                        "System firmware updates are applied on the next reboot".to_string(),
+                    ],
+                    rollback_instructions: vec![
+                        "Reinstall the previous version with 'fwupdmgr downgrade' if the vendor allows it".to_string(),
+                    ],
+                    compatibility_notes: vec![],
+                },
+            });
+        }
+
+        Ok(recommendations)
+    }
+
+    fn generate_package_recommendations(
+        &self,
+        package_installations: &[PackageInstallation],
+    ) -> Result<Vec<Recommendation>, LxHwError> {
         let mut recommendations = Vec::new();
 
         for installation in package_installations {
@@ -344,8 +576,8 @@ impl RecommendationEngine {
                     ],
                     files_to_modify: vec![],
                     verification_commands: vec![
-                        format!("which {} || dpkg -l {} || rpm -q {} || pacman -Q {}", 
-                            installation.package_name, installation.package_name, 
+                        format!("which {} || dpkg -l {} || rpm -q {} || pacman -Q {}",
+                            installation.package_name, installation.package_name,
                             installation.package_name, installation.package_name)
                     ],
                 },
@@ -357,7 +589,7 @@ impl RecommendationEngine {
                         "Repository access issues".to_string(),
                     ],
                     rollback_instructions: vec![
-                        format!("Remove package if needed: apt remove {} || dnf remove {} || pacman -R {}", 
+                        format!("Remove package if needed: apt remove {} || dnf remove {} || pacman -R {}",
                             installation.package_name, installation.package_name, installation.package_name
                         ),
                     ],
@@ -369,7 +601,10 @@ impl RecommendationEngine {
         Ok(recommendations)
     }
 
-    fn generate_dkms_recommendations(&self, dkms_modules: &[DkmsModule]) -> Result<Vec<Recommendation>, LxHwError> {
+    fn generate_dkms_recommendations(
+        &self,
+        dkms_modules: &[DkmsModule],
+    ) -> Result<Vec<Recommendation>, LxHwError> {
         let mut recommendations = Vec::new();
 
         for module in dkms_modules {
@@ -409,11 +644,16 @@ impl RecommendationEngine {
         Ok(recommendations)
     }
 
-    fn generate_performance_recommendations(&self, optimizations: &[PerformanceOptimization]) -> Result<Vec<Recommendation>, LxHwError> {
+    fn generate_performance_recommendations(
+        &self,
+        optimizations: &[PerformanceOptimization],
+    ) -> Result<Vec<Recommendation>, LxHwError> {
         let mut recommendations = Vec::new();
 
         for optimization in optimizations {
-            let file_modifications = optimization.configuration_changes.iter()
+            let file_modifications = optimization
+                .configuration_changes
+                .iter()
                 .map(|change| FileModification {
                     file_path: change.file_path.clone(),
                     modification_type: ModificationType::ModifyParameter,
@@ -426,7 +666,10 @@ impl RecommendationEngine {
                 category: RecommendationCategory::PerformanceOptimization,
                 priority: Priority::Medium,
                 title: format!("Performance Optimization: {}", optimization.optimization_type),
-                description: format!("{} - {}", optimization.description, optimization.expected_improvement),
+                description: format!(
+                    "{} - {}",
+                    optimization.description, optimization.expected_improvement
+                ),
                 implementation: Implementation {
                     implementation_type: ImplementationType::FileModification,
                     commands: vec![],
@@ -440,11 +683,16 @@ impl RecommendationEngine {
                         "Performance changes may not suit all workloads".to_string(),
                         "Some optimizations may increase power consumption".to_string(),
                     ],
-                    rollback_instructions: optimization.configuration_changes.iter()
-                        .map(|change| format!("Restore {} to {}", 
-                            change.parameter, 
-                            change.old_value.as_ref().unwrap_or(&"default".to_string())
-                        ))
+                    rollback_instructions: optimization
+                        .configuration_changes
+                        .iter()
+                        .map(|change| {
+                            format!(
+                                "Restore {} to {}",
+                                change.parameter,
+                                change.old_value.as_ref().unwrap_or(&"default".to_string())
+                            )
+                        })
                         .collect(),
                     compatibility_notes: vec![],
                 },
@@ -454,7 +702,10 @@ impl RecommendationEngine {
         Ok(recommendations)
     }
 
-    fn generate_configuration_file_recommendations(&self, config_files: &HashMap<String, ConfigurationFile>) -> Result<Vec<Recommendation>, LxHwError> {
+    fn generate_configuration_file_recommendations(
+        &self,
+        config_files: &HashMap<String, ConfigurationFile>,
+    ) -> Result<Vec<Recommendation>, LxHwError> {
         let mut recommendations = Vec::new();
 
         for (name, config_file) in config_files {
@@ -462,23 +713,28 @@ impl RecommendationEngine {
                 category: RecommendationCategory::ConfigurationFile,
                 priority: Priority::Medium,
                 title: format!("Configure {}", name),
-                description: format!("Create or modify configuration file: {}", config_file.file_path),
+                description: format!(
+                    "Create or modify configuration file: {}",
+                    config_file.file_path
+                ),
                 implementation: Implementation {
                     implementation_type: ImplementationType::FileModification,
                     commands: if config_file.backup_original {
-                        vec![format!("cp {} {}.backup", config_file.file_path, config_file.file_path)]
+                        vec![format!(
+                            "cp {} {}.backup",
+                            config_file.file_path, config_file.file_path
+                        )]
                     } else {
                         vec![]
                     },
-                    files_to_modify: vec![
-                        FileModification {
-                            file_path: config_file.file_path.clone(),
-                            modification_type: ModificationType::Create,
-                            content: config_file.content.clone(),
-                            backup_required: config_file.backup_original,
-                        },
-                    ],
-                    verification_commands: if let Some(validation) = &config_file.validation_command {
+                    files_to_modify: vec![FileModification {
+                        file_path: config_file.file_path.clone(),
+                        modification_type: ModificationType::Create,
+                        content: config_file.content.clone(),
+                        backup_required: config_file.backup_original,
+                    }],
+                    verification_commands: if let Some(validation) = &config_file.validation_command
+                    {
                         vec![validation.clone()]
                     } else {
                         vec![format!("test -f {}", config_file.file_path)]
@@ -492,7 +748,10 @@ impl RecommendationEngine {
                         "Service restart required".to_string(),
                     ],
                     rollback_instructions: if config_file.backup_original {
-                        vec![format!("cp {}.backup {}", config_file.file_path, config_file.file_path)]
+                        vec![format!(
+                            "cp {}.backup {}",
+                            config_file.file_path, config_file.file_path
+                        )]
                     } else {
                         vec![format!("Remove or edit {}", config_file.file_path)]
                     },
@@ -504,21 +763,33 @@ impl RecommendationEngine {
         Ok(recommendations)
     }
 
-    fn analyze_potential_issues(&self, configuration: &Configuration, dkms_modules: &[DkmsModule]) -> Result<Vec<String>, LxHwError> {
+    fn analyze_potential_issues(
+        &self,
+        configuration: &Configuration,
+        dkms_modules: &[DkmsModule],
+    ) -> Result<Vec<String>, LxHwError> {
         let mut warnings = Vec::new();
 
         // Check for NVIDIA-specific warnings
         for driver_rec in &configuration.driver_recommendations {
             if driver_rec.recommended_driver.contains("nvidia") {
                 warnings.push("NVIDIA proprietary drivers may conflict with nouveau. Ensure nouveau is blacklisted.".to_string());
-                warnings.push("Secure boot may need to be disabled for NVIDIA drivers to work properly.".to_string());
+                warnings.push(
+                    "Secure boot may need to be disabled for NVIDIA drivers to work properly."
+                        .to_string(),
+                );
             }
         }
 
         // Check for DKMS warnings
         if !dkms_modules.is_empty() {
-            warnings.push("DKMS modules require kernel headers and build tools. Ensure these are installed.".to_string());
-            warnings.push("DKMS modules may need manual rebuilding after major kernel updates.".to_string());
+            warnings.push(
+                "DKMS modules require kernel headers and build tools. Ensure these are installed."
+                    .to_string(),
+            );
+            warnings.push(
+                "DKMS modules may need manual rebuilding after major kernel updates.".to_string(),
+            );
         }
 
         // Check for compatibility score warnings
@@ -532,16 +803,24 @@ impl RecommendationEngine {
         Ok(warnings)
     }
 
-    fn generate_performance_notes(&self, configuration: &Configuration) -> Result<Vec<String>, LxHwError> {
+    fn generate_performance_notes(
+        &self,
+        configuration: &Configuration,
+    ) -> Result<Vec<String>, LxHwError> {
         let mut notes = Vec::new();
 
         // CPU performance notes
         if let Some(cpu) = &configuration.hardware_profile.cpu {
             if cpu.power_management.turbo_boost {
-                notes.push("Turbo boost is enabled for better single-threaded performance".to_string());
+                notes.push(
+                    "Turbo boost is enabled for better single-threaded performance".to_string(),
+                );
             }
-            
-            notes.push(format!("Using {} CPU governor for optimal performance", cpu.power_management.cpu_governor));
+
+            notes.push(format!(
+                "Using {} CPU governor for optimal performance",
+                cpu.power_management.cpu_governor
+            ));
         }
 
         // GPU performance notes
@@ -552,16 +831,23 @@ impl RecommendationEngine {
         // Storage performance notes
         for storage in &configuration.hardware_profile.storage {
             if storage.device_type == "ssd" || storage.device_type == "nvme" {
-                notes.push(format!("{} optimizations applied for better performance", storage.device_type.to_uppercase()));
+                notes.push(format!(
+                    "{} optimizations applied for better performance",
+                    storage.device_type.to_uppercase()
+                ));
             }
         }
 
         Ok(notes)
     }
 
-    fn generate_installation_script(&self, recommendations: &[Recommendation], target_distribution: &str) -> Result<String, LxHwError> {
+    fn generate_installation_script(
+        &self,
+        recommendations: &[Recommendation],
+        target_distribution: &str,
+    ) -> Result<String, LxHwError> {
         let mut script = String::new();
-        
+
         script.push_str("#!/bin/bash\n");
         script.push_str("# Hardware Configuration Installation Script\n");
         script.push_str(&format!("# Generated for: {}\n", target_distribution));
@@ -572,14 +858,15 @@ impl RecommendationEngine {
 
         // Group recommendations by category for logical execution order
         let mut categorized: HashMap<RecommendationCategory, Vec<&Recommendation>> = HashMap::new();
-        
+
         for rec in recommendations {
-            categorized.entry(rec.category.clone()).or_insert_with(Vec::new).push(rec);
+            categorized.entry(rec.category.clone()).or_default().push(rec);
         }
 
         // Execute in logical order
         let execution_order = vec![
             RecommendationCategory::PackageInstallation,
+            RecommendationCategory::FirmwareUpdate,
             RecommendationCategory::DkmsModule,
             RecommendationCategory::DriverInstallation,
             RecommendationCategory::ConfigurationFile,
@@ -588,22 +875,15 @@ impl RecommendationEngine {
         ];
 
         for category in execution_order {
-            if let Some(recommendations) = categorized.get(&category) {
-                script.push_str(&format!("# {:?} recommendations\n", category));
-                
-                for rec in recommendations {
-                    script.push_str(&format!("echo \"Implementing: {}\"\n", rec.title));
-                    
-                    for command in &rec.implementation.commands {
-                        if !command.starts_with('#') {
-                            script.push_str(&format!("{}\n", command));
-                        } else {
-                            script.push_str(&format!("{}\n", command));
-                        }
-                    }
-                    
-                    script.push_str("\n");
+            let Some(recommendations) = categorized.get(&category) else { continue };
+            script.push_str(&format!("# {:?} recommendations\n", category));
+
+            for rec in recommendations {
+                script.push_str(&format!("echo \"Implementing: {}\"\n", rec.title));
+                for command in &rec.implementation.commands {
+                    script.push_str(&format!("{}\n", command));
                 }
+                script.push('\n');
             }
         }
 
@@ -613,13 +893,390 @@ impl RecommendationEngine {
         Ok(script)
     }
 
-    fn generate_configuration_files_content(&self, config_files: &HashMap<String, ConfigurationFile>) -> Result<HashMap<String, String>, LxHwError> {
+    fn generate_configuration_files_content(
+        &self,
+        config_files: &HashMap<String, ConfigurationFile>,
+    ) -> Result<HashMap<String, String>, LxHwError> {
         let mut content_map = HashMap::new();
-        
+
         for (name, config_file) in config_files {
-            content_map.insert(format!("{} ({})", name, config_file.file_path), config_file.content.clone());
+            content_map.insert(
+                format!("{} ({})", name, config_file.file_path),
+                config_file.content.clone(),
+            );
         }
-        
+
         Ok(content_map)
     }
-}
\ No newline at end of file
+}
+
+/// File listing the recommended modules for systemd-modules-load
+const ANSIBLE_MODULES_FILE: &str = "/etc/modules-load.d/lx-hw-detect.conf";
+
+#[derive(Debug, Serialize)]
+struct AnsiblePlay {
+    name: String,
+    hosts: String,
+    #[serde(rename = "become")]
+    become_root: bool,
+    tasks: Vec<AnsibleTask>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    handlers: Vec<AnsibleTask>,
+}
+
+#[derive(Debug, Serialize)]
+struct AnsibleTask {
+    name: String,
+    #[serde(flatten)]
+    module: Mapping,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    register: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    when: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    changed_when: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    notify: Option<String>,
+}
+
+impl AnsibleTask {
+    fn new(name: impl Into<String>, module: &str, args: Vec<(&str, YamlValue)>) -> Self {
+        let args: Mapping =
+            args.into_iter().map(|(key, value)| (YamlValue::from(key), value)).collect();
+        let mut module_call = Mapping::new();
+        module_call.insert(YamlValue::from(module), YamlValue::Mapping(args));
+        Self {
+            name: name.into(),
+            module: module_call,
+            register: None,
+            when: None,
+            changed_when: None,
+            notify: None,
+        }
+    }
+}
+
+/// Render a configuration as an Ansible playbook for the hosts running its
+/// target distribution
+///
+/// Packages are installed through the generic package module, kernel modules
+/// are listed for systemd-modules-load, kernel parameters are appended to the
+/// GRUB command line (through grubby on Fedora and RHEL derivatives) and
+/// configuration files are copied with a backup of the original.
+pub fn ansible_playbook(configuration: &Configuration) -> Result<String, LxHwError> {
+    let distribution = configuration.target_distribution.to_lowercase();
+    let mut tasks = Vec::new();
+    let mut handlers = Vec::new();
+
+    // Packages, with their post-install steps run only after a change
+    let mut packages: Vec<YamlValue> = Vec::new();
+    for name in configuration.package_installations.iter().flat_map(|p| p.package_names()) {
+        let name = YamlValue::from(name);
+        if !packages.contains(&name) {
+            packages.push(name);
+        }
+    }
+    if !packages.is_empty() {
+        let mut install = AnsibleTask::new(
+            "Install hardware support packages",
+            "ansible.builtin.package",
+            vec![("name", YamlValue::Sequence(packages)), ("state", YamlValue::from("present"))],
+        );
+        install.register = Some("lx_hw_packages".to_string());
+        tasks.push(install);
+    }
+    for installation in &configuration.package_installations {
+        for command in &installation.post_install_commands {
+            let mut task = AnsibleTask::new(
+                format!("Set up {}", installation.package_name),
+                "ansible.builtin.shell",
+                vec![("cmd", YamlValue::from(command.clone()))],
+            );
+            task.when = Some("lx_hw_packages is changed".to_string());
+            tasks.push(task);
+        }
+    }
+
+    // Kernel modules, loaded now and on every boot
+    let mut modules: Vec<String> = Vec::new();
+    for module in configuration.driver_recommendations.iter().flat_map(|r| &r.kernel_modules) {
+        if !modules.contains(module) {
+            modules.push(module.clone());
+        }
+    }
+    if !modules.is_empty() {
+        tasks.push(AnsibleTask::new(
+            "Load recommended kernel modules at boot",
+            "ansible.builtin.copy",
+            vec![
+                ("dest", YamlValue::from(ANSIBLE_MODULES_FILE)),
+                (
+                    "content",
+                    YamlValue::from(format!(
+                        "# Managed by Ansible from lx-hw-detect recommendations\n{}\n",
+                        modules.join("\n")
+                    )),
+                ),
+                ("mode", YamlValue::from("0644")),
+            ],
+        ));
+    }
+
+    // Kernel parameters on the boot command line
+    let mut parameters: Vec<&KernelParameter> = configuration.kernel_parameters.iter().collect();
+    parameters.sort_by_key(|p| p.boot_order);
+    let mut cmdline: Vec<String> = Vec::new();
+    for parameter in parameters {
+        let argument = match &parameter.value {
+            Some(value) => format!("{}={}", parameter.parameter, value),
+            None => parameter.parameter.clone(),
+        };
+        if !cmdline.contains(&argument) {
+            cmdline.push(argument);
+        }
+    }
+    if !cmdline.is_empty() {
+        let cmdline = cmdline.join(" ");
+        let uses_grubby = ["fedora", "rhel", "red hat", "centos", "rocky", "alma"]
+            .iter()
+            .any(|d| distribution.contains(d));
+        if uses_grubby {
+            tasks.push(AnsibleTask::new(
+                "Add kernel parameters to all boot entries",
+                "ansible.builtin.command",
+                vec![(
+                    "cmd",
+                    YamlValue::from(format!("grubby --update-kernel=ALL --args=\"{}\"", cmdline)),
+                )],
+            ));
+        } else {
+            let mut task = AnsibleTask::new(
+                "Add kernel parameters to the GRUB command line",
+                "ansible.builtin.lineinfile",
+                vec![
+                    ("path", YamlValue::from("/etc/default/grub")),
+                    (
+                        "line",
+                        YamlValue::from(format!(
+                            "GRUB_CMDLINE_LINUX_DEFAULT=\"$GRUB_CMDLINE_LINUX_DEFAULT {}\"",
+                            cmdline
+                        )),
+                    ),
+                    ("backup", YamlValue::from(true)),
+                ],
+            );
+            task.notify = Some("Regenerate GRUB configuration".to_string());
+            tasks.push(task);
+
+            let update =
+                if ["debian", "ubuntu", "mint", "pop"].iter().any(|d| distribution.contains(d)) {
+                    "update-grub"
+                } else if distribution.contains("suse") {
+                    "grub2-mkconfig -o /boot/grub2/grub.cfg"
+                } else {
+                    "grub-mkconfig -o /boot/grub/grub.cfg"
+                };
+            handlers.push(AnsibleTask::new(
+                "Regenerate GRUB configuration",
+                "ansible.builtin.command",
+                vec![("cmd", YamlValue::from(update))],
+            ));
+        }
+    }
+
+    // Configuration files, checked after writing when they can be validated
+    let mut files: Vec<&ConfigurationFile> = configuration.configuration_files.values().collect();
+    files.sort_by(|a, b| a.file_path.cmp(&b.file_path));
+    for file in files {
+        tasks.push(AnsibleTask::new(
+            format!("Write {}", file.file_path),
+            "ansible.builtin.copy",
+            vec![
+                ("dest", YamlValue::from(file.file_path.clone())),
+                ("content", YamlValue::from(file.content.clone())),
+                ("mode", YamlValue::from(file.file_permissions.clone())),
+                ("backup", YamlValue::from(file.backup_original)),
+            ],
+        ));
+        if let Some(command) = &file.validation_command {
+            let mut task = AnsibleTask::new(
+                format!("Validate {}", file.file_path),
+                "ansible.builtin.command",
+                vec![("cmd", YamlValue::from(command.clone()))],
+            );
+            task.changed_when = Some(false);
+            tasks.push(task);
+        }
+    }
+
+    let play = AnsiblePlay {
+        name: format!(
+            "Apply lx-hw-detect hardware configuration for {}",
+            configuration.target_distribution
+        ),
+        hosts: "all".to_string(),
+        become_root: true,
+        tasks,
+        handlers,
+    };
+
+    let mut playbook = String::new();
+    playbook.push_str("# Hardware configuration playbook\n");
+    playbook.push_str(&format!(
+        "# Generated for: {} (kernel {})\n",
+        configuration.target_distribution, configuration.kernel_version
+    ));
+    playbook.push_str("# Generated by: lx-hw-db Configuration Engine\n");
+    playbook.push_str("# Run with: ansible-playbook -i <inventory> <playbook>; reboot afterwards for kernel parameters\n");
+    playbook.push_str(&serde_yaml::to_string(&vec![play])?);
+    Ok(playbook)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn workstation() -> HardwareReport {
+        serde_json::from_str(include_str!("../../tests/fixtures/configuration/workstation.json"))
+            .unwrap()
+    }
+
+    fn tasks(playbook: &str) -> Vec<YamlValue> {
+        let plays: Vec<YamlValue> = serde_yaml::from_str(playbook).unwrap();
+        plays[0]["tasks"].as_sequence().unwrap().clone()
+    }
+
+    fn microcode_recommendations(
+        report: &HardwareReport,
+        distribution: &str,
+    ) -> Vec<Recommendation> {
+        RecommendationEngine::new()
+            .unwrap()
+            .generate_recommendations(report, distribution)
+            .unwrap()
+            .recommendations
+            .into_iter()
+            .filter(|r| {
+                r.category == RecommendationCategory::FirmwareUpdate
+                    && r.title.contains("microcode")
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_outdated_microcode_recommends_distribution_package() {
+        let fedora = microcode_recommendations(&workstation(), "Fedora Linux 40");
+        assert_eq!(fedora.len(), 1);
+        assert_eq!(fedora[0].title, "Update CPU microcode (microcode_ctl)");
+        assert_eq!(fedora[0].priority, Priority::High);
+        assert_eq!(fedora[0].implementation.commands, ["sudo dnf upgrade microcode_ctl"]);
+        assert!(fedora[0].description.contains("revision 0x2c but revision 0x35"));
+
+        let debian = microcode_recommendations(&workstation(), "Debian 12");
+        assert_eq!(
+            debian[0].implementation.commands,
+            ["sudo apt install --only-upgrade intel-microcode"]
+        );
+    }
+
+    #[test]
+    fn test_microcode_reboot_and_current_status() {
+        let mut report = workstation();
+        let microcode = report.cpu.as_mut().unwrap().microcode.as_mut().unwrap();
+        microcode.status = MicrocodeStatus::RebootRequired;
+        microcode.installed_revision = Some(0x35);
+        let reboot = microcode_recommendations(&report, "Fedora Linux 40");
+        assert_eq!(reboot[0].title, "Reboot to load updated CPU microcode");
+        assert!(reboot[0].description.contains("Microcode revision 0x35 is installed"));
+
+        report.cpu.as_mut().unwrap().microcode.as_mut().unwrap().status = MicrocodeStatus::Current;
+        assert!(microcode_recommendations(&report, "Fedora Linux 40").is_empty());
+    }
+
+    #[test]
+    fn test_lvfs_firmware_updates_become_recommendations() {
+        let mut report = workstation();
+        report.firmware_updates.push(crate::hardware::FirmwareDevice {
+            name: "System Firmware".to_string(),
+            vendor: None,
+            plugin: Some("uefi_capsule".to_string()),
+            current_version: Some("1.20".to_string()),
+            update_version: None,
+            update_summary: None,
+            update_urgency: None,
+        });
+        let recommendations = RecommendationEngine::new()
+            .unwrap()
+            .generate_recommendations(&report, "Fedora Linux 40")
+            .unwrap()
+            .recommendations;
+        let firmware: Vec<&Recommendation> =
+            recommendations.iter().filter(|r| r.title.ends_with("firmware to 5B2QGXA9")).collect();
+
+        // Devices without a pending update are not recommended
+        assert!(!recommendations.iter().any(|r| r.title.contains("System Firmware")));
+        assert_eq!(firmware.len(), 1);
+        assert_eq!(firmware[0].title, "Update Samsung SSD 980 PRO 1TB firmware to 5B2QGXA9");
+        assert_eq!(firmware[0].priority, Priority::High);
+        assert_eq!(
+            firmware[0].description,
+            "Samsung SSD 980 PRO 1TB is running firmware 5B2QGXA7 and version 5B2QGXA9 is \
+             available from the LVFS: Fixes a rare power loss issue"
+        );
+        assert_eq!(firmware[0].implementation.commands, ["fwupdmgr refresh", "fwupdmgr update"]);
+    }
+
+    #[test]
+    fn test_ansible_playbook_uses_grubby_on_fedora() {
+        let engine = RecommendationEngine::new().unwrap();
+        let playbook = engine.generate_ansible_playbook(&workstation(), "Fedora Linux 40").unwrap();
+        let tasks = tasks(&playbook);
+
+        let install = &tasks[0]["ansible.builtin.package"];
+        let packages = install["name"].as_sequence().unwrap();
+        assert!(packages.contains(&YamlValue::from("akmod-nvidia")));
+        assert_eq!(tasks[0]["register"], YamlValue::from("lx_hw_packages"));
+
+        let grubby =
+            tasks.iter().find_map(|task| task["ansible.builtin.command"]["cmd"].as_str()).unwrap();
+        assert!(grubby.starts_with("grubby --update-kernel=ALL --args=\""));
+        assert!(grubby.contains("nouveau.modeset=0"));
+        assert!(!playbook.contains("handlers:"));
+    }
+
+    #[test]
+    fn test_ansible_playbook_regenerates_grub_on_debian_derivatives() {
+        let engine = RecommendationEngine::new().unwrap();
+        let playbook = engine.generate_ansible_playbook(&workstation(), "Ubuntu 24.04").unwrap();
+        let plays: Vec<YamlValue> = serde_yaml::from_str(&playbook).unwrap();
+
+        let grub = tasks(&playbook)
+            .into_iter()
+            .find(|task| task.get("ansible.builtin.lineinfile").is_some())
+            .unwrap();
+        assert_eq!(
+            grub["ansible.builtin.lineinfile"]["path"],
+            YamlValue::from("/etc/default/grub")
+        );
+        assert_eq!(grub["notify"], YamlValue::from("Regenerate GRUB configuration"));
+
+        let handlers = plays[0]["handlers"].as_sequence().unwrap();
+        assert_eq!(handlers[0]["name"], YamlValue::from("Regenerate GRUB configuration"));
+        assert_eq!(handlers[0]["ansible.builtin.command"]["cmd"], YamlValue::from("update-grub"));
+    }
+
+    #[test]
+    fn test_ansible_playbook_post_install_steps_run_only_after_a_change() {
+        let engine = RecommendationEngine::new().unwrap();
+        let playbook = engine.generate_ansible_playbook(&workstation(), "Fedora Linux 40").unwrap();
+
+        let setup: Vec<YamlValue> = tasks(&playbook)
+            .into_iter()
+            .filter(|task| task["ansible.builtin.shell"].is_mapping())
+            .collect();
+        assert!(!setup.is_empty());
+        for task in setup {
+            assert_eq!(task["when"], YamlValue::from("lx_hw_packages is changed"));
+        }
+    }
+}
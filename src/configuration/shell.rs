@@ -0,0 +1,407 @@
+//! Bash install script rendered from a generated configuration
+//!
+//! The script records every change it makes under a state directory: files
+//! it replaced are copied there first (when the configuration asks for a
+//! backup), files and packages it added are listed, and so is the way the
+//! kernel parameters reached the boot command line. Running it again with
+//! `--undo` uses that record to put the system back.
+
+use crate::configuration::*;
+use std::fmt::Write as _;
+
+/// File listing the recommended modules for systemd-modules-load
+const MODULES_FILE: &str = "/etc/modules-load.d/lx-hw-detect.conf";
+
+/// Helpers shared by every generated script
+const SCRIPT_FUNCTIONS: &str = r##"set -euo pipefail
+
+STATE_DIR=/var/lib/lx-hw-detect/setup
+BACKUP_DIR="$STATE_DIR/backup"
+MARKER="# added by lx-hw-detect"
+
+if [ "$(id -u)" -ne 0 ]; then
+    echo "This script must be run as root" >&2
+    exit 1
+fi
+
+PACKAGE_MANAGER=""
+# Image-based systems layer packages; their base package managers cannot
+# change the read-only system
+if [ -e /run/ostree-booted ] && command -v rpm-ostree >/dev/null 2>&1; then
+    PACKAGE_MANAGER=rpm-ostree
+elif command -v transactional-update >/dev/null 2>&1 && findmnt -n -o OPTIONS / | grep -qw ro; then
+    PACKAGE_MANAGER=transactional-update
+fi
+for manager in apt-get dnf zypper pacman apk; do
+    if [ -n "$PACKAGE_MANAGER" ]; then
+        break
+    fi
+    if command -v "$manager" >/dev/null 2>&1; then
+        PACKAGE_MANAGER="$manager"
+    fi
+done
+
+package_installed() {
+    case "$PACKAGE_MANAGER" in
+        apt-get) dpkg-query -W -f='${Status}' "$1" 2>/dev/null | grep -q "install ok installed" ;;
+        dnf|zypper|rpm-ostree|transactional-update) rpm -q "$1" >/dev/null 2>&1 ;;
+        pacman) pacman -Q "$1" >/dev/null 2>&1 ;;
+        apk) apk info -e "$1" >/dev/null 2>&1 ;;
+        *) return 0 ;;
+    esac
+}
+
+# Install a package unless present; fails (returns 1) when nothing was installed
+install_package() {
+    if [ -z "$PACKAGE_MANAGER" ]; then
+        echo "No supported package manager found; install $1 manually" >&2
+        return 1
+    fi
+    if package_installed "$1"; then
+        return 1
+    fi
+    echo "Installing $1"
+    case "$PACKAGE_MANAGER" in
+        apt-get) DEBIAN_FRONTEND=noninteractive apt-get install -y "$1" ;;
+        dnf) dnf install -y "$1" ;;
+        zypper) zypper --non-interactive install "$1" ;;
+        pacman) pacman -S --noconfirm --needed "$1" ;;
+        apk) apk add "$1" ;;
+        rpm-ostree) rpm-ostree install --idempotent "$1" ;;
+        transactional-update) transactional-update --non-interactive pkg install "$1" ;;
+    esac || { echo "Failed to install $1" >&2; exit 1; }
+    echo "$1" >> "$STATE_DIR/packages"
+}
+
+remove_package() {
+    echo "Removing $1"
+    case "$PACKAGE_MANAGER" in
+        apt-get) DEBIAN_FRONTEND=noninteractive apt-get remove -y "$1" ;;
+        dnf) dnf remove -y "$1" ;;
+        zypper) zypper --non-interactive remove "$1" ;;
+        pacman) pacman -R --noconfirm "$1" ;;
+        apk) apk del "$1" ;;
+        rpm-ostree) rpm-ostree uninstall "$1" ;;
+        transactional-update) transactional-update --non-interactive pkg remove "$1" ;;
+    esac || echo "Could not remove $1" >&2
+}
+
+# Keep the original of a file once, or note that the file is new
+backup_file() {
+    if [ -e "$1" ]; then
+        if [ ! -e "$BACKUP_DIR$1" ]; then
+            mkdir -p "$BACKUP_DIR$(dirname "$1")"
+            cp -a "$1" "$BACKUP_DIR$1"
+        fi
+    elif ! grep -qxF "$1" "$STATE_DIR/created" 2>/dev/null; then
+        echo "$1" >> "$STATE_DIR/created"
+    fi
+}
+
+# write_file <path> <mode> <backup: yes|no>, content on stdin
+write_file() {
+    if [ "$3" = yes ]; then
+        backup_file "$1"
+    elif [ ! -e "$1" ]; then
+        echo "$1" >> "$STATE_DIR/created"
+    fi
+    echo "Writing $1"
+    mkdir -p "$(dirname "$1")"
+    cat > "$1"
+    chmod "$2" "$1"
+}
+
+regenerate_grub() {
+    if command -v update-grub >/dev/null 2>&1; then
+        update-grub
+    elif command -v grub2-mkconfig >/dev/null 2>&1; then
+        grub2-mkconfig -o /boot/grub2/grub.cfg
+    elif command -v grub-mkconfig >/dev/null 2>&1; then
+        grub-mkconfig -o /boot/grub/grub.cfg
+    fi
+}
+
+add_kernel_parameters() {
+    echo "Adding kernel parameters: $1"
+    if command -v grubby >/dev/null 2>&1; then
+        grubby --update-kernel=ALL --args="$1"
+        echo grubby > "$STATE_DIR/bootloader"
+    elif [ -f /etc/default/grub ]; then
+        backup_file /etc/default/grub
+        if ! grep -qF "$MARKER" /etc/default/grub; then
+            printf 'GRUB_CMDLINE_LINUX_DEFAULT="$GRUB_CMDLINE_LINUX_DEFAULT %s" %s\n' "$1" "$MARKER" >> /etc/default/grub
+        fi
+        regenerate_grub
+        echo grub > "$STATE_DIR/bootloader"
+    elif [ -f /etc/kernel/cmdline ]; then
+        backup_file /etc/kernel/cmdline
+        if ! grep -qF -- "$1" /etc/kernel/cmdline; then
+            printf '%s %s\n' "$(cat /etc/kernel/cmdline)" "$1" > /etc/kernel/cmdline.new
+            mv /etc/kernel/cmdline.new /etc/kernel/cmdline
+        fi
+        echo "Boot entries pick up /etc/kernel/cmdline when kernel-install next runs"
+        echo cmdline > "$STATE_DIR/bootloader"
+    else
+        echo "No supported bootloader configuration found; add to the kernel command line manually: $1" >&2
+        return
+    fi
+    echo "$1" > "$STATE_DIR/kernel-parameters"
+}
+
+undo() {
+    if [ ! -d "$STATE_DIR" ]; then
+        echo "Nothing to undo"
+        return
+    fi
+    local bootloader=""
+    if [ -f "$STATE_DIR/bootloader" ]; then
+        bootloader=$(cat "$STATE_DIR/bootloader")
+    fi
+    if [ "$bootloader" = grubby ]; then
+        grubby --update-kernel=ALL --remove-args="$(cat "$STATE_DIR/kernel-parameters")"
+    fi
+    if [ -d "$BACKUP_DIR" ]; then
+        echo "Restoring original files"
+        cp -a "$BACKUP_DIR/." /
+    fi
+    if [ -f "$STATE_DIR/created" ]; then
+        mapfile -t created < "$STATE_DIR/created"
+        for file in "${created[@]}"; do
+            echo "Removing $file"
+            rm -f "$file"
+        done
+    fi
+    if [ -f "$STATE_DIR/packages" ]; then
+        mapfile -t packages < "$STATE_DIR/packages"
+        for package in "${packages[@]}"; do
+            remove_package "$package"
+        done
+    fi
+    if [ "$bootloader" = grub ]; then
+        regenerate_grub
+    fi
+    rm -rf "$STATE_DIR"
+    echo "Configuration undone. Reboot to return to the previous kernel command line."
+}
+
+case "${1:-}" in
+    --undo)
+        undo
+        exit 0
+        ;;
+    "") ;;
+    *)
+        echo "Usage: $0 [--undo]" >&2
+        exit 2
+        ;;
+esac
+
+mkdir -p "$BACKUP_DIR"
+"##;
+
+impl Configuration {
+    /// Bash script applying this configuration, with an `--undo` mode that
+    /// reverts it
+    pub fn to_shell_script(&self) -> String {
+        let mut script = String::new();
+        script.push_str("#!/bin/bash\n");
+        script.push_str("# Hardware Configuration Installation Script\n");
+        let _ = writeln!(
+            script,
+            "# Generated for: {} (kernel {})",
+            self.target_distribution, self.kernel_version
+        );
+        script.push_str("# Generated by: lx-hw-db Configuration Engine\n");
+        script.push_str("#\n# Usage: sudo ./lx-hw-setup.sh           apply the configuration\n");
+        script.push_str(
+            "#        sudo ./lx-hw-setup.sh --undo    revert the changes this script made\n\n",
+        );
+        script.push_str(SCRIPT_FUNCTIONS);
+
+        self.write_package_steps(&mut script);
+        self.write_file_steps(&mut script);
+        self.write_kernel_parameter_step(&mut script);
+
+        script.push_str("\necho \"Hardware configuration installation completed!\"\n");
+        script.push_str("echo \"Please reboot your system to ensure all changes take effect.\"\n");
+        script
+    }
+
+    fn write_package_steps(&self, script: &mut String) {
+        for installation in &self.package_installations {
+            let names = installation.package_names();
+            let _ = writeln!(script, "\n# {}", installation.package_description);
+            for note in &installation.notes {
+                let _ = writeln!(script, "# Note: {}", note);
+            }
+            if installation.post_install_commands.is_empty() {
+                for name in names {
+                    let _ = writeln!(script, "install_package {} || true", shell_quote(&name));
+                }
+                continue;
+            }
+            // Post-install steps only run when something was installed
+            script.push_str("installed=no\n");
+            for name in names {
+                let _ = writeln!(script, "install_package {} && installed=yes", shell_quote(&name));
+            }
+            script.push_str("if [ \"$installed\" = yes ]; then\n");
+            for command in &installation.post_install_commands {
+                let _ = writeln!(script, "    {}", command);
+            }
+            script.push_str("fi\n");
+        }
+    }
+
+    fn write_file_steps(&self, script: &mut String) {
+        let mut modules: Vec<&String> = Vec::new();
+        for module in self.driver_recommendations.iter().flat_map(|r| &r.kernel_modules) {
+            if !modules.contains(&module) {
+                modules.push(module);
+            }
+        }
+        if !modules.is_empty() {
+            script.push_str("\n# Load the recommended kernel modules at boot\n");
+            let content = modules.iter().map(|m| m.as_str()).collect::<Vec<_>>().join("\n");
+            write_file(script, MODULES_FILE, "0644", false, &content);
+        }
+
+        let mut files: Vec<&ConfigurationFile> = self.configuration_files.values().collect();
+        files.sort_by(|a, b| a.file_path.cmp(&b.file_path));
+        for file in files {
+            script.push('\n');
+            write_file(
+                script,
+                &file.file_path,
+                &file.file_permissions,
+                file.backup_original,
+                &file.content,
+            );
+            if let Some(command) = &file.validation_command {
+                let _ = writeln!(script, "{}", command);
+            }
+        }
+    }
+
+    fn write_kernel_parameter_step(&self, script: &mut String) {
+        let mut parameters: Vec<&KernelParameter> = self.kernel_parameters.iter().collect();
+        parameters.sort_by_key(|p| p.boot_order);
+        let mut cmdline: Vec<String> = Vec::new();
+        for parameter in parameters {
+            let argument = match &parameter.value {
+                Some(value) => format!("{}={}", parameter.parameter, value),
+                None => parameter.parameter.clone(),
+            };
+            if !cmdline.contains(&argument) {
+                cmdline.push(argument);
+            }
+        }
+        if !cmdline.is_empty() {
+            script.push_str("\n# Kernel parameters\n");
+            let _ = writeln!(script, "add_kernel_parameters {}", shell_quote(&cmdline.join(" ")));
+        }
+    }
+}
+
+/// Emit a write_file call with the content in a quoted here-document
+fn write_file(script: &mut String, path: &str, mode: &str, backup: bool, content: &str) {
+    let mut delimiter = String::from("LX_HW_EOF");
+    while content.lines().any(|line| line == delimiter) {
+        delimiter.push('_');
+    }
+    let backup = if backup { "yes" } else { "no" };
+    let _ = writeln!(
+        script,
+        "write_file {} {} {} <<'{}'",
+        shell_quote(path),
+        shell_quote(mode),
+        backup,
+        delimiter
+    );
+    script.push_str(content);
+    if !content.ends_with('\n') {
+        script.push('\n');
+    }
+    let _ = writeln!(script, "{}", delimiter);
+}
+
+/// Quote a value as a single shell word
+fn shell_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "'\\''"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::configuration::engine::ConfigurationEngineImpl;
+    use crate::hardware::HardwareReport;
+
+    fn workstation() -> Configuration {
+        let report: HardwareReport = serde_json::from_str(include_str!(
+            "../../tests/fixtures/configuration/workstation.json"
+        ))
+        .unwrap();
+        ConfigurationEngineImpl::new()
+            .unwrap()
+            .generate_configuration(&report, "Fedora Linux 40")
+            .unwrap()
+    }
+
+    #[test]
+    fn test_shell_script_is_valid_bash() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("lx-hw-setup.sh");
+        std::fs::write(&path, workstation().to_shell_script()).unwrap();
+
+        let status = std::process::Command::new("bash").arg("-n").arg(&path).status().unwrap();
+        assert!(status.success());
+    }
+
+    #[test]
+    fn test_shell_script_backs_up_originals_and_has_undo() {
+        let mut configuration = workstation();
+        configuration.configuration_files.insert(
+            "grub".to_string(),
+            ConfigurationFile {
+                file_path: "/etc/modprobe.d/it's.conf".to_string(),
+                content: "LX_HW_EOF\noptions snd_hda_intel power_save=1".to_string(),
+                backup_original: true,
+                file_permissions: "0640".to_string(),
+                validation_command: None,
+            },
+        );
+        let script = configuration.to_shell_script();
+
+        assert!(script.contains(
+            "write_file '/etc/modprobe.d/it'\\''s.conf' '0640' yes <<'LX_HW_EOF_'\n\
+             LX_HW_EOF\noptions snd_hda_intel power_save=1\nLX_HW_EOF_\n"
+        ));
+        assert!(script.contains("write_file '/etc/modules-load.d/lx-hw-detect.conf' '0644' no"));
+        assert!(script.contains("    --undo)\n        undo\n"));
+        // Packages with post-install steps only run them after installing something
+        assert!(script.contains("install_package 'alsa-utils' && installed=yes"));
+        assert!(script.contains("add_kernel_parameters '"));
+    }
+
+    #[test]
+    fn test_shell_script_notes_for_layered_packages() {
+        let report: HardwareReport = serde_json::from_str(include_str!(
+            "../../tests/fixtures/configuration/workstation.json"
+        ))
+        .unwrap();
+        let script = ConfigurationEngineImpl::new()
+            .unwrap()
+            .generate_configuration(&report, "Fedora Silverblue 40")
+            .unwrap()
+            .to_shell_script();
+
+        assert!(script.contains(
+            "# NVIDIA proprietary graphics driver\n\
+             # Note: This is an rpm-ostree system"
+        ));
+        assert!(script.contains(
+            "# Note: Reboot into the new deployment, then run: nvidia-xconfig\n\
+             install_package 'akmod-nvidia' || true\n"
+        ));
+    }
+}
@@ -0,0 +1,123 @@
+//! D-Bus API
+//!
+//! Publishes `org.lxhwdb.Detect1` at `/org/lxhwdb/Detect1`:
+//!
+//! - `GetReport() -> s`: the latest anonymized report as JSON
+//! - `Rescan()`: request a detection run
+//! - `LastDetection` (s): RFC 3339 time of the last successful run
+//! - `DeviceCount` (u): devices in the latest report
+//! - `HardwareChanged(s)`: change events of a run as a JSON array
+//!
+//! Owning the name on the system bus needs the policy file shipped in
+//! `packaging/dbus`.
+
+use super::{BusKind, SharedState};
+use crate::diff::ChangeEvent;
+use crate::errors::{LxHwError, Result};
+use std::sync::Arc;
+use tokio::sync::{broadcast, Notify};
+use zbus::object_server::SignalEmitter;
+
+pub const BUS_NAME: &str = "org.lxhwdb.Detect1";
+pub const OBJECT_PATH: &str = "/org/lxhwdb/Detect1";
+
+/// Object served at [`OBJECT_PATH`]
+pub struct DetectService {
+    state: SharedState,
+    rescan: Arc<Notify>,
+}
+
+#[zbus::interface(name = "org.lxhwdb.Detect1")]
+impl DetectService {
+    /// Latest report as JSON
+    async fn get_report(&self) -> zbus::fdo::Result<String> {
+        let state =
+            self.state.read().map_err(|_| zbus::fdo::Error::Failed("state unavailable".into()))?;
+        let report = state
+            .report
+            .as_ref()
+            .ok_or_else(|| zbus::fdo::Error::Failed("no detection run has completed yet".into()))?;
+        serde_json::to_string(report).map_err(|e| zbus::fdo::Error::Failed(e.to_string()))
+    }
+
+    /// Request a detection run
+    async fn rescan(&self) {
+        self.rescan.notify_one();
+    }
+
+    #[zbus(property)]
+    async fn last_detection(&self) -> String {
+        self.state
+            .read()
+            .ok()
+            .and_then(|state| state.metrics.last_detection)
+            .map(|at| at.to_rfc3339())
+            .unwrap_or_default()
+    }
+
+    #[zbus(property)]
+    async fn device_count(&self) -> u32 {
+        let Ok(state) = self.state.read() else {
+            return 0;
+        };
+        state.report.as_ref().map_or(0, |report| {
+            (report.storage.len()
+                + report.graphics.len()
+                + report.network.len()
+                + report.usb.len()
+                + report.audio.len()) as u32
+        })
+    }
+
+    #[zbus(signal)]
+    async fn hardware_changed(emitter: &SignalEmitter<'_>, changes: &str) -> zbus::Result<()>;
+}
+
+fn dbus_error(e: zbus::Error) -> LxHwError {
+    LxHwError::SystemError { message: format!("D-Bus: {}", e) }
+}
+
+/// Publish the service and forward change events as signals
+pub async fn serve(
+    bus: BusKind,
+    state: SharedState,
+    rescan: Arc<Notify>,
+    changes: broadcast::Sender<Arc<Vec<ChangeEvent>>>,
+) -> Result<()> {
+    // Subscribe before serving so no run finishing in between is missed
+    let mut changes = changes.subscribe();
+    let builder = match bus {
+        BusKind::Session => zbus::connection::Builder::session(),
+        _ => zbus::connection::Builder::system(),
+    };
+    let connection = builder
+        .and_then(|b| b.name(BUS_NAME))
+        .and_then(|b| b.serve_at(OBJECT_PATH, DetectService { state, rescan }))
+        .map_err(dbus_error)?
+        .build()
+        .await
+        .map_err(dbus_error)?;
+    let service = connection
+        .object_server()
+        .interface::<_, DetectService>(OBJECT_PATH)
+        .await
+        .map_err(dbus_error)?;
+    log::info!("Serving {} on the {:?} bus", BUS_NAME, bus);
+
+    loop {
+        let events = match changes.recv().await {
+            Ok(events) => events,
+            Err(broadcast::error::RecvError::Lagged(missed)) => {
+                log::warn!("Dropped {} change notifications", missed);
+                continue;
+            }
+            Err(broadcast::error::RecvError::Closed) => return Ok(()),
+        };
+        let json = serde_json::to_string(events.as_ref())?;
+        let emitter = service.signal_emitter();
+        DetectService::hardware_changed(emitter, &json).await.map_err(dbus_error)?;
+        let interface = service.get().await;
+        interface.last_detection_changed(emitter).await.map_err(dbus_error)?;
+        interface.device_count_changed(emitter).await.map_err(dbus_error)?;
+    }
+}
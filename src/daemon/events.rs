@@ -0,0 +1,110 @@
+//! Change event log
+//!
+//! Writes the change events of every detection run as JSON lines, one event
+//! per line with the time of the run, to stdout or an append-only file.
+//! Fleet monitoring tools can tail the log or read the daemon's stdout from
+//! the journal without talking D-Bus.
+
+use crate::diff::ChangeEvent;
+use crate::errors::{LxHwError, Result};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::path::Path;
+use std::sync::Arc;
+use tokio::io::{AsyncWrite, AsyncWriteExt};
+use tokio::sync::broadcast;
+
+/// `event_log` value that writes to stdout
+pub const STDOUT: &str = "-";
+
+/// One line of the event log
+#[derive(Debug, Serialize)]
+struct EventLine<'a> {
+    timestamp: DateTime<Utc>,
+    #[serde(flatten)]
+    event: &'a ChangeEvent,
+}
+
+/// Format the events of one detection run as JSON lines
+pub fn format_events(events: &[ChangeEvent], timestamp: DateTime<Utc>) -> Result<String> {
+    let mut lines = String::new();
+    for event in events {
+        let line = serde_json::to_string(&EventLine { timestamp, event })
+            .map_err(|e| LxHwError::SerializationError(e.to_string()))?;
+        lines.push_str(&line);
+        lines.push('\n');
+    }
+    Ok(lines)
+}
+
+/// Append change events to the log until the daemon stops
+pub async fn write_log(
+    path: &Path,
+    changes: broadcast::Sender<Arc<Vec<ChangeEvent>>>,
+) -> Result<()> {
+    // Subscribe before opening so no run finishing in between is missed
+    let changes = changes.subscribe();
+    if path == Path::new(STDOUT) {
+        log::info!("Writing change events to stdout");
+        forward(tokio::io::stdout(), changes).await
+    } else {
+        if let Some(parent) = path.parent().filter(|parent| !parent.as_os_str().is_empty()) {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+        let file = tokio::fs::OpenOptions::new().create(true).append(true).open(path).await?;
+        log::info!("Writing change events to {}", path.display());
+        forward(file, changes).await
+    }
+}
+
+async fn forward(
+    mut output: impl AsyncWrite + Unpin,
+    mut changes: broadcast::Receiver<Arc<Vec<ChangeEvent>>>,
+) -> Result<()> {
+    loop {
+        let events = match changes.recv().await {
+            Ok(events) => events,
+            Err(broadcast::error::RecvError::Lagged(missed)) => {
+                log::warn!("Dropped {} change notifications from the event log", missed);
+                continue;
+            }
+            Err(broadcast::error::RecvError::Closed) => return Ok(()),
+        };
+        output.write_all(format_events(&events, Utc::now())?.as_bytes()).await?;
+        output.flush().await?;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::diff::ComponentCategory;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_format_events_as_json_lines() {
+        let timestamp = Utc.with_ymd_and_hms(2026, 10, 1, 12, 0, 0).unwrap();
+        let events = vec![
+            ChangeEvent::DeviceAdded {
+                category: ComponentCategory::Usb,
+                key: "046d:c52b".to_string(),
+                name: "Unifying Receiver".to_string(),
+            },
+            ChangeEvent::DriverChanged {
+                category: ComponentCategory::Graphics,
+                key: "10de:2484".to_string(),
+                old: Some("nouveau".to_string()),
+                new: Some("nvidia".to_string()),
+            },
+        ];
+
+        let lines = format_events(&events, timestamp).unwrap();
+        let parsed: Vec<serde_json::Value> =
+            lines.lines().map(|line| serde_json::from_str(line).unwrap()).collect();
+        assert_eq!(parsed.len(), 2);
+        assert_eq!(parsed[0]["event"], "device_added");
+        assert_eq!(parsed[0]["timestamp"], "2026-10-01T12:00:00Z");
+        assert_eq!(parsed[1]["event"], "driver_changed");
+        assert_eq!(parsed[1]["new"], "nvidia");
+    }
+}
@@ -0,0 +1,214 @@
+//! Prometheus endpoint
+//!
+//! Serves `GET /metrics` in the Prometheus text exposition format. The
+//! endpoint is read-only and has no other routes, so a minimal HTTP/1.1
+//! responder is enough and keeps the daemon free of a web framework.
+
+use super::{DaemonState, SharedState};
+use crate::errors::Result;
+use chrono::{DateTime, Utc};
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::net::SocketAddr;
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+
+/// Largest request head read before answering
+const MAX_REQUEST_BYTES: usize = 8192;
+
+/// How long a client may take to send its request
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Counters maintained by the daemon's components
+#[derive(Debug, Default)]
+pub struct Metrics {
+    pub detection_runs: u64,
+    pub detection_failures: u64,
+    pub last_detection: Option<DateTime<Utc>>,
+    pub last_duration: Duration,
+    pub uevents: u64,
+    /// Restarts per component name
+    pub restarts: BTreeMap<&'static str, u64>,
+}
+
+impl Metrics {
+    pub fn record_detection(&mut self, success: bool, at: DateTime<Utc>, duration: Duration) {
+        self.detection_runs += 1;
+        if success {
+            self.last_detection = Some(at);
+            self.last_duration = duration;
+        } else {
+            self.detection_failures += 1;
+        }
+    }
+}
+
+/// Render the daemon state in the text exposition format
+pub fn render(state: &DaemonState) -> String {
+    let mut out = String::new();
+    let mut metric = |name: &str, kind: &str, help: &str, samples: &[(String, String)]| {
+        let _ = writeln!(out, "# HELP {} {}\n# TYPE {} {}", name, help, name, kind);
+        for (labels, value) in samples {
+            let _ = writeln!(out, "{}{} {}", name, labels, value);
+        }
+    };
+    let plain = |value: String| vec![(String::new(), value)];
+    let metrics = &state.metrics;
+
+    metric(
+        "lx_hw_detection_runs_total",
+        "counter",
+        "Detection runs since the daemon started",
+        &plain(metrics.detection_runs.to_string()),
+    );
+    metric(
+        "lx_hw_detection_failures_total",
+        "counter",
+        "Detection runs that failed",
+        &plain(metrics.detection_failures.to_string()),
+    );
+    if let Some(at) = metrics.last_detection {
+        metric(
+            "lx_hw_last_detection_timestamp_seconds",
+            "gauge",
+            "Time of the last successful detection run",
+            &plain(at.timestamp().to_string()),
+        );
+        metric(
+            "lx_hw_detection_duration_seconds",
+            "gauge",
+            "Duration of the last successful detection run",
+            &plain(format!("{:.3}", metrics.last_duration.as_secs_f64())),
+        );
+    }
+    metric(
+        "lx_hw_uevents_total",
+        "counter",
+        "Kernel uevents that requested a detection run",
+        &plain(metrics.uevents.to_string()),
+    );
+    let restarts: Vec<(String, String)> = metrics
+        .restarts
+        .iter()
+        .map(|(component, count)| (format!("{{component=\"{}\"}}", component), count.to_string()))
+        .collect();
+    metric(
+        "lx_hw_component_restarts_total",
+        "counter",
+        "Restarts of daemon components after a failure",
+        &restarts,
+    );
+
+    if let Some(report) = &state.report {
+        let devices = [
+            ("storage", report.storage.len()),
+            ("graphics", report.graphics.len()),
+            ("network", report.network.len()),
+            ("usb", report.usb.len()),
+            ("audio", report.audio.len()),
+        ];
+        let devices: Vec<(String, String)> = devices
+            .iter()
+            .map(|(category, count)| (format!("{{category=\"{}\"}}", category), count.to_string()))
+            .collect();
+        metric("lx_hw_devices", "gauge", "Devices in the latest report", &devices);
+
+        if let Some(support) = &report.kernel_support {
+            let statuses = [
+                ("supported", support.supported_devices),
+                ("unsupported", support.unsupported_devices),
+                ("experimental", support.experimental_devices),
+            ];
+            let statuses: Vec<(String, String)> = statuses
+                .iter()
+                .map(|(status, count)| (format!("{{status=\"{}\"}}", status), count.to_string()))
+                .collect();
+            metric(
+                "lx_hw_kernel_support_devices",
+                "gauge",
+                "Devices by kernel support status in the latest report",
+                &statuses,
+            );
+        }
+    }
+
+    out
+}
+
+/// Serve metrics on `addr` until the listener fails
+pub async fn serve(addr: SocketAddr, state: SharedState) -> Result<()> {
+    let listener = TcpListener::bind(addr).await?;
+    log::info!("Serving metrics on http://{}/metrics", addr);
+    loop {
+        let (stream, _) = listener.accept().await?;
+        let state = state.clone();
+        tokio::spawn(async move {
+            if let Err(e) = respond(stream, &state).await {
+                log::debug!("Metrics request failed: {}", e);
+            }
+        });
+    }
+}
+
+async fn respond(mut stream: TcpStream, state: &SharedState) -> std::io::Result<()> {
+    let mut request = Vec::new();
+    let mut buf = [0u8; 1024];
+    tokio::time::timeout(REQUEST_TIMEOUT, async {
+        while !request.windows(4).any(|w| w == b"\r\n\r\n") && request.len() < MAX_REQUEST_BYTES {
+            let read = stream.read(&mut buf).await?;
+            if read == 0 {
+                break;
+            }
+            request.extend_from_slice(&buf[..read]);
+        }
+        Ok::<_, std::io::Error>(())
+    })
+    .await
+    .map_err(|_| std::io::Error::from(std::io::ErrorKind::TimedOut))??;
+
+    let request_line = String::from_utf8_lossy(&request);
+    let mut parts = request_line.split_whitespace();
+    let (status, content_type, body) = match (parts.next(), parts.next()) {
+        (Some("GET"), Some("/metrics")) => {
+            let body = match state.read() {
+                Ok(state) => render(&state),
+                Err(_) => String::new(),
+            };
+            ("200 OK", "text/plain; version=0.0.4", body)
+        }
+        (Some("GET"), _) => ("404 Not Found", "text/plain", "Not found\n".to_string()),
+        _ => ("405 Method Not Allowed", "text/plain", "Method not allowed\n".to_string()),
+    };
+
+    let response = format!(
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        content_type,
+        body.len(),
+        body
+    );
+    stream.write_all(response.as_bytes()).await?;
+    stream.shutdown().await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_counters() {
+        let mut state = DaemonState::default();
+        state.metrics.record_detection(true, Utc::now(), Duration::from_millis(1500));
+        state.metrics.record_detection(false, Utc::now(), Duration::ZERO);
+        state.metrics.restarts.insert("uevents", 2);
+
+        let text = render(&state);
+        assert!(text
+            .contains("# TYPE lx_hw_detection_runs_total counter\nlx_hw_detection_runs_total 2\n"));
+        assert!(text.contains("lx_hw_detection_failures_total 1\n"));
+        assert!(text.contains("lx_hw_detection_duration_seconds 1.500\n"));
+        assert!(text.contains("lx_hw_component_restarts_total{component=\"uevents\"} 2\n"));
+        assert!(!text.contains("lx_hw_devices"));
+    }
+}
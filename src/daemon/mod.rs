@@ -0,0 +1,476 @@
+//! Long-running hardware monitoring service
+//!
+//! `lx-hw-detectd` combines the monitoring pieces into one service for
+//! distributions to package. It watches kernel uevents for hotplug, re-runs
+//! detection when hardware changes and optionally on a fixed interval,
+//! records each run in the snapshot history, serves Prometheus metrics,
+//! exposes the latest report and change events over D-Bus and can write the
+//! change events to a JSON lines log.
+//!
+//! Every piece runs as a supervised component: a component that fails or
+//! panics is restarted with exponential backoff while the others keep
+//! running. The latest report is restored from the snapshot history on
+//! startup, so a restarted daemon does not report hardware that was already
+//! present as new. `SIGHUP` reloads the configuration file and restarts the
+//! components whose settings changed.
+
+pub mod dbus;
+pub mod events;
+pub mod metrics;
+pub mod uevent;
+
+use crate::detectors::integration::HardwareAnalyzer;
+use crate::detectors::profile::DetectionProfile;
+use crate::diff::history::{SnapshotHistory, DEFAULT_RETENTION};
+use crate::diff::ChangeEvent;
+use crate::errors::{LxHwError, Result};
+use crate::hardware::{HardwareReport, PrivacyLevel};
+use futures::FutureExt;
+use metrics::Metrics;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::panic::AssertUnwindSafe;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
+use tokio::signal::unix::{signal, SignalKind};
+use tokio::sync::{broadcast, Notify};
+use tokio::task::{AbortHandle, JoinSet};
+
+/// Configuration file read when none is given
+pub const DEFAULT_CONFIG_PATH: &str = "/etc/lx-hw-db/lx-hw-detectd.toml";
+
+/// Longest wait before restarting a failed component
+const MAX_BACKOFF: Duration = Duration::from_secs(60);
+
+/// A component running this long is considered healthy again
+const HEALTHY_RUN: Duration = Duration::from_secs(300);
+
+/// Message bus the D-Bus API is published on
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BusKind {
+    System,
+    Session,
+    /// No D-Bus API
+    Off,
+}
+
+/// Daemon settings, read from a TOML file
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DaemonConfig {
+    pub privacy_level: PrivacyLevel,
+    pub profile: DetectionProfile,
+    /// Snapshot history directory (default: the per-user history)
+    pub history_dir: Option<PathBuf>,
+    pub history_retention: usize,
+    /// Quiet period after a uevent before detection runs, so that a device
+    /// and its child devices appearing together cause a single run
+    pub debounce_secs: u64,
+    /// Re-run detection this often even without uevents, catching changes
+    /// the kernel does not announce such as driver updates; unset disables it
+    pub interval_secs: Option<u64>,
+    /// Change event log, "-" for stdout; unset disables it
+    pub event_log: Option<PathBuf>,
+    /// Address of the Prometheus endpoint; unset disables it
+    pub metrics_listen: Option<SocketAddr>,
+    pub dbus: BusKind,
+}
+
+impl Default for DaemonConfig {
+    fn default() -> Self {
+        Self {
+            privacy_level: PrivacyLevel::Basic,
+            profile: DetectionProfile::Quick,
+            history_dir: None,
+            history_retention: DEFAULT_RETENTION,
+            debounce_secs: 5,
+            interval_secs: None,
+            event_log: None,
+            metrics_listen: Some(SocketAddr::from(([127, 0, 0, 1], 9464))),
+            dbus: BusKind::System,
+        }
+    }
+}
+
+impl DaemonConfig {
+    /// Load settings; a missing file means defaults
+    pub fn load(path: &Path) -> Result<Self> {
+        match std::fs::read_to_string(path) {
+            Ok(content) => toml::from_str(&content).map_err(|e| {
+                LxHwError::ConfigError(format!(
+                    "Invalid daemon configuration {}: {}",
+                    path.display(),
+                    e
+                ))
+            }),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Time between periodic detection runs
+    fn interval(&self) -> Option<Duration> {
+        self.interval_secs.filter(|secs| *secs > 0).map(Duration::from_secs)
+    }
+
+    fn history(&self) -> SnapshotHistory {
+        SnapshotHistory::new(self.history_dir.clone().unwrap_or_else(SnapshotHistory::default_dir))
+            .with_retention(self.history_retention)
+    }
+}
+
+/// Settings given on the command line, taking precedence over the file
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ConfigOverrides {
+    pub interval_secs: Option<u64>,
+    pub event_log: Option<PathBuf>,
+}
+
+impl ConfigOverrides {
+    fn apply(&self, mut config: DaemonConfig) -> DaemonConfig {
+        if let Some(secs) = self.interval_secs {
+            config.interval_secs = Some(secs);
+        }
+        if let Some(path) = &self.event_log {
+            config.event_log = Some(path.clone());
+        }
+        config
+    }
+}
+
+/// State shared between components
+#[derive(Debug, Default)]
+pub struct DaemonState {
+    /// Report of the most recent successful detection run
+    pub report: Option<HardwareReport>,
+    pub metrics: Metrics,
+}
+
+pub type SharedState = Arc<RwLock<DaemonState>>;
+
+/// Channels connecting components
+#[derive(Clone)]
+struct Context {
+    state: SharedState,
+    /// Requests a detection run; pending requests coalesce
+    rescan: Arc<Notify>,
+    /// Changes found by each detection run
+    changes: broadcast::Sender<Arc<Vec<ChangeEvent>>>,
+}
+
+/// Independently restarted part of the daemon
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Component {
+    Uevents,
+    Detection,
+    Metrics,
+    Dbus,
+    Events,
+}
+
+impl Component {
+    const ALL: [Component; 5] =
+        [Self::Uevents, Self::Detection, Self::Metrics, Self::Dbus, Self::Events];
+
+    fn name(self) -> &'static str {
+        match self {
+            Self::Uevents => "uevents",
+            Self::Detection => "detection",
+            Self::Metrics => "metrics",
+            Self::Dbus => "dbus",
+            Self::Events => "events",
+        }
+    }
+
+    /// Whether a configuration change requires restarting this component
+    fn affected_by(self, old: &DaemonConfig, new: &DaemonConfig) -> bool {
+        match self {
+            Self::Uevents => false,
+            Self::Detection => {
+                (old.privacy_level, old.profile, &old.history_dir, old.history_retention)
+                    != (new.privacy_level, new.profile, &new.history_dir, new.history_retention)
+                    || (old.debounce_secs, old.interval_secs)
+                        != (new.debounce_secs, new.interval_secs)
+            }
+            Self::Metrics => old.metrics_listen != new.metrics_listen,
+            Self::Dbus => old.dbus != new.dbus,
+            Self::Events => old.event_log != new.event_log,
+        }
+    }
+
+    async fn run(self, config: DaemonConfig, context: Context) -> Result<()> {
+        match self {
+            Self::Uevents => uevent::listen(context.state, context.rescan).await,
+            Self::Detection => run_detection(config, context).await,
+            Self::Metrics => match config.metrics_listen {
+                Some(addr) => metrics::serve(addr, context.state).await,
+                None => std::future::pending().await,
+            },
+            Self::Dbus => match config.dbus {
+                BusKind::Off => std::future::pending().await,
+                bus => dbus::serve(bus, context.state, context.rescan, context.changes).await,
+            },
+            Self::Events => match &config.event_log {
+                Some(path) => events::write_log(path, context.changes).await,
+                None => std::future::pending().await,
+            },
+        }
+    }
+}
+
+/// Supervisor owning the components
+pub struct Daemon {
+    config_path: PathBuf,
+    config: DaemonConfig,
+    overrides: ConfigOverrides,
+    context: Context,
+    tasks: JoinSet<(Component, Instant, std::result::Result<Result<()>, String>)>,
+    running: HashMap<Component, AbortHandle>,
+    failures: HashMap<Component, u32>,
+}
+
+impl Daemon {
+    pub fn new(config_path: impl Into<PathBuf>) -> Result<Self> {
+        let config_path = config_path.into();
+        let config = DaemonConfig::load(&config_path)?;
+
+        let state = DaemonState { report: config.history().latest()?, ..Default::default() };
+        let (changes, _) = broadcast::channel(16);
+        let context = Context {
+            state: Arc::new(RwLock::new(state)),
+            rescan: Arc::new(Notify::new()),
+            changes,
+        };
+
+        Ok(Self {
+            config_path,
+            config,
+            overrides: ConfigOverrides::default(),
+            context,
+            tasks: JoinSet::new(),
+            running: HashMap::new(),
+            failures: HashMap::new(),
+        })
+    }
+
+    /// Apply command-line settings, now and after every reload
+    pub fn with_overrides(mut self, overrides: ConfigOverrides) -> Self {
+        self.config = overrides.apply(self.config);
+        self.overrides = overrides;
+        self
+    }
+
+    /// Run until `SIGTERM` or `SIGINT`
+    pub async fn run(mut self) -> Result<()> {
+        let mut hangup = signal(SignalKind::hangup())?;
+        let mut terminate = signal(SignalKind::terminate())?;
+        let mut interrupt = signal(SignalKind::interrupt())?;
+
+        for component in Component::ALL {
+            self.start(component, Duration::ZERO);
+        }
+        log::info!("lx-hw-detectd started with {}", self.config_path.display());
+
+        loop {
+            tokio::select! {
+                Some(finished) = self.tasks.join_next() => {
+                    // Aborted components were replaced already
+                    if let Ok((component, started, outcome)) = finished {
+                        self.restart(component, started, outcome);
+                    }
+                }
+                _ = hangup.recv() => self.reload(),
+                _ = terminate.recv() => break,
+                _ = interrupt.recv() => break,
+            }
+        }
+
+        log::info!("lx-hw-detectd stopping");
+        self.tasks.shutdown().await;
+        Ok(())
+    }
+
+    /// Start a component after `delay`, replacing a running instance
+    fn start(&mut self, component: Component, delay: Duration) {
+        if let Some(previous) = self.running.remove(&component) {
+            previous.abort();
+        }
+        let config = self.config.clone();
+        let context = self.context.clone();
+        let handle = self.tasks.spawn(async move {
+            tokio::time::sleep(delay).await;
+            let started = Instant::now();
+            let outcome = AssertUnwindSafe(component.run(config, context))
+                .catch_unwind()
+                .await
+                .map_err(|panic| {
+                    panic
+                        .downcast_ref::<&str>()
+                        .map(|s| s.to_string())
+                        .or_else(|| panic.downcast_ref::<String>().cloned())
+                        .unwrap_or_else(|| "unknown panic".to_string())
+                });
+            (component, started, outcome)
+        });
+        self.running.insert(component, handle);
+    }
+
+    /// Restart a component that stopped, backing off while it keeps failing
+    fn restart(
+        &mut self,
+        component: Component,
+        started: Instant,
+        outcome: std::result::Result<Result<()>, String>,
+    ) {
+        match outcome {
+            Ok(Ok(())) => log::warn!("Component {} stopped", component.name()),
+            Ok(Err(e)) => log::error!("Component {} failed: {}", component.name(), e),
+            Err(panic) => log::error!("Component {} panicked: {}", component.name(), panic),
+        }
+
+        let failures = self.failures.entry(component).or_default();
+        if started.elapsed() >= HEALTHY_RUN {
+            *failures = 0;
+        }
+        let delay = Duration::from_secs(1 << (*failures).min(6)).min(MAX_BACKOFF);
+        *failures += 1;
+
+        if let Ok(mut state) = self.context.state.write() {
+            *state.metrics.restarts.entry(component.name()).or_default() += 1;
+        }
+        log::info!("Restarting {} in {}s", component.name(), delay.as_secs());
+        self.start(component, delay);
+    }
+
+    /// Re-read the configuration file and apply changed settings
+    fn reload(&mut self) {
+        let config = match DaemonConfig::load(&self.config_path) {
+            Ok(config) => self.overrides.apply(config),
+            Err(e) => {
+                log::error!("Keeping previous configuration: {}", e);
+                return;
+            }
+        };
+        let restart: Vec<Component> = Component::ALL
+            .into_iter()
+            .filter(|component| component.affected_by(&self.config, &config))
+            .collect();
+        log::info!(
+            "Reloaded {} ({} components affected)",
+            self.config_path.display(),
+            restart.len()
+        );
+
+        self.config = config;
+        for component in restart {
+            self.failures.remove(&component);
+            self.start(component, Duration::ZERO);
+        }
+    }
+}
+
+/// Detect once at startup, then again after every rescan request and
+/// every interval
+async fn run_detection(config: DaemonConfig, context: Context) -> Result<()> {
+    let history = config.history();
+    let interval = config.interval();
+    loop {
+        let started = Instant::now();
+        let result = detect(&config, &history).await;
+        let finished = chrono::Utc::now();
+
+        let changes = {
+            let mut state = context.state.write().map_err(|_| LxHwError::SystemError {
+                message: "daemon state lock poisoned".to_string(),
+            })?;
+            state.metrics.record_detection(result.is_ok(), finished, started.elapsed());
+            match result {
+                Ok((report, changes)) => {
+                    state.report = Some(report);
+                    changes
+                }
+                Err(e) => {
+                    log::error!("Detection failed: {}", e);
+                    Vec::new()
+                }
+            }
+        };
+        if !changes.is_empty() {
+            log::info!("{} hardware change(s) detected", changes.len());
+            // No subscribers is fine
+            let _ = context.changes.send(Arc::new(changes));
+        }
+
+        let periodic = async {
+            match interval {
+                Some(interval) => tokio::time::sleep(interval).await,
+                None => std::future::pending().await,
+            }
+        };
+        tokio::select! {
+            _ = context.rescan.notified() => {
+                // Let the burst of uevents from one hotplug settle
+                tokio::time::sleep(Duration::from_secs(config.debounce_secs)).await;
+            }
+            _ = periodic => log::debug!("Running periodic detection"),
+        }
+    }
+}
+
+/// Run detection and record the report, returning changes since the last run
+async fn detect(
+    config: &DaemonConfig,
+    history: &SnapshotHistory,
+) -> Result<(HardwareReport, Vec<ChangeEvent>)> {
+    let mut analyzer = HardwareAnalyzer::new(config.privacy_level)?;
+    analyzer.set_profile(config.profile);
+    let report = analyzer.analyze_system().await?;
+    let changes = history.record_changes(&report)?.map(|diff| diff.events).unwrap_or_default();
+    Ok((report, changes))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_config_defaults_and_reload_scope() {
+        let config: DaemonConfig = toml::from_str("metrics_listen = \"0.0.0.0:9500\"").unwrap();
+        assert_eq!(config.profile, DetectionProfile::Quick);
+        assert_eq!(config.dbus, BusKind::System);
+
+        let reloaded: DaemonConfig =
+            toml::from_str("metrics_listen = \"0.0.0.0:9500\"\nprofile = \"full\"").unwrap();
+        let affected: Vec<&str> = Component::ALL
+            .into_iter()
+            .filter(|c| c.affected_by(&config, &reloaded))
+            .map(Component::name)
+            .collect();
+        assert_eq!(affected, ["detection"]);
+    }
+
+    #[test]
+    fn test_overrides_survive_reload() {
+        let overrides = ConfigOverrides {
+            interval_secs: Some(600),
+            event_log: Some(PathBuf::from(events::STDOUT)),
+        };
+        let file: DaemonConfig =
+            toml::from_str("interval_secs = 60\nevent_log = \"/var/log/lx-hw-db/events.jsonl\"")
+                .unwrap();
+        let config = overrides.apply(file.clone());
+        assert_eq!(config.interval(), Some(Duration::from_secs(600)));
+        assert_eq!(config.event_log.as_deref(), Some(Path::new("-")));
+
+        let affected: Vec<&str> = Component::ALL
+            .into_iter()
+            .filter(|c| c.affected_by(&file, &config))
+            .map(Component::name)
+            .collect();
+        assert_eq!(affected, ["detection", "events"]);
+        assert_eq!(ConfigOverrides::default().apply(file.clone()), file);
+    }
+}
@@ -0,0 +1,37 @@
+//! Kernel uevent listener
+//!
+//! Requests a detection run when a device is added, removed or bound to a
+//! different driver, as reported by [`UdevMonitor`]. The daemon needs no
+//! udev properties, only the fact that hardware changed.
+
+use super::SharedState;
+use crate::detectors::udev::{MonitorEvent, UdevMonitor};
+use crate::errors::Result;
+use std::sync::Arc;
+use tokio::sync::Notify;
+
+pub use crate::detectors::udev::Uevent;
+
+/// Request a detection run for every hardware uevent
+pub async fn listen(state: SharedState, rescan: Arc<Notify>) -> Result<()> {
+    let mut monitor = UdevMonitor::open()?;
+    log::info!("Listening for kernel uevents");
+
+    loop {
+        match monitor.next().await? {
+            MonitorEvent::Device(event) if event.is_hardware_change() => {
+                log::debug!("{} {} ({})", event.action, event.devpath, event.subsystem);
+                if let Ok(mut state) = state.write() {
+                    state.metrics.uevents += 1;
+                }
+                rescan.notify_one();
+            }
+            MonitorEvent::Device(_) => {}
+            // Events were lost, so rescan rather than guess what changed
+            MonitorEvent::Overflow => {
+                log::warn!("Uevents were dropped; requesting a full rescan");
+                rescan.notify_one();
+            }
+        }
+    }
+}
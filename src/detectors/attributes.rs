@@ -0,0 +1,18 @@
+//! Reading single-value attribute files from sysfs and procfs
+
+use std::path::Path;
+
+/// Content of an attribute file without surrounding whitespace
+pub(crate) fn read_trimmed(path: &Path) -> Option<String> {
+    std::fs::read_to_string(path).ok().map(|value| value.trim().to_string())
+}
+
+/// Like [`read_trimmed`], treating an empty attribute as missing
+pub(crate) fn read_nonempty(path: &Path) -> Option<String> {
+    read_trimmed(path).filter(|value| !value.is_empty())
+}
+
+/// Name of the file a symlink points at, e.g. the driver behind `device/driver`
+pub(crate) fn link_target_name(path: &Path) -> Option<String> {
+    std::fs::read_link(path).ok()?.file_name().map(|name| name.to_string_lossy().into_owned())
+}
@@ -0,0 +1,202 @@
+//! Codecs and sound server profiles of ALSA cards
+//!
+//! HD Audio cards list their codecs in `/proc/asound/cardN/codec#M`: the
+//! analog codec of the board, or the HDMI/DisplayPort codec of a GPU. The
+//! profiles PipeWire or PulseAudio offer for a card, and the one in use,
+//! come from `pactl list cards`; a profile is unavailable while nothing is
+//! plugged into its jack or no display with audio is attached. Both are
+//! keyed by the card's PCI address and attached to the audio devices lspci
+//! or sysfs found.
+
+use super::audio_routing::parse_cards;
+use crate::hardware::{AudioCardProfiles, AudioCodec};
+use std::collections::HashMap;
+use std::path::Path;
+
+/// Codecs and profiles of one card
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AudioCardDetails {
+    pub codecs: Vec<AudioCodec>,
+    pub profiles: Option<AudioCardProfiles>,
+}
+
+/// Read the codecs and profiles of every PCI sound card, by PCI address
+///
+/// `pactl_cards` is the output of `pactl list cards`, if a sound server runs.
+pub fn read_audio_cards(
+    asound_root: &Path,
+    sound_class_root: &Path,
+    pactl_cards: Option<&str>,
+) -> HashMap<String, AudioCardDetails> {
+    let Ok(cards) = std::fs::read_to_string(asound_root.join("cards")) else {
+        return HashMap::new();
+    };
+    let mut profiles = pactl_cards.map(parse_pactl_cards).unwrap_or_default();
+
+    let mut details = HashMap::new();
+    for card in parse_cards(&cards) {
+        let Some(address) = pci_address(&sound_class_root.join(format!("card{}", card.index)))
+        else {
+            continue;
+        };
+        details.insert(
+            address,
+            AudioCardDetails {
+                codecs: read_codecs(&asound_root.join(format!("card{}", card.index))),
+                profiles: profiles.remove(&card.index),
+            },
+        );
+    }
+    details
+}
+
+/// Ask the sound server for its cards with `pactl list cards`
+pub fn query_pactl_cards() -> Option<String> {
+    let output = std::process::Command::new("pactl")
+        .args(["list", "cards"])
+        .env("LC_ALL", "C")
+        .output()
+        .ok()?;
+    output.status.success().then(|| String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// PCI address of a card, e.g. "0000:00:1f.3"; None for USB and other buses
+fn pci_address(card_class_dir: &Path) -> Option<String> {
+    let device = std::fs::canonicalize(card_class_dir.join("device")).ok()?;
+    let address = device.file_name()?.to_string_lossy().into_owned();
+    let is_pci = address.split([':', '.']).count() == 4 && address.contains(':');
+    is_pci.then_some(address)
+}
+
+/// Codecs of a card in address order
+fn read_codecs(card_dir: &Path) -> Vec<AudioCodec> {
+    let Ok(entries) = std::fs::read_dir(card_dir) else {
+        return Vec::new();
+    };
+    let mut files: Vec<_> = entries
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.file_name().to_string_lossy().starts_with("codec#"))
+        .map(|entry| entry.path())
+        .collect();
+    files.sort();
+    files
+        .iter()
+        .filter_map(|path| std::fs::read_to_string(path).ok())
+        .filter_map(|content| parse_codec(&content))
+        .collect()
+}
+
+/// Parse the header of a `codec#N` file
+fn parse_codec(content: &str) -> Option<AudioCodec> {
+    let field = |key: &str| content.lines().find_map(|line| line.strip_prefix(key)).map(str::trim);
+    // "0x10ec0287" is vendor 10ec, device 0287
+    let id = |key: &str| {
+        let hex = field(key)?.strip_prefix("0x")?;
+        (hex.len() == 8).then(|| format!("{}:{}", &hex[..4], &hex[4..]))
+    };
+    Some(AudioCodec {
+        name: field("Codec:")?.to_string(),
+        vendor_id: id("Vendor Id:"),
+        subsystem_id: id("Subsystem Id:"),
+    })
+}
+
+/// Profiles of each ALSA card index from `pactl list cards`
+fn parse_pactl_cards(output: &str) -> HashMap<u32, AudioCardProfiles> {
+    let mut cards = HashMap::new();
+    for block in output.split("Card #").skip(1) {
+        let mut index = None;
+        let mut profiles = AudioCardProfiles { active: None, available: Vec::new() };
+        let mut in_profiles = false;
+        for line in block.lines() {
+            let trimmed = line.trim();
+            // PulseAudio sets alsa.card, PipeWire api.alsa.card
+            if let Some(value) =
+                trimmed.strip_prefix("alsa.card = ").or(trimmed.strip_prefix("api.alsa.card = "))
+            {
+                index = index.or(value.trim_matches('"').parse().ok());
+            } else if let Some(active) = trimmed.strip_prefix("Active Profile:") {
+                profiles.active = Some(active.trim().to_string());
+            }
+
+            if line.starts_with("\t\t") && in_profiles {
+                // "output:hdmi-stereo: Digital Stereo (HDMI) Output (..., available: no)"
+                let Some((name, description)) = trimmed.split_once(": ") else {
+                    continue;
+                };
+                if name != "off" && !description.contains("available: no") {
+                    profiles.available.push(name.to_string());
+                }
+            } else {
+                in_profiles = trimmed == "Profiles:";
+            }
+        }
+        if let Some(index) = index {
+            cards.insert(index, profiles);
+        }
+    }
+    cards
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PACTL_CARDS: &str = "Card #42
+\tName: alsa_card.pci-0000_00_1f.3
+\tDriver: alsa
+\tProperties:
+\t\tapi.alsa.card = \"0\"
+\t\tapi.alsa.card.name = \"HDA Intel PCH\"
+\tProfiles:
+\t\toff: Off (sinks: 0, sources: 0, priority: 0, available: yes)
+\t\toutput:analog-stereo+input:analog-stereo: Analog Stereo Duplex (sinks: 1, sources: 1, priority: 6565, available: yes)
+\t\toutput:hdmi-stereo: Digital Stereo (HDMI) Output (sinks: 1, sources: 0, priority: 5900, available: no)
+\tActive Profile: output:analog-stereo+input:analog-stereo
+\tPorts:
+\t\tanalog-output-speaker: Speakers (type: Speaker, priority: 10000, latency offset: 0 usec, availability unknown)
+";
+
+    #[test]
+    fn test_read_audio_cards() {
+        let root = tempfile::TempDir::new().unwrap();
+        let asound = root.path().join("asound");
+        std::fs::create_dir_all(asound.join("card0")).unwrap();
+        std::fs::write(
+            asound.join("cards"),
+            " 0 [PCH            ]: HDA-Intel - HDA Intel PCH\n                      HDA Intel PCH at 0xa1230000 irq 142\n",
+        )
+        .unwrap();
+        std::fs::write(
+            asound.join("card0/codec#0"),
+            "Codec: Realtek ALC287\nAddress: 0\nAFG Function Id: 0x1 (unsol 1)\nVendor Id: 0x10ec0287\nSubsystem Id: 0x17aa22e7\nRevision Id: 0x100002\n",
+        )
+        .unwrap();
+        std::fs::write(
+            asound.join("card0/codec#2"),
+            "Codec: Intel Tigerlake HDMI\nAddress: 2\nVendor Id: 0x80862812\nSubsystem Id: 0x80860101\n",
+        )
+        .unwrap();
+        let device = root.path().join("devices/0000:00:1f.3");
+        let class = root.path().join("sound/card0");
+        std::fs::create_dir_all(&device).unwrap();
+        std::fs::create_dir_all(&class).unwrap();
+        std::os::unix::fs::symlink(&device, class.join("device")).unwrap();
+
+        let cards = read_audio_cards(&asound, &root.path().join("sound"), Some(PACTL_CARDS));
+        let card = &cards["0000:00:1f.3"];
+        assert_eq!(card.codecs.len(), 2);
+        assert_eq!(
+            card.codecs[0],
+            AudioCodec {
+                name: "Realtek ALC287".to_string(),
+                vendor_id: Some("10ec:0287".to_string()),
+                subsystem_id: Some("17aa:22e7".to_string()),
+            }
+        );
+        assert_eq!(card.codecs[1].name, "Intel Tigerlake HDMI");
+        let profiles = card.profiles.as_ref().unwrap();
+        assert_eq!(profiles.active.as_deref(), Some("output:analog-stereo+input:analog-stereo"));
+        assert_eq!(profiles.available, ["output:analog-stereo+input:analog-stereo"]);
+    }
+}
@@ -0,0 +1,447 @@
+//! Audio output routing from ALSA and the sound server
+//!
+//! Playback PCMs come from `/proc/asound/cardN/pcmDp/info`. For HDMI and
+//! DisplayPort outputs the `eld#codec.pin` files tell whether a display with
+//! audio is attached. The default output is taken from `pactl info`, which
+//! PulseAudio and PipeWire both answer, and otherwise from the ALSA defaults in
+//! `~/.asoundrc` and `/etc/asound.conf`. PipeWire without its PulseAudio
+//! service is recognised with `pw-cli info`, which has no default sink.
+
+use crate::hardware::{AudioOutput, AudioOutputKind, AudioRouting};
+use std::path::{Path, PathBuf};
+
+/// Default output as reported by the sound server
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ServerDefault {
+    /// "pipewire" or "pulseaudio"
+    pub server: String,
+    pub version: Option<String>,
+    pub sink: Option<String>,
+}
+
+/// Read playback outputs and resolve the default one
+///
+/// Returns None when the system has no ALSA cards.
+pub fn read_audio_routing(
+    asound_root: &Path,
+    sound_class_root: &Path,
+    server_default: Option<ServerDefault>,
+    alsa_config: &[PathBuf],
+) -> Option<AudioRouting> {
+    let cards = std::fs::read_to_string(asound_root.join("cards")).ok()?;
+    let cards = parse_cards(&cards);
+    if cards.is_empty() {
+        return None;
+    }
+
+    let mut outputs = Vec::new();
+    for card in &cards {
+        let bus = card_bus(&sound_class_root.join(format!("card{}", card.index)));
+        outputs.extend(read_card_outputs(
+            &asound_root.join(format!("card{}", card.index)),
+            card,
+            bus.as_deref(),
+        ));
+    }
+
+    let routing = match server_default {
+        Some(default) => {
+            let default_output = default
+                .sink
+                .as_ref()
+                .and_then(|sink| outputs.iter().position(|o| o.sink_name.as_ref() == Some(sink)));
+            let dummy_output = default.sink.as_deref() == Some("auto_null");
+            AudioRouting {
+                outputs,
+                sound_server: default.server,
+                sound_server_version: default.version,
+                default_output,
+                dummy_output,
+            }
+        }
+        None => {
+            let (card, device) = alsa_default(alsa_config, &cards);
+            let default_output = outputs
+                .iter()
+                .position(|o| o.card == card && o.device == device)
+                .or_else(|| outputs.iter().position(|o| o.card == card));
+            AudioRouting {
+                outputs,
+                sound_server: "alsa".to_string(),
+                sound_server_version: None,
+                default_output,
+                dummy_output: false,
+            }
+        }
+    };
+    Some(routing)
+}
+
+/// Ask the sound server for its default sink with `pactl info`, or ask
+/// PipeWire for its version with `pw-cli info`
+pub fn query_server_default() -> Option<ServerDefault> {
+    let run = |program: &str, args: &[&str]| {
+        let output =
+            std::process::Command::new(program).args(args).env("LC_ALL", "C").output().ok()?;
+        output.status.success().then(|| String::from_utf8_lossy(&output.stdout).into_owned())
+    };
+    run("pactl", &["info"])
+        .and_then(|info| parse_pactl_info(&info))
+        .or_else(|| run("pw-cli", &["info", "0"]).and_then(|info| parse_pw_cli_info(&info)))
+}
+
+/// ALSA configuration files in increasing precedence
+pub fn alsa_config_files() -> Vec<PathBuf> {
+    let mut files = vec![PathBuf::from("/etc/asound.conf")];
+    if let Some(home) = std::env::var_os("HOME") {
+        files.push(Path::new(&home).join(".asoundrc"));
+    }
+    files
+}
+
+/// An ALSA card as listed in `/proc/asound/cards`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(super) struct Card {
+    pub(super) index: u32,
+    /// Short id, e.g. "PCH"
+    id: String,
+    /// Driver, e.g. "HDA-Intel" or "USB-Audio"
+    driver: String,
+    name: String,
+}
+
+/// Parse `/proc/asound/cards` (" 0 [PCH            ]: HDA-Intel - HDA Intel PCH")
+pub(super) fn parse_cards(content: &str) -> Vec<Card> {
+    content
+        .lines()
+        .filter_map(|line| {
+            let (index, rest) = line.trim_start().split_once(' ')?;
+            let index = index.parse().ok()?;
+            let (id, rest) = rest.trim_start().strip_prefix('[')?.split_once(']')?;
+            let (driver, name) = rest.trim_start().strip_prefix(':')?.split_once(" - ")?;
+            Some(Card {
+                index,
+                id: id.trim().to_string(),
+                driver: driver.trim().to_string(),
+                name: name.trim().to_string(),
+            })
+        })
+        .collect()
+}
+
+/// Bus path of a card as used in sink names, e.g. "pci-0000_01_00.1"
+fn card_bus(card_class_dir: &Path) -> Option<String> {
+    let device = std::fs::canonicalize(card_class_dir.join("device")).ok()?;
+    let address = device.file_name()?.to_string_lossy().into_owned();
+    let is_pci = address.split([':', '.']).count() == 4 && address.contains(':');
+    is_pci.then(|| format!("pci-{}", address.replace(':', "_")))
+}
+
+fn read_card_outputs(card_dir: &Path, card: &Card, bus: Option<&str>) -> Vec<AudioOutput> {
+    let Ok(entries) = std::fs::read_dir(card_dir) else {
+        return Vec::new();
+    };
+    let mut devices: Vec<u32> = Vec::new();
+    let mut elds: Vec<(u32, u32, PathBuf)> = Vec::new();
+    for entry in entries.filter_map(|e| e.ok()) {
+        let name = entry.file_name().to_string_lossy().into_owned();
+        if let Some(device) = name.strip_prefix("pcm").and_then(|n| n.strip_suffix('p')) {
+            if let Ok(device) = device.parse() {
+                devices.push(device);
+            }
+        } else if let Some((codec, pin)) = name.strip_prefix("eld#").and_then(|n| n.split_once('.'))
+        {
+            if let (Ok(codec), Ok(pin)) = (codec.parse(), pin.parse()) {
+                elds.push((codec, pin, entry.path()));
+            }
+        }
+    }
+    devices.sort_unstable();
+    elds.sort();
+
+    let mut outputs: Vec<AudioOutput> = devices
+        .into_iter()
+        .map(|device| {
+            let info = std::fs::read_to_string(card_dir.join(format!("pcm{}p/info", device)))
+                .unwrap_or_default();
+            let field = |key: &str| {
+                info.lines()
+                    .find_map(|l| l.strip_prefix(key).and_then(|v| v.strip_prefix(": ")))
+                    .map(str::trim)
+                    .filter(|v| !v.is_empty())
+            };
+            let name = field("name").or_else(|| field("id")).unwrap_or("Unknown").to_string();
+            let kind = output_kind(&name, &card.driver);
+            AudioOutput {
+                card: card.index,
+                card_name: card.name.clone(),
+                device,
+                name,
+                kind,
+                connected: None,
+                sink_name: None,
+            }
+        })
+        .collect();
+
+    // ELD files exist per HDMI pin. When there is one per HDMI PCM they are in
+    // the same order; otherwise only "nothing attached" can be concluded.
+    let monitors: Vec<bool> = elds.iter().map(|(_, _, path)| monitor_present(path)).collect();
+    let hdmi: Vec<usize> = outputs
+        .iter()
+        .enumerate()
+        .filter(|(_, o)| o.kind == AudioOutputKind::Hdmi)
+        .map(|(i, _)| i)
+        .collect();
+    if monitors.len() == hdmi.len() {
+        for (&i, &present) in hdmi.iter().zip(&monitors) {
+            outputs[i].connected = Some(present);
+        }
+    } else if !monitors.iter().any(|&present| present) {
+        for &i in &hdmi {
+            outputs[i].connected = Some(false);
+        }
+    }
+
+    // Sink names follow the ALSA card profiles: the first analog and digital
+    // outputs, and HDMI outputs numbered hdmi-stereo, hdmi-stereo-extra1, ...
+    if let Some(bus) = bus {
+        let mut hdmi_index = 0;
+        let mut seen = Vec::new();
+        for output in &mut outputs {
+            let profile = match output.kind {
+                AudioOutputKind::Hdmi => {
+                    hdmi_index += 1;
+                    match hdmi_index {
+                        1 => "hdmi-stereo".to_string(),
+                        n => format!("hdmi-stereo-extra{}", n - 1),
+                    }
+                }
+                AudioOutputKind::Analog if !seen.contains(&AudioOutputKind::Analog) => {
+                    "analog-stereo".to_string()
+                }
+                AudioOutputKind::Digital if !seen.contains(&AudioOutputKind::Digital) => {
+                    "iec958-stereo".to_string()
+                }
+                _ => continue,
+            };
+            seen.push(output.kind);
+            output.sink_name = Some(format!("alsa_output.{}.{}", bus, profile));
+        }
+    }
+    outputs
+}
+
+fn output_kind(name: &str, driver: &str) -> AudioOutputKind {
+    let lower = name.to_lowercase();
+    if lower.contains("hdmi") || lower.contains("displayport") || lower.starts_with("dp") {
+        AudioOutputKind::Hdmi
+    } else if lower.contains("digital") || lower.contains("spdif") || lower.contains("iec958") {
+        AudioOutputKind::Digital
+    } else if driver == "USB-Audio" {
+        AudioOutputKind::Usb
+    } else if lower.contains("analog") || driver == "HDA-Intel" {
+        AudioOutputKind::Analog
+    } else {
+        AudioOutputKind::Other
+    }
+}
+
+/// Whether an ELD file reports an attached display with valid audio data
+fn monitor_present(path: &Path) -> bool {
+    let Ok(content) = std::fs::read_to_string(path) else {
+        return false;
+    };
+    let flag = |key: &str| {
+        content.lines().any(|line| {
+            let mut fields = line.split_whitespace();
+            fields.next() == Some(key) && fields.next() == Some("1")
+        })
+    };
+    flag("monitor_present") && flag("eld_valid")
+}
+
+/// Parse the server name, version and default sink from `pactl info`
+fn parse_pactl_info(output: &str) -> Option<ServerDefault> {
+    let field = |key: &str| output.lines().find_map(|l| l.strip_prefix(key)).map(str::trim);
+    let sink = field("Default Sink:")?.to_string();
+    // PipeWire answers as "PulseAudio (on PipeWire 1.0.5)"
+    let name = field("Server Name:").unwrap_or_default();
+    let (server, version) = match name.split_once("(on PipeWire ") {
+        Some((_, version)) => ("pipewire", Some(version.trim_end_matches(')').to_string())),
+        None => ("pulseaudio", field("Server Version:").map(str::to_string)),
+    };
+    Some(ServerDefault { server: server.to_string(), version, sink: Some(sink) })
+}
+
+/// Parse the PipeWire version from `pw-cli info 0`, which describes the core
+fn parse_pw_cli_info(output: &str) -> Option<ServerDefault> {
+    let version = output.lines().find_map(|line| {
+        let value = line.trim_start_matches(['*', ' ', '\t']).strip_prefix("version:")?;
+        Some(value.trim().trim_matches('"').to_string())
+    })?;
+    Some(ServerDefault { server: "pipewire".to_string(), version: Some(version), sink: None })
+}
+
+/// Card and device of the ALSA default PCM; later files override earlier ones
+fn alsa_default(config: &[PathBuf], cards: &[Card]) -> (u32, u32) {
+    let mut card = cards.first().map_or(0, |c| c.index);
+    let mut device = 0;
+    for path in config {
+        let Ok(content) = std::fs::read_to_string(path) else {
+            continue;
+        };
+        for line in content.lines() {
+            let mut fields = line.split_whitespace();
+            match (fields.next(), fields.next()) {
+                (Some("defaults.pcm.card"), Some(value)) => {
+                    let value = value.trim_matches('"');
+                    if let Some(index) = value
+                        .parse()
+                        .ok()
+                        .or_else(|| cards.iter().find(|c| c.id == value).map(|c| c.index))
+                    {
+                        card = index;
+                    }
+                }
+                (Some("defaults.pcm.device"), Some(value)) => {
+                    if let Ok(value) = value.trim_matches('"').parse() {
+                        device = value;
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+    (card, device)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    const CARDS: &str = " 0 [PCH            ]: HDA-Intel - HDA Intel PCH
+                      HDA Intel PCH at 0xa1230000 irq 142
+ 1 [NVidia         ]: HDA-Intel - HDA NVidia
+                      HDA NVidia at 0xa3080000 irq 17
+";
+
+    fn write_pcm(card_dir: &Path, device: u32, name: &str) {
+        let dir = card_dir.join(format!("pcm{}p", device));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(
+            dir.join("info"),
+            format!(
+                "card: 0\ndevice: {}\nid: {}\nname: {}\nstream: PLAYBACK\n",
+                device, name, name
+            ),
+        )
+        .unwrap();
+    }
+
+    fn write_eld(card_dir: &Path, name: &str, present: bool) {
+        let flag = if present { 1 } else { 0 };
+        std::fs::write(
+            card_dir.join(name),
+            format!("monitor_present\t\t{}\neld_valid\t\t{}\n", flag, flag),
+        )
+        .unwrap();
+    }
+
+    /// Analog PCH card plus an NVIDIA card with a display on its second HDMI output
+    fn fixture() -> TempDir {
+        let root = TempDir::new().unwrap();
+        let asound = root.path().join("asound");
+        std::fs::create_dir_all(&asound).unwrap();
+        std::fs::write(asound.join("cards"), CARDS).unwrap();
+        write_pcm(&asound.join("card0"), 0, "ALC3246 Analog");
+        let nvidia = asound.join("card1");
+        write_pcm(&nvidia, 3, "HDMI 0");
+        write_pcm(&nvidia, 7, "HDMI 1");
+        write_eld(&nvidia, "eld#0.0", false);
+        write_eld(&nvidia, "eld#0.1", true);
+
+        for (card, address) in [(0, "0000:00:1f.3"), (1, "0000:01:00.1")] {
+            let device = root.path().join("devices").join(address);
+            let class = root.path().join(format!("sound/card{}", card));
+            std::fs::create_dir_all(&device).unwrap();
+            std::fs::create_dir_all(&class).unwrap();
+            std::os::unix::fs::symlink(&device, class.join("device")).unwrap();
+        }
+        root
+    }
+
+    #[test]
+    fn test_disconnected_hdmi_default_is_flagged() {
+        let root = fixture();
+        let server = ServerDefault {
+            server: "pipewire".to_string(),
+            version: None,
+            sink: Some("alsa_output.pci-0000_01_00.1.hdmi-stereo".to_string()),
+        };
+        let routing = read_audio_routing(
+            &root.path().join("asound"),
+            &root.path().join("sound"),
+            Some(server),
+            &[],
+        )
+        .unwrap();
+
+        assert_eq!(routing.outputs.len(), 3);
+        let current = routing.current_output().unwrap();
+        assert_eq!((current.card, current.device, current.connected), (1, 3, Some(false)));
+        assert_eq!(routing.outputs[0].kind, AudioOutputKind::Analog);
+        assert_eq!(
+            routing.outputs[0].sink_name.as_deref(),
+            Some("alsa_output.pci-0000_00_1f.3.analog-stereo")
+        );
+
+        let issue = routing.routing_issue().unwrap();
+        assert!(
+            issue
+                .contains("pactl set-default-sink alsa_output.pci-0000_01_00.1.hdmi-stereo-extra1"),
+            "{}",
+            issue
+        );
+    }
+
+    #[test]
+    fn test_alsa_default_from_asoundrc() {
+        let root = fixture();
+        let asoundrc = root.path().join(".asoundrc");
+        std::fs::write(
+            &asoundrc,
+            "defaults.pcm.card NVidia\ndefaults.pcm.device 7\ndefaults.ctl.card 1\n",
+        )
+        .unwrap();
+        let routing = read_audio_routing(
+            &root.path().join("asound"),
+            &root.path().join("sound"),
+            None,
+            &[asoundrc],
+        )
+        .unwrap();
+
+        assert_eq!(routing.sound_server, "alsa");
+        assert_eq!(routing.current_output().unwrap().connected, Some(true));
+        assert_eq!(routing.routing_issue(), None);
+    }
+
+    #[test]
+    fn test_parse_pactl_info() {
+        let info = "Server String: /run/user/1000/pulse/native\nServer Name: PulseAudio (on PipeWire 1.0.5)\nDefault Sink: auto_null\n";
+        let default = parse_pactl_info(info).unwrap();
+        assert_eq!(default.server, "pipewire");
+        assert_eq!(default.version.as_deref(), Some("1.0.5"));
+        assert_eq!(default.sink.as_deref(), Some("auto_null"));
+
+        let core = "\tid: 0\n\ttype: PipeWire:Interface:Core/4\n*\tuser-name: \"alice\"\n*\tversion: \"1.2.7\"\n*\tname: \"pipewire-0\"\n";
+        let pipewire = parse_pw_cli_info(core).unwrap();
+        assert_eq!(
+            (pipewire.server.as_str(), pipewire.version.as_deref()),
+            ("pipewire", Some("1.2.7"))
+        );
+        assert_eq!(pipewire.sink, None);
+    }
+}
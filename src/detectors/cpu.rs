@@ -0,0 +1,291 @@
+//! CPU topology, caches and frequency scaling from procfs and sysfs
+//!
+//! SMBIOS describes each socket with whatever the firmware filled in, often
+//! without cache sizes, and lshw reports a CPU "width" rather than cores.
+//! The kernel knows the real topology: every logical CPU has a directory
+//! under `/sys/devices/system/cpu` with its package and core IDs, its cache
+//! hierarchy and its cpufreq policy. `/proc/cpuinfo` adds the model name and
+//! the feature flags, and stands in for the topology where sysfs lacks it.
+
+use super::attributes::read_trimmed;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::Path;
+
+/// A CPU as the kernel describes it
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct KernelCpu {
+    pub model: Option<String>,
+    pub vendor: Option<String>,
+    pub sockets: u32,
+    /// Physical cores across all sockets
+    pub cores: u32,
+    /// Online logical CPUs
+    pub threads: u32,
+    pub base_frequency_mhz: Option<f64>,
+    pub max_frequency_mhz: Option<f64>,
+    /// Total size of each cache level across all instances, in bytes; L1
+    /// counts data and instruction caches together
+    pub cache_l1: Option<u64>,
+    pub cache_l2: Option<u64>,
+    pub cache_l3: Option<u64>,
+    /// cpufreq governor of the first CPU, e.g. "schedutil" or "performance"
+    pub scaling_governor: Option<String>,
+    pub flags: Vec<String>,
+}
+
+/// Read the CPU from a `devices/system/cpu` directory and cpuinfo content
+///
+/// Returns None when neither lists a processor.
+pub fn read_cpu(sys_cpu: &Path, cpuinfo: &str) -> Option<KernelCpu> {
+    let processors = parse_cpuinfo(cpuinfo);
+    let online = online_cpus(sys_cpu);
+    if processors.is_empty() && online.is_empty() {
+        return None;
+    }
+    let first = processors.first().cloned().unwrap_or_default();
+
+    let (sockets, cores) = sysfs_topology(sys_cpu, &online)
+        .or_else(|| cpuinfo_topology(&processors))
+        .unwrap_or_else(|| {
+            let threads = online.len().max(processors.len()) as u32;
+            (1, threads)
+        });
+    let threads = if online.is_empty() { processors.len() } else { online.len() };
+    let caches = cache_totals(sys_cpu, &online);
+    let cpufreq = sys_cpu.join("cpu0/cpufreq");
+    let khz_to_mhz =
+        |file: &str| read_trimmed(&cpufreq.join(file))?.parse::<f64>().ok().map(|khz| khz / 1000.0);
+
+    Some(KernelCpu {
+        model: first.get("model name").or_else(|| first.get("cpu")).cloned(),
+        vendor: cpu_vendor(&first),
+        sockets,
+        cores: cores.max(1),
+        threads: (threads as u32).max(1),
+        base_frequency_mhz: khz_to_mhz("base_frequency"),
+        max_frequency_mhz: khz_to_mhz("cpuinfo_max_freq"),
+        cache_l1: caches.get(&1).copied(),
+        cache_l2: caches.get(&2).copied(),
+        cache_l3: caches.get(&3).copied(),
+        scaling_governor: read_trimmed(&cpufreq.join("scaling_governor")),
+        flags: first
+            .get("flags")
+            .or_else(|| first.get("Features"))
+            .map(|flags| flags.split_whitespace().map(str::to_string).collect())
+            .unwrap_or_default(),
+    })
+}
+
+/// Fields of each processor block in /proc/cpuinfo
+///
+/// Blocks without a numeric "processor" field, like the trailing hardware
+/// block on ARM, are skipped.
+fn parse_cpuinfo(content: &str) -> Vec<BTreeMap<String, String>> {
+    content
+        .split("\n\n")
+        .map(|block| {
+            block
+                .lines()
+                .filter_map(|line| {
+                    let (key, value) = line.split_once(':')?;
+                    Some((key.trim().to_string(), value.trim().to_string()))
+                })
+                .collect::<BTreeMap<_, _>>()
+        })
+        .filter(|fields| fields.get("processor").is_some_and(|n| n.parse::<u32>().is_ok()))
+        .collect()
+}
+
+/// Vendor from x86 `vendor_id` or the ARM implementer code
+fn cpu_vendor(fields: &BTreeMap<String, String>) -> Option<String> {
+    if let Some(vendor) = fields.get("vendor_id") {
+        return Some(
+            match vendor.as_str() {
+                "GenuineIntel" => "Intel",
+                "AuthenticAMD" => "AMD",
+                "HygonGenuine" => "Hygon",
+                "CentaurHauls" => "Centaur",
+                other => other,
+            }
+            .to_string(),
+        );
+    }
+    let implementer = fields.get("CPU implementer")?;
+    let name = match implementer.to_ascii_lowercase().as_str() {
+        "0x41" => "ARM",
+        "0x42" => "Broadcom",
+        "0x43" => "Cavium",
+        "0x46" => "Fujitsu",
+        "0x48" => "HiSilicon",
+        "0x4e" => "NVIDIA",
+        "0x51" => "Qualcomm",
+        "0x61" => "Apple",
+        "0xc0" => "Ampere",
+        _ => return None,
+    };
+    Some(name.to_string())
+}
+
+/// Directory names of the online logical CPUs, e.g. "cpu0"
+fn online_cpus(sys_cpu: &Path) -> Vec<String> {
+    let Ok(entries) = std::fs::read_dir(sys_cpu) else {
+        return Vec::new();
+    };
+    let mut cpus: Vec<(u32, String)> = entries
+        .flatten()
+        .filter_map(|entry| {
+            let name = entry.file_name().to_string_lossy().into_owned();
+            let index = name.strip_prefix("cpu")?.parse().ok()?;
+            // cpu0 usually cannot be offlined and has no "online" file
+            let offline = read_trimmed(&entry.path().join("online")).as_deref() == Some("0");
+            (!offline).then_some((index, name))
+        })
+        .collect();
+    cpus.sort();
+    cpus.into_iter().map(|(_, name)| name).collect()
+}
+
+/// Sockets and cores from each CPU's `topology` directory
+fn sysfs_topology(sys_cpu: &Path, online: &[String]) -> Option<(u32, u32)> {
+    let mut packages = BTreeSet::new();
+    let mut cores = BTreeSet::new();
+    for cpu in online {
+        let topology = sys_cpu.join(cpu).join("topology");
+        let id = |file: &str| read_trimmed(&topology.join(file));
+        let package = id("physical_package_id")?;
+        // Core IDs repeat across the dies of a package
+        cores.insert((package.clone(), id("die_id"), id("core_id")?));
+        packages.insert(package);
+    }
+    (!cores.is_empty()).then_some((packages.len() as u32, cores.len() as u32))
+}
+
+/// Sockets and cores from the "physical id" and "core id" cpuinfo fields
+fn cpuinfo_topology(processors: &[BTreeMap<String, String>]) -> Option<(u32, u32)> {
+    let mut packages = BTreeSet::new();
+    let mut cores = BTreeSet::new();
+    for fields in processors {
+        let package = fields.get("physical id")?;
+        cores.insert((package, fields.get("core id")?));
+        packages.insert(package);
+    }
+    (!cores.is_empty()).then_some((packages.len() as u32, cores.len() as u32))
+}
+
+/// Total bytes of each cache level, counting every shared cache once
+fn cache_totals(sys_cpu: &Path, online: &[String]) -> BTreeMap<u8, u64> {
+    let mut instances = BTreeMap::new();
+    for cpu in online {
+        let cache = sys_cpu.join(cpu).join("cache");
+        let Ok(entries) = std::fs::read_dir(&cache) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let dir = entry.path();
+            let read = |file: &str| read_trimmed(&dir.join(file));
+            let (Some(level), Some(size)) = (
+                read("level").and_then(|level| level.parse::<u8>().ok()),
+                read("size").and_then(|size| parse_cache_size(&size)),
+            ) else {
+                continue;
+            };
+            // The same cache appears under every CPU sharing it
+            let key = (level, read("type"), read("shared_cpu_list").unwrap_or_else(|| cpu.clone()));
+            instances.insert(key, size);
+        }
+    }
+
+    let mut totals = BTreeMap::new();
+    for ((level, _, _), size) in instances {
+        *totals.entry(level).or_insert(0) += size;
+    }
+    totals
+}
+
+/// Parse a sysfs cache size such as "48K" or "32M"
+fn parse_cache_size(size: &str) -> Option<u64> {
+    let (digits, multiplier) = match size.chars().last()? {
+        'K' => (&size[..size.len() - 1], 1024),
+        'M' => (&size[..size.len() - 1], 1024 * 1024),
+        _ => (size, 1),
+    };
+    digits.parse::<u64>().ok().map(|value| value * multiplier)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn write(dir: &Path, file: &str, content: &str) {
+        std::fs::create_dir_all(dir).unwrap();
+        std::fs::write(dir.join(file), content).unwrap();
+    }
+
+    const CPUINFO: &str = "processor\t: 0\nvendor_id\t: AuthenticAMD\n\
+        model name\t: AMD Ryzen 5 5600X 6-Core Processor\nflags\t\t: fpu vme sse2 avx2\n\n\
+        processor\t: 1\nvendor_id\t: AuthenticAMD\n\n";
+
+    /// One socket, two cores with two threads each; cpu3 is offline
+    fn fixture() -> TempDir {
+        let root = TempDir::new().unwrap();
+        for (cpu, core) in [(0, 0), (1, 0), (2, 1), (3, 1)] {
+            let dir = root.path().join(format!("cpu{}", cpu));
+            write(&dir.join("topology"), "physical_package_id", "0\n");
+            write(&dir.join("topology"), "core_id", &format!("{}\n", core));
+            if cpu > 0 {
+                write(&dir, "online", if cpu == 3 { "0\n" } else { "1\n" });
+            }
+            let siblings = if core == 0 { "0-1" } else { "2-3" };
+            for (index, level, kind, size, shared) in [
+                (0, 1, "Data", "32K", siblings),
+                (1, 1, "Instruction", "32K", siblings),
+                (2, 2, "Unified", "512K", siblings),
+                (3, 3, "Unified", "32768K", "0-3"),
+            ] {
+                let cache = dir.join(format!("cache/index{}", index));
+                write(&cache, "level", &format!("{}\n", level));
+                write(&cache, "type", &format!("{}\n", kind));
+                write(&cache, "size", &format!("{}\n", size));
+                write(&cache, "shared_cpu_list", &format!("{}\n", shared));
+            }
+        }
+        let cpufreq = root.path().join("cpu0/cpufreq");
+        write(&cpufreq, "cpuinfo_max_freq", "4650000\n");
+        write(&cpufreq, "scaling_governor", "schedutil\n");
+        root
+    }
+
+    #[test]
+    fn test_read_topology_and_caches() {
+        let root = fixture();
+        let cpu = read_cpu(root.path(), CPUINFO).unwrap();
+
+        assert_eq!(cpu.model.as_deref(), Some("AMD Ryzen 5 5600X 6-Core Processor"));
+        assert_eq!(cpu.vendor.as_deref(), Some("AMD"));
+        assert_eq!((cpu.sockets, cpu.cores, cpu.threads), (1, 2, 3));
+        assert_eq!(cpu.cache_l1, Some(2 * 64 * 1024));
+        assert_eq!(cpu.cache_l2, Some(2 * 512 * 1024));
+        assert_eq!(cpu.cache_l3, Some(32 * 1024 * 1024));
+        assert_eq!(cpu.max_frequency_mhz, Some(4650.0));
+        assert_eq!(cpu.base_frequency_mhz, None);
+        assert_eq!(cpu.scaling_governor.as_deref(), Some("schedutil"));
+        assert_eq!(cpu.flags, ["fpu", "vme", "sse2", "avx2"]);
+    }
+
+    #[test]
+    fn test_cpuinfo_alone() {
+        let root = TempDir::new().unwrap();
+        let cpuinfo = "processor\t: 0\nphysical id\t: 0\ncore id\t: 0\nFeatures\t: fp asimd\n\
+            CPU implementer\t: 0x41\n\nprocessor\t: 1\nphysical id\t: 0\ncore id\t: 1\n\n\
+            Hardware\t: BCM2835\n";
+        let cpu = read_cpu(&root.path().join("missing"), cpuinfo).unwrap();
+        assert_eq!(cpu.vendor.as_deref(), Some("ARM"));
+        assert_eq!((cpu.sockets, cpu.cores, cpu.threads), (1, 2, 2));
+        assert_eq!(cpu.flags, ["fp", "asimd"]);
+        assert_eq!(cpu.cache_l3, None);
+
+        assert!(read_cpu(&root.path().join("missing"), "").is_none());
+    }
+}
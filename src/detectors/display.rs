@@ -0,0 +1,218 @@
+//! Display connector detection from the DRM subsystem
+//!
+//! Each GPU appears as `/sys/class/drm/cardN` with its connectors as
+//! `cardN-<type>-<index>` siblings. The `device` link of a card resolves to
+//! the PCI device, which is how connectors are matched to GPUs found by the
+//! detection tools. DisplayPort connectors also link their AUX channel, whose
+//! `/dev/drm_dp_auxN` device reads the attached sink's DPCD registers.
+
+use crate::hardware::{DisplayConnector, DisplayOutputs};
+use std::collections::HashMap;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::Path;
+
+/// Connector types that are not external ports (internal panels, virtual outputs)
+const INTERNAL_CONNECTORS: &[&str] = &["eDP", "LVDS", "DSI", "Virtual", "Writeback"];
+
+/// External analog connector types without a hotplug detect line; the kernel polls them
+const POLLED_CONNECTORS: &[&str] =
+    &["VGA", "DVI-A", "Composite", "SVIDEO", "Component", "9PinDIN", "TV"];
+
+/// DPCD register holding the sink's MST capability (MSTM_CAP)
+const DPCD_MSTM_CAP: u64 = 0x21;
+
+/// Read display outputs for every DRM card, keyed by PCI address (0000:01:00.0)
+///
+/// `dev_root` holds the DP AUX devices, normally `/dev`.
+pub fn read_display_outputs(drm_root: &Path, dev_root: &Path) -> HashMap<String, DisplayOutputs> {
+    let mut outputs = HashMap::new();
+    let Ok(entries) = std::fs::read_dir(drm_root) else {
+        return outputs;
+    };
+
+    let mut names: Vec<String> = entries
+        .filter_map(|e| e.ok())
+        .map(|e| e.file_name().to_string_lossy().into_owned())
+        .collect();
+    names.sort();
+
+    for card in names.iter().filter(|n| is_card_name(n)) {
+        let card_path = drm_root.join(card);
+        let Some(pci_address) = std::fs::canonicalize(card_path.join("device"))
+            .ok()
+            .and_then(|p| p.file_name().map(|n| n.to_string_lossy().into_owned()))
+        else {
+            continue;
+        };
+
+        let prefix = format!("{}-", card);
+        let mut connectors = Vec::new();
+        let mut mst_caps = Vec::new();
+        for (entry, name) in names.iter().filter_map(|n| n.strip_prefix(&prefix).map(|m| (n, m))) {
+            let connector = read_connector(&drm_root.join(entry), name);
+            if connector.connector_type == "DP" && connector.connected {
+                mst_caps.push(read_mst_cap(&drm_root.join(entry), dev_root));
+            }
+            connectors.push(connector);
+        }
+        // Any MST sink counts; an unreadable sink, or none at all, leaves it unknown
+        let dp_mst = if mst_caps.contains(&Some(true)) {
+            Some(true)
+        } else if !mst_caps.is_empty() && mst_caps.iter().all(|cap| *cap == Some(false)) {
+            Some(false)
+        } else {
+            None
+        };
+
+        outputs.insert(pci_address, DisplayOutputs { connectors, dp_mst });
+    }
+
+    outputs
+}
+
+/// Whether a DRM entry is a card (card0) rather than a connector (card0-DP-1)
+fn is_card_name(name: &str) -> bool {
+    name.strip_prefix("card")
+        .is_some_and(|n| !n.is_empty() && n.chars().all(|c| c.is_ascii_digit()))
+}
+
+fn read_connector(path: &Path, name: &str) -> DisplayConnector {
+    // Connector names are <type>-<index>; types may contain dashes (HDMI-A-1)
+    let connector_type = name.rsplit_once('-').map(|(t, _)| t).unwrap_or(name).to_string();
+    let connected = std::fs::read_to_string(path.join("status"))
+        .map(|s| s.trim() == "connected")
+        .unwrap_or(false);
+    let max_mode = std::fs::read_to_string(path.join("modes")).ok().and_then(|m| max_mode(&m));
+    let external = !INTERNAL_CONNECTORS.contains(&connector_type.as_str());
+    let hotplug = external && !POLLED_CONNECTORS.contains(&connector_type.as_str());
+
+    DisplayConnector {
+        name: name.to_string(),
+        connector_type,
+        connected,
+        max_mode,
+        hotplug,
+        external,
+    }
+}
+
+/// Pick the mode with the largest pixel count from a `modes` listing
+fn max_mode(modes: &str) -> Option<String> {
+    modes
+        .lines()
+        .filter_map(|line| {
+            let mode = line.trim();
+            let (w, h) = mode.split_once('x')?;
+            let h: String = h.chars().take_while(|c| c.is_ascii_digit()).collect();
+            Some((w.parse::<u64>().ok()? * h.parse::<u64>().ok()?, mode.to_string()))
+        })
+        .max_by_key(|(pixels, _)| *pixels)
+        .map(|(_, mode)| mode)
+}
+
+/// Read whether the sink on a DP connector supports Multi-Stream Transport
+///
+/// The AUX devices are normally root-only; None means the DPCD could not be read.
+fn read_mst_cap(connector_path: &Path, dev_root: &Path) -> Option<bool> {
+    let aux = std::fs::read_dir(connector_path)
+        .ok()?
+        .filter_map(|e| e.ok())
+        .map(|e| e.file_name())
+        .find(|name| name.to_string_lossy().starts_with("drm_dp_aux"))?;
+    let mut device = std::fs::File::open(dev_root.join(aux)).ok()?;
+    let mut cap = [0u8];
+    device.seek(SeekFrom::Start(DPCD_MSTM_CAP)).ok()?;
+    device.read_exact(&mut cap).ok()?;
+    Some(cap[0] & 0x01 != 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn write_connector(root: &Path, name: &str, status: &str, modes: &str) {
+        let dir = root.join(name);
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("status"), status).unwrap();
+        std::fs::write(dir.join("modes"), modes).unwrap();
+    }
+
+    #[test]
+    fn test_read_display_outputs() {
+        let sys = TempDir::new().unwrap();
+        let pci = sys.path().join("devices/0000:00:02.0");
+        let drm = sys.path().join("drm");
+        let dev = sys.path().join("dev");
+        std::fs::create_dir_all(&pci).unwrap();
+        std::fs::create_dir_all(drm.join("card0")).unwrap();
+        std::fs::create_dir_all(&dev).unwrap();
+        std::os::unix::fs::symlink(&pci, drm.join("card0/device")).unwrap();
+
+        write_connector(&drm, "card0-eDP-1", "connected\n", "1920x1080\n1280x720\n");
+        write_connector(&drm, "card0-HDMI-A-1", "disconnected\n", "");
+        write_connector(&drm, "card0-DP-1", "connected\n", "2560x1440\n3840x2160\n1920x1080\n");
+        write_dpcd(&drm.join("card0-DP-1"), &dev, "drm_dp_aux0", 0x01);
+
+        let outputs = read_display_outputs(&drm, &dev);
+        let gpu = &outputs["0000:00:02.0"];
+
+        assert_eq!(gpu.connectors.len(), 3);
+        assert_eq!(gpu.dp_mst, Some(true));
+
+        let dp = gpu.connectors.iter().find(|c| c.name == "DP-1").unwrap();
+        assert!(dp.connected && dp.external && dp.hotplug);
+        assert_eq!(dp.max_mode.as_deref(), Some("3840x2160"));
+
+        let hdmi = gpu.connectors.iter().find(|c| c.name == "HDMI-A-1").unwrap();
+        assert_eq!(hdmi.connector_type, "HDMI-A");
+        assert!(!hdmi.connected);
+        assert_eq!(hdmi.max_mode, None);
+
+        let edp = gpu.connectors.iter().find(|c| c.name == "eDP-1").unwrap();
+        assert!(!edp.external && !edp.hotplug);
+
+        // An analog port is external but has to be polled
+        write_connector(&drm, "card0-VGA-1", "disconnected\n", "");
+        let outputs = read_display_outputs(&drm, &dev);
+        let vga = outputs["0000:00:02.0"].connectors.iter().find(|c| c.name == "VGA-1").unwrap();
+        assert!(vga.external && !vga.hotplug);
+
+        // Without a connected DisplayPort sink nothing was read
+        std::fs::write(drm.join("card0-DP-1/status"), "disconnected\n").unwrap();
+        assert_eq!(read_display_outputs(&drm, &dev)["0000:00:02.0"].dp_mst, None);
+    }
+
+    #[test]
+    fn test_dp_mst_from_dpcd() {
+        let sys = TempDir::new().unwrap();
+        let dev = sys.path().join("dev");
+        std::fs::create_dir_all(&dev).unwrap();
+
+        // A single-stream monitor on a GPU that could drive MST
+        let single_stream = sys.path().join("card0-DP-1");
+        std::fs::create_dir_all(&single_stream).unwrap();
+        write_dpcd(&single_stream, &dev, "drm_dp_aux1", 0x00);
+        assert_eq!(read_mst_cap(&single_stream, &dev), Some(false));
+
+        let unreadable = sys.path().join("card0-DP-2");
+        std::fs::create_dir_all(unreadable.join("drm_dp_aux2")).unwrap();
+        assert_eq!(read_mst_cap(&unreadable, &dev), None);
+    }
+
+    /// Link an AUX channel to a connector with the sink's MSTM_CAP register
+    fn write_dpcd(connector: &Path, dev: &Path, aux: &str, mstm_cap: u8) {
+        std::fs::create_dir_all(connector.join(aux)).unwrap();
+        let mut dpcd = vec![0u8; DPCD_MSTM_CAP as usize + 1];
+        dpcd[DPCD_MSTM_CAP as usize] = mstm_cap;
+        std::fs::write(dev.join(aux), dpcd).unwrap();
+    }
+
+    #[test]
+    fn test_is_card_name() {
+        assert!(is_card_name("card0"));
+        assert!(is_card_name("card12"));
+        assert!(!is_card_name("card0-DP-1"));
+        assert!(!is_card_name("renderD128"));
+    }
+}
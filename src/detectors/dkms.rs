@@ -0,0 +1,112 @@
+//! Out-of-tree kernel modules managed by DKMS
+//!
+//! `dkms status` lists every registered module version with its state for
+//! each kernel it was built for, as "nvidia/550.54.14, 6.9.3-arch1-1, x86_64:
+//! installed" (dkms 3) or "nvidia, 550.54.14, 6.9.3-arch1-1, x86_64:
+//! installed" (dkms 2). A module that is installed for earlier kernels but
+//! not for the running one did not rebuild after the last kernel update, and
+//! the hardware it drives stays without a driver until it does.
+
+use crate::hardware::{DkmsModuleStatus, DkmsState};
+
+/// Run `dkms status`; None when DKMS is not installed
+pub fn query_dkms_status() -> Option<String> {
+    let output =
+        std::process::Command::new("dkms").arg("status").env("LC_ALL", "C").output().ok()?;
+    output.status.success().then(|| String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Parse `dkms status` output of dkms 2 or 3
+pub fn parse_dkms_status(output: &str) -> Vec<DkmsModuleStatus> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let (fields, state) = line.split_once(": ")?;
+            // Warnings follow the state in parentheses
+            let state = match state.split_whitespace().next()? {
+                "added" => DkmsState::Added,
+                "built" => DkmsState::Built,
+                "installed" => DkmsState::Installed,
+                "broken" => DkmsState::Broken,
+                _ => return None,
+            };
+            let mut fields = fields.split(',').map(str::trim);
+            let first = fields.next()?;
+            let (module, version) = match first.split_once('/') {
+                Some((module, version)) => (module, version),
+                None => (first, fields.next()?),
+            };
+            Some(DkmsModuleStatus {
+                module: module.to_string(),
+                version: version.to_string(),
+                kernel: fields.next().filter(|kernel| !kernel.is_empty()).map(String::from),
+                state,
+            })
+        })
+        .collect()
+}
+
+/// Add a [`DkmsState::NotBuilt`] entry for each module installed for some
+/// kernel but not for `running_kernel`
+pub fn flag_failed_rebuilds(modules: &mut Vec<DkmsModuleStatus>, running_kernel: &str) {
+    let mut missing: Vec<(String, String)> = Vec::new();
+    for module in modules.iter().filter(|module| module.state == DkmsState::Installed) {
+        let current = modules.iter().any(|other| {
+            other.module == module.module
+                && other.kernel.as_deref() == Some(running_kernel)
+                && other.state == DkmsState::Installed
+        });
+        if !current && !missing.iter().any(|(name, _)| *name == module.module) {
+            missing.push((module.module.clone(), module.version.clone()));
+        }
+    }
+    modules.extend(missing.into_iter().map(|(module, version)| DkmsModuleStatus {
+        module,
+        version,
+        kernel: Some(running_kernel.to_string()),
+        state: DkmsState::NotBuilt,
+    }));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DKMS_STATUS: &str = "nvidia/550.54.14, 6.8.9-arch1-2, x86_64: installed
+nvidia/550.54.14, 6.9.3-arch1-1, x86_64: built
+v4l2loopback/0.13.1, 6.8.9-arch1-2, x86_64: installed (WARNING! Diff between built and installed module!)
+v4l2loopback/0.13.1, 6.9.3-arch1-1, x86_64: installed
+zfs, 2.2.4, 6.9.3-arch1-1, x86_64: installed
+openrazer-driver/3.8.0: added
+";
+
+    #[test]
+    fn test_parse_and_flag_failed_rebuilds() {
+        let mut modules = parse_dkms_status(DKMS_STATUS);
+        assert_eq!(modules.len(), 6);
+        assert_eq!(
+            modules[0],
+            DkmsModuleStatus {
+                module: "nvidia".to_string(),
+                version: "550.54.14".to_string(),
+                kernel: Some("6.8.9-arch1-2".to_string()),
+                state: DkmsState::Installed,
+            }
+        );
+        assert_eq!(modules[2].state, DkmsState::Installed);
+        assert_eq!((modules[4].module.as_str(), modules[4].version.as_str()), ("zfs", "2.2.4"));
+        assert_eq!((modules[5].kernel.as_deref(), modules[5].state), (None, DkmsState::Added));
+
+        flag_failed_rebuilds(&mut modules, "6.9.3-arch1-1");
+        assert_eq!(modules.len(), 7);
+        assert_eq!(
+            modules[6],
+            DkmsModuleStatus {
+                module: "nvidia".to_string(),
+                version: "550.54.14".to_string(),
+                kernel: Some("6.9.3-arch1-1".to_string()),
+                state: DkmsState::NotBuilt,
+            }
+        );
+    }
+}
@@ -5,7 +5,10 @@
 //! This provides complementary information to lshw, particularly for BIOS details
 //! and memory module specifications.
 
-use super::{DetectionData, DetectionResult, HardwareDetector};
+use super::{
+    sandbox, DetectionData, DetectionError, DetectionResult, HardwareDetector, Privileges,
+    RetryPolicy,
+};
 use crate::errors::{LxHwError, Result};
 use async_trait::async_trait;
 use log::{debug, error, warn};
@@ -221,15 +224,16 @@ impl HardwareDetector for DmidecodeDetector {
     async fn execute(&self) -> Result<Output> {
         debug!("Executing dmidecode hardware detection");
 
-        let output = tokio::process::Command::new("dmidecode")
-            .arg("-t") // Specify types to read
-            .arg("system,baseboard,bios,processor,memory") // Focus on key hardware types
-            .arg("-q") // Quiet mode - less verbose output
-            .output()
-            .await
-            .map_err(|_e| LxHwError::SystemCommandError {
-                command: "dmidecode -t system,baseboard,bios,processor,memory -q".to_string()
-            })?;
+        let output = sandbox::output(
+            tokio::process::Command::new("dmidecode")
+                .arg("-t") // Specify types to read
+                .arg("system,baseboard,bios,processor,memory") // Focus on key hardware types
+                .arg("-q"), // Quiet mode - less verbose output
+        )
+        .await
+        .map_err(|_e| LxHwError::SystemCommandError {
+            command: "dmidecode -t system,baseboard,bios,processor,memory -q".to_string(),
+        })?;
 
         debug!("dmidecode execution completed with status: {}", output.status);
 
@@ -262,14 +266,14 @@ impl HardwareDetector for DmidecodeDetector {
             for line in stderr_str.lines() {
                 if !line.trim().is_empty() {
                     warn!("dmidecode stderr: {}", line);
-                    errors.push(format!("Warning: {}", line));
+                    errors.push(DetectionError::from_stderr_line(line));
                 }
             }
         }
 
         // Parse stdout text
         if output.stdout.is_empty() {
-            errors.push("Empty output from dmidecode".to_string());
+            errors.push(DetectionError::EmptyOutput { tool: self.name().to_string() });
             return Ok(DetectionResult {
                 tool_name: self.name().to_string(),
                 success: false,
@@ -302,7 +306,7 @@ impl HardwareDetector for DmidecodeDetector {
             }
             Err(e) => {
                 error!("Failed to parse dmidecode text: {}", e);
-                errors.push(format!("Text parsing failed: {}", e));
+                errors.push(DetectionError::parse(format!("Text parsing failed: {}", e)));
                 Ok(DetectionResult {
                     tool_name: self.name().to_string(),
                     success: false,
@@ -317,6 +321,16 @@ impl HardwareDetector for DmidecodeDetector {
         // dmidecode is typically faster than lshw since it reads from /sys
         Duration::from_secs(15)
     }
+
+    fn privileges(&self) -> Privileges {
+        // DMI tables are readable by root only
+        Privileges::RootRequired
+    }
+
+    fn retry_policy(&self) -> RetryPolicy {
+        // Some BIOSes intermittently report busy DMI tables
+        RetryPolicy::flaky()
+    }
 }
 
 impl DmidecodeDetector {
@@ -611,7 +625,7 @@ impl DmidecodeDetector {
     fn generate_summary_with_errors(
         &self,
         data: &DmidecodeData,
-        errors: &[String],
+        errors: &[DetectionError],
     ) -> DmidecodeSummary {
         let total_memory_mb =
             data.memory_devices.iter().filter_map(|mem| mem.size_mb).map(|size| size as u64).sum();
@@ -625,9 +639,8 @@ impl DmidecodeDetector {
         // Detect privileged execution by checking for privilege-related errors
         // OR if we have comprehensive data (both system and BIOS info)
         let has_privilege_errors = errors.iter().any(|e| {
-            e.contains("Operation not permitted")
-                || e.contains("Permission denied")
-                || e.contains("/dev/mem")
+            matches!(e, DetectionError::PermissionDenied { .. })
+                || e.to_string().contains("/dev/mem")
         });
 
         let privileged_execution =
@@ -0,0 +1,176 @@
+//! Typed errors and warnings reported by detection tools
+//!
+//! Detectors keep going after many problems (missing privileges, a single
+//! unparseable section) and report them alongside whatever data they did
+//! collect. Keeping them typed lets retries, confidence scoring, and UI
+//! messages act on the category; they are rendered to text only for display.
+
+use crate::detectors::retry::{self, FailureKind};
+use crate::errors::LxHwError;
+use std::time::Duration;
+use thiserror::Error;
+
+/// Fragments of tool output that indicate missing privileges
+const PERMISSION_MARKERS: &[&str] =
+    &["permission denied", "operation not permitted", "access denied", "must be root"];
+
+/// A problem reported by a detection tool
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum DetectionError {
+    /// The tool is not installed
+    #[error("{tool} is not installed")]
+    NotFound { tool: String },
+
+    /// The tool lacked the privileges to read some or all data
+    #[error("Insufficient privileges: {message}")]
+    PermissionDenied { message: String },
+
+    /// The tool did not finish in time
+    #[error("Execution timed out after {after:?}")]
+    Timeout { after: Duration },
+
+    /// The tool's output could not be parsed
+    #[error("{message}{}", location(*line, *col))]
+    ParseError { message: String, line: Option<usize>, col: Option<usize> },
+
+    /// The tool exited unsuccessfully
+    #[error("{tool} execution failed{}: {stderr}", exit_code(*code))]
+    BadExit { tool: String, code: Option<i32>, stderr: String },
+
+    /// The tool ran but printed nothing
+    #[error("Empty output from {tool}")]
+    EmptyOutput { tool: String },
+
+    /// Non-fatal diagnostic printed by the tool
+    #[error("Warning: {0}")]
+    Warning(String),
+
+    /// Anything else
+    #[error("{0}")]
+    Other(String),
+}
+
+impl DetectionError {
+    /// Parse error without a known position
+    pub fn parse(message: impl Into<String>) -> Self {
+        Self::ParseError { message: message.into(), line: None, col: None }
+    }
+
+    /// Parse error from a JSON parser, keeping its position
+    pub fn json(context: &str, error: &serde_json::Error) -> Self {
+        // serde_json appends the position to its message; it is kept separately
+        let text = error.to_string();
+        let text = text.split(" at line ").next().unwrap_or_default();
+        Self::ParseError {
+            message: format!("{}: {}", context, text),
+            line: Some(error.line()),
+            col: Some(error.column()),
+        }
+    }
+
+    /// Classify a line a tool printed to stderr
+    pub fn from_stderr_line(line: &str) -> Self {
+        let lower = line.to_lowercase();
+        if PERMISSION_MARKERS.iter().any(|marker| lower.contains(marker)) {
+            Self::PermissionDenied { message: line.to_string() }
+        } else {
+            Self::Warning(line.to_string())
+        }
+    }
+
+    /// Classify an error returned while executing a tool
+    pub fn from_execution(tool: &str, error: &LxHwError) -> Self {
+        if let LxHwError::IoError(e) = error {
+            match e.kind() {
+                std::io::ErrorKind::NotFound => return Self::NotFound { tool: tool.to_string() },
+                std::io::ErrorKind::PermissionDenied => {
+                    return Self::PermissionDenied { message: e.to_string() }
+                }
+                _ => {}
+            }
+        }
+
+        let message = error.to_string();
+        if message.contains("No such file or directory") {
+            Self::NotFound { tool: tool.to_string() }
+        } else if PERMISSION_MARKERS.iter().any(|m| message.to_lowercase().contains(m)) {
+            Self::PermissionDenied { message }
+        } else {
+            Self::Other(message)
+        }
+    }
+
+    /// Whether retrying the tool could help
+    pub fn failure_kind(&self) -> FailureKind {
+        match self {
+            Self::Timeout { .. } => FailureKind::Transient,
+            Self::NotFound { .. }
+            | Self::PermissionDenied { .. }
+            | Self::ParseError { .. }
+            | Self::EmptyOutput { .. } => FailureKind::Permanent,
+            Self::BadExit { stderr, .. } => retry::classify_message(stderr),
+            Self::Warning(message) | Self::Other(message) => retry::classify_message(message),
+        }
+    }
+}
+
+fn location(line: Option<usize>, col: Option<usize>) -> String {
+    match (line, col) {
+        (Some(line), Some(col)) => format!(" (line {}, column {})", line, col),
+        (Some(line), None) => format!(" (line {})", line),
+        _ => String::new(),
+    }
+}
+
+fn exit_code(code: Option<i32>) -> String {
+    code.map(|code| format!(" with exit code {}", code)).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stderr_classification() {
+        let denied = DetectionError::from_stderr_line("/dev/mem: Operation not permitted");
+        assert!(matches!(denied, DetectionError::PermissionDenied { .. }));
+        assert!(denied.to_string().contains("Operation not permitted"));
+
+        let warning = DetectionError::from_stderr_line("WARNING: output may be incomplete");
+        assert_eq!(warning.to_string(), "Warning: WARNING: output may be incomplete");
+    }
+
+    #[test]
+    fn test_json_parse_error_keeps_position() {
+        let err = serde_json::from_str::<serde_json::Value>("{\n  \"a\": }").unwrap_err();
+        let error = DetectionError::json("JSON parsing failed", &err);
+
+        assert!(matches!(error, DetectionError::ParseError { line: Some(2), .. }));
+        assert!(error.to_string().starts_with("JSON parsing failed"));
+        assert!(error.to_string().contains("(line 2, column"));
+    }
+
+    #[test]
+    fn test_failure_kinds() {
+        assert_eq!(
+            DetectionError::Timeout { after: Duration::from_secs(5) }.failure_kind(),
+            FailureKind::Transient
+        );
+        let busy = DetectionError::BadExit {
+            tool: "lsusb".to_string(),
+            code: Some(1),
+            stderr: "Resource temporarily unavailable".to_string(),
+        };
+        assert_eq!(busy.failure_kind(), FailureKind::Transient);
+        assert_eq!(
+            busy.to_string(),
+            "lsusb execution failed with exit code 1: Resource temporarily unavailable"
+        );
+
+        let missing = LxHwError::IoError(std::io::Error::from(std::io::ErrorKind::NotFound));
+        assert_eq!(
+            DetectionError::from_execution("inxi", &missing),
+            DetectionError::NotFound { tool: "inxi".to_string() }
+        );
+    }
+}
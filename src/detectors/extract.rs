@@ -0,0 +1,806 @@
+//! Conversion of individual tool output into hardware report fragments
+//!
+//! Each tool's parsed data implements [`ComponentExtractor`], producing the
+//! report components that tool knows about. `HardwareAnalyzer` combines the
+//! fragments of all tools into a report, deciding which tool wins when
+//! several describe the same component; library users and tests can convert
+//! a single tool's output without running a full analysis.
+
+use crate::detectors::audio_cards::AudioCardDetails;
+#[cfg(feature = "dmidecode")]
+use crate::detectors::dmidecode::DmidecodeData;
+#[cfg(feature = "inxi")]
+use crate::detectors::inxi::InxiData;
+use crate::detectors::kernel::KernelSupportData;
+#[cfg(feature = "lshw")]
+use crate::detectors::lshw::{LshwComponent, LshwData};
+#[cfg(feature = "lspci")]
+use crate::detectors::lspci::LspciData;
+#[cfg(feature = "lsusb")]
+use crate::detectors::lsusb::{parse_usb_speed, LsusbData};
+use crate::detectors::sysfs::{PciFunction, SysfsData};
+use crate::detectors::DetectionData;
+use crate::errors::Result;
+use crate::hardware::pci_class::{self, PciClassCode};
+use crate::hardware::taxonomy::Category;
+use crate::hardware::taxonomy::Taxonomy;
+#[cfg(feature = "dmidecode")]
+use crate::hardware::MemoryDimm;
+use crate::hardware::{
+    AudioDevice, CpuInfo, DisplayOutputs, GraphicsDevice, MemoryInfo, NetworkDevice, Printer,
+    Scanner, StorageDevice, UsbDevice,
+};
+use crate::privacy::PrivacyManager;
+use std::collections::HashMap;
+
+/// Shared state for converting tool output into report components
+pub struct ExtractionContext<'a> {
+    taxonomy: &'a Taxonomy,
+    privacy: &'a mut PrivacyManager,
+    display_outputs: HashMap<String, DisplayOutputs>,
+    audio_cards: HashMap<String, AudioCardDetails>,
+}
+
+impl<'a> ExtractionContext<'a> {
+    /// Classify with `taxonomy` and anonymize serials with `privacy`
+    pub fn new(taxonomy: &'a Taxonomy, privacy: &'a mut PrivacyManager) -> Self {
+        Self { taxonomy, privacy, display_outputs: HashMap::new(), audio_cards: HashMap::new() }
+    }
+
+    /// Display outputs by PCI address, attached to the GPUs that drive them
+    pub fn with_display_outputs(mut self, outputs: HashMap<String, DisplayOutputs>) -> Self {
+        self.display_outputs = outputs;
+        self
+    }
+
+    /// Codecs and profiles of ALSA cards by PCI address, attached to their devices
+    pub fn with_audio_cards(mut self, cards: HashMap<String, AudioCardDetails>) -> Self {
+        self.audio_cards = cards;
+        self
+    }
+
+    pub fn taxonomy(&self) -> &'a Taxonomy {
+        self.taxonomy
+    }
+
+    /// Anonymize a serial number or other identifier
+    pub fn anonymize(&mut self, identifier: &str) -> Result<String> {
+        self.privacy.anonymize_identifier(identifier)
+    }
+
+    /// Take the display outputs of the GPU at a sysfs PCI address
+    fn take_displays(&mut self, pci_address: &str) -> Option<DisplayOutputs> {
+        self.display_outputs.remove(pci_address)
+    }
+
+    /// Take the codecs and profiles of the sound card at a sysfs PCI address
+    fn take_audio_card(&mut self, pci_address: &str) -> AudioCardDetails {
+        self.audio_cards.remove(pci_address).unwrap_or_default()
+    }
+}
+
+/// Report components a tool's output can describe
+///
+/// Every method defaults to "nothing found", so a tool only implements the
+/// components it reports. New detectors implement this trait and add their
+/// [`DetectionData`] variant to [`DetectionData::extractor`].
+pub trait ComponentExtractor {
+    fn cpu(&self, _ctx: &mut ExtractionContext) -> Result<Option<CpuInfo>> {
+        Ok(None)
+    }
+
+    fn memory(&self, _ctx: &mut ExtractionContext) -> Result<Option<MemoryInfo>> {
+        Ok(None)
+    }
+
+    fn storage(&self, _ctx: &mut ExtractionContext) -> Result<Vec<StorageDevice>> {
+        Ok(Vec::new())
+    }
+
+    fn graphics(&self, _ctx: &mut ExtractionContext) -> Result<Vec<GraphicsDevice>> {
+        Ok(Vec::new())
+    }
+
+    fn network(&self, _ctx: &mut ExtractionContext) -> Result<Vec<NetworkDevice>> {
+        Ok(Vec::new())
+    }
+
+    fn usb(&self, _ctx: &mut ExtractionContext) -> Result<Vec<UsbDevice>> {
+        Ok(Vec::new())
+    }
+
+    fn audio(&self, _ctx: &mut ExtractionContext) -> Result<Vec<AudioDevice>> {
+        Ok(Vec::new())
+    }
+
+    fn printers(&self, _ctx: &mut ExtractionContext) -> Result<Vec<Printer>> {
+        Ok(Vec::new())
+    }
+
+    fn scanners(&self, _ctx: &mut ExtractionContext) -> Result<Vec<Scanner>> {
+        Ok(Vec::new())
+    }
+}
+
+impl DetectionData {
+    /// Extractor for the tool output held by this variant
+    pub fn extractor(&self) -> &dyn ComponentExtractor {
+        match self {
+            #[cfg(feature = "lshw")]
+            DetectionData::Lshw(data) => data,
+            #[cfg(feature = "dmidecode")]
+            DetectionData::Dmidecode(data) => data.as_ref(),
+            #[cfg(feature = "lspci")]
+            DetectionData::Lspci(data) => data,
+            #[cfg(feature = "lsusb")]
+            DetectionData::Lsusb(data) => data,
+            #[cfg(feature = "inxi")]
+            DetectionData::Inxi(data) => data.as_ref(),
+            DetectionData::Kernel(data) => data,
+            #[cfg(feature = "fwupd")]
+            DetectionData::Fwupd(data) => data,
+            #[cfg(feature = "nvme")]
+            DetectionData::Nvme(data) => data,
+            #[cfg(feature = "smartctl")]
+            DetectionData::Smartctl(data) => data,
+            #[cfg(feature = "lsblk")]
+            DetectionData::Lsblk(data) => data,
+            #[cfg(feature = "nvidia-smi")]
+            DetectionData::NvidiaSmi(data) => data,
+            #[cfg(feature = "iw")]
+            DetectionData::Iw(data) => data,
+            #[cfg(feature = "lpinfo")]
+            DetectionData::Lpinfo(data) => data,
+            #[cfg(feature = "scanimage")]
+            DetectionData::Scanimage(data) => data,
+            DetectionData::Sysfs(data) => data.as_ref(),
+        }
+    }
+}
+
+#[cfg(feature = "dmidecode")]
+impl ComponentExtractor for DmidecodeData {
+    fn cpu(&self, _ctx: &mut ExtractionContext) -> Result<Option<CpuInfo>> {
+        Ok(self.processors.first().map(|processor| CpuInfo {
+            model: processor.version.clone(),
+            vendor: processor.manufacturer.clone(),
+            cores: processor.core_count.unwrap_or(1),
+            threads: processor.thread_count.unwrap_or(processor.core_count.unwrap_or(1)),
+            base_frequency: processor.current_speed.map(|f| f as f64),
+            max_frequency: processor.max_speed.map(|f| f as f64),
+            cache_l1: None, // dmidecode doesn't typically provide cache info
+            cache_l2: None,
+            cache_l3: None,
+            flags: processor.flags.clone(),
+            scaling_governor: None,
+            microcode: None,
+        }))
+    }
+
+    fn memory(&self, _ctx: &mut ExtractionContext) -> Result<Option<MemoryInfo>> {
+        // Only include populated memory slots
+        let dimms: Vec<MemoryDimm> = self
+            .memory_devices
+            .iter()
+            .filter_map(|device| {
+                let size_mb = device.size_mb.filter(|&size| size > 0)?;
+                Some(MemoryDimm {
+                    size_bytes: (size_mb as u64) * 1024 * 1024,
+                    speed_mhz: device.speed_mts,
+                    memory_type: device.memory_type.clone(),
+                    manufacturer: device.manufacturer.clone(),
+                })
+            })
+            .collect();
+
+        if dimms.is_empty() {
+            return Ok(None);
+        }
+        let total_bytes = dimms.iter().map(|dimm| dimm.size_bytes).sum();
+        Ok(Some(MemoryInfo {
+            total_bytes,
+            available_bytes: estimate_available(total_bytes),
+            dimm_count: None,
+            swap: None,
+            dimms,
+        }))
+    }
+}
+
+#[cfg(feature = "lshw")]
+impl ComponentExtractor for LshwData {
+    fn cpu(&self, ctx: &mut ExtractionContext) -> Result<Option<CpuInfo>> {
+        let Some(component) = self.components_in(ctx.taxonomy(), Category::Cpu).next() else {
+            return Ok(None);
+        };
+        let model = component
+            .product
+            .clone()
+            .unwrap_or_else(|| component.description.clone().unwrap_or("Unknown CPU".to_string()));
+        // lshw reports the width but doesn't distinguish cores vs threads
+        let cores = component.width.unwrap_or(1);
+        // Frequencies in the report are in MHz
+        let frequency = component.capacity.map(|c| match component.units.as_deref() {
+            Some("Hz") => c as f64 / 1_000_000.0,
+            _ => c as f64,
+        });
+
+        Ok(Some(CpuInfo {
+            model,
+            vendor: component.vendor.clone().unwrap_or("Unknown".to_string()),
+            cores,
+            threads: cores,
+            base_frequency: frequency,
+            max_frequency: frequency,
+            cache_l1: None,
+            cache_l2: None,
+            cache_l3: None,
+            flags: Vec::new(),
+            scaling_governor: None,
+            microcode: None,
+        }))
+    }
+
+    fn memory(&self, ctx: &mut ExtractionContext) -> Result<Option<MemoryInfo>> {
+        let total_bytes: u64 =
+            self.components_in(ctx.taxonomy(), Category::Memory).filter_map(|c| c.size).sum();
+        if total_bytes == 0 {
+            return Ok(None);
+        }
+        Ok(Some(MemoryInfo {
+            total_bytes,
+            available_bytes: estimate_available(total_bytes),
+            dimm_count: None,
+            swap: None,
+            dimms: Vec::new(), // No detailed DIMM info from lshw
+        }))
+    }
+
+    fn storage(&self, ctx: &mut ExtractionContext) -> Result<Vec<StorageDevice>> {
+        let mut storage_devices = Vec::new();
+
+        for component in self.components_in(ctx.taxonomy(), Category::Storage) {
+            let model = component.product.clone().unwrap_or_else(|| {
+                component.description.clone().unwrap_or("Unknown Storage".to_string())
+            });
+            let device_type = classify_storage_device(&model, component.description.as_ref());
+            let anonymized_serial = match &component.serial {
+                Some(serial) => ctx.anonymize(serial)?,
+                None => "unknown".to_string(),
+            };
+
+            // Extract interface information from businfo
+            let interface = component.businfo.as_ref().and_then(|businfo| {
+                if businfo.starts_with("scsi@") {
+                    Some("SCSI".to_string())
+                } else if businfo.starts_with("ide@") {
+                    Some("IDE".to_string())
+                } else if businfo.contains("nvme") {
+                    Some("NVMe".to_string())
+                } else {
+                    None
+                }
+            });
+
+            storage_devices.push(StorageDevice {
+                anonymized_serial,
+                device_type,
+                size_bytes: component.size.unwrap_or(0),
+                model,
+                vendor: component.vendor.clone(),
+                interface,
+                nvme: None,
+                health: None,
+                topology: None,
+            });
+        }
+
+        Ok(storage_devices)
+    }
+
+    fn graphics(&self, ctx: &mut ExtractionContext) -> Result<Vec<GraphicsDevice>> {
+        let mut graphics_devices = Vec::new();
+
+        for component in self.components_in(ctx.taxonomy(), Category::Gpu) {
+            let model = component.product.clone().unwrap_or_else(|| {
+                component.description.clone().unwrap_or("Unknown Graphics".to_string())
+            });
+            let pci_id = component
+                .businfo
+                .as_deref()
+                .and_then(pci_id_from_businfo)
+                .map(|(v, d)| format!("{}:{}", v, d))
+                .unwrap_or("unknown".to_string());
+            let displays = component
+                .businfo
+                .as_deref()
+                .and_then(|bus| bus.strip_prefix("pci@"))
+                .and_then(|address| ctx.take_displays(address));
+
+            graphics_devices.push(GraphicsDevice {
+                vendor: component.vendor.clone().unwrap_or("Unknown".to_string()),
+                model,
+                driver: None,
+                memory_bytes: component.size,
+                pci_id,
+                displays,
+                nvidia: None,
+            });
+        }
+
+        Ok(graphics_devices)
+    }
+
+    fn network(&self, ctx: &mut ExtractionContext) -> Result<Vec<NetworkDevice>> {
+        let mut network_devices = Vec::new();
+
+        for component in self.components_in(ctx.taxonomy(), Category::Network) {
+            let model = component.product.clone().unwrap_or_else(|| {
+                component.description.clone().unwrap_or("Unknown Network".to_string())
+            });
+            let device_type =
+                classify_network_device(&model, component.description.as_deref().unwrap_or(""));
+
+            // lshw reports the MAC address as the interface's serial
+            let mac = component
+                .configuration
+                .as_ref()
+                .and_then(|config| config.get("serial"))
+                .and_then(|serial| serial.as_str());
+            let anonymized_mac = match mac {
+                Some(mac) => ctx.anonymize(mac)?,
+                None => "unknown".to_string(),
+            };
+
+            network_devices.push(NetworkDevice {
+                device_type,
+                vendor: component.vendor.clone().unwrap_or("Unknown".to_string()),
+                model,
+                driver: None, // lshw doesn't always provide driver info
+                anonymized_mac,
+                wireless_capabilities: None,
+            });
+        }
+
+        Ok(network_devices)
+    }
+
+    fn usb(&self, ctx: &mut ExtractionContext) -> Result<Vec<UsbDevice>> {
+        Ok(self
+            .components
+            .iter()
+            .filter(|component| {
+                ctx.taxonomy().lshw_category(&component.class) == Category::Usb
+                    || component.businfo.as_deref().is_some_and(|bus| bus.starts_with("usb@"))
+            })
+            .map(|component| {
+                let (vendor_id, product_id) = usb_ids_from_component(component);
+                UsbDevice {
+                    vendor_id,
+                    product_id,
+                    vendor_name: component.vendor.clone(),
+                    product_name: component.product.clone(),
+                    usb_version: None,
+                    device_class: None,
+                    device_subclass: None,
+                    speed_mbps: None,
+                    port_path: None,
+                    max_power_ma: None,
+                    anonymized_serial: None,
+                }
+            })
+            .collect())
+    }
+
+    fn audio(&self, ctx: &mut ExtractionContext) -> Result<Vec<AudioDevice>> {
+        Ok(self
+            .components_in(ctx.taxonomy(), Category::Audio)
+            .map(|component| AudioDevice {
+                vendor: component.vendor.clone().unwrap_or("Unknown".to_string()),
+                model: component.product.clone().unwrap_or_else(|| {
+                    component.description.clone().unwrap_or("Unknown Audio".to_string())
+                }),
+                driver: None,
+                device_type: "multimedia".to_string(),
+                codecs: Vec::new(),
+                profiles: None,
+            })
+            .collect())
+    }
+}
+
+#[cfg(feature = "lshw")]
+impl LshwData {
+    /// Components the taxonomy places in a category
+    fn components_in<'d>(
+        &'d self,
+        taxonomy: &'d Taxonomy,
+        category: Category,
+    ) -> impl Iterator<Item = &'d LshwComponent> {
+        self.components.iter().filter(move |c| taxonomy.lshw_category(&c.class) == category)
+    }
+}
+
+#[cfg(feature = "lspci")]
+impl ComponentExtractor for LspciData {
+    fn graphics(&self, ctx: &mut ExtractionContext) -> Result<Vec<GraphicsDevice>> {
+        let mut graphics_devices = Vec::new();
+
+        for device in &self.devices {
+            if ctx.taxonomy().pci_category(&device.class_code) != Category::Gpu {
+                continue;
+            }
+            let pci_id = format!("{}:{}", device.vendor_id, device.device_id);
+            let model =
+                device.device_name.clone().unwrap_or_else(|| format!("Graphics Device {}", pci_id));
+
+            graphics_devices.push(GraphicsDevice {
+                vendor: device.vendor_name.clone().unwrap_or("Unknown".to_string()),
+                model,
+                driver: device.kernel_driver.clone(),
+                memory_bytes: None, // Would need additional parsing
+                pci_id,
+                displays: ctx.take_displays(&sysfs_pci_address(&device.address)),
+                nvidia: None,
+            });
+        }
+
+        Ok(graphics_devices)
+    }
+
+    fn network(&self, ctx: &mut ExtractionContext) -> Result<Vec<NetworkDevice>> {
+        Ok(self
+            .devices
+            .iter()
+            .filter(|device| ctx.taxonomy().pci_category(&device.class_code) == Category::Network)
+            .map(|device| {
+                let model = device.device_name.clone().unwrap_or_else(|| {
+                    format!("Network Device {}:{}", device.vendor_id, device.device_id)
+                });
+                NetworkDevice {
+                    device_type: classify_network_device(&model, &device.class_description),
+                    vendor: device.vendor_name.clone().unwrap_or("Unknown".to_string()),
+                    model,
+                    driver: device.kernel_driver.clone(),
+                    anonymized_mac: "unknown".to_string(), // PCI data doesn't include MAC
+                    wireless_capabilities: None,
+                }
+            })
+            .collect())
+    }
+
+    fn audio(&self, ctx: &mut ExtractionContext) -> Result<Vec<AudioDevice>> {
+        let taxonomy = ctx.taxonomy();
+        Ok(self
+            .devices
+            .iter()
+            .filter(|device| taxonomy.pci_category(&device.class_code) == Category::Audio)
+            .map(|device| {
+                let card = ctx.take_audio_card(&sysfs_pci_address(&device.address));
+                AudioDevice {
+                    vendor: device.vendor_name.clone().unwrap_or("Unknown".to_string()),
+                    model: device.device_name.clone().unwrap_or_else(|| {
+                        format!("Audio Device {}:{}", device.vendor_id, device.device_id)
+                    }),
+                    driver: device.kernel_driver.clone(),
+                    device_type: match PciClassCode::parse(&device.class_code) {
+                        Some(PciClassCode { subclass: Some(0x01), .. }) => "multimedia_audio",
+                        _ => "audio",
+                    }
+                    .to_string(),
+                    codecs: card.codecs,
+                    profiles: card.profiles,
+                }
+            })
+            .collect())
+    }
+}
+
+#[cfg(feature = "lsusb")]
+impl ComponentExtractor for LsusbData {
+    fn usb(&self, ctx: &mut ExtractionContext) -> Result<Vec<UsbDevice>> {
+        let mut usb_devices = Vec::new();
+
+        for device in &self.devices {
+            let anonymized_serial = match &device.serial_number {
+                Some(serial) => Some(ctx.anonymize(serial)?),
+                None => None,
+            };
+            usb_devices.push(UsbDevice {
+                vendor_id: device.vendor_id.clone(),
+                product_id: device.product_id.clone(),
+                vendor_name: device.vendor_name.clone(),
+                product_name: device.product_name.clone(),
+                usb_version: device.usb_version.clone(),
+                device_class: device.device_class.clone(),
+                device_subclass: device.device_subclass.clone(),
+                speed_mbps: device.speed.as_deref().and_then(parse_usb_speed),
+                port_path: device.port_path.clone(),
+                max_power_ma: device.max_power,
+                anonymized_serial,
+            });
+        }
+
+        Ok(usb_devices)
+    }
+}
+
+impl ComponentExtractor for SysfsData {
+    fn cpu(&self, _ctx: &mut ExtractionContext) -> Result<Option<CpuInfo>> {
+        Ok(self.cpu.as_ref().map(|cpu| CpuInfo {
+            model: cpu.model.clone().unwrap_or("Unknown CPU".to_string()),
+            vendor: cpu.vendor.clone().unwrap_or("Unknown".to_string()),
+            cores: cpu.cores,
+            threads: cpu.threads,
+            base_frequency: cpu.base_frequency_mhz,
+            max_frequency: cpu.max_frequency_mhz,
+            cache_l1: cpu.cache_l1,
+            cache_l2: cpu.cache_l2,
+            cache_l3: cpu.cache_l3,
+            flags: cpu.flags.clone(),
+            scaling_governor: cpu.scaling_governor.clone(),
+            microcode: None,
+        }))
+    }
+
+    fn memory(&self, _ctx: &mut ExtractionContext) -> Result<Option<MemoryInfo>> {
+        Ok(self.memory.map(|memory| MemoryInfo {
+            total_bytes: memory.total_bytes,
+            available_bytes: memory.available_bytes,
+            dimms: Vec::new(), // DIMMs are only described by the firmware tables
+            dimm_count: None,
+            swap: None,
+        }))
+    }
+
+    fn storage(&self, ctx: &mut ExtractionContext) -> Result<Vec<StorageDevice>> {
+        let mut storage_devices = Vec::new();
+
+        for disk in &self.disks {
+            let anonymized_serial = match &disk.serial {
+                Some(serial) => ctx.anonymize(serial)?,
+                None => "unknown".to_string(),
+            };
+            let transport = disk.transport.as_deref();
+            let device_type = match transport {
+                Some("nvme") => "NVMe SSD",
+                Some("usb") => "USB Drive",
+                _ if disk.removable => "USB Drive",
+                _ if disk.rotational == Some(false) => "SSD",
+                _ => "HDD",
+            };
+            let interface = transport.map(|transport| match transport {
+                "nvme" => "NVMe".to_string(),
+                "ata" => "SATA".to_string(),
+                "virtio" => "VirtIO".to_string(),
+                other => other.to_uppercase(),
+            });
+
+            storage_devices.push(StorageDevice {
+                anonymized_serial,
+                device_type: device_type.to_string(),
+                size_bytes: disk.size_bytes,
+                model: disk.model.clone().unwrap_or("Unknown Storage".to_string()),
+                vendor: disk.vendor.clone(),
+                interface,
+                nvme: None,
+                health: None,
+                topology: None,
+            });
+        }
+
+        Ok(storage_devices)
+    }
+
+    fn graphics(&self, ctx: &mut ExtractionContext) -> Result<Vec<GraphicsDevice>> {
+        let mut graphics_devices = Vec::new();
+
+        for device in &self.pci_devices {
+            if ctx.taxonomy().pci_category(&device.class_code) != Category::Gpu {
+                continue;
+            }
+            let pci_id = format!("{}:{}", device.vendor_id, device.device_id);
+            graphics_devices.push(GraphicsDevice {
+                vendor: device.vendor_name.clone().unwrap_or("Unknown".to_string()),
+                model: sysfs_model(device, "Graphics Device"),
+                driver: device.driver.clone(),
+                memory_bytes: None,
+                pci_id,
+                displays: ctx.take_displays(&device.address),
+                nvidia: None,
+            });
+        }
+
+        Ok(graphics_devices)
+    }
+
+    fn network(&self, ctx: &mut ExtractionContext) -> Result<Vec<NetworkDevice>> {
+        let mut network_devices = Vec::new();
+
+        for interface in &self.interfaces {
+            let bus_address = interface.bus_address.as_deref().unwrap_or("");
+            let (vendor, model) = if let Some(device) = self.pci_device(bus_address) {
+                (device.vendor_name.clone(), sysfs_model(device, "Network Device"))
+            } else if let Some(device) = self.usb_device(bus_address) {
+                let model = device.product.clone().unwrap_or_else(|| {
+                    format!("Network Device {}:{}", device.vendor_id, device.product_id)
+                });
+                (device.manufacturer.clone(), model)
+            } else {
+                (None, format!("Network Interface {}", interface.name))
+            };
+            network_devices.push(NetworkDevice {
+                device_type: if interface.wireless { "wifi" } else { "ethernet" }.to_string(),
+                vendor: vendor.unwrap_or("Unknown".to_string()),
+                model,
+                driver: interface.driver.clone(),
+                anonymized_mac: ctx.anonymize(&interface.mac_address)?,
+                wireless_capabilities: None,
+            });
+        }
+
+        // Controllers without a driver have no interface but are still worth reporting
+        for device in &self.pci_devices {
+            if ctx.taxonomy().pci_category(&device.class_code) != Category::Network
+                || self
+                    .interfaces
+                    .iter()
+                    .any(|interface| interface.bus_address.as_deref() == Some(&device.address))
+            {
+                continue;
+            }
+            let model = sysfs_model(device, "Network Device");
+            let description = pci_class::describe(&device.class_code).unwrap_or_default();
+            network_devices.push(NetworkDevice {
+                device_type: classify_network_device(&model, &description),
+                vendor: device.vendor_name.clone().unwrap_or("Unknown".to_string()),
+                model,
+                driver: device.driver.clone(),
+                anonymized_mac: "unknown".to_string(),
+                wireless_capabilities: None,
+            });
+        }
+
+        Ok(network_devices)
+    }
+
+    fn usb(&self, ctx: &mut ExtractionContext) -> Result<Vec<UsbDevice>> {
+        let mut usb_devices = Vec::new();
+
+        for device in &self.usb_devices {
+            let anonymized_serial = match &device.serial {
+                Some(serial) => Some(ctx.anonymize(serial)?),
+                None => None,
+            };
+            usb_devices.push(device.clone().into_usb_device(anonymized_serial));
+        }
+
+        Ok(usb_devices)
+    }
+
+    fn audio(&self, ctx: &mut ExtractionContext) -> Result<Vec<AudioDevice>> {
+        let taxonomy = ctx.taxonomy();
+        Ok(self
+            .pci_devices
+            .iter()
+            .filter(|device| taxonomy.pci_category(&device.class_code) == Category::Audio)
+            .map(|device| {
+                let card = ctx.take_audio_card(&device.address);
+                AudioDevice {
+                    vendor: device.vendor_name.clone().unwrap_or("Unknown".to_string()),
+                    model: sysfs_model(device, "Audio Device"),
+                    driver: device.driver.clone(),
+                    device_type: match PciClassCode::parse(&device.class_code) {
+                        Some(PciClassCode { subclass: Some(0x01), .. }) => "multimedia_audio",
+                        _ => "audio",
+                    }
+                    .to_string(),
+                    codecs: card.codecs,
+                    profiles: card.profiles,
+                }
+            })
+            .collect())
+    }
+}
+
+// inxi output is used for cross-checking only and kernel data describes
+// driver support rather than components
+#[cfg(feature = "inxi")]
+impl ComponentExtractor for InxiData {}
+impl ComponentExtractor for KernelSupportData {}
+
+/// Rough estimate of memory available to applications
+#[cfg(any(feature = "dmidecode", feature = "lshw"))]
+fn estimate_available(total_bytes: u64) -> u64 {
+    total_bytes.saturating_sub(total_bytes / 10)
+}
+
+/// Classify a storage device from its model and description
+#[cfg(feature = "lshw")]
+fn classify_storage_device(model: &str, description: Option<&String>) -> String {
+    let combined = format!(
+        "{} {}",
+        model.to_lowercase(),
+        description.map(|d| d.to_lowercase()).unwrap_or_default()
+    );
+
+    if combined.contains("nvme") {
+        "NVMe SSD".to_string()
+    } else if combined.contains("ssd") {
+        "SSD".to_string()
+    } else if combined.contains("usb") || combined.contains("removable") {
+        "USB Drive".to_string()
+    } else if combined.contains("optical") || combined.contains("dvd") || combined.contains("cd") {
+        "Optical Drive".to_string()
+    } else {
+        "HDD".to_string() // Default assumption
+    }
+}
+
+/// Classify a network device from its model and description
+fn classify_network_device(model: &str, description: &str) -> String {
+    let combined = format!("{} {}", model.to_lowercase(), description.to_lowercase());
+
+    if combined.contains("wireless") || combined.contains("wifi") || combined.contains("802.11") {
+        "wifi".to_string()
+    } else if combined.contains("bluetooth") {
+        "bluetooth".to_string()
+    } else if combined.contains("ethernet") || combined.contains("network") {
+        "ethernet".to_string()
+    } else {
+        "network".to_string() // Generic fallback
+    }
+}
+
+/// Extract USB vendor/product IDs from an lshw component
+#[cfg(feature = "lshw")]
+fn usb_ids_from_component(component: &LshwComponent) -> (String, String) {
+    // Look for a 4-digit hex ID under a vendor or product configuration key
+    let id = component.configuration.iter().flatten().find_map(|(key, value)| {
+        let id_str =
+            value.as_str().filter(|_| key.contains("vendor") || key.contains("product"))?;
+        (id_str.len() == 4 && id_str.chars().all(|c| c.is_ascii_hexdigit())).then_some(id_str)
+    });
+    if let Some(id_str) = id {
+        return (id_str.to_string(), "0000".to_string());
+    }
+
+    // Fallback to unknown IDs
+    ("unknown".to_string(), "unknown".to_string())
+}
+
+/// Read the PCI vendor and device ID for an lshw businfo like "pci@0000:01:00.0"
+#[cfg(feature = "lshw")]
+pub(crate) fn pci_id_from_businfo(businfo: &str) -> Option<(String, String)> {
+    // lshw only gives the address; the IDs come from sysfs, which belongs to
+    // another machine when detecting a remote host
+    if !super::remote::is_local() {
+        return None;
+    }
+    let pci_addr = businfo.strip_prefix("pci@")?;
+    let sysfs_path = format!("/sys/bus/pci/devices/{}", pci_addr);
+
+    let vendor = std::fs::read_to_string(format!("{}/vendor", sysfs_path)).ok()?;
+    let device = std::fs::read_to_string(format!("{}/device", sysfs_path)).ok()?;
+    let vendor = vendor.trim().strip_prefix("0x")?.trim();
+    let device = device.trim().strip_prefix("0x")?.trim();
+    Some((vendor.to_string(), device.to_string()))
+}
+
+/// Model of a PCI function: its pci.ids name, or its class and IDs
+fn sysfs_model(device: &PciFunction, fallback: &str) -> String {
+    device.device_name.clone().unwrap_or_else(|| {
+        let kind = pci_class::describe(&device.class_code).unwrap_or(fallback.to_string());
+        format!("{} {}:{}", kind, device.vendor_id, device.device_id)
+    })
+}
+
+/// Full sysfs form of a PCI address; lspci omits the 0000 domain by default
+#[cfg(feature = "lspci")]
+pub(crate) fn sysfs_pci_address(address: &str) -> String {
+    if address.matches(':').count() == 1 {
+        format!("0000:{}", address)
+    } else {
+        address.to_string()
+    }
+}
@@ -0,0 +1,121 @@
+//! System firmware, boot mode, Secure Boot and TPM
+//!
+//! The BIOS vendor, version and date are world-readable under
+//! `/sys/class/dmi/id`, so they need neither root nor dmidecode. The kernel
+//! creates `/sys/firmware/efi` only when it was started by UEFI firmware,
+//! and then exposes the `SecureBoot` variable through efivarfs; `mokutil
+//! --sb-state` is asked when efivarfs is not mounted. TPMs register with
+//! `/sys/class/tpm`, which reports the major specification version on
+//! kernels since 5.6.
+//!
+//! Firmware released before Linux support matured, legacy boot and Secure
+//! Boot all explain problems that the hardware alone does not: unsigned
+//! out-of-tree modules refusing to load, missing ACPI features, or suspend
+//! broken until a BIOS update.
+
+use super::attributes::read_nonempty;
+use crate::hardware::{BootMode, FirmwareInfo};
+use std::path::Path;
+
+/// efivarfs name of the Secure Boot variable in the EFI global namespace
+const SECURE_BOOT_VARIABLE: &str = "SecureBoot-8be4df61-93ca-11d2-aa0d-00e098032b8c";
+
+/// Read firmware information from the system under `root`
+///
+/// `root` is "/" except in tests; `mokutil` is the output of
+/// `mokutil --sb-state`, if any.
+pub fn read_firmware_info(root: &Path, mokutil: Option<&str>) -> FirmwareInfo {
+    let dmi = root.join("sys/class/dmi/id");
+    let efi = root.join("sys/firmware/efi");
+    let boot_mode = if efi.is_dir() { BootMode::Uefi } else { BootMode::Legacy };
+    let secure_boot = match boot_mode {
+        BootMode::Uefi => read_secure_boot(&efi.join("efivars").join(SECURE_BOOT_VARIABLE))
+            .or_else(|| mokutil.and_then(parse_mokutil)),
+        BootMode::Legacy => None,
+    };
+    let tpm = root.join("sys/class/tpm/tpm0");
+
+    FirmwareInfo {
+        bios_vendor: read_nonempty(&dmi.join("bios_vendor")),
+        bios_version: read_nonempty(&dmi.join("bios_version")),
+        bios_date: read_nonempty(&dmi.join("bios_date")),
+        boot_mode,
+        secure_boot,
+        tpm_present: tpm.exists(),
+        tpm_version: read_nonempty(&tpm.join("tpm_version_major")).and_then(|major| {
+            match major.as_str() {
+                "2" => Some("2.0".to_string()),
+                "1" => Some("1.2".to_string()),
+                _ => None,
+            }
+        }),
+    }
+}
+
+/// Run `mokutil --sb-state`; None when it is not installed
+pub fn query_mokutil() -> Option<String> {
+    let output =
+        std::process::Command::new("mokutil").arg("--sb-state").env("LC_ALL", "C").output().ok()?;
+    // It exits with 1 on systems without EFI variables but still explains why
+    Some(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Secure Boot state from the efivarfs file: four attribute bytes, then
+/// the one-byte value
+fn read_secure_boot(variable: &Path) -> Option<bool> {
+    let data = std::fs::read(variable).ok()?;
+    data.get(4).map(|value| *value == 1)
+}
+
+fn parse_mokutil(output: &str) -> Option<bool> {
+    output.lines().find_map(|line| match line.trim() {
+        "SecureBoot enabled" => Some(true),
+        "SecureBoot disabled" => Some(false),
+        _ => None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn write(root: &Path, file: &str, content: &[u8]) {
+        let path = root.join(file);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, content).unwrap();
+    }
+
+    #[test]
+    fn test_read_firmware_info() {
+        let root = TempDir::new().unwrap();
+        write(root.path(), "sys/class/dmi/id/bios_vendor", b"LENOVO\n");
+        write(root.path(), "sys/class/dmi/id/bios_version", b"N3AET80W (1.45 )\n");
+        write(root.path(), "sys/class/dmi/id/bios_date", b"03/14/2024\n");
+
+        let legacy = read_firmware_info(root.path(), Some("SecureBoot enabled\n"));
+        assert_eq!(legacy.boot_mode, BootMode::Legacy);
+        assert_eq!(legacy.secure_boot, None);
+        assert_eq!(legacy.bios_version.as_deref(), Some("N3AET80W (1.45 )"));
+        assert!(!legacy.tpm_present);
+
+        std::fs::create_dir_all(root.path().join("sys/firmware/efi")).unwrap();
+        write(root.path(), "sys/class/tpm/tpm0/tpm_version_major", b"2\n");
+        let uefi = read_firmware_info(root.path(), Some("SecureBoot disabled\n"));
+        assert_eq!(uefi.boot_mode, BootMode::Uefi);
+        assert_eq!(uefi.secure_boot, Some(false));
+        assert!(uefi.tpm_present);
+        assert_eq!(uefi.tpm_version.as_deref(), Some("2.0"));
+
+        let efivars = format!("sys/firmware/efi/efivars/{}", SECURE_BOOT_VARIABLE);
+        write(root.path(), &efivars, &[0x06, 0x00, 0x00, 0x00, 0x01]);
+        assert_eq!(read_firmware_info(root.path(), None).secure_boot, Some(true));
+    }
+
+    #[test]
+    fn test_parse_mokutil() {
+        assert_eq!(parse_mokutil("SecureBoot enabled\n"), Some(true));
+        assert_eq!(parse_mokutil("SecureBoot disabled\nPlatform is in Setup Mode\n"), Some(false));
+        assert_eq!(parse_mokutil("EFI variables are not supported on this system\n"), None);
+    }
+}
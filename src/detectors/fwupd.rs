@@ -0,0 +1,236 @@
+//! fwupd firmware update detection
+//!
+//! Queries the fwupd daemon through `fwupdmgr get-devices --json` for devices
+//! whose firmware is distributed through the LVFS and any upgrades it offers.
+//! The tool is optional: without fwupd installed the detector is simply not
+//! available and the report carries no firmware section.
+
+use super::extract::ComponentExtractor;
+use super::{sandbox, DetectionData, DetectionError, DetectionResult, HardwareDetector};
+use crate::errors::{LxHwError, Result};
+use crate::hardware::FirmwareDevice;
+use async_trait::async_trait;
+use serde::Deserialize;
+use std::process::Output;
+use std::time::Duration;
+
+/// Devices with updatable firmware reported by fwupd
+#[derive(Debug, Clone, Default)]
+pub struct FwupdData {
+    pub devices: Vec<FirmwareDevice>,
+}
+
+impl FwupdData {
+    /// Devices with an upgrade pending
+    pub fn pending_updates(&self) -> impl Iterator<Item = &FirmwareDevice> {
+        self.devices.iter().filter(|device| device.update_version.is_some())
+    }
+}
+
+/// Firmware is not a report component; the data is read by the report builder
+impl ComponentExtractor for FwupdData {}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct FwupdOutput {
+    #[serde(default)]
+    devices: Vec<FwupdDevice>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct FwupdDevice {
+    name: Option<String>,
+    vendor: Option<String>,
+    plugin: Option<String>,
+    version: Option<String>,
+    #[serde(default)]
+    flags: Vec<String>,
+    #[serde(default)]
+    releases: Vec<FwupdRelease>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct FwupdRelease {
+    version: Option<String>,
+    summary: Option<String>,
+    urgency: Option<String>,
+    #[serde(default)]
+    flags: Vec<String>,
+}
+
+/// Parse `fwupdmgr get-devices --json` output, keeping updatable devices
+pub fn parse_devices(json: &str) -> serde_json::Result<Vec<FirmwareDevice>> {
+    let output: FwupdOutput = serde_json::from_str(json)?;
+
+    Ok(output
+        .devices
+        .into_iter()
+        .filter(|device| device.flags.iter().any(|flag| flag == "updatable"))
+        .filter_map(|device| {
+            // fwupd lists releases newest first
+            let upgrade =
+                device.releases.into_iter().find(|r| r.flags.iter().any(|f| f == "is-upgrade"));
+            let (update_version, update_summary, update_urgency) = match upgrade {
+                Some(release) => (release.version, release.summary, release.urgency),
+                None => (None, None, None),
+            };
+            Some(FirmwareDevice {
+                name: device.name?,
+                vendor: device.vendor,
+                plugin: device.plugin,
+                current_version: device.version,
+                update_version,
+                update_summary,
+                update_urgency,
+            })
+        })
+        .collect())
+}
+
+/// Detector querying fwupd for firmware updates
+pub struct FwupdDetector;
+
+impl FwupdDetector {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl Default for FwupdDetector {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl HardwareDetector for FwupdDetector {
+    fn name(&self) -> &'static str {
+        "fwupd"
+    }
+
+    fn program(&self) -> &'static str {
+        "fwupdmgr"
+    }
+
+    async fn is_available(&self) -> bool {
+        tokio::process::Command::new("which")
+            .arg("fwupdmgr")
+            .output()
+            .await
+            .map(|output| output.status.success())
+            .unwrap_or(false)
+    }
+
+    async fn execute(&self) -> Result<Output> {
+        sandbox::output(tokio::process::Command::new("fwupdmgr").args(["get-devices", "--json"]))
+            .await
+            .map_err(|e| LxHwError::SystemCommandError { command: format!("fwupdmgr: {}", e) })
+    }
+
+    fn timeout(&self) -> Duration {
+        // The daemon may need to be started and enumerate devices first
+        Duration::from_secs(20)
+    }
+
+    fn parse_output(&self, output: &Output) -> Result<DetectionResult> {
+        if !output.status.success() {
+            return Ok(DetectionResult {
+                tool_name: self.name().to_string(),
+                success: false,
+                data: DetectionData::Fwupd(FwupdData::default()),
+                errors: vec![DetectionError::BadExit {
+                    tool: self.name().to_string(),
+                    code: output.status.code(),
+                    stderr: String::from_utf8_lossy(&output.stderr).to_string(),
+                }],
+            });
+        }
+
+        if output.stdout.is_empty() {
+            return Ok(DetectionResult {
+                tool_name: self.name().to_string(),
+                success: false,
+                data: DetectionData::Fwupd(FwupdData::default()),
+                errors: vec![DetectionError::EmptyOutput { tool: self.name().to_string() }],
+            });
+        }
+
+        match parse_devices(&String::from_utf8_lossy(&output.stdout)) {
+            Ok(devices) => Ok(DetectionResult {
+                tool_name: self.name().to_string(),
+                success: true,
+                data: DetectionData::Fwupd(FwupdData { devices }),
+                errors: Vec::new(),
+            }),
+            Err(e) => Ok(DetectionResult {
+                tool_name: self.name().to_string(),
+                success: false,
+                data: DetectionData::Fwupd(FwupdData::default()),
+                errors: vec![DetectionError::json("JSON parsing failed", &e)],
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GET_DEVICES: &str = r#"{
+      "Devices": [
+        {
+          "Name": "System Firmware",
+          "DeviceId": "a45df35ac0e948ee180fe216a5f703f32dda163f",
+          "Guid": ["230c8b18-8d9b-53ec-838b-6cfc0383493a"],
+          "Plugin": "uefi_capsule",
+          "Flags": ["internal", "updatable", "require-ac", "registered"],
+          "Vendor": "LENOVO",
+          "Version": "0.1.56",
+          "Releases": [
+            {
+              "Version": "0.1.61",
+              "Summary": "Lenovo ThinkPad X1 Carbon System Firmware",
+              "Urgency": "high",
+              "Flags": ["trusted-payload", "is-upgrade"]
+            },
+            {
+              "Version": "0.1.50",
+              "Flags": ["trusted-payload", "is-downgrade"]
+            }
+          ]
+        },
+        {
+          "Name": "Samsung SSD 970 EVO Plus 1TB",
+          "Plugin": "nvme",
+          "Flags": ["internal", "updatable", "registered"],
+          "Vendor": "Samsung",
+          "Version": "2B2QEXM7"
+        },
+        {
+          "Name": "Integrated Camera",
+          "Plugin": "usb",
+          "Flags": ["registered"],
+          "Version": "0.0.1"
+        }
+      ]
+    }"#;
+
+    #[test]
+    fn test_parse_devices() {
+        let data = FwupdData { devices: parse_devices(GET_DEVICES).unwrap() };
+        assert_eq!(data.devices.len(), 2);
+
+        let firmware = &data.devices[0];
+        assert_eq!(firmware.current_version.as_deref(), Some("0.1.56"));
+        assert_eq!(firmware.update_version.as_deref(), Some("0.1.61"));
+        assert_eq!(firmware.update_urgency.as_deref(), Some("high"));
+        assert_eq!(data.devices[1].update_version, None);
+
+        let pending: Vec<&str> = data.pending_updates().map(|d| d.name.as_str()).collect();
+        assert_eq!(pending, ["System Firmware"]);
+
+        assert!(parse_devices("{}").unwrap().is_empty());
+    }
+}
@@ -0,0 +1,244 @@
+//! Hardware monitoring chips from hwmon and lm-sensors
+//!
+//! Every chip with a loaded driver registers a directory under
+//! `/sys/class/hwmon` with its name and one `tempN_input`, `fanN_input` or
+//! `inN_input` file per sensor. The directory's `device` link leads to the
+//! device the chip belongs to, whose `subsystem` and `driver` links give its
+//! bus and bound driver. `sensors -j` from lm-sensors lists the same chips
+//! and is used for those sysfs does not show, such as inside containers.
+//!
+//! Motherboard Super I/O chips need a driver such as `nct6775` or `it87`
+//! loaded before they appear at all, so a board with only CPU and GPU
+//! sensors listed usually lacks a driver for its sensor chip.
+
+use super::attributes::read_trimmed;
+use crate::hardware::SensorChip;
+use std::path::{Path, PathBuf};
+
+/// Kind of sensor an input file belongs to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SensorKind {
+    Temperature,
+    Fan,
+    Voltage,
+}
+
+/// Read the chips registered with hwmon
+///
+/// Returns an empty list when hwmon is not available.
+pub fn read_hwmon(hwmon_class: &Path) -> Vec<SensorChip> {
+    let Ok(entries) = std::fs::read_dir(hwmon_class) else {
+        return Vec::new();
+    };
+    let mut dirs: Vec<(u32, PathBuf)> = entries
+        .flatten()
+        .filter_map(|entry| {
+            let index = entry.file_name().to_str()?.strip_prefix("hwmon")?.parse().ok()?;
+            Some((index, entry.path()))
+        })
+        .collect();
+    dirs.sort();
+
+    dirs.iter().filter_map(|(_, dir)| read_chip(dir)).collect()
+}
+
+/// List chips with `sensors -j`
+///
+/// Returns an empty list when lm-sensors is not installed.
+pub fn query_lm_sensors() -> Vec<SensorChip> {
+    let output = std::process::Command::new("sensors").arg("-j").env("LC_ALL", "C").output();
+    match output {
+        Ok(output) if output.status.success() => {
+            parse_sensors_json(&String::from_utf8_lossy(&output.stdout))
+        }
+        _ => Vec::new(),
+    }
+}
+
+/// Add the chips lm-sensors found that hwmon did not list
+pub fn merge_lm_sensors(chips: &mut Vec<SensorChip>, lm_sensors: Vec<SensorChip>) {
+    for chip in lm_sensors {
+        if !chips.iter().any(|existing| existing.name == chip.name) {
+            chips.push(chip);
+        }
+    }
+}
+
+fn read_chip(dir: &Path) -> Option<SensorChip> {
+    let name = read_trimmed(&dir.join("name"))?;
+    let mut chip = SensorChip {
+        name,
+        bus: Some("virtual".to_string()),
+        driver: None,
+        temperatures: 0,
+        fans: 0,
+        voltages: 0,
+    };
+
+    if let Ok(device) = std::fs::canonicalize(dir.join("device")) {
+        chip.bus = link_name(&device.join("subsystem"));
+        chip.driver = link_name(&device.join("driver"));
+    }
+    for entry in std::fs::read_dir(dir).ok()?.flatten() {
+        if let Some(kind) = entry.file_name().to_str().and_then(input_kind) {
+            count(&mut chip, kind);
+        }
+    }
+    Some(chip)
+}
+
+/// Kind of a sensor input attribute such as "temp1_input" or "in0_input"
+fn input_kind(attribute: &str) -> Option<SensorKind> {
+    let sensor = attribute.strip_suffix("_input")?;
+    let (kind, index) =
+        [("temp", SensorKind::Temperature), ("fan", SensorKind::Fan), ("in", SensorKind::Voltage)]
+            .into_iter()
+            .find_map(|(prefix, kind)| sensor.strip_prefix(prefix).map(|index| (kind, index)))?;
+    (!index.is_empty() && index.chars().all(|c| c.is_ascii_digit())).then_some(kind)
+}
+
+fn count(chip: &mut SensorChip, kind: SensorKind) {
+    match kind {
+        SensorKind::Temperature => chip.temperatures += 1,
+        SensorKind::Fan => chip.fans += 1,
+        SensorKind::Voltage => chip.voltages += 1,
+    }
+}
+
+/// Parse `sensors -j` output
+///
+/// Chips are keyed "prefix-bus-address", e.g. "coretemp-isa-0000"; each
+/// feature holds its subfeatures such as `temp1_input`.
+fn parse_sensors_json(output: &str) -> Vec<SensorChip> {
+    let Ok(serde_json::Value::Object(chips)) = serde_json::from_str(output) else {
+        return Vec::new();
+    };
+    chips
+        .iter()
+        .filter_map(|(key, features)| {
+            let mut parts = key.rsplitn(3, '-');
+            let (_address, bus, name) = (parts.next()?, parts.next()?, parts.next()?);
+            let mut chip = SensorChip {
+                name: name.to_string(),
+                bus: Some(bus.to_string()),
+                driver: None,
+                temperatures: 0,
+                fans: 0,
+                voltages: 0,
+            };
+            let subfeatures = features
+                .as_object()?
+                .values()
+                .filter_map(|feature| feature.as_object())
+                .flat_map(|feature| feature.keys());
+            for kind in subfeatures.filter_map(|subfeature| input_kind(subfeature)) {
+                count(&mut chip, kind);
+            }
+            Some(chip)
+        })
+        .collect()
+}
+
+fn link_name(link: &Path) -> Option<String> {
+    let target = std::fs::read_link(link).ok()?;
+    Some(target.file_name()?.to_string_lossy().into_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::os::unix::fs::symlink;
+    use tempfile::TempDir;
+
+    fn write(dir: &Path, file: &str, content: &str) {
+        std::fs::create_dir_all(dir).unwrap();
+        std::fs::write(dir.join(file), content).unwrap();
+    }
+
+    /// coretemp on the platform bus, an NCT6798 Super I/O chip and ACPI's thermal zone
+    fn fixture() -> TempDir {
+        let root = TempDir::new().unwrap();
+        for dir in ["bus/platform", "bus/drivers/coretemp", "bus/drivers/nct6775"] {
+            std::fs::create_dir_all(root.path().join(dir)).unwrap();
+        }
+        let class = root.path().join("class/hwmon");
+
+        let chips = [("coretemp.0", "coretemp", "coretemp"), ("nct6775.656", "nct6798", "nct6775")];
+        for (index, (device, name, driver)) in chips.iter().enumerate() {
+            let device = root.path().join("devices/platform").join(device);
+            let hwmon = device.join(format!("hwmon/hwmon{}", index + 1));
+            write(&hwmon, "name", &format!("{}\n", name));
+            symlink(root.path().join("bus/platform"), device.join("subsystem")).unwrap();
+            symlink(root.path().join("bus/drivers").join(driver), device.join("driver")).unwrap();
+            symlink(&device, hwmon.join("device")).unwrap();
+            std::fs::create_dir_all(&class).unwrap();
+            symlink(&hwmon, class.join(format!("hwmon{}", index + 1))).unwrap();
+        }
+        let coretemp = class.join("hwmon1");
+        for file in ["temp1_input", "temp2_input", "temp2_label", "temp1_crit"] {
+            write(&coretemp, file, "45000\n");
+        }
+        let superio = class.join("hwmon2");
+        for file in ["in0_input", "in1_input", "fan1_input", "fan2_input", "temp7_input"] {
+            write(&superio, file, "0\n");
+        }
+
+        let thermal = root.path().join("devices/virtual/thermal/thermal_zone0/hwmon0");
+        write(&thermal, "name", "acpitz\n");
+        write(&thermal, "temp1_input", "27800\n");
+        symlink(&thermal, class.join("hwmon0")).unwrap();
+        root
+    }
+
+    #[test]
+    fn test_read_hwmon() {
+        let root = fixture();
+        let chips = read_hwmon(&root.path().join("class/hwmon"));
+        let summary: Vec<_> = chips
+            .iter()
+            .map(|c| (c.name.as_str(), c.bus.as_deref(), c.driver.as_deref()))
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                ("acpitz", Some("virtual"), None),
+                ("coretemp", Some("platform"), Some("coretemp")),
+                ("nct6798", Some("platform"), Some("nct6775")),
+            ]
+        );
+        assert_eq!((chips[1].temperatures, chips[1].fans, chips[1].voltages), (2, 0, 0));
+        assert_eq!((chips[2].temperatures, chips[2].fans, chips[2].voltages), (1, 2, 2));
+
+        let mut merged = chips.clone();
+        merge_lm_sensors(
+            &mut merged,
+            parse_sensors_json(
+                r#"{
+                    "coretemp-isa-0000": {
+                        "Adapter": "ISA adapter",
+                        "Package id 0": {"temp1_input": 45.0, "temp1_crit": 100.0}
+                    },
+                    "nvme-pci-0100": {
+                        "Adapter": "PCI adapter",
+                        "Composite": {"temp1_input": 38.85, "temp1_alarm": 0.0},
+                        "Sensor 1": {"temp2_input": 38.85}
+                    }
+                }"#,
+            ),
+        );
+        assert_eq!(merged.len(), 4);
+        assert_eq!(merged[3].name, "nvme");
+        assert_eq!(merged[3].bus.as_deref(), Some("pci"));
+        assert_eq!(merged[3].temperatures, 2);
+    }
+
+    #[test]
+    fn test_input_kind() {
+        assert_eq!(input_kind("temp1_input"), Some(SensorKind::Temperature));
+        assert_eq!(input_kind("fan12_input"), Some(SensorKind::Fan));
+        assert_eq!(input_kind("in0_input"), Some(SensorKind::Voltage));
+        assert_eq!(input_kind("temp1_crit"), None);
+        assert_eq!(input_kind("intrusion0_input"), None);
+        assert_eq!(input_kind("power1_input"), None);
+    }
+}
@@ -0,0 +1,247 @@
+//! Immutable distribution detection
+//!
+//! Fedora Atomic desktops (Silverblue, Kinoite and their derivatives),
+//! openSUSE MicroOS with Aeon and Kalpa, and SteamOS boot a read-only system
+//! image that updates replace as a whole. Packages cannot be installed with
+//! dnf, zypper or pacman there: rpm-ostree layers them onto the next
+//! deployment, transactional-update installs them into a new snapshot, and
+//! on SteamOS anything added to the image is lost on the next update. Install
+//! advice has to take that into account, and drivers deserve a warning, as a
+//! layered kernel module is rebuilt on every update and can hold it back.
+
+use std::path::Path;
+
+/// os-release IDs of distributions updated with transactional-update
+const TRANSACTIONAL_IDS: &[&str] =
+    &["opensuse-microos", "opensuse-aeon", "opensuse-kalpa", "sl-micro", "leap-micro"];
+
+/// Name substrings identifying immutable distributions in a report's
+/// distribution string, lowercase
+const DISTRIBUTION_NAMES: &[(&str, ImmutableKind)] = &[
+    ("silverblue", ImmutableKind::RpmOstree),
+    ("kinoite", ImmutableKind::RpmOstree),
+    ("sericea", ImmutableKind::RpmOstree),
+    ("onyx", ImmutableKind::RpmOstree),
+    ("atomic", ImmutableKind::RpmOstree),
+    ("coreos", ImmutableKind::RpmOstree),
+    ("bazzite", ImmutableKind::RpmOstree),
+    ("bluefin", ImmutableKind::RpmOstree),
+    ("aurora", ImmutableKind::RpmOstree),
+    ("microos", ImmutableKind::TransactionalUpdate),
+    ("aeon", ImmutableKind::TransactionalUpdate),
+    ("kalpa", ImmutableKind::TransactionalUpdate),
+    ("leap micro", ImmutableKind::TransactionalUpdate),
+    ("sl micro", ImmutableKind::TransactionalUpdate),
+    ("steamos", ImmutableKind::SteamOs),
+];
+
+/// How an immutable distribution takes additional packages
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImmutableKind {
+    /// Fedora Atomic desktops and CoreOS: packages are layered with rpm-ostree
+    RpmOstree,
+    /// openSUSE MicroOS family: packages go into a new btrfs snapshot
+    TransactionalUpdate,
+    /// SteamOS: the read-only image can be unlocked, but updates discard
+    /// anything installed into it
+    SteamOs,
+}
+
+impl ImmutableKind {
+    /// Command installing packages, followed by the package names
+    pub fn install_command(self) -> &'static str {
+        match self {
+            Self::RpmOstree => "rpm-ostree install --idempotent",
+            Self::TransactionalUpdate => "transactional-update --non-interactive pkg install",
+            Self::SteamOs => "steamos-readonly disable && pacman -S --needed",
+        }
+    }
+
+    /// Whether installed packages only become available after a reboot
+    pub fn requires_reboot(self) -> bool {
+        !matches!(self, Self::SteamOs)
+    }
+
+    /// Warning to show with driver installs
+    pub fn driver_warning(self) -> &'static str {
+        match self {
+            Self::RpmOstree => "This is an rpm-ostree system: the driver is layered onto the next deployment and needs a reboot. Layered kernel modules are rebuilt on every update and a failed build blocks the update, so prefer an image that ships the driver where one exists.",
+            Self::TransactionalUpdate => "This is a transactional system: the driver is installed into a new snapshot and is only available after a reboot. Out-of-tree modules must be rebuilt in a snapshot after every kernel update.",
+            Self::SteamOs => "SteamOS updates replace the system image and remove packages installed into it, so the driver has to be reinstalled after every update.",
+        }
+    }
+
+    /// Rewrite a dnf, zypper or pacman install command (with or without
+    /// sudo) for this system; None for other commands
+    pub fn rewrite_install_command(self, command: &str) -> Option<String> {
+        let command = command.trim_start();
+        let sudo = command.starts_with("sudo ");
+        let command = command.strip_prefix("sudo ").unwrap_or(command);
+        let mut words = command.split_whitespace();
+        let verb_found = match words.next()? {
+            "dnf" | "yum" | "zypper" => {
+                words.by_ref().any(|word| word == "install" || word == "in")
+            }
+            "pacman" => words.by_ref().any(|word| word.starts_with("-S")),
+            _ => false,
+        };
+        if !verb_found {
+            return None;
+        }
+        let packages: Vec<&str> = words.filter(|word| !word.starts_with('-')).collect();
+        if packages.is_empty() {
+            return None;
+        }
+        let install: Vec<String> = self
+            .install_command()
+            .split(" && ")
+            .map(|step| if sudo { format!("sudo {}", step) } else { step.to_string() })
+            .collect();
+        Some(format!("{} {}", install.join(" && "), packages.join(" ")))
+    }
+}
+
+/// A detected immutable distribution
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImmutableSystem {
+    pub kind: ImmutableKind,
+    /// os-release VARIANT_ID or ID, e.g. "silverblue" or "opensuse-aeon"
+    pub variant: Option<String>,
+}
+
+/// Detect whether the system under `root` is immutable
+///
+/// `root` is "/" except in tests. rpm-ostree systems are recognised by the
+/// marker ostree creates at boot, the others from os-release, with a
+/// read-only root carrying transactional-update as a fallback.
+pub fn detect_immutable(root: &Path) -> Option<ImmutableSystem> {
+    let os_release = std::fs::read_to_string(root.join("etc/os-release"))
+        .or_else(|_| std::fs::read_to_string(root.join("usr/lib/os-release")))
+        .unwrap_or_default();
+    let id = os_release_value(&os_release, "ID");
+    let variant = os_release_value(&os_release, "VARIANT_ID").or_else(|| id.clone());
+    let found = |kind| Some(ImmutableSystem { kind, variant: variant.clone() });
+
+    if root.join("run/ostree-booted").exists() {
+        return found(ImmutableKind::RpmOstree);
+    }
+    match id.as_deref() {
+        Some("steamos") => return found(ImmutableKind::SteamOs),
+        Some(id) if TRANSACTIONAL_IDS.contains(&id) => {
+            return found(ImmutableKind::TransactionalUpdate)
+        }
+        _ => {}
+    }
+    if root.join("usr/sbin/transactional-update").exists() && root_is_read_only(root) {
+        return found(ImmutableKind::TransactionalUpdate);
+    }
+    None
+}
+
+/// Classify a distribution name as recorded in a report, e.g.
+/// "Fedora Linux 40.20240416.0 (Silverblue)" or "openSUSE MicroOS"
+pub fn classify_distribution(name: &str) -> Option<ImmutableKind> {
+    let name = name.to_lowercase().replace(['-', '_'], " ");
+    DISTRIBUTION_NAMES.iter().find(|(needle, _)| name.contains(needle)).map(|(_, kind)| *kind)
+}
+
+fn os_release_value(content: &str, key: &str) -> Option<String> {
+    content.lines().find_map(|line| {
+        let value = line.strip_prefix(key)?.strip_prefix('=')?;
+        let value = value.trim().trim_matches('"').trim_matches('\'');
+        (!value.is_empty()).then(|| value.to_string())
+    })
+}
+
+fn root_is_read_only(root: &Path) -> bool {
+    std::fs::read_to_string(root.join("proc/mounts")).is_ok_and(|mounts| {
+        mounts.lines().any(|line| {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            fields.get(1) == Some(&"/")
+                && fields.get(3).is_some_and(|options| options.split(',').any(|o| o == "ro"))
+        })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn write(root: &Path, file: &str, content: &str) {
+        let path = root.join(file);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, content).unwrap();
+    }
+
+    #[test]
+    fn test_detect_immutable() {
+        let root = TempDir::new().unwrap();
+        write(
+            root.path(),
+            "etc/os-release",
+            "NAME=\"Fedora Linux\"\nID=fedora\nVARIANT_ID=workstation\n",
+        );
+        assert_eq!(detect_immutable(root.path()), None);
+
+        write(
+            root.path(),
+            "etc/os-release",
+            "NAME=\"Fedora Linux\"\nID=fedora\nVARIANT_ID=silverblue\n",
+        );
+        write(root.path(), "run/ostree-booted", "");
+        let silverblue = detect_immutable(root.path()).unwrap();
+        assert_eq!(silverblue.kind, ImmutableKind::RpmOstree);
+        assert_eq!(silverblue.variant.as_deref(), Some("silverblue"));
+
+        let root = TempDir::new().unwrap();
+        write(
+            root.path(),
+            "etc/os-release",
+            "NAME=\"openSUSE Tumbleweed\"\nID=\"opensuse-tumbleweed\"\n",
+        );
+        write(root.path(), "usr/sbin/transactional-update", "");
+        write(root.path(), "proc/mounts", "/dev/nvme0n1p2 / btrfs rw,relatime 0 0\n");
+        assert_eq!(detect_immutable(root.path()), None);
+        write(root.path(), "proc/mounts", "/dev/nvme0n1p2 / btrfs ro,relatime 0 0\n");
+        assert_eq!(detect_immutable(root.path()).unwrap().kind, ImmutableKind::TransactionalUpdate);
+
+        let root = TempDir::new().unwrap();
+        write(root.path(), "usr/lib/os-release", "NAME=\"SteamOS\"\nID=steamos\nID_LIKE=arch\n");
+        assert_eq!(detect_immutable(root.path()).unwrap().kind, ImmutableKind::SteamOs);
+    }
+
+    #[test]
+    fn test_classify_and_rewrite() {
+        assert_eq!(
+            classify_distribution("Fedora Linux 40.20240416.0 (Silverblue)"),
+            Some(ImmutableKind::RpmOstree)
+        );
+        assert_eq!(
+            classify_distribution("openSUSE-MicroOS"),
+            Some(ImmutableKind::TransactionalUpdate)
+        );
+        assert_eq!(classify_distribution("Fedora Linux 40 (Workstation Edition)"), None);
+
+        assert_eq!(
+            ImmutableKind::RpmOstree
+                .rewrite_install_command("sudo dnf install -y akmod-wl")
+                .as_deref(),
+            Some("sudo rpm-ostree install --idempotent akmod-wl")
+        );
+        assert_eq!(
+            ImmutableKind::TransactionalUpdate
+                .rewrite_install_command("zypper --non-interactive install ucode-intel")
+                .as_deref(),
+            Some("transactional-update --non-interactive pkg install ucode-intel")
+        );
+        assert_eq!(
+            ImmutableKind::SteamOs
+                .rewrite_install_command("sudo pacman -S --noconfirm xone-dkms")
+                .as_deref(),
+            Some("sudo steamos-readonly disable && sudo pacman -S --needed xone-dkms")
+        );
+        assert_eq!(ImmutableKind::SteamOs.rewrite_install_command("sudo apt install foo"), None);
+        assert_eq!(ImmutableKind::RpmOstree.rewrite_install_command("git clone x"), None);
+    }
+}
@@ -0,0 +1,561 @@
+//! Keyboards, pointing devices, touchscreens and tablets
+//!
+//! The kernel lists every input device in `/proc/bus/input/devices`: its bus
+//! and IDs, name, event handlers and capability bitmaps. The type is told
+//! from those bitmaps, and the kernel driver from the device the input node
+//! hangs off in sysfs. Whether a touchpad works well on a laptop mostly
+//! depends on the userspace driver handling it, so devices `libinput
+//! list-devices` reports are marked as handled by libinput, and touchpads
+//! as handled by synaptics when Xorg is configured for it.
+
+use crate::hardware::InputDevice;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+/// Directory levels searched above an input node for its bound driver
+const MAX_PARENT_DEPTH: usize = 3;
+
+// Bits of the EV and PROP bitmaps, from linux/input-event-codes.h
+const EV_REL: u32 = 0x02;
+const EV_ABS: u32 = 0x03;
+const EV_REP: u32 = 0x14;
+const INPUT_PROP_POINTER: u32 = 0x00;
+const INPUT_PROP_DIRECT: u32 = 0x01;
+
+/// Device as listed by `libinput list-devices`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LibinputDevice {
+    /// Event node, e.g. "event6"
+    pub event: String,
+    /// e.g. ["pointer", "gesture"] for a touchpad
+    pub capabilities: Vec<String>,
+}
+
+/// Read keyboards, mice, touchpads, touchscreens and tablets
+///
+/// Virtual devices and inputs that are only buttons or switches, such as
+/// the power button and lid switch, are left out.
+pub fn read_input_devices(
+    proc_devices: &Path,
+    sys_root: &Path,
+    libinput: &[LibinputDevice],
+    xorg_touchpad_driver: Option<&str>,
+) -> Vec<InputDevice> {
+    let Ok(content) = std::fs::read_to_string(proc_devices) else {
+        return Vec::new();
+    };
+
+    let mut devices: Vec<InputDevice> = Vec::new();
+    for block in parse_proc_devices(&content) {
+        let Some(bus) = bus_name(&block.bus) else {
+            continue;
+        };
+        let handled = block
+            .handlers
+            .iter()
+            .find_map(|handler| libinput.iter().find(|device| &device.event == handler));
+        let Some(device_type) = classify(&block).or_else(|| handled.and_then(classify_libinput))
+        else {
+            continue;
+        };
+        let userspace_driver = match (device_type, xorg_touchpad_driver) {
+            ("touchpad", Some(driver)) => Some(driver.to_string()),
+            _ => handled.map(|_| "libinput".to_string()),
+        };
+        let driver = block.sysfs.as_deref().and_then(|sysfs| {
+            find_driver(&sys_root.join(sysfs.trim_start_matches('/')).join("device"))
+        });
+        let device = InputDevice {
+            name: public_name(&block.name, device_type, bus),
+            device_type: device_type.to_string(),
+            bus: bus.to_string(),
+            device_id: format!("{}:{}", block.vendor, block.product),
+            driver,
+            userspace_driver,
+        };
+        if !devices.contains(&device) {
+            devices.push(device);
+        }
+    }
+
+    // Touchpads often expose a second, relative "Mouse" node with the same IDs
+    let touchpads: HashSet<(String, String)> = devices
+        .iter()
+        .filter(|device| device.device_type == "touchpad")
+        .map(|device| (device.bus.clone(), device.device_id.clone()))
+        .collect();
+    devices.retain(|device| {
+        device.device_type != "mouse"
+            || !touchpads.contains(&(device.bus.clone(), device.device_id.clone()))
+    });
+    devices
+}
+
+/// List the devices libinput handles with `libinput list-devices`
+///
+/// Needs access to `/dev/input`, so usually root; returns an empty list
+/// otherwise.
+pub fn query_libinput() -> Vec<LibinputDevice> {
+    let output =
+        std::process::Command::new("libinput").arg("list-devices").env("LC_ALL", "C").output();
+    match output {
+        Ok(output) if output.status.success() => {
+            parse_libinput_devices(&String::from_utf8_lossy(&output.stdout))
+        }
+        _ => Vec::new(),
+    }
+}
+
+/// Xorg configuration files in increasing precedence
+///
+/// Snippets are applied in file name order, with a file in `/etc`
+/// replacing the one of the same name in `/usr/share`.
+pub fn xorg_config_files() -> Vec<PathBuf> {
+    let mut snippets: Vec<(std::ffi::OsString, PathBuf)> = Vec::new();
+    for dir in ["/usr/share/X11/xorg.conf.d", "/etc/X11/xorg.conf.d"] {
+        let Ok(entries) = std::fs::read_dir(dir) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let name = entry.file_name();
+            if !name.to_string_lossy().ends_with(".conf") {
+                continue;
+            }
+            snippets.retain(|(existing, _)| existing != &name);
+            snippets.push((name, entry.path()));
+        }
+    }
+    snippets.sort();
+
+    let mut files: Vec<PathBuf> = snippets.into_iter().map(|(_, path)| path).collect();
+    files.push(PathBuf::from("/etc/X11/xorg.conf"));
+    files
+}
+
+/// Driver Xorg assigns to touchpads, from the last matching InputClass
+pub fn xorg_touchpad_driver(files: &[PathBuf]) -> Option<String> {
+    files.iter().rev().find_map(|file| {
+        std::fs::read_to_string(file).ok().and_then(|content| parse_xorg_touchpad_driver(&content))
+    })
+}
+
+/// One device block of `/proc/bus/input/devices`
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+struct ProcDevice {
+    bus: String,
+    vendor: String,
+    product: String,
+    name: String,
+    /// Path below /sys, e.g. "/devices/platform/i8042/serio1/input/input5"
+    sysfs: Option<String>,
+    handlers: Vec<String>,
+    ev: String,
+    prop: String,
+}
+
+impl ProcDevice {
+    fn has_ev(&self, bit: u32) -> bool {
+        bitmap_has(&self.ev, bit)
+    }
+
+    fn has_prop(&self, bit: u32) -> bool {
+        bitmap_has(&self.prop, bit)
+    }
+
+    fn has_handler(&self, prefix: &str) -> bool {
+        self.handlers.iter().any(|handler| {
+            handler.strip_prefix(prefix).is_some_and(|n| n.chars().all(|c| c.is_ascii_digit()))
+        })
+    }
+
+    /// Whether a lowercase word of the name is one of `words`
+    fn name_has(&self, words: &[&str]) -> bool {
+        self.name
+            .split(|c: char| !c.is_ascii_alphanumeric())
+            .any(|word| words.contains(&word.to_ascii_lowercase().as_str()))
+    }
+}
+
+/// Parse `/proc/bus/input/devices` into device blocks
+fn parse_proc_devices(content: &str) -> Vec<ProcDevice> {
+    let mut devices = Vec::new();
+    let mut current: Option<ProcDevice> = None;
+    for line in content.lines() {
+        let Some((kind, value)) = line.split_once(": ") else {
+            if line.trim().is_empty() {
+                devices.extend(current.take());
+            }
+            continue;
+        };
+        let device = current.get_or_insert_with(ProcDevice::default);
+        match kind {
+            "I" => {
+                for field in value.split_whitespace() {
+                    match field.split_once('=') {
+                        Some(("Bus", bus)) => device.bus = bus.to_ascii_lowercase(),
+                        Some(("Vendor", vendor)) => device.vendor = vendor.to_ascii_lowercase(),
+                        Some(("Product", product)) => device.product = product.to_ascii_lowercase(),
+                        _ => {}
+                    }
+                }
+            }
+            "N" => {
+                if let Some(name) = value.strip_prefix("Name=") {
+                    device.name = name.trim_matches('"').trim().to_string();
+                }
+            }
+            "S" => device.sysfs = value.strip_prefix("Sysfs=").map(str::to_string),
+            "H" => {
+                if let Some(handlers) = value.strip_prefix("Handlers=") {
+                    device.handlers = handlers.split_whitespace().map(str::to_string).collect();
+                }
+            }
+            "B" => match value.split_once('=') {
+                Some(("EV", bitmap)) => device.ev = bitmap.to_string(),
+                Some(("PROP", bitmap)) => device.prop = bitmap.to_string(),
+                _ => {}
+            },
+            _ => {}
+        }
+    }
+    devices.extend(current);
+    devices
+}
+
+/// Whether a bit is set in a bitmap as the kernel prints it
+///
+/// The bitmap is a list of hex words, most significant first, each the
+/// width of a `long`.
+fn bitmap_has(bitmap: &str, bit: u32) -> bool {
+    let word_bits = usize::BITS;
+    bitmap
+        .split_whitespace()
+        .rev()
+        .nth((bit / word_bits) as usize)
+        .and_then(|word| u64::from_str_radix(word, 16).ok())
+        .is_some_and(|word| word & (1 << (bit % word_bits)) != 0)
+}
+
+/// Type of a device from its capabilities, handlers and name
+fn classify(device: &ProcDevice) -> Option<&'static str> {
+    if device.has_ev(EV_ABS) {
+        if device.name_has(&["pen", "stylus", "eraser"]) {
+            return Some("tablet");
+        }
+        if device.has_prop(INPUT_PROP_DIRECT) || device.name_has(&["touchscreen"]) {
+            return Some("touchscreen");
+        }
+        if device.has_prop(INPUT_PROP_POINTER)
+            || device.name_has(&["touchpad", "trackpad", "clickpad", "glidepoint"])
+        {
+            return Some("touchpad");
+        }
+    }
+    if device.has_ev(EV_REL) && device.has_handler("mouse") {
+        return Some("mouse");
+    }
+    // Button-only devices such as power buttons and hotkeys have no autorepeat
+    if device.has_ev(EV_REP) && device.has_handler("kbd") {
+        return Some("keyboard");
+    }
+    None
+}
+
+/// Type of a device from the capabilities libinput gives it
+fn classify_libinput(device: &LibinputDevice) -> Option<&'static str> {
+    let has = |capability: &str| device.capabilities.iter().any(|c| c == capability);
+    if has("tablet") {
+        Some("tablet")
+    } else if has("touch") {
+        Some("touchscreen")
+    } else if has("gesture") {
+        Some("touchpad")
+    } else if has("pointer") {
+        Some("mouse")
+    } else if has("keyboard") {
+        Some("keyboard")
+    } else {
+        None
+    }
+}
+
+/// Bus of an input device from its `Bus=` code, or None for virtual devices
+fn bus_name(code: &str) -> Option<&'static str> {
+    Some(match u16::from_str_radix(code, 16).ok()? {
+        0x03 => "usb",
+        0x05 => "bluetooth",
+        0x06 => return None,
+        0x11 => "ps2",
+        0x18 => "i2c",
+        0x19 => "host",
+        0x1c => "spi",
+        0x1d => "rmi",
+        _ => "other",
+    })
+}
+
+/// Device name as it can go into a report
+///
+/// Bluetooth devices are often named after their owner ("Alice's Magic
+/// Mouse"); such names are replaced with the device type.
+fn public_name(name: &str, device_type: &str, bus: &str) -> String {
+    if name.is_empty() || name.contains("'s ") || name.contains("\u{2019}s ") {
+        format!("{} {}", bus, device_type)
+    } else {
+        name.to_string()
+    }
+}
+
+/// Driver bound to the nearest device at or above a path
+fn find_driver(device: &Path) -> Option<String> {
+    let device = std::fs::canonicalize(device).ok()?;
+    device.ancestors().take(MAX_PARENT_DEPTH).find_map(|dir| {
+        let target = std::fs::read_link(dir.join("driver")).ok()?;
+        Some(target.file_name()?.to_string_lossy().into_owned())
+    })
+}
+
+/// Parse `libinput list-devices` output
+fn parse_libinput_devices(output: &str) -> Vec<LibinputDevice> {
+    let mut devices = Vec::new();
+    let mut event: Option<String> = None;
+    let mut capabilities = Vec::new();
+    for line in output.lines().chain(std::iter::once("")) {
+        match line.split_once(':') {
+            Some(("Kernel", path)) => {
+                event = path.trim().rsplit('/').next().map(str::to_string);
+            }
+            Some(("Capabilities", list)) => {
+                capabilities = list.split_whitespace().map(str::to_string).collect();
+            }
+            _ if line.trim().is_empty() => {
+                if let Some(event) = event.take() {
+                    devices.push(LibinputDevice {
+                        event,
+                        capabilities: std::mem::take(&mut capabilities),
+                    });
+                }
+            }
+            _ => {}
+        }
+    }
+    devices
+}
+
+/// Driver of the last touchpad InputClass in an Xorg configuration file
+fn parse_xorg_touchpad_driver(content: &str) -> Option<String> {
+    let mut driver = None;
+    let mut in_class = false;
+    let mut touchpad = false;
+    let mut class_driver = None;
+    for line in content.lines() {
+        let line = line.split('#').next().unwrap_or("").trim();
+        let mut words = line.split_whitespace();
+        let keyword = words.next().unwrap_or("").to_ascii_lowercase();
+        let value = words.collect::<Vec<_>>().join(" ");
+        let value = value.trim_matches('"');
+        match keyword.as_str() {
+            "section" => {
+                in_class = value.eq_ignore_ascii_case("InputClass");
+                touchpad = false;
+                class_driver = None;
+            }
+            "endsection" if in_class => {
+                if touchpad {
+                    driver = class_driver.take().or(driver);
+                }
+                in_class = false;
+            }
+            "matchistouchpad" if in_class => {
+                touchpad =
+                    ["on", "true", "yes", "1"].iter().any(|on| value.eq_ignore_ascii_case(on));
+            }
+            "driver" if in_class => class_driver = Some(value.to_string()),
+            _ => {}
+        }
+    }
+    driver
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::os::unix::fs::symlink;
+    use tempfile::TempDir;
+
+    const PROC_DEVICES: &str = r#"I: Bus=0019 Vendor=0000 Product=0001 Version=0000
+N: Name="Power Button"
+P: Phys=PNP0C0C/button/input0
+S: Sysfs=/devices/LNXSYSTM:00/LNXPWRBN:00/input/input0
+U: Uniq=
+H: Handlers=kbd event0
+B: PROP=0
+B: EV=3
+B: KEY=10000000000000 0
+
+I: Bus=0011 Vendor=0001 Product=0001 Version=ab83
+N: Name="AT Translated Set 2 keyboard"
+P: Phys=isa0060/serio0/input0
+S: Sysfs=/devices/platform/i8042/serio0/input/input3
+U: Uniq=
+H: Handlers=sysrq kbd leds event3
+B: PROP=0
+B: EV=120013
+B: KEY=402000000 3803078f800d001 feffffdfffefffff fffffffffffffffe
+B: MSC=10
+B: LED=7
+
+I: Bus=0018 Vendor=04f3 Product=3187 Version=0100
+N: Name="ELAN0672:00 04F3:3187 Mouse"
+P: Phys=i2c-ELAN0672:00
+S: Sysfs=/devices/platform/AMDI0010:03/i2c-1/i2c-ELAN0672:00/0018:04F3:3187.0001/input/input8
+U: Uniq=
+H: Handlers=mouse0 event6
+B: PROP=0
+B: EV=17
+B: KEY=30000 0 0 0 0
+B: REL=3
+
+I: Bus=0018 Vendor=04f3 Product=3187 Version=0100
+N: Name="ELAN0672:00 04F3:3187 Touchpad"
+P: Phys=i2c-ELAN0672:00
+S: Sysfs=/devices/platform/AMDI0010:03/i2c-1/i2c-ELAN0672:00/0018:04F3:3187.0001/input/input9
+U: Uniq=
+H: Handlers=mouse1 event7
+B: PROP=5
+B: EV=1b
+B: KEY=e520 10000 0 0 0 0
+B: ABS=2e0800000000003
+
+I: Bus=0005 Vendor=004c Product=0269 Version=0001
+N: Name="Alice's Magic Mouse"
+P: Phys=3c:7c:3f:12:34:56
+S: Sysfs=/devices/virtual/misc/uhid/0005:004C:0269.0002/input/input12
+U: Uniq=3c:7c:3f:aa:bb:cc
+H: Handlers=mouse2 event10
+B: PROP=0
+B: EV=17
+B: REL=143
+
+I: Bus=0006 Vendor=0000 Product=0000 Version=0000
+N: Name="ydotoold virtual device"
+P: Phys=
+S: Sysfs=/devices/virtual/input/input20
+U: Uniq=
+H: Handlers=sysrq kbd mouse3 event20
+B: PROP=0
+B: EV=100007
+"#;
+
+    fn fixture() -> TempDir {
+        let root = TempDir::new().unwrap();
+        let drivers = root.path().join("bus/drivers");
+        for driver in ["atkbd", "hid-multitouch"] {
+            std::fs::create_dir_all(drivers.join(driver)).unwrap();
+        }
+        let devices = root.path().join("devices");
+
+        let serio = devices.join("platform/i8042/serio0");
+        std::fs::create_dir_all(serio.join("input/input3")).unwrap();
+        symlink(drivers.join("atkbd"), serio.join("driver")).unwrap();
+        symlink(&serio, serio.join("input/input3/device")).unwrap();
+
+        let hid = devices.join("platform/AMDI0010:03/i2c-1/i2c-ELAN0672:00/0018:04F3:3187.0001");
+        std::fs::create_dir_all(&hid).unwrap();
+        symlink(drivers.join("hid-multitouch"), hid.join("driver")).unwrap();
+        for input in ["input8", "input9"] {
+            std::fs::create_dir_all(hid.join("input").join(input)).unwrap();
+            symlink(&hid, hid.join("input").join(input).join("device")).unwrap();
+        }
+
+        std::fs::write(root.path().join("devices.txt"), PROC_DEVICES).unwrap();
+        root
+    }
+
+    #[test]
+    fn test_read_input_devices() {
+        let root = fixture();
+        let libinput = parse_libinput_devices(
+            "Device:           AT Translated Set 2 keyboard\n\
+             Kernel:           /dev/input/event3\n\
+             Capabilities:     keyboard \n\
+             \n\
+             Device:           ELAN0672:00 04F3:3187 Touchpad\n\
+             Kernel:           /dev/input/event7\n\
+             Size:             70x49mm\n\
+             Capabilities:     pointer gesture\n\
+             Tap-to-click:     disabled\n",
+        );
+        assert_eq!(libinput.len(), 2);
+        assert_eq!(libinput[1].capabilities, ["pointer", "gesture"]);
+
+        let devices =
+            read_input_devices(&root.path().join("devices.txt"), root.path(), &libinput, None);
+        let summary: Vec<(&str, &str, &str)> = devices
+            .iter()
+            .map(|d| (d.name.as_str(), d.device_type.as_str(), d.bus.as_str()))
+            .collect();
+        // The power button, the touchpad's mouse node and the virtual device are left out
+        assert_eq!(
+            summary,
+            [
+                ("AT Translated Set 2 keyboard", "keyboard", "ps2"),
+                ("ELAN0672:00 04F3:3187 Touchpad", "touchpad", "i2c"),
+                ("bluetooth mouse", "mouse", "bluetooth"),
+            ]
+        );
+        assert_eq!(devices[0].driver.as_deref(), Some("atkbd"));
+        assert_eq!(devices[1].device_id, "04f3:3187");
+        assert_eq!(devices[1].driver.as_deref(), Some("hid-multitouch"));
+        assert_eq!(devices[1].userspace_driver.as_deref(), Some("libinput"));
+        assert_eq!(devices[2].userspace_driver, None);
+
+        let devices = read_input_devices(
+            &root.path().join("devices.txt"),
+            root.path(),
+            &libinput,
+            Some("synaptics"),
+        );
+        assert_eq!(devices[0].userspace_driver.as_deref(), Some("libinput"));
+        assert_eq!(devices[1].userspace_driver.as_deref(), Some("synaptics"));
+    }
+
+    #[test]
+    fn test_xorg_touchpad_driver() {
+        let libinput = r#"
+Section "InputClass"
+        Identifier "libinput touchpad catchall"
+        MatchIsTouchpad "on"
+        MatchDevicePath "/dev/input/event*"
+        Driver "libinput"
+EndSection
+"#;
+        let synaptics = r#"
+Section "InputClass"
+        Identifier "touchpad catchall"
+        Driver "synaptics"
+        MatchIsTouchpad "on"
+# Driver "evdev"
+EndSection
+
+Section "InputClass"
+        Identifier "keyboard"
+        MatchIsKeyboard "on"
+        Driver "evdev"
+EndSection
+"#;
+        assert_eq!(parse_xorg_touchpad_driver(libinput).as_deref(), Some("libinput"));
+        assert_eq!(parse_xorg_touchpad_driver(synaptics).as_deref(), Some("synaptics"));
+
+        let root = TempDir::new().unwrap();
+        let files = [root.path().join("40-libinput.conf"), root.path().join("70-synaptics.conf")];
+        std::fs::write(&files[0], libinput).unwrap();
+        std::fs::write(&files[1], synaptics).unwrap();
+        assert_eq!(xorg_touchpad_driver(&files).as_deref(), Some("synaptics"));
+        assert_eq!(xorg_touchpad_driver(&files[..1]).as_deref(), Some("libinput"));
+
+        assert!(bitmap_has("120013", EV_REP));
+        assert!(!bitmap_has("3", EV_REP));
+        assert!(bitmap_has("5", INPUT_PROP_POINTER));
+    }
+}
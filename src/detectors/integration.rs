@@ -5,22 +5,55 @@
 
 #![allow(clippy::excessive_nesting)]
 
+#[cfg(feature = "lshw")]
+use crate::detectors::extract::pci_id_from_businfo;
+#[cfg(feature = "lspci")]
+use crate::detectors::extract::sysfs_pci_address;
 use crate::detectors::kernel::{KernelSupportVerifier, SupportLevel};
-use crate::detectors::{DetectionData, DetectionResult, DetectorRegistry};
+use crate::detectors::microcode::MicrocodeDatabase;
+use crate::detectors::raw::RawOutput;
+use crate::detectors::udev::{self, Uevent};
+use crate::detectors::DetectionData;
+use crate::detectors::{privilege, remote, replay, sandbox, virt};
+use crate::detectors::{
+    ComponentExtractor, DetectionError, DetectionProfile, DetectionResult, DetectorRegistry,
+    ExtractionContext, RetryPolicy,
+};
 use crate::errors::Result;
+use crate::hardware::lifecycle::LifecycleDatabase;
+use crate::hardware::pci_class;
+use crate::hardware::provenance::Provenance;
+use crate::hardware::taxonomy::Taxonomy;
 use crate::hardware::{
-    AudioDevice, CpuInfo, DeviceCompatibility, GraphicsDevice, HardwareReport,
-    KernelCompatibilityInfo, MemoryDimm, MemoryInfo, NetworkDevice, PrivacyLevel, ReportMetadata,
-    StorageDevice, SystemInfo, UsbDevice,
+    AudioDevice, AudioRouting, CpuInfo, DeviceCompatibility, DkmsState, FirmwareDevice,
+    FirmwareInfo, GraphicsDevice, HardwareReport, InputDevice, KernelCompatibilityInfo,
+    LifecycleNotice, MemoryDimm, MemoryInfo, NetworkDevice, PcieDeviceLink, Peripherals,
+    PhaseError, PrivacyLevel, PrivilegeStatus, RadioState, ReportMetadata, SensorChip,
+    StorageDevice, StorageStack, SystemInfo, UnknownDevice, UsbDevice,
 };
-use crate::privacy::PrivacyManager;
+use crate::privacy::raw::RawSanitizer;
+use crate::privacy::{PrivacyManager, PseudonymKey};
 use chrono::Utc;
+use futures::FutureExt;
+use std::collections::HashMap;
+use std::future::Future;
+use std::panic::AssertUnwindSafe;
+use std::path::Path;
+
+/// Maximum number of kernel log lines captured per unknown device
+const MAX_DMESG_LINES: usize = 5;
 
 /// Comprehensive hardware analysis combining detection and kernel verification
 pub struct HardwareAnalyzer {
     detector_registry: DetectorRegistry,
     kernel_verifier: KernelSupportVerifier,
     privacy_manager: PrivacyManager,
+    taxonomy: Taxonomy,
+    lifecycle: LifecycleDatabase,
+    microcode: MicrocodeDatabase,
+    profile: DetectionProfile,
+    /// Field sources and tool disagreements of the report being built
+    provenance: Provenance,
 }
 
 impl HardwareAnalyzer {
@@ -30,9 +63,30 @@ impl HardwareAnalyzer {
             detector_registry: DetectorRegistry::new(),
             kernel_verifier: KernelSupportVerifier::new()?,
             privacy_manager: PrivacyManager::new(privacy_level)?,
+            taxonomy: Taxonomy::builtin(),
+            lifecycle: LifecycleDatabase::builtin(),
+            microcode: MicrocodeDatabase::builtin(),
+            profile: DetectionProfile::Full,
+            provenance: Provenance::default(),
         })
     }
 
+    /// Select the detection profile (see [`DetectionProfile`])
+    pub fn set_profile(&mut self, profile: DetectionProfile) {
+        self.profile = profile;
+        self.detector_registry.set_profile(profile);
+    }
+
+    /// Truncate anonymized identifiers (see [`PrivacyManager::set_digest_length`])
+    pub fn set_digest_length(&mut self, length: usize) -> Result<()> {
+        self.privacy_manager.set_digest_length(length)
+    }
+
+    /// Derive identifiers from a user-held key (see [`PrivacyManager::use_pseudonym_key`])
+    pub fn use_pseudonym_key(&mut self, key: &PseudonymKey) -> Result<()> {
+        self.privacy_manager.use_pseudonym_key(key)
+    }
+
     /// Set specific tools to enable (filters out others)
     pub fn set_enabled_tools(&mut self, tool_names: Vec<String>) -> Result<()> {
         self.detector_registry.set_enabled_tools(tool_names)
@@ -43,33 +97,156 @@ impl HardwareAnalyzer {
         self.detector_registry.set_detection_timeout(timeout);
     }
 
+    /// Use a custom taxonomy to classify devices into components
+    pub fn set_taxonomy(&mut self, taxonomy: Taxonomy) {
+        self.taxonomy = taxonomy;
+    }
+
+    /// Override the retry policy for one tool, or for all tools when `tool` is None
+    pub fn set_retry_policy(&mut self, tool: Option<&str>, policy: RetryPolicy) -> Result<()> {
+        self.detector_registry.set_retry_policy(tool, policy)
+    }
+
+    /// Keep each tool's output for [`take_raw_outputs`](Self::take_raw_outputs)
+    pub fn set_capture_raw(&mut self, capture: bool) {
+        self.detector_registry.set_capture_raw(capture);
+    }
+
+    /// Take the tool output kept during analysis, with identifiers replaced
+    /// by the digests used in the report
+    pub fn take_raw_outputs(&mut self) -> Vec<RawOutput> {
+        let mut outputs = self.detector_registry.take_raw_outputs();
+        let mut sanitizer = self.raw_sanitizer();
+        for output in &mut outputs {
+            output.stdout = sanitizer.sanitize(&output.stdout);
+            output.stderr = sanitizer.sanitize(&output.stderr);
+        }
+        outputs
+    }
+
+    /// Sanitizer replacing identifiers in tool output and kernel log lines
+    /// with the digests used in the report
+    fn raw_sanitizer(&mut self) -> RawSanitizer<'_> {
+        let hostname = remote::is_local().then(local_hostname).flatten();
+        let sanitizer = RawSanitizer::new(&mut self.privacy_manager);
+        match &hostname {
+            Some(hostname) => sanitizer.with_hostname(hostname),
+            None => sanitizer,
+        }
+    }
+
+    /// Take fingerprints of tool output that failed to parse during analysis
+    pub fn take_parse_failures(&self) -> Vec<crate::telemetry::ParseFailure> {
+        self.detector_registry.take_parse_failures()
+    }
+
     /// Perform complete hardware analysis with kernel verification
+    ///
+    /// Each report-building phase runs in isolation: a failure, panic, or
+    /// timeout in one phase is recorded in `metadata.phase_errors` and the
+    /// remaining phases still contribute to the report.
     pub async fn analyze_system(&mut self) -> Result<HardwareReport> {
+        let mut phase_errors = Vec::new();
+
         // Step 1: Run hardware detection tools
         log::info!("Running hardware detection tools...");
         let detection_results = self.detector_registry.detect_all().await?;
 
-        // Step 2: Extract device IDs from detection results
-        let device_ids = self.extract_device_ids(&detection_results);
-
-        // Step 3: Verify kernel support for detected devices
-        log::info!("Verifying kernel support for {} devices...", device_ids.len());
-        let kernel_support = self.kernel_verifier.get_support_data(device_ids)?;
-
-        // Step 4: Build comprehensive compatibility information
-        let kernel_compatibility =
-            self.build_kernel_compatibility(&kernel_support, &detection_results)?;
+        // Steps 2-4: Extract device IDs and verify them against the running
+        // kernel's modules, which are only at hand for the local host
+        let kernel_compatibility = if remote::is_local() {
+            let device_ids = self.extract_device_ids(&detection_results);
+            log::info!("Verifying kernel support for {} devices...", device_ids.len());
+            isolate_phase(
+                "kernel_support",
+                async {
+                    let kernel_support = self.kernel_verifier.get_support_data(device_ids)?;
+                    self.build_kernel_compatibility(&kernel_support, &detection_results)
+                },
+                &mut phase_errors,
+            )
+            .await
+        } else {
+            None
+        };
 
         // Step 5: Generate anonymized hardware report
-        let report = self.build_hardware_report(detection_results, kernel_compatibility).await?;
+        let report = self
+            .build_hardware_report(detection_results, kernel_compatibility, phase_errors)
+            .await?;
 
         Ok(report)
     }
 
+    /// Privileges the tools that succeeded ran with, for the report metadata
+    fn privilege_status(&self, detection_results: &[DetectionResult]) -> Option<PrivilegeStatus> {
+        if !remote::is_local() {
+            return None;
+        }
+        let detectors = self.detector_registry.list_detectors();
+        let tools: Vec<(&str, bool)> = detection_results
+            .iter()
+            .filter(|result| result.success)
+            .filter_map(|result| {
+                let detector = detectors.iter().find(|d| d.name() == result.tool_name)?;
+                let permission_errors = result
+                    .errors
+                    .iter()
+                    .any(|error| matches!(error, DetectionError::PermissionDenied { .. }));
+                Some((detector.program(), permission_errors))
+            })
+            .collect();
+        privilege::status(&tools)
+    }
+
+    /// Apply a hotplug event to a report this analyzer built earlier
+    ///
+    /// A USB device coming or going is applied from a fresh sysfs
+    /// enumeration (see [`udev`]) instead of running the detection tools
+    /// again; devices still present keep the names the tools looked up.
+    /// Returns false for events that need a full detection run instead.
+    pub fn apply_hotplug(&mut self, report: &mut HardwareReport, event: &Uevent) -> Result<bool> {
+        if !event.is_usb_device_change() || !remote::is_local() {
+            return Ok(false);
+        }
+        log::debug!("Applying {} of {} to the report", event.action, event.devpath);
+        report.usb = self.refresh_usb(&report.usb, Path::new(udev::SYSFS_USB_DEVICES))?;
+        Ok(true)
+    }
+
+    /// USB devices enumerated from sysfs, reusing matching earlier entries
+    fn refresh_usb(&mut self, previous: &[UsbDevice], root: &Path) -> Result<Vec<UsbDevice>> {
+        let mut previous: Vec<Option<&UsbDevice>> = previous.iter().map(Some).collect();
+        let mut usb_devices = Vec::new();
+        for device in udev::enumerate_usb(root) {
+            // Entries from lsusb without sysfs attributes have no port path
+            let same = |old: &UsbDevice| {
+                old.vendor_id == device.vendor_id
+                    && old.product_id == device.product_id
+                    && (old.port_path.is_none()
+                        || old.port_path.as_deref() == Some(device.port_path.as_str()))
+            };
+            let index = previous.iter().position(|old| old.is_some_and(same));
+            if let Some(old) = index.and_then(|index| previous[index].take()) {
+                usb_devices.push(old.clone());
+                continue;
+            }
+
+            let anonymized_serial = match &device.serial {
+                Some(serial) => Some(self.privacy_manager.anonymize_identifier(serial)?),
+                None => None,
+            };
+            usb_devices.push(device.into_usb_device(anonymized_serial));
+        }
+        Ok(usb_devices)
+    }
+
     /// Extract device IDs from detection results
+    #[cfg_attr(not(feature = "lshw"), allow(unused_variables))]
     fn extract_device_ids(&self, results: &[DetectionResult]) -> Vec<(String, String)> {
         let mut device_ids = Vec::new();
 
+        #[cfg(feature = "lshw")]
         for result in results {
             let DetectionData::Lshw(data) = &result.data else {
                 continue;
@@ -81,7 +258,7 @@ impl HardwareAnalyzer {
                     continue;
                 };
 
-                if let Some(pci_id) = self.extract_pci_id_from_businfo(businfo) {
+                if let Some(pci_id) = pci_id_from_businfo(businfo) {
                     device_ids.push(pci_id);
                 }
             }
@@ -138,13 +315,14 @@ impl HardwareAnalyzer {
                 }
             };
 
-            // Generate configuration recommendations
+            // Recommend only options the running kernel's configuration leaves unset
             for dep in &device_support.config_dependencies {
-                let config_recommendation = format!(
-                    "Enable CONFIG_{} for module {}",
-                    dep.to_uppercase().replace('-', "_"),
-                    device_support.driver_module
-                );
+                let symbol = super::kernel_config::config_symbol(dep);
+                if kernel_support.config_options.get(&symbol) != Some(&false) {
+                    continue;
+                }
+                let config_recommendation =
+                    format!("Enable {} for module {}", symbol, device_support.driver_module);
                 if !config_recommendations.contains(&config_recommendation) {
                     config_recommendations.push(config_recommendation);
                 }
@@ -161,6 +339,21 @@ impl HardwareAnalyzer {
             });
         }
 
+        let root = Path::new("/");
+        let mut modules = super::kernel::configured_modules(root);
+        modules.extend(device_details.iter().map(|device| device.driver_module.clone()));
+
+        let mut dkms_modules = super::dkms::query_dkms_status()
+            .map(|status| super::dkms::parse_dkms_status(&status))
+            .unwrap_or_default();
+        super::dkms::flag_failed_rebuilds(&mut dkms_modules, &kernel_support.kernel_version);
+        for module in dkms_modules.iter().filter(|module| module.state == DkmsState::NotBuilt) {
+            missing_modules.push(format!(
+                "DKMS module {} {} did not build for kernel {}",
+                module.module, module.version, kernel_support.kernel_version
+            ));
+        }
+
         Ok(KernelCompatibilityInfo {
             kernel_version: kernel_support.kernel_version.clone(),
             total_devices_detected: kernel_support.supported_devices.len() as u32,
@@ -170,31 +363,15 @@ impl HardwareAnalyzer {
             device_support_details: device_details,
             missing_modules,
             config_recommendations,
+            module_parameters: super::kernel::read_module_parameters(root, &modules),
+            blacklisted_modules: super::kernel::read_module_blacklist(root),
+            dkms_modules,
         })
     }
 
-    /// Extract PCI ID from businfo string like "pci@0000:01:00.0"
-    fn extract_pci_id_from_businfo(&self, businfo: &str) -> Option<(String, String)> {
-        // lshw businfo format: "pci@0000:01:00.0"
-        // We need to read the corresponding sysfs files for vendor/device IDs
-        if businfo.starts_with("pci@") {
-            let pci_addr = businfo.strip_prefix("pci@")?;
-            let sysfs_path = format!("/sys/bus/pci/devices/{}", pci_addr);
-
-            if let (Ok(vendor), Ok(device)) = (
-                std::fs::read_to_string(format!("{}/vendor", sysfs_path)),
-                std::fs::read_to_string(format!("{}/device", sysfs_path)),
-            ) {
-                let vendor = vendor.trim().strip_prefix("0x")?.trim();
-                let device = device.trim().strip_prefix("0x")?.trim();
-                return Some((vendor.to_string(), device.to_string()));
-            }
-        }
-        None
-    }
-
     /// Get human-readable device name from detection results
     fn get_device_name(&self, device_id: &str, detection_results: &[DetectionResult]) -> String {
+        #[cfg(feature = "lshw")]
         for result in detection_results {
             let DetectionData::Lshw(data) = &result.data else {
                 continue;
@@ -205,7 +382,7 @@ impl HardwareAnalyzer {
                     continue;
                 };
 
-                let Some((vendor, device)) = self.extract_pci_id_from_businfo(businfo) else {
+                let Some((vendor, device)) = pci_id_from_businfo(businfo) else {
                     continue;
                 };
 
@@ -219,7 +396,37 @@ impl HardwareAnalyzer {
             }
         }
 
-        format!("Unknown Device {}", device_id)
+        // sysfs carries the pci.ids name when lshw is not installed
+        let sysfs_device = detection_results.iter().find_map(|result| match &result.data {
+            DetectionData::Sysfs(data) => data
+                .pci_devices
+                .iter()
+                .find(|d| format!("{}:{}", d.vendor_id, d.device_id) == device_id),
+            _ => None,
+        });
+        if let Some(name) = sysfs_device.and_then(|d| d.device_name.clone()) {
+            return name;
+        }
+
+        // Devices lshw did not describe are labeled with their PCI class
+        #[cfg(feature = "lspci")]
+        let class_name = detection_results.iter().find_map(|result| match &result.data {
+            DetectionData::Lspci(data) => data
+                .devices
+                .iter()
+                .find(|d| format!("{}:{}", d.vendor_id, d.device_id) == device_id)
+                .and_then(|d| pci_class::describe(&d.class_code)),
+            _ => None,
+        });
+        #[cfg(not(feature = "lspci"))]
+        let class_name: Option<String> = None;
+        let class_name =
+            class_name.or_else(|| sysfs_device.and_then(|d| pci_class::describe(&d.class_code)));
+
+        match class_name {
+            Some(class_name) => format!("{} {}", class_name, device_id),
+            None => format!("Unknown Device {}", device_id),
+        }
     }
 
     /// Generate compatibility notes based on support level
@@ -237,34 +444,163 @@ impl HardwareAnalyzer {
     async fn build_hardware_report(
         &mut self,
         detection_results: Vec<DetectionResult>,
-        kernel_compatibility: KernelCompatibilityInfo,
+        mut kernel_compatibility: Option<KernelCompatibilityInfo>,
+        mut phase_errors: Vec<PhaseError>,
     ) -> Result<HardwareReport> {
+        self.provenance = Provenance::default();
+
         // Generate anonymized system ID
         let system_id = self.privacy_manager.anonymize_identifier("system")?;
 
+        // Extract system information from detection results
+        let system = isolate_phase(
+            "system",
+            self.extract_system_info(&detection_results),
+            &mut phase_errors,
+        )
+        .await
+        .unwrap_or_else(Self::fallback_system_info);
+
+        // Extract hardware components from detection results
+        let mut cpu =
+            isolate_phase("cpu", self.extract_cpu_info(&detection_results), &mut phase_errors)
+                .await
+                .flatten();
+        if let Some(cpu) = cpu.as_mut().filter(|_| remote::is_local()) {
+            cpu.microcode = isolate_phase(
+                "microcode",
+                async { Ok(self.microcode.detect()) },
+                &mut phase_errors,
+            )
+            .await
+            .flatten();
+        }
+        let mut memory = isolate_phase(
+            "memory",
+            self.extract_memory_info(&detection_results),
+            &mut phase_errors,
+        )
+        .await
+        .flatten();
+        if let Some(memory) = memory.as_mut().filter(|_| remote::is_local()) {
+            memory.swap = super::swap::read_swap(
+                Path::new("/proc/swaps"),
+                Path::new("/sys/module/zswap/parameters/enabled"),
+            );
+        }
+        let storage = isolate_phase(
+            "storage",
+            self.extract_storage_devices(&detection_results),
+            &mut phase_errors,
+        )
+        .await
+        .unwrap_or_default();
+        let storage_stack =
+            isolate_phase("storage_stack", self.extract_storage_stack(), &mut phase_errors)
+                .await
+                .flatten();
+        let graphics = isolate_phase(
+            "graphics",
+            self.extract_graphics_devices(&detection_results),
+            &mut phase_errors,
+        )
+        .await
+        .unwrap_or_default();
+        let network = isolate_phase(
+            "network",
+            self.extract_network_devices(&detection_results),
+            &mut phase_errors,
+        )
+        .await
+        .unwrap_or_default();
+        let usb =
+            isolate_phase("usb", self.extract_usb_devices(&detection_results), &mut phase_errors)
+                .await
+                .unwrap_or_default();
+        let audio = isolate_phase(
+            "audio",
+            self.extract_audio_devices(&detection_results),
+            &mut phase_errors,
+        )
+        .await
+        .unwrap_or_default();
+        let audio_routing =
+            isolate_phase("audio_routing", self.extract_audio_routing(), &mut phase_errors)
+                .await
+                .flatten();
+        let radios = isolate_phase("radios", self.extract_radios(), &mut phase_errors)
+            .await
+            .unwrap_or_default();
+        let input = isolate_phase("input", self.extract_input_devices(), &mut phase_errors)
+            .await
+            .unwrap_or_default();
+        let peripherals = isolate_phase(
+            "peripherals",
+            self.extract_peripherals(&detection_results),
+            &mut phase_errors,
+        )
+        .await
+        .flatten();
+        let pcie_links = isolate_phase("pcie_links", self.extract_pcie_links(), &mut phase_errors)
+            .await
+            .unwrap_or_default();
+        let mut sensors = isolate_phase("sensors", self.extract_sensors(), &mut phase_errors)
+            .await
+            .unwrap_or_default();
+        let firmware =
+            isolate_phase("firmware", self.extract_firmware(), &mut phase_errors).await.flatten();
+        let unknown_devices = isolate_phase(
+            "unknown_devices",
+            self.extract_unknown_devices(&detection_results),
+            &mut phase_errors,
+        )
+        .await
+        .unwrap_or_default();
+        let lifecycle_notices = isolate_phase(
+            "lifecycle",
+            self.extract_lifecycle_notices(
+                &system,
+                cpu.as_ref(),
+                &graphics,
+                kernel_compatibility.as_ref(),
+            ),
+            &mut phase_errors,
+        )
+        .await
+        .unwrap_or_default();
+        // After the lifecycle phase, which reads the per-device support details
+        anonymize_counts(
+            &mut self.privacy_manager,
+            memory.as_mut(),
+            kernel_compatibility.as_mut(),
+            &mut sensors,
+        );
+
+        let firmware_updates = isolate_phase(
+            "firmware_updates",
+            self.extract_firmware_updates(&detection_results),
+            &mut phase_errors,
+        )
+        .await
+        .unwrap_or_default();
+
+        let tools_used: Vec<String> =
+            detection_results.iter().filter(|r| r.success).map(|r| r.tool_name.clone()).collect();
         let metadata = ReportMetadata {
             version: env!("CARGO_PKG_VERSION").to_string(),
             generated_at: Utc::now(),
             privacy_level: self.privacy_manager.privacy_level(),
-            tools_used: detection_results
-                .iter()
-                .filter(|r| r.success)
-                .map(|r| r.tool_name.clone())
-                .collect(),
+            sandbox: sandbox::active().map(|sandbox| sandbox.status(&tools_used)),
+            privileges: self.privilege_status(&detection_results),
+            tools_used,
             anonymized_system_id: system_id,
+            phase_errors,
+            remote: remote::active().is_some(),
+            replayed: replay::active().is_some(),
+            salt_context: None,
+            digest_length: self.privacy_manager.truncated_digest_length(),
         };
-
-        // Extract system information from detection results
-        let system = self.extract_system_info(&detection_results).await?;
-
-        // Extract hardware components from detection results
-        let cpu = self.extract_cpu_info(&detection_results).await?;
-        let memory = self.extract_memory_info(&detection_results).await?;
-        let storage = self.extract_storage_devices(&detection_results).await?;
-        let graphics = self.extract_graphics_devices(&detection_results).await?;
-        let network = self.extract_network_devices(&detection_results).await?;
-        let usb = self.extract_usb_devices(&detection_results).await?;
-        let audio = self.extract_audio_devices(&detection_results).await?;
+        let provenance = std::mem::take(&mut self.provenance);
 
         Ok(HardwareReport {
             metadata,
@@ -272,19 +608,65 @@ impl HardwareAnalyzer {
             cpu,
             memory,
             storage,
+            storage_stack,
             graphics,
             network,
             usb,
             audio,
-            kernel_support: Some(kernel_compatibility),
+            audio_routing,
+            radios,
+            input,
+            peripherals,
+            pcie_links,
+            sensors,
+            firmware,
+            kernel_support: kernel_compatibility,
+            unknown_devices,
+            lifecycle_notices,
+            firmware_updates,
+            user_notes: Vec::new(),
+            blobs: Vec::new(),
+            provenance: (!provenance.is_empty()).then_some(provenance),
         })
     }
 
+    /// System information used when the system phase fails
+    fn fallback_system_info() -> SystemInfo {
+        SystemInfo {
+            anonymized_hostname: "unknown".to_string(),
+            kernel_version: "unknown".to_string(),
+            distribution: None,
+            architecture: std::env::consts::ARCH.to_string(),
+            boot_time: None,
+            virtualization: None,
+        }
+    }
+
     /// Extract system information with privacy protection
     async fn extract_system_info(
         &mut self,
         _detection_results: &[DetectionResult],
     ) -> Result<SystemInfo> {
+        if let Some(remote) = remote::active() {
+            let system = remote.system().await?;
+            return Ok(SystemInfo {
+                anonymized_hostname: self.privacy_manager.anonymize_identifier(&system.hostname)?,
+                kernel_version: system.kernel_version,
+                distribution: system.distribution,
+                architecture: system.architecture,
+                boot_time: None,
+                virtualization: None,
+            });
+        }
+        if replay::active().is_some() {
+            // Saved output does not tell the kernel or hostname it came from
+            return Ok(SystemInfo {
+                kernel_version: "unknown".to_string(),
+                architecture: "unknown".to_string(),
+                ..Self::fallback_system_info()
+            });
+        }
+
         // Get system information from uname and /proc files
         let kernel_version = std::process::Command::new("uname")
             .arg("-r")
@@ -307,7 +689,7 @@ impl HardwareAnalyzer {
         let anonymized_hostname = self.privacy_manager.anonymize_identifier(&hostname)?;
 
         // Try to detect distribution
-        let distribution = self.detect_distribution();
+        let distribution = detect_distribution();
 
         Ok(SystemInfo {
             anonymized_hostname,
@@ -315,568 +697,957 @@ impl HardwareAnalyzer {
             distribution,
             architecture,
             boot_time: None, // TODO: parse from /proc/stat
+            virtualization: Some(virt::detect_virtualization(
+                Path::new("/"),
+                virt::query_systemd_detect_virt(),
+            )),
         })
     }
 
-    /// Detect Linux distribution
-    fn detect_distribution(&self) -> Option<String> {
-        // Try /etc/os-release first
-        if let Ok(content) = std::fs::read_to_string("/etc/os-release") {
-            for line in content.lines() {
-                let Some(name) = line.strip_prefix("PRETTY_NAME=\"") else {
-                    continue;
-                };
-
-                if let Some(end) = name.find('"') {
-                    return Some(name[..end].to_string());
-                }
-            }
-        }
-
-        // Fallback to other detection methods
-        if std::path::Path::new("/etc/debian_version").exists() {
-            return Some("Debian-based".to_string());
-        }
-        if std::path::Path::new("/etc/redhat-release").exists() {
-            return Some("Red Hat-based".to_string());
-        }
-
-        None
-    }
-
-    /// Extract CPU information from detection results
+    /// Extract CPU information, preferring dmidecode over lshw for the
+    /// model and the kernel for topology, caches and flags
+    ///
+    /// Every tool's CPU is extracted so disagreements end up in the provenance.
     async fn extract_cpu_info(
         &mut self,
         detection_results: &[DetectionResult],
     ) -> Result<Option<CpuInfo>> {
-        // First try dmidecode for detailed CPU info
-        for result in detection_results {
-            if let DetectionData::Dmidecode(dmi_data) = &result.data {
-                if let Some(processor) = dmi_data.processors.first() {
-                    // Parse CPU flags from dmidecode
-                    let flags = processor.flags.clone();
-
-                    // Get frequency information
-                    let base_frequency = processor.current_speed.map(|f| f as f64);
-                    let max_frequency = processor.max_speed.map(|f| f as f64);
-
-                    return Ok(Some(CpuInfo {
-                        model: processor.version.clone(),
-                        vendor: processor.manufacturer.clone(),
-                        cores: processor.core_count.unwrap_or(1),
-                        threads: processor
-                            .thread_count
-                            .unwrap_or(processor.core_count.unwrap_or(1)),
-                        base_frequency,
-                        max_frequency,
-                        cache_l1: None, // dmidecode doesn't typically provide cache info
-                        cache_l2: None,
-                        cache_l3: None,
-                        flags,
-                    }));
-                }
+        let mut ctx = ExtractionContext::new(&self.taxonomy, &mut self.privacy_manager);
+        let mut candidates = Vec::new();
+        for (tool, data) in tool_sources(detection_results, &["dmidecode", "lshw"]) {
+            if let Some(cpu) = data.cpu(&mut ctx)? {
+                candidates.push((tool, cpu));
             }
         }
-
-        // Fallback: try to extract basic info from lshw
-        for result in detection_results {
-            if let DetectionData::Lshw(lshw_data) = &result.data {
-                for component in &lshw_data.components {
-                    if component.class == "processor" {
-                        let model = component.product.clone().unwrap_or_else(|| {
-                            component.description.clone().unwrap_or("Unknown CPU".to_string())
-                        });
-                        let vendor = component.vendor.clone().unwrap_or("Unknown".to_string());
-
-                        // Try to parse width/cores from lshw data
-                        let cores = component.width.unwrap_or(1);
-                        let threads = cores; // lshw doesn't distinguish cores vs threads
-
-                        // Parse frequency from lshw capacity field
-                        let frequency = component.capacity.map(|c| c as f64);
-
-                        return Ok(Some(CpuInfo {
-                            model,
-                            vendor,
-                            cores,
-                            threads,
-                            base_frequency: frequency,
-                            max_frequency: frequency,
-                            cache_l1: None,
-                            cache_l2: None,
-                            cache_l3: None,
-                            flags: Vec::new(),
-                        }));
-                    }
-                }
+        let mut kernel = Vec::new();
+        for (tool, data) in tool_sources(detection_results, &["sysfs"]) {
+            if let Some(cpu) = data.cpu(&mut ctx)? {
+                kernel.push((tool, cpu));
             }
         }
 
-        Ok(None)
+        // The kernel's vendor names differ from SMBIOS ones, so it only
+        // names the CPU when no other tool does
+        let identity = if candidates.is_empty() { &kernel } else { &candidates };
+        let topology: Vec<(&str, &CpuInfo)> =
+            kernel.iter().chain(&candidates).map(|(tool, cpu)| (*tool, cpu)).collect();
+        let provenance = &mut self.provenance;
+        provenance.record_from("cpu.vendor", identity, |cpu| Some(cpu.vendor.clone()));
+        provenance.record_from("cpu.model", identity, |cpu| Some(cpu.model.clone()));
+        provenance.record_from("cpu.cores", &topology, |cpu| Some(cpu.cores.to_string()));
+        provenance.record_from("cpu.threads", &topology, |cpu| Some(cpu.threads.to_string()));
+        provenance.record_from("cpu.max_frequency", &topology, |cpu| {
+            cpu.max_frequency.map(|mhz| format!("{:.0} MHz", mhz))
+        });
+
+        let Some((_, mut cpu)) = identity.first().cloned() else {
+            return Ok(None);
+        };
+        if let Some((_, kernel)) = kernel.into_iter().next() {
+            cpu.cores = kernel.cores;
+            cpu.threads = kernel.threads;
+            cpu.base_frequency = kernel.base_frequency.or(cpu.base_frequency);
+            cpu.max_frequency = kernel.max_frequency.or(cpu.max_frequency);
+            cpu.cache_l1 = kernel.cache_l1.or(cpu.cache_l1);
+            cpu.cache_l2 = kernel.cache_l2.or(cpu.cache_l2);
+            cpu.cache_l3 = kernel.cache_l3.or(cpu.cache_l3);
+            cpu.scaling_governor = kernel.scaling_governor;
+            if !kernel.flags.is_empty() {
+                cpu.flags = kernel.flags;
+            }
+        }
+        Ok(Some(cpu))
     }
 
-    /// Extract memory information from detection results
+    /// Extract memory information, preferring dmidecode's per-DIMM data over
+    /// lshw, and either over /proc/meminfo
     async fn extract_memory_info(
         &mut self,
         detection_results: &[DetectionResult],
     ) -> Result<Option<MemoryInfo>> {
-        let mut total_bytes = 0u64;
-        let mut dimms = Vec::new();
-
-        // Extract memory information from dmidecode
-        for result in detection_results {
-            if let DetectionData::Dmidecode(dmi_data) = &result.data {
-                for memory_device in &dmi_data.memory_devices {
-                    // Only include populated memory slots
-                    if let Some(size_mb) = memory_device.size_mb {
-                        if size_mb > 0 {
-                            let size_bytes = (size_mb as u64) * 1024 * 1024;
-                            total_bytes += size_bytes;
-
-                            dimms.push(MemoryDimm {
-                                size_bytes,
-                                speed_mhz: memory_device.speed_mts,
-                                memory_type: memory_device.memory_type.clone(),
-                                manufacturer: memory_device.manufacturer.clone(),
-                            });
-                        }
-                    }
-                }
-
-                if !dimms.is_empty() {
-                    // Calculate available memory (approximate)
-                    let available_bytes = total_bytes.saturating_sub(total_bytes / 10); // Rough estimate
-
-                    return Ok(Some(MemoryInfo { total_bytes, available_bytes, dimms }));
-                }
+        let mut ctx = ExtractionContext::new(&self.taxonomy, &mut self.privacy_manager);
+        let mut candidates = Vec::new();
+        for (tool, data) in tool_sources(detection_results, &["dmidecode", "lshw"]) {
+            if let Some(memory) = data.memory(&mut ctx)? {
+                candidates.push((tool, memory));
             }
         }
-
-        // Fallback: try to get total memory from lshw
-        for result in detection_results {
-            if let DetectionData::Lshw(lshw_data) = &result.data {
-                for component in &lshw_data.components {
-                    if component.class == "memory" {
-                        if let Some(size) = component.size {
-                            total_bytes += size;
-                        }
-                    }
-                }
-
-                if total_bytes > 0 {
-                    let available_bytes = total_bytes.saturating_sub(total_bytes / 10);
-                    return Ok(Some(MemoryInfo {
-                        total_bytes,
-                        available_bytes,
-                        dimms: Vec::new(), // No detailed DIMM info from lshw
-                    }));
+        // MemTotal leaves out memory the kernel reserves, so it only fills in
+        if candidates.is_empty() {
+            for (tool, data) in tool_sources(detection_results, &["sysfs"]) {
+                if let Some(memory) = data.memory(&mut ctx)? {
+                    candidates.push((tool, memory));
                 }
             }
         }
 
-        Ok(None)
+        self.provenance.record_from("memory.total_bytes", &candidates, |memory| {
+            Some(memory.total_bytes.to_string())
+        });
+        Ok(candidates.into_iter().next().map(|(_, memory)| memory))
     }
 
-    /// Extract storage devices from detection results
+    /// Extract storage devices from lshw, falling back to sysfs
+    ///
+    /// nvme-cli and smartctl add details to the drives found; health data is
+    /// left out at the Strict level.
     async fn extract_storage_devices(
         &mut self,
         detection_results: &[DetectionResult],
     ) -> Result<Vec<StorageDevice>> {
+        let mut ctx = ExtractionContext::new(&self.taxonomy, &mut self.privacy_manager);
         let mut storage_devices = Vec::new();
+        for data in tool_data(detection_results, &["lshw"]) {
+            storage_devices.extend(data.storage(&mut ctx)?);
+        }
+        if storage_devices.is_empty() {
+            for data in tool_data(detection_results, &["sysfs"]) {
+                storage_devices.extend(data.storage(&mut ctx)?);
+            }
+        }
+        for data in tool_data(detection_results, &["nvme"]) {
+            merge_drive_details(&mut storage_devices, data.storage(&mut ctx)?, |device, drive| {
+                device.nvme = drive.nvme
+            });
+        }
+        for data in tool_data(detection_results, &["smartctl"]) {
+            merge_drive_details(&mut storage_devices, data.storage(&mut ctx)?, |device, drive| {
+                device.health = drive.health
+            });
+        }
+        for data in tool_data(detection_results, &["lsblk"]) {
+            merge_drive_details(&mut storage_devices, data.storage(&mut ctx)?, |device, drive| {
+                device.topology = drive.topology
+            });
+        }
 
-        // Extract storage information from lshw
-        for result in detection_results {
-            if let DetectionData::Lshw(lshw_data) = &result.data {
-                for component in &lshw_data.components {
-                    if component.class == "disk" || component.class == "storage" {
-                        let model = component.product.clone().unwrap_or_else(|| {
-                            component.description.clone().unwrap_or("Unknown Storage".to_string())
-                        });
-                        let vendor = component.vendor.clone();
-                        let size_bytes = component.size.unwrap_or(0);
-
-                        // Determine device type based on description and model
-                        let device_type =
-                            self.classify_storage_device(&model, component.description.as_ref());
-
-                        // Anonymize serial number if present
-                        let anonymized_serial = if let Some(serial) = &component.serial {
-                            self.privacy_manager.anonymize_identifier(serial)?
-                        } else {
-                            "unknown".to_string()
-                        };
-
-                        // Extract interface information from businfo
-                        let interface = component.businfo.as_ref().and_then(|businfo| {
-                            if businfo.starts_with("scsi@") {
-                                Some("SCSI".to_string())
-                            } else if businfo.starts_with("ide@") {
-                                Some("IDE".to_string())
-                            } else if businfo.contains("nvme") {
-                                Some("NVMe".to_string())
-                            } else {
-                                None
-                            }
-                        });
-
-                        storage_devices.push(StorageDevice {
-                            anonymized_serial,
-                            device_type,
-                            size_bytes,
-                            model,
-                            vendor,
-                            interface,
-                        });
-                    }
+        // Power-on hours and wear make a drive recognisable across reports
+        if self.privacy_manager.privacy_level() == PrivacyLevel::Strict {
+            for device in &mut storage_devices {
+                device.health = None;
+                if let Some(nvme) = &mut device.nvme {
+                    nvme.health = None;
                 }
             }
         }
-
         Ok(storage_devices)
     }
 
-    /// Extract graphics devices from detection results
+    /// Extract graphics devices from lspci, falling back to lshw and then sysfs
+    ///
+    /// nvidia-smi adds driver details and memory size to the NVIDIA GPUs found.
     async fn extract_graphics_devices(
         &mut self,
         detection_results: &[DetectionResult],
     ) -> Result<Vec<GraphicsDevice>> {
-        let mut graphics_devices = Vec::new();
+        let display_outputs = if remote::is_local() {
+            super::display::read_display_outputs(Path::new("/sys/class/drm"), Path::new("/dev"))
+        } else {
+            HashMap::new()
+        };
+        let mut ctx = ExtractionContext::new(&self.taxonomy, &mut self.privacy_manager)
+            .with_display_outputs(display_outputs);
 
-        // First try lspci for detailed graphics info
-        for result in detection_results {
-            if let DetectionData::Lspci(lspci_data) = &result.data {
-                for device in &lspci_data.devices {
-                    if device.class_code.starts_with("03") {
-                        // VGA/Display class
-                        let pci_id = format!("{}:{}", device.vendor_id, device.device_id);
-                        let vendor = device.vendor_name.clone().unwrap_or("Unknown".to_string());
-                        let model = device
-                            .device_name
-                            .clone()
-                            .unwrap_or_else(|| format!("Graphics Device {}", pci_id));
-                        let driver = device.kernel_driver.clone();
-
-                        graphics_devices.push(GraphicsDevice {
-                            vendor,
-                            model,
-                            driver,
-                            memory_bytes: None, // Would need additional parsing
-                            pci_id,
-                        });
-                    }
+        let mut graphics_devices = Vec::new();
+        for data in tool_data(detection_results, &["lspci"]) {
+            graphics_devices.extend(data.graphics(&mut ctx)?);
+        }
+        for fallback in ["lshw", "sysfs"] {
+            if graphics_devices.is_empty() {
+                for data in tool_data(detection_results, &[fallback]) {
+                    graphics_devices.extend(data.graphics(&mut ctx)?);
                 }
             }
         }
-
-        // Fallback: extract graphics info from lshw
-        if graphics_devices.is_empty() {
-            for result in detection_results {
-                if let DetectionData::Lshw(lshw_data) = &result.data {
-                    for component in &lshw_data.components {
-                        if component.class == "display" {
-                            let vendor = component.vendor.clone().unwrap_or("Unknown".to_string());
-                            let model = component.product.clone().unwrap_or_else(|| {
-                                component
-                                    .description
-                                    .clone()
-                                    .unwrap_or("Unknown Graphics".to_string())
-                            });
-
-                            // Extract PCI ID from businfo
-                            let pci_id = component
-                                .businfo
-                                .as_ref()
-                                .and_then(|bus| self.extract_pci_id_from_businfo(bus))
-                                .map(|(v, d)| format!("{}:{}", v, d))
-                                .unwrap_or("unknown".to_string());
-
-                            graphics_devices.push(GraphicsDevice {
-                                vendor,
-                                model,
-                                driver: None,
-                                memory_bytes: component.size,
-                                pci_id,
-                            });
-                        }
+        for data in tool_data(detection_results, &["nvidia-smi"]) {
+            for gpu in data.graphics(&mut ctx)? {
+                match graphics_devices.iter_mut().find(|device| device.pci_id == gpu.pci_id) {
+                    Some(device) => {
+                        device.memory_bytes = device.memory_bytes.or(gpu.memory_bytes);
+                        device.nvidia = gpu.nvidia;
                     }
+                    None => graphics_devices.push(gpu),
                 }
             }
         }
-
         Ok(graphics_devices)
     }
 
-    /// Extract network devices from detection results
+    /// Extract network devices from lspci, adding lshw devices lspci missed
+    ///
+    /// sysfs is used when neither tool found any. iw adds radio capabilities
+    /// to the Wi-Fi devices; their regulatory domain is left out at the
+    /// Strict level.
     async fn extract_network_devices(
         &mut self,
         detection_results: &[DetectionResult],
     ) -> Result<Vec<NetworkDevice>> {
+        let mut ctx = ExtractionContext::new(&self.taxonomy, &mut self.privacy_manager);
         let mut network_devices = Vec::new();
-
-        // First try lspci for detailed network device info
-        for result in detection_results {
-            if let DetectionData::Lspci(lspci_data) = &result.data {
-                for device in &lspci_data.devices {
-                    if device.class_code.starts_with("02") {
-                        // Network controller class
-                        let vendor = device.vendor_name.clone().unwrap_or("Unknown".to_string());
-                        let model = device.device_name.clone().unwrap_or_else(|| {
-                            format!("Network Device {}:{}", device.vendor_id, device.device_id)
-                        });
-                        let driver = device.kernel_driver.clone();
-
-                        // Determine device type from model/description
-                        let device_type =
-                            self.classify_network_device(&model, device.class_description.as_str());
-
-                        network_devices.push(NetworkDevice {
-                            device_type,
-                            vendor,
-                            model,
-                            driver,
-                            anonymized_mac: "unknown".to_string(), // PCI data doesn't include MAC
-                        });
-                    }
-                }
+        for data in tool_data(detection_results, &["lspci"]) {
+            network_devices.extend(data.network(&mut ctx)?);
+        }
+        for data in tool_data(detection_results, &["lshw"]) {
+            push_unique(&mut network_devices, data.network(&mut ctx)?, |a, b| {
+                a.model == b.model && a.vendor == b.vendor
+            });
+        }
+        if network_devices.is_empty() {
+            for data in tool_data(detection_results, &["sysfs"]) {
+                network_devices.extend(data.network(&mut ctx)?);
             }
         }
+        for data in tool_data(detection_results, &["iw"]) {
+            merge_wireless_capabilities(&mut network_devices, data.network(&mut ctx)?);
+        }
 
-        // Extract network devices from lshw
-        for result in detection_results {
-            if let DetectionData::Lshw(lshw_data) = &result.data {
-                for component in &lshw_data.components {
-                    if component.class == "network" {
-                        let vendor = component.vendor.clone().unwrap_or("Unknown".to_string());
-                        let model = component.product.clone().unwrap_or_else(|| {
-                            component.description.clone().unwrap_or("Unknown Network".to_string())
-                        });
-
-                        // Determine device type
-                        let device_type = self.classify_network_device(
-                            &model,
-                            component.description.as_deref().unwrap_or(""),
-                        );
-
-                        // Anonymize MAC address if available in configuration
-                        let anonymized_mac = if let Some(config) = &component.configuration {
-                            if let Some(serial_val) = config.get("serial") {
-                                if let Some(serial_str) = serial_val.as_str() {
-                                    self.privacy_manager.anonymize_identifier(serial_str)?
-                                } else {
-                                    "unknown".to_string()
-                                }
-                            } else {
-                                "unknown".to_string()
-                            }
-                        } else {
-                            "unknown".to_string()
-                        };
-
-                        // Check if we already added this device from lspci
-                        if !network_devices
-                            .iter()
-                            .any(|dev| dev.model == model && dev.vendor == vendor)
-                        {
-                            network_devices.push(NetworkDevice {
-                                device_type,
-                                vendor,
-                                model,
-                                driver: None, // lshw doesn't always provide driver info
-                                anonymized_mac,
-                            });
-                        }
-                    }
+        // The regulatory domain tells which country the machine is in
+        if self.privacy_manager.privacy_level() == PrivacyLevel::Strict {
+            for device in &mut network_devices {
+                if let Some(capabilities) = &mut device.wireless_capabilities {
+                    capabilities.regulatory_domain = None;
                 }
             }
         }
-
         Ok(network_devices)
     }
 
-    /// Extract USB devices from detection results
+    /// Extract USB devices from lsusb, falling back to lshw and then sysfs
     async fn extract_usb_devices(
         &mut self,
         detection_results: &[DetectionResult],
     ) -> Result<Vec<UsbDevice>> {
+        let mut ctx = ExtractionContext::new(&self.taxonomy, &mut self.privacy_manager);
         let mut usb_devices = Vec::new();
-
-        // Extract USB device information from lsusb
-        for result in detection_results {
-            if let DetectionData::Lsusb(lsusb_data) = &result.data {
-                for device in &lsusb_data.devices {
-                    usb_devices.push(UsbDevice {
-                        vendor_id: device.vendor_id.clone(),
-                        product_id: device.product_id.clone(),
-                        vendor_name: device.vendor_name.clone(),
-                        product_name: device.product_name.clone(),
-                        usb_version: device.usb_version.clone(),
-                    });
-                }
-            }
+        for data in tool_data(detection_results, &["lsusb"]) {
+            usb_devices.extend(data.usb(&mut ctx)?);
         }
-
-        // Fallback: extract USB info from lshw if available
-        if usb_devices.is_empty() {
-            for result in detection_results {
-                if let DetectionData::Lshw(lshw_data) = &result.data {
-                    for component in &lshw_data.components {
-                        if component.class == "usb"
-                            || (component.businfo.is_some()
-                                && component.businfo.as_ref().unwrap().starts_with("usb@"))
-                        {
-                            // Extract vendor/product IDs from businfo or configuration
-                            let (vendor_id, product_id) =
-                                self.extract_usb_ids_from_component(component);
-
-                            usb_devices.push(UsbDevice {
-                                vendor_id,
-                                product_id,
-                                vendor_name: component.vendor.clone(),
-                                product_name: component.product.clone(),
-                                usb_version: None,
-                            });
-                        }
-                    }
+        for fallback in ["lshw", "sysfs"] {
+            if usb_devices.is_empty() {
+                for data in tool_data(detection_results, &[fallback]) {
+                    usb_devices.extend(data.usb(&mut ctx)?);
                 }
             }
         }
-
         Ok(usb_devices)
     }
 
-    /// Extract audio devices from detection results
+    /// Extract audio devices from lspci, adding lshw devices lspci missed
+    ///
+    /// sysfs is used when neither tool found any. On the local machine the
+    /// codecs and sound server profiles of each card are attached.
     async fn extract_audio_devices(
         &mut self,
         detection_results: &[DetectionResult],
     ) -> Result<Vec<AudioDevice>> {
+        let audio_cards = if remote::is_local() {
+            super::audio_cards::read_audio_cards(
+                Path::new("/proc/asound"),
+                Path::new("/sys/class/sound"),
+                super::audio_cards::query_pactl_cards().as_deref(),
+            )
+        } else {
+            HashMap::new()
+        };
+        let mut ctx = ExtractionContext::new(&self.taxonomy, &mut self.privacy_manager)
+            .with_audio_cards(audio_cards);
         let mut audio_devices = Vec::new();
+        for data in tool_data(detection_results, &["lspci"]) {
+            audio_devices.extend(data.audio(&mut ctx)?);
+        }
+        for data in tool_data(detection_results, &["lshw"]) {
+            push_unique(&mut audio_devices, data.audio(&mut ctx)?, |a, b| {
+                a.model == b.model && a.vendor == b.vendor
+            });
+        }
+        if audio_devices.is_empty() {
+            for data in tool_data(detection_results, &["sysfs"]) {
+                audio_devices.extend(data.audio(&mut ctx)?);
+            }
+        }
+        Ok(audio_devices)
+    }
 
-        // Extract audio devices from lspci
-        for result in detection_results {
-            if let DetectionData::Lspci(lspci_data) = &result.data {
-                for device in &lspci_data.devices {
-                    if device.class_code.starts_with("0401") || // Multimedia audio controller
-                       device.class_code.starts_with("0403")
-                    {
-                        // Audio device
-
-                        let vendor = device.vendor_name.clone().unwrap_or("Unknown".to_string());
-                        let model = device.device_name.clone().unwrap_or_else(|| {
-                            format!("Audio Device {}:{}", device.vendor_id, device.device_id)
-                        });
-                        let driver = device.kernel_driver.clone();
+    /// Read ALSA playback outputs and where the default sink routes
+    async fn extract_audio_routing(&self) -> Result<Option<AudioRouting>> {
+        if !remote::is_local() {
+            return Ok(None);
+        }
+        Ok(super::audio_routing::read_audio_routing(
+            Path::new("/proc/asound"),
+            Path::new("/sys/class/sound"),
+            super::audio_routing::query_server_default(),
+            &super::audio_routing::alsa_config_files(),
+        ))
+    }
 
-                        // Classify audio device type
-                        let device_type = if device.class_code.starts_with("0401") {
-                            "multimedia_audio".to_string()
-                        } else {
-                            "audio".to_string()
-                        };
+    /// Read software RAID arrays and LVM volume groups
+    async fn extract_storage_stack(&self) -> Result<Option<StorageStack>> {
+        if !remote::is_local() {
+            return Ok(None);
+        }
+        Ok(super::storage_stack::read_storage_stack(
+            Path::new("/proc/mdstat"),
+            super::storage_stack::query_lvs().as_deref(),
+            super::storage_stack::query_dmraid().as_deref(),
+        ))
+    }
 
-                        audio_devices.push(AudioDevice { vendor, model, driver, device_type });
-                    }
+    /// Read radio block states from rfkill
+    async fn extract_radios(&self) -> Result<Vec<RadioState>> {
+        if !remote::is_local() {
+            return Ok(Vec::new());
+        }
+        Ok(super::rfkill::read_radios(
+            Path::new("/sys/class/rfkill"),
+            &super::rfkill::CoexistenceDatabase::builtin(),
+        ))
+    }
+
+    /// Read keyboards, pointing devices and touchscreens from the input layer
+    async fn extract_input_devices(&self) -> Result<Vec<InputDevice>> {
+        if !remote::is_local() {
+            return Ok(Vec::new());
+        }
+        Ok(super::input::read_input_devices(
+            Path::new("/proc/bus/input/devices"),
+            Path::new("/sys"),
+            &super::input::query_libinput(),
+            super::input::xorg_touchpad_driver(&super::input::xorg_config_files()).as_deref(),
+        ))
+    }
+
+    /// Extract printers from lpinfo and scanners from scanimage
+    async fn extract_peripherals(
+        &mut self,
+        detection_results: &[DetectionResult],
+    ) -> Result<Option<Peripherals>> {
+        let mut ctx = ExtractionContext::new(&self.taxonomy, &mut self.privacy_manager);
+        let mut peripherals = Peripherals::default();
+        for data in tool_data(detection_results, &["lpinfo"]) {
+            peripherals.printers.extend(data.printers(&mut ctx)?);
+        }
+        for data in tool_data(detection_results, &["scanimage"]) {
+            peripherals.scanners.extend(data.scanners(&mut ctx)?);
+        }
+        Ok((!peripherals.is_empty()).then_some(peripherals))
+    }
+
+    /// Read hardware monitoring chips from hwmon and lm-sensors
+    async fn extract_sensors(&self) -> Result<Vec<SensorChip>> {
+        if !remote::is_local() {
+            return Ok(Vec::new());
+        }
+        let mut chips = super::hwmon::read_hwmon(Path::new("/sys/class/hwmon"));
+        super::hwmon::merge_lm_sensors(&mut chips, super::hwmon::query_lm_sensors());
+        Ok(chips)
+    }
+
+    /// Read the PCIe link and resizable BAR of each PCI device
+    async fn extract_pcie_links(&self) -> Result<Vec<PcieDeviceLink>> {
+        if !remote::is_local() {
+            return Ok(Vec::new());
+        }
+        Ok(super::pcie::read_pcie_links(Path::new("/sys/bus/pci/devices")))
+    }
+
+    /// Read BIOS, boot mode, Secure Boot and TPM state
+    async fn extract_firmware(&self) -> Result<Option<FirmwareInfo>> {
+        if !remote::is_local() {
+            return Ok(None);
+        }
+        let mokutil = super::firmware::query_mokutil();
+        Ok(Some(super::firmware::read_firmware_info(Path::new("/"), mokutil.as_deref())))
+    }
+
+    /// Devices with LVFS-updatable firmware, from fwupd when it ran
+    #[cfg(feature = "fwupd")]
+    async fn extract_firmware_updates(
+        &self,
+        detection_results: &[DetectionResult],
+    ) -> Result<Vec<FirmwareDevice>> {
+        Ok(detection_results
+            .iter()
+            .filter(|result| result.success)
+            .find_map(|result| match &result.data {
+                DetectionData::Fwupd(data) => Some(data.devices.clone()),
+                _ => None,
+            })
+            .unwrap_or_default())
+    }
+
+    #[cfg(not(feature = "fwupd"))]
+    async fn extract_firmware_updates(
+        &self,
+        _detection_results: &[DetectionResult],
+    ) -> Result<Vec<FirmwareDevice>> {
+        Ok(Vec::new())
+    }
+
+    /// Estimate hardware age and flag legacy drivers the running kernel cannot use
+    async fn extract_lifecycle_notices(
+        &self,
+        system: &SystemInfo,
+        cpu: Option<&CpuInfo>,
+        graphics: &[GraphicsDevice],
+        kernel_compatibility: Option<&KernelCompatibilityInfo>,
+    ) -> Result<Vec<LifecycleNotice>> {
+        let mut notices = Vec::new();
+
+        if let Some(generation) = cpu.and_then(|cpu| self.lifecycle.lookup_cpu(&cpu.model)) {
+            notices.push(generation.notice(None, &system.kernel_version));
+        }
+
+        let pci_ids = graphics.iter().map(|gpu| gpu.pci_id.as_str()).chain(
+            kernel_compatibility
+                .into_iter()
+                .flat_map(|info| info.device_support_details.iter())
+                .map(|device| device.device_id.as_str()),
+        );
+        for pci_id in pci_ids {
+            let Some((vendor, device)) = pci_id.split_once(':') else {
+                continue;
+            };
+            if let Some(generation) = self.lifecycle.lookup_pci(vendor, device) {
+                let pci_id = pci_id.to_lowercase();
+                if !notices.iter().any(|n| n.device_id.as_deref() == Some(pci_id.as_str())) {
+                    notices.push(generation.notice(Some(pci_id), &system.kernel_version));
                 }
             }
         }
 
-        // Extract audio devices from lshw
+        Ok(notices)
+    }
+
+    /// Extract devices with no vendor/product mapping and no bound driver
+    ///
+    /// Kernel log lines mentioning a device go through the same sanitizer as
+    /// raw tool output; the strict privacy level leaves the kernel log out.
+    #[cfg_attr(
+        not(any(feature = "lspci", feature = "lsusb")),
+        allow(unused_variables, unused_mut, clippy::match_single_binding)
+    )]
+    async fn extract_unknown_devices(
+        &mut self,
+        detection_results: &[DetectionResult],
+    ) -> Result<Vec<UnknownDevice>> {
+        let mut unknown_devices: Vec<UnknownDevice> = Vec::new();
+        let local = remote::is_local();
+        let share_kernel_log = self.profile.reads_kernel_log()
+            && local
+            && self.privacy_manager.privacy_level() != PrivacyLevel::Strict;
+        let kernel_log = if share_kernel_log { read_kernel_log() } else { Vec::new() };
+
         for result in detection_results {
-            if let DetectionData::Lshw(lshw_data) = &result.data {
-                for component in &lshw_data.components {
-                    if component.class == "multimedia" || component.class == "sound" {
-                        let vendor = component.vendor.clone().unwrap_or("Unknown".to_string());
-                        let model = component.product.clone().unwrap_or_else(|| {
-                            component.description.clone().unwrap_or("Unknown Audio".to_string())
-                        });
+            match &result.data {
+                #[cfg(feature = "lspci")]
+                DetectionData::Lspci(lspci_data) => {
+                    for device in &lspci_data.devices {
+                        let unmapped = is_unmapped_name(device.vendor_name.as_deref())
+                            || is_unmapped_name(device.device_name.as_deref());
+                        if !unmapped || device.kernel_driver.is_some() {
+                            continue;
+                        }
 
-                        // Check if we already added this device from lspci
-                        if !audio_devices
-                            .iter()
-                            .any(|dev| dev.model == model && dev.vendor == vendor)
-                        {
-                            audio_devices.push(AudioDevice {
-                                vendor,
-                                model,
-                                driver: None,
-                                device_type: "multimedia".to_string(),
-                            });
+                        let sysfs_address = sysfs_pci_address(&device.address);
+                        let modalias = local
+                            .then(|| {
+                                std::fs::read_to_string(format!(
+                                    "/sys/bus/pci/devices/{}/modalias",
+                                    sysfs_address
+                                ))
+                                .ok()
+                            })
+                            .flatten()
+                            .map(|alias| alias.trim().to_string());
+
+                        let id_needle = format!("[{}:{}]", device.vendor_id, device.device_id);
+                        unknown_devices.push(UnknownDevice {
+                            bus: "pci".to_string(),
+                            vendor_id: device.vendor_id.clone(),
+                            device_id: device.device_id.clone(),
+                            class_code: Some(device.class_code.clone()),
+                            modalias,
+                            dmesg_lines: dmesg_lines_for(
+                                &kernel_log,
+                                &[sysfs_address.as_str(), id_needle.as_str()],
+                            ),
+                        });
+                    }
+                }
+                #[cfg(feature = "lsusb")]
+                DetectionData::Lsusb(lsusb_data) => {
+                    for device in &lsusb_data.devices {
+                        let unmapped = is_unmapped_name(device.vendor_name.as_deref())
+                            || is_unmapped_name(device.product_name.as_deref());
+                        let has_driver = device.interfaces.iter().any(|i| i.driver.is_some());
+                        if !unmapped || has_driver {
+                            continue;
                         }
+
+                        let id_needle = format!(
+                            "idVendor={}, idProduct={}",
+                            device.vendor_id, device.product_id
+                        );
+                        unknown_devices.push(UnknownDevice {
+                            bus: "usb".to_string(),
+                            vendor_id: device.vendor_id.clone(),
+                            device_id: device.product_id.clone(),
+                            class_code: device.device_class.clone(),
+                            modalias: None,
+                            dmesg_lines: dmesg_lines_for(&kernel_log, &[id_needle.as_str()]),
+                        });
                     }
                 }
+                _ => {}
             }
         }
 
-        Ok(audio_devices)
+        let mut sanitizer = self.raw_sanitizer();
+        for device in &mut unknown_devices {
+            device.dmesg_lines = sanitizer.sanitize_lines(&device.dmesg_lines);
+        }
+
+        unknown_devices.sort_by(|a, b| {
+            (&a.bus, &a.vendor_id, &a.device_id).cmp(&(&b.bus, &b.vendor_id, &b.device_id))
+        });
+        unknown_devices.dedup();
+        Ok(unknown_devices)
     }
+}
 
-    /// Helper to classify storage device types
-    fn classify_storage_device(&self, model: &str, description: Option<&String>) -> String {
-        let combined = format!(
-            "{} {}",
-            model.to_lowercase(),
-            description.map(|d| d.to_lowercase()).unwrap_or_default()
-        );
+/// Release the report's DIMM, device and sensor counts through the privacy
+/// manager, which adds noise at the strict level
+///
+/// At that level the lists the counts could be recounted from go too: DIMMs
+/// are merged into one entry per kind of module and the per-device kernel
+/// support details are left out.
+fn anonymize_counts(
+    privacy: &mut PrivacyManager,
+    memory: Option<&mut MemoryInfo>,
+    kernel_compatibility: Option<&mut KernelCompatibilityInfo>,
+    sensors: &mut [SensorChip],
+) {
+    let strict = privacy.privacy_level() == PrivacyLevel::Strict;
+    if let Some(memory) = memory.filter(|memory| strict && !memory.dimms.is_empty()) {
+        memory.dimm_count = Some(privacy.anonymize_count(memory.dimms.len() as u32, 1));
+        memory.dimms = aggregate_dimms(&memory.dimms);
+    }
+    if let Some(kernel) = kernel_compatibility {
+        kernel.supported_devices = privacy.anonymize_count(kernel.supported_devices, 1);
+        kernel.unsupported_devices = privacy.anonymize_count(kernel.unsupported_devices, 1);
+        kernel.experimental_devices = privacy.anonymize_count(kernel.experimental_devices, 1);
+        // Every device falls in one of the levels, so the total stays their sum
+        kernel.total_devices_detected =
+            kernel.supported_devices + kernel.unsupported_devices + kernel.experimental_devices;
+        if strict {
+            kernel.device_support_details.clear();
+        }
+    }
+    for chip in sensors {
+        chip.temperatures = privacy.anonymize_count(chip.temperatures, 1);
+        chip.fans = privacy.anonymize_count(chip.fans, 1);
+        chip.voltages = privacy.anonymize_count(chip.voltages, 1);
+    }
+}
 
-        if combined.contains("nvme") {
-            "NVMe SSD".to_string()
-        } else if combined.contains("ssd") {
-            "SSD".to_string()
-        } else if combined.contains("usb") || combined.contains("removable") {
-            "USB Drive".to_string()
-        } else if combined.contains("optical")
-            || combined.contains("dvd")
-            || combined.contains("cd")
-        {
-            "Optical Drive".to_string()
-        } else {
-            "HDD".to_string() // Default assumption
+/// One DIMM per kind of module, in the order first seen, with the combined size
+fn aggregate_dimms(dimms: &[MemoryDimm]) -> Vec<MemoryDimm> {
+    let mut aggregated: Vec<MemoryDimm> = Vec::new();
+    for dimm in dimms {
+        let kind = aggregated.iter_mut().find(|kind| {
+            (&kind.memory_type, kind.speed_mhz, &kind.manufacturer)
+                == (&dimm.memory_type, dimm.speed_mhz, &dimm.manufacturer)
+        });
+        match kind {
+            Some(kind) => kind.size_bytes += dimm.size_bytes,
+            None => aggregated.push(dimm.clone()),
         }
     }
+    aggregated
+}
 
-    /// Helper to classify network device types
-    fn classify_network_device(&self, model: &str, description: &str) -> String {
-        let combined = format!("{} {}", model.to_lowercase(), description.to_lowercase());
+/// Extractors of the given tools' output, in the order the tools are listed
+fn tool_data<'r>(
+    results: &'r [DetectionResult],
+    tools: &'r [&str],
+) -> impl Iterator<Item = &'r dyn ComponentExtractor> {
+    tool_sources(results, tools).map(|(_, data)| data)
+}
 
-        if combined.contains("wireless") || combined.contains("wifi") || combined.contains("802.11")
-        {
-            "wifi".to_string()
-        } else if combined.contains("bluetooth") {
-            "bluetooth".to_string()
-        } else if combined.contains("ethernet") || combined.contains("network") {
-            "ethernet".to_string()
-        } else {
-            "network".to_string() // Generic fallback
+/// Extractors of the given tools' output with the tool's name, in the order
+/// the tools are listed
+fn tool_sources<'r>(
+    results: &'r [DetectionResult],
+    tools: &'r [&str],
+) -> impl Iterator<Item = (&'r str, &'r dyn ComponentExtractor)> {
+    tools.iter().flat_map(move |tool| {
+        results
+            .iter()
+            .filter(move |r| r.tool_name == *tool)
+            .map(|r| (r.tool_name.as_str(), r.data.extractor()))
+    })
+}
+
+/// Attach details from nvme-cli or smartctl to the drives other tools found
+///
+/// Drives are matched by anonymized serial, or by model and size when a
+/// tool reported no serial; drives only the detailing tool listed are added.
+fn merge_drive_details(
+    devices: &mut Vec<StorageDevice>,
+    detailed: Vec<StorageDevice>,
+    apply: impl Fn(&mut StorageDevice, StorageDevice),
+) {
+    for drive in detailed {
+        let known = |device: &&mut StorageDevice| {
+            (device.anonymized_serial != "unknown"
+                && device.anonymized_serial == drive.anonymized_serial)
+                || (device.model == drive.model && device.size_bytes == drive.size_bytes)
+        };
+        match devices.iter_mut().find(known) {
+            Some(device) => apply(device, drive),
+            None => devices.push(drive),
         }
     }
+}
 
-    /// Helper to extract USB vendor/product IDs from lshw component
-    fn extract_usb_ids_from_component(
-        &self,
-        component: &crate::detectors::lshw::LshwComponent,
-    ) -> (String, String) {
-        // Try to extract from configuration or other fields
-        if let Some(config) = &component.configuration {
-            // Look for USB IDs in various formats
-            for (key, value) in config {
-                if key.contains("vendor") || key.contains("product") {
-                    if let Some(id_str) = value.as_str() {
-                        // Simple parsing - in real implementation, would be more sophisticated
-                        if id_str.len() == 4 && id_str.chars().all(|c| c.is_ascii_hexdigit()) {
-                            return (id_str.to_string(), "0000".to_string());
-                        }
-                    }
-                }
+/// Attach iw radio capabilities to the Wi-Fi devices other tools found
+///
+/// Radios are matched by interface MAC address, then by driver, then to the
+/// only Wi-Fi device left; radios matching nothing are kept as devices of
+/// their own.
+fn merge_wireless_capabilities(devices: &mut Vec<NetworkDevice>, radios: Vec<NetworkDevice>) {
+    for radio in radios {
+        let unmatched = |device: &NetworkDevice| {
+            device.device_type == "wifi" && device.wireless_capabilities.is_none()
+        };
+        let by_mac = devices.iter().position(|device| {
+            device.anonymized_mac != "unknown" && device.anonymized_mac == radio.anonymized_mac
+        });
+        let by_driver = || {
+            devices.iter().position(|device| {
+                unmatched(device) && radio.driver.is_some() && device.driver == radio.driver
+            })
+        };
+        let only_wifi = || {
+            let mut candidates = devices.iter().enumerate().filter(|(_, device)| unmatched(device));
+            match (candidates.next(), candidates.next()) {
+                (Some((index, _)), None) => Some(index),
+                _ => None,
             }
+        };
+        match by_mac.or_else(by_driver).or_else(only_wifi) {
+            Some(index) => devices[index].wireless_capabilities = radio.wireless_capabilities,
+            None => devices.push(radio),
         }
+    }
+}
+
+/// Append devices not already present according to `same`
+fn push_unique<T>(devices: &mut Vec<T>, extra: Vec<T>, same: impl Fn(&T, &T) -> bool) {
+    for device in extra {
+        if !devices.iter().any(|existing| same(existing, &device)) {
+            devices.push(device);
+        }
+    }
+}
 
-        // Fallback to unknown IDs
-        ("unknown".to_string(), "unknown".to_string())
+/// Whether a tool-reported name is missing or a placeholder for an unknown ID
+#[cfg(any(feature = "lspci", feature = "lsusb"))]
+fn is_unmapped_name(name: Option<&str>) -> bool {
+    match name.map(str::trim) {
+        None | Some("") => true,
+        Some(name) => {
+            // lspci prints "Device 1234" when the ID is not in pci.ids
+            name.strip_prefix("Device ")
+                .is_some_and(|id| id.len() == 4 && id.chars().all(|c| c.is_ascii_hexdigit()))
+        }
+    }
+}
+
+/// Detect Linux distribution
+pub(crate) fn detect_distribution() -> Option<String> {
+    // Try /etc/os-release first
+    if let Some(name) =
+        std::fs::read_to_string("/etc/os-release").ok().and_then(|c| parse_os_release(&c))
+    {
+        return Some(name);
+    }
+
+    // Fallback to other detection methods
+    if std::path::Path::new("/etc/debian_version").exists() {
+        return Some("Debian-based".to_string());
+    }
+    if std::path::Path::new("/etc/redhat-release").exists() {
+        return Some("Red Hat-based".to_string());
+    }
+
+    None
+}
+
+/// Distribution name from the contents of os-release
+pub(crate) fn parse_os_release(content: &str) -> Option<String> {
+    content.lines().find_map(|line| {
+        let name = line.strip_prefix("PRETTY_NAME=\"")?;
+        name.find('"').map(|end| name[..end].to_string())
+    })
+}
+
+/// Hostname of the running system
+pub(crate) fn local_hostname() -> Option<String> {
+    std::fs::read_to_string("/proc/sys/kernel/hostname").ok()
+}
+
+/// Read the kernel log, which may be restricted to root
+pub(crate) fn read_kernel_log() -> Vec<String> {
+    std::process::Command::new("dmesg")
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .map(|output| String::from_utf8_lossy(&output.stdout).lines().map(String::from).collect())
+        .unwrap_or_default()
+}
+
+/// Select kernel log lines that mention any of the given needles
+pub fn dmesg_lines_for(kernel_log: &[String], needles: &[&str]) -> Vec<String> {
+    kernel_log
+        .iter()
+        .filter(|line| needles.iter().any(|needle| line.contains(needle)))
+        .take(MAX_DMESG_LINES)
+        .cloned()
+        .collect()
+}
+
+/// Run a report-building phase, isolating errors and panics
+///
+/// Returns `None` and records a [`PhaseError`] when the phase does not
+/// complete successfully, so the caller can continue with the next phase.
+/// Phases are not time-limited: they mostly parse detector output and read
+/// sysfs without awaiting, so a timeout could not interrupt them. Time is
+/// bounded where it is spent, by the tool and command timeouts.
+async fn isolate_phase<T, F>(
+    phase: &str,
+    future: F,
+    phase_errors: &mut Vec<PhaseError>,
+) -> Option<T>
+where
+    F: Future<Output = Result<T>>,
+{
+    let future = crate::telemetry::crash::in_phase(phase, future);
+    let message = match AssertUnwindSafe(future).catch_unwind().await {
+        Ok(Ok(value)) => return Some(value),
+        Ok(Err(e)) => e.to_string(),
+        Err(panic) => format!("panicked: {}", panic_message(panic.as_ref())),
+    };
+
+    log::warn!(phase, error = message.as_str(); "Report phase '{}' failed: {}", phase, message);
+    phase_errors.push(PhaseError { phase: phase.to_string(), message });
+    None
+}
+
+/// Extract a readable message from a panic payload
+fn panic_message(payload: &(dyn std::any::Any + Send)) -> String {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        message.to_string()
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        "unknown panic".to_string()
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::errors::LxHwError;
+    use crate::hardware::{NvmeDetails, WirelessCapabilities};
+
+    #[test]
+    #[cfg(any(feature = "lspci", feature = "lsusb"))]
+    fn test_is_unmapped_name() {
+        assert!(is_unmapped_name(None));
+        assert!(is_unmapped_name(Some("")));
+        assert!(is_unmapped_name(Some("Device 7a3c")));
+        assert!(!is_unmapped_name(Some("Device Controller")));
+        assert!(!is_unmapped_name(Some("Intel Corporation")));
+    }
+
+    #[test]
+    fn test_dmesg_lines_for() {
+        let log: Vec<String> = (0..10)
+            .map(|i| format!("[{}.0] pci 0000:03:00.0: [1d6a:07b1] type 00 class 0x020000", i))
+            .chain(std::iter::once("[11.0] usb 1-2: new device".to_string()))
+            .collect();
+
+        let lines = dmesg_lines_for(&log, &["0000:03:00.0"]);
+        assert_eq!(lines.len(), MAX_DMESG_LINES);
+        assert!(dmesg_lines_for(&log, &["0000:04:00.0"]).is_empty());
+    }
+
+    #[test]
+    fn test_merge_drive_details() {
+        let drive = |serial: &str, model: &str, nvme: Option<NvmeDetails>| StorageDevice {
+            anonymized_serial: serial.to_string(),
+            device_type: "NVMe SSD".to_string(),
+            size_bytes: 1_000_204_886_016,
+            model: model.to_string(),
+            vendor: None,
+            interface: Some("NVMe".to_string()),
+            nvme,
+            health: None,
+            topology: None,
+        };
+        let details = NvmeDetails {
+            firmware: Some("2B2QEXM7".to_string()),
+            namespace: Some(1),
+            sector_size: Some(512),
+            pcie_link: None,
+            health: None,
+        };
+
+        let mut devices = vec![drive("a1b2", "Samsung SSD 970 EVO Plus 1TB", None)];
+        merge_drive_details(
+            &mut devices,
+            vec![
+                drive("a1b2", "Samsung SSD 970 EVO Plus 1TB", Some(details.clone())),
+                drive("c3d4", "WD_BLACK SN850X 2000GB", Some(details.clone())),
+            ],
+            |device, drive| device.nvme = drive.nvme,
+        );
+        assert_eq!(devices.len(), 2);
+        assert_eq!(devices[0].nvme, Some(details));
+        assert_eq!(devices[1].model, "WD_BLACK SN850X 2000GB");
+    }
+
+    #[test]
+    fn test_merge_wireless_capabilities() {
+        let device = |device_type: &str, driver: &str, mac: &str| NetworkDevice {
+            device_type: device_type.to_string(),
+            vendor: "Intel Corporation".to_string(),
+            model: "Wi-Fi 6E AX211".to_string(),
+            driver: Some(driver.to_string()),
+            anonymized_mac: mac.to_string(),
+            wireless_capabilities: None,
+        };
+        let capabilities = WirelessCapabilities {
+            bands: vec!["2.4 GHz".to_string(), "5 GHz".to_string()],
+            standards: vec!["802.11n".to_string(), "802.11ax".to_string()],
+            regulatory_domain: None,
+            ap_mode: true,
+            monitor_mode: false,
+        };
+        let radio = |driver: &str, mac: &str| NetworkDevice {
+            wireless_capabilities: Some(capabilities.clone()),
+            ..device("wifi", driver, mac)
+        };
+
+        let mut devices = vec![
+            device("ethernet", "e1000e", "unknown"),
+            device("wifi", "iwlwifi", "unknown"),
+            device("wifi", "mt7921e", "unknown"),
+        ];
+        merge_wireless_capabilities(&mut devices, vec![radio("mt7921e", "f00d")]);
+        assert!(devices[1].wireless_capabilities.is_none());
+        assert_eq!(devices[2].wireless_capabilities, Some(capabilities.clone()));
+
+        // Without a driver the one Wi-Fi device left is the match
+        let mut unknown_driver = radio("", "beef");
+        unknown_driver.driver = None;
+        merge_wireless_capabilities(&mut devices, vec![unknown_driver]);
+        assert!(devices[1].wireless_capabilities.is_some());
+        assert_eq!(devices.len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_isolate_phase_success() {
+        let mut errors = Vec::new();
+        let value = isolate_phase("ok", async { Ok(42) }, &mut errors).await;
+        assert_eq!(value, Some(42));
+        assert!(errors.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_isolate_phase_records_error() {
+        let mut errors = Vec::new();
+        let value: Option<u32> = isolate_phase(
+            "cpu",
+            async { Err(LxHwError::DetectionError("bad data".to_string())) },
+            &mut errors,
+        )
+        .await;
+        assert!(value.is_none());
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].phase, "cpu");
+        assert!(errors[0].message.contains("bad data"));
+    }
+
+    #[tokio::test]
+    async fn test_isolate_phase_catches_panic() {
+        let mut errors = Vec::new();
+        let value: Option<u32> =
+            isolate_phase("network", async { panic!("malformed lshw configuration") }, &mut errors)
+                .await;
+        assert!(value.is_none());
+        assert!(errors[0].message.contains("malformed lshw configuration"));
+    }
+
+    #[test]
+    fn test_strict_counts_cannot_be_recounted() {
+        let dimm = |manufacturer: &str| MemoryDimm {
+            size_bytes: 16 << 30,
+            speed_mhz: Some(4800),
+            memory_type: Some("DDR5".to_string()),
+            manufacturer: Some(manufacturer.to_string()),
+        };
+        let mut memory = MemoryInfo {
+            total_bytes: 48 << 30,
+            available_bytes: 40 << 30,
+            dimms: vec![dimm("Samsung"), dimm("Kingston"), dimm("Samsung")],
+            dimm_count: None,
+            swap: None,
+        };
+        let device = DeviceCompatibility {
+            device_id: "10de:2484".to_string(),
+            device_name: "GA104".to_string(),
+            support_status: "supported".to_string(),
+            driver_module: "nvidia".to_string(),
+            since_kernel_version: None,
+            config_dependencies: Vec::new(),
+            notes: None,
+        };
+        let mut kernel = KernelCompatibilityInfo {
+            kernel_version: "6.8.0".to_string(),
+            total_devices_detected: 12,
+            supported_devices: 12,
+            unsupported_devices: 0,
+            experimental_devices: 0,
+            device_support_details: vec![device; 12],
+            missing_modules: Vec::new(),
+            config_recommendations: Vec::new(),
+            module_parameters: Vec::new(),
+            blacklisted_modules: Vec::new(),
+            dkms_modules: Vec::new(),
+        };
+
+        let mut basic = PrivacyManager::with_salt(PrivacyLevel::Basic, b"salt");
+        let (mut basic_memory, mut basic_kernel) = (memory.clone(), kernel.clone());
+        anonymize_counts(&mut basic, Some(&mut basic_memory), Some(&mut basic_kernel), &mut []);
+        assert_eq!(basic_memory.dimms.len(), 3);
+        assert_eq!(basic_memory.dimm_count, None);
+        assert_eq!(basic_kernel.device_support_details.len(), 12);
+
+        let mut strict = PrivacyManager::with_salt(PrivacyLevel::Strict, b"salt");
+        anonymize_counts(&mut strict, Some(&mut memory), Some(&mut kernel), &mut []);
+        let sizes: Vec<(Option<&str>, u64)> =
+            memory.dimms.iter().map(|d| (d.manufacturer.as_deref(), d.size_bytes)).collect();
+        assert_eq!(sizes, [(Some("Samsung"), 32 << 30), (Some("Kingston"), 16 << 30)]);
+        assert_eq!(memory.dimm_count(), memory.dimm_count.unwrap() as usize);
+        assert!(kernel.device_support_details.is_empty());
+        assert_eq!(
+            kernel.total_devices_detected,
+            kernel.supported_devices + kernel.unsupported_devices + kernel.experimental_devices
+        );
+    }
 
     #[tokio::test]
     async fn test_hardware_analyzer_creation() {
@@ -889,6 +1660,54 @@ mod tests {
         // Expected in test environments - error case handled implicitly
     }
 
+    #[test]
+    fn test_refresh_usb_keeps_known_devices() {
+        let root = tempfile::tempdir().unwrap();
+        for (entry, vendor, product) in [("1-2", "046d", "c52b"), ("1-3", "0781", "5581")] {
+            let dir = root.path().join(entry);
+            std::fs::create_dir_all(&dir).unwrap();
+            std::fs::write(dir.join("idVendor"), vendor).unwrap();
+            std::fs::write(dir.join("idProduct"), product).unwrap();
+        }
+        std::fs::write(root.path().join("1-3/product"), "Ultra").unwrap();
+        std::fs::write(root.path().join("1-3/serial"), "4C530001").unwrap();
+
+        let known = |vendor: &str, product: &str, name: &str| UsbDevice {
+            vendor_id: vendor.to_string(),
+            product_id: product.to_string(),
+            vendor_name: None,
+            product_name: Some(name.to_string()),
+            usb_version: None,
+            device_class: None,
+            device_subclass: None,
+            speed_mbps: None,
+            port_path: None,
+            max_power_ma: None,
+            anonymized_serial: None,
+        };
+        // The receiver stays, the keyboard was unplugged, the stick is new
+        let previous =
+            vec![known("046d", "c52b", "Unifying Receiver"), known("04d9", "0006", "Keyboard")];
+
+        let mut analyzer = HardwareAnalyzer::new(PrivacyLevel::Basic).unwrap();
+        let usb = analyzer.refresh_usb(&previous, root.path()).unwrap();
+        assert_eq!(usb.len(), 2);
+        assert_eq!(usb[0].product_name.as_deref(), Some("Unifying Receiver"));
+        assert_eq!(usb[1].product_name.as_deref(), Some("Ultra"));
+        assert_eq!(usb[1].port_path.as_deref(), Some("1-3"));
+        let serial = usb[1].anonymized_serial.as_deref().unwrap();
+        assert!(!serial.contains("4C530001"));
+
+        let event = Uevent {
+            action: "bind".to_string(),
+            subsystem: "usb".to_string(),
+            devpath: "/devices/pci0000:00/0000:00:14.0/usb1/1-3/1-3:1.0".to_string(),
+            devtype: Some("usb_interface".to_string()),
+        };
+        let mut report = crate::hardware::examples::ExampleGenerator::new(1).report(0);
+        assert!(!analyzer.apply_hotplug(&mut report, &event).unwrap());
+    }
+
     #[test]
     fn test_device_id_extraction() {
         // Test that device ID extraction handles empty results gracefully
@@ -899,6 +1718,11 @@ mod tests {
                 panic!("Cannot create kernel verifier in test")
             }),
             privacy_manager: PrivacyManager::new(PrivacyLevel::Basic).unwrap(),
+            taxonomy: Taxonomy::builtin(),
+            lifecycle: LifecycleDatabase::builtin(),
+            microcode: MicrocodeDatabase::builtin(),
+            profile: DetectionProfile::Full,
+            provenance: Provenance::default(),
         };
 
         let empty_results = Vec::new();
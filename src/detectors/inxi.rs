@@ -1,6 +1,8 @@
 //! inxi hardware detection implementation
 
-use super::{DetectionData, DetectionResult, HardwareDetector};
+use super::{
+    sandbox, DetectionData, DetectionError, DetectionResult, HardwareDetector, Privileges,
+};
 use crate::errors::{LxHwError, Result};
 use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
@@ -754,13 +756,11 @@ impl HardwareDetector for InxiDetector {
     }
 
     async fn execute(&self) -> Result<Output> {
-        let output = tokio::process::Command::new("inxi")
-            .arg("-F")  // Full system info
-            .output()
-            .await
-            .map_err(|e| LxHwError::SystemCommandError {
-                command: format!("inxi: {}", e)
-            })?;
+        let output = sandbox::output(
+            tokio::process::Command::new("inxi").arg("-F"), // Full system info
+        )
+        .await
+        .map_err(|e| LxHwError::SystemCommandError { command: format!("inxi: {}", e) })?;
 
         Ok(output)
     }
@@ -769,18 +769,26 @@ impl HardwareDetector for InxiDetector {
         Duration::from_secs(20)
     }
 
+    fn privileges(&self) -> Privileges {
+        // Memory and disk details come from sudo helpers
+        Privileges::RootRecommended
+    }
+
     fn parse_output(&self, output: &Output) -> Result<DetectionResult> {
         let mut errors = Vec::new();
         let mut warnings = Vec::new();
 
         // Check for execution errors
         if !output.status.success() {
-            let error_msg = String::from_utf8_lossy(&output.stderr);
             return Ok(DetectionResult {
                 tool_name: self.name().to_string(),
                 success: false,
                 data: DetectionData::Inxi(Box::default()),
-                errors: vec![format!("inxi execution failed: {}", error_msg)],
+                errors: vec![DetectionError::BadExit {
+                    tool: self.name().to_string(),
+                    code: output.status.code(),
+                    stderr: String::from_utf8_lossy(&output.stderr).to_string(),
+                }],
             });
         }
 
@@ -790,7 +798,7 @@ impl HardwareDetector for InxiDetector {
                 tool_name: self.name().to_string(),
                 success: false,
                 data: DetectionData::Inxi(Box::default()),
-                errors: vec!["Empty output from inxi".to_string()],
+                errors: vec![DetectionError::EmptyOutput { tool: self.name().to_string() }],
             });
         }
 
@@ -813,7 +821,10 @@ impl HardwareDetector for InxiDetector {
                     tool_name: self.name().to_string(),
                     success: false,
                     data: DetectionData::Inxi(Box::default()),
-                    errors: vec![format!("Failed to parse inxi output: {}", e)],
+                    errors: vec![DetectionError::parse(format!(
+                        "Failed to parse inxi output: {}",
+                        e
+                    ))],
                 });
             }
         };
@@ -824,7 +835,7 @@ impl HardwareDetector for InxiDetector {
 
         let summary = self.generate_summary(&data, privileged, warnings.clone());
         data.summary = Some(summary);
-        errors.extend(warnings);
+        errors.extend(warnings.iter().map(|w| DetectionError::from_stderr_line(w)));
 
         Ok(DetectionResult {
             tool_name: self.name().to_string(),
@@ -0,0 +1,390 @@
+//! IOMMU groups and VFIO passthrough readiness
+//!
+//! With the IOMMU enabled, the kernel puts every PCI function in a group
+//! under `/sys/kernel/iommu_groups/N/devices`. A group is the smallest set of
+//! devices that can be handed to a virtual machine, so a GPU or NIC can only
+//! be passed through on its own when its group holds nothing else but other
+//! functions of the same card (such as a GPU's HDMI audio) and PCI bridges.
+//! The IOMMU registers itself under `/sys/class/iommu` as `dmar*` on Intel and
+//! `ivhd*` on AMD; without entries there the firmware or kernel command line
+//! left it off.
+
+use std::path::Path;
+
+/// Class code prefixes of devices commonly passed through
+const GPU_CLASS: &str = "03";
+const NETWORK_CLASS: &str = "02";
+
+/// Class code prefix of PCI bridges, which may share a device's group
+const BRIDGE_CLASS: &str = "0604";
+
+/// IOMMU hardware of the platform
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IommuVendor {
+    /// Intel VT-d
+    Intel,
+    /// AMD-Vi
+    Amd,
+}
+
+/// IOMMU state and groups of the running system
+#[derive(Debug, Clone, Default)]
+pub struct IommuStatus {
+    /// The kernel registered an IOMMU
+    pub enabled: bool,
+    pub vendor: Option<IommuVendor>,
+    /// Arguments on the kernel command line
+    pub cmdline: Vec<String>,
+    pub groups: Vec<IommuGroup>,
+}
+
+/// One IOMMU group with its PCI functions in address order
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IommuGroup {
+    pub id: u32,
+    pub devices: Vec<IommuDevice>,
+}
+
+/// PCI function in an IOMMU group
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IommuDevice {
+    /// e.g. "0000:01:00.0"
+    pub address: String,
+    /// Vendor:device ID, e.g. "10de:2684"
+    pub pci_id: String,
+    /// e.g. "030000"
+    pub class_code: String,
+    pub driver: Option<String>,
+    /// Firmware initialised the console on this GPU
+    pub boot_vga: bool,
+}
+
+impl IommuDevice {
+    /// Slot of the function, e.g. "0000:01:00" for "0000:01:00.1"
+    fn slot(&self) -> &str {
+        self.address.rsplit_once('.').map_or(&self.address, |(slot, _)| slot)
+    }
+}
+
+/// Kind of device considered for passthrough
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PassthroughKind {
+    Gpu,
+    Network,
+}
+
+/// GPU or NIC and what else would have to be passed through with it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PassthroughCandidate {
+    pub kind: PassthroughKind,
+    pub group: u32,
+    pub device: IommuDevice,
+    /// Other functions of the same card in the group
+    pub companions: Vec<IommuDevice>,
+    /// Unrelated devices sharing the group
+    pub conflicts: Vec<IommuDevice>,
+}
+
+impl PassthroughCandidate {
+    /// The device can be bound to vfio-pci without taking other hardware along
+    pub fn is_isolatable(&self) -> bool {
+        self.conflicts.is_empty()
+    }
+
+    /// IDs vfio-pci has to claim: the device and its companions
+    pub fn vfio_ids(&self) -> Vec<String> {
+        let mut ids: Vec<String> = std::iter::once(&self.device)
+            .chain(&self.companions)
+            .map(|device| device.pci_id.clone())
+            .collect();
+        ids.dedup();
+        ids
+    }
+}
+
+/// Kernel command line argument needed for passthrough
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VfioParameter {
+    pub parameter: String,
+    pub value: Option<String>,
+    pub purpose: &'static str,
+    /// Already on the running kernel's command line
+    pub applied: bool,
+}
+
+impl VfioParameter {
+    fn new(
+        parameter: &str,
+        value: Option<String>,
+        purpose: &'static str,
+        cmdline: &[String],
+    ) -> Self {
+        let argument = match &value {
+            Some(value) => format!("{}={}", parameter, value),
+            None => parameter.to_string(),
+        };
+        Self {
+            parameter: parameter.to_string(),
+            applied: cmdline.contains(&argument),
+            value,
+            purpose,
+        }
+    }
+}
+
+impl IommuStatus {
+    /// GPUs and NICs with the devices sharing their group
+    pub fn passthrough_candidates(&self) -> Vec<PassthroughCandidate> {
+        let mut candidates = Vec::new();
+        for group in &self.groups {
+            for device in &group.devices {
+                let kind = if device.class_code.starts_with(GPU_CLASS) {
+                    PassthroughKind::Gpu
+                } else if device.class_code.starts_with(NETWORK_CLASS) {
+                    PassthroughKind::Network
+                } else {
+                    continue;
+                };
+                let (companions, conflicts): (Vec<IommuDevice>, Vec<IommuDevice>) = group
+                    .devices
+                    .iter()
+                    .filter(|other| {
+                        other.address != device.address
+                            && !other.class_code.starts_with(BRIDGE_CLASS)
+                    })
+                    .cloned()
+                    .partition(|other| other.slot() == device.slot());
+                candidates.push(PassthroughCandidate {
+                    kind,
+                    group: group.id,
+                    device: device.clone(),
+                    companions,
+                    conflicts,
+                });
+            }
+        }
+        candidates
+    }
+
+    /// Kernel arguments to pass the isolatable `candidates` through
+    pub fn vfio_parameters(&self, candidates: &[PassthroughCandidate]) -> Vec<VfioParameter> {
+        let mut parameters = Vec::new();
+        match self.vendor {
+            Some(IommuVendor::Intel) => parameters.push(VfioParameter::new(
+                "intel_iommu",
+                Some("on".to_string()),
+                "Enable the Intel VT-d IOMMU",
+                &self.cmdline,
+            )),
+            // AMD-Vi is on by default; only a disabled one needs the argument
+            Some(IommuVendor::Amd) if !self.enabled => parameters.push(VfioParameter::new(
+                "amd_iommu",
+                Some("on".to_string()),
+                "Enable the AMD-Vi IOMMU",
+                &self.cmdline,
+            )),
+            _ => {}
+        }
+        parameters.push(VfioParameter::new(
+            "iommu",
+            Some("pt".to_string()),
+            "Skip IOMMU translation for devices the host keeps",
+            &self.cmdline,
+        ));
+
+        let mut ids: Vec<String> = candidates
+            .iter()
+            .filter(|candidate| candidate.is_isolatable())
+            .flat_map(PassthroughCandidate::vfio_ids)
+            .collect();
+        ids.sort();
+        ids.dedup();
+        if !ids.is_empty() {
+            parameters.push(VfioParameter::new(
+                "vfio-pci.ids",
+                Some(ids.join(",")),
+                "Bind the passed-through devices to vfio-pci",
+                &self.cmdline,
+            ));
+            parameters.push(VfioParameter::new(
+                "rd.driver.pre",
+                Some("vfio-pci".to_string()),
+                "Load vfio-pci before the GPU and network drivers claim the devices",
+                &self.cmdline,
+            ));
+        }
+        parameters
+    }
+}
+
+/// Read the IOMMU state and groups of the system under `root`
+pub fn read_iommu(root: &Path) -> IommuStatus {
+    let units: Vec<String> = std::fs::read_dir(root.join("sys/class/iommu"))
+        .map(|entries| {
+            entries
+                .flatten()
+                .map(|entry| entry.file_name().to_string_lossy().into_owned())
+                .collect()
+        })
+        .unwrap_or_default();
+    let vendor = units
+        .iter()
+        .find_map(|unit| {
+            if unit.starts_with("dmar") {
+                Some(IommuVendor::Intel)
+            } else if unit.starts_with("ivhd") {
+                Some(IommuVendor::Amd)
+            } else {
+                None
+            }
+        })
+        .or_else(|| cpu_vendor(root));
+    let cmdline = std::fs::read_to_string(root.join("proc/cmdline"))
+        .unwrap_or_default()
+        .split_whitespace()
+        .map(String::from)
+        .collect();
+
+    let groups = read_groups(&root.join("sys/kernel/iommu_groups"));
+    IommuStatus { enabled: !units.is_empty() || !groups.is_empty(), vendor, cmdline, groups }
+}
+
+/// IOMMU vendor implied by the CPU, for systems with the IOMMU off
+fn cpu_vendor(root: &Path) -> Option<IommuVendor> {
+    let cpuinfo = std::fs::read_to_string(root.join("proc/cpuinfo")).ok()?;
+    let line = cpuinfo.lines().find(|line| line.starts_with("vendor_id"))?;
+    match line.split_once(':')?.1.trim() {
+        "GenuineIntel" => Some(IommuVendor::Intel),
+        "AuthenticAMD" => Some(IommuVendor::Amd),
+        _ => None,
+    }
+}
+
+fn read_groups(groups_dir: &Path) -> Vec<IommuGroup> {
+    let Ok(entries) = std::fs::read_dir(groups_dir) else {
+        return Vec::new();
+    };
+    let mut groups: Vec<IommuGroup> = entries
+        .flatten()
+        .filter_map(|entry| {
+            let id = entry.file_name().to_str()?.parse().ok()?;
+            let mut devices: Vec<IommuDevice> = std::fs::read_dir(entry.path().join("devices"))
+                .ok()?
+                .flatten()
+                .filter_map(|device| read_device(&device.path()))
+                .collect();
+            devices.sort_by(|a, b| a.address.cmp(&b.address));
+            Some(IommuGroup { id, devices })
+        })
+        .collect();
+    groups.sort_by_key(|group| group.id);
+    groups
+}
+
+fn read_device(dir: &Path) -> Option<IommuDevice> {
+    let hex = |file: &str| {
+        let value = std::fs::read_to_string(dir.join(file)).ok()?.trim().to_ascii_lowercase();
+        Some(value.strip_prefix("0x").unwrap_or(&value).to_string())
+    };
+    Some(IommuDevice {
+        address: dir.file_name()?.to_string_lossy().into_owned(),
+        pci_id: format!("{}:{}", hex("vendor")?, hex("device")?),
+        class_code: hex("class")?,
+        driver: std::fs::read_link(dir.join("driver"))
+            .ok()
+            .and_then(|target| Some(target.file_name()?.to_string_lossy().into_owned())),
+        boot_vga: hex("boot_vga").as_deref() == Some("1"),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::os::unix::fs::symlink;
+    use tempfile::TempDir;
+
+    fn add_device(root: &Path, group: u32, address: &str, id: (&str, &str), class: &str) {
+        let device = root.join("sys/devices/pci0000:00").join(address);
+        std::fs::create_dir_all(&device).unwrap();
+        std::fs::write(device.join("vendor"), format!("0x{}\n", id.0)).unwrap();
+        std::fs::write(device.join("device"), format!("0x{}\n", id.1)).unwrap();
+        std::fs::write(device.join("class"), format!("0x{}\n", class)).unwrap();
+        let devices = root.join(format!("sys/kernel/iommu_groups/{}/devices", group));
+        std::fs::create_dir_all(&devices).unwrap();
+        symlink(&device, devices.join(address)).unwrap();
+    }
+
+    /// Discrete GPU alone with its audio function, and a NIC sharing a group
+    /// with a USB controller behind the same bridge
+    fn fixture() -> TempDir {
+        let root = TempDir::new().unwrap();
+        let path = root.path();
+        add_device(path, 1, "0000:00:01.0", ("8086", "a70d"), "060400");
+        add_device(path, 1, "0000:01:00.0", ("10de", "2684"), "030000");
+        add_device(path, 1, "0000:01:00.1", ("10de", "22ba"), "040300");
+        add_device(path, 12, "0000:00:1c.0", ("8086", "7a38"), "060400");
+        add_device(path, 12, "0000:03:00.0", ("8086", "125c"), "020000");
+        add_device(path, 12, "0000:04:00.0", ("1b21", "2142"), "0c0330");
+        std::fs::write(path.join("sys/devices/pci0000:00/0000:01:00.0/boot_vga"), "0\n").unwrap();
+        std::fs::create_dir_all(path.join("sys/class/iommu/dmar0")).unwrap();
+        std::fs::create_dir_all(path.join("proc")).unwrap();
+        std::fs::write(path.join("proc/cmdline"), "BOOT_IMAGE=/vmlinuz root=/dev/sda1 iommu=pt\n")
+            .unwrap();
+        root
+    }
+
+    #[test]
+    fn test_passthrough_candidates() {
+        let root = fixture();
+        let iommu = read_iommu(root.path());
+        assert!(iommu.enabled);
+        assert_eq!(iommu.vendor, Some(IommuVendor::Intel));
+        assert_eq!(iommu.groups.iter().map(|group| group.id).collect::<Vec<_>>(), [1, 12]);
+
+        let candidates = iommu.passthrough_candidates();
+        assert_eq!(candidates.len(), 2);
+        let gpu = &candidates[0];
+        assert_eq!((gpu.kind, gpu.group), (PassthroughKind::Gpu, 1));
+        assert!(gpu.is_isolatable());
+        assert_eq!(gpu.vfio_ids(), ["10de:2684", "10de:22ba"]);
+        assert!(!gpu.device.boot_vga);
+
+        let nic = &candidates[1];
+        assert_eq!(nic.kind, PassthroughKind::Network);
+        assert!(!nic.is_isolatable());
+        assert_eq!(nic.conflicts[0].pci_id, "1b21:2142");
+
+        let parameters = iommu.vfio_parameters(&candidates);
+        let arguments: Vec<(&str, Option<&str>, bool)> = parameters
+            .iter()
+            .map(|p| (p.parameter.as_str(), p.value.as_deref(), p.applied))
+            .collect();
+        assert_eq!(
+            arguments,
+            [
+                ("intel_iommu", Some("on"), false),
+                ("iommu", Some("pt"), true),
+                ("vfio-pci.ids", Some("10de:22ba,10de:2684"), false),
+                ("rd.driver.pre", Some("vfio-pci"), false),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_disabled_iommu() {
+        let root = TempDir::new().unwrap();
+        std::fs::create_dir_all(root.path().join("proc")).unwrap();
+        std::fs::write(
+            root.path().join("proc/cpuinfo"),
+            "processor\t: 0\nvendor_id\t: AuthenticAMD\n",
+        )
+        .unwrap();
+        let iommu = read_iommu(root.path());
+        assert!(!iommu.enabled);
+        assert_eq!(iommu.vendor, Some(IommuVendor::Amd));
+        assert!(iommu.passthrough_candidates().is_empty());
+        let parameters = iommu.vfio_parameters(&[]);
+        assert_eq!(parameters[0].parameter, "amd_iommu");
+        assert_eq!(parameters.len(), 2);
+    }
+}
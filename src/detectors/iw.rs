@@ -0,0 +1,430 @@
+//! Wi-Fi radio capabilities from iw
+//!
+//! `iw list` describes every wireless PHY: the bands it tunes to, the HT,
+//! VHT, HE and EHT capabilities of each band (802.11n, ac, ax and be) and
+//! the interface modes it supports. `iw dev` ties the PHYs to interface MAC
+//! addresses and `iw reg get` gives the regulatory domain. Only the PHY and
+//! address lines of `iw dev` are kept, as it also prints the connected SSID.
+//! On the local machine the driver bound to each PHY is read from sysfs, so
+//! radios can be matched to the network devices lspci found.
+
+use super::extract::{ComponentExtractor, ExtractionContext};
+use super::{remote, sandbox, DetectionData, DetectionError, DetectionResult, HardwareDetector};
+use crate::errors::{LxHwError, Result};
+use crate::hardware::{NetworkDevice, WirelessCapabilities};
+use async_trait::async_trait;
+use std::path::Path;
+use std::process::Output;
+
+const DEV_MARKER: &str = "--- IW DEV ---";
+const REG_MARKER: &str = "--- REG ---";
+const DRIVER_MARKER: &str = "--- DRIVERS ---";
+
+/// `iw list` band lines announcing each standard, oldest first
+const STANDARDS: &[(&str, &str)] = &[
+    ("HT Max RX data rate", "802.11n"),
+    ("VHT Capabilities", "802.11ac"),
+    ("HE Iftypes", "802.11ax"),
+    ("EHT Iftypes", "802.11be"),
+];
+
+/// Wireless PHYs reported by iw
+#[derive(Debug, Clone, Default)]
+pub struct IwData {
+    pub phys: Vec<WirelessPhy>,
+    pub regulatory_domain: Option<String>,
+}
+
+/// One wireless PHY and what it supports
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct WirelessPhy {
+    /// PHY name, e.g. "phy0"
+    pub name: String,
+    pub bands: Vec<String>,
+    pub standards: Vec<String>,
+    pub ap_mode: bool,
+    pub monitor_mode: bool,
+    /// MAC addresses of the interfaces on this PHY
+    pub addresses: Vec<String>,
+    /// Kernel driver bound to the PHY's device
+    pub driver: Option<String>,
+}
+
+impl ComponentExtractor for IwData {
+    fn network(&self, ctx: &mut ExtractionContext) -> Result<Vec<NetworkDevice>> {
+        let mut network_devices = Vec::new();
+        for phy in &self.phys {
+            let anonymized_mac = match phy.addresses.first() {
+                Some(mac) => ctx.anonymize(mac)?,
+                None => "unknown".to_string(),
+            };
+            network_devices.push(NetworkDevice {
+                device_type: "wifi".to_string(),
+                vendor: "Unknown".to_string(),
+                model: "Wireless Interface".to_string(),
+                driver: phy.driver.clone(),
+                anonymized_mac,
+                wireless_capabilities: Some(WirelessCapabilities {
+                    bands: phy.bands.clone(),
+                    standards: phy.standards.clone(),
+                    regulatory_domain: self.regulatory_domain.clone(),
+                    ap_mode: phy.ap_mode,
+                    monitor_mode: phy.monitor_mode,
+                }),
+            });
+        }
+        Ok(network_devices)
+    }
+}
+
+/// Band a frequency in MHz falls in
+fn band_name(mhz: f64) -> Option<&'static str> {
+    match mhz as u32 {
+        2400..=2500 => Some("2.4 GHz"),
+        4900..=5899 => Some("5 GHz"),
+        5925..=7125 => Some("6 GHz"),
+        57000..=71000 => Some("60 GHz"),
+        _ => None,
+    }
+}
+
+fn push_unique(list: &mut Vec<String>, value: &str) {
+    if !list.iter().any(|existing| existing == value) {
+        list.push(value.to_string());
+    }
+}
+
+/// Parse `iw list` into one entry per PHY
+pub fn parse_list(output: &str) -> Vec<WirelessPhy> {
+    let mut phys: Vec<WirelessPhy> = Vec::new();
+    // Section header at one tab of indentation, e.g. "Supported interface modes:"
+    let mut section = String::new();
+    for line in output.lines() {
+        if let Some(name) = line.strip_prefix("Wiphy ") {
+            phys.push(WirelessPhy { name: name.trim().to_string(), ..Default::default() });
+            section.clear();
+            continue;
+        }
+        let Some(phy) = phys.last_mut() else {
+            continue;
+        };
+        let trimmed = line.trim();
+        if line.starts_with('\t') && !line.starts_with("\t\t") {
+            section = trimmed.trim_end_matches(':').to_string();
+            continue;
+        }
+
+        if section == "Supported interface modes" {
+            match trimmed.trim_start_matches("* ") {
+                "AP" => phy.ap_mode = true,
+                "monitor" => phy.monitor_mode = true,
+                _ => {}
+            }
+        } else if section.starts_with("Band ") {
+            if let Some((_, standard)) =
+                STANDARDS.iter().find(|(prefix, _)| trimmed.starts_with(prefix))
+            {
+                push_unique(&mut phy.standards, standard);
+            }
+            // Frequency lines look like "* 5180.0 MHz [36] (22.0 dBm)";
+            // channels the regulatory domain disables still count
+            let band = trimmed
+                .strip_prefix("* ")
+                .and_then(|rest| rest.split_once(" MHz"))
+                .and_then(|(mhz, _)| mhz.parse::<f64>().ok())
+                .and_then(band_name);
+            if let Some(band) = band {
+                push_unique(&mut phy.bands, band);
+            }
+        }
+    }
+    for phy in &mut phys {
+        phy.standards
+            .sort_by_key(|standard| STANDARDS.iter().position(|(_, known)| known == standard));
+    }
+    phys
+}
+
+/// Interface MAC addresses by PHY name from `iw dev`
+pub fn parse_dev(output: &str) -> Vec<(String, String)> {
+    let mut addresses = Vec::new();
+    let mut phy = None;
+    for line in output.lines().map(str::trim) {
+        if let Some(index) = line.strip_prefix("phy#") {
+            phy = Some(format!("phy{}", index));
+        } else if let (Some(address), Some(phy)) = (line.strip_prefix("addr "), &phy) {
+            addresses.push((phy.clone(), address.trim().to_string()));
+        }
+    }
+    addresses
+}
+
+/// Country code of the global regulatory domain from `iw reg get`
+///
+/// "00" is the world domain used before a country is set and is not a country.
+pub fn parse_regulatory_domain(output: &str) -> Option<String> {
+    let line = output.lines().find(|line| line.starts_with("country "))?;
+    let code = line.strip_prefix("country ")?.split(':').next()?.trim();
+    (code.len() == 2 && code != "00" && code.chars().all(|c| c.is_ascii_alphabetic()))
+        .then(|| code.to_string())
+}
+
+/// Only the lines of `iw dev` naming PHYs and interface addresses
+fn filter_dev(output: &str) -> String {
+    output
+        .lines()
+        .filter(|line| {
+            let line = line.trim();
+            line.starts_with("phy#") || line.starts_with("addr ")
+        })
+        .map(|line| format!("{}\n", line))
+        .collect()
+}
+
+/// Driver bound to each PHY in /sys/class/ieee80211, as "phy0 iwlwifi" lines
+fn read_drivers(ieee80211: &Path) -> String {
+    let Ok(entries) = std::fs::read_dir(ieee80211) else {
+        return String::new();
+    };
+    let mut lines = Vec::new();
+    for entry in entries.flatten() {
+        let driver = std::fs::read_link(entry.path().join("device/driver")).ok();
+        if let Some(driver) = driver.as_deref().and_then(Path::file_name) {
+            lines.push(format!(
+                "{} {}\n",
+                entry.file_name().to_string_lossy(),
+                driver.to_string_lossy()
+            ));
+        }
+    }
+    lines.sort();
+    lines.concat()
+}
+
+/// Detector reading Wi-Fi capabilities with iw
+pub struct IwDetector;
+
+impl IwDetector {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl Default for IwDetector {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl HardwareDetector for IwDetector {
+    fn name(&self) -> &'static str {
+        "iw"
+    }
+
+    async fn is_available(&self) -> bool {
+        tokio::process::Command::new("which")
+            .arg("iw")
+            .output()
+            .await
+            .map(|output| output.status.success())
+            .unwrap_or(false)
+    }
+
+    async fn execute(&self) -> Result<Output> {
+        let mut output = sandbox::output(tokio::process::Command::new("iw").arg("list"))
+            .await
+            .map_err(|e| LxHwError::SystemCommandError { command: format!("iw: {}", e) })?;
+        if !output.status.success() {
+            return Ok(output);
+        }
+
+        match sandbox::output(tokio::process::Command::new("iw").arg("dev")).await {
+            Ok(dev) => output.stdout.extend_from_slice(
+                format!("\n{}\n{}", DEV_MARKER, filter_dev(&String::from_utf8_lossy(&dev.stdout)))
+                    .as_bytes(),
+            ),
+            Err(e) => log::debug!("iw dev failed: {}", e),
+        }
+        match sandbox::output(tokio::process::Command::new("iw").args(["reg", "get"])).await {
+            Ok(reg) => {
+                output.stdout.extend_from_slice(format!("\n{}\n", REG_MARKER).as_bytes());
+                output.stdout.extend_from_slice(&reg.stdout);
+            }
+            Err(e) => log::debug!("iw reg get failed: {}", e),
+        }
+        if remote::is_local() {
+            let drivers = read_drivers(Path::new("/sys/class/ieee80211"));
+            output.stdout.extend_from_slice(format!("\n{}\n{}", DRIVER_MARKER, drivers).as_bytes());
+        }
+        Ok(output)
+    }
+
+    fn parse_output(&self, output: &Output) -> Result<DetectionResult> {
+        if !output.status.success() {
+            return Ok(DetectionResult {
+                tool_name: self.name().to_string(),
+                success: false,
+                data: DetectionData::Iw(IwData::default()),
+                errors: vec![DetectionError::BadExit {
+                    tool: self.name().to_string(),
+                    code: output.status.code(),
+                    stderr: String::from_utf8_lossy(&output.stderr).to_string(),
+                }],
+            });
+        }
+
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let section = |marker: &str| {
+            let (_, rest) = stdout.split_once(marker)?;
+            Some(rest.split("\n--- ").next().unwrap_or_default())
+        };
+        let list = stdout.split("\n--- ").next().unwrap_or_default();
+
+        let mut phys = parse_list(list);
+        if phys.is_empty() {
+            // No wireless hardware, or no cfg80211 drivers loaded
+            return Ok(DetectionResult {
+                tool_name: self.name().to_string(),
+                success: false,
+                data: DetectionData::Iw(IwData::default()),
+                errors: vec![DetectionError::EmptyOutput { tool: self.name().to_string() }],
+            });
+        }
+        for (name, address) in section(DEV_MARKER).map(parse_dev).unwrap_or_default() {
+            if let Some(phy) = phys.iter_mut().find(|phy| phy.name == name) {
+                phy.addresses.push(address);
+            }
+        }
+        for line in section(DRIVER_MARKER).unwrap_or_default().lines() {
+            let Some((name, driver)) = line.split_once(' ') else {
+                continue;
+            };
+            if let Some(phy) = phys.iter_mut().find(|phy| phy.name == name) {
+                phy.driver = Some(driver.trim().to_string());
+            }
+        }
+
+        Ok(DetectionResult {
+            tool_name: self.name().to_string(),
+            success: true,
+            data: DetectionData::Iw(IwData {
+                phys,
+                regulatory_domain: section(REG_MARKER).and_then(parse_regulatory_domain),
+            }),
+            errors: Vec::new(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::os::unix::process::ExitStatusExt;
+
+    const LIST: &str = "Wiphy phy0
+\twiphy index: 0
+\tmax # scan SSIDs: 20
+\tSupported Ciphers:
+\t\t* WEP40 (00-0f-ac:1)
+\tSupported interface modes:
+\t\t * IBSS
+\t\t * managed
+\t\t * AP
+\t\t * AP/VLAN
+\t\t * monitor
+\tBand 1:
+\t\tCapabilities: 0x19ef
+\t\tHT Max RX data rate: 300 Mbps
+\t\tHE Iftypes: managed
+\t\tEHT Iftypes: managed
+\t\tFrequencies:
+\t\t\t* 2412.0 MHz [1] (22.0 dBm)
+\t\t\t* 2484.0 MHz [14] (disabled)
+\tBand 2:
+\t\tVHT Capabilities (0x039071f6):
+\t\tHE Iftypes: managed, AP
+\t\tFrequencies:
+\t\t\t* 5180.0 MHz [36] (22.0 dBm)
+\tBand 4:
+\t\tHE Iftypes: managed
+\t\tFrequencies:
+\t\t\t* 5955.0 MHz [1] (disabled)
+\tsoftware interface modes (can always be added):
+\t\t * AP/VLAN
+\t\t * monitor
+";
+
+    const DEV: &str = "phy#0
+\tInterface wlp0s20f3
+\t\tifindex 3
+\t\twdev 0x1
+\t\taddr 12:34:56:78:9a:bc
+\t\tssid HomeNetwork
+\t\ttype managed
+";
+
+    const REG: &str = "global
+country DE: DFS-ETSI
+\t(2400 - 2483 @ 40), (N/A, 20), (N/A)
+
+phy#0 (self-managed)
+country US: DFS-UNSET
+";
+
+    fn output(stdout: String) -> Output {
+        Output {
+            status: ExitStatusExt::from_raw(0),
+            stdout: stdout.into_bytes(),
+            stderr: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_parse_capabilities() {
+        let stdout = format!(
+            "{}\n{}\n{}\n{}\n{}\n{}\nphy0 iwlwifi\n",
+            LIST,
+            DEV_MARKER,
+            filter_dev(DEV),
+            REG_MARKER,
+            REG,
+            DRIVER_MARKER
+        );
+        assert!(!stdout.contains("HomeNetwork"));
+        let result = IwDetector::new().parse_output(&output(stdout)).unwrap();
+        assert!(result.success);
+        let DetectionData::Iw(data) = result.data else {
+            panic!("expected iw data");
+        };
+        assert_eq!(data.regulatory_domain.as_deref(), Some("DE"));
+        assert_eq!(
+            data.phys,
+            [WirelessPhy {
+                name: "phy0".to_string(),
+                bands: vec!["2.4 GHz".to_string(), "5 GHz".to_string(), "6 GHz".to_string()],
+                standards: vec![
+                    "802.11n".to_string(),
+                    "802.11ac".to_string(),
+                    "802.11ax".to_string(),
+                    "802.11be".to_string(),
+                ],
+                ap_mode: true,
+                monitor_mode: true,
+                addresses: vec!["12:34:56:78:9a:bc".to_string()],
+                driver: Some("iwlwifi".to_string()),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_world_domain_and_no_radios() {
+        assert_eq!(parse_regulatory_domain("global\ncountry 00: DFS-UNSET\n"), None);
+        let client_only = "Wiphy phy1\n\tSupported interface modes:\n\t\t * managed\n";
+        let phy = &parse_list(client_only)[0];
+        assert!(!phy.ap_mode && !phy.monitor_mode && phy.bands.is_empty());
+
+        let result = IwDetector::new().parse_output(&output(String::new())).unwrap();
+        assert!(!result.success);
+    }
+}
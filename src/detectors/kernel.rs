@@ -3,12 +3,19 @@
 //! This module leverages Linux kernel information to verify hardware support
 //! by checking modules.alias files, sysfs information, and kernel device tables.
 
+use super::immutable::detect_immutable;
+use super::kernel_config::{config_symbol, ConfigValue, KernelConfig};
 use crate::errors::{LxHwError, Result};
+use crate::hardware::kernel_releases::{KernelReleases, KernelSeriesStatus};
+use crate::hardware::lifecycle::{Generation, LifecycleDatabase};
+use crate::hardware::out_of_tree::OutOfTreeDatabase;
+use crate::hardware::{BlacklistMethod, BlacklistedModule, ModuleParameters};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::fs;
 use std::path::Path;
 use std::process::Command;
+use std::sync::OnceLock;
 
 /// Kernel support verification data
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -49,6 +56,13 @@ pub struct KernelSupportVerifier {
     kernel_version: String,
     modules_alias_path: String,
     config_path: Option<String>,
+    lifecycle: LifecycleDatabase,
+    out_of_tree: OutOfTreeDatabase,
+    kernel_releases: KernelReleases,
+    /// PCI alias patterns and their modules, read from modules.alias on first use
+    pci_aliases: OnceLock<Vec<(String, String)>>,
+    /// Configuration of the running kernel, read on first use
+    kernel_config: OnceLock<Option<KernelConfig>>,
 }
 
 impl KernelSupportVerifier {
@@ -58,7 +72,31 @@ impl KernelSupportVerifier {
         let modules_alias_path = format!("/lib/modules/{}/modules.alias", kernel_version);
         let config_path = Self::find_kernel_config(&kernel_version);
 
-        Ok(Self { kernel_version, modules_alias_path, config_path })
+        Ok(Self {
+            kernel_version,
+            modules_alias_path,
+            config_path,
+            lifecycle: LifecycleDatabase::builtin(),
+            out_of_tree: OutOfTreeDatabase::builtin(),
+            kernel_releases: KernelReleases::load(),
+            pci_aliases: OnceLock::new(),
+            kernel_config: OnceLock::new(),
+        })
+    }
+
+    /// Kernel version this verifier inspects
+    pub fn kernel_version(&self) -> &str {
+        &self.kernel_version
+    }
+
+    /// Path of the modules.alias file used for driver lookups
+    pub fn modules_alias_path(&self) -> &str {
+        &self.modules_alias_path
+    }
+
+    /// Path of the kernel configuration file, if one was found
+    pub fn config_path(&self) -> Option<&str> {
+        self.config_path.as_deref()
     }
 
     /// Get current kernel version
@@ -77,13 +115,14 @@ impl KernelSupportVerifier {
 
     /// Find kernel configuration file
     fn find_kernel_config(kernel_version: &str) -> Option<String> {
-        let possible_paths = vec![
-            format!("/boot/config-{}", kernel_version),
-            "/proc/config.gz".to_string(),
-            "/boot/config".to_string(),
-        ];
+        KernelConfig::candidates(kernel_version).into_iter().find(|path| Path::new(path).exists())
+    }
 
-        possible_paths.into_iter().find(|path| Path::new(path).exists())
+    /// Configuration of the running kernel, if a configuration file is readable
+    pub fn kernel_config(&self) -> Option<&KernelConfig> {
+        self.kernel_config
+            .get_or_init(|| KernelConfig::load(Path::new("/"), &self.kernel_version))
+            .as_ref()
     }
 
     /// Verify support for a specific PCI device
@@ -118,26 +157,28 @@ impl KernelSupportVerifier {
 
     /// Check modules.alias for device support
     fn check_modules_alias(&self, device_id: &str) -> Result<Option<String>> {
-        let alias_content = match fs::read_to_string(&self.modules_alias_path) {
-            Ok(content) => content,
+        let aliases = self.pci_aliases.get_or_init(|| self.load_pci_aliases());
+        Ok(aliases
+            .iter()
+            .find(|(alias, _)| self.matches_pci_alias(device_id, alias))
+            .map(|(_, module)| module.clone()))
+    }
+
+    /// Read the PCI entries of modules.alias once per verifier
+    fn load_pci_aliases(&self) -> Vec<(String, String)> {
+        match fs::read_to_string(&self.modules_alias_path) {
+            Ok(content) => content.lines().filter_map(Self::parse_pci_alias_line).collect(),
             Err(_) => {
                 log::warn!("Could not read modules.alias at {}", self.modules_alias_path);
-                return Ok(None);
-            }
-        };
-
-        // Parse modules.alias format: alias pci:v00001B21d00000612sv*sd*bc*sc*i* ahci
-        for line in alias_content.lines() {
-            if let Some(module) = self.parse_pci_alias_line(line, device_id) {
-                return Ok(Some(module));
+                Vec::new()
             }
         }
-
-        Ok(None)
     }
 
-    /// Parse a PCI alias line and return module name if it matches the device_id
-    fn parse_pci_alias_line(&self, line: &str, device_id: &str) -> Option<String> {
+    /// Parse a PCI alias line into its pattern and module name
+    ///
+    /// Format: `alias pci:v00001B21d00000612sv*sd*bc*sc*i* ahci`
+    fn parse_pci_alias_line(line: &str) -> Option<(String, String)> {
         if !line.starts_with("alias pci:") {
             return None;
         }
@@ -147,14 +188,7 @@ impl KernelSupportVerifier {
             return None;
         }
 
-        let alias = parts[1]; // The PCI alias pattern
-        let module = parts[2]; // The module name
-
-        if self.matches_pci_alias(device_id, alias) {
-            Some(module.trim().to_string())
-        } else {
-            None
-        }
+        Some((parts[1].to_string(), parts[2].trim().to_string()))
     }
 
     /// Check if PCI device ID matches alias pattern
@@ -219,21 +253,13 @@ impl KernelSupportVerifier {
 
     /// Check if a specific kernel configuration option is enabled
     pub fn check_config_option(&self, config_name: &str) -> Result<bool> {
-        if let Some(ref config_path) = self.config_path {
-            let config_content = fs::read_to_string(config_path)
-                .map_err(|_| LxHwError::ConfigError("Could not read kernel config".to_string()))?;
-
-            // Look for CONFIG_OPTION=y or CONFIG_OPTION=m
-            let search_pattern = format!("{}=", config_name);
-            for line in config_content.lines() {
-                if line.starts_with(&search_pattern) {
-                    return Ok(line.ends_with("=y") || line.ends_with("=m"));
-                }
-            }
+        if self.config_path.is_some() && self.kernel_config().is_none() {
+            return Err(LxHwError::ConfigError("Could not read kernel config".to_string()));
         }
-
-        // If no config file or option not found, assume it could be enabled
-        Ok(false)
+        Ok(self
+            .kernel_config()
+            .and_then(|config| config.get(config_name))
+            .is_some_and(|value| matches!(value, ConfigValue::BuiltIn | ConfigValue::Module)))
     }
 
     /// Check for missing kernel configuration dependencies
@@ -244,31 +270,32 @@ impl KernelSupportVerifier {
     ) {
         use crate::detectors::kernel::{ActionType, RiskLevel, UserAction};
 
+        // Without a configuration file nothing is known about the options, and
+        // symbols the file does not list are not options of this kernel
+        let Some(config) = self.kernel_config() else {
+            return;
+        };
         for config_dep in &device.config_dependencies {
-            if let Ok(enabled) =
-                self.check_config_option(&format!("CONFIG_{}", config_dep.to_uppercase()))
-            {
-                if !enabled {
-                    configuration_changes.push(UserAction {
-                        action_type: ActionType::ReconfigureKernel,
-                        description: format!("Enable {} in kernel configuration", config_dep),
-                        commands: vec![
-                            "# This requires kernel recompilation".to_string(),
-                            format!(
-                                "# Enable CONFIG_{} in kernel config",
-                                config_dep.to_uppercase()
-                            ),
-                            "# Or install a kernel with this option enabled".to_string(),
-                        ],
-                        risk_level: RiskLevel::High,
-                        explanation: format!(
-                            "The {} module requires CONFIG_{} to be enabled in the kernel.",
-                            device.driver_module,
-                            config_dep.to_uppercase()
-                        ),
-                    });
-                }
+            let symbol = config_symbol(config_dep);
+            if config.get(&symbol) != Some(&ConfigValue::NotSet) {
+                continue;
             }
+            configuration_changes.push(UserAction {
+                action_type: ActionType::ReconfigureKernel,
+                description: format!("Enable {} in kernel configuration", symbol),
+                commands: vec![
+                    "# This requires kernel recompilation".to_string(),
+                    format!("# Set {}=m in kernel config", symbol),
+                    "# Or install a kernel with this option enabled".to_string(),
+                ],
+                risk_level: RiskLevel::High,
+                explanation: format!(
+                    "The {} module requires {}, which is not set in the running kernel ({}).",
+                    device.driver_module,
+                    symbol,
+                    config.source.display()
+                ),
+            });
         }
     }
 
@@ -292,12 +319,29 @@ impl KernelSupportVerifier {
 
         Ok(KernelSupportData {
             kernel_version: self.kernel_version.clone(),
-            supported_devices,
             module_aliases,
-            config_options: HashMap::new(), // TODO: populate with relevant CONFIG_ options
+            config_options: self.dependency_config_options(&supported_devices),
+            supported_devices,
         })
     }
 
+    /// Whether each config symbol the devices' modules depend on is enabled,
+    /// for the symbols the running kernel's configuration lists
+    fn dependency_config_options(&self, devices: &[DeviceSupport]) -> HashMap<String, bool> {
+        let Some(config) = self.kernel_config() else {
+            return HashMap::new();
+        };
+        devices
+            .iter()
+            .flat_map(|device| &device.config_dependencies)
+            .filter_map(|dependency| {
+                let symbol = config_symbol(dependency);
+                let enabled = config.get(&symbol)?.is_enabled();
+                Some((symbol, enabled))
+            })
+            .collect()
+    }
+
     /// Generate user-friendly recommendations for hardware support
     pub fn generate_user_recommendations(
         &self,
@@ -321,6 +365,7 @@ impl KernelSupportVerifier {
                         kernel_upgrade_needed = true;
                         recommendations.kernel_upgrades.push(upgrade_rec);
                     }
+                    missing_modules.extend(self.out_of_tree_action("pci", &device.device_id));
                 }
                 SupportLevel::Experimental => {
                     configuration_changes.push(UserAction {
@@ -331,17 +376,15 @@ impl KernelSupportVerifier {
                         explanation: "This device has experimental kernel support. It may work but could be unstable.".to_string(),
                     });
                 }
-                SupportLevel::Supported => {
-                    // Check if module is loaded
-                    if !self.is_module_loaded(&device.driver_module) {
-                        missing_modules.push(UserAction {
-                            action_type: ActionType::LoadModule,
-                            description: format!("Load driver module for {}", device.device_id),
-                            commands: vec![format!("sudo modprobe {}", device.driver_module)],
-                            risk_level: RiskLevel::Low,
-                            explanation: "This device is supported but the driver module is not currently loaded.".to_string(),
-                        });
-                    }
+                // Supported, but the module is not loaded
+                SupportLevel::Supported if !self.is_module_loaded(&device.driver_module) => {
+                    missing_modules.push(UserAction {
+                        action_type: ActionType::LoadModule,
+                        description: format!("Load driver module for {}", device.device_id),
+                        commands: vec![format!("sudo modprobe {}", device.driver_module)],
+                        risk_level: RiskLevel::Low,
+                        explanation: "This device is supported but the driver module is not currently loaded.".to_string(),
+                    });
                 }
                 _ => {}
             }
@@ -357,10 +400,66 @@ impl KernelSupportVerifier {
 
         // Add general recommendations
         self.add_general_recommendations(&mut recommendations);
+        self.add_legacy_driver_recommendations(support_data, &mut recommendations);
 
         recommendations
     }
 
+    /// Action installing the out-of-tree driver known for a device on a bus
+    /// ("pci" or "usb") the kernel has no driver for
+    pub fn out_of_tree_action(&self, bus: &str, device_id: &str) -> Option<UserAction> {
+        let driver = self.out_of_tree.lookup(bus, device_id)?;
+        let mut explanation = format!(
+            "No in-kernel driver supports this device. {} is maintained outside the kernel and is rebuilt by DKMS as module {} after each kernel update.",
+            driver.name, driver.dkms_module
+        );
+        if let Some(mainline) = driver.mainline.as_deref() {
+            if !driver.in_mainline(&self.kernel_version) {
+                explanation.push_str(&format!(
+                    " Kernel {} and newer include a driver for it; upgrading the kernel avoids the out-of-tree module.",
+                    mainline
+                ));
+            }
+        }
+        if let Some(notes) = &driver.notes {
+            explanation.push_str(&format!(" {}.", notes.trim_end_matches('.')));
+        }
+        let mut commands = driver.install_commands(Self::package_family());
+        if let Some(system) = detect_immutable(Path::new("/")) {
+            commands = commands
+                .iter()
+                .map(|command| {
+                    system.kind.rewrite_install_command(command).unwrap_or_else(|| command.clone())
+                })
+                .collect();
+            explanation.push(' ');
+            explanation.push_str(system.kind.driver_warning());
+        }
+        Some(UserAction {
+            action_type: ActionType::InstallDkmsDriver,
+            description: format!(
+                "Install the out-of-tree {} driver for {} ({})",
+                driver.name, device_id, driver.project
+            ),
+            commands,
+            risk_level: RiskLevel::Medium,
+            explanation,
+        })
+    }
+
+    /// Distribution family used to pick a packaged driver build
+    fn package_family() -> Option<&'static str> {
+        if Path::new("/etc/debian_version").exists() {
+            Some("debian")
+        } else if Path::new("/etc/fedora-release").exists() {
+            Some("fedora")
+        } else if Path::new("/etc/arch-release").exists() {
+            Some("arch")
+        } else {
+            None
+        }
+    }
+
     /// Check if a kernel module is currently loaded
     fn is_module_loaded(&self, module_name: &str) -> bool {
         std::fs::read_to_string("/proc/modules")
@@ -375,16 +474,71 @@ impl KernelSupportVerifier {
 
         let current_version = self.parse_kernel_version(&self.kernel_version);
 
-        // Example logic: if current kernel is older than 5.15 and device is unsupported,
-        // recommend upgrade to latest LTS
-        if current_version.0 < 5 || (current_version.0 == 5 && current_version.1 < 15) {
+        // Hardware tied to a legacy driver cannot use kernels past the
+        // driver's last supported series
+        if let Some(generation) = self.legacy_generation(device_id) {
+            let driver = generation.driver.as_ref()?;
+            let max = generation.max_kernel_series()?;
+            if current_version >= max {
+                return None;
+            }
+            // Prefer a longterm series the driver supports over its newest,
+            // possibly end-of-life, series
+            let (recommended_kernel, support_status) = match self
+                .kernel_releases
+                .recommended_longterm(current_version, Some(max))
+            {
+                Some((series, status)) => (
+                    format!(
+                        "{} ({}; newest LTS supported by {})",
+                        series,
+                        status.label(),
+                        driver.name
+                    ),
+                    Some(status),
+                ),
+                None => {
+                    let series = format!("{}.{}", max.0, max.1);
+                    let status = self.kernel_releases.status(&series);
+                    let label = status
+                        .as_ref()
+                        .filter(|status| !status.is_supported())
+                        .map(|status| format!("; {}", status.label()))
+                        .unwrap_or_default();
+                    (format!("{} (newest supported by {}{})", series, driver.name, label), status)
+                }
+            };
+            return Some(KernelUpgradeRecommendation {
+                device_id: device_id.to_string(),
+                current_kernel: self.kernel_version.clone(),
+                recommended_kernel,
+                reason: format!("{} is limited to the {} driver", generation.name, driver.name),
+                upgrade_method: self.suggest_upgrade_method(),
+                estimated_support_probability: 50,
+                support_status,
+            });
+        }
+
+        // Kernels older than 5.15, or no longer receiving fixes, are moved to
+        // the newest supported LTS series
+        let end_of_life = self.kernel_releases.status(&self.kernel_version)
+            == Some(KernelSeriesStatus::EndOfLife);
+        if current_version < (5, 15) || end_of_life {
+            let (recommended_kernel, support_status) =
+                match self.kernel_releases.recommended_longterm(current_version, None) {
+                    Some((series, status)) => {
+                        (format!("{} ({})", series, status.label()), Some(status))
+                    }
+                    None => ("6.1 LTS or newer".to_string(), None),
+                };
             return Some(KernelUpgradeRecommendation {
                 device_id: device_id.to_string(),
                 current_kernel: self.kernel_version.clone(),
-                recommended_kernel: "6.1 LTS or newer".to_string(),
+                recommended_kernel,
                 reason: "Hardware support significantly improved in newer kernels".to_string(),
                 upgrade_method: self.suggest_upgrade_method(),
                 estimated_support_probability: 70, // Percentage chance of support
+                support_status,
             });
         }
 
@@ -410,7 +564,7 @@ impl KernelSupportVerifier {
             vec![
                 "# For Ubuntu/Debian:".to_string(),
                 "sudo apt update".to_string(),
-                "sudo apt install linux-generic-hwe-22.04".to_string(),
+                format!("sudo apt install {}", Self::hwe_kernel_package()),
                 "# Or for latest mainline:".to_string(),
                 "# Use mainline kernel PPA or download from kernel.org".to_string(),
             ]
@@ -437,16 +591,35 @@ impl KernelSupportVerifier {
         }
     }
 
+    /// Ubuntu's hardware enablement kernel package for the running release
+    fn hwe_kernel_package() -> String {
+        let release = fs::read_to_string("/etc/os-release").ok().and_then(|content| {
+            content.lines().find_map(|line| {
+                line.strip_prefix("VERSION_ID=").map(|v| v.trim_matches('"').to_string())
+            })
+        });
+        format!("linux-generic-hwe-{}", release.as_deref().unwrap_or("22.04"))
+    }
+
     /// Add general system recommendations
     fn add_general_recommendations(&self, recommendations: &mut UserRecommendations) {
-        // Check system health indicators
-        let kernel_age = self.estimate_kernel_age(&self.kernel_version);
-
-        if kernel_age > 365 {
-            // Older than 1 year
-            recommendations.general_advice.push(
-                "Consider upgrading to a newer kernel for better hardware support and security updates.".to_string()
+        // Kernels past end of life no longer receive security fixes
+        if self.kernel_releases.status(&self.kernel_version) == Some(KernelSeriesStatus::EndOfLife)
+        {
+            let current = self.parse_kernel_version(&self.kernel_version);
+            let mut advice = format!(
+                "Consider upgrading to a newer kernel: the {}.{} series is end of life and no longer receives fixes.",
+                current.0, current.1
             );
+            if let Some((series, status)) = self.kernel_releases.recommended_longterm(current, None)
+            {
+                advice.push_str(&format!(
+                    " {} is a supported LTS series ({}).",
+                    series,
+                    status.label()
+                ));
+            }
+            recommendations.general_advice.push(advice);
         }
 
         // Check for common issues
@@ -464,18 +637,44 @@ impl KernelSupportVerifier {
         }
     }
 
-    /// Estimate kernel age in days (simplified)
-    fn estimate_kernel_age(&self, kernel_version: &str) -> u32 {
-        // This is a simplified estimation - real implementation would use a database
-        let version = self.parse_kernel_version(kernel_version);
-        match version.0 {
-            6 => 30,   // Very recent
-            5 => 200,  // Recent
-            4 => 800,  // Old
-            _ => 1000, // Very old
+    /// Warn about hardware limited to a legacy driver branch, and drop
+    /// generic upgrade advice the legacy driver cannot follow
+    fn add_legacy_driver_recommendations(
+        &self,
+        support_data: &KernelSupportData,
+        recommendations: &mut UserRecommendations,
+    ) {
+        let mut legacy: Vec<&Generation> = support_data
+            .supported_devices
+            .iter()
+            .filter_map(|device| self.legacy_generation(&device.device_id))
+            .collect();
+        legacy.sort_by(|a, b| a.name.cmp(&b.name));
+        legacy.dedup_by(|a, b| a.name == b.name);
+
+        for generation in &legacy {
+            if let Some(warning) = generation.kernel_warning(&self.kernel_version) {
+                recommendations.general_advice.push(warning);
+            } else if let Some((driver, (major, minor))) =
+                generation.driver.as_ref().zip(generation.max_kernel_series())
+            {
+                recommendations
+                    .general_advice
+                    .retain(|advice| !advice.starts_with("Consider upgrading to a newer kernel"));
+                recommendations.general_advice.push(format!(
+                    "{} requires the {} driver; do not upgrade beyond kernel {}.{} while using it.",
+                    generation.name, driver.name, major, minor
+                ));
+            }
         }
     }
 
+    /// Hardware generation of a device whose driver stops at a kernel version
+    fn legacy_generation(&self, device_id: &str) -> Option<&Generation> {
+        let (vendor, device) = device_id.split_once(':')?;
+        self.lifecycle.lookup_pci(vendor, device).filter(|g| g.max_kernel_series().is_some())
+    }
+
     /// Check if system is virtualized
     fn is_virtualized(&self) -> bool {
         std::fs::read_to_string("/proc/cpuinfo")
@@ -513,6 +712,193 @@ impl KernelSupportVerifier {
 
         Ok(device_ids)
     }
+
+    /// IDs of the system's USB devices with no driver bound to any interface
+    pub fn system_unclaimed_usb_device_ids(&self) -> Vec<String> {
+        let Ok(entries) = fs::read_dir("/sys/bus/usb/devices") else {
+            return Vec::new();
+        };
+
+        let mut device_ids: Vec<String> = entries
+            .flatten()
+            .map(|entry| entry.path())
+            .filter_map(|path| {
+                let read = |file: &str| {
+                    fs::read_to_string(path.join(file)).ok().map(|v| v.trim().to_lowercase())
+                };
+                let id = format!("{}:{}", read("idVendor")?, read("idProduct")?);
+                // Interfaces are subdirectories named "<bus>-<port>:<config>.<interface>"
+                let interfaces: Vec<_> = fs::read_dir(&path)
+                    .ok()?
+                    .flatten()
+                    .filter(|entry| entry.file_name().to_string_lossy().contains(':'))
+                    .map(|entry| entry.path())
+                    .collect();
+                let unclaimed = !interfaces.is_empty()
+                    && interfaces.iter().all(|interface| !interface.join("driver").exists());
+                unclaimed.then_some(id)
+            })
+            .collect();
+        device_ids.sort();
+        device_ids.dedup();
+        device_ids
+    }
+
+    /// PCI class codes of the system's devices, keyed by "vendor:device"
+    pub fn system_device_classes(&self) -> HashMap<String, String> {
+        let Ok(entries) = fs::read_dir("/sys/bus/pci/devices") else {
+            return HashMap::new();
+        };
+
+        let read = |path: &Path, file: &str| {
+            let value = fs::read_to_string(path.join(file)).ok()?;
+            let value = value.trim();
+            Some(value.strip_prefix("0x").unwrap_or(value).to_lowercase())
+        };
+
+        entries
+            .flatten()
+            .filter_map(|entry| {
+                let path = entry.path();
+                let id = format!("{}:{}", read(&path, "vendor")?, read(&path, "device")?);
+                Some((id, read(&path, "class")?))
+            })
+            .collect()
+    }
+}
+
+/// Directories read by modprobe, in increasing precedence
+const MODPROBE_DIRS: [&str; 5] = [
+    "lib/modprobe.d",
+    "usr/lib/modprobe.d",
+    "usr/local/lib/modprobe.d",
+    "run/modprobe.d",
+    "etc/modprobe.d",
+];
+
+/// Parameter values of loaded modules under `root`
+///
+/// Modules without parameters, or not loaded, are left out, as are
+/// parameters only root can read.
+pub fn read_module_parameters(root: &Path, modules: &[String]) -> Vec<ModuleParameters> {
+    let mut modules: Vec<String> = modules.iter().map(|module| module.replace('-', "_")).collect();
+    modules.sort();
+    modules.dedup();
+
+    modules
+        .into_iter()
+        .filter_map(|module| {
+            let dir = root.join("sys/module").join(&module).join("parameters");
+            let parameters: BTreeMap<String, String> = fs::read_dir(dir)
+                .ok()?
+                .flatten()
+                .filter_map(|entry| {
+                    let value = fs::read_to_string(entry.path()).ok()?;
+                    Some((
+                        entry.file_name().to_string_lossy().into_owned(),
+                        value.trim().to_string(),
+                    ))
+                })
+                .collect();
+            (!parameters.is_empty()).then_some(ModuleParameters { module, parameters })
+        })
+        .collect()
+}
+
+/// Modules given options in modprobe.d or as `module.option=` on the kernel
+/// command line under `root`
+pub fn configured_modules(root: &Path) -> Vec<String> {
+    let mut modules = Vec::new();
+    for (_, content) in modprobe_config(root) {
+        for line in content.lines() {
+            if let ["options", module, ..] = line.split_whitespace().collect::<Vec<_>>()[..] {
+                modules.push(module.replace('-', "_"));
+            }
+        }
+    }
+    let cmdline = fs::read_to_string(root.join("proc/cmdline")).unwrap_or_default();
+    for argument in cmdline.split_whitespace() {
+        let key = argument.split_once('=').map_or(argument, |(key, _)| key);
+        if let Some((module, _)) = key.split_once('.') {
+            modules.push(module.replace('-', "_"));
+        }
+    }
+    modules.sort();
+    modules.dedup();
+    modules
+}
+
+/// Modules modprobe.d or the kernel command line under `root` keep from loading
+pub fn read_module_blacklist(root: &Path) -> Vec<BlacklistedModule> {
+    let mut blacklisted = Vec::new();
+    for (source, content) in modprobe_config(root) {
+        for line in content.lines() {
+            let words: Vec<&str> = line.split_whitespace().collect();
+            let method = match words[..] {
+                ["blacklist", _] => BlacklistMethod::Blacklist,
+                // Only installs that replace loading with a no-op block the module
+                ["install", _, command, ..]
+                    if matches!(command.rsplit('/').next(), Some("true" | "false")) =>
+                {
+                    BlacklistMethod::Install
+                }
+                _ => continue,
+            };
+            blacklisted.push(BlacklistedModule {
+                module: words[1].replace('-', "_"),
+                method,
+                source: source.clone(),
+            });
+        }
+    }
+
+    let cmdline = fs::read_to_string(root.join("proc/cmdline")).unwrap_or_default();
+    for argument in cmdline.split_whitespace() {
+        let Some(("module_blacklist" | "modprobe.blacklist", modules)) = argument.split_once('=')
+        else {
+            continue;
+        };
+        for module in modules.split(',').filter(|module| !module.is_empty()) {
+            blacklisted.push(BlacklistedModule {
+                module: module.replace('-', "_"),
+                method: BlacklistMethod::Cmdline,
+                source: "/proc/cmdline".to_string(),
+            });
+        }
+    }
+    blacklisted
+}
+
+/// Contents of the modprobe.d files under `root` in the order modprobe
+/// applies them, keyed by their path on the system, with comments removed
+///
+/// A file overrides any file of the same name in a lower-precedence directory.
+fn modprobe_config(root: &Path) -> Vec<(String, String)> {
+    let mut by_name: BTreeMap<String, String> = BTreeMap::new();
+    for dir in MODPROBE_DIRS {
+        let Ok(entries) = fs::read_dir(root.join(dir)) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let name = entry.file_name().to_string_lossy().into_owned();
+            if name.ends_with(".conf") {
+                by_name.insert(name.clone(), format!("/{}/{}", dir, name));
+            }
+        }
+    }
+
+    by_name
+        .into_values()
+        .filter_map(|path| {
+            let content = fs::read_to_string(root.join(path.trim_start_matches('/'))).ok()?;
+            let content = content
+                .lines()
+                .map(|line| line.split('#').next().unwrap_or_default())
+                .collect::<Vec<_>>()
+                .join("\n");
+            Some((path, content))
+        })
+        .collect()
 }
 
 impl Default for KernelSupportData {
@@ -538,6 +924,9 @@ pub struct UserRecommendations {
     pub module_actions: Vec<UserAction>,
     pub configuration_actions: Vec<UserAction>,
     pub general_advice: Vec<String>,
+    /// Recommended packages missing from the configured repositories
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub unavailable_packages: Vec<String>,
 }
 
 /// Kernel upgrade recommendation
@@ -549,6 +938,9 @@ pub struct KernelUpgradeRecommendation {
     pub reason: String,
     pub upgrade_method: Vec<String>,
     pub estimated_support_probability: u8, // 0-100%
+    /// LTS/end-of-life status of the recommended kernel series
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub support_status: Option<KernelSeriesStatus>,
 }
 
 /// User action recommendation
@@ -569,6 +961,7 @@ pub enum ActionType {
     ReconfigureKernel,
     UpgradeKernel,
     InstallFirmware,
+    InstallDkmsDriver,
 }
 
 /// Risk level for user actions
@@ -591,6 +984,7 @@ impl UserRecommendations {
             module_actions: Vec::new(),
             configuration_actions: Vec::new(),
             general_advice: Vec::new(),
+            unavailable_packages: Vec::new(),
         }
     }
 }
@@ -605,6 +999,11 @@ mod tests {
             kernel_version: "test".to_string(),
             modules_alias_path: "/test".to_string(),
             config_path: None,
+            lifecycle: LifecycleDatabase::builtin(),
+            out_of_tree: OutOfTreeDatabase::builtin(),
+            kernel_releases: KernelReleases::builtin(),
+            pci_aliases: OnceLock::new(),
+            kernel_config: OnceLock::new(),
         };
 
         // Test vendor:device format matching
@@ -615,6 +1014,35 @@ mod tests {
             .matches_pci_alias("1234:5678", "alias pci:v00001B21d00000612sv*sd*bc*sc*i* ahci"));
     }
 
+    #[test]
+    fn test_modules_alias_read_once() {
+        let dir = tempfile::tempdir().unwrap();
+        let alias_path = dir.path().join("modules.alias");
+        fs::write(
+            &alias_path,
+            "alias usb:v046Dp*d*dc*dsc*dp*ic*isc*ip*in* hid_logitech\n\
+             alias pci:v00001B21d00000612sv*sd*bc*sc*i* ahci\n",
+        )
+        .unwrap();
+        let verifier = KernelSupportVerifier {
+            kernel_version: "test".to_string(),
+            modules_alias_path: alias_path.to_string_lossy().into_owned(),
+            config_path: None,
+            lifecycle: LifecycleDatabase::builtin(),
+            out_of_tree: OutOfTreeDatabase::builtin(),
+            kernel_releases: KernelReleases::builtin(),
+            pci_aliases: OnceLock::new(),
+            kernel_config: OnceLock::new(),
+        };
+
+        assert_eq!(verifier.check_modules_alias("1b21:0612").unwrap().as_deref(), Some("ahci"));
+
+        // Later lookups use the parsed aliases rather than the file
+        fs::remove_file(&alias_path).unwrap();
+        assert_eq!(verifier.check_modules_alias("1b21:0612").unwrap().as_deref(), Some("ahci"));
+        assert_eq!(verifier.check_modules_alias("1234:5678").unwrap(), None);
+    }
+
     #[test]
     fn test_support_level_serialization() {
         let support = DeviceSupport {
@@ -631,4 +1059,186 @@ mod tests {
         assert_eq!(support.device_id, deserialized.device_id);
         assert_eq!(support.driver_module, deserialized.driver_module);
     }
+
+    #[test]
+    fn test_missing_config_dependencies() {
+        let mut config = KernelConfig::parse(
+            "CONFIG_DRM=y\nCONFIG_DRM_TTM=m\n# CONFIG_DRM_DISPLAY_HELPER is not set\n",
+        );
+        config.source = "/proc/config.gz".into();
+        let verifier = verifier("6.10.0");
+        verifier.kernel_config.set(Some(config)).unwrap();
+        let device = DeviceSupport {
+            device_id: "1002:73bf".to_string(),
+            driver_module: "amdgpu".to_string(),
+            support_level: SupportLevel::Supported,
+            kernel_version_added: None,
+            config_dependencies: ["drm", "drm_ttm", "drm_display_helper", "gpu_sched"]
+                .map(String::from)
+                .to_vec(),
+        };
+
+        let mut actions = Vec::new();
+        verifier.check_missing_config_dependencies(&device, &mut actions);
+        assert_eq!(actions.len(), 1);
+        assert_eq!(
+            actions[0].description,
+            "Enable CONFIG_DRM_DISPLAY_HELPER in kernel configuration"
+        );
+        assert!(actions[0].explanation.contains("/proc/config.gz"));
+
+        let options = verifier.dependency_config_options(std::slice::from_ref(&device));
+        assert_eq!(options.len(), 3);
+        assert!(options["CONFIG_DRM_TTM"]);
+        assert!(!options["CONFIG_DRM_DISPLAY_HELPER"]);
+        assert!(verifier.check_config_option("CONFIG_DRM").unwrap());
+        assert!(!verifier.check_config_option("CONFIG_GPU_SCHED").unwrap());
+    }
+
+    /// Verifier for a kernel version with fixed release data
+    fn verifier(kernel_version: &str) -> KernelSupportVerifier {
+        let kernel_releases = KernelReleases::parse(
+            r#"
+            updated = "2024-06-01"
+            stable = "6.9"
+            longterm = [
+                { series = "6.6", eol = "2099-12" },
+                { series = "6.1", eol = "2099-12" },
+                { series = "5.10", eol = "2099-12" },
+            ]
+            "#,
+        )
+        .unwrap();
+        KernelSupportVerifier {
+            kernel_version: kernel_version.to_string(),
+            modules_alias_path: "/test".to_string(),
+            config_path: None,
+            lifecycle: LifecycleDatabase::builtin(),
+            out_of_tree: OutOfTreeDatabase::builtin(),
+            kernel_releases,
+            pci_aliases: OnceLock::new(),
+            kernel_config: OnceLock::new(),
+        }
+    }
+
+    fn unsupported_device(device_id: &str) -> KernelSupportData {
+        KernelSupportData {
+            kernel_version: String::new(),
+            supported_devices: vec![DeviceSupport {
+                device_id: device_id.to_string(),
+                driver_module: "unknown".to_string(),
+                support_level: SupportLevel::Unsupported,
+                kernel_version_added: None,
+                config_dependencies: Vec::new(),
+            }],
+            module_aliases: HashMap::new(),
+            config_options: HashMap::new(),
+        }
+    }
+
+    #[test]
+    fn test_legacy_driver_limits_recommendations() {
+        let support_data = unsupported_device("10de:1180");
+
+        let too_new = verifier("6.10.2").generate_user_recommendations(&support_data);
+        assert!(too_new.kernel_upgrades.is_empty());
+        assert!(too_new.general_advice.iter().any(|a| a.contains("not available for kernels")));
+
+        let old = verifier("5.10.0").generate_user_recommendations(&support_data);
+        assert_eq!(
+            old.kernel_upgrades[0].recommended_kernel,
+            "6.6 (LTS, supported until 2099-12; newest LTS supported by NVIDIA 470.xx legacy)"
+        );
+        assert!(!old.general_advice.iter().any(|a| a.starts_with("Consider upgrading")));
+
+        // Without a longer-supported LTS in reach, the driver's newest series
+        // is recommended and flagged as end of life
+        let lts = verifier("6.6.30").generate_user_recommendations(&support_data);
+        assert_eq!(
+            lts.kernel_upgrades[0].recommended_kernel,
+            "6.8 (newest supported by NVIDIA 470.xx legacy; end of life)"
+        );
+        assert_eq!(lts.kernel_upgrades[0].support_status, Some(KernelSeriesStatus::EndOfLife));
+    }
+
+    #[test]
+    fn test_module_parameters_and_blacklist() {
+        let root = tempfile::TempDir::new().unwrap();
+        let write = |file: &str, content: &str| {
+            let path = root.path().join(file);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, content).unwrap();
+        };
+        write("sys/module/i915/parameters/enable_psr", "0\n");
+        write("sys/module/i915/parameters/enable_guc", "-1\n");
+        write("sys/module/snd_hda_intel/parameters/power_save", "1\n");
+        std::fs::create_dir_all(root.path().join("sys/module/loop")).unwrap();
+        write(
+            "proc/cmdline",
+            "BOOT_IMAGE=/vmlinuz root=UUID=1234 i915.enable_psr=0 module_blacklist=pcspkr,floppy\n",
+        );
+        write("usr/lib/modprobe.d/blacklist.conf", "blacklist nouveau\nblacklist evbug\n");
+        // Overrides the vendor file of the same name
+        write("etc/modprobe.d/blacklist.conf", "# keep nouveau usable\nblacklist evbug # noisy\n");
+        write(
+            "etc/modprobe.d/local.conf",
+            "options snd-hda-intel power_save=1\ninstall nvidiafb /bin/false\ninstall foo /sbin/modprobe --ignore-install foo\n",
+        );
+
+        let configured = configured_modules(root.path());
+        assert_eq!(configured, ["i915", "snd_hda_intel"]);
+
+        let parameters = read_module_parameters(
+            root.path(),
+            &["loop".to_string(), "snd-hda-intel".to_string(), "i915".to_string()],
+        );
+        assert_eq!(parameters.len(), 2);
+        assert_eq!(parameters[0].module, "i915");
+        assert_eq!(parameters[0].parameters["enable_psr"], "0");
+        assert_eq!(parameters[1].parameters["power_save"], "1");
+
+        let blacklist: Vec<_> = read_module_blacklist(root.path())
+            .into_iter()
+            .map(|module| (module.module, module.method, module.source))
+            .collect();
+        assert_eq!(
+            blacklist,
+            [
+                (
+                    "evbug".to_string(),
+                    BlacklistMethod::Blacklist,
+                    "/etc/modprobe.d/blacklist.conf".to_string()
+                ),
+                (
+                    "nvidiafb".to_string(),
+                    BlacklistMethod::Install,
+                    "/etc/modprobe.d/local.conf".to_string()
+                ),
+                ("pcspkr".to_string(), BlacklistMethod::Cmdline, "/proc/cmdline".to_string()),
+                ("floppy".to_string(), BlacklistMethod::Cmdline, "/proc/cmdline".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_end_of_life_kernel_recommends_supported_lts() {
+        let recommendations =
+            verifier("6.4.12").generate_user_recommendations(&unsupported_device("1234:5678"));
+
+        let upgrade = &recommendations.kernel_upgrades[0];
+        assert_eq!(upgrade.recommended_kernel, "6.6 (LTS, supported until 2099-12)");
+        assert_eq!(
+            upgrade.support_status,
+            Some(KernelSeriesStatus::Longterm { eol: Some("2099-12".to_string()) })
+        );
+        assert!(recommendations
+            .general_advice
+            .iter()
+            .any(|a| a.contains("6.4 series is end of life") && a.contains("6.6")));
+
+        // Supported kernels newer than 5.15 get no generic upgrade advice
+        let current =
+            verifier("6.9.3").generate_user_recommendations(&unsupported_device("1234:5678"));
+        assert!(current.kernel_upgrades.is_empty());
+    }
 }
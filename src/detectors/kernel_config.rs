@@ -0,0 +1,159 @@
+//! Configuration of the running kernel
+//!
+//! Kernels built with `CONFIG_IKCONFIG_PROC` expose their configuration as
+//! `/proc/config.gz` (with the `configs` module loaded where it is modular);
+//! most distributions also install it as `/boot/config-<release>`. Each
+//! option is listed as `CONFIG_X=y`, `=m` or a value, and disabled options
+//! that could have been enabled as `# CONFIG_X is not set`. Symbols missing
+//! from the file entirely are not options of this kernel's source, so their
+//! state is unknown rather than disabled.
+
+use flate2::read::GzDecoder;
+use std::collections::HashMap;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+/// State of one option in the kernel configuration
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConfigValue {
+    /// `=y`
+    BuiltIn,
+    /// `=m`
+    Module,
+    /// `# CONFIG_X is not set`
+    NotSet,
+    /// A string or number, e.g. `CONFIG_HZ=1000`
+    Value(String),
+}
+
+impl ConfigValue {
+    /// The option is compiled in or available as a module
+    pub fn is_enabled(&self) -> bool {
+        !matches!(self, Self::NotSet)
+    }
+}
+
+/// Parsed kernel configuration
+#[derive(Debug, Clone, Default)]
+pub struct KernelConfig {
+    /// File the configuration was read from
+    pub source: PathBuf,
+    options: HashMap<String, ConfigValue>,
+}
+
+impl KernelConfig {
+    /// Read the configuration of kernel `release` under `root`, preferring
+    /// `/proc/config.gz` since it always describes the running kernel
+    pub fn load(root: &Path, release: &str) -> Option<Self> {
+        Self::candidates(release).into_iter().find_map(|path| {
+            let content = read_config(&root.join(path.trim_start_matches('/')))?;
+            Some(Self { source: PathBuf::from(path), ..Self::parse(&content) })
+        })
+    }
+
+    /// Paths a kernel configuration may be found at, in order of preference
+    pub fn candidates(release: &str) -> Vec<String> {
+        vec![
+            "/proc/config.gz".to_string(),
+            format!("/boot/config-{}", release),
+            "/boot/config".to_string(),
+        ]
+    }
+
+    /// Parse a `.config` file
+    pub fn parse(content: &str) -> Self {
+        let mut options = HashMap::new();
+        for line in content.lines().map(str::trim) {
+            if let Some(name) =
+                line.strip_prefix("# ").and_then(|rest| rest.strip_suffix(" is not set"))
+            {
+                options.insert(name.to_string(), ConfigValue::NotSet);
+            } else if let Some((name, value)) = line.split_once('=') {
+                if !name.starts_with("CONFIG_") {
+                    continue;
+                }
+                let value = match value {
+                    "y" => ConfigValue::BuiltIn,
+                    "m" => ConfigValue::Module,
+                    "n" => ConfigValue::NotSet,
+                    value => ConfigValue::Value(value.trim_matches('"').to_string()),
+                };
+                options.insert(name.to_string(), value);
+            }
+        }
+        Self { source: PathBuf::new(), options }
+    }
+
+    /// State of an option, with or without the `CONFIG_` prefix; None when
+    /// the kernel has no such option
+    pub fn get(&self, option: &str) -> Option<&ConfigValue> {
+        self.options.get(&config_symbol(option))
+    }
+}
+
+/// Config symbol named after a module or option, e.g. "CONFIG_DRM_KMS_HELPER"
+/// for "drm_kms_helper"
+pub fn config_symbol(name: &str) -> String {
+    let name = name.to_uppercase().replace('-', "_");
+    match name.strip_prefix("CONFIG_") {
+        Some(_) => name,
+        None => format!("CONFIG_{}", name),
+    }
+}
+
+/// Read a configuration file, decompressing it when gzipped
+fn read_config(path: &Path) -> Option<String> {
+    let bytes = std::fs::read(path).ok()?;
+    if path.extension().is_some_and(|extension| extension == "gz") {
+        let mut content = String::new();
+        GzDecoder::new(bytes.as_slice()).read_to_string(&mut content).ok()?;
+        Some(content)
+    } else {
+        String::from_utf8(bytes).ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::write::GzEncoder;
+    use flate2::Compression;
+    use std::io::Write;
+
+    const CONFIG: &str = "#
+# Automatically generated file; DO NOT EDIT.
+# Linux/x86 6.10.0 Kernel Configuration
+#
+CONFIG_CC_VERSION_TEXT=\"gcc (GCC) 14.1.1 20240522\"
+CONFIG_HZ=1000
+CONFIG_DRM=y
+CONFIG_DRM_KMS_HELPER=m
+# CONFIG_DRM_NOUVEAU is not set
+";
+
+    #[test]
+    fn test_parse_and_load() {
+        let root = tempfile::TempDir::new().unwrap();
+        std::fs::create_dir_all(root.path().join("proc")).unwrap();
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(CONFIG.as_bytes()).unwrap();
+        std::fs::write(root.path().join("proc/config.gz"), encoder.finish().unwrap()).unwrap();
+        std::fs::create_dir_all(root.path().join("boot")).unwrap();
+        std::fs::write(root.path().join("boot/config-6.10.0"), "CONFIG_DRM=n\n").unwrap();
+
+        let config = KernelConfig::load(root.path(), "6.10.0").unwrap();
+        assert_eq!(config.source, Path::new("/proc/config.gz"));
+        assert_eq!(config.get("CONFIG_DRM"), Some(&ConfigValue::BuiltIn));
+        assert_eq!(config.get("drm_kms_helper"), Some(&ConfigValue::Module));
+        assert_eq!(config.get("drm-nouveau"), Some(&ConfigValue::NotSet));
+        assert!(!config.get("DRM_NOUVEAU").unwrap().is_enabled());
+        assert_eq!(config.get("HZ"), Some(&ConfigValue::Value("1000".to_string())));
+        assert_eq!(config.get("CONFIG_DRM_AMDGPU"), None);
+
+        std::fs::remove_file(root.path().join("proc/config.gz")).unwrap();
+        let config = KernelConfig::load(root.path(), "6.10.0").unwrap();
+        assert_eq!(config.source, Path::new("/boot/config-6.10.0"));
+        assert_eq!(config.get("DRM"), Some(&ConfigValue::NotSet));
+        assert!(KernelConfig::load(root.path(), "6.9.0").is_none());
+    }
+}
@@ -4,6 +4,8 @@
 //! real hardware support information directly from kernel source code.
 
 use crate::errors::{LxHwError, Result};
+use crate::hardware::kernel_releases::{KernelReleases, KernelSeriesStatus};
+use crate::hardware::lifecycle::kernel_series;
 use regex::Regex;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
@@ -351,20 +353,23 @@ impl KernelSourceAnalyzer {
         current_kernel: &str,
         unsupported_devices: &[String],
     ) -> Vec<crate::detectors::kernel::KernelUpgradeRecommendation> {
+        let releases = KernelReleases::load();
         let mut recommendations = Vec::new();
 
         for device_id in unsupported_devices {
             // Analyze when support was likely added
-            let estimated_version = self.estimate_support_version(device_id, current_kernel);
+            let estimated_version =
+                self.estimate_support_version(&releases, device_id, current_kernel);
 
-            if let Some(rec_version) = estimated_version {
+            if let Some((rec_version, status)) = estimated_version {
                 recommendations.push(crate::detectors::kernel::KernelUpgradeRecommendation {
                     device_id: device_id.clone(),
                     current_kernel: current_kernel.to_string(),
-                    recommended_kernel: rec_version.clone(),
+                    recommended_kernel: format!("{} ({})", rec_version, status.label()),
                     reason: format!("Hardware support added in kernel {}", rec_version),
                     upgrade_method: self.suggest_upgrade_method_for_version(&rec_version),
                     estimated_support_probability: 85, // High confidence from source analysis
+                    support_status: Some(status),
                 });
             }
         }
@@ -373,18 +378,21 @@ impl KernelSourceAnalyzer {
     }
 
     /// Estimate which kernel version first supported a device
-    fn estimate_support_version(&self, _device_id: &str, current_kernel: &str) -> Option<String> {
+    ///
+    /// Only supported LTS series newer than the running kernel are suggested.
+    fn estimate_support_version(
+        &self,
+        releases: &KernelReleases,
+        _device_id: &str,
+        current_kernel: &str,
+    ) -> Option<(String, KernelSeriesStatus)> {
         // This would use the cached support data or heuristics
         // Based on device vendor, age, and complexity
-
-        let current_major = self.parse_kernel_major_version(current_kernel);
-        if current_major < 5 {
-            Some("5.15".to_string()) // LTS recommendation
-        } else if current_major < 6 {
-            Some("6.1".to_string()) // Latest LTS
-        } else {
-            Some("6.8".to_string()) // Latest stable
-        }
+        let current = kernel_series(current_kernel)
+            .unwrap_or_else(|| (self.parse_kernel_major_version(current_kernel), 0));
+        releases
+            .recommended_longterm(current, None)
+            .map(|(series, status)| (series.to_string(), status))
     }
 
     /// Parse major version number from kernel version string
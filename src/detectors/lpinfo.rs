@@ -0,0 +1,250 @@
+//! Printers from CUPS
+//!
+//! `lpinfo -l -v` asks every CUPS backend for the printers it can reach,
+//! over USB and on the network. Each entry carries the make and model and
+//! the IEEE 1284 device ID, whose command set tells whether the printer
+//! takes PWG or Apple raster and so prints driverless. Device URIs hold
+//! serial numbers, network addresses and UUIDs, so only the backend name is
+//! kept from them.
+
+use super::extract::{ComponentExtractor, ExtractionContext};
+use super::{sandbox, DetectionData, DetectionError, DetectionResult, HardwareDetector};
+use crate::errors::{LxHwError, Result};
+use crate::hardware::Printer;
+use async_trait::async_trait;
+use std::process::Output;
+use std::time::Duration;
+
+/// Device ID command sets of printers accepting PWG raster (IPP Everywhere)
+/// or Apple raster (AirPrint)
+const DRIVERLESS_LANGUAGES: &[&str] = &["PWG", "PWGRaster", "URF"];
+
+/// Printers reported by lpinfo
+#[derive(Debug, Clone, Default)]
+pub struct LpinfoData {
+    pub printers: Vec<Printer>,
+}
+
+impl ComponentExtractor for LpinfoData {
+    fn printers(&self, _ctx: &mut ExtractionContext) -> Result<Vec<Printer>> {
+        Ok(self.printers.clone())
+    }
+}
+
+/// Parse `lpinfo -l -v`, dropping backends that found no device
+pub fn parse_devices(output: &str) -> Vec<Printer> {
+    let mut printers: Vec<Printer> = Vec::new();
+    for block in output.split("Device: ").skip(1) {
+        let field = |key: &str| {
+            block.lines().find_map(|line| {
+                let (name, value) = line.split_once(" = ")?;
+                (name.trim() == key).then(|| value.trim())
+            })
+        };
+        let Some(uri) = field("uri") else {
+            continue;
+        };
+        // Backends without a device are listed by scheme alone, e.g. "socket"
+        let Some((backend, rest)) = uri.split_once("://") else {
+            continue;
+        };
+        let make_and_model = match field("make-and-model") {
+            Some(model) if !model.is_empty() && model != "Unknown" => model.to_string(),
+            _ => continue,
+        };
+
+        let languages: Vec<String> = field("device-id")
+            .unwrap_or_default()
+            .split(';')
+            .find_map(|entry| {
+                let (key, value) = entry.split_once(':')?;
+                matches!(key.trim(), "CMD" | "COMMAND SET").then_some(value)
+            })
+            .map(|value| {
+                value
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|v| !v.is_empty())
+                    .map(String::from)
+                    .collect()
+            })
+            .unwrap_or_default();
+        // DNS-SD lists IPP printers as "<name>._ipp._tcp.local" or "._ipps._tcp"
+        let ipp = matches!(backend, "ipp" | "ipps") || rest.contains("._ipp");
+        let driverless = ipp
+            || languages.iter().any(|language| DRIVERLESS_LANGUAGES.contains(&language.as_str()));
+        let connection = if backend == "usb" || rest.starts_with("/usb/") {
+            "usb"
+        } else {
+            match field("class") {
+                Some("network") => "network",
+                Some("serial") => "serial",
+                Some("direct") if backend == "parallel" => "parallel",
+                _ => "usb",
+            }
+        };
+
+        let printer = Printer {
+            make_and_model,
+            connection: connection.to_string(),
+            backend: backend.to_string(),
+            languages,
+            driverless,
+        };
+        // CUPS finds network printers once per discovery protocol
+        match printers.iter_mut().find(|known| {
+            known.make_and_model == printer.make_and_model && known.connection == printer.connection
+        }) {
+            Some(known) => {
+                known.driverless |= printer.driverless;
+                if known.languages.is_empty() {
+                    known.languages = printer.languages;
+                }
+            }
+            None => printers.push(printer),
+        }
+    }
+    printers
+}
+
+/// Detector listing printers with lpinfo
+pub struct LpinfoDetector;
+
+impl LpinfoDetector {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl Default for LpinfoDetector {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl HardwareDetector for LpinfoDetector {
+    fn name(&self) -> &'static str {
+        "lpinfo"
+    }
+
+    async fn is_available(&self) -> bool {
+        tokio::process::Command::new("which")
+            .arg("lpinfo")
+            .output()
+            .await
+            .map(|output| output.status.success())
+            .unwrap_or(false)
+    }
+
+    async fn execute(&self) -> Result<Output> {
+        // Network backends wait for replies up to the timeout
+        sandbox::output(tokio::process::Command::new("lpinfo").args([
+            "--timeout",
+            "10",
+            "-l",
+            "-v",
+        ]))
+        .await
+        .map_err(|e| LxHwError::SystemCommandError { command: format!("lpinfo: {}", e) })
+    }
+
+    fn timeout(&self) -> Duration {
+        Duration::from_secs(30)
+    }
+
+    fn parse_output(&self, output: &Output) -> Result<DetectionResult> {
+        if !output.status.success() {
+            // Also the case when the CUPS scheduler is not running
+            return Ok(DetectionResult {
+                tool_name: self.name().to_string(),
+                success: false,
+                data: DetectionData::Lpinfo(LpinfoData::default()),
+                errors: vec![DetectionError::BadExit {
+                    tool: self.name().to_string(),
+                    code: output.status.code(),
+                    stderr: String::from_utf8_lossy(&output.stderr).to_string(),
+                }],
+            });
+        }
+
+        let printers = parse_devices(&String::from_utf8_lossy(&output.stdout));
+        if printers.is_empty() {
+            return Ok(DetectionResult {
+                tool_name: self.name().to_string(),
+                success: false,
+                data: DetectionData::Lpinfo(LpinfoData::default()),
+                errors: vec![DetectionError::EmptyOutput { tool: self.name().to_string() }],
+            });
+        }
+
+        Ok(DetectionResult {
+            tool_name: self.name().to_string(),
+            success: true,
+            data: DetectionData::Lpinfo(LpinfoData { printers }),
+            errors: Vec::new(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LPINFO: &str = "Device: uri = socket
+        class = network
+        info = AppSocket/HP JetDirect
+        make-and-model = Unknown
+        device-id =
+        location =
+Device: uri = usb://HP/LaserJet%20Pro%20M404-M405?serial=PHBBK12345
+        class = direct
+        info = HP LaserJet Pro M404-M405
+        make-and-model = HP LaserJet Pro M404-M405
+        device-id = MFG:HP;MDL:LaserJet Pro M404-M405;CMD:PJL,PCL,PCLXL,PWG,URF;CLS:PRINTER;
+        location =
+Device: uri = dnssd://Brother%20HL-L2350DW%20series._ipp._tcp.local/?uuid=e3248000-80ce-11db-8000-3c2af4123456
+        class = network
+        info = Brother HL-L2350DW series
+        make-and-model = Brother HL-L2350DW series
+        device-id = MFG:Brother;MDL:HL-L2350DW series;CMD:PJL,PCL,PCLXL,URF;
+        location = Office
+Device: uri = lpd://192.168.1.23/BINARY_P1
+        class = network
+        info = Brother HL-L2350DW series
+        make-and-model = Brother HL-L2350DW series
+        device-id = MFG:Brother;MDL:HL-L2350DW series;
+        location =
+Device: uri = usb://Samsung/ML-1860%20Series?serial=Z5M6BKBB12345
+        class = direct
+        info = Samsung ML-1860 Series
+        make-and-model = Samsung ML-1860 Series
+        device-id = MFG:Samsung;CMD:SPL,FWV,PIC,BDN,EXT;MDL:ML-1860 Series;CLS:PRINTER;
+        location =
+";
+
+    #[test]
+    fn test_parse_devices() {
+        let printers = parse_devices(LPINFO);
+        assert_eq!(printers.len(), 3);
+        assert_eq!(
+            printers[0],
+            Printer {
+                make_and_model: "HP LaserJet Pro M404-M405".to_string(),
+                connection: "usb".to_string(),
+                backend: "usb".to_string(),
+                languages: ["PJL", "PCL", "PCLXL", "PWG", "URF"].map(String::from).to_vec(),
+                driverless: true,
+            }
+        );
+        let brother = &printers[1];
+        assert_eq!((brother.connection.as_str(), brother.backend.as_str()), ("network", "dnssd"));
+        assert!(brother.driverless);
+        let samsung = &printers[2];
+        assert!(!samsung.driverless);
+        assert_eq!(samsung.languages[0], "SPL");
+
+        let serialized = serde_json::to_string(&printers).unwrap();
+        assert!(!serialized.contains("PHBBK12345") && !serialized.contains("192.168.1.23"));
+    }
+}
@@ -0,0 +1,394 @@
+//! Partition layout and block device stacking from lsblk and blkid
+//!
+//! `lsblk -J -b` prints each drive with its partitions and the devices
+//! stacked on them: RAID arrays, LVM volumes and LUKS mappings. The tree is
+//! attached to the storage devices other tools found, so reports show which
+//! storage stacks a system runs on, such as ext4 on LVM on LUKS or a RAID 1
+//! of two drives.
+//!
+//! lsblk takes filesystem types and UUIDs from the udev database, which is
+//! missing in some containers and initramfs images; `blkid -o export` fills
+//! them in there. UUIDs are anonymized like serial numbers, and labels,
+//! device names and mount points, which users choose, are left out.
+
+use super::extract::{ComponentExtractor, ExtractionContext};
+use super::{sandbox, DetectionData, DetectionError, DetectionResult, HardwareDetector};
+use crate::errors::{LxHwError, Result};
+use crate::hardware::{BlockFilesystem, BlockLayer, StorageDevice, StorageTopology};
+use async_trait::async_trait;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::process::Output;
+
+const BLKID_MARKER: &str = "--- BLKID ---";
+
+/// Columns requested from lsblk; FSVER and newer ones are left out for older util-linux
+const COLUMNS: &str = "NAME,TYPE,SIZE,FSTYPE,UUID,PTTYPE,MODEL,SERIAL,TRAN,ROTA";
+
+/// Block devices read by lsblk
+#[derive(Debug, Clone, Default)]
+pub struct LsblkData {
+    /// Drives, with partitions and stacked devices as children
+    pub devices: Vec<BlockDevice>,
+}
+
+/// One node of the lsblk tree
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct BlockDevice {
+    pub name: String,
+    /// lsblk device type: "disk", "part", "raid1", "lvm", "crypt", "loop", ...
+    pub device_type: String,
+    pub size_bytes: u64,
+    pub fstype: Option<String>,
+    pub uuid: Option<String>,
+    /// Partition table type of a drive
+    pub pttype: Option<String>,
+    pub model: Option<String>,
+    pub serial: Option<String>,
+    /// Transport: "sata", "nvme", "usb", ...
+    pub transport: Option<String>,
+    pub rotational: Option<bool>,
+    pub children: Vec<BlockDevice>,
+}
+
+impl ComponentExtractor for LsblkData {
+    fn storage(&self, ctx: &mut ExtractionContext) -> Result<Vec<StorageDevice>> {
+        let mut storage_devices = Vec::new();
+        for disk in self.devices.iter().filter(|device| device.device_type == "disk") {
+            let anonymized_serial = match &disk.serial {
+                Some(serial) => ctx.anonymize(serial)?,
+                None => "unknown".to_string(),
+            };
+            let device_type = match (disk.transport.as_deref(), disk.rotational) {
+                (Some("nvme"), _) => "NVMe SSD",
+                (_, Some(false)) => "SSD",
+                _ => "HDD",
+            };
+            let interface = disk.transport.as_deref().map(|transport| match transport {
+                "sata" | "ata" => "SATA".to_string(),
+                "nvme" => "NVMe".to_string(),
+                other => other.to_uppercase(),
+            });
+            let children =
+                disk.children.iter().map(|child| layer(child, ctx)).collect::<Result<_>>()?;
+            storage_devices.push(StorageDevice {
+                anonymized_serial,
+                device_type: device_type.to_string(),
+                size_bytes: disk.size_bytes,
+                model: disk.model.clone().unwrap_or("Unknown Storage".to_string()),
+                vendor: None,
+                interface,
+                nvme: None,
+                health: None,
+                topology: Some(StorageTopology {
+                    partition_table: disk.pttype.clone(),
+                    filesystem: filesystem(disk, ctx)?,
+                    children,
+                }),
+            });
+        }
+        Ok(storage_devices)
+    }
+}
+
+/// Report layer for a partition or stacked device and everything on it
+fn layer(device: &BlockDevice, ctx: &mut ExtractionContext) -> Result<BlockLayer> {
+    Ok(BlockLayer {
+        layer_type: device.device_type.clone(),
+        size_bytes: device.size_bytes,
+        filesystem: filesystem(device, ctx)?,
+        children: device.children.iter().map(|child| layer(child, ctx)).collect::<Result<_>>()?,
+    })
+}
+
+fn filesystem(
+    device: &BlockDevice,
+    ctx: &mut ExtractionContext,
+) -> Result<Option<BlockFilesystem>> {
+    let Some(fs_type) = &device.fstype else {
+        return Ok(None);
+    };
+    Ok(Some(BlockFilesystem {
+        fs_type: fs_type.clone(),
+        anonymized_uuid: device.uuid.as_deref().map(|uuid| ctx.anonymize(uuid)).transpose()?,
+    }))
+}
+
+/// Parse `lsblk -J -b` output
+///
+/// Older util-linux prints sizes and flags as strings, so values are read
+/// leniently.
+pub fn parse_lsblk(json: &str) -> serde_json::Result<Vec<BlockDevice>> {
+    let data: Value = serde_json::from_str(json)?;
+    Ok(data
+        .get("blockdevices")
+        .and_then(Value::as_array)
+        .map(|devices| devices.iter().filter_map(parse_device).collect())
+        .unwrap_or_default())
+}
+
+fn parse_device(value: &Value) -> Option<BlockDevice> {
+    let text = |key: &str| {
+        value
+            .get(key)
+            .and_then(Value::as_str)
+            .map(|text| text.trim().to_string())
+            .filter(|text| !text.is_empty())
+    };
+    let size_bytes = match value.get("size") {
+        Some(Value::Number(size)) => size.as_u64(),
+        Some(Value::String(size)) => size.trim().parse().ok(),
+        _ => None,
+    };
+    let rotational = match value.get("rota") {
+        Some(Value::Bool(rota)) => Some(*rota),
+        Some(Value::String(rota)) => Some(rota.trim() == "1"),
+        _ => None,
+    };
+    Some(BlockDevice {
+        name: text("name")?,
+        device_type: text("type")?,
+        size_bytes: size_bytes.unwrap_or(0),
+        fstype: text("fstype"),
+        uuid: text("uuid"),
+        pttype: text("pttype"),
+        model: text("model"),
+        serial: text("serial"),
+        transport: text("tran"),
+        rotational,
+        children: value
+            .get("children")
+            .and_then(Value::as_array)
+            .map(|children| children.iter().filter_map(parse_device).collect())
+            .unwrap_or_default(),
+    })
+}
+
+/// Parse `blkid -o export` into device paths and their TYPE and UUID
+pub fn parse_blkid(text: &str) -> HashMap<String, (Option<String>, Option<String>)> {
+    let mut devices = HashMap::new();
+    for block in text.split("\n\n") {
+        let field = |key: &str| {
+            block.lines().find_map(|line| {
+                line.strip_prefix(key)?.strip_prefix('=').map(|value| value.trim().to_string())
+            })
+        };
+        if let Some(path) = field("DEVNAME") {
+            devices.insert(path, (field("TYPE"), field("UUID")));
+        }
+    }
+    devices
+}
+
+/// Fill filesystem types and UUIDs lsblk lacks from blkid
+fn fill_from_blkid(
+    devices: &mut [BlockDevice],
+    blkid: &HashMap<String, (Option<String>, Option<String>)>,
+) {
+    for device in devices {
+        let probed = [format!("/dev/{}", device.name), format!("/dev/mapper/{}", device.name)]
+            .iter()
+            .find_map(|path| blkid.get(path));
+        if let Some((fstype, uuid)) = probed {
+            if device.fstype.is_none() {
+                device.fstype = fstype.clone();
+            }
+            if device.uuid.is_none() {
+                device.uuid = uuid.clone();
+            }
+        }
+        fill_from_blkid(&mut device.children, blkid);
+    }
+}
+
+/// Detector reading the block device tree with lsblk
+pub struct LsblkDetector;
+
+impl LsblkDetector {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl Default for LsblkDetector {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl HardwareDetector for LsblkDetector {
+    fn name(&self) -> &'static str {
+        "lsblk"
+    }
+
+    async fn is_available(&self) -> bool {
+        tokio::process::Command::new("which")
+            .arg("lsblk")
+            .output()
+            .await
+            .map(|output| output.status.success())
+            .unwrap_or(false)
+    }
+
+    async fn execute(&self) -> Result<Output> {
+        let mut output = sandbox::output(
+            tokio::process::Command::new("lsblk").args(["-J", "-b", "-o", COLUMNS]),
+        )
+        .await
+        .map_err(|e| LxHwError::SystemCommandError { command: format!("lsblk: {}", e) })?;
+        if !output.status.success() {
+            return Ok(output);
+        }
+
+        // blkid is optional; without root it answers from its cache
+        match sandbox::output(tokio::process::Command::new("blkid").args(["-o", "export"])).await {
+            Ok(blkid) if !blkid.stdout.is_empty() => {
+                output.stdout.extend_from_slice(format!("\n{}\n", BLKID_MARKER).as_bytes());
+                output.stdout.extend_from_slice(&blkid.stdout);
+            }
+            Ok(_) => {}
+            Err(e) => log::debug!("blkid failed: {}", e),
+        }
+        Ok(output)
+    }
+
+    fn parse_output(&self, output: &Output) -> Result<DetectionResult> {
+        if !output.status.success() {
+            return Ok(DetectionResult {
+                tool_name: self.name().to_string(),
+                success: false,
+                data: DetectionData::Lsblk(LsblkData::default()),
+                errors: vec![DetectionError::BadExit {
+                    tool: self.name().to_string(),
+                    code: output.status.code(),
+                    stderr: String::from_utf8_lossy(&output.stderr).to_string(),
+                }],
+            });
+        }
+
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let separator = format!("\n{}\n", BLKID_MARKER);
+        let (lsblk, blkid) = stdout.split_once(separator.as_str()).unwrap_or((&stdout, ""));
+        let mut devices = match parse_lsblk(lsblk) {
+            Ok(devices) => devices,
+            Err(e) => {
+                return Ok(DetectionResult {
+                    tool_name: self.name().to_string(),
+                    success: false,
+                    data: DetectionData::Lsblk(LsblkData::default()),
+                    errors: vec![DetectionError::json("JSON parsing failed", &e)],
+                })
+            }
+        };
+        fill_from_blkid(&mut devices, &parse_blkid(blkid));
+
+        Ok(DetectionResult {
+            tool_name: self.name().to_string(),
+            success: true,
+            data: DetectionData::Lsblk(LsblkData { devices }),
+            errors: Vec::new(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hardware::taxonomy::Taxonomy;
+    use crate::hardware::PrivacyLevel;
+    use crate::privacy::PrivacyManager;
+    use std::os::unix::process::ExitStatusExt;
+
+    /// A laptop drive with LVM on LUKS, and two disks in a RAID 1
+    const LSBLK: &str = r#"{
+       "blockdevices": [
+          {"name":"nvme0n1", "type":"disk", "size":512110190592, "fstype":null, "uuid":null, "pttype":"gpt", "model":"WD PC SN810 512GB", "serial":"21433D801234", "tran":"nvme", "rota":false,
+             "children": [
+                {"name":"nvme0n1p1", "type":"part", "size":536870912, "fstype":"vfat", "uuid":"6A1B-2C3D", "pttype":"gpt", "model":null, "serial":null, "tran":"nvme", "rota":false},
+                {"name":"nvme0n1p2", "type":"part", "size":511571509248, "fstype":"crypto_LUKS", "uuid":"0b9a3c52-4f1e-4d2b-9a8e-5c7d6e1f2a3b", "pttype":"gpt", "model":null, "serial":null, "tran":"nvme", "rota":false,
+                   "children": [
+                      {"name":"luks-0b9a3c52", "type":"crypt", "size":511554732032, "fstype":"LVM2_member", "uuid":"Xk3f2a-aBcD-eFgH-iJkL-mNoP-qRsT-uVwX12", "pttype":null, "model":null, "serial":null, "tran":null, "rota":false,
+                         "children": [
+                            {"name":"vg-root", "type":"lvm", "size":107374182400, "fstype":"ext4", "uuid":"7d1e9f40-3b2a-4c5d-8e6f-7a8b9c0d1e2f", "pttype":null, "model":null, "serial":null, "tran":null, "rota":false}
+                         ]
+                      }
+                   ]
+                }
+             ]
+          },
+          {"name":"sda", "type":"disk", "size":"2000398934016", "fstype":"linux_raid_member", "uuid":"c3a1b2d4-e5f6-7a8b-9c0d-1e2f3a4b5c6d", "pttype":null, "model":"ST2000DM008-2FR102", "serial":"ZFL1ABCD", "tran":"sata", "rota":"1",
+             "children": [
+                {"name":"md0", "type":"raid1", "size":"2000264691712", "fstype":"xfs", "uuid":null, "pttype":null, "model":null, "serial":null, "tran":null, "rota":"1"}
+             ]
+          },
+          {"name":"loop0", "type":"loop", "size":4096, "fstype":"squashfs", "uuid":null, "pttype":null, "model":null, "serial":null, "tran":null, "rota":false}
+       ]
+    }"#;
+
+    const BLKID: &str = "DEVNAME=/dev/md0\nLABEL=backup\nUUID=e2b7c9a4-1d3f-4e5a-8b6c-9d0e1f2a3b4c\nBLOCK_SIZE=512\nTYPE=xfs\n\nDEVNAME=/dev/sda\nUUID=c3a1b2d4-e5f6-7a8b-9c0d-1e2f3a4b5c6d\nTYPE=linux_raid_member\n";
+
+    fn output(stdout: String) -> Output {
+        Output {
+            status: ExitStatusExt::from_raw(0),
+            stdout: stdout.into_bytes(),
+            stderr: Vec::new(),
+        }
+    }
+
+    fn parse(stdout: String) -> LsblkData {
+        match LsblkDetector::new().parse_output(&output(stdout)).unwrap().data {
+            DetectionData::Lsblk(data) => data,
+            other => panic!("Unexpected data: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_parse_stacked_devices() {
+        let data = parse(format!("{}\n{}\n{}", LSBLK, BLKID_MARKER, BLKID));
+        assert_eq!(data.devices.len(), 3);
+        let sda = &data.devices[1];
+        assert_eq!(sda.size_bytes, 2_000_398_934_016);
+        assert_eq!(sda.rotational, Some(true));
+        // blkid fills in what lsblk lacked, and keeps what it had
+        let md0 = &sda.children[0];
+        assert_eq!(md0.fstype.as_deref(), Some("xfs"));
+        assert_eq!(md0.uuid.as_deref(), Some("e2b7c9a4-1d3f-4e5a-8b6c-9d0e1f2a3b4c"));
+        assert_eq!(sda.uuid.as_deref(), Some("c3a1b2d4-e5f6-7a8b-9c0d-1e2f3a4b5c6d"));
+
+        let broken = LsblkDetector::new().parse_output(&output("lsblk: unknown".into())).unwrap();
+        assert!(!broken.success);
+    }
+
+    #[test]
+    fn test_storage_topology() {
+        let data = parse(LSBLK.to_string());
+        let taxonomy = Taxonomy::builtin();
+        let mut manager = PrivacyManager::with_salt(PrivacyLevel::Basic, b"salt");
+        let mut ctx = ExtractionContext::new(&taxonomy, &mut manager);
+        let storage = data.storage(&mut ctx).unwrap();
+
+        // Loop devices are not drives
+        assert_eq!(storage.len(), 2);
+        assert_eq!(storage[0].device_type, "NVMe SSD");
+        assert_eq!(storage[1].interface.as_deref(), Some("SATA"));
+        let topology = storage[0].topology.as_ref().unwrap();
+        assert_eq!(topology.partition_table.as_deref(), Some("gpt"));
+        let luks = &topology.children[1];
+        let types = |layer: &BlockLayer| {
+            (layer.layer_type.clone(), layer.filesystem.as_ref().map(|fs| fs.fs_type.clone()))
+        };
+        assert_eq!(types(luks), ("part".to_string(), Some("crypto_LUKS".to_string())));
+        assert_eq!(types(&luks.children[0]), ("crypt".to_string(), Some("LVM2_member".into())));
+        assert_eq!(types(&luks.children[0].children[0]), ("lvm".to_string(), Some("ext4".into())));
+
+        let uuid = luks.filesystem.as_ref().unwrap().anonymized_uuid.as_deref().unwrap();
+        assert!(!uuid.contains("0b9a3c52"));
+        let json = serde_json::to_string(&storage).unwrap();
+        assert!(!json.contains("luks-0b9a3c52") && !json.contains("vg-root"));
+
+        let raid = storage[1].topology.as_ref().unwrap();
+        assert_eq!(raid.filesystem.as_ref().unwrap().fs_type, "linux_raid_member");
+        assert_eq!(raid.children[0].layer_type, "raid1");
+    }
+}
@@ -1,6 +1,8 @@
 //! lshw hardware detection implementation
 
-use super::{DetectionData, DetectionResult, HardwareDetector};
+use super::{
+    sandbox, DetectionData, DetectionError, DetectionResult, HardwareDetector, Privileges,
+};
 use crate::errors::{LxHwError, Result};
 use async_trait::async_trait;
 use log::{debug, error, warn};
@@ -108,17 +110,16 @@ impl HardwareDetector for LshwDetector {
     async fn execute(&self) -> Result<Output> {
         debug!("Executing lshw hardware detection");
 
-        let output = tokio::process::Command::new("lshw")
-            .arg("-json") // Request JSON output
-            .arg("-quiet") // Suppress header information
-            .arg("-sanitize") // Remove sensitive information like serials by default
-            .arg("-class")
-            .arg("system,memory,processor,bridge,network,storage,multimedia,display")
-            .output()
-            .await
-            .map_err(|e| LxHwError::SystemCommandError {
-                command: format!("lshw: {}", e)
-            })?;
+        let output = sandbox::output(
+            tokio::process::Command::new("lshw")
+                .arg("-json") // Request JSON output
+                .arg("-quiet") // Suppress header information
+                .arg("-sanitize") // Remove sensitive information like serials by default
+                .arg("-class")
+                .arg("system,memory,processor,bridge,network,storage,multimedia,display"),
+        )
+        .await
+        .map_err(|e| LxHwError::SystemCommandError { command: format!("lshw: {}", e) })?;
 
         debug!("lshw execution completed with status: {}", output.status);
 
@@ -143,7 +144,7 @@ impl HardwareDetector for LshwDetector {
             for line in stderr_str.lines() {
                 if !line.trim().is_empty() {
                     warn!("lshw stderr: {}", line);
-                    errors.push(format!("Warning: {}", line));
+                    errors.push(DetectionError::from_stderr_line(line));
                 }
             }
         }
@@ -154,15 +155,17 @@ impl HardwareDetector for LshwDetector {
                 tool_name: self.name().to_string(),
                 success: false,
                 data: DetectionData::Lshw(LshwData::default()),
-                errors: vec!["Empty output from lshw".to_string()],
+                errors: vec![DetectionError::EmptyOutput { tool: self.name().to_string() }],
             });
         }
 
         let stdout_str = String::from_utf8_lossy(&output.stdout);
         debug!("Parsing lshw JSON output ({} bytes)", stdout_str.len());
 
-        match self.parse_json(&stdout_str) {
-            Ok(lshw_data) => {
+        match serde_json::from_str::<Vec<LshwComponent>>(&stdout_str) {
+            Ok(components) => {
+                let summary = self.generate_summary(&components);
+                let lshw_data = LshwData { components, summary: Some(summary) };
                 debug!("Successfully parsed {} components from lshw", lshw_data.components.len());
                 Ok(DetectionResult {
                     tool_name: self.name().to_string(),
@@ -173,7 +176,7 @@ impl HardwareDetector for LshwDetector {
             }
             Err(e) => {
                 error!("Failed to parse lshw JSON: {}", e);
-                errors.push(format!("JSON parsing failed: {}", e));
+                errors.push(DetectionError::json("JSON parsing failed", &e));
                 Ok(DetectionResult {
                     tool_name: self.name().to_string(),
                     success: false,
@@ -187,22 +190,14 @@ impl HardwareDetector for LshwDetector {
     fn timeout(&self) -> Duration {
         Duration::from_secs(30)
     }
-}
-
-impl LshwDetector {
-    /// Parse lshw JSON output into structured data
-    fn parse_json(&self, json_str: &str) -> Result<LshwData> {
-        // Parse the JSON array of components
-        let components: Vec<LshwComponent> = serde_json::from_str(json_str).map_err(|e| {
-            LxHwError::SerializationError(format!("lshw JSON parsing failed: {}", e))
-        })?;
 
-        // Generate summary statistics
-        let summary = self.generate_summary(&components);
-
-        Ok(LshwData { components, summary: Some(summary) })
+    fn privileges(&self) -> Privileges {
+        // Disk, memory and firmware details need root
+        Privileges::RootRecommended
     }
+}
 
+impl LshwDetector {
     /// Generate summary statistics from components
     fn generate_summary(&self, components: &[LshwComponent]) -> LshwSummary {
         let mut components_by_class = HashMap::new();
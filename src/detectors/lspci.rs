@@ -1,7 +1,10 @@
 //! lspci hardware detection implementation
 
-use super::{DetectionData, DetectionResult, HardwareDetector};
+use super::{
+    sandbox, DetectionData, DetectionError, DetectionResult, HardwareDetector, Privileges,
+};
 use crate::errors::{LxHwError, Result};
+use crate::hardware::pci_class;
 use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
@@ -311,6 +314,11 @@ impl LspciDetector {
                 numeric_data.get(&device.address)
             {
                 device.class_code = class_code.clone();
+                // lspci prints "Class 0880" when its pci.ids lacks the class
+                let unnamed = device.class_description.starts_with("Class ");
+                if let Some(name) = pci_class::describe(class_code).filter(|_| unnamed) {
+                    device.class_description = name;
+                }
                 device.vendor_id = vendor_id.clone();
                 device.device_id = device_id.clone();
                 device.revision = revision.clone();
@@ -336,17 +344,17 @@ impl HardwareDetector for LspciDetector {
 
     async fn execute(&self) -> Result<Output> {
         // Execute both verbose and numeric commands and combine results
-        let verbose_future = tokio::process::Command::new("lspci")
+        let mut verbose = tokio::process::Command::new("lspci");
+        verbose
             .arg("-v")  // verbose output
-            .arg("-k")  // show kernel drivers
-            .output();
+            .arg("-k"); // show kernel drivers
 
-        let numeric_future = tokio::process::Command::new("lspci")
-            .arg("-n")  // numeric IDs
-            .output();
+        let mut numeric = tokio::process::Command::new("lspci");
+        numeric.arg("-n"); // numeric IDs
 
-        let (verbose_result, numeric_result) = tokio::try_join!(verbose_future, numeric_future)
-            .map_err(|e| LxHwError::SystemCommandError { command: format!("lspci: {}", e) })?;
+        let (verbose_result, numeric_result) =
+            tokio::try_join!(sandbox::output(&mut verbose), sandbox::output(&mut numeric))
+                .map_err(|e| LxHwError::SystemCommandError { command: format!("lspci: {}", e) })?;
 
         // Create combined output - we'll put numeric data in stderr for parsing
         let mut combined_stdout = verbose_result.stdout;
@@ -370,18 +378,26 @@ impl HardwareDetector for LspciDetector {
         Duration::from_secs(15)
     }
 
+    fn privileges(&self) -> Privileges {
+        // Extended configuration space, with capabilities and link state, needs root
+        Privileges::RootRecommended
+    }
+
     fn parse_output(&self, output: &Output) -> Result<DetectionResult> {
         let mut errors = Vec::new();
         let mut warnings = Vec::new();
 
         // Check for execution errors
         if !output.status.success() {
-            let error_msg = String::from_utf8_lossy(&output.stderr);
             return Ok(DetectionResult {
                 tool_name: self.name().to_string(),
                 success: false,
                 data: DetectionData::Lspci(LspciData::default()),
-                errors: vec![format!("lspci execution failed: {}", error_msg)],
+                errors: vec![DetectionError::BadExit {
+                    tool: self.name().to_string(),
+                    code: output.status.code(),
+                    stderr: String::from_utf8_lossy(&output.stderr).to_string(),
+                }],
             });
         }
 
@@ -391,7 +407,7 @@ impl HardwareDetector for LspciDetector {
                 tool_name: self.name().to_string(),
                 success: false,
                 data: DetectionData::Lspci(LspciData::default()),
-                errors: vec!["Empty output from lspci".to_string()],
+                errors: vec![DetectionError::EmptyOutput { tool: self.name().to_string() }],
             });
         }
 
@@ -417,7 +433,10 @@ impl HardwareDetector for LspciDetector {
                     tool_name: self.name().to_string(),
                     success: false,
                     data: DetectionData::Lspci(LspciData::default()),
-                    errors: vec![format!("Failed to parse lspci verbose output: {}", e)],
+                    errors: vec![DetectionError::parse(format!(
+                        "Failed to parse lspci verbose output: {}",
+                        e
+                    ))],
                 });
             }
         };
@@ -440,7 +459,7 @@ impl HardwareDetector for LspciDetector {
             !warnings.iter().any(|w| w.contains("access denied") || w.contains("permission"));
 
         let summary = self.generate_summary(&devices, privileged, warnings.clone());
-        errors.extend(warnings);
+        errors.extend(warnings.iter().map(|w| DetectionError::from_stderr_line(w)));
 
         let data = LspciData { devices, summary: Some(summary) };
 
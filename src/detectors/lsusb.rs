@@ -1,13 +1,35 @@
 //! lsusb hardware detection implementation
 
-use super::{DetectionData, DetectionResult, HardwareDetector};
+use super::udev::SYSFS_USB_DEVICES;
+use super::{
+    remote, sandbox, DetectionData, DetectionError, DetectionResult, HardwareDetector, RetryPolicy,
+};
 use crate::errors::{LxHwError, Result};
 use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::path::Path;
 use std::process::Output;
 use std::time::Duration;
 
+pub use super::udev::usb_class_name;
+
+/// Marker separating sysfs device attributes from lsusb output
+const SYSFS_MARKER: &str = "--- SYSFS DATA ---";
+
+/// sysfs attributes captured per device
+const SYSFS_ATTRIBUTES: &[&str] = &[
+    "busnum",
+    "devnum",
+    "bDeviceClass",
+    "bDeviceSubClass",
+    "bDeviceProtocol",
+    "speed",
+    "bMaxPower",
+    "version",
+    "serial",
+];
+
 /// Complete USB device information from lsusb
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct LsusbData {
@@ -43,6 +65,8 @@ pub struct UsbDevice {
     pub max_packet_size: Option<u16>,
     /// Serial number
     pub serial_number: Option<String>,
+    /// Position in the USB tree as bus and port chain (e.g., "3-1.2")
+    pub port_path: Option<String>,
     /// Manufacturer string
     pub manufacturer: Option<String>,
     /// Device speed (e.g., "480Mbps", "5Gbps")
@@ -198,6 +222,7 @@ impl LsusbDetector {
             device_protocol: None,
             max_packet_size: None,
             serial_number: None,
+            port_path: None,
             manufacturer: None,
             speed: None,
             max_power: None,
@@ -307,6 +332,53 @@ impl LsusbDetector {
         Ok(None)
     }
 
+    /// Fill class, speed, power, topology and serial from sysfs attributes
+    ///
+    /// The section holds one block of `key=value` lines per device, separated
+    /// by blank lines, as written by [`read_sysfs_attributes`]. Devices are
+    /// matched on bus and device number.
+    pub fn apply_sysfs_attributes(&self, devices: &mut [UsbDevice], section: &str) {
+        for block in section.split("\n\n") {
+            let attributes: HashMap<&str, &str> = block
+                .lines()
+                .filter_map(|line| line.split_once('='))
+                .map(|(key, value)| (key.trim(), value.trim()))
+                .collect();
+
+            let number = |key: &str| attributes.get(key).and_then(|v| v.parse::<u8>().ok());
+            let (Some(bus), Some(devnum)) = (number("busnum"), number("devnum")) else {
+                continue;
+            };
+            let Some(device) = devices.iter_mut().find(|d| d.bus == bus && d.device == devnum)
+            else {
+                continue;
+            };
+            let text =
+                |key: &str| attributes.get(key).filter(|v| !v.is_empty()).map(|v| v.to_string());
+
+            // Class 00 defers to the interfaces; use the first interface's class
+            let class = text("bDeviceClass")
+                .filter(|class| class != "00")
+                .or_else(|| text("bInterfaceClass"));
+            // Attributes sysfs lacks keep what lsusb reported
+            device.device_class = class
+                .and_then(|class| u8::from_str_radix(&class, 16).ok())
+                .map(|class| usb_class_name(class).to_string())
+                .or(device.device_class.take());
+            device.device_subclass = text("bDeviceSubClass").or(device.device_subclass.take());
+            device.device_protocol = text("bDeviceProtocol").or(device.device_protocol.take());
+            device.speed =
+                text("speed").and_then(|speed| format_usb_speed(&speed)).or(device.speed.take());
+            device.max_power = attributes
+                .get("bMaxPower")
+                .and_then(|power| power.trim_end_matches("mA").parse::<u16>().ok())
+                .or(device.max_power);
+            device.usb_version = text("version").or(device.usb_version.take());
+            device.serial_number = text("serial").or(device.serial_number.take());
+            device.port_path = text("device").or(device.port_path.take());
+        }
+    }
+
     /// Generate summary statistics
     fn generate_summary(
         &self,
@@ -345,6 +417,20 @@ impl LsusbDetector {
     }
 }
 
+/// Format a sysfs speed in Mbit/s the way lsusb does (e.g. "480Mbps", "5Gbps")
+fn format_usb_speed(mbps: &str) -> Option<String> {
+    let mbps: f64 = mbps.parse().ok()?;
+    Some(if mbps >= 1000.0 { format!("{}Gbps", mbps / 1000.0) } else { format!("{}Mbps", mbps) })
+}
+
+/// Speed in Mbit/s of a device speed such as "480Mbps" or "5Gbps"
+pub fn parse_usb_speed(speed: &str) -> Option<f64> {
+    if let Some(gbps) = speed.strip_suffix("Gbps") {
+        return gbps.parse::<f64>().ok().map(|gbps| gbps * 1000.0);
+    }
+    speed.trim_end_matches("Mbps").parse().ok()
+}
+
 #[async_trait]
 impl HardwareDetector for LsusbDetector {
     fn name(&self) -> &'static str {
@@ -362,17 +448,30 @@ impl HardwareDetector for LsusbDetector {
 
     async fn execute(&self) -> Result<Output> {
         // Execute both device listing and topology commands
-        let device_future = tokio::process::Command::new("lsusb").output();
-        let topology_future = tokio::process::Command::new("lsusb").arg("-t").output();
+        let mut devices = tokio::process::Command::new("lsusb");
+        let mut topology = tokio::process::Command::new("lsusb");
+        topology.arg("-t");
 
-        let (device_result, topology_result) = tokio::try_join!(device_future, topology_future)
-            .map_err(|e| LxHwError::SystemCommandError { command: format!("lsusb: {}", e) })?;
+        let (device_result, topology_result) =
+            tokio::try_join!(sandbox::output(&mut devices), sandbox::output(&mut topology))
+                .map_err(|e| LxHwError::SystemCommandError { command: format!("lsusb: {}", e) })?;
 
         // Create combined output - topology data goes in a separate section
         let mut combined_stdout = device_result.stdout;
         combined_stdout.extend_from_slice(b"\n--- TOPOLOGY DATA ---\n");
         combined_stdout.extend_from_slice(&topology_result.stdout);
 
+        // Class, speed and power come from sysfs, which needs no privileges
+        let sysfs = if remote::is_local() {
+            read_sysfs_attributes(Path::new(SYSFS_USB_DEVICES))
+        } else {
+            String::new()
+        };
+        if !sysfs.is_empty() {
+            combined_stdout.extend_from_slice(format!("\n{}\n", SYSFS_MARKER).as_bytes());
+            combined_stdout.extend_from_slice(sysfs.as_bytes());
+        }
+
         let mut combined_stderr = device_result.stderr;
         if !topology_result.stderr.is_empty() {
             combined_stderr.extend_from_slice(b"\nTopology command stderr:\n");
@@ -390,18 +489,26 @@ impl HardwareDetector for LsusbDetector {
         Duration::from_secs(10)
     }
 
+    fn retry_policy(&self) -> RetryPolicy {
+        // Devices that are still enumerating can make lsusb fail
+        RetryPolicy::flaky()
+    }
+
     fn parse_output(&self, output: &Output) -> Result<DetectionResult> {
         let mut errors = Vec::new();
         let mut warnings = Vec::new();
 
         // Check for execution errors
         if !output.status.success() {
-            let error_msg = String::from_utf8_lossy(&output.stderr);
             return Ok(DetectionResult {
                 tool_name: self.name().to_string(),
                 success: false,
                 data: DetectionData::Lsusb(LsusbData::default()),
-                errors: vec![format!("lsusb execution failed: {}", error_msg)],
+                errors: vec![DetectionError::BadExit {
+                    tool: self.name().to_string(),
+                    code: output.status.code(),
+                    stderr: String::from_utf8_lossy(&output.stderr).to_string(),
+                }],
             });
         }
 
@@ -411,7 +518,7 @@ impl HardwareDetector for LsusbDetector {
                 tool_name: self.name().to_string(),
                 success: false,
                 data: DetectionData::Lsusb(LsusbData::default()),
-                errors: vec!["Empty output from lsusb".to_string()],
+                errors: vec![DetectionError::EmptyOutput { tool: self.name().to_string() }],
             });
         }
 
@@ -425,23 +532,34 @@ impl HardwareDetector for LsusbDetector {
             }
         }
 
-        // Parse combined output (device list + topology)
-        let stdout_str = String::from_utf8_lossy(&output.stdout);
+        // Parse combined output (device list + topology + sysfs attributes)
+        let combined = String::from_utf8_lossy(&output.stdout);
+        let (stdout_str, sysfs_section) = match combined.split_once(SYSFS_MARKER) {
+            Some((stdout_str, sysfs)) => (stdout_str, Some(sysfs)),
+            None => (combined.as_ref(), None),
+        };
         let parts: Vec<&str> = stdout_str.splitn(2, "--- TOPOLOGY DATA ---").collect();
 
         // Parse device list
-        let devices = match self.parse_device_list(parts[0]) {
+        let mut devices = match self.parse_device_list(parts[0]) {
             Ok(devices) => devices,
             Err(e) => {
                 return Ok(DetectionResult {
                     tool_name: self.name().to_string(),
                     success: false,
                     data: DetectionData::Lsusb(LsusbData::default()),
-                    errors: vec![format!("Failed to parse lsusb device list: {}", e)],
+                    errors: vec![DetectionError::parse(format!(
+                        "Failed to parse lsusb device list: {}",
+                        e
+                    ))],
                 });
             }
         };
 
+        if let Some(section) = sysfs_section {
+            self.apply_sysfs_attributes(&mut devices, section);
+        }
+
         // Parse topology data if available
         let bus_topology = if parts.len() > 1 {
             match self.parse_topology(parts[1].trim()) {
@@ -460,7 +578,7 @@ impl HardwareDetector for LsusbDetector {
             !warnings.iter().any(|w| w.contains("permission") || w.contains("access denied"));
 
         let summary = self.generate_summary(&devices, &bus_topology, privileged, warnings.clone());
-        errors.extend(warnings);
+        errors.extend(warnings.iter().map(|w| DetectionError::from_stderr_line(w)));
 
         let data = LsusbData { devices, bus_topology, summary: Some(summary) };
 
@@ -472,3 +590,36 @@ impl HardwareDetector for LsusbDetector {
         })
     }
 }
+
+/// Read USB device attributes from sysfs as `key=value` blocks
+///
+/// Interface entries (`1-2:1.0`) are skipped, except that the first
+/// interface's class is recorded for devices that declare class 00.
+pub fn read_sysfs_attributes(root: &Path) -> String {
+    let Ok(entries) = std::fs::read_dir(root) else {
+        return String::new();
+    };
+    let mut names: Vec<String> = entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.file_name().to_string_lossy().into_owned())
+        .filter(|name| !name.contains(':'))
+        .collect();
+    names.sort();
+
+    let read = |path: &Path| std::fs::read_to_string(path).ok().map(|v| v.trim().to_string());
+    let mut blocks = Vec::new();
+    for name in names {
+        let dir = root.join(&name);
+        let mut block = format!("device={}\n", name);
+        for attribute in SYSFS_ATTRIBUTES {
+            if let Some(value) = read(&dir.join(attribute)) {
+                block.push_str(&format!("{}={}\n", attribute, value));
+            }
+        }
+        if let Some(class) = read(&dir.join(format!("{}:1.0", name)).join("bInterfaceClass")) {
+            block.push_str(&format!("bInterfaceClass={}\n", class));
+        }
+        blocks.push(block);
+    }
+    blocks.join("\n")
+}
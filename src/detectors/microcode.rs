@@ -0,0 +1,405 @@
+//! CPU microcode revision detection
+//!
+//! Reads the running microcode revision from `/proc/cpuinfo` and compares it
+//! against the newest revision for the same CPU in the installed Intel or AMD
+//! microcode files and the published revisions in
+//! `data/microcode-revisions.toml`. Microcode shipped only inside an early
+//! initramfs image is not inspected, so such systems compare against the
+//! published revisions alone.
+
+use crate::errors::{LxHwError, Result};
+use crate::hardware::{MicrocodeInfo, MicrocodeStatus};
+use serde::Deserialize;
+use std::path::Path;
+
+/// Built-in published revisions, compiled into the binary
+const REVISION_DATA: &str = include_str!("../../data/microcode-revisions.toml");
+
+/// Directories searched for microcode files
+const FIRMWARE_DIRS: &[&str] =
+    &["/lib/firmware", "/usr/lib/firmware", "/run/current-system/firmware"];
+
+/// Magic number at the start of an AMD microcode container ("DMA\0")
+const AMD_CONTAINER_MAGIC: u32 = 0x0041_4d44;
+
+/// CPU vendors with a known microcode file format
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MicrocodeVendor {
+    Intel,
+    Amd,
+}
+
+/// Identity and running microcode revision of the boot CPU
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CpuMicrocode {
+    pub vendor: Option<MicrocodeVendor>,
+    /// Family and model as displayed by `/proc/cpuinfo`, extended bits included
+    pub family: u32,
+    pub model: u32,
+    pub stepping: u32,
+    pub revision: u32,
+}
+
+impl CpuMicrocode {
+    /// Parse the first processor block of `/proc/cpuinfo`
+    ///
+    /// Returns `None` when the kernel does not report a microcode revision,
+    /// as on non-x86 systems.
+    pub fn from_cpuinfo(content: &str) -> Option<Self> {
+        let mut vendor = None;
+        let (mut family, mut model, mut stepping, mut revision) = (None, None, None, None);
+
+        for line in content.lines() {
+            if line.trim().is_empty() {
+                break;
+            }
+            let Some((key, value)) = line.split_once(':') else {
+                continue;
+            };
+            let value = value.trim();
+            match key.trim() {
+                "vendor_id" => {
+                    vendor = match value {
+                        "GenuineIntel" => Some(MicrocodeVendor::Intel),
+                        "AuthenticAMD" => Some(MicrocodeVendor::Amd),
+                        _ => None,
+                    }
+                }
+                "cpu family" => family = value.parse().ok(),
+                "model" => model = value.parse().ok(),
+                "stepping" => stepping = value.parse().ok(),
+                "microcode" => revision = parse_revision(value),
+                _ => {}
+            }
+        }
+
+        Some(Self {
+            vendor,
+            family: family?,
+            model: model?,
+            stepping: stepping?,
+            revision: revision?,
+        })
+    }
+
+    /// Family-model-stepping in hex, as used in Intel microcode file names
+    pub fn signature(&self) -> String {
+        format!("{:02x}-{:02x}-{:02x}", self.family, self.model, self.stepping)
+    }
+
+    /// Processor signature as returned in EAX by CPUID leaf 1
+    pub fn cpuid(&self) -> u32 {
+        let (base_family, extended_family) =
+            if self.family >= 0xf { (0xf, self.family - 0xf) } else { (self.family, 0) };
+        (self.stepping & 0xf)
+            | (self.model & 0xf) << 4
+            | base_family << 8
+            | (self.model >> 4 & 0xf) << 16
+            | (extended_family & 0xff) << 20
+    }
+
+    /// Microcode file for this CPU, relative to a firmware directory
+    fn firmware_file(&self) -> Option<String> {
+        match self.vendor? {
+            MicrocodeVendor::Intel => Some(format!("intel-ucode/{}", self.signature())),
+            MicrocodeVendor::Amd if self.family >= 0x15 => {
+                Some(format!("amd-ucode/microcode_amd_fam{:02x}h.bin", self.family))
+            }
+            MicrocodeVendor::Amd => Some("amd-ucode/microcode_amd.bin".to_string()),
+        }
+    }
+
+    /// Newest revision for this CPU in a microcode file
+    pub fn revision_in_file(&self, data: &[u8]) -> Option<u32> {
+        match self.vendor? {
+            MicrocodeVendor::Intel => intel_file_revision(data, self.cpuid()),
+            MicrocodeVendor::Amd => amd_file_revision(data, self.cpuid()),
+        }
+    }
+}
+
+/// Published revision for one CPU signature
+#[derive(Debug, Clone, Deserialize)]
+pub struct KnownRevision {
+    pub vendor: MicrocodeVendor,
+    pub signature: String,
+    pub name: String,
+    revision: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct RevisionFile {
+    #[serde(default)]
+    cpu: Vec<KnownRevision>,
+}
+
+/// Lookup table of the newest published microcode revisions
+#[derive(Debug)]
+pub struct MicrocodeDatabase {
+    revisions: Vec<(KnownRevision, u32)>,
+}
+
+impl MicrocodeDatabase {
+    /// Database from `data/microcode-revisions.toml`
+    pub fn builtin() -> Self {
+        Self::parse(REVISION_DATA).expect("built-in microcode data must be valid")
+    }
+
+    /// Parse published revisions from TOML
+    pub fn parse(content: &str) -> Result<Self> {
+        let file: RevisionFile = toml::from_str(content)
+            .map_err(|e| LxHwError::ConfigError(format!("Invalid microcode data: {}", e)))?;
+
+        let revisions = file
+            .cpu
+            .into_iter()
+            .map(|known| {
+                let revision = parse_revision(&known.revision).ok_or_else(|| {
+                    LxHwError::ConfigError(format!(
+                        "Invalid microcode revision for {}: {}",
+                        known.name, known.revision
+                    ))
+                })?;
+                Ok((known, revision))
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(Self { revisions })
+    }
+
+    /// Newest published revision for a CPU
+    pub fn latest(&self, cpu: &CpuMicrocode) -> Option<u32> {
+        let vendor = cpu.vendor?;
+        let signature = cpu.signature();
+        self.revisions
+            .iter()
+            .find(|(known, _)| known.vendor == vendor && known.signature == signature)
+            .map(|(_, revision)| *revision)
+    }
+
+    /// Compare a CPU's running revision with installed and published ones
+    pub fn evaluate(&self, cpu: &CpuMicrocode, installed_revision: Option<u32>) -> MicrocodeInfo {
+        let latest_known_revision = self.latest(cpu);
+        MicrocodeInfo {
+            signature: cpu.signature(),
+            revision: cpu.revision,
+            installed_revision,
+            latest_known_revision,
+            status: microcode_status(cpu.revision, installed_revision, latest_known_revision),
+        }
+    }
+
+    /// Microcode status of the running system
+    pub fn detect(&self) -> Option<MicrocodeInfo> {
+        let cpu = CpuMicrocode::from_cpuinfo(&std::fs::read_to_string("/proc/cpuinfo").ok()?)?;
+        let installed = cpu.firmware_file().and_then(|file| {
+            FIRMWARE_DIRS
+                .iter()
+                .filter_map(|dir| std::fs::read(Path::new(dir).join(&file)).ok())
+                .filter_map(|data| cpu.revision_in_file(&data))
+                .max()
+        });
+        Some(self.evaluate(&cpu, installed))
+    }
+}
+
+/// Status of a running revision given the newest installed and published ones
+pub fn microcode_status(
+    running: u32,
+    installed: Option<u32>,
+    latest_known: Option<u32>,
+) -> MicrocodeStatus {
+    if installed.is_some_and(|installed| installed > running) {
+        MicrocodeStatus::RebootRequired
+    } else if latest_known.is_some_and(|latest| latest > running.max(installed.unwrap_or(0))) {
+        MicrocodeStatus::Outdated
+    } else if installed.is_some() || latest_known.is_some() {
+        MicrocodeStatus::Current
+    } else {
+        MicrocodeStatus::Unknown
+    }
+}
+
+/// Parse a hex revision such as "0xf0"
+fn parse_revision(value: &str) -> Option<u32> {
+    let value = value.trim();
+    let hex = value.strip_prefix("0x").or_else(|| value.strip_prefix("0X")).unwrap_or(value);
+    u32::from_str_radix(hex, 16).ok()
+}
+
+fn read_u32(data: &[u8], offset: usize) -> Option<u32> {
+    let bytes = data.get(offset..offset.checked_add(4)?)?;
+    Some(u32::from_le_bytes(bytes.try_into().ok()?))
+}
+
+fn read_u16(data: &[u8], offset: usize) -> Option<u16> {
+    let bytes = data.get(offset..offset.checked_add(2)?)?;
+    Some(u16::from_le_bytes(bytes.try_into().ok()?))
+}
+
+/// Newest revision for `cpuid` in a file of concatenated Intel updates
+///
+/// Each update starts with a 48-byte header holding the revision at offset
+/// 4, the processor signature at offset 12 and the total size at offset 32,
+/// where zero means the legacy 2048-byte size.
+fn intel_file_revision(data: &[u8], cpuid: u32) -> Option<u32> {
+    let mut best = None;
+    let mut offset = 0;
+    while offset + 48 <= data.len() {
+        let header_version = read_u32(data, offset)?;
+        if header_version != 1 {
+            break;
+        }
+        let revision = read_u32(data, offset + 4)?;
+        let signature = read_u32(data, offset + 12)?;
+        let total_size = match read_u32(data, offset + 32)? {
+            0 => 2048,
+            size => size as usize,
+        };
+        if signature == cpuid {
+            best = best.max(Some(revision));
+        }
+        if total_size < 48 {
+            break;
+        }
+        offset += total_size;
+    }
+    best
+}
+
+/// Newest patch for `cpuid` in a file of AMD microcode containers
+///
+/// A container holds an equivalence table mapping CPUID signatures to
+/// 16-bit equivalent processor IDs, followed by patch sections that each
+/// carry a patch ID at offset 4 and the equivalent processor ID at offset 24.
+fn amd_file_revision(data: &[u8], cpuid: u32) -> Option<u32> {
+    let mut best = None;
+    let mut offset = 0;
+    while read_u32(data, offset) == Some(AMD_CONTAINER_MAGIC) {
+        offset += 4;
+        if read_u32(data, offset)? != 0 {
+            break;
+        }
+        let table_size = read_u32(data, offset + 4)? as usize;
+        let table = data.get(offset + 8..offset + 8 + table_size)?;
+        let equivalent_id = table
+            .chunks_exact(16)
+            .map(|entry| (read_u32(entry, 0), read_u16(entry, 12)))
+            .take_while(|(installed, _)| *installed != Some(0))
+            .find(|(installed, _)| *installed == Some(cpuid))
+            .and_then(|(_, equivalent)| equivalent);
+        offset += 8 + table_size;
+
+        while read_u32(data, offset) == Some(1) {
+            let patch_size = read_u32(data, offset + 4)? as usize;
+            let patch = data.get(offset + 8..offset + 8 + patch_size)?;
+            if equivalent_id.is_some() && read_u16(patch, 24) == equivalent_id {
+                best = best.max(read_u32(patch, 4));
+            }
+            offset += 8 + patch_size;
+        }
+    }
+    best
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CPUINFO: &str = "processor\t: 0\n\
+        vendor_id\t: GenuineIntel\n\
+        cpu family\t: 6\n\
+        model\t\t: 158\n\
+        model name\t: Intel(R) Core(TM) i7-8700 CPU @ 3.20GHz\n\
+        stepping\t: 10\n\
+        microcode\t: 0xde\n\
+        \n\
+        processor\t: 1\n\
+        microcode\t: 0xf8\n";
+
+    fn intel_update(revision: u32, signature: u32) -> Vec<u8> {
+        let mut update = vec![0u8; 64];
+        update[0..4].copy_from_slice(&1u32.to_le_bytes());
+        update[4..8].copy_from_slice(&revision.to_le_bytes());
+        update[12..16].copy_from_slice(&signature.to_le_bytes());
+        update[32..36].copy_from_slice(&64u32.to_le_bytes());
+        update
+    }
+
+    fn amd_container(cpuid: u32, equivalent: u16, patches: &[(u32, u16)]) -> Vec<u8> {
+        let mut data = AMD_CONTAINER_MAGIC.to_le_bytes().to_vec();
+        data.extend(0u32.to_le_bytes());
+        data.extend(32u32.to_le_bytes());
+        let mut entry = [0u8; 16];
+        entry[0..4].copy_from_slice(&cpuid.to_le_bytes());
+        entry[12..14].copy_from_slice(&equivalent.to_le_bytes());
+        data.extend(entry);
+        data.extend([0u8; 16]);
+        for (patch_id, rev_id) in patches {
+            let mut patch = [0u8; 64];
+            patch[4..8].copy_from_slice(&patch_id.to_le_bytes());
+            patch[24..26].copy_from_slice(&rev_id.to_le_bytes());
+            data.extend(1u32.to_le_bytes());
+            data.extend(64u32.to_le_bytes());
+            data.extend(patch);
+        }
+        data
+    }
+
+    #[test]
+    fn test_parse_cpuinfo() {
+        let cpu = CpuMicrocode::from_cpuinfo(CPUINFO).unwrap();
+        assert_eq!(cpu.vendor, Some(MicrocodeVendor::Intel));
+        assert_eq!(cpu.revision, 0xde);
+        assert_eq!(cpu.signature(), "06-9e-0a");
+        assert_eq!(cpu.cpuid(), 0x906ea);
+        assert_eq!(cpu.firmware_file().as_deref(), Some("intel-ucode/06-9e-0a"));
+
+        assert!(CpuMicrocode::from_cpuinfo("processor\t: 0\nBogoMIPS\t: 48.00\n").is_none());
+    }
+
+    #[test]
+    fn test_intel_file_revision() {
+        let cpu = CpuMicrocode::from_cpuinfo(CPUINFO).unwrap();
+        let mut data = intel_update(0xf4, 0x906ea);
+        data.extend(intel_update(0xfa, 0x906eb));
+        data.extend(intel_update(0xf0, 0x906ea));
+
+        assert_eq!(cpu.revision_in_file(&data), Some(0xf4));
+        assert_eq!(cpu.revision_in_file(&intel_update(0xf4, 0x906e9)), None);
+    }
+
+    #[test]
+    fn test_amd_file_revision() {
+        let cpu = CpuMicrocode {
+            vendor: Some(MicrocodeVendor::Amd),
+            family: 0x19,
+            model: 0x21,
+            stepping: 0,
+            revision: 0x0a201016,
+        };
+        assert_eq!(cpu.cpuid(), 0x00a20f10);
+        assert_eq!(cpu.firmware_file().as_deref(), Some("amd-ucode/microcode_amd_fam19h.bin"));
+
+        let data = amd_container(
+            0x00a20f10,
+            0xa210,
+            &[(0x0a201016, 0xa210), (0x0a201025, 0xa210), (0x0a50000f, 0xa500)],
+        );
+        assert_eq!(cpu.revision_in_file(&data), Some(0x0a201025));
+    }
+
+    #[test]
+    fn test_microcode_status() {
+        assert_eq!(microcode_status(0xf0, Some(0xf8), None), MicrocodeStatus::RebootRequired);
+        assert_eq!(microcode_status(0xf0, Some(0xf0), Some(0xf8)), MicrocodeStatus::Outdated);
+        assert_eq!(microcode_status(0xf8, Some(0xf0), Some(0xf8)), MicrocodeStatus::Current);
+        assert_eq!(microcode_status(0xf8, None, None), MicrocodeStatus::Unknown);
+
+        let cpu = CpuMicrocode::from_cpuinfo(CPUINFO).unwrap();
+        let info = MicrocodeDatabase::builtin().evaluate(&cpu, None);
+        assert_eq!(info.latest_known_revision, Some(0xf8));
+        assert_eq!(info.status, MicrocodeStatus::Outdated);
+    }
+}
@@ -1,18 +1,82 @@
 //! Hardware detection tools and interfaces
 
 use crate::errors::Result;
+use crate::telemetry::ParseFailure;
 use async_trait::async_trait;
+use retry::FailureKind;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::process::Output;
-use std::time::Duration;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
 
+pub(crate) mod attributes;
+pub mod audio_cards;
+pub mod audio_routing;
+pub mod cpu;
+pub mod display;
+pub mod dkms;
+#[cfg(feature = "dmidecode")]
 pub mod dmidecode;
+pub mod error;
+pub mod extract;
+pub mod firmware;
+#[cfg(feature = "fwupd")]
+pub mod fwupd;
+pub mod hwmon;
+pub mod immutable;
+pub mod input;
 pub mod integration;
+#[cfg(feature = "inxi")]
 pub mod inxi;
+pub mod iommu;
+#[cfg(feature = "iw")]
+pub mod iw;
 pub mod kernel;
+pub mod kernel_config;
 pub mod kernel_source;
+#[cfg(feature = "lpinfo")]
+pub mod lpinfo;
+#[cfg(feature = "lsblk")]
+pub mod lsblk;
+#[cfg(feature = "lshw")]
 pub mod lshw;
+#[cfg(feature = "lspci")]
 pub mod lspci;
+#[cfg(feature = "lsusb")]
 pub mod lsusb;
+pub mod microcode;
+pub mod modules;
+pub mod naming;
+#[cfg(feature = "nvidia-smi")]
+pub mod nvidia_smi;
+#[cfg(feature = "nvme")]
+pub mod nvme;
+pub mod packages;
+pub mod pcie;
+pub mod privilege;
+pub mod profile;
+pub mod raw;
+pub mod remote;
+pub mod replay;
+pub mod retry;
+pub mod rfkill;
+pub mod sandbox;
+#[cfg(feature = "scanimage")]
+pub mod scanimage;
+#[cfg(feature = "smartctl")]
+pub mod smartctl;
+pub mod sources;
+pub mod storage_stack;
+pub mod swap;
+pub mod sysfs;
+pub mod udev;
+pub mod virt;
+
+pub use error::DetectionError;
+pub use extract::{ComponentExtractor, ExtractionContext};
+pub use profile::DetectionProfile;
+pub use retry::RetryPolicy;
 
 /// Trait for hardware detection tools
 #[async_trait]
@@ -23,6 +87,19 @@ pub trait HardwareDetector: Send + Sync {
     /// Check if this detector is available on the system
     async fn is_available(&self) -> bool;
 
+    /// Executable the detector runs, looked up on remote hosts
+    fn program(&self) -> &'static str {
+        self.name()
+    }
+
+    /// Whether the detector runs in-process instead of an external program
+    ///
+    /// Built-in detectors need nothing installed, but read the local machine
+    /// only and are skipped when detecting a remote host.
+    fn is_builtin(&self) -> bool {
+        false
+    }
+
     /// Execute the detection tool and return raw output
     async fn execute(&self) -> Result<Output>;
 
@@ -33,6 +110,28 @@ pub trait HardwareDetector: Send + Sync {
     fn timeout(&self) -> Duration {
         Duration::from_secs(30)
     }
+
+    /// Get the retry policy for this detector
+    fn retry_policy(&self) -> RetryPolicy {
+        RetryPolicy::none()
+    }
+
+    /// Privileges the tool needs for a complete result
+    fn privileges(&self) -> Privileges {
+        Privileges::None
+    }
+}
+
+/// Privileges a detection tool needs
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Privileges {
+    /// Complete as an ordinary user
+    None,
+    /// Runs as an ordinary user, but some details need root
+    RootRecommended,
+    /// Reports little or nothing without root
+    RootRequired,
 }
 
 /// Result from a hardware detection tool
@@ -41,40 +140,99 @@ pub struct DetectionResult {
     pub tool_name: String,
     pub success: bool,
     pub data: DetectionData,
-    pub errors: Vec<String>,
+    /// Problems the tool reported, including non-fatal warnings
+    pub errors: Vec<DetectionError>,
 }
 
 /// Data extracted by detection tools
 #[derive(Debug, Clone)]
 pub enum DetectionData {
+    #[cfg(feature = "lshw")]
     Lshw(lshw::LshwData),
+    #[cfg(feature = "dmidecode")]
     Dmidecode(Box<dmidecode::DmidecodeData>),
+    #[cfg(feature = "lspci")]
     Lspci(lspci::LspciData),
+    #[cfg(feature = "lsusb")]
     Lsusb(lsusb::LsusbData),
+    #[cfg(feature = "inxi")]
     Inxi(Box<inxi::InxiData>),
     Kernel(kernel::KernelSupportData),
+    #[cfg(feature = "fwupd")]
+    Fwupd(fwupd::FwupdData),
+    #[cfg(feature = "nvme")]
+    Nvme(nvme::NvmeData),
+    #[cfg(feature = "smartctl")]
+    Smartctl(smartctl::SmartctlData),
+    #[cfg(feature = "lsblk")]
+    Lsblk(lsblk::LsblkData),
+    #[cfg(feature = "nvidia-smi")]
+    NvidiaSmi(nvidia_smi::NvidiaSmiData),
+    #[cfg(feature = "iw")]
+    Iw(iw::IwData),
+    #[cfg(feature = "lpinfo")]
+    Lpinfo(lpinfo::LpinfoData),
+    #[cfg(feature = "scanimage")]
+    Scanimage(scanimage::ScanimageData),
+    Sysfs(Box<sysfs::SysfsData>),
 }
 
 /// Registry for managing multiple hardware detectors
 pub struct DetectorRegistry {
     detectors: Vec<Box<dyn HardwareDetector>>,
     enabled_tools: Option<Vec<String>>,
+    profile: DetectionProfile,
     custom_timeout: Option<Duration>,
+    retry_overrides: HashMap<String, RetryPolicy>,
+    /// Fingerprints of tool output that failed to parse during detection
+    parse_failures: Mutex<Vec<ParseFailure>>,
+    /// Keep each tool's output for a raw output bundle
+    capture_raw: bool,
+    raw_outputs: Mutex<Vec<raw::RawOutput>>,
 }
 
 impl DetectorRegistry {
-    /// Create a new detector registry with default detectors
+    /// Create a new detector registry with the detectors compiled into this build
+    #[allow(clippy::vec_init_then_push)] // most pushes are behind their own feature
     pub fn new() -> Self {
+        let mut detectors: Vec<Box<dyn HardwareDetector>> = Vec::new();
+        #[cfg(feature = "lshw")]
+        detectors.push(Box::new(lshw::LshwDetector::new()));
+        #[cfg(feature = "dmidecode")]
+        detectors.push(Box::new(dmidecode::DmidecodeDetector::new()));
+        #[cfg(feature = "lspci")]
+        detectors.push(Box::new(lspci::LspciDetector::new()));
+        #[cfg(feature = "lsusb")]
+        detectors.push(Box::new(lsusb::LsusbDetector::new()));
+        #[cfg(feature = "inxi")]
+        detectors.push(Box::new(inxi::InxiDetector::new()));
+        #[cfg(feature = "fwupd")]
+        detectors.push(Box::new(fwupd::FwupdDetector::new()));
+        #[cfg(feature = "nvme")]
+        detectors.push(Box::new(nvme::NvmeDetector::new()));
+        #[cfg(feature = "smartctl")]
+        detectors.push(Box::new(smartctl::SmartctlDetector::new()));
+        #[cfg(feature = "lsblk")]
+        detectors.push(Box::new(lsblk::LsblkDetector::new()));
+        #[cfg(feature = "nvidia-smi")]
+        detectors.push(Box::new(nvidia_smi::NvidiaSmiDetector::new()));
+        #[cfg(feature = "iw")]
+        detectors.push(Box::new(iw::IwDetector::new()));
+        #[cfg(feature = "lpinfo")]
+        detectors.push(Box::new(lpinfo::LpinfoDetector::new()));
+        #[cfg(feature = "scanimage")]
+        detectors.push(Box::new(scanimage::ScanimageDetector::new()));
+        detectors.push(Box::new(sysfs::SysfsDetector::new()));
+
         Self {
-            detectors: vec![
-                Box::new(lshw::LshwDetector::new()),
-                Box::new(dmidecode::DmidecodeDetector::new()),
-                Box::new(lspci::LspciDetector::new()),
-                Box::new(lsusb::LsusbDetector::new()),
-                Box::new(inxi::InxiDetector::new()),
-            ],
+            detectors,
             enabled_tools: None,
+            profile: DetectionProfile::Full,
             custom_timeout: None,
+            retry_overrides: HashMap::new(),
+            parse_failures: Mutex::new(Vec::new()),
+            capture_raw: false,
+            raw_outputs: Mutex::new(Vec::new()),
         }
     }
 
@@ -102,33 +260,96 @@ impl DetectorRegistry {
         Ok(())
     }
 
+    /// Select the detection profile
+    ///
+    /// An explicit tool list from `set_enabled_tools` takes precedence over
+    /// the tools the profile would pick.
+    pub fn set_profile(&mut self, profile: DetectionProfile) {
+        self.profile = profile;
+    }
+
     /// Set custom timeout for all detectors
     pub fn set_detection_timeout(&mut self, timeout: Duration) {
         self.custom_timeout = Some(timeout);
     }
 
+    /// Override the retry policy for one tool, or for all tools when `tool` is None
+    pub fn set_retry_policy(&mut self, tool: Option<&str>, policy: RetryPolicy) -> Result<()> {
+        match tool {
+            Some(name) => {
+                if !self.detectors.iter().any(|d| d.name() == name) {
+                    return Err(crate::errors::LxHwError::ConfigError(format!(
+                        "Unknown detection tool: {}",
+                        name
+                    )));
+                }
+                self.retry_overrides.insert(name.to_string(), policy);
+            }
+            None => {
+                for detector in &self.detectors {
+                    self.retry_overrides.insert(detector.name().to_string(), policy);
+                }
+            }
+        }
+        Ok(())
+    }
+
     /// Check if a tool is enabled based on filtering configuration
     fn is_tool_enabled(&self, tool_name: &str) -> bool {
         match &self.enabled_tools {
             Some(enabled) => enabled.contains(&tool_name.to_string()),
-            None => true, // If no filter is set, all tools are enabled
+            None => self.profile.runs_tool(tool_name),
         }
     }
 
     /// Get the effective timeout for a detector
     fn get_effective_timeout(&self, detector: &dyn HardwareDetector) -> Duration {
-        self.custom_timeout.unwrap_or_else(|| detector.timeout())
+        self.custom_timeout
+            .or_else(|| self.profile.tool_timeout())
+            .unwrap_or_else(|| detector.timeout())
+    }
+
+    /// Get the effective retry policy for a detector
+    fn get_effective_retry_policy(&self, detector: &dyn HardwareDetector) -> RetryPolicy {
+        self.retry_overrides
+            .get(detector.name())
+            .copied()
+            .or_else(|| self.profile.retry_policy())
+            .or_else(|| replay::active().map(|_| RetryPolicy::none()))
+            .unwrap_or_else(|| detector.retry_policy())
     }
 
     /// Create default data variant for a specific detector type
     fn default_data_for_detector(detector_name: &str) -> DetectionData {
         match detector_name {
+            #[cfg(feature = "lshw")]
             "lshw" => DetectionData::Lshw(lshw::LshwData::default()),
+            #[cfg(feature = "dmidecode")]
             "dmidecode" => DetectionData::Dmidecode(Box::default()),
+            #[cfg(feature = "lspci")]
             "lspci" => DetectionData::Lspci(lspci::LspciData::default()),
+            #[cfg(feature = "lsusb")]
             "lsusb" => DetectionData::Lsusb(lsusb::LsusbData::default()),
+            #[cfg(feature = "inxi")]
             "inxi" => DetectionData::Inxi(Box::default()),
-            _ => DetectionData::Lshw(lshw::LshwData::default()), // fallback
+            #[cfg(feature = "fwupd")]
+            "fwupd" => DetectionData::Fwupd(fwupd::FwupdData::default()),
+            #[cfg(feature = "nvme")]
+            "nvme" => DetectionData::Nvme(nvme::NvmeData::default()),
+            #[cfg(feature = "smartctl")]
+            "smartctl" => DetectionData::Smartctl(smartctl::SmartctlData::default()),
+            #[cfg(feature = "lsblk")]
+            "lsblk" => DetectionData::Lsblk(lsblk::LsblkData::default()),
+            #[cfg(feature = "nvidia-smi")]
+            "nvidia-smi" => DetectionData::NvidiaSmi(nvidia_smi::NvidiaSmiData::default()),
+            #[cfg(feature = "iw")]
+            "iw" => DetectionData::Iw(iw::IwData::default()),
+            #[cfg(feature = "lpinfo")]
+            "lpinfo" => DetectionData::Lpinfo(lpinfo::LpinfoData::default()),
+            #[cfg(feature = "scanimage")]
+            "scanimage" => DetectionData::Scanimage(scanimage::ScanimageData::default()),
+            "sysfs" => DetectionData::Sysfs(Box::default()),
+            _ => DetectionData::Kernel(kernel::KernelSupportData::default()), // fallback
         }
     }
 
@@ -137,7 +358,16 @@ impl DetectorRegistry {
         let mut available = Vec::new();
         for detector in &self.detectors {
             // Check if tool is enabled by filter and available on system
-            if self.is_tool_enabled(detector.name()) && detector.is_available().await {
+            if !self.is_tool_enabled(detector.name()) {
+                continue;
+            }
+            let installed = match (replay::active(), remote::active()) {
+                (Some(replay), _) => replay.contains(detector.name()),
+                (None, Some(_)) if detector.is_builtin() => false,
+                (None, Some(remote)) => remote.has_program(detector.program()).await,
+                (None, None) => detector.is_available().await,
+            };
+            if installed {
                 available.push(detector.as_ref());
             }
         }
@@ -149,54 +379,180 @@ impl DetectorRegistry {
         self.detectors.iter().map(|d| d.as_ref()).collect()
     }
 
-    /// Run all available detectors with timeout handling
+    /// Run all available detectors concurrently with timeout handling
+    ///
+    /// Results keep the registry's detector order regardless of which tool
+    /// finishes first.
     pub async fn detect_all(&self) -> Result<Vec<DetectionResult>> {
         let available = self.get_available_detectors().await;
-        let mut results = Vec::new();
-
-        for detector in available {
-            let timeout = self.get_effective_timeout(detector);
-
-            // Execute with timeout
-            let execution_result = tokio::time::timeout(timeout, detector.execute()).await;
-
-            match execution_result {
-                Ok(Ok(output)) => {
-                    // Successful execution within timeout
-                    match detector.parse_output(&output) {
-                        Ok(result) => results.push(result),
-                        Err(e) => {
-                            results.push(DetectionResult {
-                                tool_name: detector.name().to_string(),
-                                success: false,
-                                data: Self::default_data_for_detector(detector.name()),
-                                errors: vec![e.to_string()],
-                            });
-                        }
-                    }
+        let runs = available.into_iter().map(|detector| {
+            crate::telemetry::crash::in_phase(detector.name(), self.detect_with_retries(detector))
+        });
+        Ok(futures::future::join_all(runs).await)
+    }
+
+    /// Run one detector, retrying transient failures per its retry policy
+    async fn detect_with_retries(&self, detector: &dyn HardwareDetector) -> DetectionResult {
+        let policy = self.get_effective_retry_policy(detector);
+        let mut attempt = 1;
+
+        loop {
+            log::debug!(detector = detector.name(), attempt; "Running {}", detector.name());
+            let started = Instant::now();
+            let (mut result, kind) = self.run_detector(detector).await;
+            let duration_ms = started.elapsed().as_millis() as u64;
+            log::debug!(
+                detector = detector.name(), attempt, duration_ms, success = result.success;
+                "{} finished in {} ms",
+                detector.name(),
+                duration_ms
+            );
+
+            if result.success || kind == FailureKind::Permanent || attempt >= policy.max_attempts {
+                if attempt > 1 && result.success {
+                    log::info!("{} succeeded on attempt {}", detector.name(), attempt);
+                } else if attempt > 1 {
+                    result
+                        .errors
+                        .push(DetectionError::Other(format!("Failed after {} attempts", attempt)));
                 }
-                Ok(Err(e)) => {
-                    // Execution failed
-                    results.push(DetectionResult {
-                        tool_name: detector.name().to_string(),
-                        success: false,
-                        data: Self::default_data_for_detector(detector.name()),
-                        errors: vec![e.to_string()],
-                    });
+                if !result.success {
+                    let error = result.errors.iter().map(|e| e.to_string()).collect::<Vec<_>>();
+                    let error = error.join("; ");
+                    log::warn!(
+                        detector = detector.name(), attempts = attempt, error = error.as_str();
+                        "{} failed: {}",
+                        detector.name(),
+                        error
+                    );
                 }
-                Err(_) => {
-                    // Execution timed out
-                    results.push(DetectionResult {
-                        tool_name: detector.name().to_string(),
-                        success: false,
-                        data: Self::default_data_for_detector(detector.name()),
-                        errors: vec![format!("Execution timed out after {:?}", timeout)],
-                    });
+                return result;
+            }
+
+            let delay = policy.backoff(attempt);
+            log::debug!(
+                "{} failed transiently ({}), retrying in {:?}",
+                detector.name(),
+                result.errors.iter().map(|e| e.to_string()).collect::<Vec<_>>().join("; "),
+                delay
+            );
+            tokio::time::sleep(delay).await;
+            attempt += 1;
+        }
+    }
+
+    /// Run a single detection attempt and classify any failure
+    async fn run_detector(
+        &self,
+        detector: &dyn HardwareDetector,
+    ) -> (DetectionResult, FailureKind) {
+        let timeout = self.get_effective_timeout(detector);
+
+        // Execute with timeout, or take the saved output when replaying
+        let execution_result = match replay::active().and_then(|r| r.output(detector.name())) {
+            Some(output) => Ok(Ok(output)),
+            None => tokio::time::timeout(timeout, detector.execute()).await,
+        };
+
+        match execution_result {
+            Ok(Ok(output)) => {
+                // Successful execution within timeout
+                if self.capture_raw {
+                    self.record_raw_output(raw::RawOutput::new(detector.name(), &output));
                 }
+                match detector.parse_output(&output) {
+                    Ok(result) => {
+                        // Most parsers report malformed output here rather than failing
+                        if result
+                            .errors
+                            .iter()
+                            .any(|e| matches!(e, DetectionError::ParseError { .. }))
+                        {
+                            self.record_parse_failure(ParseFailure::reported(
+                                detector.name(),
+                                &output.stdout,
+                            ));
+                        }
+                        // Tools may run but report errors (e.g. a busy device)
+                        let kind = if result
+                            .errors
+                            .iter()
+                            .any(|e| e.failure_kind() == FailureKind::Transient)
+                        {
+                            FailureKind::Transient
+                        } else {
+                            FailureKind::Permanent
+                        };
+                        (result, kind)
+                    }
+                    Err(e) => {
+                        self.record_parse_failure(ParseFailure::new(
+                            detector.name(),
+                            &output.stdout,
+                            &e,
+                        ));
+                        let error = DetectionError::parse(e.to_string());
+                        (self.failed_result(detector, error), FailureKind::Permanent)
+                    }
+                }
+            }
+            Ok(Err(e)) => {
+                // Execution failed
+                let kind = retry::classify_failure(&e);
+                let error = DetectionError::from_execution(detector.name(), &e);
+                (self.failed_result(detector, error), kind)
+            }
+            Err(_) => {
+                // Execution timed out
+                let error = DetectionError::Timeout { after: timeout };
+                (self.failed_result(detector, error), FailureKind::Transient)
             }
         }
+    }
+
+    fn failed_result(
+        &self,
+        detector: &dyn HardwareDetector,
+        error: DetectionError,
+    ) -> DetectionResult {
+        DetectionResult {
+            tool_name: detector.name().to_string(),
+            success: false,
+            data: Self::default_data_for_detector(detector.name()),
+            errors: vec![error],
+        }
+    }
+
+    fn record_parse_failure(&self, failure: ParseFailure) {
+        if let Ok(mut failures) = self.parse_failures.lock() {
+            failures.push(failure);
+        }
+    }
+
+    /// Keep the output of the last attempt of each tool
+    fn record_raw_output(&self, output: raw::RawOutput) {
+        if let Ok(mut outputs) = self.raw_outputs.lock() {
+            outputs.retain(|previous| previous.tool != output.tool);
+            outputs.push(output);
+        }
+    }
+
+    /// Keep tool output for [`take_raw_outputs`](Self::take_raw_outputs)
+    pub fn set_capture_raw(&mut self, capture: bool) {
+        self.capture_raw = capture;
+    }
+
+    /// Take the unsanitized tool output kept by previous detection runs
+    pub fn take_raw_outputs(&self) -> Vec<raw::RawOutput> {
+        self.raw_outputs.lock().map(|mut outputs| std::mem::take(&mut *outputs)).unwrap_or_default()
+    }
 
-        Ok(results)
+    /// Take the parser failures recorded by previous detection runs
+    pub fn take_parse_failures(&self) -> Vec<ParseFailure> {
+        self.parse_failures
+            .lock()
+            .map(|mut failures| std::mem::take(&mut *failures))
+            .unwrap_or_default()
     }
 }
 
@@ -0,0 +1,203 @@
+//! Kernel module dependency resolution
+//!
+//! Resolves the full dependency chain of a kernel module from `modules.dep`
+//! and the firmware each module in the chain declares, so module load and
+//! initramfs instructions include everything needed on first boot.
+
+use crate::errors::{LxHwError, Result};
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use std::process::Command;
+use std::sync::Mutex;
+
+/// A module together with everything it needs to load
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ModuleChain {
+    /// Modules in load order: dependencies first, requested modules last
+    pub modules: Vec<String>,
+    /// Firmware files declared by any module in the chain
+    pub firmware: Vec<String>,
+}
+
+/// Dependency graph of the loadable modules of one kernel
+#[derive(Debug, Default)]
+pub struct ModuleDependencies {
+    kernel_version: Option<String>,
+    dependencies: HashMap<String, Vec<String>>,
+    builtin: HashSet<String>,
+    firmware: Mutex<HashMap<String, Vec<String>>>,
+}
+
+impl ModuleDependencies {
+    /// Load the dependency graph of the running kernel
+    pub fn for_running_kernel() -> Result<Self> {
+        let release = kernel_release()?;
+        Self::load(&release, Path::new("/lib/modules").join(&release).as_path())
+    }
+
+    /// Load `modules.dep` and `modules.builtin` from a module directory
+    pub fn load(kernel_version: &str, modules_dir: &Path) -> Result<Self> {
+        let dep = std::fs::read_to_string(modules_dir.join("modules.dep")).map_err(|e| {
+            LxHwError::SystemError {
+                message: format!("Failed to read modules.dep for {}: {}", kernel_version, e),
+            }
+        })?;
+        let builtin =
+            std::fs::read_to_string(modules_dir.join("modules.builtin")).unwrap_or_default();
+
+        let mut dependencies = Self::parse(&dep, &builtin);
+        dependencies.kernel_version = Some(kernel_version.to_string());
+        Ok(dependencies)
+    }
+
+    /// Build the dependency graph from `modules.dep` and `modules.builtin` content
+    pub fn parse(modules_dep: &str, modules_builtin: &str) -> Self {
+        let dependencies = modules_dep
+            .lines()
+            .filter_map(|line| line.split_once(':'))
+            .map(|(module, deps)| {
+                (module_name(module), deps.split_whitespace().map(module_name).collect())
+            })
+            .collect();
+        let builtin = modules_builtin.lines().filter(|l| !l.is_empty()).map(module_name).collect();
+
+        Self { kernel_version: None, dependencies, builtin, firmware: Mutex::new(HashMap::new()) }
+    }
+
+    /// Record the firmware a module declares instead of querying modinfo
+    pub fn with_firmware(self, module: &str, files: Vec<String>) -> Self {
+        self.firmware.lock().unwrap().insert(normalize(module), files);
+        self
+    }
+
+    /// Whether a module is compiled into the kernel
+    pub fn is_builtin(&self, module: &str) -> bool {
+        self.builtin.contains(&normalize(module))
+    }
+
+    /// Resolve modules into their full load chain and required firmware
+    pub fn resolve(&self, modules: &[String]) -> ModuleChain {
+        let mut chain = ModuleChain::default();
+        let mut visited = HashSet::new();
+        for module in modules {
+            self.visit(&normalize(module), &mut visited, &mut chain.modules);
+        }
+
+        for module in &chain.modules {
+            for file in self.module_firmware(module) {
+                if !chain.firmware.contains(&file) {
+                    chain.firmware.push(file);
+                }
+            }
+        }
+        chain
+    }
+
+    /// Depth-first walk that emits dependencies before their dependents
+    fn visit(&self, module: &str, visited: &mut HashSet<String>, order: &mut Vec<String>) {
+        if !visited.insert(module.to_string()) {
+            return;
+        }
+        if let Some(dependencies) = self.dependencies.get(module) {
+            for dependency in dependencies {
+                self.visit(dependency, visited, order);
+            }
+        }
+        order.push(module.to_string());
+    }
+
+    /// Firmware files declared by a module, queried once through modinfo
+    fn module_firmware(&self, module: &str) -> Vec<String> {
+        let mut cache = self.firmware.lock().unwrap();
+        if let Some(files) = cache.get(module) {
+            return files.clone();
+        }
+
+        let files = match (&self.kernel_version, self.dependencies.contains_key(module)) {
+            (Some(kernel_version), true) => modinfo_firmware(kernel_version, module),
+            _ => Vec::new(),
+        };
+        cache.insert(module.to_string(), files.clone());
+        files
+    }
+}
+
+/// Firmware files a module declares, as reported by modinfo
+fn modinfo_firmware(kernel_version: &str, module: &str) -> Vec<String> {
+    Command::new("modinfo")
+        .args(["-k", kernel_version, "-F", "firmware", module])
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .map(|output| {
+            String::from_utf8_lossy(&output.stdout)
+                .lines()
+                .map(str::trim)
+                .filter(|line| !line.is_empty())
+                .map(str::to_string)
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Release of the running kernel, as used for `/lib/modules/<release>`
+fn kernel_release() -> Result<String> {
+    std::fs::read_to_string("/proc/sys/kernel/osrelease")
+        .map(|release| release.trim().to_string())
+        .map_err(|e| LxHwError::SystemError {
+            message: format!("Failed to read kernel release: {}", e),
+        })
+}
+
+/// Module name from a `modules.dep` path, e.g. `kernel/drivers/net/e1000e/e1000e.ko.zst`
+fn module_name(path: &str) -> String {
+    let file = path.trim().rsplit('/').next().unwrap_or_default();
+    let name = file.split_once(".ko").map_or(file, |(name, _)| name);
+    normalize(name)
+}
+
+/// Module names treat `-` and `_` as equivalent; modprobe reports `_`
+fn normalize(module: &str) -> String {
+    module.replace('-', "_")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MODULES_DEP: &str = "\
+kernel/drivers/gpu/drm/amd/amdgpu/amdgpu.ko.zst: kernel/drivers/gpu/drm/drm_ttm_helper.ko.zst kernel/drivers/gpu/drm/ttm/ttm.ko.zst kernel/drivers/gpu/drm/drm_kms_helper.ko.zst
+kernel/drivers/gpu/drm/drm_ttm_helper.ko.zst: kernel/drivers/gpu/drm/ttm/ttm.ko.zst
+kernel/drivers/gpu/drm/ttm/ttm.ko.zst:
+kernel/drivers/gpu/drm/drm_kms_helper.ko.zst:
+kernel/drivers/net/ethernet/intel/e1000e/e1000e.ko.xz:
+";
+
+    #[test]
+    fn test_resolve_orders_dependencies_first() {
+        let deps = ModuleDependencies::parse(MODULES_DEP, "");
+        let chain = deps.resolve(&["amdgpu".to_string()]);
+
+        assert_eq!(chain.modules, vec!["ttm", "drm_ttm_helper", "drm_kms_helper", "amdgpu"]);
+    }
+
+    #[test]
+    fn test_resolve_collects_firmware_and_dedups() {
+        let deps = ModuleDependencies::parse(MODULES_DEP, "kernel/drivers/char/misc.ko\n")
+            .with_firmware("amdgpu", vec!["amdgpu/polaris10_mc.bin".to_string()])
+            .with_firmware("ttm", vec![]);
+        let chain = deps.resolve(&["amdgpu".to_string(), "drm-ttm-helper".to_string()]);
+
+        assert_eq!(chain.modules.len(), 4);
+        assert_eq!(chain.firmware, vec!["amdgpu/polaris10_mc.bin"]);
+        assert!(deps.is_builtin("misc"));
+    }
+
+    #[test]
+    fn test_unknown_module_kept() {
+        let deps = ModuleDependencies::parse(MODULES_DEP, "");
+        let chain = deps.resolve(&["nvidia".to_string()]);
+        assert_eq!(chain.modules, vec!["nvidia"]);
+        assert!(chain.firmware.is_empty());
+    }
+}
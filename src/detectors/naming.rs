@@ -0,0 +1,289 @@
+//! Persistent network interface and disk names
+//!
+//! Kernel names such as `eth1` or `sdb` depend on probe order and can swap
+//! between boots on systems with several NICs or disks. This module lists
+//! the names udev derives from stable hardware properties and generates
+//! systemd.link files or udev rules that pin each device to a fixed name.
+//!
+//! The output contains MAC addresses and disk serial numbers, so it is only
+//! meant for configuring the local system and never enters a report.
+
+use super::attributes::{link_target_name, read_trimmed};
+use clap::ValueEnum;
+use std::path::Path;
+
+/// Kernel block devices that are not physical disks
+const VIRTUAL_BLOCK_PREFIXES: &[&str] = &["loop", "ram", "zram", "dm-", "md", "sr", "fd", "nbd"];
+
+/// Bus prefixes of `/dev/disk/by-id` links carrying the udev `ID_SERIAL`
+const SERIAL_LINK_PREFIXES: &[&str] = &["ata-", "nvme-", "scsi-", "usb-", "mmc-", "virtio-"];
+
+/// Physical network interface and the properties it can be matched on
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InterfaceName {
+    /// Current kernel name, e.g. "enp3s0" or "eth0"
+    pub name: String,
+    pub mac_address: String,
+    /// Bus address of the underlying device, e.g. "0000:03:00.0"
+    pub bus_address: Option<String>,
+    pub driver: Option<String>,
+    pub wireless: bool,
+}
+
+/// Physical disk and its persistent `/dev/disk` links
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DiskName {
+    /// Current kernel name, e.g. "sda" or "nvme0n1"
+    pub name: String,
+    /// udev `ID_SERIAL` property, derived from the `by-id` links
+    pub serial: Option<String>,
+    pub by_id: Vec<String>,
+    pub by_path: Vec<String>,
+}
+
+/// Format of generated naming rules
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum NamingRuleStyle {
+    /// systemd.link files in /etc/systemd/network
+    Link,
+    /// A udev rules file in /etc/udev/rules.d
+    Udev,
+}
+
+/// A generated configuration file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RuleFile {
+    pub path: String,
+    pub content: String,
+}
+
+/// Current names of the system's physical interfaces and disks
+#[derive(Debug, Clone, Default)]
+pub struct PersistentNames {
+    pub interfaces: Vec<InterfaceName>,
+    pub disks: Vec<DiskName>,
+}
+
+impl PersistentNames {
+    /// Names on the running system
+    pub fn read() -> Self {
+        Self::read_from(Path::new("/sys"), Path::new("/dev"))
+    }
+
+    /// Names from a sysfs and devfs tree rooted elsewhere
+    pub fn read_from(sys: &Path, dev: &Path) -> Self {
+        Self { interfaces: read_interfaces(sys), disks: read_disks(sys, dev) }
+    }
+
+    /// Each interface paired with the name it should be pinned to
+    ///
+    /// Predictable names (`enp3s0`, `wlp2s0`) are kept. Interfaces still on
+    /// kernel names get `lanN` or `wifiN` instead, since renaming within the
+    /// kernel's own `ethN` namespace races with probing.
+    pub fn stable_interface_names(&self) -> Vec<(&InterfaceName, String)> {
+        let (mut lan, mut wifi) = (0, 0);
+        self.interfaces
+            .iter()
+            .map(|interface| {
+                let kernel_name = ["eth", "wlan"].iter().any(|prefix| {
+                    interface
+                        .name
+                        .strip_prefix(prefix)
+                        .is_some_and(|n| !n.is_empty() && n.bytes().all(|b| b.is_ascii_digit()))
+                });
+                let name = if !kernel_name {
+                    interface.name.clone()
+                } else if interface.wireless {
+                    wifi += 1;
+                    format!("wifi{}", wifi - 1)
+                } else {
+                    lan += 1;
+                    format!("lan{}", lan - 1)
+                };
+                (interface, name)
+            })
+            .collect()
+    }
+
+    /// Files pinning every interface and disk to its current or stable name
+    ///
+    /// Disks always get a udev rule adding a `/dev/disk/persistent/<name>`
+    /// link, as systemd.link files only apply to network interfaces.
+    pub fn rule_files(&self, style: NamingRuleStyle) -> Vec<RuleFile> {
+        let mut files = Vec::new();
+        let interfaces = self.stable_interface_names();
+
+        match style {
+            NamingRuleStyle::Link => {
+                for (interface, name) in &interfaces {
+                    files.push(RuleFile {
+                        path: format!("/etc/systemd/network/10-persistent-{}.link", name),
+                        content: format!(
+                            "# {} ({})\n[Match]\nMACAddress={}\n\n[Link]\nName={}\n",
+                            interface.name,
+                            interface.driver.as_deref().unwrap_or("unknown driver"),
+                            interface.mac_address,
+                            name
+                        ),
+                    });
+                }
+            }
+            NamingRuleStyle::Udev if !interfaces.is_empty() => {
+                let mut content = String::from("# Generated by lx-hw-detect\n");
+                for (interface, name) in &interfaces {
+                    content.push_str(&format!(
+                        "# {} ({})\nSUBSYSTEM==\"net\", ACTION==\"add\", ATTR{{address}}==\"{}\", NAME=\"{}\"\n",
+                        interface.name,
+                        interface.driver.as_deref().unwrap_or("unknown driver"),
+                        interface.mac_address,
+                        name
+                    ));
+                }
+                files.push(RuleFile {
+                    path: "/etc/udev/rules.d/70-persistent-net.rules".to_string(),
+                    content,
+                });
+            }
+            NamingRuleStyle::Udev => {}
+        }
+
+        let disks: Vec<(&DiskName, &String)> =
+            self.disks.iter().filter_map(|d| Some((d, d.serial.as_ref()?))).collect();
+        if !disks.is_empty() {
+            let mut content = String::from("# Generated by lx-hw-detect\n");
+            for (disk, serial) in disks {
+                content.push_str(&format!(
+                    "SUBSYSTEM==\"block\", ENV{{DEVTYPE}}==\"disk\", ENV{{ID_SERIAL}}==\"{}\", SYMLINK+=\"disk/persistent/{}\"\n",
+                    serial, disk.name
+                ));
+            }
+            files.push(RuleFile {
+                path: "/etc/udev/rules.d/71-persistent-disk.rules".to_string(),
+                content,
+            });
+        }
+
+        files
+    }
+}
+
+fn sorted_entries(dir: &Path) -> Vec<String> {
+    let mut names: Vec<String> = std::fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .filter_map(|entry| entry.ok())
+                .map(|entry| entry.file_name().to_string_lossy().into_owned())
+                .collect()
+        })
+        .unwrap_or_default();
+    names.sort();
+    names
+}
+
+/// Interfaces backed by a device, skipping loopback, bridges and tunnels
+fn read_interfaces(sys: &Path) -> Vec<InterfaceName> {
+    let net = sys.join("class/net");
+    sorted_entries(&net)
+        .into_iter()
+        .filter_map(|name| {
+            let dir = net.join(&name);
+            let device = dir.join("device");
+            if !device.exists() {
+                return None;
+            }
+            Some(InterfaceName {
+                mac_address: read_trimmed(&dir.join("address"))?,
+                bus_address: link_target_name(&device),
+                driver: link_target_name(&device.join("driver")),
+                wireless: dir.join("wireless").exists() || dir.join("phy80211").exists(),
+                name,
+            })
+        })
+        .collect()
+}
+
+/// Block devices that are disks, with the `/dev/disk` links pointing at them
+fn read_disks(sys: &Path, dev: &Path) -> Vec<DiskName> {
+    let links_to = |kind: &str, disk: &str| -> Vec<String> {
+        let dir = dev.join("disk").join(kind);
+        sorted_entries(&dir)
+            .into_iter()
+            .filter(|link| link_target_name(&dir.join(link)).as_deref() == Some(disk))
+            .collect()
+    };
+
+    sorted_entries(&sys.join("block"))
+        .into_iter()
+        .filter(|name| !VIRTUAL_BLOCK_PREFIXES.iter().any(|prefix| name.starts_with(prefix)))
+        .map(|name| {
+            let by_id = links_to("by-id", &name);
+            // Namespaced NVMe links add a "_1" suffix; the shortest is ID_SERIAL
+            let serial = by_id
+                .iter()
+                .filter(|link| !link.contains("eui.") && !link.starts_with("wwn-"))
+                .filter_map(|link| {
+                    SERIAL_LINK_PREFIXES.iter().find_map(|prefix| link.strip_prefix(prefix))
+                })
+                .min_by_key(|serial| serial.len())
+                .map(str::to_string);
+            DiskName { serial, by_path: links_to("by-path", &name), by_id, name }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::os::unix::fs::symlink;
+
+    fn interface(root: &Path, name: &str, mac: &str, device: &str, wireless: bool) {
+        let dir = root.join("sys/class/net").join(name);
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("address"), format!("{}\n", mac)).unwrap();
+        let device_dir = root.join("sys/devices").join(device);
+        std::fs::create_dir_all(&device_dir).unwrap();
+        symlink(&device_dir, dir.join("device")).unwrap();
+        if wireless {
+            std::fs::create_dir(dir.join("wireless")).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_read_and_generate_rules() {
+        let root = tempfile::tempdir().unwrap();
+        let root = root.path();
+        interface(root, "enp3s0", "52:54:00:12:34:56", "0000:03:00.0", false);
+        interface(root, "eth1", "52:54:00:12:34:57", "0000:04:00.0", false);
+        interface(root, "wlan0", "52:54:00:12:34:58", "0000:05:00.0", true);
+        std::fs::create_dir_all(root.join("sys/class/net/lo")).unwrap();
+
+        for disk in ["nvme0n1", "loop0"] {
+            std::fs::create_dir_all(root.join("sys/block").join(disk)).unwrap();
+        }
+        let by_id = root.join("dev/disk/by-id");
+        std::fs::create_dir_all(&by_id).unwrap();
+        for link in ["nvme-Samsung_SSD_970_S4EW_1", "nvme-Samsung_SSD_970_S4EW", "nvme-eui.0025"] {
+            symlink("../../nvme0n1", by_id.join(link)).unwrap();
+        }
+
+        let names = PersistentNames::read_from(&root.join("sys"), &root.join("dev"));
+        assert_eq!(names.interfaces.len(), 3);
+        assert_eq!(names.interfaces[0].bus_address.as_deref(), Some("0000:03:00.0"));
+        assert_eq!(names.disks.len(), 1);
+        assert_eq!(names.disks[0].serial.as_deref(), Some("Samsung_SSD_970_S4EW"));
+
+        let stable = names.stable_interface_names();
+        let stable: Vec<&str> = stable.iter().map(|(_, name)| name.as_str()).collect();
+        assert_eq!(stable, ["enp3s0", "lan0", "wifi0"]);
+
+        let links = names.rule_files(NamingRuleStyle::Link);
+        assert_eq!(links.len(), 4);
+        assert_eq!(links[1].path, "/etc/systemd/network/10-persistent-lan0.link");
+        assert!(links[1].content.contains("MACAddress=52:54:00:12:34:57\n"));
+        assert!(links[3].content.contains("ENV{ID_SERIAL}==\"Samsung_SSD_970_S4EW\""));
+
+        let udev = names.rule_files(NamingRuleStyle::Udev);
+        assert_eq!(udev.len(), 2);
+        assert!(udev[0].content.contains("ATTR{address}==\"52:54:00:12:34:58\", NAME=\"wifi0\""));
+    }
+}
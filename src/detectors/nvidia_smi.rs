@@ -0,0 +1,291 @@
+//! NVIDIA driver details from nvidia-smi
+//!
+//! `nvidia-smi --query-gpu` lists each GPU's PCI device ID, name, memory
+//! size and driver version. The CUDA version the driver supports is only
+//! printed in the banner of plain `nvidia-smi`, so just that line is kept;
+//! the process table below it is dropped. Whether the open or proprietary
+//! kernel module is loaded is read from `/proc/driver/nvidia/version`,
+//! which is only possible on the local machine.
+
+use super::extract::{ComponentExtractor, ExtractionContext};
+use super::{remote, sandbox, DetectionData, DetectionError, DetectionResult, HardwareDetector};
+use crate::errors::{LxHwError, Result};
+use crate::hardware::{GraphicsDevice, NvidiaDetails, NvidiaKernelModule};
+use async_trait::async_trait;
+use std::process::Output;
+
+const CUDA_MARKER: &str = "--- CUDA ---";
+const MODULE_MARKER: &str = "--- KERNEL MODULE ---";
+
+/// Fields asked from `nvidia-smi --query-gpu`, in output order
+const QUERY_FIELDS: &str = "pci.device_id,name,memory.total,driver_version";
+
+/// GPUs reported by nvidia-smi
+#[derive(Debug, Clone, Default)]
+pub struct NvidiaSmiData {
+    pub gpus: Vec<NvidiaGpu>,
+    pub cuda_version: Option<String>,
+    pub kernel_module: Option<NvidiaKernelModule>,
+}
+
+/// One GPU's identity and driver
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct NvidiaGpu {
+    /// Vendor and device ID, e.g. "10de:2684"
+    pub pci_id: String,
+    pub name: String,
+    pub memory_bytes: Option<u64>,
+    pub driver_version: String,
+}
+
+impl ComponentExtractor for NvidiaSmiData {
+    fn graphics(&self, _ctx: &mut ExtractionContext) -> Result<Vec<GraphicsDevice>> {
+        Ok(self
+            .gpus
+            .iter()
+            .map(|gpu| GraphicsDevice {
+                vendor: "NVIDIA Corporation".to_string(),
+                model: gpu.name.clone(),
+                // Both kernel module flavours load as "nvidia"
+                driver: Some("nvidia".to_string()),
+                memory_bytes: gpu.memory_bytes,
+                pci_id: gpu.pci_id.clone(),
+                displays: None,
+                nvidia: Some(NvidiaDetails {
+                    driver_version: gpu.driver_version.clone(),
+                    cuda_version: self.cuda_version.clone(),
+                    kernel_module: self.kernel_module.unwrap_or(NvidiaKernelModule::Unknown),
+                }),
+            })
+            .collect())
+    }
+}
+
+/// Turn nvidia-smi's "0x268410DE" (device then vendor) into "10de:2684"
+fn pci_id_from_device_id(device_id: &str) -> Option<String> {
+    let hex = device_id.trim().trim_start_matches("0x").trim_start_matches("0X");
+    if hex.len() != 8 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    let hex = hex.to_ascii_lowercase();
+    Some(format!("{}:{}", &hex[4..], &hex[..4]))
+}
+
+/// Parse `--query-gpu` CSV output without header or units
+pub fn parse_gpus(csv: &str) -> Vec<NvidiaGpu> {
+    csv.lines()
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+            let [device_id, name, memory, driver_version] = fields[..] else {
+                return None;
+            };
+            Some(NvidiaGpu {
+                pci_id: pci_id_from_device_id(device_id)?,
+                name: name.to_string(),
+                // Reported in MiB; "[N/A]" on some virtual GPUs
+                memory_bytes: memory.parse::<u64>().ok().map(|mib| mib * 1024 * 1024),
+                driver_version: driver_version.to_string(),
+            })
+        })
+        .collect()
+}
+
+/// CUDA version from the `nvidia-smi` banner line
+pub fn parse_cuda_version(banner: &str) -> Option<String> {
+    let (_, rest) = banner.split_once("CUDA Version:")?;
+    let version = rest.split_whitespace().next()?;
+    (version != "N/A").then(|| version.to_string())
+}
+
+/// Kernel module flavour from `/proc/driver/nvidia/version`
+pub fn parse_kernel_module(version: &str) -> Option<NvidiaKernelModule> {
+    let line = version.lines().find(|line| line.starts_with("NVRM version:"))?;
+    Some(if line.contains("Open Kernel Module") {
+        NvidiaKernelModule::Open
+    } else {
+        NvidiaKernelModule::Proprietary
+    })
+}
+
+/// Detector reading NVIDIA driver details with nvidia-smi
+pub struct NvidiaSmiDetector;
+
+impl NvidiaSmiDetector {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl Default for NvidiaSmiDetector {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl HardwareDetector for NvidiaSmiDetector {
+    fn name(&self) -> &'static str {
+        "nvidia-smi"
+    }
+
+    async fn is_available(&self) -> bool {
+        tokio::process::Command::new("which")
+            .arg("nvidia-smi")
+            .output()
+            .await
+            .map(|output| output.status.success())
+            .unwrap_or(false)
+    }
+
+    async fn execute(&self) -> Result<Output> {
+        let mut output = sandbox::output(
+            tokio::process::Command::new("nvidia-smi")
+                .args([&format!("--query-gpu={}", QUERY_FIELDS), "--format=csv,noheader,nounits"]),
+        )
+        .await
+        .map_err(|e| LxHwError::SystemCommandError { command: format!("nvidia-smi: {}", e) })?;
+        if !output.status.success() {
+            return Ok(output);
+        }
+
+        // Only the banner is kept, the process table names running programs
+        match sandbox::output(&mut tokio::process::Command::new("nvidia-smi")).await {
+            Ok(summary) => {
+                let summary = String::from_utf8_lossy(&summary.stdout);
+                if let Some(banner) = summary.lines().find(|line| line.contains("CUDA Version")) {
+                    output
+                        .stdout
+                        .extend_from_slice(format!("\n{}\n{}\n", CUDA_MARKER, banner).as_bytes());
+                }
+            }
+            Err(e) => log::debug!("nvidia-smi summary failed: {}", e),
+        }
+
+        if remote::is_local() {
+            if let Ok(version) = std::fs::read_to_string("/proc/driver/nvidia/version") {
+                output
+                    .stdout
+                    .extend_from_slice(format!("\n{}\n{}", MODULE_MARKER, version).as_bytes());
+            }
+        }
+        Ok(output)
+    }
+
+    fn parse_output(&self, output: &Output) -> Result<DetectionResult> {
+        if !output.status.success() {
+            return Ok(DetectionResult {
+                tool_name: self.name().to_string(),
+                success: false,
+                data: DetectionData::NvidiaSmi(NvidiaSmiData::default()),
+                errors: vec![DetectionError::BadExit {
+                    tool: self.name().to_string(),
+                    code: output.status.code(),
+                    stderr: String::from_utf8_lossy(&output.stderr).to_string(),
+                }],
+            });
+        }
+
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let (rest, module) = match stdout.split_once(MODULE_MARKER) {
+            Some((rest, module)) => (rest, parse_kernel_module(module)),
+            None => (stdout.as_ref(), None),
+        };
+        let (csv, cuda_version) = match rest.split_once(CUDA_MARKER) {
+            Some((csv, banner)) => (csv, parse_cuda_version(banner)),
+            None => (rest, None),
+        };
+
+        let gpus = parse_gpus(csv);
+        if gpus.is_empty() {
+            return Ok(DetectionResult {
+                tool_name: self.name().to_string(),
+                success: false,
+                data: DetectionData::NvidiaSmi(NvidiaSmiData::default()),
+                errors: vec![DetectionError::EmptyOutput { tool: self.name().to_string() }],
+            });
+        }
+
+        Ok(DetectionResult {
+            tool_name: self.name().to_string(),
+            success: true,
+            data: DetectionData::NvidiaSmi(NvidiaSmiData {
+                gpus,
+                cuda_version,
+                kernel_module: module,
+            }),
+            errors: Vec::new(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::os::unix::process::ExitStatusExt;
+
+    const BANNER: &str = "| NVIDIA-SMI 550.54.14              Driver Version: 550.54.14      CUDA Version: 12.4     |";
+
+    fn output(stdout: String) -> Output {
+        Output {
+            status: ExitStatusExt::from_raw(0),
+            stdout: stdout.into_bytes(),
+            stderr: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_parse_gpus() {
+        let stdout = format!(
+            "0x268410DE, NVIDIA GeForce RTX 4090, 24564, 550.54.14\n\
+             0x1EB810DE, Tesla T4, [N/A], 550.54.14\n\
+             \n{}\n{}\n\n{}\n{}",
+            CUDA_MARKER,
+            BANNER,
+            MODULE_MARKER,
+            "NVRM version: NVIDIA UNIX Open Kernel Module for x86_64  550.54.14  Release Build  (dvs-builder@U16-I3-B03-4-3)  Thu Feb 22 01:25:25 UTC 2024\n\
+             GCC version:  gcc version 13.2.1 20230801 (GCC)"
+        );
+        let result = NvidiaSmiDetector::new().parse_output(&output(stdout)).unwrap();
+        assert!(result.success);
+        let DetectionData::NvidiaSmi(data) = result.data else {
+            panic!("expected nvidia-smi data");
+        };
+        assert_eq!(data.cuda_version.as_deref(), Some("12.4"));
+        assert_eq!(data.kernel_module, Some(NvidiaKernelModule::Open));
+        assert_eq!(
+            data.gpus[0],
+            NvidiaGpu {
+                pci_id: "10de:2684".to_string(),
+                name: "NVIDIA GeForce RTX 4090".to_string(),
+                memory_bytes: Some(24564 * 1024 * 1024),
+                driver_version: "550.54.14".to_string(),
+            }
+        );
+        assert_eq!(data.gpus[1].pci_id, "10de:1eb8");
+        assert_eq!(data.gpus[1].memory_bytes, None);
+    }
+
+    #[test]
+    fn test_proprietary_module_and_missing_details() {
+        let proprietary = "NVRM version: NVIDIA UNIX x86_64 Kernel Module  535.154.05  Thu Dec 28 15:37:48 UTC 2023";
+        assert_eq!(parse_kernel_module(proprietary), Some(NvidiaKernelModule::Proprietary));
+        assert_eq!(parse_kernel_module(""), None);
+        assert_eq!(parse_cuda_version("| NVIDIA-SMI 470.223.02   CUDA Version: N/A |"), None);
+
+        // Without the banner or /proc the GPUs are still reported
+        let result = NvidiaSmiDetector::new()
+            .parse_output(&output(
+                "0x1C8210DE, NVIDIA GeForce GTX 1050 Ti, 4096, 470.223.02\n".into(),
+            ))
+            .unwrap();
+        let DetectionData::NvidiaSmi(data) = result.data else {
+            panic!("expected nvidia-smi data");
+        };
+        assert_eq!(data.gpus.len(), 1);
+        assert_eq!(data.cuda_version, None);
+        assert_eq!(data.kernel_module, None);
+
+        let empty = NvidiaSmiDetector::new().parse_output(&output(String::new())).unwrap();
+        assert!(!empty.success);
+    }
+}
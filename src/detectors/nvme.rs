@@ -0,0 +1,455 @@
+//! NVMe drive details from nvme-cli
+//!
+//! `nvme list -o json` lists namespaces with the model, serial and firmware
+//! revision of their controller, and `nvme smart-log -o json` reads each
+//! controller's health log. The PCIe link the controller negotiated comes
+//! from its PCI device in sysfs. The data enriches the storage devices
+//! other tools found; serials are only used to match them and are
+//! anonymized like every other serial.
+
+use super::extract::{ComponentExtractor, ExtractionContext};
+use super::{
+    remote, sandbox, DetectionData, DetectionError, DetectionResult, HardwareDetector, Privileges,
+};
+use crate::errors::{LxHwError, Result};
+use crate::hardware::{NvmeDetails, NvmeHealth, PcieLink, StorageDevice};
+use async_trait::async_trait;
+use serde::Deserialize;
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::process::Output;
+use std::time::Duration;
+
+const SMART_LOG_MARKER: &str = "--- SMART LOG ";
+const PCIE_LINK_MARKER: &str = "--- PCIE LINK ---";
+
+/// Link attributes read from a controller's PCI device
+const LINK_ATTRIBUTES: &[&str] =
+    &["current_link_speed", "current_link_width", "max_link_speed", "max_link_width"];
+
+/// Namespaces found by nvme-cli
+#[derive(Debug, Clone, Default)]
+pub struct NvmeData {
+    pub namespaces: Vec<NvmeNamespace>,
+}
+
+/// One namespace with its controller's identity and health
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct NvmeNamespace {
+    /// Block device, e.g. "/dev/nvme0n1"
+    pub device_path: String,
+    pub model: Option<String>,
+    pub serial: Option<String>,
+    pub firmware: Option<String>,
+    pub namespace: Option<u32>,
+    pub size_bytes: u64,
+    pub sector_size: Option<u32>,
+    pub health: Option<NvmeHealth>,
+    pub pcie_link: Option<PcieLink>,
+}
+
+impl NvmeNamespace {
+    /// Controller the namespace belongs to, e.g. "nvme0" for "/dev/nvme0n1"
+    pub fn controller(&self) -> Option<&str> {
+        controller_name(&self.device_path)
+    }
+}
+
+impl ComponentExtractor for NvmeData {
+    fn storage(&self, ctx: &mut ExtractionContext) -> Result<Vec<StorageDevice>> {
+        let mut storage_devices = Vec::new();
+        for namespace in &self.namespaces {
+            let anonymized_serial = match &namespace.serial {
+                Some(serial) => ctx.anonymize(serial)?,
+                None => "unknown".to_string(),
+            };
+            storage_devices.push(StorageDevice {
+                anonymized_serial,
+                device_type: "NVMe SSD".to_string(),
+                size_bytes: namespace.size_bytes,
+                model: namespace.model.clone().unwrap_or("Unknown Storage".to_string()),
+                vendor: None,
+                interface: Some("NVMe".to_string()),
+                nvme: Some(NvmeDetails {
+                    firmware: namespace.firmware.clone(),
+                    namespace: namespace.namespace,
+                    sector_size: namespace.sector_size,
+                    pcie_link: namespace.pcie_link.clone(),
+                    health: namespace.health.clone(),
+                }),
+                health: None,
+                topology: None,
+            });
+        }
+        Ok(storage_devices)
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct NvmeList {
+    #[serde(default)]
+    devices: Vec<ListedDevice>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct ListedDevice {
+    device_path: String,
+    model_number: Option<String>,
+    serial_number: Option<String>,
+    firmware: Option<String>,
+    name_space: Option<u32>,
+    #[serde(default)]
+    physical_size: u64,
+    sector_size: Option<u32>,
+}
+
+/// Parse `nvme list -o json` output
+pub fn parse_list(json: &str) -> serde_json::Result<Vec<NvmeNamespace>> {
+    let list: NvmeList = serde_json::from_str(json)?;
+    let trimmed = |value: Option<String>| {
+        value.map(|value| value.trim().to_string()).filter(|value| !value.is_empty())
+    };
+    Ok(list
+        .devices
+        .into_iter()
+        .map(|device| NvmeNamespace {
+            device_path: device.device_path,
+            model: trimmed(device.model_number),
+            serial: trimmed(device.serial_number),
+            firmware: trimmed(device.firmware),
+            namespace: device.name_space,
+            size_bytes: device.physical_size,
+            sector_size: device.sector_size,
+            health: None,
+            pcie_link: None,
+        })
+        .collect())
+}
+
+/// Parse `nvme smart-log -o json` output
+///
+/// nvme-cli 1.x prints every field as a number; 2.x nests the critical
+/// warning bits in an object with the raw value under "value".
+pub fn parse_smart_log(json: &str) -> Option<NvmeHealth> {
+    let log: Value = serde_json::from_str(json).ok()?;
+    let number = |key: &str| {
+        let value = log.get(key)?;
+        value.as_u64().or_else(|| value.get("value")?.as_u64())
+    };
+    let percent = |key: &str| number(key).and_then(|value| u8::try_from(value).ok());
+    Some(NvmeHealth {
+        critical_warning: percent("critical_warning").unwrap_or(0),
+        // The composite temperature is reported in Kelvin
+        temperature_celsius: number("temperature").map(|kelvin| kelvin as i32 - 273),
+        available_spare_percent: percent("avail_spare"),
+        percent_used: percent("percent_used").or_else(|| percent("percentage_used")),
+        power_on_hours: number("power_on_hours"),
+        unsafe_shutdowns: number("unsafe_shutdowns"),
+        media_errors: number("media_errors"),
+    })
+}
+
+/// Controller of a namespace block device, e.g. "nvme0" for "/dev/nvme0n1"
+fn controller_name(device_path: &str) -> Option<&str> {
+    let name = device_path.rsplit('/').next()?;
+    let rest = name.strip_prefix("nvme")?;
+    let digits = rest.find(|c: char| !c.is_ascii_digit()).unwrap_or(rest.len());
+    (digits > 0).then(|| &name[..4 + digits])
+}
+
+/// Read the PCIe link attributes of NVMe controllers
+///
+/// One "controller attribute value" line per attribute, the layout
+/// [`parse_pcie_links`] reads back.
+fn read_pcie_links(nvme_class: &Path, controllers: &[&str]) -> String {
+    let mut lines = String::new();
+    for controller in controllers {
+        let device = nvme_class.join(controller).join("device");
+        for attribute in LINK_ATTRIBUTES {
+            if let Ok(value) = std::fs::read_to_string(device.join(attribute)) {
+                lines.push_str(&format!("{} {} {}\n", controller, attribute, value.trim()));
+            }
+        }
+    }
+    lines
+}
+
+/// Parse the link attributes [`read_pcie_links`] wrote, by controller
+fn parse_pcie_links(section: &str) -> BTreeMap<String, PcieLink> {
+    let mut attributes: BTreeMap<&str, BTreeMap<&str, &str>> = BTreeMap::new();
+    for line in section.lines() {
+        let Some((controller, rest)) = line.split_once(' ') else {
+            continue;
+        };
+        if let Some((attribute, value)) = rest.split_once(' ') {
+            attributes.entry(controller).or_default().insert(attribute, value.trim());
+        }
+    }
+
+    attributes
+        .into_iter()
+        .filter_map(|(controller, values)| {
+            let speed = |key: &str| {
+                values.get(key).filter(|speed| !speed.starts_with("Unknown")).map(|s| s.to_string())
+            };
+            let width = |key: &str| values.get(key).and_then(|width| width.parse().ok());
+            let link = PcieLink {
+                speed: speed("current_link_speed")?,
+                width: width("current_link_width")?,
+                max_speed: speed("max_link_speed"),
+                max_width: width("max_link_width"),
+            };
+            Some((controller.to_string(), link))
+        })
+        .collect()
+}
+
+/// Detector reading NVMe namespaces, health logs and PCIe links
+pub struct NvmeDetector;
+
+impl NvmeDetector {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl Default for NvmeDetector {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl HardwareDetector for NvmeDetector {
+    fn name(&self) -> &'static str {
+        "nvme"
+    }
+
+    async fn is_available(&self) -> bool {
+        tokio::process::Command::new("which")
+            .arg("nvme")
+            .output()
+            .await
+            .map(|output| output.status.success())
+            .unwrap_or(false)
+    }
+
+    async fn execute(&self) -> Result<Output> {
+        let mut output =
+            sandbox::output(tokio::process::Command::new("nvme").args(["list", "-o", "json"]))
+                .await
+                .map_err(|e| LxHwError::SystemCommandError { command: format!("nvme: {}", e) })?;
+        if !output.status.success() {
+            return Ok(output);
+        }
+
+        let namespaces = parse_list(&String::from_utf8_lossy(&output.stdout)).unwrap_or_default();
+        let mut controllers: Vec<&str> =
+            namespaces.iter().filter_map(NvmeNamespace::controller).collect();
+        controllers.dedup();
+
+        // Health logs need admin commands, usually root; a missing log only
+        // leaves out the health data
+        for controller in &controllers {
+            let log = sandbox::output(tokio::process::Command::new("nvme").args([
+                "smart-log",
+                &format!("/dev/{}", controller),
+                "-o",
+                "json",
+            ]))
+            .await;
+            match log {
+                Ok(log) if log.status.success() => {
+                    output.stdout.extend_from_slice(
+                        format!("\n{}{} ---\n", SMART_LOG_MARKER, controller).as_bytes(),
+                    );
+                    output.stdout.extend_from_slice(&log.stdout);
+                }
+                Ok(log) => output.stderr.extend_from_slice(&log.stderr),
+                Err(e) => log::debug!("nvme smart-log {} failed: {}", controller, e),
+            }
+        }
+
+        if remote::is_local() {
+            let links = read_pcie_links(Path::new("/sys/class/nvme"), &controllers);
+            if !links.is_empty() {
+                output.stdout.extend_from_slice(format!("\n{}\n", PCIE_LINK_MARKER).as_bytes());
+                output.stdout.extend_from_slice(links.as_bytes());
+            }
+        }
+        Ok(output)
+    }
+
+    fn timeout(&self) -> Duration {
+        Duration::from_secs(20)
+    }
+
+    fn privileges(&self) -> Privileges {
+        // NVMe admin commands need root
+        Privileges::RootRequired
+    }
+
+    fn parse_output(&self, output: &Output) -> Result<DetectionResult> {
+        if !output.status.success() {
+            return Ok(DetectionResult {
+                tool_name: self.name().to_string(),
+                success: false,
+                data: DetectionData::Nvme(NvmeData::default()),
+                errors: vec![DetectionError::BadExit {
+                    tool: self.name().to_string(),
+                    code: output.status.code(),
+                    stderr: String::from_utf8_lossy(&output.stderr).to_string(),
+                }],
+            });
+        }
+
+        if output.stdout.is_empty() {
+            return Ok(DetectionResult {
+                tool_name: self.name().to_string(),
+                success: false,
+                data: DetectionData::Nvme(NvmeData::default()),
+                errors: vec![DetectionError::EmptyOutput { tool: self.name().to_string() }],
+            });
+        }
+
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let mut sections = stdout.split("\n--- ");
+        let list = sections.next().unwrap_or_default();
+        let mut namespaces = match parse_list(list) {
+            Ok(namespaces) => namespaces,
+            Err(e) => {
+                return Ok(DetectionResult {
+                    tool_name: self.name().to_string(),
+                    success: false,
+                    data: DetectionData::Nvme(NvmeData::default()),
+                    errors: vec![DetectionError::json("JSON parsing failed", &e)],
+                })
+            }
+        };
+
+        let mut health = BTreeMap::new();
+        let mut links = BTreeMap::new();
+        for section in sections {
+            let section = format!("--- {}", section);
+            if let Some(rest) = section.strip_prefix(SMART_LOG_MARKER) {
+                if let Some((controller, log)) = rest.split_once(" ---\n") {
+                    health.extend(parse_smart_log(log).map(|log| (controller.to_string(), log)));
+                }
+            } else if let Some(rest) = section.strip_prefix(PCIE_LINK_MARKER) {
+                links = parse_pcie_links(rest);
+            }
+        }
+        for namespace in &mut namespaces {
+            if let Some(controller) = namespace.controller().map(str::to_string) {
+                namespace.health = health.get(&controller).cloned();
+                namespace.pcie_link = links.get(&controller).cloned();
+            }
+        }
+
+        Ok(DetectionResult {
+            tool_name: self.name().to_string(),
+            success: true,
+            data: DetectionData::Nvme(NvmeData { namespaces }),
+            errors: Vec::new(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::os::unix::process::ExitStatusExt;
+
+    const LIST: &str = r#"{
+      "Devices" : [
+        {
+          "NameSpace" : 1,
+          "DevicePath" : "/dev/nvme0n1",
+          "Firmware" : "2B2QEXM7",
+          "Index" : 0,
+          "ModelNumber" : "Samsung SSD 970 EVO Plus 1TB            ",
+          "SerialNumber" : "S4EWNX0N123456      ",
+          "UsedBytes" : 612374528000,
+          "MaximumLBA" : 1953525168,
+          "PhysicalSize" : 1000204886016,
+          "SectorSize" : 512
+        }
+      ]
+    }"#;
+
+    /// nvme-cli 2.x layout, with the critical warning as an object
+    const SMART_LOG: &str = r#"{
+      "critical_warning" : {"value" : 0, "available_spare" : 0},
+      "temperature" : 310,
+      "avail_spare" : 100,
+      "spare_thresh" : 10,
+      "percent_used" : 3,
+      "power_cycles" : 1480,
+      "power_on_hours" : 5321,
+      "unsafe_shutdowns" : 97,
+      "media_errors" : 0
+    }"#;
+
+    fn output(stdout: String) -> Output {
+        Output {
+            status: ExitStatusExt::from_raw(0),
+            stdout: stdout.into_bytes(),
+            stderr: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_parse_list_with_health_and_link() {
+        let stdout = format!(
+            "{}\n{}nvme0 ---\n{}\n{}\n{}",
+            LIST,
+            SMART_LOG_MARKER,
+            SMART_LOG,
+            PCIE_LINK_MARKER,
+            "nvme0 current_link_speed 8.0 GT/s PCIe\nnvme0 current_link_width 2\n\
+             nvme0 max_link_speed 8.0 GT/s PCIe\nnvme0 max_link_width 4\n"
+        );
+        let result = NvmeDetector::new().parse_output(&output(stdout)).unwrap();
+        assert!(result.success);
+        let DetectionData::Nvme(data) = result.data else {
+            panic!("expected NVMe data");
+        };
+
+        let namespace = &data.namespaces[0];
+        assert_eq!(namespace.controller(), Some("nvme0"));
+        assert_eq!(namespace.model.as_deref(), Some("Samsung SSD 970 EVO Plus 1TB"));
+        assert_eq!(namespace.serial.as_deref(), Some("S4EWNX0N123456"));
+        assert_eq!(namespace.firmware.as_deref(), Some("2B2QEXM7"));
+        assert_eq!(namespace.size_bytes, 1_000_204_886_016);
+
+        let health = namespace.health.as_ref().unwrap();
+        assert_eq!(health.temperature_celsius, Some(37));
+        assert_eq!(health.percent_used, Some(3));
+        assert_eq!(health.power_on_hours, Some(5321));
+        assert_eq!(health.critical_warning, 0);
+
+        let link = namespace.pcie_link.as_ref().unwrap();
+        assert_eq!((link.speed.as_str(), link.width), ("8.0 GT/s PCIe", 2));
+        assert_eq!(link.max_width, Some(4));
+    }
+
+    #[test]
+    fn test_list_without_health_log() {
+        let result = NvmeDetector::new().parse_output(&output(LIST.to_string())).unwrap();
+        let DetectionData::Nvme(data) = result.data else {
+            panic!("expected NVMe data");
+        };
+        assert_eq!(data.namespaces[0].health, None);
+        assert_eq!(data.namespaces[0].pcie_link, None);
+
+        // nvme-cli 1.x prints the warning as a plain number
+        let health = parse_smart_log(r#"{"critical_warning": 4, "temperature": 300}"#).unwrap();
+        assert_eq!(health.critical_warning, 4);
+        assert_eq!(health.temperature_celsius, Some(27));
+        assert_eq!(controller_name("/dev/nvme12n3"), Some("nvme12"));
+        assert_eq!(controller_name("/dev/sda"), None);
+    }
+}
@@ -0,0 +1,228 @@
+//! Distribution package availability checks for recommendations
+//!
+//! Recommendations suggest install commands with package names that can go
+//! stale or differ between releases. When running on the machine being
+//! analyzed, each package is looked up in the configured repositories and
+//! commands referring to packages that cannot be installed are flagged
+//! instead of being offered as working advice.
+
+use crate::detectors::kernel::UserRecommendations;
+use std::collections::HashMap;
+use std::path::Path;
+use std::process::Command;
+
+/// Whether a package can be installed from the configured repositories
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PackageAvailability {
+    Available,
+    Unavailable,
+    /// The package manager could not be queried
+    Unknown,
+}
+
+/// Package manager of the running distribution
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PackageManager {
+    Apt,
+    Dnf,
+    Pacman,
+}
+
+impl PackageManager {
+    /// Detect the package manager from distribution release files
+    pub fn detect() -> Option<Self> {
+        if Path::new("/etc/debian_version").exists() {
+            Some(Self::Apt)
+        } else if Path::new("/etc/redhat-release").exists() {
+            Some(Self::Dnf)
+        } else if Path::new("/etc/arch-release").exists() {
+            Some(Self::Pacman)
+        } else {
+            None
+        }
+    }
+
+    /// Look a package up in the configured repositories
+    pub fn query(self, package: &str) -> PackageAvailability {
+        let (program, args): (&str, &[&str]) = match self {
+            Self::Apt => ("apt-cache", &["policy"]),
+            Self::Dnf => ("dnf", &["-q", "info"]),
+            Self::Pacman => ("pacman", &["-Si"]),
+        };
+        let Ok(output) = Command::new(program).args(args).arg(package).output() else {
+            return PackageAvailability::Unknown;
+        };
+
+        match self {
+            // apt-cache policy succeeds for unknown packages and prints nothing
+            Self::Apt if output.status.success() => {
+                parse_apt_policy(&String::from_utf8_lossy(&output.stdout))
+            }
+            Self::Apt => PackageAvailability::Unknown,
+            Self::Dnf | Self::Pacman if output.status.success() => PackageAvailability::Available,
+            Self::Dnf | Self::Pacman => PackageAvailability::Unavailable,
+        }
+    }
+}
+
+/// Cached package lookups for one package manager
+pub struct PackageChecker {
+    manager: PackageManager,
+    cache: HashMap<String, PackageAvailability>,
+}
+
+impl PackageChecker {
+    pub fn new(manager: PackageManager) -> Self {
+        Self { manager, cache: HashMap::new() }
+    }
+
+    /// Checker for the running distribution, if its package manager is known
+    pub fn detect() -> Option<Self> {
+        PackageManager::detect().map(Self::new)
+    }
+
+    pub fn availability(&mut self, package: &str) -> PackageAvailability {
+        let manager = self.manager;
+        *self.cache.entry(package.to_string()).or_insert_with(|| manager.query(package))
+    }
+}
+
+/// Availability from `apt-cache policy` output
+pub fn parse_apt_policy(output: &str) -> PackageAvailability {
+    let candidate =
+        output.lines().find_map(|line| line.trim().strip_prefix("Candidate:")).map(str::trim);
+    match candidate {
+        Some("(none)") | None => PackageAvailability::Unavailable,
+        Some(_) => PackageAvailability::Available,
+    }
+}
+
+/// Packages a shell command would install, if it is an install command
+///
+/// Recognizes `apt`/`apt-get install`, `dnf`/`yum install` and `pacman -S`,
+/// with or without `sudo`.
+pub fn install_targets(command: &str) -> Option<Vec<&str>> {
+    let mut words = command.split_whitespace().peekable();
+    if words.peek() == Some(&"sudo") {
+        words.next();
+    }
+    let is_install = match (words.next()?, words.next()?) {
+        ("apt" | "apt-get" | "dnf" | "yum", "install") => true,
+        ("pacman", flags) => flags.starts_with("-S") && !flags.contains(['s', 'i', 'c']),
+        _ => false,
+    };
+    is_install.then(|| words.filter(|word| !word.starts_with('-')).collect())
+}
+
+impl UserRecommendations {
+    /// Flag install commands that reference packages which cannot be installed
+    ///
+    /// Such commands are commented out with a note naming the missing
+    /// packages, and each package is listed once in `unavailable_packages`.
+    /// Packages whose availability is unknown are left alone.
+    pub fn verify_packages(&mut self, mut lookup: impl FnMut(&str) -> PackageAvailability) {
+        let mut unavailable = Vec::new();
+        let command_lists = self
+            .kernel_upgrades
+            .iter_mut()
+            .map(|upgrade| &mut upgrade.upgrade_method)
+            .chain(self.module_actions.iter_mut().map(|action| &mut action.commands))
+            .chain(self.configuration_actions.iter_mut().map(|action| &mut action.commands));
+
+        for commands in command_lists {
+            let mut verified = Vec::with_capacity(commands.len());
+            for command in commands.drain(..) {
+                let missing: Vec<String> = install_targets(&command)
+                    .unwrap_or_default()
+                    .into_iter()
+                    .filter(|package| lookup(package) == PackageAvailability::Unavailable)
+                    .map(String::from)
+                    .collect();
+                if missing.is_empty() {
+                    verified.push(command);
+                    continue;
+                }
+                verified.push(format!(
+                    "# Not available in the configured repositories: {}",
+                    missing.join(", ")
+                ));
+                verified.push(format!("# {}", command));
+                unavailable.extend(missing);
+            }
+            *commands = verified;
+        }
+
+        unavailable.sort();
+        unavailable.dedup();
+        for package in &unavailable {
+            self.general_advice.push(format!(
+                "Package '{}' is not available from the configured repositories; \
+                 it may have been renamed or need an additional repository.",
+                package
+            ));
+        }
+        self.unavailable_packages.extend(unavailable);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::detectors::kernel::KernelUpgradeRecommendation;
+
+    #[test]
+    fn test_install_targets() {
+        assert_eq!(
+            install_targets("sudo apt install -y linux-generic-hwe-24.04"),
+            Some(vec!["linux-generic-hwe-24.04"])
+        );
+        assert_eq!(install_targets("sudo pacman -Syu linux"), Some(vec!["linux"]));
+        assert_eq!(install_targets("pacman -Ss linux"), None);
+        assert_eq!(install_targets("sudo dnf update kernel"), None);
+        assert_eq!(install_targets("# sudo dnf install kernel-ml"), None);
+    }
+
+    #[test]
+    fn test_parse_apt_policy() {
+        let installed = "linux-generic-hwe-22.04:\n  Installed: (none)\n  Candidate: 6.8.0.45\n";
+        assert_eq!(parse_apt_policy(installed), PackageAvailability::Available);
+        let no_candidate = "nvidia-driver-390:\n  Installed: (none)\n  Candidate: (none)\n";
+        assert_eq!(parse_apt_policy(no_candidate), PackageAvailability::Unavailable);
+        assert_eq!(parse_apt_policy(""), PackageAvailability::Unavailable);
+    }
+
+    #[test]
+    fn test_verify_packages_flags_unavailable() {
+        let mut recommendations = UserRecommendations::new("6.1.0".to_string());
+        recommendations.kernel_upgrades.push(KernelUpgradeRecommendation {
+            device_id: "10de:1180".to_string(),
+            current_kernel: "6.1.0".to_string(),
+            recommended_kernel: "6.8".to_string(),
+            reason: "test".to_string(),
+            upgrade_method: vec![
+                "sudo dnf update kernel".to_string(),
+                "sudo dnf install kernel-ml".to_string(),
+                "sudo dnf install kernel-devel".to_string(),
+            ],
+            estimated_support_probability: 50,
+            support_status: None,
+        });
+
+        recommendations.verify_packages(|package| match package {
+            "kernel-ml" => PackageAvailability::Unavailable,
+            _ => PackageAvailability::Available,
+        });
+
+        assert_eq!(
+            recommendations.kernel_upgrades[0].upgrade_method,
+            vec![
+                "sudo dnf update kernel",
+                "# Not available in the configured repositories: kernel-ml",
+                "# sudo dnf install kernel-ml",
+                "sudo dnf install kernel-devel",
+            ]
+        );
+        assert_eq!(recommendations.unavailable_packages, vec!["kernel-ml"]);
+        assert_eq!(recommendations.general_advice.len(), 1);
+    }
+}
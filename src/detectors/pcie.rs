@@ -0,0 +1,200 @@
+//! PCI Express link state and resizable BAR from sysfs
+//!
+//! Every PCIe function under `/sys/bus/pci/devices` has the negotiated and
+//! maximum speed and width of its link in `current_link_*` and
+//! `max_link_*`. A card in a slot with fewer lanes, behind a slower bridge or
+//! with a bad riser trains to a lower link than it supports, which lspci only
+//! shows with `-vv` as root. Functions with a resizable BAR have a
+//! `resourceN_resize` file holding the sizes the BAR supports; the current
+//! size comes from the matching line of `resource`.
+
+use crate::hardware::{PcieDeviceLink, PcieLink, ResizableBar};
+use std::path::Path;
+
+/// Class code prefix of PCI bridges, whose links are listed by the device below
+const BRIDGE_CLASS: &str = "0604";
+
+/// Class code prefix of display controllers
+const DISPLAY_CLASS: &str = "03";
+
+/// Number of memory BARs of a PCI function
+const BAR_COUNT: u8 = 6;
+
+/// Read the PCIe link of every function under a `bus/pci/devices` directory
+pub fn read_pcie_links(root: &Path) -> Vec<PcieDeviceLink> {
+    let Ok(entries) = std::fs::read_dir(root) else {
+        return Vec::new();
+    };
+    let mut dirs: Vec<_> = entries.flatten().map(|entry| entry.path()).collect();
+    dirs.sort();
+    dirs.iter().filter_map(|dir| read_device(dir)).collect()
+}
+
+fn read_device(dir: &Path) -> Option<PcieDeviceLink> {
+    let read = |file: &str| {
+        std::fs::read_to_string(dir.join(file)).ok().map(|value| value.trim().to_string())
+    };
+    let hex = |file: &str| {
+        let value = read(file)?.to_ascii_lowercase();
+        Some(value.strip_prefix("0x").unwrap_or(&value).to_string())
+    };
+    let class_code = hex("class")?;
+    if class_code.starts_with(BRIDGE_CLASS) {
+        return None;
+    }
+    // Conventional PCI and virtual functions report "Unknown" or no link
+    let speed = |file: &str| read(file).filter(|speed| !speed.starts_with("Unknown"));
+    let width = |file: &str| read(file)?.parse::<u8>().ok().filter(|width| *width > 0);
+    let link = PcieLink {
+        speed: speed("current_link_speed")?,
+        width: width("current_link_width")?,
+        max_speed: speed("max_link_speed"),
+        max_width: width("max_link_width"),
+    };
+
+    let generation = generation_of(&link.speed);
+    let max_generation = link.max_speed.as_deref().and_then(generation_of);
+    let narrow = link.max_width.is_some_and(|max| link.width < max);
+    // GPUs drop their link speed when idle and raise it under load
+    let slow = !class_code.starts_with(DISPLAY_CLASS)
+        && matches!((generation, max_generation), (Some(current), Some(max)) if current < max);
+    Some(PcieDeviceLink {
+        pci_id: format!("{}:{}", hex("vendor")?, hex("device")?),
+        class_code,
+        degraded: narrow || slow,
+        generation,
+        max_generation,
+        resizable_bar: read_resizable_bar(dir),
+        link,
+    })
+}
+
+/// PCIe generation of a sysfs link speed such as "16.0 GT/s PCIe"
+fn generation_of(speed: &str) -> Option<u8> {
+    let rate: f32 = speed.split_whitespace().next()?.parse().ok()?;
+    // Each generation doubles the transfer rate from 8 GT/s on
+    Some(match rate {
+        r if r < 5.0 => 1,
+        r if r < 8.0 => 2,
+        r if r < 16.0 => 3,
+        r if r < 32.0 => 4,
+        r if r < 64.0 => 5,
+        _ => 6,
+    })
+}
+
+/// First resizable BAR of a function with its current and largest size
+fn read_resizable_bar(dir: &Path) -> Option<ResizableBar> {
+    let resources = std::fs::read_to_string(dir.join("resource")).ok()?;
+    (0..BAR_COUNT).find_map(|bar| {
+        let sizes = std::fs::read_to_string(dir.join(format!("resource{}_resize", bar))).ok()?;
+        // Bit n of the mask is a supported size of 2^n MiB
+        let sizes = u64::from_str_radix(sizes.trim().trim_start_matches("0x"), 16).ok()?;
+        if sizes == 0 {
+            return None;
+        }
+        let max_size_bytes = (1u64 << 20) << (63 - sizes.leading_zeros());
+        let line = resources.lines().nth(bar as usize)?;
+        let mut fields = line
+            .split_whitespace()
+            .map(|field| u64::from_str_radix(field.trim_start_matches("0x"), 16).ok());
+        let (start, end) = (fields.next()??, fields.next()??);
+        (end > start).then(|| ResizableBar { bar, size_bytes: end - start + 1, max_size_bytes })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn add_device(root: &Path, address: &str, files: &[(&str, &str)]) {
+        let dir = root.join(address);
+        std::fs::create_dir_all(&dir).unwrap();
+        for (file, content) in files {
+            std::fs::write(dir.join(file), format!("{}\n", content)).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_read_pcie_links() {
+        let root = TempDir::new().unwrap();
+        let link = |speed, width, max_speed, max_width| {
+            [
+                ("current_link_speed", speed),
+                ("current_link_width", width),
+                ("max_link_speed", max_speed),
+                ("max_link_width", max_width),
+            ]
+        };
+        // Idle GPU at 2.5 GT/s with a 256 MiB BAR out of 16 GiB
+        let gpu = [
+            [("vendor", "0x10de"), ("device", "0x2684"), ("class", "0x030000")].as_slice(),
+            &link("2.5 GT/s PCIe", "16", "16.0 GT/s PCIe", "16"),
+            &[
+                (
+                    "resource",
+                    "0x00000000a0000000 0x00000000a0ffffff 0x0000000000040200\n\
+                     0x0000006000000000 0x000000600fffffff 0x000000000014220c",
+                ),
+                ("resource1_resize", "0000000000007f00"),
+            ],
+        ]
+        .concat();
+        add_device(root.path(), "0000:01:00.0", &gpu);
+        // Gen4 NVMe drive trained at Gen3 x2
+        let nvme = [
+            [("vendor", "0x144d"), ("device", "0xa80a"), ("class", "0x010802")].as_slice(),
+            &link("8.0 GT/s PCIe", "2", "16.0 GT/s PCIe", "4"),
+        ]
+        .concat();
+        add_device(root.path(), "0000:02:00.0", &nvme);
+        let bridge = [
+            [("vendor", "0x8086"), ("device", "0x7a38"), ("class", "0x060400")].as_slice(),
+            &link("16.0 GT/s PCIe", "4", "16.0 GT/s PCIe", "4"),
+        ]
+        .concat();
+        add_device(root.path(), "0000:00:1c.0", &bridge);
+        add_device(
+            root.path(),
+            "0000:00:1f.0",
+            &[
+                ("vendor", "0x8086"),
+                ("device", "0x7a06"),
+                ("class", "0x060100"),
+                ("current_link_speed", "Unknown"),
+                ("current_link_width", "0"),
+            ],
+        );
+
+        let links = read_pcie_links(root.path());
+        assert_eq!(links.len(), 2);
+        assert_eq!(
+            links[0],
+            PcieDeviceLink {
+                pci_id: "10de:2684".to_string(),
+                class_code: "030000".to_string(),
+                link: PcieLink {
+                    speed: "2.5 GT/s PCIe".to_string(),
+                    width: 16,
+                    max_speed: Some("16.0 GT/s PCIe".to_string()),
+                    max_width: Some(16),
+                },
+                generation: Some(1),
+                max_generation: Some(4),
+                degraded: false,
+                resizable_bar: Some(ResizableBar {
+                    bar: 1,
+                    size_bytes: 256 << 20,
+                    max_size_bytes: 16 << 30,
+                }),
+            }
+        );
+        assert!(!links[0].resizable_bar.as_ref().unwrap().is_enabled());
+
+        let nvme = &links[1];
+        assert_eq!((nvme.generation, nvme.max_generation), (Some(3), Some(4)));
+        assert!(nvme.degraded);
+        assert_eq!(nvme.resizable_bar, None);
+    }
+}
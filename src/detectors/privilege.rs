@@ -0,0 +1,290 @@
+//! Privileges of detection tools, and running tools that lack them as root
+//!
+//! Several tools only see everything with capabilities an ordinary user does
+//! not hold:
+//!
+//! - dmidecode: `/sys/firmware/dmi/tables` is readable by root only
+//!   (`CAP_DAC_READ_SEARCH`)
+//! - lshw and smartctl: raw SCSI and ATA commands (`CAP_SYS_RAWIO`,
+//!   `CAP_SYS_ADMIN`)
+//! - lspci and nvme: extended PCI configuration space and NVMe admin
+//!   commands (`CAP_SYS_ADMIN`)
+//! - inxi: its sudo helpers, which need all of the above
+//!
+//! The capabilities are compared with the effective set of this process.
+//! With `--escalate sudo` or `--escalate pkexec`, only the tools lacking
+//! some are run through the helper, which asks the user to authenticate;
+//! the rest of the program keeps running unprivileged. Tools that ran
+//! without what they need, and tools that were escalated, are recorded in
+//! the report metadata.
+
+use crate::errors::{LxHwError, Result};
+use crate::hardware::{DegradedTool, PrivilegeStatus};
+use clap::ValueEnum;
+use std::process::Output;
+use std::sync::OnceLock;
+use tokio::process::Command;
+use tokio::sync::Mutex;
+
+/// Escalation helper used for tool execution, set once by [`enable`]
+static ESCALATION: OnceLock<Escalation> = OnceLock::new();
+
+/// Held while an escalated tool runs, so concurrent detectors do not
+/// prompt for authentication at the same time
+static PROMPT: Mutex<()> = Mutex::const_new(());
+
+/// Capabilities each tool program needs for a complete result
+const TOOL_CAPABILITIES: &[(&str, &[Capability])] = &[
+    ("dmidecode", &[Capability::DacReadSearch]),
+    ("lshw", &[Capability::SysRawio, Capability::SysAdmin]),
+    ("smartctl", &[Capability::SysRawio, Capability::SysAdmin]),
+    ("lspci", &[Capability::SysAdmin]),
+    ("nvme", &[Capability::SysAdmin]),
+    ("inxi", &[Capability::DacReadSearch, Capability::SysRawio, Capability::SysAdmin]),
+];
+
+/// Linux capabilities detection tools depend on
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Capability {
+    DacReadSearch,
+    SysRawio,
+    SysAdmin,
+}
+
+impl Capability {
+    /// Bit of the capability in `/proc/<pid>/status` capability masks
+    fn bit(self) -> u32 {
+        match self {
+            Self::DacReadSearch => 2,
+            Self::SysRawio => 17,
+            Self::SysAdmin => 21,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::DacReadSearch => "CAP_DAC_READ_SEARCH",
+            Self::SysRawio => "CAP_SYS_RAWIO",
+            Self::SysAdmin => "CAP_SYS_ADMIN",
+        }
+    }
+}
+
+/// Helper that runs tools as root
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Escalation {
+    /// sudo, authenticating once in the terminal before detection starts
+    Sudo,
+    /// pkexec, authenticating through the polkit agent for each tool
+    Pkexec,
+}
+
+impl Escalation {
+    pub fn helper(self) -> &'static str {
+        match self {
+            Self::Sudo => "sudo",
+            Self::Pkexec => "pkexec",
+        }
+    }
+
+    /// Command running `tool` through the helper, keeping its environment
+    /// overrides, which sudo and pkexec would otherwise reset
+    fn command(self, tool: &Command) -> Command {
+        let tool = tool.as_std();
+        let mut command = Command::new(self.helper());
+        if self == Self::Sudo {
+            command.arg("--");
+        }
+        let envs: Vec<String> = tool
+            .get_envs()
+            .filter_map(|(key, value)| {
+                Some(format!("{}={}", key.to_string_lossy(), value?.to_string_lossy()))
+            })
+            .collect();
+        if !envs.is_empty() {
+            command.arg("env").args(envs);
+        }
+        command.arg(tool.get_program()).args(tool.get_args());
+        command
+    }
+}
+
+/// Run tools lacking privileges through `escalation` from now on
+///
+/// sudo authenticates here, so that concurrent tools find its credentials
+/// cached instead of each asking for a password.
+pub async fn enable(escalation: Escalation) -> Result<()> {
+    let helper = escalation.helper();
+    if escalation == Escalation::Sudo {
+        let status = Command::new(helper).arg("-v").status().await.map_err(|e| {
+            LxHwError::SystemCommandError {
+                command: format!("--escalate sudo requires sudo: {}", e),
+            }
+        })?;
+        if !status.success() {
+            return Err(LxHwError::ConfigError(
+                "--escalate sudo: authentication failed; run without --escalate to detect unprivileged"
+                    .to_string(),
+            ));
+        }
+    }
+    let _ = ESCALATION.set(escalation);
+    Ok(())
+}
+
+/// The helper tools lacking privileges run through, if escalation is enabled
+pub fn active() -> Option<Escalation> {
+    ESCALATION.get().copied()
+}
+
+/// Run `command` as root through the active helper if its program lacks
+/// capabilities it needs; None when it should run as is
+pub async fn escalated_output(command: &Command) -> Option<std::io::Result<Output>> {
+    let escalation = active()?;
+    let program = command.as_std().get_program().to_string_lossy().into_owned();
+    if missing_capabilities(&program, effective_capabilities()).is_empty() {
+        return None;
+    }
+
+    let _prompt = PROMPT.lock().await;
+    log::info!(detector = program.as_str(); "Running {} with {}", program, escalation.helper());
+    Some(escalation.command(command).output().await)
+}
+
+/// Capabilities `program` needs that the `effective` mask lacks
+pub fn missing_capabilities(program: &str, effective: u64) -> Vec<Capability> {
+    TOOL_CAPABILITIES
+        .iter()
+        .find(|(tool, _)| *tool == program)
+        .map(|(_, capabilities)| capabilities.to_vec())
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|capability| effective & (1 << capability.bit()) == 0)
+        .collect()
+}
+
+/// Effective capability mask of this process; tools in the sandbox run
+/// with none
+pub fn effective_capabilities() -> u64 {
+    if super::sandbox::active().is_some() {
+        return 0;
+    }
+    std::fs::read_to_string("/proc/self/status")
+        .ok()
+        .and_then(|status| parse_effective_capabilities(&status))
+        .unwrap_or(0)
+}
+
+/// The `CapEff` mask from the contents of `/proc/<pid>/status`
+fn parse_effective_capabilities(status: &str) -> Option<u64> {
+    let mask = status.lines().find_map(|line| line.strip_prefix("CapEff:"))?;
+    u64::from_str_radix(mask.trim(), 16).ok()
+}
+
+/// Privilege status recorded in report metadata
+///
+/// `tools` pairs the program of each tool that ran with whether it reported
+/// permission errors. None when every tool had what it needed and
+/// escalation was not enabled.
+pub fn status(tools: &[(&str, bool)]) -> Option<PrivilegeStatus> {
+    status_with(tools, effective_capabilities(), active())
+}
+
+fn status_with(
+    tools: &[(&str, bool)],
+    effective: u64,
+    escalation: Option<Escalation>,
+) -> Option<PrivilegeStatus> {
+    let mut status = PrivilegeStatus {
+        escalation: escalation.map(|escalation| escalation.helper().to_string()),
+        escalated_tools: Vec::new(),
+        degraded_tools: Vec::new(),
+    };
+    for &(tool, permission_errors) in tools {
+        let missing = missing_capabilities(tool, effective);
+        if escalation.is_some() && !missing.is_empty() {
+            status.escalated_tools.push(tool.to_string());
+        } else if !missing.is_empty() || permission_errors {
+            status.degraded_tools.push(DegradedTool {
+                tool: tool.to_string(),
+                missing_capabilities: missing.iter().map(|cap| cap.name().to_string()).collect(),
+                permission_errors,
+            });
+        }
+    }
+
+    (status.escalation.is_some() || !status.degraded_tools.is_empty()).then_some(status)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Capability mask of a process running as root
+    const ROOT: u64 = 0x000001ffffffffff;
+
+    #[test]
+    fn test_parse_effective_capabilities() {
+        let status = "Name:\tlx-hw-detect\nCapInh:\t0000000000000000\nCapEff:\t0000000000200004\n";
+        let effective = parse_effective_capabilities(status).unwrap();
+
+        assert_eq!(effective, (1 << 21) | (1 << 2));
+        assert!(missing_capabilities("dmidecode", effective).is_empty());
+        assert_eq!(missing_capabilities("lshw", effective), [Capability::SysRawio]);
+        assert!(parse_effective_capabilities("Name:\tbash\n").is_none());
+    }
+
+    #[test]
+    fn test_missing_capabilities() {
+        assert_eq!(missing_capabilities("nvme", 0), [Capability::SysAdmin]);
+        assert!(missing_capabilities("nvme", ROOT).is_empty());
+        assert!(missing_capabilities("lsusb", 0).is_empty());
+    }
+
+    #[test]
+    fn test_status_records_degraded_and_escalated_tools() {
+        let tools = [("dmidecode", false), ("lsusb", false), ("lspci", false)];
+        assert!(status_with(&tools, ROOT, None).is_none());
+
+        let status = status_with(&tools, 0, None).unwrap();
+        assert_eq!(status.escalation, None);
+        assert_eq!(
+            status.degraded_tools,
+            [
+                DegradedTool {
+                    tool: "dmidecode".to_string(),
+                    missing_capabilities: vec!["CAP_DAC_READ_SEARCH".to_string()],
+                    permission_errors: false,
+                },
+                DegradedTool {
+                    tool: "lspci".to_string(),
+                    missing_capabilities: vec!["CAP_SYS_ADMIN".to_string()],
+                    permission_errors: false,
+                },
+            ]
+        );
+
+        let status =
+            status_with(&[("dmidecode", false), ("lsusb", true)], 0, Some(Escalation::Sudo))
+                .unwrap();
+        assert_eq!(status.escalation.as_deref(), Some("sudo"));
+        assert_eq!(status.escalated_tools, ["dmidecode"]);
+        assert_eq!(status.degraded_tools.len(), 1);
+        assert_eq!(status.degraded_tools[0].tool, "lsusb");
+        assert!(status.degraded_tools[0].missing_capabilities.is_empty());
+    }
+
+    #[test]
+    fn test_escalated_command_keeps_environment() {
+        let mut tool = Command::new("lspci");
+        tool.arg("-vmm").env("LC_ALL", "C");
+
+        let sudo = Escalation::Sudo.command(&tool);
+        let sudo = sudo.as_std();
+        assert_eq!(sudo.get_program(), "sudo");
+        assert_eq!(sudo.get_args().collect::<Vec<_>>(), ["--", "env", "LC_ALL=C", "lspci", "-vmm"]);
+
+        let pkexec = Escalation::Pkexec.command(&Command::new("dmidecode"));
+        assert_eq!(pkexec.as_std().get_args().collect::<Vec<_>>(), ["dmidecode"]);
+    }
+}
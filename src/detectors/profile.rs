@@ -0,0 +1,79 @@
+//! Detection profiles trading report depth for run time
+//!
+//! The full profile runs every available tool with its own timeout and retry
+//! policy. The quick profile is meant for GUI startup and monitoring: it skips
+//! tools that walk every bus, gives each remaining tool a short time box and
+//! never retries, producing a smaller but still valid report.
+
+use crate::detectors::RetryPolicy;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// Tools the quick profile skips: lshw scans every bus, inxi is a large perl
+/// script, fwupd may have to start its daemon and enumerate devices and
+/// smartctl wakes drives in standby
+const SLOW_TOOLS: &[&str] = &["lshw", "inxi", "fwupd", "smartctl"];
+
+/// Time box for each tool under the quick profile
+const QUICK_TOOL_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// How thorough a detection run is
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DetectionProfile {
+    /// Every available tool with its normal timeout and retries
+    #[default]
+    Full,
+    /// Fast tools only, time-boxed and without retries
+    Quick,
+}
+
+impl DetectionProfile {
+    /// Whether this profile runs a tool when no explicit tool list is given
+    pub fn runs_tool(self, tool: &str) -> bool {
+        match self {
+            Self::Full => true,
+            Self::Quick => !SLOW_TOOLS.contains(&tool),
+        }
+    }
+
+    /// Timeout applied to every tool, replacing the tool's own default
+    pub fn tool_timeout(self) -> Option<Duration> {
+        match self {
+            Self::Full => None,
+            Self::Quick => Some(QUICK_TOOL_TIMEOUT),
+        }
+    }
+
+    /// Retry policy applied to every tool, replacing the tool's own default
+    pub fn retry_policy(self) -> Option<RetryPolicy> {
+        match self {
+            Self::Full => None,
+            Self::Quick => Some(RetryPolicy::none()),
+        }
+    }
+
+    /// Whether kernel log lines are captured for unknown devices
+    pub fn reads_kernel_log(self) -> bool {
+        self == Self::Full
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_quick_profile_skips_slow_tools() {
+        for tool in ["lspci", "lsusb", "dmidecode"] {
+            assert!(DetectionProfile::Quick.runs_tool(tool));
+        }
+        assert!(!DetectionProfile::Quick.runs_tool("lshw"));
+        assert!(!DetectionProfile::Quick.runs_tool("inxi"));
+        assert!(!DetectionProfile::Quick.runs_tool("fwupd"));
+        assert!(DetectionProfile::Full.runs_tool("lshw"));
+
+        assert_eq!(DetectionProfile::Quick.tool_timeout(), Some(QUICK_TOOL_TIMEOUT));
+        assert_eq!(DetectionProfile::Full.tool_timeout(), None);
+    }
+}
@@ -0,0 +1,146 @@
+//! Bundles of raw tool output for debugging parsers
+//!
+//! With `detect --save-raw`, the registry keeps what each tool printed on
+//! its last attempt. After sanitizing (see [`crate::privacy::raw`]) the
+//! output is written as a gzipped tarball with one `<tool>.stdout` and
+//! `<tool>.stderr` file per tool and a `manifest.json` describing the run,
+//! which users can attach to an issue about a misdetected device, and which
+//! [`super::replay`] turns back into a report.
+
+use crate::errors::{LxHwError, Result};
+use crate::hardware::PrivacyLevel;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// Output of one tool run
+#[derive(Debug, Clone)]
+pub struct RawOutput {
+    pub tool: String,
+    pub exit_code: Option<i32>,
+    pub stdout: String,
+    pub stderr: String,
+}
+
+impl RawOutput {
+    pub fn new(tool: &str, output: &std::process::Output) -> Self {
+        Self {
+            tool: tool.to_string(),
+            exit_code: output.status.code(),
+            stdout: String::from_utf8_lossy(&output.stdout).into_owned(),
+            stderr: String::from_utf8_lossy(&output.stderr).into_owned(),
+        }
+    }
+}
+
+/// Contents of `manifest.json` in a bundle
+#[derive(Debug, Serialize, Deserialize)]
+pub(crate) struct Manifest {
+    pub version: String,
+    pub privacy_level: PrivacyLevel,
+    pub tools: Vec<ManifestEntry>,
+}
+
+/// One tool in the manifest, naming its files in the bundle
+#[derive(Debug, Serialize, Deserialize)]
+pub(crate) struct ManifestEntry {
+    pub tool: String,
+    pub exit_code: Option<i32>,
+    pub stdout: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stderr: Option<String>,
+}
+
+/// Write sanitized `outputs` to a `.tar.gz` bundle at `path`
+pub fn write_bundle(path: &Path, outputs: &[RawOutput], privacy_level: PrivacyLevel) -> Result<()> {
+    let file = std::fs::File::create(path).map_err(|e| {
+        LxHwError::Io(format!("Cannot create raw output bundle {}: {}", path.display(), e))
+    })?;
+    let mut archive = tar::Builder::new(GzEncoder::new(file, Compression::default()));
+
+    let mut entries = Vec::new();
+    for output in outputs {
+        let stdout = format!("{}.stdout", output.tool);
+        append(&mut archive, &stdout, output.stdout.as_bytes())?;
+        let stderr = (!output.stderr.is_empty()).then(|| format!("{}.stderr", output.tool));
+        if let Some(name) = &stderr {
+            append(&mut archive, name, output.stderr.as_bytes())?;
+        }
+        entries.push(ManifestEntry {
+            tool: output.tool.clone(),
+            exit_code: output.exit_code,
+            stdout,
+            stderr,
+        });
+    }
+    let manifest =
+        Manifest { version: env!("CARGO_PKG_VERSION").to_string(), privacy_level, tools: entries };
+    let manifest = serde_json::to_vec_pretty(&manifest)
+        .map_err(|e| LxHwError::SerializationError(e.to_string()))?;
+    append(&mut archive, "manifest.json", &manifest)?;
+
+    archive
+        .into_inner()
+        .and_then(|encoder| encoder.finish())
+        .map_err(|e| LxHwError::Io(format!("Cannot write {}: {}", path.display(), e)))?;
+    Ok(())
+}
+
+fn append<W: std::io::Write>(archive: &mut tar::Builder<W>, name: &str, data: &[u8]) -> Result<()> {
+    let mut header = tar::Header::new_gnu();
+    header.set_size(data.len() as u64);
+    header.set_mode(0o644);
+    header.set_mtime(chrono::Utc::now().timestamp().max(0) as u64);
+    header.set_cksum();
+    archive
+        .append_data(&mut header, name, data)
+        .map_err(|e| LxHwError::Io(format!("Cannot add {} to bundle: {}", name, e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::read::GzDecoder;
+    use std::io::Read;
+
+    #[test]
+    fn test_bundle_contents() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("raw.tar.gz");
+        let outputs = [
+            RawOutput {
+                tool: "lspci".to_string(),
+                exit_code: Some(0),
+                stdout: "00:02.0 VGA compatible controller".to_string(),
+                stderr: String::new(),
+            },
+            RawOutput {
+                tool: "dmidecode".to_string(),
+                exit_code: Some(1),
+                stdout: String::new(),
+                stderr: "Permission denied".to_string(),
+            },
+        ];
+        write_bundle(&path, &outputs, PrivacyLevel::Enhanced).unwrap();
+
+        let mut archive = tar::Archive::new(GzDecoder::new(std::fs::File::open(&path).unwrap()));
+        let mut files = std::collections::BTreeMap::new();
+        for entry in archive.entries().unwrap() {
+            let mut entry = entry.unwrap();
+            let mut contents = String::new();
+            entry.read_to_string(&mut contents).unwrap();
+            files.insert(entry.path().unwrap().display().to_string(), contents);
+        }
+
+        assert_eq!(
+            files.keys().collect::<Vec<_>>(),
+            ["dmidecode.stderr", "dmidecode.stdout", "lspci.stdout", "manifest.json"]
+        );
+        assert_eq!(files["dmidecode.stderr"], "Permission denied");
+        let manifest: serde_json::Value = serde_json::from_str(&files["manifest.json"]).unwrap();
+        assert_eq!(manifest["privacy_level"], "Enhanced");
+        assert_eq!(manifest["tools"][1]["exit_code"], 1);
+        assert_eq!(manifest["tools"][0].get("stderr"), None);
+    }
+}
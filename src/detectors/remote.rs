@@ -0,0 +1,183 @@
+//! Running detection tools on another host over SSH
+//!
+//! With `--remote user@host`, every detection tool is executed on the remote
+//! host through `ssh` and its output is parsed locally, so nothing has to be
+//! installed there beyond the tools themselves. Tools that need root, such as
+//! dmidecode and lshw, report less unless the login is root.
+//!
+//! Checks that read the local kernel or sysfs directly (kernel driver
+//! support, display connectors, audio routing, microcode, swap, USB sysfs
+//! attributes and the kernel log) are skipped for remote hosts.
+
+use crate::errors::{LxHwError, Result};
+use std::ffi::OsStr;
+use std::process::Output;
+use std::sync::OnceLock;
+use tokio::process::Command;
+
+/// Remote host tools run on, set once by [`enable`]
+static REMOTE: OnceLock<Remote> = OnceLock::new();
+
+/// Options keeping ssh from prompting or hanging on unreachable hosts
+const SSH_OPTIONS: &[&str] = &["-o", "BatchMode=yes", "-o", "ConnectTimeout=10", "-T"];
+
+/// System details read from the remote host
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RemoteSystem {
+    pub kernel_version: String,
+    pub architecture: String,
+    pub hostname: String,
+    pub distribution: Option<String>,
+}
+
+/// SSH destination detection tools run on
+#[derive(Debug, Clone)]
+pub struct Remote {
+    destination: String,
+}
+
+impl Remote {
+    /// Validate an ssh destination (`host`, `user@host` or `ssh://user@host:port`)
+    pub fn new(destination: &str) -> Result<Self> {
+        let valid = !destination.is_empty()
+            && !destination.starts_with('-')
+            && !destination.chars().any(|c| c.is_whitespace() || c.is_control());
+        if !valid {
+            return Err(LxHwError::InvalidInput {
+                message: format!("Invalid SSH destination: {:?}", destination),
+            });
+        }
+        Ok(Self { destination: destination.to_string() })
+    }
+
+    pub fn destination(&self) -> &str {
+        &self.destination
+    }
+
+    /// ssh command running `script` through the remote user's shell
+    fn ssh(&self, script: &str) -> Command {
+        let mut command = Command::new("ssh");
+        command.args(SSH_OPTIONS).arg("--").arg(&self.destination).arg(script);
+        command.kill_on_drop(true);
+        command
+    }
+
+    /// Fail early when the host cannot be reached without a prompt
+    pub async fn check(&self) -> Result<()> {
+        let output = self.ssh("true").output().await.map_err(|e| {
+            LxHwError::SystemCommandError { command: format!("ssh {}: {}", self.destination, e) }
+        })?;
+        if !output.status.success() {
+            return Err(LxHwError::SystemError {
+                message: format!(
+                    "Cannot reach {} over SSH without a password prompt: {}",
+                    self.destination,
+                    String::from_utf8_lossy(&output.stderr).trim()
+                ),
+            });
+        }
+        Ok(())
+    }
+
+    /// Whether `program` is installed on the remote host
+    pub async fn has_program(&self, program: &str) -> bool {
+        self.ssh(&format!("command -v {} >/dev/null", shell_quote(OsStr::new(program))))
+            .output()
+            .await
+            .is_ok_and(|output| output.status.success())
+    }
+
+    /// Run a tool's command on the remote host
+    ///
+    /// Arguments are quoted for the remote shell; environment variables set
+    /// on the command are passed as assignments in front of it.
+    pub async fn output(&self, tool: &Command) -> std::io::Result<Output> {
+        let tool = tool.as_std();
+        let mut words: Vec<String> = tool
+            .get_envs()
+            .filter_map(|(key, value)| Some(format!("{}={}", key.to_str()?, shell_quote(value?))))
+            .collect();
+        words.push(shell_quote(tool.get_program()));
+        words.extend(tool.get_args().map(shell_quote));
+        self.ssh(&words.join(" ")).output().await
+    }
+
+    /// Kernel, architecture, hostname and distribution of the remote host
+    pub async fn system(&self) -> Result<RemoteSystem> {
+        let output = self
+            .ssh("uname -r; uname -m; hostname; cat /etc/os-release 2>/dev/null")
+            .output()
+            .await
+            .map_err(|e| LxHwError::SystemCommandError {
+                command: format!("ssh {}: {}", self.destination, e),
+            })?;
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let mut lines = stdout.lines();
+        let mut next = || lines.next().map(str::trim).filter(|l| !l.is_empty()).map(String::from);
+        let kernel_version = next().unwrap_or_else(|| "unknown".to_string());
+        let architecture = next().unwrap_or_else(|| "unknown".to_string());
+        let hostname = next().unwrap_or_else(|| "localhost".to_string());
+        let os_release: Vec<&str> = lines.collect();
+        Ok(RemoteSystem {
+            kernel_version,
+            architecture,
+            hostname,
+            distribution: super::integration::parse_os_release(&os_release.join("\n")),
+        })
+    }
+}
+
+/// Run detection tools on `destination` from now on
+pub async fn enable(destination: &str) -> Result<()> {
+    let remote = Remote::new(destination)?;
+    remote.check().await?;
+    let _ = REMOTE.set(remote);
+    Ok(())
+}
+
+/// The remote host tools run on, if one is set
+pub fn active() -> Option<&'static Remote> {
+    REMOTE.get()
+}
+
+/// Whether detection reads the machine this process runs on, rather than a
+/// remote host or saved output (see [`super::replay`])
+pub fn is_local() -> bool {
+    active().is_none() && super::replay::active().is_none()
+}
+
+/// Quote a word for a POSIX shell
+fn shell_quote(word: &OsStr) -> String {
+    let word = word.to_string_lossy();
+    let safe = !word.is_empty()
+        && word.chars().all(|c| c.is_ascii_alphanumeric() || "-_./=:,@%+".contains(c));
+    if safe {
+        word.into_owned()
+    } else {
+        format!("'{}'", word.replace('\'', r"'\''"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_shell_quote() {
+        assert_eq!(shell_quote(OsStr::new("-json")), "-json");
+        assert_eq!(shell_quote(OsStr::new("system,memory")), "system,memory");
+        assert_eq!(shell_quote(OsStr::new("a b")), "'a b'");
+        assert_eq!(shell_quote(OsStr::new("it's")), r"'it'\''s'");
+        assert_eq!(shell_quote(OsStr::new("")), "''");
+        assert_eq!(shell_quote(OsStr::new("$(reboot)")), "'$(reboot)'");
+    }
+
+    #[test]
+    fn test_destination_validation() {
+        assert!(Remote::new("admin@server01").is_ok());
+        assert!(Remote::new("ssh://root@10.0.0.5:2222").is_ok());
+        assert!(Remote::new("-oProxyCommand=evil").is_err());
+        assert!(Remote::new("host name").is_err());
+        assert!(Remote::new("").is_err());
+    }
+}
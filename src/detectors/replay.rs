@@ -0,0 +1,207 @@
+//! Building reports from saved tool output instead of running the tools
+//!
+//! With `detect --replay <BUNDLE>`, detectors are fed the output kept in a
+//! `--save-raw` bundle (see [`super::raw`]) or in a directory of
+//! `<tool>.stdout` and `<tool>.stderr` files, such as test fixtures. Only
+//! tools with saved output take part, and nothing is retried. Maintainers
+//! can regenerate a report from a user's bundle once a parser is fixed, and
+//! parser tests get the same report for the same input on every machine.
+//!
+//! Like detection on a remote host, replay skips every check that reads
+//! the local kernel or sysfs directly, as the output belongs to another
+//! machine or another time.
+
+use super::raw::{Manifest, RawOutput};
+use crate::errors::{LxHwError, Result};
+use flate2::read::GzDecoder;
+use std::collections::HashMap;
+use std::io::Read;
+use std::os::unix::process::ExitStatusExt;
+use std::path::Path;
+use std::process::{ExitStatus, Output};
+use std::sync::OnceLock;
+
+/// Saved output detectors read from, set once by [`enable`]
+static REPLAY: OnceLock<Replay> = OnceLock::new();
+
+/// Saved output of detection tools, by tool name
+#[derive(Debug, Clone, Default)]
+pub struct Replay {
+    outputs: HashMap<String, RawOutput>,
+}
+
+impl Replay {
+    pub fn from_outputs(outputs: impl IntoIterator<Item = RawOutput>) -> Self {
+        Self { outputs: outputs.into_iter().map(|output| (output.tool.clone(), output)).collect() }
+    }
+
+    /// Load a `.tar.gz` bundle or a directory of output files
+    pub fn load(path: &Path) -> Result<Self> {
+        let files = if path.is_dir() { read_directory(path)? } else { read_bundle(path)? };
+        let replay = Self::from_files(&files)?;
+        if replay.outputs.is_empty() {
+            return Err(LxHwError::InvalidInput {
+                message: format!("No tool output found in {}", path.display()),
+            });
+        }
+        Ok(replay)
+    }
+
+    /// Outputs named by the manifest, or every `<tool>.stdout` file if there is none
+    fn from_files(files: &HashMap<String, Vec<u8>>) -> Result<Self> {
+        let text =
+            |name: &str| files.get(name).map(|data| String::from_utf8_lossy(data).into_owned());
+        let outputs = match files.get("manifest.json") {
+            Some(manifest) => {
+                let manifest: Manifest = serde_json::from_slice(manifest).map_err(|e| {
+                    LxHwError::SerializationError(format!("Invalid replay manifest: {}", e))
+                })?;
+                manifest
+                    .tools
+                    .into_iter()
+                    .map(|entry| RawOutput {
+                        stdout: text(&entry.stdout).unwrap_or_default(),
+                        stderr: entry.stderr.as_deref().and_then(text).unwrap_or_default(),
+                        tool: entry.tool,
+                        exit_code: entry.exit_code,
+                    })
+                    .collect()
+            }
+            None => files
+                .keys()
+                .filter_map(|name| name.strip_suffix(".stdout"))
+                .map(|tool| RawOutput {
+                    tool: tool.to_string(),
+                    exit_code: Some(0),
+                    stdout: text(&format!("{}.stdout", tool)).unwrap_or_default(),
+                    stderr: text(&format!("{}.stderr", tool)).unwrap_or_default(),
+                })
+                .collect::<Vec<_>>(),
+        };
+        Ok(Self::from_outputs(outputs))
+    }
+
+    /// Names of the tools with saved output
+    pub fn tools(&self) -> impl Iterator<Item = &str> {
+        self.outputs.keys().map(String::as_str)
+    }
+
+    /// Whether output of `tool` was saved
+    pub fn contains(&self, tool: &str) -> bool {
+        self.outputs.contains_key(tool)
+    }
+
+    /// Saved output of `tool`, as if it had just run
+    pub fn output(&self, tool: &str) -> Option<Output> {
+        let output = self.outputs.get(tool)?;
+        // A process killed by a signal has no exit code; report it as failed
+        let code = output.exit_code.unwrap_or(1);
+        Some(Output {
+            status: ExitStatus::from_raw((code & 0xff) << 8),
+            stdout: output.stdout.clone().into_bytes(),
+            stderr: output.stderr.clone().into_bytes(),
+        })
+    }
+}
+
+/// Files of a `.tar.gz` bundle by name
+fn read_bundle(path: &Path) -> Result<HashMap<String, Vec<u8>>> {
+    let file = std::fs::File::open(path)
+        .map_err(|e| LxHwError::Io(format!("Cannot open {}: {}", path.display(), e)))?;
+    let invalid = |e: std::io::Error| {
+        LxHwError::Io(format!("Cannot read raw output bundle {}: {}", path.display(), e))
+    };
+    let mut archive = tar::Archive::new(GzDecoder::new(file));
+    let mut files = HashMap::new();
+    for entry in archive.entries().map_err(invalid)? {
+        let mut entry = entry.map_err(invalid)?;
+        let name = entry.path().map_err(invalid)?.to_string_lossy().into_owned();
+        let mut data = Vec::new();
+        entry.read_to_end(&mut data).map_err(invalid)?;
+        files.insert(name, data);
+    }
+    Ok(files)
+}
+
+/// Regular files of a directory by name
+fn read_directory(path: &Path) -> Result<HashMap<String, Vec<u8>>> {
+    let invalid =
+        |e: std::io::Error| LxHwError::Io(format!("Cannot read {}: {}", path.display(), e));
+    let mut files = HashMap::new();
+    for entry in std::fs::read_dir(path).map_err(invalid)? {
+        let entry = entry.map_err(invalid)?;
+        if entry.file_type().map_err(invalid)?.is_file() {
+            let data = std::fs::read(entry.path()).map_err(invalid)?;
+            files.insert(entry.file_name().to_string_lossy().into_owned(), data);
+        }
+    }
+    Ok(files)
+}
+
+/// Feed detectors the output saved at `path` from now on
+pub fn enable(path: &Path) -> Result<()> {
+    let replay = Replay::load(path)?;
+    log::info!("Replaying saved output of {}", replay.tools().collect::<Vec<_>>().join(", "));
+    let _ = REPLAY.set(replay);
+    Ok(())
+}
+
+/// The saved output detectors read from, if replay is enabled
+pub fn active() -> Option<&'static Replay> {
+    REPLAY.get()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hardware::PrivacyLevel;
+
+    fn output(tool: &str, exit_code: i32, stdout: &str, stderr: &str) -> RawOutput {
+        RawOutput {
+            tool: tool.to_string(),
+            exit_code: Some(exit_code),
+            stdout: stdout.to_string(),
+            stderr: stderr.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_load_bundle() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("raw.tar.gz");
+        let outputs = [
+            output("lspci", 0, "00:02.0 VGA compatible controller", ""),
+            output("dmidecode", 1, "", "Permission denied"),
+        ];
+        super::super::raw::write_bundle(&path, &outputs, PrivacyLevel::Basic).unwrap();
+
+        let replay = Replay::load(&path).unwrap();
+        let mut tools: Vec<&str> = replay.tools().collect();
+        tools.sort();
+        assert_eq!(tools, ["dmidecode", "lspci"]);
+        let lspci = replay.output("lspci").unwrap();
+        assert!(lspci.status.success());
+        assert_eq!(lspci.stdout, b"00:02.0 VGA compatible controller");
+        let dmidecode = replay.output("dmidecode").unwrap();
+        assert_eq!(dmidecode.status.code(), Some(1));
+        assert_eq!(dmidecode.stderr, b"Permission denied");
+        assert!(!replay.contains("lsusb") && replay.output("lsusb").is_none());
+    }
+
+    #[test]
+    fn test_load_fixture_directory() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("lsusb.stdout"), "Bus 001 Device 001").unwrap();
+        std::fs::write(dir.path().join("lsusb.stderr"), "warning").unwrap();
+        std::fs::write(dir.path().join("README"), "fixtures").unwrap();
+
+        let replay = Replay::load(dir.path()).unwrap();
+        assert_eq!(replay.tools().collect::<Vec<_>>(), ["lsusb"]);
+        let lsusb = replay.output("lsusb").unwrap();
+        assert!(lsusb.status.success());
+        assert_eq!(lsusb.stderr, b"warning");
+
+        let empty = tempfile::tempdir().unwrap();
+        assert!(Replay::load(empty.path()).is_err());
+    }
+}
@@ -0,0 +1,137 @@
+//! Retry policy for detection tools that fail intermittently
+//!
+//! Some tools fail transiently: dmidecode on certain BIOSes, lsusb while
+//! devices are still enumerating. Failures are classified as transient or
+//! permanent, and transient ones are retried with jittered exponential
+//! backoff so a single hiccup does not show up as a failed tool.
+
+use crate::errors::LxHwError;
+use rand::Rng;
+use std::time::Duration;
+
+/// Error text fragments that indicate a failure worth retrying
+const TRANSIENT_MARKERS: &[&str] = &[
+    "timed out",
+    "resource temporarily unavailable",
+    "device or resource busy",
+    "interrupted system call",
+    "try again",
+    "no such device",
+];
+
+/// How often and how patiently a detector is retried
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// Total attempts, including the first
+    pub max_attempts: u32,
+    /// Delay before the first retry; doubled for each following retry
+    pub initial_backoff: Duration,
+    /// Upper bound on the delay between attempts
+    pub max_backoff: Duration,
+}
+
+impl RetryPolicy {
+    /// Run once and never retry
+    pub fn none() -> Self {
+        Self { max_attempts: 1, ..Self::default() }
+    }
+
+    /// Policy for tools known to fail intermittently
+    pub fn flaky() -> Self {
+        Self { max_attempts: 3, ..Self::default() }
+    }
+
+    /// Jittered delay before retry number `retry` (1-based)
+    ///
+    /// The delay is drawn uniformly from the upper half of the exponential
+    /// backoff window so concurrent runs do not retry in lockstep.
+    pub fn backoff(&self, retry: u32) -> Duration {
+        let exponential = self
+            .initial_backoff
+            .saturating_mul(2u32.saturating_pow(retry.saturating_sub(1)))
+            .min(self.max_backoff);
+        let millis = exponential.as_millis() as u64;
+        if millis == 0 {
+            return Duration::ZERO;
+        }
+        Duration::from_millis(rand::thread_rng().gen_range(millis / 2..=millis))
+    }
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 1,
+            initial_backoff: Duration::from_millis(250),
+            max_backoff: Duration::from_secs(2),
+        }
+    }
+}
+
+/// Whether a failed detection is worth retrying
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FailureKind {
+    /// May succeed on another attempt
+    Transient,
+    /// Will fail the same way again (missing tool, permissions, bad output)
+    Permanent,
+}
+
+/// Classify an execution error
+pub fn classify_failure(error: &LxHwError) -> FailureKind {
+    if let LxHwError::IoError(e) = error {
+        use std::io::ErrorKind;
+        if matches!(e.kind(), ErrorKind::Interrupted | ErrorKind::WouldBlock | ErrorKind::TimedOut)
+        {
+            return FailureKind::Transient;
+        }
+    }
+
+    classify_message(&error.to_string())
+}
+
+/// Classify a failure from its message
+pub fn classify_message(message: &str) -> FailureKind {
+    let message = message.to_lowercase();
+    if TRANSIENT_MARKERS.iter().any(|marker| message.contains(marker)) {
+        FailureKind::Transient
+    } else {
+        FailureKind::Permanent
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_classify_failure() {
+        let busy = LxHwError::DetectionError("dmidecode: Device or resource busy".to_string());
+        assert_eq!(classify_failure(&busy), FailureKind::Transient);
+
+        let missing = LxHwError::SystemCommandError { command: "lsusb".to_string() };
+        assert_eq!(classify_failure(&missing), FailureKind::Permanent);
+
+        let interrupted = LxHwError::IoError(std::io::Error::from(std::io::ErrorKind::Interrupted));
+        assert_eq!(classify_failure(&interrupted), FailureKind::Transient);
+
+        assert_eq!(classify_message("Execution timed out after 10s"), FailureKind::Transient);
+    }
+
+    #[test]
+    fn test_backoff_is_jittered_and_capped() {
+        let policy = RetryPolicy {
+            max_attempts: 5,
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_millis(300),
+        };
+
+        for _ in 0..20 {
+            let first = policy.backoff(1);
+            assert!(first >= Duration::from_millis(50) && first <= Duration::from_millis(100));
+
+            let capped = policy.backoff(4);
+            assert!(capped >= Duration::from_millis(150) && capped <= Duration::from_millis(300));
+        }
+    }
+}
@@ -0,0 +1,249 @@
+//! Radio block state from rfkill and combo chip coexistence quirks
+//!
+//! Each radio registered with rfkill has a directory under
+//! `/sys/class/rfkill` with its type, kernel name and `soft`/`hard` block
+//! flags. The radio's `device` link points at the phy or hci device, whose own
+//! `device` link leads to the PCI or USB device that provides it; the vendor
+//! and device IDs found there are looked up in
+//! `data/radio-coexistence.toml`.
+//!
+//! A blocked radio keeps its PCI/USB device and bound driver, so lspci and
+//! lsusb show it as working hardware; the block state is the only sign that
+//! it is switched off.
+
+use super::attributes::read_trimmed;
+use crate::errors::{LxHwError, Result};
+use crate::hardware::{CoexistenceQuirk, RadioState};
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+
+/// Built-in coexistence quirks, compiled into the binary
+const COEXISTENCE_DATA: &str = include_str!("../../data/radio-coexistence.toml");
+
+/// Directory levels searched above a radio's device for its IDs and driver
+const MAX_PARENT_DEPTH: usize = 6;
+
+#[derive(Debug, Deserialize)]
+struct CoexistenceFile {
+    #[serde(default)]
+    chip: Vec<KnownChip>,
+}
+
+/// Combo chip with a known coexistence problem
+#[derive(Debug, Clone, Deserialize)]
+pub struct KnownChip {
+    pub chip: String,
+    /// Vendor:device IDs of the chip's functions, lowercase hex
+    pub ids: Vec<String>,
+    pub note: String,
+}
+
+/// Combo chips with known Wi-Fi/Bluetooth coexistence problems
+#[derive(Debug, Clone)]
+pub struct CoexistenceDatabase {
+    chips: Vec<KnownChip>,
+}
+
+impl CoexistenceDatabase {
+    /// Database from `data/radio-coexistence.toml`
+    pub fn builtin() -> Self {
+        Self::parse(COEXISTENCE_DATA).expect("built-in coexistence data must be valid")
+    }
+
+    /// Parse coexistence quirks from TOML
+    pub fn parse(content: &str) -> Result<Self> {
+        let file: CoexistenceFile = toml::from_str(content)
+            .map_err(|e| LxHwError::ConfigError(format!("Invalid coexistence data: {}", e)))?;
+        for chip in &file.chip {
+            if let Some(id) = chip.ids.iter().find(|id| !is_device_id(id)) {
+                return Err(LxHwError::ConfigError(format!(
+                    "Invalid device ID for {}: {}",
+                    chip.chip, id
+                )));
+            }
+        }
+        Ok(Self { chips: file.chip })
+    }
+
+    /// Quirk of the chip with a vendor:device ID
+    pub fn lookup(&self, device_id: &str) -> Option<CoexistenceQuirk> {
+        let device_id = device_id.to_ascii_lowercase();
+        self.chips
+            .iter()
+            .find(|chip| chip.ids.contains(&device_id))
+            .map(|chip| CoexistenceQuirk { chip: chip.chip.clone(), note: chip.note.clone() })
+    }
+}
+
+/// Read every radio registered with rfkill
+///
+/// Returns an empty list when rfkill is not available.
+pub fn read_radios(rfkill_class: &Path, db: &CoexistenceDatabase) -> Vec<RadioState> {
+    let Ok(entries) = std::fs::read_dir(rfkill_class) else {
+        return Vec::new();
+    };
+    let mut dirs: Vec<(u32, PathBuf)> = entries
+        .flatten()
+        .filter_map(|entry| {
+            let index = entry.file_name().to_str()?.strip_prefix("rfkill")?.parse().ok()?;
+            Some((index, entry.path()))
+        })
+        .collect();
+    dirs.sort();
+
+    dirs.iter().filter_map(|(_, dir)| read_radio(dir, db)).collect()
+}
+
+fn read_radio(dir: &Path, db: &CoexistenceDatabase) -> Option<RadioState> {
+    let kind = read_trimmed(&dir.join("type"))?;
+    let name = read_trimmed(&dir.join("name")).unwrap_or_else(|| "unknown".to_string());
+    let flag = |file: &str| read_trimmed(&dir.join(file)).is_some_and(|value| value == "1");
+
+    // rfkillN/device is the phy or hci device, its device the PCI/USB function
+    let parent = std::fs::canonicalize(dir.join("device").join("device")).ok();
+    let (device_id, driver) = match &parent {
+        Some(parent) => (find_device_id(parent), find_driver(parent)),
+        None => (None, None),
+    };
+    let coexistence = device_id.as_deref().and_then(|id| db.lookup(id));
+
+    Some(RadioState {
+        kind,
+        name,
+        device_id,
+        driver,
+        soft_blocked: flag("soft"),
+        hard_blocked: flag("hard"),
+        coexistence,
+    })
+}
+
+/// PCI or USB vendor:device ID of the nearest device at or above a path
+fn find_device_id(device: &Path) -> Option<String> {
+    device.ancestors().take(MAX_PARENT_DEPTH).find_map(|dir| {
+        let pci = || Some((read_hex_id(&dir.join("vendor"))?, read_hex_id(&dir.join("device"))?));
+        let usb =
+            || Some((read_hex_id(&dir.join("idVendor"))?, read_hex_id(&dir.join("idProduct"))?));
+        pci().or_else(usb).map(|(vendor, device)| format!("{}:{}", vendor, device))
+    })
+}
+
+/// Driver bound to the nearest device at or above a path
+fn find_driver(device: &Path) -> Option<String> {
+    device.ancestors().take(MAX_PARENT_DEPTH).find_map(|dir| {
+        let target = std::fs::read_link(dir.join("driver")).ok()?;
+        Some(target.file_name()?.to_string_lossy().into_owned())
+    })
+}
+
+/// Four-digit hex ID from a sysfs file, with or without a "0x" prefix
+fn read_hex_id(path: &Path) -> Option<String> {
+    let value = read_trimmed(path)?.to_ascii_lowercase();
+    let id = value.strip_prefix("0x").unwrap_or(&value);
+    (id.len() == 4 && id.chars().all(|c| c.is_ascii_hexdigit())).then(|| id.to_string())
+}
+
+fn is_device_id(id: &str) -> bool {
+    id.split_once(':').is_some_and(|(vendor, device)| {
+        [vendor, device].iter().all(|part| {
+            part.len() == 4 && part.chars().all(|c| c.is_ascii_digit() || matches!(c, 'a'..='f'))
+        })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::os::unix::fs::symlink;
+    use tempfile::TempDir;
+
+    fn write(dir: &Path, file: &str, content: &str) {
+        std::fs::create_dir_all(dir).unwrap();
+        std::fs::write(dir.join(file), content).unwrap();
+    }
+
+    fn add_radio(
+        root: &Path,
+        index: u32,
+        kind: &str,
+        name: &str,
+        owner: &Path,
+        soft: u8,
+        hard: u8,
+    ) {
+        let dir = root.join(format!("class/rfkill/rfkill{}", index));
+        write(&dir, "type", &format!("{}\n", kind));
+        write(&dir, "name", &format!("{}\n", name));
+        write(&dir, "soft", &format!("{}\n", soft));
+        write(&dir, "hard", &format!("{}\n", hard));
+        symlink(owner, dir.join("device")).unwrap();
+    }
+
+    /// AX200 Wi-Fi on PCI, soft-blocked, and its Bluetooth on USB, hard-blocked
+    fn fixture() -> TempDir {
+        let root = TempDir::new().unwrap();
+        let drivers = root.path().join("bus/drivers");
+        for driver in ["iwlwifi", "btusb", "usb"] {
+            std::fs::create_dir_all(drivers.join(driver)).unwrap();
+        }
+
+        let pci = root.path().join("devices/pci0000:00/0000:00:14.3");
+        write(&pci, "vendor", "0x8086\n");
+        write(&pci, "device", "0x2723\n");
+        symlink(drivers.join("iwlwifi"), pci.join("driver")).unwrap();
+        let phy = pci.join("ieee80211/phy0");
+        std::fs::create_dir_all(&phy).unwrap();
+        symlink(&pci, phy.join("device")).unwrap();
+
+        let usb = root.path().join("devices/pci0000:00/0000:00:14.0/usb1/1-10");
+        write(&usb, "idVendor", "8087\n");
+        write(&usb, "idProduct", "0029\n");
+        symlink(drivers.join("usb"), usb.join("driver")).unwrap();
+        let interface = usb.join("1-10:1.0");
+        std::fs::create_dir_all(&interface).unwrap();
+        symlink(drivers.join("btusb"), interface.join("driver")).unwrap();
+        let hci = interface.join("bluetooth/hci0");
+        std::fs::create_dir_all(&hci).unwrap();
+        symlink(&interface, hci.join("device")).unwrap();
+
+        add_radio(root.path(), 0, "wlan", "phy0", &phy, 1, 0);
+        add_radio(root.path(), 1, "bluetooth", "hci0", &hci, 0, 1);
+        root
+    }
+
+    #[test]
+    fn test_read_radios_resolves_devices_and_quirks() {
+        let root = fixture();
+        let radios =
+            read_radios(&root.path().join("class/rfkill"), &CoexistenceDatabase::builtin());
+        assert_eq!(radios.len(), 2);
+
+        let wifi = &radios[0];
+        assert_eq!((wifi.kind.as_str(), wifi.name.as_str()), ("wlan", "phy0"));
+        assert_eq!(wifi.device_id.as_deref(), Some("8086:2723"));
+        assert_eq!(wifi.driver.as_deref(), Some("iwlwifi"));
+        assert!(wifi.soft_blocked && !wifi.hard_blocked);
+        assert_eq!(wifi.coexistence.as_ref().unwrap().chip, "Intel Wi-Fi 6 AX200");
+        assert!(wifi.unblock_steps().unwrap().contains("rfkill unblock wlan"));
+
+        let bluetooth = &radios[1];
+        assert_eq!(bluetooth.device_id.as_deref(), Some("8087:0029"));
+        assert_eq!(bluetooth.driver.as_deref(), Some("btusb"));
+        assert!(bluetooth.hard_blocked);
+        assert!(bluetooth.unblock_steps().unwrap().contains("BIOS/UEFI"));
+    }
+
+    #[test]
+    fn test_missing_rfkill_class_is_empty() {
+        let root = TempDir::new().unwrap();
+        assert!(
+            read_radios(&root.path().join("rfkill"), &CoexistenceDatabase::builtin()).is_empty()
+        );
+    }
+
+    #[test]
+    fn test_parse_rejects_invalid_ids() {
+        let content = "[[chip]]\nchip = \"Test\"\nids = [\"8086:AX200\"]\nnote = \"n\"\n";
+        assert!(matches!(CoexistenceDatabase::parse(content), Err(LxHwError::ConfigError(_))));
+    }
+}
@@ -0,0 +1,266 @@
+//! Sandboxed execution of external detection tools
+//!
+//! With `--sandbox`, every detection tool runs under bubblewrap (`bwrap`) in
+//! fresh user, network, PID, IPC and UTS namespaces with no capabilities,
+//! the host filesystem mounted read-only apart from a new `/proc` and a
+//! private `/tmp`, and a seccomp filter refusing syscalls no detector needs
+//! (module loading, mounting, ptrace, kexec, bpf, keyrings).
+//!
+//! `/sys` and `/dev` stay readable, but anything that needs a capability in
+//! the host namespace is lost:
+//!
+//! - lshw: SCSI and disk details that require `CAP_SYS_RAWIO`
+//! - dmidecode: the `/dev/mem` fallback on systems without
+//!   `/sys/firmware/dmi/tables`
+//! - lspci: extended PCI configuration space, so capability lists and link
+//!   details are missing
+//! - inxi: its sudo helpers, blocked by `no_new_privs`
+//!
+//! lsusb and fwupd, which talks to its daemon over D-Bus, are unaffected.
+//! The tools that ran sandboxed and lost data are recorded in the report
+//! metadata.
+
+use crate::errors::{LxHwError, Result};
+use crate::hardware::SandboxStatus;
+use nix::sys::memfd::{memfd_create, MemFdCreateFlag};
+use std::ffi::CStr;
+use std::io::{Seek, Write};
+use std::os::fd::{AsRawFd, OwnedFd};
+use std::path::PathBuf;
+use std::process::Output;
+use std::sync::OnceLock;
+use tokio::process::Command;
+
+/// Sandbox used for tool execution, set once by [`enable`]
+static SANDBOX: OnceLock<Sandbox> = OnceLock::new();
+
+/// Sandboxing helper looked up in `PATH`
+const HELPER: &str = "bwrap";
+
+/// bubblewrap options isolating the tool from the network and the filesystem
+const BWRAP_ARGS: &[&str] = &[
+    "--unshare-all",
+    "--cap-drop",
+    "ALL",
+    "--die-with-parent",
+    "--new-session",
+    "--ro-bind",
+    "/",
+    "/",
+    "--proc",
+    "/proc",
+    "--tmpfs",
+    "/tmp",
+];
+
+/// Tools that report less under the sandbox, with what they lose
+pub const LIMITED_TOOLS: &[(&str, &str)] = &[
+    ("lshw", "SCSI and disk details requiring CAP_SYS_RAWIO"),
+    ("dmidecode", "/dev/mem fallback when sysfs DMI tables are missing"),
+    ("lspci", "extended PCI configuration space"),
+    ("inxi", "sudo helpers"),
+];
+
+/// Syscalls refused inside the sandbox
+const DENIED_SYSCALLS: &[libc::c_long] = &[
+    libc::SYS_ptrace,
+    libc::SYS_mount,
+    libc::SYS_umount2,
+    libc::SYS_pivot_root,
+    libc::SYS_reboot,
+    libc::SYS_kexec_load,
+    libc::SYS_init_module,
+    libc::SYS_finit_module,
+    libc::SYS_delete_module,
+    libc::SYS_bpf,
+    libc::SYS_perf_event_open,
+    libc::SYS_add_key,
+    libc::SYS_keyctl,
+    libc::SYS_swapon,
+    libc::SYS_swapoff,
+    libc::SYS_unshare,
+    libc::SYS_setns,
+];
+
+/// `AUDIT_ARCH_*` value seccomp reports for native syscalls
+#[cfg(target_arch = "x86_64")]
+const AUDIT_ARCH: Option<u32> = Some(0xc000_003e);
+#[cfg(target_arch = "aarch64")]
+const AUDIT_ARCH: Option<u32> = Some(0xc000_00b7);
+#[cfg(not(any(target_arch = "x86_64", target_arch = "aarch64")))]
+const AUDIT_ARCH: Option<u32> = None;
+
+// Classic BPF opcodes and seccomp return values
+const BPF_LD_W_ABS: u16 = 0x20;
+const BPF_JEQ_K: u16 = 0x15;
+const BPF_JGE_K: u16 = 0x35;
+const BPF_RET_K: u16 = 0x06;
+const SECCOMP_RET_ALLOW: u32 = 0x7fff_0000;
+const SECCOMP_RET_ERRNO: u32 = 0x0005_0000;
+
+/// Offsets into `struct seccomp_data`
+const SECCOMP_DATA_NR: u32 = 0;
+const SECCOMP_DATA_ARCH: u32 = 4;
+
+/// Syscall numbers at or above this use the x32 ABI on x86_64
+const X32_SYSCALL_BIT: u32 = 0x4000_0000;
+
+/// Helper confining tool execution
+#[derive(Debug, Clone)]
+pub struct Sandbox {
+    helper: PathBuf,
+}
+
+impl Sandbox {
+    /// Locate the sandboxing helper
+    pub fn new() -> Result<Self> {
+        let helper = std::env::var_os("PATH")
+            .iter()
+            .flat_map(std::env::split_paths)
+            .map(|dir| dir.join(HELPER))
+            .find(|path| path.is_file())
+            .ok_or_else(|| {
+                LxHwError::ConfigError(format!(
+                    "--sandbox requires bubblewrap ({}), which was not found in PATH",
+                    HELPER
+                ))
+            })?;
+        Ok(Self { helper })
+    }
+
+    /// Sandbox status recorded in report metadata
+    pub fn status(&self, tools_used: &[String]) -> SandboxStatus {
+        SandboxStatus {
+            helper: HELPER.to_string(),
+            seccomp: AUDIT_ARCH.is_some(),
+            limited_tools: LIMITED_TOOLS
+                .iter()
+                .filter(|(tool, _)| tools_used.iter().any(|used| used == tool))
+                .map(|(tool, _)| tool.to_string())
+                .collect(),
+        }
+    }
+
+    /// Run a tool's command under the helper
+    async fn output(&self, tool: &Command) -> std::io::Result<Output> {
+        // bwrap reads the filter from an inherited descriptor; it must stay
+        // open until the helper has started
+        let filter = seccomp_filter()?;
+
+        let mut command = Command::new(&self.helper);
+        command.args(BWRAP_ARGS);
+        if let Some(fd) = &filter {
+            command.arg("--seccomp").arg(fd.as_raw_fd().to_string());
+        }
+        let tool = tool.as_std();
+        command.arg("--").arg(tool.get_program()).args(tool.get_args());
+
+        let output = command.output().await;
+        drop(filter);
+        output
+    }
+}
+
+/// Run detection tools inside the sandbox from now on
+pub fn enable() -> Result<()> {
+    let sandbox = Sandbox::new()?;
+    let _ = SANDBOX.set(sandbox);
+    Ok(())
+}
+
+/// The sandbox tools run in, if one is enabled
+pub fn active() -> Option<&'static Sandbox> {
+    SANDBOX.get()
+}
+
+/// Run a detection tool's command, on the remote host, inside the sandbox
+/// or as root when one of them is enabled
+pub async fn output(command: &mut Command) -> std::io::Result<Output> {
+    if let Some(remote) = super::remote::active() {
+        return remote.output(command).await;
+    }
+    if let Some(output) = super::privilege::escalated_output(command).await {
+        return output;
+    }
+    match active() {
+        Some(sandbox) => sandbox.output(command).await,
+        None => command.output().await,
+    }
+}
+
+/// seccomp BPF program refusing [`DENIED_SYSCALLS`] with `EPERM`
+///
+/// Syscalls from a foreign ABI are refused outright. Returns `None` on
+/// architectures without a known audit architecture value.
+pub fn seccomp_program() -> Option<Vec<u8>> {
+    let deny = SECCOMP_RET_ERRNO | libc::EPERM as u32;
+    let mut program = vec![
+        (BPF_LD_W_ABS, 0, 0, SECCOMP_DATA_ARCH),
+        (BPF_JEQ_K, 1, 0, AUDIT_ARCH?),
+        (BPF_RET_K, 0, 0, deny),
+        (BPF_LD_W_ABS, 0, 0, SECCOMP_DATA_NR),
+        (BPF_JGE_K, 0, 1, X32_SYSCALL_BIT),
+        (BPF_RET_K, 0, 0, deny),
+    ];
+    for &syscall in DENIED_SYSCALLS {
+        program.push((BPF_JEQ_K, 0, 1, syscall as u32));
+        program.push((BPF_RET_K, 0, 0, deny));
+    }
+    program.push((BPF_RET_K, 0, 0, SECCOMP_RET_ALLOW));
+
+    // struct sock_filter { u16 code; u8 jt; u8 jf; u32 k; }
+    Some(
+        program
+            .into_iter()
+            .flat_map(|(code, jt, jf, k)| {
+                let mut instruction = code.to_ne_bytes().to_vec();
+                instruction.extend([jt, jf]);
+                instruction.extend(k.to_ne_bytes());
+                instruction
+            })
+            .collect(),
+    )
+}
+
+/// Memory file holding the seccomp program, inheritable by the helper
+fn seccomp_filter() -> std::io::Result<Option<OwnedFd>> {
+    let Some(program) = seccomp_program() else {
+        return Ok(None);
+    };
+    let name = CStr::from_bytes_with_nul(b"lx-hw-seccomp\0").expect("valid C string");
+    let mut file = std::fs::File::from(memfd_create(name, MemFdCreateFlag::empty())?);
+    file.write_all(&program)?;
+    file.rewind()?;
+    Ok(Some(file.into()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_seccomp_program() {
+        let Some(program) = seccomp_program() else {
+            return;
+        };
+        assert_eq!(program.len() % 8, 0);
+        let instructions = program.len() / 8;
+        assert_eq!(instructions, 6 + 2 * DENIED_SYSCALLS.len() + 1);
+
+        let last = &program[program.len() - 8..];
+        assert_eq!(u16::from_ne_bytes([last[0], last[1]]), BPF_RET_K);
+        assert_eq!(u32::from_ne_bytes(last[4..8].try_into().unwrap()), SECCOMP_RET_ALLOW);
+
+        let filter = seccomp_filter().unwrap().unwrap();
+        let mut contents = Vec::new();
+        std::io::Read::read_to_end(&mut std::fs::File::from(filter), &mut contents).unwrap();
+        assert_eq!(contents, program);
+    }
+
+    #[test]
+    fn test_status_lists_limited_tools() {
+        let sandbox = Sandbox { helper: PathBuf::from("/usr/bin/bwrap") };
+        let status = sandbox.status(&["lspci".to_string(), "lsusb".to_string()]);
+        assert_eq!(status.limited_tools, ["lspci"]);
+    }
+}
@@ -0,0 +1,211 @@
+//! Scanners from SANE
+//!
+//! `scanimage -L` lists every device a SANE backend can open as
+//! "device `backend:name' is a Vendor Model type". Device names of USB
+//! scanners can hold serial numbers and those of network scanners hold
+//! addresses, so only the backend is kept. Webcams that the v4l backend
+//! offers as scanners are left out.
+
+use super::extract::{ComponentExtractor, ExtractionContext};
+use super::{sandbox, DetectionData, DetectionError, DetectionResult, HardwareDetector};
+use crate::errors::{LxHwError, Result};
+use crate::hardware::Scanner;
+use async_trait::async_trait;
+use std::process::Output;
+use std::time::Duration;
+
+/// Backends that are not scanner hardware
+const IGNORED_BACKENDS: &[&str] = &["v4l", "test"];
+
+/// Backends reaching scanners over the network
+const NETWORK_BACKENDS: &[&str] = &["airscan", "escl", "net"];
+
+/// Device types SANE backends report, longest first
+const DEVICE_TYPES: &[&str] = &[
+    "multi-function peripheral",
+    "flatbed scanner",
+    "sheetfed scanner",
+    "handheld scanner",
+    "film scanner",
+    "all-in-one",
+    "scanner",
+];
+
+/// Scanners reported by scanimage
+#[derive(Debug, Clone, Default)]
+pub struct ScanimageData {
+    pub scanners: Vec<Scanner>,
+}
+
+impl ComponentExtractor for ScanimageData {
+    fn scanners(&self, _ctx: &mut ExtractionContext) -> Result<Vec<Scanner>> {
+        Ok(self.scanners.clone())
+    }
+}
+
+/// Parse one `scanimage -L` line
+fn parse_device(line: &str) -> Option<Scanner> {
+    let (name, description) = line.strip_prefix("device `")?.split_once("' is a ")?;
+    let backend = name.split(':').next()?;
+    if IGNORED_BACKENDS.contains(&backend) {
+        return None;
+    }
+
+    // sane-airscan prints the protocol first and the address last
+    let mut words: Vec<&str> =
+        description.split_whitespace().filter(|word| !word.starts_with("ip=")).collect();
+    if matches!(words.first(), Some(&"eSCL") | Some(&"WSD")) {
+        words.remove(0);
+    }
+    let description = words.join(" ");
+    let (description, device_type) =
+        match DEVICE_TYPES.iter().find(|device_type| description.ends_with(*device_type)) {
+            Some(device_type) => {
+                (description[..description.len() - device_type.len()].trim(), Some(*device_type))
+            }
+            None => (description.as_str(), None),
+        };
+    let (vendor, model) = description.split_once(' ').unwrap_or((description, ""));
+
+    let network = NETWORK_BACKENDS.contains(&backend)
+        || name.contains("/net/")
+        || name.contains("bjnp://")
+        || name.contains("tcp ");
+    Some(Scanner {
+        vendor: vendor.to_string(),
+        model: model.replace('_', " "),
+        device_type: device_type.map(str::to_string),
+        backend: backend.to_string(),
+        connection: if network { "network" } else { "usb" }.to_string(),
+    })
+}
+
+/// Parse `scanimage -L`
+pub fn parse_devices(output: &str) -> Vec<Scanner> {
+    let mut scanners: Vec<Scanner> = Vec::new();
+    for scanner in output.lines().filter_map(parse_device) {
+        // Backends such as hpaio and airscan can both offer the same device
+        if !scanners.iter().any(|known| {
+            known.vendor == scanner.vendor
+                && known.model == scanner.model
+                && known.connection == scanner.connection
+        }) {
+            scanners.push(scanner);
+        }
+    }
+    scanners
+}
+
+/// Detector listing scanners with scanimage
+pub struct ScanimageDetector;
+
+impl ScanimageDetector {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl Default for ScanimageDetector {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl HardwareDetector for ScanimageDetector {
+    fn name(&self) -> &'static str {
+        "scanimage"
+    }
+
+    async fn is_available(&self) -> bool {
+        tokio::process::Command::new("which")
+            .arg("scanimage")
+            .output()
+            .await
+            .map(|output| output.status.success())
+            .unwrap_or(false)
+    }
+
+    async fn execute(&self) -> Result<Output> {
+        sandbox::output(tokio::process::Command::new("scanimage").arg("-L"))
+            .await
+            .map_err(|e| LxHwError::SystemCommandError { command: format!("scanimage: {}", e) })
+    }
+
+    fn timeout(&self) -> Duration {
+        // Network backends probe the local network
+        Duration::from_secs(30)
+    }
+
+    fn parse_output(&self, output: &Output) -> Result<DetectionResult> {
+        if !output.status.success() {
+            return Ok(DetectionResult {
+                tool_name: self.name().to_string(),
+                success: false,
+                data: DetectionData::Scanimage(ScanimageData::default()),
+                errors: vec![DetectionError::BadExit {
+                    tool: self.name().to_string(),
+                    code: output.status.code(),
+                    stderr: String::from_utf8_lossy(&output.stderr).to_string(),
+                }],
+            });
+        }
+
+        // "No scanners were identified" also exits with success
+        let scanners = parse_devices(&String::from_utf8_lossy(&output.stdout));
+        if scanners.is_empty() {
+            return Ok(DetectionResult {
+                tool_name: self.name().to_string(),
+                success: false,
+                data: DetectionData::Scanimage(ScanimageData::default()),
+                errors: vec![DetectionError::EmptyOutput { tool: self.name().to_string() }],
+            });
+        }
+
+        Ok(DetectionResult {
+            tool_name: self.name().to_string(),
+            success: true,
+            data: DetectionData::Scanimage(ScanimageData { scanners }),
+            errors: Vec::new(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SCANIMAGE: &str = "device `genesys:libusb:001:005' is a Canon LiDE 220 flatbed scanner
+device `hpaio:/usb/LaserJet_Pro_MFP_M428f?serial=PHB8K12345' is a Hewlett-Packard LaserJet_Pro_MFP_M428f all-in-one
+device `airscan:e0:HP LaserJet MFP M234sdw' is a eSCL HP LaserJet MFP M234sdw ip=192.168.1.5
+device `v4l:/dev/video0' is a Noname Integrated Camera: Integrated C virtual device
+";
+
+    #[test]
+    fn test_parse_devices() {
+        let scanners = parse_devices(SCANIMAGE);
+        assert_eq!(scanners.len(), 3);
+        assert_eq!(
+            scanners[0],
+            Scanner {
+                vendor: "Canon".to_string(),
+                model: "LiDE 220".to_string(),
+                device_type: Some("flatbed scanner".to_string()),
+                backend: "genesys".to_string(),
+                connection: "usb".to_string(),
+            }
+        );
+        assert_eq!(scanners[1].model, "LaserJet Pro MFP M428f");
+        assert_eq!(scanners[1].device_type.as_deref(), Some("all-in-one"));
+
+        let network = &scanners[2];
+        assert_eq!(
+            (network.vendor.as_str(), network.model.as_str()),
+            ("HP", "LaserJet MFP M234sdw")
+        );
+        assert_eq!((network.backend.as_str(), network.connection.as_str()), ("airscan", "network"));
+        assert_eq!(network.device_type, None);
+
+        assert!(parse_devices("\nNo scanners were identified.\n").is_empty());
+    }
+}
@@ -0,0 +1,382 @@
+//! Drive health from smartmontools
+//!
+//! `smartctl --scan -j` lists the drives smartctl can talk to and the
+//! device type to address each with; `smartctl -j -a` then reads every
+//! drive's SMART data. Power-on hours, remapped sectors and SSD wear are
+//! kept, and the data is attached to the storage devices other tools found.
+//! Reading SMART data needs root. Health data makes a drive recognisable
+//! across reports, so it is left out of reports at the Strict level.
+
+use super::extract::{ComponentExtractor, ExtractionContext};
+use super::{
+    sandbox, DetectionData, DetectionError, DetectionResult, HardwareDetector, Privileges,
+};
+use crate::errors::{LxHwError, Result};
+use crate::hardware::{DriveHealth, StorageDevice};
+use async_trait::async_trait;
+use serde::Deserialize;
+use serde_json::Value;
+use std::process::Output;
+use std::time::Duration;
+
+const DEVICE_MARKER: &str = "--- DEVICE ";
+
+/// ATA attributes whose normalized value is the rated endurance left, in percent
+const REMAINING_LIFE_ATTRIBUTES: &[&str] = &[
+    "Wear_Leveling_Count",
+    "SSD_Life_Left",
+    "Media_Wearout_Indicator",
+    "Percent_Lifetime_Remain",
+    "Remaining_Lifetime_Perc",
+];
+
+/// Drives read by smartctl
+#[derive(Debug, Clone, Default)]
+pub struct SmartctlData {
+    pub drives: Vec<SmartDrive>,
+}
+
+/// One drive's identity and health
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SmartDrive {
+    /// Device node, e.g. "/dev/sda"
+    pub device: String,
+    /// "ATA", "NVMe" or "SCSI"
+    pub protocol: Option<String>,
+    pub model: Option<String>,
+    pub serial: Option<String>,
+    pub size_bytes: u64,
+    /// Spindle speed; 0 for solid state drives
+    pub rotation_rate: Option<u64>,
+    pub health: DriveHealth,
+}
+
+impl ComponentExtractor for SmartctlData {
+    fn storage(&self, ctx: &mut ExtractionContext) -> Result<Vec<StorageDevice>> {
+        let mut storage_devices = Vec::new();
+        for drive in &self.drives {
+            let anonymized_serial = match &drive.serial {
+                Some(serial) => ctx.anonymize(serial)?,
+                None => "unknown".to_string(),
+            };
+            let device_type = match (drive.protocol.as_deref(), drive.rotation_rate) {
+                (Some("NVMe"), _) => "NVMe SSD",
+                (_, Some(0)) => "SSD",
+                _ => "HDD",
+            };
+            let interface = drive.protocol.as_deref().map(|protocol| match protocol {
+                "ATA" => "SATA".to_string(),
+                other => other.to_string(),
+            });
+            storage_devices.push(StorageDevice {
+                anonymized_serial,
+                device_type: device_type.to_string(),
+                size_bytes: drive.size_bytes,
+                model: drive.model.clone().unwrap_or("Unknown Storage".to_string()),
+                vendor: None,
+                interface,
+                nvme: None,
+                health: Some(drive.health.clone()),
+                topology: None,
+            });
+        }
+        Ok(storage_devices)
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct Scan {
+    #[serde(default)]
+    devices: Vec<ScannedDevice>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ScannedDevice {
+    name: String,
+    #[serde(rename = "type")]
+    device_type: Option<String>,
+}
+
+/// Parse `smartctl --scan -j` into device nodes and their `-d` types
+fn parse_scan(json: &str) -> serde_json::Result<Vec<(String, Option<String>)>> {
+    let scan: Scan = serde_json::from_str(json)?;
+    Ok(scan.devices.into_iter().map(|device| (device.name, device.device_type)).collect())
+}
+
+/// Parse `smartctl -j -a` output for one drive
+pub fn parse_drive(device: &str, json: &str) -> Option<SmartDrive> {
+    let data: Value = serde_json::from_str(json).ok()?;
+    let text = |pointer: &str| {
+        data.pointer(pointer)
+            .and_then(Value::as_str)
+            .map(|value| value.trim().to_string())
+            .filter(|value| !value.is_empty())
+    };
+    let number = |pointer: &str| data.pointer(pointer).and_then(Value::as_u64);
+
+    // smartctl prints the identity even when the SMART commands failed
+    let model = text("/model_name")?;
+    let attributes: Vec<&Value> = data
+        .pointer("/ata_smart_attributes/table")
+        .and_then(Value::as_array)
+        .map(|table| table.iter().collect())
+        .unwrap_or_default();
+    let attribute = |name: &str| {
+        attributes.iter().find(|entry| entry.get("name").and_then(Value::as_str) == Some(name))
+    };
+    let raw = |name: &str| attribute(name).and_then(|entry| entry.pointer("/raw/value")?.as_u64());
+
+    let remaining_life = REMAINING_LIFE_ATTRIBUTES
+        .iter()
+        .find_map(|name| attribute(name).and_then(|entry| entry.get("value")?.as_u64()));
+    let wear_percent = number("/endurance_used/current_percent")
+        .or_else(|| number("/nvme_smart_health_information_log/percentage_used"))
+        .or_else(|| remaining_life.map(|left| 100 - left.min(100)))
+        .map(|percent| percent.min(u64::from(u8::MAX)) as u8);
+
+    Some(SmartDrive {
+        device: device.to_string(),
+        protocol: text("/device/protocol"),
+        model: Some(model),
+        serial: text("/serial_number"),
+        size_bytes: number("/user_capacity/bytes")
+            .or_else(|| number("/nvme_total_capacity"))
+            .unwrap_or(0),
+        rotation_rate: number("/rotation_rate"),
+        health: DriveHealth {
+            passed: data.pointer("/smart_status/passed").and_then(Value::as_bool),
+            power_on_hours: number("/power_on_time/hours"),
+            reallocated_sectors: raw("Reallocated_Sector_Ct"),
+            pending_sectors: raw("Current_Pending_Sector"),
+            wear_percent,
+            temperature_celsius: data
+                .pointer("/temperature/current")
+                .and_then(Value::as_i64)
+                .map(|celsius| celsius as i32),
+        },
+    })
+}
+
+/// Detector reading SMART health data with smartctl
+pub struct SmartctlDetector;
+
+impl SmartctlDetector {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl Default for SmartctlDetector {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl HardwareDetector for SmartctlDetector {
+    fn name(&self) -> &'static str {
+        "smartctl"
+    }
+
+    async fn is_available(&self) -> bool {
+        tokio::process::Command::new("which")
+            .arg("smartctl")
+            .output()
+            .await
+            .map(|output| output.status.success())
+            .unwrap_or(false)
+    }
+
+    async fn execute(&self) -> Result<Output> {
+        let mut output =
+            sandbox::output(tokio::process::Command::new("smartctl").args(["--scan", "-j"]))
+                .await
+                .map_err(|e| LxHwError::SystemCommandError {
+                    command: format!("smartctl: {}", e),
+                })?;
+        if !output.status.success() {
+            return Ok(output);
+        }
+
+        let devices = parse_scan(&String::from_utf8_lossy(&output.stdout)).unwrap_or_default();
+        for (device, device_type) in devices {
+            let mut command = tokio::process::Command::new("smartctl");
+            command.args(["-j", "-a"]);
+            if let Some(device_type) = &device_type {
+                command.args(["-d", device_type]);
+            }
+            command.arg(&device);
+            // The exit status is a bit mask that is also nonzero for failing
+            // drives, so any JSON output is kept
+            match sandbox::output(&mut command).await {
+                Ok(drive) if !drive.stdout.is_empty() => {
+                    output.stdout.extend_from_slice(
+                        format!("\n{}{} ---\n", DEVICE_MARKER, device).as_bytes(),
+                    );
+                    output.stdout.extend_from_slice(&drive.stdout);
+                }
+                Ok(drive) => output.stderr.extend_from_slice(&drive.stderr),
+                Err(e) => log::debug!("smartctl {} failed: {}", device, e),
+            }
+        }
+        Ok(output)
+    }
+
+    fn timeout(&self) -> Duration {
+        // Drives in standby spin up to answer
+        Duration::from_secs(30)
+    }
+
+    fn privileges(&self) -> Privileges {
+        // Drives only answer SMART queries from root
+        Privileges::RootRequired
+    }
+
+    fn parse_output(&self, output: &Output) -> Result<DetectionResult> {
+        if !output.status.success() {
+            return Ok(DetectionResult {
+                tool_name: self.name().to_string(),
+                success: false,
+                data: DetectionData::Smartctl(SmartctlData::default()),
+                errors: vec![DetectionError::BadExit {
+                    tool: self.name().to_string(),
+                    code: output.status.code(),
+                    stderr: String::from_utf8_lossy(&output.stderr).to_string(),
+                }],
+            });
+        }
+
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let separator = format!("\n{}", DEVICE_MARKER);
+        let mut sections = stdout.split(separator.as_str());
+        if let Err(e) = parse_scan(sections.next().unwrap_or_default()) {
+            return Ok(DetectionResult {
+                tool_name: self.name().to_string(),
+                success: false,
+                data: DetectionData::Smartctl(SmartctlData::default()),
+                errors: vec![DetectionError::json("JSON parsing failed", &e)],
+            });
+        }
+
+        let drives: Vec<SmartDrive> = sections
+            .filter_map(|section| {
+                let (device, json) = section.split_once(" ---\n")?;
+                parse_drive(device, json)
+            })
+            .collect();
+        if drives.is_empty() {
+            // Without root smartctl lists the drives but cannot open them
+            return Ok(DetectionResult {
+                tool_name: self.name().to_string(),
+                success: false,
+                data: DetectionData::Smartctl(SmartctlData::default()),
+                errors: vec![DetectionError::EmptyOutput { tool: self.name().to_string() }],
+            });
+        }
+
+        Ok(DetectionResult {
+            tool_name: self.name().to_string(),
+            success: true,
+            data: DetectionData::Smartctl(SmartctlData { drives }),
+            errors: Vec::new(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::os::unix::process::ExitStatusExt;
+
+    const SCAN: &str = r#"{
+      "json_format_version": [1, 0],
+      "devices": [
+        {"name": "/dev/sda", "info_name": "/dev/sda [SAT]", "type": "sat", "protocol": "ATA"},
+        {"name": "/dev/nvme0", "info_name": "/dev/nvme0", "type": "nvme", "protocol": "NVMe"}
+      ]
+    }"#;
+
+    const SATA_SSD: &str = r#"{
+      "device": {"name": "/dev/sda", "type": "sat", "protocol": "ATA"},
+      "model_name": "Samsung SSD 860 EVO 500GB",
+      "serial_number": "S3Z2NB0K123456A",
+      "user_capacity": {"blocks": 976773168, "bytes": 500107862016},
+      "rotation_rate": 0,
+      "smart_status": {"passed": true},
+      "ata_smart_attributes": {
+        "table": [
+          {"id": 5, "name": "Reallocated_Sector_Ct", "value": 100, "raw": {"value": 2, "string": "2"}},
+          {"id": 9, "name": "Power_On_Hours", "value": 95, "raw": {"value": 21034, "string": "21034"}},
+          {"id": 177, "name": "Wear_Leveling_Count", "value": 88, "raw": {"value": 131, "string": "131"}},
+          {"id": 197, "name": "Current_Pending_Sector", "value": 100, "raw": {"value": 0, "string": "0"}}
+        ]
+      },
+      "power_on_time": {"hours": 21034},
+      "temperature": {"current": 31}
+    }"#;
+
+    const NVME: &str = r#"{
+      "device": {"name": "/dev/nvme0", "type": "nvme", "protocol": "NVMe"},
+      "model_name": "WD_BLACK SN850X 2000GB",
+      "serial_number": "23123A801234",
+      "nvme_total_capacity": 2000398934016,
+      "smart_status": {"passed": true},
+      "nvme_smart_health_information_log": {"percentage_used": 2, "power_on_hours": 1840},
+      "power_on_time": {"hours": 1840},
+      "temperature": {"current": 42}
+    }"#;
+
+    fn output(stdout: String) -> Output {
+        Output {
+            status: ExitStatusExt::from_raw(0),
+            stdout: stdout.into_bytes(),
+            stderr: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_parse_drives() {
+        let stdout = format!(
+            "{}\n{}/dev/sda ---\n{}\n{}/dev/nvme0 ---\n{}",
+            SCAN, DEVICE_MARKER, SATA_SSD, DEVICE_MARKER, NVME
+        );
+        let result = SmartctlDetector::new().parse_output(&output(stdout)).unwrap();
+        assert!(result.success);
+        let DetectionData::Smartctl(data) = result.data else {
+            panic!("expected smartctl data");
+        };
+        assert_eq!(data.drives.len(), 2);
+
+        let ssd = &data.drives[0];
+        assert_eq!(ssd.device, "/dev/sda");
+        assert_eq!(ssd.size_bytes, 500_107_862_016);
+        assert_eq!(ssd.rotation_rate, Some(0));
+        assert_eq!(ssd.health.passed, Some(true));
+        assert_eq!(ssd.health.power_on_hours, Some(21034));
+        assert_eq!(ssd.health.reallocated_sectors, Some(2));
+        assert_eq!(ssd.health.pending_sectors, Some(0));
+        assert_eq!(ssd.health.wear_percent, Some(12));
+        assert_eq!(ssd.health.temperature_celsius, Some(31));
+
+        let nvme = &data.drives[1];
+        assert_eq!(nvme.protocol.as_deref(), Some("NVMe"));
+        assert_eq!(nvme.size_bytes, 2_000_398_934_016);
+        assert_eq!(nvme.health.wear_percent, Some(2));
+        assert_eq!(nvme.health.reallocated_sectors, None);
+    }
+
+    #[test]
+    fn test_unreadable_drives_are_not_a_success() {
+        // Without root every drive fails to open and prints no model
+        let stdout = format!(
+            "{}\n{}/dev/sda ---\n{}",
+            SCAN, DEVICE_MARKER, r#"{"smartctl": {"exit_status": 2}}"#
+        );
+        let result = SmartctlDetector::new().parse_output(&output(stdout)).unwrap();
+        assert!(!result.success);
+        assert_eq!(
+            parse_scan(SCAN).unwrap()[1],
+            ("/dev/nvme0".to_string(), Some("nvme".to_string()))
+        );
+    }
+}
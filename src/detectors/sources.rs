@@ -0,0 +1,327 @@
+//! Verification of the kernel data sources the analyzer depends on
+//!
+//! Besides the userspace detection tools, hardware analysis reads sysfs,
+//! the kernel's modules.alias table and configuration, DMI tables, and
+//! (for kernel source analysis) the network. This module checks each of
+//! those sources and explains how to fix the ones that are missing, and
+//! where the detection tools themselves are installed.
+
+use crate::detectors::kernel::KernelSupportVerifier;
+use crate::detectors::{privilege, DetectorRegistry, Privileges};
+use serde::{Deserialize, Serialize};
+use std::ffi::OsStr;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// Host probed to decide whether kernel source analysis can reach GitHub
+const KERNEL_SOURCE_HOST: &str = "api.github.com:443";
+
+/// Timeout for the network reachability probe
+const NETWORK_PROBE_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// Directories holding system tools that an ordinary user's PATH may leave out
+const SBIN_DIRS: [&str; 3] = ["/usr/local/sbin", "/usr/sbin", "/sbin"];
+
+/// Availability of a data source
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SourceStatus {
+    /// Source is fully usable
+    Available,
+    /// Source is usable with reduced detail
+    Degraded,
+    /// Source cannot be used
+    Unavailable,
+}
+
+/// Result of checking a single data source
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DataSourceCheck {
+    /// Short identifier of the source
+    pub name: String,
+    pub status: SourceStatus,
+    /// What was found
+    pub detail: String,
+    /// How to fix the source when it is not available
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub remediation: Option<String>,
+}
+
+impl DataSourceCheck {
+    fn available(name: &str, detail: impl Into<String>) -> Self {
+        Self {
+            name: name.to_string(),
+            status: SourceStatus::Available,
+            detail: detail.into(),
+            remediation: None,
+        }
+    }
+
+    fn problem(
+        name: &str,
+        status: SourceStatus,
+        detail: impl Into<String>,
+        remediation: impl Into<String>,
+    ) -> Self {
+        Self {
+            name: name.to_string(),
+            status,
+            detail: detail.into(),
+            remediation: Some(remediation.into()),
+        }
+    }
+}
+
+/// Result of checking a detection tool
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ToolCheck {
+    /// Detector name, as given to `--tools`
+    pub name: String,
+    pub available: bool,
+    /// Whether the detector runs in-process, with nothing to install
+    pub builtin: bool,
+    /// Executable that runs, None for built-in detectors and missing tools
+    pub path: Option<PathBuf>,
+    /// First line of the tool's version output
+    pub version: Option<String>,
+    pub privileges: Privileges,
+    /// Capabilities the tool needs that this process lacks, e.g. "CAP_SYS_RAWIO"
+    pub missing_capabilities: Vec<String>,
+    pub timeout_secs: u64,
+}
+
+/// Everything `check` verifies about the detection environment
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EnvironmentCheck {
+    pub running_as_root: bool,
+    pub tools: Vec<ToolCheck>,
+    pub data_sources: Vec<DataSourceCheck>,
+}
+
+impl EnvironmentCheck {
+    /// Check the registry's tools and the kernel data sources
+    pub async fn run(registry: &DetectorRegistry) -> Self {
+        Self {
+            running_as_root: is_root(),
+            tools: check_tools(registry).await,
+            data_sources: check_data_sources().await,
+        }
+    }
+}
+
+/// Check every detection tool in the registry
+pub async fn check_tools(registry: &DetectorRegistry) -> Vec<ToolCheck> {
+    let path_var = std::env::var_os("PATH");
+    let effective = privilege::effective_capabilities();
+    let mut checks = Vec::new();
+    for detector in registry.list_detectors() {
+        let available = detector.is_available().await;
+        let path = (available && !detector.is_builtin())
+            .then(|| find_program(detector.program(), path_var.as_deref()))
+            .flatten();
+        let version = match &path {
+            Some(path) => crate::telemetry::tool_version(&path.to_string_lossy()).await,
+            None => None,
+        };
+        checks.push(ToolCheck {
+            name: detector.name().to_string(),
+            available,
+            builtin: detector.is_builtin(),
+            path,
+            version,
+            privileges: detector.privileges(),
+            missing_capabilities: privilege::missing_capabilities(detector.program(), effective)
+                .into_iter()
+                .map(|capability| capability.name().to_string())
+                .collect(),
+            timeout_secs: detector.timeout().as_secs(),
+        });
+    }
+    checks
+}
+
+/// Locate `program` on `path_var`, then in the sbin directories
+fn find_program(program: &str, path_var: Option<&OsStr>) -> Option<PathBuf> {
+    path_var
+        .into_iter()
+        .flat_map(std::env::split_paths)
+        .chain(SBIN_DIRS.iter().map(PathBuf::from))
+        .map(|dir| dir.join(program))
+        .find(|path| path.is_file())
+}
+
+/// Check every kernel data source used during analysis
+pub async fn check_data_sources() -> Vec<DataSourceCheck> {
+    let verifier = KernelSupportVerifier::new().ok();
+
+    vec![
+        check_sysfs(Path::new("/sys/bus/pci/devices")),
+        check_modules_alias(verifier.as_ref()),
+        check_kernel_config(verifier.as_ref()),
+        check_dmi_access(is_root(), Path::new("/sys/firmware/dmi/tables/DMI")),
+        check_network().await,
+    ]
+}
+
+/// Check that sysfs PCI device entries can be listed
+fn check_sysfs(pci_devices: &Path) -> DataSourceCheck {
+    match std::fs::read_dir(pci_devices) {
+        Ok(entries) => {
+            let count = entries.count();
+            if count > 0 {
+                DataSourceCheck::available("sysfs", format!("{} PCI devices readable", count))
+            } else {
+                DataSourceCheck::problem(
+                    "sysfs",
+                    SourceStatus::Degraded,
+                    format!("{} contains no devices", pci_devices.display()),
+                    "Running in a container or VM without PCI passthrough limits detection; run on the host instead",
+                )
+            }
+        }
+        Err(e) => DataSourceCheck::problem(
+            "sysfs",
+            SourceStatus::Unavailable,
+            format!("Cannot read {}: {}", pci_devices.display(), e),
+            "Ensure /sys is mounted (mount -t sysfs sysfs /sys) and readable by this user",
+        ),
+    }
+}
+
+/// Check that the modules.alias table for the running kernel exists
+fn check_modules_alias(verifier: Option<&KernelSupportVerifier>) -> DataSourceCheck {
+    let Some(verifier) = verifier else {
+        return DataSourceCheck::problem(
+            "modules.alias",
+            SourceStatus::Unavailable,
+            "Could not determine the running kernel version",
+            "Ensure the uname command is available",
+        );
+    };
+
+    let path = verifier.modules_alias_path();
+    if Path::new(path).is_file() {
+        DataSourceCheck::available("modules.alias", path)
+    } else {
+        DataSourceCheck::problem(
+            "modules.alias",
+            SourceStatus::Unavailable,
+            format!("{} not found", path),
+            format!(
+                "Install the module package for kernel {} or run 'depmod -a' to regenerate it",
+                verifier.kernel_version()
+            ),
+        )
+    }
+}
+
+/// Check that a kernel configuration file is available
+fn check_kernel_config(verifier: Option<&KernelSupportVerifier>) -> DataSourceCheck {
+    match verifier.and_then(|v| v.config_path()) {
+        Some(path) => DataSourceCheck::available("kernel-config", path),
+        None => DataSourceCheck::problem(
+            "kernel-config",
+            SourceStatus::Degraded,
+            "No /proc/config.gz or /boot/config-* found",
+            "Load the configs module (modprobe configs) or install the kernel config file so CONFIG_ dependencies can be verified",
+        ),
+    }
+}
+
+/// Check whether DMI tables can be read, which dmidecode requires
+fn check_dmi_access(is_root: bool, dmi_table: &Path) -> DataSourceCheck {
+    if is_root {
+        return DataSourceCheck::available("dmi", "Running as root");
+    }
+
+    if std::fs::File::open(dmi_table).is_ok() {
+        return DataSourceCheck::available("dmi", format!("{} readable", dmi_table.display()));
+    }
+
+    DataSourceCheck::problem(
+        "dmi",
+        SourceStatus::Degraded,
+        "DMI tables require root; dmidecode will report no BIOS, memory, or processor details",
+        "Run with sudo to include DMI data",
+    )
+}
+
+/// Check that the kernel source analysis endpoint is reachable
+async fn check_network() -> DataSourceCheck {
+    let probe = tokio::time::timeout(
+        NETWORK_PROBE_TIMEOUT,
+        tokio::net::TcpStream::connect(KERNEL_SOURCE_HOST),
+    )
+    .await;
+
+    match probe {
+        Ok(Ok(_)) => DataSourceCheck::available("network", format!("{} reachable", KERNEL_SOURCE_HOST)),
+        Ok(Err(e)) => DataSourceCheck::problem(
+            "network",
+            SourceStatus::Degraded,
+            format!("Cannot reach {}: {}", KERNEL_SOURCE_HOST, e),
+            "Kernel source analysis needs network access; use --kernel-repo with a local checkout instead",
+        ),
+        Err(_) => DataSourceCheck::problem(
+            "network",
+            SourceStatus::Degraded,
+            format!("Timed out connecting to {} after {:?}", KERNEL_SOURCE_HOST, NETWORK_PROBE_TIMEOUT),
+            "Kernel source analysis needs network access; use --kernel-repo with a local checkout instead",
+        ),
+    }
+}
+
+fn is_root() -> bool {
+    nix::unistd::geteuid().is_root()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_sysfs_missing_directory() {
+        let check = check_sysfs(Path::new("/nonexistent/sys/bus/pci/devices"));
+        assert_eq!(check.status, SourceStatus::Unavailable);
+        assert!(check.remediation.is_some());
+    }
+
+    #[test]
+    fn test_sysfs_empty_directory_is_degraded() {
+        let dir = TempDir::new().unwrap();
+        let check = check_sysfs(dir.path());
+        assert_eq!(check.status, SourceStatus::Degraded);
+    }
+
+    #[test]
+    fn test_dmi_access() {
+        let missing = Path::new("/nonexistent/DMI");
+        assert_eq!(check_dmi_access(true, missing).status, SourceStatus::Available);
+
+        let check = check_dmi_access(false, missing);
+        assert_eq!(check.status, SourceStatus::Degraded);
+        assert!(check.remediation.unwrap().contains("sudo"));
+    }
+
+    #[test]
+    fn test_find_program_searches_path_in_order() {
+        let first = TempDir::new().unwrap();
+        let second = TempDir::new().unwrap();
+        std::fs::write(second.path().join("lshw"), "").unwrap();
+        let path_var = std::env::join_paths([first.path(), second.path()]).unwrap();
+
+        assert_eq!(find_program("lshw", Some(&path_var)), Some(second.path().join("lshw")));
+        std::fs::write(first.path().join("lshw"), "").unwrap();
+        assert_eq!(find_program("lshw", Some(&path_var)), Some(first.path().join("lshw")));
+        assert_eq!(find_program("lx-hw-no-such-tool", Some(&path_var)), None);
+    }
+
+    #[test]
+    fn test_status_serializes_lowercase() {
+        let check = DataSourceCheck::available("sysfs", "ok");
+        let json = serde_json::to_value(&check).unwrap();
+        assert_eq!(json["status"], "available");
+        assert!(json.get("remediation").is_none());
+    }
+}
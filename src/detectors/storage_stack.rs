@@ -0,0 +1,386 @@
+//! Software RAID arrays and LVM volume groups
+//!
+//! md arrays come from `/proc/mdstat`, including Intel Matrix and DDF
+//! firmware RAID assembled by mdadm, whose metadata shows as `external:imsm`
+//! or `external:ddf`. Older firmware RAID sets run by dmraid come from
+//! `dmraid -s`, and LVM logical volumes from `lvs`, which needs root.
+//! Volume group, volume and set names are chosen by users or installers,
+//! which put hostnames in them, so only levels, sizes and health are kept.
+
+use crate::hardware::{
+    LogicalVolume, RaidArray, RaidHealth, RaidMember, StorageStack, VolumeGroup,
+};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::path::Path;
+
+/// Read the storage stack from `/proc/mdstat` and the output of lvs and dmraid
+///
+/// Returns None when the system has neither RAID nor LVM.
+pub fn read_storage_stack(
+    proc_mdstat: &Path,
+    lvs: Option<&str>,
+    dmraid: Option<&str>,
+) -> Option<StorageStack> {
+    let (md_personalities, mut raid_arrays) = std::fs::read_to_string(proc_mdstat)
+        .map(|mdstat| parse_mdstat(&mdstat))
+        .unwrap_or_default();
+    raid_arrays.extend(dmraid.map(parse_dmraid).unwrap_or_default());
+    let stack = StorageStack {
+        md_personalities,
+        raid_arrays,
+        volume_groups: lvs.map(parse_lvs).unwrap_or_default(),
+    };
+    (!stack.is_empty()).then_some(stack)
+}
+
+/// Output of `lvs` with the fields [`parse_lvs`] reads
+pub fn query_lvs() -> Option<String> {
+    let output = std::process::Command::new("lvs")
+        .args(["--reportformat", "json", "--units", "b", "--nosuffix"])
+        .args(["-o", "vg_name,lv_size,segtype,lv_attr"])
+        .env("LC_ALL", "C")
+        .output()
+        .ok()?;
+    output.status.success().then(|| String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Output of `dmraid -s`, when dmraid is installed and finds sets
+pub fn query_dmraid() -> Option<String> {
+    let output = std::process::Command::new("dmraid").arg("-s").env("LC_ALL", "C").output().ok()?;
+    output.status.success().then(|| String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Parse `/proc/mdstat` into the loaded personalities and the arrays
+fn parse_mdstat(content: &str) -> (Vec<String>, Vec<RaidArray>) {
+    let mut personalities = Vec::new();
+    // Arrays by name, with the raw metadata of external arrays
+    let mut arrays: Vec<(String, RaidArray)> = Vec::new();
+    let mut lines = content.lines().peekable();
+
+    while let Some(line) = lines.next() {
+        if let Some(list) = line.strip_prefix("Personalities :") {
+            personalities = list
+                .split_whitespace()
+                .map(|p| p.trim_matches(|c| c == '[' || c == ']').to_string())
+                .collect();
+            continue;
+        }
+        let Some((name, description)) = line.split_once(" : ") else {
+            continue;
+        };
+        if !name.starts_with("md") {
+            continue;
+        }
+
+        let mut words = description.split_whitespace().peekable();
+        let active = words.next() == Some("active");
+        // "active (auto-read-only) raid1 ..." and "active (read-only) ..."
+        while words.peek().is_some_and(|word| word.starts_with('(')) {
+            words.next();
+        }
+        let level = match words.peek() {
+            Some(word) if !word.contains('[') => words.next().unwrap_or_default().to_string(),
+            _ => "container".to_string(),
+        };
+        let members: Vec<RaidMember> = words.filter_map(parse_member).collect();
+
+        // Indented lines with size, state and progress follow until a blank line
+        let mut details = String::new();
+        while let Some(detail) = lines.next_if(|line| line.starts_with(char::is_whitespace)) {
+            details.push_str(detail);
+            details.push('\n');
+        }
+        let size_kib: u64 =
+            details.split_whitespace().next().and_then(|size| size.parse().ok()).unwrap_or(0);
+        let metadata = details
+            .split_whitespace()
+            .skip_while(|word| *word != "super")
+            .nth(1)
+            .map(str::to_string);
+        let (raid_devices, working_devices) = disk_counts(&details).unwrap_or_else(|| {
+            let working = members.iter().filter(|m| m.role != "faulty" && m.role != "spare");
+            let working = working.count() as u32;
+            (working, working)
+        });
+        let sync_action = ["recovery", "resync", "reshape", "check", "repair"]
+            .iter()
+            .find(|action| details.contains(&format!("{} =", action)))
+            .map(|action| action.to_string());
+
+        let health = if !active {
+            RaidHealth::Inactive
+        } else if working_devices == 0 {
+            RaidHealth::Failed
+        } else if sync_action.as_deref() == Some("recovery") {
+            RaidHealth::Recovering
+        } else if working_devices < raid_devices {
+            RaidHealth::Degraded
+        } else {
+            RaidHealth::Healthy
+        };
+        arrays.push((
+            name.trim().to_string(),
+            RaidArray {
+                driver: "md".to_string(),
+                level,
+                metadata,
+                size_bytes: size_kib * 1024,
+                raid_devices,
+                working_devices,
+                members,
+                health,
+                sync_action,
+            },
+        ));
+    }
+
+    // "external:imsm" names the format; "external:/md127/0" the container
+    let containers: HashMap<String, Option<String>> =
+        arrays.iter().map(|(name, array)| (name.clone(), array.metadata.clone())).collect();
+    let external = |metadata: &str| -> Option<String> {
+        let external = metadata.strip_prefix("external:")?;
+        match external.strip_prefix('/') {
+            Some(path) => {
+                let container = containers.get(path.split('/').next()?)?.as_deref()?;
+                container.strip_prefix("external:").map(str::to_string)
+            }
+            None => Some(external.to_string()),
+        }
+    };
+    let arrays = arrays
+        .into_iter()
+        .map(|(_, mut array)| {
+            if let Some(format) = array.metadata.as_deref().and_then(external) {
+                array.metadata = Some(format);
+            }
+            array
+        })
+        .collect();
+    (personalities, arrays)
+}
+
+/// Parse a member like `sdb1[1]`, `sdc1[2](F)` or `sdd[0](S)`
+fn parse_member(word: &str) -> Option<RaidMember> {
+    let (device, rest) = word.split_once('[')?;
+    let role = match rest.split_once('(').map(|(_, flag)| flag.trim_end_matches(')')) {
+        Some("F") => "faulty",
+        Some("S") => "spare",
+        Some("W") => "write-mostly",
+        Some("R") => "replacement",
+        _ => "active",
+    };
+    Some(RaidMember { device: device.to_string(), role: role.to_string() })
+}
+
+/// Device counts from the `[3/2]` field of an array's status line
+fn disk_counts(details: &str) -> Option<(u32, u32)> {
+    details.split_whitespace().find_map(|word| {
+        let (total, working) = word.strip_prefix('[')?.strip_suffix(']')?.split_once('/')?;
+        Some((total.parse().ok()?, working.parse().ok()?))
+    })
+}
+
+/// Parse `lvs --reportformat json` into volume groups
+fn parse_lvs(json: &str) -> Vec<VolumeGroup> {
+    let Ok(data) = serde_json::from_str::<Value>(json) else {
+        return Vec::new();
+    };
+    let volumes = data
+        .pointer("/report/0/lv")
+        .and_then(Value::as_array)
+        .map(Vec::as_slice)
+        .unwrap_or_default();
+
+    let mut groups: Vec<(String, VolumeGroup)> = Vec::new();
+    for volume in volumes {
+        let text = |key: &str| volume.get(key).and_then(Value::as_str).unwrap_or_default().trim();
+        let attributes: Vec<char> = text("lv_attr").chars().collect();
+        let health = match attributes.get(8) {
+            None | Some('-') => "ok",
+            Some('p') => "partial",
+            Some('r') => "refresh_needed",
+            Some('m') => "mismatches",
+            Some('s') => "reshaping",
+            Some(_) => "unknown",
+        };
+        let logical_volume = LogicalVolume {
+            segment_type: text("segtype").to_string(),
+            size_bytes: text("lv_size").parse().unwrap_or(0),
+            active: attributes.get(4) == Some(&'a'),
+            health: health.to_string(),
+        };
+        let vg_name = text("vg_name");
+        match groups.iter_mut().find(|(name, _)| name == vg_name) {
+            Some((_, group)) => group.logical_volumes.push(logical_volume),
+            None => groups
+                .push((vg_name.to_string(), VolumeGroup { logical_volumes: vec![logical_volume] })),
+        }
+    }
+    groups.into_iter().map(|(_, group)| group).collect()
+}
+
+/// Parse the `name : value` blocks of `dmraid -s`
+fn parse_dmraid(content: &str) -> Vec<RaidArray> {
+    let mut arrays = Vec::new();
+    let mut fields: HashMap<&str, &str> = HashMap::new();
+    // A set starts at each `name :` line; blocks end at the next one
+    let mut flush = |fields: &mut HashMap<&str, &str>| {
+        if let Some(array) = dmraid_array(fields) {
+            arrays.push(array);
+        }
+        fields.clear();
+    };
+    for line in content.lines() {
+        let Some((key, value)) = line.split_once(':') else {
+            continue;
+        };
+        let key = key.trim();
+        if key == "name" {
+            flush(&mut fields);
+        }
+        fields.insert(key, value.trim());
+    }
+    flush(&mut fields);
+    arrays
+}
+
+fn dmraid_array(fields: &HashMap<&str, &str>) -> Option<RaidArray> {
+    fields.get("name")?;
+    let level = match *fields.get("type")? {
+        "mirror" => "raid1".to_string(),
+        "stripe" => "raid0".to_string(),
+        "raid10" | "raid01" => "raid10".to_string(),
+        other if other.starts_with("raid5") => "raid5".to_string(),
+        other => other.to_string(),
+    };
+    let number = |key: &str| fields.get(key).and_then(|value| value.parse::<u64>().ok());
+    let devices = number("devs").unwrap_or(0) as u32;
+    let health = match fields.get("status").copied() {
+        Some("ok") => RaidHealth::Healthy,
+        Some("inconsistent") | Some("nosync") => RaidHealth::Degraded,
+        Some("broken") => RaidHealth::Failed,
+        _ => RaidHealth::Inactive,
+    };
+    Some(RaidArray {
+        driver: "dmraid".to_string(),
+        level,
+        metadata: None,
+        // dmraid counts 512-byte sectors
+        size_bytes: number("size").unwrap_or(0) * 512,
+        raid_devices: devices,
+        working_devices: if health == RaidHealth::Healthy { devices } else { 0 },
+        members: Vec::new(),
+        health,
+        sync_action: None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MDSTAT: &str = "\
+Personalities : [raid1] [raid6] [raid5] [raid4]
+md127 : inactive sdb[1](S) sda[0](S)
+      5288 blocks super external:imsm
+
+md126 : active raid1 sda[1] sdb[0]
+      976759808 blocks super external:/md127/0 [2/2] [UU]
+
+md0 : active raid5 sdd1[3] sdc1[1] sdb1[0](F)
+      1953260544 blocks super 1.2 level 5, 512k chunk, algorithm 2 [3/1] [_U_]
+      [==>..................]  recovery = 12.6% (123136/976630272) finish=75.1min speed=189000K/sec
+      bitmap: 0/8 pages [0KB], 65536KB chunk
+
+md1 : active (auto-read-only) raid1 nvme1n1p1[1] nvme0n1p1[0]
+      523264 blocks super 1.0 [2/2] [UU]
+
+unused devices: <none>
+";
+
+    #[test]
+    fn test_parse_mdstat() {
+        let (personalities, arrays) = parse_mdstat(MDSTAT);
+        assert_eq!(personalities, ["raid1", "raid6", "raid5", "raid4"]);
+        assert_eq!(arrays.len(), 4);
+
+        let container = &arrays[0];
+        assert_eq!(
+            (container.level.as_str(), container.health),
+            ("container", RaidHealth::Inactive)
+        );
+        assert_eq!(container.metadata.as_deref(), Some("imsm"));
+        assert!(container.members.iter().all(|m| m.role == "spare"));
+
+        // Volumes inside a firmware RAID container take its format
+        let volume = &arrays[1];
+        assert_eq!(volume.metadata.as_deref(), Some("imsm"));
+        assert_eq!(volume.size_bytes, 976_759_808 * 1024);
+        assert_eq!(volume.health, RaidHealth::Healthy);
+
+        let raid5 = &arrays[2];
+        assert_eq!((raid5.raid_devices, raid5.working_devices), (3, 1));
+        assert_eq!(raid5.health, RaidHealth::Recovering);
+        assert_eq!(raid5.sync_action.as_deref(), Some("recovery"));
+        assert_eq!(raid5.members[2], RaidMember { device: "sdb1".into(), role: "faulty".into() });
+
+        let boot = &arrays[3];
+        assert_eq!((boot.level.as_str(), boot.metadata.as_deref()), ("raid1", Some("1.0")));
+        assert_eq!(boot.members.len(), 2);
+    }
+
+    #[test]
+    fn test_parse_lvs() {
+        let lvs = r#"{
+          "report": [
+            {
+              "lv": [
+                {"vg_name":"fedora_workstation", "lv_size":"107374182400", "segtype":"linear", "lv_attr":"-wi-ao----"},
+                {"vg_name":"fedora_workstation", "lv_size":"8589934592", "segtype":"linear", "lv_attr":"-wi-ao----"},
+                {"vg_name":"data", "lv_size":"1000204886016", "segtype":"raid1", "lv_attr":"rwi-a-r-p-"}
+              ]
+            }
+          ]
+        }"#;
+        let groups = parse_lvs(lvs);
+        assert_eq!(groups.len(), 2);
+        assert_eq!(groups[0].logical_volumes.len(), 2);
+        let mirror = &groups[1].logical_volumes[0];
+        assert_eq!((mirror.segment_type.as_str(), mirror.size_bytes), ("raid1", 1_000_204_886_016));
+        assert!(mirror.active);
+        assert_eq!(mirror.health, "partial");
+        assert!(parse_lvs("  WARNING: Running as a non-root user.").is_empty());
+    }
+
+    #[test]
+    fn test_parse_dmraid() {
+        let dmraid = "\
+*** Group superset isw_bhfgdjfii
+--> Active Subset
+name   : isw_bhfgdjfii_Volume0
+size   : 976768256
+stride : 128
+type   : mirror
+status : ok
+subsets: 0
+devs   : 2
+spares : 0
+";
+        let arrays = parse_dmraid(dmraid);
+        assert_eq!(arrays.len(), 1);
+        assert_eq!((arrays[0].driver.as_str(), arrays[0].level.as_str()), ("dmraid", "raid1"));
+        assert_eq!(arrays[0].size_bytes, 976_768_256 * 512);
+        assert_eq!((arrays[0].raid_devices, arrays[0].health), (2, RaidHealth::Healthy));
+        assert!(parse_dmraid("no raid disks").is_empty());
+    }
+
+    #[test]
+    fn test_no_storage_stack() {
+        let dir = tempfile::tempdir().unwrap();
+        let mdstat = dir.path().join("mdstat");
+        std::fs::write(&mdstat, "Personalities : \nunused devices: <none>\n").unwrap();
+        assert_eq!(read_storage_stack(&mdstat, None, None), None);
+        assert!(read_storage_stack(&mdstat, None, Some("name : x\ntype : stripe\n")).is_some());
+    }
+}
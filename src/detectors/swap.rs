@@ -0,0 +1,76 @@
+//! Active swap from `/proc/swaps` and the zswap module parameter
+//!
+//! zram devices show up in `/proc/swaps` as partitions named `/dev/zramN`.
+//! Device and file paths are not kept in the report.
+
+use crate::hardware::{SwapArea, SwapInfo, SwapKind};
+use std::path::Path;
+
+/// Read active swap areas and whether zswap is enabled
+///
+/// Returns None when `/proc/swaps` cannot be read.
+pub fn read_swap(proc_swaps: &Path, zswap_enabled: &Path) -> Option<SwapInfo> {
+    let swaps = std::fs::read_to_string(proc_swaps).ok()?;
+    let zswap_enabled =
+        std::fs::read_to_string(zswap_enabled).ok().and_then(|value| match value.trim() {
+            "Y" | "1" => Some(true),
+            "N" | "0" => Some(false),
+            _ => None,
+        });
+    Some(SwapInfo { areas: parse_proc_swaps(&swaps), zswap_enabled })
+}
+
+/// Parse `/proc/swaps`, whose sizes are in KiB
+fn parse_proc_swaps(content: &str) -> Vec<SwapArea> {
+    content
+        .lines()
+        .skip(1)
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            let name = fields.next()?;
+            let kind = fields.next()?;
+            let size_kib: u64 = fields.next()?.parse().ok()?;
+            let kind = if name.starts_with("/dev/zram") {
+                SwapKind::Zram
+            } else if kind == "file" {
+                SwapKind::File
+            } else {
+                SwapKind::Partition
+            };
+            Some(SwapArea { kind, size_bytes: size_kib * 1024 })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_proc_swaps() {
+        let content = "\
+Filename\t\t\t\tType\t\tSize\t\tUsed\t\tPriority
+/dev/nvme0n1p3                          partition\t8388604\t\t0\t\t-2
+/swap/swapfile                          file\t\t4194300\t\t1024\t\t-3
+/dev/zram0                              partition\t8388604\t\t123456\t\t100
+";
+        let areas = parse_proc_swaps(content);
+        assert_eq!(
+            areas,
+            vec![
+                SwapArea { kind: SwapKind::Partition, size_bytes: 8388604 * 1024 },
+                SwapArea { kind: SwapKind::File, size_bytes: 4194300 * 1024 },
+                SwapArea { kind: SwapKind::Zram, size_bytes: 8388604 * 1024 },
+            ]
+        );
+
+        let swap = SwapInfo { areas, zswap_enabled: None };
+        assert!(swap.has_zram());
+        assert_eq!(swap.disk_bytes(), (8388604 + 4194300) * 1024);
+    }
+
+    #[test]
+    fn test_no_swap() {
+        assert!(parse_proc_swaps("Filename\tType\tSize\tUsed\tPriority\n").is_empty());
+    }
+}
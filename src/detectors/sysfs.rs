@@ -0,0 +1,555 @@
+//! Native detection from sysfs and procfs, without external tools
+//!
+//! lspci, lsusb and lshw mostly reformat what the kernel exports under
+//! `/sys`. Reading it directly gives a usable report on minimal systems and
+//! containers where none of them is installed: PCI functions with their
+//! class and bound driver, USB devices, network interfaces, disks, the CPU
+//! topology and total memory. Device names come from the hwdata `pci.ids` database when one is
+//! installed; without it devices are described by their PCI class.
+//!
+//! The detector runs in-process and is always available. `execute`
+//! serializes what it read as JSON, so the snapshot goes through the same
+//! parse step as the output of the external tools.
+
+use super::attributes::{link_target_name, read_trimmed};
+use super::cpu::{self, KernelCpu};
+use super::naming::PersistentNames;
+use super::udev::{self, SysfsUsbDevice};
+use super::{DetectionData, DetectionError, DetectionResult, HardwareDetector};
+use crate::errors::{LxHwError, Result};
+use async_trait::async_trait;
+use log::debug;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap};
+use std::io::BufRead;
+use std::os::unix::process::ExitStatusExt;
+use std::path::{Path, PathBuf};
+use std::process::{ExitStatus, Output};
+use std::time::Duration;
+
+/// Locations of the pciutils ID database, by distribution
+const PCI_IDS_PATHS: &[&str] =
+    &["/usr/share/hwdata/pci.ids", "/usr/share/misc/pci.ids", "/usr/share/pci.ids"];
+
+/// Directory levels searched above an interface's device for its bus device
+const MAX_PARENT_DEPTH: usize = 3;
+
+/// Unit of `/sys/block/*/size`, whatever the disk's logical block size
+const SECTOR_SIZE: u64 = 512;
+
+/// Everything read from sysfs and procfs
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SysfsData {
+    pub pci_devices: Vec<PciFunction>,
+    pub usb_devices: Vec<SysfsUsbDevice>,
+    pub interfaces: Vec<NetInterface>,
+    pub disks: Vec<Disk>,
+    pub cpu: Option<KernelCpu>,
+    /// Totals from /proc/meminfo
+    pub memory: Option<MemorySummary>,
+}
+
+/// A PCI function from `/sys/bus/pci/devices`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PciFunction {
+    /// Full address, e.g. "0000:01:00.0"
+    pub address: String,
+    pub vendor_id: String,
+    pub device_id: String,
+    /// Class, subclass and programming interface as six hex digits
+    pub class_code: String,
+    /// Names from pci.ids, when installed
+    pub vendor_name: Option<String>,
+    pub device_name: Option<String>,
+    pub driver: Option<String>,
+}
+
+/// A network interface backed by a device
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NetInterface {
+    pub name: String,
+    /// Unanonymized MAC address
+    pub mac_address: String,
+    /// PCI address or USB interface of the device, e.g. "0000:03:00.0" or "2-1:1.0"
+    pub bus_address: Option<String>,
+    pub driver: Option<String>,
+    pub wireless: bool,
+}
+
+/// A physical disk from `/sys/block`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Disk {
+    /// Kernel name, e.g. "sda" or "nvme0n1"
+    pub name: String,
+    pub vendor: Option<String>,
+    pub model: Option<String>,
+    pub size_bytes: u64,
+    /// None when the kernel does not say
+    pub rotational: Option<bool>,
+    pub removable: bool,
+    /// Bus the disk is attached to: "nvme", "ata", "usb", "mmc", "virtio" or "scsi"
+    pub transport: Option<String>,
+    /// Unanonymized udev `ID_SERIAL`
+    pub serial: Option<String>,
+}
+
+/// Memory totals from /proc/meminfo
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MemorySummary {
+    pub total_bytes: u64,
+    pub available_bytes: u64,
+}
+
+impl SysfsData {
+    /// Read the running system
+    pub fn read() -> Self {
+        Self::read_from(Path::new("/sys"), Path::new("/proc"), Path::new("/dev"))
+    }
+
+    /// Read sysfs, procfs and devfs trees rooted elsewhere
+    ///
+    /// Device names are looked up in the first pci.ids found on this system.
+    pub fn read_from(sys: &Path, proc: &Path, dev: &Path) -> Self {
+        let names = PersistentNames::read_from(sys, dev);
+        let mut pci_devices = read_pci_devices(&sys.join("bus/pci/devices"));
+        if let Some(ids) = PCI_IDS_PATHS.iter().map(Path::new).find(|path| path.exists()) {
+            name_pci_devices(&mut pci_devices, ids);
+        }
+
+        let usb_devices = udev::enumerate_usb(&sys.join("bus/usb/devices"));
+        let interfaces = names
+            .interfaces
+            .into_iter()
+            .map(|interface| NetInterface {
+                bus_address: bus_device(
+                    &sys.join("class/net").join(&interface.name).join("device"),
+                    &pci_devices,
+                    &usb_devices,
+                )
+                .or(interface.bus_address),
+                name: interface.name,
+                mac_address: interface.mac_address,
+                driver: interface.driver,
+                wireless: interface.wireless,
+            })
+            .collect();
+
+        Self {
+            pci_devices,
+            usb_devices,
+            interfaces,
+            disks: names
+                .disks
+                .into_iter()
+                .map(|disk| read_disk(&sys.join("block").join(&disk.name), disk.name, disk.serial))
+                .collect(),
+            cpu: cpu::read_cpu(
+                &sys.join("devices/system/cpu"),
+                &std::fs::read_to_string(proc.join("cpuinfo")).unwrap_or_default(),
+            ),
+            memory: std::fs::read_to_string(proc.join("meminfo"))
+                .ok()
+                .and_then(|meminfo| parse_meminfo(&meminfo)),
+        }
+    }
+
+    /// PCI function at a bus address
+    pub fn pci_device(&self, address: &str) -> Option<&PciFunction> {
+        self.pci_devices.iter().find(|device| device.address == address)
+    }
+
+    /// USB device owning a USB interface such as "2-1:1.0"
+    pub fn usb_device(&self, interface: &str) -> Option<&SysfsUsbDevice> {
+        let port_path = interface.split(':').next()?;
+        self.usb_devices.iter().find(|device| device.port_path == port_path)
+    }
+}
+
+/// Native sysfs detector
+pub struct SysfsDetector {
+    sys: PathBuf,
+    proc: PathBuf,
+    dev: PathBuf,
+}
+
+impl SysfsDetector {
+    pub fn new() -> Self {
+        Self::with_roots("/sys", "/proc", "/dev")
+    }
+
+    /// Detector reading trees rooted elsewhere, e.g. a captured snapshot
+    pub fn with_roots(
+        sys: impl Into<PathBuf>,
+        proc: impl Into<PathBuf>,
+        dev: impl Into<PathBuf>,
+    ) -> Self {
+        Self { sys: sys.into(), proc: proc.into(), dev: dev.into() }
+    }
+}
+
+impl Default for SysfsDetector {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl HardwareDetector for SysfsDetector {
+    fn name(&self) -> &'static str {
+        "sysfs"
+    }
+
+    async fn is_available(&self) -> bool {
+        true
+    }
+
+    fn is_builtin(&self) -> bool {
+        true
+    }
+
+    async fn execute(&self) -> Result<Output> {
+        debug!("Reading hardware from {}", self.sys.display());
+        let data = SysfsData::read_from(&self.sys, &self.proc, &self.dev);
+        let stdout = serde_json::to_vec(&data)
+            .map_err(|e| LxHwError::SerializationError(format!("sysfs snapshot: {}", e)))?;
+        Ok(Output { status: ExitStatus::from_raw(0), stdout, stderr: Vec::new() })
+    }
+
+    fn parse_output(&self, output: &Output) -> Result<DetectionResult> {
+        match serde_json::from_slice::<SysfsData>(&output.stdout) {
+            Ok(data) => {
+                debug!(
+                    "sysfs: {} PCI functions, {} USB devices, {} interfaces, {} disks",
+                    data.pci_devices.len(),
+                    data.usb_devices.len(),
+                    data.interfaces.len(),
+                    data.disks.len()
+                );
+                Ok(DetectionResult {
+                    tool_name: self.name().to_string(),
+                    success: true,
+                    data: DetectionData::Sysfs(Box::new(data)),
+                    errors: Vec::new(),
+                })
+            }
+            Err(e) => Ok(DetectionResult {
+                tool_name: self.name().to_string(),
+                success: false,
+                data: DetectionData::Sysfs(Box::default()),
+                errors: vec![DetectionError::json("sysfs snapshot parsing failed", &e)],
+            }),
+        }
+    }
+
+    fn timeout(&self) -> Duration {
+        Duration::from_secs(10)
+    }
+}
+
+/// Address of the PCI function or USB interface at or above a device
+///
+/// virtio and some wireless drivers put the interface's device one level
+/// below the PCI function.
+fn bus_device(
+    device: &Path,
+    pci_devices: &[PciFunction],
+    usb_devices: &[SysfsUsbDevice],
+) -> Option<String> {
+    let device = std::fs::canonicalize(device).ok()?;
+    device.ancestors().take(MAX_PARENT_DEPTH).find_map(|dir| {
+        let name = dir.file_name()?.to_str()?;
+        let is_pci = pci_devices.iter().any(|pci| pci.address == name);
+        let is_usb = name
+            .split_once(':')
+            .is_some_and(|(port_path, _)| usb_devices.iter().any(|usb| usb.port_path == port_path));
+        (is_pci || is_usb).then(|| name.to_string())
+    })
+}
+
+/// PCI functions under a `bus/pci/devices` directory, in address order
+fn read_pci_devices(root: &Path) -> Vec<PciFunction> {
+    let Ok(entries) = std::fs::read_dir(root) else {
+        return Vec::new();
+    };
+    let mut addresses: Vec<String> =
+        entries.flatten().map(|entry| entry.file_name().to_string_lossy().into_owned()).collect();
+    addresses.sort();
+
+    addresses
+        .into_iter()
+        .filter_map(|address| {
+            let dir = root.join(&address);
+            let hex = |file: &str| {
+                let value = read_trimmed(&dir.join(file))?.to_ascii_lowercase();
+                Some(value.strip_prefix("0x").unwrap_or(&value).to_string())
+            };
+            Some(PciFunction {
+                vendor_id: hex("vendor")?,
+                device_id: hex("device")?,
+                class_code: hex("class")?,
+                vendor_name: None,
+                device_name: None,
+                driver: link_target_name(&dir.join("driver")),
+                address,
+            })
+        })
+        .collect()
+}
+
+/// Fill in vendor and device names from a pci.ids file
+///
+/// The file has a vendor per unindented line and its devices on lines below
+/// indented with one tab; the class list after the vendors is skipped.
+fn name_pci_devices(devices: &mut [PciFunction], ids: &Path) {
+    let Ok(file) = std::fs::File::open(ids) else {
+        return;
+    };
+    let wanted: BTreeSet<&str> = devices.iter().map(|d| d.vendor_id.as_str()).collect();
+    let mut vendors: HashMap<String, String> = HashMap::new();
+    let mut names: HashMap<(String, String), String> = HashMap::new();
+    let mut vendor: Option<String> = None;
+
+    for line in std::io::BufReader::new(file).lines().map_while(|line| line.ok()) {
+        if line.starts_with('#') || line.trim().is_empty() {
+            continue;
+        }
+        if line.starts_with("C ") {
+            break;
+        }
+        if let Some(device_line) = line.strip_prefix('\t') {
+            let Some(vendor) = &vendor else {
+                continue;
+            };
+            if device_line.starts_with('\t') {
+                continue; // subsystem
+            }
+            if let Some((id, name)) = split_id_line(device_line) {
+                names.insert((vendor.clone(), id), name);
+            }
+            continue;
+        }
+        vendor = split_id_line(&line).filter(|(id, _)| wanted.contains(id.as_str())).map(
+            |(id, name)| {
+                vendors.insert(id.clone(), name);
+                id
+            },
+        );
+    }
+
+    for device in devices {
+        device.vendor_name = vendors.get(&device.vendor_id).cloned();
+        device.device_name =
+            names.get(&(device.vendor_id.clone(), device.device_id.clone())).cloned();
+    }
+}
+
+/// Split an "8086  Intel Corporation" line into lowercase ID and name
+fn split_id_line(line: &str) -> Option<(String, String)> {
+    let (id, name) = line.split_once("  ")?;
+    let valid = id.len() == 4 && id.chars().all(|c| c.is_ascii_hexdigit());
+    valid.then(|| (id.to_ascii_lowercase(), name.trim().to_string()))
+}
+
+fn read_disk(dir: &Path, name: String, serial: Option<String>) -> Disk {
+    let device = dir.join("device");
+    let attribute = |path: PathBuf| read_trimmed(&path).filter(|value| !value.is_empty());
+    Disk {
+        // SATA disks behind libata all report "ATA" as their vendor, virtio
+        // disks the virtio vendor ID
+        vendor: attribute(device.join("vendor"))
+            .filter(|vendor| vendor != "ATA" && !vendor.starts_with("0x")),
+        model: attribute(device.join("model")),
+        size_bytes: attribute(dir.join("size"))
+            .and_then(|sectors| sectors.parse::<u64>().ok())
+            .map_or(0, |sectors| sectors.saturating_mul(SECTOR_SIZE)),
+        rotational: attribute(dir.join("queue/rotational")).map(|value| value == "1"),
+        removable: attribute(dir.join("removable")).is_some_and(|value| value == "1"),
+        transport: disk_transport(&name, dir),
+        serial,
+        name,
+    }
+}
+
+/// Bus a disk is attached to, from its name or its path in the device tree
+fn disk_transport(name: &str, dir: &Path) -> Option<String> {
+    let by_name = [("nvme", "nvme"), ("mmcblk", "mmc"), ("vd", "virtio")]
+        .iter()
+        .find(|(prefix, _)| name.starts_with(prefix))
+        .map(|(_, transport)| transport.to_string());
+    by_name.or_else(|| {
+        let path = std::fs::canonicalize(dir).ok()?;
+        let path = path.to_string_lossy();
+        if path.contains("/usb") {
+            Some("usb".to_string())
+        } else if path.contains("/ata") {
+            Some("ata".to_string())
+        } else if path.contains("/host") {
+            Some("scsi".to_string())
+        } else {
+            None
+        }
+    })
+}
+
+/// MemTotal and MemAvailable from /proc/meminfo, which reports kB
+fn parse_meminfo(meminfo: &str) -> Option<MemorySummary> {
+    let field = |name: &str| {
+        meminfo.lines().find_map(|line| {
+            let value = line.strip_prefix(name)?.strip_prefix(':')?;
+            value.trim().trim_end_matches("kB").trim().parse::<u64>().ok()
+        })
+    };
+    let total_kb = field("MemTotal")?;
+    // Kernels before 3.14 lack MemAvailable
+    let available_kb = field("MemAvailable").or_else(|| field("MemFree")).unwrap_or(0);
+    Some(MemorySummary { total_bytes: total_kb * 1024, available_bytes: available_kb * 1024 })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::os::unix::fs::symlink;
+    use tempfile::TempDir;
+
+    fn write(dir: &Path, file: &str, content: &str) {
+        std::fs::create_dir_all(dir).unwrap();
+        std::fs::write(dir.join(file), content).unwrap();
+    }
+
+    /// A GPU, an Ethernet controller with its interface and a SATA disk
+    fn fixture() -> TempDir {
+        let root = TempDir::new().unwrap();
+        let sys = root.path().join("sys");
+        let drivers = sys.join("bus/pci/drivers");
+        std::fs::create_dir_all(drivers.join("amdgpu")).unwrap();
+        std::fs::create_dir_all(drivers.join("igb")).unwrap();
+
+        let devices = sys.join("devices/pci0000:00");
+        let bus = sys.join("bus/pci/devices");
+        std::fs::create_dir_all(&bus).unwrap();
+        for (address, vendor, device, class, driver) in [
+            ("0000:03:00.0", "0x1002", "0x73bf", "0x030000", "amdgpu"),
+            ("0000:05:00.0", "0x8086", "0x1533", "0x020000", "igb"),
+        ] {
+            let dir = devices.join(address);
+            write(&dir, "vendor", &format!("{}\n", vendor));
+            write(&dir, "device", &format!("{}\n", device));
+            write(&dir, "class", &format!("{}\n", class));
+            symlink(drivers.join(driver), dir.join("driver")).unwrap();
+            symlink(&dir, bus.join(address)).unwrap();
+        }
+
+        let net = sys.join("class/net/enp5s0");
+        write(&net, "address", "3c:7c:3f:12:34:56\n");
+        symlink(devices.join("0000:05:00.0"), net.join("device")).unwrap();
+        // virtio NICs hang off a virtio device below the PCI function
+        let virtio = devices.join("0000:05:00.0/virtio0");
+        std::fs::create_dir_all(&virtio).unwrap();
+        let net = sys.join("class/net/eth1");
+        write(&net, "address", "52:54:00:12:34:56\n");
+        symlink(&virtio, net.join("device")).unwrap();
+
+        let disk = devices.join("0000:00:17.0/ata1/host0/target0:0:0/0:0:0:0/block/sda");
+        write(&disk, "size", "1953525168\n");
+        write(&disk.join("queue"), "rotational", "0\n");
+        write(&disk, "removable", "0\n");
+        write(&disk.join("device"), "vendor", "ATA     \n");
+        write(&disk.join("device"), "model", "Samsung SSD 870\n");
+        std::fs::create_dir_all(sys.join("block")).unwrap();
+        symlink(&disk, sys.join("block/sda")).unwrap();
+
+        write(
+            &root.path().join("proc"),
+            "meminfo",
+            "MemTotal:       16318540 kB\nMemFree:         1000000 kB\nMemAvailable:    8000000 kB\n",
+        );
+        root
+    }
+
+    #[test]
+    fn test_read_sysfs_tree() {
+        let root = fixture();
+        let data = SysfsData::read_from(
+            &root.path().join("sys"),
+            &root.path().join("proc"),
+            &root.path().join("dev"),
+        );
+
+        assert_eq!(data.pci_devices.len(), 2);
+        let gpu = data.pci_device("0000:03:00.0").unwrap();
+        assert_eq!((gpu.vendor_id.as_str(), gpu.device_id.as_str()), ("1002", "73bf"));
+        assert_eq!(gpu.class_code, "030000");
+        assert_eq!(gpu.driver.as_deref(), Some("amdgpu"));
+
+        assert_eq!(data.interfaces.len(), 2);
+        for interface in &data.interfaces {
+            assert_eq!(interface.bus_address.as_deref(), Some("0000:05:00.0"));
+        }
+        assert_eq!(data.interfaces[0].driver.as_deref(), Some("igb"));
+
+        let disk = &data.disks[0];
+        assert_eq!(disk.size_bytes, 1953525168 * SECTOR_SIZE);
+        assert_eq!(disk.vendor, None);
+        assert_eq!(disk.model.as_deref(), Some("Samsung SSD 870"));
+        assert_eq!(disk.rotational, Some(false));
+        assert_eq!(disk.transport.as_deref(), Some("ata"));
+
+        let memory = data.memory.unwrap();
+        assert_eq!(memory.total_bytes, 16318540 * 1024);
+        assert_eq!(memory.available_bytes, 8000000 * 1024);
+    }
+
+    #[test]
+    fn test_pci_names_from_ids_file() {
+        let root = TempDir::new().unwrap();
+        let ids = root.path().join("pci.ids");
+        std::fs::write(
+            &ids,
+            "# comment\n1002  Advanced Micro Devices, Inc. [AMD/ATI]\n\
+             \t73bf  Navi 21 [Radeon RX 6800/6800 XT / 6900 XT]\n\
+             \t\t1002 0e3a  Radeon RX 6900 XT\n\
+             8086  Intel Corporation\n\t1533  I210 Gigabit Network Connection\n\
+             C 03  Display controller\n\t00  VGA compatible controller\n",
+        )
+        .unwrap();
+        let function = |vendor: &str, device: &str| PciFunction {
+            address: String::new(),
+            vendor_id: vendor.to_string(),
+            device_id: device.to_string(),
+            class_code: "030000".to_string(),
+            vendor_name: None,
+            device_name: None,
+            driver: None,
+        };
+        let mut devices = vec![function("1002", "73bf"), function("10de", "2484")];
+        name_pci_devices(&mut devices, &ids);
+
+        assert_eq!(
+            devices[0].vendor_name.as_deref(),
+            Some("Advanced Micro Devices, Inc. [AMD/ATI]")
+        );
+        assert_eq!(
+            devices[0].device_name.as_deref(),
+            Some("Navi 21 [Radeon RX 6800/6800 XT / 6900 XT]")
+        );
+        assert_eq!(devices[1].vendor_name, None);
+    }
+
+    #[tokio::test]
+    async fn test_snapshot_round_trips_through_output() {
+        let root = fixture();
+        let detector = SysfsDetector::with_roots(
+            root.path().join("sys"),
+            root.path().join("proc"),
+            root.path().join("dev"),
+        );
+        assert!(detector.is_available().await);
+        let output = detector.execute().await.unwrap();
+        let result = detector.parse_output(&output).unwrap();
+        assert!(result.success);
+        let DetectionData::Sysfs(data) = result.data else {
+            panic!("Expected sysfs data");
+        };
+        assert_eq!(data.pci_devices.len(), 2);
+    }
+}
@@ -0,0 +1,335 @@
+//! Device enumeration and hotplug monitoring through udev's kernel sources
+//!
+//! Enumeration walks `/sys/bus/usb/devices`, which udev itself populates its
+//! database from; monitoring subscribes to the kernel's kobject uevent
+//! netlink group, the same source udevd reads. Reading the kernel group
+//! rather than udevd's avoids a libudev dependency: the events carry the
+//! action, subsystem and device path, and the device's attributes are read
+//! from sysfs afterwards.
+//!
+//! [`HardwareAnalyzer::apply_hotplug`](super::integration::HardwareAnalyzer::apply_hotplug)
+//! applies USB events to an existing report from a fresh enumeration, so a
+//! plugged device shows up without running the external tools again.
+
+use crate::errors::Result;
+use crate::hardware::UsbDevice;
+use nix::errno::Errno;
+use nix::sys::socket::{
+    bind, recv, socket, AddressFamily, MsgFlags, NetlinkAddr, SockFlag, SockProtocol, SockType,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::os::fd::{AsRawFd, OwnedFd};
+use std::path::Path;
+use tokio::io::unix::AsyncFd;
+
+/// sysfs directory with one entry per USB device and interface
+pub const SYSFS_USB_DEVICES: &str = "/sys/bus/usb/devices";
+
+/// Netlink multicast group of kernel-originated uevents
+const KERNEL_GROUP: u32 = 1;
+
+/// Largest uevent the kernel sends, with room to spare
+const MESSAGE_BUFFER: usize = 16 * 1024;
+
+/// Subsystems whose devices appear in reports
+const WATCHED_SUBSYSTEMS: &[&str] =
+    &["pci", "usb", "net", "block", "sound", "drm", "nvme", "input", "bluetooth"];
+
+/// Actions that change the hardware or driver set
+const WATCHED_ACTIONS: &[&str] = &["add", "remove", "bind", "unbind"];
+
+/// A parsed uevent
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Uevent {
+    pub action: String,
+    pub subsystem: String,
+    pub devpath: String,
+    /// Device type within the subsystem, e.g. "usb_device" or "usb_interface"
+    pub devtype: Option<String>,
+}
+
+impl Uevent {
+    /// Parse a kernel uevent: an "action@devpath" header followed by
+    /// NUL-separated KEY=value pairs
+    pub fn parse(message: &[u8]) -> Option<Self> {
+        let mut fields = message.split(|&b| b == 0).map(String::from_utf8_lossy);
+        if !fields.next()?.contains('@') {
+            // udevd messages carry a binary header instead
+            return None;
+        }
+        let mut properties: HashMap<String, String> = fields
+            .filter_map(|field| {
+                let (key, value) = field.split_once('=')?;
+                Some((key.to_string(), value.to_string()))
+            })
+            .collect();
+        Some(Self {
+            action: properties.remove("ACTION")?,
+            subsystem: properties.remove("SUBSYSTEM")?,
+            devpath: properties.remove("DEVPATH").unwrap_or_default(),
+            devtype: properties.remove("DEVTYPE"),
+        })
+    }
+
+    /// Whether this event can change what a report contains
+    pub fn is_hardware_change(&self) -> bool {
+        WATCHED_ACTIONS.contains(&self.action.as_str())
+            && WATCHED_SUBSYSTEMS.contains(&self.subsystem.as_str())
+    }
+
+    /// Whether a whole USB device, not one of its interfaces, came or went
+    pub fn is_usb_device_change(&self) -> bool {
+        self.subsystem == "usb"
+            && self.devtype.as_deref() == Some("usb_device")
+            && matches!(self.action.as_str(), "add" | "remove")
+    }
+}
+
+/// What the monitor received
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MonitorEvent {
+    Device(Uevent),
+    /// The socket buffer overflowed during a burst and events were lost;
+    /// only a full enumeration tells what changed
+    Overflow,
+}
+
+/// Subscription to kernel uevents
+///
+/// Must be opened inside a Tokio runtime.
+pub struct UdevMonitor {
+    fd: AsyncFd<OwnedFd>,
+    buf: Vec<u8>,
+}
+
+impl UdevMonitor {
+    /// Subscribe to the kernel uevent group
+    pub fn open() -> Result<Self> {
+        let fd = socket(
+            AddressFamily::Netlink,
+            SockType::Datagram,
+            SockFlag::SOCK_CLOEXEC | SockFlag::SOCK_NONBLOCK,
+            SockProtocol::NetlinkKObjectUEvent,
+        )
+        .map_err(std::io::Error::from)?;
+        bind(fd.as_raw_fd(), &NetlinkAddr::new(0, KERNEL_GROUP)).map_err(std::io::Error::from)?;
+        Ok(Self { fd: AsyncFd::new(fd)?, buf: vec![0u8; MESSAGE_BUFFER] })
+    }
+
+    /// Wait for the next uevent
+    ///
+    /// Messages that are not kernel uevents are skipped.
+    pub async fn next(&mut self) -> Result<MonitorEvent> {
+        loop {
+            let mut guard = self.fd.readable().await?;
+            let received = match guard.try_io(|fd| {
+                recv(fd.as_raw_fd(), &mut self.buf, MsgFlags::empty()).map_err(std::io::Error::from)
+            }) {
+                Ok(received) => received,
+                Err(_would_block) => continue,
+            };
+
+            match received {
+                Ok(len) => {
+                    if let Some(event) = Uevent::parse(&self.buf[..len]) {
+                        return Ok(MonitorEvent::Device(event));
+                    }
+                }
+                Err(e) if e.raw_os_error() == Some(Errno::ENOBUFS as i32) => {
+                    return Ok(MonitorEvent::Overflow)
+                }
+                Err(e) => return Err(e.into()),
+            }
+        }
+    }
+}
+
+/// A USB device as sysfs describes it
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SysfsUsbDevice {
+    /// sysfs entry name: bus and port chain (e.g., "3-1.2"), or "usbN" for
+    /// root hubs
+    pub port_path: String,
+    pub vendor_id: String,
+    pub product_id: String,
+    /// Manufacturer string from the device descriptor
+    pub manufacturer: Option<String>,
+    /// Product string from the device descriptor
+    pub product: Option<String>,
+    pub usb_version: Option<String>,
+    /// Class name, from the first interface for class 00 devices
+    pub device_class: Option<String>,
+    pub device_subclass: Option<String>,
+    pub speed_mbps: Option<f64>,
+    pub max_power_ma: Option<u16>,
+    /// Unanonymized serial number
+    pub serial: Option<String>,
+}
+
+impl SysfsUsbDevice {
+    /// Report entry for the device, with its serial already anonymized
+    pub fn into_usb_device(self, anonymized_serial: Option<String>) -> UsbDevice {
+        UsbDevice {
+            vendor_id: self.vendor_id,
+            product_id: self.product_id,
+            vendor_name: self.manufacturer,
+            product_name: self.product,
+            usb_version: self.usb_version,
+            device_class: self.device_class,
+            device_subclass: self.device_subclass,
+            speed_mbps: self.speed_mbps,
+            port_path: Some(self.port_path),
+            max_power_ma: self.max_power_ma,
+            anonymized_serial,
+        }
+    }
+}
+
+/// Enumerate the USB devices under a sysfs `bus/usb/devices` directory
+///
+/// Interface entries (`1-2:1.0`) are skipped. Returns an empty list when
+/// the directory does not exist.
+pub fn enumerate_usb(root: &Path) -> Vec<SysfsUsbDevice> {
+    let Ok(entries) = std::fs::read_dir(root) else {
+        return Vec::new();
+    };
+    let mut names: Vec<String> = entries
+        .flatten()
+        .map(|entry| entry.file_name().to_string_lossy().into_owned())
+        .filter(|name| !name.contains(':'))
+        .collect();
+    names.sort();
+
+    names.into_iter().filter_map(|name| read_usb_device(&root.join(&name), name)).collect()
+}
+
+fn read_usb_device(dir: &Path, port_path: String) -> Option<SysfsUsbDevice> {
+    let read = |file: &str| {
+        std::fs::read_to_string(dir.join(file))
+            .ok()
+            .map(|value| value.trim().to_string())
+            .filter(|value| !value.is_empty())
+    };
+
+    // Class 00 defers to the interfaces; use the first interface's class
+    let class = read("bDeviceClass").filter(|class| class != "00").or_else(|| {
+        std::fs::read_to_string(dir.join(format!("{}:1.0", port_path)).join("bInterfaceClass"))
+            .ok()
+            .map(|class| class.trim().to_string())
+    });
+
+    Some(SysfsUsbDevice {
+        vendor_id: read("idVendor")?.to_ascii_lowercase(),
+        product_id: read("idProduct")?.to_ascii_lowercase(),
+        manufacturer: read("manufacturer"),
+        product: read("product"),
+        usb_version: read("version"),
+        device_class: class
+            .and_then(|class| u8::from_str_radix(&class, 16).ok())
+            .map(|class| usb_class_name(class).to_string()),
+        device_subclass: read("bDeviceSubClass"),
+        speed_mbps: read("speed").and_then(|speed| speed.parse().ok()),
+        max_power_ma: read("bMaxPower").and_then(|power| power.trim_end_matches("mA").parse().ok()),
+        serial: read("serial"),
+        port_path,
+    })
+}
+
+/// Name of a USB base class code
+pub fn usb_class_name(class: u8) -> &'static str {
+    match class {
+        0x01 => "Audio",
+        0x02 => "Communications",
+        0x03 => "Human Interface Device",
+        0x05 => "Physical",
+        0x06 => "Image",
+        0x07 => "Printer",
+        0x08 => "Mass Storage",
+        0x09 => "Hub",
+        0x0a => "CDC Data",
+        0x0b => "Smart Card",
+        0x0d => "Content Security",
+        0x0e => "Video",
+        0x0f => "Personal Healthcare",
+        0x10 => "Audio/Video",
+        0x11 => "Billboard",
+        0x12 => "USB Type-C Bridge",
+        0xdc => "Diagnostic",
+        0xe0 => "Wireless",
+        0xef => "Miscellaneous",
+        0xfe => "Application Specific",
+        0xff => "Vendor Specific",
+        _ => "Unknown",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_parse_uevent() {
+        let message = b"add@/devices/pci0000:00/0000:00:14.0/usb1/1-2\0ACTION=add\0\
+            DEVPATH=/devices/pci0000:00/0000:00:14.0/usb1/1-2\0SUBSYSTEM=usb\0\
+            DEVTYPE=usb_device\0PRODUCT=46d/c52b/1211\0SEQNUM=4242\0";
+        let event = Uevent::parse(message).unwrap();
+        assert_eq!(event.action, "add");
+        assert_eq!(event.subsystem, "usb");
+        assert!(event.is_hardware_change());
+        assert!(event.is_usb_device_change());
+
+        let interface = b"bind@/devices/pci0000:00/0000:00:14.0/usb1/1-2/1-2:1.0\0\
+            ACTION=bind\0SUBSYSTEM=usb\0DEVTYPE=usb_interface\0DRIVER=usbhid\0";
+        let interface = Uevent::parse(interface).unwrap();
+        assert!(interface.is_hardware_change() && !interface.is_usb_device_change());
+
+        let battery = b"change@/devices/LNXSYSTM:00/PNP0C0A:00/power_supply/BAT0\0\
+            ACTION=change\0SUBSYSTEM=power_supply\0";
+        assert!(!Uevent::parse(battery).unwrap().is_hardware_change());
+        assert_eq!(Uevent::parse(b"libudev\0\xfe\xed\xca\xfe"), None);
+    }
+
+    #[test]
+    fn test_enumerate_usb_devices() {
+        let root = TempDir::new().unwrap();
+        let write = |entry: &str, attributes: &[(&str, &str)]| {
+            let dir = root.path().join(entry);
+            std::fs::create_dir_all(&dir).unwrap();
+            for (file, value) in attributes {
+                std::fs::write(dir.join(file), format!("{}\n", value)).unwrap();
+            }
+        };
+        write("usb1", &[("idVendor", "1d6b"), ("idProduct", "0002"), ("bDeviceClass", "09")]);
+        write(
+            "1-2",
+            &[
+                ("idVendor", "046D"),
+                ("idProduct", "c52b"),
+                ("bDeviceClass", "00"),
+                ("product", "USB Receiver"),
+                ("speed", "12"),
+                ("bMaxPower", "98mA"),
+                ("serial", "ABC123"),
+            ],
+        );
+        write("1-2/1-2:1.0", &[("bInterfaceClass", "03")]);
+        // Entries without IDs are not devices
+        write("1-3", &[("speed", "480")]);
+
+        let devices = enumerate_usb(root.path());
+        assert_eq!(devices.len(), 2);
+        let receiver = &devices[0];
+        assert_eq!(receiver.port_path, "1-2");
+        assert_eq!((receiver.vendor_id.as_str(), receiver.product_id.as_str()), ("046d", "c52b"));
+        assert_eq!(receiver.device_class.as_deref(), Some("Human Interface Device"));
+        assert_eq!(receiver.speed_mbps, Some(12.0));
+        assert_eq!(receiver.max_power_ma, Some(98));
+        assert_eq!(receiver.serial.as_deref(), Some("ABC123"));
+        assert_eq!(devices[1].port_path, "usb1");
+        assert_eq!(devices[1].device_class.as_deref(), Some("Hub"));
+
+        assert!(enumerate_usb(&root.path().join("missing")).is_empty());
+    }
+}
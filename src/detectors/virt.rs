@@ -0,0 +1,204 @@
+//! Virtual machine and container detection
+//!
+//! A report from a virtual machine describes emulated hardware, and one from
+//! a container the host's hardware seen through a sandbox; neither says much
+//! about how Linux runs on a physical machine. `systemd-detect-virt` answers
+//! both questions where systemd is installed. Otherwise containers are told
+//! from the marker files their runtimes create and WSL from its kernel
+//! release, and virtual machines from `/sys/hypervisor`, the DMI vendor
+//! strings and the CPU's hypervisor flag.
+
+use super::attributes::read_nonempty;
+use crate::hardware::{Virtualization, VirtualizationKind};
+use std::path::Path;
+
+/// DMI vendor and product substrings naming a hypervisor, in
+/// `systemd-detect-virt` terms
+const DMI_VENDORS: &[(&str, &str)] = &[
+    ("QEMU", "qemu"),
+    ("KVM", "kvm"),
+    ("Google Compute Engine", "kvm"),
+    ("VMware", "vmware"),
+    ("VirtualBox", "oracle"),
+    ("innotek GmbH", "oracle"),
+    ("Xen", "xen"),
+    ("Amazon EC2", "amazon"),
+    ("Parallels", "parallels"),
+    ("BHYVE", "bhyve"),
+    ("Bochs", "bochs"),
+];
+
+/// DMI files searched for [`DMI_VENDORS`]
+const DMI_FILES: &[&str] = &["sys_vendor", "product_name", "board_vendor", "bios_vendor"];
+
+/// Answers of `systemd-detect-virt --container` and `--vm`
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SystemdVirt {
+    pub container: Option<String>,
+    pub vm: Option<String>,
+}
+
+/// Ask `systemd-detect-virt`; None when it is not installed
+pub fn query_systemd_detect_virt() -> Option<SystemdVirt> {
+    let run = |flag: &str| {
+        let output = std::process::Command::new("systemd-detect-virt").arg(flag).output();
+        match output {
+            // It exits with 1 and prints "none" when nothing is detected
+            Ok(output) if output.status.code().is_some() => {
+                let answer = String::from_utf8_lossy(&output.stdout).trim().to_string();
+                Some((!answer.is_empty() && answer != "none").then_some(answer))
+            }
+            _ => None,
+        }
+    };
+    Some(SystemdVirt { container: run("--container")?, vm: run("--vm")? })
+}
+
+/// Detect the environment the system under `root` runs in
+///
+/// `root` is "/" except in tests; `systemd` is the answer of
+/// [`query_systemd_detect_virt`], if any.
+pub fn detect_virtualization(root: &Path, systemd: Option<SystemdVirt>) -> Virtualization {
+    if let Some(systemd) = systemd {
+        let (kind, technology) = match (systemd.container, systemd.vm) {
+            (Some(container), _) => (VirtualizationKind::Container, Some(container)),
+            (None, Some(vm)) => (VirtualizationKind::VirtualMachine, Some(vm)),
+            (None, None) => (VirtualizationKind::BareMetal, None),
+        };
+        return Virtualization { kind, technology, source: "systemd-detect-virt".to_string() };
+    }
+
+    let found = |kind, technology: &str, source: &str| Virtualization {
+        kind,
+        technology: Some(technology.to_string()),
+        source: source.to_string(),
+    };
+    if let Some(container) = detect_container(root) {
+        return found(VirtualizationKind::Container, &container, "container");
+    }
+    if let Some(hypervisor) = read_nonempty(&root.join("sys/hypervisor/type")) {
+        return found(VirtualizationKind::VirtualMachine, &hypervisor, "hypervisor");
+    }
+    if let Some(vendor) = detect_dmi_vendor(&root.join("sys/class/dmi/id")) {
+        return found(VirtualizationKind::VirtualMachine, vendor, "dmi");
+    }
+    if has_hypervisor_flag(&root.join("proc/cpuinfo")) {
+        return Virtualization {
+            kind: VirtualizationKind::VirtualMachine,
+            technology: None,
+            source: "cpuinfo".to_string(),
+        };
+    }
+    Virtualization {
+        kind: VirtualizationKind::BareMetal,
+        technology: None,
+        source: "none".to_string(),
+    }
+}
+
+/// Container runtime from its marker files, or WSL from the kernel release
+fn detect_container(root: &Path) -> Option<String> {
+    if let Some(container) = read_nonempty(&root.join("run/systemd/container")) {
+        return Some(container);
+    }
+    if root.join("run/.containerenv").exists() {
+        return Some("podman".to_string());
+    }
+    if root.join(".dockerenv").exists() {
+        return Some("docker".to_string());
+    }
+    let release = read_nonempty(&root.join("proc/sys/kernel/osrelease"))?.to_ascii_lowercase();
+    (release.contains("microsoft") || release.contains("wsl")).then(|| "wsl".to_string())
+}
+
+/// Hypervisor named by the DMI vendor or product strings
+fn detect_dmi_vendor(dmi: &Path) -> Option<&'static str> {
+    let strings: Vec<String> =
+        DMI_FILES.iter().filter_map(|file| read_nonempty(&dmi.join(file))).collect();
+    // Hyper-V guests are "Microsoft Corporation" "Virtual Machine"; Surface
+    // laptops share the vendor
+    if strings.iter().any(|s| s == "Microsoft Corporation")
+        && strings.iter().any(|s| s == "Virtual Machine")
+    {
+        return Some("microsoft");
+    }
+    DMI_VENDORS
+        .iter()
+        .find(|(needle, _)| strings.iter().any(|s| s.contains(needle)))
+        .map(|(_, technology)| *technology)
+}
+
+fn has_hypervisor_flag(cpuinfo: &Path) -> bool {
+    std::fs::read_to_string(cpuinfo).is_ok_and(|content| {
+        content
+            .lines()
+            .filter(|line| line.starts_with("flags"))
+            .any(|line| line.split_whitespace().any(|flag| flag == "hypervisor"))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn write(root: &Path, file: &str, content: &str) {
+        let path = root.join(file);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, content).unwrap();
+    }
+
+    #[test]
+    fn test_systemd_detect_virt_answer() {
+        let root = TempDir::new().unwrap();
+        let detect = |container: Option<&str>, vm: Option<&str>| {
+            let systemd = SystemdVirt {
+                container: container.map(str::to_string),
+                vm: vm.map(str::to_string),
+            };
+            detect_virtualization(root.path(), Some(systemd))
+        };
+
+        let wsl = detect(Some("wsl"), Some("microsoft"));
+        assert_eq!(wsl.kind, VirtualizationKind::Container);
+        assert_eq!(wsl.technology.as_deref(), Some("wsl"));
+        assert_eq!(detect(None, Some("kvm")).kind, VirtualizationKind::VirtualMachine);
+        let bare = detect(None, None);
+        assert_eq!(bare.kind, VirtualizationKind::BareMetal);
+        assert_eq!(bare.source, "systemd-detect-virt");
+    }
+
+    #[test]
+    fn test_fallback_detection() {
+        let root = TempDir::new().unwrap();
+        write(root.path(), "proc/cpuinfo", "processor\t: 0\nflags\t\t: fpu vme sse2\n");
+        write(root.path(), "sys/class/dmi/id/sys_vendor", "LENOVO\n");
+        write(root.path(), "sys/class/dmi/id/product_name", "21CB0011GE\n");
+        write(root.path(), "proc/sys/kernel/osrelease", "6.8.0-45-generic\n");
+        assert_eq!(detect_virtualization(root.path(), None).kind, VirtualizationKind::BareMetal);
+
+        write(root.path(), "proc/cpuinfo", "processor\t: 0\nflags\t\t: fpu hypervisor\n");
+        let unknown = detect_virtualization(root.path(), None);
+        assert_eq!(unknown.kind, VirtualizationKind::VirtualMachine);
+        assert_eq!(unknown.technology, None);
+
+        write(root.path(), "sys/class/dmi/id/sys_vendor", "QEMU\n");
+        let qemu = detect_virtualization(root.path(), None);
+        assert_eq!((qemu.technology.as_deref(), qemu.source.as_str()), (Some("qemu"), "dmi"));
+
+        write(root.path(), "sys/class/dmi/id/sys_vendor", "Microsoft Corporation\n");
+        write(root.path(), "sys/class/dmi/id/product_name", "Virtual Machine\n");
+        assert_eq!(
+            detect_virtualization(root.path(), None).technology.as_deref(),
+            Some("microsoft")
+        );
+
+        write(root.path(), "proc/sys/kernel/osrelease", "5.15.153.1-microsoft-standard-WSL2\n");
+        let wsl = detect_virtualization(root.path(), None);
+        assert_eq!(wsl.kind, VirtualizationKind::Container);
+        assert_eq!(wsl.technology.as_deref(), Some("wsl"));
+
+        write(root.path(), ".dockerenv", "");
+        assert_eq!(detect_virtualization(root.path(), None).technology.as_deref(), Some("docker"));
+    }
+}
@@ -0,0 +1,202 @@
+//! Local history of hardware report snapshots
+//!
+//! Each detection run can record its (anonymized) report as a snapshot so
+//! the next run reports only what changed since then. Snapshots are JSON
+//! files named by generation time, and only the most recent ones are kept.
+
+use crate::diff::{diff_reports, ChangeEvent, ReportDiff};
+use crate::errors::{LxHwError, Result};
+use crate::hardware::HardwareReport;
+use crate::preflight::{self, OutputEstimate};
+use std::path::{Path, PathBuf};
+
+/// Number of snapshots kept by default
+pub const DEFAULT_RETENTION: usize = 30;
+
+/// Directory of report snapshots, oldest first by file name
+#[derive(Debug, Clone)]
+pub struct SnapshotHistory {
+    dir: PathBuf,
+    retention: usize,
+}
+
+impl SnapshotHistory {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into(), retention: DEFAULT_RETENTION }
+    }
+
+    /// Keep at most `retention` snapshots (at least one)
+    pub fn with_retention(mut self, retention: usize) -> Self {
+        self.retention = retention.max(1);
+        self
+    }
+
+    /// Default history directory under `$XDG_STATE_HOME/lx-hw-db/history`
+    pub fn default_dir() -> PathBuf {
+        let state_dir = std::env::var_os("XDG_STATE_HOME")
+            .map(PathBuf::from)
+            .or_else(|| std::env::var_os("HOME").map(|h| PathBuf::from(h).join(".local/state")))
+            .unwrap_or_else(std::env::temp_dir);
+        state_dir.join("lx-hw-db").join("history")
+    }
+
+    /// Snapshot files, oldest first
+    pub fn snapshots(&self) -> Result<Vec<PathBuf>> {
+        if !self.dir.exists() {
+            return Ok(Vec::new());
+        }
+        let mut snapshots: Vec<PathBuf> = std::fs::read_dir(&self.dir)?
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+            .collect();
+        snapshots.sort();
+        Ok(snapshots)
+    }
+
+    /// Most recent snapshot, if any
+    pub fn latest(&self) -> Result<Option<HardwareReport>> {
+        match self.snapshots()?.last() {
+            Some(path) => HardwareReport::load(path).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    /// Store a report as the newest snapshot and prune old ones
+    ///
+    /// Checks that the directory is writable and has room for the snapshot
+    /// first, so a failing run does not leave a truncated snapshot behind
+    /// that later runs would compare against.
+    pub fn record(&self, report: &HardwareReport) -> Result<PathBuf> {
+        let name = report.metadata.generated_at.format("%Y%m%dT%H%M%S%.6fZ");
+        let path = self.dir.join(format!("{}.json", name));
+        let json = serde_json::to_string_pretty(report)
+            .map_err(|e| LxHwError::SerializationError(e.to_string()))?;
+        preflight::check_output_dir(
+            &self.dir,
+            OutputEstimate { bytes: json.len() as u64, files: 1 },
+        )?;
+        std::fs::write(&path, json)?;
+
+        let snapshots = self.snapshots()?;
+        let excess = snapshots.len().saturating_sub(self.retention);
+        for old in &snapshots[..excess] {
+            std::fs::remove_file(old)?;
+        }
+        Ok(path)
+    }
+
+    /// Record a report and return what is new compared to the previous snapshot
+    ///
+    /// Returns `None` when there was no previous snapshot to compare against.
+    pub fn record_changes(&self, report: &HardwareReport) -> Result<Option<ReportDiff>> {
+        let previous = self.latest()?;
+        self.record(report)?;
+        Ok(previous.map(|previous| diff_reports(&previous, report).only_new()))
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+}
+
+impl ReportDiff {
+    /// Keep only additions and changes, dropping devices that disappeared
+    ///
+    /// Unplugged peripherals are routine; periodic runs care about what
+    /// appeared or changed.
+    pub fn only_new(mut self) -> Self {
+        self.events.retain(|event| !matches!(event, ChangeEvent::DeviceRemoved { .. }));
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hardware::UsbDevice;
+
+    fn report(generated_at: &str) -> HardwareReport {
+        serde_json::from_value(serde_json::json!({
+            "metadata": {
+                "version": "1.0.0",
+                "generated_at": generated_at,
+                "privacy_level": "Basic",
+                "tools_used": [],
+                "anonymized_system_id": "test",
+            },
+            "system": {
+                "anonymized_hostname": "test",
+                "kernel_version": "6.8.0",
+                "distribution": null,
+                "architecture": "x86_64",
+                "boot_time": null,
+            },
+            "cpu": null,
+            "memory": null,
+            "storage": [],
+            "graphics": [],
+            "network": [],
+            "usb": [{
+                "vendor_id": "046d",
+                "product_id": "c52b",
+                "vendor_name": "Logitech",
+                "product_name": "Unifying Receiver",
+                "usb_version": "2.00",
+            }],
+            "audio": [],
+            "kernel_support": null,
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_record_changes_reports_only_new() {
+        let dir = tempfile::tempdir().unwrap();
+        let history = SnapshotHistory::new(dir.path());
+
+        let first = report("2024-01-01T00:00:00Z");
+        assert!(history.record_changes(&first).unwrap().is_none());
+
+        let mut second = report("2024-01-02T00:00:00Z");
+        second.usb[0].product_id = "c534".to_string();
+        second.usb.push(UsbDevice {
+            vendor_id: "0781".to_string(),
+            product_id: "5581".to_string(),
+            vendor_name: Some("SanDisk".to_string()),
+            product_name: Some("Ultra".to_string()),
+            usb_version: None,
+            device_class: None,
+            device_subclass: None,
+            speed_mbps: None,
+            port_path: None,
+            max_power_ma: None,
+            anonymized_serial: None,
+        });
+        let diff = history.record_changes(&second).unwrap().unwrap();
+
+        // The replaced receiver shows up as added; its removal is dropped
+        assert_eq!(diff.events.len(), 2);
+        assert!(diff.events.iter().all(|e| matches!(e, ChangeEvent::DeviceAdded { .. })));
+
+        let mut third = second.clone();
+        third.metadata.generated_at = "2024-01-03T00:00:00Z".parse().unwrap();
+        let diff = history.record_changes(&third).unwrap().unwrap();
+        assert!(diff.is_empty());
+    }
+
+    #[test]
+    fn test_retention() {
+        let dir = tempfile::tempdir().unwrap();
+        let history = SnapshotHistory::new(dir.path()).with_retention(2);
+        for day in 1..=4 {
+            history.record(&report(&format!("2024-01-0{}T00:00:00Z", day))).unwrap();
+        }
+
+        let snapshots = history.snapshots().unwrap();
+        assert_eq!(snapshots.len(), 2);
+        assert_eq!(
+            history.latest().unwrap().unwrap().metadata.generated_at.to_rfc3339(),
+            "2024-01-04T00:00:00+00:00"
+        );
+    }
+}
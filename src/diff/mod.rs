@@ -0,0 +1,633 @@
+//! Semantic comparison of hardware reports
+//!
+//! Reports are compared component by component using stable keys (PCI IDs,
+//! USB IDs, vendor/model pairs) rather than as raw JSON, so reordering of
+//! devices and re-anonymization with a different salt do not show up as
+//! changes. Differences are reported as typed [`ChangeEvent`]s that render
+//! consistently to Markdown, JSON, and YAML.
+
+use crate::errors::{LxHwError, Result};
+use crate::hardware::HardwareReport;
+use crate::output::{html, OutputFormat};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+
+pub mod history;
+
+/// Category of hardware a change applies to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ComponentCategory {
+    Cpu,
+    Memory,
+    Storage,
+    Graphics,
+    Network,
+    Usb,
+    Audio,
+}
+
+/// A single semantic difference between two reports
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum ChangeEvent {
+    /// A component is present only in the new report
+    DeviceAdded { category: ComponentCategory, key: String, name: String },
+    /// A component is present only in the old report
+    DeviceRemoved { category: ComponentCategory, key: String, name: String },
+    /// A component is bound to a different driver
+    DriverChanged {
+        category: ComponentCategory,
+        key: String,
+        old: Option<String>,
+        new: Option<String>,
+    },
+    /// Kernel support status of a device changed
+    SupportStatusChanged { device_id: String, old: String, new: String },
+    /// Running kernel version changed
+    KernelChanged { old: String, new: String },
+    /// Linux distribution changed
+    DistributionChanged { old: Option<String>, new: Option<String> },
+}
+
+/// Word-level change inside a field value
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WordChange<'a> {
+    Same(&'a str),
+    Removed(&'a str),
+    Added(&'a str),
+}
+
+/// Ordered set of changes between two reports
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ReportDiff {
+    pub events: Vec<ChangeEvent>,
+}
+
+/// Compare two reports and return the semantic differences
+pub fn diff_reports(old: &HardwareReport, new: &HardwareReport) -> ReportDiff {
+    let mut events = Vec::new();
+
+    if old.system.kernel_version != new.system.kernel_version {
+        events.push(ChangeEvent::KernelChanged {
+            old: old.system.kernel_version.clone(),
+            new: new.system.kernel_version.clone(),
+        });
+    }
+    if old.system.distribution != new.system.distribution {
+        events.push(ChangeEvent::DistributionChanged {
+            old: old.system.distribution.clone(),
+            new: new.system.distribution.clone(),
+        });
+    }
+
+    diff_components(&mut events, ComponentCategory::Cpu, cpu_components(old), cpu_components(new));
+    diff_components(
+        &mut events,
+        ComponentCategory::Memory,
+        memory_components(old),
+        memory_components(new),
+    );
+    diff_components(
+        &mut events,
+        ComponentCategory::Storage,
+        storage_components(old),
+        storage_components(new),
+    );
+    diff_components(
+        &mut events,
+        ComponentCategory::Graphics,
+        graphics_components(old),
+        graphics_components(new),
+    );
+    diff_components(
+        &mut events,
+        ComponentCategory::Network,
+        network_components(old),
+        network_components(new),
+    );
+    diff_components(&mut events, ComponentCategory::Usb, usb_components(old), usb_components(new));
+    diff_components(
+        &mut events,
+        ComponentCategory::Audio,
+        audio_components(old),
+        audio_components(new),
+    );
+    diff_support_status(&mut events, old, new);
+
+    ReportDiff { events }
+}
+
+/// Component identity and the fields compared between reports
+struct ComponentEntry {
+    name: String,
+    driver: Option<String>,
+}
+
+/// Components keyed by a stable identity, with duplicates numbered
+type ComponentMap = BTreeMap<String, ComponentEntry>;
+
+fn insert_component(map: &mut ComponentMap, key: String, entry: ComponentEntry) {
+    let mut unique_key = key.clone();
+    let mut index = 2;
+    while map.contains_key(&unique_key) {
+        unique_key = format!("{}#{}", key, index);
+        index += 1;
+    }
+    map.insert(unique_key, entry);
+}
+
+fn cpu_components(report: &HardwareReport) -> ComponentMap {
+    let mut map = ComponentMap::new();
+    if let Some(cpu) = &report.cpu {
+        let name = format!("{} {}", cpu.vendor, cpu.model);
+        insert_component(&mut map, name.clone(), ComponentEntry { name, driver: None });
+    }
+    map
+}
+
+fn memory_components(report: &HardwareReport) -> ComponentMap {
+    let mut map = ComponentMap::new();
+    if let Some(memory) = &report.memory {
+        for dimm in &memory.dimms {
+            let name = format!(
+                "{} {} MiB {}",
+                dimm.manufacturer.as_deref().unwrap_or("Unknown"),
+                dimm.size_bytes / (1024 * 1024),
+                dimm.memory_type.as_deref().unwrap_or("")
+            )
+            .trim()
+            .to_string();
+            insert_component(&mut map, name.clone(), ComponentEntry { name, driver: None });
+        }
+    }
+    map
+}
+
+fn storage_components(report: &HardwareReport) -> ComponentMap {
+    let mut map = ComponentMap::new();
+    for device in &report.storage {
+        // Serial numbers are salted, so identify drives by model and size
+        let key = format!("{}:{}", device.model, device.size_bytes);
+        insert_component(
+            &mut map,
+            key,
+            ComponentEntry { name: device.model.clone(), driver: None },
+        );
+    }
+    map
+}
+
+fn graphics_components(report: &HardwareReport) -> ComponentMap {
+    let mut map = ComponentMap::new();
+    for device in &report.graphics {
+        insert_component(
+            &mut map,
+            device.pci_id.clone(),
+            ComponentEntry {
+                name: format!("{} {}", device.vendor, device.model),
+                driver: device.driver.clone(),
+            },
+        );
+    }
+    map
+}
+
+fn network_components(report: &HardwareReport) -> ComponentMap {
+    let mut map = ComponentMap::new();
+    for device in &report.network {
+        // MAC addresses are salted, so identify adapters by type, vendor, and model
+        let key = format!("{}:{}:{}", device.device_type, device.vendor, device.model);
+        insert_component(
+            &mut map,
+            key,
+            ComponentEntry {
+                name: format!("{} {}", device.vendor, device.model),
+                driver: device.driver.clone(),
+            },
+        );
+    }
+    map
+}
+
+fn usb_components(report: &HardwareReport) -> ComponentMap {
+    let mut map = ComponentMap::new();
+    for device in &report.usb {
+        let name = device
+            .product_name
+            .clone()
+            .unwrap_or_else(|| format!("USB device {}:{}", device.vendor_id, device.product_id));
+        insert_component(
+            &mut map,
+            format!("{}:{}", device.vendor_id, device.product_id),
+            ComponentEntry { name, driver: None },
+        );
+    }
+    map
+}
+
+fn audio_components(report: &HardwareReport) -> ComponentMap {
+    let mut map = ComponentMap::new();
+    for device in &report.audio {
+        let name = format!("{} {}", device.vendor, device.model);
+        insert_component(
+            &mut map,
+            name.clone(),
+            ComponentEntry { name, driver: device.driver.clone() },
+        );
+    }
+    map
+}
+
+fn diff_components(
+    events: &mut Vec<ChangeEvent>,
+    category: ComponentCategory,
+    old: ComponentMap,
+    new: ComponentMap,
+) {
+    for (key, old_entry) in &old {
+        match new.get(key) {
+            None => events.push(ChangeEvent::DeviceRemoved {
+                category,
+                key: key.clone(),
+                name: old_entry.name.clone(),
+            }),
+            Some(new_entry) if new_entry.driver != old_entry.driver => {
+                events.push(ChangeEvent::DriverChanged {
+                    category,
+                    key: key.clone(),
+                    old: old_entry.driver.clone(),
+                    new: new_entry.driver.clone(),
+                })
+            }
+            Some(_) => {}
+        }
+    }
+
+    for (key, new_entry) in &new {
+        if !old.contains_key(key) {
+            events.push(ChangeEvent::DeviceAdded {
+                category,
+                key: key.clone(),
+                name: new_entry.name.clone(),
+            });
+        }
+    }
+}
+
+fn diff_support_status(events: &mut Vec<ChangeEvent>, old: &HardwareReport, new: &HardwareReport) {
+    let (Some(old_support), Some(new_support)) = (&old.kernel_support, &new.kernel_support) else {
+        return;
+    };
+
+    let old_status: BTreeMap<_, _> = old_support
+        .device_support_details
+        .iter()
+        .map(|d| (d.device_id.as_str(), d.support_status.as_str()))
+        .collect();
+
+    for device in &new_support.device_support_details {
+        let Some(old_status) = old_status.get(device.device_id.as_str()) else {
+            continue;
+        };
+
+        if *old_status != device.support_status {
+            events.push(ChangeEvent::SupportStatusChanged {
+                device_id: device.device_id.clone(),
+                old: old_status.to_string(),
+                new: device.support_status.clone(),
+            });
+        }
+    }
+}
+
+/// Compute a word-level diff between two field values
+pub fn word_diff<'a>(old: &'a str, new: &'a str) -> Vec<WordChange<'a>> {
+    let old_words: Vec<&str> = old.split_whitespace().collect();
+    let new_words: Vec<&str> = new.split_whitespace().collect();
+
+    // Longest common subsequence table over words
+    let mut lcs = vec![vec![0usize; new_words.len() + 1]; old_words.len() + 1];
+    for i in (0..old_words.len()).rev() {
+        for j in (0..new_words.len()).rev() {
+            lcs[i][j] = if old_words[i] == new_words[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let mut changes = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < old_words.len() && j < new_words.len() {
+        if old_words[i] == new_words[j] {
+            changes.push(WordChange::Same(old_words[i]));
+            i += 1;
+            j += 1;
+        } else if lcs[i + 1][j] >= lcs[i][j + 1] {
+            changes.push(WordChange::Removed(old_words[i]));
+            i += 1;
+        } else {
+            changes.push(WordChange::Added(new_words[j]));
+            j += 1;
+        }
+    }
+    changes.extend(old_words[i..].iter().map(|w| WordChange::Removed(w)));
+    changes.extend(new_words[j..].iter().map(|w| WordChange::Added(w)));
+    changes
+}
+
+/// Render a word-level diff with Markdown strikethrough and emphasis
+fn render_word_diff(old: &str, new: &str) -> String {
+    word_diff(old, new)
+        .into_iter()
+        .map(|change| match change {
+            WordChange::Same(word) => word.to_string(),
+            WordChange::Removed(word) => format!("~~{}~~", word),
+            WordChange::Added(word) => format!("**{}**", word),
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+impl ReportDiff {
+    /// Whether the reports are semantically identical
+    pub fn is_empty(&self) -> bool {
+        self.events.is_empty()
+    }
+
+    /// Render the diff in the requested output format
+    pub fn render(&self, format: OutputFormat) -> Result<String> {
+        match format {
+            OutputFormat::Yaml => Ok(serde_yaml::to_string(self)?),
+            OutputFormat::Json => Ok(serde_json::to_string_pretty(self)?),
+            OutputFormat::Markdown => Ok(self.render_markdown()),
+            OutputFormat::Html => Ok(html::page(
+                "Hardware Report Comparison",
+                &format!("<pre>{}</pre>\n", html::escape(&self.render_markdown())),
+            )),
+            OutputFormat::Xml => Err(LxHwError::InvalidInput {
+                message: "XML output is only available for hardware reports".to_string(),
+            }),
+        }
+    }
+
+    fn render_markdown(&self) -> String {
+        let mut output = String::from("# Hardware Report Comparison\n\n");
+
+        if self.events.is_empty() {
+            output.push_str("No hardware changes detected.\n");
+            return output;
+        }
+
+        output.push_str(&format!("{} change(s) detected.\n\n", self.events.len()));
+        for event in &self.events {
+            output.push_str(&format!("- {}\n", event));
+        }
+        output
+    }
+}
+
+impl fmt::Display for ComponentCategory {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            ComponentCategory::Cpu => "CPU",
+            ComponentCategory::Memory => "Memory",
+            ComponentCategory::Storage => "Storage",
+            ComponentCategory::Graphics => "Graphics",
+            ComponentCategory::Network => "Network",
+            ComponentCategory::Usb => "USB",
+            ComponentCategory::Audio => "Audio",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl fmt::Display for ChangeEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ChangeEvent::DeviceAdded { category, name, .. } => {
+                write!(f, "**Added** {}: {}", category, name)
+            }
+            ChangeEvent::DeviceRemoved { category, name, .. } => {
+                write!(f, "**Removed** {}: {}", category, name)
+            }
+            ChangeEvent::DriverChanged { category, key, old, new } => write!(
+                f,
+                "**Driver changed** {} `{}`: {}",
+                category,
+                key,
+                render_word_diff(
+                    old.as_deref().unwrap_or("none"),
+                    new.as_deref().unwrap_or("none")
+                )
+            ),
+            ChangeEvent::SupportStatusChanged { device_id, old, new } => write!(
+                f,
+                "**Support status changed** `{}`: {}",
+                device_id,
+                render_word_diff(&old.replace('_', " "), &new.replace('_', " "))
+            ),
+            ChangeEvent::KernelChanged { old, new } => {
+                write!(f, "**Kernel changed**: {} → {}", old, new)
+            }
+            ChangeEvent::DistributionChanged { old, new } => write!(
+                f,
+                "**Distribution changed**: {}",
+                render_word_diff(
+                    old.as_deref().unwrap_or("unknown"),
+                    new.as_deref().unwrap_or("unknown")
+                )
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hardware::{
+        DeviceCompatibility, GraphicsDevice, KernelCompatibilityInfo, NetworkDevice, PrivacyLevel,
+        ReportMetadata, SystemInfo,
+    };
+    use chrono::Utc;
+
+    fn create_report(salt: &str) -> HardwareReport {
+        HardwareReport {
+            metadata: ReportMetadata {
+                version: "1.0.0".to_string(),
+                generated_at: Utc::now(),
+                privacy_level: PrivacyLevel::Basic,
+                tools_used: vec!["lspci".to_string()],
+                anonymized_system_id: format!("system_{}", salt),
+                phase_errors: Vec::new(),
+                sandbox: None,
+                privileges: None,
+                remote: false,
+                replayed: false,
+                salt_context: None,
+                digest_length: None,
+            },
+            system: SystemInfo {
+                anonymized_hostname: format!("host_{}", salt),
+                kernel_version: "6.8.0".to_string(),
+                distribution: Some("Ubuntu 24.04".to_string()),
+                architecture: "x86_64".to_string(),
+                boot_time: None,
+                virtualization: None,
+            },
+            cpu: None,
+            memory: None,
+            storage: Vec::new(),
+            storage_stack: None,
+            graphics: vec![
+                GraphicsDevice {
+                    vendor: "Intel".to_string(),
+                    model: "UHD Graphics 620".to_string(),
+                    driver: Some("i915".to_string()),
+                    memory_bytes: None,
+                    pci_id: "8086:5917".to_string(),
+                    displays: None,
+                    nvidia: None,
+                },
+                GraphicsDevice {
+                    vendor: "NVIDIA".to_string(),
+                    model: "GeForce MX150".to_string(),
+                    driver: Some("nouveau".to_string()),
+                    memory_bytes: None,
+                    pci_id: "10de:1d10".to_string(),
+                    displays: None,
+                    nvidia: None,
+                },
+            ],
+            network: vec![NetworkDevice {
+                device_type: "wifi".to_string(),
+                vendor: "Intel".to_string(),
+                model: "Wireless 8265".to_string(),
+                driver: Some("iwlwifi".to_string()),
+                anonymized_mac: format!("mac_{}", salt),
+                wireless_capabilities: None,
+            }],
+            usb: Vec::new(),
+            audio: Vec::new(),
+            audio_routing: None,
+            radios: Vec::new(),
+            input: Vec::new(),
+            peripherals: None,
+            pcie_links: Vec::new(),
+            sensors: Vec::new(),
+            firmware: None,
+            kernel_support: Some(KernelCompatibilityInfo {
+                kernel_version: "6.8.0".to_string(),
+                total_devices_detected: 1,
+                supported_devices: 0,
+                unsupported_devices: 0,
+                experimental_devices: 1,
+                device_support_details: vec![DeviceCompatibility {
+                    device_id: "10de:1d10".to_string(),
+                    device_name: "GeForce MX150".to_string(),
+                    support_status: "experimental".to_string(),
+                    driver_module: "nouveau".to_string(),
+                    since_kernel_version: None,
+                    config_dependencies: Vec::new(),
+                    notes: None,
+                }],
+                missing_modules: Vec::new(),
+                config_recommendations: Vec::new(),
+                module_parameters: Vec::new(),
+                blacklisted_modules: Vec::new(),
+                dkms_modules: Vec::new(),
+            }),
+            unknown_devices: Vec::new(),
+            lifecycle_notices: Vec::new(),
+            firmware_updates: Vec::new(),
+            user_notes: Vec::new(),
+            blobs: Vec::new(),
+            provenance: None,
+        }
+    }
+
+    #[test]
+    fn test_identical_reports_with_different_salts() {
+        let old = create_report("salt_a");
+        let mut new = create_report("salt_b");
+        new.graphics.reverse();
+
+        assert!(diff_reports(&old, &new).is_empty());
+    }
+
+    #[test]
+    fn test_driver_and_support_changes() {
+        let old = create_report("a");
+        let mut new = create_report("a");
+        new.graphics[1].driver = Some("nvidia".to_string());
+        new.kernel_support.as_mut().unwrap().device_support_details[0].support_status =
+            "supported".to_string();
+
+        let diff = diff_reports(&old, &new);
+        assert_eq!(
+            diff.events,
+            vec![
+                ChangeEvent::DriverChanged {
+                    category: ComponentCategory::Graphics,
+                    key: "10de:1d10".to_string(),
+                    old: Some("nouveau".to_string()),
+                    new: Some("nvidia".to_string()),
+                },
+                ChangeEvent::SupportStatusChanged {
+                    device_id: "10de:1d10".to_string(),
+                    old: "experimental".to_string(),
+                    new: "supported".to_string(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_added_and_removed_devices() {
+        let old = create_report("a");
+        let mut new = create_report("a");
+        new.network.clear();
+        new.system.kernel_version = "6.9.1".to_string();
+
+        let diff = diff_reports(&old, &new);
+        assert!(diff.events.contains(&ChangeEvent::KernelChanged {
+            old: "6.8.0".to_string(),
+            new: "6.9.1".to_string(),
+        }));
+        assert!(diff.events.iter().any(|e| matches!(
+            e,
+            ChangeEvent::DeviceRemoved { category: ComponentCategory::Network, .. }
+        )));
+    }
+
+    #[test]
+    fn test_word_diff() {
+        assert_eq!(
+            word_diff("similar device supported", "supported"),
+            vec![
+                WordChange::Removed("similar"),
+                WordChange::Removed("device"),
+                WordChange::Same("supported"),
+            ]
+        );
+        assert_eq!(render_word_diff("nouveau", "nvidia"), "~~nouveau~~ **nvidia**");
+    }
+
+    #[test]
+    fn test_render_formats() {
+        let old = create_report("a");
+        let mut new = create_report("a");
+        new.graphics[0].driver = Some("xe".to_string());
+        let diff = diff_reports(&old, &new);
+
+        let markdown = diff.render(OutputFormat::Markdown).unwrap();
+        assert!(markdown.contains("~~i915~~ **xe**"));
+
+        let json: serde_json::Value =
+            serde_json::from_str(&diff.render(OutputFormat::Json).unwrap()).unwrap();
+        assert_eq!(json["events"][0]["event"], "driver_changed");
+    }
+}
@@ -0,0 +1,237 @@
+//! GitHub device flow authentication
+//!
+//! Instead of creating a personal access token on github.com and pasting it
+//! into the terminal, the user is shown a short code to enter at
+//! github.com/login/device. The tool polls GitHub until the code is
+//! authorized and receives a token for the OAuth app named by the client ID,
+//! scoped to what submission needs: forking the database, pushing a branch
+//! and opening a pull request.
+
+use crate::errors::{LxHwError, Result};
+use serde::Deserialize;
+use std::process::{Command, Stdio};
+use std::time::Duration;
+
+/// Environment variable overriding the OAuth app client ID
+pub const CLIENT_ID_ENV: &str = "LX_HW_GITHUB_CLIENT_ID";
+
+/// Client ID of the OAuth app, set by packagers at build time
+const BUILTIN_CLIENT_ID: Option<&str> = option_env!("LX_HW_GITHUB_CLIENT_ID");
+
+/// Scopes needed to fork the database and open pull requests
+const SCOPES: &str = "public_repo";
+
+const DEVICE_CODE_URL: &str = "https://github.com/login/device/code";
+const ACCESS_TOKEN_URL: &str = "https://github.com/login/oauth/access_token";
+const USER_URL: &str = "https://api.github.com/user";
+const GRANT_TYPE: &str = "urn:ietf:params:oauth:grant-type:device_code";
+
+/// Seconds added to the polling interval when GitHub asks to slow down
+const SLOW_DOWN_SECONDS: u64 = 5;
+
+/// A token obtained through the device flow
+#[derive(Debug, Clone)]
+pub struct DeviceFlowToken {
+    pub access_token: String,
+    pub scope: String,
+    /// Login of the user who authorized the code
+    pub username: String,
+}
+
+/// Client ID of the OAuth app used for the device flow, if one is configured
+pub fn client_id() -> Option<String> {
+    std::env::var(CLIENT_ID_ENV)
+        .ok()
+        .filter(|id| !id.trim().is_empty())
+        .or_else(|| BUILTIN_CLIENT_ID.map(str::to_string))
+}
+
+#[derive(Debug, Deserialize)]
+struct DeviceCode {
+    device_code: String,
+    user_code: String,
+    verification_uri: String,
+    expires_in: u64,
+    interval: u64,
+}
+
+/// Answer of the access token endpoint while polling
+#[derive(Debug, Deserialize)]
+struct AccessTokenResponse {
+    access_token: Option<String>,
+    #[serde(default)]
+    scope: String,
+    error: Option<String>,
+    error_description: Option<String>,
+    /// New minimum polling interval sent with `slow_down`
+    interval: Option<u64>,
+}
+
+/// What to do after a poll
+#[derive(Debug, PartialEq, Eq)]
+enum PollOutcome {
+    Authorized {
+        access_token: String,
+        scope: String,
+    },
+    /// Poll again after the given number of seconds
+    Wait(u64),
+    Failed(String),
+}
+
+impl AccessTokenResponse {
+    fn outcome(self, interval: u64) -> PollOutcome {
+        if let Some(access_token) = self.access_token {
+            return PollOutcome::Authorized { access_token, scope: self.scope };
+        }
+        match self.error.as_deref() {
+            Some("authorization_pending") => PollOutcome::Wait(interval),
+            Some("slow_down") => {
+                PollOutcome::Wait(self.interval.unwrap_or(interval + SLOW_DOWN_SECONDS))
+            }
+            Some("expired_token") => {
+                PollOutcome::Failed("The code expired before it was entered; try again".to_string())
+            }
+            Some("access_denied") => {
+                PollOutcome::Failed("Authorization was denied on GitHub".to_string())
+            }
+            Some(error) => PollOutcome::Failed(
+                self.error_description.unwrap_or_else(|| format!("GitHub returned {}", error)),
+            ),
+            None => PollOutcome::Failed("GitHub returned neither a token nor an error".to_string()),
+        }
+    }
+}
+
+/// Authorize through the device flow, printing the code for the user
+pub async fn authorize(client_id: &str) -> Result<DeviceFlowToken> {
+    let client = http_client()?;
+    let request_error = |e: reqwest::Error| {
+        LxHwError::Submission(format!("GitHub device authorization failed: {}", e))
+    };
+
+    let code: DeviceCode = client
+        .post(DEVICE_CODE_URL)
+        .header("Accept", "application/json")
+        .form(&[("client_id", client_id), ("scope", SCOPES)])
+        .send()
+        .await
+        .and_then(|response| response.error_for_status())
+        .map_err(request_error)?
+        .json()
+        .await
+        .map_err(request_error)?;
+
+    println!("🔑 To authorize lx-hw-detect, open {}", code.verification_uri);
+    println!("   and enter the code: {}", code.user_code);
+    open_browser(&code.verification_uri);
+    println!(
+        "⏳ Waiting for authorization (the code expires in {} minutes)...",
+        code.expires_in / 60
+    );
+
+    let deadline = tokio::time::Instant::now() + Duration::from_secs(code.expires_in);
+    let mut interval = code.interval;
+    let (access_token, scope) = loop {
+        tokio::time::sleep(Duration::from_secs(interval)).await;
+        if tokio::time::Instant::now() >= deadline {
+            return Err(LxHwError::Submission(
+                "The code expired before it was entered; try again".to_string(),
+            ));
+        }
+
+        let response: AccessTokenResponse = client
+            .post(ACCESS_TOKEN_URL)
+            .header("Accept", "application/json")
+            .form(&[
+                ("client_id", client_id),
+                ("device_code", code.device_code.as_str()),
+                ("grant_type", GRANT_TYPE),
+            ])
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(request_error)?
+            .json()
+            .await
+            .map_err(request_error)?;
+
+        match response.outcome(interval) {
+            PollOutcome::Authorized { access_token, scope } => break (access_token, scope),
+            PollOutcome::Wait(seconds) => interval = seconds,
+            PollOutcome::Failed(message) => return Err(LxHwError::Submission(message)),
+        }
+    };
+
+    let username = authenticated_user(&access_token).await?;
+    println!("✅ Authorized as {}", username);
+    Ok(DeviceFlowToken { access_token, scope, username })
+}
+
+/// Login of the user a token belongs to; fails for revoked or expired tokens
+pub async fn authenticated_user(token: &str) -> Result<String> {
+    #[derive(Deserialize)]
+    struct User {
+        login: String,
+    }
+
+    let client = http_client()?;
+    let request_error =
+        |e: reqwest::Error| LxHwError::Submission(format!("Failed to look up GitHub user: {}", e));
+    let user: User = client
+        .get(USER_URL)
+        .bearer_auth(token)
+        .header("Accept", "application/vnd.github+json")
+        .send()
+        .await
+        .and_then(|response| response.error_for_status())
+        .map_err(request_error)?
+        .json()
+        .await
+        .map_err(request_error)?;
+    Ok(user.login)
+}
+
+fn http_client() -> Result<reqwest::Client> {
+    reqwest::Client::builder()
+        .user_agent(concat!("lx-hw-detect/", env!("CARGO_PKG_VERSION")))
+        .build()
+        .map_err(|e| LxHwError::Submission(format!("Failed to create HTTP client: {}", e)))
+}
+
+/// Open the verification page when a desktop session can; the printed
+/// address is enough otherwise
+fn open_browser(url: &str) {
+    if std::env::var_os("DISPLAY").is_none() && std::env::var_os("WAYLAND_DISPLAY").is_none() {
+        return;
+    }
+    let _ = Command::new("xdg-open").arg(url).stdout(Stdio::null()).stderr(Stdio::null()).spawn();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn outcome(json: &str, interval: u64) -> PollOutcome {
+        serde_json::from_str::<AccessTokenResponse>(json).unwrap().outcome(interval)
+    }
+
+    #[test]
+    fn test_poll_outcomes() {
+        assert_eq!(
+            outcome(r#"{"access_token":"gho_abc","token_type":"bearer","scope":"public_repo"}"#, 5),
+            PollOutcome::Authorized {
+                access_token: "gho_abc".to_string(),
+                scope: "public_repo".to_string()
+            }
+        );
+        assert_eq!(outcome(r#"{"error":"authorization_pending"}"#, 5), PollOutcome::Wait(5));
+        assert_eq!(outcome(r#"{"error":"slow_down","interval":10}"#, 5), PollOutcome::Wait(10));
+        assert_eq!(outcome(r#"{"error":"slow_down"}"#, 5), PollOutcome::Wait(10));
+        assert!(matches!(outcome(r#"{"error":"access_denied"}"#, 5), PollOutcome::Failed(_)));
+        assert_eq!(
+            outcome(r#"{"error":"incorrect_client_credentials","error_description":"bad id"}"#, 5),
+            PollOutcome::Failed("bad id".to_string())
+        );
+    }
+}
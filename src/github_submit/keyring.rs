@@ -0,0 +1,156 @@
+//! GitHub token storage in the system keyring
+//!
+//! Tokens are kept by the Secret Service (GNOME Keyring, KWallet and
+//! KeePassXC implement it) through libsecret's `secret-tool`, never in a
+//! configuration file, so an authorization survives between runs without the
+//! token being written to disk in plain text or passed on the command line.
+
+use crate::errors::{LxHwError, Result};
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+/// Attributes identifying the stored token
+const SERVICE: &str = "lx-hw-detect";
+const ACCOUNT: &str = "github";
+
+/// Store a token for a GitHub user, replacing any earlier one
+///
+/// The username is an attribute of the item, so earlier items are cleared
+/// first; otherwise logging in as another user would add a second item that
+/// a lookup might not return.
+pub fn store_token(username: &str, token: &str) -> Result<()> {
+    clear_tokens()?;
+    let mut child = Command::new("secret-tool")
+        .args(["store", "--label", "lx-hw-detect GitHub token"])
+        .args(["service", SERVICE, "account", ACCOUNT, "username", username])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| {
+            LxHwError::Submission(format!(
+                "secret-tool not found; install libsecret-tools to keep the token in the keyring: {}",
+                e
+            ))
+        })?;
+
+    // The secret is read from stdin so it never appears in the process list
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(token.as_bytes())?;
+    }
+    let output = child.wait_with_output()?;
+    if !output.status.success() {
+        return Err(LxHwError::Submission(format!(
+            "Failed to store the token in the keyring: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    Ok(())
+}
+
+/// A token found in the keyring
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StoredToken {
+    pub username: String,
+    pub token: String,
+}
+
+/// The stored token, if any; None as well when `secret-tool` is not installed
+pub fn load_token() -> Result<Option<StoredToken>> {
+    let output = match Command::new("secret-tool")
+        .args(["search", "--unlock", "service", SERVICE, "account", ACCOUNT])
+        .stdin(Stdio::null())
+        .output()
+    {
+        Ok(output) => output,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e.into()),
+    };
+    // Finding nothing is not an error; a locked or missing keyring is
+    if !output.status.success() && !output.stderr.is_empty() {
+        return Err(LxHwError::Submission(format!(
+            "Failed to read the keyring: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    Ok(parse_search_output(&String::from_utf8_lossy(&output.stdout)))
+}
+
+/// Remove the stored token; Ok(false) when there was none
+pub fn delete_token() -> Result<bool> {
+    if load_token()?.is_none() {
+        return Ok(false);
+    }
+    clear_tokens()?;
+    Ok(true)
+}
+
+/// Remove every stored token, whichever user it is for
+fn clear_tokens() -> Result<()> {
+    let output = match Command::new("secret-tool")
+        .args(["clear", "service", SERVICE, "account", ACCOUNT])
+        .stdin(Stdio::null())
+        .output()
+    {
+        Ok(output) => output,
+        // Reported by the store that follows
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+        Err(e) => return Err(e.into()),
+    };
+    if !output.status.success() {
+        return Err(LxHwError::Submission(format!(
+            "Failed to remove the token from the keyring: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    Ok(())
+}
+
+/// First item of `secret-tool search` output with a secret and a username
+fn parse_search_output(output: &str) -> Option<StoredToken> {
+    let mut username = None;
+    let mut token = None;
+    for line in output.lines() {
+        if line.starts_with('[') {
+            // Next item
+            if let (Some(username), Some(token)) = (username.take(), token.take()) {
+                return Some(StoredToken { username, token });
+            }
+            continue;
+        }
+        match line.split_once(" = ") {
+            Some(("secret", value)) => token = Some(value.to_string()),
+            Some(("attribute.username", value)) => username = Some(value.to_string()),
+            _ => {}
+        }
+    }
+    Some(StoredToken { username: username?, token: token? })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_search_output() {
+        let output = "[/org/freedesktop/secrets/collection/login/7]
+label = lx-hw-detect GitHub token
+secret = gho_example_token
+created = 2024-05-02 10:11:12
+modified = 2024-05-02 10:11:12
+schema = org.freedesktop.Secret.Generic
+attribute.service = lx-hw-detect
+attribute.account = github
+attribute.username = octocat
+";
+        assert_eq!(
+            parse_search_output(output),
+            Some(StoredToken {
+                username: "octocat".to_string(),
+                token: "gho_example_token".to_string(),
+            })
+        );
+        assert_eq!(parse_search_output(""), None);
+        assert_eq!(parse_search_output("[/item]\nsecret = abc\n"), None);
+    }
+}
@@ -4,8 +4,12 @@
 //! It handles forking repositories, creating branches, committing files, and opening
 //! pull requests with minimal user interaction.
 
+use crate::detectors::kernel::{KernelSupportData, SupportLevel};
 use crate::errors::{LxHwError, Result};
+use crate::hardware::lifecycle::LifecycleDatabase;
 use crate::hardware::{HardwareReport, PrivacyLevel};
+use crate::privacy::raw::RawSanitizer;
+use crate::privacy::PrivacyManager;
 use chrono::{DateTime, Utc};
 use serde::Deserialize;
 use std::fs;
@@ -14,6 +18,12 @@ use std::path::{Path, PathBuf};
 use std::process::{Command, Stdio};
 use tempfile::TempDir;
 
+pub mod device_flow;
+pub mod keyring;
+pub mod relay;
+
+pub use keyring::StoredToken;
+
 /// GitHub repository configuration
 #[derive(Debug, Clone)]
 pub struct GitHubConfig {
@@ -34,6 +44,124 @@ pub struct SubmissionInfo {
     pub tools_used: Vec<String>,
 }
 
+/// Labels applied by the hardware compatibility issue template
+const HARDWARE_ISSUE_LABELS: &str = "hardware,compatibility";
+
+/// Issue draft for a PCI device the running kernel has no driver for
+#[derive(Debug, Clone)]
+pub struct UnsupportedDeviceIssue {
+    /// PCI ID in vendor:device format
+    pub device_id: String,
+    pub kernel_version: String,
+    pub distribution: Option<String>,
+    /// Kernel log lines mentioning the device, posted in the issue
+    pub dmesg_lines: Vec<String>,
+}
+
+/// Result of offering an issue for one device
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DeviceIssueOutcome {
+    /// A new issue was opened at this URL
+    Opened(String),
+    /// An issue for the device already exists at this URL
+    AlreadyReported(String),
+    /// The user declined to open an issue
+    Skipped,
+}
+
+impl UnsupportedDeviceIssue {
+    /// Drafts for unsupported devices that no known driver branch covers
+    ///
+    /// Devices whose hardware generation is mapped to a legacy or
+    /// out-of-tree driver are already documented and are left out.
+    pub fn for_unsupported_devices(
+        support_data: &KernelSupportData,
+        lifecycle: &LifecycleDatabase,
+        distribution: Option<&str>,
+        dmesg_lines: impl Fn(&str) -> Vec<String>,
+    ) -> Vec<Self> {
+        support_data
+            .supported_devices
+            .iter()
+            .filter(|device| device.support_level == SupportLevel::Unsupported)
+            .filter(|device| {
+                let known_driver = device.device_id.split_once(':').and_then(|(vendor, id)| {
+                    lifecycle.lookup_pci(vendor, id).and_then(|g| g.driver.as_ref())
+                });
+                known_driver.is_none()
+            })
+            .map(|device| Self {
+                device_id: device.device_id.clone(),
+                kernel_version: support_data.kernel_version.clone(),
+                distribution: distribution.map(String::from),
+                dmesg_lines: dmesg_lines(&device.device_id),
+            })
+            .collect()
+    }
+
+    /// Replace identifiers in the kernel log lines of public issues as
+    /// reports do, or leave the lines out at the strict privacy level
+    pub fn sanitize_dmesg_lines(
+        issues: &mut [Self],
+        privacy_level: PrivacyLevel,
+        hostname: Option<&str>,
+    ) -> Result<()> {
+        if privacy_level == PrivacyLevel::Strict {
+            issues.iter_mut().for_each(|issue| issue.dmesg_lines.clear());
+            return Ok(());
+        }
+        let mut manager = PrivacyManager::new(privacy_level)?;
+        let mut sanitizer = RawSanitizer::new(&mut manager);
+        if let Some(hostname) = hostname {
+            sanitizer = sanitizer.with_hostname(hostname);
+        }
+        for issue in issues {
+            issue.dmesg_lines = sanitizer.sanitize_lines(&issue.dmesg_lines);
+        }
+        Ok(())
+    }
+
+    /// Issue title following the hardware compatibility template
+    pub fn title(&self) -> String {
+        format!("[Hardware] No kernel driver for PCI device {}", self.device_id)
+    }
+
+    /// Issue body with the fields of the hardware compatibility template
+    pub fn body(&self) -> String {
+        let mut body = String::new();
+
+        body.push_str("### Hardware Category\n\nOther Hardware\n\n");
+        body.push_str(&format!("### Hardware ID\n\n`{}`\n\n", self.device_id));
+        body.push_str("### Current Compatibility Status\n\nNot working at all\n\n");
+        body.push_str(&format!(
+            "### Linux Distribution\n\n{}\n\n",
+            self.distribution.as_deref().unwrap_or("Unknown")
+        ));
+        body.push_str(&format!("### Kernel Version\n\n{}\n\n", self.kernel_version));
+
+        body.push_str("### Kernel Log\n\n");
+        if self.dmesg_lines.is_empty() {
+            body.push_str("No kernel log lines are included for this device.\n\n");
+        } else {
+            body.push_str("```\n");
+            for line in &self.dmesg_lines {
+                body.push_str(line);
+                body.push('\n');
+            }
+            body.push_str("```\n\n");
+        }
+
+        body.push_str("### Additional Information\n\n");
+        body.push_str(
+            "No module in the running kernel's `modules.alias` matches this device, and it is \
+             not covered by a known legacy or out-of-tree driver. This issue was opened with \
+             `lx-hw-detect analyze --open-issues`.\n",
+        );
+
+        body
+    }
+}
+
 /// GitHub API response structures
 #[derive(Debug, Deserialize)]
 #[allow(dead_code)]
@@ -76,7 +204,7 @@ impl GitHubSubmitter {
         self.validate_credentials().await?;
 
         // Step 2: Load and validate the hardware report
-        let report = self.load_and_validate_report(&submission.report_path)?;
+        let report = load_and_validate_report(&submission.report_path)?;
 
         // Step 3: Generate proper filename and directory structure
         let (filename, directory) = self.generate_file_path(&report)?;
@@ -99,8 +227,8 @@ impl GitHubSubmitter {
         // Step 7: Create feature branch
         let branch_name = self.create_feature_branch(&repo_path, &report)?;
 
-        // Step 8: Copy report file to correct location
-        self.add_report_file(&repo_path, &submission.report_path, &directory, &filename)?;
+        // Step 8: Write report file to correct location
+        self.add_report_file(&repo_path, &report, &directory, &filename)?;
 
         // Step 9: Commit changes
         self.commit_changes(&repo_path, &submission, &report, &filename)?;
@@ -137,7 +265,7 @@ impl GitHubSubmitter {
 
         if !output.status.success() {
             return Err(LxHwError::Submission(
-                "GitHub authentication failed. Please check your token.".to_string(),
+                "GitHub authentication failed. Please check your token, or run `lx-hw-detect auth login` to authorize again.".to_string(),
             ));
         }
 
@@ -145,29 +273,6 @@ impl GitHubSubmitter {
         Ok(())
     }
 
-    /// Load and validate the hardware report
-    fn load_and_validate_report(&self, report_path: &Path) -> Result<HardwareReport> {
-        println!("📄 Loading hardware report: {}", report_path.display());
-
-        let content = fs::read_to_string(report_path)
-            .map_err(|e| LxHwError::Io(format!("Failed to read report file: {}", e)))?;
-
-        let report: HardwareReport = serde_json::from_str(&content)
-            .map_err(|e| LxHwError::Validation(format!("Invalid report format: {}", e)))?;
-
-        // Validate report has required fields
-        if report.metadata.anonymized_system_id.is_empty() {
-            return Err(LxHwError::Validation("Report missing anonymized system ID".to_string()));
-        }
-
-        if report.system.kernel_version.is_empty() {
-            return Err(LxHwError::Validation("Report missing kernel version".to_string()));
-        }
-
-        println!("✅ Report validation passed");
-        Ok(report)
-    }
-
     /// Generate proper filename and directory structure
     fn generate_file_path(&self, report: &HardwareReport) -> Result<(String, String)> {
         let date = report.metadata.generated_at.format("%Y-%m-%d");
@@ -227,16 +332,7 @@ impl GitHubSubmitter {
 
     /// Get user confirmation for submission
     fn confirm_submission(&self) -> Result<bool> {
-        print!("\n❓ Submit this hardware report? [Y/n]: ");
-        io::stdout().flush().map_err(|e| LxHwError::Io(format!("IO error: {}", e)))?;
-
-        let mut input = String::new();
-        io::stdin()
-            .read_line(&mut input)
-            .map_err(|e| LxHwError::Io(format!("Failed to read user input: {}", e)))?;
-
-        let input = input.trim().to_lowercase();
-        Ok(input.is_empty() || input == "y" || input == "yes")
+        confirm("\n❓ Submit this hardware report? [Y/n]: ", true)
     }
 
     /// Ensure the repository is forked
@@ -364,7 +460,7 @@ impl GitHubSubmitter {
     fn add_report_file(
         &self,
         repo_path: &Path,
-        report_path: &Path,
+        report: &HardwareReport,
         directory: &str,
         filename: &str,
     ) -> Result<()> {
@@ -375,10 +471,10 @@ impl GitHubSubmitter {
         fs::create_dir_all(&target_dir)
             .map_err(|e| LxHwError::Io(format!("Failed to create directory structure: {}", e)))?;
 
-        // Copy report file
+        // Write the report in canonical form so revisions diff cleanly
         let target_path = target_dir.join(filename);
-        fs::copy(report_path, &target_path)
-            .map_err(|e| LxHwError::Io(format!("Failed to copy report file: {}", e)))?;
+        fs::write(&target_path, crate::output::canonical::to_json(report)?)
+            .map_err(|e| LxHwError::Io(format!("Failed to write report file: {}", e)))?;
 
         // Add file to git
         let output = Command::new("git")
@@ -536,6 +632,113 @@ impl GitHubSubmitter {
         Ok(pr_url)
     }
 
+    /// Offer to open an issue for each unsupported device
+    ///
+    /// Every draft is shown before anything is sent and opening it must be
+    /// confirmed; devices that already have an issue are not reported again.
+    pub async fn open_device_issues(
+        &self,
+        issues: &[UnsupportedDeviceIssue],
+    ) -> Result<Vec<DeviceIssueOutcome>> {
+        self.validate_credentials().await?;
+
+        let mut outcomes = Vec::with_capacity(issues.len());
+        for issue in issues {
+            if let Some(url) = self.find_device_issue(&issue.device_id)? {
+                println!("ℹ️  {} is already reported: {}", issue.device_id, url);
+                outcomes.push(DeviceIssueOutcome::AlreadyReported(url));
+                continue;
+            }
+
+            println!("\n📋 {}", issue.title());
+            println!("═══════════════════════════════════════");
+            println!("{}", issue.body());
+            println!("═══════════════════════════════════════");
+            if !issue.dmesg_lines.is_empty() {
+                println!(
+                    "The {} kernel log line(s) above will be public exactly as shown.",
+                    issue.dmesg_lines.len()
+                );
+            }
+            if !confirm("❓ Open this public issue? [y/N]: ", false)? {
+                outcomes.push(DeviceIssueOutcome::Skipped);
+                continue;
+            }
+
+            let url = self.create_device_issue(issue)?;
+            println!("✅ Issue opened: {}", url);
+            outcomes.push(DeviceIssueOutcome::Opened(url));
+        }
+
+        Ok(outcomes)
+    }
+
+    /// URL of an existing issue mentioning the device in its title
+    fn find_device_issue(&self, device_id: &str) -> Result<Option<String>> {
+        let upstream_repo = format!("{}/{}", self.config.upstream_owner, self.config.upstream_repo);
+        let search = format!("{} in:title", device_id);
+        let args = [
+            "issue",
+            "list",
+            "--repo",
+            &upstream_repo,
+            "--state",
+            "all",
+            "--search",
+            &search,
+            "--json",
+            "url",
+            "--jq",
+            ".[0].url // empty",
+        ];
+
+        let output = Command::new("gh")
+            .args(args)
+            .env("GH_TOKEN", &self.config.token)
+            .output()
+            .map_err(|e| LxHwError::Submission(format!("Failed to search issues: {}", e)))?;
+
+        if !output.status.success() {
+            let error = String::from_utf8_lossy(&output.stderr);
+            return Err(LxHwError::Submission(format!("Issue search failed: {}", error)));
+        }
+
+        let url = String::from_utf8_lossy(&output.stdout).trim().to_string();
+        Ok((!url.is_empty()).then_some(url))
+    }
+
+    /// Open an issue in the upstream repository
+    fn create_device_issue(&self, issue: &UnsupportedDeviceIssue) -> Result<String> {
+        let upstream_repo = format!("{}/{}", self.config.upstream_owner, self.config.upstream_repo);
+        let title = issue.title();
+        let body = issue.body();
+        let args = [
+            "issue",
+            "create",
+            "--repo",
+            &upstream_repo,
+            "--title",
+            &title,
+            "--body",
+            &body,
+            "--label",
+            HARDWARE_ISSUE_LABELS,
+        ];
+
+        let output = Command::new("gh")
+            .args(args)
+            .env("GH_TOKEN", &self.config.token)
+            .output()
+            .map_err(|e| LxHwError::Submission(format!("Failed to create issue: {}", e)))?;
+
+        if !output.status.success() {
+            let error = String::from_utf8_lossy(&output.stderr);
+            return Err(LxHwError::Submission(format!("Issue creation failed: {}", error)));
+        }
+
+        Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+    }
+
     /// Generate pull request body
     fn generate_pr_body(&self, submission: &SubmissionInfo, report: &HardwareReport) -> String {
         let mut body = String::new();
@@ -607,42 +810,79 @@ impl GitHubSubmitter {
     }
 }
 
+/// Load a hardware report and check it has the fields a submission needs
+fn load_and_validate_report(report_path: &Path) -> Result<HardwareReport> {
+    println!("📄 Loading hardware report: {}", report_path.display());
+
+    let report = HardwareReport::load(report_path)
+        .map_err(|e| LxHwError::Validation(format!("Invalid report format: {}", e)))?;
+
+    // Validate report has required fields
+    if report.metadata.anonymized_system_id.is_empty() {
+        return Err(LxHwError::Validation("Report missing anonymized system ID".to_string()));
+    }
+
+    if report.system.kernel_version.is_empty() {
+        return Err(LxHwError::Validation("Report missing kernel version".to_string()));
+    }
+
+    println!("✅ Report validation passed");
+    Ok(report)
+}
+
+/// Ask a yes/no question on the terminal; an empty answer picks `default`
+fn confirm(prompt: &str, default: bool) -> Result<bool> {
+    print!("{}", prompt);
+    io::stdout().flush().map_err(|e| LxHwError::Io(format!("IO error: {}", e)))?;
+
+    let mut input = String::new();
+    io::stdin()
+        .read_line(&mut input)
+        .map_err(|e| LxHwError::Io(format!("Failed to read user input: {}", e)))?;
+
+    let input = input.trim().to_lowercase();
+    Ok(match input.as_str() {
+        "" => default,
+        answer => answer == "y" || answer == "yes",
+    })
+}
+
 /// Interactive setup for GitHub credentials and configuration
-pub fn setup_github_config(
+///
+/// A token given explicitly is used as is. Otherwise the token kept in the
+/// system keyring by `auth login` or an earlier submission is used, and
+/// without one the user logs in and the new token is stored.
+pub async fn setup_github_config(
     username: Option<String>,
     token: Option<String>,
 ) -> Result<GitHubConfig> {
     println!("🔧 Setting up GitHub submission...");
 
-    let username = if let Some(u) = username {
-        u
+    let stored = if token.is_some() {
+        None
     } else {
-        print!("GitHub username: ");
-        io::stdout().flush().map_err(|e| LxHwError::Io(format!("IO error: {}", e)))?;
-        let mut input = String::new();
-        io::stdin()
-            .read_line(&mut input)
-            .map_err(|e| LxHwError::Io(format!("Failed to read username: {}", e)))?;
-        input.trim().to_string()
+        // A locked or unavailable keyring should not stop a submission
+        keyring::load_token().unwrap_or_else(|e| {
+            println!("⚠️  {}", e);
+            None
+        })
     };
 
-    let token = if let Some(t) = token {
-        t
-    } else {
-        print!("GitHub token (will be hidden): ");
-        io::stdout().flush().map_err(|e| LxHwError::Io(format!("IO error: {}", e)))?;
-
-        // Use rpassword crate if available, otherwise fall back to regular input
-        let token = if let Ok(token) = rpassword::read_password() {
-            token
-        } else {
-            let mut input = String::new();
-            io::stdin()
-                .read_line(&mut input)
-                .map_err(|e| LxHwError::Io(format!("Failed to read token: {}", e)))?;
-            input.trim().to_string()
-        };
-        token
+    let (username, token) = match (token, stored) {
+        (Some(token), _) => (prompt_username(username)?, token),
+        (None, Some(stored)) => {
+            warn_other_account(username.as_deref(), &stored.username);
+            println!("🔒 Using the token stored in the system keyring for {}", stored.username);
+            (stored.username, stored.token)
+        }
+        (None, None) => {
+            let login = login(username, None).await?;
+            match keyring::store_token(&login.username, &login.token) {
+                Ok(()) => println!("🔒 Token stored in the system keyring"),
+                Err(e) => println!("⚠️  {}", e),
+            }
+            (login.username, login.token)
+        }
     };
 
     Ok(GitHubConfig {
@@ -653,3 +893,151 @@ pub fn setup_github_config(
         auto_fork: true,
     })
 }
+
+/// Authorize lx-hw-detect for a GitHub account
+///
+/// With `token` given, its account is looked up. Otherwise the user
+/// authorizes in the browser through the device flow when an OAuth app client
+/// ID is configured, or is asked for a personal access token. The caller
+/// decides whether to keep the result in the keyring.
+pub async fn login(username: Option<String>, token: Option<String>) -> Result<StoredToken> {
+    let (account, token) = match (token, device_flow::client_id()) {
+        (Some(token), _) => (device_flow::authenticated_user(&token).await?, token),
+        (None, Some(client_id)) => {
+            let authorized = device_flow::authorize(&client_id).await?;
+            (authorized.username, authorized.access_token)
+        }
+        (None, None) => {
+            println!(
+                "ℹ️  Browser authorization is unavailable: this build has no GitHub OAuth app \
+                 client ID. Set {} to the client ID of an OAuth app with device flow enabled \
+                 to use it, or enter a personal access token with the public_repo scope from \
+                 https://github.com/settings/tokens",
+                device_flow::CLIENT_ID_ENV
+            );
+            let token = prompt_token()?;
+            (device_flow::authenticated_user(&token).await?, token)
+        }
+    };
+    warn_other_account(username.as_deref(), &account);
+    Ok(StoredToken { username: account, token })
+}
+
+/// Point out when the token belongs to another account than the one asked for
+fn warn_other_account(requested: Option<&str>, account: &str) {
+    if let Some(requested) = requested.filter(|requested| *requested != account) {
+        println!(
+            "⚠️  The token belongs to {} rather than {}; using {}",
+            account, requested, account
+        );
+    }
+}
+
+fn prompt_username(username: Option<String>) -> Result<String> {
+    if let Some(u) = username {
+        return Ok(u);
+    }
+    print!("GitHub username: ");
+    io::stdout().flush().map_err(|e| LxHwError::Io(format!("IO error: {}", e)))?;
+    let mut input = String::new();
+    io::stdin()
+        .read_line(&mut input)
+        .map_err(|e| LxHwError::Io(format!("Failed to read username: {}", e)))?;
+    Ok(input.trim().to_string())
+}
+
+fn prompt_token() -> Result<String> {
+    print!("GitHub token (will be hidden): ");
+    io::stdout().flush().map_err(|e| LxHwError::Io(format!("IO error: {}", e)))?;
+
+    // Use rpassword crate if available, otherwise fall back to regular input
+    if let Ok(token) = rpassword::read_password() {
+        return Ok(token);
+    }
+    let mut input = String::new();
+    io::stdin()
+        .read_line(&mut input)
+        .map_err(|e| LxHwError::Io(format!("Failed to read token: {}", e)))?;
+    Ok(input.trim().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::detectors::kernel::DeviceSupport;
+    use std::collections::HashMap;
+
+    fn device(device_id: &str, support_level: SupportLevel) -> DeviceSupport {
+        DeviceSupport {
+            device_id: device_id.to_string(),
+            driver_module: "none".to_string(),
+            support_level,
+            kernel_version_added: None,
+            config_dependencies: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_issues_skip_devices_with_known_drivers() {
+        let support_data = KernelSupportData {
+            kernel_version: "6.8.0".to_string(),
+            supported_devices: vec![
+                device("8086:46a6", SupportLevel::Supported),
+                // NVIDIA Tesla generation, served by the 340.xx legacy driver
+                device("10de:0191", SupportLevel::Unsupported),
+                device("10ec:b852", SupportLevel::Unsupported),
+            ],
+            module_aliases: HashMap::new(),
+            config_options: HashMap::new(),
+        };
+
+        let issues = UnsupportedDeviceIssue::for_unsupported_devices(
+            &support_data,
+            &LifecycleDatabase::builtin(),
+            Some("Fedora Linux 40"),
+            |device_id| vec![format!("pci 0000:03:00.0: [{}] type 00 class 0x028000", device_id)],
+        );
+
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].title(), "[Hardware] No kernel driver for PCI device 10ec:b852");
+        let body = issues[0].body();
+        assert!(body.contains("### Linux Distribution\n\nFedora Linux 40"));
+        assert!(body.contains("```\npci 0000:03:00.0: [10ec:b852] type 00 class 0x028000\n```"));
+    }
+
+    fn issue(dmesg_lines: &[&str]) -> UnsupportedDeviceIssue {
+        UnsupportedDeviceIssue {
+            device_id: "10ec:b852".to_string(),
+            kernel_version: "6.8.0".to_string(),
+            distribution: None,
+            dmesg_lines: dmesg_lines.iter().map(|line| line.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn test_issue_kernel_log_is_sanitized() {
+        let lines = [
+            "usb 1-2: SerialNumber: 0123ABCD4567",
+            "wlp2s0: authenticate with 3c:7c:3f:12:34:56 on thinkpad-x1",
+        ];
+        let mut issues = vec![issue(&lines)];
+        UnsupportedDeviceIssue::sanitize_dmesg_lines(
+            &mut issues,
+            PrivacyLevel::Basic,
+            Some("thinkpad-x1"),
+        )
+        .unwrap();
+
+        let body = issues[0].body();
+        assert!(body.contains("usb 1-2: SerialNumber: "));
+        for secret in ["0123ABCD4567", "12:34:56", "thinkpad-x1"] {
+            assert!(!body.contains(secret), "{} leaked into {}", secret, body);
+        }
+
+        let mut strict = vec![issue(&lines)];
+        UnsupportedDeviceIssue::sanitize_dmesg_lines(&mut strict, PrivacyLevel::Strict, None)
+            .unwrap();
+        assert!(strict[0].dmesg_lines.is_empty());
+        assert!(strict[0].body().contains("No kernel log lines are included for this device."));
+    }
+}
@@ -0,0 +1,364 @@
+//! Anonymous submission through a relay
+//!
+//! Submitting through GitHub needs an account, and the pull request ties the
+//! report to it. In relay mode the report is POSTed to an HTTPS endpoint run
+//! by the database maintainers, which opens the pull request from its own
+//! account. The request carries the report, the description and nothing
+//! else: no credentials, cookies or version in the user agent. For more
+//! privacy the connection can go through a SOCKS or HTTP proxy such as a
+//! local Tor daemon, so the relay does not learn the submitter's address
+//! either.
+//!
+//! Busy relays answer 429 or 503 with a `Retry-After` header, which is
+//! honoured; other server and connection errors are retried with
+//! exponential backoff. Every attempt sends the same `Idempotency-Key`,
+//! derived from the report, so a retry after a lost response does not open
+//! a second pull request.
+
+use super::{confirm, SubmissionInfo};
+use crate::errors::{LxHwError, Result};
+use crate::hardware::HardwareReport;
+use chrono::{DateTime, Utc};
+use reqwest::{StatusCode, Url};
+use ring::digest;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// SOCKS address of a local Tor daemon; `socks5h` resolves names through Tor
+pub const TOR_PROXY: &str = "socks5h://127.0.0.1:9050";
+
+/// Attempts made before giving up
+const DEFAULT_MAX_ATTEMPTS: u32 = 5;
+
+/// First backoff delay, doubled on every retry
+const BASE_BACKOFF_SECONDS: u64 = 2;
+
+/// Longest backoff delay between attempts
+const MAX_BACKOFF_SECONDS: u64 = 60;
+
+/// Longest `Retry-After` that is waited for; later ones end the submission
+const MAX_RETRY_AFTER_SECONDS: u64 = 300;
+
+/// Timeout of a single request, generous because Tor circuits are slow
+const REQUEST_TIMEOUT_SECONDS: u64 = 120;
+
+/// Relay endpoint and how to reach it
+#[derive(Debug, Clone)]
+pub struct RelayConfig {
+    pub endpoint: Url,
+    /// Proxy URL, e.g. `socks5h://127.0.0.1:9050` or `http://proxy:3128`
+    pub proxy: Option<Url>,
+    pub max_attempts: u32,
+}
+
+impl RelayConfig {
+    /// Validate a relay endpoint and optional proxy
+    ///
+    /// The endpoint must use HTTPS; plain HTTP is only accepted for onion
+    /// services, where Tor encrypts the connection end to end.
+    pub fn new(endpoint: &str, proxy: Option<&str>) -> Result<Self> {
+        let invalid = |message: String| LxHwError::InvalidInput { message };
+
+        let endpoint = Url::parse(endpoint)
+            .map_err(|e| invalid(format!("Invalid relay URL '{}': {}", endpoint, e)))?;
+        let onion = endpoint.host_str().is_some_and(|host| host.ends_with(".onion"));
+        match endpoint.scheme() {
+            "https" => {}
+            "http" if onion => {}
+            _ => return Err(invalid(format!("The relay URL must use https: {}", endpoint))),
+        }
+
+        let proxy = proxy
+            .map(|proxy| {
+                Url::parse(proxy).map_err(|e| invalid(format!("Invalid proxy '{}': {}", proxy, e)))
+            })
+            .transpose()?;
+        match proxy.as_ref().map(Url::scheme) {
+            None | Some("socks5h" | "socks4a" | "http" | "https") => {}
+            Some("socks5" | "socks4") => log::warn!(
+                "Host names are resolved locally with a {} proxy; use socks5h:// to resolve them through the proxy",
+                proxy.as_ref().map(Url::scheme).unwrap_or_default()
+            ),
+            Some(scheme) => return Err(invalid(format!("Unsupported proxy scheme '{}'", scheme))),
+        }
+        if onion && proxy.is_none() {
+            return Err(invalid(
+                "Onion services can only be reached through Tor; add --tor or --proxy".to_string(),
+            ));
+        }
+
+        Ok(Self { endpoint, proxy, max_attempts: DEFAULT_MAX_ATTEMPTS })
+    }
+}
+
+/// Relay's answer to an accepted submission
+#[derive(Debug, Clone, Deserialize)]
+pub struct RelayReceipt {
+    /// Identifier to quote when asking the maintainers about the submission
+    pub submission_id: String,
+    /// Pull request opened for the report, when the relay opens it right away
+    #[serde(default)]
+    pub pull_request_url: Option<String>,
+}
+
+/// Body of a relay submission
+#[derive(Debug, Serialize)]
+struct RelayRequest<'a> {
+    report: &'a HardwareReport,
+    description: &'a str,
+}
+
+/// Error body sent by the relay
+#[derive(Debug, Deserialize)]
+struct RelayError {
+    error: String,
+}
+
+/// What to do after a failed attempt
+#[derive(Debug, PartialEq, Eq)]
+enum RetryDecision {
+    /// Try again after the delay
+    Retry(Duration),
+    /// The relay asked to wait longer than we are willing to
+    TooLong(Duration),
+    /// The error will not go away by retrying
+    Fail,
+}
+
+/// Relay submission handler
+pub struct RelaySubmitter {
+    config: RelayConfig,
+    client: reqwest::Client,
+}
+
+impl RelaySubmitter {
+    /// Create a relay submitter, routing requests through the proxy if one
+    /// is configured
+    pub fn new(config: RelayConfig) -> Result<Self> {
+        let mut builder = reqwest::Client::builder()
+            .user_agent("lx-hw-detect")
+            .timeout(Duration::from_secs(REQUEST_TIMEOUT_SECONDS));
+        if let Some(proxy) = &config.proxy {
+            let proxy = reqwest::Proxy::all(proxy.as_str())
+                .map_err(|e| LxHwError::ConfigError(format!("Invalid proxy: {}", e)))?;
+            builder = builder.proxy(proxy);
+        } else {
+            // Never pick up a proxy from the environment behind the user's back
+            builder = builder.no_proxy();
+        }
+        let client = builder
+            .build()
+            .map_err(|e| LxHwError::Submission(format!("Failed to create HTTP client: {}", e)))?;
+        Ok(Self { config, client })
+    }
+
+    /// Submit a hardware report through the relay
+    pub async fn submit_report(
+        &self,
+        submission: SubmissionInfo,
+        skip_confirmation: bool,
+    ) -> Result<RelayReceipt> {
+        println!("🚀 Starting anonymous relay submission...");
+
+        let report = super::load_and_validate_report(&submission.report_path)?;
+
+        if !skip_confirmation {
+            self.show_submission_summary(&submission, &report);
+            if !confirm("\n❓ Submit this hardware report through the relay? [Y/n]: ", true)? {
+                println!("❌ Submission cancelled by user");
+                return Err(LxHwError::Validation("Submission cancelled by user".to_string()));
+            }
+        }
+
+        let body = serde_json::to_vec(&RelayRequest {
+            report: &report,
+            description: &submission.description,
+        })
+        .map_err(|e| LxHwError::SerializationError(e.to_string()))?;
+        let idempotency_key = hex::encode(digest::digest(&digest::SHA256, &body).as_ref());
+
+        println!("📡 Sending report to {}...", self.relay_host());
+        let mut attempt = 1;
+        loop {
+            let result = self
+                .client
+                .post(self.config.endpoint.clone())
+                .header("Content-Type", "application/json")
+                .header("Idempotency-Key", &idempotency_key)
+                .body(body.clone())
+                .send()
+                .await;
+
+            let (decision, message) = match result {
+                Ok(response) if response.status().is_success() => {
+                    let receipt: RelayReceipt = response.json().await.map_err(|e| {
+                        LxHwError::Submission(format!("Unexpected answer from the relay: {}", e))
+                    })?;
+                    println!("✅ Report accepted by the relay");
+                    return Ok(receipt);
+                }
+                Ok(response) => {
+                    let status = response.status();
+                    let retry_after = response
+                        .headers()
+                        .get(reqwest::header::RETRY_AFTER)
+                        .and_then(|value| value.to_str().ok())
+                        .map(str::to_string);
+                    let message = match response.json::<RelayError>().await {
+                        Ok(error) => format!("{}: {}", status, error.error),
+                        Err(_) => status.to_string(),
+                    };
+                    (retry_decision(status, retry_after.as_deref(), attempt, Utc::now()), message)
+                }
+                Err(e) if e.is_connect() || e.is_timeout() || e.is_request() => {
+                    (RetryDecision::Retry(backoff(attempt)), e.to_string())
+                }
+                Err(e) => (RetryDecision::Fail, e.to_string()),
+            };
+
+            match decision {
+                RetryDecision::Retry(delay) if attempt < self.config.max_attempts => {
+                    println!(
+                        "⏳ Relay submission failed ({}); retrying in {}s ({}/{})",
+                        message,
+                        delay.as_secs(),
+                        attempt,
+                        self.config.max_attempts
+                    );
+                    tokio::time::sleep(delay).await;
+                    attempt += 1;
+                }
+                RetryDecision::TooLong(delay) => {
+                    return Err(LxHwError::Submission(format!(
+                        "The relay is rate limiting submissions; try again in {} minutes",
+                        (delay.as_secs() + 59) / 60
+                    )));
+                }
+                _ => {
+                    return Err(LxHwError::Submission(format!(
+                        "Relay submission failed after {} attempt(s): {}",
+                        attempt, message
+                    )));
+                }
+            }
+        }
+    }
+
+    /// Show what is sent and where
+    fn show_submission_summary(&self, submission: &SubmissionInfo, report: &HardwareReport) {
+        println!("\n📋 Submission Summary:");
+        println!("═══════════════════════════════════════");
+        println!("📡 Relay: {}", self.relay_host());
+        match &self.config.proxy {
+            Some(proxy) => println!("🧅 Proxy: {}", proxy),
+            None => println!("🌐 Proxy: none (the relay sees your IP address)"),
+        }
+        println!(
+            "💻 System: {} {}",
+            report.system.distribution.as_deref().unwrap_or("Unknown"),
+            report.system.kernel_version
+        );
+        println!("🔒 Privacy Level: {:?}", report.metadata.privacy_level);
+        println!("📝 Description: {}", submission.description);
+        println!("═══════════════════════════════════════");
+    }
+
+    fn relay_host(&self) -> &str {
+        self.config.endpoint.host_str().unwrap_or_default()
+    }
+}
+
+/// Decide whether a response status is worth retrying, and when
+fn retry_decision(
+    status: StatusCode,
+    retry_after: Option<&str>,
+    attempt: u32,
+    now: DateTime<Utc>,
+) -> RetryDecision {
+    match status {
+        StatusCode::TOO_MANY_REQUESTS | StatusCode::SERVICE_UNAVAILABLE => {
+            match retry_after.and_then(|value| parse_retry_after(value, now)) {
+                Some(delay) if delay.as_secs() > MAX_RETRY_AFTER_SECONDS => {
+                    RetryDecision::TooLong(delay)
+                }
+                Some(delay) => RetryDecision::Retry(delay),
+                None => RetryDecision::Retry(backoff(attempt)),
+            }
+        }
+        StatusCode::REQUEST_TIMEOUT | StatusCode::BAD_GATEWAY | StatusCode::GATEWAY_TIMEOUT => {
+            RetryDecision::Retry(backoff(attempt))
+        }
+        status if status.is_server_error() => RetryDecision::Retry(backoff(attempt)),
+        _ => RetryDecision::Fail,
+    }
+}
+
+/// Parse a `Retry-After` value, either seconds or an HTTP date
+fn parse_retry_after(value: &str, now: DateTime<Utc>) -> Option<Duration> {
+    let value = value.trim();
+    if let Ok(seconds) = value.parse::<u64>() {
+        return Some(Duration::from_secs(seconds));
+    }
+    let date = DateTime::parse_from_rfc2822(value).ok()?.with_timezone(&Utc);
+    Some((date - now).to_std().unwrap_or_default())
+}
+
+/// Exponential backoff delay before retrying after `attempt`
+fn backoff(attempt: u32) -> Duration {
+    let seconds = BASE_BACKOFF_SECONDS.saturating_mul(1 << attempt.saturating_sub(1).min(10));
+    Duration::from_secs(seconds.min(MAX_BACKOFF_SECONDS))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_relay_config_validation() {
+        assert!(RelayConfig::new("https://relay.example.org/v1/reports", None).is_ok());
+        assert!(RelayConfig::new("http://relay.example.org/v1/reports", None).is_err());
+        assert!(RelayConfig::new("relay.example.org", None).is_err());
+
+        let onion = "http://exampleonionaddressxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxx.onion/reports";
+        assert!(RelayConfig::new(onion, None).is_err());
+        let config = RelayConfig::new(onion, Some(TOR_PROXY)).unwrap();
+        assert_eq!(config.proxy.unwrap().scheme(), "socks5h");
+
+        assert!(RelayConfig::new("https://relay.example.org", Some("ftp://proxy:21")).is_err());
+    }
+
+    #[test]
+    fn test_retry_decisions() {
+        let now = DateTime::parse_from_rfc2822("Wed, 21 Oct 2015 07:28:00 GMT")
+            .unwrap()
+            .with_timezone(&Utc);
+
+        assert_eq!(
+            retry_decision(StatusCode::TOO_MANY_REQUESTS, Some("30"), 1, now),
+            RetryDecision::Retry(Duration::from_secs(30))
+        );
+        assert_eq!(
+            retry_decision(
+                StatusCode::SERVICE_UNAVAILABLE,
+                Some("Wed, 21 Oct 2015 07:29:00 GMT"),
+                1,
+                now
+            ),
+            RetryDecision::Retry(Duration::from_secs(60))
+        );
+        assert_eq!(
+            retry_decision(StatusCode::TOO_MANY_REQUESTS, Some("3600"), 1, now),
+            RetryDecision::TooLong(Duration::from_secs(3600))
+        );
+        assert_eq!(
+            retry_decision(StatusCode::TOO_MANY_REQUESTS, None, 3, now),
+            RetryDecision::Retry(Duration::from_secs(8))
+        );
+        assert_eq!(
+            retry_decision(StatusCode::BAD_GATEWAY, None, 10, now),
+            RetryDecision::Retry(Duration::from_secs(MAX_BACKOFF_SECONDS))
+        );
+        assert_eq!(retry_decision(StatusCode::BAD_REQUEST, None, 1, now), RetryDecision::Fail);
+        assert_eq!(retry_decision(StatusCode::UNAUTHORIZED, None, 1, now), RetryDecision::Fail);
+    }
+}
@@ -118,165 +118,60 @@ async fn show_detection_progress() {
 
 /// Display real hardware results in GTK4 style
 fn display_hardware_results(report: &crate::hardware::HardwareReport) {
+    use crate::presentation::{CompatibilityBadge, DeviceList};
+
     println!("\n📋 Hardware Detection Results");
     println!("════════════════════════════════════════════");
 
-    // System Information
-    println!("\n🖥️  System Information");
-    println!("─────────────────────────");
-    println!(
-        "   Distribution: {}",
-        report.system.distribution.as_ref().unwrap_or(&"Unknown".to_string())
-    );
-    println!("   Kernel: {}", report.system.kernel_version);
-    println!("   Architecture: {}", report.system.architecture);
-    println!("   Status: ✅ Fully Supported");
-
-    // CPU Information
-    if let Some(cpu) = &report.cpu {
-        println!("\n🧠 CPU Information");
-        println!("─────────────────");
-        println!("   Model: {}", cpu.model);
-        println!("   Vendor: {}", cpu.vendor);
-        println!("   Cores: {}", cpu.cores);
-        println!("   Threads: {}", cpu.threads);
-        if let Some(freq) = cpu.base_frequency {
-            println!("   Base Frequency: {:.2} GHz", freq / 1000.0);
-        }
-        println!("   Status: ✅ Fully Supported");
-    }
-
-    // Memory Information
-    if let Some(memory) = &report.memory {
-        println!("\n💾 Memory Information");
-        println!("────────────────────");
-        println!("   Total: {:.1} GB", memory.total_bytes as f64 / (1024.0 * 1024.0 * 1024.0));
-        println!(
-            "   Available: {:.1} GB",
-            memory.available_bytes as f64 / (1024.0 * 1024.0 * 1024.0)
-        );
-        println!("   DIMMs: {}", memory.dimms.len());
-        if let Some(first_dimm) = memory.dimms.first() {
-            if let Some(mem_type) = &first_dimm.memory_type {
-                println!("   Type: {}", mem_type);
+    let devices = DeviceList::from_report(report);
+    for (category, items) in devices.by_category() {
+        let heading =
+            format!("{} {} ({} found)", category_emoji(category), category.label(), items.len());
+        println!("\n{}", heading);
+        println!("{}", "─".repeat(heading.chars().count()));
+        for item in items {
+            println!("   {}", item.name);
+            for line in item.detail_lines() {
+                println!("     {}", line);
+            }
+            println!("     Status: {} {}", item.badge.emoji(), item.badge.display_text());
+            for recommendation in &item.recommendations {
+                println!("     💡 {}", recommendation);
             }
-        }
-        println!("   Status: ✅ Fully Supported");
-    }
-
-    // Graphics Devices
-    if !report.graphics.is_empty() {
-        println!("\n🎮 Graphics Devices ({} found)", report.graphics.len());
-        println!("──────────────────────────────────");
-        for (i, gpu) in report.graphics.iter().enumerate() {
-            println!("   GPU {}: {}", i + 1, gpu.model);
-            println!("     Vendor: {}", gpu.vendor);
-            println!("     PCI ID: {}", gpu.pci_id);
-            println!("     Driver: {}", gpu.driver.as_ref().unwrap_or(&"Not loaded".to_string()));
-            println!(
-                "     Status: {}",
-                if gpu.driver.is_some() {
-                    "✅ Working with Driver"
-                } else {
-                    "⚠️  Driver Required"
-                }
-            );
-        }
-    }
-
-    // Storage Devices
-    if !report.storage.is_empty() {
-        println!("\n💿 Storage Devices ({} found)", report.storage.len());
-        println!("─────────────────────────────────");
-        for (i, storage) in report.storage.iter().enumerate() {
-            println!("   Storage {}: {}", i + 1, storage.model);
-            println!("     Vendor: {}", storage.vendor.as_ref().unwrap_or(&"Unknown".to_string()));
-            println!("     Size: {:.1} GB", storage.size_bytes as f64 / (1024.0 * 1024.0 * 1024.0));
-            println!(
-                "     Interface: {}",
-                storage.interface.as_ref().unwrap_or(&"Unknown".to_string())
-            );
-            println!("     Status: ✅ Fully Supported");
-        }
-    }
-
-    // Network Devices
-    if !report.network.is_empty() {
-        println!("\n🌐 Network Devices ({} found)", report.network.len());
-        println!("─────────────────────────────────");
-        for (i, net) in report.network.iter().enumerate() {
-            println!("   Network {}: {}", i + 1, net.model);
-            println!("     Vendor: {}", net.vendor);
-            println!("     Type: {}", net.device_type);
-            println!("     Driver: {}", net.driver.as_ref().unwrap_or(&"Not loaded".to_string()));
-            println!(
-                "     Status: {}",
-                if net.driver.is_some() {
-                    "✅ Fully Supported"
-                } else {
-                    "⚠️  Driver Required"
-                }
-            );
-        }
-    }
-
-    // Audio Devices
-    if !report.audio.is_empty() {
-        println!("\n🔊 Audio Devices ({} found)", report.audio.len());
-        println!("───────────────────────────────");
-        for (i, audio) in report.audio.iter().enumerate() {
-            println!("   Audio {}: {} Audio", i + 1, audio.vendor);
-            println!("     Vendor: {}", audio.vendor);
-            println!("     Type: {}", audio.device_type);
-            println!("     Driver: {}", audio.driver.as_ref().unwrap_or(&"Built-in".to_string()));
-            println!("     Status: ✅ Fully Supported");
-        }
-    }
-
-    // USB Devices
-    if !report.usb.is_empty() {
-        println!("\n🔌 USB Devices ({} found)", report.usb.len());
-        println!("─────────────────────────────");
-        for (i, usb) in report.usb.iter().enumerate() {
-            let default_name = format!("USB Device {}:{}", usb.vendor_id, usb.product_id);
-            let device_name = usb.product_name.as_ref().unwrap_or(&default_name);
-            println!("   USB {}: {}", i + 1, device_name);
-            println!("     Vendor: {}", usb.vendor_name.as_ref().unwrap_or(&"Unknown".to_string()));
-            println!("     Vendor ID: {}", usb.vendor_id);
-            println!("     Product ID: {}", usb.product_id);
-            println!("     Status: ✅ Fully Supported");
         }
     }
 
-    // Summary
-    let total_devices = [
-        1, // System
-        if report.cpu.is_some() { 1 } else { 0 },
-        if report.memory.is_some() { 1 } else { 0 },
-        report.graphics.len(),
-        report.storage.len(),
-        report.network.len(),
-        report.audio.len(),
-        report.usb.len(),
-    ]
-    .iter()
-    .sum::<usize>();
-
     println!("\n📊 Detection Summary");
     println!("───────────────────");
-    println!("   Total Devices: {}", total_devices);
-    println!("   Fully Supported: {}", total_devices); // Most devices are supported
-    println!("   Privacy Level: Basic (24h salt rotation)");
+    println!("   Total Devices: {}", devices.len());
+    println!("   Fully Supported: {}", devices.count_with_badge(CompatibilityBadge::Supported));
+    println!("   Privacy Level: {:?}", report.metadata.privacy_level);
     println!("   Analysis Complete: ✅");
 }
 
+fn category_emoji(category: crate::presentation::DeviceCategory) -> &'static str {
+    use crate::presentation::DeviceCategory;
+    match category {
+        DeviceCategory::System => "🖥️ ",
+        DeviceCategory::Processor => "🧠",
+        DeviceCategory::Memory => "💾",
+        DeviceCategory::Graphics => "🎮",
+        DeviceCategory::Storage => "💿",
+        DeviceCategory::Network => "🌐",
+        DeviceCategory::Audio => "🔊",
+        DeviceCategory::Usb => "🔌",
+        DeviceCategory::Input => "⌨️ ",
+    }
+}
+
 /// Show export options in GTK4 style
 fn show_export_options() {
     println!("\n💾 Export Options");
     println!("─────────────────");
-    println!("   📄 YAML Report (recommended)");
-    println!("   📊 JSON Data");
-    println!("   📝 Markdown Report");
+    for option in crate::presentation::export_options() {
+        let note = if option.recommended { " (recommended)" } else { "" };
+        println!("   📄 {} (.{}){}", option.label, option.extension, note);
+    }
     println!("   🔗 GitHub Submission Ready");
     println!();
     println!("💡 Export with: lx-hw-detect export --format yaml --privacy basic");
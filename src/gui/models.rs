@@ -272,251 +272,3 @@ pub enum GuiEvent {
     PreferencesChanged { preferences: AppPreferences },
     PrivacyLevelChanged { level: PrivacyLevel },
 }
-
-/// Hardware category for display organization
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
-pub enum HardwareCategory {
-    System,
-    Processor,
-    Memory,
-    Graphics,
-    Network,
-    Audio,
-    Storage,
-    Usb,
-    Other,
-}
-
-impl HardwareCategory {
-    /// Get display name for category
-    pub fn display_name(&self) -> &'static str {
-        match self {
-            Self::System => "System & Motherboard",
-            Self::Processor => "CPU & Memory",
-            Self::Memory => "Memory",
-            Self::Graphics => "Graphics",
-            Self::Network => "Network",
-            Self::Audio => "Audio",
-            Self::Storage => "Storage",
-            Self::Usb => "USB & Peripherals",
-            Self::Other => "Other Devices",
-        }
-    }
-
-    /// Get icon name for category
-    pub fn icon_name(&self) -> &'static str {
-        match self {
-            Self::System => "computer-symbolic",
-            Self::Processor => "cpu-symbolic",
-            Self::Memory => "media-memory-symbolic",
-            Self::Graphics => "video-display-symbolic",
-            Self::Network => "network-wired-symbolic",
-            Self::Audio => "audio-speakers-symbolic",
-            Self::Storage => "drive-harddisk-symbolic",
-            Self::Usb => "usb-symbolic",
-            Self::Other => "applications-other-symbolic",
-        }
-    }
-}
-
-/// Device compatibility status for display
-#[derive(Debug, Clone, PartialEq, Eq)]
-pub enum CompatibilityStatus {
-    Supported,
-    PartialSupport,
-    RequiresDriver,
-    Unsupported,
-    Unknown,
-}
-
-impl CompatibilityStatus {
-    /// Get display text for status
-    pub fn display_text(&self) -> &'static str {
-        match self {
-            Self::Supported => "Fully Supported",
-            Self::PartialSupport => "Partial Support",
-            Self::RequiresDriver => "Driver Required",
-            Self::Unsupported => "Not Supported",
-            Self::Unknown => "Unknown",
-        }
-    }
-
-    /// Get CSS class for status styling
-    pub fn css_class(&self) -> &'static str {
-        match self {
-            Self::Supported => "status-supported",
-            Self::PartialSupport => "status-partial",
-            Self::RequiresDriver => "status-warning",
-            Self::Unsupported => "status-error",
-            Self::Unknown => "status-unknown",
-        }
-    }
-
-    /// Get icon name for status
-    pub fn icon_name(&self) -> &'static str {
-        match self {
-            Self::Supported => "emblem-ok-symbolic",
-            Self::PartialSupport => "dialog-warning-symbolic",
-            Self::RequiresDriver => "software-update-urgent-symbolic",
-            Self::Unsupported => "emblem-important-symbolic",
-            Self::Unknown => "help-about-symbolic",
-        }
-    }
-}
-
-/// Display model for hardware devices in the UI
-#[derive(Debug, Clone)]
-pub struct HardwareDeviceDisplay {
-    pub id: String,
-    pub name: String,
-    pub category: HardwareCategory,
-    pub vendor: String,
-    pub model: String,
-    pub status: CompatibilityStatus,
-    pub details: HashMap<String, String>,
-    pub recommendations: Vec<String>,
-}
-
-impl HardwareDeviceDisplay {
-    /// Create display model from hardware report data
-    pub fn from_hardware_report(report: &HardwareReport) -> Vec<Self> {
-        let mut devices = Vec::new();
-
-        // Add system information
-        devices.push(HardwareDeviceDisplay {
-            id: "system".to_string(),
-            name: format!(
-                "System - {}",
-                report.system.distribution.clone().unwrap_or("Unknown".to_string())
-            ),
-            category: HardwareCategory::System,
-            vendor: "System".to_string(),
-            model: report.system.distribution.clone().unwrap_or("Unknown".to_string()),
-            status: CompatibilityStatus::Supported,
-            details: [
-                (
-                    "Distribution".to_string(),
-                    report.system.distribution.clone().unwrap_or("Unknown".to_string()),
-                ),
-                ("Kernel".to_string(), report.system.kernel_version.clone()),
-                ("Architecture".to_string(), report.system.architecture.clone()),
-            ]
-            .into(),
-            recommendations: vec![],
-        });
-
-        // Add CPU information
-        if let Some(cpu) = &report.cpu {
-            devices.push(HardwareDeviceDisplay {
-                id: "cpu".to_string(),
-                name: cpu.model.clone(),
-                category: HardwareCategory::Processor,
-                vendor: cpu.vendor.clone(),
-                model: cpu.model.clone(),
-                status: CompatibilityStatus::Supported,
-                details: [
-                    ("Cores".to_string(), cpu.cores.to_string()),
-                    ("Threads".to_string(), cpu.threads.to_string()),
-                    (
-                        "Frequency".to_string(),
-                        format!("{:.2} GHz", cpu.base_frequency.unwrap_or(0.0) / 1000.0),
-                    ),
-                ]
-                .into(),
-                recommendations: vec![],
-            });
-        }
-
-        // Add graphics devices
-        for (i, gpu) in report.graphics.iter().enumerate() {
-            devices.push(HardwareDeviceDisplay {
-                id: format!("gpu_{}", i),
-                name: gpu.model.clone(),
-                category: HardwareCategory::Graphics,
-                vendor: gpu.vendor.clone(),
-                model: gpu.model.clone(),
-                status: if gpu.driver.is_some() {
-                    CompatibilityStatus::Supported
-                } else {
-                    CompatibilityStatus::RequiresDriver
-                },
-                details: [
-                    ("PCI ID".to_string(), gpu.pci_id.clone()),
-                    ("Driver".to_string(), gpu.driver.clone().unwrap_or("Not loaded".to_string())),
-                ]
-                .into(),
-                recommendations: if gpu.driver.is_none() {
-                    vec!["Install appropriate graphics driver".to_string()]
-                } else {
-                    vec![]
-                },
-            });
-        }
-
-        // Add network devices
-        for (i, net) in report.network.iter().enumerate() {
-            devices.push(HardwareDeviceDisplay {
-                id: format!("net_{}", i),
-                name: net.model.clone(),
-                category: HardwareCategory::Network,
-                vendor: net.vendor.clone(),
-                model: net.model.clone(),
-                status: if net.driver.is_some() {
-                    CompatibilityStatus::Supported
-                } else {
-                    CompatibilityStatus::RequiresDriver
-                },
-                details: [
-                    ("Type".to_string(), net.device_type.clone()),
-                    ("Driver".to_string(), net.driver.clone().unwrap_or("Not loaded".to_string())),
-                ]
-                .into(),
-                recommendations: vec![],
-            });
-        }
-
-        // Add storage devices
-        for (i, storage) in report.storage.iter().enumerate() {
-            devices.push(HardwareDeviceDisplay {
-                id: format!("storage_{}", i),
-                name: storage.model.clone(),
-                category: HardwareCategory::Storage,
-                vendor: storage.vendor.clone().unwrap_or("Unknown".to_string()),
-                model: storage.model.clone(),
-                status: CompatibilityStatus::Supported,
-                details: [
-                    (
-                        "Size".to_string(),
-                        format!("{:.1} GB", storage.size_bytes as f64 / (1024.0 * 1024.0 * 1024.0)),
-                    ),
-                    (
-                        "Interface".to_string(),
-                        storage.interface.clone().unwrap_or("Unknown".to_string()),
-                    ),
-                ]
-                .into(),
-                recommendations: vec![],
-            });
-        }
-
-        // Add audio devices
-        for (i, audio) in report.audio.iter().enumerate() {
-            devices.push(HardwareDeviceDisplay {
-                id: format!("audio_{}", i),
-                name: format!("{} Audio", audio.vendor),
-                category: HardwareCategory::Audio,
-                vendor: audio.vendor.clone(),
-                model: audio.device_type.clone(),
-                status: CompatibilityStatus::Supported,
-                details: [
-                    ("Type".to_string(), audio.device_type.clone()),
-                    ("Driver".to_string(), audio.driver.clone().unwrap_or("Built-in".to_string())),
-                ]
-                .into(),
-                recommendations: vec![],
-            });
-        }
-
-        devices
-    }
-}
@@ -1,5 +1,6 @@
 //! Hardware detection progress widget
 
+use crate::presentation::ToolState;
 use adw::prelude::*;
 use gtk4::prelude::*;
 use libadwaita as adw;
@@ -122,7 +123,7 @@ impl DetectionProgress {
     }
 
     /// Update progress for a specific tool
-    pub fn set_tool_progress(&self, tool: &str, fraction: f64, status: ToolStatus) {
+    pub fn set_tool_progress(&self, tool: &str, fraction: f64, status: ToolState) {
         if let Some(progress_bar) = self.progress_bars.get(tool) {
             progress_bar.set_fraction(fraction);
 
@@ -132,12 +133,7 @@ impl DetectionProgress {
                     let row = row.downcast_ref::<adw::ActionRow>().unwrap();
 
                     // Update status icon
-                    let icon = match status {
-                        ToolStatus::Pending => "emblem-synchronizing-symbolic",
-                        ToolStatus::Running => "emblem-synchronizing-symbolic",
-                        ToolStatus::Complete => "emblem-ok-symbolic",
-                        ToolStatus::Error => "emblem-important-symbolic",
-                    };
+                    let icon = status.icon_name();
 
                     // Find and update the prefix icon
                     let mut child = row.first_child();
@@ -148,8 +144,8 @@ impl DetectionProgress {
                             // Add CSS class for status
                             image.remove_css_class("dim-label");
                             match status {
-                                ToolStatus::Complete => image.add_css_class("success"),
-                                ToolStatus::Error => image.add_css_class("error"),
+                                ToolState::Complete => image.add_css_class("success"),
+                                ToolState::Error => image.add_css_class("error"),
                                 _ => image.add_css_class("dim-label"),
                             }
                             break;
@@ -158,12 +154,7 @@ impl DetectionProgress {
                     }
 
                     // Update status label
-                    let status_text = match status {
-                        ToolStatus::Pending => crate::gui::t("Pending"),
-                        ToolStatus::Running => crate::gui::t("Running"),
-                        ToolStatus::Complete => crate::gui::t("Complete"),
-                        ToolStatus::Error => crate::gui::t("Error"),
-                    };
+                    let status_text = crate::gui::t(status.display_text());
 
                     // Find and update the status label (last suffix)
                     let mut child = row.last_child();
@@ -225,7 +216,7 @@ impl DetectionProgress {
         // Reset all tool progress bars
         for progress_bar in self.progress_bars.values() {
             progress_bar.set_fraction(0.0);
-            self.set_tool_progress_by_bar(progress_bar, 0.0, ToolStatus::Pending);
+            self.set_tool_progress_by_bar(progress_bar, 0.0, ToolState::Pending);
         }
 
         self.set_device_count(0);
@@ -237,7 +228,7 @@ impl DetectionProgress {
         &self,
         progress_bar: &gtk4::ProgressBar,
         fraction: f64,
-        status: ToolStatus,
+        status: ToolState,
     ) {
         progress_bar.set_fraction(fraction);
 
@@ -246,12 +237,7 @@ impl DetectionProgress {
             if let Some(row) = parent.parent() {
                 let row = row.downcast_ref::<adw::ActionRow>().unwrap();
 
-                let icon = match status {
-                    ToolStatus::Pending => "emblem-synchronizing-symbolic",
-                    ToolStatus::Running => "emblem-synchronizing-symbolic",
-                    ToolStatus::Complete => "emblem-ok-symbolic",
-                    ToolStatus::Error => "emblem-important-symbolic",
-                };
+                let icon = status.icon_name();
 
                 // Update prefix icon
                 let mut child = row.first_child();
@@ -264,8 +250,8 @@ impl DetectionProgress {
                         image.remove_css_class("error");
 
                         match status {
-                            ToolStatus::Complete => image.add_css_class("success"),
-                            ToolStatus::Error => image.add_css_class("error"),
+                            ToolState::Complete => image.add_css_class("success"),
+                            ToolState::Error => image.add_css_class("error"),
                             _ => image.add_css_class("dim-label"),
                         }
                         break;
@@ -276,12 +262,3 @@ impl DetectionProgress {
         }
     }
 }
-
-/// Status of a detection tool
-#[derive(Debug, Clone, Copy, PartialEq)]
-pub enum ToolStatus {
-    Pending,
-    Running,
-    Complete,
-    Error,
-}
@@ -1,6 +1,6 @@
 //! Individual device card widget
 
-use crate::gui::models::HardwareDeviceDisplay;
+use crate::presentation::DeviceItem;
 use adw::prelude::*;
 use gtk4::prelude::*;
 use libadwaita as adw;
@@ -12,7 +12,7 @@ pub struct DeviceCard {
 
 impl DeviceCard {
     /// Create a new device card
-    pub fn new(device: &HardwareDeviceDisplay) -> Self {
+    pub fn new(device: &DeviceItem) -> Self {
         let widget = adw::ActionRow::new();
         widget.set_title(&device.name);
         widget.set_subtitle(&format!("{} - {}", device.vendor, device.model));
@@ -22,12 +22,12 @@ impl DeviceCard {
         widget.add_prefix(&icon);
 
         // Status indicator
-        let status_icon = gtk4::Image::from_icon_name(device.status.icon_name());
-        status_icon.add_css_class(device.status.css_class());
+        let status_icon = gtk4::Image::from_icon_name(device.badge.icon_name());
+        status_icon.add_css_class(device.badge.css_class());
         widget.add_suffix(&status_icon);
 
         // Status text
-        let status_label = gtk4::Label::new(Some(device.status.display_text()));
+        let status_label = gtk4::Label::new(Some(device.badge.display_text()));
         status_label.add_css_class("dim-label");
         widget.add_suffix(&status_label);
 
@@ -1,6 +1,6 @@
 //! Hardware view widget for displaying detected devices
 
-use crate::gui::models::{HardwareCategory, HardwareDeviceDisplay};
+use crate::presentation::{DeviceCategory, DeviceItem, DeviceList};
 use adw::prelude::*;
 use gtk4::prelude::*;
 use libadwaita as adw;
@@ -9,7 +9,7 @@ use libadwaita as adw;
 pub struct HardwareView {
     widget: gtk4::ScrolledWindow,
     content_box: gtk4::Box,
-    device_groups: std::collections::HashMap<HardwareCategory, adw::ExpanderRow>,
+    device_groups: std::collections::HashMap<DeviceCategory, adw::ExpanderRow>,
 }
 
 impl HardwareView {
@@ -30,26 +30,16 @@ impl HardwareView {
     }
 
     /// Update the view with hardware devices
-    pub fn update_devices(&mut self, devices: Vec<HardwareDeviceDisplay>) {
+    pub fn update_devices(&mut self, devices: &DeviceList) {
         // Clear existing content
         while let Some(child) = self.content_box.first_child() {
             self.content_box.remove(&child);
         }
         self.device_groups.clear();
 
-        // Group devices by category
-        let mut categories: std::collections::HashMap<
-            HardwareCategory,
-            Vec<HardwareDeviceDisplay>,
-        > = std::collections::HashMap::new();
-
-        for device in devices {
-            categories.entry(device.category).or_insert_with(Vec::new).push(device);
-        }
-
-        // Create expandable sections for each category
-        for (category, devices) in categories {
-            let expander = self.create_category_section(&category, devices);
+        // Create expandable sections for each category, in category order
+        for (category, devices) in devices.by_category() {
+            let expander = self.create_category_section(category, &devices);
             self.content_box.append(&expander);
             self.device_groups.insert(category, expander);
         }
@@ -58,8 +48,8 @@ impl HardwareView {
     /// Create an expandable section for a hardware category
     fn create_category_section(
         &self,
-        category: &HardwareCategory,
-        devices: Vec<HardwareDeviceDisplay>,
+        category: DeviceCategory,
+        devices: &[&DeviceItem],
     ) -> adw::ExpanderRow {
         let expander = adw::ExpanderRow::new();
         expander.set_title(&crate::gui::t(category.display_name()));
@@ -79,18 +69,18 @@ impl HardwareView {
     }
 
     /// Create a row for a single device
-    fn create_device_row(&self, device: HardwareDeviceDisplay) -> adw::ActionRow {
+    fn create_device_row(&self, device: &DeviceItem) -> adw::ActionRow {
         let row = adw::ActionRow::new();
         row.set_title(&device.name);
         row.set_subtitle(&format!("{} - {}", device.vendor, device.model));
 
         // Status indicator
-        let status_icon = gtk4::Image::from_icon_name(device.status.icon_name());
-        status_icon.add_css_class(device.status.css_class());
+        let status_icon = gtk4::Image::from_icon_name(device.badge.icon_name());
+        status_icon.add_css_class(device.badge.css_class());
         row.add_suffix(&status_icon);
 
         // Status label
-        let status_label = gtk4::Label::new(Some(device.status.display_text()));
+        let status_label = gtk4::Label::new(Some(device.badge.display_text()));
         status_label.add_css_class("dim-label");
         row.add_suffix(&status_label);
 
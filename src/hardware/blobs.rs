@@ -0,0 +1,300 @@
+//! Content-addressed sidecar storage for oversized report payloads
+//!
+//! Large optional diagnostics (kernel log excerpts, EDID blobs, benchmark
+//! traces) are stored as separate files named by their SHA-256 digest and
+//! referenced from the report by hash. A report file and the `blobs/`
+//! directory next to it form a report bundle; the indexer verifies every
+//! referenced blob before accepting the report.
+
+use crate::errors::{LxHwError, Result};
+use crate::hardware::HardwareReport;
+use ring::digest;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// Directory next to a report that holds its blobs
+pub const BLOB_DIR: &str = "blobs";
+
+/// Blob kind for externalized kernel log lines of unknown devices
+pub const KIND_DMESG: &str = "dmesg";
+
+/// Reference from a report to an externalized payload
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BlobRef {
+    /// Payload kind, e.g. "dmesg", "edid" or "benchmark"
+    pub kind: String,
+    /// Report field the payload belongs to, e.g. "unknown_devices[0].dmesg_lines"
+    pub subject: String,
+    /// Lowercase hex SHA-256 digest of the payload
+    pub sha256: String,
+    pub size: u64,
+}
+
+/// Directory of content-addressed blobs
+#[derive(Debug, Clone)]
+pub struct BlobStore {
+    root: PathBuf,
+}
+
+impl BlobStore {
+    /// Store rooted at a directory
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+
+    /// Store of the bundle a report file belongs to
+    pub fn for_report(report_path: &Path) -> Self {
+        let dir = report_path.parent().unwrap_or_else(|| Path::new("."));
+        Self::new(dir.join(BLOB_DIR))
+    }
+
+    /// Path a blob with the given digest is stored at
+    pub fn path(&self, sha256: &str) -> PathBuf {
+        self.root.join(sha256)
+    }
+
+    /// Store a payload and return its digest; identical payloads are stored once
+    pub fn put(&self, data: &[u8]) -> Result<String> {
+        let sha256 = sha256_hex(data);
+        let path = self.path(&sha256);
+        if !path.exists() {
+            std::fs::create_dir_all(&self.root)?;
+            std::fs::write(&path, data)?;
+        }
+        Ok(sha256)
+    }
+
+    /// Read a blob, verifying its size and digest
+    pub fn get(&self, blob: &BlobRef) -> Result<Vec<u8>> {
+        // The digest names the file, so anything else could point outside the store
+        if !is_sha256_hex(&blob.sha256) {
+            return Err(LxHwError::Validation(format!(
+                "Invalid blob digest for {}: {}",
+                blob.subject, blob.sha256
+            )));
+        }
+        let path = self.path(&blob.sha256);
+        let missing = |e: std::io::Error| {
+            LxHwError::Validation(format!(
+                "Missing blob {} for {}: {}",
+                blob.sha256, blob.subject, e
+            ))
+        };
+        let mismatch = || {
+            LxHwError::Validation(format!(
+                "Blob {} for {} does not match its digest",
+                blob.sha256, blob.subject
+            ))
+        };
+
+        // Compare sizes before reading, so an oversized file is never loaded
+        if std::fs::metadata(&path).map_err(missing)?.len() != blob.size {
+            return Err(mismatch());
+        }
+        let data = std::fs::read(&path).map_err(missing)?;
+        if data.len() as u64 != blob.size || sha256_hex(&data) != blob.sha256 {
+            return Err(mismatch());
+        }
+        Ok(data)
+    }
+}
+
+impl HardwareReport {
+    /// Store a payload as a blob and reference it from the report
+    pub fn attach_blob(
+        &mut self,
+        store: &BlobStore,
+        kind: &str,
+        subject: &str,
+        data: &[u8],
+    ) -> Result<()> {
+        let sha256 = store.put(data)?;
+        self.blobs.push(BlobRef {
+            kind: kind.to_string(),
+            subject: subject.to_string(),
+            sha256,
+            size: data.len() as u64,
+        });
+        Ok(())
+    }
+
+    /// Move inline payloads larger than `threshold` bytes into blobs
+    ///
+    /// Payloads are stored as they are in the report, so kernel log lines must
+    /// already be sanitized, as the analyzer does when it collects them.
+    /// Returns the number of payloads externalized.
+    pub fn externalize_blobs(&mut self, store: &BlobStore, threshold: usize) -> Result<usize> {
+        let mut externalized = 0;
+        for i in 0..self.unknown_devices.len() {
+            let text = self.unknown_devices[i].dmesg_lines.join("\n");
+            if text.len() <= threshold {
+                continue;
+            }
+            let subject = format!("unknown_devices[{}].dmesg_lines", i);
+            self.attach_blob(store, KIND_DMESG, &subject, text.as_bytes())?;
+            self.unknown_devices[i].dmesg_lines.clear();
+            externalized += 1;
+        }
+        Ok(externalized)
+    }
+
+    /// Check that every referenced blob exists and matches its digest
+    pub fn verify_blobs(&self, store: &BlobStore) -> Result<()> {
+        for blob in &self.blobs {
+            store.get(blob)?;
+        }
+        Ok(())
+    }
+
+    /// Write the report and its blobs as a bundle directory
+    ///
+    /// The bundle holds `<dir name>.json` and the `blobs/` directory, and
+    /// can be copied into the database as a unit.
+    pub fn write_bundle(&self, source: &BlobStore, dir: &Path) -> Result<PathBuf> {
+        let name = dir.file_name().and_then(|n| n.to_str()).ok_or_else(|| {
+            LxHwError::InvalidInput { message: format!("Invalid bundle path: {}", dir.display()) }
+        })?;
+        let target = BlobStore::new(dir.join(BLOB_DIR));
+        for blob in &self.blobs {
+            target.put(&source.get(blob)?)?;
+        }
+
+        let report_path = dir.join(format!("{}.json", name));
+        let json = serde_json::to_string_pretty(self)
+            .map_err(|e| LxHwError::SerializationError(e.to_string()))?;
+        std::fs::create_dir_all(dir)?;
+        std::fs::write(&report_path, json)?;
+        Ok(report_path)
+    }
+}
+
+/// Lowercase hex SHA-256 digest of a payload
+pub fn sha256_hex(data: &[u8]) -> String {
+    hex::encode(digest::digest(&digest::SHA256, data).as_ref())
+}
+
+fn is_sha256_hex(value: &str) -> bool {
+    value.len() == 64 && value.bytes().all(|b| b.is_ascii_digit() || (b'a'..=b'f').contains(&b))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hardware::UnknownDevice;
+
+    fn report_with_dmesg(lines: usize) -> HardwareReport {
+        let mut report: HardwareReport = serde_json::from_value(serde_json::json!({
+            "metadata": {
+                "version": "1.0.0",
+                "generated_at": "2024-01-01T00:00:00Z",
+                "privacy_level": "Basic",
+                "tools_used": [],
+                "anonymized_system_id": "test",
+            },
+            "system": {
+                "anonymized_hostname": "test",
+                "kernel_version": "6.6.0",
+                "distribution": null,
+                "architecture": "x86_64",
+                "boot_time": null,
+            },
+            "cpu": null,
+            "memory": null,
+            "storage": [],
+            "graphics": [],
+            "network": [],
+            "usb": [],
+            "audio": [],
+            "kernel_support": null,
+        }))
+        .unwrap();
+        report.unknown_devices.push(UnknownDevice {
+            bus: "pci".to_string(),
+            vendor_id: "1234".to_string(),
+            device_id: "5678".to_string(),
+            class_code: None,
+            modalias: None,
+            dmesg_lines: vec!["pci 0000:03:00.0: firmware load failed".to_string(); lines],
+        });
+        report
+    }
+
+    #[test]
+    fn test_externalize_and_verify() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = BlobStore::new(dir.path().join(BLOB_DIR));
+        let mut report = report_with_dmesg(20);
+
+        assert_eq!(report.externalize_blobs(&store, 256).unwrap(), 1);
+        assert!(report.unknown_devices[0].dmesg_lines.is_empty());
+        assert_eq!(report.blobs[0].kind, KIND_DMESG);
+        assert!(store.path(&report.blobs[0].sha256).exists());
+        report.verify_blobs(&store).unwrap();
+
+        let text = String::from_utf8(store.get(&report.blobs[0]).unwrap()).unwrap();
+        assert_eq!(text.lines().count(), 20);
+    }
+
+    #[test]
+    fn test_small_payload_stays_inline() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = BlobStore::new(dir.path());
+        let mut report = report_with_dmesg(1);
+
+        assert_eq!(report.externalize_blobs(&store, 256).unwrap(), 0);
+        assert!(report.blobs.is_empty());
+    }
+
+    #[test]
+    fn test_tampered_blob_rejected() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = BlobStore::new(dir.path());
+        let mut report = report_with_dmesg(20);
+        report.externalize_blobs(&store, 0).unwrap();
+
+        std::fs::write(store.path(&report.blobs[0].sha256), b"tampered").unwrap();
+        assert!(report.verify_blobs(&store).is_err());
+
+        std::fs::remove_file(store.path(&report.blobs[0].sha256)).unwrap();
+        assert!(report.verify_blobs(&store).is_err());
+    }
+
+    #[test]
+    fn test_get_checks_digest_and_size_first() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = BlobStore::new(dir.path().join(BLOB_DIR));
+        std::fs::write(dir.path().join("report.json"), b"{}").unwrap();
+
+        // A digest that is not a file name in the store is rejected
+        let escape = BlobRef {
+            kind: KIND_DMESG.to_string(),
+            subject: "unknown_devices[0].dmesg_lines".to_string(),
+            sha256: "../report.json".to_string(),
+            size: 2,
+        };
+        let error = store.get(&escape).unwrap_err().to_string();
+        assert!(error.contains("Invalid blob digest"), "{}", error);
+
+        let data = b"pci 0000:03:00.0: firmware load failed";
+        let sha256 = store.put(data).unwrap();
+        let mut blob = BlobRef { sha256, size: data.len() as u64, ..escape };
+        assert_eq!(store.get(&blob).unwrap(), data);
+        blob.size += 1;
+        let error = store.get(&blob).unwrap_err().to_string();
+        assert!(error.contains("does not match"), "{}", error);
+    }
+
+    #[test]
+    fn test_write_bundle() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = BlobStore::new(dir.path().join("staging"));
+        let mut report = report_with_dmesg(20);
+        report.externalize_blobs(&store, 0).unwrap();
+
+        let bundle = dir.path().join("bundle");
+        let report_path = report.write_bundle(&store, &bundle).unwrap();
+        assert_eq!(report_path, bundle.join("bundle.json"));
+        let loaded = HardwareReport::load(&report_path).unwrap();
+        loaded.verify_blobs(&BlobStore::for_report(&report_path)).unwrap();
+    }
+}
@@ -0,0 +1,491 @@
+//! Synthetic example hardware reports
+//!
+//! Generates realistic but entirely made-up reports for seeding test
+//! databases, demoing the website and benchmarking the indexer without
+//! collecting reports from real machines. Output depends only on the seed
+//! and report index, so a given seed always produces the same corpus.
+
+use crate::hardware::lifecycle::kernel_series;
+use crate::hardware::*;
+use chrono::{Duration, TimeZone, Utc};
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+use ring::digest;
+
+/// A PCI device the generator can place in a report
+struct PciDevice {
+    vendor: &'static str,
+    model: &'static str,
+    pci_id: &'static str,
+    /// Driver bound on supported kernels; None when no in-tree driver exists
+    driver: Option<&'static str>,
+    /// First kernel series with a working driver
+    since_kernel: &'static str,
+}
+
+const fn pci(
+    vendor: &'static str,
+    model: &'static str,
+    pci_id: &'static str,
+    driver: Option<&'static str>,
+    since_kernel: &'static str,
+) -> PciDevice {
+    PciDevice { vendor, model, pci_id, driver, since_kernel }
+}
+
+/// Distribution and the kernel it ships
+const SYSTEMS: &[(&str, &str)] = &[
+    ("Ubuntu 22.04.4 LTS", "5.15.0-105-generic"),
+    ("Ubuntu 24.04.1 LTS", "6.8.0-45-generic"),
+    ("Debian GNU/Linux 12 (bookworm)", "6.1.0-18-amd64"),
+    ("Fedora Linux 40 (Workstation Edition)", "6.10.12-200.fc40.x86_64"),
+    ("Arch Linux", "6.11.5-arch1-1"),
+    ("openSUSE Tumbleweed", "6.11.3-1-default"),
+    ("NixOS 24.05 (Uakari)", "6.6.58"),
+    ("Linux Mint 21.3", "5.15.0-91-generic"),
+];
+
+/// Vendor, model, cores, threads, base and max frequency in GHz
+const CPUS: &[(&str, &str, u32, u32, f64, f64)] = &[
+    ("AuthenticAMD", "AMD Ryzen 5 5600X 6-Core Processor", 6, 12, 3.7, 4.6),
+    ("AuthenticAMD", "AMD Ryzen 7 7840U w/ Radeon 780M Graphics", 8, 16, 3.3, 5.1),
+    ("AuthenticAMD", "AMD Ryzen 9 7950X 16-Core Processor", 16, 32, 4.5, 5.7),
+    ("GenuineIntel", "12th Gen Intel(R) Core(TM) i7-1260P", 12, 16, 2.1, 4.7),
+    ("GenuineIntel", "13th Gen Intel(R) Core(TM) i5-13600K", 14, 20, 3.5, 5.1),
+    ("GenuineIntel", "Intel(R) Core(TM) Ultra 7 155H", 16, 22, 1.4, 4.8),
+    ("GenuineIntel", "Intel(R) Core(TM) i5-8250U CPU @ 1.60GHz", 4, 8, 1.6, 3.4),
+];
+
+const GRAPHICS: &[PciDevice] = &[
+    pci(
+        "Intel Corporation",
+        "Alder Lake-P GT2 [Iris Xe Graphics]",
+        "8086:46a6",
+        Some("i915"),
+        "5.17",
+    ),
+    pci(
+        "Intel Corporation",
+        "Meteor Lake-P [Intel Arc Graphics]",
+        "8086:7d55",
+        Some("i915"),
+        "6.7",
+    ),
+    pci("Intel Corporation", "UHD Graphics 620", "8086:5917", Some("i915"), "4.14"),
+    pci(
+        "Advanced Micro Devices, Inc. [AMD/ATI]",
+        "Navi 21 [Radeon RX 6800]",
+        "1002:73bf",
+        Some("amdgpu"),
+        "5.10",
+    ),
+    pci(
+        "Advanced Micro Devices, Inc. [AMD/ATI]",
+        "Navi 31 [Radeon RX 7900 XTX]",
+        "1002:744c",
+        Some("amdgpu"),
+        "6.2",
+    ),
+    pci(
+        "Advanced Micro Devices, Inc. [AMD/ATI]",
+        "Phoenix1 [Radeon 780M]",
+        "1002:15bf",
+        Some("amdgpu"),
+        "6.3",
+    ),
+    pci("NVIDIA Corporation", "GA104 [GeForce RTX 3070]", "10de:2484", Some("nvidia"), "5.4"),
+    pci(
+        "NVIDIA Corporation",
+        "AD107M [GeForce RTX 4060 Max-Q]",
+        "10de:28e0",
+        Some("nvidia"),
+        "6.1",
+    ),
+    pci("NVIDIA Corporation", "GK107 [GeForce GT 640]", "10de:0fc1", Some("nouveau"), "3.10"),
+];
+
+const NETWORK: &[(PciDevice, &str)] = &[
+    (pci("Intel Corporation", "Wi-Fi 6 AX201", "8086:a0f0", Some("iwlwifi"), "5.2"), "wifi"),
+    (pci("Intel Corporation", "Wi-Fi 6E AX211", "8086:51f0", Some("iwlwifi"), "5.16"), "wifi"),
+    (
+        pci("Intel Corporation", "Ethernet Controller I225-V", "8086:15f3", Some("igc"), "5.0"),
+        "ethernet",
+    ),
+    (
+        pci(
+            "Realtek Semiconductor Co., Ltd.",
+            "RTL8125 2.5GbE Controller",
+            "10ec:8125",
+            Some("r8169"),
+            "5.9",
+        ),
+        "ethernet",
+    ),
+    (
+        pci(
+            "Realtek Semiconductor Co., Ltd.",
+            "RTL8852BE PCIe 802.11ax",
+            "10ec:b852",
+            Some("rtw89_8852be"),
+            "6.2",
+        ),
+        "wifi",
+    ),
+    (
+        pci("MEDIATEK Corp.", "MT7921 802.11ax PCI Express", "14c3:7961", Some("mt7921e"), "5.12"),
+        "wifi",
+    ),
+    (
+        pci(
+            "Qualcomm Technologies, Inc",
+            "QCNFA765 Wireless Network Adapter",
+            "17cb:1103",
+            Some("ath11k_pci"),
+            "5.18",
+        ),
+        "wifi",
+    ),
+    (pci("Broadcom Inc. and subsidiaries", "BCM4360 802.11ac", "14e4:43a0", None, "99.0"), "wifi"),
+];
+
+const AUDIO: &[PciDevice] = &[
+    pci(
+        "Intel Corporation",
+        "Alder Lake PCH-P High Definition Audio",
+        "8086:51c8",
+        Some("sof-audio-pci-intel-tgl"),
+        "5.17",
+    ),
+    pci(
+        "Intel Corporation",
+        "Meteor Lake-P HD Audio Controller",
+        "8086:7e28",
+        Some("sof-audio-pci-intel-mtl"),
+        "6.6",
+    ),
+    pci(
+        "Advanced Micro Devices, Inc. [AMD]",
+        "Family 17h/19h HD Audio Controller",
+        "1022:15e3",
+        Some("snd_hda_intel"),
+        "5.4",
+    ),
+    pci(
+        "NVIDIA Corporation",
+        "GA104 High Definition Audio Controller",
+        "10de:228b",
+        Some("snd_hda_intel"),
+        "5.4",
+    ),
+];
+
+/// Vendor, model, device type, interface and size in GB
+const STORAGE: &[(&str, &str, &str, &str, u64)] = &[
+    ("Samsung", "Samsung SSD 980 PRO 1TB", "NVMe", "nvme", 1000),
+    ("Western Digital", "WD_BLACK SN770 2TB", "NVMe", "nvme", 2000),
+    ("SK hynix", "PC801 NVMe 512GB", "NVMe", "nvme", 512),
+    ("Crucial", "CT1000MX500SSD1", "SSD", "sata", 1000),
+    ("Seagate", "ST4000DM004-2U9104", "HDD", "sata", 4000),
+];
+
+/// Vendor ID, product ID, vendor name, product name, class
+const USB: &[(&str, &str, &str, &str, &str)] = &[
+    ("046d", "c52b", "Logitech, Inc.", "Unifying Receiver", "hid"),
+    ("8087", "0033", "Intel Corp.", "AX211 Bluetooth", "wireless"),
+    ("0bda", "5634", "Realtek Semiconductor Corp.", "Integrated Camera", "video"),
+    (
+        "27c6",
+        "6594",
+        "Shenzhen Goodix Technology Co.,Ltd.",
+        "Goodix USB2.0 MISC",
+        "vendor-specific",
+    ),
+    ("1050", "0407", "Yubico.com", "Yubikey 4/5 OTP+U2F+CCID", "hid"),
+    ("0781", "5581", "SanDisk Corp.", "Ultra", "mass-storage"),
+];
+
+/// Deterministic generator of synthetic hardware reports
+pub struct ExampleGenerator {
+    seed: u64,
+}
+
+impl ExampleGenerator {
+    pub fn new(seed: u64) -> Self {
+        Self { seed }
+    }
+
+    /// Generate the report at `index` of this seed's corpus
+    pub fn report(&self, index: usize) -> HardwareReport {
+        let mut rng = StdRng::from_seed(self.digest(&format!("report-{}", index)));
+        let &(distribution, kernel) = SYSTEMS.choose(&mut rng).unwrap();
+
+        let generated_at = Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap()
+            + Duration::seconds(rng.gen_range(0..3 * 365 * 86_400));
+        let system_id = self.hash(&format!("system-{}", index));
+
+        let &(cpu_vendor, cpu_model, cores, threads, base, max) = CPUS.choose(&mut rng).unwrap();
+        let cpu = CpuInfo {
+            model: cpu_model.to_string(),
+            vendor: cpu_vendor.to_string(),
+            cores,
+            threads,
+            base_frequency: Some(base),
+            max_frequency: Some(max),
+            cache_l1: Some(cores as u64 * 80 * 1024),
+            cache_l2: Some(cores as u64 * 1024 * 1024),
+            cache_l3: Some(rng.gen_range(2..=8) * 4 * 1024 * 1024),
+            flags: ["fpu", "sse4_2", "avx2", "aes"].map(String::from).to_vec(),
+            scaling_governor: None,
+            microcode: None,
+        };
+
+        let dimm_count = *[1u64, 2, 4].choose(&mut rng).unwrap();
+        let dimm_gb = *[8u64, 16, 32].choose(&mut rng).unwrap();
+        let &(memory_type, speed_mhz) =
+            [("DDR4", 3200), ("DDR5", 4800), ("DDR5", 5600)].choose(&mut rng).unwrap();
+        let total_bytes = (dimm_count * dimm_gb) << 30;
+        let memory = MemoryInfo {
+            total_bytes,
+            available_bytes: total_bytes / 100 * rng.gen_range(40..90),
+            dimm_count: None,
+            swap: None,
+            dimms: (0..dimm_count)
+                .map(|_| MemoryDimm {
+                    size_bytes: dimm_gb << 30,
+                    speed_mhz: Some(speed_mhz),
+                    memory_type: Some(memory_type.to_string()),
+                    manufacturer: None,
+                })
+                .collect(),
+        };
+
+        let gpu_count = rng.gen_range(1..=2);
+        let nic_count = rng.gen_range(1..=2);
+        let disk_count = rng.gen_range(1..=2);
+        let usb_count = rng.gen_range(0..=3);
+        let mut support = Vec::new();
+        let mut unknown_devices = Vec::new();
+
+        let graphics: Vec<GraphicsDevice> = GRAPHICS
+            .choose_multiple(&mut rng, gpu_count)
+            .map(|device| GraphicsDevice {
+                vendor: device.vendor.to_string(),
+                model: device.model.to_string(),
+                driver: bound_driver(device, kernel, &mut support),
+                memory_bytes: None,
+                pci_id: device.pci_id.to_string(),
+                displays: None,
+                nvidia: None,
+            })
+            .collect();
+
+        let network: Vec<NetworkDevice> = NETWORK
+            .choose_multiple(&mut rng, nic_count)
+            .map(|(device, device_type)| NetworkDevice {
+                device_type: device_type.to_string(),
+                vendor: device.vendor.to_string(),
+                model: device.model.to_string(),
+                driver: bound_driver(device, kernel, &mut support),
+                anonymized_mac: self.hash(&format!("mac-{}-{}", index, device.pci_id)),
+                wireless_capabilities: None,
+            })
+            .collect();
+
+        let audio: Vec<AudioDevice> = AUDIO
+            .choose_multiple(&mut rng, 1)
+            .map(|device| AudioDevice {
+                vendor: device.vendor.to_string(),
+                model: device.model.to_string(),
+                driver: bound_driver(device, kernel, &mut support),
+                device_type: "audio".to_string(),
+                codecs: Vec::new(),
+                profiles: None,
+            })
+            .collect();
+
+        let storage: Vec<StorageDevice> = STORAGE
+            .choose_multiple(&mut rng, disk_count)
+            .map(|&(vendor, model, device_type, interface, size_gb)| StorageDevice {
+                anonymized_serial: self.hash(&format!("serial-{}-{}", index, model))[..16]
+                    .to_string(),
+                device_type: device_type.to_string(),
+                size_bytes: size_gb * 1_000_000_000,
+                model: model.to_string(),
+                vendor: Some(vendor.to_string()),
+                interface: Some(interface.to_string()),
+                nvme: None,
+                health: None,
+                topology: None,
+            })
+            .collect();
+
+        let usb: Vec<UsbDevice> = USB
+            .choose_multiple(&mut rng, usb_count)
+            .map(|&(vendor_id, product_id, vendor_name, product_name, class)| UsbDevice {
+                vendor_id: vendor_id.to_string(),
+                product_id: product_id.to_string(),
+                vendor_name: Some(vendor_name.to_string()),
+                product_name: Some(product_name.to_string()),
+                usb_version: Some("2.00".to_string()),
+                device_class: Some(class.to_string()),
+                device_subclass: None,
+                speed_mbps: Some(480.0),
+                port_path: None,
+                max_power_ma: Some(100),
+                anonymized_serial: None,
+            })
+            .collect();
+
+        // Roughly one machine in ten has a device nothing could identify
+        if rng.gen_ratio(1, 10) {
+            let device_id = format!("{:04x}", rng.gen_range(0x1000..0xffff));
+            unknown_devices.push(UnknownDevice {
+                bus: "pci".to_string(),
+                vendor_id: "1dbe".to_string(),
+                device_id: device_id.clone(),
+                class_code: Some("0280".to_string()),
+                modalias: Some(format!("pci:v00001DBEd0000{}sv*sd*bc02sc80i00", device_id)),
+                dmesg_lines: Vec::new(),
+            });
+        }
+
+        let count = |status: &str| {
+            support.iter().filter(|device| device.support_status == status).count() as u32
+        };
+        let kernel_support = KernelCompatibilityInfo {
+            kernel_version: kernel.to_string(),
+            total_devices_detected: support.len() as u32,
+            supported_devices: count("supported"),
+            unsupported_devices: count("unsupported"),
+            experimental_devices: count("experimental"),
+            missing_modules: Vec::new(),
+            config_recommendations: Vec::new(),
+            module_parameters: Vec::new(),
+            blacklisted_modules: Vec::new(),
+            dkms_modules: Vec::new(),
+            device_support_details: support,
+        };
+
+        HardwareReport {
+            metadata: ReportMetadata {
+                version: env!("CARGO_PKG_VERSION").to_string(),
+                generated_at,
+                privacy_level: PrivacyLevel::Basic,
+                tools_used: ["lshw", "lspci", "lsusb", "inxi"].map(String::from).to_vec(),
+                anonymized_system_id: system_id,
+                phase_errors: Vec::new(),
+                sandbox: None,
+                privileges: None,
+                remote: false,
+                replayed: false,
+                salt_context: None,
+                digest_length: None,
+            },
+            system: SystemInfo {
+                anonymized_hostname: self.hash(&format!("host-{}", index))[..16].to_string(),
+                kernel_version: kernel.to_string(),
+                distribution: Some(distribution.to_string()),
+                architecture: "x86_64".to_string(),
+                boot_time: None,
+                virtualization: None,
+            },
+            cpu: Some(cpu),
+            memory: Some(memory),
+            storage,
+            storage_stack: None,
+            graphics,
+            network,
+            usb,
+            audio,
+            audio_routing: None,
+            radios: Vec::new(),
+            input: Vec::new(),
+            peripherals: None,
+            pcie_links: Vec::new(),
+            sensors: Vec::new(),
+            firmware: None,
+            kernel_support: Some(kernel_support),
+            unknown_devices,
+            lifecycle_notices: Vec::new(),
+            firmware_updates: Vec::new(),
+            user_notes: Vec::new(),
+            blobs: Vec::new(),
+            provenance: None,
+        }
+    }
+
+    /// Stable anonymized identifier derived from the seed
+    fn hash(&self, value: &str) -> String {
+        hex::encode(self.digest(value))
+    }
+
+    fn digest(&self, value: &str) -> [u8; 32] {
+        let input = format!("lx-hw-example-{}-{}", self.seed, value);
+        let mut bytes = [0; 32];
+        bytes.copy_from_slice(digest::digest(&digest::SHA256, input.as_bytes()).as_ref());
+        bytes
+    }
+}
+
+/// Record kernel support for a device and return the driver bound to it
+fn bound_driver(
+    device: &PciDevice,
+    kernel: &str,
+    support: &mut Vec<DeviceCompatibility>,
+) -> Option<String> {
+    let since = kernel_series(device.since_kernel);
+    let running = kernel_series(kernel);
+    let (status, notes) = match (device.driver, since <= running) {
+        (Some(_), true) => ("supported", None),
+        (Some(_), false) => (
+            "experimental",
+            Some(format!("Full support requires Linux {} or newer", device.since_kernel)),
+        ),
+        (None, _) => ("unsupported", Some("No in-tree driver available".to_string())),
+    };
+
+    support.push(DeviceCompatibility {
+        device_id: device.pci_id.to_string(),
+        device_name: device.model.to_string(),
+        support_status: status.to_string(),
+        driver_module: device.driver.unwrap_or_default().to_string(),
+        since_kernel_version: device.driver.map(|_| device.since_kernel.to_string()),
+        config_dependencies: Vec::new(),
+        notes,
+    });
+
+    device.driver.filter(|_| status == "supported").map(String::from)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reports_are_deterministic() {
+        let first = serde_json::to_string(&ExampleGenerator::new(7).report(3)).unwrap();
+        let second = serde_json::to_string(&ExampleGenerator::new(7).report(3)).unwrap();
+        assert_eq!(first, second);
+
+        let other_seed = serde_json::to_string(&ExampleGenerator::new(8).report(3)).unwrap();
+        assert_ne!(first, other_seed);
+    }
+
+    #[test]
+    fn test_kernel_support_matches_devices() {
+        let generator = ExampleGenerator::new(1);
+        for index in 0..20 {
+            let report = generator.report(index);
+            let support = report.kernel_support.unwrap();
+            let pci_devices = report.graphics.len() + report.network.len() + report.audio.len();
+            assert_eq!(support.total_devices_detected as usize, pci_devices);
+            assert_eq!(
+                support.supported_devices
+                    + support.experimental_devices
+                    + support.unsupported_devices,
+                support.total_devices_detected
+            );
+        }
+    }
+}
@@ -0,0 +1,308 @@
+//! Kernel release series and their LTS/end-of-life status
+//!
+//! Built from `data/kernel-releases.toml`, or from a local copy refreshed from
+//! kernel.org with `lx-hw-detect update-kernel-releases`. Upgrade
+//! recommendations use it to label candidate kernels and to point at longterm
+//! series that still receive fixes instead of series that are already end of
+//! life.
+
+use crate::errors::{LxHwError, Result};
+use crate::hardware::lifecycle::kernel_series;
+use chrono::{NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// Built-in release data, compiled into the binary
+const RELEASE_DATA: &str = include_str!("../../data/kernel-releases.toml");
+
+/// kernel.org's machine-readable list of current releases
+pub const KERNEL_ORG_RELEASES_URL: &str = "https://www.kernel.org/releases.json";
+
+/// A longterm (LTS) kernel series
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LongtermSeries {
+    pub series: String,
+    /// Projected end of life (YYYY-MM), if announced
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub eol: Option<String>,
+}
+
+/// Support status of a kernel series
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum KernelSeriesStatus {
+    /// Longterm series still receiving fixes
+    Longterm { eol: Option<String> },
+    /// Current stable series
+    Stable,
+    /// Newer than the release data knows about
+    Newer,
+    /// No longer receives fixes
+    EndOfLife,
+}
+
+impl KernelSeriesStatus {
+    /// Whether the series still receives fixes
+    pub fn is_supported(&self) -> bool {
+        !matches!(self, Self::EndOfLife)
+    }
+
+    /// Short label, e.g. "LTS, supported until 2026-12"
+    pub fn label(&self) -> String {
+        match self {
+            Self::Longterm { eol: Some(eol) } => format!("LTS, supported until {}", eol),
+            Self::Longterm { eol: None } => "LTS".to_string(),
+            Self::Stable => "stable".to_string(),
+            Self::Newer => "newer than known releases".to_string(),
+            Self::EndOfLife => "end of life".to_string(),
+        }
+    }
+}
+
+/// Release series known to be supported, as of `updated`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KernelReleases {
+    /// Date the data was taken from kernel.org
+    pub updated: String,
+    /// Newest stable series
+    pub stable: String,
+    #[serde(default)]
+    pub longterm: Vec<LongtermSeries>,
+}
+
+/// Subset of kernel.org's releases.json
+#[derive(Debug, Deserialize)]
+struct KernelOrgReleases {
+    latest_stable: KernelOrgVersion,
+    releases: Vec<KernelOrgRelease>,
+}
+
+#[derive(Debug, Deserialize)]
+struct KernelOrgVersion {
+    version: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct KernelOrgRelease {
+    moniker: String,
+    version: String,
+    #[serde(default)]
+    iseol: bool,
+}
+
+impl KernelReleases {
+    /// Release data from `data/kernel-releases.toml`
+    pub fn builtin() -> Self {
+        Self::parse(RELEASE_DATA).expect("built-in kernel release data must be valid")
+    }
+
+    /// Refreshed release data if present and valid, the built-in data otherwise
+    pub fn load() -> Self {
+        let path = Self::default_path();
+        match std::fs::read_to_string(&path) {
+            Ok(content) => Self::parse(&content).unwrap_or_else(|e| {
+                log::warn!("Ignoring {}: {}", path.display(), e);
+                Self::builtin()
+            }),
+            Err(_) => Self::builtin(),
+        }
+    }
+
+    /// Location of refreshed release data, overridable with `LX_HW_KERNEL_RELEASES`
+    pub fn default_path() -> PathBuf {
+        if let Some(path) = std::env::var_os("LX_HW_KERNEL_RELEASES") {
+            return PathBuf::from(path);
+        }
+        let cache_dir = std::env::var_os("XDG_CACHE_HOME")
+            .map(PathBuf::from)
+            .or_else(|| std::env::var_os("HOME").map(|h| PathBuf::from(h).join(".cache")))
+            .unwrap_or_else(std::env::temp_dir);
+        cache_dir.join("lx-hw-db").join("kernel-releases.toml")
+    }
+
+    /// Parse release data from TOML
+    pub fn parse(content: &str) -> Result<Self> {
+        let releases: Self = toml::from_str(content)
+            .map_err(|e| LxHwError::ConfigError(format!("Invalid kernel release data: {}", e)))?;
+
+        let mut series =
+            std::iter::once(&releases.stable).chain(releases.longterm.iter().map(|l| &l.series));
+        if let Some(invalid) = series.find(|s| kernel_series(s).is_none()) {
+            return Err(LxHwError::ConfigError(format!("Invalid kernel series: {}", invalid)));
+        }
+        Ok(releases)
+    }
+
+    /// Build release data from kernel.org's releases.json
+    ///
+    /// releases.json does not carry end-of-life dates, so they are kept from
+    /// `previous` for longterm series that are still listed.
+    pub fn from_kernel_org(json: &str, previous: &Self, updated: NaiveDate) -> Result<Self> {
+        let releases: KernelOrgReleases = serde_json::from_str(json).map_err(|e| {
+            LxHwError::SerializationError(format!("Invalid kernel.org releases: {}", e))
+        })?;
+
+        let series_of = |version: &str| {
+            kernel_series(version).map(|(major, minor)| format!("{}.{}", major, minor)).ok_or_else(
+                || LxHwError::SerializationError(format!("Invalid kernel version: {}", version)),
+            )
+        };
+
+        let mut longterm = Vec::new();
+        for release in releases.releases.iter().filter(|r| r.moniker == "longterm" && !r.iseol) {
+            let series = series_of(&release.version)?;
+            let eol =
+                previous.longterm.iter().find(|l| l.series == series).and_then(|l| l.eol.clone());
+            longterm.push(LongtermSeries { series, eol });
+        }
+        longterm.sort_by_key(|l| std::cmp::Reverse(kernel_series(&l.series)));
+        longterm.dedup();
+
+        Ok(Self {
+            updated: updated.to_string(),
+            stable: series_of(&releases.latest_stable.version)?,
+            longterm,
+        })
+    }
+
+    /// Write the release data as TOML
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let content =
+            toml::to_string(self).map_err(|e| LxHwError::SerializationError(e.to_string()))?;
+        std::fs::write(path, content)?;
+        Ok(())
+    }
+
+    /// Support status of the series a kernel version belongs to, as of today
+    pub fn status(&self, version: &str) -> Option<KernelSeriesStatus> {
+        self.status_on(version, Utc::now().date_naive())
+    }
+
+    /// Support status of the series a kernel version belongs to on `date`
+    pub fn status_on(&self, version: &str, date: NaiveDate) -> Option<KernelSeriesStatus> {
+        let series = kernel_series(version)?;
+        if let Some(longterm) =
+            self.longterm.iter().find(|l| kernel_series(&l.series) == Some(series))
+        {
+            let month = date.format("%Y-%m").to_string();
+            return Some(match &longterm.eol {
+                Some(eol) if eol.as_str() < month.as_str() => KernelSeriesStatus::EndOfLife,
+                eol => KernelSeriesStatus::Longterm { eol: eol.clone() },
+            });
+        }
+
+        let stable = kernel_series(&self.stable)?;
+        Some(if series == stable {
+            KernelSeriesStatus::Stable
+        } else if series > stable {
+            KernelSeriesStatus::Newer
+        } else {
+            KernelSeriesStatus::EndOfLife
+        })
+    }
+
+    /// Newest longterm series still supported, newer than `after` and no
+    /// newer than `up_to`
+    pub fn recommended_longterm(
+        &self,
+        after: (u32, u32),
+        up_to: Option<(u32, u32)>,
+    ) -> Option<(&str, KernelSeriesStatus)> {
+        self.longterm
+            .iter()
+            .filter_map(|l| Some((kernel_series(&l.series)?, l.series.as_str())))
+            .filter(|(series, _)| *series > after && up_to.map_or(true, |max| *series <= max))
+            .filter_map(|(series, name)| {
+                let status = self.status(name)?;
+                status.is_supported().then_some((series, name, status))
+            })
+            .max_by_key(|(series, _, _)| *series)
+            .map(|(_, name, status)| (name, status))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn releases() -> KernelReleases {
+        KernelReleases::parse(
+            r#"
+            updated = "2024-06-01"
+            stable = "6.9"
+
+            [[longterm]]
+            series = "6.6"
+            eol = "2026-12"
+
+            [[longterm]]
+            series = "6.1"
+
+            [[longterm]]
+            series = "4.19"
+            eol = "2024-12"
+            "#,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_builtin_data_parses() {
+        let releases = KernelReleases::builtin();
+        assert!(kernel_series(&releases.stable).is_some());
+        assert!(!releases.longterm.is_empty());
+    }
+
+    #[test]
+    fn test_series_status() {
+        let releases = releases();
+        let date = NaiveDate::from_ymd_opt(2025, 3, 1).unwrap();
+        let status = |version| releases.status_on(version, date).unwrap();
+
+        assert_eq!(
+            status("6.6.30-1-lts"),
+            KernelSeriesStatus::Longterm { eol: Some("2026-12".into()) }
+        );
+        assert_eq!(status("6.1.90"), KernelSeriesStatus::Longterm { eol: None });
+        assert_eq!(status("4.19.300"), KernelSeriesStatus::EndOfLife);
+        assert_eq!(status("6.4.12"), KernelSeriesStatus::EndOfLife);
+        assert_eq!(status("6.9.3"), KernelSeriesStatus::Stable);
+        assert_eq!(status("6.11.0"), KernelSeriesStatus::Newer);
+        assert!(releases.status_on("unknown", date).is_none());
+    }
+
+    #[test]
+    fn test_from_kernel_org_keeps_known_eol_dates() {
+        let json = r#"{
+            "latest_stable": {"version": "6.10.2"},
+            "releases": [
+                {"moniker": "mainline", "version": "6.11-rc1", "iseol": false},
+                {"moniker": "stable", "version": "6.10.2", "iseol": false},
+                {"moniker": "stable", "version": "6.9.12", "iseol": true},
+                {"moniker": "longterm", "version": "6.6.43", "iseol": false},
+                {"moniker": "longterm", "version": "6.1.102", "iseol": false},
+                {"moniker": "longterm", "version": "4.19.318", "iseol": true},
+                {"moniker": "linux-next", "version": "next-20240729", "iseol": false}
+            ]
+        }"#;
+        let updated = NaiveDate::from_ymd_opt(2024, 7, 29).unwrap();
+        let refreshed = KernelReleases::from_kernel_org(json, &releases(), updated).unwrap();
+
+        assert_eq!(refreshed.updated, "2024-07-29");
+        assert_eq!(refreshed.stable, "6.10");
+        assert_eq!(
+            refreshed.longterm,
+            vec![
+                LongtermSeries { series: "6.6".into(), eol: Some("2026-12".into()) },
+                LongtermSeries { series: "6.1".into(), eol: None },
+            ]
+        );
+        assert_eq!(
+            KernelReleases::parse(&toml::to_string(&refreshed).unwrap()).unwrap(),
+            refreshed
+        );
+    }
+}
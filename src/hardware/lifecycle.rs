@@ -0,0 +1,222 @@
+//! Hardware age and driver end-of-life awareness
+//!
+//! Maps GPU and chipset PCI IDs and CPU model names to hardware generations
+//! from `data/hardware-lifecycle.toml`. Each generation carries its release
+//! year and, for hardware stuck on a legacy driver branch, the newest kernel
+//! that branch supports, so reports and recommendations can warn about
+//! driver/kernel combinations that cannot work.
+
+use crate::errors::{LxHwError, Result};
+use crate::hardware::taxonomy::Category;
+use crate::hardware::LifecycleNotice;
+use chrono::Datelike;
+use regex::Regex;
+use serde::Deserialize;
+
+/// Built-in lifecycle data, compiled into the binary
+const LIFECYCLE_DATA: &str = include_str!("../../data/hardware-lifecycle.toml");
+
+/// Legacy driver branch required by a hardware generation
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct DriverBranch {
+    pub name: String,
+    /// When the branch stopped receiving updates (YYYY-MM)
+    pub eol: Option<String>,
+    /// Newest kernel series the branch supports
+    pub max_kernel: Option<String>,
+    /// Driver to use on newer kernels
+    pub alternative: Option<String>,
+}
+
+/// A hardware generation or family
+#[derive(Debug, Clone, Deserialize)]
+pub struct Generation {
+    pub name: String,
+    pub category: Category,
+    pub release_year: u32,
+    #[serde(default)]
+    pub vendor_id: Option<String>,
+    #[serde(default)]
+    pub device_ranges: Vec<(String, String)>,
+    #[serde(default)]
+    pub model_patterns: Vec<String>,
+    pub driver: Option<DriverBranch>,
+    pub notes: Option<String>,
+}
+
+impl Generation {
+    /// Newest kernel series (major, minor) the legacy driver supports
+    pub fn max_kernel_series(&self) -> Option<(u32, u32)> {
+        self.driver.as_ref().and_then(|d| d.max_kernel.as_deref()).and_then(kernel_series)
+    }
+
+    /// Whether a kernel version is newer than the legacy driver supports
+    pub fn kernel_unsupported(&self, kernel_version: &str) -> bool {
+        match (kernel_series(kernel_version), self.max_kernel_series()) {
+            (Some(kernel), Some(max)) => kernel > max,
+            _ => false,
+        }
+    }
+
+    /// Explanation of why the legacy driver cannot be used on this kernel
+    pub fn kernel_warning(&self, kernel_version: &str) -> Option<String> {
+        if !self.kernel_unsupported(kernel_version) {
+            return None;
+        }
+        let driver = self.driver.as_ref()?;
+        let max = driver.max_kernel.as_deref()?;
+        let mut warning = format!(
+            "{} requires the {} driver, which is not available for kernels newer than {}",
+            self.name, driver.name, max
+        );
+        if let Some(alternative) = &driver.alternative {
+            warning.push_str(&format!("; use {} instead or stay on kernel {}", alternative, max));
+        }
+        Some(warning)
+    }
+
+    /// Build the notice included in reports
+    pub fn notice(&self, device_id: Option<String>, kernel_version: &str) -> LifecycleNotice {
+        let current_year = chrono::Utc::now().year() as u32;
+        LifecycleNotice {
+            generation: self.name.clone(),
+            device_id,
+            release_year: self.release_year,
+            age_years: current_year.saturating_sub(self.release_year),
+            legacy_driver: self.driver.as_ref().map(|d| d.name.clone()),
+            warning: self.kernel_warning(kernel_version),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct LifecycleFile {
+    #[serde(default)]
+    pci: Vec<Generation>,
+    #[serde(default)]
+    cpu: Vec<Generation>,
+}
+
+/// Lookup table of hardware generations
+#[derive(Debug)]
+pub struct LifecycleDatabase {
+    pci: Vec<Generation>,
+    cpu: Vec<(Generation, Vec<Regex>)>,
+}
+
+impl LifecycleDatabase {
+    /// Database from `data/hardware-lifecycle.toml`
+    pub fn builtin() -> Self {
+        Self::parse(LIFECYCLE_DATA).expect("built-in lifecycle data must be valid")
+    }
+
+    /// Parse lifecycle data from TOML
+    pub fn parse(content: &str) -> Result<Self> {
+        let file: LifecycleFile = toml::from_str(content)
+            .map_err(|e| LxHwError::ConfigError(format!("Invalid lifecycle data: {}", e)))?;
+
+        let cpu = file
+            .cpu
+            .into_iter()
+            .map(|generation| {
+                let patterns = generation
+                    .model_patterns
+                    .iter()
+                    .map(|p| Regex::new(p))
+                    .collect::<std::result::Result<Vec<_>, _>>()
+                    .map_err(|e| {
+                        LxHwError::ConfigError(format!(
+                            "Invalid model pattern for {}: {}",
+                            generation.name, e
+                        ))
+                    })?;
+                Ok((generation, patterns))
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(Self { pci: file.pci, cpu })
+    }
+
+    /// Generation of a PCI device; the narrowest matching ID range wins
+    pub fn lookup_pci(&self, vendor_id: &str, device_id: &str) -> Option<&Generation> {
+        let device = u32::from_str_radix(device_id, 16).ok()?;
+        self.pci
+            .iter()
+            .filter(|g| g.vendor_id.as_deref().is_some_and(|v| v.eq_ignore_ascii_case(vendor_id)))
+            .filter_map(|g| {
+                g.device_ranges
+                    .iter()
+                    .filter_map(|(start, end)| {
+                        let start = u32::from_str_radix(start, 16).ok()?;
+                        let end = u32::from_str_radix(end, 16).ok()?;
+                        (start..=end).contains(&device).then_some(end - start)
+                    })
+                    .min()
+                    .map(|width| (width, g))
+            })
+            .min_by_key(|(width, _)| *width)
+            .map(|(_, g)| g)
+    }
+
+    /// Generation of a CPU from its model name
+    pub fn lookup_cpu(&self, model: &str) -> Option<&Generation> {
+        self.cpu
+            .iter()
+            .find(|(_, patterns)| patterns.iter().any(|p| p.is_match(model)))
+            .map(|(g, _)| g)
+    }
+}
+
+/// Parse the major.minor series of a kernel version
+pub fn kernel_series(version: &str) -> Option<(u32, u32)> {
+    let mut parts = version.split(['.', '-']);
+    let major = parts.next()?.parse().ok()?;
+    let minor = parts.next()?.parse().ok()?;
+    Some((major, minor))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_builtin_data_parses() {
+        let db = LifecycleDatabase::builtin();
+        assert!(!db.pci.is_empty());
+        assert!(!db.cpu.is_empty());
+    }
+
+    #[test]
+    fn test_kepler_legacy_driver_warning() {
+        let db = LifecycleDatabase::builtin();
+        let kepler = db.lookup_pci("10DE", "1180").unwrap();
+
+        assert_eq!(kepler.release_year, 2012);
+        assert!(kepler.kernel_warning("6.8.12").is_none());
+
+        let warning = kepler.kernel_warning("6.9.1-arch1").unwrap();
+        assert!(warning.contains("470"));
+        assert!(warning.contains("nouveau"));
+    }
+
+    #[test]
+    fn test_pci_lookup() {
+        let db = LifecycleDatabase::builtin();
+        assert!(db.lookup_pci("10de", "15f7").unwrap().name.contains("Pascal"));
+        assert!(db.lookup_pci("10de", "1401").unwrap().name.contains("Maxwell"));
+        assert!(db.lookup_pci("10de", "ffff").is_none());
+    }
+
+    #[test]
+    fn test_cpu_lookup() {
+        let db = LifecycleDatabase::builtin();
+        let cpu = db.lookup_cpu("Intel(R) Core(TM) i7-2600K CPU @ 3.40GHz").unwrap();
+        assert_eq!(cpu.release_year, 2011);
+        assert!(db
+            .lookup_cpu("12th Gen Intel(R) Core(TM) i7-12700H")
+            .unwrap()
+            .name
+            .contains("Alder Lake"));
+        assert!(db.lookup_cpu("Unknown CPU").is_none());
+    }
+}
@@ -1,12 +1,26 @@
 //! Hardware data structures and system information types
 
+use crate::errors::{LxHwError, Result};
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+pub mod blobs;
+pub mod examples;
+pub mod kernel_releases;
+pub mod lifecycle;
+pub mod notes;
+pub mod out_of_tree;
+pub mod pci_class;
+pub mod provenance;
+pub mod taxonomy;
 
 /// Privacy levels for hardware data collection
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
 pub enum PrivacyLevel {
     /// Basic privacy with 24-hour salt rotation
+    #[default]
     Basic,
     /// Enhanced privacy with 12-hour salt rotation
     Enhanced,
@@ -22,11 +36,54 @@ pub struct HardwareReport {
     pub cpu: Option<CpuInfo>,
     pub memory: Option<MemoryInfo>,
     pub storage: Vec<StorageDevice>,
+    /// Software RAID arrays and LVM volume groups
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub storage_stack: Option<StorageStack>,
     pub graphics: Vec<GraphicsDevice>,
     pub network: Vec<NetworkDevice>,
     pub usb: Vec<UsbDevice>,
     pub audio: Vec<AudioDevice>,
+    /// ALSA playback outputs and default sink routing
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub audio_routing: Option<AudioRouting>,
+    /// Radios from rfkill with their block state
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub radios: Vec<RadioState>,
+    /// Keyboards, pointing devices, touchscreens and tablets
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub input: Vec<InputDevice>,
+    /// Printers and scanners from CUPS and SANE
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub peripherals: Option<Peripherals>,
+    /// PCI Express links of local PCI devices
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub pcie_links: Vec<PcieDeviceLink>,
+    /// Hardware monitoring chips and their sensors
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub sensors: Vec<SensorChip>,
+    /// BIOS/UEFI firmware, boot mode, Secure Boot and TPM
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub firmware: Option<FirmwareInfo>,
     pub kernel_support: Option<KernelCompatibilityInfo>,
+    /// Devices with no known vendor/product name and no bound driver
+    #[serde(default)]
+    pub unknown_devices: Vec<UnknownDevice>,
+    /// Hardware age and legacy driver warnings
+    #[serde(default)]
+    pub lifecycle_notices: Vec<LifecycleNotice>,
+    /// Devices with firmware updatable through fwupd
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub firmware_updates: Vec<FirmwareDevice>,
+    /// Notes the submitting user attached to components
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub user_notes: Vec<notes::UserNote>,
+    /// Oversized payloads stored as content-addressed sidecar files
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub blobs: Vec<blobs::BlobRef>,
+    /// Tools that supplied CPU and memory fields and where they disagreed;
+    /// diagnostic data, only rendered in debug output
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub provenance: Option<provenance::Provenance>,
 }
 
 /// Report metadata and privacy settings
@@ -37,6 +94,78 @@ pub struct ReportMetadata {
     pub privacy_level: PrivacyLevel,
     pub tools_used: Vec<String>,
     pub anonymized_system_id: String,
+    /// Report-building phases that failed and were skipped
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub phase_errors: Vec<PhaseError>,
+    /// Set when detection tools ran inside a sandbox
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sandbox: Option<SandboxStatus>,
+    /// Set when tools ran without privileges they need, or as root with
+    /// `--escalate`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub privileges: Option<PrivilegeStatus>,
+    /// Set when detection tools ran on another host over SSH; checks reading
+    /// the local kernel and sysfs were skipped
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub remote: bool,
+    /// Set when the report was built from saved tool output (`--replay`);
+    /// checks reading the local kernel and sysfs were skipped
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub replayed: bool,
+    /// Salt context the report's identifiers were re-keyed for, e.g.
+    /// "internal:acme"; None for the detection-time pseudonyms
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub salt_context: Option<String>,
+    /// Hex characters kept of each anonymized identifier; None for full
+    /// 64-character digests
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub digest_length: Option<usize>,
+}
+
+/// Isolation applied to detection tools run with `--sandbox`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SandboxStatus {
+    /// Helper that confined the tools, e.g. "bwrap"
+    pub helper: String,
+    /// Whether a seccomp filter was applied
+    pub seccomp: bool,
+    /// Tools that ran but could not collect everything while sandboxed
+    #[serde(default)]
+    pub limited_tools: Vec<String>,
+}
+
+/// Privileges detection tools ran with
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PrivilegeStatus {
+    /// Helper escalated tools ran through, e.g. "sudo"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub escalation: Option<String>,
+    /// Tools that ran as root through the helper
+    #[serde(default)]
+    pub escalated_tools: Vec<String>,
+    /// Tools that ran without privileges they need, so their data may be incomplete
+    #[serde(default)]
+    pub degraded_tools: Vec<DegradedTool>,
+}
+
+/// A tool that ran without the privileges it needs
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DegradedTool {
+    pub tool: String,
+    /// Capabilities the tool needs that it lacked, e.g. "CAP_SYS_RAWIO"
+    #[serde(default)]
+    pub missing_capabilities: Vec<String>,
+    /// Whether the tool itself reported permission errors
+    #[serde(default)]
+    pub permission_errors: bool,
+}
+
+/// Failure of a single report-building phase
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PhaseError {
+    /// Name of the phase, e.g. "cpu" or "kernel_support"
+    pub phase: String,
+    pub message: String,
 }
 
 /// System-level information
@@ -47,6 +176,37 @@ pub struct SystemInfo {
     pub distribution: Option<String>,
     pub architecture: String,
     pub boot_time: Option<DateTime<Utc>>,
+    /// Virtual machine or container the report was generated in
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub virtualization: Option<Virtualization>,
+}
+
+impl SystemInfo {
+    /// Whether the report describes a virtual machine or container rather
+    /// than physical hardware
+    pub fn is_virtual(&self) -> bool {
+        self.virtualization.as_ref().is_some_and(|virt| virt.kind != VirtualizationKind::BareMetal)
+    }
+}
+
+/// Environment the report was generated in
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Virtualization {
+    pub kind: VirtualizationKind,
+    /// Hypervisor or container runtime in `systemd-detect-virt` terms, e.g.
+    /// "kvm", "vmware", "wsl" or "docker"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub technology: Option<String>,
+    /// What the answer came from, e.g. "systemd-detect-virt" or "dmi"
+    pub source: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum VirtualizationKind {
+    BareMetal,
+    VirtualMachine,
+    Container,
 }
 
 /// CPU information
@@ -62,6 +222,39 @@ pub struct CpuInfo {
     pub cache_l2: Option<u64>,
     pub cache_l3: Option<u64>,
     pub flags: Vec<String>,
+    /// cpufreq scaling governor, e.g. "schedutil" or "powersave"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scaling_governor: Option<String>,
+    /// Microcode revision and update status, when detected on the live system
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub microcode: Option<MicrocodeInfo>,
+}
+
+/// CPU microcode revision compared against installed and known updates
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MicrocodeInfo {
+    /// CPU signature as family-model-stepping in hex, e.g. "06-9e-0a"
+    pub signature: String,
+    /// Revision the CPU is running
+    pub revision: u32,
+    /// Newest revision for this CPU in the installed microcode files
+    pub installed_revision: Option<u32>,
+    /// Newest revision published for this CPU that the tool knows of
+    pub latest_known_revision: Option<u32>,
+    pub status: MicrocodeStatus,
+}
+
+/// Whether a CPU is running the newest available microcode
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MicrocodeStatus {
+    Current,
+    /// A newer revision is installed but was not loaded at boot
+    RebootRequired,
+    /// A newer revision has been published than any installed
+    Outdated,
+    /// Neither installed nor known revisions are available to compare against
+    Unknown,
 }
 
 /// Memory information
@@ -69,7 +262,22 @@ pub struct CpuInfo {
 pub struct MemoryInfo {
     pub total_bytes: u64,
     pub available_bytes: u64,
+    /// Memory modules; at the strict privacy level one entry per kind of
+    /// module, holding their combined size
     pub dimms: Vec<MemoryDimm>,
+    /// Number of modules when `dimms` is aggregated, with privacy noise
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dimm_count: Option<u32>,
+    /// Active swap, from /proc/swaps
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub swap: Option<SwapInfo>,
+}
+
+impl MemoryInfo {
+    /// Number of memory modules
+    pub fn dimm_count(&self) -> usize {
+        self.dimm_count.map_or(self.dimms.len(), |count| count as usize)
+    }
 }
 
 /// Individual memory DIMM
@@ -81,6 +289,40 @@ pub struct MemoryDimm {
     pub manufacturer: Option<String>,
 }
 
+/// Swap configuration of the running system
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SwapInfo {
+    pub areas: Vec<SwapArea>,
+    /// Whether zswap compresses pages before they reach disk swap
+    pub zswap_enabled: Option<bool>,
+}
+
+impl SwapInfo {
+    /// Total size of swap on disk, which hibernation can use
+    pub fn disk_bytes(&self) -> u64 {
+        self.areas.iter().filter(|a| a.kind != SwapKind::Zram).map(|a| a.size_bytes).sum()
+    }
+
+    pub fn has_zram(&self) -> bool {
+        self.areas.iter().any(|a| a.kind == SwapKind::Zram)
+    }
+}
+
+/// Active swap partition, file or zram device
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SwapArea {
+    pub kind: SwapKind,
+    pub size_bytes: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SwapKind {
+    Partition,
+    File,
+    Zram,
+}
+
 /// Storage device information
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct StorageDevice {
@@ -90,6 +332,220 @@ pub struct StorageDevice {
     pub model: String,
     pub vendor: Option<String>,
     pub interface: Option<String>,
+    /// Namespace, PCIe link and health details from nvme-cli
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub nvme: Option<NvmeDetails>,
+    /// SMART health from smartctl; left out at the Strict privacy level
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub health: Option<DriveHealth>,
+    /// Partitions and stacked RAID, LVM and LUKS devices from lsblk
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub topology: Option<StorageTopology>,
+}
+
+/// Software RAID arrays and LVM volume groups of the running system
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StorageStack {
+    /// RAID levels the md driver has loaded, e.g. "raid1"
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub md_personalities: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub raid_arrays: Vec<RaidArray>,
+    /// Volume groups, in the order lvs lists them; their names are left out
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub volume_groups: Vec<VolumeGroup>,
+}
+
+impl StorageStack {
+    pub fn is_empty(&self) -> bool {
+        self.md_personalities.is_empty()
+            && self.raid_arrays.is_empty()
+            && self.volume_groups.is_empty()
+    }
+}
+
+/// A software RAID array run by md or a firmware RAID set run by dmraid
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RaidArray {
+    /// Kernel driver running the array: "md" or "dmraid"
+    pub driver: String,
+    /// e.g. "raid1" or "raid5"; "container" for the metadata container of
+    /// an Intel Matrix or DDF firmware RAID
+    pub level: String,
+    /// Superblock format: "1.2", or "imsm" and "ddf" for firmware RAID
+    pub metadata: Option<String>,
+    pub size_bytes: u64,
+    /// Devices the array is made of, and how many of them work
+    pub raid_devices: u32,
+    pub working_devices: u32,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub members: Vec<RaidMember>,
+    pub health: RaidHealth,
+    /// Resync, recovery, reshape or check in progress
+    pub sync_action: Option<String>,
+}
+
+/// A member device of an md array
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RaidMember {
+    /// Kernel name, e.g. "sda1"
+    pub device: String,
+    /// "active", "faulty", "spare", "write-mostly" or "replacement"
+    pub role: String,
+}
+
+/// Condition of a RAID array
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RaidHealth {
+    Healthy,
+    /// Running with fewer working devices than it should have
+    Degraded,
+    /// Rebuilding redundancy onto a new or returning device
+    Recovering,
+    /// Assembled but not running, e.g. a firmware RAID container
+    Inactive,
+    Failed,
+}
+
+/// An LVM volume group
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VolumeGroup {
+    pub logical_volumes: Vec<LogicalVolume>,
+}
+
+/// An LVM logical volume; its name is left out
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LogicalVolume {
+    /// LVM segment type, e.g. "linear", "striped", "raid1", "thin-pool" or "cache"
+    pub segment_type: String,
+    pub size_bytes: u64,
+    pub active: bool,
+    /// "ok", or the problem lvs reports: "partial", "refresh_needed",
+    /// "mismatches", "reshaping" or "unknown"
+    pub health: String,
+}
+
+/// Partition layout of a drive and the block devices stacked on it
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StorageTopology {
+    /// Partition table type, "gpt" or "dos"
+    pub partition_table: Option<String>,
+    /// Signature on the whole drive, e.g. a RAID member without partitions
+    pub filesystem: Option<BlockFilesystem>,
+    /// Partitions, and devices stacked directly on the drive
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub children: Vec<BlockLayer>,
+}
+
+/// A partition or a device stacked on another: RAID array, LVM volume or LUKS mapping
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BlockLayer {
+    /// lsblk device type, e.g. "part", "raid1", "lvm" or "crypt"
+    pub layer_type: String,
+    pub size_bytes: u64,
+    pub filesystem: Option<BlockFilesystem>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub children: Vec<BlockLayer>,
+}
+
+/// Filesystem or other signature found on a block device
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BlockFilesystem {
+    /// Signature type, e.g. "ext4", "swap", "crypto_LUKS", "LVM2_member"
+    /// or "linux_raid_member"
+    pub fs_type: String,
+    /// Filesystem, LUKS or RAID UUID, anonymized like serial numbers; RAID
+    /// members of one array share it
+    pub anonymized_uuid: Option<String>,
+}
+
+/// Health of a drive from its SMART data
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DriveHealth {
+    /// Overall SMART self-assessment; false when the drive predicts its own failure
+    pub passed: Option<bool>,
+    pub power_on_hours: Option<u64>,
+    /// Sectors remapped to spare sectors
+    pub reallocated_sectors: Option<u64>,
+    /// Unreadable sectors waiting to be remapped
+    pub pending_sectors: Option<u64>,
+    /// Share of the rated SSD endurance used, in percent
+    pub wear_percent: Option<u8>,
+    pub temperature_celsius: Option<i32>,
+}
+
+/// Details of an NVMe drive from nvme-cli
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NvmeDetails {
+    /// Firmware revision of the controller
+    pub firmware: Option<String>,
+    /// Namespace ID of the block device
+    pub namespace: Option<u32>,
+    /// Logical block size in bytes
+    pub sector_size: Option<u32>,
+    pub pcie_link: Option<PcieLink>,
+    pub health: Option<NvmeHealth>,
+}
+
+/// Negotiated and maximum PCI Express link of a device
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PcieLink {
+    /// e.g. "8.0 GT/s PCIe"
+    pub speed: String,
+    /// Number of lanes
+    pub width: u8,
+    pub max_speed: Option<String>,
+    pub max_width: Option<u8>,
+}
+
+/// PCI Express link and resizable BAR of a PCI device
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PcieDeviceLink {
+    /// Vendor:device ID, e.g. "10de:2684"
+    pub pci_id: String,
+    /// PCI class code, e.g. "030000"
+    pub class_code: String,
+    pub link: PcieLink,
+    /// PCIe generation of the negotiated speed, 1 for 2.5 GT/s
+    pub generation: Option<u8>,
+    /// Highest generation the device supports
+    pub max_generation: Option<u8>,
+    /// Running with fewer lanes or at a lower generation than it supports
+    pub degraded: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub resizable_bar: Option<ResizableBar>,
+}
+
+/// Resizable BAR capability of a device
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ResizableBar {
+    /// Index of the resizable BAR, 0 to 5
+    pub bar: u8,
+    pub size_bytes: u64,
+    /// Largest size the device supports
+    pub max_size_bytes: u64,
+}
+
+impl ResizableBar {
+    /// The BAR is at its largest size, so the CPU can map all of it
+    pub fn is_enabled(&self) -> bool {
+        self.size_bytes >= self.max_size_bytes
+    }
+}
+
+/// Health of an NVMe drive from its SMART / health information log
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NvmeHealth {
+    /// Critical warning bits; nonzero when the drive reports a problem
+    pub critical_warning: u8,
+    pub temperature_celsius: Option<i32>,
+    pub available_spare_percent: Option<u8>,
+    /// Vendor estimate of the rated endurance used, may exceed 100
+    pub percent_used: Option<u8>,
+    pub power_on_hours: Option<u64>,
+    pub unsafe_shutdowns: Option<u64>,
+    pub media_errors: Option<u64>,
 }
 
 /// Graphics device information
@@ -100,6 +556,60 @@ pub struct GraphicsDevice {
     pub driver: Option<String>,
     pub memory_bytes: Option<u64>,
     pub pci_id: String,
+    /// Display outputs driven by this GPU, from /sys/class/drm
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub displays: Option<DisplayOutputs>,
+    /// Driver details reported by nvidia-smi for NVIDIA GPUs
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub nvidia: Option<NvidiaDetails>,
+}
+
+/// NVIDIA driver stack of a GPU, from nvidia-smi
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NvidiaDetails {
+    pub driver_version: String,
+    /// Highest CUDA version the driver supports
+    pub cuda_version: Option<String>,
+    pub kernel_module: NvidiaKernelModule,
+}
+
+/// Flavour of the loaded nvidia kernel module
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NvidiaKernelModule {
+    /// The MIT/GPLv2 open kernel module
+    Open,
+    /// The closed source kernel module
+    Proprietary,
+    Unknown,
+}
+
+/// Display connectors and multi-monitor capabilities of a GPU
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DisplayOutputs {
+    pub connectors: Vec<DisplayConnector>,
+    /// Whether a connected DisplayPort display or dock supports Multi-Stream
+    /// Transport, from its DPCD; None when no DisplayPort sink is connected or
+    /// one could not be read
+    pub dp_mst: Option<bool>,
+}
+
+/// A single display connector exposed by a GPU
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DisplayConnector {
+    /// Connector name, e.g. DP-1
+    pub name: String,
+    /// Connector type: DP, HDMI-A, eDP, DVI-D, VGA, etc.
+    pub connector_type: String,
+    pub connected: bool,
+    /// Largest mode advertised by the connected display, e.g. 3840x2160
+    pub max_mode: Option<String>,
+    /// Whether the connector signals hotplug events; analog outputs have no
+    /// hotplug detect line and internal panels are never plugged
+    pub hotplug: bool,
+    /// Whether the connector is an external port rather than an internal panel
+    #[serde(default)]
+    pub external: bool,
 }
 
 /// Network device information
@@ -110,6 +620,23 @@ pub struct NetworkDevice {
     pub model: String,
     pub driver: Option<String>,
     pub anonymized_mac: String,
+    /// Radio capabilities of Wi-Fi devices, from iw
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub wireless_capabilities: Option<WirelessCapabilities>,
+}
+
+/// What a Wi-Fi radio supports, from `iw list`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WirelessCapabilities {
+    /// Frequency bands, e.g. "2.4 GHz", "5 GHz", "6 GHz"
+    pub bands: Vec<String>,
+    /// 802.11 standards beyond the legacy a/b/g ones, e.g. "802.11ax"
+    pub standards: Vec<String>,
+    /// Country code of the regulatory domain in effect, e.g. "DE"; left
+    /// out at the Strict privacy level
+    pub regulatory_domain: Option<String>,
+    pub ap_mode: bool,
+    pub monitor_mode: bool,
 }
 
 /// USB device information
@@ -120,6 +647,23 @@ pub struct UsbDevice {
     pub vendor_name: Option<String>,
     pub product_name: Option<String>,
     pub usb_version: Option<String>,
+    /// Device class name, from the first interface for class 00 devices
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub device_class: Option<String>,
+    /// Device subclass code (hex)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub device_subclass: Option<String>,
+    /// Negotiated link speed in Mbit/s
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub speed_mbps: Option<f64>,
+    /// Position in the USB tree as bus and port chain (e.g., "3-1.2")
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub port_path: Option<String>,
+    /// Maximum power draw declared by the device, in mA
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_power_ma: Option<u16>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub anonymized_serial: Option<String>,
 }
 
 /// Audio device information
@@ -129,6 +673,369 @@ pub struct AudioDevice {
     pub model: String,
     pub driver: Option<String>,
     pub device_type: String, // playback, capture, etc.
+    /// Codecs on the card's HD Audio link, from /proc/asound
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub codecs: Vec<AudioCodec>,
+    /// Sound server profiles of the card
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub profiles: Option<AudioCardProfiles>,
+}
+
+/// An HD Audio codec, e.g. the analog codec or a GPU's HDMI/DisplayPort codec
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AudioCodec {
+    /// Codec name, e.g. "Realtek ALC287"
+    pub name: String,
+    /// Codec vendor and device ID, e.g. "10ec:0287"
+    pub vendor_id: Option<String>,
+    /// Subsystem ID the board vendor programmed, which selects driver quirks
+    pub subsystem_id: Option<String>,
+}
+
+/// Card profiles offered by PipeWire or PulseAudio
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AudioCardProfiles {
+    /// Profile in use, e.g. "output:analog-stereo+input:analog-stereo"
+    pub active: Option<String>,
+    /// Profiles that can be selected; HDMI profiles are missing while no
+    /// display with audio is attached
+    pub available: Vec<String>,
+}
+
+/// A radio transmitter registered with rfkill
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RadioState {
+    /// rfkill type: "wlan", "bluetooth", "wwan", "nfc", ...
+    pub kind: String,
+    /// Kernel name of the radio, e.g. "phy0" or "hci0"
+    pub name: String,
+    /// Vendor:device ID of the PCI or USB device providing the radio
+    pub device_id: Option<String>,
+    pub driver: Option<String>,
+    /// Turned off in software, by `rfkill block` or airplane mode
+    pub soft_blocked: bool,
+    /// Turned off by a wireless switch, hotkey or firmware setting
+    pub hard_blocked: bool,
+    /// Known Wi-Fi/Bluetooth coexistence problem of the chip
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub coexistence: Option<CoexistenceQuirk>,
+}
+
+/// A keyboard, pointing device, touchscreen or tablet
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InputDevice {
+    pub name: String,
+    /// "keyboard", "mouse", "touchpad", "touchscreen" or "tablet"
+    pub device_type: String,
+    /// "usb", "i2c", "ps2", "bluetooth", "spi", "rmi", "host" or "other"
+    pub bus: String,
+    /// Vendor:product ID from the input layer, lowercase hex
+    pub device_id: String,
+    /// Kernel driver, e.g. "atkbd", "psmouse" or "hid-multitouch"
+    pub driver: Option<String>,
+    /// Userspace driver: "libinput", or the Xorg driver configured for touchpads
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub userspace_driver: Option<String>,
+}
+
+/// Printers and scanners the machine can reach
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Peripherals {
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub printers: Vec<Printer>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub scanners: Vec<Scanner>,
+}
+
+impl Peripherals {
+    pub fn is_empty(&self) -> bool {
+        self.printers.is_empty() && self.scanners.is_empty()
+    }
+}
+
+/// A printer found by a CUPS backend
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Printer {
+    /// Make and model, e.g. "HP LaserJet Pro M404-M405"
+    pub make_and_model: String,
+    /// "usb", "network", "serial" or "parallel"
+    pub connection: String,
+    /// CUPS backend that found it, e.g. "usb", "dnssd" or "ipp"
+    pub backend: String,
+    /// Page description languages from the IEEE 1284 device ID, e.g. "PCL"
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub languages: Vec<String>,
+    /// Prints without a vendor driver through IPP Everywhere or AirPrint
+    pub driverless: bool,
+}
+
+/// A scanner found by a SANE backend
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Scanner {
+    pub vendor: String,
+    pub model: String,
+    /// Kind of device as SANE describes it, e.g. "flatbed scanner" or "all-in-one"
+    pub device_type: Option<String>,
+    /// SANE backend driving it, e.g. "genesys", "hpaio" or "airscan"
+    pub backend: String,
+    /// "usb" or "network"
+    pub connection: String,
+}
+
+/// A hardware monitoring chip with a driver registered with hwmon
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SensorChip {
+    /// hwmon chip name, e.g. "coretemp", "k10temp" or "nct6798"
+    pub name: String,
+    /// Bus of the chip's device, e.g. "pci", "i2c" or "platform"; "virtual" without one
+    pub bus: Option<String>,
+    /// Kernel driver bound to the chip's device
+    pub driver: Option<String>,
+    /// Number of temperature sensors
+    pub temperatures: u32,
+    /// Number of fan speed sensors
+    pub fans: u32,
+    /// Number of voltage sensors
+    pub voltages: u32,
+}
+
+/// System firmware and boot security state
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FirmwareInfo {
+    pub bios_vendor: Option<String>,
+    pub bios_version: Option<String>,
+    /// Release date as the firmware reports it, usually MM/DD/YYYY
+    pub bios_date: Option<String>,
+    pub boot_mode: BootMode,
+    /// Secure Boot state; None when it could not be read or without UEFI
+    pub secure_boot: Option<bool>,
+    pub tpm_present: bool,
+    /// TPM specification version, "2.0" or "1.2"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tpm_version: Option<String>,
+}
+
+/// How the running system was booted
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BootMode {
+    Uefi,
+    /// BIOS or UEFI's compatibility support module
+    Legacy,
+}
+
+/// Coexistence problem of a Wi-Fi/Bluetooth combo chip
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CoexistenceQuirk {
+    pub chip: String,
+    pub note: String,
+}
+
+impl RadioState {
+    pub fn is_blocked(&self) -> bool {
+        self.soft_blocked || self.hard_blocked
+    }
+
+    /// Readable radio type, e.g. "Wi-Fi"
+    pub fn label(&self) -> &str {
+        match self.kind.as_str() {
+            "wlan" => "Wi-Fi",
+            "bluetooth" => "Bluetooth",
+            "wwan" => "Mobile broadband",
+            "nfc" => "NFC",
+            "gps" => "GPS",
+            "uwb" => "Ultra-wideband",
+            "fm" => "FM radio",
+            other => other,
+        }
+    }
+
+    /// Steps to turn a blocked radio back on
+    ///
+    /// A blocked radio still shows its device and driver, so without this it
+    /// looks like working hardware.
+    pub fn unblock_steps(&self) -> Option<String> {
+        match (self.hard_blocked, self.soft_blocked) {
+            (true, soft) => Some(format!(
+                "{} ({}) is switched off by a wireless switch, hotkey or firmware setting, which `rfkill unblock` cannot override. Turn on the switch or Fn hotkey, or enable the radio in the BIOS/UEFI setup{}.",
+                self.label(),
+                self.name,
+                if soft { format!(", then run `rfkill unblock {}`", self.kind) } else { String::new() }
+            )),
+            (false, true) => Some(format!(
+                "{} ({}) is turned off in software. Turn off airplane mode or run `rfkill unblock {}`; if it is blocked again after a reboot, check that systemd-rfkill or your desktop does not restore the blocked state.",
+                self.label(),
+                self.name,
+                self.kind
+            )),
+            (false, false) => None,
+        }
+    }
+}
+
+/// ALSA playback outputs and the one the default sink plays to
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AudioRouting {
+    pub outputs: Vec<AudioOutput>,
+    /// What chose the default: "pipewire", "pulseaudio" or "alsa"
+    pub sound_server: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sound_server_version: Option<String>,
+    /// Index into `outputs` of the default output, if it could be resolved
+    pub default_output: Option<usize>,
+    /// The sound server has no real output and plays to a dummy sink
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub dummy_output: bool,
+}
+
+/// A playback PCM of an ALSA card
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AudioOutput {
+    pub card: u32,
+    /// Card name, e.g. "HDA Intel PCH"
+    pub card_name: String,
+    pub device: u32,
+    /// PCM name, e.g. "ALC3246 Analog" or "HDMI 0"
+    pub name: String,
+    pub kind: AudioOutputKind,
+    /// Whether a display with audio is attached; only known for HDMI/DisplayPort
+    pub connected: Option<bool>,
+    /// PipeWire/PulseAudio sink name of the output, when it follows the usual scheme
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sink_name: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AudioOutputKind {
+    Analog,
+    /// HDMI or DisplayPort audio through a GPU
+    Hdmi,
+    /// S/PDIF
+    Digital,
+    Usb,
+    Other,
+}
+
+impl AudioOutput {
+    pub fn label(&self) -> String {
+        format!("{} on {} (card {}, device {})", self.name, self.card_name, self.card, self.device)
+    }
+}
+
+impl AudioRouting {
+    /// The output the default sink plays to
+    pub fn current_output(&self) -> Option<&AudioOutput> {
+        self.default_output.and_then(|i| self.outputs.get(i))
+    }
+
+    /// Diagnose the default route, with a fix, when sound is unlikely to reach a listener
+    ///
+    /// Covers the common case of a GPU's HDMI output being the default while no
+    /// display with speakers is attached to it, and the reverse case of a display
+    /// with audio being attached while another card is the default.
+    pub fn routing_issue(&self) -> Option<String> {
+        if self.dummy_output {
+            return Some(format!(
+                "{} plays to a dummy output although ALSA lists {} playback device(s). Restart the sound server (systemctl --user restart wireplumber pipewire pipewire-pulse) and check that your user may access /dev/snd.",
+                self.server_name(),
+                self.outputs.len()
+            ));
+        }
+        let current = self.current_output()?;
+        let connected_hdmi = self
+            .outputs
+            .iter()
+            .find(|o| o.kind == AudioOutputKind::Hdmi && o.connected == Some(true));
+
+        if current.kind == AudioOutputKind::Hdmi && current.connected == Some(false) {
+            let target = connected_hdmi
+                .or_else(|| self.outputs.iter().find(|o| o.kind == AudioOutputKind::Analog))?;
+            return Some(format!(
+                "The default output {} has no display with audio attached, so there is no sound. Switch to {}: {}",
+                current.label(),
+                target.label(),
+                self.switch_command(target)
+            ));
+        }
+
+        let target = connected_hdmi?;
+        if current.kind != AudioOutputKind::Hdmi && current.card != target.card {
+            return Some(format!(
+                "A display with audio is attached to {}, but the default output is {}. If you expect sound from the display, switch with: {}",
+                target.label(),
+                current.label(),
+                self.switch_command(target)
+            ));
+        }
+        None
+    }
+
+    fn server_name(&self) -> &str {
+        match self.sound_server.as_str() {
+            "pipewire" => "PipeWire",
+            "pulseaudio" => "PulseAudio",
+            _ => "ALSA",
+        }
+    }
+
+    /// Command making `target` the default output
+    fn switch_command(&self, target: &AudioOutput) -> String {
+        match (self.sound_server.as_str(), &target.sink_name) {
+            ("alsa", _) => format!(
+                "add \"defaults.pcm.card {0}\", \"defaults.pcm.device {1}\" and \"defaults.ctl.card {0}\" to ~/.asoundrc",
+                target.card, target.device
+            ),
+            (_, Some(sink)) => format!("pactl set-default-sink {}", sink),
+            _ => "pick the output in your desktop's sound settings, or run pactl list short sinks and pactl set-default-sink <name>".to_string(),
+        }
+    }
+}
+
+/// Device that could not be identified or matched to a driver
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UnknownDevice {
+    pub bus: String, // pci, usb
+    pub vendor_id: String,
+    pub device_id: String,
+    pub class_code: Option<String>,
+    pub modalias: Option<String>,
+    /// Kernel log lines mentioning the device, with identifiers replaced;
+    /// empty at the strict privacy level
+    pub dmesg_lines: Vec<String>,
+}
+
+/// Age and driver end-of-life information for a detected component
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LifecycleNotice {
+    /// Hardware generation or family, e.g. "NVIDIA Kepler (GK1xx)"
+    pub generation: String,
+    /// PCI ID ("vendor:device") of the component, if it is a PCI device
+    pub device_id: Option<String>,
+    pub release_year: u32,
+    pub age_years: u32,
+    /// Legacy driver branch the hardware is limited to
+    pub legacy_driver: Option<String>,
+    /// Set when the running kernel is too new for the legacy driver
+    pub warning: Option<String>,
+}
+
+/// Device whose firmware can be updated from the LVFS through fwupd
+///
+/// Device IDs, GUIDs and serial numbers reported by fwupd are not kept, as
+/// they identify the individual device.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FirmwareDevice {
+    pub name: String,
+    pub vendor: Option<String>,
+    /// fwupd plugin handling the device, e.g. "uefi_capsule" or "nvme"
+    pub plugin: Option<String>,
+    pub current_version: Option<String>,
+    /// Newest upgrade offered by the LVFS, if one is pending
+    pub update_version: Option<String>,
+    pub update_summary: Option<String>,
+    /// Urgency set by the vendor: "low", "medium", "high" or "critical"
+    pub update_urgency: Option<String>,
 }
 
 /// Kernel compatibility and support information
@@ -142,6 +1049,69 @@ pub struct KernelCompatibilityInfo {
     pub device_support_details: Vec<DeviceCompatibility>,
     pub missing_modules: Vec<String>,
     pub config_recommendations: Vec<String>,
+    /// Parameters of the modules driving detected devices or configured
+    /// through modprobe.d or the kernel command line
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub module_parameters: Vec<ModuleParameters>,
+    /// Modules modprobe is configured not to load
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub blacklisted_modules: Vec<BlacklistedModule>,
+    /// Out-of-tree modules managed by DKMS, per kernel
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub dkms_modules: Vec<DkmsModuleStatus>,
+}
+
+/// Build state of a DKMS module for one kernel, from `dkms status`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DkmsModuleStatus {
+    pub module: String,
+    pub version: String,
+    /// Kernel release; None for sources added but not built for any kernel
+    pub kernel: Option<String>,
+    pub state: DkmsState,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DkmsState {
+    /// Source registered with `dkms add`
+    Added,
+    /// Compiled but not installed into the kernel's module tree
+    Built,
+    Installed,
+    /// Source tree missing or damaged
+    Broken,
+    /// Installed for an earlier kernel but not rebuilt for the running one,
+    /// usually because the build failed after a kernel update
+    NotBuilt,
+}
+
+/// Parameter values of a loaded module, from `/sys/module/<module>/parameters`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ModuleParameters {
+    pub module: String,
+    pub parameters: BTreeMap<String, String>,
+}
+
+/// A module kept from loading
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BlacklistedModule {
+    pub module: String,
+    pub method: BlacklistMethod,
+    /// modprobe.d file or "/proc/cmdline"
+    pub source: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BlacklistMethod {
+    /// `blacklist <module>`: not loaded for its device aliases, but still
+    /// loaded when requested by name or as a dependency
+    Blacklist,
+    /// `install <module> /bin/false`: every load attempt fails
+    Install,
+    /// `module_blacklist=` or `modprobe.blacklist=` on the kernel command line
+    Cmdline,
 }
 
 /// Individual device compatibility information
@@ -156,8 +1126,51 @@ pub struct DeviceCompatibility {
     pub notes: Option<String>,
 }
 
-impl Default for PrivacyLevel {
-    fn default() -> Self {
-        Self::Basic
+impl HardwareReport {
+    /// Load a hardware report from a JSON or YAML file, or stdin when the path is `-`
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = if is_stdin_path(path) {
+            let mut content = String::new();
+            std::io::Read::read_to_string(&mut std::io::stdin(), &mut content).map_err(|e| {
+                LxHwError::SystemError { message: format!("Failed to read stdin: {}", e) }
+            })?;
+            content
+        } else {
+            std::fs::read_to_string(path).map_err(|e| LxHwError::SystemError {
+                message: format!("Failed to read {}: {}", path.display(), e),
+            })?
+        };
+        Self::parse(&content, path)
+    }
+
+    /// Parse report content, using the file extension as a format hint
+    pub fn parse(content: &str, path: &Path) -> Result<Self> {
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("json") => serde_json::from_str(content).map_err(|e| LxHwError::InvalidInput {
+                message: format!("Invalid JSON in {}: {}", path.display(), e),
+            }),
+            Some("yaml") | Some("yml") => {
+                serde_yaml::from_str(content).map_err(|e| LxHwError::InvalidInput {
+                    message: format!("Invalid YAML in {}: {}", path.display(), e),
+                })
+            }
+            _ => {
+                // Try JSON first, then YAML
+                serde_json::from_str(content).or_else(|_| {
+                    serde_yaml::from_str(content).map_err(|e| LxHwError::InvalidInput {
+                        message: format!(
+                            "Could not parse {} as JSON or YAML: {}",
+                            path.display(),
+                            e
+                        ),
+                    })
+                })
+            }
+        }
     }
 }
+
+/// Whether a path argument refers to stdin/stdout (`-`)
+pub fn is_stdin_path(path: &Path) -> bool {
+    path == Path::new("-")
+}
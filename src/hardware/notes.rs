@@ -0,0 +1,227 @@
+//! Notes users attach to the components of their report
+//!
+//! A note records what detection cannot see, usually a workaround:
+//! `--annotate 8086:9dc8="sound works only after options snd-hda-intel
+//! model=alc255-acer"`. Notes are keyed by a component's vendor:device ID
+//! as it appears in the report, or by a category name for components
+//! reported without one. The indexer adds them to the workaround lists of
+//! the component they name.
+//!
+//! Notes are free text written by a person, so they are checked for
+//! personal data when they are added and again when a report is validated.
+
+use super::HardwareReport;
+use crate::errors::{LxHwError, Result};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::OnceLock;
+
+/// Longest note accepted, in characters
+pub const MAX_NOTE_LENGTH: usize = 500;
+
+/// Keys for notes on a whole category rather than one device
+pub const CATEGORY_KEYS: &[&str] =
+    &["system", "cpu", "memory", "storage", "graphics", "network", "usb", "audio", "input"];
+
+/// A note on one component
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UserNote {
+    /// Vendor:device ID of the component, or one of [`CATEGORY_KEYS`]
+    pub component: String,
+    pub note: String,
+}
+
+impl UserNote {
+    /// Parse a `component_key=note` argument
+    pub fn parse(argument: &str) -> Result<Self> {
+        let (component, note) =
+            argument.split_once('=').ok_or_else(|| LxHwError::InvalidInput {
+                message: format!(
+                    "Invalid annotation '{}': expected component_key=\"note\"",
+                    argument
+                ),
+            })?;
+        let note = note.trim();
+        let note = note.strip_prefix('"').and_then(|note| note.strip_suffix('"')).unwrap_or(note);
+        Ok(Self { component: component.trim().to_ascii_lowercase(), note: note.trim().to_string() })
+    }
+
+    /// Whether the note is on a category rather than a device
+    pub fn is_category(&self) -> bool {
+        CATEGORY_KEYS.contains(&self.component.as_str())
+    }
+
+    /// Check length and personal data
+    pub fn check(&self) -> Result<()> {
+        if self.note.is_empty() {
+            return Err(LxHwError::InvalidInput {
+                message: format!("The note on {} is empty", self.component),
+            });
+        }
+        let length = self.note.chars().count();
+        if length > MAX_NOTE_LENGTH {
+            return Err(LxHwError::InvalidInput {
+                message: format!(
+                    "The note on {} has {} characters; shorten it to {} or fewer",
+                    self.component, length, MAX_NOTE_LENGTH
+                ),
+            });
+        }
+        if let Some(kind) = find_personal_data(&self.note) {
+            return Err(LxHwError::PrivacyError(format!(
+                "The note on {} appears to contain personal data ({}); remove it and try again",
+                self.component, kind
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// Keys the components of a report can be annotated with, and their names
+pub fn component_keys(report: &HardwareReport) -> BTreeMap<String, String> {
+    let mut keys: BTreeMap<String, String> = CATEGORY_KEYS
+        .iter()
+        .map(|key| (key.to_string(), format!("All {} components", key)))
+        .collect();
+    if let Some(kernel_support) = &report.kernel_support {
+        for device in &kernel_support.device_support_details {
+            keys.insert(device.device_id.to_ascii_lowercase(), device.device_name.clone());
+        }
+    }
+    for device in &report.graphics {
+        keys.insert(
+            device.pci_id.to_ascii_lowercase(),
+            format!("{} {}", device.vendor, device.model),
+        );
+    }
+    for device in &report.usb {
+        let name = [&device.vendor_name, &device.product_name]
+            .iter()
+            .filter_map(|name| name.as_deref())
+            .collect::<Vec<_>>()
+            .join(" ");
+        keys.entry(format!("{}:{}", device.vendor_id, device.product_id).to_ascii_lowercase())
+            .or_insert(name);
+    }
+    for device in &report.input {
+        keys.entry(device.device_id.clone()).or_insert_with(|| device.name.clone());
+    }
+    for device in &report.unknown_devices {
+        keys.entry(format!("{}:{}", device.vendor_id, device.device_id).to_ascii_lowercase())
+            .or_insert_with(|| format!("Unknown {} device", device.bus));
+    }
+    keys
+}
+
+/// Check notes and add them to a report
+///
+/// Every note must name a component of the report; notes already present
+/// are not added twice.
+pub fn annotate(report: &mut HardwareReport, notes: Vec<UserNote>) -> Result<()> {
+    let keys = component_keys(report);
+    for note in notes {
+        if !keys.contains_key(&note.component) {
+            return Err(LxHwError::InvalidInput {
+                message: format!(
+                    "No component with key '{}' in the report; use a vendor:device ID such as \
+                     those shown by `lspci -nn` or `lsusb`, or one of: {}",
+                    note.component,
+                    CATEGORY_KEYS.join(", ")
+                ),
+            });
+        }
+        note.check()?;
+        if !report.user_notes.contains(&note) {
+            report.user_notes.push(note);
+        }
+    }
+    Ok(())
+}
+
+/// Kind of personal data a text appears to contain
+///
+/// Extends the report-wide PII patterns with data people tend to paste
+/// from their own terminal: MAC addresses, home directories and prompts.
+pub fn find_personal_data(text: &str) -> Option<&'static str> {
+    patterns().find(|(regex, _)| regex.is_match(text)).map(|(_, description)| *description)
+}
+
+/// Replace everything [`find_personal_data`] would find
+pub fn redact_personal_data(text: &str, replacement: &str) -> String {
+    patterns().fold(text.to_string(), |text, (regex, _)| {
+        regex.replace_all(&text, replacement).into_owned()
+    })
+}
+
+fn patterns() -> impl Iterator<Item = &'static (Regex, &'static str)> {
+    crate::validation::privacy::get_pii_patterns().iter().chain(note_patterns())
+}
+
+static NOTE_PATTERNS: OnceLock<Vec<(Regex, &'static str)>> = OnceLock::new();
+
+fn note_patterns() -> &'static Vec<(Regex, &'static str)> {
+    NOTE_PATTERNS.get_or_init(|| {
+        [
+            (r"\b[0-9A-Fa-f]{2}(?:[:-][0-9A-Fa-f]{2}){5}\b", "MAC address"),
+            (r"(?:/home/|/Users/)[^/\s]+", "home directory"),
+            (r"\b[a-z_][a-z0-9_-]*@[A-Za-z0-9-]+:[~/]", "shell prompt"),
+        ]
+        .into_iter()
+        .map(|(pattern, description)| (Regex::new(pattern).expect("Valid regex"), description))
+        .collect()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hardware::examples::ExampleGenerator;
+
+    #[test]
+    fn test_parse_annotation() {
+        let note =
+            UserNote::parse("8086:9DC8=\"options snd-hda-intel model=alc255-acer\"").unwrap();
+        assert_eq!(note.component, "8086:9dc8");
+        assert_eq!(note.note, "options snd-hda-intel model=alc255-acer");
+        assert!(!note.is_category());
+        assert!(UserNote::parse("audio=needs a firmware update").unwrap().is_category());
+        assert!(UserNote::parse("no separator").is_err());
+    }
+
+    #[test]
+    fn test_annotate_checks_keys_and_personal_data() {
+        let mut report = ExampleGenerator::new(7).report(0);
+        let device = report.graphics[0].pci_id.clone();
+
+        let note = |component: &str, text: &str| UserNote {
+            component: component.to_string(),
+            note: text.to_string(),
+        };
+        annotate(&mut report, vec![note(&device, "Needs nomodeset on 6.1")]).unwrap();
+        annotate(&mut report, vec![note(&device, "Needs nomodeset on 6.1")]).unwrap();
+        assert_eq!(report.user_notes.len(), 1);
+
+        assert!(matches!(
+            annotate(&mut report, vec![note("ffff:0000", "ghost")]),
+            Err(LxHwError::InvalidInput { .. })
+        ));
+        for leak in [
+            "Firmware from /home/alice/Downloads/fw.bin",
+            "MAC 3c:7c:3f:12:34:56 drops off",
+            "mail me at alice@example.com",
+        ] {
+            assert!(
+                matches!(
+                    annotate(&mut report, vec![note("audio", leak)]),
+                    Err(LxHwError::PrivacyError(_))
+                ),
+                "{}",
+                leak
+            );
+        }
+        let long = "x".repeat(MAX_NOTE_LENGTH + 1);
+        assert!(annotate(&mut report, vec![note("audio", &long)]).is_err());
+        assert_eq!(report.user_notes.len(), 1);
+    }
+}
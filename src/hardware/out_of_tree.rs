@@ -0,0 +1,183 @@
+//! Out-of-tree drivers for devices the kernel has no driver for
+//!
+//! Maps PCI and USB IDs from `data/out-of-tree-drivers.toml` to driver
+//! projects maintained outside the kernel, with the commands that build and
+//! install them through DKMS and the distribution packages that ship them,
+//! so an unsupported device comes with a concrete way to make it work.
+
+use crate::errors::{LxHwError, Result};
+use crate::hardware::lifecycle::kernel_series;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+
+/// Built-in out-of-tree driver data, compiled into the binary
+const OUT_OF_TREE_DATA: &str = include_str!("../../data/out-of-tree-drivers.toml");
+
+/// Buses entries can be matched on
+const BUSES: [&str; 2] = ["pci", "usb"];
+
+/// A driver project maintained outside the kernel
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct OutOfTreeDriver {
+    pub name: String,
+    pub project: String,
+    pub bus: String,
+    pub ids: Vec<String>,
+    pub dkms_module: String,
+    /// Commands building and installing the module from source
+    #[serde(default)]
+    pub install: Vec<String>,
+    /// Package name by distribution family
+    #[serde(default)]
+    pub packages: BTreeMap<String, String>,
+    /// Kernel series whose in-tree driver supports the devices
+    pub mainline: Option<String>,
+    pub notes: Option<String>,
+}
+
+impl OutOfTreeDriver {
+    /// Whether a kernel version has an in-tree driver for the devices
+    pub fn in_mainline(&self, kernel_version: &str) -> bool {
+        match (kernel_series(kernel_version), self.mainline.as_deref().and_then(kernel_series)) {
+            (Some(kernel), Some(mainline)) => kernel >= mainline,
+            _ => false,
+        }
+    }
+
+    /// Commands installing the driver on a distribution family ("debian",
+    /// "fedora" or "arch"), preferring a packaged build over the source
+    pub fn install_commands(&self, family: Option<&str>) -> Vec<String> {
+        let package = family.and_then(|family| Some((family, self.packages.get(family)?)));
+        if let Some(command) =
+            package.and_then(|(family, package)| install_package(family, package))
+        {
+            return vec![command];
+        }
+
+        let mut commands = if self.install.is_empty() {
+            self.packages
+                .iter()
+                .map(|(family, package)| format!("# Packaged as {} for {}", package, family))
+                .collect()
+        } else {
+            self.install.clone()
+        };
+        if let (Some("arch"), Some(package)) = (family, self.packages.get("aur")) {
+            commands.push(format!("# Also available from the AUR as {}", package));
+        }
+        commands
+    }
+}
+
+/// Package manager command installing a package on a distribution family
+fn install_package(family: &str, package: &str) -> Option<String> {
+    let command = match family {
+        "debian" => "sudo apt install",
+        "fedora" => "sudo dnf install",
+        "arch" => "sudo pacman -S",
+        _ => return None,
+    };
+    Some(format!("{} {}", command, package))
+}
+
+#[derive(Debug, Deserialize)]
+struct OutOfTreeFile {
+    #[serde(default)]
+    driver: Vec<OutOfTreeDriver>,
+}
+
+/// Lookup table of out-of-tree drivers
+#[derive(Debug)]
+pub struct OutOfTreeDatabase {
+    drivers: Vec<OutOfTreeDriver>,
+}
+
+impl OutOfTreeDatabase {
+    /// Database from `data/out-of-tree-drivers.toml`
+    pub fn builtin() -> Self {
+        Self::parse(OUT_OF_TREE_DATA).expect("built-in out-of-tree driver data must be valid")
+    }
+
+    /// Parse out-of-tree driver data from TOML
+    pub fn parse(content: &str) -> Result<Self> {
+        let file: OutOfTreeFile = toml::from_str(content).map_err(|e| {
+            LxHwError::ConfigError(format!("Invalid out-of-tree driver data: {}", e))
+        })?;
+
+        for driver in &file.driver {
+            if !BUSES.contains(&driver.bus.as_str()) {
+                return Err(LxHwError::ConfigError(format!(
+                    "Invalid bus for {}: {}",
+                    driver.name, driver.bus
+                )));
+            }
+            if let Some(id) = driver.ids.iter().find(|id| id.split_once(':').is_none()) {
+                return Err(LxHwError::ConfigError(format!(
+                    "Invalid device ID for {}: {}",
+                    driver.name, id
+                )));
+            }
+        }
+
+        Ok(Self { drivers: file.driver })
+    }
+
+    /// Driver for a "vendor:device" ID on a bus
+    pub fn lookup(&self, bus: &str, device_id: &str) -> Option<&OutOfTreeDriver> {
+        self.drivers.iter().find(|driver| {
+            driver.bus == bus && driver.ids.iter().any(|id| id.eq_ignore_ascii_case(device_id))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_builtin_data_parses() {
+        let db = OutOfTreeDatabase::builtin();
+        assert!(!db.drivers.is_empty());
+        assert!(db
+            .drivers
+            .iter()
+            .all(|driver| !driver.install.is_empty() || !driver.packages.is_empty()));
+    }
+
+    #[test]
+    fn test_lookup_and_install_commands() {
+        let db = OutOfTreeDatabase::builtin();
+        let rtl8821ce = db.lookup("pci", "10EC:C821").unwrap();
+        assert_eq!(rtl8821ce.dkms_module, "rtl8821ce");
+        assert!(rtl8821ce.in_mainline("5.15.0-91-generic"));
+        assert!(!rtl8821ce.in_mainline("5.4.0-150-generic"));
+        assert_eq!(
+            rtl8821ce.install_commands(Some("debian")),
+            vec!["sudo apt install rtl8821ce-dkms"]
+        );
+
+        let xone = db.lookup("usb", "045e:02fe").unwrap();
+        assert!(db.lookup("pci", "045e:02fe").is_none());
+        assert!(!xone.in_mainline("6.10.0"));
+        let commands = xone.install_commands(Some("arch"));
+        assert_eq!(commands.first().map(String::as_str), Some(xone.install[0].as_str()));
+        assert_eq!(commands.last().unwrap(), "# Also available from the AUR as xone-dkms");
+
+        let wl = db.lookup("pci", "14e4:43a0").unwrap();
+        assert_eq!(wl.install_commands(Some("fedora")), vec!["sudo dnf install akmod-wl"]);
+        assert!(wl
+            .install_commands(None)
+            .iter()
+            .all(|command| command.starts_with("# Packaged as")));
+
+        let isa = r#"
+[[driver]]
+name = "isa"
+project = ""
+bus = "isa"
+ids = []
+dkms_module = "isa"
+"#;
+        assert!(OutOfTreeDatabase::parse(isa).is_err());
+    }
+}
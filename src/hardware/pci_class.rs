@@ -0,0 +1,458 @@
+//! PCI class code names
+//!
+//! Names for every class, subclass and programming interface defined by the
+//! PCI-SIG, following the `C` section of pci.ids. Class codes are accepted in
+//! the forms detection tools print them: "03" (class), "0403" (class and
+//! subclass, as in `lspci -n`) and "0x0c0330" (with programming interface, as
+//! in sysfs). Categories for the component index come from the taxonomy; this
+//! table only provides names.
+
+use std::fmt;
+
+/// Parsed PCI class code
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct PciClassCode {
+    pub class: u8,
+    pub subclass: Option<u8>,
+    pub prog_if: Option<u8>,
+}
+
+impl PciClassCode {
+    /// Parse a hex class code of 2, 4 or 6 digits, with or without "0x"
+    pub fn parse(code: &str) -> Option<Self> {
+        let code = code.trim();
+        let code = code.strip_prefix("0x").or_else(|| code.strip_prefix("0X")).unwrap_or(code);
+        if !code.is_ascii() || !matches!(code.len(), 2 | 4 | 6) {
+            return None;
+        }
+        let byte = |i: usize| u8::from_str_radix(code.get(i..i + 2)?, 16).ok();
+        Some(Self {
+            class: byte(0)?,
+            subclass: if code.len() >= 4 { Some(byte(2)?) } else { None },
+            prog_if: if code.len() == 6 { Some(byte(4)?) } else { None },
+        })
+    }
+
+    pub fn class_name(&self) -> Option<&'static str> {
+        self.class_entry().map(|class| class.name)
+    }
+
+    pub fn subclass_name(&self) -> Option<&'static str> {
+        self.subclass_entry().map(|subclass| subclass.name)
+    }
+
+    pub fn prog_if_name(&self) -> Option<&'static str> {
+        let prog_if = self.prog_if?;
+        self.subclass_entry()?
+            .prog_ifs
+            .iter()
+            .find(|(code, _)| *code == prog_if)
+            .map(|(_, name)| *name)
+    }
+
+    /// Most specific name, e.g. "Audio device" or "USB controller (XHCI)"
+    ///
+    /// Falls back to the class name for unknown subclasses; None when the
+    /// class itself is unknown.
+    pub fn name(&self) -> Option<String> {
+        let Some(subclass) = self.subclass_name() else {
+            return self.class_name().map(String::from);
+        };
+        Some(match self.prog_if_name() {
+            Some(prog_if) => format!("{} ({})", subclass, prog_if),
+            None => subclass.to_string(),
+        })
+    }
+
+    fn class_entry(&self) -> Option<&'static ClassEntry> {
+        CLASSES.iter().find(|class| class.code == self.class)
+    }
+
+    fn subclass_entry(&self) -> Option<&'static SubclassEntry> {
+        let subclass = self.subclass?;
+        self.class_entry()?.subclasses.iter().find(|entry| entry.code == subclass)
+    }
+}
+
+impl fmt::Display for PciClassCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:02x}", self.class)?;
+        if let Some(subclass) = self.subclass {
+            write!(f, "{:02x}", subclass)?;
+        }
+        if let Some(prog_if) = self.prog_if {
+            write!(f, "{:02x}", prog_if)?;
+        }
+        Ok(())
+    }
+}
+
+/// Name of a class code string, None when it cannot be parsed or is unknown
+pub fn describe(code: &str) -> Option<String> {
+    PciClassCode::parse(code)?.name()
+}
+
+struct ClassEntry {
+    code: u8,
+    name: &'static str,
+    subclasses: &'static [SubclassEntry],
+}
+
+struct SubclassEntry {
+    code: u8,
+    name: &'static str,
+    prog_ifs: &'static [(u8, &'static str)],
+}
+
+const fn sub(code: u8, name: &'static str) -> SubclassEntry {
+    SubclassEntry { code, name, prog_ifs: &[] }
+}
+
+const fn sub_if(
+    code: u8,
+    name: &'static str,
+    prog_ifs: &'static [(u8, &'static str)],
+) -> SubclassEntry {
+    SubclassEntry { code, name, prog_ifs }
+}
+
+const CLASSES: &[ClassEntry] = &[
+    ClassEntry {
+        code: 0x00,
+        name: "Unclassified device",
+        subclasses: &[
+            sub(0x00, "Non-VGA unclassified device"),
+            sub(0x01, "VGA compatible unclassified device"),
+            sub(0x05, "Image coprocessor"),
+        ],
+    },
+    ClassEntry {
+        code: 0x01,
+        name: "Mass storage controller",
+        subclasses: &[
+            sub(0x00, "SCSI storage controller"),
+            sub_if(
+                0x01,
+                "IDE interface",
+                &[
+                    (0x00, "ISA Compatibility mode-only controller"),
+                    (0x05, "PCI native mode-only controller"),
+                    (0x0a, "ISA Compatibility mode controller, supports PCI native mode"),
+                    (0x0f, "PCI native mode controller, supports ISA compatibility mode"),
+                    (0x80, "ISA Compatibility mode-only controller, supports bus mastering"),
+                    (0x85, "PCI native mode-only controller, supports bus mastering"),
+                    (
+                        0x8a,
+                        "ISA Compatibility mode controller, supports PCI native mode and bus mastering",
+                    ),
+                    (
+                        0x8f,
+                        "PCI native mode controller, supports ISA compatibility mode and bus mastering",
+                    ),
+                ],
+            ),
+            sub(0x02, "Floppy disk controller"),
+            sub(0x03, "IPI bus controller"),
+            sub(0x04, "RAID bus controller"),
+            sub_if(0x05, "ATA controller", &[(0x20, "ADMA single stepping"), (0x30, "ADMA continuous operation")]),
+            sub_if(
+                0x06,
+                "SATA controller",
+                &[(0x00, "Vendor specific"), (0x01, "AHCI 1.0"), (0x02, "Serial Storage Bus")],
+            ),
+            sub_if(0x07, "Serial Attached SCSI controller", &[(0x01, "Serial Storage Bus")]),
+            sub_if(0x08, "Non-Volatile memory controller", &[(0x01, "NVMHCI"), (0x02, "NVM Express")]),
+            sub_if(0x09, "Universal Flash Storage controller", &[(0x00, "Vendor specific"), (0x01, "UFSHCI")]),
+            sub(0x80, "Mass storage controller"),
+        ],
+    },
+    ClassEntry {
+        code: 0x02,
+        name: "Network controller",
+        subclasses: &[
+            sub(0x00, "Ethernet controller"),
+            sub(0x01, "Token ring network controller"),
+            sub(0x02, "FDDI network controller"),
+            sub(0x03, "ATM network controller"),
+            sub(0x04, "ISDN controller"),
+            sub(0x05, "WorldFip controller"),
+            sub(0x06, "PICMG controller"),
+            sub(0x07, "Infiniband controller"),
+            sub(0x08, "Fabric controller"),
+            sub(0x80, "Network controller"),
+        ],
+    },
+    ClassEntry {
+        code: 0x03,
+        name: "Display controller",
+        subclasses: &[
+            sub_if(0x00, "VGA compatible controller", &[(0x00, "VGA controller"), (0x01, "8514 controller")]),
+            sub(0x01, "XGA compatible controller"),
+            sub(0x02, "3D controller"),
+            sub(0x80, "Display controller"),
+        ],
+    },
+    ClassEntry {
+        code: 0x04,
+        name: "Multimedia controller",
+        subclasses: &[
+            sub(0x00, "Multimedia video controller"),
+            sub(0x01, "Multimedia audio controller"),
+            sub(0x02, "Computer telephony device"),
+            sub(0x03, "Audio device"),
+            sub(0x80, "Multimedia controller"),
+        ],
+    },
+    ClassEntry {
+        code: 0x05,
+        name: "Memory controller",
+        subclasses: &[
+            sub(0x00, "RAM memory"),
+            sub(0x01, "FLASH memory"),
+            sub_if(
+                0x02,
+                "CXL",
+                &[(0x00, "CXL Memory Device - vendor specific"), (0x10, "CXL Memory Device (CXL 2.x)")],
+            ),
+            sub(0x80, "Memory controller"),
+        ],
+    },
+    ClassEntry {
+        code: 0x06,
+        name: "Bridge",
+        subclasses: &[
+            sub(0x00, "Host bridge"),
+            sub(0x01, "ISA bridge"),
+            sub(0x02, "EISA bridge"),
+            sub(0x03, "MicroChannel bridge"),
+            sub_if(0x04, "PCI bridge", &[(0x00, "Normal decode"), (0x01, "Subtractive decode")]),
+            sub(0x05, "PCMCIA bridge"),
+            sub(0x06, "NuBus bridge"),
+            sub(0x07, "CardBus bridge"),
+            sub_if(0x08, "RACEway bridge", &[(0x00, "Transparent mode"), (0x01, "Endpoint mode")]),
+            sub_if(
+                0x09,
+                "Semi-transparent PCI-to-PCI bridge",
+                &[(0x40, "Primary bus towards host CPU"), (0x80, "Secondary bus towards host CPU")],
+            ),
+            sub(0x0a, "InfiniBand to PCI host bridge"),
+            sub(0x80, "Bridge"),
+        ],
+    },
+    ClassEntry {
+        code: 0x07,
+        name: "Communication controller",
+        subclasses: &[
+            sub_if(
+                0x00,
+                "Serial controller",
+                &[
+                    (0x00, "8250"),
+                    (0x01, "16450"),
+                    (0x02, "16550"),
+                    (0x03, "16650"),
+                    (0x04, "16750"),
+                    (0x05, "16850"),
+                    (0x06, "16950"),
+                ],
+            ),
+            sub_if(
+                0x01,
+                "Parallel controller",
+                &[(0x00, "SPP"), (0x01, "BiDir"), (0x02, "ECP"), (0x03, "IEEE1284"), (0xfe, "IEEE1284 Target")],
+            ),
+            sub(0x02, "Multiport serial controller"),
+            sub_if(
+                0x03,
+                "Modem",
+                &[
+                    (0x00, "Generic"),
+                    (0x01, "Hayes/16450"),
+                    (0x02, "Hayes/16550"),
+                    (0x03, "Hayes/16650"),
+                    (0x04, "Hayes/16750"),
+                ],
+            ),
+            sub(0x04, "GPIB controller"),
+            sub(0x05, "Smart Card controller"),
+            sub(0x80, "Communication controller"),
+        ],
+    },
+    ClassEntry {
+        code: 0x08,
+        name: "Generic system peripheral",
+        subclasses: &[
+            sub_if(
+                0x00,
+                "PIC",
+                &[(0x00, "8259"), (0x01, "ISA PIC"), (0x02, "EISA PIC"), (0x10, "IO-APIC"), (0x20, "IO(X)-APIC")],
+            ),
+            sub_if(0x01, "DMA controller", &[(0x00, "8237"), (0x01, "ISA DMA"), (0x02, "EISA DMA")]),
+            sub_if(0x02, "Timer", &[(0x00, "8254"), (0x01, "ISA Timer"), (0x02, "EISA Timers"), (0x03, "HPET")]),
+            sub_if(0x03, "RTC", &[(0x00, "Generic"), (0x01, "ISA RTC")]),
+            sub(0x04, "PCI Hot-plug controller"),
+            sub(0x05, "SD Host controller"),
+            sub(0x06, "IOMMU"),
+            sub(0x80, "System peripheral"),
+            sub(0x99, "Timing Card"),
+        ],
+    },
+    ClassEntry {
+        code: 0x09,
+        name: "Input device controller",
+        subclasses: &[
+            sub(0x00, "Keyboard controller"),
+            sub(0x01, "Digitizer Pen"),
+            sub(0x02, "Mouse controller"),
+            sub(0x03, "Scanner controller"),
+            sub_if(0x04, "Gameport controller", &[(0x00, "Generic"), (0x10, "Extended")]),
+            sub(0x80, "Input device controller"),
+        ],
+    },
+    ClassEntry {
+        code: 0x0a,
+        name: "Docking station",
+        subclasses: &[sub(0x00, "Generic Docking Station"), sub(0x80, "Docking Station")],
+    },
+    ClassEntry {
+        code: 0x0b,
+        name: "Processor",
+        subclasses: &[
+            sub(0x00, "386"),
+            sub(0x01, "486"),
+            sub(0x02, "Pentium"),
+            sub(0x10, "Alpha"),
+            sub(0x20, "Power PC"),
+            sub(0x30, "MIPS"),
+            sub(0x40, "Co-processor"),
+            sub(0x80, "Processor"),
+        ],
+    },
+    ClassEntry {
+        code: 0x0c,
+        name: "Serial bus controller",
+        subclasses: &[
+            sub_if(0x00, "FireWire (IEEE 1394)", &[(0x00, "Generic"), (0x10, "OHCI")]),
+            sub(0x01, "ACCESS Bus"),
+            sub(0x02, "SSA"),
+            sub_if(
+                0x03,
+                "USB controller",
+                &[
+                    (0x00, "UHCI"),
+                    (0x10, "OHCI"),
+                    (0x20, "EHCI"),
+                    (0x30, "XHCI"),
+                    (0x40, "USB4 Host Interface"),
+                    (0x80, "Unspecified"),
+                    (0xfe, "USB Device"),
+                ],
+            ),
+            sub(0x04, "Fibre Channel"),
+            sub(0x05, "SMBus"),
+            sub(0x06, "InfiniBand"),
+            sub_if(0x07, "IPMI Interface", &[(0x00, "SMIC"), (0x01, "KCS"), (0x02, "BT (Block Transfer)")]),
+            sub(0x08, "SERCOS interface"),
+            sub(0x09, "CANBUS"),
+            sub(0x80, "Serial bus controller"),
+        ],
+    },
+    ClassEntry {
+        code: 0x0d,
+        name: "Wireless controller",
+        subclasses: &[
+            sub(0x00, "IRDA controller"),
+            sub(0x01, "Consumer IR controller"),
+            sub(0x10, "RF controller"),
+            sub(0x11, "Bluetooth"),
+            sub(0x12, "Broadband"),
+            sub(0x20, "802.1a controller"),
+            sub(0x21, "802.1b controller"),
+            sub(0x80, "Wireless controller"),
+        ],
+    },
+    ClassEntry { code: 0x0e, name: "Intelligent controller", subclasses: &[sub(0x00, "I2O")] },
+    ClassEntry {
+        code: 0x0f,
+        name: "Satellite communications controller",
+        subclasses: &[
+            sub(0x01, "Satellite TV controller"),
+            sub(0x02, "Satellite audio communication controller"),
+            sub(0x03, "Satellite voice communication controller"),
+            sub(0x04, "Satellite data communication controller"),
+        ],
+    },
+    ClassEntry {
+        code: 0x10,
+        name: "Encryption controller",
+        subclasses: &[
+            sub(0x00, "Network and computing encryption device"),
+            sub(0x10, "Entertainment encryption device"),
+            sub(0x80, "Encryption controller"),
+        ],
+    },
+    ClassEntry {
+        code: 0x11,
+        name: "Signal processing controller",
+        subclasses: &[
+            sub(0x00, "DPIO module"),
+            sub(0x01, "Performance counters"),
+            sub(0x10, "Communication synchronizer"),
+            sub(0x20, "Signal processing management"),
+            sub(0x80, "Signal processing controller"),
+        ],
+    },
+    ClassEntry {
+        code: 0x12,
+        name: "Processing accelerators",
+        subclasses: &[
+            sub(0x00, "Processing accelerators"),
+            sub(0x01, "SNIA Smart Data Accelerator Interface (SDXI) controller"),
+        ],
+    },
+    ClassEntry { code: 0x13, name: "Non-Essential Instrumentation", subclasses: &[] },
+    ClassEntry { code: 0x40, name: "Coprocessor", subclasses: &[] },
+    ClassEntry { code: 0xff, name: "Unassigned class", subclasses: &[] },
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_forms() {
+        let usb = PciClassCode::parse("0x0c0330").unwrap();
+        assert_eq!(usb, PciClassCode { class: 0x0c, subclass: Some(0x03), prog_if: Some(0x30) });
+        assert_eq!(usb.to_string(), "0c0330");
+        assert_eq!(PciClassCode::parse("0403").unwrap().prog_if, None);
+        assert_eq!(PciClassCode::parse("03").unwrap().subclass, None);
+        assert_eq!(PciClassCode::parse("040"), None);
+        assert_eq!(PciClassCode::parse("zz00"), None);
+        assert_eq!(PciClassCode::parse("0é0"), None);
+    }
+
+    #[test]
+    fn test_names() {
+        assert_eq!(describe("0403").as_deref(), Some("Audio device"));
+        assert_eq!(describe("0c0330").as_deref(), Some("USB controller (XHCI)"));
+        assert_eq!(
+            describe("010802").as_deref(),
+            Some("Non-Volatile memory controller (NVM Express)")
+        );
+        // Unknown programming interfaces and subclasses fall back to the broader name
+        assert_eq!(describe("0c03aa").as_deref(), Some("USB controller"));
+        assert_eq!(describe("0377").as_deref(), Some("Display controller"));
+        assert_eq!(describe("03").as_deref(), Some("Display controller"));
+        assert_eq!(describe("7700"), None);
+    }
+
+    #[test]
+    fn test_class_codes_unique() {
+        for (i, class) in CLASSES.iter().enumerate() {
+            assert!(CLASSES[i + 1..].iter().all(|other| other.code != class.code));
+            for (j, subclass) in class.subclasses.iter().enumerate() {
+                assert!(class.subclasses[j + 1..].iter().all(|other| other.code != subclass.code));
+            }
+        }
+    }
+}
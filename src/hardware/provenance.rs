@@ -0,0 +1,181 @@
+//! Which detection tool supplied each report field
+//!
+//! Several tools describe the CPU and memory and they do not always agree:
+//! lshw reports a CPU "width" that dmidecode splits into cores and threads,
+//! and SMBIOS tables are often stale or filled in wrong by the firmware. The
+//! report keeps a single value per field; this section records which tool it
+//! came from, how far that tool is trusted for the field, and what the other
+//! tools reported when they disagreed, so parser bugs and BIOS quirks show up
+//! instead of being resolved silently.
+
+use serde::{Deserialize, Serialize};
+
+/// How far a tool is trusted for a field
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Confidence {
+    High,
+    Medium,
+    Low,
+}
+
+impl Confidence {
+    /// Confidence in a tool's value for a field, e.g. ("lshw", "cpu.cores")
+    pub fn of(tool: &str, field: &str) -> Self {
+        match (tool, field) {
+            // lshw's CPU "width" is not a core count
+            ("lshw", "cpu.cores" | "cpu.threads") => Self::Low,
+            // SMBIOS vendor strings are free text set by the firmware
+            ("dmidecode", "cpu.vendor" | "cpu.model") => Self::Medium,
+            ("dmidecode", _) => Self::High,
+            // The kernel enumerates every logical CPU and its core
+            ("sysfs", "cpu.cores" | "cpu.threads") => Self::High,
+            _ => Self::Medium,
+        }
+    }
+}
+
+/// Tool a report field was taken from
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FieldSource {
+    /// Field path, e.g. "cpu.cores"
+    pub field: String,
+    pub tool: String,
+    pub confidence: Confidence,
+}
+
+/// A value as reported by one tool
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReportedValue {
+    pub tool: String,
+    pub value: String,
+}
+
+/// Tools that disagreed on a field
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FieldConflict {
+    /// Field path, e.g. "cpu.cores"
+    pub field: String,
+    /// Value kept in the report
+    pub chosen: ReportedValue,
+    /// Differing values from other tools
+    pub others: Vec<ReportedValue>,
+}
+
+/// Sources of report fields and the disagreements between tools
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Provenance {
+    #[serde(default)]
+    pub fields: Vec<FieldSource>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub conflicts: Vec<FieldConflict>,
+}
+
+impl Provenance {
+    pub fn is_empty(&self) -> bool {
+        self.fields.is_empty() && self.conflicts.is_empty()
+    }
+
+    /// Record the values tools reported for a field, in order of preference
+    ///
+    /// The first tool with a value is the one kept in the report; tools that
+    /// reported something else are recorded as a conflict.
+    pub fn record(&mut self, field: &str, candidates: &[(&str, Option<String>)]) {
+        let mut reported =
+            candidates.iter().filter_map(|(tool, value)| Some((*tool, value.as_ref()?)));
+        let Some((tool, value)) = reported.next() else {
+            return;
+        };
+
+        self.fields.push(FieldSource {
+            field: field.to_string(),
+            tool: tool.to_string(),
+            confidence: Confidence::of(tool, field),
+        });
+
+        let others: Vec<ReportedValue> = reported
+            .filter(|(_, other)| *other != value)
+            .map(|(tool, value)| ReportedValue { tool: tool.to_string(), value: value.clone() })
+            .collect();
+        if !others.is_empty() {
+            log::info!(
+                "Tools disagree on {}: using {} from {}, {}",
+                field,
+                value,
+                tool,
+                others
+                    .iter()
+                    .map(|other| format!("{} reported {}", other.tool, other.value))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            );
+            self.conflicts.push(FieldConflict {
+                field: field.to_string(),
+                chosen: ReportedValue { tool: tool.to_string(), value: value.clone() },
+                others,
+            });
+        }
+    }
+
+    /// Record a field of components reported by several tools, in order of
+    /// preference
+    pub fn record_from<T>(
+        &mut self,
+        field: &str,
+        candidates: &[(&str, T)],
+        value: impl Fn(&T) -> Option<String>,
+    ) {
+        let values: Vec<(&str, Option<String>)> =
+            candidates.iter().map(|(tool, candidate)| (*tool, value(candidate))).collect();
+        self.record(field, &values);
+    }
+
+    /// Tool a field was taken from
+    pub fn source(&self, field: &str) -> Option<&FieldSource> {
+        self.fields.iter().find(|source| source.field == field)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_record_keeps_first_value_and_conflicts() {
+        let mut provenance = Provenance::default();
+        provenance.record(
+            "cpu.cores",
+            &[("dmidecode", Some("8".to_string())), ("lshw", Some("16".to_string()))],
+        );
+        provenance.record(
+            "cpu.vendor",
+            &[("dmidecode", None), ("lshw", Some("Intel Corp.".to_string()))],
+        );
+        provenance.record("memory.total_bytes", &[("dmidecode", None), ("lshw", None)]);
+
+        assert_eq!(provenance.fields.len(), 2);
+        let cores = provenance.source("cpu.cores").unwrap();
+        assert_eq!((cores.tool.as_str(), cores.confidence), ("dmidecode", Confidence::High));
+        assert_eq!(provenance.source("cpu.vendor").unwrap().tool, "lshw");
+
+        assert_eq!(
+            provenance.conflicts,
+            vec![FieldConflict {
+                field: "cpu.cores".to_string(),
+                chosen: ReportedValue { tool: "dmidecode".to_string(), value: "8".to_string() },
+                others: vec![ReportedValue { tool: "lshw".to_string(), value: "16".to_string() }],
+            }]
+        );
+    }
+
+    #[test]
+    fn test_agreeing_tools_are_not_a_conflict() {
+        let mut provenance = Provenance::default();
+        provenance.record(
+            "cpu.threads",
+            &[("dmidecode", Some("16".to_string())), ("lshw", Some("16".to_string()))],
+        );
+        assert!(provenance.conflicts.is_empty());
+        assert_eq!(provenance.source("cpu.threads").unwrap().tool, "dmidecode");
+    }
+}
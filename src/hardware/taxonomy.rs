@@ -0,0 +1,181 @@
+//! Component taxonomy shared by report extraction and the indexer
+//!
+//! Detection tools classify devices differently: lspci uses PCI class codes,
+//! lshw its own class names, and older reports free-form component types.
+//! The taxonomy maps all of these onto a fixed [`Category`] set. The default
+//! mapping lives in `config/taxonomy.toml`; a custom file can extend it.
+
+use crate::errors::{LxHwError, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+use std::path::Path;
+
+/// Default mapping, compiled into the binary
+const DEFAULT_TAXONOMY: &str = include_str!("../../config/taxonomy.toml");
+
+/// Fixed set of component categories
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Category {
+    Cpu,
+    Memory,
+    Storage,
+    Gpu,
+    Network,
+    Audio,
+    Multimedia,
+    Usb,
+    Input,
+    Bridge,
+    Other,
+}
+
+impl Category {
+    /// Every category, in documentation order
+    pub const ALL: [Category; 11] = [
+        Category::Cpu,
+        Category::Memory,
+        Category::Storage,
+        Category::Gpu,
+        Category::Network,
+        Category::Audio,
+        Category::Multimedia,
+        Category::Usb,
+        Category::Input,
+        Category::Bridge,
+        Category::Other,
+    ];
+
+    /// Name used for component index buckets
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Category::Cpu => "CPU",
+            Category::Memory => "Memory",
+            Category::Storage => "Storage",
+            Category::Gpu => "GPU",
+            Category::Network => "Network",
+            Category::Audio => "Audio",
+            Category::Multimedia => "Multimedia",
+            Category::Usb => "USB",
+            Category::Input => "Input",
+            Category::Bridge => "Bridge",
+            Category::Other => "Other",
+        }
+    }
+}
+
+impl fmt::Display for Category {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Mapping from raw tool classes to categories
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Taxonomy {
+    /// PCI class code prefixes (lowercase hex)
+    #[serde(default)]
+    pub pci_classes: HashMap<String, Category>,
+    /// lshw class names
+    #[serde(default)]
+    pub lshw_classes: HashMap<String, Category>,
+    /// Component type names
+    #[serde(default)]
+    pub component_types: HashMap<String, Category>,
+}
+
+impl Taxonomy {
+    /// Built-in taxonomy from `config/taxonomy.toml`
+    pub fn builtin() -> Self {
+        Self::parse(DEFAULT_TAXONOMY).expect("built-in taxonomy must be valid")
+    }
+
+    /// Built-in taxonomy extended with the mappings in a custom file
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path)?;
+        let mut taxonomy = Self::builtin();
+        taxonomy.merge(Self::parse(&content)?);
+        Ok(taxonomy)
+    }
+
+    /// Parse a taxonomy from TOML
+    pub fn parse(content: &str) -> Result<Self> {
+        let mut taxonomy: Self = toml::from_str(content)
+            .map_err(|e| LxHwError::ConfigError(format!("Invalid taxonomy file: {}", e)))?;
+        taxonomy.pci_classes =
+            taxonomy.pci_classes.into_iter().map(|(k, v)| (k.to_lowercase(), v)).collect();
+        Ok(taxonomy)
+    }
+
+    /// Add or replace entries with those from another taxonomy
+    pub fn merge(&mut self, other: Taxonomy) {
+        self.pci_classes.extend(other.pci_classes);
+        self.lshw_classes.extend(other.lshw_classes);
+        self.component_types.extend(other.component_types);
+    }
+
+    /// Category of a PCI class code, using the longest matching prefix
+    pub fn pci_category(&self, class_code: &str) -> Category {
+        let class_code = class_code.to_lowercase();
+        self.pci_classes
+            .iter()
+            .filter(|(prefix, _)| class_code.starts_with(prefix.as_str()))
+            .max_by_key(|(prefix, _)| prefix.len())
+            .map(|(_, category)| *category)
+            .unwrap_or(Category::Other)
+    }
+
+    /// Category of an lshw class name
+    pub fn lshw_category(&self, class: &str) -> Category {
+        self.lshw_classes.get(class).copied().unwrap_or(Category::Other)
+    }
+
+    /// Category of a component type name
+    pub fn component_category(&self, component_type: &str) -> Category {
+        self.component_types.get(component_type).copied().unwrap_or(Category::Other)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_builtin_pci_longest_prefix() {
+        let taxonomy = Taxonomy::builtin();
+        assert_eq!(taxonomy.pci_category("0300"), Category::Gpu);
+        assert_eq!(taxonomy.pci_category("0380"), Category::Gpu);
+        assert_eq!(taxonomy.pci_category("0403"), Category::Audio);
+        assert_eq!(taxonomy.pci_category("0400"), Category::Multimedia);
+        assert_eq!(taxonomy.pci_category("0C03"), Category::Usb);
+        assert_eq!(taxonomy.pci_category("ff00"), Category::Other);
+    }
+
+    #[test]
+    fn test_builtin_lshw_and_component_types() {
+        let taxonomy = Taxonomy::builtin();
+        assert_eq!(taxonomy.lshw_category("display"), Category::Gpu);
+        assert_eq!(taxonomy.lshw_category("multimedia"), Category::Audio);
+        assert_eq!(taxonomy.component_category("MemoryDIMM"), Category::Memory);
+        assert_eq!(taxonomy.component_category("Display"), Category::Gpu);
+        assert_eq!(taxonomy.component_category("Mystery"), Category::Other);
+    }
+
+    #[test]
+    fn test_custom_entries_override_builtin() {
+        let mut taxonomy = Taxonomy::builtin();
+        taxonomy.merge(
+            Taxonomy::parse("[pci_classes]\n\"0480\" = \"audio\"\n[lshw_classes]\nmultimedia = \"multimedia\"\n")
+                .unwrap(),
+        );
+        assert_eq!(taxonomy.pci_category("0480"), Category::Audio);
+        assert_eq!(taxonomy.lshw_category("multimedia"), Category::Multimedia);
+        assert_eq!(taxonomy.pci_category("0300"), Category::Gpu);
+    }
+
+    #[test]
+    fn test_invalid_category_rejected() {
+        assert!(Taxonomy::parse("[pci_classes]\n\"03\" = \"graphics\"\n").is_err());
+    }
+}
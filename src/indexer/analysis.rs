@@ -5,10 +5,11 @@
 
 use super::statistics::TrendAnalysis;
 use super::*;
-use crate::errors::Result;
+use crate::errors::{LxHwError, Result};
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::path::Path;
 
 /// Advanced compatibility analyzer
 pub struct CompatibilityAnalyzer<'a> {
@@ -456,6 +457,174 @@ pub enum Priority {
     Low,
 }
 
+/// Kernel versions listed in the executive summary
+const SUMMARY_KERNEL_LIMIT: usize = 10;
+
+/// Analysis results written by [`CompatibilityAnalysis::write_to`]
+pub const ANALYSIS_FILE: &str = "compatibility-analysis.json";
+
+/// Executive summary written by [`CompatibilityAnalysis::write_to`]
+pub const SUMMARY_FILE: &str = "executive-summary.md";
+
+impl CompatibilityAnalysis {
+    /// Write the analysis JSON and the executive summary into a directory
+    pub fn write_to(
+        &self,
+        output_dir: &Path,
+        report_count: usize,
+        generated_at: DateTime<Utc>,
+    ) -> Result<()> {
+        std::fs::create_dir_all(output_dir)?;
+
+        let json = serde_json::to_string_pretty(self)
+            .map_err(|e| LxHwError::SerializationError(e.to_string()))?;
+        std::fs::write(output_dir.join(ANALYSIS_FILE), json)?;
+        std::fs::write(
+            output_dir.join(SUMMARY_FILE),
+            self.executive_summary(report_count, generated_at),
+        )?;
+        Ok(())
+    }
+
+    /// Markdown executive summary for the monthly database health report
+    pub fn executive_summary(&self, report_count: usize, generated_at: DateTime<Utc>) -> String {
+        let health = &self.health_metrics;
+        let quality = &health.data_quality;
+        let freshness = &health.freshness;
+        let mut md = String::new();
+
+        md.push_str("# Hardware Database Health Report\n\n");
+        md.push_str(&format!(
+            "Generated {} from {} reports.\n\n",
+            generated_at.format("%Y-%m-%d"),
+            report_count
+        ));
+
+        md.push_str("## Overview\n\n");
+        md.push_str("| Metric | Value |\n|--------|-------|\n");
+        let rows = [
+            ("Overall health", format!("{}/100", health.overall_score)),
+            ("Completeness", format!("{}%", quality.completeness_score)),
+            ("Consistency", format!("{}%", quality.consistency_score)),
+            ("Validation accuracy", format!("{}%", quality.accuracy_score)),
+            ("Duplicate rate", format!("{:.1}%", quality.duplicate_rate * 100.0)),
+            ("Freshness", format!("{}/100", freshness.freshness_score)),
+            ("Average report age", format!("{:.0} days", freshness.average_age_days)),
+            (
+                "Reports from the last 30 days",
+                format!("{:.1}%", freshness.recent_reports_percentage),
+            ),
+            (
+                "Submission velocity",
+                format!("{:.1} reports/month", health.community_metrics.submission_velocity),
+            ),
+            ("Vendors analyzed", self.vendor_analysis.len().to_string()),
+        ];
+        for (metric, value) in rows {
+            md.push_str(&format!("| {} | {} |\n", metric, value));
+        }
+        md.push('\n');
+
+        md.push_str("## Regressions\n\n");
+        if self.regressions.is_empty() {
+            md.push_str("No compatibility regressions detected.\n\n");
+        } else {
+            md.push_str("| Hardware | Regressed In | Last Working | Severity | Confidence |\n");
+            md.push_str("|----------|--------------|--------------|----------|------------|\n");
+            for regression in &self.regressions {
+                md.push_str(&format!(
+                    "| {} | {} | {} | {:?} | {:.0}% |\n",
+                    regression.hardware_description,
+                    regression.regression_kernel,
+                    regression.previous_working_kernel,
+                    regression.severity,
+                    regression.confidence * 100.0
+                ));
+            }
+            md.push('\n');
+        }
+
+        if !self.kernel_trends.is_empty() {
+            md.push_str("## Kernel Trends\n\n");
+            md.push_str("| Kernel | Average Compatibility | Trend |\n");
+            md.push_str("|--------|-----------------------|-------|\n");
+            for trend in self.kernel_trends.iter().take(SUMMARY_KERNEL_LIMIT) {
+                md.push_str(&format!(
+                    "| {} | {:.1} | {:?} |\n",
+                    trend.kernel_version, trend.avg_compatibility, trend.trend_direction
+                ));
+            }
+            md.push('\n');
+        }
+
+        let mut scores = Vec::new();
+        if !self.distribution_analysis.is_empty() {
+            scores.push((
+                "Distributions",
+                "Distribution",
+                self.distribution_analysis
+                    .iter()
+                    .map(|d| (d.distribution.as_str(), d.compatibility_score))
+                    .collect::<Vec<_>>(),
+            ));
+        }
+        if !self.category_insights.is_empty() {
+            scores.push((
+                "Hardware Categories",
+                "Category",
+                self.category_insights
+                    .iter()
+                    .map(|c| (c.category.as_str(), c.compatibility_score))
+                    .collect(),
+            ));
+        }
+        for (heading, column, mut rows) in scores {
+            rows.sort_by(|a, b| b.1.total_cmp(&a.1).then_with(|| a.0.cmp(b.0)));
+            md.push_str(&format!("## {}\n\n", heading));
+            md.push_str(&format!("| {} | Average Compatibility |\n", column));
+            md.push_str(&format!("|{}|-----------------------|\n", "-".repeat(column.len() + 2)));
+            for (name, score) in rows {
+                md.push_str(&format!("| {} | {:.1} |\n", name, score));
+            }
+            md.push('\n');
+        }
+
+        let recommendations = &self.recommendations;
+        if !recommendations.best_overall.is_empty() || !recommendations.avoid_list.is_empty() {
+            md.push_str("## Recommendations\n\n");
+            for hardware in &recommendations.best_overall {
+                md.push_str(&format!(
+                    "- Recommended: {} {} ({:.1}): {}\n",
+                    hardware.vendor, hardware.model, hardware.compatibility_score, hardware.reason
+                ));
+            }
+            for warning in &recommendations.avoid_list {
+                md.push_str(&format!(
+                    "- Avoid: {} {} ({:?}): {}\n",
+                    warning.vendor, warning.model, warning.warning_type, warning.reason
+                ));
+            }
+            md.push('\n');
+        }
+
+        if !health.coverage.gaps.is_empty() {
+            md.push_str("## Coverage Gaps\n\n");
+            for gap in &health.coverage.gaps {
+                md.push_str(&format!(
+                    "- **{:?}** {}: {}\n",
+                    gap.priority, gap.gap_type, gap.description
+                ));
+                for action in &gap.suggested_actions {
+                    md.push_str(&format!("  - {}\n", action));
+                }
+            }
+            md.push('\n');
+        }
+
+        md
+    }
+}
+
 impl<'a> CompatibilityAnalyzer<'a> {
     pub fn new(reports: &'a [IndexedReport], config: AnalysisConfig) -> Self {
         Self { reports, config }
@@ -468,7 +637,7 @@ impl<'a> CompatibilityAnalyzer<'a> {
             self.reports.len()
         );
 
-        let analysis = CompatibilityAnalysis {
+        let mut analysis = CompatibilityAnalysis {
             health_metrics: self.analyze_health_metrics()?,
             kernel_trends: self.analyze_kernel_trends()?,
             vendor_analysis: self.analyze_vendor_compatibility()?,
@@ -483,6 +652,11 @@ impl<'a> CompatibilityAnalyzer<'a> {
             recommendations: self.generate_recommendations()?,
         };
 
+        // Grouped results come out of hash maps; keep runs comparable
+        analysis.vendor_analysis.sort_by(|a, b| a.vendor.cmp(&b.vendor));
+        analysis.category_insights.sort_by(|a, b| a.category.cmp(&b.category));
+        analysis.distribution_analysis.sort_by(|a, b| a.distribution.cmp(&b.distribution));
+
         log::info!("Compatibility analysis complete with {} kernel trends, {} vendor analyses, {} regressions detected",
             analysis.kernel_trends.len(),
             analysis.vendor_analysis.len(),
@@ -533,8 +707,8 @@ impl<'a> CompatibilityAnalyzer<'a> {
         // Calculate missing data rate
         let missing_data_rate = self.calculate_missing_data_rate()?;
 
-        // Accuracy is harder to assess automatically, use heuristics
-        let accuracy_score = self.estimate_accuracy_score()?;
+        // Accuracy follows the validator's confidence in each report
+        let accuracy_score = self.estimate_accuracy_score();
 
         Ok(DataQuality {
             completeness_score,
@@ -815,9 +989,9 @@ impl<'a> CompatibilityAnalyzer<'a> {
         Ok(0.05) // 5% missing data (placeholder)
     }
 
-    fn estimate_accuracy_score(&self) -> Result<u8> {
-        // Use heuristics to estimate accuracy
-        Ok(85) // Placeholder
+    fn estimate_accuracy_score(&self) -> u8 {
+        let total: f64 = self.reports.iter().map(|r| r.validation.confidence_score).sum();
+        (total / self.reports.len() as f64 * 100.0).round().clamp(0.0, 100.0) as u8
     }
 
     fn calculate_overall_health_score(
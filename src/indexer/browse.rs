@@ -0,0 +1,448 @@
+//! Offline browsing of published compatibility indices
+//!
+//! Loads the JSON indices written by the indexer from a local directory or
+//! the URL they are published under, and answers the questions the `browse`
+//! terminal UI asks: which hardware matches a search, how it scored on each
+//! kernel, what issues were reported for it, and which entries correspond to
+//! hardware detected on this machine.
+
+use super::builder::IndexBuilder;
+use super::{
+    CompatibilityMatrix, CompatibilityScore, DeviceIndex, HardwareIndexer, IndexerConfig,
+    KnownIssueEntry, KnownIssuesIndex, VendorIndex,
+};
+use crate::errors::{LxHwError, Result};
+use crate::hardware::HardwareReport;
+use std::cmp::Reverse;
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+
+const VENDOR_INDEX: &str = "by-vendor.json";
+const COMPATIBILITY_MATRIX: &str = "compatibility-matrix.json";
+const KNOWN_ISSUES: &str = "known-issues.json";
+const DEVICE_INDEX: &str = "by-device.json";
+
+/// Index files the browser reads; only the vendor index is required
+const INDEX_FILES: &[&str] = &[VENDOR_INDEX, COMPATIBILITY_MATRIX, KNOWN_ISSUES, DEVICE_INDEX];
+
+/// Hardware model listed in the indices
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BrowseEntry {
+    /// Normalized vendor name
+    pub vendor: String,
+    /// Product model
+    pub model: String,
+    /// Taxonomy category
+    pub component_type: String,
+}
+
+impl BrowseEntry {
+    /// Key of this hardware in the compatibility matrix and known issues
+    pub fn key(&self) -> String {
+        format!("{} {}", self.vendor, self.model)
+    }
+}
+
+/// Compatibility score for one kernel and distribution
+#[derive(Debug, Clone)]
+pub struct KernelScore<'a> {
+    pub kernel: &'a str,
+    pub distribution: &'a str,
+    pub score: &'a CompatibilityScore,
+}
+
+/// Read-only view over a set of published indices
+pub struct IndexBrowser {
+    compatibility_matrix: CompatibilityMatrix,
+    known_issues: KnownIssuesIndex,
+    by_device: DeviceIndex,
+    entries: Vec<BrowseEntry>,
+}
+
+impl IndexBrowser {
+    /// Browser over indices already in memory
+    pub fn new(
+        by_vendor: &VendorIndex,
+        compatibility_matrix: CompatibilityMatrix,
+        known_issues: KnownIssuesIndex,
+    ) -> Self {
+        let mut entries: Vec<BrowseEntry> = by_vendor
+            .iter()
+            .flat_map(|(vendor, entry)| {
+                entry.components.iter().flat_map(move |(component_type, models)| {
+                    models.iter().map(move |model| BrowseEntry {
+                        vendor: vendor.clone(),
+                        model: model.clone(),
+                        component_type: component_type.clone(),
+                    })
+                })
+            })
+            .collect();
+        entries.sort_by(|a, b| (&a.vendor, &a.model).cmp(&(&b.vendor, &b.model)));
+        entries.dedup_by(|a, b| a.vendor == b.vendor && a.model == b.model);
+
+        Self { compatibility_matrix, known_issues, by_device: DeviceIndex::new(), entries }
+    }
+
+    /// Use a device ID index to find hardware by device ID
+    pub fn with_device_index(mut self, by_device: DeviceIndex) -> Self {
+        self.by_device = by_device;
+        self
+    }
+
+    /// Load indices from a directory or an `http(s)://` base URL
+    pub async fn load(source: &str) -> Result<Self> {
+        let mut files = HashMap::new();
+        for name in INDEX_FILES {
+            let contents = if is_url(source) {
+                fetch_index_file(source, name).await?
+            } else {
+                read_index_file(Path::new(source), name)?
+            };
+            if let Some(contents) = contents {
+                files.insert(*name, contents);
+            }
+        }
+        Self::from_files(&files)
+    }
+
+    /// Parse index file contents keyed by file name
+    fn from_files(files: &HashMap<&str, String>) -> Result<Self> {
+        let by_vendor: VendorIndex =
+            serde_json::from_str(files.get(VENDOR_INDEX).ok_or_else(|| {
+                LxHwError::DatabaseError {
+                    message: format!("Indices are missing {}", VENDOR_INDEX),
+                }
+            })?)?;
+        let compatibility_matrix = match files.get(COMPATIBILITY_MATRIX) {
+            Some(contents) => serde_json::from_str(contents)?,
+            None => CompatibilityMatrix::new(),
+        };
+        // Indices generated before known issues were exported lack this file
+        let known_issues = match files.get(KNOWN_ISSUES) {
+            Some(contents) => serde_json::from_str(contents)?,
+            None => KnownIssuesIndex::new(),
+        };
+        // Indices generated before device IDs were indexed lack this file
+        let by_device = match files.get(DEVICE_INDEX) {
+            Some(contents) => serde_json::from_str(contents)?,
+            None => DeviceIndex::new(),
+        };
+        Ok(Self::new(&by_vendor, compatibility_matrix, known_issues).with_device_index(by_device))
+    }
+
+    /// All hardware models, sorted by vendor and model
+    pub fn entries(&self) -> &[BrowseEntry] {
+        &self.entries
+    }
+
+    /// Positions of entries matching every word of `query`, case-insensitively
+    ///
+    /// Words are matched against vendor, model, category and device ID.
+    pub fn search(&self, query: &str) -> Vec<usize> {
+        let words: Vec<String> = query.split_whitespace().map(str::to_lowercase).collect();
+        self.entries
+            .iter()
+            .enumerate()
+            .filter(|(_, entry)| {
+                let device_id = self
+                    .known_issues(entry)
+                    .and_then(|issues| issues.device_id.as_deref())
+                    .unwrap_or_default();
+                let haystack = format!("{} {} {}", entry.key(), entry.component_type, device_id)
+                    .to_lowercase();
+                words.iter().all(|word| haystack.contains(word.as_str()))
+            })
+            .map(|(position, _)| position)
+            .collect()
+    }
+
+    /// Scores per kernel and distribution, newest kernel first
+    pub fn kernel_scores(&self, entry: &BrowseEntry) -> Vec<KernelScore<'_>> {
+        let Some(scores) = self.compatibility_matrix.get(&entry.key()) else {
+            return Vec::new();
+        };
+        let mut scores: Vec<KernelScore> = scores
+            .iter()
+            .map(|(key, score)| {
+                let (kernel, distribution) = key.split_once('_').unwrap_or((key, ""));
+                KernelScore { kernel, distribution, score }
+            })
+            .collect();
+        scores.sort_by_key(|s| (Reverse(kernel_sort_key(s.kernel)), s.distribution));
+        scores
+    }
+
+    /// Issues and workarounds reported for an entry
+    pub fn known_issues(&self, entry: &BrowseEntry) -> Option<&KnownIssueEntry> {
+        self.known_issues.get(&entry.key())
+    }
+
+    /// Positions of entries reported with a device ID ("vendor:device")
+    pub fn device_entries(&self, device_id: &str) -> Vec<usize> {
+        let device_id = device_id.to_lowercase();
+        let keys: HashSet<&String> = self.by_device.get(&device_id).into_iter().flatten().collect();
+        self.entries
+            .iter()
+            .enumerate()
+            .filter(|(_, entry)| {
+                let key = entry.key();
+                keys.contains(&key)
+                    || self
+                        .known_issues
+                        .get(&key)
+                        .and_then(|issues| issues.device_id.as_ref())
+                        .is_some_and(|id| id.to_lowercase() == device_id)
+            })
+            .map(|(position, _)| position)
+            .collect()
+    }
+
+    /// Positions of entries describing hardware found in `report`
+    pub fn local_matches(&self, report: &HardwareReport) -> Vec<usize> {
+        let (keys, device_ids) = report_hardware(report);
+
+        self.entries
+            .iter()
+            .enumerate()
+            .filter(|(_, entry)| {
+                let key = entry.key();
+                keys.contains(&key)
+                    || self
+                        .known_issues
+                        .get(&key)
+                        .and_then(|issues| issues.device_id.as_ref())
+                        .is_some_and(|id| device_ids.contains(&id.to_lowercase()))
+            })
+            .map(|(position, _)| position)
+            .collect()
+    }
+
+    /// Number of published reports with each piece of hardware in `report`,
+    /// by compatibility matrix key
+    ///
+    /// Each report counts once per kernel and distribution it was tested on,
+    /// so the sample sizes across a key's kernels add up to its reports.
+    /// Hardware missing from the matrix has no published reports.
+    pub fn report_counts(&self, report: &HardwareReport) -> HashMap<String, usize> {
+        report_hardware(report)
+            .0
+            .into_iter()
+            .map(|key| {
+                let count = self
+                    .compatibility_matrix
+                    .get(&key)
+                    .map_or(0, |kernels| kernels.values().map(|score| score.sample_size).sum());
+                (key, count)
+            })
+            .collect()
+    }
+}
+
+/// Compatibility matrix keys and lowercase device IDs of the hardware in `report`
+fn report_hardware(report: &HardwareReport) -> (HashSet<String>, HashSet<String>) {
+    // Derive keys exactly as the indexer does so models line up
+    let config = IndexerConfig::default();
+    let builder = IndexBuilder::new(&config);
+    let indexer = HardwareIndexer::new(config.clone());
+    let mut keys = HashSet::new();
+    let mut device_ids = HashSet::new();
+    let Ok(components) = indexer.extract_components(report) else {
+        return (keys, device_ids);
+    };
+
+    for component in components {
+        if let (Some(vendor), Some(model)) = (&component.vendor, &component.model) {
+            keys.insert(format!("{} {}", builder.normalize_vendor_name(vendor), model));
+        }
+        if let Some(device_id) = component.device_id {
+            device_ids.insert(device_id.to_lowercase());
+        }
+    }
+    (keys, device_ids)
+}
+
+/// Device IDs of the hardware in `report`, as the indexer records them
+pub fn report_device_ids(report: &HardwareReport) -> Vec<String> {
+    let indexer = HardwareIndexer::new(IndexerConfig::default());
+    let mut device_ids: Vec<String> = indexer
+        .extract_components(report)
+        .unwrap_or_default()
+        .into_iter()
+        .filter_map(|component| component.device_id.map(|id| id.to_lowercase()))
+        .collect();
+    device_ids.sort();
+    device_ids.dedup();
+    device_ids
+}
+
+fn is_url(source: &str) -> bool {
+    source.starts_with("http://") || source.starts_with("https://")
+}
+
+fn read_index_file(dir: &Path, name: &str) -> Result<Option<String>> {
+    match std::fs::read_to_string(dir.join(name)) {
+        Ok(contents) => Ok(Some(contents)),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e.into()),
+    }
+}
+
+async fn fetch_index_file(base: &str, name: &str) -> Result<Option<String>> {
+    let url = format!("{}/{}", base.trim_end_matches('/'), name);
+    let fetch_error =
+        |e: reqwest::Error| LxHwError::DatabaseError { message: format!("{}: {}", url, e) };
+
+    let response = reqwest::get(&url).await.map_err(fetch_error)?;
+    if response.status() == reqwest::StatusCode::NOT_FOUND {
+        return Ok(None);
+    }
+    let response = response.error_for_status().map_err(fetch_error)?;
+    response.text().await.map(Some).map_err(fetch_error)
+}
+
+/// Numeric release components of a kernel version ("6.8.0-45-generic" → [6, 8, 0])
+fn kernel_sort_key(kernel: &str) -> Vec<u32> {
+    kernel
+        .split(['-', '+', '~'])
+        .next()
+        .unwrap_or_default()
+        .split('.')
+        .map_while(|part| part.parse().ok())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::indexer::{ConfidenceLevel, VendorEntry};
+    use chrono::Utc;
+    use serde_json::json;
+
+    fn score(score: u8) -> CompatibilityScore {
+        CompatibilityScore {
+            score,
+            driver: Some("iwlwifi".to_string()),
+            sample_size: 1,
+            confidence: ConfidenceLevel::Low,
+            low_sample: false,
+            last_updated: Utc::now(),
+        }
+    }
+
+    fn browser() -> IndexBrowser {
+        let vendor = |components: &[(&str, &[&str])]| VendorEntry {
+            total_reports: 1,
+            components: components
+                .iter()
+                .map(|(kind, models)| {
+                    (kind.to_string(), models.iter().map(|m| m.to_string()).collect())
+                })
+                .collect(),
+            recent_reports: Vec::new(),
+            compatibility_score: 0.0,
+            last_updated: Utc::now(),
+        };
+        let by_vendor = VendorIndex::from([
+            ("Intel".to_string(), vendor(&[("network", &["Wi-Fi 6 AX201"])])),
+            ("NVIDIA".to_string(), vendor(&[("gpu", &["GeForce GTX 1050"])])),
+        ]);
+        let matrix = CompatibilityMatrix::from([(
+            "Intel Wi-Fi 6 AX201".to_string(),
+            HashMap::from([
+                ("5.15.0-91-generic_Ubuntu 22.04".to_string(), score(60)),
+                ("6.10.3_Fedora 41".to_string(), score(95)),
+                ("6.8.0-45-generic_Ubuntu 24.04".to_string(), score(90)),
+            ]),
+        )]);
+        let known_issues = KnownIssuesIndex::from([(
+            "NVIDIA GeForce GTX 1050".to_string(),
+            KnownIssueEntry {
+                device_id: Some("10de:1c8d".to_string()),
+                report_count: 2,
+                issues: vec!["No driver available".to_string()],
+                workarounds: vec!["Install the proprietary driver".to_string()],
+            },
+        )]);
+        IndexBrowser::new(&by_vendor, matrix, known_issues)
+    }
+
+    #[test]
+    fn test_search_matches_all_words() {
+        let browser = browser();
+        assert_eq!(browser.entries().len(), 2);
+        assert_eq!(browser.search("intel wi-fi"), vec![0]);
+        assert_eq!(browser.search("GPU"), vec![1]);
+        assert_eq!(browser.search("10de:1c8d"), vec![1]);
+        assert_eq!(browser.search(""), vec![0, 1]);
+        assert!(browser.search("intel gpu").is_empty());
+    }
+
+    #[test]
+    fn test_kernel_scores_newest_first() {
+        let browser = browser();
+        let scores = browser.kernel_scores(&browser.entries()[0]);
+        let kernels: Vec<&str> = scores.iter().map(|s| s.kernel).collect();
+        assert_eq!(kernels, vec!["6.10.3", "6.8.0-45-generic", "5.15.0-91-generic"]);
+        assert_eq!(scores[0].distribution, "Fedora 41");
+        assert!(browser.kernel_scores(&browser.entries()[1]).is_empty());
+    }
+
+    fn local_report() -> HardwareReport {
+        serde_json::from_value(json!({
+            "metadata": {
+                "version": "1.0",
+                "generated_at": "2024-01-01T00:00:00Z",
+                "privacy_level": "Basic",
+                "tools_used": ["lspci"],
+                "anonymized_system_id": "abc"
+            },
+            "system": {
+                "anonymized_hostname": "host",
+                "kernel_version": "6.8.0",
+                "distribution": "Ubuntu 24.04",
+                "architecture": "x86_64"
+            },
+            "cpu": null,
+            "memory": null,
+            "storage": [],
+            "graphics": [{
+                "vendor": "NVIDIA Corporation",
+                "model": "GP107M",
+                "driver": null,
+                "memory_bytes": null,
+                "pci_id": "10de:1c8d"
+            }],
+            "network": [{
+                "device_type": "wifi",
+                "vendor": "Intel Corporation",
+                "model": "Wi-Fi 6 AX201",
+                "driver": "iwlwifi",
+                "anonymized_mac": "unknown"
+            }],
+            "usb": [],
+            "audio": [],
+            "kernel_support": null
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_local_matches_by_model_and_device_id() {
+        assert_eq!(browser().local_matches(&local_report()), vec![0, 1]);
+    }
+
+    #[test]
+    fn test_report_counts_sum_kernel_samples() {
+        let counts = browser().report_counts(&local_report());
+        assert_eq!(counts.get("Intel Wi-Fi 6 AX201"), Some(&3));
+        assert_eq!(counts.get("NVIDIA GP107M"), Some(&0));
+    }
+
+    #[test]
+    fn test_from_files_tolerates_missing_optional_indices() {
+        let files = HashMap::from([(VENDOR_INDEX, "{}".to_string())]);
+        assert!(IndexBrowser::from_files(&files).unwrap().entries().is_empty());
+        assert!(IndexBrowser::from_files(&HashMap::new()).is_err());
+    }
+}
@@ -1,10 +1,21 @@
 //! Index builder implementation for generating search indices from hardware reports
+//!
+//! Indices are built incrementally by an [`IndexAccumulator`], so a corpus can
+//! be fed in batches without holding every report in memory. Values that need
+//! a view of the whole corpus (vendor averages, popular models, top hardware)
+//! are tallied per model while reports stream past and resolved once all
+//! batches have been added.
 
 use super::*;
 use crate::errors::Result;
+use crate::hardware::pci_class;
 use chrono::Utc;
+use std::cmp::Reverse;
 use std::collections::{HashMap, HashSet};
 
+/// Report count and running compatibility score for one hardware model
+type ModelTally = (usize, f64);
+
 /// Builder for generating all types of indices from hardware reports
 pub struct IndexBuilder<'a> {
     config: &'a IndexerConfig,
@@ -23,550 +34,706 @@ impl<'a> IndexBuilder<'a> {
             println!("Building indices from {} reports...", reports.len());
         }
 
-        // Build each index type
-        let indices = IndexCollection {
-            by_vendor: self.build_vendor_index(reports)?,
-            by_component: self.build_component_index(reports)?,
-            by_kernel: self.build_kernel_index(reports)?,
-            by_distribution: self.build_distribution_index(reports)?,
-            search_terms: self.build_search_terms_index(reports)?,
-            compatibility_matrix: self.build_compatibility_matrix(reports)?,
-            statistics: self.build_statistics(reports)?,
-        };
-
-        if self.config.verbose {
-            println!("All indices built successfully");
-            self.print_index_summary(&indices);
-        }
-
-        Ok(indices)
+        let mut accumulator = self.accumulator();
+        accumulator.add_reports(reports);
+        Ok(accumulator.finish())
     }
 
-    /// Build vendor-organized index
-    #[allow(clippy::excessive_nesting)]
-    fn build_vendor_index(&self, reports: &[IndexedReport]) -> Result<VendorIndex> {
-        if self.config.verbose {
-            println!("Building vendor index...");
+    /// Start building indices one batch of reports at a time
+    pub fn accumulator(&self) -> IndexAccumulator<'_, 'a> {
+        IndexAccumulator {
+            builder: self,
+            indices: IndexCollection::default(),
+            vendor_scores: HashMap::new(),
+            model_tallies: HashMap::new(),
+            top_hardware: HashMap::new(),
+            unique_systems: HashSet::new(),
+            all_kernels: HashSet::new(),
+            all_distributions: HashSet::new(),
+            unknown_devices: HashMap::new(),
+            confidence_sum: 0.0,
         }
+    }
 
-        let mut vendor_index = HashMap::new();
+    /// Helper methods
+    /// Create vendor name normalization aliases
+    fn create_vendor_aliases() -> HashMap<String, String> {
+        let mut aliases = HashMap::new();
 
-        for report in reports {
-            for component in &report.components {
-                if let Some(vendor) = &component.vendor {
-                    let normalized_vendor = self.normalize_vendor_name(vendor);
-
-                    let entry =
-                        vendor_index.entry(normalized_vendor.clone()).or_insert_with(|| {
-                            VendorEntry {
-                                total_reports: 0,
-                                components: HashMap::new(),
-                                recent_reports: Vec::new(),
-                                compatibility_score: 0.0,
-                                last_updated: Utc::now(),
-                            }
-                        });
-
-                    entry.total_reports += 1;
-                    entry.last_updated = Utc::now();
-
-                    // Add component to vendor's component list
-                    if let Some(model) = &component.model {
-                        let component_list = entry
-                            .components
-                            .entry(component.component_type.clone())
-                            .or_insert_with(Vec::new);
-
-                        if !component_list.contains(model) {
-                            component_list.push(model.clone());
-                        }
-                    }
+        // Common vendor name variations
+        aliases.insert("Advanced Micro Devices, Inc.".to_string(), "AMD".to_string());
+        aliases.insert("Advanced Micro Devices [AMD]".to_string(), "AMD".to_string());
+        aliases.insert("Intel Corporation".to_string(), "Intel".to_string());
+        aliases.insert("Intel Corp.".to_string(), "Intel".to_string());
+        aliases.insert("NVIDIA Corporation".to_string(), "NVIDIA".to_string());
+        aliases.insert("NVIDIA Corp.".to_string(), "NVIDIA".to_string());
+        aliases.insert("Realtek Semiconductor Co., Ltd.".to_string(), "Realtek".to_string());
+        aliases.insert("Broadcom Limited".to_string(), "Broadcom".to_string());
+        aliases.insert("Broadcom Inc.".to_string(), "Broadcom".to_string());
 
-                    // Add to recent reports (keep last 10)
-                    if !entry.recent_reports.contains(&report.id) {
-                        entry.recent_reports.push(report.id.clone());
-                        if entry.recent_reports.len() > 10 {
-                            entry.recent_reports.remove(0);
-                        }
-                    }
+        aliases
+    }
 
-                    // Update compatibility score
-                    entry.compatibility_score =
-                        self.calculate_vendor_compatibility_score(&normalized_vendor, reports);
-                }
-            }
-        }
+    /// Normalize vendor name using aliases
+    pub(crate) fn normalize_vendor_name(&self, vendor: &str) -> String {
+        self.vendor_aliases.get(vendor).unwrap_or(&vendor.to_string()).clone()
+    }
 
-        // Sort component lists for each vendor
-        for entry in vendor_index.values_mut() {
-            for component_list in entry.components.values_mut() {
-                component_list.sort();
-                component_list.dedup();
+    /// Add search terms from a text string
+    fn add_search_terms(&self, terms: &mut HashSet<String>, text: &str) {
+        // Split on common delimiters and clean terms
+        for word in text.split(&[' ', '-', '_', '.', '(', ')', '[', ']']) {
+            let clean_word = word.trim().to_lowercase();
+            if clean_word.len() >= 2 && !clean_word.chars().all(char::is_numeric) {
+                terms.insert(clean_word);
             }
         }
+    }
 
-        if self.config.verbose {
-            println!("   Found {} unique vendors", vendor_index.len());
+    /// Calculate compatibility score for a component
+    fn component_compatibility_score(
+        &self,
+        _component: &HardwareComponent,
+        report: &IndexedReport,
+    ) -> f64 {
+        match report.compatibility.status {
+            CompatibilityStatus::Excellent => 100.0,
+            CompatibilityStatus::Good => 85.0,
+            CompatibilityStatus::Fair => 65.0,
+            CompatibilityStatus::Poor => 35.0,
+            CompatibilityStatus::Unknown => 50.0,
         }
+    }
 
-        Ok(vendor_index)
+    /// Get compatibility status for a component
+    fn get_component_compatibility_status(
+        &self,
+        _component: &HardwareComponent,
+        report: &IndexedReport,
+    ) -> CompatibilityStatus {
+        // For now, use overall report status
+        // In a real implementation, this would analyze component-specific data
+        report.compatibility.status.clone()
     }
 
-    /// Build component type organized index
-    #[allow(clippy::excessive_nesting)]
-    fn build_component_index(&self, reports: &[IndexedReport]) -> Result<ComponentIndex> {
-        if self.config.verbose {
-            println!("Building component index...");
-        }
+    /// Print summary of built indices
+    fn print_index_summary(&self, indices: &IndexCollection) {
+        println!("\nIndex Summary:");
+        println!("   Vendors: {}", indices.by_vendor.len());
+        println!("   Component Types: {}", indices.by_component.len());
+        println!("   Kernel Versions: {}", indices.by_kernel.len());
+        println!("   Distributions: {}", indices.by_distribution.len());
+        println!("   Search Terms: {}", indices.search_terms.len());
+        println!(
+            "   Hardware/Kernel Combinations: {}",
+            indices.compatibility_matrix.values().map(|v| v.len()).sum::<usize>()
+        );
+        println!("   Total Reports: {}", indices.statistics.total_reports);
+        println!("   Invalid Reports (not scored): {}", indices.statistics.invalid_reports);
+        println!("   Virtualized Reports (not scored): {}", indices.statistics.virtualized_reports);
+        println!("   Unique Systems: {}", indices.statistics.unique_systems);
+        println!("   Unknown Devices: {}", indices.unknown_devices.len());
+        println!("   Excluded (below min_reports): {}", indices.excluded.len());
+        println!("   Hardware With Known Issues: {}", indices.known_issues.len());
+    }
+}
 
-        let mut component_index = HashMap::new();
+/// Indices under construction, fed one batch of reports at a time
+///
+/// Reports are only borrowed while their batch is added; what is kept between
+/// batches grows with the number of distinct hardware models, kernels and
+/// search terms rather than with the number of reports.
+pub struct IndexAccumulator<'b, 'a> {
+    builder: &'b IndexBuilder<'a>,
+    indices: IndexCollection,
+    /// Sum of component scores and component count per vendor
+    vendor_scores: HashMap<String, (f64, usize)>,
+    /// Popular model tallies per component type
+    model_tallies: HashMap<String, HashMap<(String, String), ModelTally>>,
+    /// Model tallies across all component types
+    top_hardware: HashMap<(String, String), ModelTally>,
+    unique_systems: HashSet<String>,
+    all_kernels: HashSet<String>,
+    all_distributions: HashSet<String>,
+    unknown_devices: HashMap<(String, String, String), UnknownDeviceEntry>,
+    /// Sum of validator confidence across all reports
+    confidence_sum: f64,
+}
 
+impl IndexAccumulator<'_, '_> {
+    /// Merge a batch of reports into the indices
+    ///
+    /// Reports that failed validation, and those from virtual machines and
+    /// containers whose hardware is emulated or shared with the host, only
+    /// count towards the statistics.
+    pub fn add_reports(&mut self, reports: &[IndexedReport]) {
         for report in reports {
-            for component in &report.components {
-                let entry =
-                    component_index.entry(component.component_type.clone()).or_insert_with(|| {
-                        ComponentEntry {
-                            total_reports: 0,
-                            vendors: HashMap::new(),
-                            popular_models: Vec::new(),
-                            compatibility_distribution: HashMap::new(),
-                        }
-                    });
-
-                entry.total_reports += 1;
-
-                // Count vendors for this component type
-                if let Some(vendor) = &component.vendor {
-                    let normalized_vendor = self.normalize_vendor_name(vendor);
-                    *entry.vendors.entry(normalized_vendor).or_insert(0) += 1;
-                }
-
-                // Update compatibility distribution
-                let compat_status = self.get_component_compatibility_status(component, report);
-                *entry.compatibility_distribution.entry(compat_status).or_insert(0) += 1;
+            self.add_to_validation_summary(report);
+            self.add_to_statistics(report);
+            if !report.validation.valid || report.metadata.virtualization.is_some() {
+                continue;
             }
+            self.add_to_vendor_index(report);
+            self.add_to_component_index(report);
+            self.add_to_kernel_index(report);
+            self.add_to_distribution_index(report);
+            self.add_to_search_terms(report);
+            self.add_to_compatibility_matrix(report);
+            self.add_to_unknown_devices(report);
+            self.add_to_known_issues(report);
+            self.add_to_device_index(report);
         }
+    }
 
-        // Build popular models for each component type
-        for (component_type, entry) in component_index.iter_mut() {
-            entry.popular_models = self.build_popular_models_for_component(component_type, reports);
+    /// Resolve corpus-wide values and return the finished indices
+    pub fn finish(mut self) -> IndexCollection {
+        let builder = self.builder;
+        let verbose = builder.config.verbose;
+
+        for (vendor, entry) in self.indices.by_vendor.iter_mut() {
+            // Sort component lists for each vendor
+            for component_list in entry.components.values_mut() {
+                component_list.sort();
+                component_list.dedup();
+            }
+            entry.compatibility_score = match self.vendor_scores.get(vendor) {
+                Some((total, count)) if *count > 0 => total / *count as f64,
+                _ => 0.0,
+            };
         }
 
-        if self.config.verbose {
-            println!("   Found {} component types", component_index.len());
+        for models in self.indices.by_device.values_mut() {
+            models.sort();
         }
 
-        Ok(component_index)
-    }
+        self.apply_min_reports();
 
-    /// Build kernel version organized index
-    #[allow(clippy::excessive_nesting)]
-    fn build_kernel_index(&self, reports: &[IndexedReport]) -> Result<KernelIndex> {
-        if self.config.verbose {
-            println!("Building kernel index...");
+        // Build popular models for each component type
+        for (component_type, entry) in self.indices.by_component.iter_mut() {
+            let tallies = self.model_tallies.remove(component_type).unwrap_or_default();
+            entry.popular_models = self.builder.popular_models(tallies);
         }
 
-        let mut kernel_index = HashMap::new();
-
-        for report in reports {
-            let kernel_version = &report.metadata.kernel_version;
+        // Sort kernel lists and keep most common
+        for entry in self.indices.by_distribution.values_mut() {
+            entry.common_kernels.sort();
+            entry.common_kernels.dedup();
+            entry.common_kernels.truncate(10); // Keep top 10 most common kernels
+        }
 
-            let entry = kernel_index.entry(kernel_version.clone()).or_insert_with(|| KernelEntry {
-                total_reports: 0,
-                compatibility_stats: HashMap::new(),
-                problematic_hardware: Vec::new(),
-                release_date: None, // Would be populated from external data
-            });
+        // Deduplicate and sort report lists
+        for report_list in self.indices.search_terms.values_mut() {
+            report_list.sort();
+            report_list.dedup();
+        }
 
-            entry.total_reports += 1;
+        let mut unknown_devices: Vec<_> = self.unknown_devices.into_values().collect();
+        unknown_devices.sort_by(|a, b| {
+            b.report_count.cmp(&a.report_count).then_with(|| {
+                (&a.bus, &a.vendor_id, &a.device_id).cmp(&(&b.bus, &b.vendor_id, &b.device_id))
+            })
+        });
+        self.indices.unknown_devices = unknown_devices;
+
+        let stats = &mut self.indices.statistics;
+        stats.unique_systems = self.unique_systems.len();
+        stats.total_vendors = self.indices.by_vendor.len();
+        stats.component_types = self.indices.by_component.len();
+        stats.kernel_versions = self.all_kernels.len();
+        stats.distributions = self.all_distributions.len();
+        stats.top_hardware = top_hardware_list(self.top_hardware);
+        // Build growth statistics (simplified)
+        stats.growth_stats = vec![GrowthDataPoint {
+            date: Utc::now(),
+            total_reports: stats.total_reports,
+            new_reports: stats.total_reports,
+        }];
+        stats.last_updated = Utc::now();
 
-            // Update compatibility statistics
-            let status_str = match report.compatibility.status {
-                CompatibilityStatus::Excellent => "excellent",
-                CompatibilityStatus::Good => "good",
-                CompatibilityStatus::Fair => "fair",
-                CompatibilityStatus::Poor => "poor",
-                CompatibilityStatus::Unknown => "unknown",
-            };
-            *entry.compatibility_stats.entry(status_str.to_string()).or_insert(0) += 1;
-
-            // Track problematic hardware
-            if matches!(
-                report.compatibility.status,
-                CompatibilityStatus::Poor | CompatibilityStatus::Fair
-            ) {
-                for component in &report.components {
-                    if let (Some(vendor), Some(model)) = (&component.vendor, &component.model) {
-                        let hw_id = format!("{} {}", vendor, model);
-                        if !entry.problematic_hardware.contains(&hw_id) {
-                            entry.problematic_hardware.push(hw_id);
-                        }
-                    }
-                }
-            }
+        let validation = &mut self.indices.validation;
+        if validation.total_reports > 0 {
+            validation.average_confidence = self.confidence_sum / validation.total_reports as f64;
         }
+        validation.invalid.sort_by(|a, b| a.id.cmp(&b.id));
 
-        if self.config.verbose {
-            println!("   Found {} kernel versions", kernel_index.len());
+        if verbose {
+            println!("All indices built successfully");
+            builder.print_index_summary(&self.indices);
         }
 
-        Ok(kernel_index)
+        self.indices
     }
 
-    /// Build Linux distribution organized index
-    fn build_distribution_index(&self, reports: &[IndexedReport]) -> Result<DistributionIndex> {
-        if self.config.verbose {
-            println!("Building distribution index...");
+    /// Exclude hardware with fewer than `min_reports` reports
+    ///
+    /// Excluded models are dropped from vendor model lists and the
+    /// compatibility matrix, and vendors left without models and component
+    /// types below the threshold are dropped entirely. Everything excluded is
+    /// listed so contributors can see where reports are needed. With
+    /// `include_low_sample` the hardware is kept and its scores are flagged
+    /// as low confidence instead.
+    fn apply_min_reports(&mut self) {
+        let min_reports = self.builder.config.min_reports;
+        let sparse: HashSet<String> = self
+            .top_hardware
+            .iter()
+            .filter(|(_, (count, _))| *count < min_reports)
+            .map(|((vendor, model), _)| format!("{} {}", vendor, model))
+            .collect();
+
+        if self.builder.config.include_low_sample {
+            let matrix = self.indices.compatibility_matrix.iter_mut();
+            for (_, kernels) in matrix.filter(|(hw_key, _)| sparse.contains(*hw_key)) {
+                for score in kernels.values_mut() {
+                    score.low_sample = true;
+                    score.confidence = ConfidenceLevel::Low;
+                }
+            }
+            return;
+        }
+
+        for (component_type, tallies) in &self.model_tallies {
+            for (vendor, model) in tallies.keys() {
+                let Some((count, _)) = self.top_hardware.get(&(vendor.clone(), model.clone()))
+                else {
+                    continue;
+                };
+                if *count < min_reports {
+                    self.indices.excluded.push(ExcludedHardware {
+                        component_type: component_type.clone(),
+                        vendor: vendor.clone(),
+                        model: model.clone(),
+                        report_count: *count,
+                        reports_needed: min_reports - count,
+                    });
+                }
+            }
         }
+        self.indices.excluded.sort_by(|a, b| {
+            b.report_count.cmp(&a.report_count).then_with(|| {
+                (&a.component_type, &a.vendor, &a.model).cmp(&(
+                    &b.component_type,
+                    &b.vendor,
+                    &b.model,
+                ))
+            })
+        });
 
-        let mut dist_index = HashMap::new();
+        self.indices.compatibility_matrix.retain(|hw_key, _| !sparse.contains(hw_key));
+        self.indices.by_device.retain(|_, models| {
+            models.retain(|hw_key| !sparse.contains(hw_key));
+            !models.is_empty()
+        });
+        self.indices.by_vendor.retain(|vendor, entry| {
+            let had_models = !entry.components.is_empty();
+            for models in entry.components.values_mut() {
+                models.retain(|model| !sparse.contains(&format!("{} {}", vendor, model)));
+            }
+            entry.components.retain(|_, models| !models.is_empty());
+            !had_models || !entry.components.is_empty()
+        });
+        self.indices.by_component.retain(|_, entry| entry.total_reports >= min_reports);
+    }
 
-        for report in reports {
-            let distribution = &report.metadata.distribution;
+    /// Add a report to the vendor-organized index
+    fn add_to_vendor_index(&mut self, report: &IndexedReport) {
+        for component in &report.components {
+            let Some(vendor) = &component.vendor else {
+                continue;
+            };
+            let normalized_vendor = self.builder.normalize_vendor_name(vendor);
 
             let entry =
-                dist_index.entry(distribution.clone()).or_insert_with(|| DistributionEntry {
-                    total_reports: 0,
-                    vendor_compatibility: HashMap::new(),
-                    common_kernels: Vec::new(),
-                    notes: Vec::new(),
+                self.indices.by_vendor.entry(normalized_vendor.clone()).or_insert_with(|| {
+                    VendorEntry {
+                        total_reports: 0,
+                        components: HashMap::new(),
+                        recent_reports: Vec::new(),
+                        compatibility_score: 0.0,
+                        last_updated: Utc::now(),
+                    }
                 });
 
             entry.total_reports += 1;
-
-            // Track kernel versions for this distribution
-            let kernel = &report.metadata.kernel_version;
-            if !entry.common_kernels.contains(kernel) {
-                entry.common_kernels.push(kernel.clone());
+            entry.last_updated = Utc::now();
+
+            // Add component to vendor's component list
+            if let Some(model) = &component.model {
+                let component_list =
+                    entry.components.entry(component.component_type.clone()).or_default();
+                if !component_list.contains(model) {
+                    component_list.push(model.clone());
+                }
             }
 
-            // Update vendor compatibility scores
-            for component in &report.components {
-                if let Some(vendor) = &component.vendor {
-                    let normalized_vendor = self.normalize_vendor_name(vendor);
-                    let compat_score = self.component_compatibility_score(component, report);
-
-                    let current_score =
-                        entry.vendor_compatibility.get(&normalized_vendor).unwrap_or(&0.0);
-                    let new_score = (current_score + compat_score) / 2.0;
-                    entry.vendor_compatibility.insert(normalized_vendor, new_score);
+            // Add to recent reports (keep last 10)
+            if !entry.recent_reports.contains(&report.id) {
+                entry.recent_reports.push(report.id.clone());
+                if entry.recent_reports.len() > 10 {
+                    entry.recent_reports.remove(0);
                 }
             }
-        }
 
-        // Sort kernel lists and keep most common
-        for entry in dist_index.values_mut() {
-            entry.common_kernels.sort();
-            entry.common_kernels.dedup();
-            entry.common_kernels.truncate(10); // Keep top 10 most common kernels
+            // Tally the vendor's average compatibility score
+            let score = self.builder.component_compatibility_score(component, report);
+            let (total, count) = self.vendor_scores.entry(normalized_vendor).or_default();
+            *total += score;
+            *count += 1;
         }
+    }
 
-        if self.config.verbose {
-            println!("   Found {} distributions", dist_index.len());
-        }
+    /// Add a report to the component type organized index
+    fn add_to_component_index(&mut self, report: &IndexedReport) {
+        for component in &report.components {
+            let entry =
+                self.indices.by_component.entry(component.component_type.clone()).or_insert_with(
+                    || ComponentEntry {
+                        total_reports: 0,
+                        vendors: HashMap::new(),
+                        popular_models: Vec::new(),
+                        compatibility_distribution: HashMap::new(),
+                    },
+                );
 
-        Ok(dist_index)
-    }
+            entry.total_reports += 1;
 
-    /// Build full-text search terms index
-    fn build_search_terms_index(&self, reports: &[IndexedReport]) -> Result<SearchTermsIndex> {
-        if self.config.verbose {
-            println!("Building search terms index...");
-        }
+            // Count vendors for this component type
+            if let Some(vendor) = &component.vendor {
+                let normalized_vendor = self.builder.normalize_vendor_name(vendor);
+                *entry.vendors.entry(normalized_vendor).or_insert(0) += 1;
+            }
 
-        let mut search_index = HashMap::new();
+            // Update compatibility distribution
+            let compat_status = self.builder.get_component_compatibility_status(component, report);
+            *entry.compatibility_distribution.entry(compat_status).or_insert(0) += 1;
 
-        for report in reports {
-            let mut terms = HashSet::new();
+            if let (Some(vendor), Some(model)) = (&component.vendor, &component.model) {
+                let key = (self.builder.normalize_vendor_name(vendor), model.clone());
+                let score = self.builder.component_compatibility_score(component, report);
+                let tallies =
+                    self.model_tallies.entry(component.component_type.clone()).or_default();
+                tally_model(tallies, key, score);
+            }
+        }
+    }
 
-            // Extract search terms from components
-            for component in &report.components {
-                // Add vendor terms
-                if let Some(vendor) = &component.vendor {
-                    self.add_search_terms(&mut terms, vendor);
-                }
+    /// Add a report to the kernel version organized index
+    fn add_to_kernel_index(&mut self, report: &IndexedReport) {
+        let kernel_version = &report.metadata.kernel_version;
 
-                // Add model terms
-                if let Some(model) = &component.model {
-                    self.add_search_terms(&mut terms, model);
-                }
+        let entry =
+            self.indices.by_kernel.entry(kernel_version.clone()).or_insert_with(|| KernelEntry {
+                total_reports: 0,
+                compatibility_stats: HashMap::new(),
+                problematic_hardware: Vec::new(),
+                release_date: None, // Would be populated from external data
+            });
 
-                // Add component type
-                self.add_search_terms(&mut terms, &component.component_type);
+        entry.total_reports += 1;
 
-                // Add driver name
-                if let Some(driver) = &component.driver {
-                    self.add_search_terms(&mut terms, driver);
+        // Update compatibility statistics
+        let status_str = match report.compatibility.status {
+            CompatibilityStatus::Excellent => "excellent",
+            CompatibilityStatus::Good => "good",
+            CompatibilityStatus::Fair => "fair",
+            CompatibilityStatus::Poor => "poor",
+            CompatibilityStatus::Unknown => "unknown",
+        };
+        *entry.compatibility_stats.entry(status_str.to_string()).or_insert(0) += 1;
+
+        // Track problematic hardware
+        if matches!(
+            report.compatibility.status,
+            CompatibilityStatus::Poor | CompatibilityStatus::Fair
+        ) {
+            let hw_ids = report.components.iter().filter_map(|component| {
+                Some(format!("{} {}", component.vendor.as_ref()?, component.model.as_ref()?))
+            });
+            for hw_id in hw_ids {
+                if !entry.problematic_hardware.contains(&hw_id) {
+                    entry.problematic_hardware.push(hw_id);
                 }
             }
+        }
+    }
 
-            // Add distribution terms
-            self.add_search_terms(&mut terms, &report.metadata.distribution);
-
-            // Add kernel version terms
-            self.add_search_terms(&mut terms, &report.metadata.kernel_version);
+    /// Add a report to the Linux distribution organized index
+    fn add_to_distribution_index(&mut self, report: &IndexedReport) {
+        let distribution = &report.metadata.distribution;
 
-            // Add terms to index
-            for term in terms {
-                search_index.entry(term).or_insert_with(Vec::new).push(report.id.clone());
+        let entry = self.indices.by_distribution.entry(distribution.clone()).or_insert_with(|| {
+            DistributionEntry {
+                total_reports: 0,
+                vendor_compatibility: HashMap::new(),
+                common_kernels: Vec::new(),
+                notes: Vec::new(),
             }
-        }
+        });
 
-        // Deduplicate and sort report lists
-        for report_list in search_index.values_mut() {
-            report_list.sort();
-            report_list.dedup();
-        }
+        entry.total_reports += 1;
 
-        if self.config.verbose {
-            println!("   Built index with {} search terms", search_index.len());
+        // Track kernel versions for this distribution
+        let kernel = &report.metadata.kernel_version;
+        if !entry.common_kernels.contains(kernel) {
+            entry.common_kernels.push(kernel.clone());
         }
 
-        Ok(search_index)
-    }
+        // Update vendor compatibility scores
+        for component in &report.components {
+            if let Some(vendor) = &component.vendor {
+                let normalized_vendor = self.builder.normalize_vendor_name(vendor);
+                let compat_score = self.builder.component_compatibility_score(component, report);
 
-    /// Build hardware compatibility scoring matrix
-    #[allow(clippy::excessive_nesting)]
-    fn build_compatibility_matrix(&self, reports: &[IndexedReport]) -> Result<CompatibilityMatrix> {
-        if self.config.verbose {
-            println!("Building compatibility matrix...");
+                let current_score =
+                    entry.vendor_compatibility.get(&normalized_vendor).unwrap_or(&0.0);
+                let new_score = (current_score + compat_score) / 2.0;
+                entry.vendor_compatibility.insert(normalized_vendor, new_score);
+            }
         }
+    }
 
-        let mut matrix = HashMap::new();
+    /// Add a report's terms to the full-text search index
+    fn add_to_search_terms(&mut self, report: &IndexedReport) {
+        let builder = self.builder;
+        let mut terms = HashSet::new();
 
-        for report in reports {
-            for component in &report.components {
-                if let (Some(vendor), Some(model)) = (&component.vendor, &component.model) {
-                    let hw_key = format!("{} {}", self.normalize_vendor_name(vendor), model);
-                    let kernel_key = format!(
-                        "{}_{}",
-                        report.metadata.kernel_version, report.metadata.distribution
-                    );
-
-                    let hardware_entry = matrix.entry(hw_key).or_insert_with(HashMap::new);
-
-                    let score_entry =
-                        hardware_entry.entry(kernel_key).or_insert_with(|| CompatibilityScore {
-                            score: 0,
-                            driver: component.driver.clone(),
-                            sample_size: 0,
-                            confidence: ConfidenceLevel::Low,
-                            last_updated: Utc::now(),
-                        });
-
-                    // Update score based on compatibility
-                    let component_score =
-                        self.component_compatibility_score(component, report) as u8;
-                    let total_samples = score_entry.sample_size + 1;
-
-                    // Calculate weighted average
-                    score_entry.score = (((score_entry.score as usize * score_entry.sample_size)
-                        + component_score as usize)
-                        / total_samples) as u8;
-                    score_entry.sample_size = total_samples;
-                    score_entry.last_updated = Utc::now();
-
-                    // Update confidence level
-                    score_entry.confidence = match total_samples {
-                        1..=2 => ConfidenceLevel::Low,
-                        3..=9 => ConfidenceLevel::Medium,
-                        _ => ConfidenceLevel::High,
-                    };
-
-                    // Update driver if more recent
-                    if component.driver.is_some() {
-                        score_entry.driver = component.driver.clone();
-                    }
-                }
+        // Extract search terms from components
+        for component in &report.components {
+            // Add vendor terms
+            if let Some(vendor) = &component.vendor {
+                builder.add_search_terms(&mut terms, vendor);
             }
-        }
 
-        if self.config.verbose {
-            let total_combinations = matrix.values().map(|v| v.len()).sum::<usize>();
-            println!("   Built matrix with {} hardware/kernel combinations", total_combinations);
-        }
+            // Add model terms
+            if let Some(model) = &component.model {
+                builder.add_search_terms(&mut terms, model);
+            }
 
-        Ok(matrix)
-    }
+            // Add component type
+            builder.add_search_terms(&mut terms, &component.component_type);
 
-    /// Build aggregated statistics
-    fn build_statistics(&self, reports: &[IndexedReport]) -> Result<Statistics> {
-        if self.config.verbose {
-            println!("Building statistics...");
+            // Add driver name
+            if let Some(driver) = &component.driver {
+                builder.add_search_terms(&mut terms, driver);
+            }
         }
 
-        let mut stats = Statistics {
-            total_reports: reports.len(),
-            last_updated: Utc::now(),
-            ..Default::default()
-        };
+        // Add distribution terms
+        builder.add_search_terms(&mut terms, &report.metadata.distribution);
 
-        // Count unique systems
-        let mut unique_systems = HashSet::new();
-        let mut all_vendors = HashSet::new();
-        let mut all_component_types = HashSet::new();
-        let mut all_kernels = HashSet::new();
-        let mut all_distributions = HashSet::new();
+        // Add kernel version terms
+        builder.add_search_terms(&mut terms, &report.metadata.kernel_version);
 
-        for report in reports {
-            unique_systems.insert(&report.metadata.system_id);
-            all_kernels.insert(&report.metadata.kernel_version);
-            all_distributions.insert(&report.metadata.distribution);
-
-            // Count compatibility status
-            *stats
-                .compatibility_overview
-                .entry(report.compatibility.status.clone())
-                .or_insert(0) += 1;
-
-            for component in &report.components {
-                all_component_types.insert(&component.component_type);
-                if let Some(vendor) = &component.vendor {
-                    all_vendors.insert(self.normalize_vendor_name(vendor));
-                }
-            }
+        // Add terms to index
+        for term in terms {
+            self.indices.search_terms.entry(term).or_default().push(report.id.clone());
         }
+    }
 
-        stats.unique_systems = unique_systems.len();
-        stats.total_vendors = all_vendors.len();
-        stats.component_types = all_component_types.len();
-        stats.kernel_versions = all_kernels.len();
-        stats.distributions = all_distributions.len();
-
-        // Build top hardware list
-        stats.top_hardware = self.build_top_hardware_list(reports);
+    /// Add a report to the hardware compatibility scoring matrix
+    fn add_to_compatibility_matrix(&mut self, report: &IndexedReport) {
+        for component in &report.components {
+            let (Some(vendor), Some(model)) = (&component.vendor, &component.model) else {
+                continue;
+            };
+            let hw_key = format!("{} {}", self.builder.normalize_vendor_name(vendor), model);
+            let kernel_key =
+                format!("{}_{}", report.metadata.kernel_version, report.metadata.distribution);
+
+            let hardware_entry = self.indices.compatibility_matrix.entry(hw_key).or_default();
+
+            let score_entry =
+                hardware_entry.entry(kernel_key).or_insert_with(|| CompatibilityScore {
+                    score: 0,
+                    driver: component.driver.clone(),
+                    sample_size: 0,
+                    confidence: ConfidenceLevel::Low,
+                    low_sample: false,
+                    last_updated: Utc::now(),
+                });
 
-        // Build growth statistics (simplified)
-        stats.growth_stats = self.build_growth_statistics(reports);
+            // Update score based on compatibility
+            let component_score =
+                self.builder.component_compatibility_score(component, report) as u8;
+            let total_samples = score_entry.sample_size + 1;
+
+            // Calculate weighted average
+            score_entry.score = (((score_entry.score as usize * score_entry.sample_size)
+                + component_score as usize)
+                / total_samples) as u8;
+            score_entry.sample_size = total_samples;
+            score_entry.last_updated = Utc::now();
+
+            // Update confidence level
+            score_entry.confidence = match total_samples {
+                1..=2 => ConfidenceLevel::Low,
+                3..=9 => ConfidenceLevel::Medium,
+                _ => ConfidenceLevel::High,
+            };
 
-        if self.config.verbose {
-            println!(
-                "   Statistics: {} unique systems, {} vendors, {} distributions",
-                stats.unique_systems, stats.total_vendors, stats.distributions
-            );
+            // Update driver if more recent
+            if component.driver.is_some() {
+                score_entry.driver = component.driver.clone();
+            }
         }
-
-        Ok(stats)
     }
 
-    /// Helper methods
-    /// Create vendor name normalization aliases
-    fn create_vendor_aliases() -> HashMap<String, String> {
-        let mut aliases = HashMap::new();
+    /// Aggregate unidentified devices so the most reported can be prioritized
+    fn add_to_unknown_devices(&mut self, report: &IndexedReport) {
+        let mut seen = HashSet::new();
+        for device in &report.unknown_devices {
+            let key = (
+                device.bus.clone(),
+                device.vendor_id.to_lowercase(),
+                device.device_id.to_lowercase(),
+            );
+            if !seen.insert(key.clone()) {
+                continue;
+            }
 
-        // Common vendor name variations
-        aliases.insert("Advanced Micro Devices, Inc.".to_string(), "AMD".to_string());
-        aliases.insert("Advanced Micro Devices [AMD]".to_string(), "AMD".to_string());
-        aliases.insert("Intel Corporation".to_string(), "Intel".to_string());
-        aliases.insert("Intel Corp.".to_string(), "Intel".to_string());
-        aliases.insert("NVIDIA Corporation".to_string(), "NVIDIA".to_string());
-        aliases.insert("NVIDIA Corp.".to_string(), "NVIDIA".to_string());
-        aliases.insert("Realtek Semiconductor Co., Ltd.".to_string(), "Realtek".to_string());
-        aliases.insert("Broadcom Limited".to_string(), "Broadcom".to_string());
-        aliases.insert("Broadcom Inc.".to_string(), "Broadcom".to_string());
+            let entry = self.unknown_devices.entry(key).or_insert_with(|| UnknownDeviceEntry {
+                bus: device.bus.clone(),
+                vendor_id: device.vendor_id.to_lowercase(),
+                device_id: device.device_id.to_lowercase(),
+                class_code: device.class_code.clone(),
+                class_name: device
+                    .class_code
+                    .as_deref()
+                    .filter(|_| device.bus == "pci")
+                    .and_then(pci_class::describe),
+                modalias: device.modalias.clone(),
+                report_count: 0,
+                sample_reports: Vec::new(),
+            });
 
-        aliases
+            entry.report_count += 1;
+            if entry.modalias.is_none() {
+                entry.modalias = device.modalias.clone();
+            }
+            if entry.sample_reports.len() < 10 {
+                entry.sample_reports.push(report.id.clone());
+            }
+        }
     }
 
-    /// Normalize vendor name using aliases
-    fn normalize_vendor_name(&self, vendor: &str) -> String {
-        self.vendor_aliases.get(vendor).unwrap_or(&vendor.to_string()).clone()
-    }
+    /// Collect per-component issues and workarounds by hardware model
+    fn add_to_known_issues(&mut self, report: &IndexedReport) {
+        let mut seen = HashSet::new();
+        for component in &report.components {
+            let (Some(vendor), Some(model), Some(device_id)) =
+                (&component.vendor, &component.model, &component.device_id)
+            else {
+                continue;
+            };
+            let Some(compatibility) = report.compatibility.components.get(device_id) else {
+                continue;
+            };
+            if compatibility.issues.is_empty() && compatibility.workarounds.is_empty() {
+                continue;
+            }
 
-    /// Add search terms from a text string
-    fn add_search_terms(&self, terms: &mut HashSet<String>, text: &str) {
-        // Split on common delimiters and clean terms
-        for word in text.split(&[' ', '-', '_', '.', '(', ')', '[', ']']) {
-            let clean_word = word.trim().to_lowercase();
-            if clean_word.len() >= 2 && !clean_word.chars().all(char::is_numeric) {
-                terms.insert(clean_word);
+            let hw_key = format!("{} {}", self.builder.normalize_vendor_name(vendor), model);
+            if !seen.insert(hw_key.clone()) {
+                continue;
             }
-        }
-    }
 
-    /// Calculate compatibility score for a component
-    fn component_compatibility_score(
-        &self,
-        _component: &HardwareComponent,
-        report: &IndexedReport,
-    ) -> f64 {
-        match report.compatibility.status {
-            CompatibilityStatus::Excellent => 100.0,
-            CompatibilityStatus::Good => 85.0,
-            CompatibilityStatus::Fair => 65.0,
-            CompatibilityStatus::Poor => 35.0,
-            CompatibilityStatus::Unknown => 50.0,
+            let entry = self.indices.known_issues.entry(hw_key).or_default();
+            entry.device_id.get_or_insert_with(|| device_id.clone());
+            entry.report_count += 1;
+            for issue in &compatibility.issues {
+                if !entry.issues.contains(issue) {
+                    entry.issues.push(issue.clone());
+                }
+            }
+            for workaround in &compatibility.workarounds {
+                if !entry.workarounds.contains(workaround) {
+                    entry.workarounds.push(workaround.clone());
+                }
+            }
         }
     }
 
-    /// Get compatibility status for a component
-    fn get_component_compatibility_status(
-        &self,
-        _component: &HardwareComponent,
-        report: &IndexedReport,
-    ) -> CompatibilityStatus {
-        // For now, use overall report status
-        // In a real implementation, this would analyze component-specific data
-        report.compatibility.status.clone()
-    }
-
-    /// Calculate vendor compatibility score
-    #[allow(clippy::excessive_nesting)]
-    fn calculate_vendor_compatibility_score(&self, vendor: &str, reports: &[IndexedReport]) -> f64 {
-        let mut total_score = 0.0;
-        let mut count = 0;
-
-        for report in reports {
-            for component in &report.components {
-                if let Some(comp_vendor) = &component.vendor {
-                    if self.normalize_vendor_name(comp_vendor) == *vendor {
-                        total_score += self.component_compatibility_score(component, report);
-                        count += 1;
-                    }
-                }
+    /// Map device IDs to the hardware models reported with them
+    fn add_to_device_index(&mut self, report: &IndexedReport) {
+        for component in &report.components {
+            let (Some(vendor), Some(model), Some(device_id)) =
+                (&component.vendor, &component.model, &component.device_id)
+            else {
+                continue;
+            };
+            let hw_key = format!("{} {}", self.builder.normalize_vendor_name(vendor), model);
+            let models = self.indices.by_device.entry(device_id.to_lowercase()).or_default();
+            if !models.contains(&hw_key) {
+                models.push(hw_key);
             }
         }
+    }
 
-        if count > 0 {
-            total_score / count as f64
+    /// Record a report's validation result
+    fn add_to_validation_summary(&mut self, report: &IndexedReport) {
+        let validation = &report.validation;
+        let summary = &mut self.indices.validation;
+        summary.min_confidence = if summary.total_reports == 0 {
+            validation.confidence_score
         } else {
-            0.0
+            summary.min_confidence.min(validation.confidence_score)
+        };
+        summary.total_reports += 1;
+        summary.total_warnings += validation.warning_count;
+        self.confidence_sum += validation.confidence_score;
+
+        if validation.valid {
+            summary.valid_reports += 1;
+        } else {
+            summary.invalid_reports += 1;
+            summary.invalid.push(InvalidReport {
+                id: report.id.clone(),
+                confidence_score: validation.confidence_score,
+                errors: validation.errors.clone(),
+            });
         }
     }
 
-    /// Build popular models for a component type
-    #[allow(clippy::excessive_nesting)]
-    fn build_popular_models_for_component(
-        &self,
-        component_type: &str,
-        reports: &[IndexedReport],
-    ) -> Vec<PopularModel> {
-        let mut model_counts: HashMap<(String, String), usize> = HashMap::new();
-        let mut model_scores: HashMap<(String, String), f64> = HashMap::new();
-
-        for report in reports {
-            for component in &report.components {
-                if component.component_type == component_type {
-                    if let (Some(vendor), Some(model)) = (&component.vendor, &component.model) {
-                        let key = (self.normalize_vendor_name(vendor), model.clone());
-                        *model_counts.entry(key.clone()).or_insert(0) += 1;
-
-                        let current_score = model_scores.get(&key).unwrap_or(&0.0);
-                        let component_score = self.component_compatibility_score(component, report);
-                        model_scores.insert(key, (current_score + component_score) / 2.0);
-                    }
-                }
+    /// Add a report to the aggregated statistics
+    fn add_to_statistics(&mut self, report: &IndexedReport) {
+        let stats = &mut self.indices.statistics;
+        stats.total_reports += 1;
+        self.unique_systems.insert(report.metadata.system_id.clone());
+        self.all_kernels.insert(report.metadata.kernel_version.clone());
+        self.all_distributions.insert(report.metadata.distribution.clone());
+        if !report.validation.valid {
+            stats.invalid_reports += 1;
+            return;
+        }
+        if report.metadata.virtualization.is_some() {
+            stats.virtualized_reports += 1;
+            return;
+        }
+
+        // Count compatibility status
+        *stats.compatibility_overview.entry(report.compatibility.status.clone()).or_insert(0) += 1;
+
+        for component in &report.components {
+            if let (Some(vendor), Some(model)) = (&component.vendor, &component.model) {
+                let key = (self.builder.normalize_vendor_name(vendor), model.clone());
+                let score = self.builder.component_compatibility_score(component, report);
+                tally_model(&mut self.top_hardware, key, score);
             }
         }
+    }
+}
 
-        let mut popular_models: Vec<PopularModel> = model_counts
+impl IndexBuilder<'_> {
+    /// Most reported models of a component type meeting `min_reports`
+    fn popular_models(&self, tallies: HashMap<(String, String), ModelTally>) -> Vec<PopularModel> {
+        let mut popular_models: Vec<PopularModel> = tallies
             .into_iter()
-            .filter(|(_, count)| *count >= self.config.min_reports)
-            .map(|((vendor, model), count)| PopularModel {
-                vendor: vendor.clone(),
-                model: model.clone(),
+            .filter(|(_, (count, _))| *count >= self.config.min_reports)
+            .map(|((vendor, model), (count, score))| PopularModel {
+                vendor,
+                model,
                 report_count: count,
-                avg_compatibility: *model_scores.get(&(vendor, model)).unwrap_or(&0.0),
+                avg_compatibility: score,
             })
             .collect();
 
@@ -582,60 +749,32 @@ impl<'a> IndexBuilder<'a> {
         popular_models.truncate(20); // Keep top 20
         popular_models
     }
+}
 
-    /// Build top hardware list across all categories
-    fn build_top_hardware_list(&self, reports: &[IndexedReport]) -> Vec<PopularModel> {
-        let mut all_hardware: HashMap<(String, String), (usize, f64)> = HashMap::new();
-
-        for report in reports {
-            for component in &report.components {
-                if let (Some(vendor), Some(model)) = (&component.vendor, &component.model) {
-                    let key = (self.normalize_vendor_name(vendor), model.clone());
-                    let (count, score) = all_hardware.entry(key).or_insert((0, 0.0));
-                    *count += 1;
-                    *score = (*score + self.component_compatibility_score(component, report)) / 2.0;
-                }
-            }
-        }
-
-        let mut top_hardware: Vec<PopularModel> = all_hardware
-            .into_iter()
-            .map(|((vendor, model), (count, score))| PopularModel {
-                vendor,
-                model,
-                report_count: count,
-                avg_compatibility: score,
-            })
-            .collect();
-
-        top_hardware.sort_by(|a, b| b.report_count.cmp(&a.report_count));
-        top_hardware.truncate(50);
-        top_hardware
-    }
-
-    /// Build growth statistics over time
-    fn build_growth_statistics(&self, reports: &[IndexedReport]) -> Vec<GrowthDataPoint> {
-        // Simplified growth stats - in real implementation would analyze dates
-        vec![GrowthDataPoint {
-            date: Utc::now(),
-            total_reports: reports.len(),
-            new_reports: reports.len(),
-        }]
-    }
+/// Count a model and fold its score into the running average
+fn tally_model(
+    tallies: &mut HashMap<(String, String), ModelTally>,
+    key: (String, String),
+    score: f64,
+) {
+    let (count, running_score) = tallies.entry(key).or_insert((0, 0.0));
+    *count += 1;
+    *running_score = (*running_score + score) / 2.0;
+}
 
-    /// Print summary of built indices
-    fn print_index_summary(&self, indices: &IndexCollection) {
-        println!("\nIndex Summary:");
-        println!("   Vendors: {}", indices.by_vendor.len());
-        println!("   Component Types: {}", indices.by_component.len());
-        println!("   Kernel Versions: {}", indices.by_kernel.len());
-        println!("   Distributions: {}", indices.by_distribution.len());
-        println!("   Search Terms: {}", indices.search_terms.len());
-        println!(
-            "   Hardware/Kernel Combinations: {}",
-            indices.compatibility_matrix.values().map(|v| v.len()).sum::<usize>()
-        );
-        println!("   Total Reports: {}", indices.statistics.total_reports);
-        println!("   Unique Systems: {}", indices.statistics.unique_systems);
-    }
+/// Build top hardware list across all categories
+fn top_hardware_list(tallies: HashMap<(String, String), ModelTally>) -> Vec<PopularModel> {
+    let mut top_hardware: Vec<PopularModel> = tallies
+        .into_iter()
+        .map(|((vendor, model), (count, score))| PopularModel {
+            vendor,
+            model,
+            report_count: count,
+            avg_compatibility: score,
+        })
+        .collect();
+
+    top_hardware.sort_by_key(|h| Reverse(h.report_count));
+    top_hardware.truncate(50);
+    top_hardware
 }
@@ -90,19 +90,7 @@ impl<'a> IndexValidator<'a> {
     fn validate_component_index(&self) -> Result<IndexValidation> {
         let mut validation = IndexValidation::new("component_index");
 
-        let known_components = [
-            "CPU",
-            "GPU",
-            "Storage",
-            "Network",
-            "Audio",
-            "Bluetooth",
-            "USB",
-            "Camera",
-            "Touchpad",
-            "Keyboard",
-            "Display",
-        ];
+        let known_components: Vec<&str> = Category::ALL.iter().map(|c| c.as_str()).collect();
 
         for (component_type, entry) in &self.indices.by_component {
             // Check for unknown component types
@@ -297,6 +285,7 @@ impl<'a> IndexValidator<'a> {
 
                 // Check confidence level consistency
                 let expected_confidence = match score.sample_size {
+                    _ if score.low_sample => ConfidenceLevel::Low,
                     1..=2 => ConfidenceLevel::Low,
                     3..=9 => ConfidenceLevel::Medium,
                     _ => ConfidenceLevel::High,
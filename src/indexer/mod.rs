@@ -7,18 +7,25 @@
 #![allow(clippy::needless_borrows_for_generic_args)]
 
 pub mod analysis;
+pub mod browse;
 pub mod builder;
 pub mod compatibility;
 pub mod models;
+pub mod plugins;
 pub mod search_index;
 pub mod statistics;
 
 use crate::errors::{LxHwError, Result};
-use crate::hardware::HardwareReport;
+use crate::hardware::blobs::{BlobRef, BlobStore};
+use crate::hardware::taxonomy::{Category, Taxonomy};
+use crate::hardware::{HardwareReport, UnknownDevice};
+use crate::preflight;
+use crate::validation::{HardwareReportValidator, ValidationResult};
 use chrono::{DateTime, Utc};
 use glob::glob;
+use plugins::IndexerPlugin;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::path::{Path, PathBuf};
 
 // Re-export utility functions from models
@@ -28,10 +35,18 @@ pub use models::normalize_vendor_name;
 pub struct HardwareIndexer {
     /// All loaded hardware reports
     pub reports: Vec<IndexedReport>,
+    /// Report files left unloaded for streaming into the indices batch by batch
+    report_files: Vec<PathBuf>,
     /// Generated search indices
     pub indices: IndexCollection,
+    /// Datasets produced by plugins, keyed by plugin name
+    pub derived: BTreeMap<String, serde_json::Value>,
+    /// Post-processing hooks run while building indices
+    plugins: Vec<Box<dyn IndexerPlugin>>,
     /// Configuration for index generation
     config: IndexerConfig,
+    /// Mapping of component types onto index categories
+    taxonomy: Taxonomy,
 }
 
 /// Configuration for the indexer
@@ -49,6 +64,27 @@ pub struct IndexerConfig {
     pub min_reports: usize,
     /// Enable verbose logging
     pub verbose: bool,
+    /// Custom taxonomy file merged over the built-in category mapping
+    #[serde(default)]
+    pub taxonomy_file: Option<PathBuf>,
+    /// Number of reports loaded at a time when streaming
+    #[serde(default = "default_batch_size")]
+    pub batch_size: usize,
+    /// Report count above which reports are streamed instead of loaded up front
+    #[serde(default = "default_streaming_threshold")]
+    pub streaming_threshold: usize,
+    /// Keep hardware below `min_reports` in the indices, flagged as low
+    /// confidence, instead of excluding it
+    #[serde(default)]
+    pub include_low_sample: bool,
+}
+
+fn default_batch_size() -> usize {
+    500
+}
+
+fn default_streaming_threshold() -> usize {
+    5000
 }
 
 /// Hardware report with extracted metadata for indexing
@@ -66,6 +102,51 @@ pub struct IndexedReport {
     pub compatibility: CompatibilityInfo,
     /// When this report was processed
     pub indexed_at: DateTime<Utc>,
+    /// Devices the report could not identify
+    #[serde(default)]
+    pub unknown_devices: Vec<UnknownDevice>,
+    /// Verified sidecar payloads, relative to the report's `blobs/` directory
+    #[serde(default)]
+    pub blobs: Vec<BlobRef>,
+    /// Outcome of validating the report when it was loaded
+    #[serde(default)]
+    pub validation: ReportValidation,
+}
+
+/// Validation outcome recorded for an indexed report
+///
+/// Invalid reports are counted in the statistics but left out of every
+/// compatibility score.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ReportValidation {
+    /// Whether the report passed validation
+    pub valid: bool,
+    /// Validator confidence from 0.0 to 1.0
+    pub confidence_score: f64,
+    /// Validation errors
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub errors: Vec<String>,
+    /// Number of validation warnings
+    #[serde(default)]
+    pub warning_count: usize,
+}
+
+impl Default for ReportValidation {
+    /// Reports indexed before validation was recorded are trusted as before
+    fn default() -> Self {
+        Self { valid: true, confidence_score: 1.0, errors: Vec::new(), warning_count: 0 }
+    }
+}
+
+impl From<&ValidationResult> for ReportValidation {
+    fn from(result: &ValidationResult) -> Self {
+        Self {
+            valid: result.valid,
+            confidence_score: result.confidence_score,
+            errors: result.errors.iter().map(ToString::to_string).collect(),
+            warning_count: result.warnings.len(),
+        }
+    }
 }
 
 /// Metadata extracted from hardware report
@@ -83,6 +164,10 @@ pub struct ReportMetadata {
     pub architecture: String,
     /// Privacy level used
     pub privacy_level: String,
+    /// Hypervisor or container runtime the report came from, None for
+    /// physical hardware
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub virtualization: Option<String>,
 }
 
 /// Hardware component extracted from report
@@ -164,6 +249,73 @@ pub struct IndexCollection {
     pub compatibility_matrix: CompatibilityMatrix,
     /// Aggregated statistics
     pub statistics: Statistics,
+    /// Unidentified devices, most reported first
+    pub unknown_devices: Vec<UnknownDeviceEntry>,
+    /// Issues and workarounds reported per hardware model
+    #[serde(default)]
+    pub known_issues: KnownIssuesIndex,
+    /// Hardware left out for having fewer than `min_reports` reports
+    #[serde(default)]
+    pub excluded: Vec<ExcludedHardware>,
+    /// Hardware models reported per device ID
+    #[serde(default)]
+    pub by_device: DeviceIndex,
+    /// Validation results across all reports
+    #[serde(default)]
+    pub validation: ValidationSummary,
+}
+
+/// Unidentified device aggregated across reports
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UnknownDeviceEntry {
+    /// Bus the device was found on
+    pub bus: String,
+    /// Vendor ID
+    pub vendor_id: String,
+    /// Device or product ID
+    pub device_id: String,
+    /// Class code, if reported
+    pub class_code: Option<String>,
+    /// Name of the PCI class code, e.g. "Network controller"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub class_name: Option<String>,
+    /// Modalias, if reported
+    pub modalias: Option<String>,
+    /// Number of reports containing this device
+    pub report_count: usize,
+    /// Sample report IDs
+    pub sample_reports: Vec<String>,
+}
+
+/// Known issues keyed like the compatibility matrix ("Vendor model")
+pub type KnownIssuesIndex = HashMap<String, KnownIssueEntry>;
+
+/// Hardware keys ("Vendor model") by lowercase device ID ("8086:2723")
+pub type DeviceIndex = HashMap<String, Vec<String>>;
+
+/// Hardware excluded from the indices for insufficient data
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExcludedHardware {
+    pub component_type: String,
+    pub vendor: String,
+    pub model: String,
+    /// Number of reports containing this hardware
+    pub report_count: usize,
+    /// Further reports needed before the hardware is indexed
+    pub reports_needed: usize,
+}
+
+/// Issues and workarounds collected from reports for one hardware model
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct KnownIssueEntry {
+    /// Device ID (PCI ID, USB ID, etc.), if reported
+    pub device_id: Option<String>,
+    /// Number of reports describing an issue with this hardware
+    pub report_count: usize,
+    /// Distinct issues, in the order first reported
+    pub issues: Vec<String>,
+    /// Distinct workarounds, in the order first reported
+    pub workarounds: Vec<String>,
 }
 
 /// Index organized by hardware vendor
@@ -262,6 +414,9 @@ pub struct CompatibilityScore {
     pub sample_size: usize,
     /// Confidence in this score
     pub confidence: ConfidenceLevel,
+    /// Hardware has fewer reports than `min_reports`; confidence is forced low
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub low_sample: bool,
     /// Last updated timestamp
     pub last_updated: DateTime<Utc>,
 }
@@ -277,8 +432,14 @@ pub enum ConfidenceLevel {
 /// Aggregated database statistics
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct Statistics {
-    /// Total number of hardware reports
+    /// Total number of hardware reports, including invalid and virtualized ones
     pub total_reports: usize,
+    /// Reports that failed validation and were left out of scoring
+    #[serde(default)]
+    pub invalid_reports: usize,
+    /// Reports from virtual machines and containers, left out of scoring
+    #[serde(default)]
+    pub virtualized_reports: usize,
     /// Total unique systems
     pub unique_systems: usize,
     /// Hardware vendors count
@@ -299,6 +460,36 @@ pub struct Statistics {
     pub last_updated: DateTime<Utc>,
 }
 
+/// Report validation results for `validation-summary.json`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ValidationSummary {
+    /// Reports validated
+    pub total_reports: usize,
+    /// Reports that passed validation
+    pub valid_reports: usize,
+    /// Reports that failed validation
+    pub invalid_reports: usize,
+    /// Mean validator confidence across all reports, 0.0 to 1.0
+    pub average_confidence: f64,
+    /// Lowest validator confidence seen
+    pub min_confidence: f64,
+    /// Validation warnings across all reports
+    pub total_warnings: usize,
+    /// Invalid reports, by report ID
+    pub invalid: Vec<InvalidReport>,
+}
+
+/// Report excluded from scoring for failing validation
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InvalidReport {
+    /// Report ID
+    pub id: String,
+    /// Validator confidence from 0.0 to 1.0
+    pub confidence_score: f64,
+    /// Validation errors
+    pub errors: Vec<String>,
+}
+
 /// Data point for growth statistics
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GrowthDataPoint {
@@ -313,11 +504,23 @@ pub struct GrowthDataPoint {
 impl HardwareIndexer {
     /// Create new indexer with configuration
     pub fn new(config: IndexerConfig) -> Self {
-        Self { reports: Vec::new(), indices: IndexCollection::default(), config }
+        Self {
+            reports: Vec::new(),
+            report_files: Vec::new(),
+            indices: IndexCollection::default(),
+            derived: BTreeMap::new(),
+            plugins: plugins::builtin_plugins(),
+            config,
+            taxonomy: Taxonomy::builtin(),
+        }
     }
 
     /// Scan and load all hardware reports from directory
     pub fn scan_reports(&mut self) -> Result<()> {
+        if let Some(taxonomy_file) = &self.config.taxonomy_file {
+            self.taxonomy = Taxonomy::load(taxonomy_file)?;
+        }
+
         let pattern = format!("{}/**/*.json", self.config.reports_dir.display());
         let files: Vec<PathBuf> = glob(&pattern)
             .map_err(|e| LxHwError::ConfigError(format!("Invalid glob pattern: {}", e)))?
@@ -328,15 +531,49 @@ impl HardwareIndexer {
             println!("Found {} hardware report files", files.len());
         }
 
-        let mut loaded = 0;
+        // Large corpora are loaded batch by batch while building indices
+        if files.len() > self.config.streaming_threshold {
+            if self.config.verbose {
+                println!(
+                    "Streaming reports in batches of {} (more than {} reports)",
+                    self.config.batch_size, self.config.streaming_threshold
+                );
+            }
+            self.report_files = files;
+            return Ok(());
+        }
+
+        let reports = self.load_reports(&files);
+        self.reports.extend(reports);
+        Ok(())
+    }
+
+    /// Register a plugin generating a derived dataset alongside the indices
+    pub fn register_plugin(&mut self, plugin: Box<dyn IndexerPlugin>) {
+        self.plugins.push(plugin);
+    }
+
+    /// Whether reports are streamed into the indices instead of held in memory
+    pub fn is_streaming(&self) -> bool {
+        !self.report_files.is_empty()
+    }
+
+    /// Number of reports found by the last scan
+    ///
+    /// When streaming, this counts report files not yet loaded, including
+    /// any that will fail to parse.
+    pub fn report_count(&self) -> usize {
+        self.reports.len() + self.report_files.len()
+    }
+
+    /// Load reports from files, skipping those that fail to load
+    fn load_reports(&self, files: &[PathBuf]) -> Vec<IndexedReport> {
+        let mut reports = Vec::with_capacity(files.len());
         let mut errors = 0;
 
         for file_path in files {
-            match self.load_report(&file_path) {
-                Ok(report) => {
-                    self.reports.push(report);
-                    loaded += 1;
-                }
+            match self.load_report(file_path) {
+                Ok(report) => reports.push(report),
                 Err(e) => {
                     errors += 1;
                     eprintln!("Error loading {}: {}", file_path.display(), e);
@@ -345,10 +582,10 @@ impl HardwareIndexer {
         }
 
         if self.config.verbose {
-            println!("Loaded {} reports successfully, {} errors", loaded, errors);
+            println!("Loaded {} reports successfully, {} errors", reports.len(), errors);
         }
 
-        Ok(())
+        reports
     }
 
     /// Load and parse a single hardware report
@@ -358,6 +595,9 @@ impl HardwareIndexer {
         let report: HardwareReport = serde_json::from_str(&content)
             .map_err(|e| LxHwError::SerializationError(format!("Failed to parse JSON: {}", e)))?;
 
+        // Reports referencing missing or altered blobs are rejected
+        report.verify_blobs(&BlobStore::for_report(file_path))?;
+
         // Extract report ID from filename
         let id = file_path
             .file_stem()
@@ -369,6 +609,8 @@ impl HardwareIndexer {
         let relative_path =
             file_path.strip_prefix(&self.config.reports_dir).unwrap_or(file_path).to_path_buf();
 
+        let validation = ReportValidation::from(&HardwareReportValidator::new().validate(&report));
+
         // Extract metadata and components
         let metadata = self.extract_metadata(&report)?;
         let components = self.extract_components(&report)?;
@@ -381,6 +623,9 @@ impl HardwareIndexer {
             components,
             compatibility,
             indexed_at: Utc::now(),
+            unknown_devices: report.unknown_devices,
+            blobs: report.blobs,
+            validation,
         })
     }
 
@@ -397,6 +642,12 @@ impl HardwareIndexer {
                 .unwrap_or_else(|| "Unknown".to_string()),
             architecture: report.system.architecture.clone(),
             privacy_level: format!("{:?}", report.metadata.privacy_level),
+            virtualization: report
+                .system
+                .virtualization
+                .as_ref()
+                .filter(|_| report.system.is_virtual())
+                .map(|virt| virt.technology.clone().unwrap_or_else(|| "unknown".to_string())),
         })
     }
 
@@ -464,7 +715,7 @@ impl HardwareIndexer {
                     );
                     props.insert(
                         "dimm_count".to_string(),
-                        serde_json::Value::from(memory.dimms.len()),
+                        serde_json::Value::from(memory.dimm_count()),
                     );
                     props
                 },
@@ -667,9 +918,47 @@ impl HardwareIndexer {
             }
         }
 
+        self.categorize_components(report, &mut components);
         Ok(components)
     }
 
+    /// Map component types onto the fixed taxonomy categories
+    ///
+    /// Kernel support entries carry no class information, so they take the
+    /// category of the matching detected device when there is one.
+    fn categorize_components(&self, report: &HardwareReport, components: &mut [HardwareComponent]) {
+        let mut categories_by_id: HashMap<String, Category> = report
+            .unknown_devices
+            .iter()
+            .filter_map(|d| {
+                let class_code = d.class_code.as_deref()?;
+                let category = match d.bus.as_str() {
+                    "pci" => self.taxonomy.pci_category(class_code),
+                    _ => return None,
+                };
+                Some((format!("{}:{}", d.vendor_id, d.device_id), category))
+            })
+            .collect();
+        categories_by_id.extend(report.graphics.iter().map(|g| (g.pci_id.clone(), Category::Gpu)));
+
+        for component in components.iter_mut() {
+            let category = match (component.component_type.as_str(), &component.device_id) {
+                ("PCI Device", Some(id)) => {
+                    categories_by_id.get(id).copied().unwrap_or(Category::Other)
+                }
+                (component_type, _) => self.taxonomy.component_category(component_type),
+            };
+
+            if category.as_str() != component.component_type {
+                component.properties.insert(
+                    "raw_component_type".to_string(),
+                    serde_json::Value::from(component.component_type.clone()),
+                );
+                component.component_type = category.to_string();
+            }
+        }
+    }
+
     /// Analyze compatibility from report and components
     #[allow(clippy::excessive_nesting)]
     fn analyze_compatibility(
@@ -723,6 +1012,58 @@ impl HardwareIndexer {
                 (0, 0, 0)
             };
 
+        // A link trained below what the device supports limits its bandwidth
+        for device in report.pcie_links.iter().filter(|device| device.degraded) {
+            let link = |generation: Option<u8>, speed: &str, width: u8| match generation {
+                Some(generation) => format!("PCIe Gen{} x{}", generation, width),
+                None => format!("{} x{}", speed, width),
+            };
+            let current = link(device.generation, &device.link.speed, device.link.width);
+            let max = link(
+                device.max_generation.or(device.generation),
+                device.link.max_speed.as_deref().unwrap_or(&device.link.speed),
+                device.link.max_width.unwrap_or(device.link.width),
+            );
+            let compatibility = component_compatibility
+                .entry(device.pci_id.clone())
+                .or_insert_with(|| ComponentCompatibility {
+                    working: true,
+                    performance: None,
+                    issues: Vec::new(),
+                    workarounds: Vec::new(),
+                });
+            compatibility.performance = Some("degraded".to_string());
+            let issue = format!("PCIe link degraded: running at {}, capable of {}", current, max);
+            if !compatibility.issues.contains(&issue) {
+                compatibility.issues.push(issue);
+            }
+            let workaround =
+                "Reseat the card or move it to a slot wired for its full width and generation";
+            if !compatibility.workarounds.iter().any(|known| known == workaround) {
+                compatibility.workarounds.push(workaround.to_string());
+            }
+        }
+
+        // Notes users attached are workarounds for the component they name,
+        // or for the whole system when they name a category
+        for note in &report.user_notes {
+            if note.is_category() {
+                workarounds.push(note.note.clone());
+                continue;
+            }
+            let compatibility = component_compatibility
+                .entry(note.component.clone())
+                .or_insert_with(|| ComponentCompatibility {
+                    working: true,
+                    performance: None,
+                    issues: Vec::new(),
+                    workarounds: Vec::new(),
+                });
+            if !compatibility.workarounds.contains(&note.note) {
+                compatibility.workarounds.push(note.note.clone());
+            }
+        }
+
         // Collect system-wide issues and workarounds
         if unsupported > 0 {
             issues.push(format!("{} devices lack kernel driver support", unsupported));
@@ -781,13 +1122,34 @@ impl HardwareIndexer {
     }
 
     /// Build all indices from loaded reports
+    ///
+    /// In streaming mode only one batch of reports is held in memory at a time.
     pub fn build_indices(&mut self) -> Result<()> {
         if self.config.verbose {
-            println!("Building indices from {} reports...", self.reports.len());
+            println!("Building indices from {} reports...", self.report_count());
         }
 
+        let mut plugins = std::mem::take(&mut self.plugins);
         let builder = builder::IndexBuilder::new(&self.config);
-        self.indices = builder.build_indices(&self.reports)?;
+        if self.is_streaming() {
+            let mut accumulator = builder.accumulator();
+            for batch in self.report_files.chunks(self.config.batch_size.max(1)) {
+                let reports = self.load_reports(batch);
+                observe_reports(&mut plugins, &reports);
+                accumulator.add_reports(&reports);
+            }
+            self.indices = accumulator.finish();
+        } else {
+            observe_reports(&mut plugins, &self.reports);
+            self.indices = builder.build_indices(&self.reports)?;
+        }
+
+        self.derived.clear();
+        for plugin in &mut plugins {
+            let dataset = plugin.finish(&self.indices)?;
+            self.derived.insert(plugin.name().to_string(), dataset);
+        }
+        self.plugins = plugins;
 
         if self.config.verbose {
             println!("Index generation completed");
@@ -796,8 +1158,80 @@ impl HardwareIndexer {
         Ok(())
     }
 
+    /// Check the output directories before writing (see [`crate::preflight`])
+    ///
+    /// Fails when a directory cannot be written or lacks space for the
+    /// estimated output; warns when shard file names collide.
+    pub fn preflight(&self) -> Result<()> {
+        let estimate = self.output_estimate();
+        let dirs = [&self.config.indices_dir, &self.config.api_dir, &self.config.stats_dir];
+        let mut case_insensitive = false;
+        for dir in dirs {
+            // Directories may share a filesystem; each must fit everything
+            let check = preflight::check_output_dir(dir, estimate)?;
+            if check.case_insensitive && dir == &self.config.api_dir {
+                log::warn!(
+                    "{} is on a case-insensitive filesystem; vendor and component files whose \
+                     names differ only in case will overwrite each other",
+                    dir.display()
+                );
+                case_insensitive = true;
+            }
+        }
+
+        let names = self
+            .indices
+            .by_vendor
+            .keys()
+            .map(|vendor| ("vendor", vendor))
+            .chain(self.indices.by_component.keys().map(|component| ("component", component)));
+        let mut shards: HashMap<(&str, String), Vec<&str>> = HashMap::new();
+        for (kind, name) in names {
+            let mut file_name = search_index::shard_file_name(name);
+            if case_insensitive {
+                file_name = file_name.to_uppercase();
+            }
+            shards.entry((kind, file_name)).or_default().push(name);
+        }
+        let mut collisions: Vec<_> =
+            shards.into_iter().filter(|(_, names)| names.len() > 1).collect();
+        collisions.sort();
+        for ((kind, _), names) in collisions {
+            log::warn!(
+                "{} names {} share one API file; only the last one written is kept",
+                kind,
+                names.iter().map(|name| format!("'{}'", name)).collect::<Vec<_>>().join(", ")
+            );
+        }
+        Ok(())
+    }
+
+    /// Rough size of everything [`Self::write_indices`] writes
+    ///
+    /// Pretty-printing and the API endpoints repeating index data roughly
+    /// triple the compact JSON size of the indices.
+    fn output_estimate(&self) -> preflight::OutputEstimate {
+        let mut counter = ByteCounter::default();
+        let _ = serde_json::to_writer(&mut counter, &self.indices);
+        for dataset in self.derived.values() {
+            let _ = serde_json::to_writer(&mut counter, dataset);
+        }
+
+        // Fixed index, statistics and API files plus one API file per
+        // vendor and component type
+        let files = 32
+            + self.derived.len()
+            + self.indices.by_vendor.len()
+            + self.indices.by_component.len();
+        preflight::OutputEstimate { bytes: counter.0.saturating_mul(3), files: files as u64 }
+    }
+
     /// Write all indices to disk
+    ///
+    /// Runs [`Self::preflight`] first, so a full disk or unwritable
+    /// directory fails before any file is written.
     pub fn write_indices(&self) -> Result<()> {
+        self.preflight()?;
         self.write_index_files()?;
         self.write_api_endpoints()?;
         self.write_statistics()?;
@@ -844,6 +1278,34 @@ impl HardwareIndexer {
             &self.indices.compatibility_matrix,
         )?;
 
+        self.write_json_file(
+            &self.config.indices_dir.join("unknown-devices.json"),
+            &self.indices.unknown_devices,
+        )?;
+
+        self.write_json_file(
+            &self.config.indices_dir.join("known-issues.json"),
+            &self.indices.known_issues,
+        )?;
+
+        self.write_json_file(
+            &self.config.indices_dir.join("by-device.json"),
+            &self.indices.by_device,
+        )?;
+
+        self.write_json_file(
+            &self.config.indices_dir.join("excluded.json"),
+            &self.indices.excluded,
+        )?;
+
+        if !self.derived.is_empty() {
+            let derived_dir = self.config.indices_dir.join("derived");
+            std::fs::create_dir_all(&derived_dir)?;
+            for (name, dataset) in &self.derived {
+                self.write_json_file(&derived_dir.join(format!("{}.json", name)), dataset)?;
+            }
+        }
+
         Ok(())
     }
 
@@ -867,6 +1329,11 @@ impl HardwareIndexer {
             &self.indices.statistics,
         )?;
 
+        self.write_json_file(
+            &self.config.stats_dir.join("validation-summary.json"),
+            &self.indices.validation,
+        )?;
+
         Ok(())
     }
 
@@ -899,6 +1366,30 @@ impl HardwareIndexer {
     }
 }
 
+/// Writer counting the bytes written to it
+#[derive(Default)]
+struct ByteCounter(u64);
+
+impl std::io::Write for ByteCounter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0 += buf.len() as u64;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Hand a batch of reports to every plugin
+fn observe_reports(plugins: &mut [Box<dyn IndexerPlugin>], reports: &[IndexedReport]) {
+    for report in reports {
+        for plugin in plugins.iter_mut() {
+            plugin.observe_report(report);
+        }
+    }
+}
+
 impl Default for IndexerConfig {
     fn default() -> Self {
         Self {
@@ -908,6 +1399,10 @@ impl Default for IndexerConfig {
             stats_dir: PathBuf::from("statistics"),
             min_reports: 1,
             verbose: false,
+            taxonomy_file: None,
+            batch_size: default_batch_size(),
+            streaming_threshold: default_streaming_threshold(),
+            include_low_sample: false,
         }
     }
 }
@@ -0,0 +1,218 @@
+//! Post-processing hooks for generating derived datasets
+//!
+//! Plugins see every report as it is indexed and the finished indices, and
+//! return a JSON dataset written to `derived/<name>.json` in the indices
+//! directory. Reports are handed over one at a time so plugins keep working
+//! when the indexer streams a large corpus.
+
+use super::{IndexCollection, IndexedReport};
+use crate::errors::Result;
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+/// Generator of an auxiliary dataset built alongside the core indices
+pub trait IndexerPlugin {
+    /// Dataset name, used as the output file name
+    fn name(&self) -> &str;
+
+    /// Inspect one indexed report
+    fn observe_report(&mut self, report: &IndexedReport);
+
+    /// Produce the dataset once all reports have been indexed
+    fn finish(&mut self, indices: &IndexCollection) -> Result<serde_json::Value>;
+}
+
+/// Plugins registered with every indexer
+pub fn builtin_plugins() -> Vec<Box<dyn IndexerPlugin>> {
+    vec![Box::new(SecureBootPlugin::default()), Box::new(SuspendIssuesPlugin::default())]
+}
+
+/// Drivers built outside the kernel tree, which must be signed with an
+/// enrolled key before they load with Secure Boot enabled
+const OUT_OF_TREE_DRIVERS: &[&str] =
+    &["nvidia", "wl", "broadcom-sta", "vboxdrv", "zfs", "8821cu", "88x2bu", "rtl8812au"];
+
+/// Secure Boot status of one hardware model
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct SecureBootEntry {
+    pub component_type: String,
+    /// Drivers seen in use for this hardware
+    pub drivers: Vec<String>,
+    /// Whether any of those drivers is built out of tree
+    pub requires_module_signing: bool,
+    pub report_count: usize,
+}
+
+/// Lists hardware by whether its drivers load with Secure Boot unchanged
+#[derive(Default)]
+pub struct SecureBootPlugin {
+    hardware: BTreeMap<String, SecureBootEntry>,
+}
+
+impl IndexerPlugin for SecureBootPlugin {
+    fn name(&self) -> &str {
+        "secure-boot"
+    }
+
+    fn observe_report(&mut self, report: &IndexedReport) {
+        for component in &report.components {
+            let (Some(vendor), Some(model), Some(driver)) =
+                (&component.vendor, &component.model, &component.driver)
+            else {
+                continue;
+            };
+
+            let entry = self.hardware.entry(format!("{} {}", vendor, model)).or_default();
+            entry.component_type.clone_from(&component.component_type);
+            entry.report_count += 1;
+            if !entry.drivers.contains(driver) {
+                entry.drivers.push(driver.clone());
+            }
+            entry.requires_module_signing |= OUT_OF_TREE_DRIVERS.contains(&driver.as_str());
+        }
+    }
+
+    fn finish(&mut self, _indices: &IndexCollection) -> Result<serde_json::Value> {
+        Ok(serde_json::to_value(&self.hardware)?)
+    }
+}
+
+/// Hardware reported with suspend or resume problems
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct SuspendIssueEntry {
+    pub report_count: usize,
+    pub issues: Vec<String>,
+    pub workarounds: Vec<String>,
+}
+
+/// Collects hardware whose reported issues mention suspend, resume or sleep
+#[derive(Default)]
+pub struct SuspendIssuesPlugin {
+    hardware: BTreeMap<String, SuspendIssueEntry>,
+}
+
+impl SuspendIssuesPlugin {
+    fn mentions_suspend(text: &str) -> bool {
+        let text = text.to_lowercase();
+        ["suspend", "resume", "hibernat", "sleep"].iter().any(|word| text.contains(word))
+    }
+}
+
+impl IndexerPlugin for SuspendIssuesPlugin {
+    fn name(&self) -> &str {
+        "suspend-issues"
+    }
+
+    fn observe_report(&mut self, report: &IndexedReport) {
+        for component in &report.components {
+            let (Some(vendor), Some(model), Some(device_id)) =
+                (&component.vendor, &component.model, &component.device_id)
+            else {
+                continue;
+            };
+            let Some(compatibility) = report.compatibility.components.get(device_id) else {
+                continue;
+            };
+            let issues: Vec<&String> =
+                compatibility.issues.iter().filter(|i| Self::mentions_suspend(i)).collect();
+            if issues.is_empty() {
+                continue;
+            }
+
+            let entry = self.hardware.entry(format!("{} {}", vendor, model)).or_default();
+            entry.report_count += 1;
+            for issue in issues {
+                if !entry.issues.contains(issue) {
+                    entry.issues.push(issue.clone());
+                }
+            }
+            for workaround in &compatibility.workarounds {
+                if !entry.workarounds.contains(workaround) {
+                    entry.workarounds.push(workaround.clone());
+                }
+            }
+        }
+    }
+
+    fn finish(&mut self, _indices: &IndexCollection) -> Result<serde_json::Value> {
+        Ok(serde_json::to_value(&self.hardware)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::indexer::{
+        CompatibilityInfo, CompatibilityStatus, ComponentCompatibility, HardwareComponent,
+        ReportMetadata, ReportValidation,
+    };
+    use chrono::Utc;
+    use std::collections::HashMap;
+
+    fn report(driver: &str, issue: &str) -> IndexedReport {
+        let component = HardwareComponent {
+            component_type: "GPU".to_string(),
+            vendor: Some("NVIDIA".to_string()),
+            model: Some("GA104".to_string()),
+            device_id: Some("10de:2484".to_string()),
+            driver: Some(driver.to_string()),
+            driver_version: None,
+            properties: HashMap::new(),
+        };
+        let compatibility = ComponentCompatibility {
+            working: true,
+            performance: None,
+            issues: vec![issue.to_string()],
+            workarounds: vec!["Add nvidia.NVreg_PreserveVideoMemoryAllocations=1".to_string()],
+        };
+        IndexedReport {
+            id: "report".to_string(),
+            file_path: "report.json".into(),
+            metadata: ReportMetadata {
+                system_id: "system".to_string(),
+                submission_date: Utc::now(),
+                kernel_version: "6.8.0".to_string(),
+                distribution: "Fedora 40".to_string(),
+                architecture: "x86_64".to_string(),
+                privacy_level: "Basic".to_string(),
+                virtualization: None,
+            },
+            components: vec![component],
+            compatibility: CompatibilityInfo {
+                status: CompatibilityStatus::Good,
+                components: HashMap::from([("10de:2484".to_string(), compatibility)]),
+                issues: Vec::new(),
+                workarounds: Vec::new(),
+                confidence: 80,
+            },
+            indexed_at: Utc::now(),
+            unknown_devices: Vec::new(),
+            blobs: Vec::new(),
+            validation: ReportValidation::default(),
+        }
+    }
+
+    #[test]
+    fn test_builtin_plugins() {
+        let mut plugins = builtin_plugins();
+        for plugin in &mut plugins {
+            plugin.observe_report(&report("nouveau", "Screen flickers"));
+            plugin.observe_report(&report("nvidia", "Black screen after resume"));
+        }
+        let indices = IndexCollection::default();
+
+        let secure_boot = plugins[0].finish(&indices).unwrap();
+        assert_eq!(
+            secure_boot["NVIDIA GA104"]["drivers"],
+            serde_json::json!(["nouveau", "nvidia"])
+        );
+        assert_eq!(secure_boot["NVIDIA GA104"]["requires_module_signing"], true);
+
+        let suspend = plugins[1].finish(&indices).unwrap();
+        assert_eq!(suspend["NVIDIA GA104"]["report_count"], 1);
+        assert_eq!(
+            suspend["NVIDIA GA104"]["issues"],
+            serde_json::json!(["Black screen after resume"])
+        );
+    }
+}
@@ -242,7 +242,7 @@ impl<'a> ApiBuilder<'a> {
         for (vendor, entry) in &self.indices.by_vendor {
             let recommendations = self.generate_vendor_recommendations(vendor, entry);
 
-            let vendor_filename = vendor.to_lowercase().replace(' ', "-") + ".json";
+            let vendor_filename = shard_file_name(vendor);
             let vendor_data = json!({
                 "version": "1.0",
                 "generated": Utc::now().to_rfc3339(),
@@ -263,7 +263,7 @@ impl<'a> ApiBuilder<'a> {
         for (component_type, entry) in &self.indices.by_component {
             let recommendations = self.generate_component_recommendations(component_type, entry);
 
-            let component_filename = component_type.to_lowercase().replace(' ', "-") + ".json";
+            let component_filename = shard_file_name(component_type);
             let component_data = json!({
                 "version": "1.0",
                 "generated": Utc::now().to_rfc3339(),
@@ -332,7 +332,7 @@ impl<'a> ApiBuilder<'a> {
             let total_good = excellent_count + good_count;
 
             let compatibility_percentage =
-                if entry.total_reports > 0 { (total_good * 100) / entry.total_reports } else { 0 };
+                (total_good * 100).checked_div(entry.total_reports).unwrap_or(0);
 
             trends.push(json!({
                 "kernel_version": kernel,
@@ -490,3 +490,8 @@ impl<'a> ApiBuilder<'a> {
         vec![]
     }
 }
+
+/// File name of a per-vendor or per-component API shard
+pub(crate) fn shard_file_name(name: &str) -> String {
+    name.to_lowercase().replace([' ', '/'], "-") + ".json"
+}
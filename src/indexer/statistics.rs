@@ -3,6 +3,7 @@
 use super::*;
 use crate::errors::Result;
 use chrono::{Datelike, TimeZone, Utc};
+use std::cmp::Reverse;
 use std::collections::{BTreeMap, HashMap};
 
 /// Advanced statistics generator for hardware compatibility analysis
@@ -19,6 +20,12 @@ impl<'a> StatisticsGenerator<'a> {
     pub fn generate_statistics(&self) -> Result<Statistics> {
         let stats = Statistics {
             total_reports: self.reports.len(),
+            invalid_reports: self.reports.iter().filter(|r| !r.validation.valid).count(),
+            virtualized_reports: self
+                .reports
+                .iter()
+                .filter(|r| r.validation.valid && r.metadata.virtualization.is_some())
+                .count(),
             last_updated: Utc::now(),
             unique_systems: self.count_unique_systems(),
             total_vendors: self.count_unique_vendors(),
@@ -205,7 +212,7 @@ impl<'a> StatisticsGenerator<'a> {
             })
             .collect();
 
-        top_hardware.sort_by(|a, b| b.report_count.cmp(&a.report_count));
+        top_hardware.sort_by_key(|h| Reverse(h.report_count));
         top_hardware.truncate(50);
         top_hardware
     }
@@ -281,7 +288,7 @@ impl<'a> StatisticsGenerator<'a> {
             })
             .collect();
 
-        trends.sort_by(|a, b| b.report_count.cmp(&a.report_count));
+        trends.sort_by_key(|t| Reverse(t.report_count));
         trends.truncate(20);
 
         // Calculate market share
@@ -5,18 +5,32 @@
 //! implementing comprehensive anonymization and privacy protection.
 
 pub mod cli;
+pub mod configuration;
+#[cfg(feature = "daemon")]
+pub mod daemon;
 pub mod detectors;
+pub mod diff;
 pub mod errors;
+#[cfg(feature = "github-submit")]
 pub mod github_submit;
 #[cfg(feature = "gtk-gui")]
 pub mod gui;
 pub mod hardware;
+#[cfg(feature = "indexer")]
 pub mod indexer;
+pub mod logging;
 pub mod output;
+pub mod preflight;
+pub mod presentation;
 pub mod privacy;
 #[cfg(feature = "qt6-gui")]
 pub mod qt6;
+pub mod telemetry;
+#[cfg(feature = "tui")]
+pub mod tui;
 pub mod validation;
+#[cfg(feature = "indexer")]
+pub mod watch;
 
 pub use errors::{LxHwError, Result};
 pub use hardware::{HardwareReport, PrivacyLevel, SystemInfo};
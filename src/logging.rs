@@ -0,0 +1,108 @@
+//! Log output setup for the command-line tool
+//!
+//! Logs go to stderr through `env_logger`, as text for people or as one JSON
+//! object per line for log collectors. Key-value pairs given to the `log`
+//! macros, like `detector` and `duration_ms` on detector runs, become fields
+//! of the JSON object next to `timestamp`, `level`, `target` and `message`.
+
+use chrono::{SecondsFormat, Utc};
+use clap::ValueEnum;
+use log::kv::{Key, Value as KvValue, VisitSource};
+use serde_json::{Map, Value};
+use std::io::Write;
+
+/// Format of log lines on stderr
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum LogFormat {
+    /// Human-readable lines
+    #[default]
+    Text,
+    /// One JSON object per line
+    Json,
+}
+
+/// Install the global logger with an `env_logger` filter such as `lx_hw_detect=info`
+pub fn init(filter: &str, format: LogFormat) {
+    let mut builder = env_logger::Builder::new();
+    builder.parse_filters(filter);
+    if format == LogFormat::Json {
+        builder.format(|buf, record| writeln!(buf, "{}", json_record(record)));
+    }
+    builder.init();
+}
+
+/// A log record as a JSON object
+pub fn json_record(record: &log::Record) -> Value {
+    let mut fields = Fields(Map::new());
+    // Visiting a source only fails when the visitor does, which ours never does
+    let _ = record.key_values().visit(&mut fields);
+
+    let mut object = fields.0;
+    object.insert(
+        "timestamp".to_string(),
+        Value::String(Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true)),
+    );
+    object.insert("level".to_string(), Value::String(record.level().as_str().to_lowercase()));
+    object.insert("target".to_string(), Value::String(record.target().to_string()));
+    object.insert("message".to_string(), Value::String(record.args().to_string()));
+    Value::Object(object)
+}
+
+/// Collects the key-value pairs of a record, keeping numbers and booleans typed
+struct Fields(Map<String, Value>);
+
+impl<'kvs> VisitSource<'kvs> for Fields {
+    fn visit_pair(&mut self, key: Key<'kvs>, value: KvValue<'kvs>) -> Result<(), log::kv::Error> {
+        let value = if let Some(number) = value.to_u64() {
+            Value::from(number)
+        } else if let Some(number) = value.to_i64() {
+            Value::from(number)
+        } else if let Some(flag) = value.to_bool() {
+            Value::Bool(flag)
+        } else {
+            Value::String(value.to_string())
+        };
+        self.0.insert(key.as_str().to_string(), value);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_json_record_includes_fields() {
+        let kvs: [(&str, KvValue); 3] = [
+            ("detector", KvValue::from("lshw")),
+            ("duration_ms", KvValue::from(1520u64)),
+            ("success", KvValue::from(true)),
+        ];
+        let json = json_record(
+            &log::Record::builder()
+                .level(log::Level::Debug)
+                .target("lx_hw_detect::detectors")
+                .args(format_args!("lshw finished"))
+                .key_values(&kvs)
+                .build(),
+        );
+
+        assert_eq!(json["level"], "debug");
+        assert_eq!(json["target"], "lx_hw_detect::detectors");
+        assert_eq!(json["message"], "lshw finished");
+        assert_eq!(json["detector"], "lshw");
+        assert_eq!(json["duration_ms"], 1520);
+        assert_eq!(json["success"], true);
+        assert!(json["timestamp"].as_str().unwrap().ends_with('Z'));
+    }
+
+    #[test]
+    fn test_record_fields_do_not_replace_core_fields() {
+        let kvs: [(&str, KvValue); 1] = [("message", KvValue::from("spoofed"))];
+        let json = json_record(
+            &log::Record::builder().args(format_args!("real")).key_values(&kvs).build(),
+        );
+
+        assert_eq!(json["message"], "real");
+    }
+}
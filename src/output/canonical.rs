@@ -0,0 +1,259 @@
+//! Canonical report serialization for reproducible diffs
+//!
+//! Reports submitted to the database are reviewed as pull request diffs.
+//! Canonical output sorts every component list by a stable key, orders
+//! object fields alphabetically and rounds floating point values to a fixed
+//! precision, so regenerating a report on the same hardware produces the
+//! same file apart from fields that really changed.
+
+use crate::errors::{LxHwError, Result};
+use crate::hardware::HardwareReport;
+use serde_json::{Map, Value};
+
+/// Decimal places kept for floating point values
+pub const FLOAT_PRECISION: i32 = 3;
+
+/// Copy of a report with every list in canonical order
+pub fn canonical_report(report: &HardwareReport) -> HardwareReport {
+    let mut report = report.clone();
+
+    report.metadata.tools_used.sort();
+    report.metadata.phase_errors.sort_by(|a, b| a.phase.cmp(&b.phase));
+    if let Some(cpu) = report.cpu.as_mut() {
+        cpu.flags.sort();
+    }
+    if let Some(memory) = report.memory.as_mut() {
+        memory.dimms.sort_by(|a, b| {
+            (a.size_bytes, a.speed_mhz, &a.memory_type, &a.manufacturer).cmp(&(
+                b.size_bytes,
+                b.speed_mhz,
+                &b.memory_type,
+                &b.manufacturer,
+            ))
+        });
+    }
+    report
+        .storage
+        .sort_by(|a, b| (&a.model, &a.anonymized_serial).cmp(&(&b.model, &b.anonymized_serial)));
+    for gpu in &mut report.graphics {
+        if let Some(displays) = gpu.displays.as_mut() {
+            displays.connectors.sort_by(|a, b| a.name.cmp(&b.name));
+        }
+    }
+    report.graphics.sort_by(|a, b| (&a.pci_id, &a.model).cmp(&(&b.pci_id, &b.model)));
+    report.network.sort_by(|a, b| {
+        (&a.device_type, &a.vendor, &a.model, &a.anonymized_mac).cmp(&(
+            &b.device_type,
+            &b.vendor,
+            &b.model,
+            &b.anonymized_mac,
+        ))
+    });
+    report.usb.sort_by(|a, b| {
+        (&a.vendor_id, &a.product_id, &a.product_name, &a.port_path).cmp(&(
+            &b.vendor_id,
+            &b.product_id,
+            &b.product_name,
+            &b.port_path,
+        ))
+    });
+    report.audio.sort_by(|a, b| {
+        (&a.vendor, &a.model, &a.device_type).cmp(&(&b.vendor, &b.model, &b.device_type))
+    });
+    if let Some(kernel) = report.kernel_support.as_mut() {
+        kernel.device_support_details.sort_by(|a, b| a.device_id.cmp(&b.device_id));
+        kernel.missing_modules.sort();
+        kernel.config_recommendations.sort();
+    }
+    sort_unknown_devices(&mut report);
+    report
+        .lifecycle_notices
+        .sort_by(|a, b| (&a.device_id, &a.generation).cmp(&(&b.device_id, &b.generation)));
+    report.firmware_updates.sort_by(|a, b| (&a.name, &a.vendor).cmp(&(&b.name, &b.vendor)));
+    report.blobs.sort_by(|a, b| (&a.subject, &a.sha256).cmp(&(&b.subject, &b.sha256)));
+
+    report
+}
+
+/// Canonical JSON document of a report
+pub fn to_value(report: &HardwareReport) -> Result<Value> {
+    let value = serde_json::to_value(canonical_report(report))
+        .map_err(|e| LxHwError::SerializationError(e.to_string()))?;
+    Ok(normalize(value))
+}
+
+/// Canonical pretty-printed JSON of a report
+pub fn to_json(report: &HardwareReport) -> Result<String> {
+    Ok(serde_json::to_string_pretty(&to_value(report)?)?)
+}
+
+/// Canonical YAML of a report
+pub fn to_yaml(report: &HardwareReport) -> Result<String> {
+    Ok(serde_yaml::to_string(&to_value(report)?)?)
+}
+
+/// Sort unknown devices, keeping blob references to their kernel log lines valid
+fn sort_unknown_devices(report: &mut HardwareReport) {
+    let mut order: Vec<usize> = (0..report.unknown_devices.len()).collect();
+    order.sort_by(|&a, &b| {
+        let (a, b) = (&report.unknown_devices[a], &report.unknown_devices[b]);
+        (&a.bus, &a.vendor_id, &a.device_id).cmp(&(&b.bus, &b.vendor_id, &b.device_id))
+    });
+
+    for blob in &mut report.blobs {
+        let old_index = blob
+            .subject
+            .strip_prefix("unknown_devices[")
+            .and_then(|rest| rest.split_once(']'))
+            .and_then(|(index, field)| Some((index.parse::<usize>().ok()?, field)));
+        if let Some((old_index, field)) = old_index {
+            if let Some(new_index) = order.iter().position(|&i| i == old_index) {
+                blob.subject = format!("unknown_devices[{}]{}", new_index, field);
+            }
+        }
+    }
+
+    let mut devices: Vec<_> =
+        std::mem::take(&mut report.unknown_devices).into_iter().map(Some).collect();
+    report.unknown_devices = order.into_iter().filter_map(|i| devices[i].take()).collect();
+}
+
+/// Order object keys alphabetically and round floats, recursively
+fn normalize(value: Value) -> Value {
+    match value {
+        Value::Object(map) => {
+            let mut entries: Vec<_> = map.into_iter().collect();
+            entries.sort_by(|a, b| a.0.cmp(&b.0));
+            let mut sorted = Map::new();
+            for (key, value) in entries {
+                sorted.insert(key, normalize(value));
+            }
+            Value::Object(sorted)
+        }
+        Value::Array(items) => Value::Array(items.into_iter().map(normalize).collect()),
+        Value::Number(number) if number.is_f64() => {
+            let scale = 10f64.powi(FLOAT_PRECISION);
+            number
+                .as_f64()
+                .map(|f| (f * scale).round() / scale)
+                .and_then(serde_json::Number::from_f64)
+                .map(Value::Number)
+                .unwrap_or(Value::Number(number))
+        }
+        other => other,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hardware::blobs::BlobRef;
+    use crate::hardware::{UnknownDevice, UsbDevice};
+
+    fn report() -> HardwareReport {
+        serde_json::from_value(serde_json::json!({
+            "metadata": {
+                "version": "1.0.0",
+                "generated_at": "2024-01-01T00:00:00Z",
+                "privacy_level": "Basic",
+                "tools_used": ["lspci", "dmidecode"],
+                "anonymized_system_id": "test",
+            },
+            "system": {
+                "anonymized_hostname": "test",
+                "kernel_version": "6.8.0",
+                "distribution": null,
+                "architecture": "x86_64",
+                "boot_time": null,
+            },
+            "cpu": {
+                "vendor": "AMD",
+                "model": "Ryzen 7",
+                "cores": 8,
+                "threads": 16,
+                "base_frequency": 3792.8734529,
+                "max_frequency": null,
+                "cache_l1": null,
+                "cache_l2": null,
+                "cache_l3": null,
+                "flags": [],
+            },
+            "memory": null,
+            "storage": [],
+            "graphics": [],
+            "network": [],
+            "usb": [],
+            "audio": [],
+            "kernel_support": null,
+        }))
+        .unwrap()
+    }
+
+    fn usb(vendor_id: &str, product_id: &str) -> UsbDevice {
+        UsbDevice {
+            vendor_id: vendor_id.to_string(),
+            product_id: product_id.to_string(),
+            vendor_name: None,
+            product_name: None,
+            usb_version: None,
+            device_class: None,
+            device_subclass: None,
+            speed_mbps: None,
+            port_path: None,
+            max_power_ma: None,
+            anonymized_serial: None,
+        }
+    }
+
+    fn unknown(device_id: &str) -> UnknownDevice {
+        UnknownDevice {
+            bus: "pci".to_string(),
+            vendor_id: "1234".to_string(),
+            device_id: device_id.to_string(),
+            class_code: None,
+            modalias: None,
+            dmesg_lines: vec![],
+        }
+    }
+
+    #[test]
+    fn test_reordered_reports_serialize_identically() {
+        let mut a = report();
+        a.usb = vec![usb("046d", "c52b"), usb("0781", "5581"), usb("046d", "085e")];
+        let mut b = a.clone();
+        b.usb.reverse();
+        b.metadata.tools_used.reverse();
+
+        assert_eq!(to_json(&a).unwrap(), to_json(&b).unwrap());
+        assert_eq!(to_yaml(&a).unwrap(), to_yaml(&b).unwrap());
+    }
+
+    #[test]
+    fn test_fields_sorted_and_floats_rounded() {
+        let json = to_json(&report()).unwrap();
+        assert!(json.contains("3792.873"));
+        assert!(!json.contains("3792.8734"));
+
+        let value = to_value(&report()).unwrap();
+        let keys: Vec<_> = value["system"].as_object().unwrap().keys().cloned().collect();
+        let mut sorted = keys.clone();
+        sorted.sort();
+        assert_eq!(keys, sorted);
+    }
+
+    #[test]
+    fn test_blob_subjects_follow_sorted_unknown_devices() {
+        let mut report = report();
+        report.unknown_devices = vec![unknown("ffff"), unknown("0001")];
+        report.blobs.push(BlobRef {
+            kind: "dmesg".to_string(),
+            subject: "unknown_devices[0].dmesg_lines".to_string(),
+            sha256: "0".repeat(64),
+            size: 1,
+        });
+
+        let canonical = canonical_report(&report);
+        assert_eq!(canonical.unknown_devices[1].device_id, "ffff");
+        assert_eq!(canonical.blobs[0].subject, "unknown_devices[1].dmesg_lines");
+    }
+}
@@ -0,0 +1,335 @@
+//! Standalone HTML rendering of hardware reports
+//!
+//! Produces a single self-contained page, with styles inlined and no scripts
+//! or external resources, that can be opened in a browser or attached to a
+//! forum post. Devices are grouped per category like the terminal view, using
+//! the same presentation model.
+
+use crate::hardware::provenance::Provenance;
+use crate::hardware::{HardwareReport, KernelCompatibilityInfo, UnknownDevice};
+use crate::presentation::{CompatibilityBadge, DeviceCategory, DeviceItem, DeviceList};
+use std::fmt::Write as _;
+
+/// Inline stylesheet, light and dark aware
+const STYLE: &str = r#"
+:root { color-scheme: light dark; --fg: #1f2328; --bg: #ffffff; --muted: #656d76; --line: #d0d7de; --head: #f6f8fa; }
+@media (prefers-color-scheme: dark) {
+  :root { --fg: #e6edf3; --bg: #0d1117; --muted: #8d96a0; --line: #30363d; --head: #161b22; }
+}
+body { font-family: system-ui, sans-serif; color: var(--fg); background: var(--bg); max-width: 72rem; margin: 2rem auto; padding: 0 1rem; line-height: 1.45; }
+h1 { margin-bottom: 0.25rem; }
+h2 { border-bottom: 1px solid var(--line); padding-bottom: 0.25rem; margin-top: 2rem; }
+.meta { color: var(--muted); margin-top: 0; }
+table { border-collapse: collapse; width: 100%; margin: 0.5rem 0 1rem; }
+th, td { border: 1px solid var(--line); padding: 0.35rem 0.6rem; text-align: left; vertical-align: top; }
+th { background: var(--head); }
+table.summary th { width: 12rem; }
+ul.details { margin: 0; padding-left: 1rem; }
+.badge { display: inline-block; border-radius: 0.75rem; padding: 0.05rem 0.6rem; color: #ffffff; font-size: 0.85em; font-weight: 600; white-space: nowrap; }
+.counts .badge { margin-right: 0.4rem; }
+pre { background: var(--head); border: 1px solid var(--line); padding: 0.75rem; overflow-x: auto; }
+footer { color: var(--muted); font-size: 0.85em; margin-top: 2rem; }
+"#;
+
+/// Render a hardware report as a standalone HTML page
+pub fn render(report: &HardwareReport) -> String {
+    let devices = DeviceList::from_report(report);
+    let mut body = String::new();
+
+    let _ = writeln!(body, "<h1>Hardware Compatibility Report</h1>");
+    let _ = writeln!(
+        body,
+        "<p class=\"meta\">Generated {} · privacy level {:?} · tools: {}</p>",
+        report.metadata.generated_at.format("%Y-%m-%d %H:%M UTC"),
+        report.metadata.privacy_level,
+        escape(&report.metadata.tools_used.join(", "))
+    );
+
+    let counts: Vec<String> = BADGES
+        .iter()
+        .filter_map(|&badge| {
+            let count = devices.count_with_badge(badge);
+            (count > 0).then(|| badge_span(badge, &format!("{} {}", count, badge_label(badge))))
+        })
+        .collect();
+    if !counts.is_empty() {
+        let _ = writeln!(body, "<p class=\"counts\">{}</p>", counts.join(""));
+    }
+
+    write_system_summary(&mut body, report, &devices);
+
+    for (category, items) in devices.by_category() {
+        // System details are in the summary
+        if category == DeviceCategory::System {
+            continue;
+        }
+        write_device_table(&mut body, category, &items);
+    }
+
+    if let Some(kernel_support) = &report.kernel_support {
+        write_kernel_compatibility(&mut body, kernel_support);
+    }
+
+    if !report.unknown_devices.is_empty() {
+        write_unknown_devices(&mut body, &report.unknown_devices);
+    }
+
+    if let Some(provenance) = &report.provenance {
+        write_provenance(&mut body, provenance);
+    }
+
+    let recommendations: Vec<String> = devices
+        .items
+        .iter()
+        .flat_map(|item| {
+            item.recommendations.iter().map(move |r| {
+                format!("<li><strong>{}:</strong> {}</li>", escape(&item.name), escape(r))
+            })
+        })
+        .collect();
+    if !recommendations.is_empty() {
+        let _ =
+            writeln!(body, "<h2>Recommendations</h2>\n<ul>\n{}\n</ul>", recommendations.join("\n"));
+    }
+
+    let _ = writeln!(
+        body,
+        "<footer>Report format {} · system {}</footer>",
+        escape(&report.metadata.version),
+        escape(&report.metadata.anonymized_system_id)
+    );
+
+    page("Hardware Compatibility Report", &body)
+}
+
+/// Wrap an HTML body in a standalone page with the report stylesheet
+pub fn page(title: &str, body: &str) -> String {
+    format!(
+        "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n\
+         <meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">\n\
+         <meta name=\"generator\" content=\"lx-hw-detect {}\">\n\
+         <title>{}</title>\n<style>{}</style>\n</head>\n<body>\n{}</body>\n</html>\n",
+        env!("CARGO_PKG_VERSION"),
+        escape(title),
+        STYLE,
+        body
+    )
+}
+
+/// Escape text for use in HTML content and attribute values
+pub fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn write_system_summary(body: &mut String, report: &HardwareReport, devices: &DeviceList) {
+    let mut rows = vec![
+        ("Hostname", report.system.anonymized_hostname.clone()),
+        (
+            "Distribution",
+            report.system.distribution.clone().unwrap_or_else(|| "Unknown".to_string()),
+        ),
+        ("Kernel", report.system.kernel_version.clone()),
+        ("Architecture", report.system.architecture.clone()),
+    ];
+    if let Some(cpu) = &report.cpu {
+        rows.push((
+            "CPU",
+            format!("{} {} ({} cores, {} threads)", cpu.vendor, cpu.model, cpu.cores, cpu.threads),
+        ));
+    }
+    if let Some(memory) = devices.items.iter().find(|item| item.category == DeviceCategory::Memory)
+    {
+        rows.push(("Memory", memory.name.clone()));
+    }
+
+    let _ = writeln!(body, "<h2>System Summary</h2>\n<table class=\"summary\">");
+    for (label, value) in rows {
+        let _ = writeln!(body, "<tr><th>{}</th><td>{}</td></tr>", label, escape(&value));
+    }
+    let _ = writeln!(body, "</table>");
+}
+
+fn write_device_table(body: &mut String, category: DeviceCategory, items: &[&DeviceItem]) {
+    let _ = writeln!(body, "<h2>{}</h2>", escape(category.display_name()));
+    let _ = writeln!(
+        body,
+        "<table>\n<tr><th>Status</th><th>Device</th><th>Vendor</th><th>Details</th></tr>"
+    );
+    for item in items {
+        let details: String = item
+            .details
+            .iter()
+            .map(|(label, value)| format!("<li>{}: {}</li>", escape(label), escape(value)))
+            .collect();
+        let _ = writeln!(
+            body,
+            "<tr><td>{}</td><td>{}</td><td>{}</td><td><ul class=\"details\">{}</ul></td></tr>",
+            badge_span(item.badge, item.badge.display_text()),
+            escape(&item.name),
+            escape(&item.vendor),
+            details
+        );
+    }
+    let _ = writeln!(body, "</table>");
+}
+
+fn write_kernel_compatibility(body: &mut String, kernel_support: &KernelCompatibilityInfo) {
+    let _ = writeln!(body, "<h2>Kernel Compatibility</h2>\n<table class=\"summary\">");
+    for (label, count) in [
+        ("Total devices", kernel_support.total_devices_detected),
+        ("Supported", kernel_support.supported_devices),
+        ("Unsupported", kernel_support.unsupported_devices),
+        ("Experimental", kernel_support.experimental_devices),
+    ] {
+        let _ = writeln!(body, "<tr><th>{}</th><td>{}</td></tr>", label, count);
+    }
+    let _ = writeln!(body, "</table>");
+
+    if kernel_support.device_support_details.is_empty() {
+        return;
+    }
+    let _ = writeln!(
+        body,
+        "<table>\n<tr><th>Status</th><th>Device</th><th>ID</th><th>Driver</th>\
+         <th>Since kernel</th><th>Notes</th></tr>"
+    );
+    for device in &kernel_support.device_support_details {
+        let badge = match device.support_status.as_str() {
+            "supported" => CompatibilityBadge::Supported,
+            "experimental" => CompatibilityBadge::PartialSupport,
+            "unsupported" => CompatibilityBadge::Unsupported,
+            _ => CompatibilityBadge::Unknown,
+        };
+        let _ = writeln!(
+            body,
+            "<tr><td>{}</td><td>{}</td><td><code>{}</code></td><td>{}</td><td>{}</td><td>{}</td></tr>",
+            badge_span(badge, &device.support_status),
+            escape(&device.device_name),
+            escape(&device.device_id),
+            escape(&device.driver_module),
+            escape(device.since_kernel_version.as_deref().unwrap_or("")),
+            escape(device.notes.as_deref().unwrap_or(""))
+        );
+    }
+    let _ = writeln!(body, "</table>");
+}
+
+fn write_unknown_devices(body: &mut String, devices: &[UnknownDevice]) {
+    let _ = writeln!(
+        body,
+        "<h2>Unidentified Devices</h2>\n<table>\n<tr><th>ID</th><th>Bus</th><th>Class</th></tr>"
+    );
+    for device in devices {
+        let class_name = device
+            .class_code
+            .as_deref()
+            .filter(|_| device.bus == "pci")
+            .and_then(crate::hardware::pci_class::describe)
+            .unwrap_or_else(|| "Unknown class".to_string());
+        let _ = writeln!(
+            body,
+            "<tr><td><code>{}:{}</code></td><td>{}</td><td>{}</td></tr>",
+            escape(&device.vendor_id),
+            escape(&device.device_id),
+            escape(&device.bus),
+            escape(&class_name)
+        );
+    }
+    let _ = writeln!(body, "</table>");
+}
+
+fn write_provenance(body: &mut String, provenance: &Provenance) {
+    let _ = writeln!(
+        body,
+        "<h2>Field Provenance</h2>\n<table>\n<tr><th>Field</th><th>Tool</th><th>Confidence</th>\
+         <th>Other tools</th></tr>"
+    );
+    for source in &provenance.fields {
+        let others: Vec<String> = provenance
+            .conflicts
+            .iter()
+            .filter(|conflict| conflict.field == source.field)
+            .flat_map(|conflict| &conflict.others)
+            .map(|other| format!("{}: {}", escape(&other.tool), escape(&other.value)))
+            .collect();
+        let _ = writeln!(
+            body,
+            "<tr><td><code>{}</code></td><td>{}</td><td>{:?}</td><td>{}</td></tr>",
+            escape(&source.field),
+            escape(&source.tool),
+            source.confidence,
+            others.join("<br>")
+        );
+    }
+    let _ = writeln!(body, "</table>");
+}
+
+/// Badges in summary order
+const BADGES: [CompatibilityBadge; 5] = [
+    CompatibilityBadge::Supported,
+    CompatibilityBadge::PartialSupport,
+    CompatibilityBadge::RequiresDriver,
+    CompatibilityBadge::Unsupported,
+    CompatibilityBadge::Unknown,
+];
+
+fn badge_span(badge: CompatibilityBadge, text: &str) -> String {
+    format!(
+        "<span class=\"badge {}\" style=\"background: {}\">{} {}</span>",
+        badge.css_class(),
+        badge.color(),
+        badge.emoji(),
+        escape(text)
+    )
+}
+
+fn badge_label(badge: CompatibilityBadge) -> &'static str {
+    match badge {
+        CompatibilityBadge::Supported => "supported",
+        CompatibilityBadge::PartialSupport => "partial",
+        CompatibilityBadge::RequiresDriver => "need a driver",
+        CompatibilityBadge::Unsupported => "unsupported",
+        CompatibilityBadge::Unknown => "unknown",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hardware::examples::ExampleGenerator;
+
+    #[test]
+    fn test_render_standalone_page() {
+        let report = ExampleGenerator::new(0).report(0);
+        let html = render(&report);
+
+        assert!(html.starts_with("<!DOCTYPE html>"));
+        assert!(html.trim_end().ends_with("</html>"));
+        assert!(html.contains("<h2>System Summary</h2>"));
+        assert!(html.contains(&escape(&report.system.kernel_version)));
+        assert!(html.contains("<style>"));
+        assert!(!html.contains("<script"));
+        assert!(!html.contains("src=\"http"));
+    }
+
+    #[test]
+    fn test_report_text_is_escaped() {
+        let mut report = ExampleGenerator::new(0).report(0);
+        report.system.distribution = Some("<script>alert(\"x\")</script>".to_string());
+        let html = render(&report);
+
+        assert!(!html.contains("<script>"));
+        assert!(html.contains("&lt;script&gt;alert(&quot;x&quot;)&lt;/script&gt;"));
+    }
+}
@@ -2,15 +2,131 @@
 
 use crate::errors::Result;
 use crate::hardware::HardwareReport;
-use serde_yaml;
 use std::fmt;
 
-/// Output format for hardware reports
-#[derive(Debug, Clone, Copy)]
-pub enum OutputFormat {
-    Yaml,
-    Json,
-    Markdown,
+pub mod canonical;
+pub mod html;
+pub mod terminal;
+pub mod xml;
+
+/// Declare [`OutputFormat`] and the [`FORMATS`] registry from one list
+///
+/// Each format is registered once with its name, help text and renderer;
+/// clap, the renderers and the GUI export dialog all iterate the registry,
+/// so a new format cannot be missing from any of them.
+macro_rules! output_formats {
+    ($(
+        $(#[doc = $doc:literal])*
+        $variant:ident {
+            name: $name:literal,
+            extension: $extension:literal,
+            label: $label:literal,
+            help: $help:literal,
+            render: $render:path $(,)?
+        }
+    ),* $(,)?) => {
+        /// Output format for hardware reports
+        #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+        pub enum OutputFormat {
+            $($(#[doc = $doc])* $variant,)*
+        }
+
+        /// Every output format, in the order offered to users
+        pub const FORMATS: &[FormatSpec] = &[$(
+            FormatSpec {
+                format: OutputFormat::$variant,
+                name: $name,
+                extension: $extension,
+                label: $label,
+                help: $help,
+                render: $render,
+            },
+        )*];
+
+        impl OutputFormat {
+            /// Every output format, in registry order
+            pub const ALL: &'static [OutputFormat] = &[$(OutputFormat::$variant,)*];
+        }
+    };
+}
+
+output_formats! {
+    Yaml {
+        name: "yaml",
+        extension: "yaml",
+        label: "YAML Report",
+        help: "YAML format output",
+        render: render_yaml,
+    },
+    Json {
+        name: "json",
+        extension: "json",
+        label: "JSON Data",
+        help: "JSON format output",
+        render: render_json,
+    },
+    Markdown {
+        name: "markdown",
+        extension: "md",
+        label: "Markdown Report",
+        help: "Markdown with YAML frontmatter",
+        render: render_markdown,
+    },
+    /// Standalone HTML page for viewing in a browser
+    Html {
+        name: "html",
+        extension: "html",
+        label: "HTML Page",
+        help: "Standalone HTML page for a browser",
+        render: render_html,
+    },
+    /// lshw-compatible XML for inventory tools
+    Xml {
+        name: "xml",
+        extension: "xml",
+        label: "lshw XML",
+        help: "lshw-compatible XML for inventory tools",
+        render: render_xml,
+    },
+}
+
+/// Renders a report, in canonical form when the flag is set
+type RenderFn = fn(&HardwareReport, bool) -> Result<String>;
+
+/// Registration of an output format
+pub struct FormatSpec {
+    pub format: OutputFormat,
+    /// Value of the `--format` option
+    pub name: &'static str,
+    /// File extension for reports written in the format
+    pub extension: &'static str,
+    /// Name offered in the GUI export dialog
+    pub label: &'static str,
+    /// Help text of the `--format` value
+    pub help: &'static str,
+    render: RenderFn,
+}
+
+impl FormatSpec {
+    /// Render a report in this format
+    pub fn render(&self, report: &HardwareReport, canonical: bool) -> Result<String> {
+        (self.render)(report, canonical)
+    }
+}
+
+impl OutputFormat {
+    /// Registry entry of the format
+    pub fn spec(self) -> &'static FormatSpec {
+        FORMATS
+            .iter()
+            .find(|spec| spec.format == self)
+            .expect("output_formats! registers every variant")
+    }
+
+    /// Format with a `--format` name
+    pub fn from_name(name: &str) -> Option<Self> {
+        FORMATS.iter().find(|spec| spec.name == name).map(|spec| spec.format)
+    }
 }
 
 /// Report generator for different output formats
@@ -21,6 +137,7 @@ pub struct ReportGenerator {
 /// Output renderer with enhanced formatting capabilities
 pub struct OutputRenderer {
     format: OutputFormat,
+    canonical: bool,
 }
 
 impl ReportGenerator {
@@ -31,28 +148,7 @@ impl ReportGenerator {
 
     /// Generate a formatted report from hardware data
     pub fn generate(&self, report: &HardwareReport) -> Result<String> {
-        match self.format {
-            OutputFormat::Yaml => self.generate_yaml(report),
-            OutputFormat::Json => self.generate_json(report),
-            OutputFormat::Markdown => self.generate_markdown(report),
-        }
-    }
-
-    fn generate_yaml(&self, report: &HardwareReport) -> Result<String> {
-        Ok(serde_yaml::to_string(report)?)
-    }
-
-    fn generate_json(&self, report: &HardwareReport) -> Result<String> {
-        Ok(serde_json::to_string_pretty(report)?)
-    }
-
-    fn generate_markdown(&self, report: &HardwareReport) -> Result<String> {
-        // Generate YAML frontmatter + markdown body
-        let frontmatter = serde_yaml::to_string(report)?;
-        Ok(format!(
-            "---\n{}---\n\n# Hardware Compatibility Report\n\nGenerated on: {}\n",
-            frontmatter, report.metadata.generated_at
-        ))
+        self.format.spec().render(report, false)
     }
 }
 
@@ -65,62 +161,111 @@ impl Default for ReportGenerator {
 impl OutputRenderer {
     /// Create a new output renderer with the specified format
     pub fn new(format: OutputFormat) -> Self {
-        Self { format }
+        Self { format, canonical: false }
+    }
+
+    /// Render in canonical form: sorted components and fields, fixed float precision
+    pub fn canonical(mut self) -> Self {
+        self.canonical = true;
+        self
     }
 
     /// Render a hardware report
+    ///
+    /// Field provenance is diagnostic data and left out; see [`Self::render_debug`].
     pub fn render(&self, report: &HardwareReport) -> Result<String> {
-        match self.format {
-            OutputFormat::Yaml => self.render_yaml(report),
-            OutputFormat::Json => self.render_json(report),
-            OutputFormat::Markdown => self.render_markdown(report),
+        if report.provenance.is_some() {
+            let mut report = report.clone();
+            report.provenance = None;
+            return self.render_report(&report);
         }
+        self.render_report(report)
     }
 
     /// Render a hardware report without privacy anonymization (debug mode)
+    ///
+    /// Includes field provenance, with the values tools disagreed on.
     pub fn render_debug(&self, report: &HardwareReport) -> Result<String> {
-        // Same as regular render for now - could include raw data in the future
-        self.render(report)
+        self.render_report(report)
     }
 
-    fn render_yaml(&self, report: &HardwareReport) -> Result<String> {
-        Ok(serde_yaml::to_string(report)?)
+    fn render_report(&self, report: &HardwareReport) -> Result<String> {
+        self.format.spec().render(report, self.canonical)
     }
+}
 
-    fn render_json(&self, report: &HardwareReport) -> Result<String> {
-        Ok(serde_json::to_string_pretty(report)?)
+fn render_yaml(report: &HardwareReport, canonical: bool) -> Result<String> {
+    if canonical {
+        return canonical::to_yaml(report);
     }
+    Ok(serde_yaml::to_string(report)?)
+}
 
-    fn render_markdown(&self, report: &HardwareReport) -> Result<String> {
-        let mut output = String::new();
+fn render_json(report: &HardwareReport, canonical: bool) -> Result<String> {
+    if canonical {
+        return canonical::to_json(report);
+    }
+    Ok(serde_json::to_string_pretty(report)?)
+}
 
-        // YAML frontmatter
-        output.push_str("---\n");
-        output.push_str(&serde_yaml::to_string(&report.metadata)?);
-        output.push_str("---\n\n");
+fn render_html(report: &HardwareReport, canonical: bool) -> Result<String> {
+    if canonical {
+        return Ok(html::render(&canonical::canonical_report(report)));
+    }
+    Ok(html::render(report))
+}
 
-        // Markdown content
-        output.push_str("# Hardware Compatibility Report\n\n");
-        output.push_str(&format!("Generated: {}\n", report.metadata.generated_at));
-        output.push_str(&format!("Privacy Level: {:?}\n", report.metadata.privacy_level));
-        output.push_str(&format!("Tools Used: {}\n\n", report.metadata.tools_used.join(", ")));
+fn render_xml(report: &HardwareReport, canonical: bool) -> Result<String> {
+    if canonical {
+        return Ok(xml::render(&canonical::canonical_report(report)));
+    }
+    Ok(xml::render(report))
+}
 
-        // System information
-        output.push_str("## System Information\n\n");
-        output.push_str(&format!("- **Hostname:** {}\n", report.system.anonymized_hostname));
-        output.push_str(&format!("- **Kernel:** {}\n", report.system.kernel_version));
-        output.push_str(&format!("- **Architecture:** {}\n", report.system.architecture));
-        if let Some(ref distro) = report.system.distribution {
-            output.push_str(&format!("- **Distribution:** {}\n", distro));
-        }
+fn render_markdown(report: &HardwareReport, canonical: bool) -> Result<String> {
+    let canonical_report;
+    let report = if canonical {
+        canonical_report = canonical::canonical_report(report);
+        &canonical_report
+    } else {
+        report
+    };
+    let mut output = String::new();
 
-        // Kernel compatibility
-        if let Some(ref kernel_support) = report.kernel_support {
-            write_kernel_compatibility_section(&mut output, kernel_support);
-        }
+    // YAML frontmatter
+    output.push_str("---\n");
+    output.push_str(&serde_yaml::to_string(&report.metadata)?);
+    output.push_str("---\n\n");
+
+    // Markdown content
+    output.push_str("# Hardware Compatibility Report\n\n");
+    output.push_str(&format!("Generated: {}\n", report.metadata.generated_at));
+    output.push_str(&format!("Privacy Level: {:?}\n", report.metadata.privacy_level));
+    output.push_str(&format!("Tools Used: {}\n\n", report.metadata.tools_used.join(", ")));
+
+    // System information
+    output.push_str("## System Information\n\n");
+    output.push_str(&format!("- **Hostname:** {}\n", report.system.anonymized_hostname));
+    output.push_str(&format!("- **Kernel:** {}\n", report.system.kernel_version));
+    output.push_str(&format!("- **Architecture:** {}\n", report.system.architecture));
+    if let Some(ref distro) = report.system.distribution {
+        output.push_str(&format!("- **Distribution:** {}\n", distro));
+    }
+
+    // Kernel compatibility
+    if let Some(ref kernel_support) = report.kernel_support {
+        write_kernel_compatibility_section(&mut output, kernel_support);
+    }
 
-        Ok(output)
+    if !report.unknown_devices.is_empty() {
+        write_unknown_devices_section(&mut output, &report.unknown_devices);
     }
+
+    if let Some(ref provenance) = report.provenance {
+        write_provenance_section(&mut output, provenance);
+    }
+
+    Ok(output)
 }
 
 /// Write kernel compatibility section to output
@@ -168,12 +313,111 @@ fn write_device_detail(output: &mut String, device: &crate::hardware::DeviceComp
     output.push('\n');
 }
 
+/// Write unidentified devices with the name of their PCI class
+fn write_unknown_devices_section(output: &mut String, devices: &[crate::hardware::UnknownDevice]) {
+    output.push_str("\n## Unidentified Devices\n\n");
+    for device in devices {
+        let class_name = device
+            .class_code
+            .as_deref()
+            .filter(|_| device.bus == "pci")
+            .and_then(crate::hardware::pci_class::describe)
+            .unwrap_or_else(|| "Unknown class".to_string());
+        output.push_str(&format!(
+            "- {}:{} ({}, {})\n",
+            device.vendor_id, device.device_id, device.bus, class_name
+        ));
+    }
+}
+
+/// Write the tools that supplied each field and where they disagreed
+fn write_provenance_section(
+    output: &mut String,
+    provenance: &crate::hardware::provenance::Provenance,
+) {
+    output.push_str("\n## Field Provenance\n\n");
+    for source in &provenance.fields {
+        output.push_str(&format!(
+            "- `{}`: {} ({:?} confidence)\n",
+            source.field, source.tool, source.confidence
+        ));
+    }
+
+    if !provenance.conflicts.is_empty() {
+        output.push_str("\n### Conflicts\n\n");
+        for conflict in &provenance.conflicts {
+            let others: Vec<String> = conflict
+                .others
+                .iter()
+                .map(|other| format!("{} reported {}", other.tool, other.value))
+                .collect();
+            output.push_str(&format!(
+                "- `{}`: using {} from {}; {}\n",
+                conflict.field,
+                conflict.chosen.value,
+                conflict.chosen.tool,
+                others.join(", ")
+            ));
+        }
+    }
+}
+
 impl fmt::Display for OutputFormat {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        match self {
-            OutputFormat::Yaml => write!(f, "yaml"),
-            OutputFormat::Json => write!(f, "json"),
-            OutputFormat::Markdown => write!(f, "markdown"),
+        f.write_str(self.spec().name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hardware::examples::ExampleGenerator;
+    use crate::hardware::provenance::Provenance;
+
+    #[test]
+    fn test_format_registry_is_complete() {
+        use clap::ValueEnum;
+        use std::collections::HashSet;
+
+        assert_eq!(FORMATS.len(), OutputFormat::ALL.len());
+        let names: HashSet<_> = FORMATS.iter().map(|spec| spec.name).collect();
+        let extensions: HashSet<_> = FORMATS.iter().map(|spec| spec.extension).collect();
+        assert_eq!(names.len(), FORMATS.len(), "format names must be unique");
+        assert_eq!(extensions.len(), FORMATS.len(), "file extensions must be unique");
+
+        let report = ExampleGenerator::new(0).report(0);
+        for &format in OutputFormat::ALL {
+            let spec = format.spec();
+            assert_eq!(spec.format, format);
+            assert_eq!(format.to_string(), spec.name);
+            assert_eq!(OutputFormat::from_name(spec.name), Some(format));
+            assert_eq!(OutputFormat::from_str(spec.name, false), Ok(format));
+            assert!(!spec.help.is_empty() && !spec.label.is_empty());
+            for canonical in [false, true] {
+                let output = spec.render(&report, canonical).unwrap();
+                assert!(!output.is_empty(), "{} rendered nothing", spec.name);
+            }
         }
+        assert_eq!(crate::presentation::export_options().len(), FORMATS.len());
+    }
+
+    #[test]
+    fn test_provenance_only_in_debug_output() {
+        let mut report = ExampleGenerator::new(0).report(0);
+        let mut provenance = Provenance::default();
+        provenance.record(
+            "cpu.cores",
+            &[("dmidecode", Some("8".to_string())), ("lshw", Some("64".to_string()))],
+        );
+        report.provenance = Some(provenance);
+
+        let renderer = OutputRenderer::new(OutputFormat::Markdown);
+        assert!(!renderer.render(&report).unwrap().contains("Field Provenance"));
+        let debug = renderer.render_debug(&report).unwrap();
+        assert!(debug.contains("- `cpu.cores`: using 8 from dmidecode; lshw reported 64"));
+
+        let json = OutputRenderer::new(OutputFormat::Json);
+        assert!(!json.render(&report).unwrap().contains("\"provenance\""));
+        assert!(json.render_debug(&report).unwrap().contains("\"provenance\""));
     }
 }
@@ -0,0 +1,289 @@
+//! Human-oriented terminal rendering of hardware reports
+//!
+//! Used by `detect` when stdout is a terminal and no `--format` was given.
+//! Devices are listed per category in aligned tables with a colored
+//! compatibility badge, and USB devices are also drawn as a tree following
+//! their port chain. Reports meant for files or pipelines keep using the
+//! serialized formats.
+
+use crate::hardware::{HardwareReport, UsbDevice};
+use crate::presentation::{CompatibilityBadge, DeviceCategory, DeviceList};
+use comfy_table::{presets, Attribute, Cell, Color, ContentArrangement, Table};
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+
+/// Renders reports for reading in a terminal
+#[derive(Debug, Clone)]
+pub struct TerminalRenderer {
+    color: bool,
+    width: Option<u16>,
+}
+
+impl TerminalRenderer {
+    pub fn new(color: bool) -> Self {
+        Self { color, width: None }
+    }
+
+    /// Renderer for stdout, colored unless `NO_COLOR` is set
+    pub fn for_stdout() -> Self {
+        Self::new(std::env::var_os("NO_COLOR").is_none())
+    }
+
+    /// Fixed table width instead of the terminal's
+    pub fn with_width(mut self, width: u16) -> Self {
+        self.width = Some(width);
+        self
+    }
+
+    /// Render a hardware report
+    pub fn render(&self, report: &HardwareReport) -> String {
+        let devices = DeviceList::from_report(report);
+        let mut out = String::new();
+
+        let _ = writeln!(out, "{}", self.paint("Hardware Compatibility Report", "1"));
+        let _ = writeln!(
+            out,
+            "{} · kernel {} · {} · privacy {:?}",
+            report.system.distribution.as_deref().unwrap_or("Unknown distribution"),
+            report.system.kernel_version,
+            report.system.architecture,
+            report.metadata.privacy_level
+        );
+        let summary: Vec<String> = BADGES
+            .iter()
+            .filter_map(|&badge| {
+                let count = devices.count_with_badge(badge);
+                (count > 0).then(|| self.badge(badge, &format!("{} {}", count, badge_label(badge))))
+            })
+            .collect();
+        let _ = writeln!(out, "{}", summary.join("   "));
+
+        for (category, items) in devices.by_category() {
+            // System details are in the header
+            if category == DeviceCategory::System {
+                continue;
+            }
+            let mut table = self.table();
+            table.set_header(["Status", "Device", "Vendor", "Details"]);
+            for item in items {
+                table.add_row([
+                    self.badge_cell(item.badge),
+                    Cell::new(&item.name),
+                    Cell::new(&item.vendor),
+                    Cell::new(item.detail_lines().join("\n")),
+                ]);
+            }
+            let _ = write!(out, "\n{}\n{}\n", self.paint(category.display_name(), "1"), table);
+        }
+
+        if report.usb.iter().any(|usb| usb.port_path.is_some()) {
+            let _ = writeln!(out, "\n{}", self.paint("USB Topology", "1"));
+            out.push_str(&usb_tree(&report.usb));
+        }
+
+        let recommendations: Vec<String> = devices
+            .items
+            .iter()
+            .flat_map(|item| {
+                item.recommendations.iter().map(move |r| format!("{}: {}", item.name, r))
+            })
+            .collect();
+        if !recommendations.is_empty() {
+            let _ = writeln!(out, "\n{}", self.paint("Recommendations", "1"));
+            for recommendation in recommendations {
+                let _ = writeln!(out, "  • {}", recommendation);
+            }
+        }
+
+        out
+    }
+
+    fn table(&self) -> Table {
+        let mut table = Table::new();
+        table
+            .load_preset(presets::UTF8_FULL_CONDENSED)
+            .set_content_arrangement(ContentArrangement::Dynamic);
+        if let Some(width) = self.width {
+            table.set_width(width);
+        }
+        if self.color {
+            table.enforce_styling();
+        } else {
+            table.force_no_tty();
+        }
+        table
+    }
+
+    fn badge_cell(&self, badge: CompatibilityBadge) -> Cell {
+        Cell::new(format!("{} {}", badge_symbol(badge), badge.display_text()))
+            .fg(badge_color(badge))
+            .add_attribute(Attribute::Bold)
+    }
+
+    fn badge(&self, badge: CompatibilityBadge, text: &str) -> String {
+        let sgr = match badge_color(badge) {
+            Color::Green => "1;32",
+            Color::Yellow => "1;33",
+            Color::Red => "1;31",
+            _ => "1;90",
+        };
+        self.paint(&format!("{} {}", badge_symbol(badge), text), sgr)
+    }
+
+    /// Wrap `text` in an SGR escape sequence when color is enabled
+    fn paint(&self, text: &str, sgr: &str) -> String {
+        if self.color {
+            format!("\x1b[{}m{}\x1b[0m", sgr, text)
+        } else {
+            text.to_string()
+        }
+    }
+}
+
+/// Badges in summary order
+const BADGES: [CompatibilityBadge; 5] = [
+    CompatibilityBadge::Supported,
+    CompatibilityBadge::PartialSupport,
+    CompatibilityBadge::RequiresDriver,
+    CompatibilityBadge::Unsupported,
+    CompatibilityBadge::Unknown,
+];
+
+fn badge_symbol(badge: CompatibilityBadge) -> &'static str {
+    match badge {
+        CompatibilityBadge::Supported => "✓",
+        CompatibilityBadge::PartialSupport | CompatibilityBadge::RequiresDriver => "!",
+        CompatibilityBadge::Unsupported => "✗",
+        CompatibilityBadge::Unknown => "?",
+    }
+}
+
+fn badge_label(badge: CompatibilityBadge) -> &'static str {
+    match badge {
+        CompatibilityBadge::Supported => "supported",
+        CompatibilityBadge::PartialSupport => "partial",
+        CompatibilityBadge::RequiresDriver => "need a driver",
+        CompatibilityBadge::Unsupported => "unsupported",
+        CompatibilityBadge::Unknown => "unknown",
+    }
+}
+
+fn badge_color(badge: CompatibilityBadge) -> Color {
+    match badge {
+        CompatibilityBadge::Supported => Color::Green,
+        CompatibilityBadge::PartialSupport | CompatibilityBadge::RequiresDriver => Color::Yellow,
+        CompatibilityBadge::Unsupported => Color::Red,
+        CompatibilityBadge::Unknown => Color::DarkGrey,
+    }
+}
+
+/// Bus number and port chain of a port path ("3-1.2" → (3, [1, 2]))
+fn parse_port_path(path: &str) -> Option<(u32, Vec<u32>)> {
+    let (bus, ports) = path.split_once('-')?;
+    let ports = ports.split('.').map(str::parse).collect::<Result<Vec<u32>, _>>().ok()?;
+    Some((bus.parse().ok()?, ports))
+}
+
+/// USB devices drawn as a tree per bus, children under their hub
+fn usb_tree(devices: &[UsbDevice]) -> String {
+    let mut buses: BTreeMap<u32, BTreeMap<Vec<u32>, &UsbDevice>> = BTreeMap::new();
+    for device in devices {
+        if let Some((bus, ports)) = device.port_path.as_deref().and_then(parse_port_path) {
+            buses.entry(bus).or_default().insert(ports, device);
+        }
+    }
+
+    let mut out = String::new();
+    for (bus, devices) in &buses {
+        let _ = writeln!(out, "Bus {}", bus);
+        // Attach each device to its nearest listed ancestor
+        let mut children: BTreeMap<Option<&Vec<u32>>, Vec<&Vec<u32>>> = BTreeMap::new();
+        for ports in devices.keys() {
+            let parent = (1..ports.len())
+                .rev()
+                .find_map(|len| devices.get_key_value(&ports[..len]).map(|(key, _)| key));
+            children.entry(parent).or_default().push(ports);
+        }
+        write_usb_children(&mut out, devices, &children, None, "");
+    }
+    out
+}
+
+fn write_usb_children(
+    out: &mut String,
+    devices: &BTreeMap<Vec<u32>, &UsbDevice>,
+    children: &BTreeMap<Option<&Vec<u32>>, Vec<&Vec<u32>>>,
+    parent: Option<&Vec<u32>>,
+    indent: &str,
+) {
+    let Some(nodes) = children.get(&parent) else {
+        return;
+    };
+    for (i, ports) in nodes.iter().enumerate() {
+        let last = i + 1 == nodes.len();
+        let device = devices[*ports];
+        let name = device.product_name.as_deref().unwrap_or("USB device");
+        let _ = writeln!(
+            out,
+            "{}{}{}  {} ({}:{})",
+            indent,
+            if last { "└─ " } else { "├─ " },
+            device.port_path.as_deref().unwrap_or_default(),
+            name,
+            device.vendor_id,
+            device.product_id
+        );
+        let indent = format!("{}{}", indent, if last { "   " } else { "│  " });
+        write_usb_children(out, devices, children, Some(ports), &indent);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hardware::examples::ExampleGenerator;
+
+    fn usb(port_path: &str, name: &str) -> UsbDevice {
+        serde_json::from_value(serde_json::json!({
+            "vendor_id": "05e3",
+            "product_id": "0610",
+            "vendor_name": null,
+            "product_name": name,
+            "usb_version": "2.00",
+            "port_path": port_path,
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_usb_tree_nests_under_hubs() {
+        let devices = [
+            usb("1-2.3", "Keyboard"),
+            usb("1-2", "Hub"),
+            usb("1-1", "Receiver"),
+            usb("2-4.1.2", "Webcam"),
+        ];
+        let expected = [
+            "Bus 1",
+            "├─ 1-1  Receiver (05e3:0610)",
+            "└─ 1-2  Hub (05e3:0610)",
+            "   └─ 1-2.3  Keyboard (05e3:0610)",
+            "Bus 2",
+            "└─ 2-4.1.2  Webcam (05e3:0610)",
+        ];
+        assert_eq!(usb_tree(&devices), expected.join("\n") + "\n");
+    }
+
+    #[test]
+    fn test_render_without_color() {
+        let report = ExampleGenerator::new(0).report(0);
+        let text = TerminalRenderer::new(false).with_width(100).render(&report);
+        assert!(text.starts_with("Hardware Compatibility Report\n"));
+        assert!(text.contains(&report.system.kernel_version));
+        assert!(text.contains("│ Status"));
+        assert!(!text.contains('\x1b'));
+
+        let colored = TerminalRenderer::new(true).with_width(100).render(&report);
+        assert!(colored.contains("\x1b[1m"));
+    }
+}
@@ -0,0 +1,361 @@
+//! lshw-compatible XML export of hardware reports
+//!
+//! Inventory tools that already import `lshw -xml` output can take reports in
+//! the same shape: a `<list>` holding one `system` node with nested `node`
+//! elements per device. Report metadata that lshw has no element for is kept
+//! as `<setting>` entries of the system node, prefixed with `lx-hw-db.`. The
+//! element mapping is documented in `docs/technical/xml-export.md`.
+
+use crate::hardware::HardwareReport;
+use std::fmt::Write as _;
+
+/// Render a hardware report as lshw-style XML
+pub fn render(report: &HardwareReport) -> String {
+    let mut xml = XmlWriter::default();
+    xml.raw("<?xml version=\"1.0\" standalone=\"yes\" ?>");
+    xml.raw(&format!("<!-- generated by lx-hw-detect {} -->", env!("CARGO_PKG_VERSION")));
+    xml.raw(&format!(
+        "<!-- Linux {} {} -->",
+        escape(&report.system.kernel_version),
+        escape(&report.system.architecture)
+    ));
+    xml.open("list", &[]);
+
+    xml.open("node", &[("id", "system"), ("claimed", "true"), ("class", "system")]);
+    xml.element("description", "Computer");
+    xml.element("product", report.system.distribution.as_deref().unwrap_or("Linux system"));
+    xml.element("serial", &report.system.anonymized_hostname);
+    if let Some(width) = architecture_width(&report.system.architecture) {
+        xml.element_with("width", &[("units", "bits")], width);
+    }
+    let metadata = &report.metadata;
+    xml.configuration(&[
+        ("lx-hw-db.version", Some(metadata.version.clone())),
+        ("lx-hw-db.generated", Some(metadata.generated_at.to_rfc3339())),
+        ("lx-hw-db.privacy_level", Some(format!("{:?}", metadata.privacy_level))),
+        ("lx-hw-db.tools", Some(metadata.tools_used.join(","))),
+        ("lx-hw-db.system_id", Some(metadata.anonymized_system_id.clone())),
+        ("lx-hw-db.salt_context", metadata.salt_context.clone()),
+        ("lx-hw-db.kernel", Some(report.system.kernel_version.clone())),
+        ("lx-hw-db.architecture", Some(report.system.architecture.clone())),
+    ]);
+
+    xml.open("node", &[("id", "core"), ("claimed", "true"), ("class", "bus")]);
+    xml.element("description", "Motherboard");
+    write_cpu(&mut xml, report);
+    write_memory(&mut xml, report);
+    write_pci(&mut xml, report);
+    write_usb(&mut xml, report);
+    xml.close("node");
+
+    xml.close("node");
+    xml.close("list");
+    xml.finish()
+}
+
+fn write_cpu(xml: &mut XmlWriter, report: &HardwareReport) {
+    let Some(cpu) = &report.cpu else {
+        return;
+    };
+    xml.open("node", &[("id", "cpu"), ("claimed", "true"), ("class", "processor")]);
+    xml.element("description", "CPU");
+    xml.element("product", &cpu.model);
+    xml.element("vendor", &cpu.vendor);
+    if let Some(mhz) = cpu.base_frequency {
+        xml.element_with("size", &[("units", "Hz")], &hertz(mhz));
+    }
+    if let Some(mhz) = cpu.max_frequency {
+        xml.element_with("capacity", &[("units", "Hz")], &hertz(mhz));
+    }
+    xml.configuration(&[
+        ("cores", Some(cpu.cores.to_string())),
+        ("threads", Some(cpu.threads.to_string())),
+        ("governor", cpu.scaling_governor.clone()),
+        ("microcode", cpu.microcode.as_ref().map(|m| m.revision.to_string())),
+    ]);
+    if !cpu.flags.is_empty() {
+        xml.open("capabilities", &[]);
+        for flag in &cpu.flags {
+            xml.open_empty("capability", &[("id", flag)]);
+        }
+        xml.close("capabilities");
+    }
+    xml.close("node");
+}
+
+fn write_memory(xml: &mut XmlWriter, report: &HardwareReport) {
+    let Some(memory) = &report.memory else {
+        return;
+    };
+    xml.open("node", &[("id", "memory"), ("claimed", "true"), ("class", "memory")]);
+    xml.element("description", "System Memory");
+    xml.element_with("size", &[("units", "bytes")], &memory.total_bytes.to_string());
+    for (i, dimm) in memory.dimms.iter().enumerate() {
+        let id = format!("bank:{}", i);
+        xml.open("node", &[("id", &id), ("claimed", "true"), ("class", "memory")]);
+        let description = match (&dimm.memory_type, dimm.speed_mhz) {
+            (Some(kind), Some(speed)) => format!("DIMM {} {} MHz", kind, speed),
+            (Some(kind), None) => format!("DIMM {}", kind),
+            (None, _) => "DIMM".to_string(),
+        };
+        xml.element("description", &description);
+        if let Some(manufacturer) = &dimm.manufacturer {
+            xml.element("vendor", manufacturer);
+        }
+        xml.element_with("size", &[("units", "bytes")], &dimm.size_bytes.to_string());
+        if let Some(speed) = dimm.speed_mhz {
+            xml.element_with("clock", &[("units", "Hz")], &hertz(speed as f64));
+        }
+        xml.close("node");
+    }
+    xml.close("node");
+}
+
+fn write_pci(xml: &mut XmlWriter, report: &HardwareReport) {
+    if report.graphics.is_empty()
+        && report.network.is_empty()
+        && report.audio.is_empty()
+        && report.storage.is_empty()
+    {
+        return;
+    }
+    xml.open("node", &[("id", "pci"), ("claimed", "true"), ("class", "bridge")]);
+    xml.element("description", "Host bridge");
+
+    for (i, gpu) in report.graphics.iter().enumerate() {
+        let id = format!("display:{}", i);
+        xml.open("node", &[("id", &id), ("claimed", "true"), ("class", "display")]);
+        xml.element("description", "VGA compatible controller");
+        xml.element("product", &gpu.model);
+        xml.element("vendor", &gpu.vendor);
+        if let Some(bytes) = gpu.memory_bytes {
+            xml.element_with("size", &[("units", "bytes")], &bytes.to_string());
+        }
+        xml.configuration(&[
+            ("driver", gpu.driver.clone()),
+            ("lx-hw-db.pci_id", Some(gpu.pci_id.clone())),
+        ]);
+        xml.close("node");
+    }
+
+    for (i, network) in report.network.iter().enumerate() {
+        let id = format!("network:{}", i);
+        xml.open("node", &[("id", &id), ("claimed", "true"), ("class", "network")]);
+        xml.element("description", &network_description(&network.device_type));
+        xml.element("product", &network.model);
+        xml.element("vendor", &network.vendor);
+        xml.element("serial", &network.anonymized_mac);
+        xml.configuration(&[("driver", network.driver.clone())]);
+        xml.close("node");
+    }
+
+    for (i, audio) in report.audio.iter().enumerate() {
+        let id = format!("multimedia:{}", i);
+        xml.open("node", &[("id", &id), ("claimed", "true"), ("class", "multimedia")]);
+        xml.element("description", "Audio device");
+        xml.element("product", &audio.model);
+        xml.element("vendor", &audio.vendor);
+        xml.configuration(&[
+            ("driver", audio.driver.clone()),
+            ("lx-hw-db.device_type", Some(audio.device_type.clone())),
+        ]);
+        xml.close("node");
+    }
+
+    for (i, storage) in report.storage.iter().enumerate() {
+        let id = format!("disk:{}", i);
+        xml.open("node", &[("id", &id), ("claimed", "true"), ("class", "disk")]);
+        xml.element("description", &format!("{} disk", storage.device_type));
+        xml.element("product", &storage.model);
+        if let Some(vendor) = &storage.vendor {
+            xml.element("vendor", vendor);
+        }
+        xml.element("serial", &storage.anonymized_serial);
+        xml.element_with("size", &[("units", "bytes")], &storage.size_bytes.to_string());
+        xml.configuration(&[("lx-hw-db.interface", storage.interface.clone())]);
+        xml.close("node");
+    }
+
+    xml.close("node");
+}
+
+fn write_usb(xml: &mut XmlWriter, report: &HardwareReport) {
+    if report.usb.is_empty() {
+        return;
+    }
+    xml.open("node", &[("id", "usb"), ("claimed", "true"), ("class", "bus")]);
+    xml.element("description", "USB controller");
+    for (i, usb) in report.usb.iter().enumerate() {
+        let id = format!("usb:{}", i);
+        xml.open("node", &[("id", &id), ("claimed", "true"), ("class", "generic")]);
+        xml.element("description", usb.device_class.as_deref().unwrap_or("USB device"));
+        if let Some(product) = &usb.product_name {
+            xml.element("product", product);
+        }
+        if let Some(vendor) = &usb.vendor_name {
+            xml.element("vendor", vendor);
+        }
+        if let Some(port_path) = &usb.port_path {
+            xml.element("businfo", &format!("usb@{}", port_path));
+        }
+        if let Some(version) = &usb.usb_version {
+            xml.element("version", version);
+        }
+        if let Some(serial) = &usb.anonymized_serial {
+            xml.element("serial", serial);
+        }
+        xml.configuration(&[
+            ("lx-hw-db.usb_id", Some(format!("{}:{}", usb.vendor_id, usb.product_id))),
+            ("speed", usb.speed_mbps.map(|speed| format!("{}Mbit/s", speed))),
+            ("maxpower", usb.max_power_ma.map(|ma| format!("{}mA", ma))),
+        ]);
+        xml.close("node");
+    }
+    xml.close("node");
+}
+
+fn network_description(device_type: &str) -> String {
+    match device_type {
+        "ethernet" => "Ethernet interface".to_string(),
+        "wifi" => "Wireless interface".to_string(),
+        "bluetooth" => "Bluetooth controller".to_string(),
+        other => format!("{} interface", other),
+    }
+}
+
+/// Address width of an architecture, as lshw reports for the system node
+fn architecture_width(architecture: &str) -> Option<&'static str> {
+    match architecture {
+        "x86_64" | "aarch64" | "riscv64" | "ppc64le" | "s390x" => Some("64"),
+        "i686" | "i386" | "armv7l" => Some("32"),
+        _ => None,
+    }
+}
+
+/// MHz as a whole number of Hz, the unit lshw uses
+fn hertz(mhz: f64) -> String {
+    format!("{:.0}", mhz * 1_000_000.0)
+}
+
+/// Escape text for XML content and attribute values
+pub fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            // Control characters are not allowed in XML 1.0
+            c if c.is_control() && !matches!(c, '\t' | '\n' | '\r') => {}
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Indenting XML writer in lshw's layout
+#[derive(Default)]
+struct XmlWriter {
+    out: String,
+    depth: usize,
+}
+
+impl XmlWriter {
+    fn raw(&mut self, line: &str) {
+        let _ = writeln!(self.out, "{}", line);
+    }
+
+    fn indent(&self) -> String {
+        " ".repeat(self.depth)
+    }
+
+    fn open(&mut self, name: &str, attributes: &[(&str, &str)]) {
+        let _ = writeln!(self.out, "{}<{}{}>", self.indent(), name, attrs(attributes));
+        self.depth += 1;
+    }
+
+    fn open_empty(&mut self, name: &str, attributes: &[(&str, &str)]) {
+        let _ = writeln!(self.out, "{}<{}{} />", self.indent(), name, attrs(attributes));
+    }
+
+    fn close(&mut self, name: &str) {
+        self.depth -= 1;
+        let _ = writeln!(self.out, "{}</{}>", self.indent(), name);
+    }
+
+    fn element(&mut self, name: &str, text: &str) {
+        self.element_with(name, &[], text);
+    }
+
+    fn element_with(&mut self, name: &str, attributes: &[(&str, &str)], text: &str) {
+        let _ = writeln!(
+            self.out,
+            "{}<{}{}>{}</{}>",
+            self.indent(),
+            name,
+            attrs(attributes),
+            escape(text),
+            name
+        );
+    }
+
+    /// `<configuration>` with a `<setting>` per value that is present
+    fn configuration(&mut self, settings: &[(&str, Option<String>)]) {
+        let present: Vec<(&str, &str)> =
+            settings.iter().filter_map(|(id, value)| Some((*id, value.as_deref()?))).collect();
+        if present.is_empty() {
+            return;
+        }
+        self.open("configuration", &[]);
+        for (id, value) in present {
+            self.open_empty("setting", &[("id", id), ("value", value)]);
+        }
+        self.close("configuration");
+    }
+
+    fn finish(self) -> String {
+        self.out
+    }
+}
+
+fn attrs(attributes: &[(&str, &str)]) -> String {
+    attributes.iter().map(|(name, value)| format!(" {}=\"{}\"", name, escape(value))).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hardware::examples::ExampleGenerator;
+
+    #[test]
+    fn test_render_lshw_layout() {
+        let report = ExampleGenerator::new(0).report(0);
+        let xml = render(&report);
+
+        assert!(xml.starts_with("<?xml version=\"1.0\" standalone=\"yes\" ?>\n"));
+        assert!(xml.contains("<node id=\"system\" claimed=\"true\" class=\"system\">"));
+        assert!(xml.contains(&format!(
+            "<setting id=\"lx-hw-db.system_id\" value=\"{}\" />",
+            report.metadata.anonymized_system_id
+        )));
+        assert!(xml.trim_end().ends_with("</list>"));
+
+        // Every opened node is closed
+        assert_eq!(xml.matches("<node ").count(), xml.matches("</node>").count());
+        if report.cpu.is_some() {
+            assert!(xml.contains("class=\"processor\""));
+        }
+        assert_eq!(xml.matches("class=\"display\"").count(), report.graphics.len());
+        assert_eq!(xml.matches("class=\"network\"").count(), report.network.len());
+    }
+
+    #[test]
+    fn test_escape() {
+        assert_eq!(
+            escape("Tom & Jerry's <GPU> \"x\"\u{1}"),
+            "Tom &amp; Jerry&apos;s &lt;GPU&gt; &quot;x&quot;"
+        );
+    }
+}
@@ -0,0 +1,163 @@
+//! Pre-flight checks before writing many output files
+//!
+//! The indexer writes thousands of JSON files and the snapshot history
+//! writes one report per run. Running out of space or permissions halfway
+//! leaves partial output that later runs and the website read as complete,
+//! so both check their target directories first: the directory can be
+//! created and written to, its filesystem has room for the estimated output,
+//! and whether it folds case, which makes vendor-named files collide.
+
+use crate::errors::{LxHwError, Result};
+use std::path::{Path, PathBuf};
+
+/// Free space kept beyond the estimate, for filesystem overhead
+const SPACE_MARGIN_PERCENT: u64 = 10;
+
+/// Expected size of the output written into a directory
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct OutputEstimate {
+    pub bytes: u64,
+    pub files: u64,
+}
+
+/// What the checks found out about a directory
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DirectoryCheck {
+    pub dir: PathBuf,
+    /// Bytes available to unprivileged users; None when unknown
+    pub available_bytes: Option<u64>,
+    /// Whether file names differing only in case name the same file
+    pub case_insensitive: bool,
+}
+
+/// Check that `dir` can hold `estimate` more output
+///
+/// Creates the directory if needed. Errors say what to change; a filesystem
+/// that folds case is only reported in the result, for the caller to warn.
+pub fn check_output_dir(dir: &Path, estimate: OutputEstimate) -> Result<DirectoryCheck> {
+    std::fs::create_dir_all(dir).map_err(|e| {
+        LxHwError::Io(format!(
+            "Cannot create output directory {}: {}; create it or choose another directory",
+            dir.display(),
+            e
+        ))
+    })?;
+
+    let case_insensitive = probe_write(dir)?;
+
+    let (available_bytes, available_files) = available_space(dir);
+    if let Some(available) = available_bytes {
+        let required = estimate.bytes.saturating_add(estimate.bytes / 100 * SPACE_MARGIN_PERCENT);
+        if available < required {
+            return Err(LxHwError::Io(format!(
+                "Not enough space in {}: about {} needed, {} available; free up space or \
+                 choose a directory on another filesystem",
+                dir.display(),
+                format_bytes(required),
+                format_bytes(available)
+            )));
+        }
+    }
+    if let Some(available) = available_files {
+        if available < estimate.files {
+            return Err(LxHwError::Io(format!(
+                "Not enough free inodes in {}: {} files to write, {} available; remove old \
+                 output or choose a directory on another filesystem",
+                dir.display(),
+                estimate.files,
+                available
+            )));
+        }
+    }
+
+    Ok(DirectoryCheck { dir: dir.to_path_buf(), available_bytes, case_insensitive })
+}
+
+/// Write and remove a probe file, returning whether the directory folds case
+fn probe_write(dir: &Path) -> Result<bool> {
+    let name = format!(".lx-hw-db-preflight-{}", std::process::id());
+    let probe = dir.join(&name);
+    std::fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(&probe)
+        .and_then(|mut file| std::io::Write::write_all(&mut file, b"preflight"))
+        .map_err(|e| {
+            let _ = std::fs::remove_file(&probe);
+            LxHwError::Io(format!(
+                "Cannot write to {}: {}; fix its permissions or choose another directory",
+                dir.display(),
+                e
+            ))
+        })?;
+
+    let case_insensitive = dir.join(name.to_uppercase()).exists();
+    std::fs::remove_file(&probe)?;
+    Ok(case_insensitive)
+}
+
+/// Bytes and inodes available to unprivileged users
+///
+/// Either is None when the filesystem does not report it; some network and
+/// virtual filesystems report zero inodes, which is treated as unknown.
+#[allow(clippy::unnecessary_cast)]
+fn available_space(dir: &Path) -> (Option<u64>, Option<u64>) {
+    match nix::sys::statvfs::statvfs(dir) {
+        Ok(stat) => {
+            let bytes =
+                (stat.blocks_available() as u64).saturating_mul(stat.fragment_size() as u64);
+            let files = (stat.files() > 0).then_some(stat.files_available() as u64);
+            (Some(bytes), files)
+        }
+        Err(e) => {
+            log::debug!("Cannot query free space of {}: {}", dir.display(), e);
+            (None, None)
+        }
+    }
+}
+
+fn format_bytes(bytes: u64) -> String {
+    const MIB: u64 = 1024 * 1024;
+    if bytes >= 1024 * MIB {
+        format!("{:.1} GiB", bytes as f64 / (1024 * MIB) as f64)
+    } else {
+        format!("{:.1} MiB", bytes as f64 / MIB as f64)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_creates_and_probes_directory() {
+        let root = tempfile::tempdir().unwrap();
+        let dir = root.path().join("indices/nested");
+        let check = check_output_dir(&dir, OutputEstimate { bytes: 1024, files: 4 }).unwrap();
+        assert!(dir.is_dir());
+        assert!(check.available_bytes.is_some_and(|bytes| bytes > 0));
+        // The probe file is gone afterwards
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 0);
+
+        let huge = OutputEstimate { bytes: u64::MAX / 2, files: 1 };
+        let error = check_output_dir(&dir, huge).unwrap_err().to_string();
+        assert!(error.contains("Not enough space"), "{}", error);
+    }
+
+    #[test]
+    fn test_unwritable_directory_fails_early() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let root = tempfile::tempdir().unwrap();
+        let dir = root.path().join("readonly");
+        std::fs::create_dir(&dir).unwrap();
+        std::fs::set_permissions(&dir, std::fs::Permissions::from_mode(0o555)).unwrap();
+        // Root ignores directory permissions
+        if std::fs::write(dir.join("probe"), b"").is_ok() {
+            return;
+        }
+
+        let error = check_output_dir(&dir, OutputEstimate::default()).unwrap_err().to_string();
+        assert!(error.contains("Cannot write to"), "{}", error);
+    }
+}
@@ -0,0 +1,948 @@
+//! Toolkit-agnostic presentation model shared by the GUI front-ends
+//!
+//! The GTK and Qt front-ends both render the same information: a list of
+//! detected devices grouped by category, a compatibility badge per device,
+//! detection progress and the available export formats. Deriving all of it
+//! here from a `HardwareReport` keeps the two interfaces from drifting; the
+//! front-ends only map these types onto their widgets.
+
+use crate::hardware::{
+    BootMode, FirmwareInfo, HardwareReport, LifecycleNotice, MicrocodeInfo, MicrocodeStatus,
+    PcieDeviceLink, VirtualizationKind,
+};
+use crate::output::{OutputFormat, FORMATS};
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+/// Device category, in display order
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize)]
+pub enum DeviceCategory {
+    System,
+    Processor,
+    Memory,
+    Graphics,
+    Storage,
+    Network,
+    Audio,
+    Usb,
+    Input,
+}
+
+impl DeviceCategory {
+    /// Short label used in lists and summaries
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::System => "System",
+            Self::Processor => "CPU",
+            Self::Memory => "Memory",
+            Self::Graphics => "Graphics",
+            Self::Storage => "Storage",
+            Self::Network => "Network",
+            Self::Audio => "Audio",
+            Self::Usb => "USB",
+            Self::Input => "Input",
+        }
+    }
+
+    /// Heading used for category groups
+    pub fn display_name(&self) -> &'static str {
+        match self {
+            Self::System => "System & Motherboard",
+            Self::Processor => "CPU",
+            Self::Memory => "Memory",
+            Self::Graphics => "Graphics",
+            Self::Storage => "Storage",
+            Self::Network => "Network",
+            Self::Audio => "Audio",
+            Self::Usb => "USB & Peripherals",
+            Self::Input => "Input Devices",
+        }
+    }
+
+    /// Freedesktop icon name
+    pub fn icon_name(&self) -> &'static str {
+        match self {
+            Self::System => "computer-symbolic",
+            Self::Processor => "cpu-symbolic",
+            Self::Memory => "media-memory-symbolic",
+            Self::Graphics => "video-display-symbolic",
+            Self::Storage => "drive-harddisk-symbolic",
+            Self::Network => "network-wired-symbolic",
+            Self::Audio => "audio-speakers-symbolic",
+            Self::Usb => "usb-symbolic",
+            Self::Input => "input-keyboard-symbolic",
+        }
+    }
+}
+
+/// Compatibility badge shown next to a device
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+pub enum CompatibilityBadge {
+    Supported,
+    PartialSupport,
+    RequiresDriver,
+    Unsupported,
+    Unknown,
+}
+
+impl CompatibilityBadge {
+    /// Stable identifier, e.g. for QML property bindings
+    pub fn key(&self) -> &'static str {
+        match self {
+            Self::Supported => "supported",
+            Self::PartialSupport => "partial",
+            Self::RequiresDriver => "driver-required",
+            Self::Unsupported => "unsupported",
+            Self::Unknown => "unknown",
+        }
+    }
+
+    pub fn display_text(&self) -> &'static str {
+        match self {
+            Self::Supported => "Fully Supported",
+            Self::PartialSupport => "Partial Support",
+            Self::RequiresDriver => "Driver Required",
+            Self::Unsupported => "Not Supported",
+            Self::Unknown => "Unknown",
+        }
+    }
+
+    /// Emoji prefix for text-based front-ends
+    pub fn emoji(&self) -> &'static str {
+        match self {
+            Self::Supported => "✅",
+            Self::PartialSupport | Self::RequiresDriver => "⚠️",
+            Self::Unsupported => "❌",
+            Self::Unknown => "❓",
+        }
+    }
+
+    /// CSS class for GTK styling
+    pub fn css_class(&self) -> &'static str {
+        match self {
+            Self::Supported => "status-supported",
+            Self::PartialSupport => "status-partial",
+            Self::RequiresDriver => "status-warning",
+            Self::Unsupported => "status-error",
+            Self::Unknown => "status-unknown",
+        }
+    }
+
+    /// Freedesktop icon name
+    pub fn icon_name(&self) -> &'static str {
+        match self {
+            Self::Supported => "emblem-ok-symbolic",
+            Self::PartialSupport => "dialog-warning-symbolic",
+            Self::RequiresDriver => "software-update-urgent-symbolic",
+            Self::Unsupported => "emblem-important-symbolic",
+            Self::Unknown => "help-about-symbolic",
+        }
+    }
+
+    /// Material palette color for Qt styling
+    pub fn color(&self) -> &'static str {
+        match self {
+            Self::Supported => "#4CAF50",
+            Self::PartialSupport | Self::RequiresDriver => "#FF9800",
+            Self::Unsupported => "#F44336",
+            Self::Unknown => "#9E9E9E",
+        }
+    }
+
+    /// Badge for a kernel support status ("supported", "experimental", "unsupported")
+    fn from_support_status(status: &str) -> Self {
+        match status {
+            "supported" => Self::Supported,
+            "experimental" => Self::PartialSupport,
+            "unsupported" => Self::Unsupported,
+            _ => Self::Unknown,
+        }
+    }
+}
+
+/// A device as shown in a device list
+#[derive(Debug, Clone, Serialize)]
+pub struct DeviceItem {
+    /// Identifier unique within the report, e.g. "gpu_0"
+    pub id: String,
+    pub name: String,
+    pub category: DeviceCategory,
+    pub vendor: String,
+    pub model: String,
+    pub badge: CompatibilityBadge,
+    /// Labelled details in display order
+    pub details: Vec<(String, String)>,
+    pub recommendations: Vec<String>,
+}
+
+impl DeviceItem {
+    fn new(
+        id: String,
+        category: DeviceCategory,
+        name: String,
+        vendor: String,
+        model: String,
+    ) -> Self {
+        Self {
+            id,
+            name,
+            category,
+            vendor,
+            model,
+            badge: CompatibilityBadge::Supported,
+            details: Vec::new(),
+            recommendations: Vec::new(),
+        }
+    }
+
+    fn detail(mut self, label: &str, value: impl Into<String>) -> Self {
+        self.details.push((label.to_string(), value.into()));
+        self
+    }
+
+    /// Details rendered as "Label: value" lines
+    pub fn detail_lines(&self) -> Vec<String> {
+        self.details.iter().map(|(label, value)| format!("{}: {}", label, value)).collect()
+    }
+}
+
+/// All devices of a report, in category order
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct DeviceList {
+    pub items: Vec<DeviceItem>,
+}
+
+impl DeviceList {
+    /// Build the device list shown for a report
+    pub fn from_report(report: &HardwareReport) -> Self {
+        let mut items = Vec::new();
+        let distribution = report.system.distribution.clone().unwrap_or_else(unknown);
+
+        let mut system = DeviceItem::new(
+            "system".to_string(),
+            DeviceCategory::System,
+            "System Information".to_string(),
+            "System".to_string(),
+            distribution.clone(),
+        )
+        .detail("Distribution", distribution)
+        .detail("Kernel", &report.system.kernel_version)
+        .detail("Architecture", &report.system.architecture);
+        let virtualization = report.system.virtualization.as_ref();
+        if let Some(virt) = virtualization.filter(|_| report.system.is_virtual()) {
+            let kind = match virt.kind {
+                VirtualizationKind::Container => "container",
+                _ => "virtual machine",
+            };
+            let technology = virt.technology.as_deref().unwrap_or("unknown");
+            system = system.detail("Virtualization", format!("{} {}", technology, kind));
+        }
+        if let Some(firmware) = &report.firmware {
+            system = firmware_details(system, firmware);
+        }
+        items.push(system);
+
+        if let Some(cpu) = &report.cpu {
+            let mut item = DeviceItem::new(
+                "cpu".to_string(),
+                DeviceCategory::Processor,
+                cpu.model.clone(),
+                cpu.vendor.clone(),
+                cpu.model.clone(),
+            )
+            .detail("Cores", cpu.cores.to_string())
+            .detail("Threads", cpu.threads.to_string());
+            if let Some(frequency) = cpu.base_frequency {
+                item = item.detail("Base Frequency", format!("{:.2} GHz", frequency / 1000.0));
+            }
+            if let Some(microcode) = &cpu.microcode {
+                item = item.detail("Microcode", microcode_summary(microcode));
+            }
+            items.push(apply_lifecycle(item, None, &report.lifecycle_notices));
+        }
+
+        if let Some(memory) = &report.memory {
+            let mut item = DeviceItem::new(
+                "memory".to_string(),
+                DeviceCategory::Memory,
+                format!("System Memory ({})", gigabytes(memory.total_bytes)),
+                "System".to_string(),
+                format!("{} DIMMs", memory.dimm_count()),
+            )
+            .detail("Total", gigabytes(memory.total_bytes))
+            .detail("Available", gigabytes(memory.available_bytes));
+            if let Some(memory_type) = memory.dimms.first().and_then(|d| d.memory_type.clone()) {
+                item = item.detail("Type", memory_type);
+            }
+            items.push(item);
+        }
+
+        for (i, gpu) in report.graphics.iter().enumerate() {
+            let mut item = DeviceItem::new(
+                format!("gpu_{}", i),
+                DeviceCategory::Graphics,
+                gpu.model.clone(),
+                gpu.vendor.clone(),
+                gpu.model.clone(),
+            )
+            .detail("PCI ID", &gpu.pci_id)
+            .detail("Driver", gpu.driver.clone().unwrap_or_else(|| "Not loaded".to_string()));
+            if let Some(memory) = gpu.memory_bytes {
+                item = item.detail("Memory", format!("{:.1} MB", memory as f64 / MIB));
+            }
+            if let Some(pcie) = report.pcie_links.iter().find(|link| link.pci_id == gpu.pci_id) {
+                item = pcie_details(item, pcie);
+            }
+            item = apply_driver(item, gpu.driver.is_some(), "Install appropriate graphics driver");
+            item = apply_kernel_support(item, &gpu.pci_id, report);
+            items.push(apply_lifecycle(item, Some(&gpu.pci_id), &report.lifecycle_notices));
+        }
+
+        for (i, storage) in report.storage.iter().enumerate() {
+            let mut item = DeviceItem::new(
+                format!("storage_{}", i),
+                DeviceCategory::Storage,
+                storage.model.clone(),
+                storage.vendor.clone().unwrap_or_else(unknown),
+                storage.model.clone(),
+            )
+            .detail("Type", &storage.device_type)
+            .detail("Size", gigabytes(storage.size_bytes))
+            .detail("Interface", storage.interface.clone().unwrap_or_else(unknown));
+            if let Some(health) = &storage.health {
+                if let Some(hours) = health.power_on_hours {
+                    item = item.detail("Power-On Hours", hours.to_string());
+                }
+                if let Some(wear) = health.wear_percent {
+                    item = item.detail("Wear", format!("{}%", wear));
+                }
+                if health.passed == Some(false) {
+                    item.badge = CompatibilityBadge::PartialSupport;
+                    item.recommendations.push(
+                        "The drive's SMART self-assessment failed; back up its data and \
+                         replace it"
+                            .to_string(),
+                    );
+                }
+            }
+            items.push(item);
+        }
+
+        for (i, net) in report.network.iter().enumerate() {
+            let item = DeviceItem::new(
+                format!("net_{}", i),
+                DeviceCategory::Network,
+                net.model.clone(),
+                net.vendor.clone(),
+                net.model.clone(),
+            )
+            .detail("Type", &net.device_type)
+            .detail("Driver", net.driver.clone().unwrap_or_else(|| "Not loaded".to_string()));
+            items.push(apply_driver(
+                item,
+                net.driver.is_some(),
+                "Install appropriate network driver",
+            ));
+        }
+
+        for (i, radio) in report.radios.iter().enumerate() {
+            let state = match (radio.hard_blocked, radio.soft_blocked) {
+                (true, _) => "Hard blocked",
+                (false, true) => "Soft blocked",
+                (false, false) => "Unblocked",
+            };
+            let mut item = DeviceItem::new(
+                format!("radio_{}", i),
+                DeviceCategory::Network,
+                format!("{} Radio ({})", radio.label(), radio.name),
+                unknown(),
+                radio.coexistence.as_ref().map_or_else(unknown, |quirk| quirk.chip.clone()),
+            )
+            .detail("Type", radio.label())
+            .detail("Driver", radio.driver.clone().unwrap_or_else(|| "Not loaded".to_string()))
+            .detail("State", state);
+            if let Some(device_id) = &radio.device_id {
+                item = item.detail("Device ID", device_id);
+            }
+            if let Some(steps) = radio.unblock_steps() {
+                item.badge = CompatibilityBadge::PartialSupport;
+                item.recommendations.push(steps);
+            }
+            if let Some(quirk) = &radio.coexistence {
+                item.recommendations.push(format!("{}: {}", quirk.chip, quirk.note));
+            }
+            items.push(item);
+        }
+
+        for (i, audio) in report.audio.iter().enumerate() {
+            let mut item = DeviceItem::new(
+                format!("audio_{}", i),
+                DeviceCategory::Audio,
+                format!("{} Audio", audio.vendor),
+                audio.vendor.clone(),
+                audio.model.clone(),
+            )
+            .detail("Type", &audio.device_type)
+            .detail("Driver", audio.driver.clone().unwrap_or_else(|| "Built-in".to_string()));
+            if !audio.codecs.is_empty() {
+                let codecs: Vec<&str> =
+                    audio.codecs.iter().map(|codec| codec.name.as_str()).collect();
+                item = item.detail("Codecs", codecs.join(", "));
+            }
+            if let Some(active) =
+                audio.profiles.as_ref().and_then(|profiles| profiles.active.as_ref())
+            {
+                item = item.detail("Profile", active);
+            }
+            items.push(item);
+        }
+
+        if let Some(routing) = &report.audio_routing {
+            let outputs: Vec<String> = routing
+                .outputs
+                .iter()
+                .map(|output| match output.connected {
+                    Some(true) => format!("{} (display attached)", output.name),
+                    Some(false) => format!("{} (nothing attached)", output.name),
+                    None => output.name.clone(),
+                })
+                .collect();
+            let mut item = DeviceItem::new(
+                "audio_routing".to_string(),
+                DeviceCategory::Audio,
+                "Audio Output Routing".to_string(),
+                unknown(),
+                unknown(),
+            )
+            .detail(
+                "Default Output",
+                routing.current_output().map_or_else(unknown, |output| output.label()),
+            )
+            .detail(
+                "Sound Server",
+                match &routing.sound_server_version {
+                    Some(version) => format!("{} {}", routing.sound_server, version),
+                    None => routing.sound_server.clone(),
+                },
+            )
+            .detail("Outputs", outputs.join(", "));
+            if let Some(issue) = routing.routing_issue() {
+                item.badge = CompatibilityBadge::PartialSupport;
+                item.recommendations.push(issue);
+            }
+            items.push(item);
+        }
+
+        for (i, usb) in report.usb.iter().enumerate() {
+            let name = usb
+                .product_name
+                .clone()
+                .unwrap_or_else(|| format!("USB Device {}:{}", usb.vendor_id, usb.product_id));
+            let mut item = DeviceItem::new(
+                format!("usb_{}", i),
+                DeviceCategory::Usb,
+                name,
+                usb.vendor_name.clone().unwrap_or_else(unknown),
+                usb.product_name.clone().unwrap_or_else(unknown),
+            )
+            .detail("Vendor ID", &usb.vendor_id)
+            .detail("Product ID", &usb.product_id)
+            .detail("USB Version", usb.usb_version.clone().unwrap_or_else(unknown));
+            if let Some(class) = &usb.device_class {
+                item = item.detail("Class", class);
+            }
+            if let Some(speed) = usb.speed_mbps {
+                item = item.detail("Speed", format!("{} Mbit/s", speed));
+            }
+            if let Some(port_path) = &usb.port_path {
+                item = item.detail("Port", port_path);
+            }
+            items.push(item);
+        }
+
+        for (i, input) in report.input.iter().enumerate() {
+            let mut item = DeviceItem::new(
+                format!("input_{}", i),
+                DeviceCategory::Input,
+                input.name.clone(),
+                unknown(),
+                input.name.clone(),
+            )
+            .detail("Type", &input.device_type)
+            .detail("Bus", &input.bus)
+            .detail("Device ID", &input.device_id)
+            .detail("Driver", input.driver.clone().unwrap_or_else(|| "Not loaded".to_string()));
+            if let Some(driver) = &input.userspace_driver {
+                item = item.detail("Userspace Driver", driver);
+            }
+            items.push(item);
+        }
+
+        if let Some(peripherals) = &report.peripherals {
+            for (i, printer) in peripherals.printers.iter().enumerate() {
+                items.push(
+                    DeviceItem::new(
+                        format!("printer_{}", i),
+                        DeviceCategory::Usb,
+                        printer.make_and_model.clone(),
+                        unknown(),
+                        printer.make_and_model.clone(),
+                    )
+                    .detail("Type", "printer")
+                    .detail("Connection", &printer.connection)
+                    .detail("Driverless", if printer.driverless { "yes" } else { "no" }),
+                );
+            }
+            for (i, scanner) in peripherals.scanners.iter().enumerate() {
+                items.push(
+                    DeviceItem::new(
+                        format!("scanner_{}", i),
+                        DeviceCategory::Usb,
+                        format!("{} {}", scanner.vendor, scanner.model),
+                        scanner.vendor.clone(),
+                        scanner.model.clone(),
+                    )
+                    .detail("Type", scanner.device_type.as_deref().unwrap_or("scanner"))
+                    .detail("Connection", &scanner.connection)
+                    .detail("SANE Backend", &scanner.backend),
+                );
+            }
+        }
+
+        if !report.sensors.is_empty() {
+            let chips: Vec<&str> = report.sensors.iter().map(|chip| chip.name.as_str()).collect();
+            let mut item = DeviceItem::new(
+                "sensors".to_string(),
+                DeviceCategory::System,
+                "Hardware Sensors".to_string(),
+                "System".to_string(),
+                chips.join(", "),
+            );
+            for chip in &report.sensors {
+                item = item.detail(
+                    &chip.name,
+                    format!(
+                        "{} temperature, {} fan, {} voltage ({})",
+                        chip.temperatures,
+                        chip.fans,
+                        chip.voltages,
+                        chip.driver.as_deref().unwrap_or("no driver")
+                    ),
+                );
+            }
+            if report.sensors.iter().all(|chip| chip.fans == 0) {
+                item.recommendations.push(
+                    "No fan sensors found; the motherboard's sensor chip may need a driver such \
+                     as nct6775 or it87, which `sensors-detect` can suggest"
+                        .to_string(),
+                );
+            }
+            items.push(item);
+        }
+
+        Self { items }
+    }
+
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    /// Devices grouped by category, in category order
+    pub fn by_category(&self) -> BTreeMap<DeviceCategory, Vec<&DeviceItem>> {
+        let mut groups: BTreeMap<DeviceCategory, Vec<&DeviceItem>> = BTreeMap::new();
+        for item in &self.items {
+            groups.entry(item.category).or_default().push(item);
+        }
+        groups
+    }
+
+    /// Number of devices showing a badge
+    pub fn count_with_badge(&self, badge: CompatibilityBadge) -> usize {
+        self.items.iter().filter(|item| item.badge == badge).count()
+    }
+}
+
+/// State of a detection run, as shown by progress indicators
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub enum ProgressState {
+    Idle,
+    Running { fraction: f64, message: String },
+    Complete { device_count: usize },
+    Failed { message: String },
+}
+
+impl ProgressState {
+    /// Progress bar fraction between 0.0 and 1.0
+    pub fn fraction(&self) -> f64 {
+        match self {
+            Self::Idle | Self::Failed { .. } => 0.0,
+            Self::Running { fraction, .. } => fraction.clamp(0.0, 1.0),
+            Self::Complete { .. } => 1.0,
+        }
+    }
+
+    pub fn is_running(&self) -> bool {
+        matches!(self, Self::Running { .. })
+    }
+
+    /// Status line shown next to the progress bar
+    pub fn status_text(&self) -> String {
+        match self {
+            Self::Idle => "Ready".to_string(),
+            Self::Running { message, .. } => message.clone(),
+            Self::Complete { device_count } => {
+                format!("Detection complete - {} devices found", device_count)
+            }
+            Self::Failed { message } => format!("Detection failed: {}", message),
+        }
+    }
+}
+
+/// State of a single detection tool
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum ToolState {
+    Pending,
+    Running,
+    Complete,
+    Error,
+}
+
+impl ToolState {
+    /// Stable identifier, e.g. for QML property bindings
+    pub fn key(&self) -> &'static str {
+        match self {
+            Self::Pending => "pending",
+            Self::Running => "running",
+            Self::Complete => "complete",
+            Self::Error => "error",
+        }
+    }
+
+    pub fn display_text(&self) -> &'static str {
+        match self {
+            Self::Pending => "Pending",
+            Self::Running => "Running",
+            Self::Complete => "Complete",
+            Self::Error => "Error",
+        }
+    }
+
+    /// Freedesktop icon name
+    pub fn icon_name(&self) -> &'static str {
+        match self {
+            Self::Pending | Self::Running => "emblem-synchronizing-symbolic",
+            Self::Complete => "emblem-ok-symbolic",
+            Self::Error => "emblem-important-symbolic",
+        }
+    }
+}
+
+/// An export format offered by the export dialog
+#[derive(Debug, Clone, Serialize)]
+pub struct ExportOption {
+    #[serde(skip)]
+    pub format: OutputFormat,
+    pub label: &'static str,
+    pub extension: &'static str,
+    pub recommended: bool,
+}
+
+/// Export formats in the order they are offered
+pub fn export_options() -> Vec<ExportOption> {
+    FORMATS
+        .iter()
+        .map(|spec| ExportOption {
+            format: spec.format,
+            label: spec.label,
+            extension: spec.extension,
+            recommended: spec.format == OutputFormat::Yaml,
+        })
+        .collect()
+}
+
+const MIB: f64 = 1024.0 * 1024.0;
+
+fn gigabytes(bytes: u64) -> String {
+    format!("{:.1} GB", bytes as f64 / (MIB * 1024.0))
+}
+
+fn microcode_summary(microcode: &MicrocodeInfo) -> String {
+    match (microcode.status, microcode.installed_revision, microcode.latest_known_revision) {
+        (MicrocodeStatus::RebootRequired, Some(installed), _) => {
+            format!("{:#x} ({:#x} installed, reboot required)", microcode.revision, installed)
+        }
+        (MicrocodeStatus::Outdated, _, Some(latest)) => {
+            format!("{:#x} (outdated, {:#x} available)", microcode.revision, latest)
+        }
+        (MicrocodeStatus::Current, _, _) => format!("{:#x} (up to date)", microcode.revision),
+        _ => format!("{:#x}", microcode.revision),
+    }
+}
+
+fn firmware_details(mut item: DeviceItem, firmware: &FirmwareInfo) -> DeviceItem {
+    let bios: Vec<&str> = [&firmware.bios_vendor, &firmware.bios_version]
+        .into_iter()
+        .filter_map(|value| value.as_deref())
+        .collect();
+    if !bios.is_empty() {
+        let mut bios = bios.join(" ");
+        if let Some(date) = &firmware.bios_date {
+            bios.push_str(&format!(" ({})", date));
+        }
+        item = item.detail("BIOS", bios);
+    }
+    let boot_mode = match (firmware.boot_mode, firmware.secure_boot) {
+        (BootMode::Legacy, _) => "Legacy BIOS".to_string(),
+        (BootMode::Uefi, Some(enabled)) => {
+            format!("UEFI, Secure Boot {}", if enabled { "enabled" } else { "disabled" })
+        }
+        (BootMode::Uefi, None) => "UEFI".to_string(),
+    };
+    item = item.detail("Boot Mode", boot_mode);
+    if firmware.tpm_present {
+        item = item.detail("TPM", firmware.tpm_version.as_deref().unwrap_or("present"));
+    }
+    item
+}
+
+fn pcie_details(mut item: DeviceItem, pcie: &PcieDeviceLink) -> DeviceItem {
+    item = item.detail("PCIe Link", format!("{} x{}", pcie.link.speed, pcie.link.width));
+    if let Some(bar) = &pcie.resizable_bar {
+        item = item.detail("Resizable BAR", if bar.is_enabled() { "Enabled" } else { "Disabled" });
+    }
+    item
+}
+
+fn unknown() -> String {
+    "Unknown".to_string()
+}
+
+fn apply_driver(mut item: DeviceItem, has_driver: bool, recommendation: &str) -> DeviceItem {
+    if !has_driver {
+        item.badge = CompatibilityBadge::RequiresDriver;
+        item.recommendations.push(recommendation.to_string());
+    }
+    item
+}
+
+/// Use the kernel support verdict for the device, if the report has one
+fn apply_kernel_support(mut item: DeviceItem, pci_id: &str, report: &HardwareReport) -> DeviceItem {
+    let support = report.kernel_support.as_ref().and_then(|kernel| {
+        kernel
+            .device_support_details
+            .iter()
+            .find(|device| device.device_id.eq_ignore_ascii_case(pci_id))
+    });
+    if let Some(support) = support {
+        if item.badge == CompatibilityBadge::Supported {
+            item.badge = CompatibilityBadge::from_support_status(&support.support_status);
+        }
+        if let Some(notes) = &support.notes {
+            item.recommendations.push(notes.clone());
+        }
+    }
+    item
+}
+
+/// Downgrade devices limited to a legacy driver on a too-new kernel
+fn apply_lifecycle(
+    mut item: DeviceItem,
+    device_id: Option<&str>,
+    notices: &[LifecycleNotice],
+) -> DeviceItem {
+    let notice = notices.iter().find(|notice| match (device_id, &notice.device_id) {
+        (Some(id), Some(notice_id)) => id.eq_ignore_ascii_case(notice_id),
+        (None, None) => item.category == DeviceCategory::Processor,
+        _ => false,
+    });
+    if let Some(notice) = notice {
+        item = item.detail("Released", notice.release_year.to_string());
+        if let Some(driver) = &notice.legacy_driver {
+            item = item.detail("Legacy Driver", driver);
+        }
+        if let Some(warning) = &notice.warning {
+            if item.badge == CompatibilityBadge::Supported {
+                item.badge = CompatibilityBadge::PartialSupport;
+            }
+            item.recommendations.push(warning.clone());
+        }
+    }
+    item
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hardware::{
+        CoexistenceQuirk, DeviceCompatibility, GraphicsDevice, KernelCompatibilityInfo, RadioState,
+        SensorChip,
+    };
+
+    fn report() -> HardwareReport {
+        serde_json::from_value(serde_json::json!({
+            "metadata": {
+                "version": "1.0.0",
+                "generated_at": "2024-01-01T00:00:00Z",
+                "privacy_level": "Basic",
+                "tools_used": [],
+                "anonymized_system_id": "test",
+            },
+            "system": {
+                "anonymized_hostname": "test",
+                "kernel_version": "6.10.0",
+                "distribution": "Fedora 40",
+                "architecture": "x86_64",
+                "boot_time": null,
+            },
+            "cpu": null,
+            "memory": null,
+            "storage": [],
+            "graphics": [],
+            "network": [],
+            "usb": [],
+            "audio": [],
+            "kernel_support": null,
+        }))
+        .unwrap()
+    }
+
+    fn gpu(pci_id: &str, driver: Option<&str>) -> GraphicsDevice {
+        GraphicsDevice {
+            vendor: "NVIDIA".to_string(),
+            model: "GeForce".to_string(),
+            driver: driver.map(str::to_string),
+            memory_bytes: None,
+            pci_id: pci_id.to_string(),
+            displays: None,
+            nvidia: None,
+        }
+    }
+
+    #[test]
+    fn test_device_list_order_and_badges() {
+        let mut report = report();
+        report.graphics.push(gpu("10de:1b80", Some("nouveau")));
+        report.graphics.push(gpu("10de:2484", None));
+
+        let list = DeviceList::from_report(&report);
+        let ids: Vec<_> = list.items.iter().map(|item| item.id.as_str()).collect();
+        assert_eq!(ids, vec!["system", "gpu_0", "gpu_1"]);
+        assert_eq!(list.items[2].badge, CompatibilityBadge::RequiresDriver);
+        assert_eq!(list.count_with_badge(CompatibilityBadge::Supported), 2);
+        assert_eq!(list.by_category()[&DeviceCategory::Graphics].len(), 2);
+        assert!(list.items[0].detail_lines().contains(&"Kernel: 6.10.0".to_string()));
+    }
+
+    #[test]
+    fn test_kernel_support_and_lifecycle_badges() {
+        let mut report = report();
+        report.graphics.push(gpu("10de:1180", Some("nvidia")));
+        report.graphics.push(gpu("1002:73bf", Some("amdgpu")));
+        report.lifecycle_notices.push(LifecycleNotice {
+            generation: "NVIDIA Kepler (GK1xx)".to_string(),
+            device_id: Some("10de:1180".to_string()),
+            release_year: 2012,
+            age_years: 12,
+            legacy_driver: Some("470.xx".to_string()),
+            warning: Some("470.xx does not support kernels newer than 6.8".to_string()),
+        });
+        report.kernel_support = Some(KernelCompatibilityInfo {
+            kernel_version: "6.10.0".to_string(),
+            total_devices_detected: 2,
+            supported_devices: 1,
+            unsupported_devices: 0,
+            experimental_devices: 1,
+            device_support_details: vec![DeviceCompatibility {
+                device_id: "1002:73BF".to_string(),
+                device_name: "Navi 21".to_string(),
+                support_status: "experimental".to_string(),
+                driver_module: "amdgpu".to_string(),
+                since_kernel_version: None,
+                config_dependencies: vec![],
+                notes: None,
+            }],
+            missing_modules: vec![],
+            config_recommendations: vec![],
+            module_parameters: Vec::new(),
+            blacklisted_modules: Vec::new(),
+            dkms_modules: Vec::new(),
+        });
+
+        let list = DeviceList::from_report(&report);
+        let kepler = &list.items[1];
+        assert_eq!(kepler.badge, CompatibilityBadge::PartialSupport);
+        assert!(kepler.recommendations[0].contains("6.8"));
+        assert!(kepler.details.contains(&("Legacy Driver".to_string(), "470.xx".to_string())));
+        assert_eq!(list.items[2].badge, CompatibilityBadge::PartialSupport);
+    }
+
+    #[test]
+    fn test_blocked_radio_gets_unblock_steps() {
+        let mut report = report();
+        report.radios.push(RadioState {
+            kind: "bluetooth".to_string(),
+            name: "hci0".to_string(),
+            device_id: Some("8087:0029".to_string()),
+            driver: Some("btusb".to_string()),
+            soft_blocked: true,
+            hard_blocked: false,
+            coexistence: Some(CoexistenceQuirk {
+                chip: "Intel Wi-Fi 6 AX200".to_string(),
+                note: "connect Wi-Fi on 5 GHz".to_string(),
+            }),
+        });
+
+        let list = DeviceList::from_report(&report);
+        let radio = list.items.iter().find(|item| item.id == "radio_0").unwrap();
+        assert_eq!(radio.category, DeviceCategory::Network);
+        assert_eq!(radio.badge, CompatibilityBadge::PartialSupport);
+        assert!(radio.recommendations[0].contains("rfkill unblock bluetooth"));
+        assert!(radio.recommendations[1].starts_with("Intel Wi-Fi 6 AX200"));
+    }
+
+    #[test]
+    fn test_sensors_without_fans_suggest_driver() {
+        let mut report = report();
+        report.sensors.push(SensorChip {
+            name: "k10temp".to_string(),
+            bus: Some("pci".to_string()),
+            driver: Some("k10temp".to_string()),
+            temperatures: 1,
+            fans: 0,
+            voltages: 0,
+        });
+
+        let list = DeviceList::from_report(&report);
+        let sensors = list.items.iter().find(|item| item.id == "sensors").unwrap();
+        assert_eq!(sensors.category, DeviceCategory::System);
+        assert!(sensors
+            .detail_lines()
+            .contains(&"k10temp: 1 temperature, 0 fan, 0 voltage (k10temp)".to_string()));
+        assert!(sensors.recommendations[0].contains("nct6775"));
+    }
+
+    #[test]
+    fn test_progress_state() {
+        let running = ProgressState::Running { fraction: 1.5, message: "lspci".to_string() };
+        assert_eq!(running.fraction(), 1.0);
+        assert!(running.is_running());
+        assert_eq!(
+            ProgressState::Complete { device_count: 3 }.status_text(),
+            "Detection complete - 3 devices found"
+        );
+        assert_eq!(ProgressState::Idle.fraction(), 0.0);
+    }
+
+    #[test]
+    fn test_export_options() {
+        let options = export_options();
+        let recommended: Vec<_> = options.iter().filter(|option| option.recommended).collect();
+        assert_eq!(recommended.len(), 1);
+        assert_eq!(recommended[0].format.to_string(), "yaml");
+        assert_eq!(options.last().unwrap().extension, "xml");
+    }
+}
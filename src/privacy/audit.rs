@@ -0,0 +1,250 @@
+//! Privacy audit of a generated report
+//!
+//! Lists what anonymization did to a report before it is shared: which
+//! fields hold digests instead of identifiers, which raw identifiers the
+//! detection tools saw but the report leaves out, and which remaining
+//! values could still narrow down the machine or its owner. The audit walks
+//! every string in the serialized report, so fields added later are covered
+//! without changes here.
+
+use super::{FULL_DIGEST_LENGTH, MIN_DIGEST_LENGTH};
+use crate::errors::{LxHwError, Result};
+use crate::hardware::notes::find_personal_data;
+use crate::hardware::{HardwareReport, PrivacyLevel};
+use regex::Regex;
+use serde::Serialize;
+use serde_json::Value;
+use std::sync::OnceLock;
+
+/// Report fields holding anonymized identifiers
+const IDENTIFIER_FIELDS: &[&str] =
+    &["anonymized_system_id", "anonymized_hostname", "anonymized_serial", "anonymized_mac"];
+
+/// Raw identifiers each tool prints that never make it into a report
+const DISCARDED_IDENTIFIERS: &[(&str, &[&str])] = &[
+    (
+        "dmidecode",
+        &[
+            "system serial number",
+            "system UUID",
+            "baseboard serial number",
+            "chassis serial number and asset tag",
+            "memory module serial numbers",
+        ],
+    ),
+    ("lshw", &["system and board serial numbers", "network interface IP addresses"]),
+    ("inxi", &["user name", "network interface IP addresses"]),
+    ("nvme", &["namespace EUI-64 and NGUID"]),
+    ("smartctl", &["drive World Wide Name"]),
+    ("lsblk", &["device mapper names", "filesystem labels", "mount points"]),
+    ("nvidia-smi", &["names of programs using the GPU"]),
+    ("iw", &["connected network name (SSID)", "interface names"]),
+    ("lpinfo", &["printer serial numbers", "printer network addresses, UUIDs and locations"]),
+    ("scanimage", &["scanner serial numbers", "scanner network addresses"]),
+    ("fwupd", &["device GUIDs and instance IDs"]),
+];
+
+/// Hostnames under common local domains
+fn hostname_regex() -> &'static Regex {
+    static HOSTNAME: OnceLock<Regex> = OnceLock::new();
+    HOSTNAME.get_or_init(|| {
+        Regex::new(r"(?i)\b[a-z0-9][a-z0-9-]*\.(?:local|lan|home|localdomain|internal|corp)\b")
+            .expect("Valid regex")
+    })
+}
+
+/// Result of auditing one report
+#[derive(Debug, Clone, Serialize)]
+pub struct PrivacyAudit {
+    pub privacy_level: PrivacyLevel,
+    /// Hex characters kept of each digest
+    pub digest_length: usize,
+    pub anonymized: Vec<AnonymizedField>,
+    pub discarded: Vec<DiscardedIdentifier>,
+    pub identifying: Vec<IdentifyingField>,
+}
+
+/// A field whose identifier was replaced
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct AnonymizedField {
+    pub field: String,
+    /// How the identifier was replaced
+    pub method: &'static str,
+}
+
+/// A raw identifier a tool reported that the report leaves out
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct DiscardedIdentifier {
+    pub tool: String,
+    pub identifier: &'static str,
+}
+
+/// A field that may still identify the machine or its owner
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct IdentifyingField {
+    pub field: String,
+    pub value: String,
+    pub reason: String,
+}
+
+impl PrivacyAudit {
+    /// Audit `report`
+    ///
+    /// `local` pairs raw values of the machine running the audit, such as
+    /// its hostname, with a description; fields containing one are reported.
+    pub fn run(report: &HardwareReport, local: &[(String, &str)]) -> Result<Self> {
+        let value = serde_json::to_value(report)
+            .map_err(|e| LxHwError::SerializationError(e.to_string()))?;
+        let digest_length = report.metadata.digest_length.unwrap_or(FULL_DIGEST_LENGTH);
+        let mut audit = Self {
+            privacy_level: report.metadata.privacy_level,
+            digest_length,
+            anonymized: Vec::new(),
+            discarded: report
+                .metadata
+                .tools_used
+                .iter()
+                .flat_map(|tool| {
+                    DISCARDED_IDENTIFIERS.iter().filter(move |(name, _)| name == tool).flat_map(
+                        move |(_, identifiers)| {
+                            identifiers.iter().map(move |identifier| DiscardedIdentifier {
+                                tool: tool.clone(),
+                                identifier,
+                            })
+                        },
+                    )
+                })
+                .collect(),
+            identifying: Vec::new(),
+        };
+        audit.visit(&value, String::new(), None, local);
+        Ok(audit)
+    }
+
+    fn visit(&mut self, value: &Value, path: String, key: Option<&str>, local: &[(String, &str)]) {
+        match value {
+            Value::Object(fields) => {
+                for (name, field) in fields {
+                    let child =
+                        if path.is_empty() { name.clone() } else { format!("{}.{}", path, name) };
+                    self.visit(field, child, Some(name), local);
+                }
+            }
+            Value::Array(items) => {
+                for (index, item) in items.iter().enumerate() {
+                    self.visit(item, format!("{}[{}]", path, index), key, local);
+                }
+            }
+            Value::String(text) if key.is_some_and(|key| IDENTIFIER_FIELDS.contains(&key)) => {
+                self.check_identifier(text, path)
+            }
+            Value::String(text) => self.check_text(text, path, local),
+            _ => {}
+        }
+    }
+
+    fn check_identifier(&mut self, value: &str, field: String) {
+        match identifier_method(value, self.digest_length) {
+            Some(method) => self.anonymized.push(AnonymizedField { field, method }),
+            None => self.identifying.push(IdentifyingField {
+                field,
+                value: value.to_string(),
+                reason: "identifier is not a digest".to_string(),
+            }),
+        }
+    }
+
+    fn check_text(&mut self, text: &str, field: String, local: &[(String, &str)]) {
+        let reason = local
+            .iter()
+            .find(|(raw, _)| raw.len() >= 3 && text.contains(raw.as_str()))
+            .map(|(_, description)| description.to_string())
+            .or_else(|| find_personal_data(text).map(str::to_string))
+            .or_else(|| hostname_regex().is_match(text).then(|| "hostname".to_string()))
+            .or_else(|| {
+                text.split_whitespace()
+                    .any(is_part_number)
+                    .then(|| "model or part number".to_string())
+            });
+        if let Some(reason) = reason {
+            self.identifying.push(IdentifyingField { field, value: text.to_string(), reason });
+        }
+    }
+}
+
+/// How an identifier value was anonymized, or None if it looks raw
+fn identifier_method(value: &str, digest_length: usize) -> Option<&'static str> {
+    let is_hex = |text: &str| text.chars().all(|c| c.is_ascii_hexdigit());
+    if value == "unknown" {
+        Some("not collected")
+    } else if value.len() >= MIN_DIGEST_LENGTH.min(digest_length) && is_hex(value) {
+        Some("HMAC-SHA256 digest")
+    } else if value.len() == 17 && value.split(':').all(|octet| octet.len() == 2 && is_hex(octet)) {
+        Some("device part hashed, vendor OUI kept")
+    } else {
+        None
+    }
+}
+
+/// Whether a word looks like a part or model number, e.g. "MZVL2512HCJQ-00B00":
+/// eight or more upper-case letters, digits and dashes, at least two of
+/// them letters and two digits
+fn is_part_number(word: &str) -> bool {
+    let word = word.trim_matches(|c: char| !c.is_ascii_alphanumeric());
+    word.len() >= 8
+        && word
+            .chars()
+            .all(|c| c.is_ascii_uppercase() || c.is_ascii_digit() || c == '-' || c == '_')
+        && word.chars().filter(char::is_ascii_uppercase).count() >= 2
+        && word.chars().filter(char::is_ascii_digit).count() >= 2
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hardware::examples::ExampleGenerator;
+    use crate::hardware::notes::UserNote;
+
+    #[test]
+    fn test_audit_sorts_fields() {
+        let mut report = ExampleGenerator::new(1).report(0);
+        report.metadata.tools_used = vec!["dmidecode".to_string(), "lspci".to_string()];
+        report.network[0].anonymized_mac = "3c:7c:3f:a1:b2:c3".to_string();
+        report.system.anonymized_hostname = "workstation".to_string();
+        report.storage[0].model = "SAMSUNG MZVL2512HCJQ-00B00".to_string();
+        report.user_notes.push(UserNote {
+            component: "system".to_string(),
+            note: "works on nas.local and on dev-box".to_string(),
+        });
+
+        let audit =
+            PrivacyAudit::run(&report, &[("dev-box".to_string(), "local hostname")]).unwrap();
+
+        let anonymized: Vec<&str> = audit.anonymized.iter().map(|f| f.field.as_str()).collect();
+        assert!(anonymized.contains(&"metadata.anonymized_system_id"));
+        assert!(anonymized.contains(&"storage[0].anonymized_serial"));
+        assert!(audit.anonymized.contains(&AnonymizedField {
+            field: "network[0].anonymized_mac".to_string(),
+            method: "device part hashed, vendor OUI kept",
+        }));
+        assert_eq!(audit.discarded.len(), 5);
+        assert!(audit.discarded.iter().all(|d| d.tool == "dmidecode"));
+
+        let reason = |field: &str| {
+            audit.identifying.iter().find(|f| f.field == field).map(|f| f.reason.as_str())
+        };
+        assert_eq!(reason("system.anonymized_hostname"), Some("identifier is not a digest"));
+        assert_eq!(reason("storage[0].model"), Some("model or part number"));
+        assert_eq!(reason("user_notes[0].note"), Some("local hostname"));
+        assert_eq!(reason("system.kernel_version"), None);
+    }
+
+    #[test]
+    fn test_part_numbers() {
+        assert!(is_part_number("MZVL2512HCJQ-00B00"));
+        assert!(is_part_number("(20XWCTO1WW)"));
+        assert!(!is_part_number("GP107M"));
+        assert!(!is_part_number("5.15.0-91-generic"));
+        assert!(!is_part_number("2024-01-01T00:00:00Z"));
+    }
+}
@@ -0,0 +1,149 @@
+//! Named salt contexts for audience-specific pseudonyms
+//!
+//! A report can go to more than one database, e.g. the public one and a
+//! company-internal one. Each destination is a named context ("public",
+//! "internal:acme") with its own persistent salt, so identifiers in a report
+//! can be re-keyed per audience at render or submit time without running
+//! detection again, and pseudonyms cannot be matched across audiences.
+
+use crate::errors::{LxHwError, Result};
+use ring::rand;
+use std::path::{Path, PathBuf};
+
+/// Length of a generated context salt in bytes
+const SALT_LEN: usize = 32;
+
+/// On-disk store of salt contexts, one hex-encoded salt file per context
+pub struct SaltContexts {
+    dir: PathBuf,
+}
+
+impl SaltContexts {
+    /// Store in `dir`
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    /// Store in the default directory
+    pub fn open_default() -> Self {
+        Self::new(Self::default_dir())
+    }
+
+    /// Default directory for context salts, overridable with
+    /// `LX_HW_SALT_CONTEXTS_DIR`
+    pub fn default_dir() -> PathBuf {
+        if let Some(dir) = std::env::var_os("LX_HW_SALT_CONTEXTS_DIR") {
+            return PathBuf::from(dir);
+        }
+        crate::telemetry::state_dir().join("salt-contexts")
+    }
+
+    /// Directory holding the salt files
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Salt of a context, generated and stored on first use
+    pub fn salt(&self, name: &str) -> Result<Vec<u8>> {
+        validate_name(name)?;
+        let path = self.dir.join(format!("{}.salt", name));
+        if let Ok(content) = std::fs::read_to_string(&path) {
+            return hex::decode(content.trim()).map_err(|e| {
+                LxHwError::PrivacyError(format!("Invalid salt in {}: {}", path.display(), e))
+            });
+        }
+
+        let mut salt = vec![0u8; SALT_LEN];
+        rand::SecureRandom::fill(&rand::SystemRandom::new(), &mut salt).map_err(|_| {
+            LxHwError::PrivacyError("Failed to generate cryptographic salt".to_string())
+        })?;
+        std::fs::create_dir_all(&self.dir)?;
+        write_private(&path, &hex::encode(&salt))?;
+        log::info!("Created salt context '{}' in {}", name, path.display());
+        Ok(salt)
+    }
+
+    /// Names of the contexts that have a stored salt, sorted
+    pub fn list(&self) -> Result<Vec<String>> {
+        let entries = match std::fs::read_dir(&self.dir) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e.into()),
+        };
+
+        let mut names = Vec::new();
+        for entry in entries {
+            let file_name = entry?.file_name();
+            if let Some(name) = file_name.to_str().and_then(|f| f.strip_suffix(".salt")) {
+                if validate_name(name).is_ok() {
+                    names.push(name.to_string());
+                }
+            }
+        }
+        names.sort();
+        Ok(names)
+    }
+}
+
+/// Check a context name: lowercase letters, digits, `-`, `_` and `:`
+/// separating a scope from its audience, as in "internal:acme"
+pub fn validate_name(name: &str) -> Result<()> {
+    let valid = !name.is_empty()
+        && name.len() <= 64
+        && !name.starts_with(':')
+        && !name.ends_with(':')
+        && name
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || matches!(c, '-' | '_' | ':'));
+    if valid {
+        Ok(())
+    } else {
+        Err(LxHwError::InvalidInput {
+            message: format!(
+                "Invalid salt context '{}': use lowercase letters, digits, '-', '_' and ':', \
+                 e.g. \"public\" or \"internal:acme\"",
+                name
+            ),
+        })
+    }
+}
+
+/// Write a file readable only by its owner
+fn write_private(path: &Path, content: &str) -> Result<()> {
+    use std::io::Write;
+    #[cfg(unix)]
+    use std::os::unix::fs::OpenOptionsExt;
+
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    options.mode(0o600);
+    options.open(path)?.write_all(content.as_bytes())?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_salt_is_created_once_per_context() {
+        let dir = tempfile::tempdir().unwrap();
+        let contexts = SaltContexts::new(dir.path());
+
+        let public = contexts.salt("public").unwrap();
+        assert_eq!(public.len(), SALT_LEN);
+        assert_eq!(contexts.salt("public").unwrap(), public);
+        assert_ne!(contexts.salt("internal:acme").unwrap(), public);
+        assert_eq!(contexts.list().unwrap(), vec!["internal:acme", "public"]);
+    }
+
+    #[test]
+    fn test_context_names() {
+        assert!(validate_name("public").is_ok());
+        assert!(validate_name("internal:acme-labs").is_ok());
+        for invalid in ["", "Public", "../public", "internal:", ":acme", "a b"] {
+            assert!(validate_name(invalid).is_err(), "{:?} accepted", invalid);
+        }
+    }
+}
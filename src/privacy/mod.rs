@@ -1,43 +1,210 @@
 //! Privacy and anonymization system for hardware data
+//!
+//! Identifiers are replaced by hex HMAC-SHA256 digests. Digests can be
+//! truncated to [`MIN_DIGEST_LENGTH`] or more characters to keep reports
+//! readable; see [`PrivacyManager::set_digest_length`] for the collision risk.
+//! The HMAC salt rotates, so pseudonyms change between runs, unless it is
+//! derived from a [`PseudonymKey`] the user keeps; see
+//! [`PrivacyManager::use_pseudonym_key`].
+//! At the strict level, counts get differential privacy noise; see [`noise`].
 
 use crate::errors::{LxHwError, Result};
-use crate::hardware::PrivacyLevel;
+use crate::hardware::{HardwareReport, PrivacyLevel};
+use ::rand::rngs::StdRng;
+use ::rand::SeedableRng;
 use chrono::{DateTime, Duration, Utc};
-use ring::{hmac, rand};
+use ring::{digest, hmac, pbkdf2, rand};
+use std::collections::BTreeMap;
+use std::num::NonZeroU32;
+use std::path::PathBuf;
+
+pub mod audit;
+pub mod contexts;
+pub mod noise;
+pub mod raw;
+pub mod redact;
+pub mod scrub;
+pub mod testvectors;
+
+/// Length of an untruncated hex HMAC-SHA256 digest
+pub const FULL_DIGEST_LENGTH: usize = 64;
+
+/// Shortest digest length, 48 bits
+pub const MIN_DIGEST_LENGTH: usize = 12;
+
+/// Shortest digest length at the strict privacy level, whose validation
+/// requires 16-character identifiers
+pub const MIN_STRICT_DIGEST_LENGTH: usize = 16;
+
+/// PBKDF2 salt for pseudonym keys; changing it changes every stable pseudonym
+const PSEUDONYM_KDF_SALT: &[u8] = b"lx-hw-db pseudonym key v1";
+
+/// PBKDF2-HMAC-SHA256 rounds for pseudonym keys, to slow down guessing
+/// weak passphrases from published pseudonyms
+const PSEUDONYM_KDF_ITERATIONS: u32 = 100_000;
 
 /// Privacy manager for handling anonymization of hardware data
 pub struct PrivacyManager {
     privacy_level: PrivacyLevel,
     salt_generator: SaltGenerator,
+    /// Keys of named salt contexts, one per audience
+    salt_contexts: BTreeMap<String, hmac::Key>,
+    /// Hex characters kept of each digest
+    digest_length: usize,
+    /// Source of the noise added to counts at the strict level
+    noise_rng: StdRng,
 }
 
 /// Salt generation for time-rotating anonymization keys
 pub struct SaltGenerator {
     current_salt: Vec<u8>,
     salt_generated_at: DateTime<Utc>,
-    rotation_period: Duration,
+    /// None for a salt that never rotates
+    rotation_period: Option<Duration>,
+}
+
+/// User-held secret that stable pseudonyms are derived from
+#[derive(Debug, Clone)]
+pub enum PseudonymKey {
+    Passphrase(String),
+    /// File whose contents are the secret; trailing whitespace is ignored
+    Keyfile(PathBuf),
+}
+
+impl PseudonymKey {
+    /// HMAC salt derived from the secret
+    pub fn derive_salt(&self) -> Result<Vec<u8>> {
+        let secret = match self {
+            Self::Passphrase(passphrase) => passphrase.as_bytes().to_vec(),
+            Self::Keyfile(path) => {
+                let mut contents = std::fs::read(path).map_err(|e| {
+                    LxHwError::PrivacyError(format!(
+                        "Cannot read pseudonym key file {}: {}",
+                        path.display(),
+                        e
+                    ))
+                })?;
+                while contents.last().is_some_and(u8::is_ascii_whitespace) {
+                    contents.pop();
+                }
+                contents
+            }
+        };
+        if secret.is_empty() {
+            return Err(LxHwError::PrivacyError("Pseudonym key is empty".to_string()));
+        }
+
+        let mut salt = vec![0u8; 32];
+        let iterations = NonZeroU32::new(PSEUDONYM_KDF_ITERATIONS).expect("non-zero iterations");
+        pbkdf2::derive(
+            pbkdf2::PBKDF2_HMAC_SHA256,
+            iterations,
+            PSEUDONYM_KDF_SALT,
+            &secret,
+            &mut salt,
+        );
+        Ok(salt)
+    }
 }
 
 impl PrivacyManager {
     /// Create a new privacy manager with the specified privacy level
     pub fn new(privacy_level: PrivacyLevel) -> Result<Self> {
-        let rotation_period = match privacy_level {
-            PrivacyLevel::Basic => Duration::hours(24),
-            PrivacyLevel::Enhanced => Duration::hours(12),
-            PrivacyLevel::Strict => Duration::hours(1),
+        let rotation_period = Self::rotation_period(privacy_level);
+        let salt_generator = SaltGenerator::new(rotation_period)?;
+
+        Ok(Self {
+            privacy_level,
+            salt_generator,
+            salt_contexts: BTreeMap::new(),
+            digest_length: FULL_DIGEST_LENGTH,
+            noise_rng: StdRng::from_entropy(),
+        })
+    }
+
+    /// Create a privacy manager with a fixed salt, for reproducible output
+    pub fn with_salt(privacy_level: PrivacyLevel, salt: &[u8]) -> Self {
+        let rotation_period = Self::rotation_period(privacy_level);
+        Self {
+            privacy_level,
+            salt_generator: SaltGenerator::with_salt(rotation_period, salt),
+            salt_contexts: BTreeMap::new(),
+            digest_length: FULL_DIGEST_LENGTH,
+            noise_rng: StdRng::from_seed(
+                digest::digest(&digest::SHA256, salt).as_ref().try_into().expect("32-byte digest"),
+            ),
+        }
+    }
+
+    /// Derive identifiers from `key` instead of the rotating salt
+    ///
+    /// The same identifier then always maps to the same pseudonym, across
+    /// runs and submissions, so one system's reports can be followed across
+    /// kernel versions. Anyone holding the key can link those reports and
+    /// test guesses of the original identifiers against them, so it should be
+    /// kept like a password. Counts are still noised at the strict level.
+    pub fn use_pseudonym_key(&mut self, key: &PseudonymKey) -> Result<()> {
+        self.salt_generator = SaltGenerator::fixed(&key.derive_salt()?);
+        Ok(())
+    }
+
+    /// Truncate anonymized identifiers to `length` hex characters
+    ///
+    /// Shorter digests are easier to read and diff but more likely to
+    /// collide: two different identifiers share a digest of n bits with
+    /// probability 2^-n, and among k identifiers some pair collides with
+    /// probability about k²/2^(n+1). At 12 characters (48 bits) that is about
+    /// 0.2% for a million systems and 16% for ten million; at 16 characters
+    /// (64 bits) it stays below one in a million up to about six million
+    /// systems. A collision makes two systems look like one in the database,
+    /// it does not expose either identifier.
+    pub fn set_digest_length(&mut self, length: usize) -> Result<()> {
+        let min = match self.privacy_level {
+            PrivacyLevel::Strict => MIN_STRICT_DIGEST_LENGTH,
+            _ => MIN_DIGEST_LENGTH,
         };
+        if !(min..=FULL_DIGEST_LENGTH).contains(&length) {
+            return Err(LxHwError::PrivacyError(format!(
+                "Digest length must be between {} and {} at {:?} privacy, got {}",
+                min, FULL_DIGEST_LENGTH, self.privacy_level, length
+            )));
+        }
+        self.digest_length = length;
+        Ok(())
+    }
 
-        let salt_generator = SaltGenerator::new(rotation_period)?;
+    /// Hex characters kept of each digest
+    pub fn digest_length(&self) -> usize {
+        self.digest_length
+    }
 
-        Ok(Self { privacy_level, salt_generator })
+    /// Digest length to record in report metadata; None when untruncated
+    pub fn truncated_digest_length(&self) -> Option<usize> {
+        (self.digest_length != FULL_DIGEST_LENGTH).then_some(self.digest_length)
     }
 
     /// Anonymize a hardware identifier using HMAC-SHA256
     pub fn anonymize_identifier(&mut self, identifier: &str) -> Result<String> {
+        let digest_length = self.digest_length;
         let salt = self.salt_generator.get_current_salt()?;
         let key = hmac::Key::new(hmac::HMAC_SHA256, salt);
-        let signature = hmac::sign(&key, identifier.as_bytes());
-        Ok(hex::encode(signature.as_ref()))
+        Ok(digest(&key, identifier, digest_length))
+    }
+
+    /// Release a count, with differential privacy noise at the strict level
+    ///
+    /// `sensitivity` is how much one device can change the count, 1 for a
+    /// plain number of devices. Other levels return `value` unchanged.
+    pub fn anonymize_count(&mut self, value: u32, sensitivity: u32) -> u32 {
+        match self.privacy_level {
+            PrivacyLevel::Strict => noise::noisy_count(
+                &mut self.noise_rng,
+                value,
+                noise::STRICT_COUNT_EPSILON,
+                sensitivity,
+            ),
+            _ => value,
+        }
     }
 
     /// Anonymize a MAC address by preserving OUI but hashing device part
@@ -67,12 +234,107 @@ impl PrivacyManager {
         ))
     }
 
+    /// Register a named salt context, e.g. "public" or "internal:acme"
+    ///
+    /// Context salts do not rotate: the same identifier maps to the same
+    /// pseudonym within a context and to unrelated ones across contexts.
+    pub fn add_salt_context(&mut self, name: &str, salt: &[u8]) -> Result<()> {
+        contexts::validate_name(name)?;
+        self.salt_contexts.insert(name.to_string(), hmac::Key::new(hmac::HMAC_SHA256, salt));
+        Ok(())
+    }
+
+    /// Register a named salt context from a store, creating its salt if needed
+    pub fn load_salt_context(&mut self, store: &contexts::SaltContexts, name: &str) -> Result<()> {
+        let salt = store.salt(name)?;
+        self.add_salt_context(name, &salt)
+    }
+
+    /// Names of the registered salt contexts
+    pub fn salt_contexts(&self) -> impl Iterator<Item = &str> {
+        self.salt_contexts.keys().map(String::as_str)
+    }
+
+    /// Anonymize an identifier under a named salt context
+    pub fn anonymize_for_context(&self, context: &str, identifier: &str) -> Result<String> {
+        let key = self.context_key(context)?;
+        Ok(digest(key, identifier, self.digest_length))
+    }
+
+    /// Re-key a report's pseudonyms for a named salt context
+    ///
+    /// Identifiers were already anonymized at detection time; they are hashed
+    /// again under the context's key, so one detection run can be rendered
+    /// for several audiences. Reports already re-keyed for a context are
+    /// refused, since re-keying twice would not match other reports of the
+    /// same context. Re-keyed values keep the report's digest length.
+    pub fn apply_salt_context(&self, report: &mut HardwareReport, context: &str) -> Result<()> {
+        let key = self.context_key(context)?;
+        if let Some(applied) = &report.metadata.salt_context {
+            return Err(LxHwError::PrivacyError(format!(
+                "Report was already pseudonymized for salt context '{}'; use the original report",
+                applied
+            )));
+        }
+
+        let length = report.metadata.digest_length.unwrap_or(FULL_DIGEST_LENGTH);
+        let rekey = |value: &mut String| *value = digest(key, value, length);
+        rekey(&mut report.metadata.anonymized_system_id);
+        rekey(&mut report.system.anonymized_hostname);
+        for storage in &mut report.storage {
+            rekey(&mut storage.anonymized_serial);
+        }
+        for network in report.network.iter_mut().filter(|n| n.anonymized_mac != "unknown") {
+            match rekey_mac(&network.anonymized_mac, key) {
+                Some(mac) => network.anonymized_mac = mac,
+                None => rekey(&mut network.anonymized_mac),
+            }
+        }
+        report.metadata.salt_context = Some(context.to_string());
+        Ok(())
+    }
+
+    fn context_key(&self, context: &str) -> Result<&hmac::Key> {
+        self.salt_contexts
+            .get(context)
+            .ok_or_else(|| LxHwError::PrivacyError(format!("Unknown salt context '{}'", context)))
+    }
+
+    /// How often the salt rotates at a privacy level
+    fn rotation_period(privacy_level: PrivacyLevel) -> Duration {
+        match privacy_level {
+            PrivacyLevel::Basic => Duration::hours(24),
+            PrivacyLevel::Enhanced => Duration::hours(12),
+            PrivacyLevel::Strict => Duration::hours(1),
+        }
+    }
+
     /// Get the current privacy level
     pub fn privacy_level(&self) -> PrivacyLevel {
         self.privacy_level
     }
 }
 
+/// Hex HMAC-SHA256 digest of `data`, truncated to `length` characters
+pub(crate) fn digest(key: &hmac::Key, data: &str, length: usize) -> String {
+    let mut digest = hex::encode(hmac::sign(key, data.as_bytes()).as_ref());
+    digest.truncate(length);
+    digest
+}
+
+/// Re-hash the device part of a MAC address under `key`, keeping the vendor OUI
+///
+/// Returns None for values that are not colon-separated MAC addresses.
+pub(crate) fn rekey_mac(mac: &str, key: &hmac::Key) -> Option<String> {
+    let octets: Vec<&str> = mac.split(':').collect();
+    if octets.len() != 6 || !octets.iter().all(|o| o.len() == 2) {
+        return None;
+    }
+
+    let device = hex::encode(hmac::sign(key, octets[3..].join(":").as_bytes()).as_ref());
+    Some(format!("{}:{}:{}:{}", octets[..3].join(":"), &device[0..2], &device[2..4], &device[4..6]))
+}
+
 impl SaltGenerator {
     /// Create a new salt generator with the specified rotation period
     pub fn new(rotation_period: Duration) -> Result<Self> {
@@ -82,13 +344,31 @@ impl SaltGenerator {
             LxHwError::PrivacyError("Failed to generate cryptographic salt".to_string())
         })?;
 
-        Ok(Self { current_salt: salt, salt_generated_at: Utc::now(), rotation_period })
+        Ok(Self {
+            current_salt: salt,
+            salt_generated_at: Utc::now(),
+            rotation_period: Some(rotation_period),
+        })
+    }
+
+    /// Create a salt generator starting from a known salt
+    pub fn with_salt(rotation_period: Duration, salt: &[u8]) -> Self {
+        Self {
+            current_salt: salt.to_vec(),
+            salt_generated_at: Utc::now(),
+            rotation_period: Some(rotation_period),
+        }
+    }
+
+    /// Create a salt generator whose salt never rotates
+    pub fn fixed(salt: &[u8]) -> Self {
+        Self { current_salt: salt.to_vec(), salt_generated_at: Utc::now(), rotation_period: None }
     }
 
     /// Get the current salt, rotating if necessary
     pub fn get_current_salt(&mut self) -> Result<&[u8]> {
         let now = Utc::now();
-        if now - self.salt_generated_at > self.rotation_period {
+        if self.rotation_period.is_some_and(|period| now - self.salt_generated_at > period) {
             self.rotate_salt()?;
         }
         Ok(&self.current_salt)
@@ -105,3 +385,110 @@ impl SaltGenerator {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hardware::examples::ExampleGenerator;
+
+    #[test]
+    fn test_salt_contexts_give_unlinkable_pseudonyms() {
+        let mut manager = PrivacyManager::with_salt(PrivacyLevel::Basic, b"detection");
+        manager.add_salt_context("public", b"public-salt").unwrap();
+        manager.add_salt_context("internal:acme", b"acme-salt").unwrap();
+        assert_eq!(manager.salt_contexts().collect::<Vec<_>>(), vec!["internal:acme", "public"]);
+
+        let report = ExampleGenerator::new(1).report(0);
+        let mut public = report.clone();
+        let mut internal = report.clone();
+        manager.apply_salt_context(&mut public, "public").unwrap();
+        manager.apply_salt_context(&mut internal, "internal:acme").unwrap();
+
+        assert_eq!(public.metadata.salt_context.as_deref(), Some("public"));
+        assert_ne!(public.metadata.anonymized_system_id, report.metadata.anonymized_system_id);
+        assert_ne!(public.metadata.anonymized_system_id, internal.metadata.anonymized_system_id);
+        assert_ne!(public.system.anonymized_hostname, internal.system.anonymized_hostname);
+        assert_eq!(
+            public.metadata.anonymized_system_id,
+            manager.anonymize_for_context("public", &report.metadata.anonymized_system_id).unwrap()
+        );
+        for (rekeyed, original) in public.network.iter().zip(&report.network) {
+            assert_ne!(rekeyed.anonymized_mac, original.anonymized_mac);
+        }
+
+        // MAC addresses keep their vendor OUI
+        let mut with_mac = report.clone();
+        with_mac.network[0].anonymized_mac = "3c:7c:3f:12:34:56".to_string();
+        manager.apply_salt_context(&mut with_mac, "public").unwrap();
+        assert!(with_mac.network[0].anonymized_mac.starts_with("3c:7c:3f:"));
+        assert_ne!(with_mac.network[0].anonymized_mac, "3c:7c:3f:12:34:56");
+
+        // Re-keying twice would break linkage within the context
+        assert!(manager.apply_salt_context(&mut public, "public").is_err());
+        assert!(manager.apply_salt_context(&mut report.clone(), "unknown").is_err());
+    }
+
+    #[test]
+    fn test_counts_are_noised_only_at_strict() {
+        let mut basic = PrivacyManager::with_salt(PrivacyLevel::Basic, b"salt");
+        assert!((0..100).all(|_| basic.anonymize_count(12, 1) == 12));
+
+        let mut strict = PrivacyManager::with_salt(PrivacyLevel::Strict, b"salt");
+        let counts: Vec<u32> = (0..100).map(|_| strict.anonymize_count(12, 1)).collect();
+        assert!(counts.iter().any(|&count| count != 12));
+
+        // A fixed salt makes the noise reproducible
+        let mut again = PrivacyManager::with_salt(PrivacyLevel::Strict, b"salt");
+        assert_eq!((0..100).map(|_| again.anonymize_count(12, 1)).collect::<Vec<_>>(), counts);
+    }
+
+    #[test]
+    fn test_truncated_digests() {
+        let mut full = PrivacyManager::with_salt(PrivacyLevel::Basic, b"salt");
+        let mut short = PrivacyManager::with_salt(PrivacyLevel::Basic, b"salt");
+        short.set_digest_length(12).unwrap();
+        assert_eq!(short.truncated_digest_length(), Some(12));
+        assert_eq!(full.truncated_digest_length(), None);
+
+        let id = full.anonymize_identifier("host").unwrap();
+        assert_eq!(id.len(), FULL_DIGEST_LENGTH);
+        assert_eq!(short.anonymize_identifier("host").unwrap(), id[..12]);
+        assert!(short.set_digest_length(8).is_err());
+        assert!(short.set_digest_length(65).is_err());
+
+        let mut strict = PrivacyManager::with_salt(PrivacyLevel::Strict, b"salt");
+        assert!(strict.set_digest_length(12).is_err());
+        strict.set_digest_length(MIN_STRICT_DIGEST_LENGTH).unwrap();
+
+        // Re-keying keeps the report's digest length
+        short.add_salt_context("public", b"public-salt").unwrap();
+        let mut report = ExampleGenerator::new(1).report(0);
+        report.metadata.digest_length = Some(12);
+        short.apply_salt_context(&mut report, "public").unwrap();
+        assert_eq!(report.metadata.anonymized_system_id.len(), 12);
+        assert_eq!(report.system.anonymized_hostname.len(), 12);
+    }
+
+    #[test]
+    fn test_pseudonym_key_gives_stable_identifiers() {
+        let passphrase = PseudonymKey::Passphrase("correct horse battery staple".to_string());
+        let mut first = PrivacyManager::new(PrivacyLevel::Strict).unwrap();
+        let mut second = PrivacyManager::new(PrivacyLevel::Strict).unwrap();
+        first.use_pseudonym_key(&passphrase).unwrap();
+        second.use_pseudonym_key(&passphrase).unwrap();
+        let id = first.anonymize_identifier("system-uuid").unwrap();
+        assert_eq!(second.anonymize_identifier("system-uuid").unwrap(), id);
+
+        // A key file holding the passphrase gives the same pseudonyms
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        std::io::Write::write_all(&mut file, b"correct horse battery staple\n").unwrap();
+        let mut from_file = PrivacyManager::new(PrivacyLevel::Strict).unwrap();
+        from_file.use_pseudonym_key(&PseudonymKey::Keyfile(file.path().to_path_buf())).unwrap();
+        assert_eq!(from_file.anonymize_identifier("system-uuid").unwrap(), id);
+
+        let mut other = PrivacyManager::new(PrivacyLevel::Strict).unwrap();
+        other.use_pseudonym_key(&PseudonymKey::Passphrase("another".to_string())).unwrap();
+        assert_ne!(other.anonymize_identifier("system-uuid").unwrap(), id);
+        assert!(other.use_pseudonym_key(&PseudonymKey::Passphrase(String::new())).is_err());
+    }
+}
@@ -0,0 +1,72 @@
+//! Differential privacy noise for counted quantities
+//!
+//! At the strict privacy level, counts in a report (devices per kernel
+//! support level, sensors per hwmon chip) are released with two-sided
+//! geometric noise, the discrete form of the Laplace mechanism: a count whose
+//! value one device can change by at most `sensitivity` is offset by k with
+//! probability proportional to exp(-ε|k|/sensitivity), which makes it
+//! ε-differentially private. The noise has mean zero, so sums and averages
+//! over many reports in the database stay accurate while a single report no
+//! longer tells exactly what one machine has.
+//!
+//! Noisy counts are clamped at zero; post-processing like this keeps the
+//! guarantee. Lists whose entries carry data, such as DIMMs or disks, are left
+//! alone, as noise there would mean inventing or dropping entries.
+
+use rand::Rng;
+
+/// Privacy budget spent on each count at the strict level
+pub const STRICT_COUNT_EPSILON: f64 = 1.0;
+
+/// Sample two-sided geometric noise for `epsilon` and `sensitivity`
+pub fn geometric_noise<R: Rng + ?Sized>(rng: &mut R, epsilon: f64, sensitivity: u32) -> i64 {
+    let alpha = (-epsilon / f64::from(sensitivity.max(1))).exp();
+    // The difference of two geometric variables with success probability
+    // 1 - alpha; gen() is in [0, 1), so 1 - gen() is never zero
+    let mut geometric = || ((1.0 - rng.gen::<f64>()).ln() / alpha.ln()).floor() as i64;
+    geometric() - geometric()
+}
+
+/// `value` with geometric noise added, clamped to the range of a count
+pub fn noisy_count<R: Rng + ?Sized>(
+    rng: &mut R,
+    value: u32,
+    epsilon: f64,
+    sensitivity: u32,
+) -> u32 {
+    let noisy = i64::from(value) + geometric_noise(rng, epsilon, sensitivity);
+    noisy.clamp(0, i64::from(u32::MAX)) as u32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    #[test]
+    fn test_noise_is_centered_and_scaled() {
+        let mut rng = StdRng::seed_from_u64(7);
+        let samples: Vec<i64> = (0..20_000).map(|_| geometric_noise(&mut rng, 1.0, 1)).collect();
+        let mean = samples.iter().sum::<i64>() as f64 / samples.len() as f64;
+        let mean_abs = samples.iter().map(|k| k.abs()).sum::<i64>() as f64 / samples.len() as f64;
+
+        assert!(mean.abs() < 0.05, "mean {}", mean);
+        // E|k| = 2α / (1 - α²) for α = e^-1
+        let alpha = (-1.0f64).exp();
+        let expected = 2.0 * alpha / (1.0 - alpha * alpha);
+        assert!((mean_abs - expected).abs() < 0.05, "mean |k| {}", mean_abs);
+
+        let wider = (0..20_000).map(|_| geometric_noise(&mut rng, 1.0, 4).abs()).sum::<i64>();
+        assert!(wider as f64 / 20_000.0 > 2.0 * mean_abs);
+    }
+
+    #[test]
+    fn test_noisy_count_stays_non_negative() {
+        let mut rng = StdRng::seed_from_u64(1);
+        // About half the draws are negative and clamp to zero
+        let zeros = (0..1000).filter(|_| noisy_count(&mut rng, 0, 0.1, 1) == 0).count();
+        assert!((400..700).contains(&zeros), "{} zeros", zeros);
+        assert_eq!(noisy_count(&mut rng, u32::MAX, 1000.0, 1), u32::MAX);
+    }
+}
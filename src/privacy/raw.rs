@@ -0,0 +1,209 @@
+//! Sanitizing raw tool output before it leaves the machine
+//!
+//! `detect --save-raw` keeps what each tool printed so maintainers can debug
+//! parsers. That text holds the identifiers the report replaces, so they are
+//! replaced here too, with the digests the report uses:
+//!
+//! - values of serial number, UUID, asset tag, label and similar fields, in
+//!   the `key: value` form of dmidecode and smartctl, the JSON of lshw and
+//!   nvme, the `KEY=value` lines of blkid and the `iSerial` lines of lsusb
+//!   (common placeholders are kept)
+//! - anything else shaped like a UUID
+//! - MAC addresses, keeping the vendor OUI
+//! - the hostname, when known
+//!
+//! IP and email addresses are removed.
+
+use super::PrivacyManager;
+use regex::{Captures, Regex};
+use std::sync::OnceLock;
+
+/// Replacement for removed text
+const REDACTED: &str = "[redacted]";
+
+/// Values firmware puts in unset identifier fields; kept, as parsers see them
+const PLACEHOLDERS: &[&str] = &[
+    "0",
+    "none",
+    "n/a",
+    "not specified",
+    "not present",
+    "not available",
+    "to be filled by o.e.m.",
+    "default string",
+    "system serial number",
+    "0123456789",
+    "unknown",
+];
+
+/// Key-value fields holding identifiers; group 1 is the key and separator
+fn identifier_field_regex() -> &'static Regex {
+    static REGEX: OnceLock<Regex> = OnceLock::new();
+    REGEX.get_or_init(|| {
+        Regex::new(
+            r#"(?im)(\b(?:serial[ _]?(?:number)?|(?:part|pt)?uuid|(?:part)?label|asset[ _]tag|wwn|eui64|nguid|sn)["']?[ \t]*[:=][ \t]*["']?)([^"'\s,][^"',\r\n]*)"#,
+        )
+        .expect("Valid regex")
+    })
+}
+
+/// lsusb descriptor lines: `iSerial    3 0123ABCD`
+fn usb_serial_regex() -> &'static Regex {
+    static REGEX: OnceLock<Regex> = OnceLock::new();
+    REGEX.get_or_init(|| Regex::new(r"(?m)^(\s*iSerial\s+\d+\s+)(\S.*)$").expect("Valid regex"))
+}
+
+fn uuid_regex() -> &'static Regex {
+    static REGEX: OnceLock<Regex> = OnceLock::new();
+    REGEX.get_or_init(|| {
+        Regex::new(r"(?i)\b[0-9a-f]{8}-[0-9a-f]{4}-[0-9a-f]{4}-[0-9a-f]{4}-[0-9a-f]{12}\b")
+            .expect("Valid regex")
+    })
+}
+
+fn mac_regex() -> &'static Regex {
+    static REGEX: OnceLock<Regex> = OnceLock::new();
+    REGEX
+        .get_or_init(|| Regex::new(r"(?i)\b[0-9a-f]{2}(?::[0-9a-f]{2}){5}\b").expect("Valid regex"))
+}
+
+fn address_regex() -> &'static Regex {
+    static REGEX: OnceLock<Regex> = OnceLock::new();
+    REGEX.get_or_init(|| {
+        Regex::new(
+            r"\b[A-Za-z0-9._%+-]+@[A-Za-z0-9.-]+\.[A-Za-z]{2,}\b|\b(?:\d{1,3}\.){3}\d{1,3}\b",
+        )
+        .expect("Valid regex")
+    })
+}
+
+/// Replaces identifiers in tool output with the digests of a privacy manager
+pub struct RawSanitizer<'a> {
+    manager: &'a mut PrivacyManager,
+    hostname: Option<String>,
+}
+
+impl<'a> RawSanitizer<'a> {
+    pub fn new(manager: &'a mut PrivacyManager) -> Self {
+        Self { manager, hostname: None }
+    }
+
+    /// Also replace this hostname wherever it appears
+    pub fn with_hostname(mut self, hostname: &str) -> Self {
+        let hostname = hostname.trim();
+        // Very short names would replace parts of ordinary words
+        if hostname.len() >= 3 && hostname != "localhost" {
+            self.hostname = Some(hostname.to_string());
+        }
+        self
+    }
+
+    /// `text` with identifiers replaced
+    pub fn sanitize(&mut self, text: &str) -> String {
+        let mut text = match self.hostname.clone() {
+            Some(hostname) => text.replace(hostname.as_str(), &self.digest(&hostname)),
+            None => text.to_string(),
+        };
+
+        for regex in [identifier_field_regex(), usb_serial_regex()] {
+            text = regex
+                .replace_all(&text, |caps: &Captures| {
+                    let value = caps[2].trim_end();
+                    let trailing = &caps[2][value.len()..];
+                    if PLACEHOLDERS.contains(&value.to_lowercase().as_str()) {
+                        return caps[0].to_string();
+                    }
+                    format!("{}{}{}", &caps[1], self.digest(value), trailing)
+                })
+                .into_owned();
+        }
+        text =
+            uuid_regex().replace_all(&text, |caps: &Captures| self.digest(&caps[0])).into_owned();
+        text = mac_regex()
+            .replace_all(&text, |caps: &Captures| {
+                self.manager
+                    .anonymize_mac_address(&caps[0].to_lowercase())
+                    .unwrap_or_else(|_| REDACTED.to_string())
+            })
+            .into_owned();
+        address_regex().replace_all(&text, REDACTED).into_owned()
+    }
+
+    /// Each of `lines` with identifiers replaced
+    pub fn sanitize_lines(&mut self, lines: &[String]) -> Vec<String> {
+        lines.iter().map(|line| self.sanitize(line)).collect()
+    }
+
+    /// Digest of an identifier, as the report has it
+    fn digest(&mut self, value: &str) -> String {
+        self.manager.anonymize_identifier(value).unwrap_or_else(|_| REDACTED.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hardware::PrivacyLevel;
+
+    #[test]
+    fn test_sanitize_tool_output() {
+        let mut manager = PrivacyManager::with_salt(PrivacyLevel::Basic, b"salt");
+        let serial = manager.anonymize_identifier("PF2ABCDE").unwrap();
+        let mut sanitizer = RawSanitizer::new(&mut manager).with_hostname("thinkpad-x1");
+
+        let dmidecode = "System Information\n\tSerial Number: PF2ABCDE\n\tUUID: 4c4c4544-0042-3510-8052-b4c04f565931\n\tAsset Tag: Not Specified\n";
+        let sanitized = sanitizer.sanitize(dmidecode);
+        assert!(sanitized.contains(&format!("Serial Number: {}\n", serial)));
+        assert!(!sanitized.contains("4c4c4544"));
+        assert!(sanitized.contains("Asset Tag: Not Specified"));
+
+        let lshw = r#"{"id" : "thinkpad-x1", "serial" : "3c:7c:3f:12:34:56", "configuration" : {"ip" : "192.168.1.20"}}"#;
+        let sanitized = sanitizer.sanitize(lshw);
+        assert!(!sanitized.contains("thinkpad-x1"));
+        assert!(!sanitized.contains("12:34:56"));
+        assert!(sanitized.contains(r#""ip" : "[redacted]""#));
+
+        let lsusb =
+            "  iProduct                2 USB Receiver\n  iSerial                 3 0123ABCD4567\n";
+        let sanitized = sanitizer.sanitize(lsusb);
+        assert!(sanitized.contains("USB Receiver"));
+        assert!(!sanitized.contains("0123ABCD4567"));
+
+        let nvme = r#"{"sn" : "S4EWNX0R123456    ", "mn" : "Samsung SSD 980 PRO 1TB"}"#;
+        let sanitized = sanitizer.sanitize(nvme);
+        assert!(!sanitized.contains("S4EWNX0R123456"));
+        assert!(sanitized.contains("Samsung SSD 980 PRO 1TB"));
+
+        let blkid = "DEVNAME=/dev/sda1\nLABEL=janes-photos\nPARTUUID=1a2b3c4d-01\nTYPE=ext4\n";
+        let sanitized = sanitizer.sanitize(blkid);
+        assert!(!sanitized.contains("janes-photos") && !sanitized.contains("1a2b3c4d"));
+        assert!(sanitized.contains("TYPE=ext4"));
+    }
+
+    #[test]
+    fn test_sanitize_kernel_log_lines() {
+        let mut manager = PrivacyManager::with_salt(PrivacyLevel::Basic, b"salt");
+        let serial = manager.anonymize_identifier("0123ABCD4567").unwrap();
+        let lines = [
+            "[    2.104511] usb 1-2: New USB device found, idVendor=0bda, idProduct=8812"
+                .to_string(),
+            "[    2.104530] usb 1-2: SerialNumber: 0123ABCD4567".to_string(),
+            "[   14.300112] wlp2s0: authenticate with 3c:7c:3f:12:34:56 on thinkpad-x1".to_string(),
+        ];
+        let sanitized =
+            RawSanitizer::new(&mut manager).with_hostname("thinkpad-x1").sanitize_lines(&lines);
+
+        assert_eq!(sanitized[0], lines[0]);
+        assert_eq!(sanitized[1], format!("[    2.104530] usb 1-2: SerialNumber: {}", serial));
+        assert!(!sanitized[2].contains("12:34:56") && !sanitized[2].contains("thinkpad-x1"));
+    }
+
+    #[test]
+    fn test_mac_keeps_vendor_prefix() {
+        let mut manager = PrivacyManager::with_salt(PrivacyLevel::Basic, b"salt");
+        let sanitized =
+            RawSanitizer::new(&mut manager).sanitize("link/ether 3c:7c:3f:12:34:56 brd");
+        assert!(sanitized.starts_with("link/ether 3c:7c:3f:"));
+        assert!(!sanitized.contains("12:34:56"));
+    }
+}
@@ -0,0 +1,211 @@
+//! User-defined redaction of report fields
+//!
+//! Some values are harmless in general but identifying for a particular
+//! user: an asset tag in a DMI string, a custom name given to a USB device.
+//! Redaction rules from the `[[privacy.redact]]` tables of the config file
+//! and `--redact-rule` arguments replace them before the report is written.
+//!
+//! A rule selects fields by path, as shown by `privacy-audit` (for example
+//! `usb[0].product_name`), text by regular expression, or both. In field
+//! paths `*` matches within one path segment and `**` across segments, so
+//! `usb[*].product_name` covers every USB device.
+
+use crate::errors::{LxHwError, Result};
+use crate::hardware::HardwareReport;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// Default replacement for redacted text
+pub const REDACTED: &str = "[redacted]";
+
+/// One redaction rule as written in the config file
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RedactionRule {
+    /// Field path pattern; all string fields when absent
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub field: Option<String>,
+    /// Regular expression for the text to replace; the whole value when absent
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pattern: Option<String>,
+    #[serde(default = "default_replacement")]
+    pub replacement: String,
+}
+
+fn default_replacement() -> String {
+    REDACTED.to_string()
+}
+
+impl RedactionRule {
+    /// Parse a `FIELD=REGEX` argument; either side may be empty, but not both
+    ///
+    /// `usb[*].product_name=` redacts whole values of a field and
+    /// `=ASSET-[0-9]+` matching text in every field.
+    pub fn parse(argument: &str) -> Result<Self> {
+        let invalid = |reason: &str| LxHwError::InvalidInput {
+            message: format!("Invalid redaction rule '{}': {}", argument, reason),
+        };
+        let (field, pattern) =
+            argument.split_once('=').ok_or_else(|| invalid("expected FIELD=REGEX"))?;
+        let non_empty = |text: &str| (!text.is_empty()).then(|| text.to_string());
+        let rule = Self {
+            field: non_empty(field.trim()),
+            pattern: non_empty(pattern),
+            replacement: default_replacement(),
+        };
+        if rule.field.is_none() && rule.pattern.is_none() {
+            return Err(invalid("give a field, a pattern or both"));
+        }
+        Ok(rule)
+    }
+}
+
+/// Compiled redaction rules
+#[derive(Debug, Clone, Default)]
+pub struct Redactor {
+    rules: Vec<CompiledRule>,
+}
+
+#[derive(Debug, Clone)]
+struct CompiledRule {
+    field: Option<Regex>,
+    pattern: Option<Regex>,
+    replacement: String,
+}
+
+impl Redactor {
+    /// Compile `rules`, failing on invalid regular expressions
+    pub fn new(rules: &[RedactionRule]) -> Result<Self> {
+        let compile = |pattern: &str| {
+            Regex::new(pattern).map_err(|e| LxHwError::InvalidInput {
+                message: format!("Invalid redaction pattern '{}': {}", pattern, e),
+            })
+        };
+        let rules = rules
+            .iter()
+            .map(|rule| {
+                Ok(CompiledRule {
+                    field: rule.field.as_deref().map(field_regex).transpose()?,
+                    pattern: rule.pattern.as_deref().map(compile).transpose()?,
+                    replacement: rule.replacement.clone(),
+                })
+            })
+            .collect::<Result<_>>()?;
+        Ok(Self { rules })
+    }
+
+    /// Apply the rules to `report`, returning the paths of changed fields
+    pub fn apply(&self, report: &mut HardwareReport) -> Result<Vec<String>> {
+        if self.rules.is_empty() {
+            return Ok(Vec::new());
+        }
+        let mut value = serde_json::to_value(&*report)
+            .map_err(|e| LxHwError::SerializationError(e.to_string()))?;
+        let mut changed = Vec::new();
+        self.visit(&mut value, String::new(), &mut changed);
+        if !changed.is_empty() {
+            *report = serde_json::from_value(value)
+                .map_err(|e| LxHwError::SerializationError(e.to_string()))?;
+        }
+        Ok(changed)
+    }
+
+    fn visit(&self, value: &mut Value, path: String, changed: &mut Vec<String>) {
+        match value {
+            Value::Object(fields) => {
+                for (name, field) in fields.iter_mut() {
+                    let child =
+                        if path.is_empty() { name.clone() } else { format!("{}.{}", path, name) };
+                    self.visit(field, child, changed);
+                }
+            }
+            Value::Array(items) => {
+                for (index, item) in items.iter_mut().enumerate() {
+                    self.visit(item, format!("{}[{}]", path, index), changed);
+                }
+            }
+            Value::String(text) => {
+                let redacted = self.redact(&path, text);
+                if redacted != *text {
+                    *text = redacted;
+                    changed.push(path);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// `text` of the field at `path` with every matching rule applied
+    fn redact(&self, path: &str, text: &str) -> String {
+        self.rules
+            .iter()
+            .filter(|rule| rule.field.as_ref().map_or(true, |field| field.is_match(path)))
+            .fold(text.to_string(), |text, rule| match &rule.pattern {
+                Some(pattern) => pattern.replace_all(&text, rule.replacement.as_str()).into_owned(),
+                None => rule.replacement.clone(),
+            })
+    }
+}
+
+/// Anchored regex for a field path pattern
+fn field_regex(pattern: &str) -> Result<Regex> {
+    let regex = regex::escape(pattern).replace(r"\*\*", ".*").replace(r"\*", "[^.]*");
+    Regex::new(&format!("^{}$", regex)).map_err(|e| LxHwError::InvalidInput {
+        message: format!("Invalid redaction field '{}': {}", pattern, e),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hardware::examples::ExampleGenerator;
+
+    #[test]
+    fn test_parse_rule() {
+        let rule = RedactionRule::parse("usb[*].product_name=").unwrap();
+        assert_eq!(rule.field.as_deref(), Some("usb[*].product_name"));
+        assert_eq!(rule.pattern, None);
+        assert_eq!(rule.replacement, REDACTED);
+
+        let rule = RedactionRule::parse("=ASSET-[0-9]+").unwrap();
+        assert_eq!((rule.field, rule.pattern.as_deref()), (None, Some("ASSET-[0-9]+")));
+
+        assert!(RedactionRule::parse("=").is_err());
+        assert!(RedactionRule::parse("usb").is_err());
+        assert!(Redactor::new(&[RedactionRule::parse("=(").unwrap()]).is_err());
+    }
+
+    #[test]
+    fn test_field_patterns() {
+        let field = field_regex("storage[*].model").unwrap();
+        assert!(field.is_match("storage[0].model"));
+        assert!(field.is_match("storage[12].model"));
+        assert!(!field.is_match("storage[0].nvme.model"));
+        assert!(!field.is_match("xstorage[0].model"));
+        assert!(field_regex("**.model").unwrap().is_match("storage[0].nvme.model"));
+    }
+
+    #[test]
+    fn test_apply_rules() {
+        let mut report = ExampleGenerator::new(1).report(0);
+        report.cpu.as_mut().unwrap().model = "Intel Core i7 ASSET-0042".to_string();
+        let original_gpu = report.graphics[0].model.clone();
+        let redactor = Redactor::new(&[
+            RedactionRule::parse("=ASSET-[0-9]+").unwrap(),
+            RedactionRule {
+                field: Some("storage[*].model".to_string()),
+                pattern: None,
+                replacement: "disk".to_string(),
+            },
+        ])
+        .unwrap();
+
+        let changed = redactor.apply(&mut report).unwrap();
+        assert_eq!(report.cpu.as_ref().unwrap().model, "Intel Core i7 [redacted]");
+        assert!(report.storage.iter().all(|storage| storage.model == "disk"));
+        assert_eq!(report.graphics[0].model, original_gpu);
+        assert!(changed.contains(&"cpu.model".to_string()));
+        assert!(changed.contains(&"storage[0].model".to_string()));
+        assert!(Redactor::default().apply(&mut report).unwrap().is_empty());
+    }
+}
@@ -0,0 +1,519 @@
+//! Report scrubbing for the database's final privacy gate
+//!
+//! Submitted reports may come from any client version, so the database
+//! does not trust their anonymization. Scrubbing re-anonymizes every
+//! identifier under a key held by the database, redacts anything that looks
+//! like personal data from free-text fields, and trims fields the database
+//! does not publish.
+
+use crate::errors::{LxHwError, Result};
+use crate::hardware::blobs::{BlobRef, BlobStore, KIND_DMESG};
+use crate::hardware::notes::{redact_personal_data, MAX_NOTE_LENGTH};
+use crate::hardware::{HardwareReport, PrivacyLevel};
+use crate::privacy::raw::RawSanitizer;
+use crate::privacy::PrivacyManager;
+use ring::{hmac, rand};
+use serde::Serialize;
+
+/// Replacement for redacted text
+const REDACTED: &str = "[redacted]";
+
+/// What the scrubber is allowed to keep
+#[derive(Debug, Clone)]
+pub struct ScrubPolicy {
+    /// Longest free-text value kept; longer values are truncated
+    pub max_text_len: usize,
+    /// Keep kernel log lines attached to unknown devices, sanitized again
+    /// under the scrubbing key; never kept for strict privacy reports
+    pub keep_dmesg_lines: bool,
+    /// Keep the system boot time
+    pub keep_boot_time: bool,
+}
+
+impl Default for ScrubPolicy {
+    fn default() -> Self {
+        Self { max_text_len: 128, keep_dmesg_lines: false, keep_boot_time: false }
+    }
+}
+
+/// Changes made while scrubbing a report
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ScrubSummary {
+    /// Fields whose identifiers were re-anonymized
+    pub reanonymized: Vec<String>,
+    /// Fields where personal data was redacted
+    pub redacted: Vec<String>,
+    /// Fields that were truncated or removed
+    pub trimmed: Vec<String>,
+}
+
+impl ScrubSummary {
+    /// Whether scrubbing changed anything besides re-anonymization
+    pub fn has_findings(&self) -> bool {
+        !self.redacted.is_empty() || !self.trimmed.is_empty()
+    }
+}
+
+/// Enforces the database privacy policy on a report
+pub struct Scrubber {
+    policy: ScrubPolicy,
+    key: hmac::Key,
+    /// Raw key, for sanitizing kernel log lines like the client does
+    salt: Vec<u8>,
+    /// Stores the report's blobs are read from and written to
+    blob_stores: Option<(BlobStore, BlobStore)>,
+}
+
+impl Scrubber {
+    /// Scrubber with a random key; identifiers cannot be linked across runs
+    pub fn new(policy: ScrubPolicy) -> Result<Self> {
+        let mut key = [0u8; 32];
+        rand::SecureRandom::fill(&rand::SystemRandom::new(), &mut key)
+            .map_err(|_| LxHwError::PrivacyError("Failed to generate scrubbing key".to_string()))?;
+        Ok(Self::with_key(policy, &key))
+    }
+
+    /// Scrubber with a fixed key, so the same system maps to the same identifier
+    pub fn with_key(policy: ScrubPolicy, key: &[u8]) -> Self {
+        Self {
+            policy,
+            key: hmac::Key::new(hmac::HMAC_SHA256, key),
+            salt: key.to_vec(),
+            blob_stores: None,
+        }
+    }
+
+    /// Copy the blobs a report references from `source` to `target`,
+    /// sanitizing kernel log blobs on the way
+    pub fn with_blob_stores(mut self, source: BlobStore, target: BlobStore) -> Self {
+        self.blob_stores = Some((source, target));
+        self
+    }
+
+    /// Scrub a report in place
+    pub fn scrub(&self, report: &mut HardwareReport) -> ScrubSummary {
+        let mut summary = ScrubSummary::default();
+        let digest_length = report.metadata.digest_length.unwrap_or(super::FULL_DIGEST_LENGTH);
+
+        // Identifiers
+        self.reanonymize(
+            &mut report.metadata.anonymized_system_id,
+            "metadata.anonymized_system_id",
+            digest_length,
+            &mut summary,
+        );
+        self.reanonymize(
+            &mut report.system.anonymized_hostname,
+            "system.anonymized_hostname",
+            digest_length,
+            &mut summary,
+        );
+        for (i, storage) in report.storage.iter_mut().enumerate() {
+            self.reanonymize(
+                &mut storage.anonymized_serial,
+                &format!("storage[{}].anonymized_serial", i),
+                digest_length,
+                &mut summary,
+            );
+        }
+        for (i, network) in report.network.iter_mut().enumerate() {
+            if let Some(mac) = super::rekey_mac(&network.anonymized_mac, &self.key) {
+                network.anonymized_mac = mac;
+                summary.reanonymized.push(format!("network[{}].anonymized_mac", i));
+            }
+        }
+
+        // Free text
+        if let Some(distribution) = report.system.distribution.as_mut() {
+            self.clean(distribution, "system.distribution", &mut summary);
+        }
+        if let Some(cpu) = report.cpu.as_mut() {
+            self.clean(&mut cpu.model, "cpu.model", &mut summary);
+            self.clean(&mut cpu.vendor, "cpu.vendor", &mut summary);
+        }
+        if let Some(memory) = report.memory.as_mut() {
+            for (i, dimm) in memory.dimms.iter_mut().enumerate() {
+                if let Some(manufacturer) = dimm.manufacturer.as_mut() {
+                    self.clean(
+                        manufacturer,
+                        &format!("memory.dimms[{}].manufacturer", i),
+                        &mut summary,
+                    );
+                }
+            }
+        }
+        for (i, storage) in report.storage.iter_mut().enumerate() {
+            self.clean(&mut storage.model, &format!("storage[{}].model", i), &mut summary);
+            if let Some(vendor) = storage.vendor.as_mut() {
+                self.clean(vendor, &format!("storage[{}].vendor", i), &mut summary);
+            }
+        }
+        for (i, gpu) in report.graphics.iter_mut().enumerate() {
+            self.clean(&mut gpu.vendor, &format!("graphics[{}].vendor", i), &mut summary);
+            self.clean(&mut gpu.model, &format!("graphics[{}].model", i), &mut summary);
+        }
+        for (i, network) in report.network.iter_mut().enumerate() {
+            self.clean(&mut network.vendor, &format!("network[{}].vendor", i), &mut summary);
+            self.clean(&mut network.model, &format!("network[{}].model", i), &mut summary);
+        }
+        for (i, usb) in report.usb.iter_mut().enumerate() {
+            if let Some(vendor) = usb.vendor_name.as_mut() {
+                self.clean(vendor, &format!("usb[{}].vendor_name", i), &mut summary);
+            }
+            if let Some(product) = usb.product_name.as_mut() {
+                self.clean(product, &format!("usb[{}].product_name", i), &mut summary);
+            }
+        }
+        for (i, audio) in report.audio.iter_mut().enumerate() {
+            self.clean(&mut audio.vendor, &format!("audio[{}].vendor", i), &mut summary);
+            self.clean(&mut audio.model, &format!("audio[{}].model", i), &mut summary);
+        }
+        if let Some(kernel_support) = report.kernel_support.as_mut() {
+            for (i, device) in kernel_support.device_support_details.iter_mut().enumerate() {
+                let field = format!("kernel_support.device_support_details[{}]", i);
+                self.clean(
+                    &mut device.device_name,
+                    &format!("{}.device_name", field),
+                    &mut summary,
+                );
+                if let Some(notes) = device.notes.as_mut() {
+                    self.clean(notes, &format!("{}.notes", field), &mut summary);
+                }
+            }
+        }
+        // Notes are longer than other free text and written by hand, so they
+        // get the note patterns and length limit instead of clean()
+        for (i, note) in report.user_notes.iter_mut().enumerate() {
+            let mut redacted = redact_personal_data(&note.note, REDACTED);
+            if redacted != note.note {
+                summary.redacted.push(format!("user_notes[{}].note", i));
+            }
+            if redacted.chars().count() > MAX_NOTE_LENGTH {
+                redacted = redacted.chars().take(MAX_NOTE_LENGTH).collect();
+                summary.trimmed.push(format!("user_notes[{}].note", i));
+            }
+            note.note = redacted;
+        }
+        for (i, error) in report.metadata.phase_errors.iter_mut().enumerate() {
+            self.clean(
+                &mut error.message,
+                &format!("metadata.phase_errors[{}].message", i),
+                &mut summary,
+            );
+        }
+
+        // Fields the database does not publish
+        if !self.policy.keep_boot_time && report.system.boot_time.take().is_some() {
+            summary.trimmed.push("system.boot_time".to_string());
+        }
+        if report.provenance.take().is_some() {
+            summary.trimmed.push("provenance".to_string());
+        }
+        let keep_dmesg_lines =
+            self.policy.keep_dmesg_lines && report.metadata.privacy_level != PrivacyLevel::Strict;
+        // Re-key the identifiers the client replaced in kernel log lines, and
+        // replace those a client that did not sanitize the lines left in place
+        let mut manager = PrivacyManager::with_salt(report.metadata.privacy_level, &self.salt);
+        if manager.set_digest_length(digest_length).is_err() {
+            log::debug!("Keeping full digests in kernel log lines");
+        }
+        let mut sanitizer = RawSanitizer::new(&mut manager);
+        for (i, device) in report.unknown_devices.iter_mut().enumerate() {
+            let field = format!("unknown_devices[{}].dmesg_lines", i);
+            if device.dmesg_lines.is_empty() {
+                continue;
+            }
+            if keep_dmesg_lines {
+                device.dmesg_lines = sanitizer.sanitize_lines(&device.dmesg_lines);
+                summary.reanonymized.push(field);
+            } else {
+                device.dmesg_lines.clear();
+                summary.trimmed.push(field);
+            }
+        }
+        self.scrub_blobs(report, keep_dmesg_lines, &mut sanitizer, &mut summary);
+
+        summary
+    }
+
+    /// Carry blobs over to the target store, sanitizing kernel log blobs like
+    /// the inline lines
+    ///
+    /// Without blob stores other blobs are referenced as they are, but kernel
+    /// log blobs cannot be sanitized and are removed. So are blobs that are
+    /// missing or do not match their digest.
+    fn scrub_blobs(
+        &self,
+        report: &mut HardwareReport,
+        keep_dmesg_lines: bool,
+        sanitizer: &mut RawSanitizer,
+        summary: &mut ScrubSummary,
+    ) {
+        for mut blob in std::mem::take(&mut report.blobs) {
+            let field = format!("blobs[{}]", blob.subject);
+            let is_dmesg = blob.kind == KIND_DMESG;
+            if is_dmesg && !keep_dmesg_lines {
+                summary.trimmed.push(field);
+                continue;
+            }
+            match &self.blob_stores {
+                Some((source, target)) => {
+                    let sanitizer = if is_dmesg { Some(&mut *sanitizer) } else { None };
+                    if let Err(e) = carry_blob(&mut blob, source, target, sanitizer) {
+                        log::warn!("Removing blob reference {}: {}", field, e);
+                        summary.trimmed.push(field);
+                        continue;
+                    }
+                    if is_dmesg {
+                        summary.reanonymized.push(field);
+                    }
+                }
+                None if is_dmesg => {
+                    summary.trimmed.push(field);
+                    continue;
+                }
+                None => {}
+            }
+            report.blobs.push(blob);
+        }
+    }
+
+    fn reanonymize(
+        &self,
+        value: &mut String,
+        field: &str,
+        digest_length: usize,
+        summary: &mut ScrubSummary,
+    ) {
+        *value = super::digest(&self.key, value, digest_length);
+        summary.reanonymized.push(field.to_string());
+    }
+
+    /// Redact personal data and truncate overly long values
+    fn clean(&self, value: &mut String, field: &str, summary: &mut ScrubSummary) {
+        let mut redacted = value.clone();
+        for (regex, _) in crate::validation::privacy::get_pii_patterns() {
+            redacted = regex.replace_all(&redacted, REDACTED).into_owned();
+        }
+        if redacted != *value {
+            *value = redacted;
+            summary.redacted.push(field.to_string());
+        }
+
+        if value.chars().count() > self.policy.max_text_len {
+            *value = value.chars().take(self.policy.max_text_len).collect();
+            summary.trimmed.push(field.to_string());
+        }
+    }
+}
+
+/// Copy a blob between stores, sanitizing it as kernel log lines when a
+/// sanitizer is given
+fn carry_blob(
+    blob: &mut BlobRef,
+    source: &BlobStore,
+    target: &BlobStore,
+    sanitizer: Option<&mut RawSanitizer>,
+) -> Result<()> {
+    let mut data = source.get(blob)?;
+    if let Some(sanitizer) = sanitizer {
+        let lines: Vec<String> = String::from_utf8_lossy(&data).lines().map(String::from).collect();
+        data = sanitizer.sanitize_lines(&lines).join("\n").into_bytes();
+    }
+    blob.sha256 = target.put(&data)?;
+    blob.size = data.len() as u64;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hardware::notes::UserNote;
+    use crate::hardware::{NetworkDevice, PrivacyLevel, ReportMetadata, SystemInfo, UnknownDevice};
+    use chrono::Utc;
+
+    fn create_test_report() -> HardwareReport {
+        HardwareReport {
+            metadata: ReportMetadata {
+                version: "1.0.0".to_string(),
+                generated_at: Utc::now(),
+                privacy_level: PrivacyLevel::Basic,
+                tools_used: vec!["lspci".to_string()],
+                anonymized_system_id: "raw-system-id".to_string(),
+                phase_errors: Vec::new(),
+                sandbox: None,
+                privileges: None,
+                remote: false,
+                replayed: false,
+                salt_context: None,
+                digest_length: None,
+            },
+            system: SystemInfo {
+                anonymized_hostname: "alice-laptop".to_string(),
+                kernel_version: "6.16.0".to_string(),
+                distribution: Some("NixOS 25.11 (contact admin@example.com)".to_string()),
+                architecture: "x86_64".to_string(),
+                boot_time: Some(Utc::now()),
+                virtualization: None,
+            },
+            cpu: None,
+            memory: None,
+            storage: Vec::new(),
+            storage_stack: None,
+            graphics: Vec::new(),
+            network: vec![NetworkDevice {
+                device_type: "ethernet".to_string(),
+                vendor: "Intel".to_string(),
+                model: "I219-V".to_string(),
+                driver: Some("e1000e".to_string()),
+                anonymized_mac: "3c:7c:3f:12:34:56".to_string(),
+                wireless_capabilities: None,
+            }],
+            usb: Vec::new(),
+            audio: Vec::new(),
+            audio_routing: None,
+            radios: Vec::new(),
+            input: Vec::new(),
+            peripherals: None,
+            pcie_links: Vec::new(),
+            sensors: Vec::new(),
+            firmware: None,
+            kernel_support: None,
+            unknown_devices: vec![UnknownDevice {
+                bus: "usb".to_string(),
+                vendor_id: "1234".to_string(),
+                device_id: "5678".to_string(),
+                class_code: None,
+                modalias: None,
+                dmesg_lines: vec!["usb 1-2: SerialNumber: ABC123".to_string()],
+            }],
+            lifecycle_notices: Vec::new(),
+            firmware_updates: Vec::new(),
+            user_notes: Vec::new(),
+            blobs: Vec::new(),
+            provenance: None,
+        }
+    }
+
+    #[test]
+    fn test_scrub_enforces_policy() {
+        let mut report = create_test_report();
+        report.blobs.push(BlobRef {
+            kind: KIND_DMESG.to_string(),
+            subject: "unknown_devices[0].dmesg_lines".to_string(),
+            sha256: "0".repeat(64),
+            size: 0,
+        });
+        let summary = Scrubber::new(ScrubPolicy::default()).unwrap().scrub(&mut report);
+
+        assert_ne!(report.system.anonymized_hostname, "alice-laptop");
+        assert_eq!(report.system.distribution.as_deref(), Some("NixOS 25.11 (contact [redacted])"));
+        assert!(report.system.boot_time.is_none());
+        assert!(report.unknown_devices[0].dmesg_lines.is_empty());
+        assert!(report.blobs.is_empty());
+        assert!(report.network[0].anonymized_mac.starts_with("3c:7c:3f:"));
+        assert_ne!(report.network[0].anonymized_mac, "3c:7c:3f:12:34:56");
+        assert_eq!(report.system.kernel_version, "6.16.0");
+
+        assert!(summary.has_findings());
+        assert_eq!(summary.redacted, vec!["system.distribution"]);
+        assert!(summary.reanonymized.contains(&"network[0].anonymized_mac".to_string()));
+    }
+
+    #[test]
+    fn test_kept_dmesg_lines_are_sanitized() {
+        let policy = ScrubPolicy { keep_dmesg_lines: true, ..ScrubPolicy::default() };
+        let scrubber = Scrubber::with_key(policy, b"database-key");
+        let mut report = create_test_report();
+        report.unknown_devices[0].dmesg_lines.push("usb 1-2: Product: USB Receiver".to_string());
+        let summary = scrubber.scrub(&mut report);
+
+        let lines = &report.unknown_devices[0].dmesg_lines;
+        let serial = PrivacyManager::with_salt(PrivacyLevel::Basic, b"database-key")
+            .anonymize_identifier("ABC123")
+            .unwrap();
+        assert_eq!(lines[0], format!("usb 1-2: SerialNumber: {}", serial));
+        assert_eq!(lines[1], "usb 1-2: Product: USB Receiver");
+        assert!(summary.reanonymized.contains(&"unknown_devices[0].dmesg_lines".to_string()));
+
+        // Strict reports never carry the kernel log
+        let mut strict = create_test_report();
+        strict.metadata.privacy_level = PrivacyLevel::Strict;
+        scrubber.scrub(&mut strict);
+        assert!(strict.unknown_devices[0].dmesg_lines.is_empty());
+    }
+
+    #[test]
+    fn test_kept_dmesg_blobs_are_sanitized_into_target_store() {
+        let dir = tempfile::tempdir().unwrap();
+        let source = BlobStore::new(dir.path().join("source"));
+        let target = BlobStore::new(dir.path().join("target"));
+        let mut report = create_test_report();
+        report.unknown_devices[0].dmesg_lines.push("usb 1-2: Product: USB Receiver".to_string());
+        report.externalize_blobs(&source, 0).unwrap();
+        let policy = ScrubPolicy { keep_dmesg_lines: true, ..ScrubPolicy::default() };
+
+        // Without the blob files the kernel log cannot be sanitized
+        let mut unreadable = report.clone();
+        let summary = Scrubber::with_key(policy.clone(), b"database-key").scrub(&mut unreadable);
+        assert!(unreadable.blobs.is_empty());
+        assert!(summary.trimmed.contains(&"blobs[unknown_devices[0].dmesg_lines]".to_string()));
+
+        let scrubber = Scrubber::with_key(policy, b"database-key")
+            .with_blob_stores(source.clone(), target.clone());
+        let summary = scrubber.scrub(&mut report);
+        assert!(summary
+            .reanonymized
+            .contains(&"blobs[unknown_devices[0].dmesg_lines]".to_string()));
+        report.verify_blobs(&target).unwrap();
+        let text = String::from_utf8(target.get(&report.blobs[0]).unwrap()).unwrap();
+        let serial = PrivacyManager::with_salt(PrivacyLevel::Basic, b"database-key")
+            .anonymize_identifier("ABC123")
+            .unwrap();
+        assert_eq!(
+            text,
+            format!("usb 1-2: SerialNumber: {}\nusb 1-2: Product: USB Receiver", serial)
+        );
+        assert!(source.get(&report.blobs[0]).is_err());
+    }
+
+    #[test]
+    fn test_fixed_key_is_deterministic() {
+        let scrubber = Scrubber::with_key(ScrubPolicy::default(), b"database-key");
+        let mut first = create_test_report();
+        let mut second = create_test_report();
+        scrubber.scrub(&mut first);
+        scrubber.scrub(&mut second);
+
+        assert_eq!(first.metadata.anonymized_system_id, second.metadata.anonymized_system_id);
+    }
+
+    #[test]
+    fn test_long_text_truncated() {
+        let mut report = create_test_report();
+        report.network[0].model = "x".repeat(300);
+        let policy = ScrubPolicy { max_text_len: 64, ..ScrubPolicy::default() };
+        let summary = Scrubber::new(policy).unwrap().scrub(&mut report);
+
+        assert_eq!(report.network[0].model.len(), 64);
+        assert!(summary.trimmed.contains(&"network[0].model".to_string()));
+    }
+
+    #[test]
+    fn test_user_notes_redacted_but_not_shortened() {
+        let mut report = create_test_report();
+        let workaround = format!("Boot with pcie_aspm=off. {}", "Details follow. ".repeat(12));
+        report.user_notes = vec![
+            UserNote { component: "system".to_string(), note: workaround.clone() },
+            UserNote {
+                component: "network".to_string(),
+                note: "Firmware from /home/alice/fw works".to_string(),
+            },
+        ];
+        let summary = Scrubber::new(ScrubPolicy::default()).unwrap().scrub(&mut report);
+
+        assert_eq!(report.user_notes[0].note, workaround);
+        assert_eq!(report.user_notes[1].note, "Firmware from [redacted]/fw works");
+        assert!(summary.redacted.contains(&"user_notes[1].note".to_string()));
+    }
+}
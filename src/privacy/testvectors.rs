@@ -0,0 +1,299 @@
+//! Deterministic test vectors for the anonymization pipeline
+//!
+//! Runs the privacy functions against fixed inputs and salts published in
+//! `data/privacy-test-vectors.toml`, so auditors can confirm on their own
+//! platform that HMAC-SHA256 and identifier formatting produce the expected
+//! values and that no anonymized value leaks the identifier it came from.
+
+use crate::errors::{LxHwError, Result};
+use crate::hardware::{HardwareReport, PrivacyLevel};
+use crate::privacy::scrub::{ScrubPolicy, Scrubber};
+use crate::privacy::PrivacyManager;
+use ring::hmac;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// Built-in test vectors, compiled into the binary
+const TEST_VECTORS: &str = include_str!("../../data/privacy-test-vectors.toml");
+
+/// Raw HMAC-SHA256 vector; key and data are hex encoded
+#[derive(Debug, Clone, Deserialize)]
+pub struct HmacVector {
+    pub name: String,
+    pub key: String,
+    pub data: String,
+    pub expected: String,
+}
+
+/// Salted identifier or MAC address anonymization vector
+#[derive(Debug, Clone, Deserialize)]
+pub struct SaltedVector {
+    pub name: String,
+    /// Hex-encoded salt
+    pub salt: String,
+    pub input: String,
+    pub expected: String,
+}
+
+/// Whole-report re-anonymization vector
+#[derive(Debug, Clone, Deserialize)]
+pub struct ReportVector {
+    pub name: String,
+    /// Hex-encoded scrubbing key
+    pub key: String,
+    pub system_id: String,
+    pub hostname: String,
+    pub serial: String,
+    pub mac: String,
+    pub expected_system_id: String,
+    pub expected_hostname: String,
+    pub expected_serial: String,
+    pub expected_mac: String,
+}
+
+/// A set of test vectors
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct TestVectors {
+    #[serde(default)]
+    pub hmac: Vec<HmacVector>,
+    #[serde(default)]
+    pub identifier: Vec<SaltedVector>,
+    #[serde(default)]
+    pub mac: Vec<SaltedVector>,
+    #[serde(default)]
+    pub report: Vec<ReportVector>,
+}
+
+impl TestVectors {
+    /// Vectors from `data/privacy-test-vectors.toml`
+    pub fn builtin() -> Self {
+        Self::parse(TEST_VECTORS).expect("built-in test vectors must be valid")
+    }
+
+    /// Load vectors from a TOML file
+    pub fn load(path: &Path) -> Result<Self> {
+        Self::parse(&std::fs::read_to_string(path)?)
+    }
+
+    /// Parse vectors from TOML
+    pub fn parse(content: &str) -> Result<Self> {
+        toml::from_str(content)
+            .map_err(|e| LxHwError::ConfigError(format!("Invalid test vectors: {}", e)))
+    }
+
+    /// Run every vector and collect the outcomes
+    pub fn run(&self) -> SelfTestReport {
+        let mut results = Vec::new();
+
+        for vector in &self.hmac {
+            let actual = decode(&vector.key).and_then(|key| {
+                let data = decode(&vector.data)?;
+                let key = hmac::Key::new(hmac::HMAC_SHA256, &key);
+                Ok(hex::encode(hmac::sign(&key, &data).as_ref()))
+            });
+            results.push(VectorResult::compare("hmac", &vector.name, &vector.expected, actual));
+        }
+
+        for vector in &self.identifier {
+            let actual = decode(&vector.salt).and_then(|salt| {
+                PrivacyManager::with_salt(PrivacyLevel::Basic, &salt)
+                    .anonymize_identifier(&vector.input)
+            });
+            results.push(
+                VectorResult::compare("identifier", &vector.name, &vector.expected, actual)
+                    .check_no_leak(&[&vector.input]),
+            );
+        }
+
+        for vector in &self.mac {
+            let actual = decode(&vector.salt).and_then(|salt| {
+                PrivacyManager::with_salt(PrivacyLevel::Basic, &salt)
+                    .anonymize_mac_address(&vector.input)
+            });
+            let device_part = vector.input.get(9..).unwrap_or_default();
+            results.push(
+                VectorResult::compare("mac", &vector.name, &vector.expected, actual)
+                    .check_no_leak(&[device_part]),
+            );
+        }
+
+        for vector in &self.report {
+            results.push(run_report_vector(vector));
+        }
+
+        SelfTestReport { results }
+    }
+}
+
+/// Outcome of a single test vector
+#[derive(Debug, Clone, Serialize)]
+pub struct VectorResult {
+    pub suite: String,
+    pub name: String,
+    pub passed: bool,
+    pub expected: String,
+    pub actual: String,
+    /// Why the vector failed, if it did
+    pub failure: Option<String>,
+}
+
+impl VectorResult {
+    fn compare(suite: &str, name: &str, expected: &str, actual: Result<String>) -> Self {
+        let (actual, failure) = match actual {
+            Ok(actual) if actual.eq_ignore_ascii_case(expected) => (actual, None),
+            Ok(actual) => (actual, Some("output does not match".to_string())),
+            Err(e) => (String::new(), Some(e.to_string())),
+        };
+        Self {
+            suite: suite.to_string(),
+            name: name.to_string(),
+            passed: failure.is_none(),
+            expected: expected.to_string(),
+            actual,
+            failure,
+        }
+    }
+
+    /// Fail the vector if the output contains any of the original identifiers
+    fn check_no_leak(mut self, originals: &[&str]) -> Self {
+        let actual = self.actual.to_lowercase();
+        let leaked = originals
+            .iter()
+            .filter(|original| original.len() >= 4)
+            .any(|original| actual.contains(&original.to_lowercase()));
+        if self.passed && leaked {
+            self.passed = false;
+            self.failure = Some("output contains the original identifier".to_string());
+        }
+        self
+    }
+}
+
+/// Outcome of a self-test run
+#[derive(Debug, Clone, Serialize)]
+pub struct SelfTestReport {
+    pub results: Vec<VectorResult>,
+}
+
+impl SelfTestReport {
+    /// Whether every vector passed
+    pub fn passed(&self) -> bool {
+        self.results.iter().all(|r| r.passed)
+    }
+
+    /// Vectors that failed
+    pub fn failures(&self) -> impl Iterator<Item = &VectorResult> {
+        self.results.iter().filter(|r| !r.passed)
+    }
+}
+
+/// Run the built-in test vectors
+pub fn run_self_test() -> SelfTestReport {
+    TestVectors::builtin().run()
+}
+
+/// Scrub a report built from the vector and compare every identifier
+fn run_report_vector(vector: &ReportVector) -> VectorResult {
+    let expected = [
+        &vector.expected_system_id,
+        &vector.expected_hostname,
+        &vector.expected_serial,
+        &vector.expected_mac,
+    ]
+    .map(|value| value.as_str())
+    .join(" ");
+    let actual = decode(&vector.key).and_then(|key| {
+        let mut report = vector_report(vector)?;
+        Scrubber::with_key(ScrubPolicy::default(), &key).scrub(&mut report);
+        Ok([
+            report.metadata.anonymized_system_id.as_str(),
+            report.system.anonymized_hostname.as_str(),
+            report.storage[0].anonymized_serial.as_str(),
+            report.network[0].anonymized_mac.as_str(),
+        ]
+        .join(" "))
+    });
+
+    VectorResult::compare("report", &vector.name, &expected, actual).check_no_leak(&[
+        &vector.system_id,
+        &vector.hostname,
+        &vector.serial,
+        vector.mac.get(9..).unwrap_or_default(),
+    ])
+}
+
+/// Minimal report carrying the vector's raw identifiers
+fn vector_report(vector: &ReportVector) -> Result<HardwareReport> {
+    let report = serde_json::json!({
+        "metadata": {
+            "version": env!("CARGO_PKG_VERSION"),
+            "generated_at": "2024-01-01T00:00:00Z",
+            "privacy_level": "Basic",
+            "tools_used": [],
+            "anonymized_system_id": vector.system_id,
+        },
+        "system": {
+            "anonymized_hostname": vector.hostname,
+            "kernel_version": "6.6.0",
+            "distribution": null,
+            "architecture": "x86_64",
+            "boot_time": null,
+        },
+        "cpu": null,
+        "memory": null,
+        "storage": [{
+            "anonymized_serial": vector.serial,
+            "device_type": "HDD",
+            "size_bytes": 0,
+            "model": "Test Disk",
+            "vendor": null,
+            "interface": null,
+        }],
+        "graphics": [],
+        "network": [{
+            "device_type": "ethernet",
+            "vendor": "Test",
+            "model": "Test NIC",
+            "driver": null,
+            "anonymized_mac": vector.mac,
+        }],
+        "usb": [],
+        "audio": [],
+        "kernel_support": null,
+    });
+    serde_json::from_value(report).map_err(|e| LxHwError::SerializationError(e.to_string()))
+}
+
+fn decode(value: &str) -> Result<Vec<u8>> {
+    hex::decode(value).map_err(|e| LxHwError::ConfigError(format!("Invalid hex value: {}", e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_builtin_vectors_pass() {
+        let report = run_self_test();
+        let failures: Vec<_> = report.failures().collect();
+        assert!(failures.is_empty(), "failed vectors: {:?}", failures);
+        assert!(report.results.iter().any(|r| r.suite == "report"));
+    }
+
+    #[test]
+    fn test_mismatch_is_reported() {
+        let mut vectors = TestVectors::builtin();
+        vectors.identifier[0].expected = "00".repeat(32);
+        let report = vectors.run();
+
+        assert!(!report.passed());
+        assert_eq!(report.failures().count(), 1);
+    }
+
+    #[test]
+    fn test_leaked_identifier_fails() {
+        let result = VectorResult::compare("identifier", "leak", "abc-host", Ok("abc-host".into()))
+            .check_no_leak(&["abc-host"]);
+        assert!(!result.passed);
+    }
+}
@@ -4,6 +4,7 @@
 
 use crate::detectors::integration::HardwareAnalyzer;
 use crate::hardware::{HardwareReport, PrivacyLevel};
+use crate::presentation::{CompatibilityBadge, DeviceItem, DeviceList};
 use std::collections::HashMap;
 
 /// Real hardware manager for Qt6 application
@@ -29,9 +30,24 @@ pub struct DeviceInfo {
     pub vendor: String,
     pub model: String,
     pub status: String,
+    pub badge: CompatibilityBadge,
     pub details: Vec<String>,
 }
 
+impl From<&DeviceItem> for DeviceInfo {
+    fn from(item: &DeviceItem) -> Self {
+        Self {
+            category: item.category.label().to_string(),
+            name: item.name.clone(),
+            vendor: item.vendor.clone(),
+            model: item.model.clone(),
+            status: format!("{} {}", item.badge.emoji(), item.badge.display_text()),
+            badge: item.badge,
+            details: item.detail_lines(),
+        }
+    }
+}
+
 impl Default for HardwareManager {
     fn default() -> Self {
         Self {
@@ -73,231 +89,15 @@ impl HardwareManager {
 
     /// Process hardware report and organize for QML display
     fn process_hardware_report(&mut self, report: &HardwareReport) {
+        let devices = DeviceList::from_report(report);
         self.devices_by_category.clear();
-        let mut total_devices = 0;
-
-        // System information
-        let system_device = DeviceInfo {
-            category: "System".to_string(),
-            name: "System Information".to_string(),
-            vendor: "System".to_string(),
-            model: report.system.distribution.clone().unwrap_or("Unknown".to_string()),
-            status: "✅ Fully Supported".to_string(),
-            details: vec![
-                format!(
-                    "Distribution: {}",
-                    report.system.distribution.clone().unwrap_or("Unknown".to_string())
-                ),
-                format!("Kernel: {}", report.system.kernel_version),
-                format!("Architecture: {}", report.system.architecture),
-                format!("Hostname: {}", report.system.anonymized_hostname),
-            ],
-        };
-        self.devices_by_category.insert("System".to_string(), vec![system_device]);
-        total_devices += 1;
-
-        // CPU information
-        if let Some(cpu) = &report.cpu {
-            let cpu_device = DeviceInfo {
-                category: "CPU".to_string(),
-                name: cpu.model.clone(),
-                vendor: cpu.vendor.clone(),
-                model: cpu.model.clone(),
-                status: "✅ Fully Supported".to_string(),
-                details: vec![
-                    format!("Cores: {}", cpu.cores),
-                    format!("Threads: {}", cpu.threads),
-                    format!(
-                        "Base Frequency: {:.2} GHz",
-                        cpu.base_frequency.unwrap_or(0.0) / 1000.0
-                    ),
-                    format!("Max Frequency: {:.2} GHz", cpu.max_frequency.unwrap_or(0.0) / 1000.0),
-                ],
-            };
-            self.devices_by_category.insert("CPU".to_string(), vec![cpu_device]);
-            total_devices += 1;
+        for item in &devices.items {
+            self.devices_by_category
+                .entry(item.category.label().to_string())
+                .or_default()
+                .push(DeviceInfo::from(item));
         }
-
-        // Memory information
-        if let Some(memory) = &report.memory {
-            let memory_device = DeviceInfo {
-                category: "Memory".to_string(),
-                name: format!(
-                    "System Memory ({:.1} GB)",
-                    memory.total_bytes as f64 / (1024.0 * 1024.0 * 1024.0)
-                ),
-                vendor: "System".to_string(),
-                model: format!("{} DIMMs", memory.dimms.len()),
-                status: "✅ Fully Supported".to_string(),
-                details: vec![
-                    format!(
-                        "Total: {:.1} GB",
-                        memory.total_bytes as f64 / (1024.0 * 1024.0 * 1024.0)
-                    ),
-                    format!(
-                        "Available: {:.1} GB",
-                        memory.available_bytes as f64 / (1024.0 * 1024.0 * 1024.0)
-                    ),
-                    format!("DIMMs: {}", memory.dimms.len()),
-                    format!(
-                        "Type: {}",
-                        memory
-                            .dimms
-                            .first()
-                            .and_then(|d| d.memory_type.clone())
-                            .unwrap_or("Unknown".to_string())
-                    ),
-                ],
-            };
-            self.devices_by_category.insert("Memory".to_string(), vec![memory_device]);
-            total_devices += 1;
-        }
-
-        // Graphics devices
-        if !report.graphics.is_empty() {
-            let graphics_devices: Vec<DeviceInfo> = report
-                .graphics
-                .iter()
-                .map(|gpu| DeviceInfo {
-                    category: "Graphics".to_string(),
-                    name: gpu.model.clone(),
-                    vendor: gpu.vendor.clone(),
-                    model: gpu.model.clone(),
-                    status: if gpu.driver.is_some() {
-                        "✅ Working with Driver".to_string()
-                    } else {
-                        "⚠️ Driver Required".to_string()
-                    },
-                    details: vec![
-                        format!("PCI ID: {}", gpu.pci_id),
-                        format!(
-                            "Driver: {}",
-                            gpu.driver.clone().unwrap_or("Not loaded".to_string())
-                        ),
-                        format!(
-                            "Memory: {}",
-                            gpu.memory_bytes
-                                .map(|m| format!("{:.1} MB", m as f64 / (1024.0 * 1024.0)))
-                                .unwrap_or("Unknown".to_string())
-                        ),
-                    ],
-                })
-                .collect();
-            total_devices += graphics_devices.len();
-            self.devices_by_category.insert("Graphics".to_string(), graphics_devices);
-        }
-
-        // Storage devices
-        if !report.storage.is_empty() {
-            let storage_devices: Vec<DeviceInfo> = report
-                .storage
-                .iter()
-                .map(|storage| DeviceInfo {
-                    category: "Storage".to_string(),
-                    name: storage.model.clone(),
-                    vendor: storage.vendor.clone().unwrap_or("Unknown".to_string()),
-                    model: storage.model.clone(),
-                    status: "✅ Fully Supported".to_string(),
-                    details: vec![
-                        format!("Type: {}", storage.device_type),
-                        format!(
-                            "Size: {:.1} GB",
-                            storage.size_bytes as f64 / (1024.0 * 1024.0 * 1024.0)
-                        ),
-                        format!(
-                            "Interface: {}",
-                            storage.interface.clone().unwrap_or("Unknown".to_string())
-                        ),
-                    ],
-                })
-                .collect();
-            total_devices += storage_devices.len();
-            self.devices_by_category.insert("Storage".to_string(), storage_devices);
-        }
-
-        // Network devices
-        if !report.network.is_empty() {
-            let network_devices: Vec<DeviceInfo> = report
-                .network
-                .iter()
-                .map(|net| DeviceInfo {
-                    category: "Network".to_string(),
-                    name: net.model.clone(),
-                    vendor: net.vendor.clone(),
-                    model: net.model.clone(),
-                    status: if net.driver.is_some() {
-                        "✅ Fully Supported".to_string()
-                    } else {
-                        "⚠️ Driver Required".to_string()
-                    },
-                    details: vec![
-                        format!("Type: {}", net.device_type),
-                        format!(
-                            "Driver: {}",
-                            net.driver.clone().unwrap_or("Not loaded".to_string())
-                        ),
-                        format!("MAC: {}", net.anonymized_mac),
-                    ],
-                })
-                .collect();
-            total_devices += network_devices.len();
-            self.devices_by_category.insert("Network".to_string(), network_devices);
-        }
-
-        // Audio devices
-        if !report.audio.is_empty() {
-            let audio_devices: Vec<DeviceInfo> = report
-                .audio
-                .iter()
-                .map(|audio| DeviceInfo {
-                    category: "Audio".to_string(),
-                    name: format!("{} Audio", audio.vendor),
-                    vendor: audio.vendor.clone(),
-                    model: audio.device_type.clone(),
-                    status: "✅ Fully Supported".to_string(),
-                    details: vec![
-                        format!("Type: {}", audio.device_type),
-                        format!(
-                            "Driver: {}",
-                            audio.driver.clone().unwrap_or("Built-in".to_string())
-                        ),
-                        format!("Model: {}", audio.model),
-                    ],
-                })
-                .collect();
-            total_devices += audio_devices.len();
-            self.devices_by_category.insert("Audio".to_string(), audio_devices);
-        }
-
-        // USB devices
-        if !report.usb.is_empty() {
-            let usb_devices: Vec<DeviceInfo> = report
-                .usb
-                .iter()
-                .map(|usb| DeviceInfo {
-                    category: "USB".to_string(),
-                    name: usb
-                        .product_name
-                        .clone()
-                        .unwrap_or(format!("USB Device {}:{}", usb.vendor_id, usb.product_id)),
-                    vendor: usb.vendor_name.clone().unwrap_or("Unknown".to_string()),
-                    model: usb.product_name.clone().unwrap_or("Unknown".to_string()),
-                    status: "✅ Fully Supported".to_string(),
-                    details: vec![
-                        format!("Vendor ID: {}", usb.vendor_id),
-                        format!("Product ID: {}", usb.product_id),
-                        format!(
-                            "USB Version: {}",
-                            usb.usb_version.clone().unwrap_or("Unknown".to_string())
-                        ),
-                    ],
-                })
-                .collect();
-            total_devices += usb_devices.len();
-            self.devices_by_category.insert("USB".to_string(), usb_devices);
-        }
-
-        self.device_count = total_devices as i32;
+        self.device_count = devices.len() as i32;
     }
 
     /// Get devices for a specific category
@@ -315,24 +115,12 @@ impl HardwareManager {
     }
 
     pub fn get_supported_count(&self) -> i32 {
-        // Count devices with "✅" status
-        self.get_all_devices().iter().filter(|device| device.status.contains("✅")).count() as i32
+        self.get_count_by_status(CompatibilityBadge::Supported.key())
     }
 
+    /// Count devices by badge key ("supported", "partial", "driver-required", ...)
     pub fn get_count_by_status(&self, status: &str) -> i32 {
-        match status {
-            "supported" => self.get_supported_count(),
-            "partial" => {
-                self.get_all_devices().iter().filter(|d| d.status.contains("⚠️")).count() as i32
-            }
-            "unsupported" => {
-                self.get_all_devices().iter().filter(|d| d.status.contains("❌")).count() as i32
-            }
-            "unknown" => {
-                self.get_all_devices().iter().filter(|d| d.status.contains("❓")).count() as i32
-            }
-            _ => 0,
-        }
+        self.get_all_devices().iter().filter(|device| device.badge.key() == status).count() as i32
     }
 }
 
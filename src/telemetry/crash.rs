@@ -0,0 +1,386 @@
+//! Opt-in diagnostic bundles for crashes
+//!
+//! When enabled, a panic hook writes a JSON bundle with the panic message and
+//! location, the backtrace, the command line reduced to lx-hw-detect's own
+//! vocabulary, the detection phase that panicked, and the versions of
+//! the detection tools. The bundle stays on disk; the user is told where it is
+//! and how to attach it to a bug report.
+
+use crate::detectors::DetectorRegistry;
+use crate::errors::{LxHwError, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::any::TypeId;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::future::Future;
+use std::path::{Path, PathBuf};
+
+/// Issue form crash bundles are attached to
+const BUG_REPORT_URL: &str =
+    "https://github.com/olafkfreund/lx-hw-db/issues/new?template=bug-report.yml";
+
+/// Placeholder for command line values outside the CLI's vocabulary
+const REDACTED: &str = "<redacted>";
+
+/// Flags whose values are secrets, redacted whatever they look like
+const SECRET_FLAGS: &[&str] = &["--pseudonym-passphrase", "--github-token"];
+
+tokio::task_local! {
+    /// Detection phase of the future being polled
+    static PHASE: String;
+}
+
+/// Diagnostic information captured when lx-hw-detect panics
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CrashBundle {
+    /// lx-hw-detect version
+    pub version: String,
+    /// When the panic happened
+    pub created_at: DateTime<Utc>,
+    /// Panic message
+    pub message: String,
+    /// Source location of the panic
+    pub location: Option<String>,
+    /// Detection phase that panicked, if the panic happened in one
+    pub phase: Option<String>,
+    /// Command line with values outside the CLI's vocabulary redacted
+    pub args: Vec<String>,
+    /// Running kernel release
+    pub kernel: Option<String>,
+    /// CPU architecture
+    pub architecture: String,
+    /// First line of each detection tool's version output, None if missing
+    pub tool_versions: BTreeMap<String, Option<String>>,
+    /// Backtrace, one frame line per entry
+    pub backtrace: Vec<String>,
+}
+
+impl CrashBundle {
+    /// Capture the state of the process for a panic
+    pub fn capture(message: String, location: Option<String>, args: Vec<String>) -> Self {
+        let tool_versions = DetectorRegistry::new()
+            .list_detectors()
+            .into_iter()
+            .filter(|detector| !detector.is_builtin())
+            .map(|detector| {
+                let program = detector.program();
+                (program.to_string(), super::tool_version_blocking(program))
+            })
+            .collect();
+
+        Self {
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            created_at: Utc::now(),
+            message,
+            location,
+            phase: current_phase(),
+            args,
+            kernel: std::fs::read_to_string("/proc/sys/kernel/osrelease")
+                .ok()
+                .map(|release| release.trim().to_string()),
+            architecture: std::env::consts::ARCH.to_string(),
+            tool_versions,
+            backtrace: std::backtrace::Backtrace::force_capture()
+                .to_string()
+                .lines()
+                .map(str::to_string)
+                .collect(),
+        }
+    }
+
+    /// Write the bundle into `dir`, returning the file's path
+    pub fn write(&self, dir: &Path) -> Result<PathBuf> {
+        std::fs::create_dir_all(dir)?;
+        let path = dir.join(format!(
+            "crash-{}-{}.json",
+            self.created_at.format("%Y%m%dT%H%M%SZ"),
+            std::process::id()
+        ));
+        let json = serde_json::to_string_pretty(self)
+            .map_err(|e| LxHwError::SerializationError(e.to_string()))?;
+        std::fs::write(&path, json)?;
+        Ok(path)
+    }
+}
+
+/// Default directory for crash bundles
+pub fn default_dir() -> PathBuf {
+    super::state_dir().join("crashes")
+}
+
+/// Run `future` as a detection phase, recorded in the bundle if it panics
+///
+/// The phase is set only while `future` itself is polled, so detectors run
+/// concurrently on one task each report their own, and a nested phase
+/// overrides the outer one.
+pub async fn in_phase<F: Future>(phase: &str, future: F) -> F::Output {
+    PHASE.scope(phase.to_string(), future).await
+}
+
+fn current_phase() -> Option<String> {
+    PHASE.try_with(Clone::clone).ok()
+}
+
+/// Replace the panic hook with one writing a [`CrashBundle`] to [`default_dir`]
+///
+/// `args` should already be reduced with [`sanitize_args`].
+pub fn install(args: Vec<String>) {
+    std::panic::set_hook(Box::new(move |info| {
+        let payload = info.payload();
+        let message = payload
+            .downcast_ref::<&str>()
+            .map(|message| message.to_string())
+            .or_else(|| payload.downcast_ref::<String>().cloned())
+            .unwrap_or_else(|| "unknown panic".to_string());
+        let location = info.location().map(ToString::to_string);
+
+        eprintln!("lx-hw-detect hit an internal error: {}", message);
+        let bundle = CrashBundle::capture(message, location, args.clone());
+        match bundle.write(&default_dir()) {
+            Ok(path) => {
+                eprintln!("A diagnostic bundle was written to:\n  {}", path.display());
+                eprintln!(
+                    "It holds the backtrace, the redacted command line, the detection phase and \
+                     tool versions. Review it, then attach it to a bug report at:\n  {}",
+                    BUG_REPORT_URL
+                );
+            }
+            Err(e) => {
+                eprintln!("Could not write a diagnostic bundle: {}", e);
+                eprintln!("{}", std::backtrace::Backtrace::force_capture());
+            }
+        }
+    }));
+}
+
+/// Reduce a command line to the CLI's own vocabulary
+///
+/// Flags, subcommand names and possible values are kept, as are numbers given
+/// to flags that only accept numbers; anything else, such as paths, hosts and
+/// tokens, is replaced with `<redacted>`. Values of [`SECRET_FLAGS`] are
+/// always redacted.
+pub fn sanitize_args(
+    args: impl IntoIterator<Item = String>,
+    command: &clap::Command,
+) -> Vec<String> {
+    let mut vocabulary = Vocabulary::default();
+    vocabulary.collect(command);
+
+    let mut args = args.into_iter();
+    let program = args.next().map(|program| {
+        Path::new(&program)
+            .file_name()
+            .map_or(program.clone(), |name| name.to_string_lossy().into_owned())
+    });
+
+    // Flag whose value is the next argument
+    let mut pending: Option<String> = None;
+    program
+        .into_iter()
+        .chain(args.map(|arg| {
+            if let Some(flag) = pending.take() {
+                vocabulary.value(&flag, &arg).to_string()
+            } else if let Some((flag, value)) =
+                arg.split_once('=').filter(|_| arg.starts_with("--"))
+            {
+                format!("{}={}", flag, vocabulary.value(flag, value))
+            } else if arg.starts_with("--") || (arg.starts_with('-') && arg.len() <= 2) {
+                if vocabulary.value_flags.contains_key(&arg) {
+                    pending = Some(arg.clone());
+                }
+                arg
+            } else if arg.starts_with('-') {
+                // A short flag with its value attached
+                let flag: String = arg.chars().take(2).collect();
+                format!("{}{}", flag, vocabulary.value(&flag, &arg[flag.len()..]))
+            } else if vocabulary.words.contains(&arg) {
+                arg
+            } else {
+                REDACTED.to_string()
+            }
+        }))
+        .collect()
+}
+
+/// What a reduced command line may keep
+#[derive(Default)]
+struct Vocabulary {
+    /// Subcommand names, their aliases and possible values
+    words: HashSet<String>,
+    /// Flags taking a value, and whether every definition parses it as a number
+    value_flags: HashMap<String, bool>,
+}
+
+impl Vocabulary {
+    fn collect(&mut self, command: &clap::Command) {
+        for arg in command.get_arguments() {
+            for value in arg.get_possible_values() {
+                self.words.extend(value.get_name_and_aliases().map(str::to_string));
+            }
+            if arg.is_positional() || !arg.get_action().takes_values() {
+                continue;
+            }
+            let numeric = is_numeric(arg);
+            let longs = arg.get_long_and_visible_aliases().into_iter().flatten();
+            let shorts = arg.get_short_and_visible_aliases().into_iter().flatten();
+            let flags = longs
+                .map(|long| format!("--{}", long))
+                .chain(shorts.map(|short| format!("-{}", short)));
+            for flag in flags {
+                *self.value_flags.entry(flag).or_insert(numeric) &= numeric;
+            }
+        }
+        for subcommand in command.get_subcommands() {
+            self.words.insert(subcommand.get_name().to_string());
+            self.words.extend(subcommand.get_all_aliases().map(str::to_string));
+            self.collect(subcommand);
+        }
+    }
+
+    /// `value` as given to `flag`, or the placeholder when it cannot be kept
+    fn value<'a>(&self, flag: &str, value: &'a str) -> &'a str {
+        let number = self.value_flags.get(flag) == Some(&true) && value.parse::<f64>().is_ok();
+        if !SECRET_FLAGS.contains(&flag) && (number || self.words.contains(value)) {
+            value
+        } else {
+            REDACTED
+        }
+    }
+}
+
+/// Whether clap parses the argument's values as numbers
+fn is_numeric(arg: &clap::Arg) -> bool {
+    let parsed = arg.get_value_parser().type_id();
+    [
+        TypeId::of::<u8>(),
+        TypeId::of::<u16>(),
+        TypeId::of::<u32>(),
+        TypeId::of::<u64>(),
+        TypeId::of::<usize>(),
+        TypeId::of::<i32>(),
+        TypeId::of::<i64>(),
+        TypeId::of::<f32>(),
+        TypeId::of::<f64>(),
+    ]
+    .into_iter()
+    .any(|numeric| parsed == numeric)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::Cli;
+    use clap::CommandFactory;
+
+    fn sanitize(args: &[&str]) -> Vec<String> {
+        sanitize_args(args.iter().map(|arg| arg.to_string()), &Cli::command())
+    }
+
+    #[test]
+    fn test_sanitize_args_keeps_only_cli_vocabulary() {
+        assert_eq!(
+            sanitize(&[
+                "/home/user/bin/lx-hw-detect",
+                "detect",
+                "--format",
+                "json",
+                "--output",
+                "/home/user/report.json",
+                "--timeout=5",
+                "--remote=user@workstation",
+                "-oreport.json",
+                "-v",
+                "-",
+            ]),
+            vec![
+                "lx-hw-detect",
+                "detect",
+                "--format",
+                "json",
+                "--output",
+                REDACTED,
+                "--timeout=5",
+                "--remote=<redacted>",
+                "-o<redacted>",
+                "-v",
+                "-",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_sanitize_args_keeps_numbers_only_for_numeric_flags() {
+        assert_eq!(
+            sanitize(&[
+                "lx-hw-detect",
+                "detect",
+                "--timeout",
+                "30",
+                "--digest-length=12",
+                "--remote",
+                "10",
+                "--pseudonym-passphrase",
+                "1234",
+                "--pseudonym-passphrase=json",
+                "42",
+            ]),
+            vec![
+                "lx-hw-detect",
+                "detect",
+                "--timeout",
+                "30",
+                "--digest-length=12",
+                "--remote",
+                REDACTED,
+                "--pseudonym-passphrase",
+                REDACTED,
+                "--pseudonym-passphrase=<redacted>",
+                REDACTED,
+            ]
+        );
+        assert_eq!(
+            sanitize(&["lx-hw-detect", "submit", "--github-token", "123456"]),
+            vec!["lx-hw-detect", "submit", "--github-token", REDACTED]
+        );
+    }
+
+    #[test]
+    fn test_bundle_round_trip() {
+        let bundle = CrashBundle::capture(
+            "index out of bounds".to_string(),
+            Some("src/detectors/lspci.rs:10:5".to_string()),
+            vec!["lx-hw-detect".to_string(), "detect".to_string()],
+        );
+        assert!(!bundle.backtrace.is_empty());
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = bundle.write(dir.path()).unwrap();
+        let written: CrashBundle =
+            serde_json::from_str(&std::fs::read_to_string(path).unwrap()).unwrap();
+        assert_eq!(written, bundle);
+    }
+
+    #[tokio::test]
+    async fn test_phase_is_tracked_per_future() {
+        // The first detector resumes after the second has entered its phase
+        let (resume, resumed) = tokio::sync::oneshot::channel();
+        let first = in_phase("lspci", async {
+            resumed.await.unwrap();
+            current_phase()
+        });
+        let second = in_phase("lsusb", async {
+            let phase = current_phase();
+            resume.send(()).unwrap();
+            phase
+        });
+
+        let (first, second) = futures::join!(first, second);
+        assert_eq!(first.as_deref(), Some("lspci"));
+        assert_eq!(second.as_deref(), Some("lsusb"));
+        assert_eq!(
+            in_phase("report", in_phase("cpu", async { current_phase() })).await.as_deref(),
+            Some("cpu")
+        );
+        assert_eq!(current_phase(), None);
+    }
+}
@@ -0,0 +1,383 @@
+//! Opt-in reporting of parser failures
+//!
+//! When a detection tool produces output that a parser cannot handle, the
+//! failure is reduced to a fingerprint: the tool name and version, the
+//! parser that failed, the error category, and a truncated SHA-256 of the start
+//! of the tool output. No raw output, error text, or hardware identifiers
+//! leave the machine. Nothing is sent unless the user opts in, and
+//! submissions are rate limited and deduplicated through a local state file.
+
+use crate::errors::{LxHwError, Result};
+use chrono::{DateTime, Duration, Utc};
+use ring::digest;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+pub mod crash;
+
+/// Issue form used when no collection endpoint is configured
+const ISSUE_TEMPLATE_URL: &str =
+    "https://github.com/olafkfreund/lx-hw-db/issues/new?template=parser-failure.yml";
+
+/// Bytes of tool output included in the excerpt hash
+const EXCERPT_BYTES: usize = 512;
+
+/// Hex characters kept from each hash
+const HASH_CHARS: usize = 16;
+
+/// Maximum submissions in any 24 hour window
+const MAX_SUBMISSIONS_PER_DAY: usize = 3;
+
+/// How long an already reported fingerprint is suppressed
+const DEDUP_DAYS: i64 = 30;
+
+/// Anonymous description of a parser failure
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ParseFailure {
+    /// Detection tool whose output failed to parse
+    pub tool: String,
+    /// First line of the tool's version output, if available
+    pub tool_version: Option<String>,
+    /// Parser that failed, e.g. lspci::parse_output
+    pub parser: String,
+    /// Error category (never the error message)
+    pub error_kind: String,
+    /// Truncated SHA-256 of the start of the tool output
+    pub excerpt_hash: String,
+}
+
+impl ParseFailure {
+    /// Build a fingerprint from a failed parse of `output`
+    pub fn new(tool: &str, output: &[u8], error: &LxHwError) -> Self {
+        Self::with_kind(tool, output, error_kind(error))
+    }
+
+    /// Build a fingerprint from a parse error a detector reported alongside
+    /// its (partial) result for `output`
+    pub fn reported(tool: &str, output: &[u8]) -> Self {
+        Self::with_kind(tool, output, "ParseError")
+    }
+
+    fn with_kind(tool: &str, output: &[u8], error_kind: &str) -> Self {
+        let excerpt = &output[..output.len().min(EXCERPT_BYTES)];
+        Self {
+            tool: tool.to_string(),
+            tool_version: None,
+            parser: format!("{}::parse_output", tool),
+            error_kind: error_kind.to_string(),
+            excerpt_hash: short_hash(excerpt),
+        }
+    }
+
+    /// Stable identifier used for deduplication
+    pub fn fingerprint(&self) -> String {
+        short_hash(
+            format!(
+                "{}|{}|{}|{}|{}",
+                self.tool,
+                self.tool_version.as_deref().unwrap_or(""),
+                self.parser,
+                self.error_kind,
+                self.excerpt_hash
+            )
+            .as_bytes(),
+        )
+    }
+
+    /// GitHub issue URL with the parser failure form pre-filled
+    pub fn issue_url(&self) -> String {
+        let title = format!("[Parser] {} output could not be parsed", self.tool);
+        let mut url = format!("{}&title={}", ISSUE_TEMPLATE_URL, urlencoding::encode(&title));
+        for (field, value) in [
+            ("tool", self.tool.as_str()),
+            ("tool-version", self.tool_version.as_deref().unwrap_or("unknown")),
+            ("parser", self.parser.as_str()),
+            ("error-kind", self.error_kind.as_str()),
+            ("excerpt-hash", self.excerpt_hash.as_str()),
+        ] {
+            url.push_str(&format!("&{}={}", field, urlencoding::encode(value)));
+        }
+        url
+    }
+}
+
+/// Category name of an error, without its message
+fn error_kind(error: &LxHwError) -> &'static str {
+    match error {
+        LxHwError::DetectionError(_) => "DetectionError",
+        LxHwError::SerializationError(_) => "SerializationError",
+        LxHwError::SystemCommandError { .. } => "SystemCommandError",
+        LxHwError::InvalidInput { .. } => "InvalidInput",
+        LxHwError::IoError(_) | LxHwError::Io(_) => "IoError",
+        _ => "Other",
+    }
+}
+
+fn short_hash(data: &[u8]) -> String {
+    let digest = digest::digest(&digest::SHA256, data);
+    hex::encode(digest.as_ref())[..HASH_CHARS].to_string()
+}
+
+/// Query the version of a detection tool
+pub async fn tool_version(tool: &str) -> Option<String> {
+    let output = tokio::process::Command::new(tool).arg(version_flag(tool)).output().await.ok()?;
+    version_line(output)
+}
+
+/// Query the version of a detection tool without an async runtime
+pub fn tool_version_blocking(tool: &str) -> Option<String> {
+    let output = std::process::Command::new(tool).arg(version_flag(tool)).output().ok()?;
+    version_line(output)
+}
+
+fn version_flag(tool: &str) -> &'static str {
+    match tool {
+        "lshw" => "-version",
+        _ => "--version",
+    }
+}
+
+fn version_line(output: std::process::Output) -> Option<String> {
+    // Some tools print their version on stderr
+    let text = if output.stdout.is_empty() { output.stderr } else { output.stdout };
+    String::from_utf8_lossy(&text).lines().next().map(|line| line.trim().to_string())
+}
+
+/// Directory for lx-hw-db state such as the submission log and crash bundles
+pub fn state_dir() -> PathBuf {
+    std::env::var_os("XDG_STATE_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|h| PathBuf::from(h).join(".local/state")))
+        .unwrap_or_else(std::env::temp_dir)
+        .join("lx-hw-db")
+}
+
+/// Record of previous submissions used for rate limiting
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct SubmissionLog {
+    submissions: Vec<LoggedSubmission>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct LoggedSubmission {
+    fingerprint: String,
+    submitted_at: DateTime<Utc>,
+}
+
+impl SubmissionLog {
+    /// Default location of the submission log
+    pub fn default_path() -> PathBuf {
+        state_dir().join("telemetry.json")
+    }
+
+    /// Load the log, starting empty if it is missing or unreadable
+    pub fn load(path: &Path) -> Self {
+        std::fs::read_to_string(path)
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default()
+    }
+
+    /// Persist the log
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let json = serde_json::to_string_pretty(self)
+            .map_err(|e| LxHwError::SerializationError(e.to_string()))?;
+        std::fs::write(path, json)?;
+        Ok(())
+    }
+
+    /// Whether a failure may be submitted at `now`
+    pub fn allows(&self, failure: &ParseFailure, now: DateTime<Utc>) -> bool {
+        let fingerprint = failure.fingerprint();
+        let recent =
+            self.submissions.iter().filter(|s| now - s.submitted_at < Duration::hours(24)).count();
+        let duplicate = self.submissions.iter().any(|s| {
+            s.fingerprint == fingerprint && now - s.submitted_at < Duration::days(DEDUP_DAYS)
+        });
+
+        recent < MAX_SUBMISSIONS_PER_DAY && !duplicate
+    }
+
+    /// Record a submission and drop entries older than the dedup window
+    pub fn record(&mut self, failure: &ParseFailure, now: DateTime<Utc>) {
+        self.submissions.retain(|s| now - s.submitted_at < Duration::days(DEDUP_DAYS));
+        self.submissions
+            .push(LoggedSubmission { fingerprint: failure.fingerprint(), submitted_at: now });
+    }
+}
+
+/// Where parser failure reports are delivered
+#[derive(Debug, Clone)]
+pub enum TelemetryTarget {
+    /// POST the fingerprint as JSON to a collection endpoint
+    Endpoint(String),
+    /// Print a pre-filled GitHub issue URL for the user to open
+    IssueTemplate,
+}
+
+/// Submit parser failures, skipping rate-limited and duplicate entries
+///
+/// Returns the failures that were actually reported.
+pub async fn report_parse_failures(
+    failures: Vec<ParseFailure>,
+    target: &TelemetryTarget,
+    log_path: &Path,
+) -> Result<Vec<ParseFailure>> {
+    let mut log = SubmissionLog::load(log_path);
+    let mut reported = Vec::new();
+    let mut error = None;
+
+    for mut failure in failures {
+        if failure.tool_version.is_none() {
+            failure.tool_version = tool_version(&failure.tool).await;
+        }
+
+        let now = Utc::now();
+        if !log.allows(&failure, now) {
+            log::info!("Skipping parser failure report for {} (rate limited)", failure.tool);
+            continue;
+        }
+
+        // Stop at the first failed submission, but keep the log of those
+        // already sent so they are not sent again
+        if let Err(e) = submit(&failure, target).await {
+            error = Some(e);
+            break;
+        }
+        log.record(&failure, now);
+        reported.push(failure);
+    }
+
+    log.save(log_path)?;
+    match error {
+        Some(e) => Err(e),
+        None => Ok(reported),
+    }
+}
+
+async fn submit(failure: &ParseFailure, target: &TelemetryTarget) -> Result<()> {
+    match target {
+        #[cfg(feature = "http")]
+        TelemetryTarget::Endpoint(url) => {
+            reqwest::Client::new()
+                .post(url)
+                .json(failure)
+                .send()
+                .await
+                .and_then(|response| response.error_for_status())
+                .map_err(|e| LxHwError::SystemError {
+                    message: format!("Failed to submit parser failure: {}", e),
+                })?;
+        }
+        #[cfg(not(feature = "http"))]
+        TelemetryTarget::Endpoint(_) => {
+            return Err(LxHwError::ConfigError(
+                "lx-hw-detect was built without HTTP support; rebuild with `--features http`"
+                    .to_string(),
+            ));
+        }
+        TelemetryTarget::IssueTemplate => {
+            eprintln!("Report this parser failure at:\n  {}", failure.issue_url());
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn failure() -> ParseFailure {
+        ParseFailure::new(
+            "lspci",
+            b"00:00.0 Host bridge: serial ABC123",
+            &LxHwError::DetectionError("unexpected field 'serial ABC123'".to_string()),
+        )
+    }
+
+    #[test]
+    fn test_fingerprint_excludes_raw_content() {
+        let failure = failure();
+        let json = serde_json::to_string(&failure).unwrap();
+
+        assert_eq!(failure.error_kind, "DetectionError");
+        assert_eq!(failure.excerpt_hash.len(), HASH_CHARS);
+        assert!(!json.contains("ABC123"));
+        assert!(!failure.issue_url().contains("ABC123"));
+    }
+
+    #[test]
+    fn test_excerpt_hash_uses_output_prefix() {
+        let error = LxHwError::DetectionError(String::new());
+        let mut long = vec![b'a'; EXCERPT_BYTES];
+        let prefix = ParseFailure::new("lsusb", &long, &error);
+        long.extend_from_slice(b"trailing");
+
+        assert_eq!(prefix.excerpt_hash, ParseFailure::new("lsusb", &long, &error).excerpt_hash);
+    }
+
+    #[test]
+    fn test_rate_limit_and_dedup() {
+        let mut log = SubmissionLog::default();
+        let now = Utc::now();
+        let failure = failure();
+
+        assert!(log.allows(&failure, now));
+        log.record(&failure, now);
+        assert!(!log.allows(&failure, now));
+        assert!(log.allows(&failure, now + Duration::days(DEDUP_DAYS + 1)));
+
+        for i in 0..MAX_SUBMISSIONS_PER_DAY {
+            let mut other = failure.clone();
+            other.excerpt_hash = format!("{:016}", i);
+            log.record(&other, now);
+        }
+        let mut fresh = failure.clone();
+        fresh.tool = "lsusb".to_string();
+        assert!(!log.allows(&fresh, now));
+        assert!(log.allows(&fresh, now + Duration::hours(25)));
+    }
+
+    #[cfg(feature = "http")]
+    #[tokio::test]
+    async fn test_failed_submission_keeps_earlier_ones_logged() {
+        use std::io::{Read, Write};
+
+        // Accept the first submission and reject the second
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/", listener.local_addr().unwrap());
+        let server = std::thread::spawn(move || {
+            for status in ["200 OK", "500 Internal Server Error"] {
+                let (mut stream, _) = listener.accept().unwrap();
+                let mut request = [0u8; 4096];
+                let _ = stream.read(&mut request).unwrap();
+                let response = format!(
+                    "HTTP/1.1 {}\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+                    status
+                );
+                stream.write_all(response.as_bytes()).unwrap();
+            }
+        });
+
+        let dir = tempfile::tempdir().unwrap();
+        let log_path = dir.path().join("telemetry.json");
+        let mut failures = vec![failure(), failure()];
+        failures[1].excerpt_hash = "0".repeat(HASH_CHARS);
+        for failure in &mut failures {
+            failure.tool_version = Some("test".to_string());
+        }
+
+        let result =
+            report_parse_failures(failures.clone(), &TelemetryTarget::Endpoint(url), &log_path)
+                .await;
+        server.join().unwrap();
+        assert!(result.is_err());
+
+        let log = SubmissionLog::load(&log_path);
+        assert!(!log.allows(&failures[0], Utc::now()));
+        assert!(log.allows(&failures[1], Utc::now()));
+    }
+}
@@ -0,0 +1,314 @@
+//! Terminal UI for browsing the compatibility database offline
+//!
+//! A two-pane browser over [`IndexBrowser`]: a filterable list of hardware
+//! models on the left and, for the selected model, compatibility scores by
+//! kernel plus reported issues and workarounds on the right. Entries matching
+//! hardware detected on this machine are marked and can be shown on their own.
+
+use crate::errors::Result;
+use crate::indexer::browse::{BrowseEntry, IndexBrowser};
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use ratatui::layout::{Constraint, Layout, Rect};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, List, ListItem, ListState, Paragraph, Row, Table, Wrap};
+use ratatui::{DefaultTerminal, Frame};
+use std::collections::HashSet;
+
+/// Run the browser until the user quits
+///
+/// `local` holds positions of entries matching this machine's hardware.
+pub fn run(browser: &IndexBrowser, local: Vec<usize>) -> Result<()> {
+    let mut terminal = ratatui::try_init()?;
+    let result = BrowseApp::new(browser, local).run(&mut terminal);
+    ratatui::restore();
+    result
+}
+
+/// Browser state independent of the terminal
+pub struct BrowseApp<'a> {
+    browser: &'a IndexBrowser,
+    local: HashSet<usize>,
+    query: String,
+    editing_query: bool,
+    local_only: bool,
+    results: Vec<usize>,
+    list_state: ListState,
+    quit: bool,
+}
+
+impl<'a> BrowseApp<'a> {
+    pub fn new(browser: &'a IndexBrowser, local: Vec<usize>) -> Self {
+        let mut app = Self {
+            browser,
+            local: local.into_iter().collect(),
+            query: String::new(),
+            editing_query: false,
+            local_only: false,
+            results: Vec::new(),
+            list_state: ListState::default(),
+            quit: false,
+        };
+        app.refresh();
+        app
+    }
+
+    fn run(&mut self, terminal: &mut DefaultTerminal) -> Result<()> {
+        while !self.quit {
+            terminal.draw(|frame| self.draw(frame))?;
+            if let Event::Key(key) = event::read()? {
+                if key.kind == KeyEventKind::Press {
+                    self.handle_key(key);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Entry currently highlighted in the list
+    pub fn selected(&self) -> Option<&'a BrowseEntry> {
+        let position = *self.results.get(self.list_state.selected()?)?;
+        self.browser.entries().get(position)
+    }
+
+    pub fn handle_key(&mut self, key: KeyEvent) {
+        if key.modifiers.contains(KeyModifiers::CONTROL) && key.code == KeyCode::Char('c') {
+            self.quit = true;
+            return;
+        }
+
+        if self.editing_query {
+            match key.code {
+                KeyCode::Esc | KeyCode::Enter => self.editing_query = false,
+                KeyCode::Backspace => {
+                    self.query.pop();
+                    self.refresh();
+                }
+                KeyCode::Char(c) => {
+                    self.query.push(c);
+                    self.refresh();
+                }
+                _ => {}
+            }
+            return;
+        }
+
+        match key.code {
+            KeyCode::Char('q') | KeyCode::Esc => self.quit = true,
+            KeyCode::Char('/') => self.editing_query = true,
+            KeyCode::Char('l') => {
+                self.local_only = !self.local_only;
+                self.refresh();
+            }
+            KeyCode::Down | KeyCode::Char('j') => self.list_state.select_next(),
+            KeyCode::Up | KeyCode::Char('k') => self.list_state.select_previous(),
+            KeyCode::PageDown => self.list_state.scroll_down_by(10),
+            KeyCode::PageUp => self.list_state.scroll_up_by(10),
+            KeyCode::Home => self.list_state.select_first(),
+            KeyCode::End => self.list_state.select_last(),
+            _ => {}
+        }
+    }
+
+    /// Recompute the result list after the query or filter changed
+    fn refresh(&mut self) {
+        self.results = self.browser.search(&self.query);
+        if self.local_only {
+            self.results.retain(|position| self.local.contains(position));
+        }
+        self.list_state.select((!self.results.is_empty()).then_some(0));
+    }
+
+    fn draw(&mut self, frame: &mut Frame) {
+        let [search, body, help] =
+            Layout::vertical([Constraint::Length(3), Constraint::Min(0), Constraint::Length(1)])
+                .areas(frame.area());
+        let [list, details] =
+            Layout::horizontal([Constraint::Percentage(40), Constraint::Percentage(60)])
+                .areas(body);
+
+        self.draw_search(frame, search);
+        self.draw_list(frame, list);
+        self.draw_details(frame, details);
+
+        let help_text = if self.editing_query {
+            "Type to search  Enter/Esc: done"
+        } else {
+            "/: search  l: this machine only  j/k: move  q: quit"
+        };
+        frame
+            .render_widget(Paragraph::new(help_text).style(Style::new().fg(Color::DarkGray)), help);
+    }
+
+    fn draw_search(&self, frame: &mut Frame, area: Rect) {
+        let title = if self.local_only { " Search (this machine) " } else { " Search " };
+        let style = if self.editing_query { Style::new().fg(Color::Yellow) } else { Style::new() };
+        let search = Paragraph::new(self.query.as_str())
+            .block(Block::default().borders(Borders::ALL).title(title).border_style(style));
+        frame.render_widget(search, area);
+        if self.editing_query {
+            let cursor_x = area.x + 1 + self.query.chars().count() as u16;
+            frame.set_cursor_position((cursor_x.min(area.right() - 2), area.y + 1));
+        }
+    }
+
+    fn draw_list(&mut self, frame: &mut Frame, area: Rect) {
+        let entries = self.browser.entries();
+        let items: Vec<ListItem> = self
+            .results
+            .iter()
+            .map(|&position| {
+                let entry = &entries[position];
+                let marker = if self.local.contains(&position) { "● " } else { "  " };
+                ListItem::new(Line::from(vec![
+                    Span::styled(marker, Style::new().fg(Color::Green)),
+                    Span::raw(entry.key()),
+                ]))
+            })
+            .collect();
+
+        let title = format!(" Hardware ({}/{}) ", self.results.len(), entries.len());
+        let list = List::new(items)
+            .block(Block::default().borders(Borders::ALL).title(title))
+            .highlight_style(Style::new().add_modifier(Modifier::REVERSED));
+        frame.render_stateful_widget(list, area, &mut self.list_state);
+    }
+
+    fn draw_details(&self, frame: &mut Frame, area: Rect) {
+        let block = Block::default().borders(Borders::ALL).title(" Details ");
+        let Some(entry) = self.selected() else {
+            frame.render_widget(Paragraph::new("No matching hardware").block(block), area);
+            return;
+        };
+        let inner = block.inner(area);
+        frame.render_widget(block, area);
+
+        let scores = self.browser.kernel_scores(entry);
+        let [header, table, issues] = Layout::vertical([
+            Constraint::Length(3),
+            Constraint::Length(scores.len().clamp(1, 12) as u16 + 2),
+            Constraint::Min(0),
+        ])
+        .areas(inner);
+
+        let known_issues = self.browser.known_issues(entry);
+        let device_id = known_issues.and_then(|issues| issues.device_id.as_deref());
+        let header_lines = vec![
+            Line::styled(entry.key(), Style::new().add_modifier(Modifier::BOLD)),
+            Line::from(format!("Category: {}", entry.component_type)),
+            Line::from(format!("Device ID: {}", device_id.unwrap_or("unknown"))),
+        ];
+        frame.render_widget(Paragraph::new(header_lines), header);
+
+        let rows = scores.iter().map(|s| {
+            Row::new(vec![
+                s.kernel.to_string(),
+                s.distribution.to_string(),
+                format!("{}%", s.score.score),
+                s.score.driver.clone().unwrap_or_else(|| "-".to_string()),
+                s.score.sample_size.to_string(),
+            ])
+            .style(Style::new().fg(score_color(s.score.score)))
+        });
+        let scores_table = Table::new(
+            rows,
+            [
+                Constraint::Percentage(28),
+                Constraint::Percentage(28),
+                Constraint::Length(6),
+                Constraint::Percentage(24),
+                Constraint::Length(8),
+            ],
+        )
+        .header(
+            Row::new(vec!["Kernel", "Distribution", "Score", "Driver", "Reports"])
+                .style(Style::new().add_modifier(Modifier::BOLD)),
+        )
+        .block(Block::default().borders(Borders::TOP).title(" Compatibility by kernel "));
+        frame.render_widget(scores_table, table);
+
+        let mut lines = Vec::new();
+        match known_issues {
+            Some(known) => {
+                lines.push(Line::styled(
+                    format!("Known issues ({} reports)", known.report_count),
+                    Style::new().add_modifier(Modifier::BOLD),
+                ));
+                lines.extend(known.issues.iter().map(|issue| Line::from(format!("• {}", issue))));
+                if !known.workarounds.is_empty() {
+                    lines.push(Line::default());
+                    lines.push(Line::styled(
+                        "Workarounds",
+                        Style::new().add_modifier(Modifier::BOLD),
+                    ));
+                    lines.extend(
+                        known.workarounds.iter().map(|fix| Line::from(format!("• {}", fix))),
+                    );
+                }
+            }
+            None => lines.push(Line::from("No known issues reported")),
+        }
+        frame.render_widget(
+            Paragraph::new(lines)
+                .wrap(Wrap { trim: false })
+                .block(Block::default().borders(Borders::TOP)),
+            issues,
+        );
+    }
+}
+
+fn score_color(score: u8) -> Color {
+    match score {
+        80.. => Color::Green,
+        50..=79 => Color::Yellow,
+        _ => Color::Red,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::indexer::{CompatibilityMatrix, KnownIssuesIndex, VendorEntry, VendorIndex};
+    use chrono::Utc;
+    use std::collections::HashMap;
+
+    fn key(code: KeyCode) -> KeyEvent {
+        KeyEvent::new(code, KeyModifiers::NONE)
+    }
+
+    #[test]
+    fn test_search_and_local_filter() {
+        let vendor = VendorEntry {
+            total_reports: 1,
+            components: HashMap::from([(
+                "gpu".to_string(),
+                vec!["Arc A770".to_string(), "Iris Xe".to_string()],
+            )]),
+            recent_reports: Vec::new(),
+            compatibility_score: 0.0,
+            last_updated: Utc::now(),
+        };
+        let by_vendor = VendorIndex::from([("Intel".to_string(), vendor)]);
+        let browser =
+            IndexBrowser::new(&by_vendor, CompatibilityMatrix::new(), KnownIssuesIndex::new());
+        let mut app = BrowseApp::new(&browser, vec![1]);
+        assert_eq!(app.selected().unwrap().model, "Arc A770");
+
+        for code in [KeyCode::Char('/'), KeyCode::Char('x'), KeyCode::Char('e'), KeyCode::Enter] {
+            app.handle_key(key(code));
+        }
+        assert_eq!(app.selected().unwrap().model, "Iris Xe");
+
+        // Clear the query, then keep only this machine's hardware
+        for code in [KeyCode::Char('/'), KeyCode::Backspace, KeyCode::Backspace, KeyCode::Esc] {
+            app.handle_key(key(code));
+        }
+        assert_eq!(app.results, vec![0, 1]);
+        app.handle_key(key(KeyCode::Char('l')));
+        assert_eq!(app.results, vec![1]);
+
+        assert!(!app.quit);
+        app.handle_key(key(KeyCode::Char('q')));
+        assert!(app.quit);
+    }
+}
@@ -271,24 +271,23 @@ fn validate_system_coherence(
     // Validate CPU architecture alignment
     if let Some(cpu) = &report.cpu {
         match arch.as_str() {
-            "x86_64" => {
-                if cpu.vendor != "Intel" && cpu.vendor != "AMD" && !cpu.vendor.contains("Virtual") {
-                    warnings.push(format!(
-                        "x86_64 architecture with unexpected CPU vendor: {}",
-                        cpu.vendor
-                    ));
-                }
+            "x86_64"
+                if cpu.vendor != "Intel"
+                    && cpu.vendor != "AMD"
+                    && !cpu.vendor.contains("Virtual") =>
+            {
+                warnings.push(format!(
+                    "x86_64 architecture with unexpected CPU vendor: {}",
+                    cpu.vendor
+                ));
             }
-            "aarch64" | "armv7l" => {
+            "aarch64" | "armv7l"
                 if !cpu.vendor.to_lowercase().contains("arm")
                     && !cpu.vendor.to_lowercase().contains("qualcomm")
-                    && !cpu.vendor.to_lowercase().contains("apple")
-                {
-                    warnings.push(format!(
-                        "ARM architecture with unexpected CPU vendor: {}",
-                        cpu.vendor
-                    ));
-                }
+                    && !cpu.vendor.to_lowercase().contains("apple") =>
+            {
+                warnings
+                    .push(format!("ARM architecture with unexpected CPU vendor: {}", cpu.vendor));
             }
             _ => {}
         }
@@ -418,6 +417,13 @@ mod tests {
                 privacy_level: PrivacyLevel::Basic,
                 tools_used: vec!["lshw".to_string(), "dmidecode".to_string()],
                 anonymized_system_id: "test_id_123456".to_string(),
+                phase_errors: Vec::new(),
+                sandbox: None,
+                privileges: None,
+                remote: false,
+                replayed: false,
+                salt_context: None,
+                digest_length: None,
             },
             system: SystemInfo {
                 anonymized_hostname: "test_host_456789".to_string(),
@@ -425,6 +431,7 @@ mod tests {
                 distribution: Some("NixOS 25.11".to_string()),
                 architecture: "x86_64".to_string(),
                 boot_time: Some(Utc::now()),
+                virtualization: None,
             },
             cpu: Some(CpuInfo {
                 model: "AMD Ryzen 9 5950X".to_string(),
@@ -437,10 +444,14 @@ mod tests {
                 cache_l2: Some(8388608),
                 cache_l3: Some(67108864),
                 flags: vec!["fpu".to_string(), "vme".to_string(), "de".to_string()],
+                scaling_governor: None,
+                microcode: None,
             }),
             memory: Some(MemoryInfo {
                 total_bytes: 68719476736,     // 64GB
                 available_bytes: 34359738368, // 32GB available
+                dimm_count: None,
+                swap: None,
                 dimms: vec![
                     MemoryDimm {
                         size_bytes: 34359738368, // 32GB
@@ -457,11 +468,25 @@ mod tests {
                 ],
             }),
             storage: Vec::new(),
+            storage_stack: None,
             graphics: Vec::new(),
             network: Vec::new(),
             usb: Vec::new(),
             audio: Vec::new(),
+            audio_routing: None,
+            radios: Vec::new(),
+            input: Vec::new(),
+            peripherals: None,
+            pcie_links: Vec::new(),
+            sensors: Vec::new(),
+            firmware: None,
             kernel_support: None,
+            unknown_devices: Vec::new(),
+            lifecycle_notices: Vec::new(),
+            firmware_updates: Vec::new(),
+            user_notes: Vec::new(),
+            blobs: Vec::new(),
+            provenance: None,
         }
     }
 
@@ -4,7 +4,6 @@ use crate::hardware::{HardwareReport, PrivacyLevel};
 use crate::validation::{HardwareReportValidator, ValidationConfig, ValidationResult};
 use crate::LxHwError;
 use clap::Args;
-use std::fs;
 use std::path::{Path, PathBuf};
 
 /// CLI arguments for validation command
@@ -63,6 +62,7 @@ pub async fn execute_validate(args: ValidateArgs) -> Result<(), LxHwError> {
         require_kernel_support: args.require_kernel_support,
         minimum_device_count: args.minimum_devices,
         validate_hardware_compatibility: !args.skip_compatibility,
+        custom_rules: Vec::new(),
     };
 
     let validator = HardwareReportValidator::with_config(config);
@@ -122,15 +122,11 @@ pub async fn execute_validate(args: ValidateArgs) -> Result<(), LxHwError> {
 /// Validate a single file
 async fn validate_single_file(
     validator: &HardwareReportValidator,
-    file_path: &PathBuf,
+    file_path: &Path,
     args: &ValidateArgs,
 ) -> Result<ValidationResult, LxHwError> {
     // Read and parse the report file
-    let content = fs::read_to_string(file_path).map_err(|e| LxHwError::SystemError {
-        message: format!("Failed to read {}: {}", file_path.display(), e),
-    })?;
-
-    let report: HardwareReport = parse_report_file(&content, file_path)?;
+    let report = HardwareReport::load(file_path)?;
 
     // Validate the report
     let result = validator.validate(&report);
@@ -232,32 +228,6 @@ fn print_yaml_results(
     Ok(())
 }
 
-/// Parse report file content based on extension or content detection
-fn parse_report_file(content: &str, file_path: &Path) -> Result<HardwareReport, LxHwError> {
-    match file_path.extension().and_then(|ext| ext.to_str()) {
-        Some("json") => serde_json::from_str(content).map_err(|e| LxHwError::InvalidInput {
-            message: format!("Invalid JSON in {}: {}", file_path.display(), e),
-        }),
-        Some("yaml") | Some("yml") => {
-            serde_yaml::from_str(content).map_err(|e| LxHwError::InvalidInput {
-                message: format!("Invalid YAML in {}: {}", file_path.display(), e),
-            })
-        }
-        _ => {
-            // Try JSON first, then YAML
-            serde_json::from_str(content).or_else(|_| {
-                serde_yaml::from_str(content).map_err(|e| LxHwError::InvalidInput {
-                    message: format!(
-                        "Could not parse {} as JSON or YAML: {}",
-                        file_path.display(),
-                        e
-                    ),
-                })
-            })
-        }
-    }
-}
-
 /// Parse privacy level string
 fn parse_privacy_level(level_str: &str) -> Result<PrivacyLevel, LxHwError> {
     match level_str.to_lowercase().as_str() {
@@ -289,6 +259,13 @@ mod tests {
                 privacy_level: PrivacyLevel::Basic,
                 tools_used: vec!["lshw".to_string()],
                 anonymized_system_id: "test_id_123456".to_string(),
+                phase_errors: Vec::new(),
+                sandbox: None,
+                privileges: None,
+                remote: false,
+                replayed: false,
+                salt_context: None,
+                digest_length: None,
             },
             system: SystemInfo {
                 anonymized_hostname: "test_host_456789".to_string(),
@@ -296,15 +273,30 @@ mod tests {
                 distribution: Some("NixOS 25.11".to_string()),
                 architecture: "x86_64".to_string(),
                 boot_time: Some(Utc::now()),
+                virtualization: None,
             },
             cpu: None,
             memory: None,
             storage: Vec::new(),
+            storage_stack: None,
             graphics: Vec::new(),
             network: Vec::new(),
             usb: Vec::new(),
             audio: Vec::new(),
+            audio_routing: None,
+            radios: Vec::new(),
+            input: Vec::new(),
+            peripherals: None,
+            pcie_links: Vec::new(),
+            sensors: Vec::new(),
+            firmware: None,
             kernel_support: None,
+            unknown_devices: Vec::new(),
+            lifecycle_notices: Vec::new(),
+            firmware_updates: Vec::new(),
+            user_notes: Vec::new(),
+            blobs: Vec::new(),
+            provenance: None,
         }
     }
 
@@ -36,7 +36,7 @@ fn validate_cross_references(
     // Check CPU and memory relationship
     if let (Some(cpu), Some(memory)) = (&report.cpu, &report.memory) {
         // Validate memory channels vs CPU support
-        let dimm_count = memory.dimms.len();
+        let dimm_count = memory.dimm_count();
         let expected_channels = match cpu.cores {
             1..=4 => 2,   // Dual channel
             5..=8 => 4,   // Quad channel
@@ -67,15 +67,14 @@ fn validate_cross_references(
     // Check graphics and system architecture consistency
     for graphics in &report.graphics {
         match report.system.architecture.as_str() {
-            "aarch64" | "armv7l" => {
+            "aarch64" | "armv7l"
                 if graphics.vendor.to_lowercase().contains("nvidia")
-                    || graphics.vendor.to_lowercase().contains("amd")
-                {
-                    warnings.push(format!(
-                        "Discrete GPU '{}' on ARM architecture is unusual",
-                        graphics.model
-                    ));
-                }
+                    || graphics.vendor.to_lowercase().contains("amd") =>
+            {
+                warnings.push(format!(
+                    "Discrete GPU '{}' on ARM architecture is unusual",
+                    graphics.model
+                ));
             }
             _ => {}
         }
@@ -577,6 +576,13 @@ mod tests {
                 privacy_level: PrivacyLevel::Basic,
                 tools_used: vec!["lshw".to_string(), "dmidecode".to_string()],
                 anonymized_system_id: "test_id_123456".to_string(),
+                phase_errors: Vec::new(),
+                sandbox: None,
+                privileges: None,
+                remote: false,
+                replayed: false,
+                salt_context: None,
+                digest_length: None,
             },
             system: SystemInfo {
                 anonymized_hostname: "test_host_456789".to_string(),
@@ -584,6 +590,7 @@ mod tests {
                 distribution: Some("NixOS 25.11".to_string()),
                 architecture: "x86_64".to_string(),
                 boot_time: Some(Utc::now() - chrono::Duration::hours(2)),
+                virtualization: None,
             },
             cpu: Some(CpuInfo {
                 model: "AMD Ryzen 7 5800X".to_string(),
@@ -596,10 +603,14 @@ mod tests {
                 cache_l2: Some(4194304),
                 cache_l3: Some(33554432),
                 flags: vec!["fpu".to_string(), "vme".to_string()],
+                scaling_governor: None,
+                microcode: None,
             }),
             memory: Some(MemoryInfo {
                 total_bytes: 34359738368,     // 32GB
                 available_bytes: 17179869184, // 16GB available
+                dimm_count: None,
+                swap: None,
                 dimms: vec![
                     MemoryDimm {
                         size_bytes: 17179869184, // 16GB
@@ -616,11 +627,25 @@ mod tests {
                 ],
             }),
             storage: Vec::new(),
+            storage_stack: None,
             graphics: Vec::new(),
             network: Vec::new(),
             usb: Vec::new(),
             audio: Vec::new(),
+            audio_routing: None,
+            radios: Vec::new(),
+            input: Vec::new(),
+            peripherals: None,
+            pcie_links: Vec::new(),
+            sensors: Vec::new(),
+            firmware: None,
             kernel_support: None,
+            unknown_devices: Vec::new(),
+            lifecycle_notices: Vec::new(),
+            firmware_updates: Vec::new(),
+            user_notes: Vec::new(),
+            blobs: Vec::new(),
+            provenance: None,
         }
     }
 
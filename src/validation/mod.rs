@@ -6,6 +6,7 @@
 //! - Privacy anonymization verification to ensure PII protection
 //! - Data consistency checks across different hardware components
 //! - Hardware compatibility validation against kernel support data
+//! - User-defined rules composed through [`ValidationConfig::builder`]
 //!
 //! # Examples
 //!
@@ -41,8 +42,11 @@ pub mod cli;
 pub mod consistency;
 pub mod constants;
 pub mod privacy;
+pub mod rules;
 pub mod schema;
 
+pub use rules::{RuleSeverity, ValidationConfigBuilder, ValidationRule};
+
 /// Validation errors that can occur during report validation
 #[derive(Debug, Clone, Error, Serialize, Deserialize, PartialEq, Eq)]
 #[non_exhaustive]
@@ -61,6 +65,9 @@ pub enum ValidationError {
 
     #[error("Hardware compatibility validation failed: {device} - {message}")]
     CompatibilityError { device: String, message: String },
+
+    #[error("Custom rule '{rule}' failed: {message}")]
+    CustomRuleError { rule: String, message: String },
 }
 
 /// Validation result with confidence score
@@ -81,6 +88,7 @@ pub struct ValidationConfig {
     pub require_kernel_support: bool,
     pub minimum_device_count: Option<u32>,
     pub validate_hardware_compatibility: bool,
+    pub custom_rules: Vec<ValidationRule>,
 }
 
 /// Confidence score impact constants for different validation failures
@@ -90,6 +98,7 @@ mod confidence_impact {
     pub const PRIVACY_ERROR: f64 = 0.7;
     pub const CONSISTENCY_ERROR: f64 = 0.9;
     pub const COMPATIBILITY_ERROR: f64 = 0.85;
+    pub const CUSTOM_RULE_ERROR: f64 = 0.8;
     pub const WARNING_PENALTY_PER_WARNING: f64 = 0.05;
 }
 
@@ -139,9 +148,34 @@ impl HardwareReportValidator {
                 .unwrap_or_else(|e| result.add_error(e, confidence_impact::COMPATIBILITY_ERROR));
         }
 
+        // 6. Custom Rules
+        self.validate_custom_rules(report, &mut result);
+
         result.build()
     }
 
+    /// Evaluate user-registered custom rules
+    fn validate_custom_rules(&self, report: &HardwareReport, result: &mut ValidationResultBuilder) {
+        for rule in &self.config.custom_rules {
+            let Err(message) = rule.evaluate(report) else {
+                continue;
+            };
+
+            match rule.severity() {
+                RuleSeverity::Error => result.add_error(
+                    ValidationError::CustomRuleError { rule: rule.name().to_string(), message },
+                    confidence_impact::CUSTOM_RULE_ERROR,
+                ),
+                RuleSeverity::Warning => {
+                    result.add_warnings([format!("{}: {}", rule.name(), message)])
+                }
+                RuleSeverity::Suggestion => {
+                    result.add_suggestions([format!("{}: {}", rule.name(), message)])
+                }
+            }
+        }
+    }
+
     /// Validate report against JSON schema
     fn validate_schema(&self, report: &HardwareReport) -> Result<(), ValidationError> {
         schema::validate_report_schema(report)
@@ -205,6 +239,7 @@ impl Default for ValidationConfig {
             require_kernel_support: false,
             minimum_device_count: None,
             validate_hardware_compatibility: true,
+            custom_rules: Vec::new(),
         }
     }
 }
@@ -298,6 +333,13 @@ mod tests {
                 privacy_level: PrivacyLevel::Basic,
                 tools_used: vec!["lshw".to_string()],
                 anonymized_system_id: "test_id_123".to_string(),
+                phase_errors: Vec::new(),
+                sandbox: None,
+                privileges: None,
+                remote: false,
+                replayed: false,
+                salt_context: None,
+                digest_length: None,
             },
             system: SystemInfo {
                 anonymized_hostname: "test_host_456".to_string(),
@@ -305,15 +347,30 @@ mod tests {
                 distribution: Some("NixOS 25.11".to_string()),
                 architecture: "x86_64".to_string(),
                 boot_time: Some(Utc::now()),
+                virtualization: None,
             },
             cpu: None,
             memory: None,
             storage: Vec::new(),
+            storage_stack: None,
             graphics: Vec::new(),
             network: Vec::new(),
             usb: Vec::new(),
             audio: Vec::new(),
+            audio_routing: None,
+            radios: Vec::new(),
+            input: Vec::new(),
+            peripherals: None,
+            pcie_links: Vec::new(),
+            sensors: Vec::new(),
+            firmware: None,
             kernel_support: None,
+            unknown_devices: Vec::new(),
+            lifecycle_notices: Vec::new(),
+            firmware_updates: Vec::new(),
+            user_notes: Vec::new(),
+            blobs: Vec::new(),
+            provenance: None,
         }
     }
 
@@ -340,6 +397,7 @@ mod tests {
             require_kernel_support: true,
             minimum_device_count: Some(5),
             validate_hardware_compatibility: true,
+            custom_rules: Vec::new(),
         };
 
         let validator = HardwareReportValidator::with_config(config);
@@ -349,4 +407,32 @@ mod tests {
         // Should have validation errors due to strict requirements
         assert!(!result.errors.is_empty());
     }
+
+    #[test]
+    fn test_custom_rules_by_severity() {
+        let config = ValidationConfig::builder()
+            .rule(ValidationRule::new("needs-cpu", RuleSeverity::Error, |report| {
+                report.cpu.as_ref().map(|_| ()).ok_or_else(|| "no CPU".to_string())
+            }))
+            .rule(ValidationRule::new("needs-gpu", RuleSeverity::Warning, |report| {
+                if report.graphics.is_empty() {
+                    Err("no GPU".to_string())
+                } else {
+                    Ok(())
+                }
+            }))
+            .rule(ValidationRule::new("always-ok", RuleSeverity::Suggestion, |_| Ok(())))
+            .build();
+
+        let result =
+            HardwareReportValidator::with_config(config).validate(&create_minimal_report());
+
+        assert!(!result.valid);
+        assert!(result.errors.contains(&ValidationError::CustomRuleError {
+            rule: "needs-cpu".to_string(),
+            message: "no CPU".to_string(),
+        }));
+        assert!(result.warnings.contains(&"needs-gpu: no GPU".to_string()));
+        assert!(!result.suggestions.iter().any(|s| s.starts_with("always-ok")));
+    }
 }
@@ -1,8 +1,13 @@
 //! Privacy validation for hardware reports
 
+use crate::hardware::notes::find_personal_data;
 use crate::hardware::{HardwareReport, PrivacyLevel};
+use crate::privacy::{digest, rekey_mac, FULL_DIGEST_LENGTH, MIN_DIGEST_LENGTH};
 use crate::validation::{ValidationConfig, ValidationError};
 use regex::Regex;
+use ring::hmac;
+use ring::rand::SystemRandom;
+use std::collections::HashMap;
 use std::sync::OnceLock;
 
 /// Validate privacy compliance and anonymization
@@ -18,6 +23,7 @@ pub fn validate_privacy_compliance(
 
     // Check for potential PII leaks
     validate_no_pii_leaks(report)?;
+    validate_user_notes(report)?;
 
     // Validate anonymization strength
     validate_anonymization_strength(report)?;
@@ -58,6 +64,20 @@ fn validate_privacy_level_requirements(
 
 /// Validate that sensitive fields are properly anonymized
 fn validate_anonymized_fields(report: &HardwareReport) -> Result<(), ValidationError> {
+    // Truncated digests; reports without a length use full digests
+    let digest_length = report.metadata.digest_length;
+    if let Some(length) = digest_length {
+        if !(MIN_DIGEST_LENGTH..=FULL_DIGEST_LENGTH).contains(&length) {
+            return Err(ValidationError::PrivacyError {
+                field: "metadata.digest_length".to_string(),
+                message: format!(
+                    "Digest length {} is outside {}..={}",
+                    length, MIN_DIGEST_LENGTH, FULL_DIGEST_LENGTH
+                ),
+            });
+        }
+    }
+
     // Check system ID anonymization
     validate_anonymized_id(
         &report.metadata.anonymized_system_id,
@@ -82,6 +102,7 @@ fn validate_anonymized_fields(report: &HardwareReport) -> Result<(), ValidationE
         validate_mac_address_anonymization(
             &network.anonymized_mac,
             &format!("network[{}].anonymized_mac", index),
+            digest_length,
         )?;
     }
 
@@ -149,7 +170,22 @@ fn get_mac_regex() -> &'static Regex {
 }
 
 /// Validate MAC address anonymization
-fn validate_mac_address_anonymization(mac: &str, field_name: &str) -> Result<(), ValidationError> {
+///
+/// Besides OUI-preserving MACs, accepts the forms detection writes: "unknown"
+/// when no MAC was found and a hex HMAC. Full digests are too long to be a
+/// MAC; truncated ones are accepted only at the report's declared digest
+/// length, since a 12-character digest looks like a MAC without separators.
+fn validate_mac_address_anonymization(
+    mac: &str,
+    field_name: &str,
+    digest_length: Option<usize>,
+) -> Result<(), ValidationError> {
+    let is_digest = mac.chars().all(|c| c.is_ascii_hexdigit())
+        && (mac.len() >= 32 || Some(mac.len()) == digest_length);
+    if mac == "unknown" || is_digest {
+        return Ok(());
+    }
+
     // Check MAC address format using cached regex
     if !get_mac_regex().is_match(mac) {
         return Err(ValidationError::PrivacyError {
@@ -192,7 +228,7 @@ fn validate_mac_address_anonymization(mac: &str, field_name: &str) -> Result<(),
 /// Compiled PII detection patterns for performance
 static PII_PATTERNS: OnceLock<Vec<(Regex, &'static str)>> = OnceLock::new();
 
-fn get_pii_patterns() -> &'static Vec<(Regex, &'static str)> {
+pub(crate) fn get_pii_patterns() -> &'static Vec<(Regex, &'static str)> {
     PII_PATTERNS.get_or_init(|| {
         [
             (r"\b[A-Za-z0-9._%+-]+@[A-Za-z0-9.-]+\.[A-Z|a-z]{2,}\b", "email address"),
@@ -237,6 +273,25 @@ fn validate_no_pii_leaks(report: &HardwareReport) -> Result<(), ValidationError>
     Ok(())
 }
 
+/// Check the notes the user wrote for personal data
+///
+/// Notes are checked on their own: free text mentioning a hardware vendor
+/// is not exempt the way detected device names are.
+fn validate_user_notes(report: &HardwareReport) -> Result<(), ValidationError> {
+    for (i, note) in report.user_notes.iter().enumerate() {
+        if let Some(description) = find_personal_data(&note.note) {
+            return Err(ValidationError::PrivacyError {
+                field: format!("user_notes[{}]", i),
+                message: format!(
+                    "Potential PII detected ({}) in the note on {}",
+                    description, note.component
+                ),
+            });
+        }
+    }
+    Ok(())
+}
+
 /// Validate anonymization strength based on privacy level
 fn validate_anonymization_strength(report: &HardwareReport) -> Result<(), ValidationError> {
     match report.metadata.privacy_level {
@@ -337,8 +392,89 @@ fn validate_strict_anonymization(report: &HardwareReport) -> Result<(), Validati
                 message: "Strict privacy requires at least 16-character storage serial".to_string(),
             });
         }
+        let nvme_health = storage.nvme.as_ref().is_some_and(|nvme| nvme.health.is_some());
+        if storage.health.is_some() || nvme_health {
+            return Err(ValidationError::PrivacyError {
+                field: format!("storage[{}].health", index),
+                message: "Strict privacy reports must not contain drive health data".to_string(),
+            });
+        }
+    }
+
+    Ok(())
+}
+
+/// Fewest reports that should share a hardware combination before a report
+/// is considered safe to publish unchanged
+pub const DEFAULT_MIN_ANONYMITY: usize = 5;
+
+/// How many reports in the database would share a report's hardware
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AnonymityEstimate {
+    /// Upper bound on reports with the same hardware combination, this one
+    /// included: no more reports can share every component than share the
+    /// rarest one
+    pub k: usize,
+    /// Hardware with fewer than the threshold of reports, this one included
+    pub rare_hardware: Vec<String>,
+}
+
+impl AnonymityEstimate {
+    /// Warning to show before submission, if the report would stand out
+    pub fn warning(&self, threshold: usize) -> Option<String> {
+        (self.k < threshold).then(|| {
+            format!(
+                "Only {} report(s) in the database would share this hardware combination \
+                 (threshold {}); rare hardware: {}",
+                self.k,
+                threshold,
+                self.rare_hardware.join(", ")
+            )
+        })
     }
+}
+
+/// Estimate k-anonymity of a report from the published report count of each
+/// piece of its hardware, as given by `IndexBrowser::report_counts`
+pub fn estimate_anonymity(counts: &HashMap<String, usize>, threshold: usize) -> AnonymityEstimate {
+    let k = counts.values().min().map_or(1, |count| count + 1);
+    let mut rare_hardware: Vec<String> = counts
+        .iter()
+        .filter(|(_, count)| *count + 1 < threshold)
+        .map(|(key, _)| key.clone())
+        .collect();
+    rare_hardware.sort();
+    AnonymityEstimate { k, rare_hardware }
+}
 
+/// Generalize a report whose hardware combination is rare
+///
+/// The combination itself is what the database collects, so it stays;
+/// what goes are the details that would let the report be linked to other
+/// reports or data from the same machine. Storage serial and MAC address
+/// digests are re-keyed with a random key that is thrown away, keeping their
+/// format and MAC vendor prefixes, and DIMM manufacturers are dropped.
+pub fn generalize_report(report: &mut HardwareReport) -> Result<(), ValidationError> {
+    let key = hmac::Key::generate(hmac::HMAC_SHA256, &SystemRandom::new()).map_err(|_| {
+        ValidationError::PrivacyError {
+            field: "storage".to_string(),
+            message: "Failed to generate a key for generalization".to_string(),
+        }
+    })?;
+
+    for storage in &mut report.storage {
+        let length = storage.anonymized_serial.len();
+        storage.anonymized_serial = digest(&key, &storage.anonymized_serial, length);
+    }
+    for network in report.network.iter_mut().filter(|n| n.anonymized_mac != "unknown") {
+        network.anonymized_mac = rekey_mac(&network.anonymized_mac, &key)
+            .unwrap_or_else(|| digest(&key, &network.anonymized_mac, network.anonymized_mac.len()));
+    }
+    if let Some(memory) = &mut report.memory {
+        for dimm in &mut memory.dimms {
+            dimm.manufacturer = None;
+        }
+    }
     Ok(())
 }
 
@@ -416,6 +552,13 @@ mod tests {
                 privacy_level,
                 tools_used: vec!["lshw".to_string()],
                 anonymized_system_id: "abcd1234efgh5678".to_string(), // 16 chars
+                phase_errors: Vec::new(),
+                sandbox: None,
+                privileges: None,
+                remote: false,
+                replayed: false,
+                salt_context: None,
+                digest_length: None,
             },
             system: SystemInfo {
                 anonymized_hostname: "host_abcd1234efgh".to_string(), // 16 chars
@@ -423,10 +566,12 @@ mod tests {
                 distribution: Some("NixOS 25.11".to_string()),
                 architecture: "x86_64".to_string(),
                 boot_time: Some(Utc::now()),
+                virtualization: None,
             },
             cpu: None,
             memory: None,
             storage: Vec::new(),
+            storage_stack: None,
             graphics: Vec::new(),
             network: vec![NetworkDevice {
                 device_type: "ethernet".to_string(),
@@ -434,10 +579,24 @@ mod tests {
                 model: "I225-V Gigabit Network Connection".to_string(),
                 driver: Some("igc".to_string()),
                 anonymized_mac: "12:34:56:78:9a:bc".to_string(),
+                wireless_capabilities: None,
             }],
             usb: Vec::new(),
             audio: Vec::new(),
+            audio_routing: None,
+            radios: Vec::new(),
+            input: Vec::new(),
+            peripherals: None,
+            pcie_links: Vec::new(),
+            sensors: Vec::new(),
+            firmware: None,
             kernel_support: None,
+            unknown_devices: Vec::new(),
+            lifecycle_notices: Vec::new(),
+            firmware_updates: Vec::new(),
+            user_notes: Vec::new(),
+            blobs: Vec::new(),
+            provenance: None,
         }
     }
 
@@ -484,13 +643,105 @@ mod tests {
         let result = validate_privacy_compliance(&report, &config);
 
         assert!(result.is_err());
+
+        // An unseparated MAC is not anonymized
+        report.network[0].anonymized_mac = "3c7c3f123456".to_string();
+        assert!(validate_privacy_compliance(&report, &config).is_err());
+
+        // Hashed and missing MACs from detection are accepted
+        report.network[0].anonymized_mac = "ab".repeat(32);
+        assert!(validate_privacy_compliance(&report, &config).is_ok());
+        report.network[0].anonymized_mac = "unknown".to_string();
+        assert!(validate_privacy_compliance(&report, &config).is_ok());
+    }
+
+    #[test]
+    fn test_truncated_digests_accepted_at_declared_length() {
+        let config = ValidationConfig::default();
+        let mut report = create_test_report_with_privacy(PrivacyLevel::Basic);
+        report.metadata.anonymized_system_id = "3f9a1c0b7d2e".to_string();
+        report.network[0].anonymized_mac = "8b41f0c2d9a7".to_string();
+
+        // Without a declared length a 12-character digest could be a raw MAC
+        assert!(validate_privacy_compliance(&report, &config).is_err());
+        report.metadata.digest_length = Some(12);
+        assert!(validate_privacy_compliance(&report, &config).is_ok());
+
+        // Full digests from before truncation stay valid
+        report.network[0].anonymized_mac = "ab".repeat(32);
+        assert!(validate_privacy_compliance(&report, &config).is_ok());
+
+        report.metadata.digest_length = Some(6);
+        assert!(validate_privacy_compliance(&report, &config).is_err());
     }
 
     #[test]
     fn test_strict_privacy_requirements() {
-        let report = create_test_report_with_privacy(PrivacyLevel::Strict);
+        let mut report = create_test_report_with_privacy(PrivacyLevel::Strict);
         let config = ValidationConfig::default();
 
         assert!(validate_privacy_compliance(&report, &config).is_ok());
+
+        report.storage.push(crate::hardware::StorageDevice {
+            anonymized_serial: "3f9a1c7e5b2d8046a1e9c3b7d5f20864".to_string(),
+            device_type: "SSD".to_string(),
+            size_bytes: 500_107_862_016,
+            model: "Samsung SSD 860 EVO 500GB".to_string(),
+            vendor: None,
+            interface: Some("SATA".to_string()),
+            nvme: None,
+            health: Some(crate::hardware::DriveHealth {
+                power_on_hours: Some(21034),
+                ..Default::default()
+            }),
+            topology: None,
+        });
+        assert!(validate_privacy_compliance(&report, &config).is_err());
+        report.storage[0].health = None;
+        assert!(validate_privacy_compliance(&report, &config).is_ok());
+    }
+
+    #[test]
+    fn test_estimate_anonymity_from_rarest_hardware() {
+        let counts = HashMap::from([
+            ("Intel Wi-Fi 6 AX201".to_string(), 40),
+            ("Framework Laptop 13".to_string(), 2),
+            ("AMD Radeon 780M".to_string(), 12),
+        ]);
+        let estimate = estimate_anonymity(&counts, DEFAULT_MIN_ANONYMITY);
+        assert_eq!(estimate.k, 3);
+        assert_eq!(estimate.rare_hardware, ["Framework Laptop 13"]);
+        assert!(estimate.warning(DEFAULT_MIN_ANONYMITY).unwrap().contains("Framework Laptop 13"));
+        assert!(estimate.warning(3).is_none());
+
+        // Hardware nobody reported yet makes the report unique
+        let counts = HashMap::from([("Acme Prototype".to_string(), 0)]);
+        assert_eq!(estimate_anonymity(&counts, DEFAULT_MIN_ANONYMITY).k, 1);
+    }
+
+    #[test]
+    fn test_generalize_report_unlinks_identifiers() {
+        let config = ValidationConfig::default();
+        let mut report = create_test_report_with_privacy(PrivacyLevel::Basic);
+        report.storage.push(crate::hardware::StorageDevice {
+            anonymized_serial: "3f9a1c7e5b2d8046a1e9c3b7d5f20864".to_string(),
+            device_type: "SSD".to_string(),
+            size_bytes: 500_107_862_016,
+            model: "Samsung SSD 860 EVO 500GB".to_string(),
+            vendor: None,
+            interface: Some("SATA".to_string()),
+            nvme: None,
+            health: None,
+            topology: None,
+        });
+        let original = report.clone();
+
+        generalize_report(&mut report).unwrap();
+        assert_ne!(report.storage[0].anonymized_serial, original.storage[0].anonymized_serial);
+        assert_eq!(report.storage[0].anonymized_serial.len(), 32);
+        assert_ne!(report.network[0].anonymized_mac, original.network[0].anonymized_mac);
+        assert!(report.network[0].anonymized_mac.starts_with("12:34:56:"));
+        assert_eq!(report.metadata.anonymized_system_id, original.metadata.anonymized_system_id);
+        assert!(validate_privacy_compliance(&report, &config).is_ok());
     }
 }
@@ -0,0 +1,194 @@
+//! Programmatic construction of validation policies
+//!
+//! This module provides a builder for [`ValidationConfig`] and support for
+//! registering named custom rules, so downstream integrators can compose
+//! validation policies without modifying the built-in validators.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use lx_hw_detect::hardware::PrivacyLevel;
+//! use lx_hw_detect::validation::{RuleSeverity, ValidationConfig, ValidationRule};
+//!
+//! let config = ValidationConfig::builder()
+//!     .strict()
+//!     .require_privacy(PrivacyLevel::Enhanced)
+//!     .min_devices(5)
+//!     .rule(ValidationRule::new("cpu-present", RuleSeverity::Error, |report| {
+//!         report.cpu.as_ref().map(|_| ()).ok_or_else(|| "CPU information missing".to_string())
+//!     }))
+//!     .build();
+//!
+//! assert!(config.strict_mode);
+//! assert_eq!(config.custom_rules.len(), 1);
+//! ```
+
+use crate::hardware::{HardwareReport, PrivacyLevel};
+use crate::validation::ValidationConfig;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::sync::Arc;
+
+/// Signature of a custom rule check: `Err(message)` reports a violation
+pub type RuleCheck = dyn Fn(&HardwareReport) -> Result<(), String> + Send + Sync;
+
+/// Severity of a custom validation rule violation
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RuleSeverity {
+    /// Violation makes the report invalid
+    Error,
+    /// Violation is reported as a warning and lowers confidence
+    Warning,
+    /// Violation is reported as a suggestion only
+    Suggestion,
+}
+
+/// A named, user-supplied validation rule
+#[derive(Clone)]
+pub struct ValidationRule {
+    name: String,
+    severity: RuleSeverity,
+    check: Arc<RuleCheck>,
+}
+
+impl ValidationRule {
+    /// Create a new custom rule from a closure
+    pub fn new<F>(name: impl Into<String>, severity: RuleSeverity, check: F) -> Self
+    where
+        F: Fn(&HardwareReport) -> Result<(), String> + Send + Sync + 'static,
+    {
+        Self { name: name.into(), severity, check: Arc::new(check) }
+    }
+
+    /// Name of this rule
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Severity of this rule
+    pub fn severity(&self) -> RuleSeverity {
+        self.severity
+    }
+
+    /// Run the rule against a report
+    pub fn evaluate(&self, report: &HardwareReport) -> Result<(), String> {
+        (self.check)(report)
+    }
+}
+
+impl fmt::Debug for ValidationRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ValidationRule")
+            .field("name", &self.name)
+            .field("severity", &self.severity)
+            .finish_non_exhaustive()
+    }
+}
+
+// Rules are identified by name and severity; closures cannot be compared
+impl PartialEq for ValidationRule {
+    fn eq(&self, other: &Self) -> bool {
+        self.name == other.name && self.severity == other.severity
+    }
+}
+
+impl Eq for ValidationRule {}
+
+/// Builder for [`ValidationConfig`]
+#[derive(Debug, Clone, Default)]
+pub struct ValidationConfigBuilder {
+    config: ValidationConfig,
+}
+
+impl ValidationConfigBuilder {
+    /// Create a builder starting from the default configuration
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Enable strict validation mode
+    pub fn strict(mut self) -> Self {
+        self.config.strict_mode = true;
+        self
+    }
+
+    /// Require reports to be generated with at least the given privacy level
+    pub fn require_privacy(mut self, level: PrivacyLevel) -> Self {
+        self.config.privacy_level_required = Some(level);
+        self
+    }
+
+    /// Require kernel support information to be present
+    pub fn require_kernel_support(mut self) -> Self {
+        self.config.require_kernel_support = true;
+        self
+    }
+
+    /// Require a minimum number of detected devices
+    pub fn min_devices(mut self, count: u32) -> Self {
+        self.config.minimum_device_count = Some(count);
+        self
+    }
+
+    /// Skip hardware compatibility validation
+    pub fn skip_compatibility(mut self) -> Self {
+        self.config.validate_hardware_compatibility = false;
+        self
+    }
+
+    /// Register a custom rule
+    pub fn rule(mut self, rule: ValidationRule) -> Self {
+        self.config.custom_rules.push(rule);
+        self
+    }
+
+    /// Build the final configuration
+    pub fn build(self) -> ValidationConfig {
+        self.config
+    }
+}
+
+impl ValidationConfig {
+    /// Start building a validation configuration
+    pub fn builder() -> ValidationConfigBuilder {
+        ValidationConfigBuilder::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_builder_sets_fields() {
+        let config = ValidationConfig::builder()
+            .strict()
+            .require_privacy(PrivacyLevel::Enhanced)
+            .require_kernel_support()
+            .min_devices(5)
+            .skip_compatibility()
+            .build();
+
+        assert!(config.strict_mode);
+        assert_eq!(config.privacy_level_required, Some(PrivacyLevel::Enhanced));
+        assert!(config.require_kernel_support);
+        assert_eq!(config.minimum_device_count, Some(5));
+        assert!(!config.validate_hardware_compatibility);
+        assert!(config.custom_rules.is_empty());
+    }
+
+    #[test]
+    fn test_builder_defaults_match_default_config() {
+        assert_eq!(ValidationConfig::builder().build(), ValidationConfig::default());
+    }
+
+    #[test]
+    fn test_rule_equality_by_name_and_severity() {
+        let a = ValidationRule::new("rule", RuleSeverity::Warning, |_| Ok(()));
+        let b = ValidationRule::new("rule", RuleSeverity::Warning, |_| Err("x".to_string()));
+        let c = ValidationRule::new("rule", RuleSeverity::Error, |_| Ok(()));
+
+        assert_eq!(a, b);
+        assert_ne!(a, c);
+    }
+}
@@ -1,5 +1,6 @@
 //! JSON schema validation for hardware reports
 
+use crate::hardware::notes::MAX_NOTE_LENGTH;
 use crate::hardware::HardwareReport;
 use crate::validation::ValidationError;
 use serde_json::Value;
@@ -45,6 +46,12 @@ const HARDWARE_REPORT_SCHEMA: &str = r#"
           "type": "string",
           "minLength": 8,
           "description": "Anonymized system identifier"
+        },
+        "digest_length": {
+          "type": "integer",
+          "minimum": 12,
+          "maximum": 64,
+          "description": "Hex characters kept of each anonymized identifier; absent for full 64-character digests"
         }
       }
     },
@@ -75,6 +82,19 @@ const HARDWARE_REPORT_SCHEMA: &str = r#"
           "type": ["string", "null"],
           "format": "date-time",
           "description": "System boot time"
+        },
+        "virtualization": {
+          "type": ["object", "null"],
+          "required": ["kind", "source"],
+          "properties": {
+            "kind": {
+              "type": "string",
+              "enum": ["bare_metal", "virtual_machine", "container"]
+            },
+            "technology": {"type": ["string", "null"]},
+            "source": {"type": "string"}
+          },
+          "description": "Virtual machine or container the report was generated in"
         }
       }
     },
@@ -136,6 +156,10 @@ const HARDWARE_REPORT_SCHEMA: &str = r#"
             "type": "string"
           },
           "description": "CPU flags and features"
+        },
+        "scaling_governor": {
+          "type": ["string", "null"],
+          "description": "cpufreq scaling governor"
         }
       },
       "required": ["model", "vendor", "cores", "threads"]
@@ -356,6 +380,30 @@ const HARDWARE_REPORT_SCHEMA: &str = r#"
       },
       "description": "Audio devices"
     },
+    "firmware": {
+      "type": ["object", "null"],
+      "properties": {
+        "bios_vendor": {"type": ["string", "null"]},
+        "bios_version": {"type": ["string", "null"]},
+        "bios_date": {"type": ["string", "null"]},
+        "boot_mode": {
+          "type": "string",
+          "enum": ["uefi", "legacy"],
+          "description": "Whether the system booted through UEFI or legacy BIOS"
+        },
+        "secure_boot": {
+          "type": ["boolean", "null"],
+          "description": "Secure Boot state, null when unknown"
+        },
+        "tpm_present": {"type": "boolean"},
+        "tpm_version": {
+          "type": ["string", "null"],
+          "enum": ["2.0", "1.2", null]
+        }
+      },
+      "required": ["boot_mode", "tpm_present"],
+      "description": "System firmware and boot security state"
+    },
     "kernel_support": {
       "type": ["object", "null"],
       "properties": {
@@ -454,6 +502,27 @@ const HARDWARE_REPORT_SCHEMA: &str = r#"
         "missing_modules",
         "config_recommendations"
       ]
+    },
+    "user_notes": {
+      "type": "array",
+      "items": {
+        "type": "object",
+        "properties": {
+          "component": {
+            "type": "string",
+            "minLength": 1,
+            "description": "Vendor:device ID or category the note is on"
+          },
+          "note": {
+            "type": "string",
+            "minLength": 1,
+            "maxLength": 500,
+            "description": "Note written by the submitting user"
+          }
+        },
+        "required": ["component", "note"]
+      },
+      "description": "Notes the submitting user attached to components"
     }
   }
 }
@@ -614,6 +683,18 @@ fn validate_field_constraints(data: &Value, _schema: &Value) -> Result<(), Valid
         }
     }
 
+    // Validate user notes
+    if let Some(notes) = data.get("user_notes").and_then(|n| n.as_array()) {
+        for note in notes {
+            let length = note.get("note").and_then(|n| n.as_str()).map_or(0, |n| n.chars().count());
+            if length == 0 || length > MAX_NOTE_LENGTH {
+                return Err(ValidationError::SchemaError {
+                    message: format!("User notes must have 1 to {} characters", MAX_NOTE_LENGTH),
+                });
+            }
+        }
+    }
+
     Ok(())
 }
 
@@ -631,6 +712,13 @@ mod tests {
                 privacy_level: PrivacyLevel::Basic,
                 tools_used: vec!["lshw".to_string()],
                 anonymized_system_id: "test_id_123456".to_string(),
+                phase_errors: Vec::new(),
+                sandbox: None,
+                privileges: None,
+                remote: false,
+                replayed: false,
+                salt_context: None,
+                digest_length: None,
             },
             system: SystemInfo {
                 anonymized_hostname: "test_host_456789".to_string(),
@@ -638,15 +726,30 @@ mod tests {
                 distribution: Some("NixOS 25.11".to_string()),
                 architecture: "x86_64".to_string(),
                 boot_time: Some(Utc::now()),
+                virtualization: None,
             },
             cpu: None,
             memory: None,
             storage: Vec::new(),
+            storage_stack: None,
             graphics: Vec::new(),
             network: Vec::new(),
             usb: Vec::new(),
             audio: Vec::new(),
+            audio_routing: None,
+            radios: Vec::new(),
+            input: Vec::new(),
+            peripherals: None,
+            pcie_links: Vec::new(),
+            sensors: Vec::new(),
+            firmware: None,
             kernel_support: None,
+            unknown_devices: Vec::new(),
+            lifecycle_notices: Vec::new(),
+            firmware_updates: Vec::new(),
+            user_notes: Vec::new(),
+            blobs: Vec::new(),
+            provenance: None,
         }
     }
 
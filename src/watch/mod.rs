@@ -0,0 +1,388 @@
+//! Hardware watchlist
+//!
+//! Users who own, or are about to buy, a piece of hardware with poor Linux
+//! support can watch its device ID ("vendor:device"). Each `watch sync`
+//! loads the latest published indices, compares what they say about every
+//! watched device with what they said on the previous sync, and reports the
+//! differences: first reports, kernels where the device now works, kernels
+//! where it regressed, and newly reported issues and workarounds.
+//!
+//! The watchlist lives under `$XDG_CONFIG_HOME/lx-hw-db`; what the indices
+//! said at the last sync lives under `$XDG_STATE_HOME/lx-hw-db`. The first
+//! sync of a device only records a baseline.
+
+use crate::errors::{LxHwError, Result};
+use crate::indexer::browse::IndexBrowser;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// Compatibility score from which a device counts as working on a kernel
+pub const SUPPORTED_SCORE: u8 = 70;
+
+/// A watched device
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WatchedDevice {
+    /// PCI or USB device ID, lowercase ("8086:a0f0")
+    pub device_id: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+}
+
+/// Devices the user watches for database changes
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Watchlist {
+    #[serde(default)]
+    pub devices: Vec<WatchedDevice>,
+}
+
+impl Watchlist {
+    /// Default location, overridable with `LX_HW_WATCHLIST`
+    pub fn default_path() -> PathBuf {
+        if let Some(path) = std::env::var_os("LX_HW_WATCHLIST") {
+            return PathBuf::from(path);
+        }
+        let config_dir = std::env::var_os("XDG_CONFIG_HOME")
+            .map(PathBuf::from)
+            .or_else(|| std::env::var_os("HOME").map(|h| PathBuf::from(h).join(".config")))
+            .unwrap_or_else(std::env::temp_dir);
+        config_dir.join("lx-hw-db").join("watchlist.json")
+    }
+
+    /// Load the watchlist; a missing file is an empty watchlist
+    pub fn load(path: &Path) -> Result<Self> {
+        match std::fs::read_to_string(path) {
+            Ok(content) => serde_json::from_str(&content).map_err(|e| {
+                LxHwError::ConfigError(format!("Invalid watchlist {}: {}", path.display(), e))
+            }),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Persist the watchlist
+    pub fn save(&self, path: &Path) -> Result<()> {
+        save_json(self, path)
+    }
+
+    /// Watch a device, updating its note if it is already watched
+    ///
+    /// Returns whether the device was newly added.
+    pub fn add(&mut self, device_id: &str, note: Option<String>) -> Result<bool> {
+        let device_id = normalize_device_id(device_id)?;
+        if let Some(device) = self.devices.iter_mut().find(|d| d.device_id == device_id) {
+            if note.is_some() {
+                device.note = note;
+            }
+            return Ok(false);
+        }
+        self.devices.push(WatchedDevice { device_id, note });
+        Ok(true)
+    }
+
+    /// Stop watching a device; returns whether it was watched
+    pub fn remove(&mut self, device_id: &str) -> bool {
+        let device_id = device_id.to_lowercase();
+        let before = self.devices.len();
+        self.devices.retain(|device| device.device_id != device_id);
+        self.devices.len() != before
+    }
+}
+
+/// Validate a "vendor:device" ID of two hex numbers and lowercase it
+pub fn normalize_device_id(device_id: &str) -> Result<String> {
+    let valid = device_id.split_once(':').is_some_and(|(vendor, device)| {
+        [vendor, device].iter().all(|part| {
+            (1..=4).contains(&part.len()) && part.bytes().all(|b| b.is_ascii_hexdigit())
+        })
+    });
+    if !valid {
+        return Err(LxHwError::InvalidInput {
+            message: format!(
+                "'{}' is not a device ID of the form vendor:device (e.g. 8086:a0f0)",
+                device_id
+            ),
+        });
+    }
+    Ok(device_id.to_lowercase())
+}
+
+/// What the indices say about one hardware model
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ModelSnapshot {
+    /// Score per "kernel_distribution" key of the compatibility matrix
+    #[serde(default)]
+    pub scores: BTreeMap<String, u8>,
+    #[serde(default)]
+    pub issues: Vec<String>,
+    #[serde(default)]
+    pub workarounds: Vec<String>,
+}
+
+/// Hardware models reported with a device ID, keyed by "Vendor model"
+pub type DeviceSnapshot = BTreeMap<String, ModelSnapshot>;
+
+/// What the indices said about every watched device at the last sync
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct WatchState {
+    #[serde(default)]
+    pub devices: BTreeMap<String, DeviceSnapshot>,
+}
+
+impl WatchState {
+    /// Default location under `$XDG_STATE_HOME/lx-hw-db`
+    pub fn default_path() -> PathBuf {
+        let state_dir = std::env::var_os("XDG_STATE_HOME")
+            .map(PathBuf::from)
+            .or_else(|| std::env::var_os("HOME").map(|h| PathBuf::from(h).join(".local/state")))
+            .unwrap_or_else(std::env::temp_dir);
+        state_dir.join("lx-hw-db").join("watch-state.json")
+    }
+
+    /// Load the state, starting over if it is missing or unreadable
+    pub fn load(path: &Path) -> Self {
+        std::fs::read_to_string(path)
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default()
+    }
+
+    /// Persist the state
+    pub fn save(&self, path: &Path) -> Result<()> {
+        save_json(self, path)
+    }
+
+    /// Compare the indices with the last sync and remember what they say now
+    ///
+    /// Devices seen for the first time only get a baseline. Devices no
+    /// longer on the watchlist are forgotten.
+    pub fn sync(&mut self, watchlist: &Watchlist, browser: &IndexBrowser) -> Vec<WatchChange> {
+        let mut changes = Vec::new();
+        let mut devices = BTreeMap::new();
+        for watched in &watchlist.devices {
+            let current = snapshot(browser, &watched.device_id);
+            if let Some(previous) = self.devices.get(&watched.device_id) {
+                changes.extend(diff_snapshots(&watched.device_id, previous, &current));
+            }
+            devices.insert(watched.device_id.clone(), current);
+        }
+        self.devices = devices;
+        changes
+    }
+}
+
+/// Kind of change affecting a watched device
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WatchChangeKind {
+    /// Hardware with this device ID was reported for the first time
+    FirstReports,
+    /// A kernel and distribution on which the device now works
+    KernelSupport,
+    /// A kernel and distribution on which the device stopped working
+    Regression,
+    NewIssue,
+    NewWorkaround,
+}
+
+/// A change in the indices affecting a watched device
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WatchChange {
+    pub device_id: String,
+    /// Hardware model the change was reported for
+    pub hardware: String,
+    pub kind: WatchChangeKind,
+    pub detail: String,
+}
+
+impl std::fmt::Display for WatchChange {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let kind = match self.kind {
+            WatchChangeKind::FirstReports => "first reports",
+            WatchChangeKind::KernelSupport => "now supported",
+            WatchChangeKind::Regression => "regression",
+            WatchChangeKind::NewIssue => "new issue",
+            WatchChangeKind::NewWorkaround => "new workaround",
+        };
+        write!(f, "{} ({}): {}: {}", self.hardware, self.device_id, kind, self.detail)
+    }
+}
+
+/// What the indices currently say about a device ID
+pub fn snapshot(browser: &IndexBrowser, device_id: &str) -> DeviceSnapshot {
+    browser
+        .device_entries(device_id)
+        .into_iter()
+        .map(|position| {
+            let entry = &browser.entries()[position];
+            let scores = browser
+                .kernel_scores(entry)
+                .into_iter()
+                .map(|s| (format!("{}_{}", s.kernel, s.distribution), s.score.score))
+                .collect();
+            let (issues, workarounds) = browser
+                .known_issues(entry)
+                .map(|known| (known.issues.clone(), known.workarounds.clone()))
+                .unwrap_or_default();
+            (entry.key(), ModelSnapshot { scores, issues, workarounds })
+        })
+        .collect()
+}
+
+/// Changes between two snapshots of the same device
+pub fn diff_snapshots(
+    device_id: &str,
+    previous: &DeviceSnapshot,
+    current: &DeviceSnapshot,
+) -> Vec<WatchChange> {
+    let mut changes = Vec::new();
+    let mut change = |hardware: &str, kind, detail: String| {
+        changes.push(WatchChange {
+            device_id: device_id.to_string(),
+            hardware: hardware.to_string(),
+            kind,
+            detail,
+        })
+    };
+
+    for (hardware, model) in current {
+        let Some(old) = previous.get(hardware) else {
+            change(
+                hardware,
+                WatchChangeKind::FirstReports,
+                format!("reported on {} kernel/distribution combinations", model.scores.len()),
+            );
+            continue;
+        };
+
+        for (kernel, &score) in &model.scores {
+            let describe = |key: &str| key.replacen('_', " on ", 1);
+            match old.scores.get(kernel) {
+                Some(&before) if before >= SUPPORTED_SCORE && score < SUPPORTED_SCORE => change(
+                    hardware,
+                    WatchChangeKind::Regression,
+                    format!("{}: score dropped from {} to {}", describe(kernel), before, score),
+                ),
+                Some(&before) if before < SUPPORTED_SCORE && score >= SUPPORTED_SCORE => change(
+                    hardware,
+                    WatchChangeKind::KernelSupport,
+                    format!("{}: score rose from {} to {}", describe(kernel), before, score),
+                ),
+                None if score >= SUPPORTED_SCORE => change(
+                    hardware,
+                    WatchChangeKind::KernelSupport,
+                    format!("{}: works (score {})", describe(kernel), score),
+                ),
+                _ => {}
+            }
+        }
+
+        for issue in model.issues.iter().filter(|issue| !old.issues.contains(issue)) {
+            change(hardware, WatchChangeKind::NewIssue, issue.clone());
+        }
+        for workaround in model.workarounds.iter().filter(|w| !old.workarounds.contains(w)) {
+            change(hardware, WatchChangeKind::NewWorkaround, workaround.clone());
+        }
+    }
+
+    changes
+}
+
+/// Show changes as a desktop notification through `notify-send`
+pub fn notify(changes: &[WatchChange]) -> Result<()> {
+    if changes.is_empty() {
+        return Ok(());
+    }
+    let summary = format!("{} hardware database change(s) for watched devices", changes.len());
+    let body: Vec<String> = changes.iter().map(ToString::to_string).collect();
+    let status = std::process::Command::new("notify-send")
+        .args(["--app-name=lx-hw-detect", &summary, &body.join("\n")])
+        .status()
+        .map_err(|e| LxHwError::SystemCommandError { command: format!("notify-send: {}", e) })?;
+    if !status.success() {
+        return Err(LxHwError::SystemCommandError {
+            command: format!("notify-send exited with {}", status),
+        });
+    }
+    Ok(())
+}
+
+fn save_json<T: Serialize>(value: &T, path: &Path) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let json = serde_json::to_string_pretty(value)
+        .map_err(|e| LxHwError::SerializationError(e.to_string()))?;
+    std::fs::write(path, json)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn model(scores: &[(&str, u8)], issues: &[&str], workarounds: &[&str]) -> ModelSnapshot {
+        ModelSnapshot {
+            scores: scores.iter().map(|(k, s)| (k.to_string(), *s)).collect(),
+            issues: issues.iter().map(|s| s.to_string()).collect(),
+            workarounds: workarounds.iter().map(|s| s.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn test_watchlist_add_remove() {
+        let mut watchlist = Watchlist::default();
+        assert!(watchlist.add("8086:A0F0", None).unwrap());
+        assert!(!watchlist.add("8086:a0f0", Some("laptop wifi".to_string())).unwrap());
+        assert_eq!(watchlist.devices.len(), 1);
+        assert_eq!(watchlist.devices[0].note.as_deref(), Some("laptop wifi"));
+        assert!(watchlist.add("intel wifi", None).is_err());
+        assert!(watchlist.remove("8086:A0F0"));
+        assert!(!watchlist.remove("8086:a0f0"));
+    }
+
+    #[test]
+    fn test_diff_snapshots() {
+        let hardware = "Intel Wi-Fi 6 AX201";
+        let previous = DeviceSnapshot::from([(
+            hardware.to_string(),
+            model(
+                &[("5.15.0_Ubuntu 22.04", 40), ("6.8.0_Ubuntu 24.04", 90)],
+                &["Drops connection after resume"],
+                &[],
+            ),
+        )]);
+        let current = DeviceSnapshot::from([
+            (
+                hardware.to_string(),
+                model(
+                    &[
+                        ("5.15.0_Ubuntu 22.04", 75),
+                        ("6.8.0_Ubuntu 24.04", 50),
+                        ("6.10.3_Fedora 41", 95),
+                    ],
+                    &["Drops connection after resume", "No 6 GHz band"],
+                    &["Disable power saving"],
+                ),
+            ),
+            ("Intel AX201 CNVi".to_string(), model(&[("6.10.3_Fedora 41", 80)], &[], &[])),
+        ]);
+
+        let changes = diff_snapshots("8086:a0f0", &previous, &current);
+        let kinds: Vec<(&str, WatchChangeKind)> =
+            changes.iter().map(|c| (c.hardware.as_str(), c.kind)).collect();
+        assert_eq!(
+            kinds,
+            [
+                ("Intel AX201 CNVi", WatchChangeKind::FirstReports),
+                (hardware, WatchChangeKind::KernelSupport),
+                (hardware, WatchChangeKind::KernelSupport),
+                (hardware, WatchChangeKind::Regression),
+                (hardware, WatchChangeKind::NewIssue),
+                (hardware, WatchChangeKind::NewWorkaround),
+            ]
+        );
+        assert_eq!(changes[3].detail, "6.8.0 on Ubuntu 24.04: score dropped from 90 to 50");
+        assert!(diff_snapshots("8086:a0f0", &current, &current).is_empty());
+    }
+}
@@ -0,0 +1,101 @@
+//! Integration tests for the configure command
+//!
+//! Rendered targets are compared with the golden files next to the fixture
+//! report in tests/fixtures/configuration.
+
+use assert_cmd::Command;
+use predicates::prelude::*;
+use std::path::{Path, PathBuf};
+
+fn fixture(name: &str) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/configuration").join(name)
+}
+
+fn configure(args: &[&str]) -> String {
+    let report = fixture("workstation.json");
+    let output = Command::cargo_bin("lx-hw-detect")
+        .unwrap()
+        .arg("configure")
+        .arg("--report")
+        .arg(&report)
+        .args(args)
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    String::from_utf8(output.stdout).unwrap()
+}
+
+fn assert_golden(actual: &str, golden: &str) {
+    let expected = std::fs::read_to_string(fixture(golden)).unwrap();
+    assert_eq!(actual, expected, "output differs from tests/fixtures/configuration/{}", golden);
+}
+
+#[test]
+fn test_configure_nixos_target_matches_golden() {
+    assert_golden(&configure(&["--target", "nixos"]), "workstation.nix");
+}
+
+#[test]
+fn test_configure_target_conflicts_with_format() {
+    Command::cargo_bin("lx-hw-detect")
+        .unwrap()
+        .args(["configure", "--report", "-", "--target", "nixos", "--format", "markdown"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("cannot be used with"));
+}
+
+#[test]
+fn test_configure_json_is_applied_by_apply() {
+    let dir = tempfile::TempDir::new().unwrap();
+    let configuration = dir.path().join("configuration.json");
+    std::fs::write(&configuration, configure(&["--distribution", "Fedora Linux 40"])).unwrap();
+    let root = dir.path().join("root");
+    std::fs::create_dir(&root).unwrap();
+
+    Command::cargo_bin("lx-hw-detect")
+        .unwrap()
+        .arg("apply")
+        .arg(&configuration)
+        .arg("--dry-run")
+        .arg("--root")
+        .arg(&root)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("dnf install -y akmod-nvidia"));
+    assert_eq!(std::fs::read_dir(&root).unwrap().count(), 0, "dry run changed the root");
+}
+
+#[test]
+fn test_configure_ansible_target_matches_golden() {
+    assert_golden(
+        &configure(&["--target", "ansible", "--distribution", "Fedora Linux 40"]),
+        "workstation.ansible.yml",
+    );
+}
+
+#[test]
+fn test_configure_shell_target_matches_golden() {
+    assert_golden(
+        &configure(&["--target", "shell", "--distribution", "Fedora Linux 40"]),
+        "workstation.sh",
+    );
+}
+
+#[test]
+fn test_configure_nixos_target_ignores_distribution() {
+    let module = configure(&["--target", "nixos", "--distribution", "Fedora Linux 40"]);
+    assert_golden(&module, "workstation.nix");
+    assert!(module.contains("hardware.nvidia.open = false;"));
+    assert!(!module.contains("akmod-nvidia"));
+}
+
+#[test]
+fn test_configure_markdown_guide_matches_golden() {
+    assert_golden(&configure(&["--format", "markdown"]), "workstation.guide.md");
+}
+
+#[test]
+fn test_configure_html_guide_matches_golden() {
+    assert_golden(&configure(&["--format", "html"]), "workstation.guide.html");
+}
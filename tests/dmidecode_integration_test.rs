@@ -1,5 +1,6 @@
 //! Integration tests for dmidecode detector with real system execution
 
+#![cfg(feature = "dmidecode")]
 #![allow(clippy::excessive_nesting)]
 
 use lx_hw_detect::detectors::dmidecode::DmidecodeDetector;
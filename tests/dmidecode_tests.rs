@@ -1,7 +1,9 @@
 //! Comprehensive unit tests for dmidecode detector
 
+#![cfg(feature = "dmidecode")]
+
 use lx_hw_detect::detectors::dmidecode::DmidecodeDetector;
-use lx_hw_detect::detectors::{DetectionData, HardwareDetector};
+use lx_hw_detect::detectors::{DetectionData, DetectionError, HardwareDetector};
 use std::process::{ExitStatus, Output};
 
 /// Mock dmidecode output for testing - represents real dmidecode output structure
@@ -269,7 +271,7 @@ async fn test_parse_no_privileges_output() {
     assert_eq!(result.tool_name, "dmidecode");
     assert!(!result.errors.is_empty());
     // The error should contain the permission message from stderr
-    assert!(result.errors.iter().any(|e| e.contains("Operation not permitted")));
+    assert!(result.errors.iter().any(|e| matches!(e, DetectionError::PermissionDenied { .. })));
 
     if let DetectionData::Dmidecode(data) = result.data {
         assert!(data.bios.is_none());
@@ -288,7 +290,7 @@ async fn test_parse_empty_output() {
     let result = detector.parse_output(&output).unwrap();
 
     assert!(!result.success);
-    assert!(result.errors.contains(&"Empty output from dmidecode".to_string()));
+    assert!(result.errors.contains(&DetectionError::EmptyOutput { tool: "dmidecode".to_string() }));
 }
 
 #[tokio::test]
@@ -0,0 +1,82 @@
+//! Integration tests for the drift command
+//!
+//! The recommendations for the fixture workstation report are audited
+//! against a system tree in tests/fixtures/configuration/drift-root.
+
+use assert_cmd::Command;
+use predicates::prelude::*;
+use std::path::{Path, PathBuf};
+
+fn fixture(name: &str) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/configuration").join(name)
+}
+
+fn drift() -> Command {
+    let mut command = Command::cargo_bin("lx-hw-detect").unwrap();
+    command
+        .arg("drift")
+        .arg("--report")
+        .arg(fixture("workstation.json"))
+        .arg("--root")
+        .arg(fixture("drift-root"));
+    command
+}
+
+fn status_of(settings: &[serde_json::Value], parameter: &str) -> serde_json::Value {
+    settings.iter().find(|s| s["parameter"] == parameter).unwrap()["status"].clone()
+}
+
+#[test]
+fn test_drift_against_fixture_root() {
+    let output = drift().arg("--json").output().unwrap();
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let settings = report["settings"].as_array().unwrap();
+
+    assert_eq!(status_of(settings, "intel_pstate"), serde_json::json!({"status": "applied"}));
+    // Written with a dash on the command line
+    assert_eq!(
+        status_of(settings, "transparent_hugepage"),
+        serde_json::json!({"status": "applied"})
+    );
+    assert_eq!(status_of(settings, "kaslr"), serde_json::json!({"status": "applied"}));
+    assert_eq!(status_of(settings, "intel_iommu"), serde_json::json!({"status": "missing"}));
+    assert_eq!(
+        status_of(settings, "mitigations"),
+        serde_json::json!({"status": "conflicting", "configured_in": "/proc/cmdline"})
+    );
+    assert_eq!(
+        status_of(settings, "nouveau.modeset"),
+        serde_json::json!({
+            "status": "conflicting",
+            "configured_in": "/etc/modprobe.d/nouveau.conf"
+        })
+    );
+    assert!(report["kernel_cmdline"].as_str().unwrap().starts_with("BOOT_IMAGE=/vmlinuz-6.8.0"));
+}
+
+#[test]
+fn test_drift_markdown_summary() {
+    drift()
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("**Applied**: 3 | **Missing**: 4 | **Conflicting**: 2"))
+        .stdout(predicate::str::contains(
+            "| ✗ conflicts (/proc/cmdline) | `mitigations` | auto | off |",
+        ));
+}
+
+#[test]
+fn test_drift_needs_kernel_cmdline() {
+    let root = tempfile::TempDir::new().unwrap();
+    Command::cargo_bin("lx-hw-detect")
+        .unwrap()
+        .arg("drift")
+        .arg("--report")
+        .arg(fixture("workstation.json"))
+        .arg("--root")
+        .arg(root.path())
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Failed to read /proc/cmdline"));
+}
@@ -0,0 +1,86 @@
+//! Tests for converting individual tool output into report components
+
+#![cfg(all(feature = "lspci", feature = "lsusb"))]
+
+use lx_hw_detect::detectors::lspci::LspciDetector;
+use lx_hw_detect::detectors::lsusb::LsusbDetector;
+use lx_hw_detect::detectors::{ExtractionContext, HardwareDetector};
+use lx_hw_detect::hardware::taxonomy::Taxonomy;
+use lx_hw_detect::hardware::PrivacyLevel;
+use lx_hw_detect::privacy::PrivacyManager;
+use std::os::unix::process::ExitStatusExt;
+use std::process::{ExitStatus, Output};
+
+const SAMPLE_LSPCI_VERBOSE: &str = r#"00:02.0 VGA compatible controller: Intel Corporation Alder Lake-P Integrated Graphics Controller (rev 0c) (prog-if 00 [VGA controller])
+	Subsystem: Lenovo Device 22e4
+	Kernel driver in use: i915
+	Kernel modules: i915
+
+00:1f.3 Audio device: Intel Corporation Alder Lake PCH-P High Definition Audio Controller (rev 01)
+	Subsystem: Lenovo Device 22e4
+	Kernel driver in use: sof-audio-pci-intel-tgl
+
+00:14.3 Network controller: Intel Corporation Alder Lake-P PCH CNVi WiFi (rev 01)
+	Subsystem: Intel Corporation Wi-Fi 6 AX201 160MHz
+	Kernel driver in use: iwlwifi
+"#;
+
+const SAMPLE_LSPCI_NUMERIC: &str = r#"00:02.0 0300: 8086:46a6 (rev 0c)
+00:1f.3 0403: 8086:51c8 (rev 01)
+00:14.3 0280: 8086:51f0 (rev 01)
+"#;
+
+fn output(stdout: Vec<u8>) -> Output {
+    Output { status: ExitStatus::from_raw(0), stdout, stderr: Vec::new() }
+}
+
+#[test]
+fn test_lspci_output_converts_without_analysis() {
+    let mut stdout = SAMPLE_LSPCI_VERBOSE.as_bytes().to_vec();
+    stdout.extend_from_slice(b"\n--- NUMERIC DATA ---\n");
+    stdout.extend_from_slice(SAMPLE_LSPCI_NUMERIC.as_bytes());
+    let result = LspciDetector::new().parse_output(&output(stdout)).unwrap();
+
+    let taxonomy = Taxonomy::builtin();
+    let mut privacy = PrivacyManager::new(PrivacyLevel::Basic).unwrap();
+    let mut ctx = ExtractionContext::new(&taxonomy, &mut privacy);
+    let extractor = result.data.extractor();
+
+    let graphics = extractor.graphics(&mut ctx).unwrap();
+    assert_eq!(graphics.len(), 1);
+    assert_eq!(graphics[0].pci_id, "8086:46a6");
+    assert_eq!(graphics[0].driver.as_deref(), Some("i915"));
+
+    let network = extractor.network(&mut ctx).unwrap();
+    assert_eq!(network.len(), 1);
+    assert_eq!(network[0].driver.as_deref(), Some("iwlwifi"));
+    assert_eq!(network[0].anonymized_mac, "unknown");
+
+    let audio = extractor.audio(&mut ctx).unwrap();
+    assert_eq!(audio.len(), 1);
+    assert_eq!(audio[0].device_type, "audio");
+
+    // lspci knows nothing about these components
+    assert!(extractor.cpu(&mut ctx).unwrap().is_none());
+    assert!(extractor.usb(&mut ctx).unwrap().is_empty());
+}
+
+#[test]
+fn test_lsusb_serials_are_anonymized() {
+    let mut stdout = b"Bus 001 Device 004: ID 0781:5581 SanDisk Corp. Ultra\n".to_vec();
+    stdout.extend_from_slice(b"\n--- SYSFS DATA ---\n");
+    stdout.extend_from_slice(b"device=1-2\nbusnum=1\ndevnum=4\nspeed=5000\nserial=4C530001\n");
+    let result = LsusbDetector::new().parse_output(&output(stdout)).unwrap();
+
+    let taxonomy = Taxonomy::builtin();
+    let mut privacy = PrivacyManager::new(PrivacyLevel::Basic).unwrap();
+    let mut ctx = ExtractionContext::new(&taxonomy, &mut privacy);
+    let usb = result.data.extractor().usb(&mut ctx).unwrap();
+
+    assert_eq!(usb.len(), 1);
+    assert_eq!(usb[0].speed_mbps, Some(5000.0));
+    assert_eq!(usb[0].port_path.as_deref(), Some("1-2"));
+    let serial = usb[0].anonymized_serial.as_deref().unwrap();
+    assert_ne!(serial, "4C530001");
+    assert!(!serial.contains("4C530001"));
+}
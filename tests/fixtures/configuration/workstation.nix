@@ -0,0 +1,15 @@
+# Generated by lx-hw-detect for kernel 6.8.0 (compatibility score 100%)
+# Import it from configuration.nix: imports = [ ./lx-hw-configuration.nix ];
+{ config, lib, pkgs, ... }:
+
+{
+  boot.kernelModules = [ "intel_pstate" "nvidia" "nvidia_modeset" "nvidia_uvm" "nvidia_drm" "iwlwifi" "r8169" "snd_hda_intel" "snd_pcm" ];
+  boot.kernelParams = [ "intel_pstate=enable" "intel_iommu=on" "mitigations=auto" "nouveau.modeset=0" "rd.driver.blacklist=nouveau" "transparent_hugepage=madvise" "elevator=none" "pcie_aspm=off" "kaslr" ];
+  hardware.enableRedistributableFirmware = true;
+  hardware.cpu.intel.updateMicrocode = true;
+  hardware.graphics.enable = true;
+  services.xserver.videoDrivers = [ "nvidia" ];
+  hardware.nvidia.modesetting.enable = true;
+  hardware.nvidia.open = false;
+  environment.systemPackages = with pkgs; [ alsa-utils lshw pciutils usbutils dmidecode ];
+}
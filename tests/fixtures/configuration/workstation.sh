@@ -0,0 +1,247 @@
+#!/bin/bash
+# Hardware Configuration Installation Script
+# Generated for: Fedora Linux 40 (kernel 6.8.0)
+# Generated by: lx-hw-db Configuration Engine
+#
+# Usage: sudo ./lx-hw-setup.sh           apply the configuration
+#        sudo ./lx-hw-setup.sh --undo    revert the changes this script made
+
+set -euo pipefail
+
+STATE_DIR=/var/lib/lx-hw-detect/setup
+BACKUP_DIR="$STATE_DIR/backup"
+MARKER="# added by lx-hw-detect"
+
+if [ "$(id -u)" -ne 0 ]; then
+    echo "This script must be run as root" >&2
+    exit 1
+fi
+
+PACKAGE_MANAGER=""
+# Image-based systems layer packages; their base package managers cannot
+# change the read-only system
+if [ -e /run/ostree-booted ] && command -v rpm-ostree >/dev/null 2>&1; then
+    PACKAGE_MANAGER=rpm-ostree
+elif command -v transactional-update >/dev/null 2>&1 && findmnt -n -o OPTIONS / | grep -qw ro; then
+    PACKAGE_MANAGER=transactional-update
+fi
+for manager in apt-get dnf zypper pacman apk; do
+    if [ -n "$PACKAGE_MANAGER" ]; then
+        break
+    fi
+    if command -v "$manager" >/dev/null 2>&1; then
+        PACKAGE_MANAGER="$manager"
+    fi
+done
+
+package_installed() {
+    case "$PACKAGE_MANAGER" in
+        apt-get) dpkg-query -W -f='${Status}' "$1" 2>/dev/null | grep -q "install ok installed" ;;
+        dnf|zypper|rpm-ostree|transactional-update) rpm -q "$1" >/dev/null 2>&1 ;;
+        pacman) pacman -Q "$1" >/dev/null 2>&1 ;;
+        apk) apk info -e "$1" >/dev/null 2>&1 ;;
+        *) return 0 ;;
+    esac
+}
+
+# Install a package unless present; fails (returns 1) when nothing was installed
+install_package() {
+    if [ -z "$PACKAGE_MANAGER" ]; then
+        echo "No supported package manager found; install $1 manually" >&2
+        return 1
+    fi
+    if package_installed "$1"; then
+        return 1
+    fi
+    echo "Installing $1"
+    case "$PACKAGE_MANAGER" in
+        apt-get) DEBIAN_FRONTEND=noninteractive apt-get install -y "$1" ;;
+        dnf) dnf install -y "$1" ;;
+        zypper) zypper --non-interactive install "$1" ;;
+        pacman) pacman -S --noconfirm --needed "$1" ;;
+        apk) apk add "$1" ;;
+        rpm-ostree) rpm-ostree install --idempotent "$1" ;;
+        transactional-update) transactional-update --non-interactive pkg install "$1" ;;
+    esac || { echo "Failed to install $1" >&2; exit 1; }
+    echo "$1" >> "$STATE_DIR/packages"
+}
+
+remove_package() {
+    echo "Removing $1"
+    case "$PACKAGE_MANAGER" in
+        apt-get) DEBIAN_FRONTEND=noninteractive apt-get remove -y "$1" ;;
+        dnf) dnf remove -y "$1" ;;
+        zypper) zypper --non-interactive remove "$1" ;;
+        pacman) pacman -R --noconfirm "$1" ;;
+        apk) apk del "$1" ;;
+        rpm-ostree) rpm-ostree uninstall "$1" ;;
+        transactional-update) transactional-update --non-interactive pkg remove "$1" ;;
+    esac || echo "Could not remove $1" >&2
+}
+
+# Keep the original of a file once, or note that the file is new
+backup_file() {
+    if [ -e "$1" ]; then
+        if [ ! -e "$BACKUP_DIR$1" ]; then
+            mkdir -p "$BACKUP_DIR$(dirname "$1")"
+            cp -a "$1" "$BACKUP_DIR$1"
+        fi
+    elif ! grep -qxF "$1" "$STATE_DIR/created" 2>/dev/null; then
+        echo "$1" >> "$STATE_DIR/created"
+    fi
+}
+
+# write_file <path> <mode> <backup: yes|no>, content on stdin
+write_file() {
+    if [ "$3" = yes ]; then
+        backup_file "$1"
+    elif [ ! -e "$1" ]; then
+        echo "$1" >> "$STATE_DIR/created"
+    fi
+    echo "Writing $1"
+    mkdir -p "$(dirname "$1")"
+    cat > "$1"
+    chmod "$2" "$1"
+}
+
+regenerate_grub() {
+    if command -v update-grub >/dev/null 2>&1; then
+        update-grub
+    elif command -v grub2-mkconfig >/dev/null 2>&1; then
+        grub2-mkconfig -o /boot/grub2/grub.cfg
+    elif command -v grub-mkconfig >/dev/null 2>&1; then
+        grub-mkconfig -o /boot/grub/grub.cfg
+    fi
+}
+
+add_kernel_parameters() {
+    echo "Adding kernel parameters: $1"
+    if command -v grubby >/dev/null 2>&1; then
+        grubby --update-kernel=ALL --args="$1"
+        echo grubby > "$STATE_DIR/bootloader"
+    elif [ -f /etc/default/grub ]; then
+        backup_file /etc/default/grub
+        if ! grep -qF "$MARKER" /etc/default/grub; then
+            printf 'GRUB_CMDLINE_LINUX_DEFAULT="$GRUB_CMDLINE_LINUX_DEFAULT %s" %s\n' "$1" "$MARKER" >> /etc/default/grub
+        fi
+        regenerate_grub
+        echo grub > "$STATE_DIR/bootloader"
+    elif [ -f /etc/kernel/cmdline ]; then
+        backup_file /etc/kernel/cmdline
+        if ! grep -qF -- "$1" /etc/kernel/cmdline; then
+            printf '%s %s\n' "$(cat /etc/kernel/cmdline)" "$1" > /etc/kernel/cmdline.new
+            mv /etc/kernel/cmdline.new /etc/kernel/cmdline
+        fi
+        echo "Boot entries pick up /etc/kernel/cmdline when kernel-install next runs"
+        echo cmdline > "$STATE_DIR/bootloader"
+    else
+        echo "No supported bootloader configuration found; add to the kernel command line manually: $1" >&2
+        return
+    fi
+    echo "$1" > "$STATE_DIR/kernel-parameters"
+}
+
+undo() {
+    if [ ! -d "$STATE_DIR" ]; then
+        echo "Nothing to undo"
+        return
+    fi
+    local bootloader=""
+    if [ -f "$STATE_DIR/bootloader" ]; then
+        bootloader=$(cat "$STATE_DIR/bootloader")
+    fi
+    if [ "$bootloader" = grubby ]; then
+        grubby --update-kernel=ALL --remove-args="$(cat "$STATE_DIR/kernel-parameters")"
+    fi
+    if [ -d "$BACKUP_DIR" ]; then
+        echo "Restoring original files"
+        cp -a "$BACKUP_DIR/." /
+    fi
+    if [ -f "$STATE_DIR/created" ]; then
+        mapfile -t created < "$STATE_DIR/created"
+        for file in "${created[@]}"; do
+            echo "Removing $file"
+            rm -f "$file"
+        done
+    fi
+    if [ -f "$STATE_DIR/packages" ]; then
+        mapfile -t packages < "$STATE_DIR/packages"
+        for package in "${packages[@]}"; do
+            remove_package "$package"
+        done
+    fi
+    if [ "$bootloader" = grub ]; then
+        regenerate_grub
+    fi
+    rm -rf "$STATE_DIR"
+    echo "Configuration undone. Reboot to return to the previous kernel command line."
+}
+
+case "${1:-}" in
+    --undo)
+        undo
+        exit 0
+        ;;
+    "") ;;
+    *)
+        echo "Usage: $0 [--undo]" >&2
+        exit 2
+        ;;
+esac
+
+mkdir -p "$BACKUP_DIR"
+
+# Intel CPU microcode updates
+install_package 'microcode_ctl' || true
+
+# NVIDIA proprietary graphics driver
+installed=no
+install_package 'akmod-nvidia' && installed=yes
+if [ "$installed" = yes ]; then
+    nvidia-xconfig
+fi
+
+# Intel wireless firmware
+installed=no
+install_package 'iwl*-firmware' && installed=yes
+if [ "$installed" = yes ]; then
+    modprobe -r iwlwifi && modprobe iwlwifi
+fi
+
+# ALSA sound utilities
+installed=no
+install_package 'alsa-utils' && installed=yes
+if [ "$installed" = yes ]; then
+    alsactl init
+fi
+
+# Essential system packages
+install_package 'lshw' || true
+install_package 'pciutils' || true
+install_package 'usbutils' || true
+install_package 'dmidecode' || true
+
+# Load the recommended kernel modules at boot
+write_file '/etc/modules-load.d/lx-hw-detect.conf' '0644' no <<'LX_HW_EOF'
+intel_pstate
+nvidia
+nvidia_modeset
+nvidia_uvm
+nvidia_drm
+iwlwifi
+r8169
+snd_hda_intel
+snd_pcm
+LX_HW_EOF
+
+write_file '/etc/tmpfiles.d/zswap.conf' '0644' no <<'LX_HW_EOF'
+w /sys/module/zswap/parameters/compressor - - - - zstd
+w /sys/module/zswap/parameters/enabled - - - - 1
+LX_HW_EOF
+systemd-tmpfiles --create /etc/tmpfiles.d/zswap.conf
+
+# Kernel parameters
+add_kernel_parameters 'intel_pstate=enable intel_iommu=on mitigations=auto nouveau.modeset=0 rd.driver.blacklist=nouveau transparent_hugepage=madvise elevator=none pcie_aspm=off kaslr'
+
+echo "Hardware configuration installation completed!"
+echo "Please reboot your system to ensure all changes take effect."
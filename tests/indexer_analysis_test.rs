@@ -0,0 +1,47 @@
+//! Tests for the database analysis written by `lx-hw-indexer analyze`
+
+#![cfg(feature = "indexer")]
+
+use chrono::{TimeZone, Utc};
+use lx_hw_detect::hardware::examples::ExampleGenerator;
+use lx_hw_detect::indexer::analysis::{
+    AnalysisConfig, CompatibilityAnalysis, CompatibilityAnalyzer, ANALYSIS_FILE, SUMMARY_FILE,
+};
+use lx_hw_detect::indexer::{HardwareIndexer, IndexerConfig};
+
+#[test]
+fn test_analysis_writes_json_and_summary() {
+    let corpus = tempfile::tempdir().unwrap();
+    let generator = ExampleGenerator::new(5);
+    for index in 0..30 {
+        let report = serde_json::to_string(&generator.report(index)).unwrap();
+        std::fs::write(corpus.path().join(format!("example-{}.json", index)), report).unwrap();
+    }
+
+    let mut indexer = HardwareIndexer::new(IndexerConfig {
+        reports_dir: corpus.path().to_path_buf(),
+        ..IndexerConfig::default()
+    });
+    indexer.scan_reports().unwrap();
+    let analysis =
+        CompatibilityAnalyzer::new(&indexer.reports, AnalysisConfig::default()).analyze().unwrap();
+
+    let output = tempfile::tempdir().unwrap();
+    let generated_at = Utc.with_ymd_and_hms(2026, 10, 1, 0, 0, 0).unwrap();
+    analysis.write_to(&output.path().join("analysis"), 30, generated_at).unwrap();
+
+    let json = std::fs::read_to_string(output.path().join("analysis").join(ANALYSIS_FILE)).unwrap();
+    let written: CompatibilityAnalysis = serde_json::from_str(&json).unwrap();
+    assert_eq!(written.health_metrics.overall_score, analysis.health_metrics.overall_score);
+
+    let summary =
+        std::fs::read_to_string(output.path().join("analysis").join(SUMMARY_FILE)).unwrap();
+    assert!(summary.starts_with("# Hardware Database Health Report"));
+    assert!(summary.contains("Generated 2026-10-01 from 30 reports."));
+    assert!(summary
+        .contains(&format!("| Overall health | {}/100 |", analysis.health_metrics.overall_score)));
+    assert!(summary.contains("No compatibility regressions detected."));
+    if !analysis.distribution_analysis.is_empty() {
+        assert!(summary.contains("## Distributions"));
+    }
+}
@@ -0,0 +1,55 @@
+//! Tests for excluding hardware with too few reports from the indices
+
+#![cfg(feature = "indexer")]
+
+use lx_hw_detect::hardware::examples::ExampleGenerator;
+use lx_hw_detect::indexer::{HardwareIndexer, IndexCollection, IndexerConfig};
+use std::path::Path;
+
+const MIN_REPORTS: usize = 8;
+
+fn write_corpus(dir: &Path) {
+    let generator = ExampleGenerator::new(3);
+    for index in 0..40 {
+        let report = serde_json::to_string(&generator.report(index)).unwrap();
+        std::fs::write(dir.join(format!("example-{}.json", index)), report).unwrap();
+    }
+}
+
+fn build(dir: &Path, include_low_sample: bool) -> IndexCollection {
+    let mut indexer = HardwareIndexer::new(IndexerConfig {
+        reports_dir: dir.to_path_buf(),
+        min_reports: MIN_REPORTS,
+        include_low_sample,
+        ..IndexerConfig::default()
+    });
+    indexer.scan_reports().unwrap();
+    indexer.build_indices().unwrap();
+    indexer.indices
+}
+
+#[test]
+fn test_low_sample_hardware_is_excluded_or_flagged() {
+    let corpus = tempfile::tempdir().unwrap();
+    write_corpus(corpus.path());
+
+    let indices = build(corpus.path(), false);
+    assert!(!indices.excluded.is_empty());
+    for hardware in &indices.excluded {
+        assert!(hardware.report_count < MIN_REPORTS);
+        assert_eq!(hardware.report_count + hardware.reports_needed, MIN_REPORTS);
+
+        let hw_key = format!("{} {}", hardware.vendor, hardware.model);
+        assert!(!indices.compatibility_matrix.contains_key(&hw_key));
+        if let Some(vendor) = indices.by_vendor.get(&hardware.vendor) {
+            assert!(vendor.components.values().all(|models| !models.contains(&hardware.model)));
+        }
+    }
+
+    let flagged = build(corpus.path(), true);
+    assert!(flagged.excluded.is_empty());
+    for hardware in &indices.excluded {
+        let hw_key = format!("{} {}", hardware.vendor, hardware.model);
+        assert!(flagged.compatibility_matrix[&hw_key].values().all(|score| score.low_sample));
+    }
+}
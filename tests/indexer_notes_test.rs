@@ -0,0 +1,48 @@
+//! Tests for indexing the notes users attach to report components
+
+#![cfg(feature = "indexer")]
+
+use lx_hw_detect::hardware::examples::ExampleGenerator;
+use lx_hw_detect::hardware::notes::{annotate, UserNote};
+use lx_hw_detect::indexer::{HardwareIndexer, IndexerConfig};
+
+#[test]
+fn test_user_notes_become_workarounds() {
+    let corpus = tempfile::tempdir().unwrap();
+    let output = tempfile::tempdir().unwrap();
+    let generator = ExampleGenerator::new(11);
+    let mut gpu = String::new();
+    for index in 0..3 {
+        let mut report = generator.report(index);
+        if index == 0 {
+            gpu = report.graphics[0].pci_id.clone();
+            let notes = vec![
+                UserNote::parse(&format!("{}=\"Add nomodeset until the first update\"", gpu))
+                    .unwrap(),
+                UserNote::parse("system=Disable secure boot to load the driver").unwrap(),
+            ];
+            annotate(&mut report, notes).unwrap();
+        }
+        let json = serde_json::to_string(&report).unwrap();
+        std::fs::write(corpus.path().join(format!("example-{}.json", index)), json).unwrap();
+    }
+
+    let mut indexer = HardwareIndexer::new(IndexerConfig {
+        reports_dir: corpus.path().to_path_buf(),
+        stats_dir: output.path().join("statistics"),
+        indices_dir: output.path().join("indices"),
+        api_dir: output.path().join("api"),
+        min_reports: 1,
+        ..IndexerConfig::default()
+    });
+    indexer.scan_reports().unwrap();
+    indexer.build_indices().unwrap();
+
+    let known = indexer
+        .indices
+        .known_issues
+        .values()
+        .find(|entry| entry.device_id.as_deref() == Some(gpu.as_str()))
+        .expect("annotated GPU is a known issue");
+    assert!(known.workarounds.contains(&"Add nomodeset until the first update".to_string()));
+}
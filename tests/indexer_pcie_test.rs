@@ -0,0 +1,53 @@
+//! Tests for flagging degraded PCIe links in the index
+
+#![cfg(feature = "indexer")]
+
+use lx_hw_detect::hardware::examples::ExampleGenerator;
+use lx_hw_detect::hardware::{PcieDeviceLink, PcieLink};
+use lx_hw_detect::indexer::{HardwareIndexer, IndexerConfig};
+
+#[test]
+fn test_degraded_pcie_link_is_known_issue() {
+    let corpus = tempfile::tempdir().unwrap();
+    let output = tempfile::tempdir().unwrap();
+    let mut report = ExampleGenerator::new(5).report(0);
+    let gpu = report.graphics[0].pci_id.clone();
+    report.pcie_links.push(PcieDeviceLink {
+        pci_id: gpu.clone(),
+        class_code: "030000".to_string(),
+        link: PcieLink {
+            speed: "16.0 GT/s PCIe".to_string(),
+            width: 4,
+            max_speed: Some("16.0 GT/s PCIe".to_string()),
+            max_width: Some(16),
+        },
+        generation: Some(4),
+        max_generation: Some(4),
+        degraded: true,
+        resizable_bar: None,
+    });
+    let json = serde_json::to_string(&report).unwrap();
+    std::fs::write(corpus.path().join("example-0.json"), json).unwrap();
+
+    let mut indexer = HardwareIndexer::new(IndexerConfig {
+        reports_dir: corpus.path().to_path_buf(),
+        stats_dir: output.path().join("statistics"),
+        indices_dir: output.path().join("indices"),
+        api_dir: output.path().join("api"),
+        min_reports: 1,
+        ..IndexerConfig::default()
+    });
+    indexer.scan_reports().unwrap();
+    indexer.build_indices().unwrap();
+
+    let known = indexer
+        .indices
+        .known_issues
+        .values()
+        .find(|entry| entry.device_id.as_deref() == Some(gpu.as_str()))
+        .expect("GPU with a degraded link is a known issue");
+    assert_eq!(
+        known.issues,
+        ["PCIe link degraded: running at PCIe Gen4 x4, capable of PCIe Gen4 x16"]
+    );
+}
@@ -0,0 +1,78 @@
+//! Tests for building indices from reports streamed in batches
+
+#![cfg(feature = "indexer")]
+
+use lx_hw_detect::indexer::{HardwareIndexer, IndexCollection, IndexerConfig};
+use std::path::Path;
+
+const REAL_REPORT: &str = "hardware-reports/2024/12/real-system-20250826.json";
+
+/// Write variants of the real report differing in kernel and system id
+fn write_corpus(dir: &Path) {
+    let report: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(REAL_REPORT).unwrap()).unwrap();
+    for i in 0..7 {
+        let mut variant = report.clone();
+        variant["system"]["kernel_version"] = format!("6.{}.0", i % 3).into();
+        variant["metadata"]["anonymized_system_id"] = format!("system-{}", i % 4).into();
+        let path = dir.join(format!("report-{}.json", i));
+        std::fs::write(path, serde_json::to_string(&variant).unwrap()).unwrap();
+    }
+}
+
+fn build(dir: &Path, streaming_threshold: usize) -> (bool, IndexCollection) {
+    let mut indexer = HardwareIndexer::new(IndexerConfig {
+        reports_dir: dir.to_path_buf(),
+        batch_size: 3,
+        streaming_threshold,
+        ..IndexerConfig::default()
+    });
+    indexer.scan_reports().unwrap();
+    indexer.build_indices().unwrap();
+    (indexer.is_streaming(), indexer.indices)
+}
+
+#[test]
+fn test_streamed_indices_match_loaded_indices() {
+    let corpus = tempfile::tempdir().unwrap();
+    write_corpus(corpus.path());
+
+    let (loaded_streaming, loaded) = build(corpus.path(), 100);
+    let (streaming, streamed) = build(corpus.path(), 2);
+    assert!(!loaded_streaming);
+    assert!(streaming);
+
+    let (a, b) = (&loaded.statistics, &streamed.statistics);
+    assert_eq!(b.total_reports, 7);
+    assert_eq!(b.unique_systems, 4);
+    assert_eq!(b.kernel_versions, 3);
+    assert_eq!((a.total_vendors, a.component_types), (b.total_vendors, b.component_types));
+    assert_eq!(a.compatibility_overview, b.compatibility_overview);
+
+    let top = |collection: &IndexCollection| -> Vec<(String, usize)> {
+        let mut top: Vec<_> = collection
+            .statistics
+            .top_hardware
+            .iter()
+            .map(|hw| (format!("{} {}", hw.vendor, hw.model), hw.report_count))
+            .collect();
+        top.sort();
+        top
+    };
+    assert_eq!(top(&loaded), top(&streamed));
+
+    assert_eq!(loaded.search_terms, streamed.search_terms);
+    assert_eq!(loaded.by_vendor.len(), streamed.by_vendor.len());
+    for (vendor, entry) in &loaded.by_vendor {
+        let other = &streamed.by_vendor[vendor];
+        assert_eq!(entry.total_reports, other.total_reports);
+        assert_eq!(entry.components, other.components);
+        assert_eq!(entry.compatibility_score, other.compatibility_score);
+    }
+    for (hardware, kernels) in &loaded.compatibility_matrix {
+        for (kernel, score) in kernels {
+            let other = &streamed.compatibility_matrix[hardware][kernel];
+            assert_eq!((score.score, score.sample_size), (other.score, other.sample_size));
+        }
+    }
+}
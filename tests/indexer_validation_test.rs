@@ -0,0 +1,52 @@
+//! Tests for recording report validation in the indexer output
+
+#![cfg(feature = "indexer")]
+
+use lx_hw_detect::hardware::examples::ExampleGenerator;
+use lx_hw_detect::indexer::{HardwareIndexer, IndexerConfig, ValidationSummary};
+
+#[test]
+fn test_invalid_reports_are_counted_but_not_scored() {
+    let corpus = tempfile::tempdir().unwrap();
+    let output = tempfile::tempdir().unwrap();
+    let generator = ExampleGenerator::new(7);
+    for index in 0..6 {
+        let mut report = generator.report(index);
+        if index == 0 {
+            // No detection tools listed fails schema validation
+            report.metadata.tools_used.clear();
+        }
+        let json = serde_json::to_string(&report).unwrap();
+        std::fs::write(corpus.path().join(format!("example-{}.json", index)), json).unwrap();
+    }
+
+    let mut indexer = HardwareIndexer::new(IndexerConfig {
+        reports_dir: corpus.path().to_path_buf(),
+        stats_dir: output.path().join("statistics"),
+        indices_dir: output.path().join("indices"),
+        api_dir: output.path().join("api"),
+        min_reports: 1,
+        ..IndexerConfig::default()
+    });
+    indexer.scan_reports().unwrap();
+    indexer.build_indices().unwrap();
+
+    let stats = &indexer.indices.statistics;
+    assert_eq!(stats.total_reports, 6);
+    assert_eq!(stats.invalid_reports, 1);
+    assert_eq!(stats.compatibility_overview.values().sum::<usize>(), 5);
+    assert!(!indexer.indices.search_terms.values().flatten().any(|id| id == "example-0"));
+
+    indexer.write_indices().unwrap();
+    let summary: ValidationSummary = serde_json::from_str(
+        &std::fs::read_to_string(output.path().join("statistics/validation-summary.json")).unwrap(),
+    )
+    .unwrap();
+    assert_eq!(summary.total_reports, 6);
+    assert_eq!(summary.valid_reports, 5);
+    assert_eq!(summary.invalid.len(), 1);
+    assert_eq!(summary.invalid[0].id, "example-0");
+    assert!(!summary.invalid[0].errors.is_empty());
+    assert!(summary.min_confidence < 1.0);
+    assert!(summary.min_confidence <= summary.average_confidence);
+}
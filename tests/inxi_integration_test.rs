@@ -1,5 +1,6 @@
 //! Integration test for inxi with real system execution
 
+#![cfg(feature = "inxi")]
 #![allow(clippy::excessive_nesting)]
 
 use lx_hw_detect::detectors::inxi::InxiDetector;
@@ -1,7 +1,9 @@
 //! Tests for inxi hardware detection
 
+#![cfg(feature = "inxi")]
+
 use lx_hw_detect::detectors::inxi::{InxiData, InxiDetector, InxiSystem};
-use lx_hw_detect::detectors::{DetectionData, HardwareDetector};
+use lx_hw_detect::detectors::{DetectionData, DetectionError, HardwareDetector};
 use std::os::unix::process::ExitStatusExt;
 use std::process::{ExitStatus, Output};
 
@@ -114,7 +116,7 @@ fn test_inxi_empty_output() {
     let result = detector.parse_output(&output).unwrap();
     assert!(!result.success);
     assert!(!result.errors.is_empty());
-    assert!(result.errors[0].contains("Empty output"));
+    assert!(matches!(result.errors[0], DetectionError::EmptyOutput { .. }));
 }
 
 #[test]
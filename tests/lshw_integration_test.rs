@@ -1,5 +1,6 @@
 //! Integration test for lshw with real system execution
 
+#![cfg(feature = "lshw")]
 #![allow(clippy::excessive_nesting)]
 
 use lx_hw_detect::detectors::lshw::LshwDetector;
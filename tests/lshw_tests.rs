@@ -1,7 +1,9 @@
 //! Tests for lshw hardware detection
 
+#![cfg(feature = "lshw")]
+
 use lx_hw_detect::detectors::lshw::{LshwComponent, LshwData, LshwDetector};
-use lx_hw_detect::detectors::{DetectionData, HardwareDetector};
+use lx_hw_detect::detectors::{DetectionData, DetectionError, HardwareDetector};
 use std::os::unix::process::ExitStatusExt;
 use std::process::{ExitStatus, Output};
 
@@ -150,7 +152,8 @@ fn test_lshw_json_parsing_malformed() {
     let result = detector.parse_output(&output).unwrap();
     assert!(!result.success);
     assert!(!result.errors.is_empty());
-    assert!(result.errors[0].contains("JSON parsing failed"));
+    assert!(matches!(result.errors[0], DetectionError::ParseError { line: Some(_), .. }));
+    assert!(result.errors[0].to_string().contains("JSON parsing failed"));
 }
 
 #[test]
@@ -161,7 +164,7 @@ fn test_lshw_empty_output() {
     let result = detector.parse_output(&output).unwrap();
     assert!(!result.success);
     assert!(!result.errors.is_empty());
-    assert!(result.errors[0].contains("Empty output"));
+    assert!(matches!(result.errors[0], DetectionError::EmptyOutput { .. }));
 }
 
 #[test]
@@ -177,7 +180,7 @@ fn test_lshw_stderr_handling() {
     let result = detector.parse_output(&output).unwrap();
     assert!(result.success); // Should still succeed despite warnings
     assert!(!result.errors.is_empty()); // But warnings should be captured
-    assert!(result.errors[0].contains("WARNING"));
+    assert!(result.errors[0].to_string().contains("WARNING"));
 }
 
 #[test]
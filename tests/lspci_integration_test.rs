@@ -1,5 +1,6 @@
 //! Integration test for lspci with real system execution
 
+#![cfg(feature = "lspci")]
 #![allow(clippy::excessive_nesting)]
 
 use lx_hw_detect::detectors::lspci::LspciDetector;
@@ -66,8 +67,8 @@ async fn test_lspci_real_execution() {
                                 if let Some(driver) = &device.kernel_driver {
                                     println!("  Driver: {}", driver);
                                 }
-                                if device.iommu_group.is_some() {
-                                    println!("  IOMMU group: {}", device.iommu_group.unwrap());
+                                if let Some(group) = device.iommu_group {
+                                    println!("  IOMMU group: {}", group);
                                 }
                             }
                         }
@@ -1,7 +1,9 @@
 //! Tests for lspci hardware detection
 
+#![cfg(feature = "lspci")]
+
 use lx_hw_detect::detectors::lspci::{LspciData, LspciDetector, PciDevice};
-use lx_hw_detect::detectors::{DetectionData, HardwareDetector};
+use lx_hw_detect::detectors::{DetectionData, DetectionError, HardwareDetector};
 use std::os::unix::process::ExitStatusExt;
 use std::process::{ExitStatus, Output};
 
@@ -149,7 +151,7 @@ fn test_lspci_empty_output() {
     let result = detector.parse_output(&output).unwrap();
     assert!(!result.success);
     assert!(!result.errors.is_empty());
-    assert!(result.errors[0].contains("Empty output"));
+    assert!(matches!(result.errors[0], DetectionError::EmptyOutput { .. }));
 }
 
 #[test]
@@ -1,5 +1,6 @@
 //! Integration test for lsusb with real system execution
 
+#![cfg(feature = "lsusb")]
 #![allow(clippy::excessive_nesting)]
 
 use lx_hw_detect::detectors::lsusb::LsusbDetector;
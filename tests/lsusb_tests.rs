@@ -1,7 +1,11 @@
 //! Tests for lsusb hardware detection
 
-use lx_hw_detect::detectors::lsusb::{LsusbData, LsusbDetector, UsbDevice};
-use lx_hw_detect::detectors::{DetectionData, HardwareDetector};
+#![cfg(feature = "lsusb")]
+
+use lx_hw_detect::detectors::lsusb::{
+    parse_usb_speed, read_sysfs_attributes, usb_class_name, LsusbData, LsusbDetector, UsbDevice,
+};
+use lx_hw_detect::detectors::{DetectionData, DetectionError, HardwareDetector};
 use std::os::unix::process::ExitStatusExt;
 use std::process::{ExitStatus, Output};
 
@@ -157,7 +161,7 @@ fn test_lsusb_empty_output() {
     let result = detector.parse_output(&output).unwrap();
     assert!(!result.success);
     assert!(!result.errors.is_empty());
-    assert!(result.errors[0].contains("Empty output"));
+    assert!(matches!(result.errors[0], DetectionError::EmptyOutput { .. }));
 }
 
 #[test]
@@ -195,6 +199,7 @@ fn test_usb_device_serialization() {
         device_protocol: None,
         max_packet_size: Some(64),
         serial_number: None,
+        port_path: None,
         manufacturer: Some("VIA Labs".to_string()),
         speed: Some("480Mbps".to_string()),
         max_power: Some(100),
@@ -246,3 +251,107 @@ fn test_lsusb_device_parsing_edge_cases() {
         }
     }
 }
+
+#[test]
+fn test_lsusb_sysfs_attributes() {
+    let detector = LsusbDetector::new();
+    let sysfs = "device=usb3\nbusnum=3\ndevnum=1\nbDeviceClass=09\nspeed=480\nversion= 2.00\n\n\
+                 device=3-2\nbusnum=3\ndevnum=3\nbDeviceClass=00\nbDeviceSubClass=00\n\
+                 speed=12\nbMaxPower=98mA\nserial=ABC123\nbInterfaceClass=03\n\n\
+                 device=6-4\nbusnum=6\ndevnum=2\nbDeviceClass=ef\nspeed=5000\n";
+
+    let mut combined_output = SAMPLE_LSUSB_OUTPUT.as_bytes().to_vec();
+    combined_output.extend_from_slice(b"\n--- TOPOLOGY DATA ---\n");
+    combined_output.extend_from_slice(SAMPLE_LSUSB_TOPOLOGY.as_bytes());
+    combined_output.extend_from_slice(b"\n--- SYSFS DATA ---\n");
+    combined_output.extend_from_slice(sysfs.as_bytes());
+    let output =
+        Output { status: ExitStatus::from_raw(0), stdout: combined_output, stderr: Vec::new() };
+
+    let result = detector.parse_output(&output).unwrap();
+    let DetectionData::Lsusb(data) = result.data else {
+        panic!("Expected LsusbData");
+    };
+    assert_eq!(data.devices.len(), 9);
+
+    let root_hub = data.devices.iter().find(|d| d.bus == 3 && d.device == 1).unwrap();
+    assert_eq!(root_hub.device_class.as_deref(), Some("Hub"));
+    assert_eq!(root_hub.speed.as_deref(), Some("480Mbps"));
+    assert_eq!(root_hub.usb_version.as_deref(), Some("2.00"));
+    assert_eq!(root_hub.port_path.as_deref(), Some("usb3"));
+
+    // Class 00 devices take the class of their first interface
+    let mouse = data.devices.iter().find(|d| d.bus == 3 && d.device == 3).unwrap();
+    assert_eq!(mouse.device_class.as_deref(), Some("Human Interface Device"));
+    assert_eq!(mouse.speed.as_deref(), Some("12Mbps"));
+    assert_eq!(mouse.max_power, Some(98));
+    assert_eq!(mouse.serial_number.as_deref(), Some("ABC123"));
+    assert_eq!(mouse.port_path.as_deref(), Some("3-2"));
+
+    let camera = data.devices.iter().find(|d| d.bus == 6 && d.device == 2).unwrap();
+    assert_eq!(camera.speed.as_deref(), Some("5Gbps"));
+    assert_eq!(camera.speed.as_deref().and_then(parse_usb_speed), Some(5000.0));
+    assert_eq!(parse_usb_speed("1.5Mbps"), Some(1.5));
+
+    let untouched = data.devices.iter().find(|d| d.bus == 5 && d.device == 2).unwrap();
+    assert!(untouched.speed.is_none());
+}
+
+#[test]
+fn test_lsusb_sysfs_attributes_keep_parsed_values() {
+    let detector = LsusbDetector::new();
+    let mut devices = vec![UsbDevice {
+        bus: 3,
+        device: 2,
+        vendor_id: "2109".to_string(),
+        product_id: "2822".to_string(),
+        vendor_name: None,
+        product_name: None,
+        usb_version: Some("2.10".to_string()),
+        device_class: Some("Hub".to_string()),
+        device_subclass: Some("00".to_string()),
+        device_protocol: Some("02".to_string()),
+        max_packet_size: Some(64),
+        serial_number: Some("VL812".to_string()),
+        port_path: None,
+        manufacturer: None,
+        speed: Some("480Mbps".to_string()),
+        max_power: Some(100),
+        interfaces: Vec::new(),
+        descriptor: None,
+    }];
+
+    // Only the topology and power are readable in sysfs
+    detector
+        .apply_sysfs_attributes(&mut devices, "device=3-1\nbusnum=3\ndevnum=2\nbMaxPower=0mA\n");
+
+    let hub = &devices[0];
+    assert_eq!(hub.port_path.as_deref(), Some("3-1"));
+    assert_eq!(hub.max_power, Some(0));
+    assert_eq!(hub.device_class.as_deref(), Some("Hub"));
+    assert_eq!(hub.device_subclass.as_deref(), Some("00"));
+    assert_eq!(hub.device_protocol.as_deref(), Some("02"));
+    assert_eq!(hub.speed.as_deref(), Some("480Mbps"));
+    assert_eq!(hub.usb_version.as_deref(), Some("2.10"));
+    assert_eq!(hub.serial_number.as_deref(), Some("VL812"));
+}
+
+#[test]
+fn test_read_sysfs_attributes() {
+    let root = tempfile::tempdir().unwrap();
+    let device = root.path().join("1-4");
+    std::fs::create_dir_all(device.join("1-4:1.0")).unwrap();
+    std::fs::write(device.join("busnum"), "1\n").unwrap();
+    std::fs::write(device.join("devnum"), "5\n").unwrap();
+    std::fs::write(device.join("bDeviceClass"), "00\n").unwrap();
+    std::fs::write(device.join("speed"), "5000\n").unwrap();
+    std::fs::write(device.join("1-4:1.0").join("bInterfaceClass"), "08\n").unwrap();
+    std::fs::create_dir_all(root.path().join("1-4:1.0")).unwrap();
+
+    let section = read_sysfs_attributes(root.path());
+    assert_eq!(
+        section,
+        "device=1-4\nbusnum=1\ndevnum=5\nbDeviceClass=00\nspeed=5000\nbInterfaceClass=08\n"
+    );
+    assert_eq!(usb_class_name(0x08), "Mass Storage");
+}
@@ -0,0 +1,25 @@
+//! Integration test for fingerprinting tool output a parser rejects
+//!
+//! Replay is process-wide, so this lives in its own test binary.
+
+#![cfg(feature = "lshw")]
+
+use lx_hw_detect::detectors::integration::HardwareAnalyzer;
+use lx_hw_detect::detectors::replay;
+use lx_hw_detect::hardware::PrivacyLevel;
+
+#[tokio::test]
+async fn test_garbage_output_is_recorded_as_parse_failure() {
+    let fixtures = tempfile::tempdir().unwrap();
+    std::fs::write(fixtures.path().join("lshw.stdout"), "<html>not json</html>\n").unwrap();
+    replay::enable(fixtures.path()).unwrap();
+
+    let mut analyzer = HardwareAnalyzer::new(PrivacyLevel::Basic).unwrap();
+    analyzer.analyze_system().await.unwrap();
+
+    let failures = analyzer.take_parse_failures();
+    assert_eq!(failures.len(), 1, "{:?}", failures);
+    assert_eq!(failures[0].tool, "lshw");
+    assert_eq!(failures[0].parser, "lshw::parse_output");
+    assert_eq!(failures[0].error_kind, "ParseError");
+}
@@ -1,5 +1,6 @@
 //! Phase 2 integration test verifying lshw + dmidecode detectors work together
 
+#![cfg(all(feature = "lshw", feature = "dmidecode"))]
 #![allow(clippy::excessive_nesting)]
 
 use lx_hw_detect::detectors::{DetectionData, DetectorRegistry};
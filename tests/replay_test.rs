@@ -0,0 +1,50 @@
+//! Integration test for building reports from saved tool output
+//!
+//! Replay is process-wide, so this lives in its own test binary.
+
+#![cfg(feature = "lspci")]
+
+use lx_hw_detect::detectors::integration::HardwareAnalyzer;
+use lx_hw_detect::detectors::replay;
+use lx_hw_detect::hardware::PrivacyLevel;
+
+const LSPCI_OUTPUT: &str = r#"00:02.0 VGA compatible controller: Intel Corporation TigerLake-LP GT2 [Iris Xe Graphics] (rev 01) (prog-if 00 [VGA controller])
+	Subsystem: Lenovo Device 22d8
+	Flags: bus master, fast devsel, latency 0, IRQ 145, IOMMU group 1
+	Kernel driver in use: i915
+	Kernel modules: i915
+
+00:14.3 Network controller: Intel Corporation Wi-Fi 6 AX201 (rev 20)
+	Subsystem: Intel Corporation Wi-Fi 6 AX201 160MHz
+	Flags: bus master, fast devsel, latency 0, IRQ 16, IOMMU group 8
+	Kernel driver in use: iwlwifi
+	Kernel modules: iwlwifi
+
+--- NUMERIC DATA ---
+00:02.0 0300: 8086:9a49 (rev 01)
+00:14.3 0280: 8086:a0f0 (rev 20)
+"#;
+
+#[tokio::test]
+async fn test_report_from_saved_output() {
+    let fixtures = tempfile::tempdir().unwrap();
+    std::fs::write(fixtures.path().join("lspci.stdout"), LSPCI_OUTPUT).unwrap();
+    replay::enable(fixtures.path()).unwrap();
+
+    let mut analyzer = HardwareAnalyzer::new(PrivacyLevel::Basic).unwrap();
+    let report = analyzer.analyze_system().await.unwrap();
+
+    assert!(report.metadata.replayed);
+    assert_eq!(report.metadata.tools_used, ["lspci"]);
+    assert_eq!(report.system.kernel_version, "unknown");
+    assert!(report.kernel_support.is_none());
+    assert!(report.graphics.iter().any(|gpu| gpu.pci_id == "8086:9a49"));
+    assert!(report.network.iter().any(|nic| nic.driver.as_deref() == Some("iwlwifi")));
+
+    // The same saved output gives the same devices
+    let again = analyzer.analyze_system().await.unwrap();
+    assert_eq!(
+        serde_json::to_value(&again.graphics).unwrap(),
+        serde_json::to_value(&report.graphics).unwrap()
+    );
+}
@@ -0,0 +1,84 @@
+//! Integration tests for reading hardware reports from stdin
+
+use assert_cmd::Command;
+use predicates::prelude::*;
+
+fn minimal_report_json() -> String {
+    serde_json::json!({
+        "metadata": {
+            "version": "1.0.0",
+            "generated_at": "2025-01-01T00:00:00Z",
+            "privacy_level": "Basic",
+            "tools_used": ["lshw"],
+            "anonymized_system_id": "test_id_123456"
+        },
+        "system": {
+            "anonymized_hostname": "test_host_456789",
+            "kernel_version": "6.16.0",
+            "distribution": "NixOS 25.11",
+            "architecture": "x86_64",
+            "boot_time": null
+        },
+        "cpu": null,
+        "memory": null,
+        "storage": [],
+        "graphics": [],
+        "network": [],
+        "usb": [],
+        "audio": [],
+        "kernel_support": null
+    })
+    .to_string()
+}
+
+#[test]
+fn test_render_reads_stdin() {
+    Command::cargo_bin("lx-hw-detect")
+        .unwrap()
+        .args(["render", "-", "--format", "yaml"])
+        .write_stdin(minimal_report_json())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("kernel_version: 6.16.0"));
+}
+
+#[test]
+fn test_validate_reads_stdin() {
+    Command::cargo_bin("lx-hw-detect")
+        .unwrap()
+        .args(["validate", "-"])
+        .write_stdin(minimal_report_json())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("VALID -"));
+}
+
+#[test]
+fn test_compare_reads_stdin() {
+    let report = minimal_report_json();
+    let file = tempfile::NamedTempFile::new().unwrap();
+    std::fs::write(file.path(), &report).unwrap();
+
+    Command::cargo_bin("lx-hw-detect")
+        .unwrap()
+        .arg("compare")
+        .arg(file.path())
+        .arg("-")
+        .write_stdin(report)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("No hardware changes detected"));
+}
+
+#[test]
+fn test_compare_rejects_stdin_for_both_reports() {
+    Command::cargo_bin("lx-hw-detect")
+        .unwrap()
+        .args(["compare", "-", "-"])
+        .write_stdin(minimal_report_json())
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "Only one of the compared reports can be read from stdin",
+        ));
+}
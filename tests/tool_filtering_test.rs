@@ -1,5 +1,7 @@
 //! Integration test for tool filtering and timeout functionality
 
+#![cfg(all(feature = "lshw", feature = "lspci"))]
+
 use lx_hw_detect::detectors::DetectorRegistry;
 use lx_hw_detect::errors::Result;
 use std::time::Duration;